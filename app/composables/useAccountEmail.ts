@@ -19,6 +19,9 @@ export interface SendFromAccountRequest {
   attachments: AttachmentData[]
   draft_id?: string
   conversation_id?: string
+  in_reply_to?: string
+  references?: string
+  identity_id?: string
 }
 
 export interface SaveDraftRequest {
@@ -31,12 +34,40 @@ export interface SaveDraftRequest {
   body: string
   scheduled_send_at?: string
   conversation_id?: string
+  in_reply_to?: string
+  references?: string
+  identity_id?: string
+}
+
+export type ReplyMode = 'reply' | 'reply_all' | 'forward'
+
+/**
+ * A reply or forward built from the original message by `prepare_reply`
+ */
+export interface PreparedReply {
+  account_id: string
+  identity_id: string | null
+  conversation_id: string | null
+  to: EmailAddress[]
+  cc: EmailAddress[]
+  subject: string
+  in_reply_to: string | null
+  references: string | null
+  /** The original body, for the composer's quote block */
+  quoted_body: string
+  quote_html: string
+  quote_plain: string
+  attachments: AttachmentData[]
+  /** Attachments that were never downloaded and are left out */
+  missing_attachments: string[]
 }
 
 export interface AttachmentData {
   filename: string
   content: number[]
   content_type?: string
+  /** Set for inline images, which the body references as `cid:` */
+  content_id?: string
 }
 
 export interface SendEmailResponse {
@@ -44,6 +75,58 @@ export interface SendEmailResponse {
   message: string
 }
 
+/**
+ * What an SMTP server advertises in its EHLO response
+ */
+export interface SmtpCapabilities {
+  starttls: boolean
+  encrypted: boolean
+  /** Largest accepted message in bytes */
+  max_size: number | null
+  auth_mechanisms: string[]
+  dsn: boolean
+  eight_bit_mime: boolean
+  smtputf8: boolean
+  pipelining: boolean
+}
+
+export interface SmtpProbe {
+  capabilities: SmtpCapabilities
+  /** Whether the credentials were accepted, null without credentials */
+  authenticated: boolean | null
+  auth_error: string | null
+}
+
+export type OutboxStatus = 'queued' | 'sending' | 'failed'
+
+export interface OutboxItem {
+  id: string
+  account_id: string
+  subject: string | null
+  recipients: string[]
+  status: OutboxStatus
+  attempts: number
+  last_error: string | null
+  next_attempt_at: string | null
+  created_at: string
+  updated_at: string
+}
+
+export type SendStatus =
+  | { queued: { attempts: number, next_attempt_at: string, error: string } }
+  | 'sending'
+  | 'sent'
+  | { failed: { message: string } }
+
+/**
+ * Payload of the `email:send-status` event
+ */
+export interface SendStatusEvent {
+  account_id: string
+  outbox_id: string
+  status: SendStatus
+}
+
 export interface SaveDraftResponse {
   success: boolean
   draft_id: string
@@ -149,6 +232,86 @@ export function useAccountEmail() {
     }
   }
 
+  /**
+   * Build a reply or forward to an email
+   */
+  const prepareReply = async (emailId: string, mode: ReplyMode): Promise<PreparedReply> => {
+    try {
+      error.value = null
+      return await invoke<PreparedReply>('prepare_reply', { emailId, mode })
+    }
+    catch (e) {
+      error.value = e instanceof Error ? e.message : String(e)
+      console.error('Failed to prepare reply:', error.value)
+      throw e
+    }
+  }
+
+  /**
+   * Connect to an account's SMTP server and log in without sending
+   */
+  const testSmtpConnection = async (accountId: string): Promise<SmtpProbe> => {
+    try {
+      error.value = null
+      return await invoke<SmtpProbe>('test_smtp_connection', { accountId })
+    }
+    catch (e) {
+      error.value = e instanceof Error ? e.message : String(e)
+      console.error('Failed to test SMTP connection:', error.value)
+      throw e
+    }
+  }
+
+  /**
+   * Get the emails waiting to be sent again
+   */
+  const getOutbox = async (): Promise<OutboxItem[]> => {
+    try {
+      error.value = null
+      return await invoke<OutboxItem[]>('get_outbox')
+    }
+    catch (e) {
+      error.value = e instanceof Error ? e.message : String(e)
+      console.error('Failed to get outbox:', error.value)
+      throw e
+    }
+  }
+
+  /**
+   * Send an email in the outbox right away
+   */
+  const retrySend = async (id: string): Promise<SendEmailResponse> => {
+    isSending.value = true
+    error.value = null
+
+    try {
+      return await invoke<SendEmailResponse>('retry_send', { id })
+    }
+    catch (e) {
+      error.value = e instanceof Error ? e.message : String(e)
+      console.error('Failed to retry send:', error.value)
+      throw e
+    }
+    finally {
+      isSending.value = false
+    }
+  }
+
+  /**
+   * Remove an email from the outbox without sending it
+   */
+  const discardOutboxItem = async (id: string): Promise<void> => {
+    try {
+      error.value = null
+      await invoke('discard_outbox_item', { id })
+    }
+    catch (e) {
+      error.value = e instanceof Error ? e.message : String(e)
+      console.error('Failed to discard outbox item:', error.value)
+      throw e
+    }
+  }
+
   /**
    * Convert File to AttachmentData
    */
@@ -188,6 +351,11 @@ export function useAccountEmail() {
     saveDraft,
     getDrafts,
     deleteDraft,
+    prepareReply,
+    testSmtpConnection,
+    getOutbox,
+    retrySend,
+    discardOutboxItem,
     fileToAttachmentData,
     filesToAttachmentData,
   }
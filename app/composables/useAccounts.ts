@@ -3,7 +3,7 @@ import { listen } from '@tauri-apps/api/event'
 import { useRouter } from 'vue-router'
 import { toast } from 'vue-sonner'
 import { useQuery, useMutation, useQueryClient } from '@tanstack/vue-query'
-import type { Account, AccountType, CreateAccountRequest, CredentialsRequiredEvent } from '~/types/sync'
+import type { Account, AccountHealth, AccountQuota, AccountType, CreateAccountRequest, CredentialsRequiredEvent, CredentialsUpdatedEvent, MailServer } from '~/types/sync'
 
 const QUERY_KEYS = {
   all: ['accounts'] as const,
@@ -14,6 +14,15 @@ const QUERY_KEYS = {
 }
 
 let unlistenCredentials: (() => void) | null = null
+let unlistenCredentialsUpdated: (() => void) | null = null
+
+/**
+ * SHA-256 fingerprint reported by a connection error about an untrusted
+ * server certificate
+ */
+export function untrustedCertificateFingerprint(message: string): string | null {
+  return message.match(/SHA-256 fingerprint is ((?:[0-9A-F]{2}:){31}[0-9A-F]{2})/)?.[1] ?? null
+}
 
 export function useAccounts() {
   const { t } = useI18n()
@@ -70,6 +79,11 @@ export function useAccounts() {
     }
   }
 
+  const handleCredentialsUpdated = (event: { payload: CredentialsUpdatedEvent }) => {
+    toast.dismiss(`credentials-${event.payload.account_id}`)
+    queryClient.invalidateQueries({ queryKey: QUERY_KEYS.detail(event.payload.account_id) })
+  }
+
   const navigateToAccountSettings = (accountId: string) => {
     router.push(`/settings/accounts/${accountId}`)
   }
@@ -77,6 +91,7 @@ export function useAccounts() {
   const setupCredentialsListener = async () => {
     try {
       unlistenCredentials = await listen('credentials:required', handleCredentialsRequired)
+      unlistenCredentialsUpdated = await listen('credentials:updated', handleCredentialsUpdated)
       console.log('[useAccounts] Listening for credentials:required events')
     }
     catch (err) {
@@ -89,6 +104,9 @@ export function useAccounts() {
       unlistenCredentials()
       console.log('[useAccounts] Cleaned up credentials:required listener')
     }
+    if (unlistenCredentialsUpdated) {
+      unlistenCredentialsUpdated()
+    }
   }
 
   onMounted(() => {
@@ -129,6 +147,56 @@ export function useAccounts() {
     },
   })
 
+  const getAccountQuota = async (accountId: string, refresh = false) => {
+    return await invoke<AccountQuota>('get_account_quota', { accountId, refresh })
+  }
+
+  const getAccountHealth = async (accountId: string) => {
+    return await invoke<AccountHealth>('get_account_health', { accountId })
+  }
+
+  const setSyncHistory = async (accountId: string, days: number | null) => {
+    const account = await invoke<Account>('set_sync_history', { accountId, days })
+    queryClient.invalidateQueries({ queryKey: QUERY_KEYS.lists() })
+    return account
+  }
+
+  /**
+   * Trust a server certificate that fails validation, or forget the trusted
+   * one with `null`
+   */
+  const trustServerCertificate = async (accountId: string, server: MailServer, fingerprint: string | null) => {
+    const account = await invoke<Account>('trust_server_certificate', { accountId, server, fingerprint })
+    queryClient.invalidateQueries({ queryKey: QUERY_KEYS.lists() })
+    return account
+  }
+
+  /**
+   * Set the color telling the account apart in lists, or clear it with `null`
+   */
+  const setAccountColor = async (accountId: string, color: string | null) => {
+    const account = await invoke<Account>('set_account_color', { accountId, color })
+    queryClient.invalidateQueries({ queryKey: QUERY_KEYS.lists() })
+    return account
+  }
+
+  /**
+   * Use the image at `path` as the account picture, or clear it with `null`
+   */
+  const setAccountAvatar = async (accountId: string, path: string | null) => {
+    const account = await invoke<Account>('set_account_avatar', { accountId, path })
+    queryClient.invalidateQueries({ queryKey: QUERY_KEYS.lists() })
+    return account
+  }
+
+  const pauseAccount = async (accountId: string) => {
+    await invoke('pause_account', { accountId })
+  }
+
+  const resumeAccount = async (accountId: string) => {
+    await invoke('resume_account', { accountId })
+  }
+
   return {
     accounts: computed(() => accounts.value || []),
     isLoading: computed(() => isLoading.value),
@@ -139,5 +207,13 @@ export function useAccounts() {
     deleteAccount: deleteAccountMutation.mutateAsync,
     deleteAccountMutation,
     navigateToAccountSettings,
+    getAccountQuota,
+    getAccountHealth,
+    setSyncHistory,
+    trustServerCertificate,
+    setAccountColor,
+    setAccountAvatar,
+    pauseAccount,
+    resumeAccount,
   }
 }
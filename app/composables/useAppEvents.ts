@@ -2,6 +2,7 @@ import { listen } from '@tauri-apps/api/event'
 import { invoke } from '@tauri-apps/api/core'
 import { navigateToUrl } from './useUrlNavigation'
 import { useAuth } from './useAuth'
+import { isDetachedWindow } from './useWindows'
 
 export function useAppEvents() {
   let unlistenUrl: (() => void) | null = null
@@ -44,6 +45,9 @@ export function useAppEvents() {
   }
 
   onMounted(async () => {
+    // Navigation and re-authentication are handled by the main window only
+    if (isDetachedWindow()) return
+
     console.log('[AppEvents] Setting up event listeners')
 
     // Listen for URL navigation events from menu/shortcuts
@@ -0,0 +1,99 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+
+export interface LockStatus {
+  /** A passphrase is set */
+  enabled: boolean
+  locked: boolean
+  /** Touch ID or Windows Hello can unlock */
+  biometrics_available: boolean
+  auto_lock_minutes: number
+}
+
+/** How often user input is reported to postpone the auto-lock */
+const ACTIVITY_INTERVAL_MS = 30_000
+const ACTIVITY_EVENTS = ['keydown', 'mousedown', 'mousemove', 'wheel', 'touchstart'] as const
+
+export function useAppLock() {
+  const { t } = useI18n()
+  const lockStatus = useState<LockStatus | null>('app-lock-status', () => null)
+
+  const fetchStatus = async () => {
+    lockStatus.value = await invoke<LockStatus>('get_lock_status')
+    return lockStatus.value
+  }
+
+  const lock = async () => {
+    lockStatus.value = await invoke<LockStatus>('lock')
+  }
+
+  const unlock = async (passphrase: string) => {
+    lockStatus.value = await invoke<LockStatus>('unlock', { passphrase })
+  }
+
+  const unlockWithBiometrics = async () => {
+    lockStatus.value = await invoke<LockStatus>('unlock_with_biometrics', {
+      reason: t('components.appLock.biometricReason'),
+    })
+  }
+
+  /**
+   * Set or change the passphrase, or remove it with `null`
+   */
+  const setPassphrase = async (current: string | null, passphrase: string | null) => {
+    lockStatus.value = await invoke<LockStatus>('set_lock_passphrase', { current, passphrase })
+  }
+
+  /**
+   * Report user input to the auto-lock and follow lock changes of any window.
+   * Data loaded before the lock is discarded by reloading once unlocked.
+   */
+  const watchLock = () => {
+    let lastReported = 0
+    const reportActivity = () => {
+      const now = Date.now()
+      if (lockStatus.value?.locked || now - lastReported < ACTIVITY_INTERVAL_MS) return
+      lastReported = now
+      invoke('record_activity').catch(() => {})
+    }
+
+    onMounted(async () => {
+      ACTIVITY_EVENTS.forEach(event => window.addEventListener(event, reportActivity, { passive: true }))
+
+      const unlistenLocked = await listen('app:locked', () => {
+        if (lockStatus.value) {
+          lockStatus.value = { ...lockStatus.value, locked: true }
+        }
+      })
+      const unlistenUnlocked = await listen('app:unlocked', () => {
+        window.location.reload()
+      })
+
+      onBeforeUnmount(() => {
+        unlistenLocked()
+        unlistenUnlocked()
+      })
+
+      try {
+        await fetchStatus()
+      }
+      catch (err) {
+        console.error('[useAppLock] Failed to get lock status:', err)
+      }
+    })
+
+    onBeforeUnmount(() => {
+      ACTIVITY_EVENTS.forEach(event => window.removeEventListener(event, reportActivity))
+    })
+  }
+
+  return {
+    lockStatus: readonly(lockStatus),
+    fetchStatus,
+    lock,
+    unlock,
+    unlockWithBiometrics,
+    setPassphrase,
+    watchLock,
+  }
+}
@@ -1,10 +1,15 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { open, save } from '@tauri-apps/plugin-dialog'
 import { toast } from 'vue-sonner'
 
 import type { AttachmentData } from '~/composables/useAccountEmail'
 import { getFileIconForMimeType } from '~/lib/utils/fileIcons'
-import type { Attachment } from '~/types/email'
+import type {
+  Attachment,
+  AttachmentCacheUsage,
+  AttachmentDownloadProgress,
+} from '~/types/email'
 
 interface AttachmentInfo {
   id: string
@@ -24,15 +29,64 @@ export function useAttachments() {
   const isLoading = ref(false)
   const error = ref<string | null>(null)
 
-  const getAttachmentPath = (attachment: Attachment) => {
-    if (!attachment.full_path) {
-      const message = `Attachment "${attachment.filename}" is not available locally yet`
-      console.error(message)
-      toast.error(message)
+  const downloads = ref<Record<string, AttachmentDownloadProgress>>({})
+
+  // Streams attachments that were not cached during sync into the cache
+  const downloadAttachment = async (attachment: Attachment) => {
+    const unlisten = await listen<AttachmentDownloadProgress>(
+      'attachment:download-progress',
+      (event) => {
+        if (event.payload.attachment_id === attachment.id) {
+          downloads.value[attachment.id] = event.payload
+        }
+      }
+    )
+
+    try {
+      const downloaded = await invoke<Attachment>('download_attachment', {
+        attachmentId: attachment.id,
+      })
+      attachment.is_cached = downloaded.is_cached
+      attachment.full_path = downloaded.full_path
+      return downloaded.full_path ?? null
+    } catch (err: any) {
+      if (downloads.value[attachment.id]?.status !== 'cancelled') {
+        console.error(`Failed to download attachment "${attachment.filename}":`, err)
+        toast.error(`Failed to download ${attachment.filename}`, {
+          description: err?.message || String(err),
+        })
+      }
+      return null
+    } finally {
+      unlisten()
+      delete downloads.value[attachment.id]
+    }
+  }
+
+  const cancelDownload = async (attachment: Attachment) => {
+    try {
+      return await invoke<boolean>('cancel_download', { attachmentId: attachment.id })
+    } catch (err) {
+      console.error('Failed to cancel attachment download:', err)
+      return false
+    }
+  }
+
+  const getCacheUsage = async () => {
+    try {
+      return await invoke<AttachmentCacheUsage>('get_attachment_cache_usage')
+    } catch (err) {
+      console.error('Failed to get attachment cache usage:', err)
       return null
     }
+  }
+
+  const getAttachmentPath = async (attachment: Attachment) => {
+    if (attachment.full_path) {
+      return attachment.full_path
+    }
 
-    return attachment.full_path
+    return downloadAttachment(attachment)
   }
 
   const normalizeDialogPath = (result: DialogResult) => {
@@ -95,7 +149,7 @@ export function useAttachments() {
   }
 
   const openAttachment = async (attachment: Attachment) => {
-    const filePath = getAttachmentPath(attachment)
+    const filePath = await getAttachmentPath(attachment)
     if (!filePath) {
       return
     }
@@ -113,9 +167,9 @@ export function useAttachments() {
   }
 
   const quicklookAttachments = async (attachmentList: Attachment[]) => {
-    const filePaths = attachmentList
-      .map(getAttachmentPath)
-      .filter((path): path is string => Boolean(path))
+    const filePaths = (await Promise.all(attachmentList.map(getAttachmentPath))).filter(
+      (path): path is string => Boolean(path)
+    )
 
     if (filePaths.length === 0) {
       console.error('No cached attachments to preview')
@@ -136,7 +190,7 @@ export function useAttachments() {
   }
 
   const saveAttachmentToPath = async (attachment: Attachment, destinationPath: string) => {
-    const sourcePath = getAttachmentPath(attachment)
+    const sourcePath = await getAttachmentPath(attachment)
     if (!sourcePath) {
       return false
     }
@@ -156,7 +210,7 @@ export function useAttachments() {
   }
 
   const saveToDownloads = async (attachment: Attachment) => {
-    const sourcePath = getAttachmentPath(attachment)
+    const sourcePath = await getAttachmentPath(attachment)
     if (!sourcePath) {
       return false
     }
@@ -215,7 +269,7 @@ export function useAttachments() {
   }
 
   const saveToCustomLocation = async (attachment: Attachment) => {
-    const sourcePath = getAttachmentPath(attachment)
+    const sourcePath = await getAttachmentPath(attachment)
     if (!sourcePath) {
       return false
     }
@@ -287,7 +341,11 @@ export function useAttachments() {
     attachments,
     isLoading,
     error,
+    downloads,
     loadAttachments,
+    downloadAttachment,
+    cancelDownload,
+    getCacheUsage,
     openAttachment,
     quicklookAttachments,
     saveAttachmentToPath,
@@ -3,7 +3,9 @@ import { listen } from '@tauri-apps/api/event'
 
 import type {
   AccountType,
+  DiscoveredSettings,
   ImapConnectionConfig,
+  OAuthScope,
   StartOAuth2Request,
   StartOAuth2Response,
   StoreImapCredentialsRequest,
@@ -61,6 +63,48 @@ export function useAuth() {
     }
   }
 
+  /**
+   * Grant an existing OAuth account more scopes, keeping its data
+   */
+  const reauthorizeAccount = async (
+    provider: AccountType,
+    accountId: string,
+    scopes: OAuthScope[],
+    redirectUri: string = 'https://coderscantina-ravn.s3.eu-west-1.amazonaws.com/oauth-callback.html'
+  ) => {
+    isAuthenticating.value = true
+    error.value = null
+
+    try {
+      const response = await invoke<StartOAuth2Response>('reauthorize_account', {
+        accountId,
+        scopes,
+        redirectUri,
+      })
+
+      if (typeof window !== 'undefined' && typeof localStorage !== 'undefined') {
+        localStorage.setItem('oauth_csrf_token', response.csrf_token)
+        localStorage.setItem('oauth_provider', provider)
+        localStorage.setItem('oauth_account_id', accountId)
+      }
+
+      await invoke('open_oauth_window', {
+        authUrl: response.auth_url,
+        provider,
+      })
+
+      return response
+    }
+    catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      throw new Error(errorMessage)
+    }
+    finally {
+      isAuthenticating.value = false
+    }
+  }
+
   const exchangeOAuth2Code = async (
     code: string,
   ) => {
@@ -132,13 +176,22 @@ export function useAuth() {
     }
   }
 
+  /**
+   * Look up the IMAP and SMTP servers of an address to prefill manual setup
+   */
+  const discoverAccountSettings = async (email: string) => {
+    return await invoke<DiscoveredSettings | null>('discover_account_settings', { email })
+  }
+
   return {
     isAuthenticating: readonly(isAuthenticating),
     error: readonly(error),
     oauthState: readonly(oauthState),
     startOAuth2,
+    reauthorizeAccount,
     exchangeOAuth2Code,
     storeImapCredentials,
+    discoverAccountSettings,
   }
 }
 
@@ -1,10 +1,12 @@
 import { invoke } from '@tauri-apps/api/core'
 import { useMutation, useQuery, useQueryClient } from '@tanstack/vue-query'
 import type {
+  AutocompleteRecipientsRequest,
   Contact,
   ContactSummary,
   GetContactsRequest,
   GetTopContactsRequest,
+  RecipientSuggestion,
 } from '~/types/contact'
 
 const QUERY_KEYS = {
@@ -12,6 +14,7 @@ const QUERY_KEYS = {
   list: () => [...QUERY_KEYS.all, 'list'] as const,
   search: () => [...QUERY_KEYS.all, 'search'] as const,
   searchResults: (query?: string) => [...QUERY_KEYS.search(), { query }] as const,
+  autocomplete: (request: AutocompleteRecipientsRequest) => [...QUERY_KEYS.all, 'autocomplete', request] as const,
   top: () => [...QUERY_KEYS.all, 'top'] as const,
   topList: () => [...QUERY_KEYS.top()] as const,
   details: () => [...QUERY_KEYS.all, 'detail'] as const,
@@ -69,6 +72,17 @@ export function useContacts() {
     })
   }
 
+  const useAutocompleteRecipients = (request: MaybeRef<AutocompleteRecipientsRequest>) => {
+    return useQuery({
+      queryKey: computed(() => QUERY_KEYS.autocomplete(unref(request))),
+      queryFn: async () => {
+        return await invoke<RecipientSuggestion[]>('autocomplete_recipients', {
+          request: unref(request),
+        })
+      },
+    })
+  }
+
   const useGetContactById = (contactId: string | Ref<string>) => {
     return useQuery({
       queryKey: QUERY_KEYS.detail(computed(() =>
@@ -106,6 +120,16 @@ export function useContacts() {
     },
   })
 
+  const setContactVipMutation = useMutation({
+    mutationFn: async ({ contactId, isVip }: { contactId: string, isVip: boolean }) => {
+      await invoke('set_contact_vip', { contactId, isVip })
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: QUERY_KEYS.all })
+      queryClient.invalidateQueries({ queryKey: ['emails'] })
+    },
+  })
+
   const createContactMutation = useMutation({
     mutationFn: async (contact: Contact) => {
       return await invoke<string>('create_contact', { contact })
@@ -205,6 +229,7 @@ export function useContacts() {
     useGetContacts,
     useGetTopContacts,
     useSearchContacts,
+    useAutocompleteRecipients,
     useGetContactById,
     useGetContactByEmail,
 
@@ -213,6 +238,8 @@ export function useContacts() {
     resetContactCountersMutation,
     createContact: createContactMutation.mutateAsync,
     createContactMutation,
+    setContactVip: setContactVipMutation.mutateAsync,
+    setContactVipMutation,
     useUpdateContactMutation,
     useDeleteContactMutation,
 
@@ -1,4 +1,4 @@
-import { useInfiniteQuery, useQuery } from '@tanstack/vue-query'
+import { useInfiniteQuery, useMutation, useQuery, useQueryClient } from '@tanstack/vue-query'
 import { invoke } from '@tauri-apps/api/core'
 
 import type { ConversationDetail, ConversationListItem } from '~/types/conversation'
@@ -27,7 +27,8 @@ const QUERY_KEYS = {
     ] as const,
   details: () => [...QUERY_KEYS.all, 'detail'] as const,
   detail: (id: string) => [...QUERY_KEYS.details(), id] as const,
-  detailByMessage: (messageId: string) => [...QUERY_KEYS.details(), 'message', messageId] as const,
+  detailByMessage: (messageId: string, accountId?: string) =>
+    [...QUERY_KEYS.details(), 'message', messageId, accountId] as const,
 }
 
 export type ConversationFilters = {
@@ -78,6 +79,8 @@ export type ConversationScopeQuery =
     }
 
 export function useConversation() {
+  const queryClient = useQueryClient()
+
   const useGetConversation = (conversationId: MaybeRef<string>) => {
     const resolvedConversationId = computed(() => unref(conversationId))
 
@@ -211,14 +214,21 @@ export function useConversation() {
     )
   }
 
-  const useGetConversationForMessage = (messageId: MaybeRef<string>) => {
+  const useGetConversationForMessage = (
+    messageId: MaybeRef<string>,
+    accountId?: MaybeRef<string | undefined>
+  ) => {
     const resolvedMessageId = computed(() => unref(messageId))
+    const resolvedAccountId = computed(() => unref(accountId))
 
     return useQuery({
-      queryKey: computed(() => QUERY_KEYS.detailByMessage(resolvedMessageId.value)),
+      queryKey: computed(() =>
+        QUERY_KEYS.detailByMessage(resolvedMessageId.value, resolvedAccountId.value)
+      ),
       queryFn: async () => {
-        return await invoke<ConversationDetail>('get_conversation_for_message_id', {
+        return await invoke<ConversationListItem>('get_conversation_for_message_id', {
           messageId: resolvedMessageId.value,
+          accountId: resolvedAccountId.value,
         })
       },
       enabled: computed(() => {
@@ -273,6 +283,28 @@ export function useConversation() {
     )
   }
 
+  /**
+   * Muting archives the conversation and keeps its later messages out of the
+   * inbox, notifications and the badge
+   */
+  const muteConversationMutation = useMutation({
+    mutationFn: async (conversationId: string) => {
+      await invoke('mute_conversation', { conversationId })
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: QUERY_KEYS.all })
+    },
+  })
+
+  const unmuteConversationMutation = useMutation({
+    mutationFn: async (conversationId: string) => {
+      await invoke('unmute_conversation', { conversationId })
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: QUERY_KEYS.all })
+    },
+  })
+
   return {
     useGetConversation,
     useGetConversationsInfinite,
@@ -280,5 +312,7 @@ export function useConversation() {
     useGetConversationsForLabelInfinite,
     useGetConversationsForCombinedScopeInfinite,
     useGetConversationForMessage,
+    muteConversation: muteConversationMutation.mutateAsync,
+    unmuteConversation: unmuteConversationMutation.mutateAsync,
   }
 }
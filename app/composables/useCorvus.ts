@@ -2,6 +2,7 @@ import { useQuery } from '@tanstack/vue-query'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 
+import type { ConversationSummary } from '~/types/conversation'
 import type { EmailAnalysis, EmailDetail } from '~/types/email'
 
 interface ChatMessage {
@@ -58,6 +59,38 @@ interface EmailAnalysisResult {
   error?: string
 }
 
+interface ConversationSummaryResult {
+  summary: ConversationSummary | null
+  error?: string
+}
+
+export interface MailboxAnswer {
+  answer: string
+  /** Ids of the emails the answer is based on, most relevant first */
+  sources: string[]
+}
+
+interface AskMailboxResult {
+  answer: MailboxAnswer | null
+  error?: string
+}
+
+export interface AiAuditEntry {
+  id: string
+  operation: string
+  provider: string
+  model: string
+  /** Emails whose content the request included */
+  email_ids: string[]
+  content: string
+  created_at: string // ISO date string
+}
+
+interface AiAuditLog {
+  entries: AiAuditEntry[]
+  total: number
+}
+
 interface AvailableModel {
   id: string
   name: string
@@ -68,6 +101,19 @@ interface WritingStyleResult {
   error?: string
 }
 
+export interface WritingStyleProfile {
+  account_id: string
+  profile: string
+  sample_count: number
+  created_at: string // ISO date string
+  updated_at: string // ISO date string
+}
+
+interface WritingStyleProfileResult {
+  profile: WritingStyleProfile | null
+  error?: string
+}
+
 const QUERY_KEYS = {
   all: ['corvus'] as const,
   models: () => [...QUERY_KEYS.all, 'models'] as const,
@@ -111,6 +157,19 @@ export function useCorvus() {
   const currentAnalysis = ref<EmailAnalysis | null>(null)
   const analyzingEmailId = ref<string | null>(null)
 
+  const isSummarizing = ref(false)
+  const summaryError = ref<string | null>(null)
+  const conversationSummary = ref<ConversationSummary | null>(null)
+
+  const isLoadingAuditLog = ref(false)
+  const auditLogError = ref<string | null>(null)
+  const auditLog = ref<AiAuditEntry[]>([])
+  const auditLogTotal = ref(0)
+
+  const isAskingMailbox = ref(false)
+  const mailboxError = ref<string | null>(null)
+  const mailboxAnswer = ref<MailboxAnswer | null>(null)
+
   const isLoadingModels = ref(false)
   const modelsError = ref<string | null>(null)
   const availableModels = ref<AvailableModel[]>([])
@@ -120,6 +179,9 @@ export function useCorvus() {
   const writingStyleError = ref<string | null>(null)
   const writingStyle = ref<string | null>(null)
 
+  const isLearningWritingStyle = ref(false)
+  const writingStyleProfile = ref<WritingStyleProfile | null>(null)
+
   const parseAnalysisFromCache = (email: EmailDetail | null | undefined): EmailAnalysis | null => {
     if (!email) return null
     return parseEmailAnalysis(email.ai_cache)
@@ -320,6 +382,99 @@ export function useCorvus() {
     return runEmailAnalysis(email, true)
   }
 
+  const summarizeConversation = async (
+    conversationId: string,
+    forceRefresh = false
+  ): Promise<ConversationSummary | null> => {
+    try {
+      isSummarizing.value = true
+      summaryError.value = null
+      conversationSummary.value = null
+
+      const result = await invoke<ConversationSummaryResult>('summarize_conversation', {
+        conversationId,
+        forceRefresh,
+      })
+
+      if (result.error) {
+        summaryError.value = result.error
+        return null
+      }
+
+      conversationSummary.value = result.summary
+      return result.summary
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Failed to summarize conversation'
+      console.error('summarizeConversation error:', error)
+      summaryError.value = message
+      return null
+    } finally {
+      isSummarizing.value = false
+    }
+  }
+
+  const askMailbox = async (
+    question: string,
+    accountId?: string
+  ): Promise<MailboxAnswer | null> => {
+    try {
+      isAskingMailbox.value = true
+      mailboxError.value = null
+      mailboxAnswer.value = null
+
+      const result = await invoke<AskMailboxResult>('ask_mailbox', { question, accountId })
+
+      if (result.error) {
+        mailboxError.value = result.error
+        return null
+      }
+
+      mailboxAnswer.value = result.answer
+      return result.answer
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Failed to ask mailbox'
+      console.error('askMailbox error:', error)
+      mailboxError.value = message
+      return null
+    } finally {
+      isAskingMailbox.value = false
+    }
+  }
+
+  const fetchAiAuditLog = async (limit = 50, offset = 0): Promise<AiAuditEntry[]> => {
+    try {
+      isLoadingAuditLog.value = true
+      auditLogError.value = null
+
+      const result = await invoke<AiAuditLog>('get_ai_audit_log', { limit, offset })
+      auditLog.value = result.entries
+      auditLogTotal.value = result.total
+      return result.entries
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Failed to fetch AI audit log'
+      console.error('fetchAiAuditLog error:', error)
+      auditLogError.value = message
+      return []
+    } finally {
+      isLoadingAuditLog.value = false
+    }
+  }
+
+  /** Deletes entries older than `olderThanDays`, or all of them */
+  const purgeAiAuditLog = async (olderThanDays?: number): Promise<number> => {
+    try {
+      auditLogError.value = null
+      const purged = await invoke<number>('purge_ai_audit_log', { olderThanDays })
+      await fetchAiAuditLog()
+      return purged
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Failed to purge AI audit log'
+      console.error('purgeAiAuditLog error:', error)
+      auditLogError.value = message
+      return 0
+    }
+  }
+
   const useGetModels = () =>
     useQuery({
       queryKey: QUERY_KEYS.models(),
@@ -413,11 +568,81 @@ export function useCorvus() {
     }
   }
 
+  const getWritingStyleProfile = async (accountId: string): Promise<WritingStyleProfile | null> => {
+    try {
+      writingStyleError.value = null
+      writingStyleProfile.value = await invoke<WritingStyleProfile | null>(
+        'get_writing_style_profile',
+        { accountId }
+      )
+      return writingStyleProfile.value
+    } catch (error) {
+      const message =
+        error instanceof Error ? error.message : 'Failed to fetch writing style profile'
+      console.error('getWritingStyleProfile error:', error)
+      writingStyleError.value = message
+      return null
+    }
+  }
+
+  const learnWritingStyle = async (accountId: string): Promise<WritingStyleProfile | null> => {
+    try {
+      isLearningWritingStyle.value = true
+      writingStyleError.value = null
+
+      const result = await invoke<WritingStyleProfileResult>('learn_writing_style', { accountId })
+
+      if (result.error) {
+        writingStyleError.value = result.error
+        return null
+      }
+
+      writingStyleProfile.value = result.profile
+      return result.profile
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Failed to learn writing style'
+      console.error('learnWritingStyle error:', error)
+      writingStyleError.value = message
+      return null
+    } finally {
+      isLearningWritingStyle.value = false
+    }
+  }
+
+  const deleteWritingStyleProfile = async (accountId: string): Promise<boolean> => {
+    try {
+      writingStyleError.value = null
+      await invoke('delete_writing_style_profile', { accountId })
+      writingStyleProfile.value = null
+      return true
+    } catch (error) {
+      const message =
+        error instanceof Error ? error.message : 'Failed to delete writing style profile'
+      console.error('deleteWritingStyleProfile error:', error)
+      writingStyleError.value = message
+      return false
+    }
+  }
+
   const clearWritingStyleState = () => {
     writingStyle.value = null
     writingStyleError.value = null
     isLoadingWritingStyle.value = false
     isSavingWritingStyle.value = false
+    writingStyleProfile.value = null
+    isLearningWritingStyle.value = false
+  }
+
+  const clearSummaryState = () => {
+    conversationSummary.value = null
+    summaryError.value = null
+    isSummarizing.value = false
+  }
+
+  const clearMailboxState = () => {
+    mailboxAnswer.value = null
+    mailboxError.value = null
+    isAskingMailbox.value = false
   }
 
   const clearAllStates = () => {
@@ -425,6 +650,8 @@ export function useCorvus() {
     clearCompletionState()
     clearSubjectState()
     clearAnalysisState()
+    clearSummaryState()
+    clearMailboxState()
     clearWritingStyleState()
   }
 
@@ -646,6 +873,25 @@ export function useCorvus() {
     clearAnalysisState,
     clearAnalysisCacheForEmail,
 
+    isSummarizing,
+    summaryError,
+    conversationSummary,
+    summarizeConversation,
+    clearSummaryState,
+
+    isLoadingAuditLog,
+    auditLogError,
+    auditLog,
+    auditLogTotal,
+    fetchAiAuditLog,
+    purgeAiAuditLog,
+
+    isAskingMailbox,
+    mailboxError,
+    mailboxAnswer,
+    askMailbox,
+    clearMailboxState,
+
     isLoadingModels,
     modelsError,
     availableModels,
@@ -657,6 +903,11 @@ export function useCorvus() {
     writingStyle,
     getWritingStyle,
     setWritingStyle,
+    isLearningWritingStyle,
+    writingStyleProfile,
+    getWritingStyleProfile,
+    learnWritingStyle,
+    deleteWritingStyleProfile,
     clearWritingStyleState,
 
     parseAnalysisFromCache,
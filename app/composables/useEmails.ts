@@ -2,7 +2,18 @@ import type { InfiniteData } from '@tanstack/vue-query'
 import { useQueryClient } from '@tanstack/vue-query'
 import { invoke } from '@tauri-apps/api/core'
 
-import type { EmailDetail, EmailListItem } from '~/types/email'
+import type {
+  BlockedSender,
+  CalendarEvent,
+  EmailCategory,
+  EmailDetail,
+  EmailListItem,
+  EventSource,
+  ImageAllowlistEntry,
+  RecentlyDeletedEmail,
+  RsvpStatus,
+  SenderCategory,
+} from '~/types/email'
 import type { CalendarDateField } from '~/types/view'
 
 export interface FetchForCalendarRequest {
@@ -236,6 +247,101 @@ export function useEmails() {
     }
   }
 
+  /**
+   * Blocks a sender address or a domain (`example.com` or `@example.com`).
+   * Mail already received from it is moved to junk unless `junkExisting` is
+   * false; resolves to the number of emails moved.
+   */
+  const blockSender = async (sender: string, junkExisting = true): Promise<number> => {
+    error.value = null
+
+    try {
+      const junked = await invoke<number>('block_sender', { sender, junkExisting })
+      if (junked > 0) {
+        await queryClient.invalidateQueries({ queryKey: ['emails'] })
+        await queryClient.invalidateQueries({ queryKey: ['conversations'] })
+        await updateBadgeCount()
+      }
+      return junked
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to block sender:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const unblockSender = async (pattern: string): Promise<void> => {
+    error.value = null
+
+    try {
+      await invoke('unblock_sender', { pattern })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to unblock sender:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const fetchBlockedSenders = async (): Promise<BlockedSender[]> => {
+    error.value = null
+
+    try {
+      return await invoke<BlockedSender[]>('get_blocked_senders')
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to fetch blocked senders:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  /**
+   * Corrects the category of an email. Other mail from its sender follows,
+   * and so does mail synced from them later.
+   */
+  const setCategory = async (emailId: string, category: EmailCategory): Promise<void> => {
+    error.value = null
+
+    try {
+      await invoke('set_category', { emailId, category })
+      await queryClient.invalidateQueries({ queryKey: ['emails'] })
+      await queryClient.invalidateQueries({ queryKey: ['conversations'] })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to set email category:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const fetchSenderCategories = async (): Promise<SenderCategory[]> => {
+    error.value = null
+
+    try {
+      return await invoke<SenderCategory[]>('get_sender_categories')
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to fetch sender categories:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const removeSenderCategory = async (sender: string): Promise<void> => {
+    error.value = null
+
+    try {
+      await invoke('remove_sender_category', { sender })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to remove sender category:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
   const trash = async (emailId: string): Promise<void> => {
     error.value = null
 
@@ -275,6 +381,46 @@ export function useEmails() {
     }
   }
 
+  const getRecentlyDeleted = async (accountId?: string): Promise<RecentlyDeletedEmail[]> => {
+    error.value = null
+
+    try {
+      return await invoke<RecentlyDeletedEmail[]>('get_recently_deleted', { accountId })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to fetch recently deleted emails:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const restoreDeletedEmail = async (emailId: string): Promise<void> => {
+    error.value = null
+
+    try {
+      await invoke('restore_deleted_email', { emailId })
+      await invalidateEmailRelatedCaches()
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to restore email:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const purgeDeletedEmails = async (emailIds: string[]): Promise<number> => {
+    error.value = null
+
+    try {
+      return await invoke<number>('purge_deleted_emails', { emailIds })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to purge deleted emails:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
   const updateImageBlocking = async (
     emailId: string,
     imagesBlocked: boolean,
@@ -302,6 +448,74 @@ export function useEmails() {
     return updateImageBlocking(emailId, false, false)
   }
 
+  const loadRemoteContent = async (
+    emailId: string,
+    alwaysForSender = false
+  ): Promise<EmailDetail> => {
+    error.value = null
+    try {
+      const detail = await invoke<EmailDetail>('load_remote_content', {
+        emailId,
+        alwaysForSender,
+      })
+      updateEmailDetailCache(emailId, () => detail)
+      if (alwaysForSender) {
+        await queryClient.invalidateQueries({ queryKey: ['conversations', 'detail'] })
+      }
+      return detail
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to load remote content:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  /**
+   * Always loads remote images from a sender address or a domain
+   * (`example.com` or `@example.com`)
+   */
+  const alwaysLoadImagesFrom = async (sender: string): Promise<ImageAllowlistEntry> => {
+    error.value = null
+    try {
+      const entry = await invoke<ImageAllowlistEntry>('always_load_images_from', { sender })
+      await queryClient.invalidateQueries({ queryKey: ['emails'] })
+      await queryClient.invalidateQueries({ queryKey: ['conversations', 'detail'] })
+      return entry
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to allow images from sender:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const fetchImageAllowlist = async (): Promise<ImageAllowlistEntry[]> => {
+    error.value = null
+    try {
+      return await invoke<ImageAllowlistEntry[]>('get_image_allowlist')
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to fetch image allowlist:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const removeFromImageAllowlist = async (pattern: string): Promise<void> => {
+    error.value = null
+    try {
+      await invoke('remove_from_image_allowlist', { pattern })
+      await queryClient.invalidateQueries({ queryKey: ['emails'] })
+      await queryClient.invalidateQueries({ queryKey: ['conversations', 'detail'] })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to remove from image allowlist:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
   const addLabelToEmail = async (request: AddLabelToEmailRequest): Promise<void> => {
     error.value = null
 
@@ -422,6 +636,86 @@ export function useEmails() {
     }
   }
 
+  const respondToInvite = async (
+    emailId: string,
+    response: Exclude<RsvpStatus, 'needs_action'>,
+    sendReply = true
+  ): Promise<CalendarEvent> => {
+    error.value = null
+    try {
+      const invite = await invoke<CalendarEvent>('respond_to_invite', {
+        emailId,
+        response,
+        sendReply,
+      })
+      updateEmailDetailCache(emailId, (email) => ({ ...email, invite }))
+      return invite
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to respond to invite:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const fetchPriorityInbox = async (
+    accountId?: string,
+    limit = 50,
+    offset = 0
+  ): Promise<EmailListItem[]> => {
+    isLoading.value = true
+    error.value = null
+    try {
+      return await invoke<EmailListItem[]>('get_priority_inbox', {
+        accountId,
+        limit,
+        offset,
+      })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to fetch priority inbox:', errorMessage)
+      return []
+    } finally {
+      isLoading.value = false
+    }
+  }
+
+  const fetchCalendarEvents = async (start: string, end: string): Promise<CalendarEvent[]> => {
+    try {
+      return await invoke<CalendarEvent[]>('get_calendar_events', { start, end })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to fetch calendar events:', errorMessage)
+      return []
+    }
+  }
+
+  const exportEventIcs = async (event: EventSource, destination: string): Promise<string> => {
+    error.value = null
+    try {
+      return await invoke<string>('export_event_ics', { event, destination })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to export event:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
+  const openInSystemCalendar = async (event: EventSource): Promise<void> => {
+    error.value = null
+    try {
+      await invoke('open_in_system_calendar', { event })
+    } catch (err) {
+      const errorMessage = err instanceof Error ? err.message : String(err)
+      error.value = errorMessage
+      console.error('Failed to open event in calendar:', errorMessage)
+      throw new Error(errorMessage)
+    }
+  }
+
   return {
     isLoading: readonly(isLoading),
     error: readonly(error),
@@ -433,15 +727,33 @@ export function useEmails() {
     move,
     archive,
     junk,
+    blockSender,
+    unblockSender,
+    fetchBlockedSenders,
+    setCategory,
+    fetchSenderCategories,
+    removeSenderCategory,
     trash,
     deleteEmail,
     emptyFolder,
+    getRecentlyDeleted,
+    restoreDeletedEmail,
+    purgeDeletedEmails,
     updateImageBlocking,
     addLabelToEmail,
     removeLabelFromEmail,
     allowImages,
     allowAll,
+    loadRemoteContent,
+    alwaysLoadImagesFrom,
+    fetchImageAllowlist,
+    removeFromImageAllowlist,
     setRemindAt,
     fetchForCalendar,
+    fetchPriorityInbox,
+    respondToInvite,
+    fetchCalendarEvents,
+    exportEventIcs,
+    openInSystemCalendar,
   }
 }
@@ -1,6 +1,6 @@
 import { invoke } from '@tauri-apps/api/core'
 import { useQuery, useMutation, useQueryClient } from '@tanstack/vue-query'
-import type { Account, Folder, FolderType } from '~/types/sync'
+import type { Account, AutoArchivePreview, AutoArchiveRule, Folder, FolderType, SavedSearch } from '~/types/sync'
 import type { SidebarFolderItem } from '~/composables/useSidebarNavigation'
 
 const QUERY_KEYS = {
@@ -9,7 +9,7 @@ const QUERY_KEYS = {
 }
 
 export function useFolders() {
-  // const queryClient = useQueryClient()
+  const queryClient = useQueryClient()
 
   const getFolderSortOrder = (folderType: FolderType): number => {
     const orderMap: Record<FolderType, number> = {
@@ -20,6 +20,7 @@ export function useFolders() {
       archive: 5,
       spam: 6,
       trash: 7,
+      search: 90,
       custom: 100,
     }
     return orderMap[folderType] || 999
@@ -61,6 +62,12 @@ export function useFolders() {
     },
   })
 
+  const useSetSyncEnabledMutation = () => useMutation({
+    mutationFn: async ({ folderId, enabled }: { folderId: string; enabled: boolean }) => {
+      return await invoke('set_sync_enabled', { folderId, enabled })
+    },
+  })
+
   const useRenameMutation = () => useMutation({
     mutationFn: async ({folderId, request} : {folderId: string, request: {
       name: string
@@ -84,6 +91,39 @@ export function useFolders() {
     },
   })
 
+  const previewAutoArchive = async (folderId: string, rule?: AutoArchiveRule) => {
+    return await invoke<AutoArchivePreview>('preview_auto_archive', { folderId, rule: rule ?? null })
+  }
+
+  const getSavedSearches = async (accountId?: string) => {
+    return await invoke<SavedSearch[]>('get_saved_searches', { accountId: accountId ?? null })
+  }
+
+  const useSaveSearchMutation = () => useMutation({
+    mutationFn: async (request: {
+      id?: string
+      account_id: string
+      name: string
+      query: string
+      icon?: string
+      color?: string
+      sort_order?: number
+    }) => {
+      if (request.id) {
+        return await invoke<SavedSearch>('update_saved_search', { request })
+      }
+      return await invoke<SavedSearch>('create_saved_search', { request })
+    },
+    onSuccess: () => queryClient.invalidateQueries({ queryKey: QUERY_KEYS.all }),
+  })
+
+  const useDeleteSavedSearchMutation = () => useMutation({
+    mutationFn: async (savedSearchId: string) => {
+      return await invoke('delete_saved_search', { savedSearchId })
+    },
+    onSuccess: () => queryClient.invalidateQueries({ queryKey: QUERY_KEYS.all }),
+  })
+
   const flatten = (folders: SidebarFolderItem[], level: number = 0) => {
     let result: Array<Folder & { level: number }> = []
     folders.forEach(item => {
@@ -209,10 +249,18 @@ export function useFolders() {
     updateExpanded: useUpdateExpandedMutation().mutateAsync,
     useUpdateHiddenMutation,
     updateHidden: useUpdateHiddenMutation().mutateAsync,
+    useSetSyncEnabledMutation,
+    setSyncEnabled: useSetSyncEnabledMutation().mutateAsync,
     useRenameMutation,
     updateFolderProperties: useRenameMutation().mutateAsync,
     flatten,
     flattenAccountFolders,
     useUpdateSettingsMutation,
+    previewAutoArchive,
+    getSavedSearches,
+    useSaveSearchMutation,
+    saveSearch: useSaveSearchMutation().mutateAsync,
+    useDeleteSavedSearchMutation,
+    deleteSavedSearch: useDeleteSavedSearchMutation().mutateAsync,
   }
 }
@@ -122,6 +122,16 @@ export const useGlobalEventListeners = (queryClient: QueryClient) => {
       name: 'email:deleted',
       invalidateKey: ['emails', 'list'] as const,
     },
+    {
+      type: 'query-invalidation',
+      name: 'email:restored',
+      invalidateKey: ['emails', 'list'] as const,
+    },
+    {
+      type: 'query-invalidation',
+      name: 'email:attachments-cached',
+      invalidateKey: ['conversations', 'detail'] as const,
+    },
     // AI Analysis
     {
       type: 'query-invalidation',
@@ -14,9 +14,40 @@ export type KeybindingListItem = {
   source: 'Default' | 'User'
 }
 
+export type KeyScheme = 'default' | 'gmail' | 'outlook' | 'vim'
+
+export const KEY_SCHEMES: KeyScheme[] = ['default', 'gmail', 'outlook', 'vim']
+
+export type KeyConflict = {
+  kind: 'same_key' | 'shadowed' | 'prefix'
+  context: string
+  key: string
+  action: string
+}
+
+export type BoundKey = {
+  context: string
+  key: string
+  action: string | null
+  props?: unknown
+  customized: boolean
+  conflicts: KeyConflict[]
+}
+
+export type KeyBindingsOverview = {
+  scheme: KeyScheme
+  bindings: BoundKey[]
+}
+
+export type KeyBindingChange = {
+  saved: boolean
+  conflicts: KeyConflict[]
+}
+
 export function useKeybindings() {
   const keybindings = useState<KeyMapFile>('keybindings', () => [])
   const userKeybindings = useState<KeyMapFile>('user-keybindings', () => [])
+  const scheme = useState<KeyScheme>('keybindings-scheme', () => 'default')
 
   async function getKeybindings(): Promise<KeyMapFile> {
     const result = await invoke<KeyMapFile>('get_keybindings')
@@ -53,10 +84,50 @@ export function useKeybindings() {
     await reloadKeybindings()
   }
 
+  async function getBindings(): Promise<KeyBindingsOverview> {
+    const result = await invoke<KeyBindingsOverview>('get_bindings')
+    scheme.value = result.scheme
+    return result
+  }
+
+  /**
+   * Bind a key unless it conflicts with other bindings, which `force` ignores
+   */
+  async function setBinding(
+    context: string,
+    key: string,
+    action: string | null,
+    props?: unknown,
+    force = false,
+  ): Promise<KeyBindingChange> {
+    const change = await invoke<KeyBindingChange>('set_binding', {
+      context,
+      key,
+      action,
+      props,
+      force,
+    })
+    if (change.saved) {
+      await reloadKeybindings()
+    }
+    return change
+  }
+
+  async function resetToDefault(context?: string): Promise<void> {
+    await invoke<KeyBindingsOverview>('reset_to_default', { context: context ?? null })
+    await reloadKeybindings()
+  }
+
+  async function setScheme(value: KeyScheme): Promise<void> {
+    const result = await invoke<KeyBindingsOverview>('set_keymap_scheme', { scheme: value })
+    scheme.value = result.scheme
+    await reloadKeybindings()
+  }
+
   async function reloadKeybindings(): Promise<void> {
     await Promise.all([
-      getKeybindings,
-      getUserKeybindings
+      getKeybindings(),
+      getUserKeybindings()
     ])
   }
 
@@ -67,6 +138,7 @@ export function useKeybindings() {
   if (!keybindings.value.length && !userKeybindings.value.length) {
     getKeybindings().then()
     getUserKeybindings().then()
+    getBindings().then()
   }
 
   const keybindingsList = computed(() => {
@@ -111,10 +183,15 @@ export function useKeybindings() {
     keybindings: readonly(keybindings),
     userKeybindings: readonly(userKeybindings),
     keybindingsList: readonly(keybindingsList),
+    scheme: readonly(scheme),
 
     getKeybindings,
     setKeybinding,
     removeKeybinding,
+    getBindings,
+    setBinding,
+    resetToDefault,
+    setScheme,
     onKeybindingsChanged,
   }
 }
@@ -0,0 +1,32 @@
+import { invoke } from '@tauri-apps/api/core'
+
+import type { MessageFile } from '~/types/email'
+
+export function useMailClient() {
+  const isDefault = useState<boolean | null>('mail-client-is-default', () => null)
+
+  const checkDefault = async () => {
+    isDefault.value = await invoke<boolean>('is_default_mail_client')
+    return isDefault.value
+  }
+
+  /**
+   * Make Ravn the system mail client. Resolves to false on Windows, where
+   * the user picks Ravn in the Default apps settings that are opened instead.
+   */
+  const makeDefault = async () => {
+    isDefault.value = await invoke<boolean>('set_default_mail_client')
+    return isDefault.value
+  }
+
+  const readMessageFile = async (path: string) => {
+    return await invoke<MessageFile>('read_message_file', { path })
+  }
+
+  return {
+    isDefault,
+    checkDefault,
+    makeDefault,
+    readMessageFile,
+  }
+}
@@ -15,6 +15,11 @@ interface BadgeCount {
   mode?: BadgeType | 'none'
 }
 
+interface UnreadCounts {
+  folders: Record<string, number>
+  views: Record<string, number>
+}
+
 interface NotificationEmailPreview {
   id?: string
   accountId?: string
@@ -27,8 +32,11 @@ interface NotificationEmailPreview {
   avatarUrl?: string | null
   remindAt?: string | null
   navigationTarget?: string | null
+  important?: boolean
 }
 
+type NotificationAction = 'archive' | 'mark-read' | 'reply'
+
 interface NativeNotificationPayload {
   kind?: 'incoming-email' | 'outgoing-email' | 'reminder-email' | 'system'
   title?: string
@@ -38,6 +46,8 @@ interface NativeNotificationPayload {
   suppressDuringBootstrap?: boolean
   tag?: string
   deepLink?: string | null
+  actions?: NotificationAction[]
+  emailIds?: string[]
 }
 
 type BadgeType = 'count' | 'dot' | null
@@ -463,6 +473,15 @@ export const useNotifications = () => {
     }
   }
 
+  const getUnreadCounts = async (): Promise<UnreadCounts | null> => {
+    try {
+      return await invoke<UnreadCounts>('get_unread_counts')
+    } catch (error) {
+      console.error('Failed to get unread counts:', error)
+      return null
+    }
+  }
+
   const testNotificationSound = async (soundName: string) => {
     try {
       await invoke('test_notification_sound', { soundName })
@@ -607,6 +626,7 @@ export const useNotifications = () => {
     playSound,
     updateBadgeCount,
     getBadgeCount,
+    getUnreadCounts,
     testNotificationSound,
     showNativeNotification,
     checkDueReminderNotifications,
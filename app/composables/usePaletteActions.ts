@@ -0,0 +1,62 @@
+import { invoke } from '@tauri-apps/api/core'
+
+export type PaletteActionCategory = 'navigate' | 'sync' | 'label' | 'move' | 'compose'
+
+export const PALETTE_ACTION_CATEGORIES: PaletteActionCategory[] = ['compose', 'navigate', 'label', 'move', 'sync']
+
+export type PaletteActionTarget =
+  | { type: 'navigate', url: string }
+  | { type: 'run', action: string, props: unknown }
+  | { type: 'invoke', command: string, args: Record<string, unknown> }
+
+export interface PaletteAction {
+  id: string
+  category: PaletteActionCategory
+  title: string
+  subtitle: string | null
+  icon: string | null
+  color: string | null
+  shortcut: string | null
+  context: string
+  account_id: string | null
+  target: PaletteActionTarget
+}
+
+/**
+ * Actions the command palette offers, as listed by the backend registry
+ */
+export function usePaletteActions() {
+  const paletteActions = useState<PaletteAction[]>('palette-actions', () => [])
+
+  const { executeAction } = useActions()
+  const router = useRouter()
+
+  /**
+   * Load the actions that apply in `context`, or all of them
+   */
+  const loadActions = async (context?: string) => {
+    paletteActions.value = await invoke<PaletteAction[]>('get_actions', { context: context ?? null })
+    return paletteActions.value
+  }
+
+  const runAction = async (action: PaletteAction) => {
+    const target = action.target
+    switch (target.type) {
+      case 'navigate':
+        await router.push(target.url.replace('ravn://', '/'))
+        break
+      case 'run':
+        executeAction(target.action, null, target.props ?? undefined)
+        break
+      case 'invoke':
+        await invoke(target.command, target.args)
+        break
+    }
+  }
+
+  return {
+    paletteActions: readonly(paletteActions),
+    loadActions,
+    runAction,
+  }
+}
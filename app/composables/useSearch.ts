@@ -12,9 +12,23 @@ export interface SearchOptions {
   offset?: number
 }
 
+export interface MatchedAttachment {
+  id: string
+  filename: string
+  file_type: string
+}
+
+/** A fragment of a matching field; matched terms are wrapped in `<mark>`, the rest is HTML-escaped */
+export interface SearchHighlight {
+  field: 'subject' | 'body' | 'from' | 'to' | 'cc' | 'filename'
+  snippet: string
+}
+
 export interface SearchResults {
   emails: EmailListItem[]
   total: number
+  matched_attachments: Record<string, MatchedAttachment[]>
+  highlights: Record<string, SearchHighlight[]>
 }
 
 export interface ReindexResult {
@@ -22,6 +36,22 @@ export interface ReindexResult {
   success: boolean
 }
 
+/** Payload of `search:reindex-progress` events */
+export interface ReindexProgress {
+  indexed: number
+  total: number
+  started_at: string
+  completed: boolean
+  error?: string
+}
+
+export interface IndexStatus {
+  indexed_documents: number
+  reindexing: boolean
+  interrupted: boolean
+  progress: ReindexProgress | null
+}
+
 export function useSearch() {
   const loading = ref(false)
   const emails = ref<EmailListItem[]>([])
@@ -66,14 +96,15 @@ export function useSearch() {
   }
 
   /**
-   * Reindex all emails in the search index
+   * Start rebuilding the search index in the background; progress is emitted
+   * as `search:reindex-progress` events
    */
-  const reindexAll = async (): Promise<ReindexResult | null> => {
+  const reindexAll = async (): Promise<IndexStatus | null> => {
     loading.value = true
     error.value = null
 
     try {
-      const result = await invoke<ReindexResult>('reindex_all_emails')
+      const result = await invoke<IndexStatus>('reindex_all_emails')
       return result
     } catch (err) {
       console.error('Reindex failed:', err)
@@ -84,6 +115,10 @@ export function useSearch() {
     }
   }
 
+  const getIndexStatus = async (): Promise<IndexStatus> => {
+    return await invoke<IndexStatus>('get_index_status')
+  }
+
   /**
    * Reindex emails for a specific account
    */
@@ -116,5 +151,6 @@ export function useSearch() {
     search,
     reindexAll,
     reindexAccount,
+    getIndexStatus,
   }
 }
@@ -0,0 +1,113 @@
+import { invoke } from '@tauri-apps/api/core'
+import { getCurrentWindow } from '@tauri-apps/api/window'
+
+type SessionWindowType = 'main' | 'message' | 'compose'
+
+export interface SessionViewState {
+  account_id?: string
+  folder_id?: string
+  view_id?: string
+  conversation_id?: string
+  /** First visible conversation of each list, by list key */
+  scroll_anchors?: Record<string, string>
+}
+
+interface SessionWindow {
+  label: string
+  window_type: SessionWindowType
+  route?: string
+  view_state?: SessionViewState
+}
+
+/** How long the route and anchors have to settle before they are saved */
+const SAVE_DELAY_MS = 1000
+
+/**
+ * Session type of a window, `null` for windows that are not restored
+ * (account setup, OAuth, printing)
+ */
+function sessionWindowType(label: string): SessionWindowType | null {
+  if (label === 'main') return 'main'
+  if (label.startsWith('compose-')) return 'compose'
+  if (label.startsWith('message-')) return 'message'
+  return null
+}
+
+function routeParam(value: string | string[] | undefined): string | undefined {
+  return Array.isArray(value) ? value[0] : value
+}
+
+export function useSession() {
+  const scrollAnchors = useState<Record<string, string>>('session-scroll-anchors', () => ({}))
+
+  const scrollAnchor = (key: string) => scrollAnchors.value[key]
+
+  const setScrollAnchor = (key: string, conversationId: string) => {
+    if (scrollAnchors.value[key] === conversationId) return
+    scrollAnchors.value = { ...scrollAnchors.value, [key]: conversationId }
+  }
+
+  /**
+   * Load the scroll anchors of the previous run and save the route, selection
+   * and scroll anchors of this window whenever they change, so the next start
+   * reopens the same place. Geometry is recorded by the backend.
+   */
+  const watchSession = () => {
+    if (!import.meta.client) return
+
+    const route = useRoute()
+    const label = getCurrentWindow().label
+    const windowType = sessionWindowType(label)
+    if (!windowType) return
+
+    let saveTimer: ReturnType<typeof setTimeout> | undefined
+
+    const save = () => {
+      const viewState: SessionViewState = {
+        account_id: routeParam(route.params.account_id),
+        folder_id: routeParam(route.params.folder_id),
+        view_id: routeParam(route.params.view),
+        conversation_id: routeParam(route.params.conversation),
+        scroll_anchors: scrollAnchors.value,
+      }
+
+      invoke('save_session_window', {
+        label,
+        windowType,
+        route: route.fullPath,
+        viewState,
+      }).catch((err) => {
+        console.debug('[useSession] Failed to save session window:', err)
+      })
+    }
+
+    const scheduleSave = () => {
+      clearTimeout(saveTimer)
+      saveTimer = setTimeout(save, SAVE_DELAY_MS)
+    }
+
+    onMounted(async () => {
+      try {
+        const session = await invoke<SessionWindow[]>('get_session')
+        const saved = session.find((window) => window.label === label)?.view_state?.scroll_anchors
+        if (saved) {
+          scrollAnchors.value = { ...saved, ...scrollAnchors.value }
+        }
+      } catch (err) {
+        console.debug('[useSession] Failed to load session:', err)
+      }
+
+      watch([() => route.fullPath, scrollAnchors], scheduleSave)
+    })
+
+    onBeforeUnmount(() => {
+      clearTimeout(saveTimer)
+    })
+  }
+
+  return {
+    scrollAnchor,
+    setScrollAnchor,
+    watchSession,
+  }
+}
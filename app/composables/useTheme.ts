@@ -1,10 +1,43 @@
 import { invoke } from '@tauri-apps/api/core'
-import { onMounted, ref } from 'vue'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import { onMounted, onUnmounted, ref } from 'vue'
+
+export type Appearance = 'light' | 'dark'
+
+export type ThemeMode = 'manual' | 'system' | 'schedule'
 
 export interface ThemeInfo {
   id: string
   name: string
   source: 'builtin' | 'user'
+  appearance: Appearance | null
+}
+
+/**
+ * A user theme as stored and exchanged in theme files
+ */
+export interface CustomTheme {
+  version: number
+  name: string
+  appearance: Appearance
+  base: string | null
+  tokens: Record<string, string>
+}
+
+export interface ThemeModeSettings {
+  mode: ThemeMode
+  lightTheme: string
+  darkTheme: string
+  latitude: number | null
+  longitude: number | null
+  lightFrom: string
+  darkFrom: string
+}
+
+interface ThemeChangedPayload {
+  themeId: string
+  mode: ThemeMode
+  appearance: Appearance | null
 }
 
 export function useTheme() {
@@ -13,7 +46,7 @@ export function useTheme() {
   const isLoading = ref(false)
   const error = ref<string | null>(null)
 
-  let themeStyleElement: HTMLStyleElement | null = null
+  let unlistenThemeChanged: UnlistenFn | null = null
 
   const listThemes = async () => {
     try {
@@ -44,11 +77,9 @@ export function useTheme() {
   }
 
   const loadThemeCSS = (css: string) => {
-    if (themeStyleElement) {
-      themeStyleElement.remove()
-    }
+    document.head.querySelectorAll('style[data-theme-style]').forEach(element => element.remove())
 
-    themeStyleElement = document.createElement('style')
+    const themeStyleElement = document.createElement('style')
     themeStyleElement.setAttribute('data-theme-style', 'true')
     themeStyleElement.textContent = css
     document.head.appendChild(themeStyleElement)
@@ -85,6 +116,48 @@ export function useTheme() {
     }
   }
 
+  const getThemeMode = async () => {
+    return await invoke<ThemeModeSettings>('get_theme_mode')
+  }
+
+  const setThemeMode = async (modeSettings: ThemeModeSettings) => {
+    currentTheme.value = await invoke<string>('set_theme_mode', { modeSettings })
+  }
+
+  /**
+   * Create a user theme with the tokens of `baseThemeId`
+   */
+  const createTheme = async (name: string, baseThemeId: string, appearance: Appearance) => {
+    const theme = await invoke<ThemeInfo>('create_theme', { name, baseThemeId, appearance })
+    await listThemes()
+    return theme
+  }
+
+  const getCustomTheme = async (themeId: string) => {
+    return await invoke<CustomTheme>('get_custom_theme', { themeId })
+  }
+
+  const updateTheme = async (themeId: string, theme: CustomTheme) => {
+    const info = await invoke<ThemeInfo>('update_theme', { themeId, theme })
+    await listThemes()
+    return info
+  }
+
+  const deleteTheme = async (themeId: string) => {
+    await invoke('delete_theme', { themeId })
+    await listThemes()
+  }
+
+  const importTheme = async (path: string) => {
+    const theme = await invoke<ThemeInfo>('import_theme', { path })
+    await listThemes()
+    return theme
+  }
+
+  const exportTheme = async (themeId: string, destination: string) => {
+    await invoke('export_theme', { themeId, destination })
+  }
+
   const initializeTheme = async () => {
     try {
       const themeId = await getCurrentTheme()
@@ -104,8 +177,24 @@ export function useTheme() {
     }
   }
 
-  onMounted(() => {
+  onMounted(async () => {
     initializeTheme()
+
+    // The theme mode switches themes from the backend, e.g. when the OS
+    // appearance changes
+    unlistenThemeChanged = await listen<ThemeChangedPayload>('theme-changed', async ({ payload }) => {
+      try {
+        const css = await invoke<string>('get_theme', { themeId: payload.themeId })
+        loadThemeCSS(css)
+        currentTheme.value = payload.themeId
+      } catch (err) {
+        console.error('Failed to apply theme:', err)
+      }
+    })
+  })
+
+  onUnmounted(() => {
+    unlistenThemeChanged?.()
   })
 
   return {
@@ -119,5 +208,13 @@ export function useTheme() {
     previewTheme,
     switchTheme,
     initializeTheme,
+    getThemeMode,
+    setThemeMode,
+    createTheme,
+    getCustomTheme,
+    updateTheme,
+    deleteTheme,
+    importTheme,
+    exportTheme,
   }
 }
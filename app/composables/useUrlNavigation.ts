@@ -106,7 +106,7 @@ export const navigate = {
   toEmail: (emailId: string) => navigateToUrl(`ravn://mail/${emailId}`),
 }
 
-function parseComposeSeed(url: string): ComposerSeed {
+export function parseComposeSeed(url: string): ComposerSeed {
   const params = new URLSearchParams(url.split('?')[1] || '')
 
   return {
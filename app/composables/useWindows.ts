@@ -0,0 +1,49 @@
+import { invoke } from '@tauri-apps/api/core'
+import { getCurrentWindow } from '@tauri-apps/api/window'
+
+/**
+ * What a compose window starts with. Addresses, subject and body seed a new
+ * message; the ids open a draft, a reply or a forward instead.
+ */
+export interface ComposeWindowRequest {
+  account_id?: string
+  to?: string[]
+  cc?: string[]
+  bcc?: string[]
+  subject?: string
+  body?: string
+  draft_id?: string
+  reply_to?: string
+  reply_all?: boolean
+  forward?: string
+}
+
+/**
+ * Whether this is a message or compose window rather than the main window
+ */
+export function isDetachedWindow(): boolean {
+  if (!import.meta.client) return false
+
+  const label = getCurrentWindow().label
+  return label.startsWith('compose-') || label.startsWith('message-')
+}
+
+export function useWindows() {
+  const openComposeWindow = async (request?: ComposeWindowRequest) => {
+    await invoke('open_compose_window', { request: request ?? null })
+  }
+
+  const openEmailWindow = async (emailId: string) => {
+    await invoke('open_email_window', { emailId })
+  }
+
+  const closeCurrentWindow = async () => {
+    await getCurrentWindow().close()
+  }
+
+  return {
+    openComposeWindow,
+    openEmailWindow,
+    closeCurrentWindow,
+  }
+}
@@ -15,6 +15,18 @@ export const settingsManifest: SettingsManifest = [
             description: 'settings.appearance.theme.description',
             is: 'ThemeSelector',
           },
+          {
+            id: 'appearance.themeMode',
+            name: 'settings.appearance.themeMode.name',
+            description: 'settings.appearance.themeMode.description',
+            is: 'ThemeMode',
+          },
+          {
+            id: 'appearance.customThemes',
+            name: 'settings.appearance.customThemes.name',
+            description: 'settings.appearance.customThemes.description',
+            is: 'CustomThemes',
+          },
           {
             id: 'appearance.uiScale',
             name: 'settings.appearance.uiScale.name',
@@ -148,6 +160,83 @@ export const settingsManifest: SettingsManifest = [
           },
         ],
       },
+      {
+        id: 'analysis',
+        name: 'settings.ai.analysis.section',
+        items: [
+          {
+            id: 'ai.analysis.batchSize',
+            name: 'settings.ai.analysis.batchSize.name',
+            description: 'settings.ai.analysis.batchSize.description',
+            is: 'Number',
+            props: {
+              min: 1,
+              max: 20,
+              step: 1,
+            },
+          },
+          {
+            id: 'ai.analysis.concurrency',
+            name: 'settings.ai.analysis.concurrency.name',
+            description: 'settings.ai.analysis.concurrency.description',
+            is: 'Number',
+            props: {
+              min: 1,
+              max: 10,
+              step: 1,
+            },
+          },
+          {
+            id: 'ai.analysis.tokensPerMinute',
+            name: 'settings.ai.analysis.tokensPerMinute.name',
+            description: 'settings.ai.analysis.tokensPerMinute.description',
+            is: 'Number',
+            props: {
+              min: 0,
+              max: 1000000,
+              step: 1000,
+            },
+          },
+          {
+            id: 'ai.analysis.maxAttempts',
+            name: 'settings.ai.analysis.maxAttempts.name',
+            description: 'settings.ai.analysis.maxAttempts.description',
+            is: 'Number',
+            props: {
+              min: 1,
+              max: 20,
+              step: 1,
+            },
+          },
+        ],
+      },
+      {
+        id: 'privacy',
+        name: 'settings.ai.privacy.section',
+        items: [
+          {
+            id: 'ai.audit.enabled',
+            name: 'settings.ai.privacy.auditEnabled.name',
+            description: 'settings.ai.privacy.auditEnabled.description',
+            is: 'Toggle',
+          },
+          {
+            id: 'ai.exclude.folders',
+            name: 'settings.ai.privacy.excludeFolders.name',
+            description: 'settings.ai.privacy.excludeFolders.description',
+            is: 'FolderSelector',
+            props: {
+              multiple: true,
+            },
+          },
+          {
+            id: 'ai.exclude.labels',
+            name: 'settings.ai.privacy.excludeLabels.name',
+            description: 'settings.ai.privacy.excludeLabels.description',
+            is: 'LabelSelector',
+          },
+        ],
+      },
       {
         id: 'writingStyle',
         name: 'settings.ai.writingStyle.section',
@@ -163,6 +252,12 @@ export const settingsManifest: SettingsManifest = [
               cols: 64,
             },
           },
+          {
+            id: 'ai.writingStyle.learnFromSent',
+            name: 'settings.ai.writingStyle.learnFromSent.name',
+            description: 'settings.ai.writingStyle.learnFromSent.description',
+            is: 'Toggle',
+          },
         ],
       },
       {
@@ -213,6 +308,39 @@ export const settingsManifest: SettingsManifest = [
               cols: 64,
             },
           },
+          {
+            id: 'ai.prompts.summarizeConversation',
+            name: 'settings.ai.prompts.summarizeConversation.name',
+            description: 'settings.ai.prompts.summarizeConversation.description',
+            is: 'Textarea',
+            props: {
+              autosize: true,
+              rows: 8,
+              cols: 64,
+            },
+          },
+          {
+            id: 'ai.prompts.learnWritingStyle',
+            name: 'settings.ai.prompts.learnWritingStyle.name',
+            description: 'settings.ai.prompts.learnWritingStyle.description',
+            is: 'Textarea',
+            props: {
+              autosize: true,
+              rows: 8,
+              cols: 64,
+            },
+          },
+          {
+            id: 'ai.prompts.askMailbox',
+            name: 'settings.ai.prompts.askMailbox.name',
+            description: 'settings.ai.prompts.askMailbox.description',
+            is: 'Textarea',
+            props: {
+              autosize: true,
+              rows: 8,
+              cols: 64,
+            },
+          },
           {
             id: 'ai.prompts.generateSearchQuery',
             name: 'settings.ai.prompts.generateSearchQuery.name',
@@ -258,6 +386,18 @@ export const settingsManifest: SettingsManifest = [
     id: 'email',
     name: 'settings.groups.email.name',
     sections: [
+      {
+        id: 'defaultClient',
+        name: 'settings.email.defaultClient.section',
+        items: [
+          {
+            id: 'email.defaultClient',
+            name: 'settings.email.defaultClient.name',
+            description: 'settings.email.defaultClient.description',
+            is: 'DefaultMailClient',
+          },
+        ],
+      },
       {
         id: 'display',
         name: 'settings.email.display.section',
@@ -306,6 +446,87 @@ export const settingsManifest: SettingsManifest = [
           },
         ],
       },
+      {
+        id: 'attachments',
+        name: 'settings.email.attachments.section',
+        items: [
+          {
+            id: 'email.attachments.downloadPolicy',
+            name: 'settings.email.attachments.downloadPolicy.name',
+            description: 'settings.email.attachments.downloadPolicy.description',
+            is: 'Select',
+            props: {
+              options: [
+                { label: 'Metadata only', value: 'metadata' },
+                { label: 'Inline images', value: 'inline' },
+                { label: 'Up to size limit', value: 'threshold' },
+                { label: 'All attachments', value: 'full' },
+              ],
+            },
+          },
+          {
+            id: 'email.attachments.sizeThreshold',
+            name: 'settings.email.attachments.sizeThreshold.name',
+            description: 'settings.email.attachments.sizeThreshold.description',
+            is: 'Number',
+            props: {
+              min: 1,
+              max: 100,
+              step: 1,
+            },
+          },
+          {
+            id: 'storage.attachmentCacheMaxMb',
+            name: 'settings.storage.attachmentCacheMaxMb.name',
+            description: 'settings.storage.attachmentCacheMaxMb.description',
+            is: 'Number',
+            props: {
+              min: 0,
+              step: 256,
+            },
+          },
+        ],
+      },
+      {
+        id: 'credentials',
+        name: 'settings.storage.credentials.section',
+        items: [
+          {
+            id: 'storage.systemKeychain',
+            name: 'settings.storage.systemKeychain.name',
+            description: 'settings.storage.systemKeychain.description',
+            is: 'Toggle',
+          },
+        ],
+      },
+    ],
+  },
+  {
+    id: 'security',
+    name: 'settings.groups.security.name',
+    sections: [
+      {
+        id: 'lock',
+        name: 'settings.security.lock.section',
+        items: [
+          {
+            id: 'security.passphrase',
+            name: 'settings.security.passphrase.name',
+            description: 'settings.security.passphrase.description',
+            is: 'AppLockPassphrase',
+          },
+          {
+            id: 'security.autoLockMinutes',
+            name: 'settings.security.autoLockMinutes.name',
+            description: 'settings.security.autoLockMinutes.description',
+            is: 'Number',
+            props: {
+              min: 0,
+              step: 5,
+            },
+          },
+        ],
+      },
     ],
   },
   {
@@ -424,6 +645,66 @@ export const settingsManifest: SettingsManifest = [
               multiple: true,
             },
           },
+          {
+            id: 'notifications.disabledAccounts',
+            name: 'settings.notifications.disabledAccounts.name',
+            description: 'settings.notifications.disabledAccounts.description',
+            is: 'AccountSelector',
+          },
+        ],
+      },
+      {
+        id: 'rules',
+        name: 'settings.notifications.rules.section',
+        items: [
+          {
+            id: 'notifications.vipOnly',
+            name: 'settings.notifications.vipOnly.name',
+            description: 'settings.notifications.vipOnly.description',
+            is: 'Toggle',
+          },
+          {
+            id: 'notifications.quietHoursEnabled',
+            name: 'settings.notifications.quietHoursEnabled.name',
+            description: 'settings.notifications.quietHoursEnabled.description',
+            is: 'Toggle',
+          },
+          {
+            id: 'notifications.quietHoursStart',
+            name: 'settings.notifications.quietHoursStart.name',
+            description: 'settings.notifications.quietHoursStart.description',
+            is: 'Input',
+            props: {
+              type: 'time',
+            },
+          },
+          {
+            id: 'notifications.quietHoursEnd',
+            name: 'settings.notifications.quietHoursEnd.name',
+            description: 'settings.notifications.quietHoursEnd.description',
+            is: 'Input',
+            props: {
+              type: 'time',
+            },
+          },
+          {
+            id: 'notifications.alwaysNotifyFolders',
+            name: 'settings.notifications.alwaysNotifyFolders.name',
+            description: 'settings.notifications.alwaysNotifyFolders.description',
+            is: 'FolderSelector',
+            props: {
+              multiple: true,
+            },
+          },
+          {
+            id: 'notifications.silentFolders',
+            name: 'settings.notifications.silentFolders.name',
+            description: 'settings.notifications.silentFolders.description',
+            is: 'FolderSelector',
+            props: {
+              multiple: true,
+            },
+          },
         ],
       },
       {
@@ -443,6 +724,19 @@ export const settingsManifest: SettingsManifest = [
               ],
             },
           },
+          {
+            id: 'notifications.badgeSource',
+            name: 'settings.notifications.badgeSource.name',
+            description: 'settings.notifications.badgeSource.description',
+            is: 'Select',
+            props: {
+              options: [
+                { label: 'All Accounts', value: 'all' },
+                { label: 'Selected Folders', value: 'folders' },
+                { label: 'View', value: 'view' },
+              ],
+            },
+          },
           {
             id: 'notifications.badgeFolders',
             name: 'settings.notifications.badgeFolders.name',
@@ -452,6 +746,12 @@ export const settingsManifest: SettingsManifest = [
               multiple: true,
             },
           },
+          {
+            id: 'notifications.badgeView',
+            name: 'settings.notifications.badgeView.name',
+            description: 'settings.notifications.badgeView.description',
+            is: 'ViewSelector',
+          },
         ],
       },
       {
@@ -10,6 +10,7 @@ export interface Contact {
   source: 'observed' | 'imported' | 'manual'
   avatar_type: 'gravatar' | 'unavatar' | 'favicon' | 'none' | 'unprocessed'
   avatar_path: string
+  is_vip: boolean
   send_count: number
   receive_count: number
   last_used_at: string | null
@@ -35,6 +36,23 @@ export interface SearchContactsRequest {
   limit?: number
 }
 
+export type ContactField = 'from' | 'to' | 'cc' | 'bcc'
+
+export interface AutocompleteRecipientsRequest {
+  query: string
+  account_id?: string
+  field?: ContactField
+  limit?: number
+}
+
+export interface RecipientSuggestion {
+  id: string
+  email: string
+  display_name: string | null
+  avatar_path: string | null
+  score: number
+}
+
 export interface GetTopContactsRequest {
   limit?: number
 }
@@ -44,3 +62,18 @@ export interface GetContactsRequest {
   limit?: number
   offset?: number
 }
+
+export type EncryptionPolicy = 'auto' | 'always_pgp' | 'require_smime' | 'plaintext_only'
+
+export type PgpKeySource = 'manual' | 'wkd' | 'keyserver'
+
+export interface ContactSecurity {
+  email: string
+  policy: EncryptionPolicy
+  pgp_fingerprint: string | null
+  pgp_public_key: string | null
+  pgp_key_source: PgpKeySource | null
+  pgp_key_fetched_at: string | null
+  created_at: string
+  updated_at: string
+}
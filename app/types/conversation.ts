@@ -8,6 +8,7 @@ export interface ConversationListItem {
   id: string
   message_count: number
   ai_cache?: string
+  muted: boolean
   messages: EmailListItem[]
 }
 
@@ -19,6 +20,17 @@ export interface ConversationDetail {
   id: string
   message_count: number
   ai_cache?: string
+  muted: boolean
   attachments: AttachmentInfo[]
   messages: EmailDetail[]
 }
+
+/**
+ * AI summary of a whole conversation, cached in `ai_cache` until a new
+ * message arrives
+ */
+export interface ConversationSummary {
+  summary: string
+  message_count: number
+  generated_at: string // ISO date string
+}
@@ -1,3 +1,5 @@
+import type { FolderType } from './sync'
+
 export interface EmailAddress {
   address: string
   name?: string
@@ -10,6 +12,12 @@ export interface LabelInfo {
   icon?: string
 }
 
+export interface FolderInfo {
+  id: string
+  name: string
+  folder_type: FolderType
+}
+
 export interface AttachmentInfo {
   id: string
   email_id: string
@@ -23,6 +31,57 @@ export interface AttachmentInfo {
   hash: string
 }
 
+export type RsvpStatus = 'needs_action' | 'accepted' | 'tentative' | 'declined'
+
+export interface CalendarAttendee {
+  address: string
+  name?: string
+  rsvp_status?: RsvpStatus
+}
+
+/**
+ * Event of a meeting invitation received by email
+ */
+export interface CalendarEvent {
+  id: string
+  account_id: string
+  email_id: string
+  uid: string
+  sequence: number
+  method: string
+  summary?: string
+  description?: string
+  location?: string
+  starts_at: string // ISO date string
+  ends_at?: string // ISO date string
+  all_day: boolean
+  organizer?: EmailAddress
+  attendees: CalendarAttendee[]
+  attendee_email?: string
+  cancelled: boolean
+  rsvp_status: RsvpStatus
+  responded_at?: string // ISO date string
+  created_at: string // ISO date string
+  updated_at: string // ISO date string
+}
+
+/**
+ * Event to save as .ics or add to the system calendar: the invitation of an
+ * email, or an event detected in its text
+ */
+export type EventSource =
+  | { type: 'invite'; email_id: string }
+  | {
+      type: 'detected'
+      summary: string
+      description?: string
+      location?: string
+      starts_at: string // ISO date string
+      ends_at?: string // ISO date string
+      all_day?: boolean
+      email_id?: string
+    }
+
 export type EmailCategory = 'personal' | 'transactions' | 'updates' | 'promotions'
 
 /**
@@ -56,10 +115,27 @@ export interface EmailListItem {
   sync_status: string
   has_attachments: boolean
   size: number
+  /** Sum of the priority signals: VIP sender (4), reply to my thread (2), direct to me (1) */
+  priority: number
+  /** Phishing risk from 0 to 100; 50 and above is likely phishing */
+  risk_score: number
 
   labels: LabelInfo[]
+  /** Set in views spanning folders, like a whole conversation */
+  folder?: FolderInfo
+  /** Color of the email's account */
+  account_color?: string | null
 }
 
+/**
+ * Why an email looks like phishing or spoofing
+ */
+export type RiskReason =
+  | { kind: 'authentication_failed'; method: 'spf' | 'dkim' | 'dmarc'; result: string }
+  | { kind: 'reply_to_mismatch'; from_domain: string; reply_to_domain: string }
+  | { kind: 'lookalike_domain'; domain: string; resembles: string }
+  | { kind: 'suspicious_link'; text: string; url: string }
+
 /**
  * Full email data for detail view
  * Includes all fields and related data
@@ -91,6 +167,10 @@ export interface EmailDetail extends EmailListItem {
   updated_at: string // ISO date string
 
   attachments: AttachmentInfo[]
+  invite?: CalendarEvent
+  trackers: string[]
+  blocked_images: number
+  risk_reasons: RiskReason[]
 }
 
 /**
@@ -154,3 +234,86 @@ export interface Attachment {
   content_id?: string
   full_path?: string
 }
+
+/**
+ * Deleted email still kept locally within the retention period
+ */
+export interface RecentlyDeletedEmail {
+  email: EmailListItem
+  deleted_at: string // ISO date string
+  /** The deletion has not reached the provider yet */
+  restorable: boolean
+}
+
+export type SenderPatternKind = 'sender' | 'domain'
+
+/**
+ * Sender or domain whose remote images are always loaded
+ */
+export interface ImageAllowlistEntry {
+  /** Lowercased address, or domain covering its subdomains too */
+  pattern: string
+  kind: SenderPatternKind
+  created_at: string // ISO date string
+}
+
+/**
+ * Sender or domain whose mail goes straight to junk
+ */
+export interface BlockedSender {
+  /** Lowercased address, or domain covering its subdomains too */
+  pattern: string
+  kind: SenderPatternKind
+  created_at: string // ISO date string
+}
+
+/**
+ * Category the user picked for a sender, kept for their future mail
+ */
+export interface SenderCategory {
+  /** Lowercased sender address */
+  sender: string
+  category: EmailCategory
+  corrections: number
+  created_at: string // ISO date string
+  updated_at: string // ISO date string
+}
+
+export interface AttachmentCacheUsage {
+  used_bytes: number
+  /** Null when the cache size is unlimited */
+  quota_bytes: number | null
+}
+
+export type AttachmentDownloadStatus = 'downloading' | 'completed' | 'cancelled' | 'failed'
+
+export interface AttachmentDownloadProgress {
+  attachment_id: string
+  email_id: string
+  downloaded: number
+  total: number | null
+  status: AttachmentDownloadStatus
+  error: string | null
+}
+
+/**
+ * A message read from an `.eml` file, shown without being stored
+ */
+export interface MessageFile {
+  path: string
+  from: EmailAddress
+  to: EmailAddress[]
+  cc: EmailAddress[]
+  subject?: string
+  sent_at?: string // ISO date string
+  /** Remote images and trackers are left out */
+  body_html?: string
+  body_plain?: string
+  attachments: MessageFileAttachment[]
+}
+
+export interface MessageFileAttachment {
+  filename: string
+  content_type: string
+  size: number
+}
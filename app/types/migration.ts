@@ -0,0 +1,9 @@
+export type MigrationPhase = 'backup' | 'schema' | 'data' | 'rolling_back' | 'done' | 'failed'
+
+export interface MigrationProgress {
+  phase: MigrationPhase
+  step: string
+  completed: number
+  total: number
+  error: string | null
+}
@@ -57,9 +57,15 @@ export interface EmailReminderSettings {
   presets: ReminderPresetSetting[]
 }
 
+export interface EmailAttachmentSettings {
+  downloadPolicy: 'metadata' | 'inline' | 'threshold' | 'full'
+  sizeThreshold: number
+}
+
 export interface EmailSettings {
   renderMode: 'simple' | 'normal'
   reminders: EmailReminderSettings
+  attachments: EmailAttachmentSettings
 }
 
 export interface NotificationSettings {
@@ -69,7 +75,18 @@ export interface NotificationSettings {
   reminderSound: string | null
   notificationFolders: string[]
   badgeType: 'count' | 'dot' | null
+  badgeSource: 'all' | 'folders' | 'view'
   badgeFolders: string[]
+  badgeView: string | null
+  disabledAccounts: string[]
+  vipOnly: boolean
+  quietHoursEnabled: boolean
+  /** "HH:mm", local time */
+  quietHoursStart: string
+  /** "HH:mm", local time; before the start for quiet hours past midnight */
+  quietHoursEnd: string
+  alwaysNotifyFolders: string[]
+  silentFolders: string[]
 }
 
 export interface KanbanViewSettings {
@@ -97,6 +114,17 @@ export interface RegionalSettings {
 }
 
 // Root settings interface
+export interface StorageSettings {
+  attachmentCacheMaxMb: number
+  /** Keep OAuth refresh tokens and IMAP passwords in the OS keychain */
+  systemKeychain: boolean
+}
+
+export interface SecuritySettings {
+  /** Minutes without activity before the app locks, 0 to never */
+  autoLockMinutes: number
+}
+
 export interface Settings {
   ai: AISettings
   signatures: SignaturesSettings
@@ -105,6 +133,8 @@ export interface Settings {
   notifications: NotificationSettings
   views: ViewsSettings
   regional: RegionalSettings
+  storage: StorageSettings
+  security: SecuritySettings
 }
 
 // Navigation item for settings sidebar
@@ -1,4 +1,4 @@
-import type { EmailAddress } from './email'
+import type { Email, EmailAddress } from './email'
 
 // Sync types
 export interface FolderSettings {
@@ -9,8 +9,34 @@ export interface FolderSettings {
   expanded_groups: string[]
   filter_read?: boolean | null
   filter_has_attachments?: boolean | null
+  primary_action: FolderAction
+  swipe_left_action: FolderAction
+  swipe_right_action: FolderAction
+  after_action: AfterAction
+  mark_read_delay_ms: number | null
+  auto_archive?: AutoArchiveRule | null
+  /** Seconds between background syncs, null for the folder type's default */
+  sync_interval?: number | null
 }
 
+export interface AutoArchiveRule {
+  older_than_days: number
+  only_read: boolean
+  skip_flagged: boolean
+  /** Defaults to the account's archive folder */
+  target_folder_id?: string | null
+}
+
+export interface AutoArchivePreview {
+  target_folder_id: string | null
+  total: number
+  emails: Email[]
+}
+
+export type FolderAction = 'none' | 'archive' | 'delete' | 'toggle_read' | 'toggle_flag' | 'spam' | 'snooze'
+
+export type AfterAction = 'return_to_list' | 'next_message' | 'previous_message'
+
 export interface Folder {
   id: string
   account_id: string
@@ -21,12 +47,30 @@ export interface Folder {
   icon?: string
   color?: string
   hidden?: boolean
+  /** Whether background sync keeps the folder up to date */
+  sync_enabled?: boolean
   sort_order?: number
   unread_count: number
   total_count: number
   synced_at?: string
   expanded?: boolean
   settings?: FolderSettings
+  /** Query of a saved search, set on virtual folders of type 'search' */
+  query?: string
+  /** Color of the folder's account */
+  account_color?: string | null
+}
+
+export interface SavedSearch {
+  id: string
+  account_id: string
+  name: string
+  query: string
+  icon?: string
+  color?: string
+  sort_order: number
+  created_at: string
+  updated_at: string
 }
 
 export interface NavigationFolder extends Folder {
@@ -44,6 +88,7 @@ export type FolderType =
   | 'archive'
   | 'custom'
   | 'starred'
+  | 'search'
 
 export interface SyncEmail {
   id?: string
@@ -99,28 +144,125 @@ export interface Account {
   email: string
   account_type: AccountType
   settings: AccountSettings
+  /** Owner name synced from the provider */
+  display_name?: string | null
+  /** Owner photo synced from the provider */
+  photo_path?: string | null
+  /** Color telling the account apart in lists, as `#rrggbb` */
+  color?: string | null
+  /** Picture chosen for the account, overriding the synced profile photo */
+  avatar_path?: string | null
   created_at: string
   updated_at: string
 }
 
 export type AccountType = 'gmail' | 'office365' | 'apple' | 'imap'
 
+/** Mailbox storage usage; null where the provider reports nothing */
+export interface AccountQuota {
+  account_id: string
+  /** Used storage in bytes */
+  storage_used: number | null
+  /** Storage limit in bytes, null if unknown or unlimited */
+  storage_limit: number | null
+  message_count: number | null
+  message_limit: number | null
+  updated_at: string
+}
+
+export type HealthStatus = 'healthy' | 'warning' | 'error'
+
+export type HealthIssue =
+  | { kind: 'missing_credentials' }
+  | { kind: 'token_expired' }
+  | { kind: 'folder_errors', folders: number }
+  | { kind: 'sync_stale', last_sync_at: string | null }
+  | { kind: 'failed_operations', count: number }
+  | { kind: 'quota_nearly_full', percent: number }
+  | { kind: 'quota_unavailable', error: string }
+  | { kind: 'throttled', until: string }
+
+export interface CredentialHealth {
+  uses_oauth2: boolean
+  has_oauth2_token: boolean
+  has_password: boolean
+  token_expires_at: string | null
+  has_refresh_token: boolean
+  scopes: string[]
+}
+
+export interface FolderHealth {
+  folder_id: string
+  folder_name: string
+  sync_status: string
+  /** Last successful sync */
+  last_sync_at: string | null
+  /** Failed syncs since the last successful one */
+  error_count: number
+  error_message: string | null
+}
+
+/** Diagnostics of an account, to show why sync stopped */
+export interface AccountHealth {
+  account_id: string
+  status: HealthStatus
+  issues: HealthIssue[]
+  credentials: CredentialHealth
+  sync_enabled: boolean
+  paused: boolean
+  /** The provider rate limited the account until then */
+  throttled_until: string | null
+  is_syncing: boolean
+  last_sync_at: string | null
+  folders: FolderHealth[]
+  pending_operations: number
+  failed_operations: number
+  quota: AccountQuota | null
+  checked_at: string
+}
+
 export interface AccountSettings {
   imap_host?: string
   imap_port?: number
   imap_use_tls?: boolean
+  imap_tls_mode?: TlsMode | null
+  imap_trusted_certificate?: string | null
   imap_username?: string
   smtp_host?: string
   smtp_port?: number
   smtp_use_tls?: boolean
+  smtp_tls_mode?: TlsMode | null
+  smtp_trusted_certificate?: string | null
   smtp_username?: string
   sync_enabled: boolean
   sync_interval?: number
   sync_on_startup: boolean
+  sync_history_days?: number | null
   cache_attachments: boolean
   max_attachment_cache_size?: number
   auto_download_inline: boolean
   provider_settings?: Record<string, unkown>
+  imap_delete_policy?: ImapDeletePolicy
+}
+
+export type ImapDeletePolicy = 'move_to_trash' | 'flag_and_expunge' | 'flag_only'
+
+export type TlsMode = 'implicit' | 'starttls'
+
+export type MailServer = 'imap' | 'smtp'
+
+export interface DiscoveredServer {
+  host: string
+  port: number
+  /** `null` for an unencrypted connection */
+  tls_mode: TlsMode | null
+  username: string | null
+}
+
+export interface DiscoveredSettings {
+  source: 'autoconfig' | 'ispdb' | 'autodiscover' | 'srv'
+  imap: DiscoveredServer | null
+  smtp: DiscoveredServer | null
 }
 
 // Auth types
@@ -134,6 +276,9 @@ export interface StartOAuth2Response {
   csrf_token: string
 }
 
+/** Access an OAuth account can be granted after sign-in */
+export type OAuthScope = 'contacts' | 'calendar'
+
 export interface ExchangeOAuth2CodeRequest {
   provider: string
   code: string
@@ -184,4 +329,9 @@ export interface CredentialsRequiredEvent {
   account_id: string
   provider: string
   reason: string
+}
+
+export interface CredentialsUpdatedEvent {
+  account_id: string
+  scopes: string[]
 }
\ No newline at end of file
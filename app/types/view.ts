@@ -84,6 +84,8 @@ export interface View {
 
 export interface Label {
   id: string
+  account_id?: string
+  remote_id?: string
   name: string
   color?: string
   icon?: string
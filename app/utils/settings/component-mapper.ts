@@ -1,7 +1,14 @@
 import FolderSelection from '~/components/Ravn/FolderSelection.vue'
+import AccountSelector from '~/components/Settings/components/AccountSelector.vue'
+import AppLockPassphraseField from '~/components/Settings/components/AppLockPassphraseField.vue'
 import AiModelSelector from '~/components/Settings/components/AiModelSelector.vue'
+import CustomThemesField from '~/components/Settings/components/CustomThemesField.vue'
+import DefaultMailClientField from '~/components/Settings/components/DefaultMailClientField.vue'
+import LabelSelector from '~/components/Settings/components/LabelSelector.vue'
 import ReminderPresetsField from '~/components/Settings/components/ReminderPresetsField.vue'
+import ThemeModeField from '~/components/Settings/components/ThemeModeField.vue'
 import ThemeSelector from '~/components/Settings/components/ThemeSelector.vue'
+import ViewSelector from '~/components/Settings/components/ViewSelector.vue'
 import UnknownSetting from '~/components/Settings/components/UnknownSetting.vue'
 import ComboboxField from '~/components/ui/form/ComboboxField.vue'
 import FullscreenTextField from '~/components/ui/form/FullscreenTextField.vue'
@@ -19,8 +26,15 @@ const componentRegistry: Record<string, Component> = {
   Select: SelectField,
   Textarea: FullscreenTextField,
   FolderSelector: FolderSelection,
+  AccountSelector: AccountSelector,
+  LabelSelector: LabelSelector,
   ThemeSelector: ThemeSelector,
+  ThemeMode: ThemeModeField,
+  CustomThemes: CustomThemesField,
+  ViewSelector: ViewSelector,
   ReminderPresets: ReminderPresetsField,
+  AppLockPassphrase: AppLockPassphraseField,
+  DefaultMailClient: DefaultMailClientField,
   Unknown: UnknownSetting,
 }
 
@@ -0,0 +1,15 @@
+-- Persisted window/session state restored on startup
+CREATE TABLE IF NOT EXISTS session_windows (
+    label TEXT PRIMARY KEY NOT NULL,
+    window_type TEXT NOT NULL DEFAULT 'main'
+        CHECK (window_type IN ('main', 'message', 'compose')),
+    route TEXT,
+    x INTEGER,
+    y INTEGER,
+    width INTEGER,
+    height INTEGER,
+    is_maximized BOOLEAN NOT NULL DEFAULT 0,
+    -- JSON blob owned by the frontend: selected folder/view, scroll anchors, draft ids
+    view_state TEXT NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,14 @@
+-- Microsoft Graph change-notification subscriptions, one per Office365 mail folder
+CREATE TABLE IF NOT EXISTS graph_subscriptions (
+    subscription_id TEXT PRIMARY KEY NOT NULL,
+    account_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    folder_id TEXT NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+    resource TEXT NOT NULL,
+    -- Secret echoed back by Graph in every notification, used to reject forged payloads
+    client_state TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (folder_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_graph_subscriptions_expires_at ON graph_subscriptions(expires_at);
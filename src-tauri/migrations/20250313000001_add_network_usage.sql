@@ -0,0 +1,15 @@
+-- Daily bandwidth and API usage per account and provider
+CREATE TABLE IF NOT EXISTS network_usage (
+    account_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL,
+    day DATE NOT NULL,
+    bytes_sent INTEGER NOT NULL DEFAULT 0,
+    bytes_received INTEGER NOT NULL DEFAULT 0,
+    api_calls INTEGER NOT NULL DEFAULT 0,
+    quota_units INTEGER NOT NULL DEFAULT 0,
+    -- Highest usage seen within one provider rate-limit window on that day
+    peak_window_units INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (account_id, provider, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_network_usage_day ON network_usage(day);
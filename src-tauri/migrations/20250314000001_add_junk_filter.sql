@@ -0,0 +1,25 @@
+-- Local Bayesian junk filter
+-- Token counts learned from the user's junk / not-junk actions
+CREATE TABLE IF NOT EXISTS junk_tokens (
+    token TEXT PRIMARY KEY NOT NULL,
+    spam_count INTEGER NOT NULL DEFAULT 0,
+    ham_count INTEGER NOT NULL DEFAULT 0
+);
+
+-- One row per trained message so a verdict can be reversed without double counting.
+-- No foreign key: token counts outlive the email, so the message totals must as well.
+CREATE TABLE IF NOT EXISTS junk_training (
+    email_id TEXT PRIMARY KEY NOT NULL,
+    is_spam BOOLEAN NOT NULL,
+    trained_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Scores assigned to incoming messages
+CREATE TABLE IF NOT EXISTS junk_classifications (
+    email_id TEXT PRIMARY KEY NOT NULL REFERENCES emails(id) ON DELETE CASCADE,
+    score REAL NOT NULL,
+    is_junk BOOLEAN NOT NULL DEFAULT 0,
+    -- Headers-only classifications are refined once the body arrives
+    has_body BOOLEAN NOT NULL DEFAULT 0,
+    classified_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- Birthdays and anniversaries are stored as 'YYYY-MM-DD', or '--MM-DD' when the year is unknown
+ALTER TABLE contacts ADD COLUMN birthday TEXT;
+ALTER TABLE contacts ADD COLUMN anniversary TEXT;
+
+CREATE TABLE IF NOT EXISTS contact_date_notifications (
+    contact_id TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('birthday', 'anniversary')),
+    year INTEGER NOT NULL,
+    notified_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (contact_id, kind, year),
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+);
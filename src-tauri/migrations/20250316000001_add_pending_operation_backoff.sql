@@ -0,0 +1,4 @@
+-- Operations are not replayed before next_attempt_at (NULL = as soon as possible)
+ALTER TABLE pending_operations ADD COLUMN next_attempt_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_pending_ops_next_attempt ON pending_operations(status, next_attempt_at);
@@ -0,0 +1,5 @@
+-- Snoozed emails are hidden from folder views until snoozed_until has passed
+ALTER TABLE emails ADD COLUMN snoozed_until TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_emails_snoozed_until ON emails(snoozed_until)
+    WHERE snoozed_until IS NOT NULL;
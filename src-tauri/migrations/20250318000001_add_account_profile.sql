@@ -0,0 +1,6 @@
+-- Account owner identity as reported by the provider (Graph / Google People)
+ALTER TABLE accounts ADD COLUMN display_name TEXT;
+ALTER TABLE accounts ADD COLUMN photo_path TEXT;
+ALTER TABLE accounts ADD COLUMN timezone TEXT;
+ALTER TABLE accounts ADD COLUMN locale TEXT;
+ALTER TABLE accounts ADD COLUMN profile_synced_at TIMESTAMP;
@@ -0,0 +1,19 @@
+-- User-defined filter rules applied to incoming mail
+CREATE TABLE IF NOT EXISTS rules (
+    id TEXT PRIMARY KEY NOT NULL,
+    -- NULL applies the rule to every account
+    account_id TEXT REFERENCES accounts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    -- Whether all conditions must match (AND) or any of them (OR)
+    match_all BOOLEAN NOT NULL DEFAULT 1,
+    conditions TEXT NOT NULL DEFAULT '[]',
+    actions TEXT NOT NULL DEFAULT '[]',
+    -- Skip the remaining rules once this one matched
+    stop_processing BOOLEAN NOT NULL DEFAULT 0,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_rules_account_id ON rules(account_id);
@@ -0,0 +1,15 @@
+-- Per-recipient delivery status of sent messages, from SMTP delivery status
+-- notifications (RFC 3464)
+CREATE TABLE IF NOT EXISTS email_delivery_status (
+    email_id TEXT NOT NULL REFERENCES emails(id) ON DELETE CASCADE,
+    recipient TEXT NOT NULL,
+    -- pending until a report arrives, then the report's Action field
+    action TEXT NOT NULL DEFAULT 'pending',
+    -- RFC 3463 status code, e.g. 5.1.1
+    status TEXT,
+    diagnostic TEXT,
+    -- The report message the status was read from
+    report_email_id TEXT,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (email_id, recipient)
+);
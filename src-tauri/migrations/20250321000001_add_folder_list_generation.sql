@@ -0,0 +1,39 @@
+-- Bumped whenever a folder's message list changes membership or order, so
+-- virtualized lists can tell whether their cached rows are still valid
+ALTER TABLE folders ADD COLUMN list_generation INTEGER NOT NULL DEFAULT 0;
+
+-- Covers the folder list ordering used by windowed queries
+CREATE INDEX IF NOT EXISTS idx_emails_folder_list
+    ON emails(folder_id, received_at DESC, id DESC)
+    WHERE is_deleted = 0 AND snoozed_until IS NULL;
+
+-- folders.total_count includes snoozed mail, which lists hide; this keeps
+-- counting the difference cheap
+CREATE INDEX IF NOT EXISTS idx_emails_folder_snoozed
+    ON emails(folder_id)
+    WHERE is_deleted = 0 AND snoozed_until IS NOT NULL;
+
+CREATE TRIGGER IF NOT EXISTS bump_folder_list_generation_insert
+   AFTER INSERT ON emails
+BEGIN
+    UPDATE folders SET list_generation = list_generation + 1
+    WHERE id = NEW.folder_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS bump_folder_list_generation_delete
+   AFTER DELETE ON emails
+BEGIN
+    UPDATE folders SET list_generation = list_generation + 1
+    WHERE id = OLD.folder_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS bump_folder_list_generation_update
+   AFTER UPDATE OF folder_id, is_deleted, snoozed_until, received_at ON emails
+   WHEN OLD.folder_id != NEW.folder_id
+     OR OLD.is_deleted != NEW.is_deleted
+     OR OLD.snoozed_until IS NOT NEW.snoozed_until
+     OR OLD.received_at != NEW.received_at
+BEGIN
+    UPDATE folders SET list_generation = list_generation + 1
+    WHERE id IN (OLD.folder_id, NEW.folder_id);
+END;
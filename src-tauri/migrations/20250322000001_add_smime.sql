@@ -0,0 +1,63 @@
+-- S/MIME: certificates, per-message signature results, and private key storage
+
+-- Own certificates (imported from PKCS#12, private key in encrypted_credentials)
+-- and certificates collected from signed mail, used to encrypt to their owners
+CREATE TABLE IF NOT EXISTS smime_certificates (
+    id TEXT NOT NULL PRIMARY KEY,
+    -- Set for own certificates only
+    account_id TEXT REFERENCES accounts(id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    issuer TEXT NOT NULL,
+    serial TEXT NOT NULL,
+    -- SHA-256 of the DER encoding, hex
+    fingerprint TEXT NOT NULL UNIQUE,
+    not_before TIMESTAMP NOT NULL,
+    not_after TIMESTAMP NOT NULL,
+    certificate_pem TEXT NOT NULL,
+    has_private_key INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_smime_certificates_email ON smime_certificates(email);
+CREATE INDEX IF NOT EXISTS idx_smime_certificates_account ON smime_certificates(account_id);
+
+-- Result of verifying an incoming signed message
+CREATE TABLE IF NOT EXISTS email_smime_verifications (
+    email_id TEXT NOT NULL PRIMARY KEY REFERENCES emails(id) ON DELETE CASCADE,
+    -- valid, untrusted or invalid
+    status TEXT NOT NULL,
+    signer_email TEXT,
+    signer_subject TEXT,
+    issuer TEXT,
+    fingerprint TEXT,
+    not_after TIMESTAMP,
+    error TEXT,
+    verified_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Allow S/MIME private keys in the encrypted credential store. SQLite cannot
+-- alter a CHECK constraint, so the table is rebuilt.
+CREATE TABLE encrypted_credentials_new (
+    id TEXT NOT NULL PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    credential_type TEXT NOT NULL CHECK(credential_type IN ('oauth2', 'imap', 'smime')),
+    encrypted_data BLOB NOT NULL,
+    nonce BLOB NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    UNIQUE(account_id, credential_type)
+);
+
+INSERT INTO encrypted_credentials_new
+    (id, account_id, credential_type, encrypted_data, nonce, created_at, updated_at)
+SELECT id, account_id, credential_type, encrypted_data, nonce, created_at, updated_at
+FROM encrypted_credentials;
+
+DROP TABLE encrypted_credentials;
+ALTER TABLE encrypted_credentials_new RENAME TO encrypted_credentials;
+
+CREATE INDEX IF NOT EXISTS idx_encrypted_credentials_account ON encrypted_credentials(account_id);
+CREATE INDEX IF NOT EXISTS idx_encrypted_credentials_type ON encrypted_credentials(credential_type);
@@ -0,0 +1,13 @@
+-- Reusable message templates with {{placeholder}} variables
+CREATE TABLE IF NOT EXISTS templates (
+    id TEXT PRIMARY KEY NOT NULL,
+    -- NULL makes the template available to every account
+    account_id TEXT REFERENCES accounts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    subject TEXT,
+    body TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_templates_account_id ON templates(account_id);
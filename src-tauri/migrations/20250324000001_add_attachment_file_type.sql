@@ -0,0 +1,87 @@
+-- Attachment category for `type:` search filters and SQL filtering. Mirrors
+-- FILE_TYPES in models/attachment.rs: the first matching category wins.
+ALTER TABLE attachments ADD COLUMN file_type TEXT GENERATED ALWAYS AS (
+    CASE
+        WHEN lower(content_type) LIKE 'application/pdf%'
+            OR lower(filename) LIKE '%.pdf'
+            THEN 'pdf'
+        WHEN lower(content_type) LIKE 'image/%'
+            OR lower(filename) LIKE '%.png'
+            OR lower(filename) LIKE '%.jpg'
+            OR lower(filename) LIKE '%.jpeg'
+            OR lower(filename) LIKE '%.gif'
+            OR lower(filename) LIKE '%.webp'
+            OR lower(filename) LIKE '%.heic'
+            OR lower(filename) LIKE '%.svg'
+            OR lower(filename) LIKE '%.bmp'
+            OR lower(filename) LIKE '%.tif'
+            OR lower(filename) LIKE '%.tiff'
+            THEN 'image'
+        WHEN lower(content_type) LIKE 'application/vnd.ms-excel%'
+            OR lower(content_type) LIKE 'application/vnd.openxmlformats-officedocument.spreadsheetml%'
+            OR lower(content_type) LIKE 'application/vnd.oasis.opendocument.spreadsheet%'
+            OR lower(content_type) LIKE 'text/csv%'
+            OR lower(filename) LIKE '%.xls'
+            OR lower(filename) LIKE '%.xlsx'
+            OR lower(filename) LIKE '%.xlsm'
+            OR lower(filename) LIKE '%.ods'
+            OR lower(filename) LIKE '%.csv'
+            OR lower(filename) LIKE '%.numbers'
+            THEN 'spreadsheet'
+        WHEN lower(content_type) LIKE 'application/vnd.ms-powerpoint%'
+            OR lower(content_type) LIKE 'application/vnd.openxmlformats-officedocument.presentationml%'
+            OR lower(content_type) LIKE 'application/vnd.oasis.opendocument.presentation%'
+            OR lower(filename) LIKE '%.ppt'
+            OR lower(filename) LIKE '%.pptx'
+            OR lower(filename) LIKE '%.odp'
+            OR lower(filename) LIKE '%.key'
+            THEN 'presentation'
+        WHEN lower(content_type) LIKE 'application/msword%'
+            OR lower(content_type) LIKE 'application/vnd.openxmlformats-officedocument.wordprocessingml%'
+            OR lower(content_type) LIKE 'application/vnd.oasis.opendocument.text%'
+            OR lower(content_type) LIKE 'application/rtf%'
+            OR lower(filename) LIKE '%.doc'
+            OR lower(filename) LIKE '%.docx'
+            OR lower(filename) LIKE '%.odt'
+            OR lower(filename) LIKE '%.rtf'
+            OR lower(filename) LIKE '%.pages'
+            THEN 'document'
+        WHEN lower(content_type) LIKE 'application/zip%'
+            OR lower(content_type) LIKE 'application/x-7z-compressed%'
+            OR lower(content_type) LIKE 'application/x-rar%'
+            OR lower(content_type) LIKE 'application/vnd.rar%'
+            OR lower(content_type) LIKE 'application/gzip%'
+            OR lower(content_type) LIKE 'application/x-tar%'
+            OR lower(filename) LIKE '%.zip'
+            OR lower(filename) LIKE '%.7z'
+            OR lower(filename) LIKE '%.rar'
+            OR lower(filename) LIKE '%.gz'
+            OR lower(filename) LIKE '%.tgz'
+            OR lower(filename) LIKE '%.tar'
+            THEN 'archive'
+        WHEN lower(content_type) LIKE 'text/calendar%'
+            OR lower(filename) LIKE '%.ics'
+            THEN 'calendar'
+        WHEN lower(content_type) LIKE 'audio/%'
+            OR lower(filename) LIKE '%.mp3'
+            OR lower(filename) LIKE '%.wav'
+            OR lower(filename) LIKE '%.m4a'
+            OR lower(filename) LIKE '%.ogg'
+            THEN 'audio'
+        WHEN lower(content_type) LIKE 'video/%'
+            OR lower(filename) LIKE '%.mp4'
+            OR lower(filename) LIKE '%.mov'
+            OR lower(filename) LIKE '%.avi'
+            OR lower(filename) LIKE '%.mkv'
+            OR lower(filename) LIKE '%.webm'
+            THEN 'video'
+        WHEN lower(content_type) LIKE 'text/%'
+            OR lower(filename) LIKE '%.txt'
+            OR lower(filename) LIKE '%.md'
+            THEN 'text'
+        ELSE 'other'
+    END
+) VIRTUAL;
+
+CREATE INDEX IF NOT EXISTS idx_attachments_file_type ON attachments(file_type);
+CREATE INDEX IF NOT EXISTS idx_attachments_filename ON attachments(filename COLLATE NOCASE);
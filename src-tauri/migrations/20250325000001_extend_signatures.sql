@@ -0,0 +1,7 @@
+-- Signatures per sending address, with a plain-text variant and separate
+-- defaults for new messages and replies. `signature` holds the HTML variant.
+ALTER TABLE signatures ADD COLUMN identity_email TEXT; -- NULL applies to every address of the account
+ALTER TABLE signatures ADD COLUMN signature_text TEXT; -- NULL derives the text from the HTML
+ALTER TABLE signatures ADD COLUMN is_reply_default BOOLEAN NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_signatures_account_id ON signatures(account_id);
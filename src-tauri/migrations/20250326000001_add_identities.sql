@@ -0,0 +1,14 @@
+-- Additional addresses an account can send as (aliases)
+CREATE TABLE IF NOT EXISTS identities (
+    id TEXT PRIMARY KEY NOT NULL,
+    account_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    name TEXT,
+    -- 'manual' for aliases added by the user, 'gmail' for Gmail sendAs addresses
+    source TEXT NOT NULL DEFAULT 'manual' CHECK (source IN ('manual', 'gmail')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (account_id, email)
+);
+
+CREATE INDEX IF NOT EXISTS idx_identities_account_id ON identities(account_id);
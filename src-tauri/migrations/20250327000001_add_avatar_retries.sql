@@ -0,0 +1,6 @@
+-- Retry bookkeeping for the background avatar fetcher: failed lookups stay
+-- 'unprocessed' until avatar_retry_at and are given up after a few attempts
+ALTER TABLE contacts ADD COLUMN avatar_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE contacts ADD COLUMN avatar_retry_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_contacts_avatar_queue ON contacts(avatar_type, avatar_retry_at);
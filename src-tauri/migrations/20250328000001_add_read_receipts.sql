@@ -0,0 +1,4 @@
+-- Read receipts (RFC 8098 message disposition notifications) per recipient of
+-- a sent message, next to the delivery status from DSNs
+ALTER TABLE email_delivery_status ADD COLUMN disposition TEXT;
+ALTER TABLE email_delivery_status ADD COLUMN disposition_at TIMESTAMP;
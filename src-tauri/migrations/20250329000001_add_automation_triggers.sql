@@ -0,0 +1,35 @@
+-- User-configured integrations that run when something happens to mail:
+-- a webhook POST or a local script receiving a JSON payload
+CREATE TABLE IF NOT EXISTS automation_triggers (
+    id TEXT PRIMARY KEY NOT NULL,
+    -- NULL fires the trigger for every account
+    account_id TEXT REFERENCES accounts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    event TEXT NOT NULL,
+    target TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_triggers_account_id ON automation_triggers(account_id);
+
+-- Delivery log; pending rows double as the retry queue
+CREATE TABLE IF NOT EXISTS automation_trigger_deliveries (
+    id TEXT PRIMARY KEY NOT NULL,
+    trigger_id TEXT NOT NULL REFERENCES automation_triggers(id) ON DELETE CASCADE,
+    email_id TEXT REFERENCES emails(id) ON DELETE SET NULL,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    -- 'pending', 'delivered' or 'failed' once the retries are used up
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP,
+    delivered_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_trigger_deliveries_trigger ON automation_trigger_deliveries(trigger_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_automation_trigger_deliveries_due ON automation_trigger_deliveries(status, next_attempt_at);
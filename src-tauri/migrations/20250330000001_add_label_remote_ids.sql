@@ -0,0 +1,17 @@
+-- Gmail user labels are synced into `labels` instead of being flattened into
+-- folders. Local labels keep a NULL account and remote ID.
+ALTER TABLE labels ADD COLUMN account_id TEXT REFERENCES accounts(id) ON DELETE CASCADE;
+ALTER TABLE labels ADD COLUMN remote_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_labels_account_remote_id ON labels(account_id, remote_id);
+
+-- Drop the folders that Gmail user labels were flattened into. Their messages
+-- come back through the system folders and the archive folder on the next
+-- full sync, stored once and with their labels attached.
+DELETE FROM folders
+WHERE remote_id NOT IN ('INBOX', 'SENT', 'DRAFT', 'TRASH', 'SPAM', 'STARRED')
+  AND account_id IN (SELECT id FROM accounts WHERE account_type = 'gmail');
+
+UPDATE sync_state
+SET sync_token = NULL
+WHERE account_id IN (SELECT id FROM accounts WHERE account_type = 'gmail');
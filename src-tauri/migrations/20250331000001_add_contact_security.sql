@@ -0,0 +1,19 @@
+-- Per-contact encryption preferences, consulted when sending, and the OpenPGP
+-- key found for the contact. Keyed by address so every account sending to the
+-- contact honours the same preference.
+CREATE TABLE IF NOT EXISTS contact_security (
+    email TEXT NOT NULL PRIMARY KEY,
+    policy TEXT NOT NULL DEFAULT 'auto'
+        CHECK(policy IN ('auto', 'always_pgp', 'require_smime', 'plaintext_only')),
+    -- Fingerprint of pgp_public_key, uppercase hex. Mail is encrypted to this
+    -- key under 'always_pgp'.
+    pgp_fingerprint TEXT,
+    -- ASCII-armored public key
+    pgp_public_key TEXT,
+    -- manual, wkd or keyserver
+    pgp_key_source TEXT,
+    -- Last WKD/keyserver lookup, also set when no key was found
+    pgp_key_fetched_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
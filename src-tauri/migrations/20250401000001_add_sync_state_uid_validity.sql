@@ -0,0 +1,3 @@
+-- UIDVALIDITY of IMAP folders: when it changes, the stored UIDs refer to
+-- other messages and the folder is resynced
+ALTER TABLE sync_state ADD COLUMN uid_validity INTEGER;
@@ -0,0 +1,12 @@
+-- Progress of long-running data migrations, which resume from `cursor`
+-- when the app is restarted midway
+CREATE TABLE IF NOT EXISTS data_migrations (
+    id TEXT PRIMARY KEY NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'failed')),
+    cursor TEXT,
+    processed INTEGER NOT NULL DEFAULT 0,
+    error_message TEXT,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,6 @@
+-- When a cached attachment was last opened, so the least recently opened
+-- ones are evicted first once the attachment cache exceeds its quota
+ALTER TABLE attachments ADD COLUMN last_opened_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_attachments_cache_lru
+    ON attachments(is_cached, last_opened_at);
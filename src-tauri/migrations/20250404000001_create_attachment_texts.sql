@@ -0,0 +1,13 @@
+-- Text extracted from attachments for full-text search. Kept outside the
+-- attachments table so listing attachments never loads it, and kept when an
+-- attachment is evicted from the cache so it stays searchable.
+CREATE TABLE IF NOT EXISTS attachment_texts (
+    attachment_id TEXT NOT NULL PRIMARY KEY
+        REFERENCES attachments(id) ON DELETE CASCADE,
+    email_id TEXT NOT NULL,
+    -- NULL when the attachment has no extractable text, so it is not retried
+    content TEXT,
+    extracted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachment_texts_email ON attachment_texts(email_id);
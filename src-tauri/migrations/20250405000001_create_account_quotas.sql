@@ -0,0 +1,11 @@
+-- Mailbox storage usage and limits as last reported by the provider. A NULL
+-- value is one the provider does not report.
+CREATE TABLE IF NOT EXISTS account_quotas (
+    account_id TEXT NOT NULL PRIMARY KEY
+        REFERENCES accounts(id) ON DELETE CASCADE,
+    storage_used INTEGER,
+    storage_limit INTEGER,
+    message_count INTEGER,
+    message_limit INTEGER,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,14 @@
+-- How often and how recently mail was exchanged with a contact, per account
+-- and address field. `score` is a frecency score decayed to
+-- `last_interaction_at`; it ranks recipient autocomplete.
+CREATE TABLE IF NOT EXISTS contact_interactions (
+    contact_id TEXT NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+    account_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    field TEXT NOT NULL CHECK (field IN ('from', 'to', 'cc', 'bcc')),
+    interaction_count INTEGER NOT NULL DEFAULT 0,
+    score REAL NOT NULL DEFAULT 0,
+    last_interaction_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (contact_id, account_id, field)
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_interactions_account_id ON contact_interactions(account_id);
@@ -0,0 +1,30 @@
+-- Events of meeting invitations (text/calendar parts, RFC 5546) and the
+-- user's answer to them. An updated or cancelled invitation arrives as a new
+-- email and gets its own row; rows of the same event share `uid`.
+CREATE TABLE IF NOT EXISTS calendar_events (
+    id TEXT PRIMARY KEY NOT NULL,
+    account_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    email_id TEXT NOT NULL REFERENCES emails(id) ON DELETE CASCADE,
+    uid TEXT NOT NULL,
+    sequence INTEGER NOT NULL DEFAULT 0,
+    method TEXT NOT NULL,
+    summary TEXT,
+    description TEXT,
+    location TEXT,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP,
+    all_day BOOLEAN NOT NULL DEFAULT 0,
+    organizer TEXT,
+    attendees TEXT NOT NULL DEFAULT '[]',
+    attendee_email TEXT,
+    cancelled BOOLEAN NOT NULL DEFAULT 0,
+    rsvp_status TEXT NOT NULL DEFAULT 'needs_action'
+        CHECK (rsvp_status IN ('needs_action', 'accepted', 'tentative', 'declined')),
+    responded_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (email_id, uid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_calendar_events_uid ON calendar_events(account_id, uid);
+CREATE INDEX IF NOT EXISTS idx_calendar_events_starts_at ON calendar_events(starts_at);
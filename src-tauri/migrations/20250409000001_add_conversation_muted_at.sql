@@ -0,0 +1,3 @@
+-- When a conversation was muted: its later messages skip the inbox and
+-- never notify
+ALTER TABLE conversations ADD COLUMN muted_at TIMESTAMP;
@@ -0,0 +1,8 @@
+-- Contacts whose mail is always important
+ALTER TABLE contacts ADD COLUMN is_vip BOOLEAN NOT NULL DEFAULT 0;
+
+-- Priority signals of an email, scored when it is synced: a VIP sender (4),
+-- a reply in a thread the user took part in (2), sent directly to the user (1)
+ALTER TABLE emails ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_emails_priority ON emails(priority, received_at);
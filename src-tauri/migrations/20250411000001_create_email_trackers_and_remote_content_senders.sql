@@ -0,0 +1,14 @@
+-- Trackers found in the HTML of an email, by the service behind them or by
+-- host for unknown ones
+CREATE TABLE IF NOT EXISTS email_trackers (
+    email_id TEXT NOT NULL REFERENCES emails(id) ON DELETE CASCADE,
+    tracker TEXT NOT NULL,
+    found_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (email_id, tracker)
+);
+
+-- Senders whose remote images are always loaded. Their trackers stay blocked.
+CREATE TABLE IF NOT EXISTS remote_content_senders (
+    address TEXT PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- Senders and domains whose remote images are always loaded. Their trackers
+-- stay blocked. A domain entry also covers its subdomains.
+CREATE TABLE IF NOT EXISTS image_allowlist (
+    pattern TEXT PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('sender', 'domain')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO image_allowlist (pattern, kind, created_at)
+SELECT address, 'sender', created_at FROM remote_content_senders;
+
+DROP TABLE IF EXISTS remote_content_senders;
@@ -0,0 +1,4 @@
+-- Phishing and spoofing risk of an email, analyzed when it is synced: a score
+-- from 0 to 100 and the reasons behind it as a JSON array
+ALTER TABLE emails ADD COLUMN risk_score INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE emails ADD COLUMN risk_reasons TEXT;
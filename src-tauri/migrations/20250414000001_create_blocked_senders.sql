@@ -0,0 +1,7 @@
+-- Senders and domains whose mail goes straight to junk. A domain entry also
+-- covers its subdomains.
+CREATE TABLE IF NOT EXISTS blocked_senders (
+    pattern TEXT PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('sender', 'domain')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,9 @@
+-- Categories the user picked for a sender's mail. They take precedence over
+-- the categorizer when syncing, so a corrected sender stays corrected.
+CREATE TABLE IF NOT EXISTS sender_categories (
+    sender TEXT PRIMARY KEY,
+    category TEXT NOT NULL CHECK (category IN ('personal', 'transactions', 'updates', 'promotions')),
+    corrections INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
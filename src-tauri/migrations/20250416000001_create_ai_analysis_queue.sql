@@ -0,0 +1,12 @@
+-- Emails waiting for background AI analysis. Failed analyses are retried no
+-- earlier than next_attempt_at (NULL = as soon as possible) until they run out
+-- of attempts.
+CREATE TABLE IF NOT EXISTS ai_analysis_queue (
+    email_id TEXT PRIMARY KEY REFERENCES emails(id) ON DELETE CASCADE,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_analysis_queue_next_attempt ON ai_analysis_queue(next_attempt_at);
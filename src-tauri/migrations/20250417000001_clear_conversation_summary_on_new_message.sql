@@ -0,0 +1,18 @@
+-- The AI summary cached on a conversation is dropped once a message joins it
+CREATE TRIGGER IF NOT EXISTS clear_conversation_ai_cache_insert
+   AFTER INSERT ON emails
+   WHEN NEW.conversation_id IS NOT NULL
+BEGIN
+    UPDATE conversations
+    SET ai_cache = NULL
+    WHERE id = NEW.conversation_id AND ai_cache IS NOT NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS clear_conversation_ai_cache_update
+   AFTER UPDATE OF conversation_id ON emails
+   WHEN NEW.conversation_id IS NOT NULL AND NEW.conversation_id IS NOT OLD.conversation_id
+BEGIN
+    UPDATE conversations
+    SET ai_cache = NULL
+    WHERE id = NEW.conversation_id AND ai_cache IS NOT NULL;
+END;
@@ -0,0 +1,9 @@
+-- Writing style learned from an account's sent mail, used to match the user's
+-- tone, greetings and sign-offs in AI completions
+CREATE TABLE IF NOT EXISTS writing_style_profiles (
+    account_id TEXT PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    profile TEXT NOT NULL,
+    sample_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
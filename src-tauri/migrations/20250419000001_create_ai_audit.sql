@@ -0,0 +1,13 @@
+-- What was sent to which AI provider and when, recorded while ai.audit.enabled
+-- is on. email_ids is a JSON array of the emails whose content was included.
+CREATE TABLE IF NOT EXISTS ai_audit (
+    id TEXT PRIMARY KEY,
+    operation TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    email_ids TEXT NOT NULL DEFAULT '[]',
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_audit_created_at ON ai_audit(created_at);
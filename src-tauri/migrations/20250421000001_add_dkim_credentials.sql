@@ -0,0 +1,24 @@
+-- Allow DKIM signing keys in the encrypted credential store. SQLite cannot
+-- alter a CHECK constraint, so the table is rebuilt.
+CREATE TABLE encrypted_credentials_new (
+    id TEXT NOT NULL PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    credential_type TEXT NOT NULL CHECK(credential_type IN ('oauth2', 'imap', 'smime', 'dkim')),
+    encrypted_data BLOB NOT NULL,
+    nonce BLOB NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    UNIQUE(account_id, credential_type)
+);
+
+INSERT INTO encrypted_credentials_new
+    (id, account_id, credential_type, encrypted_data, nonce, created_at, updated_at)
+SELECT id, account_id, credential_type, encrypted_data, nonce, created_at, updated_at
+FROM encrypted_credentials;
+
+DROP TABLE encrypted_credentials;
+ALTER TABLE encrypted_credentials_new RENAME TO encrypted_credentials;
+
+CREATE INDEX IF NOT EXISTS idx_encrypted_credentials_account ON encrypted_credentials(account_id);
+CREATE INDEX IF NOT EXISTS idx_encrypted_credentials_type ON encrypted_credentials(credential_type);
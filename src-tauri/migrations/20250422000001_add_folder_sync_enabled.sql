@@ -0,0 +1,3 @@
+-- Folders can be excluded from background sync, e.g. the many shared folders
+-- of a corporate mailbox
+ALTER TABLE folders ADD COLUMN sync_enabled BOOLEAN NOT NULL DEFAULT 1;
@@ -0,0 +1,4 @@
+-- Color and picture chosen by the user to tell accounts apart in lists; the
+-- picture takes precedence over the owner photo from the provider
+ALTER TABLE accounts ADD COLUMN color TEXT;
+ALTER TABLE accounts ADD COLUMN avatar_path TEXT;
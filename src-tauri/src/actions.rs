@@ -0,0 +1,442 @@
+//! Registry of the actions the command palette offers
+//!
+//! Fixed actions run handlers the frontend registers under the same
+//! `namespace:id` keys the keymaps bind. Actions on accounts, folders, labels
+//! and views are generated from local data, so the palette lists whatever
+//! exists without the frontend hard-coding it.
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::config::keybindings::KeyMapFile;
+use crate::database::error::DatabaseError;
+use crate::database::models::{
+    account::Account,
+    folder::{Folder, FolderType},
+    label::Label,
+    view::View,
+};
+use crate::database::repositories::{
+    AccountRepository, FolderRepository, LabelRepository, RepositoryFactory, ViewRepository,
+};
+use crate::navigation::NavigationUrl;
+
+/// Keymap context of actions available everywhere
+pub const GLOBAL_CONTEXT: &str = "global";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionCategory {
+    Navigate,
+    Sync,
+    Label,
+    Move,
+    Compose,
+}
+
+/// What picking an action does
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActionTarget {
+    /// Route to a `ravn://` URL
+    Navigate { url: String },
+    /// Run the frontend handler registered as `namespace:id`
+    Run {
+        action: String,
+        props: Option<JsonValue>,
+    },
+    /// Call a backend command
+    Invoke { command: String, args: JsonValue },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Action {
+    /// Stable across calls, for the palette to remember recent picks
+    pub id: String,
+    pub category: ActionCategory,
+    pub title: String,
+    /// Account the action belongs to, shown next to the title
+    pub subtitle: Option<String>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub shortcut: Option<String>,
+    /// Keymap context that has to be focused for the action to apply
+    pub context: String,
+    /// Account of the folder or label, so moves can be limited to the
+    /// account of the selected email
+    pub account_id: Option<Uuid>,
+    pub target: ActionTarget,
+}
+
+/// Local data the generated actions are built from
+#[derive(Debug, Default)]
+pub struct ActionSources {
+    pub accounts: Vec<Account>,
+    pub folders: Vec<Folder>,
+    pub labels: Vec<Label>,
+    pub views: Vec<View>,
+}
+
+impl ActionSources {
+    pub async fn load(pool: &SqlitePool) -> Result<Self, DatabaseError> {
+        let repo_factory = RepositoryFactory::new(pool.clone());
+        Ok(Self {
+            accounts: repo_factory.account_repository().find_all().await?,
+            folders: repo_factory.folder_repository().get_all().await?,
+            labels: repo_factory.label_repository().get_all().await?,
+            views: repo_factory.view_repository().get_all().await?,
+        })
+    }
+
+    fn account_name(&self, account_id: Uuid) -> Option<String> {
+        self.accounts
+            .iter()
+            .find(|account| account.id == account_id)
+            .map(|account| account.name.clone())
+    }
+}
+
+/// Actions backed by a handler the frontend registers
+struct FixedAction {
+    category: ActionCategory,
+    title: &'static str,
+    icon: &'static str,
+    action: &'static str,
+}
+
+const FIXED_ACTIONS: &[FixedAction] = &[
+    FixedAction {
+        category: ActionCategory::Navigate,
+        title: "Go Home",
+        icon: "lucide:home",
+        action: "global:home",
+    },
+    FixedAction {
+        category: ActionCategory::Navigate,
+        title: "Search",
+        icon: "lucide:search",
+        action: "global:search",
+    },
+    FixedAction {
+        category: ActionCategory::Compose,
+        title: "New Message",
+        icon: "lucide:square-pen",
+        action: "global:composeEmail",
+    },
+    FixedAction {
+        category: ActionCategory::Compose,
+        title: "Reply",
+        icon: "lucide:reply",
+        action: "MailList:replyToEmail",
+    },
+    FixedAction {
+        category: ActionCategory::Compose,
+        title: "Reply All",
+        icon: "lucide:reply-all",
+        action: "MailList:replyAllToEmail",
+    },
+    FixedAction {
+        category: ActionCategory::Compose,
+        title: "Forward",
+        icon: "lucide:forward",
+        action: "MailList:forwardEmail",
+    },
+    FixedAction {
+        category: ActionCategory::Compose,
+        title: "Reply",
+        icon: "lucide:reply",
+        action: "ConversationView:replyToEmail",
+    },
+    FixedAction {
+        category: ActionCategory::Compose,
+        title: "Reply All",
+        icon: "lucide:reply-all",
+        action: "ConversationView:replyAllToEmail",
+    },
+    FixedAction {
+        category: ActionCategory::Compose,
+        title: "Forward",
+        icon: "lucide:forward",
+        action: "ConversationView:forwardEmail",
+    },
+];
+
+/// Context of a `namespace:id` action key
+fn action_context(action: &str) -> &str {
+    action
+        .split_once(':')
+        .map_or(GLOBAL_CONTEXT, |(namespace, _)| namespace)
+}
+
+/// The shortest key bound to `action` with `props`, so sequences only show
+/// when nothing quicker runs the action
+fn shortcut_for(keymap: &KeyMapFile, action: &str, props: Option<&JsonValue>) -> Option<String> {
+    keymap
+        .iter()
+        .flat_map(|binding| binding.bindings.iter())
+        .filter(|(_, bound)| bound.action() == Some(action) && bound.props() == props)
+        .map(|(key, _)| key)
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .cloned()
+}
+
+fn run(action: &str, props: Option<JsonValue>) -> ActionTarget {
+    ActionTarget::Run {
+        action: action.to_string(),
+        props,
+    }
+}
+
+fn navigate(path: &str) -> ActionTarget {
+    ActionTarget::Navigate {
+        url: NavigationUrl::build(path, None),
+    }
+}
+
+/// Folders mail can be shown in and moved to
+fn is_listed(folder: &Folder) -> bool {
+    !folder.hidden && folder.folder_type != FolderType::Search
+}
+
+/// Every action, with shortcuts from `keymap`, limited to those that apply
+/// in `context` and globally when a context is given
+pub fn build(sources: &ActionSources, keymap: &KeyMapFile, context: Option<&str>) -> Vec<Action> {
+    let mut actions = Vec::new();
+
+    for fixed in FIXED_ACTIONS {
+        actions.push(Action {
+            id: fixed.action.to_string(),
+            category: fixed.category,
+            title: fixed.title.to_string(),
+            subtitle: None,
+            icon: Some(fixed.icon.to_string()),
+            color: None,
+            shortcut: None,
+            context: action_context(fixed.action).to_string(),
+            account_id: None,
+            target: run(fixed.action, None),
+        });
+    }
+
+    actions.push(Action {
+        id: "compose:window".to_string(),
+        category: ActionCategory::Compose,
+        title: "New Message in Window".to_string(),
+        subtitle: None,
+        icon: Some("lucide:app-window".to_string()),
+        color: None,
+        shortcut: None,
+        context: GLOBAL_CONTEXT.to_string(),
+        account_id: None,
+        target: ActionTarget::Invoke {
+            command: "open_compose_window".to_string(),
+            args: json!({ "request": null }),
+        },
+    });
+
+    actions.push(Action {
+        id: "navigate:settings".to_string(),
+        category: ActionCategory::Navigate,
+        title: "Settings".to_string(),
+        subtitle: None,
+        icon: Some("lucide:settings".to_string()),
+        color: None,
+        shortcut: None,
+        context: GLOBAL_CONTEXT.to_string(),
+        account_id: None,
+        target: navigate("settings"),
+    });
+
+    actions.push(Action {
+        id: "navigate:keymap-editor".to_string(),
+        category: ActionCategory::Navigate,
+        title: "Keymap Editor".to_string(),
+        subtitle: None,
+        icon: Some("lucide:keyboard".to_string()),
+        color: None,
+        shortcut: None,
+        context: GLOBAL_CONTEXT.to_string(),
+        account_id: None,
+        target: navigate("keymap-editor"),
+    });
+
+    for view in &sources.views {
+        actions.push(Action {
+            id: format!("navigate:view:{}", view.id),
+            category: ActionCategory::Navigate,
+            title: view.name.clone(),
+            subtitle: None,
+            icon: view.icon.clone(),
+            color: view.color.clone(),
+            shortcut: None,
+            context: GLOBAL_CONTEXT.to_string(),
+            account_id: None,
+            target: navigate(&format!("views/{}", view.id)),
+        });
+    }
+
+    for account in &sources.accounts {
+        actions.push(Action {
+            id: format!("sync:account:{}", account.id),
+            category: ActionCategory::Sync,
+            title: account.name.clone(),
+            subtitle: Some(account.email.clone()),
+            icon: Some("lucide:refresh-cw".to_string()),
+            color: None,
+            shortcut: None,
+            context: GLOBAL_CONTEXT.to_string(),
+            account_id: Some(account.id),
+            target: ActionTarget::Invoke {
+                command: "sync_account".to_string(),
+                args: json!({ "accountId": account.id }),
+            },
+        });
+    }
+
+    for folder in sources.folders.iter().filter(|folder| is_listed(folder)) {
+        let subtitle = sources.account_name(folder.account_id);
+
+        actions.push(Action {
+            id: format!("navigate:folder:{}", folder.id),
+            category: ActionCategory::Navigate,
+            title: folder.name.clone(),
+            subtitle: subtitle.clone(),
+            icon: folder.icon.clone(),
+            color: folder.color.clone(),
+            shortcut: None,
+            context: GLOBAL_CONTEXT.to_string(),
+            account_id: Some(folder.account_id),
+            target: navigate(&format!("mail/{}/folders/{}", folder.account_id, folder.id)),
+        });
+
+        let props = json!(folder.id);
+        actions.push(Action {
+            id: format!("move:folder:{}", folder.id),
+            category: ActionCategory::Move,
+            title: folder.name.clone(),
+            subtitle,
+            icon: folder.icon.clone(),
+            color: folder.color.clone(),
+            shortcut: shortcut_for(keymap, "MailList:moveEmail", Some(&props)),
+            context: "MailList".to_string(),
+            account_id: Some(folder.account_id),
+            target: run("MailList:moveEmail", Some(props)),
+        });
+    }
+
+    for label in &sources.labels {
+        let subtitle = label
+            .account_id
+            .and_then(|account_id| sources.account_name(account_id));
+
+        actions.push(Action {
+            id: format!("navigate:label:{}", label.id),
+            category: ActionCategory::Navigate,
+            title: label.name.clone(),
+            subtitle: subtitle.clone(),
+            icon: label.icon.clone(),
+            color: label.color.clone(),
+            shortcut: None,
+            context: GLOBAL_CONTEXT.to_string(),
+            account_id: label.account_id,
+            target: navigate(&format!("labels/{}", label.id)),
+        });
+
+        let props = json!(label.id);
+        actions.push(Action {
+            id: format!("label:{}", label.id),
+            category: ActionCategory::Label,
+            title: label.name.clone(),
+            subtitle,
+            icon: label.icon.clone(),
+            color: label.color.clone(),
+            shortcut: shortcut_for(keymap, "MailList:assignLabel", Some(&props)),
+            context: "MailList".to_string(),
+            account_id: label.account_id,
+            target: run("MailList:assignLabel", Some(props)),
+        });
+    }
+
+    for action in &mut actions {
+        if let ActionTarget::Run {
+            action: key,
+            props: None,
+        } = &action.target
+        {
+            action.shortcut = shortcut_for(keymap, key, None);
+        }
+    }
+
+    match context {
+        Some(context) => actions
+            .into_iter()
+            .filter(|action| action.context == GLOBAL_CONTEXT || action.context == context)
+            .collect(),
+        None => actions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::keybindings::{KeyAction, KeyBinding};
+    use std::collections::HashMap;
+
+    fn keymap() -> KeyMapFile {
+        vec![
+            KeyBinding {
+                context: "global".to_string(),
+                bindings: HashMap::from([
+                    (
+                        "c".to_string(),
+                        KeyAction::Simple("global:composeEmail".to_string()),
+                    ),
+                    (
+                        "g c".to_string(),
+                        KeyAction::Simple("global:composeEmail".to_string()),
+                    ),
+                ]),
+            },
+            KeyBinding {
+                context: "MailList".to_string(),
+                bindings: HashMap::from([(
+                    "r".to_string(),
+                    KeyAction::Simple("MailList:replyToEmail".to_string()),
+                )]),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_shortcuts_prefer_the_shortest_key() {
+        let actions = build(&ActionSources::default(), &keymap(), None);
+
+        let compose = actions
+            .iter()
+            .find(|action| action.id == "global:composeEmail")
+            .unwrap();
+        assert_eq!(compose.shortcut.as_deref(), Some("c"));
+
+        let forward = actions
+            .iter()
+            .find(|action| action.id == "MailList:forwardEmail")
+            .unwrap();
+        assert_eq!(forward.shortcut, None);
+    }
+
+    #[test]
+    fn test_context_filter() {
+        let actions = build(&ActionSources::default(), &keymap(), Some("MailList"));
+
+        assert!(actions
+            .iter()
+            .all(|action| action.context == GLOBAL_CONTEXT || action.context == "MailList"));
+        assert!(actions
+            .iter()
+            .any(|action| action.id == "MailList:replyToEmail"));
+        assert!(!actions
+            .iter()
+            .any(|action| action.id == "ConversationView:replyToEmail"));
+    }
+}
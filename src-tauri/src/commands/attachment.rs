@@ -1,10 +1,15 @@
 use crate::commands::emails::AttachmentData;
-use crate::database::repositories::{AttachmentRepository, SqliteAttachmentRepository};
+use crate::database::models::attachment::Attachment;
+use crate::database::models::email::Email;
+use crate::database::repositories::{
+    AttachmentRepository, EmailRepository, SqliteAttachmentRepository, SqliteEmailRepository,
+};
 use crate::state::AppState;
+use crate::sync::background_cleanup::AttachmentCacheUsage;
 use crate::sync::storage::PathGenerator;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::State;
 use uuid::Uuid;
 
@@ -21,6 +26,47 @@ pub struct AttachmentInfo {
     pub full_path: Option<String>,
 }
 
+/// Record the cached attachments of an opened email as recently opened, and
+/// start downloading inline attachments that were not cached during sync;
+/// `email:attachments-cached` is emitted once they are
+pub(crate) async fn email_attachments_opened(
+    state: &AppState,
+    email: &Email,
+    attachments: &[Attachment],
+) {
+    let cached_ids: Vec<Uuid> = attachments
+        .iter()
+        .filter(|attachment| attachment.is_cached)
+        .map(|attachment| attachment.id)
+        .collect();
+    if !cached_ids.is_empty() {
+        if let Err(e) = SqliteAttachmentRepository::new(state.db_pool.clone())
+            .mark_opened(&cached_ids)
+            .await
+        {
+            log::warn!(
+                "Failed to record opened attachments of email {}: {}",
+                email.id,
+                e
+            );
+        }
+    }
+
+    if email.is_draft {
+        return;
+    }
+
+    let attachment_ids = attachments
+        .iter()
+        .filter(|attachment| attachment.is_inline && !attachment.is_cached)
+        .map(|attachment| attachment.id)
+        .collect();
+
+    state
+        .sync_coordinator
+        .prefetch_attachments(email.account_id, email.id, attachment_ids);
+}
+
 #[tauri::command]
 pub async fn get_email_attachments(
     state: State<'_, AppState>,
@@ -42,33 +88,102 @@ pub async fn get_email_attachments(
 
     let attachment_infos: Vec<AttachmentInfo> = attachments
         .into_iter()
-        .map(|a| {
-            let full_path = if a.is_cached && a.cache_path.is_some() {
-                let cache_path = a.cache_path.unwrap();
-                let path_buf = PathGenerator::cache_path_to_pathbuf(&cache_path);
-                let full_path_buf = app_data_dir.join("attachments").join(path_buf);
-                Some(full_path_buf.to_string_lossy().to_string())
-            } else {
-                None
-            };
-
-            AttachmentInfo {
-                id: a.id.to_string(),
-                email_id: a.email_id.to_string(),
-                filename: a.filename,
-                content_type: a.content_type,
-                size: a.size,
-                is_inline: a.is_inline,
-                is_cached: a.is_cached,
-                content_id: a.content_id,
-                full_path,
-            }
-        })
+        .map(|a| attachment_info(a, &app_data_dir))
         .collect();
 
     Ok(attachment_infos)
 }
 
+fn attachment_info(a: Attachment, app_data_dir: &Path) -> AttachmentInfo {
+    let full_path = if a.is_cached && a.cache_path.is_some() {
+        let cache_path = a.cache_path.unwrap();
+        let path_buf = PathGenerator::cache_path_to_pathbuf(&cache_path);
+        let full_path_buf = app_data_dir.join("attachments").join(path_buf);
+        Some(full_path_buf.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    AttachmentInfo {
+        id: a.id.to_string(),
+        email_id: a.email_id.to_string(),
+        filename: a.filename,
+        content_type: a.content_type,
+        size: a.size,
+        is_inline: a.is_inline,
+        is_cached: a.is_cached,
+        content_id: a.content_id,
+        full_path,
+    }
+}
+
+/// Stream an attachment that was not cached during sync into the attachment
+/// cache. Progress is emitted as `attachment:download-progress` events.
+#[tauri::command]
+pub async fn download_attachment(
+    state: State<'_, AppState>,
+    attachment_id: String,
+) -> Result<AttachmentInfo, String> {
+    let attachment_uuid =
+        Uuid::parse_str(&attachment_id).map_err(|e| format!("Invalid attachment ID: {}", e))?;
+
+    let attachment_repo = SqliteAttachmentRepository::new(state.db_pool.clone());
+    let attachment = attachment_repo
+        .find_by_id(attachment_uuid)
+        .await
+        .map_err(|e| format!("Failed to get attachment: {}", e))?
+        .ok_or_else(|| format!("Attachment not found: {}", attachment_id))?;
+
+    let email = SqliteEmailRepository::new(state.db_pool.clone())
+        .find_by_id(attachment.email_id)
+        .await
+        .map_err(|e| format!("Failed to get email: {}", e))?
+        .ok_or_else(|| format!("Email not found: {}", attachment.email_id))?;
+
+    state
+        .sync_coordinator
+        .download_attachment(email.account_id, attachment_uuid)
+        .await
+        .map_err(|e| format!("Failed to download attachment: {}", e))?;
+
+    let attachment = attachment_repo
+        .find_by_id(attachment_uuid)
+        .await
+        .map_err(|e| format!("Failed to get attachment: {}", e))?
+        .ok_or_else(|| format!("Attachment not found: {}", attachment_id))?;
+
+    Ok(attachment_info(
+        attachment,
+        &PathBuf::from(&state.app_data_dir),
+    ))
+}
+
+/// Stop a running attachment download; returns false if none was running
+#[tauri::command]
+pub async fn cancel_download(
+    state: State<'_, AppState>,
+    attachment_id: String,
+) -> Result<bool, String> {
+    let attachment_uuid =
+        Uuid::parse_str(&attachment_id).map_err(|e| format!("Invalid attachment ID: {}", e))?;
+
+    Ok(state
+        .sync_coordinator
+        .cancel_attachment_download(attachment_uuid))
+}
+
+/// Size of the attachment cache and its configured quota
+#[tauri::command]
+pub async fn get_attachment_cache_usage(
+    state: State<'_, AppState>,
+) -> Result<AttachmentCacheUsage, String> {
+    state
+        .background_cleanup
+        .cache_usage()
+        .await
+        .map_err(|e| format!("Failed to get attachment cache usage: {}", e))
+}
+
 #[tauri::command]
 pub async fn open_attachment(_state: State<'_, AppState>, file_path: String) -> Result<(), String> {
     log::info!("Opening attachment: {}", file_path);
@@ -176,22 +291,33 @@ pub async fn read_attachment_for_forward(
         .map_err(|e| format!("Failed to get attachment: {}", e))?
         .ok_or_else(|| format!("Attachment not found: {}", attachment_id))?;
 
-    if !attachment.is_cached || attachment.cache_path.is_none() {
+    read_cached_attachment(&state.app_data_dir, &attachment)
+}
+
+/// The content of a downloaded attachment, for sending it on
+pub fn read_cached_attachment(
+    app_data_dir: &Path,
+    attachment: &Attachment,
+) -> Result<AttachmentData, String> {
+    let Some(cache_path) = attachment
+        .cache_path
+        .as_deref()
+        .filter(|_| attachment.is_cached)
+    else {
         return Err("Attachment not cached".to_string());
-    }
+    };
 
-    let app_data_dir = PathBuf::from(&state.app_data_dir);
-    let cache_path = attachment.cache_path.unwrap();
-    let path_buf = PathGenerator::cache_path_to_pathbuf(&cache_path);
+    let path_buf = PathGenerator::cache_path_to_pathbuf(cache_path);
     let full_path = app_data_dir.join("attachments").join(path_buf);
 
     let content =
         fs::read(&full_path).map_err(|e| format!("Failed to read attachment file: {}", e))?;
 
     Ok(AttachmentData {
-        filename: attachment.filename,
+        filename: attachment.filename.clone(),
         content,
-        content_type: Some(attachment.content_type),
+        content_type: Some(attachment.content_type.clone()),
+        content_id: None,
     })
 }
 
@@ -0,0 +1,167 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::database::models::automation_trigger::{
+    AutomationTrigger, TriggerDelivery, TriggerEvent, TriggerTarget,
+};
+use crate::database::repositories::{AutomationTriggerRepository, RepositoryFactory};
+use crate::services::automation_api::AutomationApiStatus;
+use crate::services::automation_triggers;
+use crate::state::AppState;
+
+/// Get the automation API settings, including the access token for scripts
+#[tauri::command]
+pub async fn get_automation_api_status(
+    state: State<'_, AppState>,
+) -> Result<AutomationApiStatus, String> {
+    state.automation_api.status()
+}
+
+/// Replace the automation API token, revoking access for existing scripts
+#[tauri::command]
+pub async fn regenerate_automation_api_token(state: State<'_, AppState>) -> Result<String, String> {
+    state.automation_api.regenerate_token()
+}
+
+/// Entries of a trigger's delivery log returned by default
+const DEFAULT_DELIVERY_LOG_LIMIT: i64 = 50;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveAutomationTriggerRequest {
+    /// `None` creates a new trigger
+    pub id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub event: TriggerEvent,
+    pub target: TriggerTarget,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn validate_trigger(name: &str, target: &TriggerTarget) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Trigger name cannot be empty".to_string());
+    }
+
+    match target {
+        TriggerTarget::Webhook { url, .. } => {
+            let parsed = url::Url::parse(url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err("Webhook URLs must use http or https".to_string());
+            }
+        }
+        TriggerTarget::Script { path, .. } => {
+            let path = std::path::Path::new(path);
+            if !path.is_absolute() {
+                return Err("Script path must be absolute".to_string());
+            }
+            if !path.is_file() {
+                return Err(format!("Script {} does not exist", path.display()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_automation_triggers(
+    state: State<'_, AppState>,
+) -> Result<Vec<AutomationTrigger>, String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .automation_trigger_repository()
+        .get_all()
+        .await
+        .map_err(|e| format!("Failed to get automation triggers: {}", e))
+}
+
+/// Create or update a trigger
+#[tauri::command]
+pub async fn save_automation_trigger(
+    state: State<'_, AppState>,
+    request: SaveAutomationTriggerRequest,
+) -> Result<AutomationTrigger, String> {
+    validate_trigger(&request.name, &request.target)?;
+
+    let repo = RepositoryFactory::new(state.db_pool.clone()).automation_trigger_repository();
+    let existing = match request.id {
+        Some(id) => Some(
+            repo.find_by_id(id)
+                .await
+                .map_err(|e| format!("Failed to find automation trigger: {}", e))?
+                .ok_or_else(|| format!("Automation trigger {} not found", id))?,
+        ),
+        None => None,
+    };
+
+    let trigger = AutomationTrigger {
+        id: existing.as_ref().map_or_else(Uuid::now_v7, |t| t.id),
+        account_id: request.account_id,
+        name: request.name.trim().to_string(),
+        enabled: request.enabled,
+        event: request.event,
+        target: request.target,
+        created_at: existing.as_ref().map_or_else(Utc::now, |t| t.created_at),
+        updated_at: Utc::now(),
+    };
+
+    if existing.is_some() {
+        repo.update(&trigger)
+            .await
+            .map_err(|e| format!("Failed to update automation trigger: {}", e))?;
+    } else {
+        repo.create(&trigger)
+            .await
+            .map_err(|e| format!("Failed to create automation trigger: {}", e))?;
+    }
+
+    Ok(trigger)
+}
+
+#[tauri::command]
+pub async fn delete_automation_trigger(
+    state: State<'_, AppState>,
+    trigger_id: Uuid,
+) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .automation_trigger_repository()
+        .delete(trigger_id)
+        .await
+        .map_err(|e| format!("Failed to delete automation trigger: {}", e))
+}
+
+/// Recent deliveries of a trigger, newest first
+#[tauri::command]
+pub async fn get_automation_trigger_deliveries(
+    state: State<'_, AppState>,
+    trigger_id: Uuid,
+    limit: Option<i64>,
+) -> Result<Vec<TriggerDelivery>, String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .automation_trigger_repository()
+        .find_deliveries_for_trigger(trigger_id, limit.unwrap_or(DEFAULT_DELIVERY_LOG_LIMIT))
+        .await
+        .map_err(|e| format!("Failed to get trigger deliveries: {}", e))
+}
+
+/// Queue a delivery with a sample payload, to check a webhook or script
+#[tauri::command]
+pub async fn test_automation_trigger(
+    state: State<'_, AppState>,
+    trigger_id: Uuid,
+) -> Result<Uuid, String> {
+    let trigger = RepositoryFactory::new(state.db_pool.clone())
+        .automation_trigger_repository()
+        .find_by_id(trigger_id)
+        .await
+        .map_err(|e| format!("Failed to find automation trigger: {}", e))?
+        .ok_or_else(|| format!("Automation trigger {} not found", trigger_id))?;
+
+    automation_triggers::enqueue(&state.db_pool, &trigger, None).await
+}
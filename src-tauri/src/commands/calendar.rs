@@ -0,0 +1,329 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::commands::emails::{send_email_from_account, AttachmentData, SendFromAccountRequest};
+use crate::database::models::calendar_event::{CalendarEvent, RsvpStatus};
+use crate::database::models::email::EmailAddress;
+use crate::database::models::signature::SignatureChoice;
+use crate::database::repositories::{
+    AccountRepository, CalendarEventRepository, EmailRepository, IdentityRepository,
+    SqliteAccountRepository, SqliteCalendarEventRepository, SqliteEmailRepository,
+    SqliteIdentityRepository,
+};
+use crate::services::icalendar::{self, Event};
+use crate::state::AppState;
+use crate::sync::storage::PathGenerator;
+
+/// An event to save as a file or add to the system calendar
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventSource {
+    /// The invitation an email carries
+    Invite { email_id: Uuid },
+    /// An event found in the text of an email, e.g. a date the AI analysis
+    /// extracted
+    Detected {
+        summary: String,
+        description: Option<String>,
+        location: Option<String>,
+        starts_at: DateTime<Utc>,
+        ends_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        all_day: bool,
+        email_id: Option<Uuid>,
+    },
+}
+
+/// Events of received invitations overlapping the given range
+#[tauri::command]
+pub async fn get_calendar_events(
+    state: State<'_, AppState>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<CalendarEvent>, String> {
+    SqliteCalendarEventRepository::new(state.db_pool.clone())
+        .find_in_range(start, end)
+        .await
+        .map_err(|e| format!("Failed to get calendar events: {}", e))
+}
+
+/// Answers the invitation of an email. Unless `send_reply` is `false`, the
+/// answer is sent to the organizer as an iTIP `REPLY` from the invited address.
+#[tauri::command]
+pub async fn respond_to_invite(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+    response: RsvpStatus,
+    send_reply: Option<bool>,
+) -> Result<CalendarEvent, String> {
+    if response == RsvpStatus::NeedsAction {
+        return Err("An invitation can only be accepted, tentatively accepted or declined".into());
+    }
+
+    let pool = state.db_pool.clone();
+    let event_repo = SqliteCalendarEventRepository::new(pool.clone());
+
+    let event = event_repo
+        .find_by_email(email_id)
+        .await
+        .map_err(|e| format!("Failed to find calendar event: {}", e))?
+        .ok_or_else(|| format!("Email {} has no invitation", email_id))?;
+    if event.cancelled {
+        return Err("The event has been cancelled".to_string());
+    }
+
+    if send_reply.unwrap_or(true) {
+        let request = reply_request(&pool, &event, response).await?;
+        send_email_from_account(state, request).await?;
+    }
+
+    event_repo
+        .set_rsvp(event.account_id, &event.uid, response, Utc::now())
+        .await
+        .map_err(|e| format!("Failed to save response: {}", e))?;
+
+    log::info!(
+        "[Calendar] Answered invitation {} with {}",
+        event.uid,
+        response.as_str()
+    );
+
+    event_repo
+        .find_by_email(email_id)
+        .await
+        .map_err(|e| format!("Failed to find calendar event: {}", e))?
+        .ok_or_else(|| format!("Email {} has no invitation", email_id))
+}
+
+/// Save an event as an .ics file at `destination`
+#[tauri::command]
+pub async fn export_event_ics(
+    state: State<'_, AppState>,
+    event: EventSource,
+    destination: String,
+) -> Result<String, String> {
+    let event = resolve_event(&state, event).await?;
+
+    std::fs::write(&destination, icalendar::build_event(&event, Utc::now()))
+        .map_err(|e| format!("Failed to write calendar file: {}", e))?;
+
+    Ok(destination)
+}
+
+/// Open an event in the default calendar app, which offers to add it
+#[tauri::command]
+pub async fn open_in_system_calendar(
+    state: State<'_, AppState>,
+    event: EventSource,
+) -> Result<(), String> {
+    let event = resolve_event(&state, event).await?;
+
+    let export_dir = state.app_data_dir.join("exports");
+    std::fs::create_dir_all(&export_dir)
+        .map_err(|e| format!("Failed to create export directory: {}", e))?;
+    let path = export_dir.join(ics_filename(&event));
+    std::fs::write(&path, icalendar::build_event(&event, Utc::now()))
+        .map_err(|e| format!("Failed to write calendar file: {}", e))?;
+
+    opener::open(&path).map_err(|e| format!("Failed to open calendar file: {}", e))
+}
+
+/// The message carrying the answer to the organizer, threaded to the invitation
+async fn reply_request(
+    pool: &sqlx::SqlitePool,
+    event: &CalendarEvent,
+    response: RsvpStatus,
+) -> Result<SendFromAccountRequest, String> {
+    let organizer = event
+        .organizer
+        .clone()
+        .ok_or_else(|| "The invitation has no organizer to reply to".to_string())?;
+
+    let email = SqliteEmailRepository::new(pool.clone())
+        .find_by_id(event.email_id)
+        .await
+        .map_err(|e| format!("Failed to find email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", event.email_id))?;
+    let account = SqliteAccountRepository::new(pool.clone())
+        .find_by_id(event.account_id)
+        .await
+        .map_err(|e| format!("Failed to find account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", event.account_id))?;
+
+    // Answer from the address that was invited, which may be an alias
+    let address = event
+        .attendee_email
+        .clone()
+        .unwrap_or_else(|| account.email.to_lowercase());
+    let identity = SqliteIdentityRepository::new(pool.clone())
+        .find_by_email(account.id, &address)
+        .await
+        .map_err(|e| format!("Failed to find identity: {}", e))?;
+    let attendee = EmailAddress {
+        name: event
+            .attendees
+            .iter()
+            .find(|attendee| attendee.address == address)
+            .and_then(|attendee| attendee.name.clone())
+            .or_else(|| Some(account.name.clone())),
+        address,
+    };
+
+    let ics = icalendar::build_reply(&Event::from(event), &attendee, response, Utc::now());
+    let summary = event.summary.clone().unwrap_or_default();
+
+    Ok(SendFromAccountRequest {
+        account_id: account.id,
+        to: vec![organizer],
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        subject: format!("{}: {}", subject_prefix(response), summary),
+        body: format!(
+            "<p>{} {} this invitation.</p>",
+            escape_html(attendee.name.as_deref().unwrap_or(&attendee.address)),
+            response_verb(response)
+        ),
+        attachments: vec![AttachmentData {
+            filename: "invite.ics".to_string(),
+            content: ics.into_bytes(),
+            content_type: Some("text/calendar; method=REPLY; charset=UTF-8".to_string()),
+            content_id: None,
+        }],
+        draft_id: None,
+        conversation_id: email.conversation_id.clone(),
+        in_reply_to: Some(email.message_id.clone()),
+        references: Some(email.message_id.clone()),
+        identity_id: identity.map(|identity| identity.id),
+        dsn: None,
+        read_receipt: false,
+        smime: None,
+        signature: SignatureChoice::None,
+    })
+}
+
+/// Subject prefix of an answer, as calendar clients show them
+fn subject_prefix(response: RsvpStatus) -> &'static str {
+    match response {
+        RsvpStatus::Accepted => "Accepted",
+        RsvpStatus::Tentative => "Tentative",
+        RsvpStatus::Declined => "Declined",
+        RsvpStatus::NeedsAction => "Invitation",
+    }
+}
+
+fn response_verb(response: RsvpStatus) -> &'static str {
+    match response {
+        RsvpStatus::Accepted => "has accepted",
+        RsvpStatus::Tentative => "has tentatively accepted",
+        RsvpStatus::Declined => "has declined",
+        RsvpStatus::NeedsAction => "has not answered",
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+async fn resolve_event(state: &AppState, source: EventSource) -> Result<Event, String> {
+    match source {
+        EventSource::Invite { email_id } => {
+            let event = SqliteCalendarEventRepository::new(state.db_pool.clone())
+                .find_by_email(email_id)
+                .await
+                .map_err(|e| format!("Failed to find calendar event: {}", e))?
+                .ok_or_else(|| format!("Email {} has no invitation", email_id))?;
+            Ok(Event::from(&event))
+        }
+        EventSource::Detected {
+            summary,
+            description,
+            location,
+            starts_at,
+            ends_at,
+            all_day,
+            email_id,
+        } => {
+            if ends_at.is_some_and(|ends_at| ends_at < starts_at) {
+                return Err("The event ends before it starts".to_string());
+            }
+
+            Ok(Event {
+                uid: detected_uid(email_id, starts_at),
+                sequence: 0,
+                recurrence_id: None,
+                summary: Some(summary),
+                description,
+                location,
+                starts_at,
+                ends_at,
+                all_day,
+                status: None,
+                organizer: None,
+                attendees: Vec::new(),
+            })
+        }
+    }
+}
+
+/// Events found in the same email at the same time keep their uid, so adding
+/// one twice updates the calendar entry instead of duplicating it
+fn detected_uid(email_id: Option<Uuid>, starts_at: DateTime<Utc>) -> String {
+    match email_id {
+        Some(email_id) => format!("{}-{}@ravn.app", email_id, starts_at.timestamp()),
+        None => format!("{}@ravn.app", Uuid::now_v7()),
+    }
+}
+
+fn ics_filename(event: &Event) -> PathBuf {
+    let name = event
+        .summary
+        .as_deref()
+        .map(str::trim)
+        .filter(|summary| !summary.is_empty())
+        .unwrap_or("event");
+    PathBuf::from(format!("{}.ics", PathGenerator::sanitize_filename(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detected_event_identity() {
+        let email_id = Uuid::now_v7();
+        let starts_at = Utc::now();
+
+        assert_eq!(
+            detected_uid(Some(email_id), starts_at),
+            detected_uid(Some(email_id), starts_at)
+        );
+        assert_ne!(detected_uid(None, starts_at), detected_uid(None, starts_at));
+
+        let mut event = Event {
+            uid: detected_uid(None, starts_at),
+            sequence: 0,
+            recurrence_id: None,
+            summary: Some("Review: Q2/Q3 plans".to_string()),
+            description: None,
+            location: None,
+            starts_at,
+            ends_at: None,
+            all_day: false,
+            status: None,
+            organizer: None,
+            attendees: Vec::new(),
+        };
+        assert_eq!(
+            ics_filename(&event),
+            PathBuf::from("Review_ Q2_Q3 plans.ics")
+        );
+
+        event.summary = Some("  ".to_string());
+        assert_eq!(ics_filename(&event), PathBuf::from("event.ics"));
+    }
+}
@@ -1,16 +1,35 @@
 use crate::config::ConfigValue;
+use crate::services::app_lock::LOCKED_ERROR;
 use crate::state::AppState;
+use crate::sync::auth::SYSTEM_KEYCHAIN_SETTING;
 use serde_json::Value as JsonValue;
 use tauri::{Emitter, State};
 
 /// Get a setting by key
 #[tauri::command]
 pub async fn get_setting(state: State<'_, AppState>, key: String) -> Result<JsonValue, String> {
+    if !state.app_lock.can_read_setting(&key) {
+        return Err(LOCKED_ERROR.to_string());
+    }
+
     let value: ConfigValue = state.settings.get(&key).map_err(|e| e.to_string())?;
 
     value.try_deserialize().map_err(|e| e.to_string())
 }
 
+/// Move the stored secrets when the keychain setting is among `keys`
+async fn migrate_secrets_if_changed(state: &AppState, keys: &[String]) -> Result<(), String> {
+    if !keys.iter().any(|key| key == SYSTEM_KEYCHAIN_SETTING) {
+        return Ok(());
+    }
+
+    state
+        .credential_store
+        .migrate_secrets()
+        .await
+        .map_err(|e| format!("Failed to move account secrets: {}", e))
+}
+
 #[tauri::command]
 pub async fn reload_settings(state: State<'_, AppState>) -> Result<(), String> {
     state.settings.reload().map_err(|e| e.to_string())
@@ -43,9 +62,12 @@ pub async fn set_setting(
     let mut results = Vec::new();
     flatten_value(&key, &value, &mut results);
 
+    let changed_keys = results.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+
     for (k, v) in results {
         state.settings.set(&k, v).map_err(|e| e.to_string())?;
     }
+    migrate_secrets_if_changed(&state, &changed_keys).await?;
 
     state
         .app_handle
@@ -58,6 +80,7 @@ pub async fn set_setting(
 #[tauri::command]
 pub async fn remove_setting(state: State<'_, AppState>, key: String) -> Result<(), String> {
     state.settings.remove(&key).map_err(|e| e.to_string())?;
+    migrate_secrets_if_changed(&state, std::slice::from_ref(&key)).await?;
 
     state
         .app_handle
@@ -75,7 +98,8 @@ pub async fn get_user_keys(state: State<'_, AppState>) -> Result<Vec<String>, St
 /// Get all settings
 #[tauri::command]
 pub async fn get_all_settings(state: State<'_, AppState>) -> Result<JsonValue, String> {
-    state.settings.get_all().map_err(|e| e.to_string())
+    let settings = state.settings.get_all().map_err(|e| e.to_string())?;
+    Ok(state.app_lock.readable_settings(settings))
 }
 
 /// Set multiple settings at once - only saves leaf values (non-object values)
@@ -117,6 +141,7 @@ pub async fn set_settings(state: State<'_, AppState>, settings: JsonValue) -> Re
     for (key, value) in flattened {
         state.settings.set(&key, value).map_err(|e| e.to_string())?;
     }
+    migrate_secrets_if_changed(&state, &changed_keys).await?;
 
     state
         .app_handle
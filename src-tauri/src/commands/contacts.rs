@@ -1,11 +1,24 @@
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use uuid::Uuid;
 
-use crate::database::models::contact::{Contact, ContactSummary};
+use crate::database::models::contact::{
+    Contact, ContactField, ContactSummary, RecipientSuggestion,
+};
+use crate::database::models::contact_security::{ContactSecurity, EncryptionPolicy, PgpKeySource};
 use crate::database::models::folder::FolderType;
-use crate::database::repositories::{ContactRepository, EmailRepository, RepositoryFactory};
+use crate::database::repositories::{
+    ContactRepository, ContactSecurityRepository, EmailRepository, RepositoryFactory,
+};
+use crate::services::app_lock;
+use crate::services::contact_ranking;
+use crate::services::contact_security;
+use crate::services::feature_flags::Feature;
+use crate::services::pgp_keys;
 use crate::state::AppState;
+use crate::sync::background_contact_date_notifier::{load_upcoming, UpcomingContactDate};
+use crate::sync::priority;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchContactsRequest {
@@ -13,6 +26,16 @@ pub struct SearchContactsRequest {
     pub limit: Option<i64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutocompleteRecipientsRequest {
+    pub query: String,
+    /// Account the message is written from; interactions of other accounts are ignored
+    pub account_id: Option<Uuid>,
+    /// Address field being filled in
+    pub field: Option<ContactField>,
+    pub limit: Option<usize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetTopContactsRequest {
     pub limit: Option<i64>,
@@ -24,6 +47,9 @@ pub struct GetContactsRequest {
     pub offset: Option<i64>,
 }
 
+/// Candidates ranked by `autocomplete_recipients`
+const AUTOCOMPLETE_CANDIDATES: i64 = 200;
+
 #[tauri::command]
 pub async fn search_contacts(
     state: State<'_, AppState>,
@@ -43,6 +69,35 @@ pub async fn search_contacts(
         .map_err(|e| format!("Failed to search contacts: {}", e))
 }
 
+/// Contacts matching the typed text, ranked by match quality and by how often
+/// and how recently they were mailed from the account in the given field
+#[tauri::command]
+pub async fn autocomplete_recipients(
+    state: State<'_, AppState>,
+    request: AutocompleteRecipientsRequest,
+) -> Result<Vec<RecipientSuggestion>, String> {
+    let contact_repo = RepositoryFactory::new(state.db_pool.clone()).contact_repository();
+
+    let candidates = contact_repo
+        .find_matching(request.query.trim(), AUTOCOMPLETE_CANDIDATES)
+        .await
+        .map_err(|e| format!("Failed to search contacts: {}", e))?;
+    let contact_ids: Vec<Uuid> = candidates.iter().map(|c| c.id).collect();
+    let interactions = contact_repo
+        .find_interactions(&contact_ids, request.account_id)
+        .await
+        .map_err(|e| format!("Failed to load contact interactions: {}", e))?;
+
+    Ok(contact_ranking::rank(
+        &request.query,
+        candidates,
+        &interactions,
+        request.field,
+        Utc::now(),
+        request.limit.unwrap_or(10),
+    ))
+}
+
 #[tauri::command]
 pub async fn get_top_contacts(
     state: State<'_, AppState>,
@@ -123,10 +178,65 @@ pub async fn update_contact(state: State<'_, AppState>, contact: Contact) -> Res
     let repo_factory = RepositoryFactory::new(state.db_pool.clone());
     let contact_repo = repo_factory.contact_repository();
 
+    let was_vip = contact_repo
+        .find_by_id(contact.id)
+        .await
+        .map_err(|e| format!("Failed to get contact: {}", e))?
+        .is_some_and(|existing| existing.is_vip);
+
     contact_repo
         .update(&contact)
         .await
-        .map_err(|e| format!("Failed to update contact: {}", e))
+        .map_err(|e| format!("Failed to update contact: {}", e))?;
+
+    if was_vip != contact.is_vip {
+        apply_vip_to_emails(&state, &contact.email, contact.is_vip).await?;
+    }
+
+    Ok(())
+}
+
+/// Mark a contact as VIP, making their mail important, or unmark them
+#[tauri::command]
+pub async fn set_contact_vip(
+    state: State<'_, AppState>,
+    contact_id: Uuid,
+    is_vip: bool,
+) -> Result<(), String> {
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+    let contact_repo = repo_factory.contact_repository();
+
+    let contact = contact_repo
+        .find_by_id(contact_id)
+        .await
+        .map_err(|e| format!("Failed to get contact: {}", e))?
+        .ok_or_else(|| format!("Contact {} not found", contact_id))?;
+
+    contact_repo
+        .set_vip(contact_id, is_vip)
+        .await
+        .map_err(|e| format!("Failed to update contact: {}", e))?;
+
+    apply_vip_to_emails(&state, &contact.email, is_vip).await
+}
+
+/// Emails are scored when they are synced, so the mail already received from
+/// the contact is rescored here
+async fn apply_vip_to_emails(state: &AppState, address: &str, is_vip: bool) -> Result<(), String> {
+    let updated = RepositoryFactory::new(state.db_pool.clone())
+        .email_repository()
+        .set_sender_priority_flag(address, priority::VIP_SENDER, is_vip)
+        .await
+        .map_err(|e| format!("Failed to update email priorities: {}", e))?;
+
+    log::debug!(
+        "Updated the priority of {} emails from {} (VIP: {})",
+        updated,
+        address,
+        is_vip
+    );
+
+    Ok(())
 }
 
 #[tauri::command]
@@ -174,26 +284,48 @@ pub async fn resync_contact_counters(state: State<'_, AppState>) -> Result<Strin
         let sent_at = email.sent_at;
 
         if *folder_type == FolderType::Sent {
-            // For sent emails, increment send_count for all recipients
-            let to = &email.to.0;
-            let cc = &email.cc.0;
-            let bcc = &email.bcc.0;
-
-            for addr in to.iter().chain(cc.iter()).chain(bcc.iter()) {
-                contact_repo
-                    .increment_send_count(&addr.address, addr.name.as_deref(), sent_at)
-                    .await
-                    .map_err(|e| format!("Failed to increment send count: {}", e))?;
-                sent_count += 1;
+            // For sent emails, increment send_count for all recipients and
+            // track the field each one was addressed in
+            let fields = [
+                (ContactField::To, &email.to.0),
+                (ContactField::Cc, &email.cc.0),
+                (ContactField::Bcc, &email.bcc.0),
+            ];
+            for (field, addrs) in fields {
+                for addr in addrs {
+                    let contact_id = contact_repo
+                        .increment_send_count(&addr.address, addr.name.as_deref(), sent_at)
+                        .await
+                        .map_err(|e| format!("Failed to increment send count: {}", e))?;
+                    contact_repo
+                        .record_interaction(
+                            contact_id,
+                            email.account_id,
+                            field,
+                            sent_at.unwrap_or(email.received_at),
+                        )
+                        .await
+                        .map_err(|e| format!("Failed to record interaction: {}", e))?;
+                    sent_count += 1;
+                }
             }
         } else {
             // For received emails, increment receive_count for sender
             let from = &email.from.0;
 
-            contact_repo
+            let contact_id = contact_repo
                 .increment_receive_count(&from.address, from.name.as_deref())
                 .await
                 .map_err(|e| format!("Failed to increment receive count: {}", e))?;
+            contact_repo
+                .record_interaction(
+                    contact_id,
+                    email.account_id,
+                    ContactField::From,
+                    email.received_at,
+                )
+                .await
+                .map_err(|e| format!("Failed to record interaction: {}", e))?;
             received_count += 1;
         }
     }
@@ -208,3 +340,196 @@ pub async fn resync_contact_counters(state: State<'_, AppState>) -> Result<Strin
 
     Ok(message)
 }
+
+/// Birthdays and anniversaries coming up in the next `days` days (default 30)
+#[tauri::command]
+pub async fn get_upcoming_contact_dates(
+    state: State<'_, AppState>,
+    days: Option<u32>,
+) -> Result<Vec<UpcomingContactDate>, String> {
+    load_upcoming(
+        &state.db_pool,
+        chrono::Local::now().date_naive(),
+        days.unwrap_or(30),
+    )
+    .await
+    .map_err(|e| format!("Failed to get upcoming contact dates: {}", e))
+}
+
+/// Pulls birthdays and anniversaries from connected Google and Microsoft accounts
+#[tauri::command]
+pub async fn sync_contact_dates(state: State<'_, AppState>) -> Result<usize, String> {
+    state
+        .background_contact_date_notifier
+        .sync_provider_dates()
+        .await
+        .map_err(|e| format!("Failed to sync contact dates: {}", e))
+}
+
+/// Encryption preferences of an address; addresses without stored
+/// preferences get the defaults
+#[tauri::command]
+pub async fn get_contact_security(
+    state: State<'_, AppState>,
+    email: String,
+) -> Result<ContactSecurity, String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .contact_security_repository()
+        .find_by_email(&email)
+        .await
+        .map(|security| security.unwrap_or_else(|| ContactSecurity::new(&email)))
+        .map_err(|e| format!("Failed to get contact security preferences: {}", e))
+}
+
+/// Encryption preferences of the composer's recipients
+///
+/// With PGP enabled, keys of recipients that were not looked up recently are
+/// discovered in the background and announced with `contact-security:updated`.
+#[tauri::command]
+pub async fn get_recipients_security(
+    state: State<'_, AppState>,
+    emails: Vec<String>,
+) -> Result<Vec<ContactSecurity>, String> {
+    let repo = RepositoryFactory::new(state.db_pool.clone()).contact_security_repository();
+    let mut preferences = Vec::with_capacity(emails.len());
+    for email in &emails {
+        let security = repo
+            .find_by_email(email)
+            .await
+            .map_err(|e| format!("Failed to get contact security preferences: {}", e))?
+            .unwrap_or_else(|| ContactSecurity::new(email));
+        preferences.push(security);
+    }
+
+    if state.feature_flags.is_enabled(Feature::Pgp).await {
+        let stale: Vec<String> = preferences
+            .iter()
+            .filter(|security| contact_security::needs_key_lookup(security))
+            .map(|security| security.email.clone())
+            .collect();
+
+        if !stale.is_empty() {
+            let pool = state.db_pool.clone();
+            let app_handle = state.app_handle.clone();
+            tokio::spawn(async move {
+                for email in stale {
+                    match contact_security::refresh_key(&pool, &email).await {
+                        Ok(security) => {
+                            if let Err(e) = app_lock::emit_mail_event(
+                                &app_handle,
+                                "contact-security:updated",
+                                &security,
+                            ) {
+                                log::error!("Failed to emit event: {}", e);
+                            }
+                        }
+                        Err(e) => log::warn!("[PGP] Key lookup for {} failed: {}", email, e),
+                    }
+                }
+            });
+        }
+    }
+
+    Ok(preferences)
+}
+
+/// Set how mail to an address must be protected. Always encrypting with PGP
+/// pins the contact's current key.
+#[tauri::command]
+pub async fn set_contact_encryption_policy(
+    state: State<'_, AppState>,
+    email: String,
+    policy: EncryptionPolicy,
+) -> Result<ContactSecurity, String> {
+    let repo = RepositoryFactory::new(state.db_pool.clone()).contact_security_repository();
+    let mut security = repo
+        .find_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to get contact security preferences: {}", e))?
+        .unwrap_or_else(|| ContactSecurity::new(&email));
+
+    if policy == EncryptionPolicy::AlwaysPgp {
+        state.feature_flags.require(Feature::Pgp).await?;
+        if security.pgp_public_key.is_none() {
+            return Err(format!("No OpenPGP key is known for {}", security.email));
+        }
+    }
+
+    security.policy = policy;
+    repo.upsert(&security)
+        .await
+        .map_err(|e| format!("Failed to save contact security preferences: {}", e))?;
+
+    Ok(security)
+}
+
+/// Look up an address's OpenPGP key through WKD and keys.openpgp.org now
+#[tauri::command]
+pub async fn discover_contact_pgp_key(
+    state: State<'_, AppState>,
+    email: String,
+) -> Result<ContactSecurity, String> {
+    state.feature_flags.require(Feature::Pgp).await?;
+
+    contact_security::refresh_key(&state.db_pool, &email).await
+}
+
+/// Import an ASCII-armored OpenPGP key for an address, replacing a discovered
+/// or pinned one. The key must carry the address as a user ID.
+#[tauri::command]
+pub async fn import_contact_pgp_key(
+    state: State<'_, AppState>,
+    email: String,
+    armored_key: String,
+) -> Result<ContactSecurity, String> {
+    state.feature_flags.require(Feature::Pgp).await?;
+
+    let key = pgp_keys::parse_keys(&pgp_keys::dearmor(&armored_key)?)?
+        .into_iter()
+        .find(|key| key.has_address(&email))
+        .ok_or_else(|| format!("The key has no user ID for {}", email))?;
+
+    let repo = RepositoryFactory::new(state.db_pool.clone()).contact_security_repository();
+    let mut security = repo
+        .find_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to get contact security preferences: {}", e))?
+        .unwrap_or_else(|| ContactSecurity::new(&email));
+
+    security.pgp_public_key = Some(pgp_keys::armor(&key.data));
+    security.pgp_fingerprint = Some(key.fingerprint);
+    security.pgp_key_source = Some(PgpKeySource::Manual);
+    security.pgp_key_fetched_at = Some(Utc::now());
+    repo.upsert(&security)
+        .await
+        .map_err(|e| format!("Failed to save contact security preferences: {}", e))?;
+
+    Ok(security)
+}
+
+/// Forget an address's OpenPGP key. A contact that was always encrypted
+/// with it falls back to the composer's choice.
+#[tauri::command]
+pub async fn remove_contact_pgp_key(
+    state: State<'_, AppState>,
+    email: String,
+) -> Result<ContactSecurity, String> {
+    let repo = RepositoryFactory::new(state.db_pool.clone()).contact_security_repository();
+    let mut security = repo
+        .find_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to get contact security preferences: {}", e))?
+        .unwrap_or_else(|| ContactSecurity::new(&email));
+
+    if security.policy == EncryptionPolicy::AlwaysPgp {
+        security.policy = EncryptionPolicy::Auto;
+    }
+    security.pgp_fingerprint = None;
+    security.pgp_public_key = None;
+    security.pgp_key_source = None;
+    repo.upsert(&security)
+        .await
+        .map_err(|e| format!("Failed to save contact security preferences: {}", e))?;
+
+    Ok(security)
+}
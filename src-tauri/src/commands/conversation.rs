@@ -1,17 +1,25 @@
 /// Conversation/thread query commands using repository pattern and DTOs
+use chrono::Utc;
 use std::collections::{HashMap, HashSet};
 use tauri::State;
 use uuid::Uuid;
 
+use crate::commands::attachment::email_attachments_opened;
+use crate::commands::emails::block_remote_content;
 use crate::database::models::conversation::{ConversationDetail, ConversationListItem};
 use crate::database::models::email_dto::{AttachmentInfo, EmailDetail, EmailListItem, LabelInfo};
+use crate::database::models::folder::FolderType;
 use crate::database::repositories::{
-    AttachmentRepository, ConversationRepository, EmailRepository, LabelRepository,
-    SqliteAttachmentRepository, SqliteConversationRepository, SqliteEmailRepository,
+    AccountRepository, AttachmentRepository, ConversationRepository, EmailRepository,
+    FolderRepository, LabelRepository, SqliteAccountRepository, SqliteAttachmentRepository,
+    SqliteConversationRepository, SqliteEmailRepository, SqliteFolderRepository,
     SqliteLabelRepository,
 };
+use crate::services::app_lock;
+use crate::services::conversation_export::{self, TranscriptMessage};
 use crate::services::notification_service::NotificationService;
 use crate::state::AppState;
+use crate::sync::muted_conversations;
 
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -99,6 +107,13 @@ async fn reminder_notification_map(
         .await
 }
 
+async fn account_color_map(state: &State<'_, AppState>) -> Result<HashMap<Uuid, String>, String> {
+    SqliteAccountRepository::new(state.db_pool.clone())
+        .find_colors()
+        .await
+        .map_err(|e| format!("Failed to fetch account colors: {}", e))
+}
+
 fn matches_scope_condition_across_emails(
     emails_with_labels: &[(crate::database::models::email::Email, HashSet<Uuid>)],
     condition: &ScopeFilterConditionRequest,
@@ -294,6 +309,7 @@ pub async fn get_conversations_for_label(
         .map_err(|e| format!("Failed to fetch conversations: {}", e))?;
 
     let mut conversation_map: HashMap<Uuid, _> = HashMap::new();
+    let account_colors = account_color_map(&state).await?;
     for conversation in conversations {
         let conversation_emails = email_repo
             .find_by_conversation_id(conversation.id)
@@ -316,6 +332,7 @@ pub async fn get_conversations_for_label(
 
             let mut email_list_item = EmailListItem::from_email(&email, labels);
             email_list_item.notified_at = notified_at_by_email.get(&email.id).copied();
+            email_list_item.account_color = account_colors.get(&email.account_id).cloned();
             email_list_items.push(email_list_item);
         }
 
@@ -388,6 +405,7 @@ pub async fn get_conversations_for_folder(
         .map_err(|e| format!("Failed to fetch conversations: {}", e))?;
 
     let mut conversation_map: HashMap<Uuid, _> = HashMap::new();
+    let account_colors = account_color_map(&state).await?;
     for conversation in conversations {
         let conversation_emails = email_repo
             .find_by_conversation_id(conversation.id)
@@ -410,6 +428,7 @@ pub async fn get_conversations_for_folder(
 
             let mut email_list_item = EmailListItem::from_email(&email, labels);
             email_list_item.notified_at = notified_at_by_email.get(&email.id).copied();
+            email_list_item.account_color = account_colors.get(&email.account_id).cloned();
             email_list_items.push(email_list_item);
         }
 
@@ -695,6 +714,7 @@ pub async fn get_conversations_for_scope(
         .map_err(|e| format!("Failed to fetch scoped conversations: {}", e))?;
 
     let mut conversation_map: HashMap<Uuid, _> = HashMap::new();
+    let account_colors = account_color_map(&state).await?;
     for conversation in conversations {
         let conversation_emails = email_repo
             .find_by_conversation_id(conversation.id)
@@ -717,6 +737,7 @@ pub async fn get_conversations_for_scope(
 
             let mut email_list_item = EmailListItem::from_email(&email, labels);
             email_list_item.notified_at = notified_at_by_email.get(&email.id).copied();
+            email_list_item.account_color = account_colors.get(&email.account_id).cloned();
             email_list_items.push(email_list_item);
         }
 
@@ -731,21 +752,29 @@ pub async fn get_conversations_for_scope(
     Ok(result)
 }
 
-/// Get conversation by email message ID
+/// Get the whole conversation of an email by its message ID, with the
+/// messages of every folder of the account, like Sent and Archive
 #[tauri::command]
 pub async fn get_conversation_for_message_id(
     state: State<'_, AppState>,
     message_id: String,
+    account_id: Option<Uuid>,
 ) -> Result<ConversationListItem, String> {
     let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
     let conversation_repo = SqliteConversationRepository::new(state.db_pool.clone());
     let label_repo = SqliteLabelRepository::new(state.db_pool.clone());
 
-    let email = email_repo
-        .find_by_message_id(&message_id)
-        .await
-        .map_err(|e| format!("Failed to fetch email: {}", e))?
-        .ok_or_else(|| format!("Email with message_id {} not found", message_id))?;
+    let email = match account_id {
+        Some(account_id) => email_repo
+            .find_by_remote_id_or_message_id(account_id, "", &message_id)
+            .await
+            .map_err(|e| format!("Failed to fetch email: {}", e))?,
+        None => email_repo
+            .find_by_message_id(&message_id)
+            .await
+            .map_err(|e| format!("Failed to fetch email: {}", e))?,
+    }
+    .ok_or_else(|| format!("Email with message_id {} not found", message_id))?;
 
     let conversation_id_str = email
         .conversation_id
@@ -760,16 +789,19 @@ pub async fn get_conversation_for_message_id(
         .map_err(|e| format!("Failed to fetch conversation: {}", e))?
         .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
 
-    let conversation_emails = email_repo
-        .find_by_conversation_id(conversation_id)
+    let conversation_messages = conversation_repo
+        .find_messages_across_folders(conversation_id, email.account_id)
         .await
         .map_err(|e| format!("Failed to fetch conversation emails: {}", e))?;
-    let conversation_email_ids: Vec<Uuid> =
-        conversation_emails.iter().map(|item| item.id).collect();
+    let conversation_email_ids: Vec<Uuid> = conversation_messages
+        .iter()
+        .map(|(item, _)| item.id)
+        .collect();
     let notified_at_by_email = reminder_notification_map(&state, &conversation_email_ids).await?;
+    let account_colors = account_color_map(&state).await?;
 
     let mut email_list_items = Vec::new();
-    for email in conversation_emails {
+    for (email, folder) in conversation_messages {
         let labels = label_repo
             .find_by_email(email.id)
             .await
@@ -780,10 +812,16 @@ pub async fn get_conversation_for_message_id(
 
         let mut email_list_item = EmailListItem::from_email(&email, labels);
         email_list_item.notified_at = notified_at_by_email.get(&email.id).copied();
+        email_list_item.account_color = account_colors.get(&email.account_id).cloned();
+        email_list_item.folder = Some(folder);
         email_list_items.push(email_list_item);
     }
 
-    Ok(conversation.to_list_item(email_list_items))
+    // Copies of a message in several folders are listed once
+    let mut conversation = conversation.to_list_item(email_list_items);
+    conversation.message_count = conversation.messages.len() as i64;
+
+    Ok(conversation)
 }
 
 /// Get full conversation details by conversation ID
@@ -821,16 +859,19 @@ pub async fn get_conversation_by_id(
             .map(LabelInfo::from)
             .collect();
 
-        let attachments = attachment_repo
+        let stored_attachments = attachment_repo
             .find_by_email(email.id)
             .await
-            .map_err(|e| format!("Failed to fetch attachments: {}", e))?
+            .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+        email_attachments_opened(&state, &email, &stored_attachments).await;
+        let attachments = stored_attachments
             .iter()
             .map(AttachmentInfo::from)
             .collect();
 
         let mut email_detail = EmailDetail::from_email(&email, labels, attachments);
         email_detail.notified_at = notified_at_by_email.get(&email.id).copied();
+        block_remote_content(&state, &email, &mut email_detail).await?;
         email_details.push(email_detail);
     }
 
@@ -844,3 +885,166 @@ pub async fn get_conversation_by_id(
 
     Ok(conversation.to_detail(email_details, all_attachments))
 }
+
+/// Export a whole conversation as one self-contained HTML file
+///
+/// Inline images are embedded when they are cached; drafts are left out.
+#[tauri::command]
+pub async fn export_html(
+    state: State<'_, AppState>,
+    conversation_id: Uuid,
+    destination: String,
+) -> Result<(), String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let conversation_repo = SqliteConversationRepository::new(state.db_pool.clone());
+    let attachment_repo = SqliteAttachmentRepository::new(state.db_pool.clone());
+
+    conversation_repo
+        .find_by_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation: {}", e))?
+        .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
+
+    let mut emails: Vec<_> = email_repo
+        .find_by_conversation_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation emails: {}", e))?
+        .into_iter()
+        .filter(|email| !email.is_draft)
+        .collect();
+    emails.sort_by_key(|email| email.sent_at.unwrap_or(email.received_at));
+
+    let email_ids: Vec<Uuid> = emails.iter().map(|email| email.id).collect();
+    let attachments_by_email = attachment_repo
+        .find_by_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+
+    let attachments_dir = std::path::PathBuf::from(&state.app_data_dir).join("attachments");
+    let mut messages = Vec::with_capacity(emails.len());
+    for email in &emails {
+        let attachments = attachments_by_email
+            .get(&email.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let inline_images =
+            conversation_export::load_inline_images(&attachments_dir, email.id, attachments);
+
+        messages.push(TranscriptMessage {
+            email,
+            attachments,
+            inline_images,
+        });
+    }
+
+    let subject = emails
+        .first()
+        .and_then(|email| email.subject.as_deref())
+        .unwrap_or("(No subject)");
+    let html = conversation_export::render_transcript(subject, &messages);
+
+    let destination = std::path::PathBuf::from(destination);
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create destination directory: {}", e))?;
+    }
+    std::fs::write(&destination, html)
+        .map_err(|e| format!("Failed to write conversation export: {}", e))?;
+
+    log::info!(
+        "[Export] Exported conversation {} ({} messages) to {}",
+        conversation_id,
+        messages.len(),
+        destination.display()
+    );
+
+    Ok(())
+}
+
+/// Mute a conversation: its later messages skip the inbox and never notify.
+/// Its messages already in the inbox are archived.
+#[tauri::command]
+pub async fn mute_conversation(
+    state: State<'_, AppState>,
+    conversation_id: Uuid,
+) -> Result<(), String> {
+    set_conversation_muted(&state, conversation_id, true).await
+}
+
+/// Unmute a conversation; archived messages stay where they are
+#[tauri::command]
+pub async fn unmute_conversation(
+    state: State<'_, AppState>,
+    conversation_id: Uuid,
+) -> Result<(), String> {
+    set_conversation_muted(&state, conversation_id, false).await
+}
+
+async fn set_conversation_muted(
+    state: &AppState,
+    conversation_id: Uuid,
+    muted: bool,
+) -> Result<(), String> {
+    let conversation_repo = SqliteConversationRepository::new(state.db_pool.clone());
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
+
+    conversation_repo
+        .find_by_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation: {}", e))?
+        .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
+
+    conversation_repo
+        .set_muted(conversation_id, muted.then(Utc::now))
+        .await
+        .map_err(|e| format!("Failed to update conversation: {}", e))?;
+
+    let emails = email_repo
+        .find_by_conversation_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation emails: {}", e))?;
+
+    muted_conversations::queue_mute_change(&state.db_pool, &emails, muted).await?;
+
+    if muted {
+        for email in &emails {
+            let folder = folder_repo
+                .find_by_id(email.folder_id)
+                .await
+                .map_err(|e| format!("Failed to fetch folder: {}", e))?;
+            if !folder.is_some_and(|folder| folder.folder_type == FolderType::Inbox) {
+                continue;
+            }
+
+            let archive = folder_repo
+                .find_by_type(email.account_id, FolderType::Archive.as_str())
+                .await
+                .map_err(|e| format!("Failed to find archive folder: {}", e))?;
+            if let Some(archive) = archive {
+                state
+                    .sync_coordinator
+                    .move_email(email.account_id, email.id, archive.id)
+                    .await
+                    .map_err(|e| format!("Failed to archive email {}: {}", email.id, e))?;
+            }
+        }
+    }
+
+    if let Err(e) = app_lock::emit_mail_event(
+        &state.app_handle,
+        "conversation:updated",
+        serde_json::json!({ "id": conversation_id.to_string(), "muted": muted }),
+    ) {
+        log::warn!("Failed to emit conversation:updated: {}", e);
+    }
+
+    log::info!(
+        "[Conversation] {} conversation {}",
+        if muted { "Muted" } else { "Unmuted" },
+        conversation_id
+    );
+
+    Ok(())
+}
@@ -1,15 +1,24 @@
+use crate::commands::search::query_scope;
+use crate::database::models::ai_audit_entry::AiAuditEntry;
 use crate::database::models::email::Email;
+use crate::database::models::writing_style_profile::WritingStyleProfile;
 use crate::database::repositories::{
-    AccountRepository, ContactRepository, EmailRepository, RepositoryFactory,
+    AccountRepository, AiAuditRepository, ContactRepository, ConversationRepository,
+    EmailRepository, RepositoryFactory, WritingStyleProfileRepository,
 };
+use crate::search::SearchQuery;
+use crate::services::ai_exclusions::AiExclusions;
+use crate::services::app_lock;
 use crate::services::corvus::{
-    AskAiRequest, AvailableModel, ChatMessage, ContactNote, CorvusService, EmailAnalysis,
-    EmailCompletionRequest, EmailMetadata, GenerateSearchQueryRequest, GenerateSubjectRequest,
-    UserContext,
+    AskAiRequest, AvailableModel, ChatMessage, ContactNote, ConversationSummary, CorvusService,
+    EmailAnalysis, EmailCompletionRequest, EmailMetadata, GenerateSearchQueryRequest,
+    GenerateSubjectRequest, MailboxAnswer, UserContext,
 };
+use crate::services::feature_flags::Feature;
 use crate::state::AppState;
+use crate::sync::background_style_learner::learn_account_style;
 use serde::{Deserialize, Serialize};
-use tauri::{command, Emitter, State};
+use tauri::{command, State};
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
@@ -86,16 +95,43 @@ pub struct EmailAnalysisResult {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ConversationSummaryResult {
+    pub summary: Option<ConversationSummary>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AskMailboxResult {
+    pub answer: Option<MailboxAnswer>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AvailableModelsResult {
     pub models: Vec<AvailableModel>,
     pub error: Option<String>,
 }
 
+/// Returned when every email an AI command would send is excluded by
+/// `ai.exclude.folders` or `ai.exclude.labels`
+const EXCLUDED_ERROR: &str = "These emails are excluded from AI processing";
+
+/// Emails retrieved as candidates for a mailbox question
+const ASK_MAILBOX_CANDIDATES: usize = 20;
+
 fn get_ai_service(state: &State<'_, AppState>) -> std::sync::Arc<CorvusService> {
     std::sync::Arc::clone(&state.ai_service)
 }
 
+/// AI service for commands that generate content, failing when the AI feature is off
+async fn get_enabled_ai_service(
+    state: &State<'_, AppState>,
+) -> Result<std::sync::Arc<CorvusService>, String> {
+    state.feature_flags.require(Feature::Ai).await?;
+    Ok(get_ai_service(state))
+}
+
 #[command]
 pub async fn ask_ai(
     state: State<'_, AppState>,
@@ -106,7 +142,7 @@ pub async fn ask_ai(
         context.history.len()
     );
 
-    let ai_service = get_ai_service(&state);
+    let ai_service = get_enabled_ai_service(&state).await?;
     let request = AskAiRequest {
         history: context
             .history
@@ -133,6 +169,41 @@ pub async fn ask_ai(
     }
 }
 
+/// Style profile learned for the account sending from `sender`
+/// (`alice@example.com` or `Alice <alice@example.com>`), if learning is on
+async fn find_learned_style(state: &State<'_, AppState>, sender: &str) -> Option<String> {
+    if !state
+        .settings
+        .get::<bool>("ai.writingStyle.learnFromSent")
+        .unwrap_or(false)
+    {
+        return None;
+    }
+
+    let address = match (sender.rfind('<'), sender.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &sender[start + 1..end],
+        _ => sender,
+    }
+    .trim();
+
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+    let account = repo_factory
+        .account_repository()
+        .find_all()
+        .await
+        .ok()?
+        .into_iter()
+        .find(|account| account.email.eq_ignore_ascii_case(address))?;
+
+    repo_factory
+        .writing_style_profile_repository()
+        .find_by_account(account.id)
+        .await
+        .ok()
+        .flatten()
+        .map(|profile| profile.profile)
+}
+
 #[command]
 pub async fn generate_email_completion(
     state: State<'_, AppState>,
@@ -140,7 +211,7 @@ pub async fn generate_email_completion(
 ) -> Result<AutoCompletionResult, String> {
     log::debug!("Received generate_email_completion request");
 
-    let ai_service = get_ai_service(&state);
+    let ai_service = get_enabled_ai_service(&state).await?;
     let contact_notes: Vec<ContactNote> = context
         .contact_notes
         .unwrap_or_default()
@@ -152,6 +223,8 @@ pub async fn generate_email_completion(
         })
         .collect();
 
+    let learned_style = find_learned_style(&state, &context.metadata.sender).await;
+
     let request = EmailCompletionRequest {
         metadata: EmailMetadata {
             sender: context.metadata.sender,
@@ -163,6 +236,7 @@ pub async fn generate_email_completion(
         current_text: context.current_text,
         cursor_position: context.cursor_position,
         contact_notes,
+        learned_style,
     };
 
     match ai_service.generate_email_completion(request).await {
@@ -187,7 +261,7 @@ pub async fn generate_subject(
 ) -> Result<AutoCompletionResult, String> {
     log::debug!("Received generate_subject request");
 
-    let ai_service = get_ai_service(&state);
+    let ai_service = get_enabled_ai_service(&state).await?;
     let contact_notes: Vec<ContactNote> = context
         .contact_notes
         .unwrap_or_default()
@@ -230,7 +304,7 @@ pub async fn generate_search_query(
 ) -> Result<GenerateSearchQueryResult, String> {
     log::debug!("Received generate_search_query request");
 
-    let ai_service = get_ai_service(&state);
+    let ai_service = get_enabled_ai_service(&state).await?;
     let request = GenerateSearchQueryRequest {
         natural_language_query,
     };
@@ -283,6 +357,17 @@ pub async fn analyze_email_with_ai(
         log::debug!("Force refresh requested for email {}", email_id);
     }
 
+    if AiExclusions::from_settings(&state.settings)
+        .retain_allowed(&state.db_pool, vec![email.clone()])
+        .await?
+        .is_empty()
+    {
+        return Ok(EmailAnalysisResult {
+            analysis: None,
+            error: Some(EXCLUDED_ERROR.to_string()),
+        });
+    }
+
     // Resolve the account that owns this email so we can tell the AI who the user is
     let user_context = account_repo
         .find_by_id(email.account_id)
@@ -332,7 +417,7 @@ pub async fn analyze_email_with_ai(
         }
     }
 
-    let ai_service = get_ai_service(&state);
+    let ai_service = get_enabled_ai_service(&state).await?;
 
     match ai_service
         .analyze_email(&email, user_context.as_ref(), &contact_notes)
@@ -349,10 +434,11 @@ pub async fn analyze_email_with_ai(
 
             log::debug!("AI cache stored for email {}", email_id);
 
-            if let Err(e) = state
-                .app_handle
-                .emit("email:ai-analysis-complete", email_id.to_string())
-            {
+            if let Err(e) = app_lock::emit_mail_event(
+                &state.app_handle,
+                "email:ai-analysis-complete",
+                email_id.to_string(),
+            ) {
                 log::error!("Failed to emit event: {}", e);
             }
 
@@ -371,6 +457,239 @@ pub async fn analyze_email_with_ai(
     }
 }
 
+/// Summarizes the whole conversation and caches the summary until a new
+/// message joins it
+#[command]
+pub async fn summarize_conversation(
+    state: State<'_, AppState>,
+    conversation_id: Uuid,
+    force_refresh: Option<bool>,
+) -> Result<ConversationSummaryResult, String> {
+    log::debug!("Summarizing conversation with ID: {}", conversation_id);
+
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+    let conversation_repo = repo_factory.conversation_repository();
+    let email_repo = repo_factory.email_repository();
+    let account_repo = repo_factory.account_repository();
+
+    let conversation = conversation_repo
+        .find_by_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation: {}", e))?
+        .ok_or_else(|| "Conversation not found".to_string())?;
+
+    if !force_refresh.unwrap_or(false) {
+        if let Some(ref cache) = conversation.ai_cache {
+            if let Ok(cached_summary) = serde_json::from_str::<ConversationSummary>(cache) {
+                log::debug!(
+                    "Returning cached summary for conversation {}",
+                    conversation_id
+                );
+                return Ok(ConversationSummaryResult {
+                    summary: Some(cached_summary),
+                    error: None,
+                });
+            }
+        }
+    }
+
+    let Some(account_id) = email_repo
+        .find_by_conversation_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation messages: {}", e))?
+        .first()
+        .map(|email| email.account_id)
+    else {
+        return Err("Conversation has no messages".to_string());
+    };
+
+    // Copies of a message in several folders are summarized once, oldest message first
+    let mut messages: Vec<Email> = conversation_repo
+        .find_messages_across_folders(conversation_id, account_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation messages: {}", e))?
+        .into_iter()
+        .map(|(email, _)| email)
+        .collect();
+    messages.reverse();
+
+    let messages = AiExclusions::from_settings(&state.settings)
+        .retain_allowed(&state.db_pool, messages)
+        .await?;
+    if messages.is_empty() {
+        return Ok(ConversationSummaryResult {
+            summary: None,
+            error: Some(EXCLUDED_ERROR.to_string()),
+        });
+    }
+
+    let user_context = account_repo
+        .find_by_id(account_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|account| UserContext::from_account(&account));
+
+    let ai_service = get_enabled_ai_service(&state).await?;
+
+    match ai_service
+        .summarize_conversation(&messages, user_context.as_ref())
+        .await
+    {
+        Ok(summary) => {
+            let summary_json = serde_json::to_string(&summary)
+                .map_err(|e| format!("Failed to serialize summary: {}", e))?;
+
+            let stored = conversation_repo
+                .update_ai_cache(conversation_id, &summary_json, conversation.message_count)
+                .await
+                .map_err(|e| {
+                    format!(
+                        "Failed to persist ai_cache for conversation {}: {}",
+                        conversation_id, e
+                    )
+                })?;
+            if !stored {
+                log::debug!(
+                    "Conversation {} changed while summarizing, summary not cached",
+                    conversation_id
+                );
+            }
+
+            Ok(ConversationSummaryResult {
+                summary: Some(summary),
+                error: None,
+            })
+        }
+        Err(e) => {
+            log::error!("summarize_conversation error: {}", e);
+            Ok(ConversationSummaryResult {
+                summary: None,
+                error: Some(e),
+            })
+        }
+    }
+}
+
+/// Any word of the question, for when no search query could be generated
+/// from it or the generated one finds nothing
+fn keyword_query(question: &str) -> String {
+    question
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 2)
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Retrieves up to `ASK_MAILBOX_CANDIDATES` emails for a search query, best
+/// match first
+async fn retrieve_emails(
+    state: &State<'_, AppState>,
+    query: String,
+    account_id: Option<Uuid>,
+) -> Result<Vec<Email>, String> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let scope = query_scope(state, &query).await?;
+    let results = state
+        .search_manager
+        .search(SearchQuery {
+            query,
+            account_id,
+            folder_id: None,
+            conversation_id: None,
+            limit: ASK_MAILBOX_CANDIDATES,
+            offset: 0,
+            scope,
+        })
+        .await
+        .map_err(|e| format!("Search failed: {}", e))?;
+
+    let email_repo = RepositoryFactory::new(state.db_pool.clone()).email_repository();
+    let mut emails = Vec::with_capacity(results.len());
+    for result in results {
+        if let Ok(Some(email)) = email_repo.find_by_id(result.id).await {
+            emails.push(email);
+        }
+    }
+    AiExclusions::from_settings(&state.settings)
+        .retain_allowed(&state.db_pool, emails)
+        .await
+}
+
+/// Answers a natural-language question from the emails a search for it
+/// retrieves, citing the emails the answer is based on
+#[command]
+pub async fn ask_mailbox(
+    state: State<'_, AppState>,
+    question: String,
+    account_id: Option<Uuid>,
+) -> Result<AskMailboxResult, String> {
+    log::debug!("Received ask_mailbox request");
+
+    let ai_service = get_enabled_ai_service(&state).await?;
+
+    let generated_query = match ai_service
+        .generate_search_query(GenerateSearchQueryRequest {
+            natural_language_query: question.clone(),
+        })
+        .await
+    {
+        Ok(query) => query.trim().trim_matches('`').trim().to_string(),
+        Err(e) => {
+            log::warn!("ask_mailbox could not generate a search query: {}", e);
+            String::new()
+        }
+    };
+
+    let mut emails = retrieve_emails(&state, generated_query, account_id)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("ask_mailbox search with generated query failed: {}", e);
+            Vec::new()
+        });
+    if emails.is_empty() {
+        emails = retrieve_emails(&state, keyword_query(&question), account_id).await?;
+    }
+
+    if emails.is_empty() {
+        return Ok(AskMailboxResult {
+            answer: None,
+            error: Some("No emails match the question".to_string()),
+        });
+    }
+
+    let user_context = match account_id {
+        Some(account_id) => RepositoryFactory::new(state.db_pool.clone())
+            .account_repository()
+            .find_by_id(account_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|account| UserContext::from_account(&account)),
+        None => None,
+    };
+
+    match ai_service
+        .ask_mailbox(&question, &emails, user_context.as_ref())
+        .await
+    {
+        Ok(answer) => Ok(AskMailboxResult {
+            answer: Some(answer),
+            error: None,
+        }),
+        Err(e) => {
+            log::error!("ask_mailbox error: {}", e);
+            Ok(AskMailboxResult {
+                answer: None,
+                error: Some(e),
+            })
+        }
+    }
+}
+
 #[command]
 pub async fn get_available_models(
     state: State<'_, AppState>,
@@ -437,3 +756,107 @@ pub async fn set_writing_style(
         error: None,
     })
 }
+
+#[derive(Debug, Serialize)]
+pub struct WritingStyleProfileResult {
+    pub profile: Option<WritingStyleProfile>,
+    pub error: Option<String>,
+}
+
+#[command]
+pub async fn get_writing_style_profile(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<Option<WritingStyleProfile>, String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .writing_style_profile_repository()
+        .find_by_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to fetch writing style profile: {}", e))
+}
+
+/// Learns the account's writing style from its sent mail right away, instead
+/// of waiting for the background learner
+#[command]
+pub async fn learn_writing_style(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<WritingStyleProfileResult, String> {
+    log::debug!("Learning writing style of account {}", account_id);
+
+    let ai_service = get_enabled_ai_service(&state).await?;
+
+    let exclusions = AiExclusions::from_settings(&state.settings);
+    match learn_account_style(&state.db_pool, &ai_service, &exclusions, account_id).await {
+        Ok(Some(profile)) => Ok(WritingStyleProfileResult {
+            profile: Some(profile),
+            error: None,
+        }),
+        Ok(None) => Ok(WritingStyleProfileResult {
+            profile: None,
+            error: Some("Not enough sent emails to learn a writing style from".to_string()),
+        }),
+        Err(e) => {
+            log::error!("learn_writing_style error: {}", e);
+            Ok(WritingStyleProfileResult {
+                profile: None,
+                error: Some(e),
+            })
+        }
+    }
+}
+
+#[command]
+pub async fn delete_writing_style_profile(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .writing_style_profile_repository()
+        .delete(account_id)
+        .await
+        .map_err(|e| format!("Failed to delete writing style profile: {}", e))
+}
+
+#[derive(Debug, Serialize)]
+pub struct AiAuditLog {
+    pub entries: Vec<AiAuditEntry>,
+    pub total: i64,
+}
+
+/// Requests sent to the AI provider while `ai.audit.enabled` was on, newest first
+#[command]
+pub async fn get_ai_audit_log(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<AiAuditLog, String> {
+    let audit_repo = RepositoryFactory::new(state.db_pool.clone()).ai_audit_repository();
+
+    let entries = audit_repo
+        .find_page(limit.unwrap_or(50), offset.unwrap_or(0))
+        .await
+        .map_err(|e| format!("Failed to fetch AI audit log: {}", e))?;
+    let total = audit_repo
+        .count()
+        .await
+        .map_err(|e| format!("Failed to count AI audit log: {}", e))?;
+
+    Ok(AiAuditLog { entries, total })
+}
+
+/// Deletes audit entries older than `older_than_days`, or all of them.
+/// Returns the number deleted.
+#[command]
+pub async fn purge_ai_audit_log(
+    state: State<'_, AppState>,
+    older_than_days: Option<i64>,
+) -> Result<u64, String> {
+    let before = older_than_days.map(|days| chrono::Utc::now() - chrono::Duration::days(days));
+
+    RepositoryFactory::new(state.db_pool.clone())
+        .ai_audit_repository()
+        .purge(before)
+        .await
+        .map_err(|e| format!("Failed to purge AI audit log: {}", e))
+}
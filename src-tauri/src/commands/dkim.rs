@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        models::account::{Account, AccountType},
+        repositories::{AccountRepository, IdentityRepository, RepositoryFactory},
+    },
+    services::dkim::{DkimDnsRecord, DkimKey},
+    state::AppState,
+};
+
+/// A DKIM key as shown to the user, without the private key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DkimKeyInfo {
+    pub address: String,
+    pub domain: String,
+    pub selector: String,
+    /// The record to publish before mail signed with the key is accepted
+    pub dns_record: DkimDnsRecord,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DkimKeyInfo {
+    fn new(address: String, key: &DkimKey) -> Result<Self, String> {
+        Ok(Self {
+            address,
+            domain: key.domain.clone(),
+            selector: key.selector.clone(),
+            dns_record: key.dns_record().map_err(|e| e.to_string())?,
+            created_at: key.created_at,
+        })
+    }
+}
+
+/// The account, if it sends over its own SMTP server
+async fn find_smtp_account(state: &AppState, account_id: Uuid) -> Result<Account, String> {
+    let account = RepositoryFactory::new(state.db_pool.clone())
+        .account_repository()
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to find account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    if account.account_type != AccountType::Imap {
+        return Err("DKIM keys are only used for accounts that send over SMTP".to_string());
+    }
+
+    Ok(account)
+}
+
+/// Lowercases `address` and checks the account sends as it, returning it
+/// with its domain
+async fn sending_address(
+    state: &AppState,
+    account: &Account,
+    address: &str,
+) -> Result<(String, String), String> {
+    let address = address.trim().to_lowercase();
+
+    let is_own = address == account.email.to_lowercase()
+        || RepositoryFactory::new(state.db_pool.clone())
+            .identity_repository()
+            .find_by_account(account.id)
+            .await
+            .map_err(|e| format!("Failed to get identities: {}", e))?
+            .iter()
+            .any(|identity| identity.email == address);
+    if !is_own {
+        return Err(format!("The account does not send as {}", address));
+    }
+
+    let domain = address
+        .split_once('@')
+        .map(|(_, domain)| domain.to_string())
+        .filter(|domain| !domain.is_empty())
+        .ok_or_else(|| format!("'{}' is not a valid email address", address))?;
+
+    Ok((address, domain))
+}
+
+/// DKIM keys of the account's sending addresses
+#[tauri::command]
+pub async fn get_dkim_keys(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<Vec<DkimKeyInfo>, String> {
+    state
+        .credential_store
+        .get_dkim_keys(account_id)
+        .await
+        .map_err(|e| format!("Failed to get DKIM keys: {}", e))?
+        .into_iter()
+        .map(|(address, key)| DkimKeyInfo::new(address, &key))
+        .collect()
+}
+
+/// Generate a signing key for mail sent as `address`, replacing any previous
+/// one. Defaults the selector to `ravn<YYYYMMDD>`, so a rotated key can be
+/// published next to the old one.
+#[tauri::command]
+pub async fn generate_dkim_key(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    address: String,
+    selector: Option<String>,
+) -> Result<DkimKeyInfo, String> {
+    let account = find_smtp_account(&state, account_id).await?;
+    let (address, domain) = sending_address(&state, &account, &address).await?;
+
+    let selector = selector
+        .filter(|selector| !selector.trim().is_empty())
+        .unwrap_or_else(|| format!("ravn{}", Utc::now().format("%Y%m%d")));
+    let key = DkimKey::generate(&domain, &selector).map_err(|e| e.to_string())?;
+
+    store_key(&state, account_id, address, key).await
+}
+
+/// Use an existing PEM private key, e.g. one already published in DNS
+#[tauri::command]
+pub async fn import_dkim_key(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    address: String,
+    selector: String,
+    private_key_pem: String,
+) -> Result<DkimKeyInfo, String> {
+    let account = find_smtp_account(&state, account_id).await?;
+    let (address, domain) = sending_address(&state, &account, &address).await?;
+
+    let key = DkimKey::from_pem(&domain, &selector, &private_key_pem).map_err(|e| e.to_string())?;
+
+    store_key(&state, account_id, address, key).await
+}
+
+async fn store_key(
+    state: &AppState,
+    account_id: Uuid,
+    address: String,
+    key: DkimKey,
+) -> Result<DkimKeyInfo, String> {
+    let info = DkimKeyInfo::new(address.clone(), &key)?;
+
+    state
+        .credential_store
+        .store_dkim(account_id, &address, key)
+        .await
+        .map_err(|e| format!("Failed to store DKIM key: {}", e))?;
+
+    log::info!(
+        "Stored DKIM key {} for {} of account {}",
+        info.dns_record.name,
+        address,
+        account_id
+    );
+
+    Ok(info)
+}
+
+/// Stop signing mail sent as `address`
+#[tauri::command]
+pub async fn delete_dkim_key(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    address: String,
+) -> Result<(), String> {
+    state
+        .credential_store
+        .delete_dkim(account_id, &address)
+        .await
+        .map_err(|e| format!("Failed to delete DKIM key: {}", e))
+}
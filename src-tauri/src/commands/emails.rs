@@ -1,31 +1,79 @@
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use tauri::{Emitter, State};
+use tauri::State;
 use uuid::Uuid;
 
-use crate::database::models::account::AccountType;
+use crate::commands::attachment::{email_attachments_opened, read_cached_attachment};
+use crate::database::models::account::{Account, AccountType};
+use crate::database::models::blocked_sender::BlockedSender;
 use crate::database::models::conversation::Conversation;
+use crate::database::models::delivery_status::RecipientDeliveryStatus;
 use crate::database::models::email::{Email, EmailAddress};
-use crate::database::models::email_dto::{AttachmentInfo, EmailDetail, EmailListItem, LabelInfo};
+use crate::database::models::email_dto::{
+    AttachmentInfo, EmailDetail, EmailListItem, EmailWindow, LabelInfo, RecentlyDeletedEmail,
+    UnifiedInboxCount,
+};
 use crate::database::models::folder::FolderType;
+use crate::database::models::identity::Identity;
+use crate::database::models::image_allowlist::ImageAllowlistEntry;
+use crate::database::models::outbox_item::OutboxItem;
+use crate::database::models::pending_operation::PendingOperationType;
+use crate::database::models::sender_category::SenderCategory;
+use crate::database::models::signature::{Signature, SignatureChoice};
+use crate::database::models::template::RenderedTemplate;
 use crate::database::repositories::{
-    AccountRepository, AttachmentRepository, ConversationRepository, EmailRepository,
-    FolderRepository, LabelRepository, SqliteAccountRepository, SqliteAttachmentRepository,
-    SqliteConversationRepository, SqliteEmailRepository, SqliteFolderRepository,
-    SqliteLabelRepository,
+    AccountRepository, AttachmentRepository, BlockedSenderRepository, CalendarEventRepository,
+    ContactRepository, ContactSecurityRepository, ConversationRepository, DeliveryStatusRepository,
+    EmailRepository, FolderRepository, IdentityRepository, ImageAllowlistRepository,
+    LabelRepository, OutboxRepository, SenderCategoryRepository, SignatureRepository,
+    SmimeRepository, SqliteAccountRepository, SqliteAttachmentRepository,
+    SqliteBlockedSenderRepository, SqliteCalendarEventRepository, SqliteContactRepository,
+    SqliteContactSecurityRepository, SqliteConversationRepository, SqliteDeliveryStatusRepository,
+    SqliteEmailRepository, SqliteFolderRepository, SqliteIdentityRepository,
+    SqliteImageAllowlistRepository, SqliteLabelRepository, SqliteOutboxRepository,
+    SqlitePendingOperationRepository, SqliteSenderCategoryRepository, SqliteSignatureRepository,
+    SqliteSmimeRepository, SqliteTemplateRepository, TemplateRepository,
+};
+use crate::services::app_lock;
+use crate::services::contact_security;
+use crate::services::conversation_export;
+use crate::services::email_service::{
+    self, DsnOptions, DsnRequest, EmailAttachment, EmailData, EmailError, EmailService, SmtpProbe,
 };
-use crate::services::email_service::{EmailAttachment, EmailData, EmailService};
+use crate::services::feature_flags::Feature;
+use crate::services::html_sanitizer::{self, BlockingOptions};
 use crate::services::notification_service::NotificationService;
+use crate::services::recipient_validator::{RecipientValidation, RecipientValidator};
+use crate::services::reply_builder::{self, ReplyMode, ReplyParts};
+use crate::services::signatures;
+use crate::services::smime::{SmimeOptions, SmimeRequest};
+use crate::services::template_renderer;
 use crate::state::AppState;
+use crate::sync::auth::OAuth2Helper;
+use crate::sync::background_cleanup::TOMBSTONE_RETENTION_DAYS;
+use crate::sync::cid_utils;
+use crate::sync::draft_sync;
+use crate::sync::email_categorizer::EmailCategory;
+use crate::sync::error::SyncError;
+use crate::sync::junk_filter::JunkFilter;
+use crate::sync::outbox_worker;
+use crate::sync::priority;
+use crate::sync::providers::icloud;
 use crate::sync::types::AccountSettings;
 use sqlx::types::Json;
 use turndown::Turndown;
 
+/// Upper bound on rows returned by a single `get_email_window` call
+const MAX_EMAIL_WINDOW: i64 = 500;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttachmentData {
     pub filename: String,
     pub content: Vec<u8>,
     pub content_type: Option<String>,
+    /// Set for inline images, which the body references as `cid:`
+    #[serde(default)]
+    pub content_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +106,21 @@ pub struct SendFromAccountRequest {
     pub conversation_id: Option<String>,
     pub in_reply_to: Option<String>,
     pub references: Option<String>,
+    /// Send as one of the account's identities instead of the account address
+    #[serde(default)]
+    pub identity_id: Option<Uuid>,
+    /// Ask for delivery status notifications (SMTP accounts only)
+    #[serde(default)]
+    pub dsn: Option<DsnOptions>,
+    /// Ask the recipients for a read receipt
+    #[serde(default)]
+    pub read_receipt: bool,
+    /// Sign and/or encrypt with S/MIME (SMTP and Office365 accounts)
+    #[serde(default)]
+    pub smime: Option<SmimeOptions>,
+    /// Added unless the body already contains a signature
+    #[serde(default)]
+    pub signature: SignatureChoice,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +136,12 @@ pub struct SaveDraftRequest {
     pub conversation_id: Option<String>,
     pub in_reply_to: Option<String>,
     pub references: Option<String>,
+    /// Send as one of the account's identities instead of the account address
+    #[serde(default)]
+    pub identity_id: Option<Uuid>,
+    /// Added unless the body already contains a signature
+    #[serde(default)]
+    pub signature: SignatureChoice,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +151,17 @@ pub struct AccountForSending {
     pub email: String,
     pub account_type: String,
     pub has_smtp_config: bool,
+    /// Aliases the composer can pick as sender
+    pub identities: Vec<Identity>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnifiedInboxResponse {
+    pub emails: Vec<EmailListItem>,
+    pub total: i64,
+    pub unread: i64,
+    pub accounts: Vec<UnifiedInboxCount>,
+    pub has_more: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -96,7 +176,7 @@ fn emit_email_event<S: Serialize + Clone>(
     event_name: &str,
     payload: S,
 ) {
-    if let Err(e) = app_handle.emit(event_name, payload) {
+    if let Err(e) = app_lock::emit_mail_event(app_handle, event_name, payload) {
         log::error!("Failed to emit email event '{}': {}", event_name, e);
     }
 }
@@ -118,6 +198,23 @@ fn apply_notified_at_to_list_item(
     item
 }
 
+async fn account_color_map(
+    state: &State<'_, AppState>,
+) -> Result<std::collections::HashMap<Uuid, String>, String> {
+    SqliteAccountRepository::new(state.db_pool.clone())
+        .find_colors()
+        .await
+        .map_err(|e| format!("Failed to fetch account colors: {}", e))
+}
+
+fn apply_account_color_to_list_item(
+    mut item: EmailListItem,
+    account_colors: &std::collections::HashMap<Uuid, String>,
+) -> EmailListItem {
+    item.account_color = account_colors.get(&item.account_id).cloned();
+    item
+}
+
 fn apply_notified_at_to_detail(
     mut detail: EmailDetail,
     notified_at_by_email: &std::collections::HashMap<Uuid, chrono::DateTime<chrono::Utc>>,
@@ -136,21 +233,98 @@ pub async fn send_email(request: SendEmailRequest) -> Result<SendEmailResponse,
     })
 }
 
+/// Connect to the account's SMTP server and log in, reporting what the
+/// server supports. Nothing is sent.
 #[tauri::command]
-pub async fn test_smtp_connection() -> Result<SendEmailResponse, String> {
-    log::info!("Testing SMTP connection");
+pub async fn test_smtp_connection(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<SmtpProbe, String> {
+    log::info!("Testing SMTP connection of account {}", account_id);
 
-    Ok(SendEmailResponse {
-        success: true,
-        message: "SMTP configuration is valid".to_string(),
-    })
+    let account = SqliteAccountRepository::new(state.db_pool.clone())
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to find account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    let probe = smtp_service(&state, &account)
+        .await
+        .map_err(|failure| failure.message().to_string())?
+        .probe()
+        .await
+        .map_err(|e| format!("Failed to connect to the SMTP server: {}", e))?;
+
+    log::info!(
+        "SMTP server of account {} offers AUTH {:?}, SIZE {:?}, STARTTLS {}",
+        account_id,
+        probe.capabilities.auth_mechanisms,
+        probe.capabilities.max_size,
+        probe.capabilities.starttls
+    );
+
+    Ok(probe)
+}
+
+/// Why an email could not be sent
+#[derive(Debug, Clone)]
+pub enum SendFailure {
+    /// The message or account setup is at fault, so sending again won't help
+    Rejected(String),
+    /// The mail server could not be reached or failed; worth trying again
+    Transport(String),
+}
+
+impl SendFailure {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Rejected(message) | Self::Transport(message) => message,
+        }
+    }
+}
+
+impl From<String> for SendFailure {
+    fn from(message: String) -> Self {
+        Self::Rejected(message)
+    }
 }
 
+/// Sends an email from an account. Sends that fail to reach the mail server
+/// are queued in the outbox and retried in the background, which the response
+/// reports with `success: false`.
 #[tauri::command]
 pub async fn send_email_from_account(
     state: State<'_, AppState>,
     request: SendFromAccountRequest,
 ) -> Result<SendEmailResponse, String> {
+    match deliver_email(&state, request.clone()).await {
+        Ok(response) => Ok(response),
+        Err(SendFailure::Rejected(message)) => Err(message),
+        Err(SendFailure::Transport(message)) => {
+            log::warn!(
+                "Failed to send email from account {}, queueing it in the outbox: {}",
+                request.account_id,
+                message
+            );
+            outbox_worker::enqueue(&state.db_pool, &state.app_handle, &request, &message).await?;
+
+            Ok(SendEmailResponse {
+                success: false,
+                message: format!(
+                    "Email could not be sent and was queued in the outbox: {}",
+                    message
+                ),
+            })
+        }
+    }
+}
+
+/// Sends an email from an account right away, moving the draft it came from
+/// to Sent or storing a copy there
+pub async fn deliver_email(
+    state: &AppState,
+    request: SendFromAccountRequest,
+) -> Result<SendEmailResponse, SendFailure> {
     log::info!(
         "Sending email from account {} with subject: {}",
         request.account_id,
@@ -215,104 +389,192 @@ pub async fn send_email_from_account(
         None
     };
 
-    if account.account_type == AccountType::Office365 {
+    let sender = resolve_sender(&state, &account, request.identity_id).await?;
+
+    let mut request = request;
+    let mut body_plain = None;
+    if let Some(signature) = resolve_signature(
+        &state,
+        &account,
+        &sender.address,
+        request.signature,
+        &request.body,
+        in_reply_to.is_some(),
+    )
+    .await?
+    {
+        body_plain = Some(signatures::plain_text(&request.body, &signature));
+        request.body = signatures::insert_html(&request.body, &signature);
+    }
+
+    let domain = account
+        .email
+        .split_once('@')
+        .map(|(_, d)| d.to_string())
+        .unwrap_or_else(|| "ravn.app".to_string());
+
+    // The local copy keeps its images embedded, as it has no attachments to
+    // resolve `cid:` references from
+    let stored_body = request.body.clone();
+    let (body, inline_images) = email_service::extract_inline_images(&request.body, &domain);
+    request.body = body;
+    request
+        .attachments
+        .extend(inline_images.into_iter().map(|image| AttachmentData {
+            filename: image.filename,
+            content: image.content,
+            content_type: image.content_type,
+            content_id: image.content_id,
+        }));
+    check_attachment_size(&account.account_type, &request.attachments)?;
+
+    let recipients: Vec<String> = request
+        .to
+        .iter()
+        .chain(&request.cc)
+        .chain(&request.bcc)
+        .map(|addr| addr.address.trim().to_lowercase())
+        .collect();
+    let preferences = SqliteContactSecurityRepository::new(state.db_pool.clone())
+        .find_by_emails(&recipients)
+        .await
+        .map_err(|e| format!("Failed to get contact security preferences: {}", e))?;
+    let smime_options = contact_security::outgoing_options(
+        &request.smime.clone().unwrap_or_default(),
+        &preferences,
+    )?;
+
+    let smime_request = if smime_options.sign || smime_options.encrypt {
+        Some(resolve_smime_request(&state, &account, &request, &smime_options).await?)
+    } else {
+        None
+    };
+
+    // Drafts keep their id when sent; it doubles as the DSN envelope id
+    let sent_email_id = request.draft_id.unwrap_or_else(Uuid::now_v7);
+    // Read receipts quote it as Original-Message-ID
+    let message_id = format!("<{}@{}>", sent_email_id, domain);
+    let mut dsn_recipients: Vec<String> = Vec::new();
+
+    if sends_through_graph(&account) {
         use crate::sync::provider::ProviderFactory;
         use crate::sync::types::{EmailAttachmentData, EmailRecipient};
 
         log::info!("[Office365] Using Microsoft Graph API to send email");
+        if request.dsn.is_some() {
+            log::info!("[Office365] Delivery status notifications are only requested over SMTP");
+        }
 
         let provider = ProviderFactory::create(&account, state.credential_store.clone())
             .map_err(|e| format!("Failed to create Office365 provider: {}", e))?;
 
-        let to_recipients: Vec<EmailRecipient> = request
-            .to
-            .iter()
-            .map(|addr| EmailRecipient {
-                address: addr.address.clone(),
-                name: addr.name.clone(),
-            })
-            .collect();
-
-        let cc_recipients: Vec<EmailRecipient> = request
-            .cc
-            .iter()
-            .map(|addr| EmailRecipient {
-                address: addr.address.clone(),
-                name: addr.name.clone(),
-            })
-            .collect();
-
-        let bcc_recipients: Vec<EmailRecipient> = request
-            .bcc
-            .iter()
-            .map(|addr| EmailRecipient {
-                address: addr.address.clone(),
-                name: addr.name.clone(),
-            })
-            .collect();
-
-        let attachment_data: Vec<EmailAttachmentData> = request
-            .attachments
-            .iter()
-            .map(|att| EmailAttachmentData {
-                filename: att.filename.clone(),
-                content: att.content.clone(),
-                content_type: att.content_type.clone(),
-            })
-            .collect();
+        // Graph would rewrite a JSON message, so protected mail goes out as MIME
+        if let Some(smime) = smime_request {
+            let email_data = EmailData {
+                from: sender.address.clone(),
+                to: request.to.clone(),
+                cc: request.cc.clone(),
+                bcc: request.bcc.clone(),
+                subject: request.subject.clone(),
+                body_html: request.body.clone(),
+                body_plain: body_plain.clone(),
+                attachments: request
+                    .attachments
+                    .iter()
+                    .map(|att| EmailAttachment {
+                        filename: att.filename.clone(),
+                        content: att.content.clone(),
+                        content_type: att.content_type.clone(),
+                        content_id: att.content_id.clone(),
+                    })
+                    .collect(),
+                in_reply_to: in_reply_to.clone(),
+                references: references_header.clone(),
+                message_id: Some(message_id.clone()),
+                read_receipt: request.read_receipt,
+                smime: Some(smime),
+                dkim: None,
+            };
+            let mime = EmailService::build_mime(email_data)
+                .map_err(|e| format!("Failed to build email: {}", e))?;
 
-        provider
-            .send_email(
-                to_recipients,
-                cc_recipients,
-                bcc_recipients,
-                request.subject.clone(),
-                request.body.clone(),
-                attachment_data,
-                in_reply_to.clone(),
-                references_header.clone(),
-                provider_conversation_id,
-            )
-            .await
-            .map_err(|e| format!("Failed to send email via Office365: {}", e))?;
+            provider
+                .send_mime(mime)
+                .await
+                .map_err(office365_send_failure)?;
+        } else {
+            let to_recipients: Vec<EmailRecipient> = request
+                .to
+                .iter()
+                .map(|addr| EmailRecipient {
+                    address: addr.address.clone(),
+                    name: addr.name.clone(),
+                })
+                .collect();
+
+            let cc_recipients: Vec<EmailRecipient> = request
+                .cc
+                .iter()
+                .map(|addr| EmailRecipient {
+                    address: addr.address.clone(),
+                    name: addr.name.clone(),
+                })
+                .collect();
+
+            let bcc_recipients: Vec<EmailRecipient> = request
+                .bcc
+                .iter()
+                .map(|addr| EmailRecipient {
+                    address: addr.address.clone(),
+                    name: addr.name.clone(),
+                })
+                .collect();
+
+            let attachment_data: Vec<EmailAttachmentData> = request
+                .attachments
+                .iter()
+                .map(|att| EmailAttachmentData {
+                    filename: att.filename.clone(),
+                    content: att.content.clone(),
+                    content_type: att.content_type.clone(),
+                    content_id: att.content_id.clone(),
+                })
+                .collect();
+
+            provider
+                .send_email(
+                    to_recipients,
+                    cc_recipients,
+                    bcc_recipients,
+                    request.subject.clone(),
+                    request.body.clone(),
+                    attachment_data,
+                    in_reply_to.clone(),
+                    references_header.clone(),
+                    provider_conversation_id,
+                    (!sender.address.eq_ignore_ascii_case(&account.email)).then(|| {
+                        EmailRecipient {
+                            address: sender.address.clone(),
+                            name: sender.name.clone(),
+                        }
+                    }),
+                    request.read_receipt,
+                )
+                .await
+                .map_err(office365_send_failure)?;
+        }
 
         log::info!("[Office365] Email sent successfully via Graph API");
     } else {
         log::info!("Using SMTP to send email");
 
-        let settings: AccountSettings = serde_json::from_value(account.settings.clone())
-            .map_err(|e| format!("Failed to parse account settings: {}", e))?;
+        let email_service = smtp_service(state, &account).await?;
 
-        let smtp_host = settings
-            .smtp_host
-            .or_else(|| settings.imap_host.clone())
-            .ok_or_else(|| "Neither SMTP nor IMAP host configured for this account".to_string())?;
-
-        let smtp_port = settings.smtp_port.unwrap_or(587);
-        let smtp_use_tls = settings
-            .smtp_use_tls
-            .unwrap_or_else(|| settings.imap_use_tls.unwrap_or(true));
-
-        let smtp_username = settings
-            .smtp_username
-            .clone()
-            .or_else(|| settings.imap_username.clone())
-            .unwrap_or(account.email.clone());
-
-        let credentials = state
+        let dkim = state
             .credential_store
-            .get_imap(account.id)
+            .get_dkim(account.id, &sender.address)
             .await
-            .map_err(|e| format!("Failed to get credentials: {}", e))?;
-
-        let email_service = EmailService::from_account_settings(
-            smtp_host.clone(),
-            smtp_port,
-            smtp_use_tls,
-            smtp_username,
-            credentials.password,
-        )
-        .map_err(|e| format!("Failed to initialize email service: {}", e))?;
+            .map_err(|e| format!("Failed to get DKIM key: {}", e))?;
 
         let attachments: Vec<EmailAttachment> = request
             .attachments
@@ -321,25 +583,52 @@ pub async fn send_email_from_account(
                 filename: att.filename,
                 content: att.content,
                 content_type: att.content_type,
+                content_id: att.content_id,
             })
             .collect();
 
         let email_data = EmailData {
-            from: account.email.clone(),
+            from: sender.address.clone(),
             to: request.to.clone(),
             cc: request.cc.clone(),
             bcc: request.bcc.clone(),
             subject: request.subject.clone(),
             body_html: request.body.clone(),
+            body_plain,
             attachments,
             in_reply_to: in_reply_to.clone(),
             references: references_header.clone(),
+            message_id: Some(message_id.clone()),
+            read_receipt: request.read_receipt,
+            smime: smime_request,
+            dkim,
         };
 
-        email_service
-            .send_email(email_data)
-            .await
-            .map_err(|e| format!("Failed to send email: {}", e))?;
+        match request.dsn.clone() {
+            Some(options) => {
+                let dsn = DsnRequest {
+                    envelope_id: sent_email_id.to_string(),
+                    options,
+                };
+                let requested = email_service
+                    .send_email_with_dsn(email_data, &dsn)
+                    .await
+                    .map_err(smtp_send_failure)?;
+                if requested {
+                    dsn_recipients = request
+                        .to
+                        .iter()
+                        .chain(&request.cc)
+                        .chain(&request.bcc)
+                        .map(|addr| addr.address.clone())
+                        .collect();
+                }
+            }
+            None => email_service
+                .send_email(email_data)
+                .await
+                .map_err(smtp_send_failure)?,
+        }
     }
 
     if let Some(draft_id) = request.draft_id {
@@ -357,7 +646,16 @@ pub async fn send_email_from_account(
                 .await
                 .map_err(|e| format!("Failed to get draft: {}", e))?
             {
+                // The server copy of the draft is replaced by the sent message
+                if let Err(e) = draft_sync::queue_remote_delete(&state.db_pool, &draft_email).await
+                {
+                    log::warn!("Failed to queue removal of draft {}: {}", draft_id, e);
+                }
+
                 draft_email.folder_id = sent_folder.id;
+                draft_email.remote_id = None;
+                draft_email.message_id = message_id;
+                draft_email.from = Json(sender);
                 draft_email.is_draft = false;
                 draft_email.sent_at = Some(Utc::now());
                 draft_email.conversation_id = request.conversation_id.clone();
@@ -381,26 +679,16 @@ pub async fn send_email_from_account(
             .map_err(|e| format!("Failed to get folders: {}", e))?;
 
         if let Some(sent_folder) = folders.iter().find(|f| f.folder_type == FolderType::Sent) {
-            let domain = account
-                .email
-                .split_once('@')
-                .map(|(_, d)| d.to_string())
-                .unwrap_or_else(|| "ravn.app".to_string());
-
-            let message_id = format!("<{}@{}>", Uuid::now_v7(), domain);
-            let size = request.body.len();
+            let size = stored_body.len();
 
             let sent_email = Email {
-                id: Uuid::now_v7(),
+                id: sent_email_id,
                 account_id: account.id,
                 folder_id: sent_folder.id,
                 message_id,
                 conversation_id: request.conversation_id.clone(),
                 remote_id: None,
-                from: Json(EmailAddress {
-                    address: account.email.clone(),
-                    name: Some(account.name.clone()),
-                }),
+                from: Json(sender),
                 to: Json(request.to),
                 cc: Json(request.cc),
                 bcc: Json(request.bcc),
@@ -408,7 +696,7 @@ pub async fn send_email_from_account(
                 subject: Some(request.subject),
                 snippet: None,
                 body_plain: None,
-                body_html: Some(request.body),
+                body_html: Some(stored_body),
                 other_mails: None,
                 category: None,
                 ai_cache: None,
@@ -416,7 +704,11 @@ pub async fn send_email_from_account(
                 sent_at: Some(Utc::now()),
                 scheduled_send_at: None,
                 remind_at: None,
+                snoozed_until: None,
                 size: size as i64,
+                priority: 0,
+                risk_score: 0,
+                risk_reasons: Json(Vec::new()),
                 headers: Some("".to_string()),
                 is_read: true,
                 is_flagged: false,
@@ -445,6 +737,19 @@ pub async fn send_email_from_account(
         }
     }
 
+    if !dsn_recipients.is_empty() {
+        if let Err(e) = SqliteDeliveryStatusRepository::new(state.db_pool.clone())
+            .record_pending(sent_email_id, &dsn_recipients)
+            .await
+        {
+            log::warn!(
+                "Failed to record pending delivery status for email {}: {}",
+                sent_email_id,
+                e
+            );
+        }
+    }
+
     if let Err(e) = state.sync_coordinator.notify_outgoing_email().await {
         log::warn!("Failed to trigger outgoing email notification: {}", e);
     }
@@ -455,91 +760,582 @@ pub async fn send_email_from_account(
     })
 }
 
+/// Rejects messages whose attachments exceed what the account's provider
+/// accepts
+fn check_attachment_size(
+    account_type: &AccountType,
+    attachments: &[AttachmentData],
+) -> Result<(), String> {
+    let Some(limit) = account_type.max_attachment_size() else {
+        return Ok(());
+    };
+
+    let total: usize = attachments.iter().map(|att| att.content.len()).sum();
+    if total > limit {
+        return Err(format!(
+            "Attachments are too large to send: {:.1} MB, {} accounts allow up to {} MB",
+            total as f64 / (1024.0 * 1024.0),
+            account_type,
+            limit / (1024 * 1024)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Office 365 accounts send through Microsoft Graph unless an SMTP server
+/// is set up for them
+fn sends_through_graph(account: &Account) -> bool {
+    account.account_type == AccountType::Office365
+        && serde_json::from_value::<AccountSettings>(account.settings.clone())
+            .map_or(true, |settings| settings.smtp_host.is_none())
+}
+
+/// SMTP server of the provider, for accounts that do not configure one
+fn default_smtp_host(account_type: &AccountType) -> Option<&'static str> {
+    match account_type {
+        AccountType::Gmail => Some("smtp.gmail.com"),
+        AccountType::Office365 => Some("smtp.office365.com"),
+        _ => None,
+    }
+}
+
+/// The SMTP connection of an account. Gmail and Office 365 accounts log in
+/// with their OAuth2 token (XOAUTH2), others with the stored password.
+async fn smtp_service(state: &AppState, account: &Account) -> Result<EmailService, SendFailure> {
+    let mut settings: AccountSettings = serde_json::from_value(account.settings.clone())
+        .map_err(|e| format!("Failed to parse account settings: {}", e))?;
+    if account.account_type == AccountType::Apple {
+        icloud::apply_server_presets(&mut settings, &account.email);
+    }
+
+    let smtp_host = settings
+        .smtp_host
+        .clone()
+        .or_else(|| default_smtp_host(&account.account_type).map(str::to_string))
+        .or_else(|| settings.imap_host.clone())
+        .ok_or_else(|| "Neither SMTP nor IMAP host configured for this account".to_string())?;
+
+    let smtp_port = settings.smtp_port.unwrap_or(587);
+    let smtp_use_tls = settings
+        .smtp_use_tls
+        .unwrap_or_else(|| settings.imap_use_tls.unwrap_or(true));
+
+    let smtp_username = settings
+        .smtp_username
+        .clone()
+        .or_else(|| settings.imap_username.clone())
+        .unwrap_or(account.email.clone());
+
+    let oauth2_provider = match account.account_type {
+        AccountType::Gmail => Some("gmail"),
+        AccountType::Office365 => Some("office365"),
+        _ => None,
+    };
+    // Accounts added with an app password have no OAuth2 credentials
+    let oauth2 = match oauth2_provider {
+        Some(provider) => state
+            .credential_store
+            .get_oauth2(account.id)
+            .await
+            .ok()
+            .map(|credentials| (provider, credentials)),
+        None => None,
+    };
+
+    if let Some((provider, credentials)) = oauth2 {
+        let access_token = OAuth2Helper::smtp_access_token(
+            provider,
+            credentials,
+            &state.credential_store,
+            account.id,
+        )
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to get a token for sending: {}", e);
+            if e.is_retryable() {
+                SendFailure::Transport(message)
+            } else {
+                SendFailure::Rejected(message)
+            }
+        })?;
+
+        return Ok(EmailService::from_oauth2(
+            smtp_host,
+            smtp_port,
+            smtp_use_tls,
+            smtp_username,
+            access_token,
+        )
+        .with_tls(settings.smtp_tls_mode, settings.smtp_trusted_certificate));
+    }
+
+    let credentials = state
+        .credential_store
+        .get_imap(account.id)
+        .await
+        .map_err(|e| format!("Failed to get credentials: {}", e))?;
+
+    EmailService::from_account_settings(
+        smtp_host,
+        smtp_port,
+        smtp_use_tls,
+        smtp_username,
+        credentials.password,
+    )
+    .map(|service| service.with_tls(settings.smtp_tls_mode, settings.smtp_trusted_certificate))
+    .map_err(|e| SendFailure::Rejected(format!("Failed to initialize email service: {}", e)))
+}
+
+fn office365_send_failure(error: SyncError) -> SendFailure {
+    let message = format!("Failed to send email via Office365: {}", error);
+    if error.is_retryable() {
+        SendFailure::Transport(message)
+    } else {
+        SendFailure::Rejected(message)
+    }
+}
+
+fn smtp_send_failure(error: EmailError) -> SendFailure {
+    let message = format!("Failed to send email: {}", error);
+    match error {
+        EmailError::SmtpError(_) => SendFailure::Transport(message),
+        _ => SendFailure::Rejected(message),
+    }
+}
+
+/// A reply or forward to an email, ready for the composer
+#[derive(Debug, Serialize)]
+pub struct PreparedReply {
+    pub account_id: Uuid,
+    /// The identity the original was addressed to, to answer from it
+    pub identity_id: Option<Uuid>,
+    pub conversation_id: Option<String>,
+    #[serde(flatten)]
+    pub parts: ReplyParts,
+    /// The attachments of a forwarded message, or the inline images a quoted
+    /// reply shows
+    pub attachments: Vec<AttachmentData>,
+    /// Attachments that were never downloaded and are left out
+    pub missing_attachments: Vec<String>,
+}
+
+/// Builds the recipients, subject, threading headers, quote and carried-over
+/// attachments of a reply or forward to an email
 #[tauri::command]
-pub async fn save_draft(
+pub async fn prepare_reply(
     state: State<'_, AppState>,
-    request: SaveDraftRequest,
-) -> Result<SaveDraftResponse, String> {
-    log::info!("Saving draft for account {}", request.account_id);
-
-    let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
-    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
-    let account_repo = SqliteAccountRepository::new(state.db_pool.clone());
+    email_id: Uuid,
+    mode: ReplyMode,
+) -> Result<PreparedReply, String> {
+    let email = SqliteEmailRepository::new(state.db_pool.clone())
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to get email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
 
-    let account = account_repo
-        .find_by_id(request.account_id)
+    let account = SqliteAccountRepository::new(state.db_pool.clone())
+        .find_by_id(email.account_id)
         .await
         .map_err(|e| format!("Failed to find account: {}", e))?
-        .ok_or_else(|| format!("Account {} not found", request.account_id))?;
+        .ok_or_else(|| format!("Account {} not found", email.account_id))?;
 
-    let folders = folder_repo
+    let identities = SqliteIdentityRepository::new(state.db_pool.clone())
         .find_by_account(account.id)
         .await
-        .map_err(|e| format!("Failed to get folders: {}", e))?;
+        .map_err(|e| format!("Failed to get identities: {}", e))?;
+    let own_addresses: Vec<String> = std::iter::once(account.email.clone())
+        .chain(identities.iter().map(|identity| identity.email.clone()))
+        .collect();
 
-    let draft_folder = folders
+    let identity_id = identities
         .iter()
-        .find(|f| f.folder_type == FolderType::Draft)
-        .ok_or_else(|| "Draft folder not found for this account".to_string())?;
+        .find(|identity| {
+            std::iter::once(&email.from.0)
+                .chain(&email.to.0)
+                .chain(&email.cc.0)
+                .any(|address| address.address.eq_ignore_ascii_case(&identity.email))
+        })
+        .map(|identity| identity.id);
 
-    let scheduled_send_at = if let Some(timestamp) = request.scheduled_send_at {
-        Some(
-            chrono::DateTime::parse_from_rfc3339(&timestamp)
-                .map_err(|e| format!("Invalid scheduled_send_at timestamp: {}", e))?
-                .with_timezone(&Utc),
-        )
-    } else {
-        None
-    };
+    let mut parts = reply_builder::build(&email, mode, &own_addresses);
 
-    // Build headers JSON with threading info
-    let headers = {
-        let mut h = serde_json::Map::new();
-        if let Some(ref irt) = request.in_reply_to {
-            h.insert(
-                "In-Reply-To".to_string(),
-                serde_json::Value::String(irt.clone()),
-            );
+    let mut attachments = Vec::new();
+    let mut missing_attachments = Vec::new();
+    if email.has_attachments {
+        let stored = SqliteAttachmentRepository::new(state.db_pool.clone())
+            .find_by_email(email.id)
+            .await
+            .map_err(|e| format!("Failed to get attachments: {}", e))?;
+
+        // Quoted inline images are embedded, so the composer shows them; they
+        // go out as inline attachments again when sent
+        let mut quoted_images = std::collections::HashMap::new();
+        for attachment in stored {
+            let quoted_content_id = attachment.content_id.clone().filter(|content_id| {
+                attachment.is_inline && cid_utils::is_cid_referenced(&parts.quoted_body, content_id)
+            });
+            if mode != ReplyMode::Forward && quoted_content_id.is_none() {
+                continue;
+            }
+
+            match read_cached_attachment(&state.app_data_dir, &attachment) {
+                Ok(data) => match quoted_content_id {
+                    Some(content_id) => {
+                        quoted_images.insert(
+                            content_id,
+                            conversation_export::data_uri(&attachment.content_type, &data.content),
+                        );
+                    }
+                    None => attachments.push(data),
+                },
+                Err(e) => {
+                    log::warn!(
+                        "Leaving attachment {} out of the reply to {}: {}",
+                        attachment.id,
+                        email.id,
+                        e
+                    );
+                    missing_attachments.push(attachment.filename);
+                }
+            }
         }
-        if let Some(ref refs) = request.references {
-            h.insert(
-                "References".to_string(),
-                serde_json::Value::String(refs.clone()),
-            );
+
+        if !quoted_images.is_empty() {
+            parts.quoted_body = cid_utils::replace_cid_urls(&parts.quoted_body, &quoted_images);
+            parts.quote_html = cid_utils::replace_cid_urls(&parts.quote_html, &quoted_images);
         }
-        serde_json::Value::Object(h).to_string()
+    }
+
+    Ok(PreparedReply {
+        account_id: account.id,
+        identity_id,
+        conversation_id: email.conversation_id.clone(),
+        parts,
+        attachments,
+        missing_attachments,
+    })
+}
+
+/// The sender of a message from `account`, with the account name as fallback
+/// display name for identities that have none
+async fn resolve_sender(
+    state: &AppState,
+    account: &Account,
+    identity_id: Option<Uuid>,
+) -> Result<EmailAddress, String> {
+    let Some(identity_id) = identity_id else {
+        return Ok(EmailAddress {
+            address: account.email.clone(),
+            name: Some(account.name.clone()),
+        });
     };
 
-    if let Some(draft_id) = request.draft_id {
-        let mut draft = email_repo
-            .find_by_id(draft_id)
+    let identity = SqliteIdentityRepository::new(state.db_pool.clone())
+        .find_by_id(identity_id)
+        .await
+        .map_err(|e| format!("Failed to find identity: {}", e))?
+        .filter(|identity| identity.account_id == account.id)
+        .ok_or_else(|| format!("Identity {} not found for this account", identity_id))?;
+
+    Ok(EmailAddress {
+        address: identity.email,
+        name: identity.name.or_else(|| Some(account.name.clone())),
+    })
+}
+
+/// The signature to add to a message from `account`. Bodies that already
+/// contain one, e.g. inserted by the composer, get none.
+async fn resolve_signature(
+    state: &AppState,
+    account: &Account,
+    from: &str,
+    choice: SignatureChoice,
+    body: &str,
+    is_reply: bool,
+) -> Result<Option<Signature>, String> {
+    if signatures::has_signature(body) {
+        return Ok(None);
+    }
+
+    let signature_repo = SqliteSignatureRepository::new(state.db_pool.clone());
+
+    match choice {
+        SignatureChoice::None => Ok(None),
+        SignatureChoice::Id(id) => {
+            let signature = signature_repo
+                .find_by_id(id)
+                .await
+                .map_err(|e| format!("Failed to find signature: {}", e))?
+                .filter(|signature| signature.account_id == account.id)
+                .ok_or_else(|| format!("Signature {} not found for this account", id))?;
+            Ok(Some(signature))
+        }
+        SignatureChoice::Auto => {
+            let account_signatures = signature_repo
+                .find_by_account(account.id)
+                .await
+                .map_err(|e| format!("Failed to get signatures: {}", e))?;
+            Ok(signatures::select(&account_signatures, from, is_reply).cloned())
+        }
+    }
+}
+
+/// Collects the key material for an S/MIME protected message.
+///
+/// Encryption needs a certificate for every recipient, and the sender's own so
+/// the copy in Sent stays readable.
+async fn resolve_smime_request(
+    state: &AppState,
+    account: &Account,
+    request: &SendFromAccountRequest,
+    options: &SmimeOptions,
+) -> Result<SmimeRequest, String> {
+    let mut smime = SmimeRequest::default();
+
+    if options.sign {
+        let identity = state
+            .credential_store
+            .get_smime(account.id)
             .await
-            .map_err(|e| format!("Failed to find draft: {}", e))?
-            .ok_or_else(|| format!("Draft {} not found", draft_id))?;
+            .map_err(|_| "No S/MIME certificate has been imported for this account".to_string())?;
+        smime.signer = Some(identity);
+    }
 
-        draft.to = Json(request.to);
-        draft.cc = Json(request.cc);
-        draft.bcc = Json(request.bcc);
-        draft.subject = Some(request.subject);
-        draft.body_html = Some(request.body);
-        draft.conversation_id = request.conversation_id;
-        draft.headers = Some(headers);
-        draft.scheduled_send_at = scheduled_send_at;
-        draft.updated_at = Utc::now();
+    if options.encrypt {
+        let smime_repo = SqliteSmimeRepository::new(state.db_pool.clone());
+        let recipients: Vec<String> = request
+            .to
+            .iter()
+            .chain(&request.cc)
+            .chain(&request.bcc)
+            .map(|addr| addr.address.trim().to_lowercase())
+            .collect();
 
-        email_repo
-            .update(&draft)
+        let certificates = smime_repo
+            .find_encryption_certificates(&recipients)
             .await
-            .map_err(|e| format!("Failed to update draft: {}", e))?;
+            .map_err(|e| format!("Failed to get S/MIME certificates: {}", e))?;
 
-        emit_email_event(&state.app_handle, "email:updated", &draft);
+        let missing: Vec<&str> = recipients
+            .iter()
+            .filter(|address| !certificates.iter().any(|cert| &cert.email == *address))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "No valid S/MIME certificate for: {}",
+                missing.join(", ")
+            ));
+        }
 
-        Ok(SaveDraftResponse {
-            success: true,
-            draft_id,
-            message: "Draft updated successfully".to_string(),
-        })
-    } else {
+        let own = smime_repo
+            .find_signing_certificate(account.id)
+            .await
+            .map_err(|e| format!("Failed to get S/MIME certificates: {}", e))?
+            .ok_or_else(|| {
+                "No S/MIME certificate has been imported for this account".to_string()
+            })?;
+
+        smime.recipient_certificates = certificates
+            .into_iter()
+            .chain(std::iter::once(own))
+            .map(|cert| cert.certificate_pem)
+            .collect();
+    }
+
+    Ok(smime)
+}
+
+/// Per-recipient delivery status and read receipts of a sent message
+#[tauri::command]
+pub async fn get_delivery_status(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+) -> Result<Vec<RecipientDeliveryStatus>, String> {
+    SqliteDeliveryStatusRepository::new(state.db_pool.clone())
+        .find_by_email(email_id)
+        .await
+        .map_err(|e| format!("Failed to get delivery status: {}", e))
+}
+
+/// Emails that failed to send and are waiting to be retried, oldest first
+#[tauri::command]
+pub async fn get_outbox(state: State<'_, AppState>) -> Result<Vec<OutboxItem>, String> {
+    SqliteOutboxRepository::new(state.db_pool.clone())
+        .find_all()
+        .await
+        .map_err(|e| format!("Failed to get outbox: {}", e))
+}
+
+/// Sends an email in the outbox right away, including one that was given up on
+#[tauri::command]
+pub async fn retry_send(state: State<'_, AppState>, id: Uuid) -> Result<SendEmailResponse, String> {
+    let item = SqliteOutboxRepository::new(state.db_pool.clone())
+        .find_by_id(id)
+        .await
+        .map_err(|e| format!("Failed to get outbox item: {}", e))?
+        .ok_or_else(|| format!("Outbox item {} not found", id))?;
+
+    outbox_worker::send_outbox_item(&state, &item).await
+}
+
+/// Removes an email from the outbox without sending it
+#[tauri::command]
+pub async fn discard_outbox_item(state: State<'_, AppState>, id: Uuid) -> Result<(), String> {
+    let discarded = SqliteOutboxRepository::new(state.db_pool.clone())
+        .discard(id)
+        .await
+        .map_err(|e| format!("Failed to discard outbox item: {}", e))?;
+    if !discarded {
+        return Err("The email is being sent or no longer in the outbox".to_string());
+    }
+
+    Ok(())
+}
+
+/// Fills in a template's placeholders for the given recipient. Addresses that
+/// are not in the contact list only get `{{email}}` and fallbacks.
+#[tauri::command]
+pub async fn apply_template(
+    state: State<'_, AppState>,
+    template_id: Uuid,
+    recipient: String,
+) -> Result<RenderedTemplate, String> {
+    let template = SqliteTemplateRepository::new(state.db_pool.clone())
+        .find_by_id(template_id)
+        .await
+        .map_err(|e| format!("Failed to find template: {}", e))?
+        .ok_or_else(|| format!("Template {} not found", template_id))?;
+
+    let recipient = recipient.trim().to_lowercase();
+    let contact = SqliteContactRepository::new(state.db_pool.clone())
+        .find_by_email(&recipient)
+        .await
+        .map_err(|e| format!("Failed to find contact: {}", e))?;
+
+    let variables = template_renderer::recipient_variables(&recipient, contact.as_ref());
+
+    Ok(RenderedTemplate {
+        template_id: template.id,
+        subject: template
+            .subject
+            .as_deref()
+            .map(|subject| template_renderer::render(subject, &variables, false)),
+        body: template_renderer::render(&template.body, &variables, true),
+    })
+}
+
+#[tauri::command]
+pub async fn save_draft(
+    state: State<'_, AppState>,
+    request: SaveDraftRequest,
+) -> Result<SaveDraftResponse, String> {
+    log::info!("Saving draft for account {}", request.account_id);
+
+    let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let account_repo = SqliteAccountRepository::new(state.db_pool.clone());
+
+    let account = account_repo
+        .find_by_id(request.account_id)
+        .await
+        .map_err(|e| format!("Failed to find account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", request.account_id))?;
+
+    let folders = folder_repo
+        .find_by_account(account.id)
+        .await
+        .map_err(|e| format!("Failed to get folders: {}", e))?;
+
+    let draft_folder = folders
+        .iter()
+        .find(|f| f.folder_type == FolderType::Draft)
+        .ok_or_else(|| "Draft folder not found for this account".to_string())?;
+
+    let sender = resolve_sender(&state, &account, request.identity_id).await?;
+
+    let mut request = request;
+    if let Some(signature) = resolve_signature(
+        &state,
+        &account,
+        &sender.address,
+        request.signature,
+        &request.body,
+        request.in_reply_to.is_some(),
+    )
+    .await?
+    {
+        request.body = signatures::insert_html(&request.body, &signature);
+    }
+
+    let scheduled_send_at = if let Some(timestamp) = request.scheduled_send_at {
+        Some(
+            chrono::DateTime::parse_from_rfc3339(&timestamp)
+                .map_err(|e| format!("Invalid scheduled_send_at timestamp: {}", e))?
+                .with_timezone(&Utc),
+        )
+    } else {
+        None
+    };
+
+    // Build headers JSON with threading info
+    let headers = {
+        let mut h = serde_json::Map::new();
+        if let Some(ref irt) = request.in_reply_to {
+            h.insert(
+                "In-Reply-To".to_string(),
+                serde_json::Value::String(irt.clone()),
+            );
+        }
+        if let Some(ref refs) = request.references {
+            h.insert(
+                "References".to_string(),
+                serde_json::Value::String(refs.clone()),
+            );
+        }
+        serde_json::Value::Object(h).to_string()
+    };
+
+    if let Some(draft_id) = request.draft_id {
+        let mut draft = email_repo
+            .find_by_id(draft_id)
+            .await
+            .map_err(|e| format!("Failed to find draft: {}", e))?
+            .ok_or_else(|| format!("Draft {} not found", draft_id))?;
+
+        draft.from = Json(sender);
+        draft.to = Json(request.to);
+        draft.cc = Json(request.cc);
+        draft.bcc = Json(request.bcc);
+        draft.subject = Some(request.subject);
+        draft.body_html = Some(request.body);
+        draft.conversation_id = request.conversation_id;
+        draft.headers = Some(headers);
+        draft.scheduled_send_at = scheduled_send_at;
+        draft.updated_at = Utc::now();
+
+        email_repo
+            .update(&draft)
+            .await
+            .map_err(|e| format!("Failed to update draft: {}", e))?;
+        email_repo
+            .update_scheduled_send_at(draft_id, scheduled_send_at)
+            .await
+            .map_err(|e| format!("Failed to update scheduled send: {}", e))?;
+
+        if let Err(e) = draft_sync::queue_upload(&state.db_pool, &draft).await {
+            log::warn!("Failed to queue upload of draft {}: {}", draft_id, e);
+        }
+
+        emit_email_event(&state.app_handle, "email:updated", &draft);
+
+        Ok(SaveDraftResponse {
+            success: true,
+            draft_id,
+            message: "Draft updated successfully".to_string(),
+        })
+    } else {
         // Create a conversation for new drafts if one wasn't provided
         let conversation_id = if let Some(conv_id) = request.conversation_id {
             Some(conv_id)
@@ -550,6 +1346,7 @@ pub async fn save_draft(
                 remote_id: format!("local-draft-{}", Uuid::now_v7()),
                 message_count: 0,
                 ai_cache: None,
+                muted_at: None,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             };
@@ -569,10 +1366,7 @@ pub async fn save_draft(
             message_id,
             conversation_id,
             remote_id: None,
-            from: Json(EmailAddress {
-                address: account.email.clone(),
-                name: Some(account.name.clone()),
-            }),
+            from: Json(sender),
             to: Json(request.to),
             cc: Json(request.cc),
             bcc: Json(request.bcc),
@@ -586,10 +1380,14 @@ pub async fn save_draft(
             ai_cache: None,
             received_at: Utc::now(),
             size: 0,
+            priority: 0,
+            risk_score: 0,
+            risk_reasons: Json(Vec::new()),
             headers: Some(headers),
             sent_at: None,
             scheduled_send_at,
             remind_at: None,
+            snoozed_until: None,
             is_read: false,
             is_flagged: false,
             is_draft: true,
@@ -612,6 +1410,16 @@ pub async fn save_draft(
             .create(&draft)
             .await
             .map_err(|e| format!("Failed to create draft: {}", e))?;
+        if scheduled_send_at.is_some() {
+            email_repo
+                .update_scheduled_send_at(draft_id, scheduled_send_at)
+                .await
+                .map_err(|e| format!("Failed to update scheduled send: {}", e))?;
+        }
+
+        if let Err(e) = draft_sync::queue_upload(&state.db_pool, &draft).await {
+            log::warn!("Failed to queue upload of draft {}: {}", draft_id, e);
+        }
 
         emit_email_event(&state.app_handle, "email:created", &draft);
 
@@ -635,10 +1443,11 @@ pub async fn get_accounts_for_sending(
         .await
         .map_err(|e| format!("Failed to get accounts: {}", e))?;
 
+    let identity_repo = SqliteIdentityRepository::new(state.db_pool.clone());
     let mut sending_accounts = Vec::new();
 
     for account in accounts {
-        let has_smtp_config = if account.account_type == AccountType::Office365 {
+        let has_smtp_config = if default_smtp_host(&account.account_type).is_some() {
             true
         } else {
             if let Ok(settings) =
@@ -650,12 +1459,18 @@ pub async fn get_accounts_for_sending(
             }
         };
 
+        let identities = identity_repo
+            .find_by_account(account.id)
+            .await
+            .map_err(|e| format!("Failed to get identities: {}", e))?;
+
         sending_accounts.push(AccountForSending {
             id: account.id,
             name: account.name,
             email: account.email,
             account_type: account.account_type.to_string(),
             has_smtp_config,
+            identities,
         });
     }
 
@@ -699,6 +1514,14 @@ pub async fn delete_draft(
 
     let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
 
+    if let Some(draft) = email_repo
+        .find_by_id(draft_id)
+        .await
+        .map_err(|e| format!("Failed to find draft: {}", e))?
+    {
+        draft_sync::queue_remote_delete(&state.db_pool, &draft).await?;
+    }
+
     email_repo
         .delete(draft_id)
         .await
@@ -732,10 +1555,12 @@ pub async fn get_emails(state: State<'_, AppState>, id: Uuid) -> Result<EmailDet
         .map(LabelInfo::from)
         .collect();
 
-    let attachments: Vec<AttachmentInfo> = attachment_repo
+    let stored_attachments = attachment_repo
         .find_by_email(email.id)
         .await
-        .map_err(|e| format!("Failed to fetch attachments: {}", e))?
+        .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+    email_attachments_opened(&state, &email, &stored_attachments).await;
+    let attachments: Vec<AttachmentInfo> = stored_attachments
         .iter()
         .map(AttachmentInfo::from)
         .collect();
@@ -745,6 +1570,12 @@ pub async fn get_emails(state: State<'_, AppState>, id: Uuid) -> Result<EmailDet
         EmailDetail::from_email(&email, labels, attachments),
         &notified_at_by_email,
     );
+    detail.invite = SqliteCalendarEventRepository::new(state.db_pool.clone())
+        .find_by_email(email.id)
+        .await
+        .map_err(|e| format!("Failed to fetch calendar event: {}", e))?;
+
+    block_remote_content(&state, &email, &mut detail).await?;
 
     // Replace cid: references in body_html with Tauri asset:// URLs so inline
     // images (logos, signatures, etc.) render correctly in the email view.
@@ -765,6 +1596,127 @@ pub async fn get_emails(state: State<'_, AppState>, id: Uuid) -> Result<EmailDet
     Ok(detail)
 }
 
+/// Blocks the remote content of an email for display: its trackers unless
+/// tracking was allowed, and its remote images unless they were loaded for
+/// the message or its sender or domain is on the image allowlist. The
+/// trackers found are recorded.
+pub(crate) async fn block_remote_content(
+    state: &AppState,
+    email: &Email,
+    detail: &mut EmailDetail,
+) -> Result<(), String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+
+    let sender_allowed = SqliteImageAllowlistRepository::new(state.db_pool.clone())
+        .is_sender_allowed(&email.from.address)
+        .await
+        .map_err(|e| format!("Failed to check image allowlist: {}", e))?;
+    let options = BlockingOptions {
+        block_trackers: email.tracking_blocked,
+        block_images: email.images_blocked && !sender_allowed,
+    };
+
+    let mut trackers = Vec::new();
+    let mut blocked_images = 0;
+    for html in [&mut detail.body_html, &mut detail.other_mails]
+        .into_iter()
+        .flatten()
+    {
+        let sanitized = html_sanitizer::sanitize(html, options);
+        *html = sanitized.html;
+        trackers.extend(sanitized.trackers);
+        blocked_images += sanitized.blocked_images as i64;
+    }
+
+    if !trackers.is_empty() {
+        email_repo
+            .record_trackers(email.id, &trackers)
+            .await
+            .map_err(|e| format!("Failed to record trackers: {}", e))?;
+    }
+    detail.trackers = email_repo
+        .find_trackers(email.id)
+        .await
+        .map_err(|e| format!("Failed to fetch trackers: {}", e))?;
+    detail.blocked_images = blocked_images;
+
+    Ok(())
+}
+
+/// Loads the remote images of an email, and of all mail from its sender if
+/// `always_for_sender` is set. Trackers stay blocked.
+#[tauri::command]
+pub async fn load_remote_content(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+    always_for_sender: Option<bool>,
+) -> Result<EmailDetail, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+
+    let mut email = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    if always_for_sender.unwrap_or(false) {
+        SqliteImageAllowlistRepository::new(state.db_pool.clone())
+            .add(&ImageAllowlistEntry::sender(&email.from.address))
+            .await
+            .map_err(|e| format!("Failed to allow images from sender: {}", e))?;
+    }
+
+    if email.images_blocked {
+        email.images_blocked = false;
+        email_repo
+            .update(&email)
+            .await
+            .map_err(|e| format!("Failed to update email blocking: {}", e))?;
+        emit_email_event(&state.app_handle, "email:updated", serde_json::json!(email));
+    }
+
+    get_emails(state, email_id).await
+}
+
+/// Always loads the remote images of mail from a sender, given as an address,
+/// or from a whole domain, given as `example.com` or `@example.com`
+#[tauri::command]
+pub async fn always_load_images_from(
+    state: State<'_, AppState>,
+    sender: String,
+) -> Result<ImageAllowlistEntry, String> {
+    let entry = ImageAllowlistEntry::parse(&sender)
+        .ok_or_else(|| format!("Invalid sender or domain: {}", sender))?;
+
+    SqliteImageAllowlistRepository::new(state.db_pool.clone())
+        .add(&entry)
+        .await
+        .map_err(|e| format!("Failed to allow images from {}: {}", entry.pattern, e))?;
+
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn get_image_allowlist(
+    state: State<'_, AppState>,
+) -> Result<Vec<ImageAllowlistEntry>, String> {
+    SqliteImageAllowlistRepository::new(state.db_pool.clone())
+        .get_all()
+        .await
+        .map_err(|e| format!("Failed to fetch image allowlist: {}", e))
+}
+
+#[tauri::command]
+pub async fn remove_from_image_allowlist(
+    state: State<'_, AppState>,
+    pattern: String,
+) -> Result<(), String> {
+    SqliteImageAllowlistRepository::new(state.db_pool.clone())
+        .delete(&pattern)
+        .await
+        .map_err(|e| format!("Failed to remove {} from image allowlist: {}", pattern, e))
+}
+
 /// Build a map from content_id → Tauri asset:// URL for all cached inline attachments.
 fn build_cid_asset_url_map(
     attachments: &[AttachmentInfo],
@@ -809,6 +1761,7 @@ pub async fn get_emails_for_folders(
         .await
         .map_err(|e| format!("Failed to fetch labels: {}", e))?;
     let notified_at_by_email = reminder_notification_map(&state, &email_ids).await?;
+    let account_colors = account_color_map(&state).await?;
 
     let list_items = emails
         .iter()
@@ -817,9 +1770,12 @@ pub async fn get_emails_for_folders(
                 .get(&email.id)
                 .map(|labels| labels.iter().map(LabelInfo::from).collect())
                 .unwrap_or_default();
-            apply_notified_at_to_list_item(
-                EmailListItem::from_email(email, labels),
-                &notified_at_by_email,
+            apply_account_color_to_list_item(
+                apply_notified_at_to_list_item(
+                    EmailListItem::from_email(email, labels),
+                    &notified_at_by_email,
+                ),
+                &account_colors,
             )
         })
         .collect();
@@ -827,24 +1783,28 @@ pub async fn get_emails_for_folders(
     Ok(list_items)
 }
 
+/// Returns the rows `start..end` of a folder for a virtualized list, together
+/// with the folder's cached total and an order token that changes whenever
+/// rows are added, removed or reordered.
 #[tauri::command]
-pub async fn get_emails_for_labels(
+pub async fn get_email_window(
     state: State<'_, AppState>,
-    label_ids: Vec<Uuid>,
-    match_all: bool,
-    limit: Option<i64>,
-    offset: Option<i64>,
-) -> Result<Vec<EmailListItem>, String> {
+    folder_id: Uuid,
+    start: i64,
+    end: i64,
+    order_token: Option<i64>,
+) -> Result<EmailWindow, String> {
     let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
     let label_repo = SqliteLabelRepository::new(state.db_pool.clone());
 
-    let limit = limit.unwrap_or(50);
-    let offset = offset.unwrap_or(0);
+    let start = start.max(0);
+    let limit = (end - start).clamp(0, MAX_EMAIL_WINDOW);
 
-    let emails = email_repo
-        .find_by_labels(&label_ids, match_all, limit, offset)
+    let (list_state, emails) = email_repo
+        .find_folder_window(folder_id, start, limit)
         .await
-        .map_err(|e| format!("Failed to fetch emails by labels: {}", e))?;
+        .map_err(|e| format!("Failed to fetch email window: {}", e))?
+        .ok_or_else(|| format!("Folder {} not found", folder_id))?;
 
     let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
     let labels_map = label_repo
@@ -852,54 +1812,231 @@ pub async fn get_emails_for_labels(
         .await
         .map_err(|e| format!("Failed to fetch labels: {}", e))?;
     let notified_at_by_email = reminder_notification_map(&state, &email_ids).await?;
+    let account_colors = account_color_map(&state).await?;
 
-    let list_items = emails
+    let items = emails
         .iter()
         .map(|email| {
             let labels = labels_map
                 .get(&email.id)
                 .map(|labels| labels.iter().map(LabelInfo::from).collect())
                 .unwrap_or_default();
-            apply_notified_at_to_list_item(
-                EmailListItem::from_email(email, labels),
-                &notified_at_by_email,
+            apply_account_color_to_list_item(
+                apply_notified_at_to_list_item(
+                    EmailListItem::from_email(email, labels),
+                    &notified_at_by_email,
+                ),
+                &account_colors,
             )
         })
         .collect();
 
-    Ok(list_items)
+    Ok(EmailWindow {
+        folder_id,
+        start,
+        items,
+        total_count: list_state.total_count,
+        order_token: list_state.order_token,
+        stale: order_token.is_some_and(|token| token != list_state.order_token),
+    })
 }
 
 #[tauri::command]
-pub async fn update_read(
+pub async fn get_unified_inbox(
     state: State<'_, AppState>,
-    email_id: Uuid,
-    is_read: bool,
-) -> Result<(), String> {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+    filter_read: Option<bool>,
+) -> Result<UnifiedInboxResponse, String> {
     let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let label_repo = SqliteLabelRepository::new(state.db_pool.clone());
 
-    let mut email = email_repo
-        .find_by_id(email_id)
+    let limit = limit.unwrap_or(50);
+    let offset = offset.unwrap_or(0);
+
+    let emails = email_repo
+        .find_unified_inbox(
+            limit,
+            offset,
+            sort_by.as_deref().unwrap_or("received_at"),
+            sort_order.as_deref().unwrap_or("desc"),
+            filter_read,
+        )
         .await
-        .map_err(|e| format!("Failed to fetch email: {}", e))?
-        .ok_or_else(|| format!("Email {} not found", email_id))?;
+        .map_err(|e| format!("Failed to fetch unified inbox: {}", e))?;
 
-    state
-        .sync_coordinator
-        .mark_as_read(email.account_id, email_id, is_read)
+    let accounts = email_repo
+        .count_unified_inbox()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| format!("Failed to count unified inbox: {}", e))?;
+    let total: i64 = accounts.iter().map(|c| c.total).sum();
+    let unread: i64 = accounts.iter().map(|c| c.unread).sum();
+    let matching = match filter_read {
+        Some(false) => unread,
+        Some(true) => total - unread,
+        None => total,
+    };
 
-    email.is_read = is_read;
-    email_repo
-        .update_read_status(email_id, is_read)
+    let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
+    let labels_map = label_repo
+        .find_by_emails(&email_ids)
         .await
-        .map_err(|e| format!("Failed to update read status: {}", e))?;
+        .map_err(|e| format!("Failed to fetch labels: {}", e))?;
+    let notified_at_by_email = reminder_notification_map(&state, &email_ids).await?;
+    let account_colors = account_color_map(&state).await?;
 
-    emit_email_event(&state.app_handle, "email:updated", serde_json::json!(email));
-    emit_email_event(
-        &state.app_handle,
-        "folder:updated",
+    let list_items: Vec<EmailListItem> = emails
+        .iter()
+        .map(|email| {
+            let labels = labels_map
+                .get(&email.id)
+                .map(|labels| labels.iter().map(LabelInfo::from).collect())
+                .unwrap_or_default();
+            apply_account_color_to_list_item(
+                apply_notified_at_to_list_item(
+                    EmailListItem::from_email(email, labels),
+                    &notified_at_by_email,
+                ),
+                &account_colors,
+            )
+        })
+        .collect();
+
+    Ok(UnifiedInboxResponse {
+        has_more: offset + (list_items.len() as i64) < matching,
+        emails: list_items,
+        total,
+        unread,
+        accounts,
+    })
+}
+
+/// Important inbox emails of one or all accounts, most important first
+#[tauri::command]
+pub async fn get_priority_inbox(
+    state: State<'_, AppState>,
+    account_id: Option<Uuid>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<EmailListItem>, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let label_repo = SqliteLabelRepository::new(state.db_pool.clone());
+
+    let emails = email_repo
+        .find_priority_inbox(
+            account_id,
+            priority::IMPORTANT,
+            limit.unwrap_or(50),
+            offset.unwrap_or(0),
+        )
+        .await
+        .map_err(|e| format!("Failed to fetch priority inbox: {}", e))?;
+
+    let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
+    let labels_map = label_repo
+        .find_by_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to fetch labels: {}", e))?;
+    let notified_at_by_email = reminder_notification_map(&state, &email_ids).await?;
+    let account_colors = account_color_map(&state).await?;
+
+    Ok(emails
+        .iter()
+        .map(|email| {
+            let labels = labels_map
+                .get(&email.id)
+                .map(|labels| labels.iter().map(LabelInfo::from).collect())
+                .unwrap_or_default();
+            apply_account_color_to_list_item(
+                apply_notified_at_to_list_item(
+                    EmailListItem::from_email(email, labels),
+                    &notified_at_by_email,
+                ),
+                &account_colors,
+            )
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_emails_for_labels(
+    state: State<'_, AppState>,
+    label_ids: Vec<Uuid>,
+    match_all: bool,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<EmailListItem>, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let label_repo = SqliteLabelRepository::new(state.db_pool.clone());
+
+    let limit = limit.unwrap_or(50);
+    let offset = offset.unwrap_or(0);
+
+    let emails = email_repo
+        .find_by_labels(&label_ids, match_all, limit, offset)
+        .await
+        .map_err(|e| format!("Failed to fetch emails by labels: {}", e))?;
+
+    let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
+    let labels_map = label_repo
+        .find_by_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to fetch labels: {}", e))?;
+    let notified_at_by_email = reminder_notification_map(&state, &email_ids).await?;
+    let account_colors = account_color_map(&state).await?;
+
+    let list_items = emails
+        .iter()
+        .map(|email| {
+            let labels = labels_map
+                .get(&email.id)
+                .map(|labels| labels.iter().map(LabelInfo::from).collect())
+                .unwrap_or_default();
+            apply_account_color_to_list_item(
+                apply_notified_at_to_list_item(
+                    EmailListItem::from_email(email, labels),
+                    &notified_at_by_email,
+                ),
+                &account_colors,
+            )
+        })
+        .collect();
+
+    Ok(list_items)
+}
+
+#[tauri::command]
+pub async fn update_read(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+    is_read: bool,
+) -> Result<(), String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+
+    let mut email = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    state
+        .sync_coordinator
+        .mark_as_read(email.account_id, email_id, is_read)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    email.is_read = is_read;
+    email_repo
+        .update_read_status(email_id, is_read)
+        .await
+        .map_err(|e| format!("Failed to update read status: {}", e))?;
+
+    emit_email_event(&state.app_handle, "email:updated", serde_json::json!(email));
+    emit_email_event(
+        &state.app_handle,
+        "folder:updated",
         serde_json::json!({
             "account_id": email.account_id.to_string(),
             "id": email.folder_id.to_string()
@@ -966,6 +2103,8 @@ pub async fn move_email(
         .map_err(|e| format!("Failed to fetch updated email: {}", e))?
         .ok_or_else(|| format!("Email {} not found after move", email_id))?;
 
+    train_junk_filter(&state, &email, source_folder_id, folder_id).await;
+
     emit_email_event(&state.app_handle, "email:updated", serde_json::json!(email));
     emit_email_event(
         &state.app_handle,
@@ -987,6 +2126,45 @@ pub async fn move_email(
     Ok(updated_email)
 }
 
+/// Moving a message into or out of the spam folder trains the local junk filter
+async fn train_junk_filter(
+    state: &State<'_, AppState>,
+    email: &Email,
+    source_folder_id: Uuid,
+    target_folder_id: Uuid,
+) {
+    if !state.feature_flags.is_enabled(Feature::JunkFilter).await {
+        return;
+    }
+
+    let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
+    let source_type = folder_repo
+        .find_by_id(source_folder_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|f| f.folder_type);
+    let target_type = folder_repo
+        .find_by_id(target_folder_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|f| f.folder_type);
+
+    let is_spam = match (source_type, target_type) {
+        (_, Some(FolderType::Spam)) => true,
+        (Some(FolderType::Spam), Some(target)) if target != FolderType::Trash => false,
+        _ => return,
+    };
+
+    if let Err(e) = JunkFilter::new(state.db_pool.clone())
+        .train(email, is_spam)
+        .await
+    {
+        log::warn!("Failed to train junk filter on email {}: {}", email.id, e);
+    }
+}
+
 #[tauri::command]
 pub async fn archive(state: State<'_, AppState>, email_id: Uuid) -> Result<Email, String> {
     let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
@@ -1033,6 +2211,148 @@ pub async fn junk(state: State<'_, AppState>, email_id: Uuid) -> Result<Email, S
     Ok(updated_email)
 }
 
+/// Blocks a sender, given as an address, or a whole domain, given as
+/// `example.com` or `@example.com`. New mail from it goes to junk, and so
+/// does the mail already received unless `junk_existing` is false.
+///
+/// Returns the number of emails moved to junk.
+#[tauri::command]
+pub async fn block_sender(
+    state: State<'_, AppState>,
+    sender: String,
+    junk_existing: Option<bool>,
+) -> Result<usize, String> {
+    let blocked = BlockedSender::parse(&sender)
+        .ok_or_else(|| format!("Invalid sender or domain: {}", sender))?;
+
+    SqliteBlockedSenderRepository::new(state.db_pool.clone())
+        .add(&blocked)
+        .await
+        .map_err(|e| format!("Failed to block {}: {}", blocked.pattern, e))?;
+
+    if !junk_existing.unwrap_or(true) {
+        return Ok(0);
+    }
+
+    let emails = SqliteEmailRepository::new(state.db_pool.clone())
+        .find_received_by_sender_suffix(&blocked.pattern)
+        .await
+        .map_err(|e| format!("Failed to fetch emails from {}: {}", blocked.pattern, e))?;
+
+    let mut junked = 0;
+    for email in emails
+        .iter()
+        .filter(|email| blocked.matches(&email.from.address))
+    {
+        match junk(state.clone(), email.id).await {
+            Ok(_) => junked += 1,
+            Err(e) => log::warn!("Failed to move email {} to junk: {}", email.id, e),
+        }
+    }
+
+    Ok(junked)
+}
+
+#[tauri::command]
+pub async fn unblock_sender(state: State<'_, AppState>, pattern: String) -> Result<(), String> {
+    SqliteBlockedSenderRepository::new(state.db_pool.clone())
+        .delete(&pattern)
+        .await
+        .map_err(|e| format!("Failed to unblock {}: {}", pattern, e))
+}
+
+#[tauri::command]
+pub async fn get_blocked_senders(state: State<'_, AppState>) -> Result<Vec<BlockedSender>, String> {
+    SqliteBlockedSenderRepository::new(state.db_pool.clone())
+        .get_all()
+        .await
+        .map_err(|e| format!("Failed to fetch blocked senders: {}", e))
+}
+
+/// Corrects the category of an email. The sender's other mail is moved to the
+/// category too, and mail synced from them later keeps it.
+#[tauri::command]
+pub async fn set_category(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+    category: String,
+) -> Result<Email, String> {
+    let category =
+        EmailCategory::parse(&category).ok_or_else(|| format!("Invalid category: {}", category))?;
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+
+    let email = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    SqliteSenderCategoryRepository::new(state.db_pool.clone())
+        .record(&email.from.address, category.as_str())
+        .await
+        .map_err(|e| format!("Failed to record sender category: {}", e))?;
+
+    email_repo
+        .set_sender_category(&email.from.address, category.as_str())
+        .await
+        .map_err(|e| format!("Failed to update email categories: {}", e))?;
+
+    let email = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    emit_email_event(&state.app_handle, "email:updated", serde_json::json!(email));
+
+    Ok(email)
+}
+
+#[tauri::command]
+pub async fn get_sender_categories(
+    state: State<'_, AppState>,
+) -> Result<Vec<SenderCategory>, String> {
+    SqliteSenderCategoryRepository::new(state.db_pool.clone())
+        .get_all()
+        .await
+        .map_err(|e| format!("Failed to fetch sender categories: {}", e))
+}
+
+/// Lets the categorizer decide for the sender's future mail again
+#[tauri::command]
+pub async fn remove_sender_category(
+    state: State<'_, AppState>,
+    sender: String,
+) -> Result<(), String> {
+    SqliteSenderCategoryRepository::new(state.db_pool.clone())
+        .delete(&sender)
+        .await
+        .map_err(|e| format!("Failed to remove sender category: {}", e))
+}
+
+#[tauri::command]
+pub async fn not_junk(state: State<'_, AppState>, email_id: Uuid) -> Result<Email, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
+
+    let email = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    let account_id = email.account_id;
+
+    let inbox_folder = folder_repo
+        .find_by_type(account_id, "inbox")
+        .await
+        .map_err(|e| format!("Failed to fetch inbox folder: {}", e))?
+        .ok_or_else(|| "Inbox folder not found for this account".to_string())?;
+
+    let updated_email = move_email(state, email_id, inbox_folder.id).await?;
+    Ok(updated_email)
+}
+
 #[tauri::command]
 pub async fn trash(state: State<'_, AppState>, email_id: Uuid) -> Result<Email, String> {
     let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
@@ -1197,6 +2517,253 @@ pub async fn set_remind_at(
     Ok(())
 }
 
+/// Hide an email from its folder until `until`, when it resurfaces with a notification
+#[tauri::command]
+pub async fn snooze_email(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+    until: chrono::DateTime<chrono::Utc>,
+) -> Result<(), String> {
+    if until <= chrono::Utc::now() {
+        return Err("Snooze time must be in the future".to_string());
+    }
+
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    email_repo
+        .update_snoozed_until(email_id, Some(until))
+        .await
+        .map_err(|e| format!("Failed to snooze email: {}", e))?;
+
+    emit_email_event(
+        &state.app_handle,
+        "email:updated",
+        serde_json::json!({ "id": email_id.to_string(), "snoozed_until": until }),
+    );
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unsnooze_email(state: State<'_, AppState>, email_id: Uuid) -> Result<(), String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    email_repo
+        .update_snoozed_until(email_id, None)
+        .await
+        .map_err(|e| format!("Failed to unsnooze email: {}", e))?;
+
+    emit_email_event(
+        &state.app_handle,
+        "email:updated",
+        serde_json::json!({ "id": email_id.to_string(), "snoozed_until": null }),
+    );
+
+    Ok(())
+}
+
+/// List snoozed emails across all accounts, soonest to resurface first
+#[tauri::command]
+pub async fn get_snoozed_emails(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<EmailListItem>, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let label_repo = SqliteLabelRepository::new(state.db_pool.clone());
+
+    let emails = email_repo
+        .find_snoozed(limit.unwrap_or(50), offset.unwrap_or(0))
+        .await
+        .map_err(|e| format!("Failed to fetch snoozed emails: {}", e))?;
+
+    let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
+    let labels_map = label_repo
+        .find_by_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to fetch labels: {}", e))?;
+    let account_colors = account_color_map(&state).await?;
+
+    Ok(emails
+        .iter()
+        .map(|email| {
+            let labels = labels_map
+                .get(&email.id)
+                .map(|labels| labels.iter().map(LabelInfo::from).collect())
+                .unwrap_or_default();
+            apply_account_color_to_list_item(
+                EmailListItem::from_email(email, labels),
+                &account_colors,
+            )
+        })
+        .collect())
+}
+
+/// List deleted emails that are still kept locally, most recently deleted first
+#[tauri::command]
+pub async fn get_recently_deleted(
+    state: State<'_, AppState>,
+    account_id: Option<Uuid>,
+) -> Result<Vec<RecentlyDeletedEmail>, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let label_repo = SqliteLabelRepository::new(state.db_pool.clone());
+    let pending_repo = SqlitePendingOperationRepository::new(state.db_pool.clone());
+
+    let since = Utc::now() - chrono::Duration::days(TOMBSTONE_RETENTION_DAYS);
+    let emails = email_repo
+        .find_recently_deleted(account_id, since)
+        .await
+        .map_err(|e| format!("Failed to fetch deleted emails: {}", e))?;
+
+    let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
+    let labels_map = label_repo
+        .find_by_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to fetch labels: {}", e))?;
+    let account_colors = account_color_map(&state).await?;
+
+    let mut deleted = Vec::with_capacity(emails.len());
+    for email in &emails {
+        let pending_ops = pending_repo
+            .find_pending_for_email(email.id)
+            .await
+            .map_err(|e| format!("Failed to fetch pending operations: {}", e))?;
+        let restorable = pending_ops.iter().any(|op| {
+            op.status == "pending"
+                && matches!(
+                    op.parsed_operation_type(),
+                    Some(PendingOperationType::Delete | PendingOperationType::PermanentDelete)
+                )
+        });
+
+        let labels = labels_map
+            .get(&email.id)
+            .map(|labels| labels.iter().map(LabelInfo::from).collect())
+            .unwrap_or_default();
+        deleted.push(RecentlyDeletedEmail {
+            email: apply_account_color_to_list_item(
+                EmailListItem::from_email(email, labels),
+                &account_colors,
+            ),
+            deleted_at: email.deleted_at.unwrap_or(email.updated_at),
+            restorable,
+        });
+    }
+
+    Ok(deleted)
+}
+
+/// Undo the deletion of an email that has not been deleted on the server yet
+#[tauri::command]
+pub async fn restore_deleted_email(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+) -> Result<(), String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+
+    let email = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    state
+        .sync_coordinator
+        .restore_email(email.account_id, email_id)
+        .await
+        .map_err(|e| format!("Failed to restore email: {}", e))?;
+
+    emit_email_event(
+        &state.app_handle,
+        "email:restored",
+        serde_json::json!({
+            "id": email_id.to_string()
+        }),
+    );
+
+    Ok(())
+}
+
+/// Remove deleted emails from this device now instead of after the retention period.
+/// Deletions still waiting to be sent to the provider are sent right away.
+#[tauri::command]
+pub async fn purge_deleted_emails(
+    state: State<'_, AppState>,
+    email_ids: Vec<Uuid>,
+) -> Result<u64, String> {
+    let pending_repo = SqlitePendingOperationRepository::new(state.db_pool.clone());
+
+    for email_id in &email_ids {
+        pending_repo
+            .release_for_email(*email_id)
+            .await
+            .map_err(|e| format!("Failed to release pending deletion: {}", e))?;
+    }
+
+    state
+        .background_cleanup
+        .purge_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to purge deleted emails: {}", e))
+}
+
+/// Queue a draft to be sent by the scheduled send worker at `send_at`
+#[tauri::command]
+pub async fn schedule_send(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+    send_at: chrono::DateTime<chrono::Utc>,
+) -> Result<(), String> {
+    if send_at <= chrono::Utc::now() {
+        return Err("Scheduled send time must be in the future".to_string());
+    }
+
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let draft = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to find draft: {}", e))?
+        .ok_or_else(|| format!("Draft {} not found", email_id))?;
+
+    if !draft.is_draft {
+        return Err("Only drafts can be scheduled for sending".to_string());
+    }
+    if draft.to.is_empty() && draft.cc.is_empty() && draft.bcc.is_empty() {
+        return Err("Draft has no recipients".to_string());
+    }
+
+    email_repo
+        .update_scheduled_send_at(email_id, Some(send_at))
+        .await
+        .map_err(|e| format!("Failed to schedule send: {}", e))?;
+
+    emit_email_event(
+        &state.app_handle,
+        "email:updated",
+        serde_json::json!({ "id": email_id.to_string(), "scheduled_send_at": send_at }),
+    );
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_scheduled_send(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+) -> Result<(), String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    email_repo
+        .update_scheduled_send_at(email_id, None)
+        .await
+        .map_err(|e| format!("Failed to cancel scheduled send: {}", e))?;
+
+    emit_email_event(
+        &state.app_handle,
+        "email:updated",
+        serde_json::json!({ "id": email_id.to_string(), "scheduled_send_at": null }),
+    );
+
+    Ok(())
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct CalendarEmailsResponse {
     pub primary: Vec<EmailListItem>,
@@ -1241,15 +2808,19 @@ pub async fn get_emails_for_calendar(
         .await
         .map_err(|e| format!("Failed to fetch labels: {}", e))?;
     let notified_at_by_email = reminder_notification_map(&state, &all_email_ids).await?;
+    let account_colors = account_color_map(&state).await?;
 
     let map_email = |email: &crate::database::models::email::Email| {
         let labels = labels_map
             .get(&email.id)
             .map(|labels| labels.iter().map(LabelInfo::from).collect())
             .unwrap_or_default();
-        apply_notified_at_to_list_item(
-            EmailListItem::from_email(email, labels),
-            &notified_at_by_email,
+        apply_account_color_to_list_item(
+            apply_notified_at_to_list_item(
+                EmailListItem::from_email(email, labels),
+                &notified_at_by_email,
+            ),
+            &account_colors,
         )
     };
 
@@ -1286,3 +2857,14 @@ pub async fn update_blocking(
 
     Ok(())
 }
+
+#[tauri::command]
+pub async fn validate_recipients(
+    addresses: Vec<String>,
+    check_mx: Option<bool>,
+) -> Result<Vec<RecipientValidation>, String> {
+    let validator = RecipientValidator::new();
+    Ok(validator
+        .validate(&addresses, check_mx.unwrap_or(false))
+        .await)
+}
@@ -0,0 +1,251 @@
+//! Export of folders, labels, search results and conversations to mbox/EML,
+//! and of single emails to PDF through the system print dialog.
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::webview::PageLoadEvent;
+use tauri::{State, WebviewWindowBuilder};
+use uuid::Uuid;
+
+use crate::commands::search::search_email_ids;
+use crate::database::repositories::{
+    AttachmentRepository, EmailRepository, RepositoryFactory, SqliteAttachmentRepository,
+    SqliteEmailRepository,
+};
+use crate::search::export::{ExportFormat, ExportProgress, ExportSummary, ExportWriter};
+use crate::services::app_lock;
+use crate::services::conversation_export::{self, TranscriptMessage};
+use crate::state::AppState;
+
+/// Emit a progress event every N exported emails
+const EXPORT_PROGRESS_INTERVAL: usize = 50;
+
+/// What to export
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportSource {
+    Folder {
+        folder_id: Uuid,
+    },
+    Label {
+        label_id: Uuid,
+    },
+    Search {
+        query: String,
+        account_id: Option<Uuid>,
+        folder_id: Option<Uuid>,
+    },
+    Conversation {
+        conversation_id: Uuid,
+    },
+}
+
+/// Export a folder, label, search result or conversation as mbox or EML
+///
+/// Emits `export:progress` events while writing.
+#[tauri::command]
+pub async fn export_emails(
+    state: State<'_, AppState>,
+    source: ExportSource,
+    format: ExportFormat,
+    destination: String,
+) -> Result<ExportSummary, String> {
+    if format == ExportFormat::Csv {
+        return Err("Mailbox exports support mbox and EML only".to_string());
+    }
+
+    let email_ids = match &source {
+        ExportSource::Folder { folder_id } => parse_ids(
+            sqlx::query_scalar::<_, String>(
+                "SELECT id FROM emails WHERE folder_id = ? AND is_deleted = 0 ORDER BY received_at ASC",
+            )
+            .bind(folder_id.to_string())
+            .fetch_all(&state.db_pool)
+            .await
+            .map_err(|e| format!("Failed to fetch folder emails: {}", e))?,
+        )?,
+        ExportSource::Label { label_id } => parse_ids(
+            sqlx::query_scalar::<_, String>(
+                "SELECT e.id FROM emails e \
+                 JOIN email_labels el ON el.email_id = e.id \
+                 WHERE el.label_id = ? AND e.is_deleted = 0 \
+                 ORDER BY e.received_at ASC",
+            )
+            .bind(label_id.to_string())
+            .fetch_all(&state.db_pool)
+            .await
+            .map_err(|e| format!("Failed to fetch label emails: {}", e))?,
+        )?,
+        ExportSource::Search {
+            query,
+            account_id,
+            folder_id,
+        } => search_email_ids(&state, query, *account_id, *folder_id).await?,
+        ExportSource::Conversation { conversation_id } => {
+            let mut emails = SqliteEmailRepository::new(state.db_pool.clone())
+                .find_by_conversation_id(*conversation_id)
+                .await
+                .map_err(|e| format!("Failed to fetch conversation emails: {}", e))?;
+            emails.sort_by_key(|email| email.received_at);
+            emails.into_iter().map(|email| email.id).collect()
+        }
+    };
+
+    log::info!("[Export] Exporting {:?} as {:?}", source, format);
+
+    write_export(
+        &state,
+        "export:progress",
+        email_ids,
+        format,
+        PathBuf::from(destination),
+        false,
+    )
+    .await
+}
+
+fn parse_ids(ids: Vec<String>) -> Result<Vec<Uuid>, String> {
+    ids.iter()
+        .map(|id| Uuid::parse_str(id).map_err(|e| format!("Invalid email id {}: {}", id, e)))
+        .collect()
+}
+
+/// Writes the given emails in order, emitting progress under `event`
+pub(crate) async fn write_export(
+    state: &AppState,
+    event: &str,
+    email_ids: Vec<Uuid>,
+    format: ExportFormat,
+    destination: PathBuf,
+    metadata_only: bool,
+) -> Result<ExportSummary, String> {
+    let export_id = Uuid::now_v7().to_string();
+    let total = email_ids.len();
+    log::info!(
+        "[Export] Writing {} emails as {:?} to {}",
+        total,
+        format,
+        destination.display()
+    );
+
+    let emit_progress = |processed: usize, done: bool| {
+        if let Err(e) = app_lock::emit_mail_event(
+            &state.app_handle,
+            event,
+            ExportProgress {
+                export_id: export_id.clone(),
+                processed,
+                total,
+                done,
+            },
+        ) {
+            log::error!("Failed to emit export progress: {}", e);
+        }
+    };
+
+    let mut writer = ExportWriter::create(format, metadata_only, &destination)
+        .map_err(|e| format!("Failed to create export file: {}", e))?;
+
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+    let email_repo = repo_factory.email_repository();
+
+    let mut exported = 0;
+    let mut skipped = 0;
+
+    emit_progress(0, false);
+
+    for (index, email_id) in email_ids.iter().enumerate() {
+        match email_repo.find_by_id(*email_id).await {
+            Ok(Some(email)) => {
+                writer
+                    .write(&email)
+                    .map_err(|e| format!("Failed to write email {}: {}", email_id, e))?;
+                exported += 1;
+            }
+            Ok(None) => skipped += 1,
+            Err(e) => {
+                log::warn!("[Export] Skipping email {} in export: {}", email_id, e);
+                skipped += 1;
+            }
+        }
+
+        if (index + 1) % EXPORT_PROGRESS_INTERVAL == 0 {
+            emit_progress(index + 1, false);
+        }
+    }
+
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to finish export: {}", e))?;
+
+    emit_progress(total, true);
+
+    Ok(ExportSummary {
+        export_id,
+        exported,
+        skipped,
+        path: destination,
+    })
+}
+
+/// Open a single email rendered as HTML in a print window
+///
+/// There is no PDF renderer in the backend, so the page is printed by the
+/// webview and saved through the "Save as PDF" option of the print dialog.
+#[tauri::command]
+pub async fn export_pdf(state: State<'_, AppState>, email_id: Uuid) -> Result<(), String> {
+    let email = SqliteEmailRepository::new(state.db_pool.clone())
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    let attachments = SqliteAttachmentRepository::new(state.db_pool.clone())
+        .find_by_email(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+
+    let inline_images = conversation_export::load_inline_images(
+        &state.app_data_dir.join("attachments"),
+        email.id,
+        &attachments,
+    );
+
+    let subject = email.subject.as_deref().unwrap_or("(No subject)");
+    let html = conversation_export::render_transcript(
+        subject,
+        &[TranscriptMessage {
+            email: &email,
+            attachments: &attachments,
+            inline_images,
+        }],
+    );
+
+    let print_dir = state.app_data_dir.join("exports");
+    std::fs::create_dir_all(&print_dir)
+        .map_err(|e| format!("Failed to create export directory: {}", e))?;
+    let print_path = print_dir.join(format!("{}.html", email_id));
+    std::fs::write(&print_path, html).map_err(|e| format!("Failed to write print file: {}", e))?;
+
+    let url = url::Url::from_file_path(&print_path)
+        .map_err(|_| format!("Invalid print path: {}", print_path.display()))?;
+
+    WebviewWindowBuilder::new(
+        &state.app_handle,
+        format!("print-{}", email_id.simple()),
+        tauri::WebviewUrl::External(url),
+    )
+    .title(subject)
+    .inner_size(800.0, 900.0)
+    .center()
+    .on_page_load(|window, payload| {
+        if payload.event() == PageLoadEvent::Finished {
+            if let Err(e) = window.print() {
+                log::error!("Failed to open print dialog: {}", e);
+            }
+        }
+    })
+    .build()
+    .map_err(|e| format!("Failed to open print window: {}", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use tauri::{Emitter, State};
+
+use crate::services::feature_flags::{FeatureFlagState, FeatureFlags};
+use crate::state::AppState;
+
+/// Resolved state of every feature flag
+#[tauri::command]
+pub async fn get_feature_flags(
+    state: State<'_, AppState>,
+) -> Result<Vec<FeatureFlagState>, String> {
+    Ok(state.feature_flags.all().await)
+}
+
+/// Switches a feature on or off for this user
+#[tauri::command]
+pub async fn set_feature_flag(
+    state: State<'_, AppState>,
+    key: String,
+    enabled: bool,
+) -> Result<Vec<FeatureFlagState>, String> {
+    let feature =
+        FeatureFlags::parse_key(&key).ok_or_else(|| format!("Unknown feature flag: {}", key))?;
+
+    state
+        .feature_flags
+        .set_enabled(feature, enabled)
+        .map_err(|e| format!("Failed to update feature flag: {}", e))?;
+
+    let flags = state.feature_flags.all().await;
+    if let Err(e) = state.app_handle.emit("feature-flags-changed", &flags) {
+        log::error!("Failed to emit feature-flags-changed event: {}", e);
+    }
+
+    Ok(flags)
+}
@@ -1,10 +1,18 @@
+use crate::commands::saved_searches::saved_search_folders;
 use crate::commands::sync::MoveFolderRequest;
-use crate::database::models::folder::{Folder, FolderSettings, FolderType};
-use crate::database::repositories::{FolderRepository, SqliteFolderRepository};
+use crate::database::models::folder::{
+    AutoArchiveRule, Folder, FolderAction, FolderSettings, FolderType,
+};
+use crate::database::repositories::{
+    AccountRepository, FolderRepository, SqliteAccountRepository, SqliteFolderRepository,
+};
+use crate::services::app_lock;
 use crate::state::AppState;
+use crate::sync::background_archive_worker::AutoArchivePreview;
+use crate::sync::background_sync::MIN_SYNC_INTERVAL;
 use crate::sync::SyncFolder;
 use serde::{Deserialize, Serialize};
-use tauri::{Emitter, State};
+use tauri::State;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,10 +27,16 @@ pub struct FolderResponse {
     pub sort_order: i32,
     pub expanded: bool,
     pub hidden: bool,
+    pub sync_enabled: bool,
     pub parent_id: Option<Uuid>,
     pub settings: FolderSettings,
     pub unread_count: i64,
     pub total_count: i64,
+    /// Query of a saved search shown as a virtual folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Color of the folder's account, to tell accounts apart
+    pub account_color: Option<String>,
 }
 
 impl From<Folder> for FolderResponse {
@@ -38,10 +52,13 @@ impl From<Folder> for FolderResponse {
             sort_order: folder.sort_order,
             expanded: folder.expanded,
             hidden: folder.hidden,
+            sync_enabled: folder.sync_enabled,
             parent_id: folder.parent_id,
             settings: folder.settings,
             unread_count: folder.unread_count,
             total_count: folder.total_count,
+            query: None,
+            account_color: None,
         }
     }
 }
@@ -59,12 +76,29 @@ pub struct UpdateSettingsRequest {
     pub settings: FolderSettings,
 }
 
+/// Mark folders with the color of their account
+async fn apply_account_colors(
+    state: &State<'_, AppState>,
+    folders: &mut [FolderResponse],
+) -> Result<(), String> {
+    let colors = SqliteAccountRepository::new(state.db_pool.clone())
+        .find_colors()
+        .await
+        .map_err(|e| format!("Failed to fetch account colors: {}", e))?;
+
+    for folder in folders {
+        folder.account_color = colors.get(&folder.account_id).cloned();
+    }
+
+    Ok(())
+}
+
 fn emit_folder_event<S: serde::Serialize + Clone>(
     app_handle: &tauri::AppHandle,
     event_name: &str,
     payload: S,
 ) {
-    if let Err(e) = app_handle.emit(event_name, payload) {
+    if let Err(e) = app_lock::emit_mail_event(app_handle, event_name, payload) {
         log::error!("Failed to emit folder event '{}': {}", event_name, e);
     }
 }
@@ -83,7 +117,10 @@ pub async fn get_folders(
         .await
         .map_err(|e| format!("Failed to fetch folders: {}", e))?;
 
-    let responses = folders.into_iter().map(FolderResponse::from).collect();
+    let mut responses: Vec<FolderResponse> =
+        folders.into_iter().map(FolderResponse::from).collect();
+    responses.extend(saved_search_folders(&state, &[account_id]).await?);
+    apply_account_colors(&state, &mut responses).await?;
 
     Ok(responses)
 }
@@ -101,7 +138,13 @@ pub async fn get_folder_navigation(
         .await
         .map_err(|e| format!("Failed to fetch navigation folders: {}", e))?;
 
-    let responses = folders.into_iter().map(FolderResponse::from).collect();
+    let mut account_ids: Vec<Uuid> = folders.iter().map(|f| f.account_id).collect();
+    account_ids.dedup();
+
+    let mut responses: Vec<FolderResponse> =
+        folders.into_iter().map(FolderResponse::from).collect();
+    responses.extend(saved_search_folders(&state, &account_ids).await?);
+    apply_account_colors(&state, &mut responses).await?;
 
     Ok(responses)
 }
@@ -121,7 +164,10 @@ pub async fn get_folder(
         .map_err(|e| format!("Failed to fetch folder: {}", e))?
         .ok_or_else(|| format!("Folder {} not found", folder_id))?;
 
-    Ok(FolderResponse::from(folder))
+    let mut response = FolderResponse::from(folder);
+    apply_account_colors(&state, std::slice::from_mut(&mut response)).await?;
+
+    Ok(response)
 }
 
 #[tauri::command]
@@ -284,6 +330,40 @@ pub async fn update_hidden(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn set_sync_enabled(
+    state: State<'_, AppState>,
+    folder_id: Uuid,
+    enabled: bool,
+) -> Result<(), String> {
+    log::info!(
+        "Setting sync enabled to {} for folder {}",
+        enabled,
+        folder_id
+    );
+
+    let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
+
+    folder_repo
+        .set_sync_enabled(folder_id, enabled)
+        .await
+        .map_err(|e| format!("Failed to update folder: {}", e))?;
+
+    let folder = folder_repo
+        .find_by_id(folder_id)
+        .await
+        .map_err(|e| format!("Failed to fetch folder: {}", e))?
+        .ok_or_else(|| format!("Folder {} not found", folder_id))?;
+
+    emit_folder_event(
+        &state.app_handle,
+        "folder:updated",
+        serde_json::json!(folder),
+    );
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn rename(
     state: State<'_, AppState>,
@@ -326,6 +406,10 @@ pub async fn update_settings(
 ) -> Result<(), String> {
     log::info!("Updating settings for folder {}", folder_id);
 
+    if settings.primary_action == FolderAction::None {
+        return Err("The primary action cannot be empty".to_string());
+    }
+
     let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
 
     let mut folder = folder_repo
@@ -334,6 +418,20 @@ pub async fn update_settings(
         .map_err(|e| format!("Failed to fetch folder: {}", e))?
         .ok_or_else(|| format!("Folder {} not found", folder_id))?;
 
+    if let Some(rule) = &settings.auto_archive {
+        validate_auto_archive_rule(&folder_repo, &folder, rule).await?;
+    }
+
+    if settings
+        .sync_interval
+        .is_some_and(|interval| i64::from(interval) < MIN_SYNC_INTERVAL)
+    {
+        return Err(format!(
+            "Folders cannot be synced more often than every {} seconds",
+            MIN_SYNC_INTERVAL
+        ));
+    }
+
     folder.settings = settings.clone();
 
     folder_repo
@@ -349,3 +447,57 @@ pub async fn update_settings(
 
     Ok(())
 }
+
+async fn validate_auto_archive_rule(
+    folder_repo: &SqliteFolderRepository,
+    folder: &Folder,
+    rule: &AutoArchiveRule,
+) -> Result<(), String> {
+    if rule.older_than_days == 0 {
+        return Err("Auto-archive needs a minimum age of at least one day".to_string());
+    }
+
+    if let Some(target_folder_id) = rule.target_folder_id {
+        if target_folder_id == folder.id {
+            return Err("Messages cannot be archived into the same folder".to_string());
+        }
+
+        let target = folder_repo
+            .find_by_id(target_folder_id)
+            .await
+            .map_err(|e| format!("Failed to fetch folder: {}", e))?
+            .ok_or_else(|| format!("Folder {} not found", target_folder_id))?;
+        if target.account_id != folder.account_id {
+            return Err("Messages can only be archived within the same account".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Dry run of an auto-archive rule. Uses the given rule, so unsaved changes can
+/// be previewed, or the folder's saved one.
+#[tauri::command]
+pub async fn preview_auto_archive(
+    state: State<'_, AppState>,
+    folder_id: Uuid,
+    rule: Option<AutoArchiveRule>,
+) -> Result<AutoArchivePreview, String> {
+    let folder_repo = SqliteFolderRepository::new(state.db_pool.clone());
+
+    let folder = folder_repo
+        .find_by_id(folder_id)
+        .await
+        .map_err(|e| format!("Failed to fetch folder: {}", e))?
+        .ok_or_else(|| format!("Folder {} not found", folder_id))?;
+
+    let rule = rule
+        .or_else(|| folder.settings.auto_archive.clone())
+        .ok_or_else(|| format!("Folder {} has no auto-archive rule", folder_id))?;
+    validate_auto_archive_rule(&folder_repo, &folder, &rule).await?;
+
+    state
+        .background_archive_worker
+        .preview(&folder, &rule)
+        .await
+}
@@ -0,0 +1,155 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        models::identity::{Identity, IdentitySource},
+        repositories::{IdentityRepository, RepositoryFactory},
+    },
+    state::AppState,
+    sync::identities,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateIdentityRequest {
+    pub account_id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateIdentityRequest {
+    pub id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+}
+
+fn normalize_email(email: &str) -> Result<String, String> {
+    let email = email.trim().to_lowercase();
+    if email.parse::<lettre::Address>().is_err() {
+        return Err(format!("'{}' is not a valid email address", email));
+    }
+
+    Ok(email)
+}
+
+fn normalize_name(name: Option<String>) -> Option<String> {
+    name.map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+#[tauri::command]
+pub async fn get_identities(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<Vec<Identity>, String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .identity_repository()
+        .find_by_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to get identities: {}", e))
+}
+
+/// Adds an alias by hand. The provider decides whether it accepts mail sent
+/// as that address.
+#[tauri::command]
+pub async fn create_identity(
+    state: State<'_, AppState>,
+    request: CreateIdentityRequest,
+) -> Result<Identity, String> {
+    let identity = Identity {
+        id: Uuid::now_v7(),
+        account_id: request.account_id,
+        email: normalize_email(&request.email)?,
+        name: normalize_name(request.name),
+        source: IdentitySource::Manual,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    RepositoryFactory::new(state.db_pool.clone())
+        .identity_repository()
+        .create(&identity)
+        .await
+        .map_err(|e| format!("Failed to create identity: {}", e))?;
+
+    Ok(identity)
+}
+
+#[tauri::command]
+pub async fn update_identity(
+    state: State<'_, AppState>,
+    request: UpdateIdentityRequest,
+) -> Result<Identity, String> {
+    let identity_repo = RepositoryFactory::new(state.db_pool.clone()).identity_repository();
+    let existing = identity_repo
+        .find_by_id(request.id)
+        .await
+        .map_err(|e| format!("Failed to find identity: {}", e))?
+        .ok_or_else(|| format!("Identity {} not found", request.id))?;
+
+    let email = normalize_email(&request.email)?;
+    if existing.source != IdentitySource::Manual && email != existing.email {
+        return Err("The address of a synced alias is managed by the provider".to_string());
+    }
+
+    let identity = Identity {
+        email,
+        name: normalize_name(request.name),
+        updated_at: Utc::now(),
+        ..existing
+    };
+
+    identity_repo
+        .update(&identity)
+        .await
+        .map_err(|e| format!("Failed to update identity: {}", e))?;
+
+    Ok(identity)
+}
+
+#[tauri::command]
+pub async fn delete_identity(state: State<'_, AppState>, identity_id: Uuid) -> Result<(), String> {
+    let identity_repo = RepositoryFactory::new(state.db_pool.clone()).identity_repository();
+    let identity = identity_repo
+        .find_by_id(identity_id)
+        .await
+        .map_err(|e| format!("Failed to find identity: {}", e))?
+        .ok_or_else(|| format!("Identity {} not found", identity_id))?;
+
+    if identity.source != IdentitySource::Manual {
+        return Err("Synced aliases are removed in the provider's settings".to_string());
+    }
+
+    identity_repo
+        .delete(identity_id)
+        .await
+        .map_err(|e| format!("Failed to delete identity: {}", e))?;
+
+    if let Err(e) = state
+        .credential_store
+        .delete_dkim(identity.account_id, &identity.email)
+        .await
+    {
+        log::warn!("Failed to delete DKIM key of {}: {}", identity.email, e);
+    }
+
+    Ok(())
+}
+
+/// Re-fetch the provider's send-as aliases (Gmail), returning all identities
+/// of the account
+#[tauri::command]
+pub async fn refresh_identities(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<Vec<Identity>, String> {
+    match identities::refresh_identities(&state.db_pool, state.credential_store.clone(), account_id)
+        .await?
+    {
+        Some(identities) => Ok(identities),
+        None => get_identities(state, account_id).await,
+    }
+}
@@ -0,0 +1,315 @@
+//! Import of messages from mbox files and single EML files, for migrating
+//! from Thunderbird, Apple Mail and other clients.
+//!
+//! Imported messages only exist locally: they are stored without a remote id,
+//! so folder syncs neither match nor delete them.
+use mail_parser::mailbox::mbox::MessageIterator;
+use serde::Serialize;
+use std::io::BufReader;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::database::models::folder::Folder;
+use crate::database::repositories::{
+    EmailRepository, FolderRepository, SqliteEmailRepository, SqliteFolderRepository,
+};
+use crate::services::app_lock;
+use crate::state::AppState;
+use crate::sync::providers::imap::ImapProvider;
+use crate::sync::EmailSync;
+
+/// Emit a progress event every N processed messages
+const IMPORT_PROGRESS_INTERVAL: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub import_id: String,
+    pub processed: usize,
+    /// Unknown for mbox files, which are read as a stream
+    pub total: Option<usize>,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub import_id: String,
+    pub imported: usize,
+    /// Messages already in the account, or deleted in the source mailbox
+    pub skipped: usize,
+    pub failed: usize,
+    pub errors: Vec<String>,
+}
+
+struct Importer<'a> {
+    state: &'a AppState,
+    email_sync: EmailSync,
+    folder: Folder,
+    summary: ImportSummary,
+    total: Option<usize>,
+}
+
+impl<'a> Importer<'a> {
+    async fn new(
+        state: &'a AppState,
+        folder_id: Uuid,
+        total: Option<usize>,
+    ) -> Result<Importer<'a>, String> {
+        let folder = SqliteFolderRepository::new(state.db_pool.clone())
+            .find_by_id(folder_id)
+            .await
+            .map_err(|e| format!("Failed to find folder: {}", e))?
+            .ok_or_else(|| format!("Folder {} not found", folder_id))?;
+
+        let email_sync = EmailSync::new(
+            state.db_pool.clone(),
+            state.app_data_dir.to_string_lossy().to_string(),
+            state.credential_store.clone(),
+        )
+        .with_search_manager(state.search_manager.clone())
+        .with_app_handle(state.app_handle.clone());
+
+        Ok(Self {
+            state,
+            email_sync,
+            folder,
+            summary: ImportSummary {
+                import_id: Uuid::now_v7().to_string(),
+                imported: 0,
+                skipped: 0,
+                failed: 0,
+                errors: Vec::new(),
+            },
+            total,
+        })
+    }
+
+    async fn import(&mut self, source: &str, raw: &[u8]) {
+        match self.import_message(raw).await {
+            Ok(true) => self.summary.imported += 1,
+            Ok(false) => self.summary.skipped += 1,
+            Err(e) => {
+                log::warn!("[Import] Failed to import message from {}: {}", source, e);
+                self.summary.failed += 1;
+                self.summary.errors.push(format!("{}: {}", source, e));
+            }
+        }
+
+        let processed = self.processed();
+        if processed % IMPORT_PROGRESS_INTERVAL == 0 {
+            self.emit_progress(false);
+        }
+    }
+
+    /// Returns `Ok(false)` for messages that are skipped
+    async fn import_message(&self, raw: &[u8]) -> Result<bool, String> {
+        let flags = mailbox_flags(raw);
+        if flags.iter().any(|flag| flag == "\\Deleted") {
+            return Ok(false);
+        }
+
+        // Hash based, so importing the same file twice does not duplicate messages
+        let fallback_message_id = format!("{:x}@import.local", md5::compute(raw));
+        let email = ImapProvider::parse_raw_email(
+            raw,
+            self.folder.id,
+            self.folder.account_id,
+            "",
+            fallback_message_id,
+            flags,
+        )
+        .map_err(|e| format!("Failed to parse message: {}", e))?;
+
+        // Never let an import take over a message synced from the server
+        let existing = SqliteEmailRepository::new(self.state.db_pool.clone())
+            .find_by_remote_id_or_message_id(self.folder.account_id, "", &email.message_id)
+            .await
+            .map_err(|e| format!("Failed to check for duplicates: {}", e))?;
+        if existing.is_some() {
+            return Ok(false);
+        }
+
+        self.email_sync
+            .upsert_email(&email, self.folder.account_id, "synced")
+            .await
+            .map_err(|e| format!("Failed to store message: {}", e))?;
+
+        Ok(true)
+    }
+
+    fn processed(&self) -> usize {
+        self.summary.imported + self.summary.skipped + self.summary.failed
+    }
+
+    fn emit_progress(&self, done: bool) {
+        if let Err(e) = app_lock::emit_mail_event(
+            &self.state.app_handle,
+            "import:progress",
+            ImportProgress {
+                import_id: self.summary.import_id.clone(),
+                processed: self.processed(),
+                total: self.total,
+                done,
+            },
+        ) {
+            log::error!("Failed to emit import progress: {}", e);
+        }
+    }
+
+    async fn finish(self) -> ImportSummary {
+        if let Err(e) = self.state.search_manager.commit().await {
+            log::warn!("[Import] Failed to commit search index: {}", e);
+        }
+
+        self.emit_progress(true);
+
+        log::info!(
+            "[Import] Imported {} messages into folder {} ({} skipped, {} failed)",
+            self.summary.imported,
+            self.folder.id,
+            self.summary.skipped,
+            self.summary.failed
+        );
+
+        self.summary
+    }
+}
+
+/// Flags kept by mail clients in mbox headers: `Status`/`X-Status` (mutt, Apple
+/// Mail) and `X-Mozilla-Status` (Thunderbird). Messages without any of them
+/// are treated as read, as archived mail usually is.
+fn mailbox_flags(raw: &[u8]) -> Vec<String> {
+    let mut flags = Vec::new();
+    let mut has_status = false;
+
+    let head = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .or_else(|| raw.windows(2).position(|w| w == b"\n\n"))
+        .map_or(raw, |end| &raw[..end]);
+
+    for line in String::from_utf8_lossy(head).lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match name.trim().to_ascii_lowercase().as_str() {
+            "status" => {
+                has_status = true;
+                if value.contains('R') {
+                    flags.push("\\Seen");
+                }
+            }
+            "x-status" => {
+                has_status = true;
+                if value.contains('F') {
+                    flags.push("\\Flagged");
+                }
+                if value.contains('A') {
+                    flags.push("\\Answered");
+                }
+                if value.contains('D') {
+                    flags.push("\\Deleted");
+                }
+            }
+            "x-mozilla-status" => {
+                let Ok(bits) = u32::from_str_radix(value, 16) else {
+                    continue;
+                };
+                has_status = true;
+                for (bit, flag) in [
+                    (0x0001, "\\Seen"),
+                    (0x0002, "\\Answered"),
+                    (0x0004, "\\Flagged"),
+                    (0x0008, "\\Deleted"),
+                ] {
+                    if bits & bit != 0 {
+                        flags.push(flag);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !has_status {
+        flags.push("\\Seen");
+    }
+
+    flags.sort_unstable();
+    flags.dedup();
+    flags.into_iter().map(String::from).collect()
+}
+
+/// Import every message of an mbox file into a folder
+///
+/// Emits `import:progress` events while reading.
+#[tauri::command]
+pub async fn import_mbox(
+    state: State<'_, AppState>,
+    path: String,
+    folder_id: Uuid,
+) -> Result<ImportSummary, String> {
+    let file = std::fs::File::open(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut importer = Importer::new(&state, folder_id, None).await?;
+
+    importer.emit_progress(false);
+
+    for (index, message) in MessageIterator::new(BufReader::new(file)).enumerate() {
+        let message = message.map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        importer
+            .import(&format!("{} #{}", path, index + 1), message.contents())
+            .await;
+    }
+
+    Ok(importer.finish().await)
+}
+
+/// Import single-message `.eml` files into a folder
+///
+/// Emits `import:progress` events while reading.
+#[tauri::command]
+pub async fn import_eml(
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    folder_id: Uuid,
+) -> Result<ImportSummary, String> {
+    let mut importer = Importer::new(&state, folder_id, Some(paths.len())).await?;
+
+    importer.emit_progress(false);
+
+    for path in &paths {
+        match std::fs::read(path) {
+            Ok(raw) => importer.import(path, &raw).await,
+            Err(e) => {
+                importer.summary.failed += 1;
+                importer
+                    .summary
+                    .errors
+                    .push(format!("Failed to read {}: {}", path, e));
+            }
+        }
+    }
+
+    Ok(importer.finish().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mailbox_flags() {
+        let thunderbird = b"X-Mozilla-Status: 0005\r\nSubject: Hi\r\n\r\nStatus: body\r\n";
+        assert_eq!(mailbox_flags(thunderbird), vec!["\\Flagged", "\\Seen"]);
+
+        let expunged = b"X-Mozilla-Status: 0009\nSubject: Hi\n\nBody\n";
+        assert_eq!(mailbox_flags(expunged), vec!["\\Deleted", "\\Seen"]);
+
+        let unread = b"Status: O\nX-Status: A\nSubject: Hi\n\nBody\n";
+        assert_eq!(mailbox_flags(unread), vec!["\\Answered"]);
+
+        let plain = b"Subject: Hi\n\nBody\n";
+        assert_eq!(mailbox_flags(plain), vec!["\\Seen"]);
+    }
+}
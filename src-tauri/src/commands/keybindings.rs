@@ -1,6 +1,23 @@
+use crate::config::keybindings::{
+    KeyBindingsOverview, KeyConflict, KeyScheme, DEFAULT_MAPPING_SETTING,
+};
 use crate::state::AppState;
+use serde::Serialize;
 use serde_json::Value as JsonValue;
-use tauri::State;
+use tauri::{Emitter, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyBindingChange {
+    /// False when the binding conflicts with others and was not forced
+    pub saved: bool,
+    pub conflicts: Vec<KeyConflict>,
+}
+
+fn notify_keybindings_changed(state: &AppState) {
+    if let Err(err) = state.app_handle.emit("keybindings-changed", ()) {
+        log::error!("Failed to emit keybindings-changed event: {}", err);
+    }
+}
 
 /// Get all keybindings (merged defaults + user overrides)
 #[tauri::command]
@@ -52,3 +69,79 @@ pub async fn remove_keybinding(
 pub async fn reload_keybindings(state: State<'_, AppState>) -> Result<(), String> {
     state.keybindings.reload().map_err(|e| e.to_string())
 }
+
+/// Get every binding of the current scheme with user changes applied, with
+/// the bindings each one conflicts with
+#[tauri::command]
+pub async fn get_bindings(state: State<'_, AppState>) -> Result<KeyBindingsOverview, String> {
+    state.keybindings.overview().map_err(|e| e.to_string())
+}
+
+/// Bind a key, or unbind it with no action. A binding that conflicts with
+/// others is only saved with `force`.
+#[tauri::command]
+pub async fn set_binding(
+    state: State<'_, AppState>,
+    context: String,
+    key: String,
+    action: Option<String>,
+    props: Option<JsonValue>,
+    force: Option<bool>,
+) -> Result<KeyBindingChange, String> {
+    let conflicts = state
+        .keybindings
+        .conflicts(&context, &key, action.as_deref())
+        .map_err(|e| e.to_string())?;
+    if !conflicts.is_empty() && !force.unwrap_or(false) {
+        return Ok(KeyBindingChange {
+            saved: false,
+            conflicts,
+        });
+    }
+
+    state
+        .keybindings
+        .set(&context, &key, action, props)
+        .map_err(|e| e.to_string())?;
+
+    Ok(KeyBindingChange {
+        saved: true,
+        conflicts,
+    })
+}
+
+/// Drop the user's bindings of a context, or all of them, restoring the
+/// scheme's bindings
+#[tauri::command]
+pub async fn reset_to_default(
+    state: State<'_, AppState>,
+    context: Option<String>,
+) -> Result<KeyBindingsOverview, String> {
+    state
+        .keybindings
+        .reset_to_default(context.as_deref())
+        .map_err(|e| e.to_string())?;
+    state.keybindings.overview().map_err(|e| e.to_string())
+}
+
+/// Switch to a bundled keymap modelled on another mail client, keeping the
+/// user's own bindings on top of it
+#[tauri::command]
+pub async fn set_keymap_scheme(
+    state: State<'_, AppState>,
+    scheme: KeyScheme,
+) -> Result<KeyBindingsOverview, String> {
+    state
+        .keybindings
+        .set_scheme(scheme)
+        .map_err(|e| e.to_string())?;
+    state
+        .settings
+        .set(DEFAULT_MAPPING_SETTING, scheme.file_name().into())
+        .map_err(|e| e.to_string())?;
+
+    // The watcher only sees changes to the user keymap
+    notify_keybindings_changed(&state);
+
+    state.keybindings.overview().map_err(|e| e.to_string())
+}
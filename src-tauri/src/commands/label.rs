@@ -6,9 +6,10 @@ use uuid::Uuid;
 use crate::{
     database::{
         models::label::Label,
-        repositories::{LabelRepository, RepositoryFactory},
+        repositories::{EmailRepository, LabelRepository, RepositoryFactory},
     },
     state::AppState,
+    sync::gmail_labels,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -82,6 +83,8 @@ pub async fn create_label(
 ) -> Result<Label, String> {
     let label = Label {
         id: Uuid::now_v7(),
+        account_id: None,
+        remote_id: None,
         name: request.name,
         color: request.color,
         icon: request.icon,
@@ -118,6 +121,8 @@ pub async fn update_label(
 
     let updated_label = Label {
         id,
+        account_id: existing.account_id,
+        remote_id: existing.remote_id,
         name: request.name,
         icon: request.icon,
         color: request.color,
@@ -162,7 +167,9 @@ pub async fn add_label_to_email(
     label_repo
         .add_to_email(email_id, label_id)
         .await
-        .map_err(|e| format!("Failed to add label to email: {}", e))
+        .map_err(|e| format!("Failed to add label to email: {}", e))?;
+
+    queue_label_change(&state, email_id, label_id, true).await
 }
 
 #[tauri::command]
@@ -180,5 +187,36 @@ pub async fn remove_label_from_email(
     label_repo
         .remove_from_email(email_id, label_id)
         .await
-        .map_err(|e| format!("Failed to remove label from email: {}", e))
+        .map_err(|e| format!("Failed to remove label from email: {}", e))?;
+
+    queue_label_change(&state, email_id, label_id, false).await
+}
+
+/// Pushes a change to a provider label (Gmail) back to the server
+async fn queue_label_change(
+    state: &AppState,
+    email_id: Uuid,
+    label_id: Uuid,
+    applied: bool,
+) -> Result<(), String> {
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+
+    let label = repo_factory
+        .label_repository()
+        .find_by_id(label_id)
+        .await
+        .map_err(|e| format!("Failed to get label: {}", e))?
+        .ok_or_else(|| format!("Label {} not found", label_id))?;
+    if label.remote_id.is_none() {
+        return Ok(());
+    }
+
+    let email = repo_factory
+        .email_repository()
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to get email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    gmail_labels::queue_label_change(&state.db_pool, &email, &label, applied).await
 }
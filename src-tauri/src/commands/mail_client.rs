@@ -0,0 +1,116 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::database::models::email::EmailAddress;
+use crate::mail_client;
+use crate::services::html_sanitizer::{self, BlockingOptions};
+use crate::sync::cid_utils::replace_cid_urls;
+use crate::sync::providers::imap::ImapProvider;
+
+/// A message read from an `.eml` file, which is shown without being stored
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageFile {
+    pub path: String,
+    pub from: EmailAddress,
+    pub to: Vec<EmailAddress>,
+    pub cc: Vec<EmailAddress>,
+    pub subject: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    /// Remote images and trackers are left out
+    pub body_html: Option<String>,
+    pub body_plain: Option<String>,
+    pub attachments: Vec<MessageFileAttachment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageFileAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+}
+
+/// Whether `mailto:` links open in Ravn
+#[tauri::command]
+pub async fn is_default_mail_client(app_handle: tauri::AppHandle) -> Result<bool, String> {
+    mail_client::is_default(&app_handle)
+}
+
+/// Make Ravn the system mail client, returning whether it now is. On Windows
+/// this opens the Default apps settings for the user to pick Ravn.
+#[tauri::command]
+pub async fn set_default_mail_client(app_handle: tauri::AppHandle) -> Result<bool, String> {
+    mail_client::set_default(&app_handle)
+}
+
+/// Read an `.eml` file opened from the file manager
+#[tauri::command]
+pub async fn read_message_file(path: String) -> Result<MessageFile, String> {
+    let raw = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+    let email = ImapProvider::parse_raw_email(
+        &raw,
+        Uuid::nil(),
+        Uuid::nil(),
+        "",
+        format!("{:x}@file.local", md5::compute(&raw)),
+        Vec::new(),
+    )
+    .map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+
+    // Inline images are embedded, since the file's parts are not cached
+    let inline_images: HashMap<String, String> = email
+        .attachments
+        .iter()
+        .filter(|attachment| attachment.is_inline)
+        .filter_map(|attachment| {
+            let content_id = attachment.content_id.as_ref()?;
+            let data = attachment.data.as_ref()?;
+            Some((
+                content_id.clone(),
+                format!(
+                    "data:{};base64,{}",
+                    attachment.content_type,
+                    general_purpose::STANDARD.encode(data)
+                ),
+            ))
+        })
+        .collect();
+
+    let body_html = email.body_html.as_deref().map(|html| {
+        let html = replace_cid_urls(html, &inline_images);
+        html_sanitizer::sanitize(
+            &html,
+            BlockingOptions {
+                block_trackers: true,
+                block_images: true,
+            },
+        )
+        .html
+    });
+
+    Ok(MessageFile {
+        path,
+        from: email.from,
+        to: email.to,
+        cc: email.cc,
+        subject: email.subject,
+        sent_at: email.sent_at,
+        body_html,
+        body_plain: email.body_plain,
+        attachments: email
+            .attachments
+            .into_iter()
+            .filter(|attachment| !attachment.is_inline)
+            .map(|attachment| MessageFileAttachment {
+                filename: attachment.filename,
+                content_type: attachment.content_type,
+                size: attachment.size,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,12 @@
+use crate::database::migrations::MigrationProgress;
+use crate::state::AppState;
+use tauri::State;
+
+/// Latest progress of the startup migrations, for a splash screen that
+/// mounts after events were already sent
+#[tauri::command]
+pub async fn get_migration_status(
+    state: State<'_, AppState>,
+) -> Result<Option<MigrationProgress>, String> {
+    Ok(state.migration_reporter.latest())
+}
@@ -1,17 +1,33 @@
 // pub mod db;
 pub mod attachment;
+pub mod automation;
+pub mod calendar;
 pub mod config;
 pub mod contacts;
 pub mod conversation;
 pub mod corvus;
+pub mod dkim;
 pub mod emails;
+pub mod export;
+pub mod feature_flags;
 pub mod folders;
+pub mod identities;
+pub mod import;
 pub mod keybindings;
 pub mod label;
 pub mod licensing;
+pub mod mail_client;
+pub mod migrations;
 pub mod navigation;
 pub mod notification;
+pub mod rules;
+pub mod saved_searches;
 pub mod search;
+pub mod security;
+pub mod session;
+pub mod signatures;
+pub mod smime;
 pub mod sync;
+pub mod templates;
 pub mod themes;
 pub mod view;
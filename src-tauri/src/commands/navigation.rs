@@ -1,12 +1,34 @@
-use crate::navigation::{NavigationDispatchState, NavigationUrl};
+use crate::actions::{self, Action, ActionSources};
+use crate::database::repositories::{
+    ContactRepository, EmailRepository, SqliteContactRepository, SqliteEmailRepository,
+};
+use crate::navigation::{NavigationDispatchState, NavigationRoute, NavigationUrl};
+use crate::state::AppState;
 use tauri::State;
+use uuid::Uuid;
 
 /// Navigate to a RAVN URL
 #[tauri::command]
-pub async fn navigate_to_url(url: String) -> Result<String, String> {
+pub async fn navigate_to_url(state: State<'_, AppState>, url: String) -> Result<String, String> {
     log::debug!("[Navigation Command] Parsing URL: {}", url);
     let nav_url = NavigationUrl::parse(&url)?;
-    let router_path = nav_url.to_router_path();
+    let router_path = match nav_url.route() {
+        NavigationRoute::Email(email_id) => {
+            let path = resolve_email_path(&state, email_id).await?;
+            // Carried over so the conversation opens with a reply to the email
+            if nav_url.query_param("reply").is_some() {
+                let separator = if path.contains('?') { '&' } else { '?' };
+                format!("{}{}reply=1", path, separator)
+            } else {
+                path
+            }
+        }
+        NavigationRoute::Conversation(conversation_id) => {
+            resolve_conversation_path(&state, conversation_id).await?
+        }
+        NavigationRoute::Contact(contact_id) => resolve_contact_path(&state, contact_id).await?,
+        NavigationRoute::Search(_) | NavigationRoute::Path => nav_url.to_router_path(),
+    };
     log::debug!(
         "[Navigation Command] Mapped to router path: {}",
         router_path
@@ -35,6 +57,21 @@ pub async fn open_external_url(url: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Actions for the command palette, limited to those that apply in `context`
+/// when one is given
+#[tauri::command]
+pub async fn get_actions(
+    state: State<'_, AppState>,
+    context: Option<String>,
+) -> Result<Vec<Action>, String> {
+    let sources = ActionSources::load(&state.db_pool)
+        .await
+        .map_err(|e| format!("Failed to load actions: {}", e))?;
+    let keymap = state.keybindings.get_all().map_err(|e| e.to_string())?;
+
+    Ok(actions::build(&sources, &keymap, context.as_deref()))
+}
+
 /// Mark the frontend navigation listeners as ready and return queued URLs.
 #[tauri::command]
 pub async fn navigation_frontend_ready(
@@ -42,3 +79,51 @@ pub async fn navigation_frontend_ready(
 ) -> Result<Vec<String>, String> {
     Ok(state.mark_frontend_ready())
 }
+
+async fn resolve_email_path(state: &AppState, email_id: Uuid) -> Result<String, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let email = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+
+    Ok(NavigationUrl::email_router_path(
+        email.account_id,
+        email.folder_id,
+        email.conversation_id.as_deref(),
+        email.id,
+    ))
+}
+
+async fn resolve_conversation_path(
+    state: &AppState,
+    conversation_id: Uuid,
+) -> Result<String, String> {
+    let email_repo = SqliteEmailRepository::new(state.db_pool.clone());
+    let latest = email_repo
+        .find_by_conversation_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to fetch conversation: {}", e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
+
+    Ok(NavigationUrl::email_router_path(
+        latest.account_id,
+        latest.folder_id,
+        latest.conversation_id.as_deref(),
+        latest.id,
+    ))
+}
+
+async fn resolve_contact_path(state: &AppState, contact_id: Uuid) -> Result<String, String> {
+    let contact_repo = SqliteContactRepository::new(state.db_pool.clone());
+    let contact = contact_repo
+        .find_by_id(contact_id)
+        .await
+        .map_err(|e| format!("Failed to fetch contact: {}", e))?
+        .ok_or_else(|| format!("Contact {} not found", contact_id))?;
+
+    NavigationUrl::parse(&NavigationUrl::search(&contact.email)).map(|url| url.to_router_path())
+}
@@ -4,7 +4,7 @@ use serde::Serialize;
 use tauri::{Emitter, State};
 
 use crate::database::repositories::{EmailRepository, RepositoryFactory};
-use crate::services::notification_service::{BadgeCount, NotificationService};
+use crate::services::notification_service::{BadgeCount, NotificationService, UnreadCounts};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -127,6 +127,15 @@ pub async fn get_badge_count(state: State<'_, AppState>) -> Result<BadgeCount, S
     })
 }
 
+/// Get the unread counts of all folders and views
+#[tauri::command]
+pub async fn get_unread_counts(state: State<'_, AppState>) -> Result<UnreadCounts, String> {
+    notification_service_from_state(&state)
+        .unread_counts()
+        .await
+        .map_err(|e| format!("Failed to get unread counts: {}", e))
+}
+
 /// Test notification sound
 #[tauri::command]
 pub async fn test_notification_sound(
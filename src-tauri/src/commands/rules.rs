@@ -0,0 +1,274 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        models::rule::{Rule, RuleAction, RuleCondition},
+        repositories::{EmailRepository, FolderRepository, RepositoryFactory, RuleRepository},
+    },
+    state::AppState,
+    sync::rules_engine::{compile_subject_pattern, RulesEngine},
+};
+
+const RUN_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRuleRequest {
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub match_all: bool,
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+    #[serde(default)]
+    pub stop_processing: bool,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateRuleRequest {
+    pub id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    pub enabled: bool,
+    pub match_all: bool,
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+    pub stop_processing: bool,
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunRulesResult {
+    pub processed: usize,
+    pub matched: usize,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn validate_rule(
+    name: &str,
+    conditions: &[RuleCondition],
+    actions: &[RuleAction],
+) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Rule name cannot be empty".to_string());
+    }
+    if conditions.is_empty() {
+        return Err("A rule needs at least one condition".to_string());
+    }
+    if actions.is_empty() {
+        return Err("A rule needs at least one action".to_string());
+    }
+
+    for condition in conditions {
+        if let RuleCondition::SubjectRegex { pattern } = condition {
+            compile_subject_pattern(pattern)?;
+        }
+    }
+    for action in actions {
+        if let RuleAction::Forward { to } = action {
+            if !to.contains('@') {
+                return Err(format!("Invalid forward address: {}", to));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_rules(state: State<'_, AppState>) -> Result<Vec<Rule>, String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .rule_repository()
+        .get_all()
+        .await
+        .map_err(|e| format!("Failed to get rules: {}", e))
+}
+
+#[tauri::command]
+pub async fn create_rule(
+    state: State<'_, AppState>,
+    request: CreateRuleRequest,
+) -> Result<Rule, String> {
+    validate_rule(&request.name, &request.conditions, &request.actions)?;
+
+    let rule = Rule {
+        id: Uuid::now_v7(),
+        account_id: request.account_id,
+        name: request.name.trim().to_string(),
+        enabled: request.enabled,
+        match_all: request.match_all,
+        conditions: request.conditions,
+        actions: request.actions,
+        stop_processing: request.stop_processing,
+        sort_order: request.sort_order,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    RepositoryFactory::new(state.db_pool.clone())
+        .rule_repository()
+        .create(&rule)
+        .await
+        .map_err(|e| format!("Failed to create rule: {}", e))?;
+
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn update_rule(
+    state: State<'_, AppState>,
+    request: UpdateRuleRequest,
+) -> Result<Rule, String> {
+    validate_rule(&request.name, &request.conditions, &request.actions)?;
+
+    let rule_repo = RepositoryFactory::new(state.db_pool.clone()).rule_repository();
+    let existing = rule_repo
+        .find_by_id(request.id)
+        .await
+        .map_err(|e| format!("Failed to find rule: {}", e))?
+        .ok_or_else(|| format!("Rule {} not found", request.id))?;
+
+    let rule = Rule {
+        id: existing.id,
+        account_id: request.account_id,
+        name: request.name.trim().to_string(),
+        enabled: request.enabled,
+        match_all: request.match_all,
+        conditions: request.conditions,
+        actions: request.actions,
+        stop_processing: request.stop_processing,
+        sort_order: request.sort_order,
+        created_at: existing.created_at,
+        updated_at: Utc::now(),
+    };
+
+    rule_repo
+        .update(&rule)
+        .await
+        .map_err(|e| format!("Failed to update rule: {}", e))?;
+
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_rule(state: State<'_, AppState>, rule_id: Uuid) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .rule_repository()
+        .delete(rule_id)
+        .await
+        .map_err(|e| format!("Failed to delete rule: {}", e))
+}
+
+/// Applies the account's enabled rules to mail already in a folder (the inbox
+/// by default). Forward actions are skipped so old mail is never sent out again.
+#[tauri::command]
+pub async fn run_rules_on_existing(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    folder_id: Option<Uuid>,
+) -> Result<RunRulesResult, String> {
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+
+    let folder_id = match folder_id {
+        Some(id) => id,
+        None => {
+            repo_factory
+                .folder_repository()
+                .find_by_type(account_id, "inbox")
+                .await
+                .map_err(|e| format!("Failed to find inbox: {}", e))?
+                .ok_or_else(|| "Account has no inbox".to_string())?
+                .id
+        }
+    };
+
+    let rules = repo_factory
+        .rule_repository()
+        .find_enabled_for_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to load rules: {}", e))?;
+
+    // Collect the whole folder up front, since moves and deletes would shift
+    // the pages underneath us
+    let email_repo = repo_factory.email_repository();
+    let mut emails = Vec::new();
+    loop {
+        let page = email_repo
+            .find_by_folder(folder_id, RUN_PAGE_SIZE, emails.len() as i64)
+            .await
+            .map_err(|e| format!("Failed to load emails: {}", e))?;
+        let done = (page.len() as i64) < RUN_PAGE_SIZE;
+        emails.extend(page);
+        if done {
+            break;
+        }
+    }
+
+    let engine = RulesEngine::new(state.db_pool.clone());
+    let mut matched = 0;
+    for email in emails.iter().filter(|e| e.account_id == account_id) {
+        let outcome = engine
+            .apply_rules(&rules, email, None)
+            .await
+            .map_err(|e| format!("Failed to apply rules to email {}: {}", email.id, e))?;
+        if !outcome.matched_rule_ids.is_empty() {
+            matched += 1;
+        }
+    }
+
+    log::info!(
+        "[Rules] Applied {} rules to {} emails in folder {}, {} matched",
+        rules.len(),
+        emails.len(),
+        folder_id,
+        matched
+    );
+
+    Ok(RunRulesResult {
+        processed: emails.len(),
+        matched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rule() {
+        let conditions = vec![RuleCondition::SubjectRegex {
+            pattern: "^\\[ci\\]".to_string(),
+        }];
+        let actions = vec![RuleAction::MarkRead];
+
+        assert!(validate_rule("CI", &conditions, &actions).is_ok());
+        assert!(validate_rule(" ", &conditions, &actions).is_err());
+        assert!(validate_rule("CI", &[], &actions).is_err());
+        assert!(validate_rule("CI", &conditions, &[]).is_err());
+        assert!(validate_rule(
+            "CI",
+            &[RuleCondition::SubjectRegex {
+                pattern: "(".to_string()
+            }],
+            &actions
+        )
+        .is_err());
+        assert!(validate_rule(
+            "CI",
+            &conditions,
+            &[RuleAction::Forward {
+                to: "nobody".to_string()
+            }]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    commands::{folders::FolderResponse, search::query_scope},
+    database::{
+        models::{
+            folder::{FolderSettings, FolderType},
+            saved_search::SavedSearch,
+        },
+        repositories::{EmailRepository, RepositoryFactory, SavedSearchRepository},
+    },
+    search::{ParsedQuery, SearchQuery},
+    state::AppState,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSavedSearchRequest {
+    pub account_id: Uuid,
+    pub name: String,
+    pub query: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateSavedSearchRequest {
+    pub id: Uuid,
+    pub name: String,
+    pub query: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+fn validate_saved_search(name: &str, query: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Saved search name cannot be empty".to_string());
+    }
+    if query.trim().is_empty() {
+        return Err("Saved search query cannot be empty".to_string());
+    }
+
+    ParsedQuery::parse(query).map_err(|e| format!("Invalid search query: {}", e))?;
+
+    Ok(())
+}
+
+/// Saved searches of an account, or of all accounts
+#[tauri::command]
+pub async fn get_saved_searches(
+    state: State<'_, AppState>,
+    account_id: Option<Uuid>,
+) -> Result<Vec<SavedSearch>, String> {
+    let saved_search_repo = RepositoryFactory::new(state.db_pool.clone()).saved_search_repository();
+
+    match account_id {
+        Some(account_id) => saved_search_repo.find_by_account(account_id).await,
+        None => saved_search_repo.get_all().await,
+    }
+    .map_err(|e| format!("Failed to get saved searches: {}", e))
+}
+
+#[tauri::command]
+pub async fn create_saved_search(
+    state: State<'_, AppState>,
+    request: CreateSavedSearchRequest,
+) -> Result<SavedSearch, String> {
+    validate_saved_search(&request.name, &request.query)?;
+
+    let saved_search = SavedSearch {
+        id: Uuid::now_v7(),
+        account_id: request.account_id,
+        name: request.name.trim().to_string(),
+        query: request.query.trim().to_string(),
+        icon: request.icon,
+        color: request.color,
+        sort_order: request.sort_order.unwrap_or(0),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    RepositoryFactory::new(state.db_pool.clone())
+        .saved_search_repository()
+        .create(&saved_search)
+        .await
+        .map_err(|e| format!("Failed to create saved search: {}", e))?;
+
+    Ok(saved_search)
+}
+
+#[tauri::command]
+pub async fn update_saved_search(
+    state: State<'_, AppState>,
+    request: UpdateSavedSearchRequest,
+) -> Result<SavedSearch, String> {
+    validate_saved_search(&request.name, &request.query)?;
+
+    let saved_search_repo = RepositoryFactory::new(state.db_pool.clone()).saved_search_repository();
+    let existing = saved_search_repo
+        .find_by_id(request.id)
+        .await
+        .map_err(|e| format!("Failed to find saved search: {}", e))?
+        .ok_or_else(|| format!("Saved search {} not found", request.id))?;
+
+    let saved_search = SavedSearch {
+        id: existing.id,
+        account_id: existing.account_id,
+        name: request.name.trim().to_string(),
+        query: request.query.trim().to_string(),
+        icon: request.icon,
+        color: request.color,
+        sort_order: request.sort_order.unwrap_or(existing.sort_order),
+        created_at: existing.created_at,
+        updated_at: Utc::now(),
+    };
+
+    saved_search_repo
+        .update(&saved_search)
+        .await
+        .map_err(|e| format!("Failed to update saved search: {}", e))?;
+
+    Ok(saved_search)
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(
+    state: State<'_, AppState>,
+    saved_search_id: Uuid,
+) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .saved_search_repository()
+        .delete(saved_search_id)
+        .await
+        .map_err(|e| format!("Failed to delete saved search: {}", e))
+}
+
+/// Saved searches of the accounts as virtual folders. The index selects the
+/// matching emails, the counts come from the database since read status
+/// changes are not reindexed.
+pub(crate) async fn saved_search_folders(
+    state: &AppState,
+    account_ids: &[Uuid],
+) -> Result<Vec<FolderResponse>, String> {
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+    let saved_search_repo = repo_factory.saved_search_repository();
+    let email_repo = repo_factory.email_repository();
+    let mut folders = Vec::new();
+
+    for &account_id in account_ids {
+        let saved_searches = saved_search_repo
+            .find_by_account(account_id)
+            .await
+            .map_err(|e| format!("Failed to get saved searches: {}", e))?;
+
+        for saved_search in saved_searches {
+            let search_query = SearchQuery {
+                query: saved_search.query.clone(),
+                account_id: Some(account_id),
+                folder_id: None,
+                conversation_id: None,
+                limit: 50,
+                offset: 0,
+                scope: query_scope(state, &saved_search.query).await?,
+            };
+
+            // A query that no longer parses shows up as an empty folder
+            let email_ids = match state.search_manager.matching_ids(&search_query) {
+                Ok(email_ids) => email_ids,
+                Err(e) => {
+                    log::warn!("Failed to run saved search {}: {}", saved_search.id, e);
+                    Vec::new()
+                }
+            };
+            let (total_count, unread_count) = email_repo
+                .count_by_ids(&email_ids)
+                .await
+                .map_err(|e| format!("Failed to count saved search results: {}", e))?;
+
+            folders.push(FolderResponse {
+                id: saved_search.id,
+                account_id,
+                name: saved_search.name,
+                folder_type: FolderType::Search,
+                remote_id: None,
+                color: saved_search.color,
+                icon: saved_search
+                    .icon
+                    .or_else(|| Some(FolderType::Search.default_icon().to_string())),
+                sort_order: saved_search.sort_order,
+                expanded: false,
+                hidden: false,
+                sync_enabled: false,
+                parent_id: None,
+                settings: FolderSettings::default(),
+                unread_count,
+                total_count,
+                query: Some(saved_search.query),
+                account_color: None,
+            });
+        }
+    }
+
+    Ok(folders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_saved_search() {
+        assert!(validate_saved_search("Unread with files", "is:unread has:attachment").is_ok());
+        assert!(validate_saved_search(" ", "is:unread").is_err());
+        assert!(validate_saved_search("Empty", "  ").is_err());
+    }
+}
@@ -1,9 +1,18 @@
+use crate::commands::export::write_export;
 use crate::database::models::conversation::ConversationListItem;
 use crate::database::models::email_dto::{EmailListItem, LabelInfo};
 use crate::database::repositories::RepositoryFactory;
-use crate::database::repositories::{EmailRepository, LabelRepository};
-use crate::search::SearchQuery;
+use crate::database::repositories::{
+    AccountRepository, AttachmentRepository, EmailRepository, FolderRepository, LabelRepository,
+};
+use crate::search::export::{ExportFormat, ExportSummary};
+use crate::search::global_search::{self, GlobalSearchHit, ProfileSource};
+use crate::search::reindex::IndexStatus;
+use crate::search::{
+    Filter, MatchedAttachment, ParsedQuery, QueryScope, SearchHighlight, SearchQuery,
+};
 use crate::state::AppState;
+use std::collections::HashMap;
 use tauri::State;
 use uuid::Uuid;
 
@@ -17,6 +26,7 @@ pub async fn search_emails(
     limit: Option<usize>,
     offset: Option<usize>,
 ) -> Result<SearchResults, String> {
+    let scope = query_scope(&state, &query).await?;
     let search_query = SearchQuery {
         query,
         account_id,
@@ -24,6 +34,7 @@ pub async fn search_emails(
         conversation_id: None,
         limit: limit.unwrap_or(50),
         offset: offset.unwrap_or(0),
+        scope,
     };
 
     let search_results = state
@@ -39,12 +50,19 @@ pub async fn search_emails(
             emails: vec![],
             conversations: vec![],
             total: 0,
+            matched_attachments: HashMap::new(),
+            highlights: HashMap::new(),
         });
     }
 
     let repo_factory = RepositoryFactory::new(state.db_pool.clone());
     let email_repo = repo_factory.email_repository();
     let label_repo = repo_factory.label_repository();
+    let account_colors = repo_factory
+        .account_repository()
+        .find_colors()
+        .await
+        .map_err(|e| format!("Failed to fetch account colors: {}", e))?;
 
     let mut emails = Vec::new();
     for email_id in &email_ids {
@@ -78,14 +96,19 @@ pub async fn search_emails(
                 received_at: email.received_at.to_rfc3339().parse().unwrap(),
                 sent_at: email.sent_at.map(|dt| dt.to_rfc3339().parse().unwrap()),
                 remind_at: email.remind_at.map(|dt| dt.to_rfc3339().parse().unwrap()),
+                snoozed_until: email.snoozed_until,
                 notified_at: None,
                 is_read: email.is_read,
                 is_draft: email.is_draft,
                 is_flagged: email.is_flagged,
                 size: email.size,
+                priority: email.priority,
+                risk_score: email.risk_score,
                 sync_status: email.sync_status.clone(),
                 has_attachments: email.has_attachments,
                 labels,
+                folder: None,
+                account_color: account_colors.get(&email.account_id).cloned(),
             };
 
             emails.push(email_list_item);
@@ -110,78 +133,96 @@ pub async fn search_emails(
             id: conv_id,
             message_count: messages.len() as i64,
             ai_cache: None,
+            muted: false,
             messages,
         });
     }
 
+    let mut matched_attachments = HashMap::new();
+    let mut highlights = HashMap::new();
+    for result in search_results {
+        if !result.matched_attachments.is_empty() {
+            matched_attachments.insert(result.id, result.matched_attachments);
+        }
+        if !result.highlights.is_empty() {
+            highlights.insert(result.id, result.highlights);
+        }
+    }
+
     Ok(SearchResults {
         emails,
         conversations,
-        total: search_results.len(),
+        total: email_ids.len(),
+        matched_attachments,
+        highlights,
     })
 }
 
-/// Reindex all emails in the search index
+/// Search this profile and, when global search is enabled, the indexes of the
+/// other configured profiles. Other profiles are only read, never written.
 #[tauri::command]
-pub async fn reindex_all_emails(state: State<'_, AppState>) -> Result<ReindexResult, String> {
-    log::info!("[Search] Starting full reindex of all emails");
-
-    state
-        .search_manager
-        .clear_index()
-        .await
-        .map_err(|e| format!("Failed to clear index: {}", e))?;
-
-    let batch_size = 1000;
-    let mut offset = 0;
-    let mut total_indexed = 0;
-
-    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
-    let email_repo = repo_factory.email_repository();
-
-    loop {
-        let emails = email_repo
-            .find_synced_batch(batch_size, offset)
-            .await
-            .map_err(|e| format!("Failed to fetch emails: {}", e))?;
-
-        if emails.is_empty() {
-            break;
-        }
-
-        let count = emails.len();
+pub async fn search_all_profiles(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<GlobalSearchHit>, String> {
+    let enabled = state
+        .settings
+        .get::<bool>("search.global.enabled")
+        .unwrap_or(false);
+    let current_name = state
+        .settings
+        .get::<String>("search.global.profileName")
+        .unwrap_or_else(|_| "Default".to_string());
+
+    let profiles = if enabled {
+        let sources = state
+            .settings
+            .get::<Vec<ProfileSource>>("search.global.profiles")
+            .map_err(|e| format!("Invalid search.global.profiles setting: {}", e))?;
+        global_search::open_profiles(&state.app_data_dir, &sources)
+    } else {
+        Vec::new()
+    };
 
-        state
-            .search_manager
-            .index_emails_batch(&emails)
-            .await
-            .map_err(|e| format!("Failed to index batch: {}", e))?;
+    let scope = query_scope(&state, &query).await?;
+    let search_query = SearchQuery {
+        query,
+        account_id: None,
+        folder_id: None,
+        conversation_id: None,
+        limit: limit.unwrap_or(50),
+        offset: offset.unwrap_or(0),
+        scope,
+    };
 
-        total_indexed += count;
-        offset += batch_size;
+    global_search::search_all(
+        &state.search_manager,
+        &current_name,
+        &profiles,
+        search_query,
+    )
+    .await
+    .map_err(|e| format!("Search failed: {}", e))
+}
 
-        log::info!(
-            "[Search] Indexed {} emails (total: {})",
-            count,
-            total_indexed
-        );
+/// Rebuild the search index from the database in the background. Progress is
+/// reported with `search:reindex-progress` events; if a reindex is already
+/// running, its status is returned.
+#[tauri::command]
+pub async fn reindex_all_emails(state: State<'_, AppState>) -> Result<IndexStatus, String> {
+    if !state.search_reindexer.start(false) {
+        log::info!("[Search] Reindex already running");
     }
 
-    state
-        .search_manager
-        .commit()
-        .await
-        .map_err(|e| format!("Failed to commit index: {}", e))?;
-
-    log::info!(
-        "[Search] Reindex complete. Total emails indexed: {}",
-        total_indexed
-    );
+    Ok(state.search_reindexer.status())
+}
 
-    Ok(ReindexResult {
-        total_indexed,
-        success: true,
-    })
+/// Number of indexed emails and the progress of a running or interrupted reindex
+#[tauri::command]
+pub async fn get_index_status(state: State<'_, AppState>) -> Result<IndexStatus, String> {
+    Ok(state.search_reindexer.status())
 }
 
 /// Reindex emails for a specific account
@@ -201,10 +242,20 @@ pub async fn reindex_account_emails(
         .map_err(|e| format!("Failed to fetch emails: {}", e))?;
 
     let total = emails.len();
+    let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
+    let attachment_repo = repo_factory.attachment_repository();
+    let attachments = attachment_repo
+        .find_by_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+    let attachment_texts = attachment_repo
+        .find_texts_by_emails(&email_ids)
+        .await
+        .map_err(|e| format!("Failed to fetch attachment texts: {}", e))?;
 
     state
         .search_manager
-        .index_emails_batch(&emails)
+        .index_emails_batch(&emails, &attachments, &attachment_texts)
         .await
         .map_err(|e| format!("Failed to index emails: {}", e))?;
 
@@ -226,11 +277,79 @@ pub async fn reindex_account_emails(
     })
 }
 
+/// Number of search hits fetched per page while collecting export results
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+/// Export all emails matching a search query as mbox, EML or CSV
+///
+/// Emits `search:export-progress` events while writing.
+#[tauri::command]
+pub async fn export_results(
+    state: State<'_, AppState>,
+    query: String,
+    account_id: Option<Uuid>,
+    folder_id: Option<Uuid>,
+    format: ExportFormat,
+    destination: String,
+    metadata_only: Option<bool>,
+) -> Result<ExportSummary, String> {
+    let email_ids = search_email_ids(&state, &query, account_id, folder_id).await?;
+
+    write_export(
+        &state,
+        "search:export-progress",
+        email_ids,
+        format,
+        std::path::PathBuf::from(destination),
+        metadata_only.unwrap_or(false),
+    )
+    .await
+}
+
+/// Collects the ids of every email matching a search query, in rank order
+pub(crate) async fn search_email_ids(
+    state: &AppState,
+    query: &str,
+    account_id: Option<Uuid>,
+    folder_id: Option<Uuid>,
+) -> Result<Vec<Uuid>, String> {
+    let scope = query_scope(state, query).await?;
+    let mut email_ids: Vec<Uuid> = Vec::new();
+    loop {
+        let page = state
+            .search_manager
+            .search(SearchQuery {
+                query: query.to_string(),
+                account_id,
+                folder_id,
+                conversation_id: None,
+                limit: EXPORT_PAGE_SIZE,
+                offset: email_ids.len(),
+                scope: scope.clone(),
+            })
+            .await
+            .map_err(|e| format!("Search failed: {}", e))?;
+
+        let page_len = page.len();
+        email_ids.extend(page.into_iter().map(|r| r.id));
+
+        if page_len < EXPORT_PAGE_SIZE {
+            break;
+        }
+    }
+
+    Ok(email_ids)
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct SearchResults {
     pub emails: Vec<EmailListItem>,
     pub conversations: Vec<ConversationListItem>,
     pub total: usize,
+    /// Attachments that matched `filename:` / `type:` terms, by email id
+    pub matched_attachments: HashMap<Uuid, Vec<MatchedAttachment>>,
+    /// Highlighted snippets of the fields each email matched, by email id
+    pub highlights: HashMap<Uuid, Vec<SearchHighlight>>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -238,3 +357,39 @@ pub struct ReindexResult {
     pub total_indexed: usize,
     pub success: bool,
 }
+
+/// Resolves the folder and label names used by `in:` and `label:` operators
+pub(crate) async fn query_scope(state: &AppState, query: &str) -> Result<QueryScope, String> {
+    let parsed = ParsedQuery::parse(query).map_err(|e| format!("Invalid search query: {}", e))?;
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+    let mut scope = QueryScope::default();
+
+    for filter in parsed.filters() {
+        match filter {
+            Filter::Folder(name) if !scope.folders.contains_key(name) => {
+                let folders = repo_factory
+                    .folder_repository()
+                    .get_all()
+                    .await
+                    .map_err(|e| format!("Failed to fetch folders: {}", e))?;
+                let folder_ids = folders
+                    .iter()
+                    .filter(|f| f.name.to_lowercase() == *name || f.folder_type.as_str() == name)
+                    .map(|f| f.id)
+                    .collect();
+                scope.folders.insert(name.clone(), folder_ids);
+            }
+            Filter::Label(name) if !scope.labels.contains_key(name) => {
+                let email_ids = repo_factory
+                    .label_repository()
+                    .find_email_ids_by_name(name)
+                    .await
+                    .map_err(|e| format!("Failed to fetch labeled emails: {}", e))?;
+                scope.labels.insert(name.clone(), email_ids);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(scope)
+}
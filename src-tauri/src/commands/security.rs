@@ -0,0 +1,56 @@
+use tauri::State;
+
+use crate::services::app_lock::LockStatus;
+use crate::state::AppState;
+
+/// Whether a passphrase is set, the app is locked and biometrics can unlock it
+#[tauri::command]
+pub async fn get_lock_status(state: State<'_, AppState>) -> Result<LockStatus, String> {
+    Ok(state.app_lock.status())
+}
+
+/// Lock the app until it is unlocked again
+#[tauri::command]
+pub async fn lock(state: State<'_, AppState>) -> Result<LockStatus, String> {
+    if !state.app_lock.lock() {
+        return Err("Set a passphrase before locking the app".to_string());
+    }
+    Ok(state.app_lock.status())
+}
+
+/// Unlock the app with its passphrase
+#[tauri::command]
+pub async fn unlock(state: State<'_, AppState>, passphrase: String) -> Result<LockStatus, String> {
+    state.app_lock.unlock(&passphrase)?;
+    Ok(state.app_lock.status())
+}
+
+/// Unlock the app with Touch ID or Windows Hello
+#[tauri::command]
+pub async fn unlock_with_biometrics(
+    state: State<'_, AppState>,
+    reason: String,
+) -> Result<LockStatus, String> {
+    state.app_lock.unlock_with_biometrics(&reason).await?;
+    Ok(state.app_lock.status())
+}
+
+/// Set, change or with `None` remove the passphrase locking the app
+#[tauri::command]
+pub async fn set_lock_passphrase(
+    state: State<'_, AppState>,
+    current: Option<String>,
+    passphrase: Option<String>,
+) -> Result<LockStatus, String> {
+    state
+        .app_lock
+        .set_passphrase(current.as_deref(), passphrase.as_deref())?;
+    Ok(state.app_lock.status())
+}
+
+/// Note user input, which postpones the auto-lock
+#[tauri::command]
+pub async fn record_activity(state: State<'_, AppState>) -> Result<(), String> {
+    state.app_lock.record_activity();
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use crate::database::models::session::{SessionWindow, SessionWindowType};
+use crate::database::repositories::{SessionRepository, SqliteSessionRepository};
+use crate::session::{capture_window, window_type_for_label};
+use crate::state::AppState;
+use tauri::{Manager, State};
+
+/// Persist geometry, route and view state (selected folder/view, scroll anchors) of a window
+#[tauri::command]
+pub async fn save_session_window(
+    state: State<'_, AppState>,
+    label: String,
+    window_type: SessionWindowType,
+    route: Option<String>,
+    view_state: Option<serde_json::Value>,
+) -> Result<SessionWindow, String> {
+    if window_type_for_label(&label) != Some(window_type) {
+        return Err(format!("Window {} is not part of the session", label));
+    }
+
+    let window = state
+        .app_handle
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("Window {} not found", label))?;
+
+    let session = capture_window(
+        &window,
+        window_type,
+        route,
+        view_state.unwrap_or_else(|| serde_json::json!({})),
+    );
+
+    SqliteSessionRepository::new(state.db_pool.clone())
+        .upsert(&session)
+        .await
+        .map_err(|e| format!("Failed to save session window: {}", e))?;
+
+    Ok(session)
+}
+
+/// Forget a window so it is not reopened on the next start (e.g. after the user closed it)
+#[tauri::command]
+pub async fn remove_session_window(
+    state: State<'_, AppState>,
+    label: String,
+) -> Result<(), String> {
+    SqliteSessionRepository::new(state.db_pool.clone())
+        .delete(&label)
+        .await
+        .map_err(|e| format!("Failed to remove session window: {}", e))
+}
+
+/// Get the persisted session so the frontend can restore selection and scroll anchors
+#[tauri::command]
+pub async fn get_session(state: State<'_, AppState>) -> Result<Vec<SessionWindow>, String> {
+    SqliteSessionRepository::new(state.db_pool.clone())
+        .get_all()
+        .await
+        .map_err(|e| format!("Failed to load session: {}", e))
+}
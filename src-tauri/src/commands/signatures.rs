@@ -0,0 +1,148 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        models::signature::Signature,
+        repositories::{RepositoryFactory, SignatureRepository},
+    },
+    state::AppState,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSignatureRequest {
+    pub account_id: Uuid,
+    pub identity_email: Option<String>,
+    pub name: String,
+    /// HTML variant
+    pub signature: String,
+    pub signature_text: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default)]
+    pub is_reply_default: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateSignatureRequest {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub identity_email: Option<String>,
+    pub name: String,
+    pub signature: String,
+    pub signature_text: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default)]
+    pub is_reply_default: bool,
+}
+
+fn validate_signature(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Signature name cannot be empty".to_string());
+    }
+
+    Ok(())
+}
+
+/// Addresses are matched case-insensitively, blank means every address
+fn normalize_identity_email(identity_email: Option<String>) -> Option<String> {
+    identity_email
+        .map(|email| email.trim().to_lowercase())
+        .filter(|email| !email.is_empty())
+}
+
+/// Signatures of an account, or all signatures
+#[tauri::command]
+pub async fn get_signatures(
+    state: State<'_, AppState>,
+    account_id: Option<Uuid>,
+) -> Result<Vec<Signature>, String> {
+    let signature_repo = RepositoryFactory::new(state.db_pool.clone()).signature_repository();
+
+    match account_id {
+        Some(account_id) => signature_repo.find_by_account(account_id).await,
+        None => signature_repo.get_all().await,
+    }
+    .map_err(|e| format!("Failed to get signatures: {}", e))
+}
+
+/// Setting a default flag takes it away from the other signatures of the same
+/// account and address
+#[tauri::command]
+pub async fn create_signature(
+    state: State<'_, AppState>,
+    request: CreateSignatureRequest,
+) -> Result<Signature, String> {
+    validate_signature(&request.name)?;
+
+    let signature = Signature {
+        id: Uuid::now_v7(),
+        account_id: request.account_id,
+        identity_email: normalize_identity_email(request.identity_email),
+        name: request.name.trim().to_string(),
+        signature: request.signature,
+        signature_text: request.signature_text.filter(|s| !s.trim().is_empty()),
+        is_default: request.is_default,
+        is_reply_default: request.is_reply_default,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    RepositoryFactory::new(state.db_pool.clone())
+        .signature_repository()
+        .create(&signature)
+        .await
+        .map_err(|e| format!("Failed to create signature: {}", e))?;
+
+    Ok(signature)
+}
+
+#[tauri::command]
+pub async fn update_signature(
+    state: State<'_, AppState>,
+    request: UpdateSignatureRequest,
+) -> Result<Signature, String> {
+    validate_signature(&request.name)?;
+
+    let signature_repo = RepositoryFactory::new(state.db_pool.clone()).signature_repository();
+    let existing = signature_repo
+        .find_by_id(request.id)
+        .await
+        .map_err(|e| format!("Failed to find signature: {}", e))?
+        .ok_or_else(|| format!("Signature {} not found", request.id))?;
+
+    let signature = Signature {
+        id: existing.id,
+        account_id: request.account_id,
+        identity_email: normalize_identity_email(request.identity_email),
+        name: request.name.trim().to_string(),
+        signature: request.signature,
+        signature_text: request.signature_text.filter(|s| !s.trim().is_empty()),
+        is_default: request.is_default,
+        is_reply_default: request.is_reply_default,
+        created_at: existing.created_at,
+        updated_at: Utc::now(),
+    };
+
+    signature_repo
+        .update(&signature)
+        .await
+        .map_err(|e| format!("Failed to update signature: {}", e))?;
+
+    Ok(signature)
+}
+
+#[tauri::command]
+pub async fn delete_signature(
+    state: State<'_, AppState>,
+    signature_id: Uuid,
+) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .signature_repository()
+        .delete(signature_id)
+        .await
+        .map_err(|e| format!("Failed to delete signature: {}", e))
+}
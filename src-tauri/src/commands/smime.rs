@@ -0,0 +1,146 @@
+use chrono::Utc;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        models::smime::{SmimeCertificate, SmimeVerification},
+        repositories::{AccountRepository, RepositoryFactory, SmimeRepository},
+    },
+    services::smime,
+    state::AppState,
+};
+
+/// Import the account's own certificate and private key from a PKCS#12 file,
+/// replacing any previously imported one
+#[tauri::command]
+pub async fn import_smime_certificate(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    data: Vec<u8>,
+    password: String,
+) -> Result<SmimeCertificate, String> {
+    let factory = RepositoryFactory::new(state.db_pool.clone());
+    factory
+        .account_repository()
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to find account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    let (identity, info) = smime::import_pkcs12(&data, &password).map_err(|e| e.to_string())?;
+
+    // The new key is stored before any certificate loses its key, and the old
+    // one is put back if the certificate cannot be saved
+    let previous = state.credential_store.get_smime(account_id).await.ok();
+    state
+        .credential_store
+        .store_smime(account_id, &identity)
+        .await
+        .map_err(|e| format!("Failed to store private key: {}", e))?;
+
+    let now = Utc::now();
+    let certificate = SmimeCertificate {
+        id: Uuid::now_v7(),
+        account_id: Some(account_id),
+        email: info.email_addresses[0].clone(),
+        subject: info.subject,
+        issuer: info.issuer,
+        serial: info.serial,
+        fingerprint: info.fingerprint,
+        not_before: info.not_before,
+        not_after: info.not_after,
+        certificate_pem: identity.certificate_pem,
+        has_private_key: true,
+        created_at: now,
+        updated_at: now,
+    };
+    let smime_repo = factory.smime_repository();
+    let saved = async {
+        smime_repo.clear_signing_certificate(account_id).await?;
+        smime_repo.upsert(&certificate).await
+    }
+    .await;
+    if let Err(e) = saved {
+        let restored = match &previous {
+            Some(previous) => {
+                state
+                    .credential_store
+                    .store_smime(account_id, previous)
+                    .await
+            }
+            None => state.credential_store.delete_smime(account_id).await,
+        };
+        if let Err(restore_error) = restored {
+            log::error!(
+                "Failed to restore the S/MIME key of account {}: {}",
+                account_id,
+                restore_error
+            );
+        }
+        return Err(format!("Failed to save certificate: {}", e));
+    }
+
+    log::info!(
+        "Imported S/MIME certificate {} for account {}",
+        certificate.fingerprint,
+        account_id
+    );
+
+    Ok(certificate)
+}
+
+/// Own certificates of an account, or all known certificates
+#[tauri::command]
+pub async fn get_smime_certificates(
+    state: State<'_, AppState>,
+    account_id: Option<Uuid>,
+) -> Result<Vec<SmimeCertificate>, String> {
+    let smime_repo = RepositoryFactory::new(state.db_pool.clone()).smime_repository();
+
+    match account_id {
+        Some(account_id) => smime_repo.find_by_account(account_id).await,
+        None => smime_repo.get_all().await,
+    }
+    .map_err(|e| format!("Failed to get certificates: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_smime_certificate(
+    state: State<'_, AppState>,
+    certificate_id: Uuid,
+) -> Result<(), String> {
+    let smime_repo = RepositoryFactory::new(state.db_pool.clone()).smime_repository();
+
+    let certificate = smime_repo
+        .find_by_id(certificate_id)
+        .await
+        .map_err(|e| format!("Failed to find certificate: {}", e))?
+        .ok_or_else(|| format!("Certificate {} not found", certificate_id))?;
+
+    if let (Some(account_id), true) = (certificate.account_id, certificate.has_private_key) {
+        state
+            .credential_store
+            .delete_smime(account_id)
+            .await
+            .map_err(|e| format!("Failed to delete private key: {}", e))?;
+    }
+
+    smime_repo
+        .delete(certificate_id)
+        .await
+        .map_err(|e| format!("Failed to delete certificate: {}", e))
+}
+
+/// Signature check of a received message, `None` if it was not signed
+#[tauri::command]
+pub async fn get_email_smime_status(
+    state: State<'_, AppState>,
+    email_id: Uuid,
+) -> Result<Option<SmimeVerification>, String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .smime_repository()
+        .find_verification(email_id)
+        .await
+        .map_err(|e| format!("Failed to get signature status: {}", e))
+}
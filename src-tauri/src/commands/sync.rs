@@ -2,12 +2,28 @@ use serde::{Deserialize, Serialize};
 use tauri::{Manager, State, WebviewWindowBuilder};
 use uuid::Uuid;
 
-use crate::database::models::account::{Account, AccountType};
-use crate::database::repositories::{AccountRepository, FolderRepository, RepositoryFactory};
+use crate::database::models::account::{Account, AccountQuota, AccountType};
+use crate::database::repositories::{
+    AccountRepository, FolderRepository, RepositoryFactory, SyncStateRepository,
+};
+use crate::services::app_lock;
 use crate::state::AppState;
 use crate::sync::{
-    auth::OAuth2Helper,
-    types::{AccountSettings, ImapCredentials, SyncFolder},
+    account_health::{self, AccountHealth},
+    account_profile,
+    auth::{OAuth2Helper, OAuthScope},
+    autoconfig::{self, DiscoveredSettings},
+    backfill,
+    events::{self, CredentialsUpdatedEvent},
+    identities, mailbox_quota,
+    network_usage::{self, NetworkUsageReport},
+    provider::ProviderFactory,
+    providers::{icloud, imap_pool},
+    tls_trust::MailServer,
+    types::{
+        AccountSettings, ImapCredentials, ImapDeletePolicy, OAuth2Credentials, ProviderCredentials,
+        SyncFolder,
+    },
 };
 
 #[derive(Debug, Serialize)]
@@ -46,6 +62,51 @@ pub async fn start_oauth2_flow(
         account_id,
         redirect_uri: request.redirect_uri.clone(),
         created_at: chrono::Utc::now(),
+        reauthorization: false,
+    };
+
+    state
+        .oauth_state_manager
+        .store(csrf_token.clone(), oauth_state)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(StartOAuth2Response {
+        auth_url,
+        csrf_token,
+    })
+}
+
+/// Start an OAuth flow granting an existing account `scopes` in addition to
+/// the ones it has, e.g. contacts or calendar access. The account keeps its
+/// data; its stored credentials are replaced once the code is exchanged.
+#[tauri::command]
+pub async fn reauthorize_account(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    scopes: Vec<OAuthScope>,
+    redirect_uri: String,
+) -> Result<StartOAuth2Response, String> {
+    let account = RepositoryFactory::new(state.db_pool.clone())
+        .account_repository()
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+    let provider = account.account_type.to_string();
+
+    let (auth_url, csrf_token, pkce_verifier) =
+        OAuth2Helper::start_reauthorization(&provider, &redirect_uri, &scopes)
+            .map_err(|e| e.to_string())?;
+
+    let oauth_state = crate::sync::oauth_state::OAuthState {
+        csrf_token: csrf_token.clone(),
+        pkce_verifier,
+        provider,
+        account_id,
+        redirect_uri,
+        created_at: chrono::Utc::now(),
+        reauthorization: true,
     };
 
     state
@@ -135,6 +196,10 @@ pub async fn exchange_oauth2_code(
     .await
     .map_err(|e| e.to_string())?;
 
+    if oauth_state.reauthorization {
+        return store_reauthorized_credentials(&state, oauth_state.account_id, credentials).await;
+    }
+
     state
         .credential_store
         .store_oauth2(oauth_state.account_id, &credentials)
@@ -146,6 +211,36 @@ pub async fn exchange_oauth2_code(
         oauth_state.account_id
     );
 
+    // Fetch the owner's identity in the background so the OAuth window can close right away
+    let pool = state.db_pool.clone();
+    let credential_store = state.credential_store.clone();
+    let photo_dir = account_photo_dir(&state);
+    let app_handle = state.app_handle.clone();
+    let account_id = oauth_state.account_id;
+    tauri::async_runtime::spawn(async move {
+        match account_profile::refresh_account_profile(
+            &pool,
+            credential_store.clone(),
+            account_id,
+            &photo_dir,
+        )
+        .await
+        {
+            Ok(Some(account)) => {
+                if let Err(e) = app_lock::emit_mail_event(&app_handle, "account:updated", &account)
+                {
+                    log::warn!("Failed to emit account:updated for {}: {}", account_id, e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to fetch profile for account {}: {}", account_id, e),
+        }
+
+        if let Err(e) = identities::refresh_identities(&pool, credential_store, account_id).await {
+            log::warn!("Failed to fetch aliases for account {}: {}", account_id, e);
+        }
+    });
+
     if let Err(e) = state
         .background_sync_manager
         .start_account_sync(&oauth_state.account_id)
@@ -162,6 +257,60 @@ pub async fn exchange_oauth2_code(
     Ok("OAuth2 authentication successful".to_string())
 }
 
+/// Replace the credentials of an account that granted more scopes. Cached
+/// sync managers are dropped so their providers load the new token instead
+/// of using the old one until it expires.
+async fn store_reauthorized_credentials(
+    state: &AppState,
+    account_id: Uuid,
+    mut credentials: OAuth2Credentials,
+) -> Result<String, String> {
+    if let Ok(previous) = state.credential_store.get_oauth2(account_id).await {
+        // A consent for more scopes does not always come with a new refresh
+        // token, the earlier one stays valid for all of them
+        if credentials.refresh_token.is_none() {
+            credentials.refresh_token = previous.refresh_token;
+        }
+        for scope in previous.scopes {
+            if !credentials.scopes.contains(&scope) {
+                credentials.scopes.push(scope);
+            }
+        }
+    }
+
+    state
+        .credential_store
+        .store_oauth2(account_id, &credentials)
+        .await
+        .map_err(|e| e.to_string())?;
+    state.sync_coordinator.invalidate_account(account_id).await;
+
+    log::info!(
+        "OAuth2 reauthorization successful for account {} ({} scopes)",
+        account_id,
+        credentials.scopes.len()
+    );
+    events::emit_event(
+        &state.app_handle,
+        "credentials:updated",
+        CredentialsUpdatedEvent {
+            account_id,
+            scopes: credentials.scopes,
+        },
+    );
+
+    Ok("OAuth2 reauthorization successful".to_string())
+}
+
+/// Look up the IMAP and SMTP servers of an address to prefill manual account
+/// setup. `None` when none of the sources knows them.
+#[tauri::command]
+pub async fn discover_account_settings(
+    email: String,
+) -> Result<Option<DiscoveredSettings>, String> {
+    autoconfig::discover(&email).await
+}
+
 #[derive(Debug, Deserialize)]
 pub struct StoreImapCredentialsRequest {
     pub account_id: Uuid,
@@ -174,14 +323,28 @@ pub async fn store_imap_credentials(
     state: State<'_, AppState>,
     request: StoreImapCredentialsRequest,
 ) -> Result<String, String> {
+    let repo_factory = RepositoryFactory::new(state.db_pool.clone());
+    let account = repo_factory
+        .account_repository()
+        .find_by_id(request.account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", request.account_id))?;
+
     let credentials = ImapCredentials {
         username: request.username,
         password: request.password,
     };
 
-    state
-        .credential_store
-        .store_imap(request.account_id, &credentials)
+    // Sessions logged in with the previous credentials are not reused
+    imap_pool::clear(request.account_id);
+
+    // Log in before storing the credentials, so a wrong password or an
+    // untrusted certificate is reported right away
+    let mut provider = ProviderFactory::create(&account, state.credential_store.clone())
+        .map_err(|e| e.to_string())?;
+    provider
+        .authenticate(ProviderCredentials::Imap(credentials))
         .await
         .map_err(|e| e.to_string())?;
 
@@ -257,9 +420,27 @@ pub async fn sync_folder(
         hidden: folder_model.hidden,
     };
 
+    let full = full.unwrap_or(false);
+
+    // Go through the account's sync queue while background sync runs, so the
+    // request jumps ahead of scheduled folders instead of racing them
+    let account = repo_factory
+        .account_repository()
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+    if let Some(result) = state
+        .background_sync_manager
+        .sync_folder_now(&account, folder.clone(), full)
+        .await
+    {
+        return result;
+    }
+
     let count = state
         .sync_coordinator
-        .sync_folder(account_id, &folder, full.unwrap_or(false))
+        .sync_folder(account_id, &folder, full)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -322,6 +503,8 @@ pub async fn create_account(
     let repo_factory = RepositoryFactory::new(state.db_pool.clone());
     let account_repo = repo_factory.account_repository();
 
+    let account_type = AccountType::from(request.account_type);
+
     let settings = if let Some(settings) = request.settings {
         settings
     } else {
@@ -329,12 +512,29 @@ pub async fn create_account(
             .map_err(|e| format!("Failed to serialize default settings: {}", e))?
     };
 
+    let settings = if account_type == AccountType::Apple {
+        let mut parsed: AccountSettings = serde_json::from_value(settings)
+            .map_err(|e| format!("Invalid account settings: {}", e))?;
+        icloud::apply_server_presets(&mut parsed, &request.email);
+        serde_json::to_value(parsed)
+            .map_err(|e| format!("Failed to serialize account settings: {}", e))?
+    } else {
+        settings
+    };
+
     let account = Account {
         id: Uuid::now_v7(),
         name: request.name,
         email: request.email,
-        account_type: AccountType::from(request.account_type),
+        account_type,
         settings,
+        display_name: None,
+        photo_path: None,
+        timezone: None,
+        locale: None,
+        profile_synced_at: None,
+        color: None,
+        avatar_path: None,
         created_at: chrono::Utc::now(),
         updated_at: chrono::Utc::now(),
     };
@@ -347,6 +547,319 @@ pub async fn create_account(
     Ok(account)
 }
 
+fn account_photo_dir(state: &State<'_, AppState>) -> std::path::PathBuf {
+    state.app_data_dir.join("avatar_cache").join("accounts")
+}
+
+/// Re-fetch the owner's name, photo, timezone and locale from the provider
+#[tauri::command]
+pub async fn refresh_account_profile(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<Account, String> {
+    let photo_dir = account_photo_dir(&state);
+    let refreshed = account_profile::refresh_account_profile(
+        &state.db_pool,
+        state.credential_store.clone(),
+        account_id,
+        &photo_dir,
+    )
+    .await?;
+
+    let account = match refreshed {
+        Some(account) => account,
+        None => RepositoryFactory::new(state.db_pool.clone())
+            .account_repository()
+            .find_by_id(account_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Account {} not found", account_id))?,
+    };
+
+    if let Err(e) = app_lock::emit_mail_event(&state.app_handle, "account:updated", &account) {
+        log::warn!("Failed to emit account:updated for {}: {}", account_id, e);
+    }
+
+    Ok(account)
+}
+
+/// Storage usage of an account's mailbox. Fetched from the provider when
+/// `refresh` is set or no quota was stored yet, otherwise the one stored by
+/// the last sync is returned.
+#[tauri::command]
+pub async fn get_account_quota(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    refresh: Option<bool>,
+) -> Result<AccountQuota, String> {
+    let account_repo = RepositoryFactory::new(state.db_pool.clone()).account_repository();
+
+    if !refresh.unwrap_or(false) {
+        let stored = account_repo
+            .find_quota(account_id)
+            .await
+            .map_err(|e| format!("Failed to load quota: {}", e))?;
+        if let Some(quota) = stored {
+            return Ok(quota);
+        }
+    }
+
+    let account = account_repo
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    mailbox_quota::refresh_quota(&state.db_pool, state.credential_store.clone(), &account).await
+}
+
+/// Choose how deleting a message behaves on an IMAP account
+#[tauri::command]
+pub async fn set_imap_delete_policy(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    policy: ImapDeletePolicy,
+) -> Result<Account, String> {
+    let account_repo = RepositoryFactory::new(state.db_pool.clone()).account_repository();
+
+    let mut account = account_repo
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    if !matches!(account.account_type, AccountType::Imap | AccountType::Apple) {
+        return Err("Delete policies only apply to IMAP accounts".to_string());
+    }
+
+    let mut settings: AccountSettings = match &account.settings {
+        serde_json::Value::String(s) => serde_json::from_str(s),
+        value => serde_json::from_value(value.clone()),
+    }
+    .map_err(|e| format!("Invalid account settings: {}", e))?;
+    settings.imap_delete_policy = policy;
+
+    account.settings = serde_json::to_value(settings)
+        .map_err(|e| format!("Failed to serialize account settings: {}", e))?;
+    account.updated_at = chrono::Utc::now();
+
+    account_repo
+        .update(&account)
+        .await
+        .map_err(|e| format!("Failed to update account: {}", e))?;
+
+    if let Err(e) = app_lock::emit_mail_event(&state.app_handle, "account:updated", &account) {
+        log::warn!("Failed to emit account:updated for {}: {}", account_id, e);
+    }
+
+    Ok(account)
+}
+
+/// Whether `color` is a `#rrggbb` hex color, as the color picker produces
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Set or with `None` clear the color marking the account's folders and emails
+#[tauri::command]
+pub async fn set_account_color(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    color: Option<String>,
+) -> Result<Account, String> {
+    if color.as_deref().is_some_and(|color| !is_hex_color(color)) {
+        return Err("Account colors must be hex colors like #3B82F6".to_string());
+    }
+
+    let account_repo = RepositoryFactory::new(state.db_pool.clone()).account_repository();
+    let account = account_repo
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    account_repo
+        .update_appearance(account_id, color.as_deref(), account.avatar_path.as_deref())
+        .await
+        .map_err(|e| format!("Failed to update account: {}", e))?;
+
+    updated_account(&state, account_id).await
+}
+
+/// Use the picture at `path` for the account, or with `None` go back to the
+/// owner photo from the provider. The picture is copied into the app data.
+#[tauri::command]
+pub async fn set_account_avatar(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    path: Option<String>,
+) -> Result<Account, String> {
+    let account_repo = RepositoryFactory::new(state.db_pool.clone()).account_repository();
+    let account = account_repo
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    let avatar_path = match path {
+        Some(path) => {
+            let source = std::path::Path::new(&path);
+            let ext = source
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(str::to_lowercase)
+                .filter(|ext| matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "webp" | "gif"))
+                .ok_or_else(|| {
+                    "Account pictures must be PNG, JPEG, WebP or GIF images".to_string()
+                })?;
+
+            let avatar_dir = state.app_data_dir.join("account_avatars");
+            tokio::fs::create_dir_all(&avatar_dir)
+                .await
+                .map_err(|e| format!("Failed to create avatar directory: {}", e))?;
+
+            // Named per upload, so webviews don't keep showing a cached picture
+            let destination = avatar_dir.join(format!("{}-{}.{}", account_id, Uuid::now_v7(), ext));
+            tokio::fs::copy(source, &destination)
+                .await
+                .map_err(|e| format!("Failed to copy {}: {}", path, e))?;
+            Some(destination.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+
+    account_repo
+        .update_appearance(account_id, account.color.as_deref(), avatar_path.as_deref())
+        .await
+        .map_err(|e| format!("Failed to update account: {}", e))?;
+
+    if let Some(previous) = &account.avatar_path {
+        if let Err(e) = tokio::fs::remove_file(previous).await {
+            log::debug!("Could not remove previous avatar {}: {}", previous, e);
+        }
+    }
+
+    updated_account(&state, account_id).await
+}
+
+async fn updated_account(state: &State<'_, AppState>, account_id: Uuid) -> Result<Account, String> {
+    let account = RepositoryFactory::new(state.db_pool.clone())
+        .account_repository()
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    if let Err(e) = app_lock::emit_mail_event(&state.app_handle, "account:updated", &account) {
+        log::warn!("Failed to emit account:updated for {}: {}", account_id, e);
+    }
+
+    Ok(account)
+}
+
+/// Trust the certificate with `fingerprint` for the account's IMAP or SMTP
+/// server although it fails validation, or stop trusting one with `None`
+#[tauri::command]
+pub async fn trust_server_certificate(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    server: MailServer,
+    fingerprint: Option<String>,
+) -> Result<Account, String> {
+    let account_repo = RepositoryFactory::new(state.db_pool.clone()).account_repository();
+
+    let mut account = account_repo
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    if let Some(fingerprint) = &fingerprint {
+        // A SHA-256 fingerprint has 64 hex digits
+        if fingerprint.chars().filter(char::is_ascii_hexdigit).count() != 64 {
+            return Err(format!("Invalid SHA-256 fingerprint: {}", fingerprint));
+        }
+    }
+
+    let mut settings: AccountSettings = match &account.settings {
+        serde_json::Value::String(s) => serde_json::from_str(s),
+        value => serde_json::from_value(value.clone()),
+    }
+    .map_err(|e| format!("Invalid account settings: {}", e))?;
+    match server {
+        MailServer::Imap => settings.imap_trusted_certificate = fingerprint,
+        MailServer::Smtp => settings.smtp_trusted_certificate = fingerprint,
+    }
+
+    account.settings = serde_json::to_value(settings)
+        .map_err(|e| format!("Failed to serialize account settings: {}", e))?;
+    account.updated_at = chrono::Utc::now();
+
+    account_repo
+        .update(&account)
+        .await
+        .map_err(|e| format!("Failed to update account: {}", e))?;
+    // Pooled sessions were opened under the previous trust
+    imap_pool::clear(account_id);
+
+    if let Err(e) = app_lock::emit_mail_event(&state.app_handle, "account:updated", &account) {
+        log::warn!("Failed to emit account:updated for {}: {}", account_id, e);
+    }
+
+    Ok(account)
+}
+
+/// Set how many days of mail the account syncs, `None` for its whole history.
+/// Folders are backfilled again when the history reaches further back.
+#[tauri::command]
+pub async fn set_sync_history(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+    days: Option<u32>,
+) -> Result<Account, String> {
+    if days == Some(0) {
+        return Err("At least one day of mail has to be synced".to_string());
+    }
+
+    let account_repo = RepositoryFactory::new(state.db_pool.clone()).account_repository();
+
+    let mut account = account_repo
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    let mut settings: AccountSettings = match &account.settings {
+        serde_json::Value::String(s) => serde_json::from_str(s),
+        value => serde_json::from_value(value.clone()),
+    }
+    .map_err(|e| format!("Invalid account settings: {}", e))?;
+    settings.sync_history_days = days;
+
+    account.settings = serde_json::to_value(settings)
+        .map_err(|e| format!("Failed to serialize account settings: {}", e))?;
+    account.updated_at = chrono::Utc::now();
+
+    account_repo
+        .update(&account)
+        .await
+        .map_err(|e| format!("Failed to update account: {}", e))?;
+
+    backfill::restart(
+        &state.db_pool,
+        account_id,
+        backfill::horizon(chrono::Utc::now(), days),
+    )
+    .await
+    .map_err(|e| format!("Failed to restart backfill: {}", e))?;
+
+    if let Err(e) = app_lock::emit_mail_event(&state.app_handle, "account:updated", &account) {
+        log::warn!("Failed to emit account:updated for {}: {}", account_id, e);
+    }
+
+    Ok(account)
+}
+
 #[tauri::command]
 pub async fn get_accounts(state: State<'_, AppState>) -> Result<Vec<Account>, String> {
     let repo_factory = RepositoryFactory::new(state.db_pool.clone());
@@ -363,6 +876,7 @@ pub async fn delete_account(
     account_id: Uuid,
 ) -> Result<String, String> {
     let _ = state.credential_store.delete(account_id).await;
+    imap_pool::clear(account_id);
 
     let repo_factory = RepositoryFactory::new(state.db_pool.clone());
     let account_repo = repo_factory.account_repository();
@@ -554,7 +1068,62 @@ pub async fn get_sync_health(
     })
 }
 
+/// Credential, sync, queue and quota diagnostics of an account, with the
+/// issues found in them
+#[tauri::command]
+pub async fn get_account_health(
+    state: State<'_, AppState>,
+    account_id: Uuid,
+) -> Result<AccountHealth, String> {
+    let account = RepositoryFactory::new(state.db_pool.clone())
+        .account_repository()
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+    let is_syncing = state.background_sync_manager.is_syncing(&account_id).await;
+
+    account_health::check_account_health(
+        &state.db_pool,
+        state.credential_store.clone(),
+        &account,
+        is_syncing,
+    )
+    .await
+}
+
+/// Pause syncing of an account. Background workers leave it alone until it
+/// is resumed, also after a restart.
+#[tauri::command]
+pub async fn pause_account(state: State<'_, AppState>, account_id: Uuid) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .sync_state_repository()
+        .set_account_paused(account_id, true)
+        .await
+        .map_err(|e| format!("Failed to pause sync of account {}: {}", account_id, e))
+}
+
+#[tauri::command]
+pub async fn resume_account(state: State<'_, AppState>, account_id: Uuid) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .sync_state_repository()
+        .set_account_paused(account_id, false)
+        .await
+        .map_err(|e| format!("Failed to resume sync of account {}: {}", account_id, e))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UndeleteEmailsRequest {
     pub account_id: Uuid,
 }
+
+#[tauri::command]
+pub async fn get_network_usage(
+    state: State<'_, AppState>,
+    account_id: Option<Uuid>,
+    days: Option<u32>,
+) -> Result<NetworkUsageReport, String> {
+    network_usage::load_report(&state.db_pool, account_id, days.unwrap_or(30).max(1))
+        .await
+        .map_err(|e| format!("Failed to load network usage: {}", e))
+}
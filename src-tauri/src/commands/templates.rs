@@ -0,0 +1,149 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        models::template::Template,
+        repositories::{RepositoryFactory, TemplateRepository},
+    },
+    services::template_renderer,
+    state::AppState,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTemplateRequest {
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTemplateRequest {
+    pub id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+fn validate_template(name: &str, subject: Option<&str>, body: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+
+    let mut unknown = template_renderer::unknown_placeholders(body);
+    unknown.extend(
+        subject
+            .map(template_renderer::unknown_placeholders)
+            .unwrap_or_default(),
+    );
+    if let Some(name) = unknown.first() {
+        return Err(format!(
+            "Unknown placeholder {{{{{}}}}}, available: {}",
+            name,
+            template_renderer::TEMPLATE_VARIABLES.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Templates available to an account (including shared ones), or all templates
+#[tauri::command]
+pub async fn get_templates(
+    state: State<'_, AppState>,
+    account_id: Option<Uuid>,
+) -> Result<Vec<Template>, String> {
+    let template_repo = RepositoryFactory::new(state.db_pool.clone()).template_repository();
+
+    match account_id {
+        Some(account_id) => template_repo.find_for_account(account_id).await,
+        None => template_repo.get_all().await,
+    }
+    .map_err(|e| format!("Failed to get templates: {}", e))
+}
+
+#[tauri::command]
+pub async fn create_template(
+    state: State<'_, AppState>,
+    request: CreateTemplateRequest,
+) -> Result<Template, String> {
+    validate_template(&request.name, request.subject.as_deref(), &request.body)?;
+
+    let template = Template {
+        id: Uuid::now_v7(),
+        account_id: request.account_id,
+        name: request.name.trim().to_string(),
+        subject: request.subject.filter(|s| !s.trim().is_empty()),
+        body: request.body,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    RepositoryFactory::new(state.db_pool.clone())
+        .template_repository()
+        .create(&template)
+        .await
+        .map_err(|e| format!("Failed to create template: {}", e))?;
+
+    Ok(template)
+}
+
+#[tauri::command]
+pub async fn update_template(
+    state: State<'_, AppState>,
+    request: UpdateTemplateRequest,
+) -> Result<Template, String> {
+    validate_template(&request.name, request.subject.as_deref(), &request.body)?;
+
+    let template_repo = RepositoryFactory::new(state.db_pool.clone()).template_repository();
+    let existing = template_repo
+        .find_by_id(request.id)
+        .await
+        .map_err(|e| format!("Failed to find template: {}", e))?
+        .ok_or_else(|| format!("Template {} not found", request.id))?;
+
+    let template = Template {
+        id: existing.id,
+        account_id: request.account_id,
+        name: request.name.trim().to_string(),
+        subject: request.subject.filter(|s| !s.trim().is_empty()),
+        body: request.body,
+        created_at: existing.created_at,
+        updated_at: Utc::now(),
+    };
+
+    template_repo
+        .update(&template)
+        .await
+        .map_err(|e| format!("Failed to update template: {}", e))?;
+
+    Ok(template)
+}
+
+#[tauri::command]
+pub async fn delete_template(state: State<'_, AppState>, template_id: Uuid) -> Result<(), String> {
+    RepositoryFactory::new(state.db_pool.clone())
+        .template_repository()
+        .delete(template_id)
+        .await
+        .map_err(|e| format!("Failed to delete template: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_template() {
+        assert!(
+            validate_template("Follow-up", Some("Re: {{company}}"), "Hi {{first_name}}").is_ok()
+        );
+        assert!(validate_template(" ", None, "Hi").is_err());
+        assert!(validate_template("Follow-up", None, "Hi {{name}}").is_err());
+        assert!(validate_template("Follow-up", Some("{{subject}}"), "Hi").is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::services::custom_themes::{self, CustomTheme, ThemeStore};
+use crate::services::theme_scheduler::{Appearance, ThemeModeSettings};
 use crate::state::AppState;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -9,6 +11,8 @@ pub struct ThemeInfo {
     pub id: String,
     pub name: String,
     pub source: ThemeSource,
+    /// Set for user themes, which declare whether they are light or dark
+    pub appearance: Option<Appearance>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +54,7 @@ pub async fn list_themes(state: State<'_, AppState>) -> Result<Vec<ThemeInfo>, S
                             id: format!("builtin/{}", filename),
                             name: name.to_string(),
                             source: ThemeSource::Builtin,
+                            appearance: None,
                         });
                     }
                 }
@@ -63,16 +68,23 @@ pub async fn list_themes(state: State<'_, AppState>) -> Result<Vec<ThemeInfo>, S
     log::debug!("Scanning for user themes in: {}", user_themes_dir.display());
     log::debug!("User themes directory exists: {}", user_themes_dir.exists());
 
+    let store = ThemeStore::new(&state.app_data_dir);
+
     if user_themes_dir.exists() {
         if let Ok(entries) = fs::read_dir(&user_themes_dir) {
             for entry in entries.flatten() {
                 if let Some(filename) = entry.file_name().to_str() {
                     if filename.ends_with(".css") {
-                        let name = filename.trim_end_matches(".css");
+                        let slug = filename.trim_end_matches(".css");
+                        // Themes created in the app carry their display name
+                        let custom = store.load(slug).ok();
                         themes.push(ThemeInfo {
                             id: format!("user/{}", filename),
-                            name: name.to_string(),
+                            name: custom
+                                .as_ref()
+                                .map_or_else(|| slug.to_string(), |theme| theme.name.clone()),
                             source: ThemeSource::User,
+                            appearance: custom.map(|theme| theme.appearance),
                         });
                     }
                 }
@@ -102,27 +114,140 @@ pub async fn switch_theme(state: State<'_, AppState>, theme_id: String) -> Resul
     // Validate that the theme exists by trying to get it
     let content = get_theme(state.clone(), theme_id.clone()).await?;
 
-    // Save the theme preference to settings
+    // Save the theme preference to settings; in automatic modes the theme
+    // replaces the one used for the appearance that is currently active
+    let key = match state.theme_scheduler.effective_appearance() {
+        Some(Appearance::Light) => "appearance.lightTheme",
+        Some(Appearance::Dark) => "appearance.darkTheme",
+        None => "appearance.theme",
+    };
     state
         .settings
-        .set("appearance.theme", serde_json::json!(theme_id))
+        .set(key, serde_json::json!(theme_id))
         .map_err(|e| format!("Failed to save theme preference: {}", e))?;
+    state.theme_scheduler.apply();
 
     Ok(content)
 }
 
-/// Get the currently selected theme
+/// Get the currently active theme, taking the theme mode into account
 #[tauri::command]
 pub async fn get_current_theme(state: State<'_, AppState>) -> Result<String, String> {
-    state
-        .settings
-        .get::<String>("appearance.theme")
-        .map_err(|_| "builtin/light.css".to_string())
-        .or(Ok("builtin/light.css".to_string()))
+    Ok(state.theme_scheduler.effective_theme())
+}
+
+/// Get the theme mode and the themes used for light and dark appearance
+#[tauri::command]
+pub async fn get_theme_mode(state: State<'_, AppState>) -> Result<ThemeModeSettings, String> {
+    Ok(state.theme_scheduler.mode_settings())
+}
+
+/// Save the theme mode and re-apply the effective theme
+#[tauri::command]
+pub async fn set_theme_mode(
+    state: State<'_, AppState>,
+    mode_settings: ThemeModeSettings,
+) -> Result<String, String> {
+    for theme_id in [&mode_settings.light_theme, &mode_settings.dark_theme] {
+        let theme_path = resolve_theme_path(&state, theme_id)?;
+        validate_theme_path(&state, &theme_path)?;
+    }
+
+    state.theme_scheduler.save_mode_settings(&mode_settings)?;
+
+    Ok(state.theme_scheduler.effective_theme())
+}
+
+/// Create a user theme with the tokens of another theme
+#[tauri::command]
+pub async fn create_theme(
+    state: State<'_, AppState>,
+    name: String,
+    base_theme_id: String,
+    appearance: Appearance,
+) -> Result<ThemeInfo, String> {
+    let base_css = get_theme(state.clone(), base_theme_id.clone()).await?;
+
+    let theme = CustomTheme {
+        version: custom_themes::THEME_FILE_VERSION,
+        name,
+        appearance,
+        base: Some(base_theme_id),
+        tokens: custom_themes::parse_css_tokens(&base_css),
+    };
+    let theme_id = ThemeStore::new(&state.app_data_dir).create(&theme)?;
+
+    Ok(theme_info(theme_id, &theme))
+}
+
+/// Get the editable tokens of a user theme
+#[tauri::command]
+pub async fn get_custom_theme(
+    state: State<'_, AppState>,
+    theme_id: String,
+) -> Result<CustomTheme, String> {
+    let slug = ThemeStore::slug_from_id(&theme_id)?;
+    ThemeStore::new(&state.app_data_dir).load(slug)
+}
+
+/// Save the name, appearance and tokens of a user theme, re-applying it
+/// when it is in use
+#[tauri::command]
+pub async fn update_theme(
+    state: State<'_, AppState>,
+    theme_id: String,
+    theme: CustomTheme,
+) -> Result<ThemeInfo, String> {
+    let slug = ThemeStore::slug_from_id(&theme_id)?;
+    ThemeStore::new(&state.app_data_dir).save(slug, &theme)?;
+
+    if state.theme_scheduler.effective_theme() == theme_id {
+        state.theme_scheduler.refresh();
+    }
+
+    Ok(theme_info(theme_id, &theme))
+}
+
+/// Delete a user theme; settings using it go back to the default themes
+#[tauri::command]
+pub async fn delete_theme(state: State<'_, AppState>, theme_id: String) -> Result<(), String> {
+    let slug = ThemeStore::slug_from_id(&theme_id)?;
+    ThemeStore::new(&state.app_data_dir).delete(slug)?;
+
+    state.theme_scheduler.forget_theme(&theme_id)
+}
+
+/// Add a user theme from an exported theme file
+#[tauri::command]
+pub async fn import_theme(state: State<'_, AppState>, path: String) -> Result<ThemeInfo, String> {
+    let theme = ThemeStore::read_file(Path::new(&path))?;
+    let theme_id = ThemeStore::new(&state.app_data_dir).create(&theme)?;
+
+    Ok(theme_info(theme_id, &theme))
+}
+
+/// Write a user theme to a theme file others can import
+#[tauri::command]
+pub async fn export_theme(
+    state: State<'_, AppState>,
+    theme_id: String,
+    destination: String,
+) -> Result<(), String> {
+    let slug = ThemeStore::slug_from_id(&theme_id)?;
+    ThemeStore::new(&state.app_data_dir).export(slug, Path::new(&destination))
 }
 
 // Helper functions
 
+fn theme_info(theme_id: String, theme: &CustomTheme) -> ThemeInfo {
+    ThemeInfo {
+        id: theme_id,
+        name: theme.name.trim().to_string(),
+        source: ThemeSource::User,
+        appearance: Some(theme.appearance),
+    }
+}
+
 fn resolve_theme_path(state: &AppState, theme_id: &str) -> Result<PathBuf, String> {
     log::debug!("Resolving theme path for: {}", theme_id);
 
@@ -1,13 +1,18 @@
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use tauri::State;
+use url::form_urlencoded::Serializer;
 use uuid::Uuid;
 
 use crate::{
     database::{
-        models::view::{SwimlaneState, View, ViewConfig, ViewType},
-        repositories::{RepositoryFactory, ViewRepository},
+        models::{
+            session::SessionWindowType,
+            view::{SwimlaneState, View, ViewConfig, ViewType},
+        },
+        repositories::{EmailRepository, RepositoryFactory, ViewRepository},
     },
+    session,
     state::AppState,
 };
 
@@ -175,3 +180,111 @@ pub async fn delete_view(state: State<'_, AppState>, view_id: String) -> Result<
         .await
         .map_err(|e| format!("Failed to delete view: {}", e))
 }
+
+/// What a compose window starts with. Addresses, subject and body seed a new
+/// message; the ids open a draft, a reply or a forward instead.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ComposeWindowRequest {
+    pub account_id: Option<Uuid>,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+    pub draft_id: Option<Uuid>,
+    pub reply_to: Option<Uuid>,
+    pub reply_all: bool,
+    pub forward: Option<Uuid>,
+}
+
+impl ComposeWindowRequest {
+    /// Router path of the compose window, carrying the request as its query
+    pub fn route(&self) -> String {
+        let mut query = Serializer::new(String::new());
+        if let Some(account_id) = self.account_id {
+            query.append_pair("account", &account_id.to_string());
+        }
+        for (name, addresses) in [("to", &self.to), ("cc", &self.cc), ("bcc", &self.bcc)] {
+            for address in addresses {
+                query.append_pair(name, address);
+            }
+        }
+        if let Some(subject) = &self.subject {
+            query.append_pair("subject", subject);
+        }
+        if let Some(body) = &self.body {
+            query.append_pair("body", body);
+        }
+        if let Some(draft_id) = self.draft_id {
+            query.append_pair("draft", &draft_id.to_string());
+        }
+        if let Some(reply_to) = self.reply_to {
+            query.append_pair("reply", &reply_to.to_string());
+            if self.reply_all {
+                query.append_pair("all", "1");
+            }
+        }
+        if let Some(forward) = self.forward {
+            query.append_pair("forward", &forward.to_string());
+        }
+
+        let query = query.finish();
+        if query.is_empty() {
+            "/windows/compose".to_string()
+        } else {
+            format!("/windows/compose?{}", query)
+        }
+    }
+}
+
+/// Open the composer in a window of its own, so mail can be read while writing
+#[tauri::command]
+pub async fn open_compose_window(
+    state: State<'_, AppState>,
+    request: Option<ComposeWindowRequest>,
+) -> Result<(), String> {
+    let request = request.unwrap_or_default();
+    let title = request
+        .subject
+        .as_deref()
+        .filter(|subject| !subject.trim().is_empty())
+        .unwrap_or("New Message");
+
+    session::open_detached_window(
+        &state.app_handle,
+        state.db_pool.clone(),
+        &session::compose_window_label(),
+        SessionWindowType::Compose,
+        request.route(),
+        title,
+    )
+    .await
+}
+
+/// Open an email in a window of its own, or focus its window if already open
+#[tauri::command]
+pub async fn open_email_window(state: State<'_, AppState>, email_id: String) -> Result<(), String> {
+    let id = Uuid::parse_str(&email_id).map_err(|e| format!("Invalid email ID: {}", e))?;
+
+    let email = RepositoryFactory::new(state.db_pool.clone())
+        .email_repository()
+        .find_by_id(id)
+        .await
+        .map_err(|e| format!("Failed to fetch email: {}", e))?
+        .ok_or_else(|| format!("Email {} not found", email_id))?;
+    let title = email
+        .subject
+        .filter(|subject| !subject.trim().is_empty())
+        .unwrap_or_else(|| "Message".to_string());
+
+    session::open_detached_window(
+        &state.app_handle,
+        state.db_pool.clone(),
+        &session::message_window_label(id),
+        SessionWindowType::Message,
+        format!("/windows/email/{}", id),
+        &title,
+    )
+    .await
+}
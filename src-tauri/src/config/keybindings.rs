@@ -39,11 +39,160 @@ impl KeyAction {
 
 pub type KeyMapFile = Vec<KeyBinding>;
 
+/// Setting holding the file name of the bundled keymap user bindings apply on
+pub const DEFAULT_MAPPING_SETTING: &str = "keyboard.defaultMapping";
+
+/// Bundled keymaps modelled on other mail clients
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyScheme {
+    #[default]
+    Default,
+    Gmail,
+    Outlook,
+    Vim,
+}
+
+impl KeyScheme {
+    pub const ALL: [KeyScheme; 4] = [
+        KeyScheme::Default,
+        KeyScheme::Gmail,
+        KeyScheme::Outlook,
+        KeyScheme::Vim,
+    ];
+
+    /// File of the scheme in `resources/keymaps`
+    pub fn file_name(self) -> &'static str {
+        match self {
+            KeyScheme::Default => "default.json",
+            KeyScheme::Gmail => "gmail.json",
+            KeyScheme::Outlook => "outlook.json",
+            KeyScheme::Vim => "vim.json",
+        }
+    }
+
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scheme| scheme.file_name() == name)
+    }
+}
+
+/// Why two bindings get in each other's way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyConflictKind {
+    /// The same key in the same context
+    SameKey,
+    /// The same key globally and in a context, where the context's binding
+    /// wins while it is focused
+    Shadowed,
+    /// One key starts the other's sequence, so the longer one never fires
+    Prefix,
+}
+
+/// Another binding a key conflicts with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyConflict {
+    pub kind: KeyConflictKind,
+    pub context: String,
+    pub key: String,
+    pub action: String,
+}
+
+/// A binding of the merged keymap
+#[derive(Debug, Clone, Serialize)]
+pub struct BoundKey {
+    pub context: String,
+    pub key: String,
+    /// `None` for keys the user unbound
+    pub action: Option<String>,
+    pub props: Option<JsonValue>,
+    /// Set or unbound by the user rather than taken from the scheme
+    pub customized: bool,
+    pub conflicts: Vec<KeyConflict>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyBindingsOverview {
+    pub scheme: KeyScheme,
+    pub bindings: Vec<BoundKey>,
+}
+
+fn normalize_key(key: &str) -> String {
+    key.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether `prefix` is the first keystrokes of the sequence `key`
+fn starts_sequence(prefix: &str, key: &str) -> bool {
+    key.len() > prefix.len() && key.starts_with(prefix) && key[prefix.len()..].starts_with(' ')
+}
+
+/// Bindings of `keymap` that get in the way of binding `key` to `action` in
+/// `context`. Bindings to the same action are not conflicts.
+pub fn find_conflicts(
+    keymap: &KeyMapFile,
+    context: &str,
+    key: &str,
+    action: Option<&str>,
+) -> Vec<KeyConflict> {
+    let Some(action) = action else {
+        return Vec::new();
+    };
+    let key = normalize_key(key);
+
+    let mut conflicts: Vec<KeyConflict> = keymap
+        .iter()
+        .filter(|other| {
+            other.context == context || other.context == "global" || context == "global"
+        })
+        .flat_map(|other| {
+            other
+                .bindings
+                .iter()
+                .map(move |(other_key, other_action)| (other, other_key, other_action))
+        })
+        .filter_map(|(other, other_key, other_action)| {
+            let other_action = other_action.action()?;
+            if other_action == action {
+                return None;
+            }
+
+            let normalized = normalize_key(other_key);
+            let kind = if normalized == key {
+                if other.context == context {
+                    KeyConflictKind::SameKey
+                } else {
+                    KeyConflictKind::Shadowed
+                }
+            } else if starts_sequence(&normalized, &key) || starts_sequence(&key, &normalized) {
+                KeyConflictKind::Prefix
+            } else {
+                return None;
+            };
+
+            Some(KeyConflict {
+                kind,
+                context: other.context.clone(),
+                key: other_key.clone(),
+                action: other_action.to_string(),
+            })
+        })
+        .collect();
+
+    conflicts.sort_by(|a, b| (&a.context, &a.key).cmp(&(&b.context, &b.key)));
+    conflicts
+}
+
 #[derive(Debug, Clone)]
 pub struct KeyBindings {
     inner: Arc<RwLock<KeyMapFile>>,
     user_keymap_path: PathBuf,
-    default_keymap_path: PathBuf,
+    keymaps_dir: PathBuf,
+    scheme: Arc<RwLock<KeyScheme>>,
 }
 
 impl KeyBindings {
@@ -55,8 +204,15 @@ impl KeyBindings {
     ) -> Result<Self, ConfigError> {
         std::fs::create_dir_all(app_data_dir)?;
 
-        let default_name = default_mapping_name.unwrap_or_else(|| "default.json".to_string());
-        let default_keymap_path = resource_dir.join(format!("resources/keymaps/{}", default_name));
+        let scheme = match default_mapping_name {
+            Some(name) => KeyScheme::from_file_name(&name).unwrap_or_else(|| {
+                log::warn!("Unknown keymap {}, using the default keymap", name);
+                KeyScheme::Default
+            }),
+            None => KeyScheme::Default,
+        };
+        let keymaps_dir = resource_dir.join("resources/keymaps");
+        let default_keymap_path = keymaps_dir.join(scheme.file_name());
         let user_keymap_path = app_data_dir.join("keymap.json");
 
         // Create user keymap file if it doesn't exist
@@ -81,10 +237,15 @@ impl KeyBindings {
         Ok(Self {
             inner: Arc::new(RwLock::new(keymap)),
             user_keymap_path,
-            default_keymap_path,
+            keymaps_dir,
+            scheme: Arc::new(RwLock::new(scheme)),
         })
     }
 
+    fn default_keymap_path(&self) -> Result<PathBuf, ConfigError> {
+        Ok(self.keymaps_dir.join(self.scheme()?.file_name()))
+    }
+
     /// Load keymaps from default and user files, merging them appropriately
     fn load_keymaps(default_path: &Path, user_path: &Path) -> Result<KeyMapFile, ConfigError> {
         // Load default keymap with fallback
@@ -147,7 +308,7 @@ impl KeyBindings {
 
     /// Reload keybindings from disk files
     pub fn reload(&self) -> Result<(), ConfigError> {
-        let new_keymap = Self::load_keymaps(&self.default_keymap_path, &self.user_keymap_path)?;
+        let new_keymap = Self::load_keymaps(&self.default_keymap_path()?, &self.user_keymap_path)?;
 
         let mut keymap_guard = self.inner.write().map_err(|_| {
             ConfigError::AccessError("Failed to acquire write lock for keybindings".to_string())
@@ -259,4 +420,155 @@ impl KeyBindings {
 
         Ok(user_keymap)
     }
+
+    /// Scheme the user bindings apply on
+    pub fn scheme(&self) -> Result<KeyScheme, ConfigError> {
+        self.scheme.read().map(|scheme| *scheme).map_err(|_| {
+            ConfigError::AccessError("Failed to acquire read lock for keymap scheme".to_string())
+        })
+    }
+
+    /// Switch to another bundled keymap. User bindings are kept and apply on
+    /// top of it.
+    pub fn set_scheme(&self, scheme: KeyScheme) -> Result<(), ConfigError> {
+        {
+            let mut scheme_guard = self.scheme.write().map_err(|_| {
+                ConfigError::AccessError(
+                    "Failed to acquire write lock for keymap scheme".to_string(),
+                )
+            })?;
+            *scheme_guard = scheme;
+        }
+
+        self.reload()
+    }
+
+    /// Drop the user bindings of `context`, or all of them, so the scheme's
+    /// bindings apply again
+    pub fn reset_to_default(&self, context: Option<&str>) -> Result<(), ConfigError> {
+        let mut user_keymap = self.get_user_keymap()?;
+        match context {
+            Some(context) => user_keymap.retain(|c| c.context != context),
+            None => user_keymap.clear(),
+        }
+
+        let serialized = serde_json::to_string_pretty(&user_keymap)
+            .map_err(|e| ConfigError::AccessError(format!("Failed to serialize keymap: {}", e)))?;
+        std::fs::write(&self.user_keymap_path, serialized)?;
+
+        self.reload()
+    }
+
+    /// Bindings of the merged keymap that get in the way of binding `key` to
+    /// `action` in `context`
+    pub fn conflicts(
+        &self,
+        context: &str,
+        key: &str,
+        action: Option<&str>,
+    ) -> Result<Vec<KeyConflict>, ConfigError> {
+        let keymap = self.get_all()?;
+        let mut conflicts = find_conflicts(&keymap, context, key, action);
+        // Rebinding a key replaces its current binding
+        conflicts.retain(|conflict| {
+            !(conflict.kind == KeyConflictKind::SameKey
+                && conflict.context == context
+                && conflict.key == key)
+        });
+
+        Ok(conflicts)
+    }
+
+    /// Every binding of the merged keymap with its conflicts, sorted by
+    /// context and key
+    pub fn overview(&self) -> Result<KeyBindingsOverview, ConfigError> {
+        let keymap = self.get_all()?;
+        let user_keymap = self.get_user_keymap()?;
+
+        let mut bindings: Vec<BoundKey> = keymap
+            .iter()
+            .flat_map(|binding| {
+                binding.bindings.iter().map(|(key, action)| BoundKey {
+                    context: binding.context.clone(),
+                    key: key.clone(),
+                    action: action.action().map(ToOwned::to_owned),
+                    props: action.props().cloned(),
+                    customized: user_keymap.iter().any(|user| {
+                        user.context == binding.context && user.bindings.contains_key(key)
+                    }),
+                    conflicts: find_conflicts(&keymap, &binding.context, key, action.action())
+                        .into_iter()
+                        .filter(|conflict| {
+                            !(conflict.context == binding.context && &conflict.key == key)
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+        bindings.sort_by(|a, b| (&a.context, &a.key).cmp(&(&b.context, &b.key)));
+
+        Ok(KeyBindingsOverview {
+            scheme: self.scheme()?,
+            bindings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn context(name: &str, bindings: &[(&str, &str)]) -> KeyBinding {
+        KeyBinding {
+            context: name.to_string(),
+            bindings: bindings
+                .iter()
+                .map(|(key, action)| (key.to_string(), KeyAction::Simple(action.to_string())))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let keymap = vec![
+            context(
+                "global",
+                &[("c", "global:composeEmail"), ("g i", "global:home")],
+            ),
+            context(
+                "MailList",
+                &[
+                    ("e", "MailList:archiveEmail"),
+                    ("j", "MailList:focusNextEmail"),
+                ],
+            ),
+        ];
+
+        let conflicts = find_conflicts(&keymap, "MailList", "E", Some("MailList:deleteEmail"));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, KeyConflictKind::SameKey);
+
+        let conflicts = find_conflicts(&keymap, "MailList", "c", Some("MailList:replyToEmail"));
+        assert_eq!(conflicts[0].kind, KeyConflictKind::Shadowed);
+
+        let conflicts = find_conflicts(&keymap, "global", "g", Some("global:search"));
+        assert_eq!(conflicts[0].kind, KeyConflictKind::Prefix);
+        assert_eq!(conflicts[0].key, "g i");
+
+        // Other contexts and bindings to the same action do not conflict
+        assert!(find_conflicts(&keymap, "ConversationView", "e", Some("x")).is_empty());
+        assert!(find_conflicts(&keymap, "MailList", "e", Some("MailList:archiveEmail")).is_empty());
+        assert!(find_conflicts(&keymap, "MailList", "e", None).is_empty());
+    }
+
+    #[test]
+    fn test_bundled_schemes_parse() {
+        let keymaps_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/keymaps");
+        for scheme in KeyScheme::ALL {
+            let content = std::fs::read_to_string(keymaps_dir.join(scheme.file_name())).unwrap();
+            let keymap: KeyMapFile = serde_json::from_str(&content).unwrap();
+            assert!(!keymap.is_empty(), "{:?} keymap is empty", scheme);
+        }
+    }
 }
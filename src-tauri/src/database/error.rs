@@ -12,6 +12,9 @@ pub enum DatabaseError {
     #[error("Migration failed: {0}")]
     MigrationError(#[from] sqlx::migrate::MigrateError),
 
+    #[error("Database backup failed: {0}")]
+    BackupError(String),
+
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
@@ -0,0 +1,673 @@
+use crate::database::error::DatabaseError;
+use crate::sync::snippet_utils::extract_snippet;
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::migrate::{Migrate, MigrateError, Migration, Migrator};
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying [`MigrationProgress`] to the splash screen
+pub const PROGRESS_EVENT: &str = "migration:progress";
+
+const SNIPPET_BATCH_SIZE: i64 = 500;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationPhase {
+    /// Copying the database before schema migrations touch it
+    Backup,
+    Schema,
+    Data,
+    /// Restoring the copy after a schema migration failed
+    RollingBack,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationProgress {
+    pub phase: MigrationPhase,
+    /// Description of the running migration
+    pub step: String,
+    pub completed: u64,
+    pub total: u64,
+    pub error: Option<String>,
+}
+
+impl MigrationProgress {
+    fn new(phase: MigrationPhase, step: impl Into<String>, completed: u64, total: u64) -> Self {
+        Self {
+            phase,
+            step: step.into(),
+            completed,
+            total,
+            error: None,
+        }
+    }
+
+    fn failed(step: impl Into<String>, error: &DatabaseError) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Self::new(MigrationPhase::Failed, step, 0, 0)
+        }
+    }
+}
+
+/// Publishes migration progress as events, and keeps the latest report for
+/// windows that start listening late
+#[derive(Clone, Default)]
+pub struct MigrationReporter {
+    latest: Arc<RwLock<Option<MigrationProgress>>>,
+    app_handle: Option<AppHandle>,
+}
+
+impl MigrationReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_app_handle(mut self, app_handle: AppHandle) -> Self {
+        self.app_handle = Some(app_handle);
+        self
+    }
+
+    pub fn report(&self, progress: MigrationProgress) {
+        log::info!(
+            "[Migrations] {:?} {} ({}/{})",
+            progress.phase,
+            progress.step,
+            progress.completed,
+            progress.total
+        );
+
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(PROGRESS_EVENT, &progress) {
+                log::warn!("[Migrations] Failed to emit progress: {}", e);
+            }
+        }
+
+        if let Ok(mut latest) = self.latest.write() {
+            *latest = Some(progress);
+        }
+    }
+
+    pub fn latest(&self) -> Option<MigrationProgress> {
+        self.latest.read().ok().and_then(|latest| latest.clone())
+    }
+}
+
+/// One batch of a [`DataMigration`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataBatch {
+    pub processed: u64,
+    /// Where the next batch starts, or `None` once the migration is done
+    pub cursor: Option<String>,
+}
+
+/// A long-running rewrite of existing rows, run in batches after startup.
+/// Each batch commits together with its cursor, so an interrupted or failed
+/// migration resumes after the last committed batch.
+#[async_trait]
+pub trait DataMigration: Send + Sync {
+    /// Stable identifier recorded in `data_migrations`
+    fn id(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    /// Rows left to migrate after `cursor`
+    async fn remaining(
+        &self,
+        pool: &SqlitePool,
+        cursor: Option<&str>,
+    ) -> Result<u64, DatabaseError>;
+    /// Migrates the batch after `cursor` within the caller's transaction
+    async fn migrate_batch(
+        &self,
+        conn: &mut SqliteConnection,
+        cursor: Option<&str>,
+    ) -> Result<DataBatch, DatabaseError>;
+}
+
+/// Applies schema migrations with progress reporting and rollback, and runs
+/// the registered data migrations
+pub struct MigrationRunner {
+    migrator: Migrator,
+    data_migrations: Vec<Box<dyn DataMigration>>,
+    reporter: MigrationReporter,
+}
+
+impl MigrationRunner {
+    pub fn new(reporter: MigrationReporter) -> Self {
+        Self {
+            migrator: sqlx::migrate!("./migrations"),
+            data_migrations: vec![Box::new(BackfillSnippets)],
+            reporter,
+        }
+    }
+
+    pub fn reporter(&self) -> &MigrationReporter {
+        &self.reporter
+    }
+
+    /// Applies pending schema migrations to the database at `db_path`. An
+    /// existing database is copied first, and the copy is put back when a
+    /// migration fails, so the database is left at its previous version
+    /// rather than halfway between two.
+    pub async fn run_schema(&self, db_path: &Path) -> Result<(), DatabaseError> {
+        let database_url = format!("sqlite:{}", db_path.display());
+        let mut conn = SqliteConnection::connect(&database_url).await?;
+
+        conn.ensure_migrations_table().await?;
+        if let Some(version) = conn.dirty_version().await? {
+            return Err(MigrateError::Dirty(version).into());
+        }
+
+        let applied: HashMap<i64, _> = conn
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|migration| (migration.version, migration.checksum))
+            .collect();
+
+        let mut pending = Vec::new();
+        for migration in self.migrator.iter() {
+            if migration.migration_type.is_down_migration() {
+                continue;
+            }
+            match applied.get(&migration.version) {
+                Some(checksum) if *checksum != migration.checksum => {
+                    return Err(MigrateError::VersionMismatch(migration.version).into());
+                }
+                Some(_) => {}
+                None => pending.push(migration),
+            }
+        }
+
+        if pending.is_empty() {
+            conn.close().await?;
+            return Ok(());
+        }
+
+        // A new database has nothing to protect
+        let backup = if applied.is_empty() {
+            None
+        } else {
+            self.reporter.report(MigrationProgress::new(
+                MigrationPhase::Backup,
+                "Backing up the database",
+                0,
+                pending.len() as u64,
+            ));
+            Some(backup_database(&mut conn, db_path).await?)
+        };
+
+        let result = self.apply(&mut conn, &pending).await;
+        conn.close().await.ok();
+
+        match (result, backup) {
+            (Ok(()), backup) => {
+                if let Some(backup) = backup {
+                    if let Err(e) = std::fs::remove_file(&backup) {
+                        log::warn!("[Migrations] Failed to remove {}: {}", backup.display(), e);
+                    }
+                }
+                Ok(())
+            }
+            (Err((step, error)), Some(backup)) => {
+                self.reporter.report(MigrationProgress::new(
+                    MigrationPhase::RollingBack,
+                    step.clone(),
+                    0,
+                    pending.len() as u64,
+                ));
+                restore_database(&backup, db_path)?;
+                self.reporter
+                    .report(MigrationProgress::failed(step, &error));
+                Err(error)
+            }
+            (Err((step, error)), None) => {
+                self.reporter
+                    .report(MigrationProgress::failed(step, &error));
+                Err(error)
+            }
+        }
+    }
+
+    async fn apply(
+        &self,
+        conn: &mut SqliteConnection,
+        pending: &[&Migration],
+    ) -> Result<(), (String, DatabaseError)> {
+        let total = pending.len() as u64;
+
+        for (index, migration) in pending.iter().enumerate() {
+            let step = migration.description.to_string();
+            self.reporter.report(MigrationProgress::new(
+                MigrationPhase::Schema,
+                step.clone(),
+                index as u64,
+                total,
+            ));
+            conn.apply(migration)
+                .await
+                .map_err(|e| (step, DatabaseError::from(e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the data migrations that have not completed yet, in order. A
+    /// failed batch is rolled back and stops the run; the next start resumes
+    /// from the last committed batch.
+    pub async fn run_data(&self, pool: &SqlitePool) -> Result<(), DatabaseError> {
+        for migration in &self.data_migrations {
+            if let Err(e) = self.run_data_migration(pool, migration.as_ref()).await {
+                self.reporter
+                    .report(MigrationProgress::failed(migration.description(), &e));
+                record_data_failure(pool, migration.id(), &e).await;
+                return Err(e);
+            }
+        }
+
+        self.reporter.report(MigrationProgress::new(
+            MigrationPhase::Done,
+            "Migrations complete",
+            0,
+            0,
+        ));
+
+        Ok(())
+    }
+
+    async fn run_data_migration(
+        &self,
+        pool: &SqlitePool,
+        migration: &dyn DataMigration,
+    ) -> Result<(), DatabaseError> {
+        let state: Option<(String, Option<String>, i64)> =
+            sqlx::query_as("SELECT status, cursor, processed FROM data_migrations WHERE id = ?")
+                .bind(migration.id())
+                .fetch_optional(pool)
+                .await?;
+
+        let (mut cursor, mut processed) = match state {
+            Some((status, _, _)) if status == "completed" => return Ok(()),
+            Some((_, cursor, processed)) => (cursor, processed.max(0) as u64),
+            None => (None, 0),
+        };
+
+        let remaining = migration.remaining(pool, cursor.as_deref()).await?;
+        let total = processed + remaining;
+        log::info!(
+            "[Migrations] Running {} ({} of {} rows done)",
+            migration.id(),
+            processed,
+            total
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO data_migrations (id, status, cursor, processed)
+            VALUES (?, 'running', ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                status = 'running',
+                error_message = NULL,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(migration.id())
+        .bind(&cursor)
+        .bind(processed as i64)
+        .execute(pool)
+        .await?;
+
+        loop {
+            self.reporter.report(MigrationProgress::new(
+                MigrationPhase::Data,
+                migration.description(),
+                processed,
+                total,
+            ));
+
+            let mut tx = pool.begin().await?;
+            let batch = migration.migrate_batch(&mut tx, cursor.as_deref()).await?;
+            processed += batch.processed;
+
+            sqlx::query(
+                r#"
+                UPDATE data_migrations
+                SET cursor = ?,
+                    processed = ?,
+                    status = CASE WHEN ? THEN 'completed' ELSE 'running' END,
+                    completed_at = CASE WHEN ? THEN CURRENT_TIMESTAMP END,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = ?
+                "#,
+            )
+            .bind(&batch.cursor)
+            .bind(processed as i64)
+            .bind(batch.cursor.is_none())
+            .bind(batch.cursor.is_none())
+            .bind(migration.id())
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+
+            match batch.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        log::info!("[Migrations] Completed {}", migration.id());
+        Ok(())
+    }
+}
+
+async fn record_data_failure(pool: &SqlitePool, id: &str, error: &DatabaseError) {
+    let result = sqlx::query(
+        "UPDATE data_migrations SET status = 'failed', error_message = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(error.to_string())
+    .bind(id)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        log::error!("[Migrations] Failed to record failure of {}: {}", id, e);
+    }
+}
+
+fn sibling_path(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+async fn backup_database(
+    conn: &mut SqliteConnection,
+    db_path: &Path,
+) -> Result<PathBuf, DatabaseError> {
+    let backup = sibling_path(db_path, ".pre-migration");
+    if backup.exists() {
+        std::fs::remove_file(&backup).map_err(|e| DatabaseError::BackupError(e.to_string()))?;
+    }
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup.to_string_lossy().to_string())
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(backup)
+}
+
+/// Puts the backup in place of the database. The write-ahead log is removed
+/// first, as it would otherwise be replayed onto the restored file.
+fn restore_database(backup: &Path, db_path: &Path) -> Result<(), DatabaseError> {
+    log::warn!(
+        "[Migrations] Restoring {} from {}",
+        db_path.display(),
+        backup.display()
+    );
+
+    for suffix in ["-wal", "-shm"] {
+        let path = sibling_path(db_path, suffix);
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| DatabaseError::BackupError(e.to_string()))?;
+        }
+    }
+
+    std::fs::copy(backup, db_path).map_err(|e| DatabaseError::BackupError(e.to_string()))?;
+    std::fs::remove_file(backup).map_err(|e| DatabaseError::BackupError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Fills in the list snippet of emails whose body was stored without one
+struct BackfillSnippets;
+
+#[async_trait]
+impl DataMigration for BackfillSnippets {
+    fn id(&self) -> &'static str {
+        "backfill_snippets"
+    }
+
+    fn description(&self) -> &'static str {
+        "Generating message previews"
+    }
+
+    async fn remaining(
+        &self,
+        pool: &SqlitePool,
+        cursor: Option<&str>,
+    ) -> Result<u64, DatabaseError> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM emails WHERE snippet IS NULL AND body_plain IS NOT NULL AND id > ?",
+        )
+        .bind(cursor.unwrap_or(""))
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count.max(0) as u64)
+    }
+
+    async fn migrate_batch(
+        &self,
+        conn: &mut SqliteConnection,
+        cursor: Option<&str>,
+    ) -> Result<DataBatch, DatabaseError> {
+        let rows: Vec<(String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, body_plain FROM emails
+            WHERE snippet IS NULL AND body_plain IS NOT NULL AND id > ?
+            ORDER BY id
+            LIMIT ?
+            "#,
+        )
+        .bind(cursor.unwrap_or(""))
+        .bind(SNIPPET_BATCH_SIZE)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        for (id, body_plain) in &rows {
+            if let Some(snippet) = extract_snippet(body_plain.as_deref()) {
+                sqlx::query("UPDATE emails SET snippet = ? WHERE id = ?")
+                    .bind(snippet)
+                    .bind(id)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+
+        Ok(DataBatch {
+            processed: rows.len() as u64,
+            cursor: if (rows.len() as i64) < SNIPPET_BATCH_SIZE {
+                None
+            } else {
+                rows.last().map(|(id, _)| id.clone())
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tempfile::TempDir;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        for statement in [
+            include_str!("../../migrations/20250402000001_add_data_migrations.sql"),
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, migrated INTEGER NOT NULL DEFAULT 0)",
+            "INSERT INTO items (id) VALUES (1), (2), (3), (4), (5)",
+        ] {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await
+                .expect("Failed to create test schema");
+        }
+
+        pool
+    }
+
+    /// Marks two items per batch, and fails on the batch after item 2 while
+    /// `fail` is set
+    struct MarkItems {
+        fail: AtomicBool,
+    }
+
+    #[async_trait]
+    impl DataMigration for MarkItems {
+        fn id(&self) -> &'static str {
+            "mark_items"
+        }
+
+        fn description(&self) -> &'static str {
+            "Marking items"
+        }
+
+        async fn remaining(
+            &self,
+            pool: &SqlitePool,
+            cursor: Option<&str>,
+        ) -> Result<u64, DatabaseError> {
+            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items WHERE id > ?")
+                .bind(cursor.unwrap_or("0").parse::<i64>().unwrap())
+                .fetch_one(pool)
+                .await?;
+            Ok(count as u64)
+        }
+
+        async fn migrate_batch(
+            &self,
+            conn: &mut SqliteConnection,
+            cursor: Option<&str>,
+        ) -> Result<DataBatch, DatabaseError> {
+            let after: i64 = cursor.unwrap_or("0").parse().unwrap();
+            let updated = sqlx::query(
+                "UPDATE items SET migrated = 1 WHERE id IN (SELECT id FROM items WHERE id > ? ORDER BY id LIMIT 2)",
+            )
+            .bind(after)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            if after == 2 && self.fail.load(Ordering::SeqCst) {
+                return Err(DatabaseError::QueryError("boom".to_string()));
+            }
+
+            Ok(DataBatch {
+                processed: updated,
+                cursor: (updated == 2).then(|| (after + 2).to_string()),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_data_migration_resumes_after_failed_batch() {
+        let pool = create_test_pool().await;
+        let mut runner = MigrationRunner {
+            migrator: Migrator::DEFAULT,
+            data_migrations: vec![Box::new(MarkItems {
+                fail: AtomicBool::new(true),
+            })],
+            reporter: MigrationReporter::new(),
+        };
+
+        assert!(runner.run_data(&pool).await.is_err());
+        assert_eq!(
+            runner.reporter().latest().unwrap().phase,
+            MigrationPhase::Failed
+        );
+
+        // The failed batch was rolled back; the first one stays committed
+        let migrated: Vec<i64> = sqlx::query_scalar("SELECT id FROM items WHERE migrated = 1")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(migrated, vec![1, 2]);
+        let (status, cursor): (String, Option<String>) =
+            sqlx::query_as("SELECT status, cursor FROM data_migrations WHERE id = 'mark_items'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!((status.as_str(), cursor.as_deref()), ("failed", Some("2")));
+
+        runner.data_migrations = vec![Box::new(MarkItems {
+            fail: AtomicBool::new(false),
+        })];
+        runner.run_data(&pool).await.unwrap();
+
+        let (status, processed): (String, i64) =
+            sqlx::query_as("SELECT status, processed FROM data_migrations WHERE id = 'mark_items'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!((status.as_str(), processed), ("completed", 5));
+        assert_eq!(
+            runner.reporter().latest().unwrap().phase,
+            MigrationPhase::Done
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_schema_migration_restores_backup() {
+        let dir = TempDir::new().unwrap();
+        let migrations = dir.path().join("migrations");
+        std::fs::create_dir(&migrations).unwrap();
+        std::fs::write(
+            migrations.join("1_create_items.sql"),
+            "CREATE TABLE items (id INTEGER PRIMARY KEY);",
+        )
+        .unwrap();
+
+        let db_path = dir.path().join("test.db");
+        std::fs::File::create(&db_path).unwrap();
+        let mut runner = MigrationRunner {
+            migrator: Migrator::new(migrations.as_path()).await.unwrap(),
+            data_migrations: Vec::new(),
+            reporter: MigrationReporter::new(),
+        };
+        runner.run_schema(&db_path).await.unwrap();
+
+        // The second migration applies, the third fails, and both are undone
+        std::fs::write(
+            migrations.join("2_add_labels.sql"),
+            "CREATE TABLE labels (id INTEGER PRIMARY KEY);",
+        )
+        .unwrap();
+        std::fs::write(
+            migrations.join("3_broken.sql"),
+            "ALTER TABLE missing ADD COLUMN name TEXT;",
+        )
+        .unwrap();
+        runner.migrator = Migrator::new(migrations.as_path()).await.unwrap();
+        assert!(runner.run_schema(&db_path).await.is_err());
+        assert_eq!(
+            runner.reporter().latest().unwrap().phase,
+            MigrationPhase::Failed
+        );
+
+        let mut conn = SqliteConnection::connect(&format!("sqlite:{}", db_path.display()))
+            .await
+            .unwrap();
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name IN ('items', 'labels')",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(tables, vec!["items".to_string()]);
+        assert!(!sibling_path(&db_path, ".pre-migration").exists());
+    }
+}
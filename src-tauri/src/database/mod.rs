@@ -1,8 +1,11 @@
-use crate::database::{error::DatabaseError, repositories::RepositoryFactory};
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePool, Connection, SqliteConnection};
+use crate::database::{
+    error::DatabaseError, migrations::MigrationRunner, repositories::RepositoryFactory,
+};
+use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePool};
 use std::path::Path;
 
 pub mod error;
+pub mod migrations;
 pub mod models;
 pub mod repositories;
 pub mod utils;
@@ -12,7 +15,7 @@ pub struct Database {
 }
 
 impl Database {
-    pub async fn new(data_dir: &Path) -> Result<Self, DatabaseError> {
+    pub async fn new(data_dir: &Path, migrations: &MigrationRunner) -> Result<Self, DatabaseError> {
         let db_path = data_dir.join("ravn.db");
         let database_url = format!("sqlite:{}", db_path.display());
 
@@ -26,14 +29,7 @@ impl Database {
                 .map_err(DatabaseError::ConnectionError)?;
         }
 
-        let mut conn = SqliteConnection::connect(&database_url)
-            .await
-            .map_err(DatabaseError::ConnectionError)?;
-
-        sqlx::migrate!("./migrations")
-            .run(&mut conn)
-            .await
-            .map_err(DatabaseError::MigrationError)?;
+        migrations.run_schema(&db_path).await?;
 
         let pool = SqlitePool::connect(&database_url)
             .await
@@ -10,11 +10,23 @@ pub struct Account {
     pub email: String,
     pub account_type: AccountType,
     pub settings: serde_json::Value,
+    /// Owner's display name as reported by the provider
+    pub display_name: Option<String>,
+    /// Locally cached owner photo
+    pub photo_path: Option<String>,
+    /// Mailbox timezone (IANA or Windows zone name, as the provider reports it)
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+    pub profile_synced_at: Option<DateTime<Utc>>,
+    /// Color marking the account's folders and emails
+    pub color: Option<String>,
+    /// Picture chosen by the user, shown instead of the owner photo
+    pub avatar_path: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq, Hash)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
 pub enum AccountType {
     Gmail,
@@ -32,6 +44,17 @@ impl AccountType {
             AccountType::Imap => "imap",
         }
     }
+
+    /// Largest total attachment size in bytes the provider accepts on a
+    /// message, where it is known
+    pub fn max_attachment_size(&self) -> Option<usize> {
+        match self {
+            AccountType::Gmail => Some(25 * 1024 * 1024),
+            // With upload sessions for attachments over 3 MB
+            AccountType::Office365 => Some(150 * 1024 * 1024),
+            AccountType::Apple | AccountType::Imap => None,
+        }
+    }
 }
 
 impl std::fmt::Display for AccountType {
@@ -75,8 +98,47 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Account {
             email: row.try_get("email")?,
             account_type: row.try_get("account_type")?,
             settings: row.try_get("settings")?,
+            display_name: row.try_get("display_name").unwrap_or(None),
+            photo_path: row.try_get("photo_path").unwrap_or(None),
+            timezone: row.try_get("timezone").unwrap_or(None),
+            locale: row.try_get("locale").unwrap_or(None),
+            profile_synced_at: row.try_get("profile_synced_at").unwrap_or(None),
+            color: row.try_get("color").unwrap_or(None),
+            avatar_path: row.try_get("avatar_path").unwrap_or(None),
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
         })
     }
 }
+
+/// Storage usage of an account's mailbox as last reported by the provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountQuota {
+    pub account_id: Uuid,
+    /// Used storage in bytes
+    pub storage_used: Option<i64>,
+    /// Storage limit in bytes, `None` if unknown or unlimited
+    pub storage_limit: Option<i64>,
+    pub message_count: Option<i64>,
+    pub message_limit: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for AccountQuota {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let account_id_str: String = row.try_get("account_id")?;
+        let account_id =
+            Uuid::parse_str(&account_id_str).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        Ok(AccountQuota {
+            account_id,
+            storage_used: row.try_get("storage_used")?,
+            storage_limit: row.try_get("storage_limit")?,
+            message_count: row.try_get("message_count")?,
+            message_limit: row.try_get("message_limit")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
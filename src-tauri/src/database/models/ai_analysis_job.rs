@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An email queued for background AI analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiAnalysisJob {
+    pub email_id: Uuid,
+    /// Failed attempts so far
+    pub attempts: i64,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for AiAnalysisJob {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let email_id: String = row.try_get("email_id")?;
+
+        Ok(AiAnalysisJob {
+            email_id: Uuid::parse_str(&email_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            attempts: row.try_get("attempts")?,
+            next_attempt_at: row.try_get("next_attempt_at")?,
+            last_error: row.try_get("last_error")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A request sent to the AI provider, as recorded in the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiAuditEntry {
+    pub id: Uuid,
+    /// Feature that made the request, e.g. `analyze_email`
+    pub operation: String,
+    /// Base URL of the API the request went to
+    pub provider: String,
+    pub model: String,
+    /// Emails whose content the request included
+    pub email_ids: Vec<Uuid>,
+    /// The prompts as sent
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AiAuditEntry {
+    pub fn new(
+        operation: &str,
+        provider: String,
+        model: String,
+        email_ids: &[Uuid],
+        content: String,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            operation: operation.to_string(),
+            provider,
+            model,
+            email_ids: email_ids.to_vec(),
+            content,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for AiAuditEntry {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let email_ids: String = row.try_get("email_ids")?;
+
+        Ok(AiAuditEntry {
+            id: Uuid::parse_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            operation: row.try_get("operation")?,
+            provider: row.try_get("provider")?,
+            model: row.try_get("model")?,
+            email_ids: serde_json::from_str(&email_ids)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            content: row.try_get("content")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
@@ -17,6 +17,92 @@ pub struct Attachment {
     pub created_at: DateTime<Utc>,
 }
 
+/// Attachment categories for `type:` searches, in matching order: the first
+/// category whose MIME type prefix or file extension matches wins.
+///
+/// Mirrored by the `attachments.file_type` generated column; keep both in sync.
+pub const FILE_TYPES: &[(&str, &[&str], &[&str])] = &[
+    ("pdf", &["application/pdf"], &["pdf"]),
+    (
+        "image",
+        &["image/"],
+        &[
+            "png", "jpg", "jpeg", "gif", "webp", "heic", "svg", "bmp", "tif", "tiff",
+        ],
+    ),
+    (
+        "spreadsheet",
+        &[
+            "application/vnd.ms-excel",
+            "application/vnd.openxmlformats-officedocument.spreadsheetml",
+            "application/vnd.oasis.opendocument.spreadsheet",
+            "text/csv",
+        ],
+        &["xls", "xlsx", "xlsm", "ods", "csv", "numbers"],
+    ),
+    (
+        "presentation",
+        &[
+            "application/vnd.ms-powerpoint",
+            "application/vnd.openxmlformats-officedocument.presentationml",
+            "application/vnd.oasis.opendocument.presentation",
+        ],
+        &["ppt", "pptx", "odp", "key"],
+    ),
+    (
+        "document",
+        &[
+            "application/msword",
+            "application/vnd.openxmlformats-officedocument.wordprocessingml",
+            "application/vnd.oasis.opendocument.text",
+            "application/rtf",
+        ],
+        &["doc", "docx", "odt", "rtf", "pages"],
+    ),
+    (
+        "archive",
+        &[
+            "application/zip",
+            "application/x-7z-compressed",
+            "application/x-rar",
+            "application/vnd.rar",
+            "application/gzip",
+            "application/x-tar",
+        ],
+        &["zip", "7z", "rar", "gz", "tgz", "tar"],
+    ),
+    ("calendar", &["text/calendar"], &["ics"]),
+    ("audio", &["audio/"], &["mp3", "wav", "m4a", "ogg"]),
+    ("video", &["video/"], &["mp4", "mov", "avi", "mkv", "webm"]),
+    ("text", &["text/"], &["txt", "md"]),
+];
+
+/// Category of an attachment, `"other"` if none matches
+pub fn file_type_for(content_type: &str, filename: &str) -> &'static str {
+    let content_type = content_type.to_ascii_lowercase();
+    let filename = filename.to_ascii_lowercase();
+
+    FILE_TYPES
+        .iter()
+        .find(|(_, mime_prefixes, extensions)| {
+            mime_prefixes
+                .iter()
+                .any(|prefix| content_type.starts_with(prefix))
+                || extensions.iter().any(|ext| {
+                    filename
+                        .strip_suffix(ext)
+                        .is_some_and(|stem| stem.ends_with('.'))
+                })
+        })
+        .map_or("other", |(file_type, _, _)| file_type)
+}
+
+impl Attachment {
+    pub fn file_type(&self) -> &'static str {
+        file_type_for(&self.content_type, &self.filename)
+    }
+}
+
 impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Attachment {
     fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
         use sqlx::Row;
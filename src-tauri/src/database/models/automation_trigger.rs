@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A user-configured integration that runs when something happens to mail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationTrigger {
+    pub id: Uuid,
+    /// `None` fires the trigger for every account
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    pub enabled: bool,
+    pub event: TriggerEvent,
+    pub target: TriggerTarget,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerEvent {
+    /// A new message arrived in an inbox and was not filed away by a rule
+    NewMail,
+    /// A new message matched the given rule
+    RuleMatched { rule_id: Uuid },
+    /// The user flagged a message
+    EmailFlagged,
+}
+
+impl TriggerEvent {
+    /// Event name sent in the payload
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NewMail => "new_mail",
+            Self::RuleMatched { .. } => "rule_matched",
+            Self::EmailFlagged => "email_flagged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerTarget {
+    /// POST the payload as JSON
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Run a local executable with the payload as JSON on stdin
+    Script {
+        path: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// One attempt to hand an event to a trigger's target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerDelivery {
+    pub id: Uuid,
+    pub trigger_id: Uuid,
+    pub email_id: Option<Uuid>,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: TriggerDeliveryStatus,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerDeliveryStatus {
+    Pending,
+    Delivered,
+    /// Gave up after the last retry
+    Failed,
+}
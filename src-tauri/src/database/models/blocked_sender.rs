@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::sender_pattern::{parse_sender_pattern, SenderPatternKind};
+
+/// A sender or domain whose mail goes straight to junk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedSender {
+    /// Lowercased address or domain
+    pub pattern: String,
+    pub kind: SenderPatternKind,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BlockedSender {
+    /// Reads an address (`spam@example.com`) or a domain (`example.com` or
+    /// `@example.com`) as the user typed it
+    pub fn parse(input: &str) -> Option<Self> {
+        let (pattern, kind) = parse_sender_pattern(input)?;
+
+        Some(Self {
+            pattern,
+            kind,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Whether mail from the address is blocked by this entry
+    pub fn matches(&self, address: &str) -> bool {
+        let address = address.trim().to_lowercase();
+        match self.kind {
+            SenderPatternKind::Sender => address == self.pattern,
+            SenderPatternKind::Domain => address.rsplit_once('@').is_some_and(|(_, domain)| {
+                domain == self.pattern || domain.ends_with(&format!(".{}", self.pattern))
+            }),
+        }
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for BlockedSender {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(BlockedSender {
+            pattern: row.try_get("pattern")?,
+            kind: row.try_get("kind")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+
+use super::email::EmailAddress;
+
+/// The user's answer to a meeting invitation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RsvpStatus {
+    NeedsAction,
+    Accepted,
+    Tentative,
+    Declined,
+}
+
+impl RsvpStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::NeedsAction => "needs_action",
+            Self::Accepted => "accepted",
+            Self::Tentative => "tentative",
+            Self::Declined => "declined",
+        }
+    }
+
+    /// The iCalendar `PARTSTAT` value (RFC 5545 section 3.2.12)
+    pub fn partstat(&self) -> &'static str {
+        match self {
+            Self::NeedsAction => "NEEDS-ACTION",
+            Self::Accepted => "ACCEPTED",
+            Self::Tentative => "TENTATIVE",
+            Self::Declined => "DECLINED",
+        }
+    }
+
+    /// Parse a `PARTSTAT` value; delegated and to-do states have no equivalent
+    pub fn from_partstat(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "NEEDS-ACTION" => Some(Self::NeedsAction),
+            "ACCEPTED" => Some(Self::Accepted),
+            "TENTATIVE" => Some(Self::Tentative),
+            "DECLINED" => Some(Self::Declined),
+            _ => None,
+        }
+    }
+}
+
+/// A participant of a calendar event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attendee {
+    pub address: String,
+    pub name: Option<String>,
+    pub rsvp_status: Option<RsvpStatus>,
+}
+
+/// An event from a meeting invitation received by email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    /// The email carrying the invitation
+    pub email_id: Uuid,
+    pub uid: String,
+    /// Revision of the event; the organizer increments it on changes
+    pub sequence: i64,
+    /// iTIP method of the invitation, e.g. `REQUEST` or `CANCEL`
+    pub method: String,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    /// All-day events start and end at midnight UTC of their dates
+    pub all_day: bool,
+    pub organizer: Option<EmailAddress>,
+    pub attendees: Vec<Attendee>,
+    /// The account address the invitation was sent to, if it is listed
+    pub attendee_email: Option<String>,
+    pub cancelled: bool,
+    pub rsvp_status: RsvpStatus,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for CalendarEvent {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let account_id: String = row.try_get("account_id")?;
+        let email_id: String = row.try_get("email_id")?;
+        let organizer: Option<String> = row.try_get("organizer")?;
+        let attendees: String = row.try_get("attendees")?;
+
+        Ok(CalendarEvent {
+            id: Uuid::parse_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            account_id: Uuid::parse_str(&account_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            email_id: Uuid::parse_str(&email_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            uid: row.try_get("uid")?,
+            sequence: row.try_get("sequence")?,
+            method: row.try_get("method")?,
+            summary: row.try_get("summary")?,
+            description: row.try_get("description")?,
+            location: row.try_get("location")?,
+            starts_at: row.try_get("starts_at")?,
+            ends_at: row.try_get("ends_at")?,
+            all_day: row.try_get("all_day")?,
+            organizer: organizer
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|e| sqlx::Error::ColumnDecode {
+                    index: "organizer".into(),
+                    source: Box::new(e),
+                })?,
+            attendees: serde_json::from_str(&attendees).map_err(|e| sqlx::Error::ColumnDecode {
+                index: "attendees".into(),
+                source: Box::new(e),
+            })?,
+            attendee_email: row.try_get("attendee_email")?,
+            cancelled: row.try_get("cancelled")?,
+            rsvp_status: row.try_get("rsvp_status")?,
+            responded_at: row.try_get("responded_at")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
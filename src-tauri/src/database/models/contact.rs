@@ -1,5 +1,5 @@
 // File: /src/database/models/contact.rs
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -15,6 +15,13 @@ pub struct Contact {
     pub source: String,      // 'observed', 'imported', 'manual'
     pub avatar_type: String, // 'gravatar', 'unavatar', 'favicon', 'none'
     pub avatar_path: Option<String>,
+    #[serde(default)]
+    pub birthday: Option<String>, // 'YYYY-MM-DD' or '--MM-DD' when the year is unknown
+    #[serde(default)]
+    pub anniversary: Option<String>,
+    /// Mail from the contact is always important
+    #[serde(default)]
+    pub is_vip: bool,
     pub send_count: i64,
     pub receive_count: i64,
     pub last_used_at: Option<DateTime<Utc>>,
@@ -41,6 +48,9 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Contact {
             source: row.try_get("source")?,
             avatar_type: row.try_get("avatar_type")?,
             avatar_path: row.try_get("avatar_path")?,
+            birthday: row.try_get("birthday").unwrap_or(None),
+            anniversary: row.try_get("anniversary").unwrap_or(None),
+            is_vip: row.try_get("is_vip").unwrap_or(false),
             send_count: row.try_get("send_count")?,
             receive_count: row.try_get("receive_count")?,
             last_used_at: row.try_get("last_used_at")?,
@@ -64,6 +74,127 @@ pub struct ContactSummary {
     pub usage_score: i64,
 }
 
+/// Address field a contact appeared in. `From` counts mail received from the
+/// contact, the others mail sent to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactField {
+    From,
+    To,
+    Cc,
+    Bcc,
+}
+
+impl ContactField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContactField::From => "from",
+            ContactField::To => "to",
+            ContactField::Cc => "cc",
+            ContactField::Bcc => "bcc",
+        }
+    }
+}
+
+impl std::str::FromStr for ContactField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "from" => Ok(ContactField::From),
+            "to" => Ok(ContactField::To),
+            "cc" => Ok(ContactField::Cc),
+            "bcc" => Ok(ContactField::Bcc),
+            _ => Err(format!("Unknown contact field: {}", s)),
+        }
+    }
+}
+
+/// Days after which an interaction counts half as much
+pub const FRECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Frecency of a contact for one account and address field. Every
+/// interaction adds 1 to the score, which halves every
+/// `FRECENCY_HALF_LIFE_DAYS`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactInteraction {
+    pub contact_id: Uuid,
+    pub account_id: Uuid,
+    pub field: ContactField,
+    pub interaction_count: i64,
+    /// Score as of `last_interaction_at`
+    pub score: f64,
+    pub last_interaction_at: DateTime<Utc>,
+}
+
+impl ContactInteraction {
+    pub fn new(contact_id: Uuid, account_id: Uuid, field: ContactField, at: DateTime<Utc>) -> Self {
+        Self {
+            contact_id,
+            account_id,
+            field,
+            interaction_count: 1,
+            score: 1.0,
+            last_interaction_at: at,
+        }
+    }
+
+    /// Counts another interaction. Interactions older than the last one, e.g.
+    /// from syncing old mail, add their decayed weight.
+    pub fn record(&mut self, at: DateTime<Utc>) {
+        if at >= self.last_interaction_at {
+            self.score = decay(self.score, at - self.last_interaction_at) + 1.0;
+            self.last_interaction_at = at;
+        } else {
+            self.score += decay(1.0, self.last_interaction_at - at);
+        }
+        self.interaction_count += 1;
+    }
+
+    /// The score decayed to `now`
+    pub fn score_at(&self, now: DateTime<Utc>) -> f64 {
+        decay(self.score, now - self.last_interaction_at)
+    }
+}
+
+fn decay(score: f64, elapsed: chrono::Duration) -> f64 {
+    let days = elapsed.num_seconds().max(0) as f64 / 86_400.0;
+    score * 0.5f64.powf(days / FRECENCY_HALF_LIFE_DAYS)
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for ContactInteraction {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let contact_id: String = row.try_get("contact_id")?;
+        let account_id: String = row.try_get("account_id")?;
+        let field: String = row.try_get("field")?;
+
+        Ok(ContactInteraction {
+            contact_id: Uuid::parse_str(&contact_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            account_id: Uuid::parse_str(&account_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            field: field
+                .parse()
+                .map_err(|e: String| sqlx::Error::Decode(e.into()))?,
+            interaction_count: row.try_get("interaction_count")?,
+            score: row.try_get("score")?,
+            last_interaction_at: row.try_get("last_interaction_at")?,
+        })
+    }
+}
+
+/// A recipient suggested by autocomplete, best match first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientSuggestion {
+    pub id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub avatar_path: Option<String>,
+    pub score: f64,
+}
+
 impl Contact {
     pub fn full_name(&self) -> String {
         match (&self.display_name, &self.first_name, &self.last_name) {
@@ -88,3 +219,58 @@ impl Contact {
         base_score
     }
 }
+
+/// A recurring contact date, with the year only when it is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContactDate {
+    pub year: Option<i32>,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl ContactDate {
+    /// Parses 'YYYY-MM-DD' or the vCard '--MM-DD' form used for dates without a year
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(month_day) = value.strip_prefix("--") {
+            let (month, day) = month_day.split_once('-')?;
+            let date = Self {
+                year: None,
+                month: month.parse().ok()?,
+                day: day.parse().ok()?,
+            };
+            // Validate against a leap year so Feb 29 is accepted
+            NaiveDate::from_ymd_opt(2000, date.month, date.day)?;
+            return Some(date);
+        }
+
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+        Some(Self {
+            year: Some(date.year()),
+            month: date.month(),
+            day: date.day(),
+        })
+    }
+
+    /// Date of the occurrence in the given year; Feb 29 falls on Feb 28 in common years
+    pub fn occurrence_in(&self, year: i32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, self.month, self.day)
+            .or_else(|| NaiveDate::from_ymd_opt(year, self.month, self.day.saturating_sub(1)))
+    }
+
+    /// Number of years completed at the occurrence in the given year
+    pub fn years_at(&self, year: i32) -> Option<i32> {
+        self.year
+            .map(|start| year - start)
+            .filter(|years| *years > 0)
+    }
+}
+
+impl std::fmt::Display for ContactDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.year {
+            Some(year) => write!(f, "{:04}-{:02}-{:02}", year, self.month, self.day),
+            None => write!(f, "--{:02}-{:02}", self.month, self.day),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+
+/// How mail to a contact must be protected
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionPolicy {
+    /// Whatever the user picks in the composer
+    #[default]
+    Auto,
+    /// Always encrypt to the contact's pinned OpenPGP key
+    AlwaysPgp,
+    /// Never send without S/MIME encryption
+    RequireSmime,
+    /// Never encrypt, e.g. for a contact whose client cannot decrypt
+    PlaintextOnly,
+}
+
+impl EncryptionPolicy {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Auto => "auto",
+            Self::AlwaysPgp => "always_pgp",
+            Self::RequireSmime => "require_smime",
+            Self::PlaintextOnly => "plaintext_only",
+        }
+    }
+}
+
+/// Where a contact's OpenPGP key came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PgpKeySource {
+    /// Pasted or imported by the user
+    Manual,
+    /// Web Key Directory of the contact's domain
+    Wkd,
+    /// keys.openpgp.org
+    Keyserver,
+}
+
+impl PgpKeySource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Manual => "manual",
+            Self::Wkd => "wkd",
+            Self::Keyserver => "keyserver",
+        }
+    }
+}
+
+/// Security preferences and OpenPGP key of one contact address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactSecurity {
+    pub email: String,
+    pub policy: EncryptionPolicy,
+    /// Uppercase hex fingerprint of `pgp_public_key`, the key mail is
+    /// encrypted to under [`EncryptionPolicy::AlwaysPgp`]
+    pub pgp_fingerprint: Option<String>,
+    /// ASCII-armored public key
+    pub pgp_public_key: Option<String>,
+    pub pgp_key_source: Option<PgpKeySource>,
+    /// Last WKD/keyserver lookup, whether or not it found a key
+    pub pgp_key_fetched_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ContactSecurity {
+    pub fn new(email: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            email: email.trim().to_lowercase(),
+            policy: EncryptionPolicy::Auto,
+            pgp_fingerprint: None,
+            pgp_public_key: None,
+            pgp_key_source: None,
+            pgp_key_fetched_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for ContactSecurity {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(ContactSecurity {
+            email: row.try_get("email")?,
+            policy: row.try_get("policy")?,
+            pgp_fingerprint: row.try_get("pgp_fingerprint")?,
+            pgp_public_key: row.try_get("pgp_public_key")?,
+            pgp_key_source: row.try_get("pgp_key_source")?,
+            pgp_key_fetched_at: row.try_get("pgp_key_fetched_at")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
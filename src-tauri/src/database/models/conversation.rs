@@ -11,6 +11,8 @@ pub struct Conversation {
     pub remote_id: String,
     pub message_count: i64,
     pub ai_cache: Option<String>,
+    /// Set while the conversation is muted
+    pub muted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -27,6 +29,7 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Conversation {
             remote_id: row.try_get("remote_id")?,
             message_count: row.try_get("message_count")?,
             ai_cache: row.try_get("ai_cache")?,
+            muted_at: row.try_get("muted_at")?,
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
         })
@@ -39,6 +42,7 @@ pub struct ConversationListItem {
     pub id: String,
     pub message_count: i64,
     pub ai_cache: Option<String>,
+    pub muted: bool,
     pub messages: Vec<EmailListItem>,
 }
 
@@ -48,6 +52,7 @@ pub struct ConversationDetail {
     pub id: String,
     pub message_count: i64,
     pub ai_cache: Option<String>,
+    pub muted: bool,
     pub attachments: Vec<AttachmentInfo>,
     pub messages: Vec<EmailDetail>,
 }
@@ -59,6 +64,7 @@ impl Conversation {
             id: self.id.to_string(),
             message_count: self.message_count,
             ai_cache: self.ai_cache,
+            muted: self.muted_at.is_some(),
             messages,
         }
     }
@@ -73,6 +79,7 @@ impl Conversation {
             id: self.id.to_string(),
             message_count: self.message_count,
             ai_cache: self.ai_cache,
+            muted: self.muted_at.is_some(),
             attachments,
             messages,
         }
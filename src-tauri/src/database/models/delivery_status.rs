@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+
+/// Delivery state of one recipient of a sent message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientDeliveryStatus {
+    pub email_id: Uuid,
+    pub recipient: String,
+    pub action: DeliveryAction,
+    /// RFC 3463 status code, e.g. `5.1.1`
+    pub status: Option<String>,
+    pub diagnostic: Option<String>,
+    pub report_email_id: Option<Uuid>,
+    /// What the recipient did with the message, from a read receipt
+    pub disposition: Option<Disposition>,
+    pub disposition_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The `Action` field of a DSN (RFC 3464 section 2.3.3), plus `Pending` for
+/// recipients no report has arrived for yet
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryAction {
+    Pending,
+    Delivered,
+    Failed,
+    Delayed,
+    Relayed,
+    Expanded,
+}
+
+impl DeliveryAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+            Self::Delayed => "delayed",
+            Self::Relayed => "relayed",
+            Self::Expanded => "expanded",
+        }
+    }
+
+    /// Parse a DSN `Action` value, which is case-insensitive and may carry a comment
+    pub fn from_report(value: &str) -> Option<Self> {
+        let value = value.split('(').next().unwrap_or_default().trim();
+        match value.to_ascii_lowercase().as_str() {
+            "delivered" => Some(Self::Delivered),
+            "failed" => Some(Self::Failed),
+            "delayed" => Some(Self::Delayed),
+            "relayed" => Some(Self::Relayed),
+            "expanded" => Some(Self::Expanded),
+            _ => None,
+        }
+    }
+}
+
+/// The disposition type of a read receipt (RFC 8098 section 3.2.6.2)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Disposition {
+    Displayed,
+    Deleted,
+    Dispatched,
+    Processed,
+}
+
+impl Disposition {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Displayed => "displayed",
+            Self::Deleted => "deleted",
+            Self::Dispatched => "dispatched",
+            Self::Processed => "processed",
+        }
+    }
+
+    /// Parse a `Disposition` field value such as
+    /// `manual-action/MDN-sent-manually; displayed`
+    pub fn from_report(value: &str) -> Option<Self> {
+        let (_, disposition) = value.split_once(';')?;
+        let disposition = disposition
+            .split(['/', '(', ' '])
+            .find(|part| !part.is_empty())?;
+        match disposition.to_ascii_lowercase().as_str() {
+            "displayed" => Some(Self::Displayed),
+            "deleted" => Some(Self::Deleted),
+            "dispatched" => Some(Self::Dispatched),
+            "processed" => Some(Self::Processed),
+            _ => None,
+        }
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for RecipientDeliveryStatus {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let email_id: String = row.try_get("email_id")?;
+        let report_email_id: Option<String> = row.try_get("report_email_id")?;
+
+        Ok(RecipientDeliveryStatus {
+            email_id: Uuid::parse_str(&email_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            recipient: row.try_get("recipient")?,
+            action: row.try_get("action")?,
+            status: row.try_get("status")?,
+            diagnostic: row.try_get("diagnostic")?,
+            report_email_id: report_email_id
+                .map(|id| Uuid::parse_str(&id))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            disposition: row.try_get("disposition")?,
+            disposition_at: row.try_get("disposition_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
@@ -43,6 +43,23 @@ impl std::ops::Deref for EmailAddressList {
     }
 }
 
+/// Why an email looks like phishing or spoofing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RiskReason {
+    /// SPF, DKIM or DMARC did not pass at the receiving server
+    AuthenticationFailed { method: String, result: String },
+    /// Replies go to another domain than the one the email is from
+    ReplyToMismatch {
+        from_domain: String,
+        reply_to_domain: String,
+    },
+    /// The sender's domain imitates a well-known or the user's own domain
+    LookalikeDomain { domain: String, resembles: String },
+    /// A link shows one address but leads to another, or to a bare IP address
+    SuspiciousLink { text: String, url: String },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum EmailSyncStatus {
@@ -98,6 +115,7 @@ pub struct Email {
     pub sent_at: Option<DateTime<Utc>>,
     pub scheduled_send_at: Option<DateTime<Utc>>,
     pub remind_at: Option<DateTime<Utc>>,
+    pub snoozed_until: Option<DateTime<Utc>>,
     pub is_read: bool,
     pub is_flagged: bool,
     pub has_attachments: bool,
@@ -116,6 +134,14 @@ pub struct Email {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub size: i64,
+    /// Sum of the `priority` signals found when the email was synced
+    #[serde(default)]
+    pub priority: i64,
+    /// Phishing risk from 0 to 100, analyzed when the email was synced
+    #[serde(default)]
+    pub risk_score: i64,
+    #[serde(default)]
+    pub risk_reasons: Json<Vec<RiskReason>>,
 }
 
 impl Email {
@@ -261,6 +287,7 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Email {
             sent_at: row.try_get("sent_at")?,
             scheduled_send_at: row.try_get("scheduled_send_at")?,
             remind_at: row.try_get("remind_at").ok(),
+            snoozed_until: row.try_get("snoozed_until").ok(),
             is_read: row.try_get("is_read")?,
             is_flagged: row.try_get("is_flagged")?,
             has_attachments: row.try_get("has_attachments")?,
@@ -279,6 +306,15 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Email {
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
             size: row.try_get("size")?,
+            priority: row.try_get("priority").unwrap_or(0),
+            risk_score: row.try_get("risk_score").unwrap_or(0),
+            risk_reasons: Json(
+                row.try_get::<Option<String>, _>("risk_reasons")
+                    .ok()
+                    .flatten()
+                    .and_then(|json_str| serde_json::from_str(&json_str).ok())
+                    .unwrap_or_default(),
+            ),
         })
     }
 }
@@ -4,7 +4,9 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::attachment::Attachment;
-use super::email::{Email, EmailAddress};
+use super::calendar_event::CalendarEvent;
+use super::email::{Email, EmailAddress, RiskReason};
+use super::folder::FolderType;
 use super::label::Label;
 
 /// Minimal email data for list views
@@ -29,6 +31,7 @@ pub struct EmailListItem {
     pub received_at: DateTime<Utc>,
     pub sent_at: Option<DateTime<Utc>>,
     pub remind_at: Option<DateTime<Utc>>,
+    pub snoozed_until: Option<DateTime<Utc>>,
     pub notified_at: Option<DateTime<Utc>>,
 
     pub is_read: bool,
@@ -37,8 +40,14 @@ pub struct EmailListItem {
     pub sync_status: String,
     pub has_attachments: bool,
     pub size: i64,
+    pub priority: i64,
+    pub risk_score: i64,
 
     pub labels: Vec<LabelInfo>,
+    /// Set in views spanning folders, like a whole conversation
+    pub folder: Option<FolderInfo>,
+    /// Color of the email's account, to tell accounts apart
+    pub account_color: Option<String>,
 }
 
 impl EmailListItem {
@@ -59,6 +68,7 @@ impl EmailListItem {
             received_at: email.received_at,
             sent_at: email.sent_at,
             remind_at: email.remind_at,
+            snoozed_until: email.snoozed_until,
             notified_at: None,
             is_read: email.is_read,
             is_draft: email.is_draft,
@@ -66,11 +76,24 @@ impl EmailListItem {
             sync_status: email.sync_status.clone(),
             has_attachments: email.has_attachments,
             size: email.size,
+            priority: email.priority,
+            risk_score: email.risk_score,
             labels,
+            folder: None,
+            account_color: None,
         }
     }
 }
 
+/// A deleted email that is still kept locally within the retention period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentlyDeletedEmail {
+    pub email: EmailListItem,
+    pub deleted_at: DateTime<Utc>,
+    /// The deletion has not reached the provider yet, so the email can be restored
+    pub restorable: bool,
+}
+
 /// Full email data for detail view
 /// Includes all fields and related data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,6 +126,7 @@ pub struct EmailDetail {
     pub sent_at: Option<DateTime<Utc>>,
     pub scheduled_send_at: Option<DateTime<Utc>>,
     pub remind_at: Option<DateTime<Utc>>,
+    pub snoozed_until: Option<DateTime<Utc>>,
     pub notified_at: Option<DateTime<Utc>>,
 
     pub is_read: bool,
@@ -118,11 +142,21 @@ pub struct EmailDetail {
     pub tracking_blocked: bool,
     pub images_blocked: bool,
 
+    /// Phishing risk from 0 to 100, with the reasons the UI warns about
+    pub risk_score: i64,
+    pub risk_reasons: Vec<RiskReason>,
+
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 
     pub labels: Vec<LabelInfo>,
     pub attachments: Vec<AttachmentInfo>,
+    /// The meeting invitation the email carries, with the user's answer
+    pub invite: Option<CalendarEvent>,
+    /// Trackers found in the email, which are never loaded unless allowed
+    pub trackers: Vec<String>,
+    /// Remote images left out until the user loads them
+    pub blocked_images: i64,
 }
 
 impl EmailDetail {
@@ -156,6 +190,7 @@ impl EmailDetail {
             sent_at: email.sent_at,
             scheduled_send_at: email.scheduled_send_at,
             remind_at: email.remind_at,
+            snoozed_until: email.snoozed_until,
             notified_at: None,
             is_read: email.is_read,
             is_flagged: email.is_flagged,
@@ -167,10 +202,15 @@ impl EmailDetail {
             last_body_fetch_attempt: email.last_body_fetch_attempt,
             tracking_blocked: email.tracking_blocked,
             images_blocked: email.images_blocked,
+            risk_score: email.risk_score,
+            risk_reasons: email.risk_reasons.0.clone(),
             created_at: email.created_at,
             updated_at: email.updated_at,
             labels,
             attachments,
+            invite: None,
+            trackers: Vec::new(),
+            blocked_images: 0,
         }
     }
 }
@@ -195,6 +235,14 @@ impl From<&Label> for LabelInfo {
     }
 }
 
+/// Lightweight folder information for email DTOs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub folder_type: FolderType,
+}
+
 /// Attachment information for email DTOs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttachmentInfo {
@@ -226,3 +274,33 @@ impl From<&Attachment> for AttachmentInfo {
         }
     }
 }
+
+/// Inbox totals of a single account within the unified inbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnifiedInboxCount {
+    pub account_id: Uuid,
+    pub total: i64,
+    pub unread: i64,
+}
+
+/// Size and ordering state of a folder's message list
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FolderListState {
+    pub total_count: i64,
+    /// Changes whenever messages enter, leave or move within the list
+    pub order_token: i64,
+}
+
+/// A slice of a folder's message list for virtualized rendering
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailWindow {
+    pub folder_id: Uuid,
+    /// Index of the first item within the folder
+    pub start: i64,
+    pub items: Vec<EmailListItem>,
+    pub total_count: i64,
+    pub order_token: i64,
+    /// The caller's order token no longer matches, so rows it cached for other
+    /// ranges may have shifted
+    pub stale: bool,
+}
@@ -28,6 +28,104 @@ pub struct FolderSettings {
 
     #[serde(default)]
     pub filter_has_attachments: Option<bool>,
+
+    // Message actions
+    /// Action of the primary toolbar button and the remove shortcut
+    #[serde(default = "default_primary_action")]
+    pub primary_action: FolderAction,
+
+    #[serde(default = "default_swipe_left_action")]
+    pub swipe_left_action: FolderAction,
+
+    #[serde(default = "default_swipe_right_action")]
+    pub swipe_right_action: FolderAction,
+
+    /// What to show after the open message was archived, deleted or moved
+    #[serde(default)]
+    pub after_action: AfterAction,
+
+    /// Delay before an opened message is marked as read, `None` to never mark
+    /// it automatically
+    #[serde(default = "default_mark_read_delay_ms")]
+    pub mark_read_delay_ms: Option<u32>,
+
+    /// Moves old messages out of the folder in the background
+    #[serde(default)]
+    pub auto_archive: Option<AutoArchiveRule>,
+
+    /// Seconds between background syncs, `None` for the default of the
+    /// folder type
+    #[serde(default)]
+    pub sync_interval: Option<u32>,
+}
+
+/// Action applied to a message from a swipe or the primary toolbar button
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderAction {
+    None,
+    Archive,
+    Delete,
+    ToggleRead,
+    ToggleFlag,
+    Spam,
+    Snooze,
+}
+
+/// Navigation after the open message leaves the folder
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AfterAction {
+    #[default]
+    ReturnToList,
+    NextMessage,
+    PreviousMessage,
+}
+
+/// Messages of a folder that the background archive worker moves away
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AutoArchiveRule {
+    /// Minimum age of a message, based on when it was received
+    pub older_than_days: u32,
+
+    /// Leave unread messages in the folder
+    #[serde(default = "default_true")]
+    pub only_read: bool,
+
+    /// Leave flagged messages in the folder
+    #[serde(default = "default_true")]
+    pub skip_flagged: bool,
+
+    /// Destination folder, `None` for the account's archive folder
+    #[serde(default)]
+    pub target_folder_id: Option<Uuid>,
+}
+
+impl AutoArchiveRule {
+    /// Messages received before this point in time are archived
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::days(i64::from(self.older_than_days))
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_primary_action() -> FolderAction {
+    FolderAction::Archive
+}
+
+fn default_swipe_left_action() -> FolderAction {
+    FolderAction::Archive
+}
+
+fn default_swipe_right_action() -> FolderAction {
+    FolderAction::ToggleRead
+}
+
+fn default_mark_read_delay_ms() -> Option<u32> {
+    Some(0)
 }
 
 fn default_sort_by() -> String {
@@ -72,6 +170,13 @@ impl Default for FolderSettings {
             expanded_groups: default_expanded_groups(),
             filter_read: None,
             filter_has_attachments: None,
+            primary_action: default_primary_action(),
+            swipe_left_action: default_swipe_left_action(),
+            swipe_right_action: default_swipe_right_action(),
+            after_action: AfterAction::default(),
+            mark_read_delay_ms: default_mark_read_delay_ms(),
+            auto_archive: None,
+            sync_interval: None,
         }
     }
 }
@@ -88,6 +193,8 @@ pub struct Folder {
     pub sort_order: i32,
     pub expanded: bool,
     pub hidden: bool,
+    /// Whether background sync keeps the folder up to date
+    pub sync_enabled: bool,
     pub parent_id: Option<Uuid>,
     pub settings: FolderSettings,
     pub sync_interval: i64,
@@ -128,6 +235,7 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Folder {
             sort_order: row.try_get("sort_order")?,
             expanded: row.try_get("expanded")?,
             hidden: row.try_get("hidden")?,
+            sync_enabled: row.try_get("sync_enabled")?,
             parent_id,
             settings,
             sync_interval: row.try_get("sync_interval")?,
@@ -140,6 +248,16 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Folder {
     }
 }
 
+impl Folder {
+    /// Seconds between background syncs, as set by the user or the default
+    /// of the folder type
+    pub fn sync_interval_secs(&self) -> i64 {
+        self.settings
+            .sync_interval
+            .map_or(self.sync_interval, i64::from)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
@@ -152,6 +270,9 @@ pub enum FolderType {
     Spam,
     Starred,
     Custom,
+    /// Virtual folder listing the results of a saved search; never stored in
+    /// the folders table
+    Search,
 }
 
 impl FolderType {
@@ -166,6 +287,7 @@ impl FolderType {
             FolderType::Archive => "archive",
             FolderType::Starred => "star",
             FolderType::Custom => "folder",
+            FolderType::Search => "search",
         }
     }
 
@@ -177,9 +299,10 @@ impl FolderType {
             FolderType::Draft => 180,    // 3 minutes
             FolderType::Trash => 600,    // 10 minutes
             FolderType::Spam => 600,     // 10 minutes
-            FolderType::Archive => 1800, // 30 minutes
+            FolderType::Archive => 3600, // 1 hour
             FolderType::Starred => 300,  // 5 minutes
             FolderType::Custom => 300,   // 5 minutes
+            FolderType::Search => 0,     // never synced
         }
     }
 
@@ -194,6 +317,7 @@ impl FolderType {
             FolderType::Spam => "spam",
             FolderType::Starred => "starred",
             FolderType::Custom => "custom",
+            FolderType::Search => "search",
         }
     }
 }
@@ -217,6 +341,7 @@ impl FromStr for FolderType {
             "spam" => Ok(FolderType::Spam),
             "starred" => Ok(FolderType::Starred),
             "custom" => Ok(FolderType::Custom),
+            "search" => Ok(FolderType::Search),
             _ => Err(format!("Unknown folder type: {}", s)),
         }
     }
@@ -227,3 +352,58 @@ impl AsRef<str> for FolderType {
         self.as_str()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_auto_archive_rule() {
+        let settings: FolderSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.auto_archive, None);
+
+        let settings: FolderSettings =
+            serde_json::from_str(r#"{"auto_archive": {"older_than_days": 90}}"#).unwrap();
+        let rule = settings.auto_archive.unwrap();
+        assert!(rule.only_read);
+        assert!(rule.skip_flagged);
+        assert_eq!(rule.target_folder_id, None);
+
+        let now = Utc.with_ymd_and_hms(2025, 4, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            rule.cutoff(now),
+            Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sync_interval() {
+        let now = Utc::now();
+        let mut folder = Folder {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            name: "Archive".to_string(),
+            folder_type: FolderType::Archive,
+            remote_id: None,
+            color: None,
+            icon: None,
+            sort_order: 0,
+            expanded: false,
+            hidden: false,
+            sync_enabled: true,
+            parent_id: None,
+            settings: FolderSettings::default(),
+            sync_interval: FolderType::Archive.default_sync_interval() as i64,
+            unread_count: 0,
+            total_count: 0,
+            synced_at: now,
+            created_at: now,
+            updated_at: now,
+        };
+        assert_eq!(folder.sync_interval_secs(), 3600);
+
+        folder.settings.sync_interval = Some(300);
+        assert_eq!(folder.sync_interval_secs(), 300);
+    }
+}
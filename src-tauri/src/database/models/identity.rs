@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+
+/// An additional address an account can send as
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    /// Lowercased address
+    pub email: String,
+    /// Display name, the account name is used when `None`
+    pub name: Option<String>,
+    pub source: IdentitySource,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum IdentitySource {
+    /// Added by the user, e.g. an alias configured on the IMAP/SMTP server
+    Manual,
+    /// A verified Gmail sendAs address, replaced on every refresh
+    Gmail,
+}
+
+impl IdentitySource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Manual => "manual",
+            Self::Gmail => "gmail",
+        }
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Identity {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let account_id: String = row.try_get("account_id")?;
+
+        Ok(Identity {
+            id: Uuid::parse_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            account_id: Uuid::parse_str(&account_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            email: row.try_get("email")?,
+            name: row.try_get("name")?,
+            source: row.try_get("source")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::sender_pattern::{parse_sender_pattern, SenderPatternKind};
+
+/// A sender or domain whose remote images are always loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageAllowlistEntry {
+    /// Lowercased address or domain
+    pub pattern: String,
+    pub kind: SenderPatternKind,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ImageAllowlistEntry {
+    /// Reads an address (`alice@example.com`) or a domain (`example.com` or
+    /// `@example.com`) as the user typed it
+    pub fn parse(input: &str) -> Option<Self> {
+        let (pattern, kind) = parse_sender_pattern(input)?;
+
+        Some(Self {
+            pattern,
+            kind,
+            created_at: Utc::now(),
+        })
+    }
+
+    pub fn sender(address: &str) -> Self {
+        Self {
+            pattern: address.trim().to_lowercase(),
+            kind: SenderPatternKind::Sender,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for ImageAllowlistEntry {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(ImageAllowlistEntry {
+            pattern: row.try_get("pattern")?,
+            kind: row.try_get("kind")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
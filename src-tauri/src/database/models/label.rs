@@ -5,6 +5,10 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Label {
     pub id: Uuid,
+    /// Account of a label synced from the provider, `None` for local labels
+    pub account_id: Option<Uuid>,
+    /// Provider label ID (Gmail)
+    pub remote_id: Option<String>,
     pub name: String,
     pub color: Option<String>,
     pub icon: Option<String>,
@@ -18,9 +22,16 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Label {
 
         let id_str: String = row.try_get("id")?;
         let id = Uuid::parse_str(&id_str).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let account_id = row
+            .try_get::<Option<String>, _>("account_id")?
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
 
         Ok(Label {
             id,
+            account_id,
+            remote_id: row.try_get("remote_id")?,
             name: row.try_get("name")?,
             color: row.try_get("color")?,
             icon: row.try_get("icon")?,
@@ -1,12 +1,30 @@
 pub mod account;
+pub mod ai_analysis_job;
+pub mod ai_audit_entry;
 pub mod attachment;
+pub mod automation_trigger;
+pub mod blocked_sender;
+pub mod calendar_event;
 pub mod contact;
+pub mod contact_security;
 pub mod conversation;
+pub mod delivery_status;
 pub mod email;
 pub mod email_dto;
 pub mod folder;
+pub mod identity;
+pub mod image_allowlist;
 pub mod label;
+pub mod outbox_item;
 pub mod pending_operation;
+pub mod rule;
+pub mod saved_search;
+pub mod sender_category;
+pub mod sender_pattern;
+pub mod session;
 pub mod signature;
+pub mod smime;
 pub mod sync_state;
+pub mod template;
 pub mod view;
+pub mod writing_style_profile;
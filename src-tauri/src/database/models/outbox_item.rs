@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    /// Waiting for the next attempt
+    Queued,
+    Sending,
+    /// Out of attempts or rejected; only sent again on request
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Queued => "queued",
+            Self::Sending => "sending",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "queued" => Some(Self::Queued),
+            "sending" => Some(Self::Sending),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// An email that did not go out when it was sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxItem {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub subject: Option<String>,
+    /// Addresses of all recipients, for listing the item
+    pub recipients: Vec<String>,
+    /// The send request as JSON. Not sent to the frontend, since it carries
+    /// the attachments.
+    #[serde(skip)]
+    pub request: String,
+    pub status: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OutboxItem {
+    pub fn new(
+        account_id: Uuid,
+        subject: Option<String>,
+        recipients: Vec<String>,
+        request: String,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            account_id,
+            subject,
+            recipients,
+            request,
+            status: OutboxStatus::Queued.as_str().to_string(),
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn parsed_status(&self) -> Option<OutboxStatus> {
+        OutboxStatus::from_str(&self.status)
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for OutboxItem {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let parse_uuid = |s: &str| Uuid::parse_str(s).map_err(|e| sqlx::Error::Decode(Box::new(e)));
+
+        let id: String = row.try_get("id")?;
+        let account_id: String = row.try_get("account_id")?;
+        let recipients: String = row.try_get("recipients")?;
+
+        Ok(Self {
+            id: parse_uuid(&id)?,
+            account_id: parse_uuid(&account_id)?,
+            subject: row.try_get("subject")?,
+            recipients: serde_json::from_str(&recipients)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            request: row.try_get("request")?,
+            status: row.try_get("status")?,
+            attempts: row.try_get("attempts")?,
+            last_error: row.try_get("last_error")?,
+            next_attempt_at: row.try_get("next_attempt_at")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
@@ -12,6 +12,10 @@ pub enum PendingOperationType {
     Move,
     Delete,
     PermanentDelete,
+    /// Add a provider label (Gmail) to the message
+    AddLabel,
+    /// Remove a provider label (Gmail) from the message
+    RemoveLabel,
     CreateDraft,
     UpdateDraft,
     Send,
@@ -27,6 +31,8 @@ impl PendingOperationType {
             Self::Move => "move",
             Self::Delete => "delete",
             Self::PermanentDelete => "permanent_delete",
+            Self::AddLabel => "add_label",
+            Self::RemoveLabel => "remove_label",
             Self::CreateDraft => "create_draft",
             Self::UpdateDraft => "update_draft",
             Self::Send => "send",
@@ -42,6 +48,8 @@ impl PendingOperationType {
             "move" => Some(Self::Move),
             "delete" => Some(Self::Delete),
             "permanent_delete" => Some(Self::PermanentDelete),
+            "add_label" => Some(Self::AddLabel),
+            "remove_label" => Some(Self::RemoveLabel),
             "create_draft" => Some(Self::CreateDraft),
             "update_draft" => Some(Self::UpdateDraft),
             "send" => Some(Self::Send),
@@ -98,6 +106,8 @@ pub struct PendingOperation {
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
     pub expires_at: Option<DateTime<Utc>>,
+    /// Earliest time the operation is replayed again after a failure
+    pub next_attempt_at: Option<DateTime<Utc>>,
 }
 
 impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for PendingOperation {
@@ -125,6 +135,7 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for PendingOperation {
             created_at: row.try_get("created_at")?,
             completed_at: row.try_get("completed_at")?,
             expires_at: row.try_get("expires_at")?,
+            next_attempt_at: row.try_get("next_attempt_at").unwrap_or(None),
         })
     }
 }
@@ -151,6 +162,7 @@ impl PendingOperation {
             created_at: Utc::now(),
             completed_at: None,
             expires_at: None,
+            next_attempt_at: None,
         }
     }
 
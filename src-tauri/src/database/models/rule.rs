@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A user-defined filter applied to incoming mail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: Uuid,
+    /// `None` applies the rule to every account
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    pub enabled: bool,
+    /// Whether all conditions must match, or any of them
+    pub match_all: bool,
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+    /// Skip the remaining rules once this one matched
+    pub stop_processing: bool,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    /// Case-insensitive substring match on the sender's address or name
+    From {
+        value: String,
+    },
+    SubjectRegex {
+        pattern: String,
+    },
+    /// Case-insensitive substring match on the List-Id header
+    ListId {
+        value: String,
+    },
+    HasAttachment {
+        value: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    MoveToFolder { folder_id: Uuid },
+    ApplyLabel { label_id: Uuid },
+    MarkRead,
+    Delete,
+    Forward { to: String },
+}
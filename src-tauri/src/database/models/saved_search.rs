@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A search query pinned as a virtual folder, like a smart mailbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub name: String,
+    /// Query in the search query language, run against the account's emails
+    pub query: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for SavedSearch {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let account_id: String = row.try_get("account_id")?;
+
+        Ok(SavedSearch {
+            id: Uuid::parse_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            account_id: Uuid::parse_str(&account_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            name: row.try_get("name")?,
+            query: row.try_get("query")?,
+            icon: row.try_get("icon")?,
+            color: row.try_get("color")?,
+            sort_order: row.try_get("sort_order")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
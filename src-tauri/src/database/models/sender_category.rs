@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A category the user picked for mail from a sender
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SenderCategory {
+    /// Lowercased sender address
+    pub sender: String,
+    pub category: String,
+    /// How many times the user has corrected this sender
+    pub corrections: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for SenderCategory {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(SenderCategory {
+            sender: row.try_get("sender")?,
+            category: row.try_get("category")?,
+            corrections: row.try_get("corrections")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
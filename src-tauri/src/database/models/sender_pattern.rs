@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+
+/// What a sender pattern matches, for lists kept by sender or by domain
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SenderPatternKind {
+    /// A single address
+    Sender,
+    /// Every address at the domain or one of its subdomains
+    Domain,
+}
+
+impl SenderPatternKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Sender => "sender",
+            Self::Domain => "domain",
+        }
+    }
+}
+
+/// Reads an address (`alice@example.com`) or a domain (`example.com` or
+/// `@example.com`) as the user typed it, lowercased
+pub fn parse_sender_pattern(input: &str) -> Option<(String, SenderPatternKind)> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() || input.chars().any(char::is_whitespace) {
+        return None;
+    }
+
+    let (pattern, kind) = match input.strip_prefix('@') {
+        Some(domain) => (domain.to_string(), SenderPatternKind::Domain),
+        None if input.contains('@') => (input, SenderPatternKind::Sender),
+        None => (input, SenderPatternKind::Domain),
+    };
+
+    let domain = match kind {
+        SenderPatternKind::Sender => {
+            let (local, domain) = pattern.rsplit_once('@')?;
+            if local.is_empty() {
+                return None;
+            }
+            domain
+        }
+        SenderPatternKind::Domain => pattern.as_str(),
+    };
+    if domain.contains('@')
+        || !domain.contains('.')
+        || domain.split('.').any(|label| label.is_empty())
+    {
+        return None;
+    }
+
+    Some((pattern, kind))
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionWindow {
+    pub label: String,
+    pub window_type: SessionWindowType,
+    pub route: Option<String>,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub is_maximized: bool,
+    #[serde(default)]
+    pub view_state: serde_json::Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionWindowType {
+    Main,
+    Message,
+    Compose,
+}
+
+impl std::fmt::Display for SessionWindowType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionWindowType::Main => write!(f, "main"),
+            SessionWindowType::Message => write!(f, "message"),
+            SessionWindowType::Compose => write!(f, "compose"),
+        }
+    }
+}
+
+impl std::str::FromStr for SessionWindowType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "main" => Ok(SessionWindowType::Main),
+            "message" => Ok(SessionWindowType::Message),
+            "compose" => Ok(SessionWindowType::Compose),
+            _ => Err(format!("Invalid session window type: {}", s)),
+        }
+    }
+}
@@ -1,16 +1,57 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Signature {
     pub id: Uuid,
     pub account_id: Uuid,
+    /// Sending address this signature belongs to, `None` for every address of the account
+    pub identity_email: Option<String>,
     pub name: String,
+    /// HTML variant
     pub signature: String,
+    /// Plain-text variant, derived from the HTML when `None`
+    pub signature_text: Option<String>,
+    /// Appended to new messages
     pub is_default: bool,
+    /// Appended to replies and forwards
+    pub is_reply_default: bool,
 
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// Which signature a message sent or saved from the backend gets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureChoice {
+    /// The default for the sending address and message kind, if any
+    #[default]
+    Auto,
+    None,
+    Id(Uuid),
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Signature {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let account_id: String = row.try_get("account_id")?;
+
+        Ok(Signature {
+            id: Uuid::parse_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            account_id: Uuid::parse_str(&account_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            identity_email: row.try_get("identity_email")?,
+            name: row.try_get("name")?,
+            signature: row.try_get("signature")?,
+            signature_text: row.try_get("signature_text")?,
+            is_default: row.try_get("is_default")?,
+            is_reply_default: row.try_get("is_reply_default")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
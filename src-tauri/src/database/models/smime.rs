@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+
+/// An S/MIME certificate: either the account's own signing certificate, whose
+/// private key lives in the credential store, or one collected from signed mail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmimeCertificate {
+    pub id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub email: String,
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    pub fingerprint: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    #[serde(skip_serializing)]
+    pub certificate_pem: String,
+    pub has_private_key: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SmimeCertificate {
+    pub fn is_expired(&self) -> bool {
+        self.not_after < Utc::now()
+    }
+}
+
+/// Outcome of checking a message's S/MIME signature
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SmimeStatus {
+    /// Intact signature from a trusted certificate issued to the sender
+    Valid,
+    /// Intact signature, but the chain is not trusted or the signer is not the sender
+    Untrusted,
+    /// The content does not match the signature
+    Invalid,
+}
+
+impl SmimeStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Valid => "valid",
+            Self::Untrusted => "untrusted",
+            Self::Invalid => "invalid",
+        }
+    }
+}
+
+/// Stored signature check of an incoming message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmimeVerification {
+    pub email_id: Uuid,
+    pub status: SmimeStatus,
+    pub signer_email: Option<String>,
+    pub signer_subject: Option<String>,
+    pub issuer: Option<String>,
+    pub fingerprint: Option<String>,
+    pub not_after: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub verified_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for SmimeCertificate {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let account_id: Option<String> = row.try_get("account_id")?;
+
+        Ok(SmimeCertificate {
+            id: Uuid::parse_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            account_id: account_id
+                .map(|id| Uuid::parse_str(&id))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            email: row.try_get("email")?,
+            subject: row.try_get("subject")?,
+            issuer: row.try_get("issuer")?,
+            serial: row.try_get("serial")?,
+            fingerprint: row.try_get("fingerprint")?,
+            not_before: row.try_get("not_before")?,
+            not_after: row.try_get("not_after")?,
+            certificate_pem: row.try_get("certificate_pem")?,
+            has_private_key: row.try_get("has_private_key")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for SmimeVerification {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let email_id: String = row.try_get("email_id")?;
+
+        Ok(SmimeVerification {
+            email_id: Uuid::parse_str(&email_id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            status: row.try_get("status")?,
+            signer_email: row.try_get("signer_email")?,
+            signer_subject: row.try_get("signer_subject")?,
+            issuer: row.try_get("issuer")?,
+            fingerprint: row.try_get("fingerprint")?,
+            not_after: row.try_get("not_after")?,
+            error: row.try_get("error")?,
+            verified_at: row.try_get("verified_at")?,
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A reusable message with `{{placeholder}}` variables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub id: Uuid,
+    /// `None` makes the template available to every account
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    /// Replaces the draft's subject when set
+    pub subject: Option<String>,
+    /// HTML body
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A template filled in for one recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedTemplate {
+    pub template_id: Uuid,
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Template {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let id: String = row.try_get("id")?;
+        let account_id: Option<String> = row.try_get("account_id")?;
+
+        Ok(Template {
+            id: Uuid::parse_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            account_id: account_id
+                .map(|id| Uuid::parse_str(&id))
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            name: row.try_get("name")?,
+            subject: row.try_get("subject")?,
+            body: row.try_get("body")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
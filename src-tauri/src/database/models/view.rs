@@ -20,6 +20,50 @@ pub struct View {
     pub updated_at: DateTime<Utc>,
 }
 
+impl View {
+    /// Folders and labels the view draws its emails from. An empty folder
+    /// list means all folders; an empty label list means any label.
+    pub fn scope(&self) -> (Vec<Uuid>, Vec<Uuid>) {
+        let mut folder_ids = self.folders.clone();
+        let mut label_ids = Vec::new();
+
+        match &self.config {
+            ViewConfig::Kanban { swimlanes } => {
+                for swimlane in swimlanes {
+                    label_ids.extend(&swimlane.label_ids);
+                    folder_ids.extend(swimlane.folder_ids.iter().flatten());
+                }
+            }
+            ViewConfig::List { filters } => {
+                // Negated rules exclude emails, so they add nothing to the scope
+                let rules = filters
+                    .groups
+                    .iter()
+                    .filter(|group| !group.negated)
+                    .flat_map(|group| &group.rules)
+                    .filter(|rule| !rule.negated);
+                for rule in rules {
+                    match rule.source {
+                        ListFilterRuleSource::Folders => folder_ids.extend(&rule.values),
+                        ListFilterRuleSource::Labels => label_ids.extend(&rule.values),
+                    }
+                }
+            }
+            ViewConfig::Calendar {
+                folder_ids: calendar_folder_ids,
+                ..
+            } => folder_ids.extend(calendar_folder_ids),
+            ViewConfig::Smart {} | ViewConfig::Unified {} => {}
+        }
+
+        folder_ids.sort();
+        folder_ids.dedup();
+        label_ids.sort();
+        label_ids.dedup();
+        (folder_ids, label_ids)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum ViewType {
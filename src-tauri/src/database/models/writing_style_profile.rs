@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Writing style of an account, derived from a sample of its sent mail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingStyleProfile {
+    pub account_id: Uuid,
+    /// Tone, greeting and sign-off guidance as markdown
+    pub profile: String,
+    /// Sent emails the profile was derived from
+    pub sample_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for WritingStyleProfile {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let account_id: String = row.try_get("account_id")?;
+
+        Ok(WritingStyleProfile {
+            account_id: Uuid::parse_str(&account_id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            profile: row.try_get("profile")?,
+            sample_count: row.try_get("sample_count")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
@@ -1,6 +1,10 @@
-use crate::database::{error::DatabaseError, models::account::Account};
+use crate::database::{
+    error::DatabaseError,
+    models::account::{Account, AccountQuota},
+};
 use async_trait::async_trait;
-use sqlx::SqlitePool;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[async_trait]
@@ -10,7 +14,27 @@ pub trait AccountRepository {
     async fn find_by_sync_enabled(&self) -> Result<Vec<Account>, DatabaseError>;
     async fn create(&self, account: &Account) -> Result<Uuid, DatabaseError>;
     async fn update(&self, account: &Account) -> Result<(), DatabaseError>;
+    async fn update_profile(
+        &self,
+        id: Uuid,
+        display_name: Option<&str>,
+        photo_path: Option<&str>,
+        timezone: Option<&str>,
+        locale: Option<&str>,
+    ) -> Result<(), DatabaseError>;
+    /// Set or with `None` clear the color and picture the user chose
+    async fn update_appearance(
+        &self,
+        id: Uuid,
+        color: Option<&str>,
+        avatar_path: Option<&str>,
+    ) -> Result<(), DatabaseError>;
+    /// Colors of the accounts that have one
+    async fn find_colors(&self) -> Result<HashMap<Uuid, String>, DatabaseError>;
     async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+    async fn find_quota(&self, account_id: Uuid) -> Result<Option<AccountQuota>, DatabaseError>;
+    /// Replaces the stored quota of the account
+    async fn upsert_quota(&self, quota: &AccountQuota) -> Result<(), DatabaseError>;
 }
 
 pub struct SqliteAccountRepository {
@@ -96,6 +120,79 @@ impl AccountRepository for SqliteAccountRepository {
         Ok(())
     }
 
+    async fn update_profile(
+        &self,
+        id: Uuid,
+        display_name: Option<&str>,
+        photo_path: Option<&str>,
+        timezone: Option<&str>,
+        locale: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        // Keep previously known values when the provider omits a field
+        sqlx::query(
+            r#"
+            UPDATE accounts
+            SET display_name = COALESCE(?, display_name),
+                photo_path = COALESCE(?, photo_path),
+                timezone = COALESCE(?, timezone),
+                locale = COALESCE(?, locale),
+                profile_synced_at = ?,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(display_name)
+        .bind(photo_path)
+        .bind(timezone)
+        .bind(locale)
+        .bind(chrono::Utc::now())
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn update_appearance(
+        &self,
+        id: Uuid,
+        color: Option<&str>,
+        avatar_path: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE accounts
+            SET color = ?, avatar_path = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(color)
+        .bind(avatar_path)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_colors(&self) -> Result<HashMap<Uuid, String>, DatabaseError> {
+        let rows = sqlx::query("SELECT id, color FROM accounts WHERE color IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let id: String = row.try_get("id").ok()?;
+                let color: String = row.try_get("color").ok()?;
+                Some((Uuid::parse_str(&id).ok()?, color))
+            })
+            .collect())
+    }
+
     async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
         let id_str = id.to_string();
         sqlx::query!("DELETE FROM accounts WHERE id = ?", id_str)
@@ -105,6 +202,41 @@ impl AccountRepository for SqliteAccountRepository {
 
         Ok(())
     }
+
+    async fn find_quota(&self, account_id: Uuid) -> Result<Option<AccountQuota>, DatabaseError> {
+        sqlx::query_as::<_, AccountQuota>("SELECT * FROM account_quotas WHERE account_id = ?")
+            .bind(account_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn upsert_quota(&self, quota: &AccountQuota) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO account_quotas
+                (account_id, storage_used, storage_limit, message_count, message_limit, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(account_id) DO UPDATE SET
+                storage_used = excluded.storage_used,
+                storage_limit = excluded.storage_limit,
+                message_count = excluded.message_count,
+                message_limit = excluded.message_limit,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(quota.account_id.to_string())
+        .bind(quota.storage_used)
+        .bind(quota.storage_limit)
+        .bind(quota.message_count)
+        .bind(quota.message_limit)
+        .bind(quota.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -133,6 +265,13 @@ mod tests {
                 email TEXT NOT NULL,
                 account_type TEXT NOT NULL CHECK (account_type IN ('gmail', 'office365', 'apple', 'imap')),
                 settings TEXT NOT NULL,
+                display_name TEXT,
+                photo_path TEXT,
+                timezone TEXT,
+                locale TEXT,
+                profile_synced_at TIMESTAMP,
+                color TEXT,
+                avatar_path TEXT,
                 created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
                 updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
             );
@@ -141,6 +280,22 @@ mod tests {
         .execute(pool)
         .await
         .expect("Failed to create test schema");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_quotas (
+                account_id TEXT NOT NULL PRIMARY KEY,
+                storage_used INTEGER,
+                storage_limit INTEGER,
+                message_count INTEGER,
+                message_limit INTEGER,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+            "#,
+        )
+        .execute(pool)
+        .await
+        .expect("Failed to create test schema");
     }
 
     /// Helper function to create a test account
@@ -154,6 +309,13 @@ mod tests {
                 "oauth_token": "test_token",
                 "refresh_token": "test_refresh_token"
             }),
+            display_name: None,
+            photo_path: None,
+            timezone: None,
+            locale: None,
+            profile_synced_at: None,
+            color: None,
+            avatar_path: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }
@@ -253,6 +415,95 @@ mod tests {
         assert_eq!(updated.settings, test_account.settings);
     }
 
+    #[tokio::test]
+    async fn test_update_profile_keeps_unreported_fields() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteAccountRepository::new(pool);
+        let id = repository.create(&create_test_account()).await.unwrap();
+
+        repository
+            .update_profile(
+                id,
+                Some("Jane Doe"),
+                Some("/tmp/photo.jpg"),
+                Some("Europe/Berlin"),
+                None,
+            )
+            .await
+            .unwrap();
+        repository
+            .update_profile(id, None, None, None, Some("de-DE"))
+            .await
+            .unwrap();
+
+        let account = repository.find_by_id(id).await.unwrap().unwrap();
+        assert_eq!(account.display_name.as_deref(), Some("Jane Doe"));
+        assert_eq!(account.photo_path.as_deref(), Some("/tmp/photo.jpg"));
+        assert_eq!(account.timezone.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(account.locale.as_deref(), Some("de-DE"));
+        assert!(account.profile_synced_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_update_appearance() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteAccountRepository::new(pool);
+        let id = repository.create(&create_test_account()).await.unwrap();
+        let other = repository.create(&create_test_account()).await.unwrap();
+
+        repository
+            .update_appearance(id, Some("#3B82F6"), Some("/tmp/avatar.png"))
+            .await
+            .unwrap();
+
+        let account = repository.find_by_id(id).await.unwrap().unwrap();
+        assert_eq!(account.color.as_deref(), Some("#3B82F6"));
+        assert_eq!(account.avatar_path.as_deref(), Some("/tmp/avatar.png"));
+
+        let colors = repository.find_colors().await.unwrap();
+        assert_eq!(colors.get(&id).map(String::as_str), Some("#3B82F6"));
+        assert!(!colors.contains_key(&other));
+
+        repository.update_appearance(id, None, None).await.unwrap();
+        let account = repository.find_by_id(id).await.unwrap().unwrap();
+        assert_eq!(account.color, None);
+        assert_eq!(account.avatar_path, None);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_quota() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteAccountRepository::new(pool);
+        let account_id = repository.create(&create_test_account()).await.unwrap();
+        assert!(repository.find_quota(account_id).await.unwrap().is_none());
+
+        let mut quota = AccountQuota {
+            account_id,
+            storage_used: Some(512 * 1024),
+            storage_limit: Some(1024 * 1024),
+            message_count: None,
+            message_limit: None,
+            updated_at: chrono::Utc::now(),
+        };
+        repository.upsert_quota(&quota).await.unwrap();
+
+        quota.storage_used = Some(768 * 1024);
+        quota.message_count = Some(42);
+        repository.upsert_quota(&quota).await.unwrap();
+
+        let stored = repository.find_quota(account_id).await.unwrap().unwrap();
+        assert_eq!(stored.storage_used, Some(768 * 1024));
+        assert_eq!(stored.storage_limit, Some(1024 * 1024));
+        assert_eq!(stored.message_count, Some(42));
+        assert_eq!(stored.message_limit, None);
+    }
+
     #[tokio::test]
     async fn test_delete_account() {
         let pool = create_test_pool().await;
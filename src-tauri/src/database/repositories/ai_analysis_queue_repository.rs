@@ -0,0 +1,246 @@
+use crate::database::{error::DatabaseError, models::ai_analysis_job::AiAnalysisJob};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait AiAnalysisQueueRepository {
+    /// Queues the emails; ones already queued keep their place and attempts
+    async fn enqueue(&self, email_ids: &[Uuid]) -> Result<u64, DatabaseError>;
+    /// Queued emails that are due and have attempts left, fresh ones and the
+    /// most recently received first
+    async fn find_due(
+        &self,
+        now: DateTime<Utc>,
+        max_attempts: i64,
+        limit: i64,
+    ) -> Result<Vec<AiAnalysisJob>, DatabaseError>;
+    async fn complete(&self, email_id: Uuid) -> Result<(), DatabaseError>;
+    /// Counts a failed attempt, trying again no earlier than `next_attempt_at`
+    async fn schedule_retry(
+        &self,
+        email_id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), DatabaseError>;
+    /// Drops emails that were analyzed some other way or deleted
+    async fn prune(&self) -> Result<u64, DatabaseError>;
+}
+
+pub struct SqliteAiAnalysisQueueRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAiAnalysisQueueRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AiAnalysisQueueRepository for SqliteAiAnalysisQueueRepository {
+    async fn enqueue(&self, email_ids: &[Uuid]) -> Result<u64, DatabaseError> {
+        let mut queued = 0;
+        for email_id in email_ids {
+            let result =
+                sqlx::query("INSERT OR IGNORE INTO ai_analysis_queue (email_id) VALUES (?)")
+                    .bind(email_id.to_string())
+                    .execute(&self.pool)
+                    .await
+                    .map_err(DatabaseError::ConnectionError)?;
+            queued += result.rows_affected();
+        }
+
+        Ok(queued)
+    }
+
+    async fn find_due(
+        &self,
+        now: DateTime<Utc>,
+        max_attempts: i64,
+        limit: i64,
+    ) -> Result<Vec<AiAnalysisJob>, DatabaseError> {
+        sqlx::query_as::<_, AiAnalysisJob>(
+            r#"
+            SELECT q.*
+            FROM ai_analysis_queue q
+            INNER JOIN emails e ON e.id = q.email_id
+            WHERE e.ai_cache IS NULL
+              AND e.is_deleted = 0
+              AND q.attempts < ?
+              AND (q.next_attempt_at IS NULL OR q.next_attempt_at <= ?)
+            ORDER BY q.attempts, e.received_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(max_attempts)
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn complete(&self, email_id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM ai_analysis_queue WHERE email_id = ?")
+            .bind(email_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn schedule_retry(
+        &self,
+        email_id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE ai_analysis_queue
+            SET attempts = attempts + 1, last_error = ?, next_attempt_at = ?
+            WHERE email_id = ?
+            "#,
+        )
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(email_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn prune(&self) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM ai_analysis_queue
+            WHERE email_id NOT IN (
+                SELECT id FROM emails WHERE ai_cache IS NULL AND is_deleted = 0
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        for statement in [
+            r#"
+            CREATE TABLE emails (
+                id TEXT PRIMARY KEY,
+                ai_cache TEXT,
+                is_deleted BOOLEAN NOT NULL DEFAULT 0,
+                received_at TIMESTAMP NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE ai_analysis_queue (
+                email_id TEXT PRIMARY KEY REFERENCES emails(id) ON DELETE CASCADE,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TIMESTAMP,
+                last_error TEXT,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        ] {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await
+                .expect("Failed to create test schema");
+        }
+
+        pool
+    }
+
+    async fn insert_email(pool: &SqlitePool, received_at: DateTime<Utc>) -> Uuid {
+        let id = Uuid::now_v7();
+        sqlx::query("INSERT INTO emails (id, received_at) VALUES (?, ?)")
+            .bind(id.to_string())
+            .bind(received_at)
+            .execute(pool)
+            .await
+            .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn test_queue_retries_with_backoff() {
+        let pool = create_test_pool().await;
+        let repository = SqliteAiAnalysisQueueRepository::new(pool.clone());
+        let now = Utc::now();
+
+        let older = insert_email(&pool, now - Duration::hours(2)).await;
+        let newer = insert_email(&pool, now - Duration::hours(1)).await;
+        let analyzed = insert_email(&pool, now).await;
+        assert_eq!(
+            repository.enqueue(&[older, newer, analyzed]).await.unwrap(),
+            3
+        );
+        assert_eq!(repository.enqueue(&[older]).await.unwrap(), 0);
+
+        sqlx::query("UPDATE emails SET ai_cache = '{}' WHERE id = ?")
+            .bind(analyzed.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let due = repository.find_due(now, 3, 10).await.unwrap();
+        let ids: Vec<Uuid> = due.iter().map(|job| job.email_id).collect();
+        assert_eq!(ids, vec![newer, older]);
+
+        repository
+            .schedule_retry(newer, "Rate limited", now + Duration::minutes(5))
+            .await
+            .unwrap();
+        let due = repository.find_due(now, 3, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].email_id, older);
+
+        let later = repository
+            .find_due(now + Duration::minutes(5), 3, 10)
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = later.iter().map(|job| job.email_id).collect();
+        assert_eq!(ids, vec![older, newer]);
+        assert_eq!(later[1].attempts, 1);
+        assert_eq!(later[1].last_error.as_deref(), Some("Rate limited"));
+
+        // Out of attempts
+        assert!(repository
+            .find_due(now + Duration::minutes(5), 1, 10)
+            .await
+            .unwrap()
+            .iter()
+            .all(|job| job.email_id != newer));
+
+        repository.complete(older).await.unwrap();
+        assert_eq!(repository.prune().await.unwrap(), 1);
+        let remaining = repository
+            .find_due(now + Duration::minutes(5), 3, 10)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].email_id, newer);
+    }
+}
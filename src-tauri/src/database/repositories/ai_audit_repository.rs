@@ -0,0 +1,161 @@
+use crate::database::{error::DatabaseError, models::ai_audit_entry::AiAuditEntry};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+#[async_trait]
+pub trait AiAuditRepository {
+    async fn record(&self, entry: &AiAuditEntry) -> Result<(), DatabaseError>;
+    /// Entries newest first
+    async fn find_page(&self, limit: i64, offset: i64) -> Result<Vec<AiAuditEntry>, DatabaseError>;
+    async fn count(&self) -> Result<i64, DatabaseError>;
+    /// Deletes the entries recorded before `before`, or all of them. Returns
+    /// the number deleted.
+    async fn purge(&self, before: Option<DateTime<Utc>>) -> Result<u64, DatabaseError>;
+}
+
+pub struct SqliteAiAuditRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAiAuditRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AiAuditRepository for SqliteAiAuditRepository {
+    async fn record(&self, entry: &AiAuditEntry) -> Result<(), DatabaseError> {
+        let email_ids = serde_json::to_string(&entry.email_ids)
+            .map_err(|e| DatabaseError::InvalidData(format!("Invalid email IDs: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO ai_audit (id, operation, provider, model, email_ids, content, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(entry.id.to_string())
+        .bind(&entry.operation)
+        .bind(&entry.provider)
+        .bind(&entry.model)
+        .bind(email_ids)
+        .bind(&entry.content)
+        .bind(entry.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_page(&self, limit: i64, offset: i64) -> Result<Vec<AiAuditEntry>, DatabaseError> {
+        sqlx::query_as::<_, AiAuditEntry>(
+            "SELECT * FROM ai_audit ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn count(&self) -> Result<i64, DatabaseError> {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM ai_audit")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn purge(&self, before: Option<DateTime<Utc>>) -> Result<u64, DatabaseError> {
+        let result = match before {
+            Some(before) => {
+                sqlx::query("DELETE FROM ai_audit WHERE created_at < ?")
+                    .bind(before)
+                    .execute(&self.pool)
+                    .await
+            }
+            None => {
+                sqlx::query("DELETE FROM ai_audit")
+                    .execute(&self.pool)
+                    .await
+            }
+        }
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use uuid::Uuid;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE ai_audit (
+                id TEXT PRIMARY KEY,
+                operation TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                email_ids TEXT NOT NULL DEFAULT '[]',
+                content TEXT NOT NULL,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn entry(operation: &str, age_days: i64) -> AiAuditEntry {
+        let mut entry = AiAuditEntry::new(
+            operation,
+            "https://openrouter.ai/api/v1".to_string(),
+            "openai/gpt-oss-120b".to_string(),
+            &[Uuid::now_v7()],
+            "prompt".to_string(),
+        );
+        entry.created_at = Utc::now() - chrono::Duration::days(age_days);
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_record_and_purge() {
+        let repository = SqliteAiAuditRepository::new(create_test_pool().await);
+
+        let old = entry("analyze_email", 40);
+        let recent = entry("ask_mailbox", 1);
+        repository.record(&old).await.unwrap();
+        repository.record(&recent).await.unwrap();
+
+        let page = repository.find_page(10, 0).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].id, recent.id);
+        assert_eq!(page[1].email_ids, old.email_ids);
+        assert_eq!(repository.find_page(1, 1).await.unwrap()[0].id, old.id);
+
+        let purged = repository
+            .purge(Some(Utc::now() - chrono::Duration::days(30)))
+            .await
+            .unwrap();
+        assert_eq!(purged, 1);
+        assert_eq!(repository.count().await.unwrap(), 1);
+
+        assert_eq!(repository.purge(None).await.unwrap(), 1);
+        assert_eq!(repository.count().await.unwrap(), 0);
+    }
+}
@@ -7,6 +7,10 @@ use uuid::Uuid;
 pub trait AttachmentRepository {
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Attachment>, DatabaseError>;
     async fn find_by_email(&self, email_id: Uuid) -> Result<Vec<Attachment>, DatabaseError>;
+    async fn find_by_emails(
+        &self,
+        email_ids: &[Uuid],
+    ) -> Result<std::collections::HashMap<Uuid, Vec<Attachment>>, DatabaseError>;
     async fn find_by_hash(&self, hash: &str) -> Result<Option<Attachment>, DatabaseError>;
     async fn find_by_conversation_id(
         &self,
@@ -17,6 +21,27 @@ pub trait AttachmentRepository {
     async fn find_all_cached(&self)
         -> Result<Vec<(String, Option<String>, String)>, DatabaseError>;
     async fn update_hash(&self, id: &str, hash: &str) -> Result<(), DatabaseError>;
+    /// Record that the attachments were opened, for attachment cache eviction
+    async fn mark_opened(&self, ids: &[Uuid]) -> Result<(), DatabaseError>;
+    /// Cached attachments of the given categories whose text was not
+    /// extracted yet, newest first
+    async fn find_pending_text_extraction(
+        &self,
+        file_types: &[&str],
+        max_size: i64,
+        limit: i64,
+    ) -> Result<Vec<Attachment>, DatabaseError>;
+    /// Store the extracted text, `None` if the attachment has none
+    async fn save_text(
+        &self,
+        attachment: &Attachment,
+        content: Option<&str>,
+    ) -> Result<(), DatabaseError>;
+    /// Extracted texts of the attachments of the given emails, by attachment ID
+    async fn find_texts_by_emails(
+        &self,
+        email_ids: &[Uuid],
+    ) -> Result<std::collections::HashMap<Uuid, String>, DatabaseError>;
 }
 
 pub struct SqliteAttachmentRepository {
@@ -47,6 +72,38 @@ impl AttachmentRepository for SqliteAttachmentRepository {
             .map_err(DatabaseError::ConnectionError)
     }
 
+    async fn find_by_emails(
+        &self,
+        email_ids: &[Uuid],
+    ) -> Result<std::collections::HashMap<Uuid, Vec<Attachment>>, DatabaseError> {
+        use std::collections::HashMap;
+
+        if email_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ids: Vec<String> = email_ids.iter().map(Uuid::to_string).collect();
+        let ids_json = serde_json::to_string(&ids).map_err(DatabaseError::JsonError)?;
+
+        let attachments = sqlx::query_as::<_, Attachment>(
+            "SELECT * FROM attachments WHERE email_id IN (SELECT value FROM json_each(?))",
+        )
+        .bind(ids_json)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        let mut by_email: HashMap<Uuid, Vec<Attachment>> = HashMap::new();
+        for attachment in attachments {
+            by_email
+                .entry(attachment.email_id)
+                .or_default()
+                .push(attachment);
+        }
+
+        Ok(by_email)
+    }
+
     async fn find_by_hash(&self, hash: &str) -> Result<Option<Attachment>, DatabaseError> {
         sqlx::query_as::<_, Attachment>("SELECT * FROM attachments WHERE hash = ?")
             .bind(hash)
@@ -143,6 +200,108 @@ impl AttachmentRepository for SqliteAttachmentRepository {
 
         Ok(())
     }
+
+    async fn mark_opened(&self, ids: &[Uuid]) -> Result<(), DatabaseError> {
+        for id in ids {
+            let id = id.to_string();
+            sqlx::query!(
+                "UPDATE attachments SET last_opened_at = CURRENT_TIMESTAMP WHERE id = ?",
+                id
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+        }
+
+        Ok(())
+    }
+
+    async fn find_pending_text_extraction(
+        &self,
+        file_types: &[&str],
+        max_size: i64,
+        limit: i64,
+    ) -> Result<Vec<Attachment>, DatabaseError> {
+        let file_types_json =
+            serde_json::to_string(file_types).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query_as::<_, Attachment>(
+            r#"
+            SELECT a.* FROM attachments a
+            LEFT JOIN attachment_texts t ON t.attachment_id = a.id
+            WHERE t.attachment_id IS NULL
+              AND a.is_cached = 1 AND a.cache_path IS NOT NULL AND a.is_inline = 0
+              AND a.size <= ?
+              AND a.file_type IN (SELECT value FROM json_each(?))
+            ORDER BY a.created_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(max_size)
+        .bind(file_types_json)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn save_text(
+        &self,
+        attachment: &Attachment,
+        content: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO attachment_texts (attachment_id, email_id, content)
+            VALUES (?, ?, ?)
+            ON CONFLICT(attachment_id) DO UPDATE SET
+                content = excluded.content,
+                extracted_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(attachment.id.to_string())
+        .bind(attachment.email_id.to_string())
+        .bind(content)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_texts_by_emails(
+        &self,
+        email_ids: &[Uuid],
+    ) -> Result<std::collections::HashMap<Uuid, String>, DatabaseError> {
+        use sqlx::Row;
+        use std::collections::HashMap;
+
+        if email_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ids: Vec<String> = email_ids.iter().map(Uuid::to_string).collect();
+        let ids_json = serde_json::to_string(&ids).map_err(DatabaseError::JsonError)?;
+
+        let rows = sqlx::query(
+            "SELECT attachment_id, content FROM attachment_texts \
+             WHERE content IS NOT NULL AND email_id IN (SELECT value FROM json_each(?))",
+        )
+        .bind(ids_json)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        let mut texts = HashMap::new();
+        for row in rows {
+            let attachment_id: String = row.get("attachment_id");
+            if let Ok(attachment_id) = Uuid::parse_str(&attachment_id) {
+                texts.insert(attachment_id, row.get("content"));
+            }
+        }
+
+        Ok(texts)
+    }
 }
 
 #[cfg(test)]
@@ -350,6 +509,59 @@ mod tests {
         assert!(found.is_some());
     }
 
+    #[tokio::test]
+    async fn test_file_type_column_matches_model() {
+        let pool = create_test_pool().await;
+        sqlx::query(
+            "CREATE TABLE attachments (filename TEXT NOT NULL, content_type TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::raw_sql(include_str!(
+            "../../../migrations/20250324000001_add_attachment_file_type.sql"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let samples = [
+            ("Invoice.PDF", "application/octet-stream"),
+            ("scan.jpg", "image/jpeg"),
+            (
+                "q3.xlsx",
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ),
+            ("export", "text/csv"),
+            ("deck.pptx", "application/octet-stream"),
+            ("letter.docx", "application/octet-stream"),
+            ("backup.tar", "application/x-tar"),
+            ("invite.ics", "text/calendar; method=REQUEST"),
+            ("notes.txt", "text/plain"),
+            ("xlsx", "application/octet-stream"),
+            ("data.bin", "application/octet-stream"),
+        ];
+
+        for (filename, content_type) in samples {
+            let file_type: String = sqlx::query_scalar(
+                "INSERT INTO attachments (filename, content_type) VALUES (?, ?) RETURNING file_type",
+            )
+            .bind(filename)
+            .bind(content_type)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+            assert_eq!(
+                file_type,
+                crate::database::models::attachment::file_type_for(content_type, filename),
+                "{} ({})",
+                filename,
+                content_type
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_error_handling() {
         let pool = create_test_pool().await;
@@ -0,0 +1,492 @@
+use crate::database::{
+    error::DatabaseError,
+    models::automation_trigger::{
+        AutomationTrigger, TriggerDelivery, TriggerDeliveryStatus, TriggerEvent, TriggerTarget,
+    },
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+#[async_trait]
+pub trait AutomationTriggerRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<AutomationTrigger>, DatabaseError>;
+    async fn get_all(&self) -> Result<Vec<AutomationTrigger>, DatabaseError>;
+    /// Enabled triggers that apply to the account, including account-independent ones
+    async fn find_enabled_for_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Vec<AutomationTrigger>, DatabaseError>;
+    async fn create(&self, trigger: &AutomationTrigger) -> Result<Uuid, DatabaseError>;
+    async fn update(&self, trigger: &AutomationTrigger) -> Result<(), DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+
+    /// Queues a delivery for its first attempt right away
+    async fn create_delivery(&self, delivery: &TriggerDelivery) -> Result<Uuid, DatabaseError>;
+    /// Pending deliveries whose next attempt is due, oldest first
+    async fn find_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<TriggerDelivery>, DatabaseError>;
+    async fn find_deliveries_for_trigger(
+        &self,
+        trigger_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<TriggerDelivery>, DatabaseError>;
+    async fn mark_delivered(&self, id: Uuid) -> Result<(), DatabaseError>;
+    /// Records a failed attempt. Without a `retry_at` the delivery is given up.
+    async fn record_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError>;
+    /// Removes finished deliveries created before `before`
+    async fn prune_deliveries(&self, before: DateTime<Utc>) -> Result<u64, DatabaseError>;
+}
+
+pub struct SqliteAutomationTriggerRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAutomationTriggerRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_uuid(value: &str) -> Result<Uuid, DatabaseError> {
+        Uuid::parse_str(value).map_err(|e| DatabaseError::RepositoryError(e.to_string()))
+    }
+
+    fn map_row_to_trigger(
+        row: &sqlx::sqlite::SqliteRow,
+    ) -> Result<AutomationTrigger, DatabaseError> {
+        let event: TriggerEvent = serde_json::from_str(&row.get::<String, _>("event"))
+            .map_err(DatabaseError::JsonError)?;
+        let target: TriggerTarget = serde_json::from_str(&row.get::<String, _>("target"))
+            .map_err(DatabaseError::JsonError)?;
+
+        Ok(AutomationTrigger {
+            id: Self::parse_uuid(&row.get::<String, _>("id"))?,
+            account_id: row
+                .get::<Option<String>, _>("account_id")
+                .as_deref()
+                .map(Self::parse_uuid)
+                .transpose()?,
+            name: row.get("name"),
+            enabled: row.get("enabled"),
+            event,
+            target,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    fn map_row_to_delivery(
+        row: &sqlx::sqlite::SqliteRow,
+    ) -> Result<TriggerDelivery, DatabaseError> {
+        Ok(TriggerDelivery {
+            id: Self::parse_uuid(&row.get::<String, _>("id"))?,
+            trigger_id: Self::parse_uuid(&row.get::<String, _>("trigger_id"))?,
+            email_id: row
+                .get::<Option<String>, _>("email_id")
+                .as_deref()
+                .map(Self::parse_uuid)
+                .transpose()?,
+            event: row.get("event"),
+            payload: serde_json::from_str(&row.get::<String, _>("payload"))
+                .map_err(DatabaseError::JsonError)?,
+            status: row.get("status"),
+            attempts: row.get("attempts"),
+            last_error: row.get("last_error"),
+            next_attempt_at: row.get("next_attempt_at"),
+            delivered_at: row.get("delivered_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[async_trait]
+impl AutomationTriggerRepository for SqliteAutomationTriggerRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<AutomationTrigger>, DatabaseError> {
+        let row = sqlx::query("SELECT * FROM automation_triggers WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        row.as_ref().map(Self::map_row_to_trigger).transpose()
+    }
+
+    async fn get_all(&self) -> Result<Vec<AutomationTrigger>, DatabaseError> {
+        let rows = sqlx::query("SELECT * FROM automation_triggers ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        rows.iter().map(Self::map_row_to_trigger).collect()
+    }
+
+    async fn find_enabled_for_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Vec<AutomationTrigger>, DatabaseError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM automation_triggers
+            WHERE enabled = 1 AND (account_id IS NULL OR account_id = ?)
+            ORDER BY created_at
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        rows.iter().map(Self::map_row_to_trigger).collect()
+    }
+
+    async fn create(&self, trigger: &AutomationTrigger) -> Result<Uuid, DatabaseError> {
+        let event_json = serde_json::to_string(&trigger.event).map_err(DatabaseError::JsonError)?;
+        let target_json =
+            serde_json::to_string(&trigger.target).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO automation_triggers (id, account_id, name, enabled, event, target)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(trigger.id.to_string())
+        .bind(trigger.account_id.map(|id| id.to_string()))
+        .bind(&trigger.name)
+        .bind(trigger.enabled)
+        .bind(event_json)
+        .bind(target_json)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(trigger.id)
+    }
+
+    async fn update(&self, trigger: &AutomationTrigger) -> Result<(), DatabaseError> {
+        let event_json = serde_json::to_string(&trigger.event).map_err(DatabaseError::JsonError)?;
+        let target_json =
+            serde_json::to_string(&trigger.target).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            UPDATE automation_triggers
+            SET account_id = ?, name = ?, enabled = ?, event = ?, target = ?,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(trigger.account_id.map(|id| id.to_string()))
+        .bind(&trigger.name)
+        .bind(trigger.enabled)
+        .bind(event_json)
+        .bind(target_json)
+        .bind(trigger.id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM automation_triggers WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn create_delivery(&self, delivery: &TriggerDelivery) -> Result<Uuid, DatabaseError> {
+        let payload_json =
+            serde_json::to_string(&delivery.payload).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO automation_trigger_deliveries
+                (id, trigger_id, email_id, event, payload, status, next_attempt_at)
+            VALUES (?, ?, ?, ?, ?, 'pending', ?)
+            "#,
+        )
+        .bind(delivery.id.to_string())
+        .bind(delivery.trigger_id.to_string())
+        .bind(delivery.email_id.map(|id| id.to_string()))
+        .bind(&delivery.event)
+        .bind(payload_json)
+        .bind(delivery.next_attempt_at.unwrap_or_else(Utc::now))
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(delivery.id)
+    }
+
+    async fn find_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<TriggerDelivery>, DatabaseError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM automation_trigger_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= ?
+            ORDER BY next_attempt_at
+            LIMIT ?
+            "#,
+        )
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        rows.iter().map(Self::map_row_to_delivery).collect()
+    }
+
+    async fn find_deliveries_for_trigger(
+        &self,
+        trigger_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<TriggerDelivery>, DatabaseError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM automation_trigger_deliveries
+            WHERE trigger_id = ?
+            ORDER BY created_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(trigger_id.to_string())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        rows.iter().map(Self::map_row_to_delivery).collect()
+    }
+
+    async fn mark_delivered(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE automation_trigger_deliveries
+            SET status = 'delivered', attempts = attempts + 1, last_error = NULL,
+                next_attempt_at = NULL, delivered_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn record_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError> {
+        let status = if retry_at.is_some() {
+            TriggerDeliveryStatus::Pending
+        } else {
+            TriggerDeliveryStatus::Failed
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE automation_trigger_deliveries
+            SET status = ?, attempts = attempts + 1, last_error = ?, next_attempt_at = ?,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(status)
+        .bind(error)
+        .bind(retry_at)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn prune_deliveries(&self, before: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            "DELETE FROM automation_trigger_deliveries WHERE status != 'pending' AND created_at < ?",
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE automation_triggers (
+                id TEXT PRIMARY KEY NOT NULL,
+                account_id TEXT,
+                name TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                event TEXT NOT NULL,
+                target TEXT NOT NULL,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+            CREATE TABLE automation_trigger_deliveries (
+                id TEXT PRIMARY KEY NOT NULL,
+                trigger_id TEXT NOT NULL,
+                email_id TEXT,
+                event TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                next_attempt_at TIMESTAMP,
+                delivered_at TIMESTAMP,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn create_test_trigger(account_id: Option<Uuid>) -> AutomationTrigger {
+        AutomationTrigger {
+            id: Uuid::now_v7(),
+            account_id,
+            name: "Home Assistant".to_string(),
+            enabled: true,
+            event: TriggerEvent::EmailFlagged,
+            target: TriggerTarget::Webhook {
+                url: "http://homeassistant.local/api/webhook/mail".to_string(),
+                headers: Default::default(),
+            },
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn create_test_delivery(trigger_id: Uuid) -> TriggerDelivery {
+        TriggerDelivery {
+            id: Uuid::now_v7(),
+            trigger_id,
+            email_id: None,
+            event: "email_flagged".to_string(),
+            payload: serde_json::json!({ "event": "email_flagged" }),
+            status: TriggerDeliveryStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
+            delivered_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_enabled_for_account() {
+        let repository = SqliteAutomationTriggerRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let global = create_test_trigger(None);
+        let own = create_test_trigger(Some(account_id));
+        let other = create_test_trigger(Some(Uuid::now_v7()));
+        let mut disabled = create_test_trigger(Some(account_id));
+        disabled.enabled = false;
+
+        for trigger in [&global, &own, &other, &disabled] {
+            repository.create(trigger).await.unwrap();
+        }
+
+        let triggers = repository
+            .find_enabled_for_account(account_id)
+            .await
+            .unwrap();
+        let mut ids: Vec<Uuid> = triggers.iter().map(|t| t.id).collect();
+        ids.sort();
+        let mut expected = vec![global.id, own.id];
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert_eq!(triggers[0].target, global.target);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries() {
+        let repository = SqliteAutomationTriggerRepository::new(create_test_pool().await);
+        let trigger = create_test_trigger(None);
+        repository.create(&trigger).await.unwrap();
+
+        let delivery = create_test_delivery(trigger.id);
+        repository.create_delivery(&delivery).await.unwrap();
+
+        let due = repository
+            .find_due_deliveries(Utc::now(), 10)
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].payload["event"], "email_flagged");
+
+        // A scheduled retry is not due yet
+        repository
+            .record_failure(
+                delivery.id,
+                "HTTP 503",
+                Some(Utc::now() + chrono::Duration::minutes(5)),
+            )
+            .await
+            .unwrap();
+        assert!(repository
+            .find_due_deliveries(Utc::now(), 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        repository
+            .record_failure(delivery.id, "HTTP 503", None)
+            .await
+            .unwrap();
+        let log = repository
+            .find_deliveries_for_trigger(trigger.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(log[0].status, TriggerDeliveryStatus::Failed);
+        assert_eq!(log[0].attempts, 2);
+        assert_eq!(log[0].last_error.as_deref(), Some("HTTP 503"));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::database::{error::DatabaseError, models::blocked_sender::BlockedSender};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+#[async_trait]
+pub trait BlockedSenderRepository {
+    async fn get_all(&self) -> Result<Vec<BlockedSender>, DatabaseError>;
+    async fn add(&self, blocked: &BlockedSender) -> Result<(), DatabaseError>;
+    async fn delete(&self, pattern: &str) -> Result<(), DatabaseError>;
+    /// Whether the address or its domain is blocked
+    async fn is_blocked(&self, sender: &str) -> Result<bool, DatabaseError>;
+}
+
+pub struct SqliteBlockedSenderRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteBlockedSenderRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl BlockedSenderRepository for SqliteBlockedSenderRepository {
+    async fn get_all(&self) -> Result<Vec<BlockedSender>, DatabaseError> {
+        sqlx::query_as::<_, BlockedSender>("SELECT * FROM blocked_senders ORDER BY kind, pattern")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn add(&self, blocked: &BlockedSender) -> Result<(), DatabaseError> {
+        sqlx::query("INSERT OR IGNORE INTO blocked_senders (pattern, kind) VALUES (?, ?)")
+            .bind(blocked.pattern.trim().to_lowercase())
+            .bind(blocked.kind.as_str())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, pattern: &str) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM blocked_senders WHERE pattern = ?")
+            .bind(pattern.trim().to_lowercase())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn is_blocked(&self, sender: &str) -> Result<bool, DatabaseError> {
+        let sender = sender.trim().to_lowercase();
+        let Some((_, domain)) = sender.rsplit_once('@') else {
+            return Ok(false);
+        };
+
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM blocked_senders
+                WHERE (kind = 'sender' AND pattern = ?1)
+                   OR (kind = 'domain'
+                       AND (pattern = ?2 OR substr(?2, -length(pattern) - 1) = '.' || pattern))
+            )
+            "#,
+        )
+        .bind(&sender)
+        .bind(domain)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE blocked_senders (
+                pattern TEXT PRIMARY KEY,
+                kind TEXT NOT NULL CHECK (kind IN ('sender', 'domain')),
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_is_blocked() {
+        let repository = SqliteBlockedSenderRepository::new(create_test_pool().await);
+        let spammer = BlockedSender::parse("Spam@Example.com").unwrap();
+        let domain = BlockedSender::parse("@junk.net").unwrap();
+        repository.add(&spammer).await.unwrap();
+        repository.add(&domain).await.unwrap();
+
+        for sender in ["spam@example.com", "offers@junk.net", "a@mail.junk.net"] {
+            assert!(repository.is_blocked(sender).await.unwrap(), "{}", sender);
+            assert!(
+                spammer.matches(sender) || domain.matches(sender),
+                "{}",
+                sender
+            );
+        }
+        for sender in ["friend@example.com", "a@notjunk.net"] {
+            assert!(!repository.is_blocked(sender).await.unwrap(), "{}", sender);
+            assert!(
+                !spammer.matches(sender) && !domain.matches(sender),
+                "{}",
+                sender
+            );
+        }
+
+        repository.delete("junk.net").await.unwrap();
+        assert_eq!(repository.get_all().await.unwrap().len(), 1);
+        assert!(!repository.is_blocked("offers@junk.net").await.unwrap());
+    }
+}
@@ -0,0 +1,362 @@
+use crate::database::{
+    error::DatabaseError,
+    models::calendar_event::{CalendarEvent, RsvpStatus},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait CalendarEventRepository {
+    async fn find_by_email(&self, email_id: Uuid) -> Result<Option<CalendarEvent>, DatabaseError>;
+    /// All invitations of an event, latest revision first
+    async fn find_by_uid(
+        &self,
+        account_id: Uuid,
+        uid: &str,
+    ) -> Result<Vec<CalendarEvent>, DatabaseError>;
+    /// Events overlapping the given range, in their latest revision only
+    async fn find_in_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>, DatabaseError>;
+    /// Stores the event of an invitation email, replacing an earlier parse of it
+    async fn upsert(&self, event: &CalendarEvent) -> Result<(), DatabaseError>;
+    /// Records the user's answer on every invitation of the event
+    async fn set_rsvp(
+        &self,
+        account_id: Uuid,
+        uid: &str,
+        status: RsvpStatus,
+        responded_at: DateTime<Utc>,
+    ) -> Result<u64, DatabaseError>;
+    /// Marks every invitation of the event as cancelled
+    async fn cancel(&self, account_id: Uuid, uid: &str) -> Result<u64, DatabaseError>;
+}
+
+pub struct SqliteCalendarEventRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteCalendarEventRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CalendarEventRepository for SqliteCalendarEventRepository {
+    async fn find_by_email(&self, email_id: Uuid) -> Result<Option<CalendarEvent>, DatabaseError> {
+        sqlx::query_as::<_, CalendarEvent>(
+            "SELECT * FROM calendar_events WHERE email_id = ? ORDER BY sequence DESC LIMIT 1",
+        )
+        .bind(email_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_uid(
+        &self,
+        account_id: Uuid,
+        uid: &str,
+    ) -> Result<Vec<CalendarEvent>, DatabaseError> {
+        sqlx::query_as::<_, CalendarEvent>(
+            r#"
+            SELECT * FROM calendar_events
+            WHERE account_id = ? AND uid = ?
+            ORDER BY sequence DESC, id DESC
+            "#,
+        )
+        .bind(account_id.to_string())
+        .bind(uid)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_in_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>, DatabaseError> {
+        // Ids are UUIDv7, so the later of two rows of one revision wins
+        sqlx::query_as::<_, CalendarEvent>(
+            r#"
+            SELECT * FROM calendar_events e
+            WHERE e.starts_at < ? AND COALESCE(e.ends_at, e.starts_at) >= ?
+              AND NOT EXISTS (
+                  SELECT 1 FROM calendar_events newer
+                  WHERE newer.account_id = e.account_id
+                    AND newer.uid = e.uid
+                    AND (newer.sequence > e.sequence
+                         OR (newer.sequence = e.sequence AND newer.id > e.id))
+              )
+            ORDER BY e.starts_at
+            "#,
+        )
+        .bind(end)
+        .bind(start)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn upsert(&self, event: &CalendarEvent) -> Result<(), DatabaseError> {
+        let organizer = event
+            .organizer
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(DatabaseError::JsonError)?;
+        let attendees =
+            serde_json::to_string(&event.attendees).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO calendar_events (
+                id, account_id, email_id, uid, sequence, method, summary, description,
+                location, starts_at, ends_at, all_day, organizer, attendees, attendee_email,
+                cancelled, rsvp_status, responded_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(email_id, uid) DO UPDATE SET
+                sequence = excluded.sequence,
+                method = excluded.method,
+                summary = excluded.summary,
+                description = excluded.description,
+                location = excluded.location,
+                starts_at = excluded.starts_at,
+                ends_at = excluded.ends_at,
+                all_day = excluded.all_day,
+                organizer = excluded.organizer,
+                attendees = excluded.attendees,
+                attendee_email = excluded.attendee_email,
+                cancelled = excluded.cancelled,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(event.id.to_string())
+        .bind(event.account_id.to_string())
+        .bind(event.email_id.to_string())
+        .bind(&event.uid)
+        .bind(event.sequence)
+        .bind(&event.method)
+        .bind(&event.summary)
+        .bind(&event.description)
+        .bind(&event.location)
+        .bind(event.starts_at)
+        .bind(event.ends_at)
+        .bind(event.all_day)
+        .bind(organizer)
+        .bind(attendees)
+        .bind(&event.attendee_email)
+        .bind(event.cancelled)
+        .bind(event.rsvp_status.as_str())
+        .bind(event.responded_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn set_rsvp(
+        &self,
+        account_id: Uuid,
+        uid: &str,
+        status: RsvpStatus,
+        responded_at: DateTime<Utc>,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE calendar_events
+            SET rsvp_status = ?, responded_at = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE account_id = ? AND uid = ?
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(responded_at)
+        .bind(account_id.to_string())
+        .bind(uid)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn cancel(&self, account_id: Uuid, uid: &str) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE calendar_events
+            SET cancelled = 1, updated_at = CURRENT_TIMESTAMP
+            WHERE account_id = ? AND uid = ?
+            "#,
+        )
+        .bind(account_id.to_string())
+        .bind(uid)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::calendar_event::Attendee;
+    use crate::database::models::email::EmailAddress;
+    use chrono::Duration;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE calendar_events (
+                id TEXT PRIMARY KEY NOT NULL,
+                account_id TEXT NOT NULL,
+                email_id TEXT NOT NULL,
+                uid TEXT NOT NULL,
+                sequence INTEGER NOT NULL DEFAULT 0,
+                method TEXT NOT NULL,
+                summary TEXT,
+                description TEXT,
+                location TEXT,
+                starts_at TIMESTAMP NOT NULL,
+                ends_at TIMESTAMP,
+                all_day BOOLEAN NOT NULL DEFAULT 0,
+                organizer TEXT,
+                attendees TEXT NOT NULL DEFAULT '[]',
+                attendee_email TEXT,
+                cancelled BOOLEAN NOT NULL DEFAULT 0,
+                rsvp_status TEXT NOT NULL DEFAULT 'needs_action',
+                responded_at TIMESTAMP,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (email_id, uid)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn create_test_event(account_id: Uuid, uid: &str, sequence: i64) -> CalendarEvent {
+        let starts_at = Utc::now() + Duration::days(1);
+        CalendarEvent {
+            id: Uuid::now_v7(),
+            account_id,
+            email_id: Uuid::now_v7(),
+            uid: uid.to_string(),
+            sequence,
+            method: "REQUEST".to_string(),
+            summary: Some("Planning".to_string()),
+            description: None,
+            location: Some("Room 1".to_string()),
+            starts_at,
+            ends_at: Some(starts_at + Duration::hours(1)),
+            all_day: false,
+            organizer: Some(EmailAddress {
+                address: "organizer@example.com".to_string(),
+                name: Some("Olivia".to_string()),
+            }),
+            attendees: vec![Attendee {
+                address: "me@example.com".to_string(),
+                name: None,
+                rsvp_status: Some(RsvpStatus::NeedsAction),
+            }],
+            attendee_email: Some("me@example.com".to_string()),
+            cancelled: false,
+            rsvp_status: RsvpStatus::NeedsAction,
+            responded_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_rsvp() {
+        let repository = SqliteCalendarEventRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+        let mut event = create_test_event(account_id, "meeting-1@example.com", 0);
+        repository.upsert(&event).await.unwrap();
+
+        // Parsing the same email again updates the row instead of adding one
+        event.location = Some("Room 2".to_string());
+        repository.upsert(&event).await.unwrap();
+
+        let stored = repository
+            .find_by_email(event.email_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.location.as_deref(), Some("Room 2"));
+        assert_eq!(stored.organizer.unwrap().address, "organizer@example.com");
+        assert_eq!(stored.attendees, event.attendees);
+
+        let updated = create_test_event(account_id, "meeting-1@example.com", 1);
+        repository.upsert(&updated).await.unwrap();
+
+        let changed = repository
+            .set_rsvp(account_id, &event.uid, RsvpStatus::Accepted, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(changed, 2);
+
+        let revisions = repository
+            .find_by_uid(account_id, &event.uid)
+            .await
+            .unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].id, updated.id);
+        assert!(revisions
+            .iter()
+            .all(|e| e.rsvp_status == RsvpStatus::Accepted && e.responded_at.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_find_in_range_returns_latest_revision() {
+        let repository = SqliteCalendarEventRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let first = create_test_event(account_id, "meeting-1@example.com", 0);
+        let second = create_test_event(account_id, "meeting-1@example.com", 1);
+        let other = create_test_event(account_id, "meeting-2@example.com", 0);
+        for event in [&first, &second, &other] {
+            repository.upsert(event).await.unwrap();
+        }
+        repository.cancel(account_id, &other.uid).await.unwrap();
+
+        let events = repository
+            .find_in_range(Utc::now(), Utc::now() + Duration::days(2))
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = events.iter().map(|e| e.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&second.id) && ids.contains(&other.id));
+        assert!(events.iter().find(|e| e.id == other.id).unwrap().cancelled);
+
+        let events = repository
+            .find_in_range(
+                Utc::now() + Duration::days(3),
+                Utc::now() + Duration::days(4),
+            )
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+    }
+}
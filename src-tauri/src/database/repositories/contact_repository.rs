@@ -1,6 +1,6 @@
 use crate::database::{
     error::DatabaseError,
-    models::contact::{Contact, ContactSummary},
+    models::contact::{Contact, ContactField, ContactInteraction, ContactSummary},
 };
 use async_trait::async_trait;
 use chrono::{TimeZone, Utc};
@@ -36,6 +36,23 @@ pub trait ContactRepository {
         limit: i64,
     ) -> Result<Vec<ContactSummary>, DatabaseError>;
     async fn get_top_contacts(&self, limit: i64) -> Result<Vec<ContactSummary>, DatabaseError>;
+    /// Contacts whose address or name contains the query, recently used first
+    async fn find_matching(&self, query: &str, limit: i64) -> Result<Vec<Contact>, DatabaseError>;
+
+    /// Adds an interaction at `at` to the contact's frecency for the account and field
+    async fn record_interaction(
+        &self,
+        contact_id: Uuid,
+        account_id: Uuid,
+        field: ContactField,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DatabaseError>;
+    /// Frecency rows of the contacts, of one account or of all accounts
+    async fn find_interactions(
+        &self,
+        contact_ids: &[Uuid],
+        account_id: Option<Uuid>,
+    ) -> Result<Vec<ContactInteraction>, DatabaseError>;
 
     async fn update_avatar(
         &self,
@@ -43,10 +60,33 @@ pub trait ContactRepository {
         avatar_type: &str,
         avatar_path: Option<String>,
     ) -> Result<(), DatabaseError>;
+    /// Contacts still waiting for an avatar lookup, senders of Inbox mail
+    /// received since `recent_since` first. Contacts without interaction since
+    /// `active_since` and lookups scheduled for a later retry are left out.
     async fn find_contacts_without_avatars(
         &self,
         limit: i64,
+        active_since: chrono::DateTime<chrono::Utc>,
+        recent_since: chrono::DateTime<chrono::Utc>,
     ) -> Result<Vec<Contact>, DatabaseError>;
+    /// Counts a failed avatar lookup and returns the number of failures so far
+    async fn increment_avatar_attempts(&self, id: Uuid) -> Result<i64, DatabaseError>;
+    async fn schedule_avatar_retry(
+        &self,
+        id: Uuid,
+        retry_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DatabaseError>;
+
+    async fn find_contacts_with_dates(&self) -> Result<Vec<Contact>, DatabaseError>;
+    async fn update_dates(
+        &self,
+        id: Uuid,
+        birthday: Option<&str>,
+        anniversary: Option<&str>,
+    ) -> Result<(), DatabaseError>;
+    async fn set_vip(&self, id: Uuid, is_vip: bool) -> Result<(), DatabaseError>;
+    /// Whether the address belongs to a VIP contact
+    async fn is_vip_address(&self, email: &str) -> Result<bool, DatabaseError>;
 }
 
 pub struct SqliteContactRepository {
@@ -78,6 +118,9 @@ impl SqliteContactRepository {
             source: "observed".to_string(),
             avatar_type: "unprocessed".to_string(),
             avatar_path: None,
+            birthday: None,
+            anniversary: None,
+            is_vip: false,
             send_count: 0,
             receive_count: 0,
             last_used_at: Some(Utc::now()),
@@ -127,9 +170,9 @@ impl ContactRepository for SqliteContactRepository {
             r#"
             INSERT INTO contacts (
                 id, email, display_name, first_name, last_name, company,
-                ai_notes, source, avatar_type, avatar_path, send_count, receive_count,
-                last_used_at, first_seen_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ai_notes, source, avatar_type, avatar_path, birthday, anniversary,
+                is_vip, send_count, receive_count, last_used_at, first_seen_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
@@ -142,6 +185,9 @@ impl ContactRepository for SqliteContactRepository {
         .bind(&contact.source)
         .bind(&contact.avatar_type)
         .bind(&contact.avatar_path)
+        .bind(&contact.birthday)
+        .bind(&contact.anniversary)
+        .bind(contact.is_vip)
         .bind(contact.send_count)
         .bind(contact.receive_count)
         .bind(contact.last_used_at)
@@ -160,8 +206,9 @@ impl ContactRepository for SqliteContactRepository {
             r#"
             UPDATE contacts
             SET display_name = ?, first_name = ?, last_name = ?, company = ?,
-                ai_notes = ?, source = ?, avatar_type = ?, avatar_path = ?, send_count = ?,
-                receive_count = ?, last_used_at = ?, updated_at = CURRENT_TIMESTAMP
+                ai_notes = ?, source = ?, avatar_type = ?, avatar_path = ?, birthday = ?,
+                anniversary = ?, is_vip = ?, send_count = ?, receive_count = ?,
+                last_used_at = ?, updated_at = CURRENT_TIMESTAMP
             WHERE id = ?
             "#,
         )
@@ -173,6 +220,9 @@ impl ContactRepository for SqliteContactRepository {
         .bind(&contact.source)
         .bind(&contact.avatar_type)
         .bind(&contact.avatar_path)
+        .bind(&contact.birthday)
+        .bind(&contact.anniversary)
+        .bind(contact.is_vip)
         .bind(contact.send_count)
         .bind(contact.receive_count)
         .bind(contact.last_used_at)
@@ -386,7 +436,8 @@ impl ContactRepository for SqliteContactRepository {
         sqlx::query!(
             r#"
             UPDATE contacts
-            SET avatar_type = ?, avatar_path = ?, updated_at = CURRENT_TIMESTAMP
+            SET avatar_type = ?, avatar_path = ?, avatar_attempts = 0, avatar_retry_at = NULL,
+                updated_at = CURRENT_TIMESTAMP
             WHERE id = ?
             "#,
             avatar_type,
@@ -403,21 +454,67 @@ impl ContactRepository for SqliteContactRepository {
     async fn find_contacts_without_avatars(
         &self,
         limit: i64,
+        active_since: chrono::DateTime<chrono::Utc>,
+        recent_since: chrono::DateTime<chrono::Utc>,
     ) -> Result<Vec<Contact>, DatabaseError> {
         sqlx::query_as::<_, Contact>(
             r#"
-            SELECT * FROM contacts
-            WHERE avatar_type = 'unprocessed'
-            ORDER BY receive_count + send_count DESC, last_used_at DESC
+            WITH recent_senders AS (
+                SELECT DISTINCT lower(json_extract(e.`from`, '$.address')) AS email
+                FROM emails e
+                JOIN folders f ON f.id = e.folder_id
+                WHERE f.folder_type = 'inbox' AND e.received_at >= ?
+            )
+            SELECT c.* FROM contacts c
+            LEFT JOIN recent_senders r ON r.email = c.email
+            WHERE c.avatar_type = 'unprocessed'
+              AND (c.avatar_retry_at IS NULL OR c.avatar_retry_at <= ?)
+              AND c.last_used_at >= ?
+            ORDER BY r.email IS NOT NULL DESC,
+                     c.receive_count + c.send_count DESC,
+                     c.last_used_at DESC
             LIMIT ?
             "#,
         )
+        .bind(recent_since)
+        .bind(Utc::now())
+        .bind(active_since)
         .bind(limit)
         .fetch_all(&self.pool)
         .await
         .map_err(DatabaseError::ConnectionError)
     }
 
+    async fn increment_avatar_attempts(&self, id: Uuid) -> Result<i64, DatabaseError> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            UPDATE contacts
+            SET avatar_attempts = avatar_attempts + 1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            RETURNING avatar_attempts
+            "#,
+        )
+        .bind(id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn schedule_avatar_retry(
+        &self,
+        id: Uuid,
+        retry_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query("UPDATE contacts SET avatar_retry_at = ? WHERE id = ?")
+            .bind(retry_at)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
     async fn reset_counters(&self) -> Result<(), DatabaseError> {
         sqlx::query!(
             r#"
@@ -429,6 +526,171 @@ impl ContactRepository for SqliteContactRepository {
         .await
         .map_err(DatabaseError::ConnectionError)?;
 
+        sqlx::query("DELETE FROM contact_interactions")
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_matching(&self, query: &str, limit: i64) -> Result<Vec<Contact>, DatabaseError> {
+        let pattern = format!("%{}%", query);
+
+        sqlx::query_as::<_, Contact>(
+            r#"
+            SELECT * FROM contacts
+            WHERE email LIKE ? OR display_name LIKE ? OR first_name LIKE ? OR last_name LIKE ?
+            ORDER BY last_used_at IS NULL, last_used_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn record_interaction(
+        &self,
+        contact_id: Uuid,
+        account_id: Uuid,
+        field: ContactField,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DatabaseError> {
+        let existing = sqlx::query_as::<_, ContactInteraction>(
+            r#"
+            SELECT * FROM contact_interactions
+            WHERE contact_id = ? AND account_id = ? AND field = ?
+            "#,
+        )
+        .bind(contact_id.to_string())
+        .bind(account_id.to_string())
+        .bind(field.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        let interaction = match existing {
+            Some(mut interaction) => {
+                interaction.record(at);
+                interaction
+            }
+            None => ContactInteraction::new(contact_id, account_id, field, at),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO contact_interactions
+                (contact_id, account_id, field, interaction_count, score, last_interaction_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(contact_id, account_id, field) DO UPDATE SET
+                interaction_count = excluded.interaction_count,
+                score = excluded.score,
+                last_interaction_at = excluded.last_interaction_at
+            "#,
+        )
+        .bind(contact_id.to_string())
+        .bind(account_id.to_string())
+        .bind(field.as_str())
+        .bind(interaction.interaction_count)
+        .bind(interaction.score)
+        .bind(interaction.last_interaction_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
         Ok(())
     }
+
+    async fn find_interactions(
+        &self,
+        contact_ids: &[Uuid],
+        account_id: Option<Uuid>,
+    ) -> Result<Vec<ContactInteraction>, DatabaseError> {
+        if contact_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let placeholders = vec!["?"; contact_ids.len()].join(", ");
+        let mut sql = format!(
+            "SELECT * FROM contact_interactions WHERE contact_id IN ({})",
+            placeholders
+        );
+        if account_id.is_some() {
+            sql.push_str(" AND account_id = ?");
+        }
+
+        let mut query = sqlx::query_as::<_, ContactInteraction>(&sql);
+        for contact_id in contact_ids {
+            query = query.bind(contact_id.to_string());
+        }
+        if let Some(account_id) = account_id {
+            query = query.bind(account_id.to_string());
+        }
+
+        query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_contacts_with_dates(&self) -> Result<Vec<Contact>, DatabaseError> {
+        sqlx::query_as::<_, Contact>(
+            "SELECT * FROM contacts WHERE birthday IS NOT NULL OR anniversary IS NOT NULL",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn update_dates(
+        &self,
+        id: Uuid,
+        birthday: Option<&str>,
+        anniversary: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE contacts
+            SET birthday = COALESCE(?, birthday),
+                anniversary = COALESCE(?, anniversary),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(birthday)
+        .bind(anniversary)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn set_vip(&self, id: Uuid, is_vip: bool) -> Result<(), DatabaseError> {
+        sqlx::query("UPDATE contacts SET is_vip = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(is_vip)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn is_vip_address(&self, email: &str) -> Result<bool, DatabaseError> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM contacts WHERE email = ? AND is_vip = 1)",
+        )
+        .bind(email.to_lowercase())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
 }
@@ -0,0 +1,179 @@
+use crate::database::{error::DatabaseError, models::contact_security::ContactSecurity};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+#[async_trait]
+pub trait ContactSecurityRepository {
+    async fn find_by_email(&self, email: &str) -> Result<Option<ContactSecurity>, DatabaseError>;
+    /// Stored preferences of the given addresses; addresses without any are left out
+    async fn find_by_emails(
+        &self,
+        emails: &[String],
+    ) -> Result<Vec<ContactSecurity>, DatabaseError>;
+    async fn upsert(&self, security: &ContactSecurity) -> Result<(), DatabaseError>;
+    async fn delete(&self, email: &str) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteContactSecurityRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteContactSecurityRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ContactSecurityRepository for SqliteContactSecurityRepository {
+    async fn find_by_email(&self, email: &str) -> Result<Option<ContactSecurity>, DatabaseError> {
+        sqlx::query_as::<_, ContactSecurity>("SELECT * FROM contact_security WHERE email = ?")
+            .bind(email.trim().to_lowercase())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_emails(
+        &self,
+        emails: &[String],
+    ) -> Result<Vec<ContactSecurity>, DatabaseError> {
+        let mut preferences = Vec::new();
+
+        for email in emails {
+            preferences.extend(self.find_by_email(email).await?);
+        }
+
+        Ok(preferences)
+    }
+
+    async fn upsert(&self, security: &ContactSecurity) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO contact_security (
+                email, policy, pgp_fingerprint, pgp_public_key, pgp_key_source,
+                pgp_key_fetched_at
+            ) VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(email) DO UPDATE SET
+                policy = excluded.policy,
+                pgp_fingerprint = excluded.pgp_fingerprint,
+                pgp_public_key = excluded.pgp_public_key,
+                pgp_key_source = excluded.pgp_key_source,
+                pgp_key_fetched_at = excluded.pgp_key_fetched_at,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(security.email.trim().to_lowercase())
+        .bind(security.policy.as_str())
+        .bind(&security.pgp_fingerprint)
+        .bind(&security.pgp_public_key)
+        .bind(
+            security
+                .pgp_key_source
+                .map(|source| source.as_str().to_string()),
+        )
+        .bind(security.pgp_key_fetched_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, email: &str) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM contact_security WHERE email = ?")
+            .bind(email.trim().to_lowercase())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::contact_security::{EncryptionPolicy, PgpKeySource};
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE contact_security (
+                email TEXT NOT NULL PRIMARY KEY,
+                policy TEXT NOT NULL DEFAULT 'auto',
+                pgp_fingerprint TEXT,
+                pgp_public_key TEXT,
+                pgp_key_source TEXT,
+                pgp_key_fetched_at TIMESTAMP,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_find_by_emails() {
+        let repository = SqliteContactSecurityRepository::new(create_test_pool().await);
+
+        repository
+            .upsert(&ContactSecurity {
+                policy: EncryptionPolicy::RequireSmime,
+                ..ContactSecurity::new("Alice@Example.com")
+            })
+            .await
+            .unwrap();
+        repository
+            .upsert(&ContactSecurity {
+                policy: EncryptionPolicy::AlwaysPgp,
+                pgp_fingerprint: Some("ABCD".to_string()),
+                pgp_public_key: Some("-----BEGIN PGP PUBLIC KEY BLOCK-----".to_string()),
+                pgp_key_source: Some(PgpKeySource::Wkd),
+                pgp_key_fetched_at: Some(Utc::now()),
+                ..ContactSecurity::new("bob@example.com")
+            })
+            .await
+            .unwrap();
+        // Updating a preference replaces the stored row
+        repository
+            .upsert(&ContactSecurity {
+                policy: EncryptionPolicy::PlaintextOnly,
+                ..ContactSecurity::new("alice@example.com")
+            })
+            .await
+            .unwrap();
+
+        let found = repository
+            .find_by_emails(&[
+                "alice@example.com".to_string(),
+                "BOB@example.com".to_string(),
+                "carol@example.com".to_string(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].policy, EncryptionPolicy::PlaintextOnly);
+        assert_eq!(found[1].pgp_key_source, Some(PgpKeySource::Wkd));
+
+        repository.delete("alice@example.com").await.unwrap();
+        assert!(repository
+            .find_by_email("alice@example.com")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
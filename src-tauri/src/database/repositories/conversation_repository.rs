@@ -1,5 +1,9 @@
-use crate::database::{error::DatabaseError, models::conversation::Conversation};
+use crate::database::{
+    error::DatabaseError,
+    models::{conversation::Conversation, email::Email, email_dto::FolderInfo, folder::FolderType},
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
@@ -11,6 +15,10 @@ pub trait ConversationRepository {
         remote_id: &str,
     ) -> Result<Option<Conversation>, DatabaseError>;
     async fn find_by_ids(&self, ids: Vec<Uuid>) -> Result<Vec<Conversation>, DatabaseError>;
+    async fn find_by_remote_ids(
+        &self,
+        remote_ids: &[String],
+    ) -> Result<Vec<Conversation>, DatabaseError>;
     async fn create(&self, conversation: &Conversation) -> Result<Uuid, DatabaseError>;
     async fn update(&self, conversation: &Conversation) -> Result<(), DatabaseError>;
     async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
@@ -18,6 +26,30 @@ pub trait ConversationRepository {
         &self,
         remote_id: &str,
     ) -> Result<Conversation, DatabaseError>;
+    /// The messages of a conversation in all folders of the account, newest
+    /// first. A message stored in several folders is returned once.
+    async fn find_messages_across_folders(
+        &self,
+        conversation_id: Uuid,
+        account_id: Uuid,
+    ) -> Result<Vec<(Email, FolderInfo)>, DatabaseError>;
+    /// Mutes the conversation as of `muted_at`, or unmutes it for `None`
+    async fn set_muted(
+        &self,
+        id: Uuid,
+        muted_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError>;
+    /// Moves the emails of conversation `from` into `into` and deletes `from`.
+    /// Returns the number of emails moved.
+    async fn merge(&self, into: Uuid, from: Uuid) -> Result<u64, DatabaseError>;
+    /// Caches the AI summary of the conversation unless its message count
+    /// changed from `message_count` meanwhile. Returns whether it was stored.
+    async fn update_ai_cache(
+        &self,
+        id: Uuid,
+        ai_cache: &str,
+        message_count: i64,
+    ) -> Result<bool, DatabaseError>;
 }
 
 pub struct SqliteConversationRepository {
@@ -70,6 +102,35 @@ impl ConversationRepository for SqliteConversationRepository {
             .map_err(DatabaseError::ConnectionError)
     }
 
+    async fn find_by_remote_ids(
+        &self,
+        remote_ids: &[String],
+    ) -> Result<Vec<Conversation>, DatabaseError> {
+        if remote_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let placeholders = remote_ids
+            .iter()
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = format!(
+            "SELECT * FROM conversations WHERE remote_id IN ({})",
+            placeholders
+        );
+
+        let mut query_builder = sqlx::query_as::<_, Conversation>(&query);
+        for remote_id in remote_ids {
+            query_builder = query_builder.bind(remote_id);
+        }
+
+        query_builder
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
     async fn create(&self, conversation: &Conversation) -> Result<Uuid, DatabaseError> {
         let id = conversation.id.to_string();
 
@@ -137,6 +198,7 @@ impl ConversationRepository for SqliteConversationRepository {
             remote_id: remote_id.to_string(),
             message_count: 0,
             ai_cache: None,
+            muted_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -144,6 +206,135 @@ impl ConversationRepository for SqliteConversationRepository {
         self.create(&conversation).await?;
         Ok(conversation)
     }
+
+    async fn find_messages_across_folders(
+        &self,
+        conversation_id: Uuid,
+        account_id: Uuid,
+    ) -> Result<Vec<(Email, FolderInfo)>, DatabaseError> {
+        #[derive(sqlx::FromRow)]
+        struct EmailInFolder {
+            #[sqlx(flatten)]
+            email: Email,
+            folder_name: String,
+            folder_type: FolderType,
+        }
+
+        let rows = sqlx::query_as::<_, EmailInFolder>(
+            r#"
+            SELECT e.*, f.name AS folder_name, f.folder_type
+            FROM emails e
+            JOIN folders f ON e.folder_id = f.id
+            WHERE e.conversation_id = ? AND e.account_id = ? AND e.is_deleted = 0
+            ORDER BY e.received_at DESC
+            "#,
+        )
+        .bind(conversation_id.to_string())
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        // Copies of one message, e.g. in the inbox and an archive folder,
+        // collapse into the one from the folder that ranks first
+        let mut messages: Vec<(Email, FolderInfo)> = Vec::with_capacity(rows.len());
+        for row in rows {
+            let folder = FolderInfo {
+                id: row.email.folder_id,
+                name: row.folder_name,
+                folder_type: row.folder_type,
+            };
+            match messages
+                .iter_mut()
+                .find(|(email, _)| email.message_id == row.email.message_id)
+            {
+                Some(existing) => {
+                    if copy_rank(folder.folder_type) < copy_rank(existing.1.folder_type) {
+                        *existing = (row.email, folder);
+                    }
+                }
+                None => messages.push((row.email, folder)),
+            }
+        }
+
+        Ok(messages)
+    }
+
+    async fn set_muted(
+        &self,
+        id: Uuid,
+        muted_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query("UPDATE conversations SET muted_at = ? WHERE id = ?")
+            .bind(muted_at)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn merge(&self, into: Uuid, from: Uuid) -> Result<u64, DatabaseError> {
+        if into == from {
+            return Ok(0);
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        // The count triggers on emails keep message_count of both in step
+        let moved = sqlx::query("UPDATE emails SET conversation_id = ? WHERE conversation_id = ?")
+            .bind(into.to_string())
+            .bind(from.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::ConnectionError)?
+            .rows_affected();
+
+        sqlx::query("DELETE FROM conversations WHERE id = ?")
+            .bind(from.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(moved)
+    }
+
+    async fn update_ai_cache(
+        &self,
+        id: Uuid,
+        ai_cache: &str,
+        message_count: i64,
+    ) -> Result<bool, DatabaseError> {
+        let result =
+            sqlx::query("UPDATE conversations SET ai_cache = ? WHERE id = ? AND message_count = ?")
+                .bind(ai_cache)
+                .bind(id.to_string())
+                .bind(message_count)
+                .execute(&self.pool)
+                .await
+                .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Which copy of a message stored in several folders is shown
+fn copy_rank(folder_type: FolderType) -> u8 {
+    match folder_type {
+        FolderType::Inbox => 0,
+        FolderType::Archive | FolderType::Custom | FolderType::Starred | FolderType::Search => 1,
+        FolderType::Sent => 2,
+        FolderType::Draft => 3,
+        FolderType::Spam => 4,
+        FolderType::Trash => 5,
+    }
 }
 
 #[cfg(test)]
@@ -156,6 +347,56 @@ mod tests {
         db.get_pool().clone()
     }
 
+    async fn insert_account(pool: &SqlitePool) -> Uuid {
+        let account_id = Uuid::now_v7();
+        sqlx::query(
+            "INSERT INTO accounts (id, name, email, account_type, settings) VALUES (?, 'Test', 'me@example.com', 'imap', '{}')",
+        )
+        .bind(account_id.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+        account_id
+    }
+
+    async fn insert_folder(pool: &SqlitePool, account_id: Uuid, folder_type: &str) -> Uuid {
+        let folder_id = Uuid::now_v7();
+        sqlx::query("INSERT INTO folders (id, account_id, name, folder_type) VALUES (?, ?, ?, ?)")
+            .bind(folder_id.to_string())
+            .bind(account_id.to_string())
+            .bind(folder_type)
+            .bind(folder_type)
+            .execute(pool)
+            .await
+            .unwrap();
+        folder_id
+    }
+
+    async fn insert_email(
+        pool: &SqlitePool,
+        account_id: Uuid,
+        folder_id: Uuid,
+        message_id: &str,
+        conversation_id: Uuid,
+    ) -> Uuid {
+        let email_id = Uuid::now_v7();
+        sqlx::query(
+            r#"
+            INSERT INTO emails (id, account_id, folder_id, message_id, conversation_id, `from`, received_at)
+            VALUES (?, ?, ?, ?, ?, '{"address":"sender@example.com"}', CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(email_id.to_string())
+        .bind(account_id.to_string())
+        .bind(folder_id.to_string())
+        .bind(message_id)
+        .bind(conversation_id.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+        email_id
+    }
+
     #[tokio::test]
     async fn test_create_and_find_conversation() {
         let pool = setup_test_db().await;
@@ -166,6 +407,7 @@ mod tests {
             remote_id: "test-remote-id".to_string(),
             message_count: 0,
             ai_cache: None,
+            muted_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -188,6 +430,7 @@ mod tests {
             remote_id: "test-remote-123".to_string(),
             message_count: 0,
             ai_cache: None,
+            muted_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -232,6 +475,7 @@ mod tests {
             remote_id: "update-test".to_string(),
             message_count: 0,
             ai_cache: None,
+            muted_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -247,6 +491,57 @@ mod tests {
         assert_eq!(found.ai_cache, Some("test cache".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_ai_cache_cleared_by_new_message() {
+        let pool = setup_test_db().await;
+        let repo = SqliteConversationRepository::new(pool.clone());
+        let account_id = insert_account(&pool).await;
+        let inbox = insert_folder(&pool, account_id, "inbox").await;
+
+        let conversation = repo.find_or_create_by_remote_id("summary").await.unwrap();
+        insert_email(
+            &pool,
+            account_id,
+            inbox,
+            "<one@example.com>",
+            conversation.id,
+        )
+        .await;
+
+        assert!(!repo
+            .update_ai_cache(conversation.id, "stale", 0)
+            .await
+            .unwrap());
+        assert!(repo
+            .update_ai_cache(conversation.id, "summary", 1)
+            .await
+            .unwrap());
+        let found = repo.find_by_id(conversation.id).await.unwrap().unwrap();
+        assert_eq!(found.ai_cache.as_deref(), Some("summary"));
+
+        insert_email(
+            &pool,
+            account_id,
+            inbox,
+            "<two@example.com>",
+            conversation.id,
+        )
+        .await;
+        let found = repo.find_by_id(conversation.id).await.unwrap().unwrap();
+        assert_eq!(found.message_count, 2);
+        assert!(found.ai_cache.is_none());
+
+        // A message moved in by merging clears it too
+        repo.update_ai_cache(conversation.id, "summary", 2)
+            .await
+            .unwrap();
+        let other = repo.find_or_create_by_remote_id("other").await.unwrap();
+        insert_email(&pool, account_id, inbox, "<three@example.com>", other.id).await;
+        repo.merge(conversation.id, other.id).await.unwrap();
+        let found = repo.find_by_id(conversation.id).await.unwrap().unwrap();
+        assert!(found.ai_cache.is_none());
+    }
+
     #[tokio::test]
     async fn test_find_by_ids() {
         let pool = setup_test_db().await;
@@ -257,6 +552,7 @@ mod tests {
             remote_id: "multi-1".to_string(),
             message_count: 0,
             ai_cache: None,
+            muted_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -266,6 +562,7 @@ mod tests {
             remote_id: "multi-2".to_string(),
             message_count: 0,
             ai_cache: None,
+            muted_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -277,6 +574,118 @@ mod tests {
         assert_eq!(found.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_set_muted() {
+        let pool = setup_test_db().await;
+        let repo = SqliteConversationRepository::new(pool);
+
+        let conversation = repo.find_or_create_by_remote_id("mute-test").await.unwrap();
+        assert!(conversation.muted_at.is_none());
+
+        repo.set_muted(conversation.id, Some(chrono::Utc::now()))
+            .await
+            .unwrap();
+        let found = repo.find_by_id(conversation.id).await.unwrap().unwrap();
+        assert!(found.muted_at.is_some());
+        assert!(found.to_list_item(Vec::new()).muted);
+
+        repo.set_muted(conversation.id, None).await.unwrap();
+        let found = repo.find_by_id(conversation.id).await.unwrap().unwrap();
+        assert!(found.muted_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_merge_conversations() {
+        let pool = setup_test_db().await;
+        let repo = SqliteConversationRepository::new(pool.clone());
+
+        let account_id = insert_account(&pool).await;
+        let folder_id = insert_folder(&pool, account_id, "inbox").await;
+
+        let root = repo
+            .find_or_create_by_remote_id("thread-root")
+            .await
+            .unwrap();
+        let child = repo
+            .find_or_create_by_remote_id("thread-child")
+            .await
+            .unwrap();
+        insert_email(&pool, account_id, folder_id, "<a@example.com>", root.id).await;
+        insert_email(&pool, account_id, folder_id, "<b@example.com>", child.id).await;
+
+        let found = repo
+            .find_by_remote_ids(&["thread-root".to_string(), "thread-child".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+
+        assert_eq!(repo.merge(root.id, child.id).await.unwrap(), 1);
+        assert_eq!(repo.merge(root.id, root.id).await.unwrap(), 0);
+
+        let merged = repo.find_by_id(root.id).await.unwrap().unwrap();
+        assert_eq!(merged.message_count, 2);
+        assert!(repo.find_by_id(child.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_find_messages_across_folders() {
+        let pool = setup_test_db().await;
+        let repo = SqliteConversationRepository::new(pool.clone());
+
+        let account_id = insert_account(&pool).await;
+        let inbox = insert_folder(&pool, account_id, "inbox").await;
+        let sent = insert_folder(&pool, account_id, "sent").await;
+        let archive = insert_folder(&pool, account_id, "archive").await;
+        let other_account = insert_account(&pool).await;
+        let other_inbox = insert_folder(&pool, other_account, "inbox").await;
+
+        let conversation = repo
+            .find_or_create_by_remote_id("cross-folder")
+            .await
+            .unwrap();
+        let question =
+            insert_email(&pool, account_id, inbox, "<q@example.com>", conversation.id).await;
+        insert_email(
+            &pool,
+            account_id,
+            archive,
+            "<q@example.com>",
+            conversation.id,
+        )
+        .await;
+        let answer =
+            insert_email(&pool, account_id, sent, "<a@example.com>", conversation.id).await;
+        insert_email(
+            &pool,
+            other_account,
+            other_inbox,
+            "<q@example.com>",
+            conversation.id,
+        )
+        .await;
+
+        let messages = repo
+            .find_messages_across_folders(conversation.id, account_id)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+
+        let (email, folder) = messages
+            .iter()
+            .find(|(email, _)| email.id == question)
+            .unwrap();
+        assert_eq!(email.account_id, account_id);
+        assert_eq!(folder.id, inbox);
+        assert_eq!(folder.folder_type, FolderType::Inbox);
+
+        let (_, folder) = messages
+            .iter()
+            .find(|(email, _)| email.id == answer)
+            .unwrap();
+        assert_eq!(folder.name, "sent");
+        assert_eq!(folder.folder_type, FolderType::Sent);
+    }
+
     #[tokio::test]
     async fn test_delete_conversation() {
         let pool = setup_test_db().await;
@@ -287,6 +696,7 @@ mod tests {
             remote_id: "delete-test".to_string(),
             message_count: 0,
             ai_cache: None,
+            muted_at: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
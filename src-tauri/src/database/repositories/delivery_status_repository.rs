@@ -0,0 +1,268 @@
+use crate::database::{
+    error::DatabaseError,
+    models::delivery_status::{DeliveryAction, Disposition, RecipientDeliveryStatus},
+};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait DeliveryStatusRepository {
+    async fn find_by_email(
+        &self,
+        email_id: Uuid,
+    ) -> Result<Vec<RecipientDeliveryStatus>, DatabaseError>;
+    /// Adds a pending entry for every recipient a DSN was requested for
+    async fn record_pending(
+        &self,
+        email_id: Uuid,
+        recipients: &[String],
+    ) -> Result<(), DatabaseError>;
+    /// Stores a recipient's status from a report. A "delayed" report never
+    /// replaces a final outcome, since reports can arrive out of order.
+    async fn apply_report(&self, status: &RecipientDeliveryStatus) -> Result<(), DatabaseError>;
+    /// Stores a read receipt. A receipt implies delivery, so pending and
+    /// delayed recipients become delivered.
+    async fn apply_disposition(
+        &self,
+        email_id: Uuid,
+        recipient: &str,
+        disposition: Disposition,
+        report_email_id: Uuid,
+    ) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteDeliveryStatusRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteDeliveryStatusRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DeliveryStatusRepository for SqliteDeliveryStatusRepository {
+    async fn find_by_email(
+        &self,
+        email_id: Uuid,
+    ) -> Result<Vec<RecipientDeliveryStatus>, DatabaseError> {
+        sqlx::query_as::<_, RecipientDeliveryStatus>(
+            "SELECT * FROM email_delivery_status WHERE email_id = ? ORDER BY recipient",
+        )
+        .bind(email_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn record_pending(
+        &self,
+        email_id: Uuid,
+        recipients: &[String],
+    ) -> Result<(), DatabaseError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        for recipient in recipients {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO email_delivery_status (email_id, recipient, action)
+                VALUES (?, ?, ?)
+                "#,
+            )
+            .bind(email_id.to_string())
+            .bind(recipient.trim().to_lowercase())
+            .bind(DeliveryAction::Pending.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+        }
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn apply_report(&self, status: &RecipientDeliveryStatus) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO email_delivery_status
+                (email_id, recipient, action, status, diagnostic, report_email_id, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(email_id, recipient) DO UPDATE SET
+                action = excluded.action,
+                status = excluded.status,
+                diagnostic = excluded.diagnostic,
+                report_email_id = excluded.report_email_id,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE excluded.action != 'delayed'
+               OR email_delivery_status.action IN ('pending', 'delayed')
+            "#,
+        )
+        .bind(status.email_id.to_string())
+        .bind(status.recipient.trim().to_lowercase())
+        .bind(status.action.as_str())
+        .bind(&status.status)
+        .bind(&status.diagnostic)
+        .bind(status.report_email_id.map(|id| id.to_string()))
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn apply_disposition(
+        &self,
+        email_id: Uuid,
+        recipient: &str,
+        disposition: Disposition,
+        report_email_id: Uuid,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO email_delivery_status
+                (email_id, recipient, action, report_email_id, disposition, disposition_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT(email_id, recipient) DO UPDATE SET
+                action = CASE
+                    WHEN email_delivery_status.action IN ('pending', 'delayed') THEN excluded.action
+                    ELSE email_delivery_status.action
+                END,
+                disposition = excluded.disposition,
+                disposition_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(email_id.to_string())
+        .bind(recipient.trim().to_lowercase())
+        .bind(DeliveryAction::Delivered.as_str())
+        .bind(report_email_id.to_string())
+        .bind(disposition.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE email_delivery_status (
+                email_id TEXT NOT NULL,
+                recipient TEXT NOT NULL,
+                action TEXT NOT NULL DEFAULT 'pending',
+                status TEXT,
+                diagnostic TEXT,
+                report_email_id TEXT,
+                disposition TEXT,
+                disposition_at TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (email_id, recipient)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn report(email_id: Uuid, action: DeliveryAction, status: &str) -> RecipientDeliveryStatus {
+        RecipientDeliveryStatus {
+            email_id,
+            recipient: "Bob@Example.com".to_string(),
+            action,
+            status: Some(status.to_string()),
+            diagnostic: None,
+            report_email_id: Some(Uuid::now_v7()),
+            disposition: None,
+            disposition_at: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delayed_report_does_not_replace_final_status() {
+        let repository = SqliteDeliveryStatusRepository::new(create_test_pool().await);
+        let email_id = Uuid::now_v7();
+
+        repository
+            .record_pending(
+                email_id,
+                &["bob@example.com".to_string(), "eve@example.com".to_string()],
+            )
+            .await
+            .unwrap();
+
+        repository
+            .apply_report(&report(email_id, DeliveryAction::Delayed, "4.4.1"))
+            .await
+            .unwrap();
+        repository
+            .apply_report(&report(email_id, DeliveryAction::Delivered, "2.0.0"))
+            .await
+            .unwrap();
+        repository
+            .apply_report(&report(email_id, DeliveryAction::Delayed, "4.4.1"))
+            .await
+            .unwrap();
+
+        let statuses = repository.find_by_email(email_id).await.unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].recipient, "bob@example.com");
+        assert_eq!(statuses[0].action, DeliveryAction::Delivered);
+        assert_eq!(statuses[0].status.as_deref(), Some("2.0.0"));
+        assert_eq!(statuses[1].action, DeliveryAction::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_read_receipt_marks_delivered() {
+        let repository = SqliteDeliveryStatusRepository::new(create_test_pool().await);
+        let email_id = Uuid::now_v7();
+
+        repository
+            .record_pending(email_id, &["bob@example.com".to_string()])
+            .await
+            .unwrap();
+        repository
+            .apply_disposition(
+                email_id,
+                "Bob@Example.com",
+                Disposition::Displayed,
+                Uuid::now_v7(),
+            )
+            .await
+            .unwrap();
+        repository
+            .apply_report(&report(email_id, DeliveryAction::Delayed, "4.4.1"))
+            .await
+            .unwrap();
+
+        let statuses = repository.find_by_email(email_id).await.unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].action, DeliveryAction::Delivered);
+        assert_eq!(statuses[0].disposition, Some(Disposition::Displayed));
+        assert!(statuses[0].disposition_at.is_some());
+    }
+}
@@ -1,6 +1,11 @@
-use crate::database::{error::DatabaseError, models::email::Email, models::folder::FolderType};
+use crate::database::{
+    error::DatabaseError,
+    models::email::{Email, RiskReason},
+    models::email_dto::{FolderListState, UnifiedInboxCount},
+    models::folder::{AutoArchiveRule, FolderType},
+};
 use async_trait::async_trait;
-use sqlx::SqlitePool;
+use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
 #[async_trait]
@@ -33,6 +38,74 @@ pub trait EmailRepository {
         &self,
         conversation_id: Uuid,
     ) -> Result<Vec<Email>, DatabaseError>;
+    /// Threaded emails of the account with one of the given Message-IDs
+    async fn find_threaded_by_message_ids(
+        &self,
+        account_id: Uuid,
+        message_ids: &[String],
+    ) -> Result<Vec<Email>, DatabaseError>;
+    /// Threaded emails of the account received since `since` whose subject
+    /// contains `subject`, newest first
+    async fn find_threaded_by_subject(
+        &self,
+        account_id: Uuid,
+        subject: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError>;
+    async fn find_unified_inbox(
+        &self,
+        limit: i64,
+        offset: i64,
+        sort_by: &str,
+        sort_order: &str,
+        filter_read: Option<bool>,
+    ) -> Result<Vec<Email>, DatabaseError>;
+    async fn count_unified_inbox(&self) -> Result<Vec<UnifiedInboxCount>, DatabaseError>;
+    /// Inbox emails of at least `min_priority`, most important first
+    async fn find_priority_inbox(
+        &self,
+        account_id: Option<Uuid>,
+        min_priority: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Email>, DatabaseError>;
+    /// Whether another email of the conversation was sent from one of the addresses
+    async fn conversation_has_sender(
+        &self,
+        conversation_id: &str,
+        exclude_id: Uuid,
+        addresses: &[String],
+    ) -> Result<bool, DatabaseError>;
+    async fn update_priority(&self, id: Uuid, priority: i64) -> Result<(), DatabaseError>;
+    async fn update_risk(
+        &self,
+        id: Uuid,
+        risk_score: i64,
+        risk_reasons: &[RiskReason],
+    ) -> Result<(), DatabaseError>;
+    /// Sets or clears a priority flag on every email from the sender
+    async fn set_sender_priority_flag(
+        &self,
+        sender: &str,
+        flag: i64,
+        set: bool,
+    ) -> Result<u64, DatabaseError>;
+    /// Sets the category of every email from the sender
+    async fn set_sender_category(&self, sender: &str, category: &str)
+        -> Result<u64, DatabaseError>;
+    /// Records trackers found in the email; known ones are kept
+    async fn record_trackers(
+        &self,
+        email_id: Uuid,
+        trackers: &[String],
+    ) -> Result<(), DatabaseError>;
+    async fn find_trackers(&self, email_id: Uuid) -> Result<Vec<String>, DatabaseError>;
+    /// Received emails whose sender address ends with `suffix`, leaving out
+    /// those already in spam or trash
+    async fn find_received_by_sender_suffix(
+        &self,
+        suffix: &str,
+    ) -> Result<Vec<Email>, DatabaseError>;
     async fn find_by_labels(
         &self,
         label_ids: &[Uuid],
@@ -56,10 +129,20 @@ pub trait EmailRepository {
     async fn soft_delete(&self, id: Uuid) -> Result<(), DatabaseError>;
     async fn undelete(&self, id: Uuid) -> Result<(), DatabaseError>;
     async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+    /// Tombstoned emails deleted at or after `since`, most recently deleted first
+    async fn find_recently_deleted(
+        &self,
+        account_id: Option<Uuid>,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError>;
     async fn count_unread_all(&self) -> Result<i64, DatabaseError>;
     async fn count_unread_by_folders(&self, folder_ids: &[Uuid]) -> Result<i64, DatabaseError>;
+    /// Total and unread count of the given emails, skipping deleted ones
+    async fn count_by_ids(&self, ids: &[Uuid]) -> Result<(i64, i64), DatabaseError>;
     async fn find_synced_batch(&self, limit: i64, offset: i64)
         -> Result<Vec<Email>, DatabaseError>;
+    /// Number of emails `find_synced_batch` pages through
+    async fn count_synced(&self) -> Result<i64, DatabaseError>;
     async fn find_synced_by_account(&self, account_id: Uuid) -> Result<Vec<Email>, DatabaseError>;
     async fn find_with_folder_type(&self) -> Result<Vec<(Email, FolderType)>, DatabaseError>;
     async fn undelete_by_account(&self, account_id: Uuid) -> Result<u64, DatabaseError>;
@@ -72,7 +155,28 @@ pub trait EmailRepository {
     async fn update_read_status(&self, id: Uuid, is_read: bool) -> Result<(), DatabaseError>;
     async fn update_flagged_status(&self, id: Uuid, is_flagged: bool) -> Result<(), DatabaseError>;
     async fn update_ai_cache(&self, id: Uuid, ai_cache_json: &str) -> Result<(), DatabaseError>;
-    async fn find_pending_ai_analysis(&self, limit: i64) -> Result<Vec<Uuid>, DatabaseError>;
+    /// Records the server copy of a draft. `updated_at` is left alone, as it
+    /// tracks local edits for reconciling drafts with the server.
+    async fn mark_draft_uploaded(
+        &self,
+        id: Uuid,
+        remote_id: &str,
+        uploaded_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DatabaseError>;
+    /// Personal inbox emails without an analysis that are not queued for one
+    /// yet, leaving out those in the excluded folders or with excluded labels
+    async fn find_pending_ai_analysis(
+        &self,
+        limit: i64,
+        excluded_folders: &[Uuid],
+        excluded_labels: &[Uuid],
+    ) -> Result<Vec<Uuid>, DatabaseError>;
+    /// The account's most recently sent emails with a body, newest first
+    async fn find_recent_sent(
+        &self,
+        account_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Email>, DatabaseError>;
     async fn find_for_calendar(
         &self,
         folder_ids: &[Uuid],
@@ -92,6 +196,47 @@ pub trait EmailRepository {
         id: Uuid,
         remind_at: Option<chrono::DateTime<chrono::Utc>>,
     ) -> Result<(), DatabaseError>;
+    async fn update_snoozed_until(
+        &self,
+        id: Uuid,
+        snoozed_until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), DatabaseError>;
+    async fn find_snoozed(&self, limit: i64, offset: i64) -> Result<Vec<Email>, DatabaseError>;
+    async fn find_due_snoozed(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError>;
+    async fn update_scheduled_send_at(
+        &self,
+        id: Uuid,
+        scheduled_send_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), DatabaseError>;
+    async fn find_due_scheduled_sends(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError>;
+    /// A range of a folder's visible messages together with the list's size and
+    /// order token, read consistently. `None` if the folder does not exist.
+    async fn find_folder_window(
+        &self,
+        folder_id: Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Option<(FolderListState, Vec<Email>)>, DatabaseError>;
+    /// Messages of a folder matched by its auto-archive rule, oldest first
+    async fn find_auto_archive_candidates(
+        &self,
+        folder_id: Uuid,
+        rule: &AutoArchiveRule,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<Email>, DatabaseError>;
+    async fn count_auto_archive_candidates(
+        &self,
+        folder_id: Uuid,
+        rule: &AutoArchiveRule,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<i64, DatabaseError>;
 }
 
 pub struct SqliteEmailRepository {
@@ -102,6 +247,22 @@ impl SqliteEmailRepository {
     pub fn new(pool: SqlitePool) -> Self {
         Self { pool }
     }
+
+    /// WHERE clause matching the messages an auto-archive rule moves. Binds
+    /// the folder id and the cutoff date.
+    fn auto_archive_filter(rule: &AutoArchiveRule) -> String {
+        let mut filter = String::from(
+            "folder_id = ? AND received_at < ? AND is_deleted = 0 AND is_draft = 0 \
+             AND snoozed_until IS NULL",
+        );
+        if rule.only_read {
+            filter.push_str(" AND is_read = 1");
+        }
+        if rule.skip_flagged {
+            filter.push_str(" AND is_flagged = 0");
+        }
+        filter
+    }
 }
 
 #[async_trait]
@@ -180,7 +341,9 @@ impl EmailRepository for SqliteEmailRepository {
         filter_read: Option<bool>,
         filter_has_attachments: Option<bool>,
     ) -> Result<Vec<Email>, DatabaseError> {
-        let mut query = String::from("SELECT * FROM emails WHERE folder_id = ? AND is_deleted = 0");
+        let mut query = String::from(
+            "SELECT * FROM emails WHERE folder_id = ? AND is_deleted = 0 AND snoozed_until IS NULL",
+        );
 
         // Add filters
         if let Some(is_read) = filter_read {
@@ -222,6 +385,257 @@ impl EmailRepository for SqliteEmailRepository {
             .map_err(DatabaseError::ConnectionError)
     }
 
+    async fn find_unified_inbox(
+        &self,
+        limit: i64,
+        offset: i64,
+        sort_by: &str,
+        sort_order: &str,
+        filter_read: Option<bool>,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        let mut query = String::from(
+            "SELECT e.* FROM emails e INNER JOIN folders f ON e.folder_id = f.id \
+             WHERE f.folder_type = 'inbox' AND e.is_deleted = 0 AND e.snoozed_until IS NULL",
+        );
+
+        if let Some(is_read) = filter_read {
+            query.push_str(&format!(" AND e.is_read = {}", if is_read { 1 } else { 0 }));
+        }
+
+        let order_column = match sort_by {
+            "sent_at" => "e.sent_at",
+            "size" => "e.size",
+            _ => "e.received_at",
+        };
+
+        let order_direction = if sort_order.to_lowercase() == "asc" {
+            "ASC"
+        } else {
+            "DESC"
+        };
+
+        // Emails from different accounts share timestamps often enough that the
+        // tie-break on `id` is needed for stable pagination
+        query.push_str(&format!(
+            " ORDER BY {} {} NULLS LAST, e.id ASC LIMIT ? OFFSET ?",
+            order_column, order_direction
+        ));
+
+        sqlx::query_as::<_, Email>(&query)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_priority_inbox(
+        &self,
+        account_id: Option<Uuid>,
+        min_priority: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        let mut query = String::from(
+            "SELECT e.* FROM emails e INNER JOIN folders f ON e.folder_id = f.id \
+             WHERE f.folder_type = 'inbox' AND e.is_deleted = 0 AND e.snoozed_until IS NULL \
+             AND e.priority >= ?",
+        );
+        if account_id.is_some() {
+            query.push_str(" AND e.account_id = ?");
+        }
+        query.push_str(" ORDER BY e.priority DESC, e.received_at DESC, e.id ASC LIMIT ? OFFSET ?");
+
+        let mut query_builder = sqlx::query_as::<_, Email>(&query).bind(min_priority);
+        if let Some(account_id) = account_id {
+            query_builder = query_builder.bind(account_id.to_string());
+        }
+
+        query_builder
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn conversation_has_sender(
+        &self,
+        conversation_id: &str,
+        exclude_id: Uuid,
+        addresses: &[String],
+    ) -> Result<bool, DatabaseError> {
+        if addresses.is_empty() {
+            return Ok(false);
+        }
+
+        let placeholders = addresses.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let query = format!(
+            "SELECT EXISTS (SELECT 1 FROM emails WHERE conversation_id = ? AND id != ? \
+             AND is_deleted = 0 AND LOWER(json_extract(`from`, '$.address')) IN ({}))",
+            placeholders
+        );
+
+        let mut query_builder = sqlx::query_scalar::<_, bool>(&query)
+            .bind(conversation_id)
+            .bind(exclude_id.to_string());
+        for address in addresses {
+            query_builder = query_builder.bind(address.to_lowercase());
+        }
+
+        query_builder
+            .fetch_one(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn update_priority(&self, id: Uuid, priority: i64) -> Result<(), DatabaseError> {
+        sqlx::query("UPDATE emails SET priority = ? WHERE id = ?")
+            .bind(priority)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn update_risk(
+        &self,
+        id: Uuid,
+        risk_score: i64,
+        risk_reasons: &[RiskReason],
+    ) -> Result<(), DatabaseError> {
+        let risk_reasons = serde_json::to_string(risk_reasons).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query("UPDATE emails SET risk_score = ?, risk_reasons = ? WHERE id = ?")
+            .bind(risk_score)
+            .bind(risk_reasons)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn set_sender_priority_flag(
+        &self,
+        sender: &str,
+        flag: i64,
+        set: bool,
+    ) -> Result<u64, DatabaseError> {
+        let priority = if set { "priority | ?" } else { "priority & ~?" };
+        let query = format!(
+            "UPDATE emails SET priority = {} WHERE LOWER(json_extract(`from`, '$.address')) = ?",
+            priority
+        );
+
+        let result = sqlx::query(&query)
+            .bind(flag)
+            .bind(sender.to_lowercase())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn set_sender_category(
+        &self,
+        sender: &str,
+        category: &str,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE emails SET category = ? WHERE LOWER(json_extract(`from`, '$.address')) = ?",
+        )
+        .bind(category)
+        .bind(sender.trim().to_lowercase())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn record_trackers(
+        &self,
+        email_id: Uuid,
+        trackers: &[String],
+    ) -> Result<(), DatabaseError> {
+        for tracker in trackers {
+            sqlx::query("INSERT OR IGNORE INTO email_trackers (email_id, tracker) VALUES (?, ?)")
+                .bind(email_id.to_string())
+                .bind(tracker)
+                .execute(&self.pool)
+                .await
+                .map_err(DatabaseError::ConnectionError)?;
+        }
+
+        Ok(())
+    }
+
+    async fn find_trackers(&self, email_id: Uuid) -> Result<Vec<String>, DatabaseError> {
+        sqlx::query_scalar::<_, String>(
+            "SELECT tracker FROM email_trackers WHERE email_id = ? ORDER BY found_at, tracker",
+        )
+        .bind(email_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_received_by_sender_suffix(
+        &self,
+        suffix: &str,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        sqlx::query_as::<_, Email>(
+            r#"
+            SELECT e.* FROM emails e
+            INNER JOIN folders f ON f.id = e.folder_id
+            WHERE e.is_deleted = 0
+              AND e.is_draft = 0
+              AND f.folder_type NOT IN ('spam', 'trash', 'sent', 'draft')
+              AND LOWER(json_extract(e.`from`, '$.address')) LIKE '%' || ?
+            ORDER BY e.received_at DESC
+            "#,
+        )
+        .bind(suffix.trim().to_lowercase())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn count_unified_inbox(&self) -> Result<Vec<UnifiedInboxCount>, DatabaseError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT e.account_id AS account_id,
+                   COUNT(*) AS total,
+                   COALESCE(SUM(CASE WHEN e.is_read = 0 THEN 1 ELSE 0 END), 0) AS unread
+            FROM emails e
+            INNER JOIN folders f ON e.folder_id = f.id
+            WHERE f.folder_type = 'inbox' AND e.is_deleted = 0 AND e.snoozed_until IS NULL
+            GROUP BY e.account_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        rows.into_iter()
+            .map(|row| {
+                let account_id: String = row.try_get("account_id")?;
+                Ok(UnifiedInboxCount {
+                    account_id: Uuid::parse_str(&account_id).map_err(|e| {
+                        DatabaseError::InvalidData(format!("Invalid account ID: {}", e))
+                    })?,
+                    total: row.try_get("total")?,
+                    unread: row.try_get("unread")?,
+                })
+            })
+            .collect()
+    }
+
     async fn find_by_conversation_id(
         &self,
         conversation_id: Uuid,
@@ -235,6 +649,59 @@ impl EmailRepository for SqliteEmailRepository {
         .map_err(DatabaseError::ConnectionError)
     }
 
+    async fn find_threaded_by_message_ids(
+        &self,
+        account_id: Uuid,
+        message_ids: &[String],
+    ) -> Result<Vec<Email>, DatabaseError> {
+        if message_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let placeholders = message_ids
+            .iter()
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = format!(
+            "SELECT * FROM emails WHERE account_id = ? AND conversation_id IS NOT NULL AND message_id IN ({})",
+            placeholders
+        );
+
+        let mut query_builder = sqlx::query_as::<_, Email>(&query).bind(account_id.to_string());
+        for message_id in message_ids {
+            query_builder = query_builder.bind(message_id);
+        }
+
+        query_builder
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_threaded_by_subject(
+        &self,
+        account_id: Uuid,
+        subject: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        sqlx::query_as::<_, Email>(
+            r#"
+            SELECT * FROM emails
+            WHERE account_id = ? AND conversation_id IS NOT NULL AND is_deleted = 0
+              AND received_at >= ? AND instr(lower(subject), lower(?)) > 0
+            ORDER BY received_at DESC
+            LIMIT 50
+            "#,
+        )
+        .bind(account_id.to_string())
+        .bind(since)
+        .bind(subject)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
     async fn find_by_labels(
         &self,
         label_ids: &[Uuid],
@@ -592,7 +1059,7 @@ impl EmailRepository for SqliteEmailRepository {
     async fn soft_delete(&self, id: Uuid) -> Result<(), DatabaseError> {
         let id_str = id.to_string();
         sqlx::query!(
-            "UPDATE emails SET is_deleted = 1, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            "UPDATE emails SET is_deleted = 1, deleted_at = CURRENT_TIMESTAMP, deletion_source = 'local', updated_at = CURRENT_TIMESTAMP WHERE id = ?",
             id_str
         )
         .execute(&self.pool)
@@ -605,7 +1072,7 @@ impl EmailRepository for SqliteEmailRepository {
     async fn undelete(&self, id: Uuid) -> Result<(), DatabaseError> {
         let id_str = id.to_string();
         sqlx::query!(
-            "UPDATE emails SET is_deleted = 0, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            "UPDATE emails SET is_deleted = 0, deleted_at = NULL, deletion_source = NULL, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
             id_str
         )
         .execute(&self.pool)
@@ -625,11 +1092,31 @@ impl EmailRepository for SqliteEmailRepository {
         Ok(())
     }
 
-    async fn count_unread_all(&self) -> Result<i64, DatabaseError> {
-        let count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM emails WHERE is_read = 0 AND is_deleted = 0",
+    async fn find_recently_deleted(
+        &self,
+        account_id: Option<Uuid>,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        // Tombstones without deleted_at fall back to their last update, like the cleanup does
+        sqlx::query_as::<_, Email>(
+            "SELECT * FROM emails WHERE is_deleted = 1 \
+             AND (? IS NULL OR account_id = ?) \
+             AND datetime(COALESCE(deleted_at, updated_at)) >= datetime(?) \
+             ORDER BY datetime(COALESCE(deleted_at, updated_at)) DESC, id DESC",
         )
-        .fetch_one(&self.pool)
+        .bind(account_id.map(|id| id.to_string()))
+        .bind(account_id.map(|id| id.to_string()))
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn count_unread_all(&self) -> Result<i64, DatabaseError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM emails WHERE is_read = 0 AND is_deleted = 0",
+        )
+        .fetch_one(&self.pool)
         .await
         .map_err(DatabaseError::ConnectionError)?;
 
@@ -666,6 +1153,35 @@ impl EmailRepository for SqliteEmailRepository {
         Ok(count)
     }
 
+    async fn count_by_ids(&self, ids: &[Uuid]) -> Result<(i64, i64), DatabaseError> {
+        // Stay below SQLite's limit of bound parameters per statement
+        const CHUNK_SIZE: usize = 500;
+
+        let (mut total, mut unread) = (0, 0);
+        for chunk in ids.chunks(CHUNK_SIZE) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let query = format!(
+                "SELECT COUNT(*), COALESCE(SUM(is_read = 0), 0) FROM emails \
+                 WHERE is_deleted = 0 AND id IN ({})",
+                placeholders
+            );
+
+            let mut sqlx_query = sqlx::query_as::<_, (i64, i64)>(&query);
+            for id in chunk {
+                sqlx_query = sqlx_query.bind(id.to_string());
+            }
+
+            let (chunk_total, chunk_unread) = sqlx_query
+                .fetch_one(&self.pool)
+                .await
+                .map_err(DatabaseError::ConnectionError)?;
+            total += chunk_total;
+            unread += chunk_unread;
+        }
+
+        Ok((total, unread))
+    }
+
     async fn find_synced_batch(
         &self,
         limit: i64,
@@ -681,6 +1197,15 @@ impl EmailRepository for SqliteEmailRepository {
         .map_err(DatabaseError::ConnectionError)
     }
 
+    async fn count_synced(&self) -> Result<i64, DatabaseError> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM emails WHERE is_deleted = 0 AND sync_status = 'synced'",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
     async fn find_synced_by_account(&self, account_id: Uuid) -> Result<Vec<Email>, DatabaseError> {
         let account_id_str = account_id.to_string();
         sqlx::query_as::<_, Email>(
@@ -729,7 +1254,7 @@ impl EmailRepository for SqliteEmailRepository {
     async fn undelete_by_account(&self, account_id: Uuid) -> Result<u64, DatabaseError> {
         let account_id_str = account_id.to_string();
         let result = sqlx::query!(
-            "UPDATE emails SET is_deleted = 0, updated_at = CURRENT_TIMESTAMP WHERE account_id = ? AND is_deleted = 1",
+            "UPDATE emails SET is_deleted = 0, deleted_at = NULL, deletion_source = NULL, updated_at = CURRENT_TIMESTAMP WHERE account_id = ? AND is_deleted = 1",
             account_id_str
         )
         .execute(&self.pool)
@@ -822,7 +1347,36 @@ impl EmailRepository for SqliteEmailRepository {
         Ok(())
     }
 
-    async fn find_pending_ai_analysis(&self, limit: i64) -> Result<Vec<Uuid>, DatabaseError> {
+    async fn mark_draft_uploaded(
+        &self,
+        id: Uuid,
+        remote_id: &str,
+        uploaded_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            "UPDATE emails SET remote_id = ?, sync_status = 'synced', last_modified_at = ? WHERE id = ?",
+        )
+        .bind(remote_id)
+        .bind(uploaded_at)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_pending_ai_analysis(
+        &self,
+        limit: i64,
+        excluded_folders: &[Uuid],
+        excluded_labels: &[Uuid],
+    ) -> Result<Vec<Uuid>, DatabaseError> {
+        let excluded_folders = serde_json::to_string(excluded_folders)
+            .map_err(|e| DatabaseError::InvalidData(format!("Invalid folder IDs: {}", e)))?;
+        let excluded_labels = serde_json::to_string(excluded_labels)
+            .map_err(|e| DatabaseError::InvalidData(format!("Invalid label IDs: {}", e)))?;
+
         let results = sqlx::query!(
             r#"
             SELECT e.id
@@ -834,9 +1388,24 @@ impl EmailRepository for SqliteEmailRepository {
               AND f.folder_type = 'inbox'
               AND (e.body_plain IS NOT NULL OR e.body_html IS NOT NULL)
               AND e.sync_status = 'synced'
+              AND NOT EXISTS (
+                  SELECT 1 FROM junk_classifications jc
+                  WHERE jc.email_id = e.id AND jc.is_junk = 1
+              )
+              AND NOT EXISTS (
+                  SELECT 1 FROM ai_analysis_queue q WHERE q.email_id = e.id
+              )
+              AND e.folder_id NOT IN (SELECT value FROM json_each(?))
+              AND NOT EXISTS (
+                  SELECT 1 FROM email_labels el
+                  WHERE el.email_id = e.id
+                    AND el.label_id IN (SELECT value FROM json_each(?))
+              )
             ORDER BY e.received_at DESC
             LIMIT ?
             "#,
+            excluded_folders,
+            excluded_labels,
             limit
         )
         .fetch_all(&self.pool)
@@ -852,6 +1421,32 @@ impl EmailRepository for SqliteEmailRepository {
             .collect()
     }
 
+    async fn find_recent_sent(
+        &self,
+        account_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        sqlx::query_as::<_, Email>(
+            r#"
+            SELECT e.*
+            FROM emails e
+            INNER JOIN folders f ON e.folder_id = f.id
+            WHERE e.account_id = ?
+              AND f.folder_type = 'sent'
+              AND e.is_deleted = 0
+              AND e.is_draft = 0
+              AND (e.body_plain IS NOT NULL OR e.body_html IS NOT NULL)
+            ORDER BY COALESCE(e.sent_at, e.received_at) DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(account_id.to_string())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
     async fn find_for_calendar(
         &self,
         folder_ids: &[Uuid],
@@ -964,6 +1559,181 @@ impl EmailRepository for SqliteEmailRepository {
 
         Ok(())
     }
+
+    async fn update_snoozed_until(
+        &self,
+        id: Uuid,
+        snoozed_until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            "UPDATE emails SET snoozed_until = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(snoozed_until)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_snoozed(&self, limit: i64, offset: i64) -> Result<Vec<Email>, DatabaseError> {
+        sqlx::query_as::<_, Email>(
+            "SELECT * FROM emails WHERE snoozed_until IS NOT NULL AND is_deleted = 0 \
+             ORDER BY snoozed_until ASC, id ASC LIMIT ? OFFSET ?",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_due_snoozed(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        sqlx::query_as::<_, Email>(
+            "SELECT * FROM emails WHERE snoozed_until IS NOT NULL AND snoozed_until <= ? \
+             AND is_deleted = 0 ORDER BY snoozed_until ASC",
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn update_scheduled_send_at(
+        &self,
+        id: Uuid,
+        scheduled_send_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            "UPDATE emails SET scheduled_send_at = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(scheduled_send_at)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_due_scheduled_sends(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        sqlx::query_as::<_, Email>(
+            "SELECT * FROM emails WHERE scheduled_send_at IS NOT NULL AND scheduled_send_at <= ? \
+             AND is_draft = 1 AND is_deleted = 0 ORDER BY scheduled_send_at ASC",
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_folder_window(
+        &self,
+        folder_id: Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Option<(FolderListState, Vec<Email>)>, DatabaseError> {
+        let folder_id = folder_id.to_string();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        // The trigger-maintained folder count avoids a COUNT(*) over the whole
+        // folder; only the (few) snoozed messages are counted here
+        let Some(row) =
+            sqlx::query("SELECT total_count, list_generation FROM folders WHERE id = ?")
+                .bind(&folder_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(DatabaseError::ConnectionError)?
+        else {
+            return Ok(None);
+        };
+        let total_count: i64 = row.get("total_count");
+        let order_token: i64 = row.get("list_generation");
+
+        let snoozed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM emails WHERE folder_id = ? AND is_deleted = 0 AND snoozed_until IS NOT NULL",
+        )
+        .bind(&folder_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        let emails = sqlx::query_as::<_, Email>(
+            r#"
+            SELECT * FROM emails
+            WHERE folder_id = ? AND is_deleted = 0 AND snoozed_until IS NULL
+            ORDER BY received_at DESC, id DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(&folder_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(Some((
+            FolderListState {
+                total_count: (total_count - snoozed).max(0),
+                order_token,
+            },
+            emails,
+        )))
+    }
+
+    async fn find_auto_archive_candidates(
+        &self,
+        folder_id: Uuid,
+        rule: &AutoArchiveRule,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<Email>, DatabaseError> {
+        let query = format!(
+            "SELECT * FROM emails WHERE {} ORDER BY received_at ASC LIMIT ?",
+            Self::auto_archive_filter(rule)
+        );
+
+        sqlx::query_as::<_, Email>(&query)
+            .bind(folder_id.to_string())
+            .bind(rule.cutoff(now))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn count_auto_archive_candidates(
+        &self,
+        folder_id: Uuid,
+        rule: &AutoArchiveRule,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<i64, DatabaseError> {
+        let query = format!(
+            "SELECT COUNT(*) FROM emails WHERE {}",
+            Self::auto_archive_filter(rule)
+        );
+
+        sqlx::query_scalar(&query)
+            .bind(folder_id.to_string())
+            .bind(rule.cutoff(now))
+            .fetch_one(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
 }
 
 #[cfg(test)]
@@ -971,7 +1741,11 @@ mod tests {
     use super::*;
     use crate::database::models::email::{Email, EmailAddress};
     use chrono::{TimeZone, Utc};
-    use sqlx::{sqlite::SqlitePoolOptions, types::Json};
+    use sqlx::{
+        sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+        types::Json,
+    };
+    use std::str::FromStr;
     /// Helper function to create a test database pool
     async fn create_test_pool() -> SqlitePool {
         SqlitePoolOptions::new()
@@ -1004,6 +1778,7 @@ mod tests {
                 received_at TIMESTAMP NOT NULL,
                 sent_at TIMESTAMP,
                 scheduled_send_at TIMESTAMP,
+                snoozed_until TIMESTAMP,
                 is_read BOOLEAN NOT NULL DEFAULT 0,
                 is_flagged BOOLEAN NOT NULL DEFAULT 0,
                 is_draft BOOLEAN NOT NULL DEFAULT 0,
@@ -1012,6 +1787,7 @@ mod tests {
                 sync_status TEXT NOT NULL DEFAULT 'synced',
                 tracking_blocked BOOLEAN NOT NULL DEFAULT 1,
                 images_blocked BOOLEAN NOT NULL DEFAULT 1,
+                priority INTEGER NOT NULL DEFAULT 0,
                 created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
                 updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
             );
@@ -1053,6 +1829,9 @@ mod tests {
             category: Some("personal".to_string()),
             other_mails: None,
             size: 512,
+            priority: 0,
+            risk_score: 0,
+            risk_reasons: Json(Vec::new()),
             ai_cache: None,
             headers: None,
             reply_to: None,
@@ -1064,6 +1843,7 @@ mod tests {
             sent_at: Some(Utc::now()),
             scheduled_send_at: None,
             remind_at: None,
+            snoozed_until: None,
             is_read: false,
             is_flagged: false,
             is_draft: false,
@@ -1156,6 +1936,290 @@ mod tests {
         assert_eq!(result.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_find_unified_inbox() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+        sqlx::query("CREATE TABLE folders (id TEXT PRIMARY KEY, folder_type TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (first_account, second_account) = (Uuid::now_v7(), Uuid::now_v7());
+        let (first_inbox, second_inbox, archive) = (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+        for (id, folder_type) in [
+            (first_inbox, "inbox"),
+            (second_inbox, "inbox"),
+            (archive, "archive"),
+        ] {
+            sqlx::query("INSERT INTO folders (id, folder_type) VALUES (?, ?)")
+                .bind(id.to_string())
+                .bind(folder_type)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let repository = SqliteEmailRepository::new(pool);
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let emails = [
+            (first_account, first_inbox, 0, false),
+            (second_account, second_inbox, 1, true),
+            (first_account, first_inbox, 2, true),
+            (first_account, archive, 3, false),
+        ];
+        for (account_id, folder_id, hours, is_read) in emails {
+            let mut email = create_test_email(account_id, folder_id);
+            email.received_at = base + chrono::Duration::hours(hours);
+            email.is_read = is_read;
+            repository.create(&email).await.unwrap();
+        }
+
+        let page = repository
+            .find_unified_inbox(2, 0, "received_at", "desc", None)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].received_at, base + chrono::Duration::hours(2));
+        assert_eq!(page[1].account_id, second_account);
+
+        let rest = repository
+            .find_unified_inbox(2, 2, "received_at", "desc", None)
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+
+        let unread = repository
+            .find_unified_inbox(10, 0, "received_at", "desc", Some(false))
+            .await
+            .unwrap();
+        assert_eq!(unread.len(), 1);
+        assert_eq!(unread[0].folder_id, first_inbox);
+
+        let mut counts = repository.count_unified_inbox().await.unwrap();
+        counts.sort_by_key(|c| c.account_id);
+        assert_eq!(counts.len(), 2);
+        assert_eq!((counts[0].total, counts[0].unread), (2, 1));
+        assert_eq!((counts[1].total, counts[1].unread), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_find_priority_inbox() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+        sqlx::query("CREATE TABLE folders (id TEXT PRIMARY KEY, folder_type TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (inbox, archive) = (Uuid::now_v7(), Uuid::now_v7());
+        for (id, folder_type) in [(inbox, "inbox"), (archive, "archive")] {
+            sqlx::query("INSERT INTO folders (id, folder_type) VALUES (?, ?)")
+                .bind(id.to_string())
+                .bind(folder_type)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let repository = SqliteEmailRepository::new(pool);
+        let account_id = Uuid::now_v7();
+        let reply = create_test_email(account_id, inbox);
+        let direct = create_test_email(account_id, inbox);
+        let archived = create_test_email(account_id, archive);
+        let mut from_vip = create_test_email(account_id, inbox);
+        from_vip.from = Json(create_email_address("vip@example.com", None));
+        for (email, priority) in [(&reply, 2), (&direct, 1), (&archived, 4), (&from_vip, 0)] {
+            repository.create(email).await.unwrap();
+            repository
+                .update_priority(email.id, priority)
+                .await
+                .unwrap();
+        }
+
+        let updated = repository
+            .set_sender_priority_flag("VIP@example.com", 4, true)
+            .await
+            .unwrap();
+        assert_eq!(updated, 1);
+
+        let important = repository
+            .find_priority_inbox(None, 2, 10, 0)
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = important.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![from_vip.id, reply.id]);
+        assert_eq!(important[0].priority, 4);
+
+        let other_account = repository
+            .find_priority_inbox(Some(Uuid::now_v7()), 2, 10, 0)
+            .await
+            .unwrap();
+        assert!(other_account.is_empty());
+
+        repository
+            .set_sender_priority_flag("vip@example.com", 4, false)
+            .await
+            .unwrap();
+        let important = repository
+            .find_priority_inbox(Some(account_id), 2, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(important.len(), 1);
+
+        // The test emails share a conversation and sender
+        let conversation_id = reply.conversation_id.as_deref().unwrap();
+        assert!(repository
+            .conversation_has_sender(conversation_id, reply.id, &["Sender@example.com".into()])
+            .await
+            .unwrap());
+        assert!(!repository
+            .conversation_has_sender(conversation_id, reply.id, &["me@example.com".into()])
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_snoozed_emails_hidden_until_due() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+        let repository = SqliteEmailRepository::new(pool);
+
+        let (account_id, folder_id) = (Uuid::now_v7(), Uuid::now_v7());
+        let snoozed = create_test_email(account_id, folder_id);
+        let visible = create_test_email(account_id, folder_id);
+        repository.create(&snoozed).await.unwrap();
+        repository.create(&visible).await.unwrap();
+
+        let now = Utc::now();
+        repository
+            .update_snoozed_until(snoozed.id, Some(now + chrono::Duration::hours(1)))
+            .await
+            .unwrap();
+
+        let listed = repository
+            .find_by_folder_with_filters(folder_id, 10, 0, "received_at", "desc", None, None)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, visible.id);
+
+        let snoozed_list = repository.find_snoozed(10, 0).await.unwrap();
+        assert_eq!(snoozed_list.len(), 1);
+        assert!(snoozed_list[0].snoozed_until.is_some());
+
+        assert!(repository.find_due_snoozed(now).await.unwrap().is_empty());
+        let due = repository
+            .find_due_snoozed(now + chrono::Duration::hours(2))
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, snoozed.id);
+
+        repository
+            .update_snoozed_until(snoozed.id, None)
+            .await
+            .unwrap();
+        let listed = repository
+            .find_by_folder_with_filters(folder_id, 10, 0, "received_at", "desc", None, None)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_folder_window_excludes_snoozed() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+        sqlx::query(
+            "CREATE TABLE folders (id TEXT PRIMARY KEY, total_count INTEGER NOT NULL, list_generation INTEGER NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (account_id, folder_id) = (Uuid::now_v7(), Uuid::now_v7());
+        sqlx::query("INSERT INTO folders (id, total_count, list_generation) VALUES (?, 3, 7)")
+            .bind(folder_id.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let repository = SqliteEmailRepository::new(pool);
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let mut ids = Vec::new();
+        for hours in 0..3 {
+            let mut email = create_test_email(account_id, folder_id);
+            email.received_at = base + chrono::Duration::hours(hours);
+            repository.create(&email).await.unwrap();
+            ids.push(email.id);
+        }
+        repository
+            .update_snoozed_until(ids[2], Some(base + chrono::Duration::days(1)))
+            .await
+            .unwrap();
+
+        let (state, emails) = repository
+            .find_folder_window(folder_id, 1, 10)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.total_count, 2);
+        assert_eq!(state.order_token, 7);
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].id, ids[0]);
+
+        assert!(repository
+            .find_folder_window(Uuid::now_v7(), 0, 10)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_find_due_scheduled_sends_only_returns_drafts() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+        let repository = SqliteEmailRepository::new(pool);
+
+        let (account_id, folder_id) = (Uuid::now_v7(), Uuid::now_v7());
+        let mut draft = create_test_email(account_id, folder_id);
+        draft.is_draft = true;
+        let sent = create_test_email(account_id, folder_id);
+        repository.create(&draft).await.unwrap();
+        repository.create(&sent).await.unwrap();
+
+        let now = Utc::now();
+        for id in [draft.id, sent.id] {
+            repository
+                .update_scheduled_send_at(id, Some(now + chrono::Duration::minutes(5)))
+                .await
+                .unwrap();
+        }
+
+        assert!(repository
+            .find_due_scheduled_sends(now)
+            .await
+            .unwrap()
+            .is_empty());
+        let due = repository
+            .find_due_scheduled_sends(now + chrono::Duration::minutes(10))
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, draft.id);
+
+        repository
+            .update_scheduled_send_at(draft.id, None)
+            .await
+            .unwrap();
+        assert!(repository
+            .find_due_scheduled_sends(now + chrono::Duration::minutes(10))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_update_email() {
         let pool = create_test_pool().await;
@@ -1199,6 +2263,105 @@ mod tests {
         assert!(find_result.is_none());
     }
 
+    #[tokio::test]
+    async fn test_count_by_ids() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteEmailRepository::new(pool);
+        let (account_id, folder_id) = (Uuid::now_v7(), Uuid::now_v7());
+
+        let mut ids = Vec::new();
+        for (i, (is_read, is_deleted)) in [(false, false), (true, false), (false, true)]
+            .into_iter()
+            .enumerate()
+        {
+            let mut email = create_test_email(account_id, folder_id);
+            email.message_id = format!("<count{}@example.com>", i);
+            email.is_read = is_read;
+            repository.create(&email).await.unwrap();
+            if is_deleted {
+                repository.soft_delete(email.id).await.unwrap();
+            }
+            ids.push(email.id);
+        }
+        ids.push(Uuid::now_v7());
+
+        assert_eq!(repository.count_by_ids(&ids).await.unwrap(), (2, 1));
+        assert_eq!(repository.count_by_ids(&[]).await.unwrap(), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_count_synced() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteEmailRepository::new(pool);
+        let (account_id, folder_id) = (Uuid::now_v7(), Uuid::now_v7());
+
+        let mut emails = Vec::new();
+        for i in 0..3 {
+            let mut email = create_test_email(account_id, folder_id);
+            email.message_id = format!("<synced{}@example.com>", i);
+            repository.create(&email).await.unwrap();
+            emails.push(email);
+        }
+        repository.soft_delete(emails[0].id).await.unwrap();
+
+        assert_eq!(repository.count_synced().await.unwrap(), 2);
+        assert_eq!(repository.find_synced_batch(10, 0).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_recently_deleted() {
+        // Runs against the real schema, which tracks when and why emails were deleted
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .foreign_keys(false);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let repository = SqliteEmailRepository::new(pool.clone());
+        let account_id = Uuid::now_v7();
+        let folder_id = Uuid::now_v7();
+
+        let recent = create_test_email(account_id, folder_id);
+        let expired = create_test_email(account_id, folder_id);
+        let other_account = create_test_email(Uuid::now_v7(), folder_id);
+        let restored = create_test_email(account_id, folder_id);
+        for email in [&recent, &expired, &other_account, &restored] {
+            repository.create(email).await.unwrap();
+            repository.soft_delete(email.id).await.unwrap();
+        }
+        repository.undelete(restored.id).await.unwrap();
+
+        sqlx::query("UPDATE emails SET deleted_at = datetime('now', '-40 days') WHERE id = ?")
+            .bind(expired.id.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let since = Utc::now() - chrono::Duration::days(30);
+        let found = repository
+            .find_recently_deleted(Some(account_id), since)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, recent.id);
+        assert!(found[0].deleted_at.is_some());
+
+        let all = repository.find_recently_deleted(None, since).await.unwrap();
+        assert_eq!(all.len(), 2);
+
+        let restored = repository.find_by_id(restored.id).await.unwrap().unwrap();
+        assert!(!restored.is_deleted);
+        assert!(restored.deleted_at.is_none());
+    }
+
     #[tokio::test]
     async fn test_email_with_multiple_recipients() {
         let pool = create_test_pool().await;
@@ -1258,6 +2421,42 @@ mod tests {
         assert!(emails[2].subject.as_ref().unwrap().contains("(3)"));
     }
 
+    #[tokio::test]
+    async fn test_find_threaded_by_message_ids() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteEmailRepository::new(pool);
+        let account_id = Uuid::now_v7();
+        let folder_id = Uuid::now_v7();
+
+        let mut threaded = create_test_email(account_id, folder_id);
+        threaded.message_id = "<parent@example.com>".to_string();
+        repository.create(&threaded).await.unwrap();
+
+        let mut unthreaded = create_test_email(account_id, folder_id);
+        unthreaded.message_id = "<other@example.com>".to_string();
+        unthreaded.conversation_id = None;
+        repository.create(&unthreaded).await.unwrap();
+
+        let message_ids = vec![
+            "<parent@example.com>".to_string(),
+            "<other@example.com>".to_string(),
+        ];
+        let found = repository
+            .find_threaded_by_message_ids(account_id, &message_ids)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, threaded.id);
+
+        let found = repository
+            .find_threaded_by_message_ids(Uuid::now_v7(), &message_ids)
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+    }
+
     #[tokio::test]
     async fn test_scheduled_email() {
         let pool = create_test_pool().await;
@@ -17,6 +17,7 @@ pub trait FolderRepository {
     async fn create(&self, folder: &Folder) -> Result<Uuid, DatabaseError>;
     async fn update(&self, folder: &Folder) -> Result<(), DatabaseError>;
     async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+    async fn set_sync_enabled(&self, id: Uuid, enabled: bool) -> Result<(), DatabaseError>;
 }
 
 pub struct SqliteFolderRepository {
@@ -119,7 +120,7 @@ impl FolderRepository for SqliteFolderRepository {
             UPDATE folders
             SET name = ?, folder_type = ?, remote_id = ?, color = ?,
                 icon = ?, sort_order = ?, parent_id = ?, settings = ?,
-                expanded = ?, hidden = ?
+                expanded = ?, hidden = ?, sync_enabled = ?
             WHERE id = ?
             "#,
             folder.name,
@@ -132,6 +133,7 @@ impl FolderRepository for SqliteFolderRepository {
             settings_json,
             folder.expanded,
             folder.hidden,
+            folder.sync_enabled,
             id
         )
         .execute(&self.pool)
@@ -150,6 +152,20 @@ impl FolderRepository for SqliteFolderRepository {
 
         Ok(())
     }
+
+    async fn set_sync_enabled(&self, id: Uuid, enabled: bool) -> Result<(), DatabaseError> {
+        let id = id.to_string();
+        sqlx::query!(
+            "UPDATE folders SET sync_enabled = ? WHERE id = ?",
+            enabled,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -205,6 +221,7 @@ mod tests {
             sort_order: 0,
             expanded: false,
             hidden: false,
+            sync_enabled: true,
             parent_id: None,
             settings: FolderSettings::default(),
             synced_at: Utc::now(),
@@ -0,0 +1,254 @@
+use crate::database::{
+    error::DatabaseError,
+    models::identity::{Identity, IdentitySource},
+};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait IdentityRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Identity>, DatabaseError>;
+    async fn find_by_account(&self, account_id: Uuid) -> Result<Vec<Identity>, DatabaseError>;
+    async fn find_by_email(
+        &self,
+        account_id: Uuid,
+        email: &str,
+    ) -> Result<Option<Identity>, DatabaseError>;
+    async fn create(&self, identity: &Identity) -> Result<Uuid, DatabaseError>;
+    async fn update(&self, identity: &Identity) -> Result<(), DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+    /// Makes the account's identities from `source` exactly `identities`,
+    /// keeping the ids of addresses that were already known
+    async fn replace_from_source(
+        &self,
+        account_id: Uuid,
+        source: IdentitySource,
+        identities: &[Identity],
+    ) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteIdentityRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteIdentityRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdentityRepository for SqliteIdentityRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Identity>, DatabaseError> {
+        sqlx::query_as::<_, Identity>("SELECT * FROM identities WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_account(&self, account_id: Uuid) -> Result<Vec<Identity>, DatabaseError> {
+        sqlx::query_as::<_, Identity>(
+            "SELECT * FROM identities WHERE account_id = ? ORDER BY email",
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_email(
+        &self,
+        account_id: Uuid,
+        email: &str,
+    ) -> Result<Option<Identity>, DatabaseError> {
+        sqlx::query_as::<_, Identity>("SELECT * FROM identities WHERE account_id = ? AND email = ?")
+            .bind(account_id.to_string())
+            .bind(email.trim().to_lowercase())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn create(&self, identity: &Identity) -> Result<Uuid, DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO identities (id, account_id, email, name, source)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(identity.id.to_string())
+        .bind(identity.account_id.to_string())
+        .bind(&identity.email)
+        .bind(&identity.name)
+        .bind(identity.source.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(identity.id)
+    }
+
+    async fn update(&self, identity: &Identity) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE identities
+            SET email = ?, name = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(&identity.email)
+        .bind(&identity.name)
+        .bind(identity.id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM identities WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn replace_from_source(
+        &self,
+        account_id: Uuid,
+        source: IdentitySource,
+        identities: &[Identity],
+    ) -> Result<(), DatabaseError> {
+        let emails: Vec<&str> = identities.iter().map(|i| i.email.as_str()).collect();
+        let emails_json = serde_json::to_string(&emails).map_err(DatabaseError::JsonError)?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM identities
+            WHERE account_id = ? AND source = ?
+              AND email NOT IN (SELECT value FROM json_each(?))
+            "#,
+        )
+        .bind(account_id.to_string())
+        .bind(source.as_str())
+        .bind(emails_json)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        for identity in identities {
+            sqlx::query(
+                r#"
+                INSERT INTO identities (id, account_id, email, name, source)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(account_id, email) DO UPDATE SET
+                    name = excluded.name,
+                    source = excluded.source,
+                    updated_at = CURRENT_TIMESTAMP
+                "#,
+            )
+            .bind(identity.id.to_string())
+            .bind(account_id.to_string())
+            .bind(&identity.email)
+            .bind(&identity.name)
+            .bind(source.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+        }
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE identities (
+                id TEXT PRIMARY KEY NOT NULL,
+                account_id TEXT NOT NULL,
+                email TEXT NOT NULL,
+                name TEXT,
+                source TEXT NOT NULL DEFAULT 'manual',
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (account_id, email)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn create_test_identity(account_id: Uuid, email: &str, source: IdentitySource) -> Identity {
+        Identity {
+            id: Uuid::now_v7(),
+            account_id,
+            email: email.to_string(),
+            name: None,
+            source,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_from_source() {
+        let repository = SqliteIdentityRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let manual = create_test_identity(account_id, "me@example.org", IdentitySource::Manual);
+        let kept = create_test_identity(account_id, "sales@example.com", IdentitySource::Gmail);
+        let removed = create_test_identity(account_id, "old@example.com", IdentitySource::Gmail);
+        for identity in [&manual, &kept, &removed] {
+            repository.create(identity).await.unwrap();
+        }
+
+        let mut renamed =
+            create_test_identity(account_id, "sales@example.com", IdentitySource::Gmail);
+        renamed.name = Some("Sales".to_string());
+        let added = create_test_identity(account_id, "help@example.com", IdentitySource::Gmail);
+        repository
+            .replace_from_source(account_id, IdentitySource::Gmail, &[renamed, added.clone()])
+            .await
+            .unwrap();
+
+        let identities = repository.find_by_account(account_id).await.unwrap();
+        let emails: Vec<&str> = identities.iter().map(|i| i.email.as_str()).collect();
+        assert_eq!(
+            emails,
+            vec!["help@example.com", "me@example.org", "sales@example.com"]
+        );
+        assert_eq!(identities[0].id, added.id);
+        assert_eq!(identities[2].id, kept.id);
+        assert_eq!(identities[2].name.as_deref(), Some("Sales"));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::database::{error::DatabaseError, models::image_allowlist::ImageAllowlistEntry};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+#[async_trait]
+pub trait ImageAllowlistRepository {
+    async fn get_all(&self) -> Result<Vec<ImageAllowlistEntry>, DatabaseError>;
+    async fn add(&self, entry: &ImageAllowlistEntry) -> Result<(), DatabaseError>;
+    async fn delete(&self, pattern: &str) -> Result<(), DatabaseError>;
+    /// Whether the address or its domain is on the allowlist
+    async fn is_sender_allowed(&self, sender: &str) -> Result<bool, DatabaseError>;
+}
+
+pub struct SqliteImageAllowlistRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteImageAllowlistRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ImageAllowlistRepository for SqliteImageAllowlistRepository {
+    async fn get_all(&self) -> Result<Vec<ImageAllowlistEntry>, DatabaseError> {
+        sqlx::query_as::<_, ImageAllowlistEntry>(
+            "SELECT * FROM image_allowlist ORDER BY kind, pattern",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn add(&self, entry: &ImageAllowlistEntry) -> Result<(), DatabaseError> {
+        sqlx::query("INSERT OR IGNORE INTO image_allowlist (pattern, kind) VALUES (?, ?)")
+            .bind(entry.pattern.trim().to_lowercase())
+            .bind(entry.kind.as_str())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, pattern: &str) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM image_allowlist WHERE pattern = ?")
+            .bind(pattern.trim().to_lowercase())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn is_sender_allowed(&self, sender: &str) -> Result<bool, DatabaseError> {
+        let sender = sender.trim().to_lowercase();
+        let Some((_, domain)) = sender.rsplit_once('@') else {
+            return Ok(false);
+        };
+
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM image_allowlist
+                WHERE (kind = 'sender' AND pattern = ?1)
+                   OR (kind = 'domain'
+                       AND (pattern = ?2 OR substr(?2, -length(pattern) - 1) = '.' || pattern))
+            )
+            "#,
+        )
+        .bind(&sender)
+        .bind(domain)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::sender_pattern::SenderPatternKind;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE image_allowlist (
+                pattern TEXT PRIMARY KEY,
+                kind TEXT NOT NULL CHECK (kind IN ('sender', 'domain')),
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let sender = ImageAllowlistEntry::parse(" Alice@Example.com ").unwrap();
+        assert_eq!(sender.pattern, "alice@example.com");
+        assert_eq!(sender.kind, SenderPatternKind::Sender);
+
+        for input in ["example.com", "@Example.com"] {
+            let domain = ImageAllowlistEntry::parse(input).unwrap();
+            assert_eq!(domain.pattern, "example.com");
+            assert_eq!(domain.kind, SenderPatternKind::Domain);
+        }
+
+        for input in [
+            "",
+            "@",
+            "alice@",
+            "@alice@example.com",
+            "localhost",
+            "a b.com",
+        ] {
+            assert!(ImageAllowlistEntry::parse(input).is_none(), "{}", input);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_is_sender_allowed() {
+        let repository = SqliteImageAllowlistRepository::new(create_test_pool().await);
+
+        repository
+            .add(&ImageAllowlistEntry::sender("News@Shop.com"))
+            .await
+            .unwrap();
+        repository
+            .add(&ImageAllowlistEntry::parse("example.com").unwrap())
+            .await
+            .unwrap();
+
+        for sender in ["news@shop.com", "bob@example.com", "Carol@Mail.Example.com"] {
+            assert!(
+                repository.is_sender_allowed(sender).await.unwrap(),
+                "{}",
+                sender
+            );
+        }
+        for sender in ["sales@shop.com", "eve@badexample.com", "not-an-address"] {
+            assert!(
+                !repository.is_sender_allowed(sender).await.unwrap(),
+                "{}",
+                sender
+            );
+        }
+
+        assert_eq!(repository.get_all().await.unwrap().len(), 2);
+        repository.delete("example.com").await.unwrap();
+        assert!(!repository
+            .is_sender_allowed("bob@example.com")
+            .await
+            .unwrap());
+    }
+}
@@ -17,6 +17,27 @@ pub trait LabelRepository {
     async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
     async fn add_to_email(&self, email_id: Uuid, label_id: Uuid) -> Result<(), DatabaseError>;
     async fn remove_from_email(&self, email_id: Uuid, label_id: Uuid) -> Result<(), DatabaseError>;
+    /// Emails carrying a label with the given name, compared case-insensitively
+    async fn find_email_ids_by_name(&self, name: &str) -> Result<Vec<Uuid>, DatabaseError>;
+    /// Labels synced from the provider for an account
+    async fn find_remote_by_account(&self, account_id: Uuid) -> Result<Vec<Label>, DatabaseError>;
+    /// Inserts a provider label or updates its name and color, keyed by the
+    /// account and remote ID. Returns the ID of the stored label.
+    async fn upsert_remote(&self, label: &Label) -> Result<Uuid, DatabaseError>;
+    /// Deletes the account's provider labels whose remote ID is not in `keep`
+    async fn delete_remote_except(
+        &self,
+        account_id: Uuid,
+        keep: &[String],
+    ) -> Result<u64, DatabaseError>;
+    /// Replaces the provider labels of an email with the account's labels among
+    /// `remote_ids`. Local labels and unknown remote IDs are left alone.
+    async fn set_remote_labels(
+        &self,
+        email_id: Uuid,
+        account_id: Uuid,
+        remote_ids: &[String],
+    ) -> Result<(), DatabaseError>;
 }
 
 pub struct SqliteLabelRepository {
@@ -113,30 +134,8 @@ impl LabelRepository for SqliteLabelRepository {
             let email_id = Uuid::parse_str(&email_id_str)
                 .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
 
-            let label = Label {
-                id: {
-                    let id_str: String = row
-                        .try_get("id")
-                        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
-                    Uuid::parse_str(&id_str)
-                        .map_err(|e| DatabaseError::QueryError(e.to_string()))?
-                },
-                name: row
-                    .try_get("name")
-                    .map_err(|e| DatabaseError::QueryError(e.to_string()))?,
-                color: row
-                    .try_get("color")
-                    .map_err(|e| DatabaseError::QueryError(e.to_string()))?,
-                icon: row
-                    .try_get("icon")
-                    .map_err(|e| DatabaseError::QueryError(e.to_string()))?,
-                created_at: row
-                    .try_get("created_at")
-                    .map_err(|e| DatabaseError::QueryError(e.to_string()))?,
-                updated_at: row
-                    .try_get("updated_at")
-                    .map_err(|e| DatabaseError::QueryError(e.to_string()))?,
-            };
+            let label = <Label as sqlx::FromRow<_>>::from_row(&row)
+                .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
 
             result.entry(email_id).or_insert_with(Vec::new).push(label);
         }
@@ -254,6 +253,159 @@ impl LabelRepository for SqliteLabelRepository {
 
         Ok(())
     }
+
+    async fn find_email_ids_by_name(&self, name: &str) -> Result<Vec<Uuid>, DatabaseError> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT el.email_id
+            FROM email_labels el
+            JOIN labels l ON l.id = el.label_id
+            WHERE LOWER(l.name) = LOWER(?)
+            "#,
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        ids.iter()
+            .map(|id| Uuid::parse_str(id).map_err(|e| DatabaseError::QueryError(e.to_string())))
+            .collect()
+    }
+
+    async fn find_remote_by_account(&self, account_id: Uuid) -> Result<Vec<Label>, DatabaseError> {
+        sqlx::query_as::<_, Label>(
+            "SELECT * FROM labels WHERE account_id = ? AND remote_id IS NOT NULL ORDER BY name",
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn upsert_remote(&self, label: &Label) -> Result<Uuid, DatabaseError> {
+        let id: String = sqlx::query_scalar(
+            r#"
+            INSERT INTO labels (id, account_id, remote_id, name, color, icon)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(account_id, remote_id) DO UPDATE SET
+                name = excluded.name,
+                color = excluded.color,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING id
+            "#,
+        )
+        .bind(label.id.to_string())
+        .bind(label.account_id.map(|id| id.to_string()))
+        .bind(&label.remote_id)
+        .bind(&label.name)
+        .bind(&label.color)
+        .bind(&label.icon)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Uuid::parse_str(&id).map_err(|e| DatabaseError::QueryError(e.to_string()))
+    }
+
+    async fn delete_remote_except(
+        &self,
+        account_id: Uuid,
+        keep: &[String],
+    ) -> Result<u64, DatabaseError> {
+        let mut condition = String::from("account_id = ? AND remote_id IS NOT NULL");
+        if !keep.is_empty() {
+            let placeholders = keep.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+            condition.push_str(&format!(" AND remote_id NOT IN ({})", placeholders));
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        let mut deleted = 0;
+        for query in [
+            format!(
+                "DELETE FROM email_labels WHERE label_id IN (SELECT id FROM labels WHERE {})",
+                condition
+            ),
+            format!("DELETE FROM labels WHERE {}", condition),
+        ] {
+            let mut sqlx_query = sqlx::query(&query).bind(account_id.to_string());
+            for remote_id in keep {
+                sqlx_query = sqlx_query.bind(remote_id);
+            }
+            deleted = sqlx_query
+                .execute(&mut *tx)
+                .await
+                .map_err(DatabaseError::ConnectionError)?
+                .rows_affected();
+        }
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(deleted)
+    }
+
+    async fn set_remote_labels(
+        &self,
+        email_id: Uuid,
+        account_id: Uuid,
+        remote_ids: &[String],
+    ) -> Result<(), DatabaseError> {
+        let email_id = email_id.to_string();
+        let account_id = account_id.to_string();
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM email_labels
+            WHERE email_id = ? AND label_id IN (
+                SELECT id FROM labels WHERE account_id = ? AND remote_id IS NOT NULL
+            )
+            "#,
+        )
+        .bind(&email_id)
+        .bind(&account_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        if !remote_ids.is_empty() {
+            let placeholders = remote_ids
+                .iter()
+                .map(|_| "?")
+                .collect::<Vec<_>>()
+                .join(", ");
+            let query = format!(
+                r#"
+                INSERT OR IGNORE INTO email_labels (email_id, label_id)
+                SELECT ?, id FROM labels WHERE account_id = ? AND remote_id IN ({})
+                "#,
+                placeholders
+            );
+
+            let mut sqlx_query = sqlx::query(&query).bind(&email_id).bind(&account_id);
+            for remote_id in remote_ids {
+                sqlx_query = sqlx_query.bind(remote_id);
+            }
+            sqlx_query
+                .execute(&mut *tx)
+                .await
+                .map_err(DatabaseError::ConnectionError)?;
+        }
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -265,7 +417,7 @@ mod tests {
     /// Helper function to create a test database pool
     async fn create_test_pool() -> SqlitePool {
         SqlitePoolOptions::new()
-            .max_connections(5)
+            .max_connections(1)
             .connect("sqlite::memory:")
             .await
             .expect("Failed to create test database pool")
@@ -276,16 +428,22 @@ mod tests {
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS labels (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                account_id INTEGER NOT NULL,
+                id TEXT NOT NULL PRIMARY KEY,
+                account_id TEXT,
+                remote_id TEXT,
                 name TEXT NOT NULL,
                 color TEXT,
-                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+                icon TEXT,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
             );
 
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_labels_account_remote_id
+                ON labels(account_id, remote_id);
+
             CREATE TABLE IF NOT EXISTS email_labels (
-                email_id INTEGER NOT NULL,
-                label_id INTEGER NOT NULL,
+                email_id TEXT NOT NULL,
+                label_id TEXT NOT NULL,
                 PRIMARY KEY (email_id, label_id)
             );
             "#,
@@ -299,14 +457,26 @@ mod tests {
     fn create_test_label() -> Label {
         Label {
             id: Uuid::now_v7(),
+            account_id: None,
+            remote_id: None,
             name: "Test Label".to_string(),
-            icon: Some * ("tag".to_string()),
+            icon: Some("tag".to_string()),
             color: Some("#FF0000".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
 
+    /// Helper function to create a provider label of an account
+    fn create_remote_label(account_id: Uuid, remote_id: &str) -> Label {
+        Label {
+            account_id: Some(account_id),
+            remote_id: Some(remote_id.to_string()),
+            name: remote_id.to_string(),
+            ..create_test_label()
+        }
+    }
+
     #[tokio::test]
     async fn test_create_label() {
         let pool = create_test_pool().await;
@@ -457,6 +627,34 @@ mod tests {
         assert!(labels.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_find_email_ids_by_name() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteLabelRepository::new(pool);
+        let test_label = create_test_label();
+        let label_name = test_label.name.clone();
+        repository.create(&test_label).await.unwrap();
+
+        let email_id = Uuid::now_v7();
+        repository
+            .add_to_email(email_id, test_label.id)
+            .await
+            .unwrap();
+
+        let ids = repository
+            .find_email_ids_by_name(&label_name.to_uppercase())
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![email_id]);
+        assert!(repository
+            .find_email_ids_by_name("missing")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_multiple_labels_per_email() {
         let pool = create_test_pool().await;
@@ -532,4 +730,84 @@ mod tests {
             .await;
         assert!(result.is_ok()); // SQLite doesn't error on non-existent rows
     }
+
+    #[tokio::test]
+    async fn test_upsert_remote_label() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteLabelRepository::new(pool);
+        let account_id = Uuid::now_v7();
+
+        let label = create_remote_label(account_id, "Label_1");
+        let id = repository.upsert_remote(&label).await.unwrap();
+        assert_eq!(id, label.id);
+
+        // A renamed label keeps its local ID
+        let mut renamed = create_remote_label(account_id, "Label_1");
+        renamed.name = "Receipts".to_string();
+        let renamed_id = repository.upsert_remote(&renamed).await.unwrap();
+        assert_eq!(renamed_id, label.id);
+
+        let labels = repository.find_remote_by_account(account_id).await.unwrap();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].name, "Receipts");
+        assert_eq!(labels[0].remote_id.as_deref(), Some("Label_1"));
+    }
+
+    #[tokio::test]
+    async fn test_set_remote_labels_keeps_local_labels() {
+        let pool = create_test_pool().await;
+        setup_test_schema(&pool).await;
+
+        let repository = SqliteLabelRepository::new(pool);
+        let account_id = Uuid::now_v7();
+        let email_id = Uuid::now_v7();
+
+        let local = create_test_label();
+        repository.create(&local).await.unwrap();
+        repository.add_to_email(email_id, local.id).await.unwrap();
+        for remote_id in ["Label_1", "Label_2"] {
+            repository
+                .upsert_remote(&create_remote_label(account_id, remote_id))
+                .await
+                .unwrap();
+        }
+
+        let remote_ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        repository
+            .set_remote_labels(email_id, account_id, &remote_ids(&["INBOX", "Label_1"]))
+            .await
+            .unwrap();
+        let names: Vec<String> = repository
+            .find_by_email(email_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|label| label.name)
+            .collect();
+        assert_eq!(names, vec!["Label_1", "Test Label"]);
+
+        repository
+            .set_remote_labels(email_id, account_id, &remote_ids(&["Label_2"]))
+            .await
+            .unwrap();
+        let names: Vec<String> = repository
+            .find_by_email(email_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|label| label.name)
+            .collect();
+        assert_eq!(names, vec!["Label_2", "Test Label"]);
+
+        // Vanished provider labels are removed, local ones stay
+        let deleted = repository
+            .delete_remote_except(account_id, &remote_ids(&["Label_1"]))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert!(repository.find_by_id(local.id).await.unwrap().is_some());
+    }
 }
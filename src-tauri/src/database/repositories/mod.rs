@@ -1,24 +1,60 @@
 mod account_repository;
+mod ai_analysis_queue_repository;
+mod ai_audit_repository;
 mod attachment_repository;
+mod automation_trigger_repository;
+mod blocked_sender_repository;
+mod calendar_event_repository;
 mod contact_repository;
+mod contact_security_repository;
 mod conversation_repository;
+mod delivery_status_repository;
 mod email_repository;
 mod folder_repository;
+mod identity_repository;
+mod image_allowlist_repository;
 mod label_repository;
+mod outbox_repository;
 mod pending_operation_repository;
+mod rule_repository;
+mod saved_search_repository;
+mod sender_category_repository;
+mod session_repository;
+mod signature_repository;
+mod smime_repository;
 mod sync_state_repository;
+mod template_repository;
 mod view_repository;
+mod writing_style_profile_repository;
 
 pub use account_repository::*;
+pub use ai_analysis_queue_repository::*;
+pub use ai_audit_repository::*;
 pub use attachment_repository::*;
+pub use automation_trigger_repository::*;
+pub use blocked_sender_repository::*;
+pub use calendar_event_repository::*;
 pub use contact_repository::*;
+pub use contact_security_repository::*;
 pub use conversation_repository::*;
+pub use delivery_status_repository::*;
 pub use email_repository::*;
 pub use folder_repository::*;
+pub use identity_repository::*;
+pub use image_allowlist_repository::*;
 pub use label_repository::*;
+pub use outbox_repository::*;
 pub use pending_operation_repository::*;
+pub use rule_repository::*;
+pub use saved_search_repository::*;
+pub use sender_category_repository::*;
+pub use session_repository::*;
+pub use signature_repository::*;
+pub use smime_repository::*;
 pub use sync_state_repository::*;
+pub use template_repository::*;
 pub use view_repository::*;
+pub use writing_style_profile_repository::*;
 
 use sqlx::SqlitePool;
 
@@ -43,6 +79,10 @@ impl RepositoryFactory {
         SqliteFolderRepository::new(self.pool.clone())
     }
 
+    pub fn identity_repository(&self) -> SqliteIdentityRepository {
+        SqliteIdentityRepository::new(self.pool.clone())
+    }
+
     pub fn label_repository(&self) -> SqliteLabelRepository {
         SqliteLabelRepository::new(self.pool.clone())
     }
@@ -55,6 +95,10 @@ impl RepositoryFactory {
         SqliteContactRepository::new(self.pool.clone())
     }
 
+    pub fn contact_security_repository(&self) -> SqliteContactSecurityRepository {
+        SqliteContactSecurityRepository::new(self.pool.clone())
+    }
+
     pub fn view_repository(&self) -> SqliteViewRepository {
         SqliteViewRepository::new(self.pool.clone())
     }
@@ -63,11 +107,75 @@ impl RepositoryFactory {
         SqliteConversationRepository::new(self.pool.clone())
     }
 
+    pub fn delivery_status_repository(&self) -> SqliteDeliveryStatusRepository {
+        SqliteDeliveryStatusRepository::new(self.pool.clone())
+    }
+
+    pub fn smime_repository(&self) -> SqliteSmimeRepository {
+        SqliteSmimeRepository::new(self.pool.clone())
+    }
+
     pub fn sync_state_repository(&self) -> SqliteSyncStateRepository {
         SqliteSyncStateRepository::new(self.pool.clone())
     }
 
+    pub fn outbox_repository(&self) -> SqliteOutboxRepository {
+        SqliteOutboxRepository::new(self.pool.clone())
+    }
+
     pub fn pending_operation_repository(&self) -> SqlitePendingOperationRepository {
         SqlitePendingOperationRepository::new(self.pool.clone())
     }
+
+    pub fn rule_repository(&self) -> SqliteRuleRepository {
+        SqliteRuleRepository::new(self.pool.clone())
+    }
+
+    pub fn saved_search_repository(&self) -> SqliteSavedSearchRepository {
+        SqliteSavedSearchRepository::new(self.pool.clone())
+    }
+
+    pub fn automation_trigger_repository(&self) -> SqliteAutomationTriggerRepository {
+        SqliteAutomationTriggerRepository::new(self.pool.clone())
+    }
+
+    pub fn calendar_event_repository(&self) -> SqliteCalendarEventRepository {
+        SqliteCalendarEventRepository::new(self.pool.clone())
+    }
+
+    pub fn session_repository(&self) -> SqliteSessionRepository {
+        SqliteSessionRepository::new(self.pool.clone())
+    }
+
+    pub fn signature_repository(&self) -> SqliteSignatureRepository {
+        SqliteSignatureRepository::new(self.pool.clone())
+    }
+
+    pub fn template_repository(&self) -> SqliteTemplateRepository {
+        SqliteTemplateRepository::new(self.pool.clone())
+    }
+
+    pub fn image_allowlist_repository(&self) -> SqliteImageAllowlistRepository {
+        SqliteImageAllowlistRepository::new(self.pool.clone())
+    }
+
+    pub fn blocked_sender_repository(&self) -> SqliteBlockedSenderRepository {
+        SqliteBlockedSenderRepository::new(self.pool.clone())
+    }
+
+    pub fn sender_category_repository(&self) -> SqliteSenderCategoryRepository {
+        SqliteSenderCategoryRepository::new(self.pool.clone())
+    }
+
+    pub fn ai_analysis_queue_repository(&self) -> SqliteAiAnalysisQueueRepository {
+        SqliteAiAnalysisQueueRepository::new(self.pool.clone())
+    }
+
+    pub fn ai_audit_repository(&self) -> SqliteAiAuditRepository {
+        SqliteAiAuditRepository::new(self.pool.clone())
+    }
+
+    pub fn writing_style_profile_repository(&self) -> SqliteWritingStyleProfileRepository {
+        SqliteWritingStyleProfileRepository::new(self.pool.clone())
+    }
 }
@@ -0,0 +1,293 @@
+use crate::database::{error::DatabaseError, models::outbox_item::OutboxItem};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait OutboxRepository {
+    async fn create(&self, item: &OutboxItem) -> Result<(), DatabaseError>;
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<OutboxItem>, DatabaseError>;
+    /// All items, oldest first
+    async fn find_all(&self) -> Result<Vec<OutboxItem>, DatabaseError>;
+    /// Queued items whose next attempt is due, oldest first
+    async fn find_due(&self, now: DateTime<Utc>) -> Result<Vec<OutboxItem>, DatabaseError>;
+    /// Marks an item as sending. Returns false when it is already being sent,
+    /// so an item never goes out twice at the same time.
+    async fn claim(&self, id: Uuid) -> Result<bool, DatabaseError>;
+    /// Counts a failed attempt, trying again no earlier than `next_attempt_at`
+    async fn schedule_retry(
+        &self,
+        id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), DatabaseError>;
+    /// Counts a failed attempt and stops retrying
+    async fn mark_failed(&self, id: Uuid, error: &str) -> Result<(), DatabaseError>;
+    /// Gives up on items left sending by a previous run, as they may have
+    /// gone out already. Returns the number of items.
+    async fn fail_interrupted(&self) -> Result<u64, DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+    /// Deletes an item unless it is being sent. Returns whether it was deleted.
+    async fn discard(&self, id: Uuid) -> Result<bool, DatabaseError>;
+}
+
+pub struct SqliteOutboxRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteOutboxRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for SqliteOutboxRepository {
+    async fn create(&self, item: &OutboxItem) -> Result<(), DatabaseError> {
+        let recipients = serde_json::to_string(&item.recipients)
+            .map_err(|e| DatabaseError::InvalidData(format!("Invalid recipients: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO outbox (
+                id, account_id, subject, recipients, request, status, attempts,
+                last_error, next_attempt_at, created_at, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(item.id.to_string())
+        .bind(item.account_id.to_string())
+        .bind(&item.subject)
+        .bind(recipients)
+        .bind(&item.request)
+        .bind(&item.status)
+        .bind(item.attempts)
+        .bind(&item.last_error)
+        .bind(item.next_attempt_at)
+        .bind(item.created_at)
+        .bind(item.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<OutboxItem>, DatabaseError> {
+        sqlx::query_as::<_, OutboxItem>("SELECT * FROM outbox WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_all(&self) -> Result<Vec<OutboxItem>, DatabaseError> {
+        sqlx::query_as::<_, OutboxItem>("SELECT * FROM outbox ORDER BY created_at ASC, id ASC")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_due(&self, now: DateTime<Utc>) -> Result<Vec<OutboxItem>, DatabaseError> {
+        sqlx::query_as::<_, OutboxItem>(
+            r#"
+            SELECT * FROM outbox
+            WHERE status = 'queued' AND (next_attempt_at IS NULL OR next_attempt_at <= ?)
+            ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn claim(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE outbox SET status = 'sending', updated_at = CURRENT_TIMESTAMP
+            WHERE id = ? AND status != 'sending'
+            "#,
+        )
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn schedule_retry(
+        &self,
+        id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = 'queued', attempts = attempts + 1, last_error = ?,
+                next_attempt_at = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn mark_failed(&self, id: Uuid, error: &str) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = 'failed', attempts = attempts + 1, last_error = ?,
+                next_attempt_at = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(error)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn fail_interrupted(&self) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = 'failed', last_error = 'Interrupted while sending',
+                next_attempt_at = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE status = 'sending'
+            "#,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM outbox WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn discard(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let result = sqlx::query("DELETE FROM outbox WHERE id = ? AND status != 'sending'")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE outbox (
+                id TEXT PRIMARY KEY,
+                account_id TEXT NOT NULL,
+                subject TEXT,
+                recipients TEXT NOT NULL DEFAULT '[]',
+                request TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'queued',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                next_attempt_at TIMESTAMP,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn item() -> OutboxItem {
+        OutboxItem::new(
+            Uuid::now_v7(),
+            Some("Quarterly report".to_string()),
+            vec!["bob@example.org".to_string()],
+            "{}".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_claim_and_retry() {
+        let repository = SqliteOutboxRepository::new(create_test_pool().await);
+        let now = Utc::now();
+
+        let queued = item();
+        repository.create(&queued).await.unwrap();
+        assert_eq!(repository.find_due(now).await.unwrap().len(), 1);
+
+        assert!(repository.claim(queued.id).await.unwrap());
+        assert!(!repository.claim(queued.id).await.unwrap());
+        assert!(!repository.discard(queued.id).await.unwrap());
+        assert!(repository.find_due(now).await.unwrap().is_empty());
+
+        repository
+            .schedule_retry(queued.id, "Connection refused", now + Duration::minutes(1))
+            .await
+            .unwrap();
+        assert!(repository.find_due(now).await.unwrap().is_empty());
+
+        let due = repository
+            .find_due(now + Duration::minutes(2))
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].attempts, 1);
+        assert_eq!(due[0].recipients, vec!["bob@example.org".to_string()]);
+        assert_eq!(due[0].last_error.as_deref(), Some("Connection refused"));
+
+        assert!(repository.discard(queued.id).await.unwrap());
+        assert!(repository.find_all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fail_interrupted() {
+        let repository = SqliteOutboxRepository::new(create_test_pool().await);
+
+        let sending = item();
+        repository.create(&sending).await.unwrap();
+        repository.claim(sending.id).await.unwrap();
+        repository.create(&item()).await.unwrap();
+
+        assert_eq!(repository.fail_interrupted().await.unwrap(), 1);
+        let failed = repository.find_by_id(sending.id).await.unwrap().unwrap();
+        assert_eq!(failed.status, "failed");
+    }
+}
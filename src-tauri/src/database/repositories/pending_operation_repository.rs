@@ -1,6 +1,6 @@
 use crate::database::error::DatabaseError;
 use crate::database::models::pending_operation::PendingOperation;
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
@@ -25,8 +25,8 @@ impl SqlitePendingOperationRepository {
             INSERT INTO pending_operations (
                 id, account_id, email_id, folder_id, operation_type,
                 payload, status, retry_count, max_retries, error_message,
-                created_at, completed_at, expires_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                created_at, completed_at, expires_at, next_attempt_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             id,
             account_id,
@@ -41,6 +41,7 @@ impl SqlitePendingOperationRepository {
             op.created_at,
             op.completed_at,
             op.expires_at,
+            op.next_attempt_at,
         )
         .execute(&self.pool)
         .await
@@ -61,15 +62,17 @@ impl SqlitePendingOperationRepository {
             r#"
             SELECT id, account_id, email_id, folder_id, operation_type,
                    payload, status, retry_count, max_retries, error_message,
-                   created_at, completed_at, expires_at
+                   created_at, completed_at, expires_at, next_attempt_at
             FROM pending_operations
             WHERE account_id = ? AND status = 'pending'
               AND (expires_at IS NULL OR expires_at > ?)
+              AND (next_attempt_at IS NULL OR next_attempt_at <= ?)
             ORDER BY created_at ASC
             "#,
         )
         .bind(account_id_str)
         .bind(now)
+        .bind(now)
         .fetch_all(&self.pool)
         .await
         .map_err(DatabaseError::ConnectionError)
@@ -86,7 +89,7 @@ impl SqlitePendingOperationRepository {
             r#"
             SELECT id, account_id, email_id, folder_id, operation_type,
                    payload, status, retry_count, max_retries, error_message,
-                   created_at, completed_at, expires_at
+                   created_at, completed_at, expires_at, next_attempt_at
             FROM pending_operations
             WHERE email_id = ? AND status IN ('pending', 'in_progress')
             ORDER BY created_at ASC
@@ -193,6 +196,105 @@ impl SqlitePendingOperationRepository {
         Ok(result.rows_affected())
     }
 
+    /// Put a failed operation back in the queue, replaying it no earlier than `next_attempt_at`
+    pub async fn schedule_retry(
+        &self,
+        id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE pending_operations
+            SET status = 'pending', error_message = ?, retry_count = retry_count + 1,
+                next_attempt_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    /// Hold back all queued operations of an account while the provider is unreachable.
+    /// Unlike `schedule_retry` this does not count against the operations' retry budget.
+    pub async fn defer_account(
+        &self,
+        account_id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE pending_operations
+            SET status = 'pending', error_message = ?, next_attempt_at = ?
+            WHERE account_id = ? AND status IN ('pending', 'in_progress')
+            "#,
+        )
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(account_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Make deferred operations of an account eligible for replay immediately,
+    /// e.g. once a sync shows the provider is reachable again. Operations that
+    /// were scheduled for later on purpose, without having failed, keep their time.
+    pub async fn resume_account(&self, account_id: Uuid) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE pending_operations
+            SET next_attempt_at = NULL
+            WHERE account_id = ? AND status = 'pending' AND next_attempt_at IS NOT NULL
+              AND error_message IS NOT NULL
+            "#,
+        )
+        .bind(account_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Make the scheduled operations of an email eligible for replay immediately
+    pub async fn release_for_email(&self, email_id: Uuid) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE pending_operations
+            SET next_attempt_at = NULL
+            WHERE email_id = ? AND status = 'pending' AND next_attempt_at IS NOT NULL
+            "#,
+        )
+        .bind(email_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Return operations left in progress by an interrupted run to the queue
+    pub async fn reset_in_progress(&self) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE pending_operations SET status = 'pending' WHERE status = 'in_progress'",
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(result.rows_affected())
+    }
+
     /// Reset a failed operation back to pending for retry
     pub async fn reset_for_retry(&self, id: Uuid) -> Result<(), DatabaseError> {
         let id_str = id.to_string();
@@ -249,15 +351,17 @@ impl SqlitePendingOperationRepository {
     pub async fn find_accounts_with_pending_ops(&self) -> Result<Vec<Uuid>, DatabaseError> {
         let now = Utc::now();
 
-        let records = sqlx::query_scalar!(
+        let records: Vec<String> = sqlx::query_scalar(
             r#"
             SELECT DISTINCT account_id
             FROM pending_operations
             WHERE status = 'pending'
               AND (expires_at IS NULL OR expires_at > ?)
+              AND (next_attempt_at IS NULL OR next_attempt_at <= ?)
             "#,
-            now,
         )
+        .bind(now)
+        .bind(now)
         .fetch_all(&self.pool)
         .await
         .map_err(DatabaseError::ConnectionError)?;
@@ -0,0 +1,250 @@
+use crate::database::{
+    error::DatabaseError,
+    models::rule::{Rule, RuleAction, RuleCondition},
+};
+use async_trait::async_trait;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+#[async_trait]
+pub trait RuleRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Rule>, DatabaseError>;
+    async fn get_all(&self) -> Result<Vec<Rule>, DatabaseError>;
+    /// Enabled rules that apply to the account, including account-independent ones,
+    /// in evaluation order
+    async fn find_enabled_for_account(&self, account_id: Uuid) -> Result<Vec<Rule>, DatabaseError>;
+    async fn create(&self, rule: &Rule) -> Result<Uuid, DatabaseError>;
+    async fn update(&self, rule: &Rule) -> Result<(), DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteRuleRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRuleRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn map_row_to_rule(row: &sqlx::sqlite::SqliteRow) -> Result<Rule, DatabaseError> {
+        let conditions_json: String = row.get("conditions");
+        let conditions: Vec<RuleCondition> =
+            serde_json::from_str(&conditions_json).map_err(DatabaseError::JsonError)?;
+
+        let actions_json: String = row.get("actions");
+        let actions: Vec<RuleAction> =
+            serde_json::from_str(&actions_json).map_err(DatabaseError::JsonError)?;
+
+        let account_id = row
+            .get::<Option<String>, _>("account_id")
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| DatabaseError::RepositoryError(e.to_string()))?;
+
+        Ok(Rule {
+            id: Uuid::parse_str(&row.get::<String, _>("id"))
+                .map_err(|e| DatabaseError::RepositoryError(e.to_string()))?,
+            account_id,
+            name: row.get("name"),
+            enabled: row.get("enabled"),
+            match_all: row.get("match_all"),
+            conditions,
+            actions,
+            stop_processing: row.get("stop_processing"),
+            sort_order: row.get("sort_order"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[async_trait]
+impl RuleRepository for SqliteRuleRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Rule>, DatabaseError> {
+        let row = sqlx::query("SELECT * FROM rules WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        match row {
+            Some(r) => Ok(Some(Self::map_row_to_rule(&r)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<Rule>, DatabaseError> {
+        let rows = sqlx::query("SELECT * FROM rules ORDER BY sort_order, name")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        rows.iter().map(Self::map_row_to_rule).collect()
+    }
+
+    async fn find_enabled_for_account(&self, account_id: Uuid) -> Result<Vec<Rule>, DatabaseError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM rules
+            WHERE enabled = 1 AND (account_id IS NULL OR account_id = ?)
+            ORDER BY sort_order, created_at
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        rows.iter().map(Self::map_row_to_rule).collect()
+    }
+
+    async fn create(&self, rule: &Rule) -> Result<Uuid, DatabaseError> {
+        let conditions_json =
+            serde_json::to_string(&rule.conditions).map_err(DatabaseError::JsonError)?;
+        let actions_json =
+            serde_json::to_string(&rule.actions).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO rules (id, account_id, name, enabled, match_all, conditions, actions, stop_processing, sort_order)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(rule.id.to_string())
+        .bind(rule.account_id.map(|id| id.to_string()))
+        .bind(&rule.name)
+        .bind(rule.enabled)
+        .bind(rule.match_all)
+        .bind(conditions_json)
+        .bind(actions_json)
+        .bind(rule.stop_processing)
+        .bind(rule.sort_order)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(rule.id)
+    }
+
+    async fn update(&self, rule: &Rule) -> Result<(), DatabaseError> {
+        let conditions_json =
+            serde_json::to_string(&rule.conditions).map_err(DatabaseError::JsonError)?;
+        let actions_json =
+            serde_json::to_string(&rule.actions).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            UPDATE rules
+            SET account_id = ?, name = ?, enabled = ?, match_all = ?, conditions = ?,
+                actions = ?, stop_processing = ?, sort_order = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(rule.account_id.map(|id| id.to_string()))
+        .bind(&rule.name)
+        .bind(rule.enabled)
+        .bind(rule.match_all)
+        .bind(conditions_json)
+        .bind(actions_json)
+        .bind(rule.stop_processing)
+        .bind(rule.sort_order)
+        .bind(rule.id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM rules WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE rules (
+                id TEXT PRIMARY KEY NOT NULL,
+                account_id TEXT,
+                name TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                match_all BOOLEAN NOT NULL DEFAULT 1,
+                conditions TEXT NOT NULL DEFAULT '[]',
+                actions TEXT NOT NULL DEFAULT '[]',
+                stop_processing BOOLEAN NOT NULL DEFAULT 0,
+                sort_order INTEGER NOT NULL DEFAULT 0,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn create_test_rule(account_id: Option<Uuid>, sort_order: i32) -> Rule {
+        Rule {
+            id: Uuid::now_v7(),
+            account_id,
+            name: format!("Rule {}", sort_order),
+            enabled: true,
+            match_all: true,
+            conditions: vec![RuleCondition::From {
+                value: "news@example.com".to_string(),
+            }],
+            actions: vec![RuleAction::MarkRead],
+            stop_processing: false,
+            sort_order,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_enabled_for_account() {
+        let repository = SqliteRuleRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let global = create_test_rule(None, 2);
+        let own = create_test_rule(Some(account_id), 1);
+        let other = create_test_rule(Some(Uuid::now_v7()), 0);
+        let mut disabled = create_test_rule(Some(account_id), 3);
+        disabled.enabled = false;
+
+        for rule in [&global, &own, &other, &disabled] {
+            repository.create(rule).await.unwrap();
+        }
+
+        let rules = repository
+            .find_enabled_for_account(account_id)
+            .await
+            .unwrap();
+        let ids: Vec<Uuid> = rules.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![own.id, global.id]);
+        assert_eq!(rules[0].conditions, own.conditions);
+        assert_eq!(rules[0].actions, vec![RuleAction::MarkRead]);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::database::{error::DatabaseError, models::saved_search::SavedSearch};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait SavedSearchRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<SavedSearch>, DatabaseError>;
+    async fn get_all(&self) -> Result<Vec<SavedSearch>, DatabaseError>;
+    async fn find_by_account(&self, account_id: Uuid) -> Result<Vec<SavedSearch>, DatabaseError>;
+    async fn create(&self, saved_search: &SavedSearch) -> Result<Uuid, DatabaseError>;
+    async fn update(&self, saved_search: &SavedSearch) -> Result<(), DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteSavedSearchRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteSavedSearchRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SavedSearchRepository for SqliteSavedSearchRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<SavedSearch>, DatabaseError> {
+        sqlx::query_as::<_, SavedSearch>("SELECT * FROM saved_searches WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn get_all(&self) -> Result<Vec<SavedSearch>, DatabaseError> {
+        sqlx::query_as::<_, SavedSearch>(
+            "SELECT * FROM saved_searches ORDER BY sort_order, name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_account(&self, account_id: Uuid) -> Result<Vec<SavedSearch>, DatabaseError> {
+        sqlx::query_as::<_, SavedSearch>(
+            r#"
+            SELECT * FROM saved_searches
+            WHERE account_id = ?
+            ORDER BY sort_order, name COLLATE NOCASE
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn create(&self, saved_search: &SavedSearch) -> Result<Uuid, DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO saved_searches (id, account_id, name, query, icon, color, sort_order)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(saved_search.id.to_string())
+        .bind(saved_search.account_id.to_string())
+        .bind(&saved_search.name)
+        .bind(&saved_search.query)
+        .bind(&saved_search.icon)
+        .bind(&saved_search.color)
+        .bind(saved_search.sort_order)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(saved_search.id)
+    }
+
+    async fn update(&self, saved_search: &SavedSearch) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE saved_searches
+            SET name = ?, query = ?, icon = ?, color = ?, sort_order = ?,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(&saved_search.name)
+        .bind(&saved_search.query)
+        .bind(&saved_search.icon)
+        .bind(&saved_search.color)
+        .bind(saved_search.sort_order)
+        .bind(saved_search.id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM saved_searches WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE saved_searches (
+                id TEXT PRIMARY KEY NOT NULL,
+                account_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                query TEXT NOT NULL,
+                icon TEXT,
+                color TEXT,
+                sort_order INTEGER NOT NULL DEFAULT 0,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn create_test_saved_search(account_id: Uuid, name: &str, sort_order: i32) -> SavedSearch {
+        SavedSearch {
+            id: Uuid::now_v7(),
+            account_id,
+            name: name.to_string(),
+            query: "is:unread has:attachment".to_string(),
+            icon: None,
+            color: None,
+            sort_order,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_by_account() {
+        let repository = SqliteSavedSearchRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let second = create_test_saved_search(account_id, "a second", 1);
+        let first = create_test_saved_search(account_id, "b first", 0);
+        let other = create_test_saved_search(Uuid::now_v7(), "other", 0);
+
+        for saved_search in [&second, &first, &other] {
+            repository.create(saved_search).await.unwrap();
+        }
+
+        let saved_searches = repository.find_by_account(account_id).await.unwrap();
+        let ids: Vec<Uuid> = saved_searches.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![first.id, second.id]);
+        assert_eq!(saved_searches[0].query, first.query);
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete() {
+        let repository = SqliteSavedSearchRepository::new(create_test_pool().await);
+        let mut saved_search = create_test_saved_search(Uuid::now_v7(), "Receipts", 0);
+        repository.create(&saved_search).await.unwrap();
+
+        saved_search.query = "from:shop label:receipts".to_string();
+        saved_search.icon = Some("receipt".to_string());
+        repository.update(&saved_search).await.unwrap();
+
+        let stored = repository
+            .find_by_id(saved_search.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.query, "from:shop label:receipts");
+        assert_eq!(stored.icon.as_deref(), Some("receipt"));
+
+        repository.delete(saved_search.id).await.unwrap();
+        assert!(repository
+            .find_by_id(saved_search.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
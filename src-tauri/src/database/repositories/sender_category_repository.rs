@@ -0,0 +1,133 @@
+use crate::database::{error::DatabaseError, models::sender_category::SenderCategory};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+#[async_trait]
+pub trait SenderCategoryRepository {
+    async fn get_all(&self) -> Result<Vec<SenderCategory>, DatabaseError>;
+    async fn find_by_sender(&self, sender: &str) -> Result<Option<SenderCategory>, DatabaseError>;
+    /// Records a correction, replacing the sender's category
+    async fn record(&self, sender: &str, category: &str) -> Result<(), DatabaseError>;
+    async fn delete(&self, sender: &str) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteSenderCategoryRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteSenderCategoryRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SenderCategoryRepository for SqliteSenderCategoryRepository {
+    async fn get_all(&self) -> Result<Vec<SenderCategory>, DatabaseError> {
+        sqlx::query_as::<_, SenderCategory>("SELECT * FROM sender_categories ORDER BY sender")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_sender(&self, sender: &str) -> Result<Option<SenderCategory>, DatabaseError> {
+        sqlx::query_as::<_, SenderCategory>("SELECT * FROM sender_categories WHERE sender = ?")
+            .bind(sender.trim().to_lowercase())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn record(&self, sender: &str, category: &str) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO sender_categories (sender, category) VALUES (?, ?)
+            ON CONFLICT (sender) DO UPDATE SET
+                category = excluded.category,
+                corrections = corrections + 1,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(sender.trim().to_lowercase())
+        .bind(category)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, sender: &str) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM sender_categories WHERE sender = ?")
+            .bind(sender.trim().to_lowercase())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE sender_categories (
+                sender TEXT PRIMARY KEY,
+                category TEXT NOT NULL CHECK (category IN ('personal', 'transactions', 'updates', 'promotions')),
+                corrections INTEGER NOT NULL DEFAULT 1,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_record_correction() {
+        let repository = SqliteSenderCategoryRepository::new(create_test_pool().await);
+
+        repository
+            .record("News@Shop.com", "promotions")
+            .await
+            .unwrap();
+        repository
+            .record("news@shop.com ", "updates")
+            .await
+            .unwrap();
+
+        let corrected = repository
+            .find_by_sender("NEWS@shop.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(corrected.sender, "news@shop.com");
+        assert_eq!(corrected.category, "updates");
+        assert_eq!(corrected.corrections, 2);
+
+        assert!(repository.record("a@b.com", "spam").await.is_err());
+        assert_eq!(repository.get_all().await.unwrap().len(), 1);
+
+        repository.delete("news@shop.com").await.unwrap();
+        assert!(repository
+            .find_by_sender("news@shop.com")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
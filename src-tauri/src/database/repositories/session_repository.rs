@@ -0,0 +1,260 @@
+use crate::database::{
+    error::DatabaseError,
+    models::session::{SessionWindow, SessionWindowType},
+};
+use async_trait::async_trait;
+use sqlx::{Row, SqlitePool};
+
+#[async_trait]
+pub trait SessionRepository {
+    async fn get_all(&self) -> Result<Vec<SessionWindow>, DatabaseError>;
+    async fn upsert(&self, window: &SessionWindow) -> Result<(), DatabaseError>;
+    /// Update only the geometry of a window, keeping its route and view state
+    async fn upsert_geometry(&self, window: &SessionWindow) -> Result<(), DatabaseError>;
+    async fn delete(&self, label: &str) -> Result<(), DatabaseError>;
+    async fn clear(&self) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteSessionRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteSessionRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn map_row(row: &sqlx::sqlite::SqliteRow) -> Result<SessionWindow, DatabaseError> {
+        let window_type = row
+            .get::<String, _>("window_type")
+            .parse::<SessionWindowType>()
+            .map_err(DatabaseError::RepositoryError)?;
+
+        let view_state_json: String = row.get("view_state");
+        let view_state =
+            serde_json::from_str(&view_state_json).map_err(DatabaseError::JsonError)?;
+
+        Ok(SessionWindow {
+            label: row.get("label"),
+            window_type,
+            route: row.get("route"),
+            x: row.get("x"),
+            y: row.get("y"),
+            width: row.get::<Option<i64>, _>("width").map(|w| w as u32),
+            height: row.get::<Option<i64>, _>("height").map(|h| h as u32),
+            is_maximized: row.get("is_maximized"),
+            view_state,
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[async_trait]
+impl SessionRepository for SqliteSessionRepository {
+    async fn get_all(&self) -> Result<Vec<SessionWindow>, DatabaseError> {
+        let rows = sqlx::query(
+            "SELECT * FROM session_windows ORDER BY window_type = 'main' DESC, updated_at",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        rows.iter().map(Self::map_row).collect()
+    }
+
+    async fn upsert(&self, window: &SessionWindow) -> Result<(), DatabaseError> {
+        let view_state_json =
+            serde_json::to_string(&window.view_state).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO session_windows
+                (label, window_type, route, x, y, width, height, is_maximized, view_state, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(label) DO UPDATE SET
+                window_type = excluded.window_type,
+                route = excluded.route,
+                x = excluded.x,
+                y = excluded.y,
+                width = excluded.width,
+                height = excluded.height,
+                is_maximized = excluded.is_maximized,
+                view_state = excluded.view_state,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&window.label)
+        .bind(window.window_type.to_string())
+        .bind(&window.route)
+        .bind(window.x)
+        .bind(window.y)
+        .bind(window.width.map(|w| w as i64))
+        .bind(window.height.map(|h| h as i64))
+        .bind(window.is_maximized)
+        .bind(view_state_json)
+        .bind(window.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn upsert_geometry(&self, window: &SessionWindow) -> Result<(), DatabaseError> {
+        let view_state_json =
+            serde_json::to_string(&window.view_state).map_err(DatabaseError::JsonError)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO session_windows
+                (label, window_type, route, x, y, width, height, is_maximized, view_state, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(label) DO UPDATE SET
+                x = excluded.x,
+                y = excluded.y,
+                width = excluded.width,
+                height = excluded.height,
+                is_maximized = excluded.is_maximized,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&window.label)
+        .bind(window.window_type.to_string())
+        .bind(&window.route)
+        .bind(window.x)
+        .bind(window.y)
+        .bind(window.width.map(|w| w as i64))
+        .bind(window.height.map(|h| h as i64))
+        .bind(window.is_maximized)
+        .bind(view_state_json)
+        .bind(window.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, label: &str) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM session_windows WHERE label = ?")
+            .bind(label)
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM session_windows")
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE session_windows (
+                label TEXT PRIMARY KEY NOT NULL,
+                window_type TEXT NOT NULL DEFAULT 'main',
+                route TEXT,
+                x INTEGER,
+                y INTEGER,
+                width INTEGER,
+                height INTEGER,
+                is_maximized BOOLEAN NOT NULL DEFAULT 0,
+                view_state TEXT NOT NULL DEFAULT '{}',
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn create_test_window(label: &str, window_type: SessionWindowType) -> SessionWindow {
+        SessionWindow {
+            label: label.to_string(),
+            window_type,
+            route: None,
+            x: Some(100),
+            y: Some(80),
+            width: Some(1200),
+            height: Some(800),
+            is_maximized: false,
+            view_state: serde_json::json!({}),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_and_restore_session() {
+        let repo = SqliteSessionRepository::new(create_test_pool().await);
+
+        let mut main = create_test_window("main", SessionWindowType::Main);
+        main.route = Some("/mail/a/folders/b/conversations/c".to_string());
+        main.view_state = serde_json::json!({
+            "folder_id": "b",
+            "conversation_id": "c",
+            "scroll_anchors": { "folder:b": "d" },
+        });
+        repo.upsert(&main).await.unwrap();
+        repo.upsert(&create_test_window("message-1", SessionWindowType::Message))
+            .await
+            .unwrap();
+
+        // Moving the window keeps the route and view state saved by the frontend
+        let mut moved = create_test_window("main", SessionWindowType::Main);
+        moved.x = Some(300);
+        moved.width = Some(900);
+        moved.is_maximized = true;
+        repo.upsert_geometry(&moved).await.unwrap();
+
+        let windows = repo.get_all().await.unwrap();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].label, "main");
+        assert_eq!(windows[0].route, main.route);
+        assert_eq!(windows[0].view_state, main.view_state);
+        assert_eq!(windows[0].x, Some(300));
+        assert_eq!(windows[0].y, Some(80));
+        assert_eq!(windows[0].width, Some(900));
+        assert!(windows[0].is_maximized);
+        assert_eq!(windows[1].window_type, SessionWindowType::Message);
+
+        repo.delete("message-1").await.unwrap();
+        assert_eq!(repo.get_all().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_geometry_of_unknown_window_is_inserted() {
+        let repo = SqliteSessionRepository::new(create_test_pool().await);
+
+        repo.upsert_geometry(&create_test_window("main", SessionWindowType::Main))
+            .await
+            .unwrap();
+
+        let windows = repo.get_all().await.unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].route, None);
+        assert_eq!(windows[0].width, Some(1200));
+    }
+}
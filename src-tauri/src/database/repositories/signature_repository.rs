@@ -0,0 +1,246 @@
+use crate::database::{error::DatabaseError, models::signature::Signature};
+use async_trait::async_trait;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+
+#[async_trait]
+pub trait SignatureRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Signature>, DatabaseError>;
+    async fn get_all(&self) -> Result<Vec<Signature>, DatabaseError>;
+    async fn find_by_account(&self, account_id: Uuid) -> Result<Vec<Signature>, DatabaseError>;
+    /// Inserts the signature, taking its default flags away from the other
+    /// signatures of the same account and address
+    async fn create(&self, signature: &Signature) -> Result<Uuid, DatabaseError>;
+    async fn update(&self, signature: &Signature) -> Result<(), DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteSignatureRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteSignatureRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// There is at most one default per account, address and message kind
+    async fn clear_other_defaults(
+        tx: &mut Transaction<'_, Sqlite>,
+        signature: &Signature,
+    ) -> Result<(), DatabaseError> {
+        if !signature.is_default && !signature.is_reply_default {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE signatures
+            SET is_default = is_default AND NOT ?,
+                is_reply_default = is_reply_default AND NOT ?,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE account_id = ? AND identity_email IS ? AND id != ?
+            "#,
+        )
+        .bind(signature.is_default)
+        .bind(signature.is_reply_default)
+        .bind(signature.account_id.to_string())
+        .bind(&signature.identity_email)
+        .bind(signature.id.to_string())
+        .execute(&mut **tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SignatureRepository for SqliteSignatureRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Signature>, DatabaseError> {
+        sqlx::query_as::<_, Signature>("SELECT * FROM signatures WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn get_all(&self) -> Result<Vec<Signature>, DatabaseError> {
+        sqlx::query_as::<_, Signature>("SELECT * FROM signatures ORDER BY name COLLATE NOCASE")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_account(&self, account_id: Uuid) -> Result<Vec<Signature>, DatabaseError> {
+        sqlx::query_as::<_, Signature>(
+            "SELECT * FROM signatures WHERE account_id = ? ORDER BY name COLLATE NOCASE",
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn create(&self, signature: &Signature) -> Result<Uuid, DatabaseError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Self::clear_other_defaults(&mut tx, signature).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO signatures
+                (id, account_id, identity_email, name, signature, signature_text, is_default, is_reply_default)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(signature.id.to_string())
+        .bind(signature.account_id.to_string())
+        .bind(&signature.identity_email)
+        .bind(&signature.name)
+        .bind(&signature.signature)
+        .bind(&signature.signature_text)
+        .bind(signature.is_default)
+        .bind(signature.is_reply_default)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(signature.id)
+    }
+
+    async fn update(&self, signature: &Signature) -> Result<(), DatabaseError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Self::clear_other_defaults(&mut tx, signature).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE signatures
+            SET account_id = ?, identity_email = ?, name = ?, signature = ?, signature_text = ?,
+                is_default = ?, is_reply_default = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(signature.account_id.to_string())
+        .bind(&signature.identity_email)
+        .bind(&signature.name)
+        .bind(&signature.signature)
+        .bind(&signature.signature_text)
+        .bind(signature.is_default)
+        .bind(signature.is_reply_default)
+        .bind(signature.id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM signatures WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE signatures (
+                id TEXT NOT NULL PRIMARY KEY,
+                account_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                is_default BOOLEAN NOT NULL DEFAULT 0,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        sqlx::raw_sql(include_str!(
+            "../../../migrations/20250325000001_extend_signatures.sql"
+        ))
+        .execute(&pool)
+        .await
+        .expect("Failed to migrate test schema");
+
+        pool
+    }
+
+    fn create_test_signature(
+        account_id: Uuid,
+        identity_email: Option<&str>,
+        is_default: bool,
+        is_reply_default: bool,
+    ) -> Signature {
+        Signature {
+            id: Uuid::now_v7(),
+            account_id,
+            identity_email: identity_email.map(String::from),
+            name: "Work".to_string(),
+            signature: "<p>Jane</p>".to_string(),
+            signature_text: None,
+            is_default,
+            is_reply_default,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_default_replaces_previous_for_same_address() {
+        let repository = SqliteSignatureRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let previous = create_test_signature(account_id, None, true, true);
+        let alias = create_test_signature(account_id, Some("sales@example.com"), true, false);
+        let other_account = create_test_signature(Uuid::now_v7(), None, true, false);
+        for signature in [&previous, &alias, &other_account] {
+            repository.create(signature).await.unwrap();
+        }
+
+        let replacement = create_test_signature(account_id, None, true, false);
+        repository.create(&replacement).await.unwrap();
+
+        let find = |id| repository.find_by_id(id);
+        let previous = find(previous.id).await.unwrap().unwrap();
+        assert!(!previous.is_default);
+        assert!(previous.is_reply_default);
+        assert!(find(replacement.id).await.unwrap().unwrap().is_default);
+        assert!(find(alias.id).await.unwrap().unwrap().is_default);
+        assert!(find(other_account.id).await.unwrap().unwrap().is_default);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::database::{
+    error::DatabaseError,
+    models::smime::{SmimeCertificate, SmimeVerification},
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait SmimeRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<SmimeCertificate>, DatabaseError>;
+    async fn get_all(&self) -> Result<Vec<SmimeCertificate>, DatabaseError>;
+    async fn find_by_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Vec<SmimeCertificate>, DatabaseError>;
+    /// The account's certificate whose private key is in the credential store
+    async fn find_signing_certificate(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<SmimeCertificate>, DatabaseError>;
+    /// The newest unexpired certificate for each address that has one
+    async fn find_encryption_certificates(
+        &self,
+        emails: &[String],
+    ) -> Result<Vec<SmimeCertificate>, DatabaseError>;
+    /// Inserts a certificate, or merges it into the stored copy with the same
+    /// fingerprint. An existing owner or private key is never dropped.
+    async fn upsert(&self, certificate: &SmimeCertificate) -> Result<(), DatabaseError>;
+    /// Marks the account's certificates as having no private key, before a new
+    /// identity replaces the stored one
+    async fn clear_signing_certificate(&self, account_id: Uuid) -> Result<(), DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+    async fn find_verification(
+        &self,
+        email_id: Uuid,
+    ) -> Result<Option<SmimeVerification>, DatabaseError>;
+    async fn save_verification(
+        &self,
+        verification: &SmimeVerification,
+    ) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteSmimeRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteSmimeRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SmimeRepository for SqliteSmimeRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<SmimeCertificate>, DatabaseError> {
+        sqlx::query_as::<_, SmimeCertificate>("SELECT * FROM smime_certificates WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn get_all(&self) -> Result<Vec<SmimeCertificate>, DatabaseError> {
+        sqlx::query_as::<_, SmimeCertificate>(
+            "SELECT * FROM smime_certificates ORDER BY email, not_after DESC",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_by_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Vec<SmimeCertificate>, DatabaseError> {
+        sqlx::query_as::<_, SmimeCertificate>(
+            "SELECT * FROM smime_certificates WHERE account_id = ? ORDER BY not_after DESC",
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_signing_certificate(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<SmimeCertificate>, DatabaseError> {
+        sqlx::query_as::<_, SmimeCertificate>(
+            r#"
+            SELECT * FROM smime_certificates
+            WHERE account_id = ? AND has_private_key = 1
+            ORDER BY not_after DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_encryption_certificates(
+        &self,
+        emails: &[String],
+    ) -> Result<Vec<SmimeCertificate>, DatabaseError> {
+        let now = Utc::now();
+        let mut certificates = Vec::new();
+
+        for email in emails {
+            let certificate = sqlx::query_as::<_, SmimeCertificate>(
+                r#"
+                SELECT * FROM smime_certificates
+                WHERE email = ? AND not_before <= ? AND not_after > ?
+                ORDER BY not_after DESC
+                LIMIT 1
+                "#,
+            )
+            .bind(email.trim().to_lowercase())
+            .bind(now)
+            .bind(now)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+            certificates.extend(certificate);
+        }
+
+        Ok(certificates)
+    }
+
+    async fn upsert(&self, certificate: &SmimeCertificate) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO smime_certificates (
+                id, account_id, email, subject, issuer, serial, fingerprint,
+                not_before, not_after, certificate_pem, has_private_key
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(fingerprint) DO UPDATE SET
+                account_id = COALESCE(excluded.account_id, smime_certificates.account_id),
+                has_private_key = MAX(excluded.has_private_key, smime_certificates.has_private_key),
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(certificate.id.to_string())
+        .bind(certificate.account_id.map(|id| id.to_string()))
+        .bind(certificate.email.trim().to_lowercase())
+        .bind(&certificate.subject)
+        .bind(&certificate.issuer)
+        .bind(&certificate.serial)
+        .bind(&certificate.fingerprint)
+        .bind(certificate.not_before)
+        .bind(certificate.not_after)
+        .bind(&certificate.certificate_pem)
+        .bind(certificate.has_private_key)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn clear_signing_certificate(&self, account_id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE smime_certificates
+            SET has_private_key = 0, updated_at = CURRENT_TIMESTAMP
+            WHERE account_id = ?
+            "#,
+        )
+        .bind(account_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM smime_certificates WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn find_verification(
+        &self,
+        email_id: Uuid,
+    ) -> Result<Option<SmimeVerification>, DatabaseError> {
+        sqlx::query_as::<_, SmimeVerification>(
+            "SELECT * FROM email_smime_verifications WHERE email_id = ?",
+        )
+        .bind(email_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn save_verification(
+        &self,
+        verification: &SmimeVerification,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO email_smime_verifications (
+                email_id, status, signer_email, signer_subject, issuer,
+                fingerprint, not_after, error, verified_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(verification.email_id.to_string())
+        .bind(verification.status.as_str())
+        .bind(&verification.signer_email)
+        .bind(&verification.signer_subject)
+        .bind(&verification.issuer)
+        .bind(&verification.fingerprint)
+        .bind(verification.not_after)
+        .bind(&verification.error)
+        .bind(verification.verified_at)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE smime_certificates (
+                id TEXT NOT NULL PRIMARY KEY,
+                account_id TEXT,
+                email TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                issuer TEXT NOT NULL,
+                serial TEXT NOT NULL,
+                fingerprint TEXT NOT NULL UNIQUE,
+                not_before TIMESTAMP NOT NULL,
+                not_after TIMESTAMP NOT NULL,
+                certificate_pem TEXT NOT NULL,
+                has_private_key INTEGER NOT NULL DEFAULT 0,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn certificate(email: &str, fingerprint: &str, days_left: i64) -> SmimeCertificate {
+        SmimeCertificate {
+            id: Uuid::now_v7(),
+            account_id: None,
+            email: email.to_string(),
+            subject: format!("CN={}", email),
+            issuer: "CN=Test CA".to_string(),
+            serial: "01".to_string(),
+            fingerprint: fingerprint.to_string(),
+            not_before: Utc::now() - Duration::days(365),
+            not_after: Utc::now() + Duration::days(days_left),
+            certificate_pem: String::new(),
+            has_private_key: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encryption_certificates_skip_expired() {
+        let repository = SqliteSmimeRepository::new(create_test_pool().await);
+
+        repository
+            .upsert(&certificate("Bob@Example.com", "aa", 30))
+            .await
+            .unwrap();
+        repository
+            .upsert(&certificate("bob@example.com", "bb", 300))
+            .await
+            .unwrap();
+        repository
+            .upsert(&certificate("eve@example.com", "cc", -1))
+            .await
+            .unwrap();
+
+        let found = repository
+            .find_encryption_certificates(&[
+                "bob@example.com".to_string(),
+                "eve@example.com".to_string(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].fingerprint, "bb");
+    }
+
+    #[tokio::test]
+    async fn test_upsert_keeps_owner_and_private_key() {
+        let repository = SqliteSmimeRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let own = SmimeCertificate {
+            account_id: Some(account_id),
+            has_private_key: true,
+            ..certificate("me@example.com", "dd", 100)
+        };
+        repository.upsert(&own).await.unwrap();
+        // The same certificate seen again on a signed message we sent ourselves
+        repository
+            .upsert(&certificate("me@example.com", "dd", 100))
+            .await
+            .unwrap();
+
+        let signing = repository
+            .find_signing_certificate(account_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(signing.id, own.id);
+
+        repository
+            .clear_signing_certificate(account_id)
+            .await
+            .unwrap();
+        assert!(repository
+            .find_signing_certificate(account_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -22,6 +22,14 @@ pub trait SyncStateRepository {
     /// Reset all folders stuck in 'syncing' status to 'idle'.
     /// Should be called on application boot to recover from unclean shutdowns.
     async fn reset_stale_syncing_states(&self) -> Result<u64, DatabaseError>;
+    /// Mark every folder of the account as 'paused', or set the paused ones
+    /// back to 'idle'. An account-level row keeps the account paused while it
+    /// has no folders yet.
+    async fn set_account_paused(&self, account_id: Uuid, paused: bool)
+        -> Result<(), DatabaseError>;
+    async fn is_account_paused(&self, account_id: Uuid) -> Result<bool, DatabaseError>;
+    /// Folders of the account with a history backfill still to run
+    async fn find_backfill_folders(&self, account_id: Uuid) -> Result<Vec<Uuid>, DatabaseError>;
 }
 
 pub struct SqliteSyncStateRepository {
@@ -106,4 +114,109 @@ impl SyncStateRepository for SqliteSyncStateRepository {
 
         Ok(result.rows_affected())
     }
+
+    async fn set_account_paused(
+        &self,
+        account_id: Uuid,
+        paused: bool,
+    ) -> Result<(), DatabaseError> {
+        let account_id_str = account_id.to_string();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        sqlx::query!(
+            "DELETE FROM sync_state WHERE account_id = ? AND folder_id IS NULL",
+            account_id_str
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        if paused {
+            let folder_ids = sqlx::query_scalar!(
+                "SELECT id FROM folders WHERE account_id = ?",
+                account_id_str
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+            for folder_id in folder_ids.into_iter().map(Some).chain([None]) {
+                let id = Uuid::now_v7().to_string();
+                sqlx::query!(
+                    r#"
+                    INSERT INTO sync_state (id, account_id, folder_id, sync_status)
+                    VALUES (?, ?, ?, 'paused')
+                    ON CONFLICT(account_id, folder_id) DO UPDATE SET
+                        sync_status = 'paused',
+                        updated_at = CURRENT_TIMESTAMP
+                    "#,
+                    id,
+                    account_id_str,
+                    folder_id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(DatabaseError::ConnectionError)?;
+            }
+        } else {
+            sqlx::query!(
+                r#"
+                UPDATE sync_state
+                SET sync_status = 'idle', updated_at = CURRENT_TIMESTAMP
+                WHERE account_id = ? AND sync_status = 'paused'
+                "#,
+                account_id_str
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+        }
+
+        tx.commit().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn is_account_paused(&self, account_id: Uuid) -> Result<bool, DatabaseError> {
+        let account_id_str = account_id.to_string();
+
+        let paused = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM sync_state
+                WHERE account_id = ? AND folder_id IS NULL AND sync_status = 'paused'
+            ) AS "paused!: bool"
+            "#,
+            account_id_str
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(paused)
+    }
+
+    async fn find_backfill_folders(&self, account_id: Uuid) -> Result<Vec<Uuid>, DatabaseError> {
+        let account_id_str = account_id.to_string();
+
+        let folder_ids = sqlx::query_scalar!(
+            r#"
+            SELECT folder_id AS "folder_id!" FROM sync_state
+            WHERE account_id = ? AND folder_id IS NOT NULL AND checkpoint_data IS NOT NULL
+            "#,
+            account_id_str
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(folder_ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect())
+    }
 }
@@ -0,0 +1,171 @@
+use crate::database::{error::DatabaseError, models::template::Template};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait TemplateRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Template>, DatabaseError>;
+    async fn get_all(&self) -> Result<Vec<Template>, DatabaseError>;
+    /// Templates of the account plus the ones shared by every account
+    async fn find_for_account(&self, account_id: Uuid) -> Result<Vec<Template>, DatabaseError>;
+    async fn create(&self, template: &Template) -> Result<Uuid, DatabaseError>;
+    async fn update(&self, template: &Template) -> Result<(), DatabaseError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteTemplateRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteTemplateRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TemplateRepository for SqliteTemplateRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Template>, DatabaseError> {
+        sqlx::query_as::<_, Template>("SELECT * FROM templates WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn get_all(&self) -> Result<Vec<Template>, DatabaseError> {
+        sqlx::query_as::<_, Template>("SELECT * FROM templates ORDER BY name COLLATE NOCASE")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn find_for_account(&self, account_id: Uuid) -> Result<Vec<Template>, DatabaseError> {
+        sqlx::query_as::<_, Template>(
+            r#"
+            SELECT * FROM templates
+            WHERE account_id IS NULL OR account_id = ?
+            ORDER BY name COLLATE NOCASE
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn create(&self, template: &Template) -> Result<Uuid, DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO templates (id, account_id, name, subject, body)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(template.id.to_string())
+        .bind(template.account_id.map(|id| id.to_string()))
+        .bind(&template.name)
+        .bind(&template.subject)
+        .bind(&template.body)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(template.id)
+    }
+
+    async fn update(&self, template: &Template) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            UPDATE templates
+            SET account_id = ?, name = ?, subject = ?, body = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(template.account_id.map(|id| id.to_string()))
+        .bind(&template.name)
+        .bind(&template.subject)
+        .bind(&template.body)
+        .bind(template.id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM templates WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE templates (
+                id TEXT PRIMARY KEY NOT NULL,
+                account_id TEXT,
+                name TEXT NOT NULL,
+                subject TEXT,
+                body TEXT NOT NULL DEFAULT '',
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    fn create_test_template(account_id: Option<Uuid>, name: &str) -> Template {
+        Template {
+            id: Uuid::now_v7(),
+            account_id,
+            name: name.to_string(),
+            subject: None,
+            body: "<p>Hi {{first_name}}</p>".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_for_account() {
+        let repository = SqliteTemplateRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        let shared = create_test_template(None, "b shared");
+        let own = create_test_template(Some(account_id), "A own");
+        let other = create_test_template(Some(Uuid::now_v7()), "c other");
+
+        for template in [&shared, &own, &other] {
+            repository.create(template).await.unwrap();
+        }
+
+        let templates = repository.find_for_account(account_id).await.unwrap();
+        let ids: Vec<Uuid> = templates.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![own.id, shared.id]);
+        assert_eq!(templates[0].body, own.body);
+    }
+}
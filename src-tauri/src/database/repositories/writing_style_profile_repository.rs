@@ -0,0 +1,149 @@
+use crate::database::{error::DatabaseError, models::writing_style_profile::WritingStyleProfile};
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait WritingStyleProfileRepository {
+    async fn find_by_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<WritingStyleProfile>, DatabaseError>;
+    /// Stores a freshly learned profile, replacing the account's previous one
+    async fn upsert(
+        &self,
+        account_id: Uuid,
+        profile: &str,
+        sample_count: i64,
+    ) -> Result<(), DatabaseError>;
+    async fn delete(&self, account_id: Uuid) -> Result<(), DatabaseError>;
+}
+
+pub struct SqliteWritingStyleProfileRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteWritingStyleProfileRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WritingStyleProfileRepository for SqliteWritingStyleProfileRepository {
+    async fn find_by_account(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<WritingStyleProfile>, DatabaseError> {
+        sqlx::query_as::<_, WritingStyleProfile>(
+            "SELECT * FROM writing_style_profiles WHERE account_id = ?",
+        )
+        .bind(account_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)
+    }
+
+    async fn upsert(
+        &self,
+        account_id: Uuid,
+        profile: &str,
+        sample_count: i64,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO writing_style_profiles (account_id, profile, sample_count) VALUES (?, ?, ?)
+            ON CONFLICT (account_id) DO UPDATE SET
+                profile = excluded.profile,
+                sample_count = excluded.sample_count,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(account_id.to_string())
+        .bind(profile)
+        .bind(sample_count)
+        .execute(&self.pool)
+        .await
+        .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, account_id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query("DELETE FROM writing_style_profiles WHERE account_id = ?")
+            .bind(account_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(DatabaseError::ConnectionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database pool");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE writing_style_profiles (
+                account_id TEXT PRIMARY KEY,
+                profile TEXT NOT NULL,
+                sample_count INTEGER NOT NULL DEFAULT 0,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create test schema");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_upsert_profile() {
+        let repository = SqliteWritingStyleProfileRepository::new(create_test_pool().await);
+        let account_id = Uuid::now_v7();
+
+        assert!(repository
+            .find_by_account(account_id)
+            .await
+            .unwrap()
+            .is_none());
+
+        repository
+            .upsert(account_id, "Formal, signs off with \"Best\"", 12)
+            .await
+            .unwrap();
+        repository
+            .upsert(account_id, "Casual, signs off with \"Cheers\"", 20)
+            .await
+            .unwrap();
+
+        let profile = repository
+            .find_by_account(account_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(profile.account_id, account_id);
+        assert_eq!(profile.profile, "Casual, signs off with \"Cheers\"");
+        assert_eq!(profile.sample_count, 20);
+
+        repository.delete(account_id).await.unwrap();
+        assert!(repository
+            .find_by_account(account_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,12 +1,15 @@
+pub mod actions;
 pub mod commands;
 pub mod config;
 pub mod database;
 pub mod licensing;
+pub mod mail_client;
 pub mod navigation;
 pub mod state;
 
 pub mod search;
 pub mod services;
+pub mod session;
 pub mod sync;
 
 pub use crate::state::AppState;
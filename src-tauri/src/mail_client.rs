@@ -0,0 +1,195 @@
+//! Ravn as the system mail client: the handler of `mailto:` links and the
+//! viewer of `.eml` files opened from the file manager
+//!
+//! `mailto:` links arrive through the deep link plugin and open the composer
+//! via the navigation layer. Message files arrive as `file://` URLs on macOS
+//! and as launch arguments elsewhere, and open in a message window.
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager, Runtime};
+use url::Url;
+
+use crate::database::models::session::SessionWindowType;
+use crate::state::AppState;
+
+pub const MAILTO_SCHEME: &str = "mailto";
+
+/// Extension of the message files Ravn opens
+const MESSAGE_FILE_EXTENSION: &str = "eml";
+
+/// Whether `mailto:` links open in Ravn
+pub fn is_default<R: Runtime>(app: &AppHandle<R>) -> Result<bool, String> {
+    #[cfg(target_os = "macos")]
+    {
+        Ok(macos::default_handler(MAILTO_SCHEME).as_deref()
+            == Some(app.config().identifier.as_str()))
+    }
+
+    // On Windows this only tells whether Ravn is registered for the scheme;
+    // which registered app opens it is the user's choice in the settings
+    #[cfg(not(target_os = "macos"))]
+    {
+        use tauri_plugin_deep_link::DeepLinkExt;
+
+        app.deep_link()
+            .is_registered(MAILTO_SCHEME)
+            .map_err(|e| format!("Failed to check the mailto handler: {}", e))
+    }
+}
+
+/// Make Ravn the handler of `mailto:` links and return whether it now is.
+/// Windows does not let apps pick their own defaults, so the Default apps
+/// settings are opened for the user to choose Ravn there.
+pub fn set_default<R: Runtime>(app: &AppHandle<R>) -> Result<bool, String> {
+    #[cfg(target_os = "macos")]
+    {
+        macos::set_default_handler(MAILTO_SCHEME, &app.config().identifier)?;
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        use tauri_plugin_deep_link::DeepLinkExt;
+
+        app.deep_link()
+            .register(MAILTO_SCHEME)
+            .map_err(|e| format!("Failed to register the mailto handler: {}", e))?;
+    }
+
+    if cfg!(windows) {
+        opener::open("ms-settings:defaultapps")
+            .map_err(|e| format!("Failed to open the Default apps settings: {}", e))?;
+        return Ok(false);
+    }
+
+    is_default(app)
+}
+
+fn is_message_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(MESSAGE_FILE_EXTENSION))
+}
+
+/// The message file a URL opened by the OS points to, if any
+pub fn message_file_from_url(url: &Url) -> Option<PathBuf> {
+    if url.scheme() != "file" {
+        return None;
+    }
+
+    url.to_file_path().ok().filter(|path| is_message_file(path))
+}
+
+/// Message files among the arguments the app was started with, without the
+/// binary itself. Relative paths are resolved against `cwd`.
+pub fn message_files_from_args<I, S>(args: I, cwd: &Path) -> Vec<PathBuf>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .skip(1)
+        .map(|arg| cwd.join(arg.as_ref()))
+        .filter(|path| is_message_file(path) && path.is_file())
+        .collect()
+}
+
+/// Show a message file in a window of its own
+pub fn open_message_file<R: Runtime>(app: &AppHandle<R>, path: PathBuf) {
+    let Some(state) = app.try_state::<AppState>() else {
+        log::error!("[Mail Client] App state not ready for {}", path.display());
+        return;
+    };
+
+    let app = app.clone();
+    let pool = state.db_pool.clone();
+    tauri::async_runtime::spawn(async move {
+        let route = format!(
+            "/windows/message-file?{}",
+            url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("path", &path.to_string_lossy())
+                .finish()
+        );
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Message".to_string());
+
+        if let Err(error) = crate::session::open_detached_window(
+            &app,
+            pool,
+            &crate::session::message_file_window_label(&path),
+            SessionWindowType::Message,
+            route,
+            &title,
+        )
+        .await
+        {
+            log::error!("[Mail Client] Failed to open {}: {}", path.display(), error);
+        }
+    });
+}
+
+/// Launch Services, which keeps the default handler of each URL scheme
+#[cfg(target_os = "macos")]
+mod macos {
+    use core_foundation::base::TCFType;
+    use core_foundation::string::{CFString, CFStringRef};
+
+    #[link(name = "CoreServices", kind = "framework")]
+    extern "C" {
+        fn LSCopyDefaultHandlerForURLScheme(scheme: CFStringRef) -> CFStringRef;
+        fn LSSetDefaultHandlerForURLScheme(scheme: CFStringRef, handler: CFStringRef) -> i32;
+    }
+
+    /// Bundle identifier of the app opening the scheme
+    pub fn default_handler(scheme: &str) -> Option<String> {
+        let scheme = CFString::new(scheme);
+        // SAFETY: the scheme outlives the call, and the returned string
+        // follows the create rule, so it is owned here
+        unsafe {
+            let handler = LSCopyDefaultHandlerForURLScheme(scheme.as_concrete_TypeRef());
+            (!handler.is_null()).then(|| CFString::wrap_under_create_rule(handler).to_string())
+        }
+    }
+
+    pub fn set_default_handler(scheme: &str, bundle_id: &str) -> Result<(), String> {
+        let scheme = CFString::new(scheme);
+        let bundle_id = CFString::new(bundle_id);
+        // SAFETY: both strings outlive the call
+        let status = unsafe {
+            LSSetDefaultHandlerForURLScheme(
+                scheme.as_concrete_TypeRef(),
+                bundle_id.as_concrete_TypeRef(),
+            )
+        };
+
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(format!(
+                "Failed to set the default mail app (OSStatus {})",
+                status
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_message_file_from_url() {
+        let url = Url::parse("file:///tmp/Invoice%20March.EML").unwrap();
+        assert_eq!(
+            message_file_from_url(&url),
+            Some(PathBuf::from("/tmp/Invoice March.EML"))
+        );
+
+        let url = Url::parse("file:///tmp/notes.txt").unwrap();
+        assert_eq!(message_file_from_url(&url), None);
+
+        let url = Url::parse("mailto:ada@example.com").unwrap();
+        assert_eq!(message_file_from_url(&url), None);
+    }
+}
@@ -2,33 +2,59 @@
 
 use app_lib::{
     commands::attachment,
+    commands::automation,
+    commands::calendar,
     commands::config,
     commands::contacts,
     commands::conversation,
     commands::corvus,
+    commands::dkim,
     commands::emails,
+    commands::export,
+    commands::feature_flags,
     commands::folders,
+    commands::identities,
+    commands::import,
     commands::keybindings as keybindings_commands,
     commands::label,
     commands::licensing,
+    commands::mail_client,
+    commands::migrations,
     commands::navigation as nav_commands,
     commands::notification,
+    commands::rules,
+    commands::saved_searches,
     commands::search,
+    commands::security,
+    commands::session,
+    commands::signatures,
+    commands::smime,
     commands::sync,
+    commands::templates,
     commands::themes,
     commands::view,
     config::ConfigWatcher,
     config::KeyBindings,
     config::KeyBindingsWatcher,
     config::Settings,
+    database::migrations::{MigrationReporter, MigrationRunner},
     database::Database,
     licensing::{LicenseManager, LicenseRefreshRunner},
+    search::reindex::SearchReindexer,
     search::SearchManager,
+    services::app_lock::{self, AppLock},
+    services::automation_api::AutomationApi,
+    services::automation_triggers::AutomationTriggerDispatcher,
     services::avatar_service::AvatarService,
     services::corvus::CorvusService,
+    services::feature_flags::FeatureFlags,
+    services::theme_scheduler::ThemeScheduler,
     sync::{
-        BackgroundAiAnalyzer, BackgroundAvatarFetcher, BackgroundBodyFetcher, BackgroundCleanup,
-        BackgroundReminderNotifier, BackgroundSyncManager, OAuthStateManager, OperationQueue,
+        BackgroundAiAnalyzer, BackgroundArchiveWorker, BackgroundAttachmentIndexer,
+        BackgroundAvatarFetcher, BackgroundBodyFetcher, BackgroundCleanup,
+        BackgroundContactDateNotifier, BackgroundReminderNotifier, BackgroundSnoozeWorker,
+        BackgroundStyleLearner, BackgroundSyncManager, GraphSubscriptionManager, OAuthStateManager,
+        OperationQueue, OutboxWorker, ScheduledSendWorker,
     },
     AppState,
 };
@@ -96,6 +122,14 @@ fn create_menu(app: &tauri::App) -> Result<Menu<tauri::Wry>, tauri::Error> {
     }
 
     let file_menu = Submenu::new(app, "File", true)?;
+    file_menu.append(&MenuItem::with_id(
+        app,
+        "new_compose_window",
+        "New Message",
+        true,
+        Some("CmdOrCtrl+N"),
+    )?)?;
+    file_menu.append(&PredefinedMenuItem::separator(app)?)?;
 
     #[cfg(not(target_os = "macos"))]
     {
@@ -182,6 +216,11 @@ fn create_menu(app: &tauri::App) -> Result<Menu<tauri::Wry>, tauri::Error> {
 }
 
 fn main() {
+    // Text extraction of PDF attachments runs in a child process of the app
+    if std::env::args().nth(1).as_deref() == Some(app_lib::search::attachment_text::PDF_TEXT_ARG) {
+        std::process::exit(app_lib::search::attachment_text::run_pdf_text_command());
+    }
+
     if cfg!(debug_assertions) {
         env_logger::init();
     }
@@ -203,32 +242,61 @@ fn main() {
         // NOTE: #[cfg] cannot annotate individual method-chain calls in Rust, so
         // we always register the handler and gate the macOS-specific logic inside.
         .on_window_event(|window, event| {
-            #[cfg(target_os = "macos")]
-            {
-                if window.label() != "main" {
-                    return;
+            // Remember where the windows are for the next start
+            match event {
+                WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+                    app_lib::session::record_geometry(window, false)
+                }
+                WindowEvent::CloseRequested { .. } => {
+                    app_lib::session::record_geometry(window, true)
                 }
+                _ => {}
+            }
+
+            #[cfg(target_os = "macos")]
+            if window.label() == "main" {
                 if let WindowEvent::CloseRequested { api, .. } = event {
                     // Prevent the default behaviour (which would quit the app when
                     // this is the last window) and hide instead.
                     api.prevent_close();
                     let _ = window.hide();
+                    return;
+                }
+            }
+
+            // A message or compose window closed by the user is not reopened on
+            // the next start
+            if let WindowEvent::CloseRequested { .. } = event {
+                if let Some(state) = window.try_state::<AppState>() {
+                    app_lib::session::forget_closed_window(window.label(), state.db_pool.clone());
+                }
+            }
+
+            // Follow the OS light/dark appearance when the theme mode is "system"
+            if let WindowEvent::ThemeChanged(theme) = event {
+                if let Some(state) = window.try_state::<AppState>() {
+                    state.theme_scheduler.set_system_appearance((*theme).into());
                 }
             }
-            // Suppress unused-variable warnings on non-macOS targets.
-            #[cfg(not(target_os = "macos"))]
-            let _ = (window, event);
         });
 
     #[cfg(any(target_os = "linux", target_os = "macos", windows))]
-    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _, _| {
-        app_lib::navigation::reveal_main_window(app);
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+        let message_files =
+            app_lib::mail_client::message_files_from_args(argv, std::path::Path::new(&cwd));
+        if message_files.is_empty() {
+            app_lib::navigation::reveal_main_window(app);
+        }
+        for path in message_files {
+            app_lib::mail_client::open_message_file(app, path);
+        }
     }));
 
     builder
         .setup(|app| {
             let app_handle = app.handle().clone();
             app_handle.manage(app_lib::navigation::NavigationDispatchState::default());
+            app_handle.manage(app_lib::session::GeometryRecorder::default());
 
             let app_data_dir = app_handle
                 .path()
@@ -252,8 +320,12 @@ fn main() {
             let _watcher = ConfigWatcher::new(Arc::clone(&settings))
                 .expect("Failed to initialize configuration watcher");
 
+            app_lib::sync::sync_limits::configure(&settings);
+
             // Initialize keybindings with optional default mapping from settings
-            let default_mapping = settings.get::<String>("keyboard.defaultMapping").ok();
+            let default_mapping = settings
+                .get::<String>(app_lib::config::keybindings::DEFAULT_MAPPING_SETTING)
+                .ok();
             let keybindings = match KeyBindings::new(&resources_dir, &app_data_dir, default_mapping)
             {
                 Ok(kb) => {
@@ -292,8 +364,12 @@ fn main() {
                     }
                 };
 
+            let migration_runner = Arc::new(MigrationRunner::new(
+                MigrationReporter::new().with_app_handle(app_handle.clone()),
+            ));
+
             let db = tauri::async_runtime::block_on(async {
-                Database::new(&app_data_dir)
+                Database::new(&app_data_dir, &migration_runner)
                     .await
                     .expect("Failed to initialize database")
             });
@@ -305,14 +381,18 @@ fn main() {
                 )
                 .with_app_handle(app_handle.clone()),
             );
+            notification_service.watch_email_changes();
 
             let oauth_state_manager = Arc::new(OAuthStateManager::new());
 
             let app_data_dir_str = app_data_dir.to_string_lossy().to_string();
-            let credential_store = Arc::new(app_lib::sync::auth::CredentialStore::new(
-                Some(db.get_pool().clone()),
-                Some(app_data_dir_str.clone()),
-            ));
+            let credential_store = Arc::new(
+                app_lib::sync::auth::CredentialStore::new(
+                    Some(db.get_pool().clone()),
+                    Some(app_data_dir_str.clone()),
+                )
+                .with_settings(Arc::clone(&settings)),
+            );
 
             let background_sync_manager = Arc::new(BackgroundSyncManager::new(
                 db.get_pool().clone(),
@@ -326,6 +406,7 @@ fn main() {
                 db.get_pool().clone(),
                 app_data_dir_str.clone(),
                 Arc::clone(&credential_store),
+                Arc::clone(&settings),
             ));
 
             // Initialize licensing system
@@ -369,12 +450,20 @@ fn main() {
             let ai_service = Arc::new(CorvusService::new(
                 Arc::clone(&settings),
                 Arc::clone(&license_manager),
+                db.get_pool().clone(),
             ));
 
             let background_ai_analyzer = Arc::new(BackgroundAiAnalyzer::new(
                 db.get_pool().clone(),
                 app_handle.clone(),
                 Arc::clone(&ai_service),
+                Arc::clone(&settings),
+            ));
+
+            let background_style_learner = Arc::new(BackgroundStyleLearner::new(
+                db.get_pool().clone(),
+                Arc::clone(&ai_service),
+                Arc::clone(&settings),
             ));
 
             let avatar_providers = settings.get::<Vec<String>>("contacts.avatar.services").ok();
@@ -387,18 +476,81 @@ fn main() {
             let background_cleanup = Arc::new(BackgroundCleanup::new(
                 db.get_pool().clone(),
                 app_data_dir_str.clone(),
+                Arc::clone(&settings),
             ));
 
             let search_index_dir = app_data_dir.join("search_index");
             let search_manager = Arc::new(
-                SearchManager::new(search_index_dir).expect("Failed to initialize search manager"),
+                SearchManager::new(&search_index_dir).expect("Failed to initialize search manager"),
             );
+            let needs_search_reindex = search_manager.needs_reindex();
+            let search_reindexer = Arc::new(
+                SearchReindexer::new(
+                    db.get_pool().clone(),
+                    Arc::clone(&search_manager),
+                    &search_index_dir,
+                )
+                .with_app_handle(app_handle.clone()),
+            );
+
+            let background_attachment_indexer = Arc::new(BackgroundAttachmentIndexer::new(
+                db.get_pool().clone(),
+                app_data_dir_str.clone(),
+                Arc::clone(&search_manager),
+            ));
 
             let background_reminder_notifier = Arc::new(BackgroundReminderNotifier::new(
                 db.get_pool().clone(),
                 Arc::clone(&notification_service),
             ));
 
+            let background_snooze_worker = Arc::new(BackgroundSnoozeWorker::new(
+                db.get_pool().clone(),
+                Arc::clone(&notification_service),
+                app_handle.clone(),
+            ));
+
+            let scheduled_send_worker = Arc::new(ScheduledSendWorker::new(
+                db.get_pool().clone(),
+                app_handle.clone(),
+            ));
+
+            let outbox_worker =
+                Arc::new(OutboxWorker::new(db.get_pool().clone(), app_handle.clone()));
+
+            let background_contact_date_notifier = Arc::new(BackgroundContactDateNotifier::new(
+                db.get_pool().clone(),
+                Arc::clone(&credential_store),
+                Arc::clone(&notification_service),
+                Arc::clone(&settings),
+            ));
+
+            let theme_scheduler = Arc::new(ThemeScheduler::new(
+                Arc::clone(&settings),
+                app_handle.clone(),
+            ));
+            if let Some(theme) = app
+                .get_webview_window("main")
+                .and_then(|window| window.theme().ok())
+            {
+                theme_scheduler.set_system_appearance(theme.into());
+            }
+
+            let app_lock = Arc::new(AppLock::new(
+                Arc::clone(&settings),
+                app_handle.clone(),
+                &app_handle.path().app_data_dir().unwrap(),
+            ));
+
+            let automation_api = Arc::new(AutomationApi::new(
+                app_handle.clone(),
+                Arc::clone(&settings),
+                &app_handle.path().app_data_dir().unwrap(),
+            ));
+
+            let automation_trigger_dispatcher =
+                Arc::new(AutomationTriggerDispatcher::new(db.get_pool().clone()));
+
             let sync_coordinator = Arc::new(
                 app_lib::sync::SyncCoordinator::new(
                     db.get_pool().clone(),
@@ -410,6 +562,18 @@ fn main() {
                 .with_notification_service(Arc::clone(&notification_service)),
             );
 
+            let background_archive_worker = Arc::new(BackgroundArchiveWorker::new(
+                db.get_pool().clone(),
+                Arc::clone(&sync_coordinator),
+            ));
+
+            let graph_subscription_manager = Arc::new(GraphSubscriptionManager::new(
+                db.get_pool().clone(),
+                Arc::clone(&credential_store),
+                Arc::clone(&sync_coordinator),
+                Arc::clone(&settings),
+            ));
+
             // Create the operation queue to process pending operations (delete, mark read, flag, move)
             let op_queue =
                 OperationQueue::new(db.get_pool().clone(), Arc::clone(&credential_store))
@@ -428,12 +592,30 @@ fn main() {
                 background_avatar_fetcher: Arc::clone(&background_avatar_fetcher),
                 background_cleanup: Arc::clone(&background_cleanup),
                 background_reminder_notifier: Arc::clone(&background_reminder_notifier),
+                background_contact_date_notifier: Arc::clone(&background_contact_date_notifier),
+                background_snooze_worker: Arc::clone(&background_snooze_worker),
+                background_archive_worker: Arc::clone(&background_archive_worker),
+                background_attachment_indexer: Arc::clone(&background_attachment_indexer),
+                background_style_learner: Arc::clone(&background_style_learner),
+                scheduled_send_worker: Arc::clone(&scheduled_send_worker),
+                outbox_worker: Arc::clone(&outbox_worker),
                 sync_coordinator,
+                graph_subscription_manager: Arc::clone(&graph_subscription_manager),
                 credential_store,
                 search_manager,
+                search_reindexer: Arc::clone(&search_reindexer),
                 notification_service: Arc::clone(&notification_service),
                 license_manager: Arc::clone(&license_manager),
                 license_refresh_runner: Arc::clone(&license_refresh_runner),
+                feature_flags: Arc::new(FeatureFlags::new(
+                    Arc::clone(&settings),
+                    Arc::clone(&license_manager),
+                )),
+                theme_scheduler: Arc::clone(&theme_scheduler),
+                app_lock: Arc::clone(&app_lock),
+                automation_api: Arc::clone(&automation_api),
+                automation_trigger_dispatcher: Arc::clone(&automation_trigger_dispatcher),
+                migration_reporter: migration_runner.reporter().clone(),
                 app_handle: app_handle.clone(),
                 download_dir: app_handle.path().download_dir().unwrap(),
                 app_data_dir: app_handle.path().app_data_dir().unwrap(),
@@ -449,19 +631,32 @@ fn main() {
 
             #[cfg(any(target_os = "linux", target_os = "macos", windows))]
             {
+                // macOS hands opened message files over as file:// URLs
+                fn dispatch_opened_url(app: &tauri::AppHandle, url: url::Url) {
+                    match app_lib::mail_client::message_file_from_url(&url) {
+                        Some(path) => app_lib::mail_client::open_message_file(app, path),
+                        None => app_lib::navigation::dispatch_navigation_url(app, url.to_string()),
+                    }
+                }
+
                 let deep_link_app = app_handle.clone();
                 app_handle.deep_link().on_open_url(move |event| {
                     for url in event.urls() {
-                        app_lib::navigation::dispatch_navigation_url(
-                            &deep_link_app,
-                            url.to_string(),
-                        );
+                        dispatch_opened_url(&deep_link_app, url);
                     }
                 });
 
                 if let Ok(Some(urls)) = app_handle.deep_link().get_current() {
                     for url in urls {
-                        app_lib::navigation::dispatch_navigation_url(&app_handle, url.to_string());
+                        dispatch_opened_url(&app_handle, url);
+                    }
+                }
+
+                if let Ok(cwd) = std::env::current_dir() {
+                    for path in
+                        app_lib::mail_client::message_files_from_args(std::env::args(), &cwd)
+                    {
+                        app_lib::mail_client::open_message_file(&app_handle, path);
                     }
                 }
 
@@ -492,6 +687,19 @@ fn main() {
                 });
             }
 
+            // Put windows, geometry and the last route back where the user left them
+            tauri::async_runtime::block_on(app_lib::session::restore_session(
+                &app_handle,
+                db.get_pool().clone(),
+            ));
+
+            let migration_pool = db.get_pool().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = migration_runner.run_data(&migration_pool).await {
+                    log::error!("Data migration failed, resuming on next start: {}", e);
+                }
+            });
+
             let sync_manager_clone = Arc::clone(&background_sync_manager);
             tauri::async_runtime::spawn(async move {
                 match sync_manager_clone.start_all().await {
@@ -552,6 +760,147 @@ fn main() {
                 }
             });
 
+            tauri::async_runtime::spawn(async move {
+                match background_style_learner.start().await {
+                    Ok(_) => {
+                        log::info!("Background style learner started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start background style learner: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match background_snooze_worker.start().await {
+                    Ok(_) => {
+                        log::info!("Background snooze worker started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start background snooze worker: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match background_archive_worker.start().await {
+                    Ok(_) => {
+                        log::info!("Background archive worker started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start background archive worker: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match background_attachment_indexer.start().await {
+                    Ok(_) => {
+                        log::info!("Background attachment indexer started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start background attachment indexer: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match scheduled_send_worker.start().await {
+                    Ok(_) => {
+                        log::info!("Scheduled send worker started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start scheduled send worker: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match outbox_worker.start().await {
+                    Ok(_) => {
+                        log::info!("Outbox worker started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start outbox worker: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match background_contact_date_notifier.start().await {
+                    Ok(_) => {
+                        log::info!("Contact date notifier started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start contact date notifier: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match automation_api.start().await {
+                    Ok(_) => {
+                        log::info!("Automation API started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start automation API: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match automation_trigger_dispatcher.start().await {
+                    Ok(_) => {
+                        log::info!("Automation trigger dispatcher started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start automation trigger dispatcher: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match theme_scheduler.start().await {
+                    Ok(_) => {
+                        log::info!("Theme scheduler started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start theme scheduler: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match app_lock.start().await {
+                    Ok(_) => {
+                        log::info!("App lock started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start app lock: {}", e);
+                    }
+                }
+            });
+
+            tauri::async_runtime::spawn(async move {
+                match graph_subscription_manager.start().await {
+                    Ok(_) => {
+                        log::info!("Graph subscription manager started successfully");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start Graph subscription manager: {}", e);
+                    }
+                }
+            });
+
+            // The index was recreated for a new schema, fill it from the database;
+            // a reindex interrupted by quitting continues where it stopped
+            if needs_search_reindex {
+                search_reindexer.start(false);
+            } else if search_reindexer.is_interrupted() {
+                log::info!("[Boot] Resuming interrupted search reindex");
+                search_reindexer.start(true);
+            }
+
             // Start the operation queue background processor
             op_queue.start();
 
@@ -575,10 +924,38 @@ fn main() {
                     // ── macOS: Cmd+W hides the window instead of closing it ──
                     #[cfg(target_os = "macos")]
                     "hide_main_window" => {
-                        if let Some(window) = app.get_webview_window("main") {
+                        // Message and compose windows are closed rather than hidden
+                        let detached = app.webview_windows().into_values().find(|window| {
+                            app_lib::session::is_detached_window(window.label())
+                                && window.is_focused().unwrap_or(false)
+                        });
+                        if let Some(window) = detached {
+                            let _ = window.close();
+                        } else if let Some(window) = app.get_webview_window("main") {
                             let _ = window.hide();
                         }
                     }
+                    "new_compose_window" => {
+                        let Some(state) = app.try_state::<AppState>() else {
+                            return;
+                        };
+                        let app = app.clone();
+                        let pool = state.db_pool.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(error) = app_lib::session::open_detached_window(
+                                &app,
+                                pool,
+                                &app_lib::session::compose_window_label(),
+                                app_lib::database::models::session::SessionWindowType::Compose,
+                                view::ComposeWindowRequest::default().route(),
+                                "New Message",
+                            )
+                            .await
+                            {
+                                log::error!("[Menu] Failed to open compose window: {}", error);
+                            }
+                        });
+                    }
                     #[cfg(debug_assertions)]
                     "toggle_devtools" => {
                         if let Some(window) = app.get_webview_window("main") {
@@ -595,15 +972,22 @@ fn main() {
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(app_lock::guard(tauri::generate_handler![
             corvus::generate_email_completion,
             corvus::ask_ai,
             corvus::generate_search_query,
             corvus::generate_subject,
             corvus::analyze_email_with_ai,
+            corvus::summarize_conversation,
+            corvus::ask_mailbox,
             corvus::get_available_models,
             corvus::get_writing_style,
             corvus::set_writing_style,
+            corvus::get_writing_style_profile,
+            corvus::learn_writing_style,
+            corvus::delete_writing_style_profile,
+            corvus::get_ai_audit_log,
+            corvus::purge_ai_audit_log,
             licensing::license_activate,
             licensing::license_trial,
             licensing::license_status,
@@ -622,31 +1006,72 @@ fn main() {
             keybindings_commands::set_keybinding,
             keybindings_commands::remove_keybinding,
             keybindings_commands::reload_keybindings,
+            keybindings_commands::get_bindings,
+            keybindings_commands::set_binding,
+            keybindings_commands::reset_to_default,
+            keybindings_commands::set_keymap_scheme,
             nav_commands::navigate_to_url,
             nav_commands::build_ravn_url,
             nav_commands::open_external_url,
             nav_commands::navigation_frontend_ready,
+            nav_commands::get_actions,
+            mail_client::is_default_mail_client,
+            mail_client::set_default_mail_client,
+            mail_client::read_message_file,
             emails::send_email,
             emails::test_smtp_connection,
             emails::send_email_from_account,
+            emails::prepare_reply,
+            emails::get_delivery_status,
+            emails::get_outbox,
+            emails::retry_send,
+            emails::discard_outbox_item,
+            emails::apply_template,
             emails::save_draft,
             emails::get_accounts_for_sending,
             emails::get_drafts,
             emails::delete_draft,
             emails::get_emails,
             emails::get_emails_for_folders,
+            emails::get_email_window,
+            emails::get_unified_inbox,
+            emails::get_priority_inbox,
             emails::get_emails_for_labels,
             emails::set_remind_at,
+            emails::snooze_email,
+            emails::unsnooze_email,
+            emails::get_snoozed_emails,
+            emails::get_recently_deleted,
+            emails::restore_deleted_email,
+            emails::purge_deleted_emails,
+            emails::schedule_send,
+            emails::cancel_scheduled_send,
             emails::get_emails_for_calendar,
+            calendar::get_calendar_events,
+            calendar::respond_to_invite,
+            calendar::export_event_ics,
+            calendar::open_in_system_calendar,
             emails::update_read,
             emails::email_parse_body_plain,
             emails::move_email,
             emails::archive,
             emails::junk,
+            emails::not_junk,
+            emails::set_category,
+            emails::get_sender_categories,
+            emails::remove_sender_category,
+            emails::block_sender,
+            emails::unblock_sender,
+            emails::get_blocked_senders,
             emails::trash,
             emails::delete,
             emails::fetch_body,
             emails::update_blocking,
+            emails::load_remote_content,
+            emails::always_load_images_from,
+            emails::get_image_allowlist,
+            emails::remove_from_image_allowlist,
+            emails::validate_recipients,
             emails::empty_folder,
             folders::get_folder_navigation,
             folders::get_folder,
@@ -654,34 +1079,58 @@ fn main() {
             folders::init_folder_sync,
             folders::update_expanded,
             folders::update_hidden,
+            folders::set_sync_enabled,
             folders::move_folder,
             folders::rename,
             folders::update_settings,
+            folders::preview_auto_archive,
             sync::start_oauth2_flow,
             sync::open_oauth_window,
             sync::close_oauth_window,
             sync::exchange_oauth2_code,
+            sync::reauthorize_account,
+            sync::discover_account_settings,
             sync::store_imap_credentials,
             sync::sync_account,
             sync::sync_folder,
             sync::open_add_account_window,
             sync::create_account,
             sync::get_accounts,
+            sync::refresh_account_profile,
+            sync::get_account_quota,
+            sync::set_imap_delete_policy,
+            sync::set_account_color,
+            sync::set_account_avatar,
+            sync::trust_server_certificate,
+            sync::set_sync_history,
             sync::delete_account,
             sync::start_background_sync,
             sync::stop_background_sync,
             sync::get_sync_status,
             sync::get_sync_health,
+            sync::get_account_health,
+            sync::pause_account,
+            sync::resume_account,
             sync::is_account_syncing,
             contacts::search_contacts,
+            contacts::autocomplete_recipients,
             contacts::get_top_contacts,
             contacts::get_contacts,
             contacts::get_contact_by_id,
             contacts::get_contact_by_email,
             contacts::create_contact,
             contacts::update_contact,
+            contacts::set_contact_vip,
             contacts::delete_contact,
             contacts::resync_contact_counters,
+            contacts::get_upcoming_contact_dates,
+            contacts::sync_contact_dates,
+            contacts::get_contact_security,
+            contacts::get_recipients_security,
+            contacts::set_contact_encryption_policy,
+            contacts::discover_contact_pgp_key,
+            contacts::import_contact_pgp_key,
+            contacts::remove_contact_pgp_key,
             attachment::get_email_attachments,
             attachment::open_attachment,
             attachment::quicklook_attachment,
@@ -689,6 +1138,9 @@ fn main() {
             attachment::get_downloads_path,
             attachment::read_attachment_for_forward,
             attachment::recalculate_attachment_hashes,
+            attachment::download_attachment,
+            attachment::cancel_download,
+            attachment::get_attachment_cache_usage,
             label::get_labels,
             label::get_label,
             label::get_email_labels,
@@ -697,28 +1149,99 @@ fn main() {
             label::delete_label,
             label::add_label_to_email,
             label::remove_label_from_email,
+            rules::get_rules,
+            rules::create_rule,
+            rules::update_rule,
+            rules::delete_rule,
+            rules::run_rules_on_existing,
+            smime::import_smime_certificate,
+            smime::get_smime_certificates,
+            smime::delete_smime_certificate,
+            smime::get_email_smime_status,
+            identities::get_identities,
+            identities::create_identity,
+            identities::update_identity,
+            identities::delete_identity,
+            identities::refresh_identities,
+            dkim::get_dkim_keys,
+            dkim::generate_dkim_key,
+            dkim::import_dkim_key,
+            dkim::delete_dkim_key,
+            signatures::get_signatures,
+            signatures::create_signature,
+            signatures::update_signature,
+            signatures::delete_signature,
+            templates::get_templates,
+            templates::create_template,
+            templates::update_template,
+            templates::delete_template,
+            saved_searches::get_saved_searches,
+            saved_searches::create_saved_search,
+            saved_searches::update_saved_search,
+            saved_searches::delete_saved_search,
             view::get_views,
             view::get_view,
             view::create_view,
             view::update_view,
             view::delete_view,
+            view::open_compose_window,
+            view::open_email_window,
             conversation::get_conversations_for_folder,
             conversation::get_conversations_for_label,
             conversation::get_conversations_for_scope,
             conversation::get_conversation_for_message_id,
+            conversation::mute_conversation,
+            conversation::unmute_conversation,
             conversation::get_conversation_by_id,
+            conversation::export_html,
+            import::import_mbox,
+            import::import_eml,
+            export::export_emails,
+            export::export_pdf,
             search::search_emails,
+            search::search_all_profiles,
             search::reindex_all_emails,
             search::reindex_account_emails,
+            search::get_index_status,
             notification::update_badge_count,
             notification::get_badge_count,
+            notification::get_unread_counts,
             notification::test_notification_sound,
             notification::get_due_reminder_notifications,
             themes::list_themes,
             themes::get_theme,
             themes::switch_theme,
             themes::get_current_theme,
-        ])
+            themes::get_theme_mode,
+            themes::set_theme_mode,
+            themes::create_theme,
+            themes::get_custom_theme,
+            themes::update_theme,
+            themes::delete_theme,
+            themes::import_theme,
+            themes::export_theme,
+            automation::get_automation_api_status,
+            automation::regenerate_automation_api_token,
+            automation::get_automation_triggers,
+            automation::save_automation_trigger,
+            automation::delete_automation_trigger,
+            automation::get_automation_trigger_deliveries,
+            automation::test_automation_trigger,
+            session::save_session_window,
+            session::remove_session_window,
+            session::get_session,
+            sync::get_network_usage,
+            search::export_results,
+            feature_flags::get_feature_flags,
+            feature_flags::set_feature_flag,
+            migrations::get_migration_status,
+            security::get_lock_status,
+            security::lock,
+            security::unlock,
+            security::unlock_with_biometrics,
+            security::set_lock_passphrase,
+            security::record_activity,
+        ]))
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         // ── macOS run-loop events ─────────────────────────────────────────────
@@ -6,6 +6,7 @@ use std::sync::{
 };
 use tauri::{AppHandle, Emitter, Manager, Runtime};
 use url::{form_urlencoded::Serializer, Url};
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NavigationUrl {
@@ -14,6 +15,17 @@ pub struct NavigationUrl {
     pub query: Option<String>,
 }
 
+/// Entity-level targets that need resolving against local data before the
+/// frontend can route to them. Everything else maps 1:1 onto a router path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NavigationRoute {
+    Email(Uuid),
+    Conversation(Uuid),
+    Search(String),
+    Contact(Uuid),
+    Path,
+}
+
 #[derive(Debug, Default)]
 pub struct NavigationDispatchState {
     frontend_ready: AtomicBool,
@@ -106,6 +118,93 @@ impl NavigationUrl {
         }
     }
 
+    pub fn route(&self) -> NavigationRoute {
+        if self.scheme != "ravn" {
+            return NavigationRoute::Path;
+        }
+
+        let segments: Vec<&str> = self
+            .path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        match segments.as_slice() {
+            ["email", id] => Uuid::parse_str(id)
+                .map(NavigationRoute::Email)
+                .unwrap_or(NavigationRoute::Path),
+            ["conversation", id] => Uuid::parse_str(id)
+                .map(NavigationRoute::Conversation)
+                .unwrap_or(NavigationRoute::Path),
+            ["contact", id] => Uuid::parse_str(id)
+                .map(NavigationRoute::Contact)
+                .unwrap_or(NavigationRoute::Path),
+            ["search"] => NavigationRoute::Search(self.query_param("q").unwrap_or_default()),
+            _ => NavigationRoute::Path,
+        }
+    }
+
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        let query = self.query.as_deref()?;
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    pub fn email(email_id: Uuid) -> String {
+        Self::build(&format!("email/{}", email_id), None)
+    }
+
+    /// Opens the email with a reply to it in the composer
+    pub fn reply(email_id: Uuid) -> String {
+        Self::build(&format!("email/{}", email_id), Some("reply=1"))
+    }
+
+    pub fn conversation(conversation_id: Uuid) -> String {
+        Self::build(&format!("conversation/{}", conversation_id), None)
+    }
+
+    pub fn contact(contact_id: Uuid) -> String {
+        Self::build(&format!("contact/{}", contact_id), None)
+    }
+
+    /// Opens the composer addressed to a single recipient
+    pub fn compose(to: &str, subject: Option<&str>) -> String {
+        let mut query = Serializer::new(String::new());
+        query.append_pair("to", to);
+        if let Some(subject) = subject {
+            query.append_pair("subject", subject);
+        }
+        Self::build("compose", Some(&query.finish()))
+    }
+
+    pub fn search(query: &str) -> String {
+        let query = Serializer::new(String::new())
+            .append_pair("q", query)
+            .finish();
+        Self::build("search", Some(&query))
+    }
+
+    /// Router path of an email inside its folder, opened in its conversation if it has one
+    pub fn email_router_path(
+        account_id: Uuid,
+        folder_id: Uuid,
+        conversation_id: Option<&str>,
+        email_id: Uuid,
+    ) -> String {
+        match conversation_id {
+            Some(conversation_id) => format!(
+                "/mail/{}/folders/{}/conversations/{}?email={}",
+                account_id, folder_id, conversation_id, email_id
+            ),
+            None => format!(
+                "/mail/{}/folders/{}/emails/{}",
+                account_id, folder_id, email_id
+            ),
+        }
+    }
+
     pub fn build(path: &str, query: Option<&str>) -> String {
         let clean_path = path.trim_start_matches('/');
         if let Some(q) = query {
@@ -336,6 +435,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_entity_routes() {
+        let email_id = Uuid::now_v7();
+        let url = NavigationUrl::parse(&NavigationUrl::email(email_id)).unwrap();
+        assert_eq!(url.route(), NavigationRoute::Email(email_id));
+
+        let url = NavigationUrl::parse(&NavigationUrl::reply(email_id)).unwrap();
+        assert_eq!(url.route(), NavigationRoute::Email(email_id));
+        assert_eq!(url.query_param("reply").as_deref(), Some("1"));
+
+        let contact_id = Uuid::now_v7();
+        let url = NavigationUrl::parse(&NavigationUrl::contact(contact_id)).unwrap();
+        assert_eq!(url.route(), NavigationRoute::Contact(contact_id));
+
+        let url = NavigationUrl::parse("ravn://email/not-a-uuid").unwrap();
+        assert_eq!(url.route(), NavigationRoute::Path);
+
+        let url = NavigationUrl::parse("ravn://settings/ai").unwrap();
+        assert_eq!(url.route(), NavigationRoute::Path);
+    }
+
+    #[test]
+    fn builds_and_parses_search_url() {
+        let built = NavigationUrl::search("from:alice subject:Q&A");
+        assert_eq!(built, "ravn://search?q=from%3Aalice+subject%3AQ%26A");
+
+        let url = NavigationUrl::parse(&built).unwrap();
+        assert_eq!(
+            url.route(),
+            NavigationRoute::Search("from:alice subject:Q&A".to_string())
+        );
+        assert_eq!(
+            url.to_router_path(),
+            "/search?q=from%3Aalice+subject%3AQ%26A"
+        );
+    }
+
+    #[test]
+    fn builds_compose_url() {
+        let built = NavigationUrl::compose("ada@example.com", Some("Happy birthday!"));
+        assert_eq!(
+            built,
+            "ravn://compose?to=ada%40example.com&subject=Happy+birthday%21"
+        );
+
+        let url = NavigationUrl::parse(&built).unwrap();
+        assert_eq!(url.route(), NavigationRoute::Path);
+        assert_eq!(url.query_param("to").as_deref(), Some("ada@example.com"));
+    }
+
     #[test]
     fn builds_ravn_url() {
         let url = NavigationUrl::build("settings/ai", None);
@@ -0,0 +1,314 @@
+//! Text of PDF, DOCX, XLSX and plain-text attachments, indexed so searches
+//! also match what is only written in an attachment.
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Cursor, Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::database::models::attachment::Attachment;
+
+/// Attachment categories (see `FILE_TYPES`) text may be extracted from
+pub const EXTRACTABLE_FILE_TYPES: &[&str] = &["pdf", "document", "spreadsheet", "text"];
+
+/// Larger attachments are not read
+pub const MAX_ATTACHMENT_SIZE: i64 = 25 * 1024 * 1024;
+
+/// Text beyond this many bytes is not indexed
+const MAX_TEXT_LEN: usize = 1024 * 1024;
+
+const WORDPROCESSING_ML: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml";
+const SPREADSHEET_ML: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml";
+
+/// Argument that makes the app binary extract the text of a PDF read from
+/// stdin instead of starting, see `run_pdf_text_command`
+pub const PDF_TEXT_ARG: &str = "--extract-pdf-text";
+
+/// PDFs whose text takes longer than this are skipped
+const PDF_TEXT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Searchable text of an attachment, `None` for unsupported formats,
+/// unreadable files and files without text
+pub fn extract_text(attachment: &Attachment, data: &[u8]) -> Option<String> {
+    let content_type = attachment.content_type.to_ascii_lowercase();
+    let filename = attachment.filename.to_ascii_lowercase();
+    let extension = filename.rsplit_once('.').map_or("", |(_, ext)| ext);
+
+    let text = match attachment.file_type() {
+        "pdf" => pdf_text_in_subprocess(data)?,
+        "document" if content_type.starts_with(WORDPROCESSING_ML) || extension == "docx" => {
+            office_text(data, |part| part == "word/document.xml", b"p")?
+        }
+        "spreadsheet" if content_type.starts_with(SPREADSHEET_ML) || extension == "xlsx" => {
+            // Cell strings live in the shared strings table; inline strings
+            // are kept in the sheets themselves
+            office_text(
+                data,
+                |part| {
+                    part == "xl/sharedStrings.xml"
+                        || (part.starts_with("xl/worksheets/") && part.ends_with(".xml"))
+                },
+                b"si",
+            )?
+        }
+        "spreadsheet" if content_type == "text/csv" || extension == "csv" => {
+            String::from_utf8_lossy(data).into_owned()
+        }
+        "text" => String::from_utf8_lossy(data).into_owned(),
+        _ => return None,
+    };
+
+    normalize(&text)
+}
+
+/// Text of a PDF, extracted by a child process of the app binary. The PDF
+/// parser panics on some malformed files, which with `panic = "abort"` would
+/// otherwise take the whole app down.
+fn pdf_text_in_subprocess(data: &[u8]) -> Option<String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| log::warn!("[AttachmentText] No executable path: {}", e))
+        .ok()?;
+    let mut child = Command::new(exe)
+        .arg(PDF_TEXT_ARG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| log::warn!("[AttachmentText] Failed to start PDF extraction: {}", e))
+        .ok()?;
+
+    // Written and read on threads, so neither pipe can fill up and block
+    let mut stdin = child.stdin.take()?;
+    let input = data.to_vec();
+    let writer = std::thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+    let mut stdout = child.stdout.take()?;
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let deadline = Instant::now() + PDF_TEXT_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            Ok(None) | Err(_) => {
+                log::warn!("[AttachmentText] PDF extraction timed out");
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+    let _ = writer.join();
+    let output = reader.join().ok()?.ok()?;
+
+    if !status.success() {
+        log::warn!("[AttachmentText] PDF extraction failed: {}", status);
+        return None;
+    }
+    String::from_utf8(output).ok()
+}
+
+/// Entry point of the `PDF_TEXT_ARG` child process: reads a PDF from stdin
+/// and writes its text to stdout. Returns the process exit code.
+pub fn run_pdf_text_command() -> i32 {
+    let mut data = Vec::new();
+    if std::io::stdin().read_to_end(&mut data).is_err() {
+        return 1;
+    }
+
+    match pdf_extract::extract_text_from_mem(&data) {
+        Ok(text) if std::io::stdout().write_all(text.as_bytes()).is_ok() => 0,
+        _ => 1,
+    }
+}
+
+/// Collapse whitespace and cut the text to `MAX_TEXT_LEN`
+fn normalize(text: &str) -> Option<String> {
+    let mut normalized = String::new();
+
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            continue;
+        }
+        if normalized.len() + line.len() > MAX_TEXT_LEN {
+            break;
+        }
+        normalized.push_str(&line);
+        normalized.push('\n');
+    }
+
+    let normalized = normalized.trim_end().to_string();
+    (!normalized.is_empty()).then_some(normalized)
+}
+
+/// Text runs (`<t>` elements) of the matching parts of an Office Open XML
+/// package, with a line break after each `line_element`
+fn office_text(
+    data: &[u8],
+    is_text_part: impl Fn(&str) -> bool,
+    line_element: &[u8],
+) -> Option<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).ok()?;
+    let parts: Vec<String> = archive
+        .file_names()
+        .filter(|name| is_text_part(name))
+        .map(str::to_string)
+        .collect();
+
+    let mut text = String::new();
+    for part in parts {
+        let mut xml = String::new();
+        archive.by_name(&part).ok()?.read_to_string(&mut xml).ok()?;
+        collect_xml_text(&xml, line_element, &mut text);
+    }
+
+    Some(text)
+}
+
+fn collect_xml_text(xml: &str, line_element: &[u8], text: &mut String) {
+    let mut reader = Reader::from_str(xml);
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) if element.local_name().as_ref() == b"t" => in_text = true,
+            Ok(Event::End(element)) => {
+                let name = element.local_name();
+                if name.as_ref() == b"t" {
+                    in_text = false;
+                } else if name.as_ref() == line_element {
+                    text.push('\n');
+                }
+            }
+            Ok(Event::Text(content)) if in_text => {
+                if let Ok(content) = content.decode() {
+                    text.push_str(&content);
+                }
+            }
+            Ok(Event::GeneralRef(reference)) if in_text => {
+                if let Ok(Some(ch)) = reference.resolve_char_ref() {
+                    text.push(ch);
+                } else if let Some(entity) = reference
+                    .decode()
+                    .ok()
+                    .and_then(|name| resolve_predefined_entity(&name))
+                {
+                    text.push_str(entity);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::io::Write;
+    use uuid::Uuid;
+    use zip::write::SimpleFileOptions;
+
+    fn attachment(filename: &str, content_type: &str) -> Attachment {
+        Attachment {
+            id: Uuid::now_v7(),
+            email_id: Uuid::now_v7(),
+            filename: filename.to_string(),
+            content_type: content_type.to_string(),
+            size: 0,
+            hash: String::new(),
+            cache_path: None,
+            is_inline: false,
+            is_cached: true,
+            content_id: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn package(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in parts {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_extract_docx() {
+        let docx = package(&[(
+            "word/document.xml",
+            r#"<w:document xmlns:w="w"><w:body>
+                <w:p><w:r><w:t>Clause 7:</w:t></w:r><w:r><w:t xml:space="preserve"> termination &amp; notice</w:t></w:r></w:p>
+                <w:p><w:r><w:t>Signed</w:t></w:r></w:p>
+            </w:body></w:document>"#,
+        )]);
+
+        assert_eq!(
+            extract_text(
+                &attachment("Contract.DOCX", "application/octet-stream"),
+                &docx
+            ),
+            Some("Clause 7: termination & notice\nSigned".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_xlsx() {
+        let xlsx = package(&[
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Revenue</t></si><si><r><t>Q3 </t></r><r><t>total</t></r></si></sst>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetData><row><c t="inlineStr"><is><t>Notes</t></is></c><c><v>42</v></c></row></sheetData></worksheet>"#,
+            ),
+        ]);
+
+        let text = extract_text(
+            &attachment(
+                "report.xlsx",
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ),
+            &xlsx,
+        )
+        .unwrap();
+        assert!(text.contains("Revenue\nQ3 total"));
+        assert!(text.contains("Notes"));
+        assert!(!text.contains("42"));
+    }
+
+    #[test]
+    fn test_extract_plain_text() {
+        assert_eq!(
+            extract_text(
+                &attachment("notes.txt", "text/plain"),
+                b"  first   line \n\n second line"
+            ),
+            Some("first line\nsecond line".to_string())
+        );
+        assert_eq!(
+            extract_text(&attachment("notes.txt", "text/plain"), b" \n "),
+            None
+        );
+        assert_eq!(
+            extract_text(&attachment("photo.jpg", "image/jpeg"), b"text"),
+            None
+        );
+        // Legacy Word documents are binary
+        assert_eq!(
+            extract_text(&attachment("old.doc", "application/msword"), b"text"),
+            None
+        );
+    }
+}
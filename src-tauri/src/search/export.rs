@@ -0,0 +1,419 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::database::models::email::{Email, EmailAddress};
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Single mboxrd file
+    Mbox,
+    /// One .eml file per message inside the destination directory
+    Eml,
+    /// Single CSV file with one row per message
+    Csv,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportProgress {
+    pub export_id: String,
+    pub processed: usize,
+    pub total: usize,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub export_id: String,
+    pub exported: usize,
+    pub skipped: usize,
+    pub path: PathBuf,
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "message_id",
+    "received_at",
+    "sent_at",
+    "from",
+    "to",
+    "cc",
+    "bcc",
+    "subject",
+    "has_attachments",
+    "is_read",
+    "is_flagged",
+    "size",
+];
+
+/// Streams exported messages into a file or directory depending on the format
+pub struct ExportWriter {
+    format: ExportFormat,
+    metadata_only: bool,
+    destination: PathBuf,
+    file: Option<std::io::BufWriter<std::fs::File>>,
+}
+
+impl ExportWriter {
+    pub fn create(
+        format: ExportFormat,
+        metadata_only: bool,
+        destination: &Path,
+    ) -> std::io::Result<Self> {
+        let file = match format {
+            ExportFormat::Eml => {
+                std::fs::create_dir_all(destination)?;
+                None
+            }
+            ExportFormat::Mbox | ExportFormat::Csv => {
+                if let Some(parent) = destination.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                Some(std::io::BufWriter::new(std::fs::File::create(destination)?))
+            }
+        };
+
+        let mut writer = Self {
+            format,
+            metadata_only,
+            destination: destination.to_path_buf(),
+            file,
+        };
+
+        if format == ExportFormat::Csv {
+            let mut header: Vec<&str> = CSV_COLUMNS.to_vec();
+            if !metadata_only {
+                header.push("body");
+            }
+            let line = header.join(",");
+            writer.write_line(&line)?;
+        }
+
+        Ok(writer)
+    }
+
+    pub fn write(&mut self, email: &Email) -> std::io::Result<()> {
+        match self.format {
+            ExportFormat::Mbox => {
+                let message = render_eml(email, self.metadata_only);
+                let block = mbox_entry(email, &message);
+                self.write_line(&block)
+            }
+            ExportFormat::Eml => {
+                let message = render_eml(email, self.metadata_only);
+                let path = self.destination.join(eml_file_name(email));
+                std::fs::write(path, message)
+            }
+            ExportFormat::Csv => {
+                let row = csv_row(email, self.metadata_only);
+                self.write_line(&row)
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(line.as_bytes())?;
+            file.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Encodes a header value as an RFC 2047 encoded word when it is not plain ASCII
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!(
+            "=?UTF-8?B?{}?=",
+            general_purpose::STANDARD.encode(value.as_bytes())
+        )
+    }
+}
+
+fn format_address(address: &EmailAddress) -> String {
+    match address.name.as_deref().filter(|n| !n.is_empty()) {
+        Some(name) if name.is_ascii() => {
+            format!("\"{}\" <{}>", name.replace('"', "\\\""), address.address)
+        }
+        Some(name) => format!("{} <{}>", encode_header(name), address.address),
+        None => address.address.clone(),
+    }
+}
+
+fn format_address_list(addresses: &[EmailAddress]) -> String {
+    addresses
+        .iter()
+        .map(format_address)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Renders a stored email as an RFC 5322 message
+///
+/// The original MIME source is not kept locally, so the message is rebuilt from
+/// the stored headers and bodies. Attachments are not included.
+pub fn render_eml(email: &Email, metadata_only: bool) -> String {
+    let mut headers = vec![
+        format!(
+            "Message-ID: <{}>",
+            email.message_id.trim_matches(['<', '>'])
+        ),
+        format!(
+            "Date: {}",
+            email.sent_at.unwrap_or(email.received_at).to_rfc2822()
+        ),
+        format!("From: {}", format_address(&email.from)),
+    ];
+
+    if !email.to.is_empty() {
+        headers.push(format!("To: {}", format_address_list(&email.to)));
+    }
+    if !email.cc.is_empty() {
+        headers.push(format!("Cc: {}", format_address_list(&email.cc)));
+    }
+    if !email.bcc.is_empty() {
+        headers.push(format!("Bcc: {}", format_address_list(&email.bcc)));
+    }
+    if let Some(reply_to) = &email.reply_to {
+        headers.push(format!("Reply-To: {}", format_address(reply_to)));
+    }
+    headers.push(format!(
+        "Subject: {}",
+        encode_header(email.subject.as_deref().unwrap_or_default())
+    ));
+    headers.push("MIME-Version: 1.0".to_string());
+
+    let plain = email.body_plain.as_deref().filter(|_| !metadata_only);
+    let html = email.body_html.as_deref().filter(|_| !metadata_only);
+
+    let body = match (plain, html) {
+        (Some(plain), Some(html)) => {
+            let boundary = format!("ravn-export-{}", email.id.simple());
+            headers.push(format!(
+                "Content-Type: multipart/alternative; boundary=\"{}\"",
+                boundary
+            ));
+            format!(
+                "--{b}\r\n{plain_headers}\r\n\r\n{plain}\r\n--{b}\r\n{html_headers}\r\n\r\n{html}\r\n--{b}--",
+                b = boundary,
+                plain_headers = "Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit",
+                plain = normalize_newlines(plain),
+                html_headers = "Content-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: 8bit",
+                html = normalize_newlines(html),
+            )
+        }
+        (None, Some(html)) => {
+            headers.push("Content-Type: text/html; charset=utf-8".to_string());
+            headers.push("Content-Transfer-Encoding: 8bit".to_string());
+            normalize_newlines(html)
+        }
+        (Some(plain), None) => {
+            headers.push("Content-Type: text/plain; charset=utf-8".to_string());
+            headers.push("Content-Transfer-Encoding: 8bit".to_string());
+            normalize_newlines(plain)
+        }
+        (None, None) => {
+            headers.push("Content-Type: text/plain; charset=utf-8".to_string());
+            String::new()
+        }
+    };
+
+    format!("{}\r\n\r\n{}\r\n", headers.join("\r\n"), body)
+}
+
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\n', "\r\n")
+}
+
+/// Wraps a message in an mboxrd envelope, quoting body lines that look like separators
+fn mbox_entry(email: &Email, message: &str) -> String {
+    let mut entry = format!(
+        "From {} {}\n",
+        if email.from.address.is_empty() {
+            "MAILER-DAEMON"
+        } else {
+            email.from.address.as_str()
+        },
+        email.received_at.format("%a %b %e %H:%M:%S %Y")
+    );
+
+    for line in message.replace("\r\n", "\n").lines() {
+        if line.trim_start_matches('>').starts_with("From ") {
+            entry.push('>');
+        }
+        entry.push_str(line);
+        entry.push('\n');
+    }
+
+    entry
+}
+
+fn eml_file_name(email: &Email) -> String {
+    let subject: String = email
+        .subject
+        .as_deref()
+        .unwrap_or("no-subject")
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(60)
+        .collect();
+
+    format!(
+        "{}-{}-{}.eml",
+        email.received_at.format("%Y%m%d-%H%M%S"),
+        if subject.is_empty() {
+            "no-subject".to_string()
+        } else {
+            subject
+        },
+        &email.id.simple().to_string()[..8]
+    )
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(email: &Email, metadata_only: bool) -> String {
+    let mut fields = vec![
+        email.id.to_string(),
+        email.message_id.clone(),
+        email.received_at.to_rfc3339(),
+        email.sent_at.map(|d| d.to_rfc3339()).unwrap_or_default(),
+        format_address(&email.from),
+        format_address_list(&email.to),
+        format_address_list(&email.cc),
+        format_address_list(&email.bcc),
+        email.subject.clone().unwrap_or_default(),
+        email.has_attachments.to_string(),
+        email.is_read.to_string(),
+        email.is_flagged.to_string(),
+        email.size.to_string(),
+    ];
+
+    if !metadata_only {
+        fields.push(email.body_plain.clone().unwrap_or_default());
+    }
+
+    fields
+        .iter()
+        .map(|f| csv_escape(f))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use sqlx::types::Json;
+    use uuid::Uuid;
+
+    fn email() -> Email {
+        let received_at = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap();
+        Email {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            folder_id: Uuid::now_v7(),
+            message_id: "<abc@example.com>".to_string(),
+            conversation_id: None,
+            remote_id: None,
+            from: Json(EmailAddress {
+                name: Some("Jürgen Example".to_string()),
+                address: "juergen@example.com".to_string(),
+            }),
+            to: Json(vec![EmailAddress {
+                name: None,
+                address: "team@example.com".to_string(),
+            }]),
+            cc: Json(Vec::new()),
+            bcc: Json(Vec::new()),
+            reply_to: None,
+            subject: Some("Quarterly report, final".to_string()),
+            snippet: None,
+            body_plain: Some("Hello\nFrom the team".to_string()),
+            body_html: None,
+            other_mails: None,
+            category: None,
+            ai_cache: None,
+            received_at,
+            sent_at: None,
+            scheduled_send_at: None,
+            remind_at: None,
+            snoozed_until: None,
+            is_read: true,
+            is_flagged: false,
+            has_attachments: false,
+            is_draft: false,
+            is_deleted: false,
+            headers: None,
+            sync_status: "synced".to_string(),
+            tracking_blocked: true,
+            images_blocked: true,
+            body_fetch_attempts: 0,
+            last_body_fetch_attempt: None,
+            change_key: None,
+            last_modified_at: None,
+            deleted_at: None,
+            deletion_source: None,
+            created_at: received_at,
+            updated_at: received_at,
+            size: 42,
+            priority: 0,
+            risk_score: 0,
+            risk_reasons: Json(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn test_render_eml_headers_and_body() {
+        let message = render_eml(&email(), false);
+        assert!(message.contains("Message-ID: <abc@example.com>\r\n"));
+        assert!(message.contains("From: =?UTF-8?B?"));
+        assert!(message.contains("To: team@example.com\r\n"));
+        assert!(message.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(message.ends_with("Hello\r\nFrom the team\r\n"));
+
+        let metadata = render_eml(&email(), true);
+        assert!(!metadata.contains("Hello"));
+    }
+
+    #[test]
+    fn test_mbox_escapes_from_lines() {
+        let email = email();
+        let entry = mbox_entry(&email, &render_eml(&email, false));
+        assert!(entry.starts_with("From juergen@example.com Fri Mar  1 09:30:00 2024\n"));
+        assert!(entry.contains("\n>From the team\n"));
+    }
+
+    #[test]
+    fn test_csv_row_quotes_fields() {
+        let row = csv_row(&email(), false);
+        assert!(row.contains(",\"Quarterly report, final\","));
+        assert!(row.ends_with(",\"Hello\nFrom the team\""));
+
+        let metadata_row = csv_row(&email(), true);
+        assert!(metadata_row.ends_with(",42"));
+    }
+}
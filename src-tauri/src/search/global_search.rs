@@ -0,0 +1,221 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tantivy::schema::Value;
+use tantivy::{Index, IndexReader, ReloadPolicy, TantivyDocument};
+use uuid::Uuid;
+
+use super::error::{SearchError, SearchResult};
+use super::search_manager::{execute_query, EmailSchema, SearchManager, SearchQuery};
+
+/// Another Ravn profile whose search index can be queried
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileSource {
+    pub name: String,
+    /// The profile's app data directory (the one containing `search_index/`)
+    pub path: PathBuf,
+}
+
+/// A search hit with the profile it came from.
+///
+/// Hits from other profiles are built from the stored index fields only, since
+/// their databases are never opened.
+#[derive(Debug, Clone, Serialize)]
+pub struct GlobalSearchHit {
+    pub profile: String,
+    pub is_current_profile: bool,
+    pub email_id: Uuid,
+    pub score: f32,
+    pub subject: Option<String>,
+    pub from: Vec<String>,
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+/// A search index belonging to another profile, opened without a writer so the
+/// owning instance keeps exclusive write access
+pub struct ProfileIndex {
+    name: String,
+    index: Index,
+    reader: IndexReader,
+    schema: EmailSchema,
+}
+
+impl ProfileIndex {
+    pub fn open(source: &ProfileSource) -> SearchResult<Self> {
+        let index_path = source.path.join("search_index");
+        if !index_path.is_dir() {
+            return Err(SearchError::IndexNotFound);
+        }
+
+        let index = Index::open_in_dir(&index_path)?;
+        let schema = EmailSchema::from_schema(&index.schema())?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()?;
+
+        Ok(Self {
+            name: source.name.clone(),
+            index,
+            reader,
+            schema,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn search(&self, query: &SearchQuery) -> SearchResult<Vec<GlobalSearchHit>> {
+        Ok(
+            execute_query(&self.index, &self.reader, &self.schema, query)?
+                .into_iter()
+                .filter_map(|(score, doc)| to_hit(&self.schema, &self.name, false, score, &doc))
+                .collect(),
+        )
+    }
+}
+
+/// Open every configured profile index, skipping the current profile and any
+/// that cannot be read
+pub fn open_profiles(current_dir: &Path, sources: &[ProfileSource]) -> Vec<Arc<ProfileIndex>> {
+    sources
+        .iter()
+        .filter(|source| source.path != current_dir)
+        .filter_map(|source| match ProfileIndex::open(source) {
+            Ok(index) => Some(Arc::new(index)),
+            Err(e) => {
+                log::warn!(
+                    "[GlobalSearch] Skipping profile '{}' at {}: {}",
+                    source.name,
+                    source.path.display(),
+                    e
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Query the current profile and all other profile indexes in parallel and
+/// merge the hits by score.
+///
+/// Scores come from independent indexes, so the ordering across profiles is
+/// approximate. Paging is applied after merging.
+///
+/// `in:` and `label:` operators are resolved against the current profile's
+/// folders and labels, so they only match in the current profile.
+pub async fn search_all(
+    current: &SearchManager,
+    current_name: &str,
+    profiles: &[Arc<ProfileIndex>],
+    query: SearchQuery,
+) -> SearchResult<Vec<GlobalSearchHit>> {
+    let (limit, offset) = (query.limit, query.offset);
+    let per_profile = SearchQuery {
+        limit: (limit + offset).min(1000),
+        offset: 0,
+        ..query
+    };
+
+    let handles: Vec<_> = profiles
+        .iter()
+        .map(|profile| {
+            let profile = Arc::clone(profile);
+            let query = per_profile.clone();
+            tokio::task::spawn_blocking(move || (profile.name.clone(), profile.search(&query)))
+        })
+        .collect();
+
+    let schema = current.schema();
+    let mut hits: Vec<GlobalSearchHit> = current
+        .search_documents(&per_profile)?
+        .into_iter()
+        .filter_map(|(score, doc)| to_hit(schema, current_name, true, score, &doc))
+        .collect();
+
+    for handle in handles {
+        match handle.await {
+            Ok((_, Ok(profile_hits))) => hits.extend(profile_hits),
+            Ok((name, Err(e))) => {
+                log::warn!("[GlobalSearch] Search in profile '{}' failed: {}", name, e)
+            }
+            Err(e) => log::warn!("[GlobalSearch] Profile search task failed: {}", e),
+        }
+    }
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(hits.into_iter().skip(offset).take(limit).collect())
+}
+
+fn to_hit(
+    schema: &EmailSchema,
+    profile: &str,
+    is_current_profile: bool,
+    score: f32,
+    doc: &TantivyDocument,
+) -> Option<GlobalSearchHit> {
+    let email_id = Uuid::parse_str(doc.get_first(schema.id)?.as_str()?).ok()?;
+
+    Some(GlobalSearchHit {
+        profile: profile.to_string(),
+        is_current_profile,
+        email_id,
+        score,
+        subject: doc
+            .get_first(schema.subject)
+            .and_then(|v| v.as_str())
+            .map(ToString::to_string),
+        from: doc
+            .get_all(schema.from)
+            .filter_map(|v| v.as_str())
+            .map(ToString::to_string)
+            .collect(),
+        received_at: doc
+            .get_first(schema.received)
+            .and_then(|v| v.as_datetime())
+            .and_then(|date| DateTime::from_timestamp(date.into_timestamp_secs(), 0)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_open_profile_requires_existing_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = ProfileSource {
+            name: "Work".to_string(),
+            path: temp_dir.path().to_path_buf(),
+        };
+        assert!(matches!(
+            ProfileIndex::open(&source),
+            Err(SearchError::IndexNotFound)
+        ));
+
+        SearchManager::new(temp_dir.path().join("search_index")).unwrap();
+        let index = ProfileIndex::open(&source).unwrap();
+        assert_eq!(index.name(), "Work");
+    }
+
+    #[test]
+    fn test_open_profiles_skips_current_and_missing() {
+        let current = TempDir::new().unwrap();
+        let missing = TempDir::new().unwrap();
+        let sources = vec![
+            ProfileSource {
+                name: "Current".to_string(),
+                path: current.path().to_path_buf(),
+            },
+            ProfileSource {
+                name: "Missing".to_string(),
+                path: missing.path().to_path_buf(),
+            },
+        ];
+
+        assert!(open_profiles(current.path(), &sources).is_empty());
+    }
+}
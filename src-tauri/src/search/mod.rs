@@ -1,8 +1,14 @@
+pub mod attachment_text;
 mod error;
+pub mod export;
+pub mod global_search;
+mod query_language;
+pub mod reindex;
 mod search_manager;
 
 pub use error::{SearchError, SearchResult};
+pub use query_language::{Filter, ParsedQuery, QueryScope};
 pub use search_manager::SearchManager;
 
 // Re-export search-related types
-pub use search_manager::{SearchQuery, SearchResultItem};
+pub use search_manager::{MatchedAttachment, SearchHighlight, SearchQuery, SearchResultItem};
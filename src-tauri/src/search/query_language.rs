@@ -0,0 +1,495 @@
+//! The search query language. Queries combine full-text terms with operators
+//! that filter on message state:
+//!
+//! - `from:`, `to:`, `cc:`, `subject:`, `filename:`, `type:`, `attachment:`
+//!   and plain terms are matched against the index with full-text scoring
+//! - `has:attachment`, `is:read`, `is:unread`, `is:flagged`, `is:unflagged`
+//! - `before:2024-12-31`, `after:2024-01-01` (also `2024/01/01`), local dates
+//! - `in:inbox`, `in:"Project X"` by folder type or name, `label:work`
+//! - `AND`, `OR`, `NOT` or `-term`, parentheses and `"quoted phrases"`
+//!
+//! Terms next to each other must all match. Filters narrow the results
+//! without changing their score.
+use chrono::{Local, NaiveDate, TimeZone};
+use std::collections::HashMap;
+use std::ops::Bound;
+use tantivy::query::{
+    AllQuery, BooleanQuery, ConstScoreQuery, EmptyQuery, Occur, Query, QueryParser, RangeQuery,
+    TermQuery, TermSetQuery,
+};
+use tantivy::schema::{Field, IndexRecordOption};
+use tantivy::Term;
+use uuid::Uuid;
+
+use super::error::{SearchError, SearchResult};
+use super::search_manager::EmailSchema;
+
+/// Operators that filter instead of matching text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    HasAttachment,
+    Read(bool),
+    Flagged(bool),
+    /// Received before the start of the day
+    Before(NaiveDate),
+    /// Received on the day or later
+    After(NaiveDate),
+    /// Folder name or type, lowercase
+    Folder(String),
+    /// Label name, lowercase
+    Label(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryNode {
+    /// A term, phrase or `field:value` matched by Tantivy's query parser
+    Text(String),
+    Filter(Filter),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+    Not(Box<QueryNode>),
+}
+
+/// Folders and labeled emails named by `in:` and `label:` operators. The index
+/// only knows folder IDs and no labels, so names are resolved before searching.
+#[derive(Debug, Clone, Default)]
+pub struct QueryScope {
+    /// Folder IDs by lowercase folder name or type
+    pub folders: HashMap<String, Vec<Uuid>>,
+    /// Email IDs by lowercase label name
+    pub labels: HashMap<String, Vec<Uuid>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+/// A parsed search query, `None` for an empty one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedQuery(pub Option<QueryNode>);
+
+impl ParsedQuery {
+    pub fn parse(query: &str) -> SearchResult<Self> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, pos: 0 };
+
+        if parser.tokens.is_empty() {
+            return Ok(Self(None));
+        }
+
+        let root = parser.parse_or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(SearchError::InvalidQuery(
+                "Unbalanced parentheses in query".to_string(),
+            ));
+        }
+
+        Ok(Self(Some(root)))
+    }
+
+    /// All filters of the query, including negated ones
+    pub fn filters(&self) -> Vec<&Filter> {
+        fn collect<'a>(node: &'a QueryNode, filters: &mut Vec<&'a Filter>) {
+            match node {
+                QueryNode::Text(_) => {}
+                QueryNode::Filter(filter) => filters.push(filter),
+                QueryNode::And(children) | QueryNode::Or(children) => {
+                    children.iter().for_each(|child| collect(child, filters))
+                }
+                QueryNode::Not(child) => collect(child, filters),
+            }
+        }
+
+        let mut filters = Vec::new();
+        if let Some(root) = &self.0 {
+            collect(root, &mut filters);
+        }
+        filters
+    }
+
+    pub fn to_query(
+        &self,
+        parser: &QueryParser,
+        schema: &EmailSchema,
+        scope: &QueryScope,
+    ) -> SearchResult<Box<dyn Query>> {
+        match &self.0 {
+            Some(root) => node_to_query(root, parser, schema, scope),
+            None => Ok(Box::new(EmptyQuery)),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> SearchResult<Vec<Token>> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+                continue;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+                continue;
+            }
+            '-' if chars.get(i + 1).is_some_and(|next| !next.is_whitespace()) => {
+                tokens.push(Token::Not);
+                i += 1;
+                continue;
+            }
+            '+' => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let start = i;
+        while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' {
+            let closing = match chars[i] {
+                '"' => Some('"'),
+                '[' => Some(']'),
+                '{' => Some('}'),
+                _ => None,
+            };
+
+            if let Some(closing) = closing {
+                // Phrases and ranges may contain spaces
+                i += 1;
+                while i < chars.len() && chars[i] != closing {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(SearchError::InvalidQuery(format!(
+                        "Missing closing '{}' in query",
+                        closing
+                    )));
+                }
+            }
+            i += 1;
+        }
+
+        let term: String = chars[start..i].iter().collect();
+        tokens.push(match term.as_str() {
+            "AND" | "&&" => Token::And,
+            "OR" | "||" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Term(term),
+        });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> SearchResult<QueryNode> {
+        let mut children = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            children.push(self.parse_and()?);
+        }
+
+        Ok(if children.len() == 1 {
+            children.remove(0)
+        } else {
+            QueryNode::Or(children)
+        })
+    }
+
+    fn parse_and(&mut self) -> SearchResult<QueryNode> {
+        let mut children = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.pos += 1;
+                    children.push(self.parse_unary()?);
+                }
+                Some(Token::Or) | Some(Token::RParen) | None => break,
+                Some(_) => children.push(self.parse_unary()?),
+            }
+        }
+
+        Ok(if children.len() == 1 {
+            children.remove(0)
+        } else {
+            QueryNode::And(children)
+        })
+    }
+
+    fn parse_unary(&mut self) -> SearchResult<QueryNode> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(QueryNode::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let node = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(SearchError::InvalidQuery(
+                        "Unbalanced parentheses in query".to_string(),
+                    ));
+                }
+                self.pos += 1;
+                Ok(node)
+            }
+            Some(Token::Term(term)) => {
+                self.pos += 1;
+                parse_term(&term)
+            }
+            Some(token) => Err(SearchError::InvalidQuery(format!(
+                "Unexpected {:?} in query",
+                token
+            ))),
+            None => Err(SearchError::InvalidQuery(
+                "Query ends with an operator".to_string(),
+            )),
+        }
+    }
+}
+
+fn parse_term(term: &str) -> SearchResult<QueryNode> {
+    let Some((field, value)) = term.split_once(':') else {
+        return Ok(QueryNode::Text(term.to_string()));
+    };
+    let value = value.trim_matches('"');
+    let invalid = |message: &str| SearchError::InvalidQuery(format!("{}: '{}'", message, term));
+
+    let filter = match field {
+        "has" => match value {
+            "attachment" | "attachments" => Filter::HasAttachment,
+            _ => return Err(invalid("Unknown has: value")),
+        },
+        "is" => match value {
+            "read" => Filter::Read(true),
+            "unread" => Filter::Read(false),
+            "flagged" | "starred" => Filter::Flagged(true),
+            "unflagged" | "unstarred" => Filter::Flagged(false),
+            _ => return Err(invalid("Unknown is: value")),
+        },
+        "before" => Filter::Before(parse_date(value).ok_or_else(|| invalid("Invalid date"))?),
+        "after" => Filter::After(parse_date(value).ok_or_else(|| invalid("Invalid date"))?),
+        "in" | "folder" => Filter::Folder(value.to_lowercase()),
+        "label" | "labels" => Filter::Label(value.to_lowercase()),
+        _ => return Ok(QueryNode::Text(term.to_string())),
+    };
+
+    Ok(QueryNode::Filter(filter))
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y/%m/%d"))
+        .ok()
+}
+
+fn node_to_query(
+    node: &QueryNode,
+    parser: &QueryParser,
+    schema: &EmailSchema,
+    scope: &QueryScope,
+) -> SearchResult<Box<dyn Query>> {
+    let query: Box<dyn Query> = match node {
+        QueryNode::Text(text) => parser.parse_query(text)?,
+        QueryNode::Filter(filter) => {
+            // Filters narrow the results without adding to their score
+            Box::new(ConstScoreQuery::new(
+                filter_to_query(filter, schema, scope),
+                0.0,
+            ))
+        }
+        QueryNode::And(children) => {
+            let mut clauses = Vec::with_capacity(children.len());
+            for child in children {
+                clauses.push(match child {
+                    QueryNode::Not(negated) => (
+                        Occur::MustNot,
+                        node_to_query(negated, parser, schema, scope)?,
+                    ),
+                    _ => (Occur::Must, node_to_query(child, parser, schema, scope)?),
+                });
+            }
+            if clauses.iter().all(|(occur, _)| *occur == Occur::MustNot) {
+                clauses.push((Occur::Must, Box::new(AllQuery)));
+            }
+            Box::new(BooleanQuery::new(clauses))
+        }
+        QueryNode::Or(children) => Box::new(BooleanQuery::union(
+            children
+                .iter()
+                .map(|child| node_to_query(child, parser, schema, scope))
+                .collect::<SearchResult<_>>()?,
+        )),
+        QueryNode::Not(negated) => Box::new(BooleanQuery::new(vec![
+            (Occur::Must, Box::new(AllQuery)),
+            (
+                Occur::MustNot,
+                node_to_query(negated, parser, schema, scope)?,
+            ),
+        ])),
+    };
+
+    Ok(query)
+}
+
+fn filter_to_query(filter: &Filter, schema: &EmailSchema, scope: &QueryScope) -> Box<dyn Query> {
+    let bool_query = |field: Field, value: bool| -> Box<dyn Query> {
+        Box::new(TermQuery::new(
+            Term::from_field_bool(field, value),
+            IndexRecordOption::Basic,
+        ))
+    };
+    let id_set = |field: Field, ids: Option<&Vec<Uuid>>| -> Box<dyn Query> {
+        match ids {
+            Some(ids) if !ids.is_empty() => Box::new(TermSetQuery::new(
+                ids.iter()
+                    .map(|id| Term::from_field_text(field, &id.to_string())),
+            )),
+            _ => Box::new(EmptyQuery),
+        }
+    };
+
+    match filter {
+        Filter::HasAttachment => bool_query(schema.has_attachment, true),
+        Filter::Read(is_read) => bool_query(schema.is_read, *is_read),
+        Filter::Flagged(is_flagged) => bool_query(schema.is_flagged, *is_flagged),
+        Filter::Before(date) => Box::new(RangeQuery::new(
+            Bound::Unbounded,
+            Bound::Excluded(day_start(schema, *date)),
+        )),
+        Filter::After(date) => Box::new(RangeQuery::new(
+            Bound::Included(day_start(schema, *date)),
+            Bound::Unbounded,
+        )),
+        Filter::Folder(name) => id_set(schema.folder_id, scope.folders.get(name)),
+        Filter::Label(name) => id_set(schema.id, scope.labels.get(name)),
+    }
+}
+
+/// Midnight of a local date as a term of the received date field
+fn day_start(schema: &EmailSchema, date: NaiveDate) -> Term {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let timestamp = Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map_or_else(|| midnight.and_utc().timestamp(), |local| local.timestamp());
+
+    Term::from_field_date_for_search(
+        schema.received,
+        tantivy::DateTime::from_timestamp_secs(timestamp),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(term: &str) -> QueryNode {
+        QueryNode::Text(term.to_string())
+    }
+
+    fn parse(query: &str) -> QueryNode {
+        ParsedQuery::parse(query).unwrap().0.unwrap()
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        assert_eq!(
+            parse(r#"from:alice "clause 7" has:attachment -is:read"#),
+            QueryNode::And(vec![
+                text("from:alice"),
+                text("\"clause 7\""),
+                QueryNode::Filter(Filter::HasAttachment),
+                QueryNode::Not(Box::new(QueryNode::Filter(Filter::Read(true)))),
+            ])
+        );
+
+        assert_eq!(
+            parse(r#"subject:"Q3 report" in:"Project X" label:Work after:2024/01/31"#),
+            QueryNode::And(vec![
+                text("subject:\"Q3 report\""),
+                QueryNode::Filter(Filter::Folder("project x".to_string())),
+                QueryNode::Filter(Filter::Label("work".to_string())),
+                QueryNode::Filter(Filter::After(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())),
+            ])
+        );
+
+        assert_eq!(
+            parse("received:[2024-01-01 TO 2024-12-31] report*"),
+            QueryNode::And(vec![
+                text("received:[2024-01-01 TO 2024-12-31]"),
+                text("report*"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_boolean_precedence() {
+        // AND binds tighter than OR
+        assert_eq!(
+            parse("a b OR c AND NOT d"),
+            QueryNode::Or(vec![
+                QueryNode::And(vec![text("a"), text("b")]),
+                QueryNode::And(vec![text("c"), QueryNode::Not(Box::new(text("d")))]),
+            ])
+        );
+
+        assert_eq!(
+            parse("(from:john OR from:jane) budget"),
+            QueryNode::And(vec![
+                QueryNode::Or(vec![text("from:john"), text("from:jane")]),
+                text("budget"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(ParsedQuery::parse("   ").unwrap(), ParsedQuery(None));
+        assert!(ParsedQuery::parse("(a OR b").is_err());
+        assert!(ParsedQuery::parse("a OR b)").is_err());
+        assert!(ParsedQuery::parse("\"unclosed phrase").is_err());
+        assert!(ParsedQuery::parse("a OR").is_err());
+        assert!(ParsedQuery::parse("before:yesterday").is_err());
+        assert!(ParsedQuery::parse("is:important").is_err());
+    }
+
+    #[test]
+    fn test_filters() {
+        let parsed = ParsedQuery::parse("in:inbox (label:a OR -label:b) budget").unwrap();
+        assert_eq!(
+            parsed.filters(),
+            vec![
+                &Filter::Folder("inbox".to_string()),
+                &Filter::Label("a".to_string()),
+                &Filter::Label("b".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Rebuilding the search index from the database. A reindex runs in the
+//! background in batches and records a checkpoint after each committed batch,
+//! so a reindex interrupted by quitting the app resumes where it stopped.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use super::SearchManager;
+use crate::database::repositories::{
+    AttachmentRepository, EmailRepository, SqliteAttachmentRepository, SqliteEmailRepository,
+};
+
+pub const PROGRESS_EVENT: &str = "search:reindex-progress";
+
+/// Stored in the index directory, so it is removed with an outdated index
+const CHECKPOINT_FILE: &str = "reindex_checkpoint.json";
+
+const BATCH_SIZE: i64 = 1000;
+
+/// How far an unfinished reindex got
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ReindexCheckpoint {
+    /// Offset into `find_synced_batch` of the next batch
+    offset: i64,
+    indexed: usize,
+    started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReindexProgress {
+    pub indexed: usize,
+    /// Emails to index, counted when the reindex (re)started
+    pub total: i64,
+    pub started_at: DateTime<Utc>,
+    pub completed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStatus {
+    pub indexed_documents: u64,
+    pub reindexing: bool,
+    /// A reindex was interrupted and has not been resumed yet
+    pub interrupted: bool,
+    /// Progress of the running or last reindex of this session
+    pub progress: Option<ReindexProgress>,
+}
+
+pub struct SearchReindexer {
+    pool: SqlitePool,
+    search_manager: Arc<SearchManager>,
+    checkpoint_path: PathBuf,
+    running: AtomicBool,
+    latest: RwLock<Option<ReindexProgress>>,
+    app_handle: Option<AppHandle>,
+}
+
+impl SearchReindexer {
+    pub fn new(
+        pool: SqlitePool,
+        search_manager: Arc<SearchManager>,
+        index_path: impl AsRef<Path>,
+    ) -> Self {
+        Self {
+            pool,
+            search_manager,
+            checkpoint_path: index_path.as_ref().join(CHECKPOINT_FILE),
+            running: AtomicBool::new(false),
+            latest: RwLock::new(None),
+            app_handle: None,
+        }
+    }
+
+    pub fn with_app_handle(mut self, app_handle: AppHandle) -> Self {
+        self.app_handle = Some(app_handle);
+        self
+    }
+
+    /// Whether an earlier reindex stopped before it was complete
+    pub fn is_interrupted(&self) -> bool {
+        !self.is_running() && self.checkpoint_path.exists()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Start reindexing in the background. With `resume`, an interrupted
+    /// reindex continues from its checkpoint; otherwise the index is cleared
+    /// and rebuilt. Returns `false` if a reindex is already running.
+    pub fn start(self: &Arc<Self>, resume: bool) -> bool {
+        if self
+            .running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+
+        if !resume {
+            self.remove_checkpoint();
+        }
+
+        let this = Arc::clone(self);
+        tauri::async_runtime::spawn(async move {
+            match this.run().await {
+                Ok(indexed) => {
+                    log::info!("[Search] Reindex complete, {} emails indexed", indexed);
+                }
+                Err(error) => {
+                    log::error!("[Search] Reindex failed: {}", error);
+                    let progress = this.latest().unwrap_or(ReindexProgress {
+                        indexed: 0,
+                        total: 0,
+                        started_at: Utc::now(),
+                        completed: false,
+                        error: None,
+                    });
+                    this.report(ReindexProgress {
+                        error: Some(error),
+                        ..progress
+                    });
+                }
+            }
+            this.running.store(false, Ordering::SeqCst);
+        });
+
+        true
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        IndexStatus {
+            indexed_documents: self.search_manager.num_docs(),
+            reindexing: self.is_running(),
+            interrupted: self.is_interrupted(),
+            progress: self.latest(),
+        }
+    }
+
+    fn latest(&self) -> Option<ReindexProgress> {
+        self.latest.read().ok().and_then(|latest| latest.clone())
+    }
+
+    async fn run(&self) -> Result<usize, String> {
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+        let attachment_repo = SqliteAttachmentRepository::new(self.pool.clone());
+
+        let mut checkpoint = match self.load_checkpoint() {
+            Some(checkpoint) => {
+                log::info!(
+                    "[Search] Resuming reindex after {} emails",
+                    checkpoint.indexed
+                );
+                checkpoint
+            }
+            None => {
+                log::info!("[Search] Starting full reindex of all emails");
+                self.search_manager
+                    .clear_index()
+                    .await
+                    .map_err(|e| format!("Failed to clear index: {}", e))?;
+
+                let checkpoint = ReindexCheckpoint {
+                    offset: 0,
+                    indexed: 0,
+                    started_at: Utc::now(),
+                };
+                self.save_checkpoint(&checkpoint)?;
+                checkpoint
+            }
+        };
+
+        let total = email_repo
+            .count_synced()
+            .await
+            .map_err(|e| format!("Failed to count emails: {}", e))?;
+        let progress = |checkpoint: &ReindexCheckpoint, completed: bool| ReindexProgress {
+            indexed: checkpoint.indexed,
+            total,
+            started_at: checkpoint.started_at,
+            completed,
+            error: None,
+        };
+        self.report(progress(&checkpoint, false));
+
+        loop {
+            let emails = email_repo
+                .find_synced_batch(BATCH_SIZE, checkpoint.offset)
+                .await
+                .map_err(|e| format!("Failed to fetch emails: {}", e))?;
+
+            if emails.is_empty() {
+                break;
+            }
+
+            let email_ids: Vec<Uuid> = emails.iter().map(|e| e.id).collect();
+            let attachments = attachment_repo
+                .find_by_emails(&email_ids)
+                .await
+                .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+            let attachment_texts = attachment_repo
+                .find_texts_by_emails(&email_ids)
+                .await
+                .map_err(|e| format!("Failed to fetch attachment texts: {}", e))?;
+
+            self.search_manager
+                .index_emails_batch(&emails, &attachments, &attachment_texts)
+                .await
+                .map_err(|e| format!("Failed to index batch: {}", e))?;
+            // The checkpoint may only move past batches that are committed
+            self.search_manager
+                .commit()
+                .await
+                .map_err(|e| format!("Failed to commit index: {}", e))?;
+
+            checkpoint.offset += BATCH_SIZE;
+            checkpoint.indexed += emails.len();
+            self.save_checkpoint(&checkpoint)?;
+            self.report(progress(&checkpoint, false));
+        }
+
+        self.remove_checkpoint();
+        self.report(progress(&checkpoint, true));
+
+        Ok(checkpoint.indexed)
+    }
+
+    fn report(&self, progress: ReindexProgress) {
+        log::debug!(
+            "[Search] Reindexed {} of {} emails",
+            progress.indexed,
+            progress.total
+        );
+
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(PROGRESS_EVENT, &progress) {
+                log::warn!("[Search] Failed to emit reindex progress: {}", e);
+            }
+        }
+
+        if let Ok(mut latest) = self.latest.write() {
+            *latest = Some(progress);
+        }
+    }
+
+    fn load_checkpoint(&self) -> Option<ReindexCheckpoint> {
+        let data = std::fs::read(&self.checkpoint_path).ok()?;
+        match serde_json::from_slice(&data) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                log::warn!("[Search] Ignoring unreadable reindex checkpoint: {}", e);
+                None
+            }
+        }
+    }
+
+    fn save_checkpoint(&self, checkpoint: &ReindexCheckpoint) -> Result<(), String> {
+        let data = serde_json::to_vec(checkpoint)
+            .map_err(|e| format!("Failed to serialize reindex checkpoint: {}", e))?;
+        std::fs::write(&self.checkpoint_path, data)
+            .map_err(|e| format!("Failed to write reindex checkpoint: {}", e))
+    }
+
+    fn remove_checkpoint(&self) {
+        if let Err(e) = std::fs::remove_file(&self.checkpoint_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("[Search] Failed to remove reindex checkpoint: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_checkpoint_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let search_manager = Arc::new(SearchManager::new(temp_dir.path()).unwrap());
+        let reindexer = SearchReindexer::new(pool, search_manager, temp_dir.path());
+
+        assert!(reindexer.load_checkpoint().is_none());
+        assert!(!reindexer.is_interrupted());
+
+        let checkpoint = ReindexCheckpoint {
+            offset: 2000,
+            indexed: 1987,
+            started_at: Utc::now(),
+        };
+        reindexer.save_checkpoint(&checkpoint).unwrap();
+        assert_eq!(reindexer.load_checkpoint(), Some(checkpoint));
+        assert!(reindexer.is_interrupted());
+
+        reindexer.remove_checkpoint();
+        assert!(!reindexer.is_interrupted());
+    }
+}
@@ -1,23 +1,30 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
-use tantivy::collector::TopDocs;
+use tantivy::collector::{DocSetCollector, TopDocs};
 use tantivy::directory::MmapDirectory;
 use tantivy::query::{BooleanQuery, Query, QueryParser, TermQuery};
 use tantivy::schema::*;
-use tantivy::{Index, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use super::error::{SearchError, SearchResult};
+use super::query_language::{ParsedQuery, QueryScope};
+use crate::database::models::attachment::Attachment;
 use crate::database::models::email::{Email, EmailAddress};
 
 /// Fields in the Tantivy search index
-/// Designed to match the user documentation's search operators:
+/// Designed to match the user documentation's search operators (see
+/// `query_language` for the full syntax):
 /// - from:, to:, cc: for email addresses
-/// - subject:, labels:, folder: for metadata
-/// - is:read, is:unread for read status
-/// - received:[DATE TO DATE] for date ranges
+/// - subject: for metadata
+/// - is:read, is:unread, is:flagged, has:attachment for message state
+/// - received:[DATE TO DATE], before:, after: for date ranges
+/// - filename:, type: for attachments
+/// - attachment: for text inside attachments
 pub struct EmailSchema {
     pub id: Field,
     pub account_id: Field,
@@ -35,7 +42,16 @@ pub struct EmailSchema {
     pub is_read: Field,
     pub is_flagged: Field,
     pub is_deleted: Field,
+    pub has_attachment: Field,
     pub labels: Field,
+
+    /// One value per attachment, in the same order in all three fields
+    pub attachment_id: Field,
+    pub filename: Field,
+    /// Category (see `FILE_TYPES`) and MIME type
+    pub file_type: Field,
+    /// Text extracted from PDF, DOCX, XLSX and plain-text attachments
+    pub attachment_text: Field,
 }
 
 impl EmailSchema {
@@ -60,6 +76,13 @@ impl EmailSchema {
 
         let fast_text_options = TextOptions::default().set_fast(Some("raw"));
 
+        // Attachment text can be large and is never displayed, so it is only indexed
+        let unstored_text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("default")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
+
         let email_schema = EmailSchema {
             id: schema_builder.add_text_field("id", STRING | STORED | FAST),
             account_id: schema_builder.add_text_field("account_id", STRING | FAST),
@@ -76,31 +99,69 @@ impl EmailSchema {
             received: schema_builder.add_date_field("received", STORED | INDEXED | FAST),
 
             is_read: schema_builder.add_bool_field("is_read", STORED | INDEXED | FAST),
-            is_flagged: schema_builder.add_bool_field("is_flagged", STORED | FAST),
+            is_flagged: schema_builder.add_bool_field("is_flagged", STORED | INDEXED | FAST),
             is_deleted: schema_builder.add_bool_field("is_deleted", STORED | INDEXED | FAST),
+            has_attachment: schema_builder.add_bool_field("has_attachment", INDEXED | FAST),
 
             labels: schema_builder.add_text_field("labels", fast_text_options),
+
+            attachment_id: schema_builder.add_text_field("attachment_id", STRING | STORED),
+            filename: schema_builder.add_text_field("filename", text_options.clone()),
+            file_type: schema_builder.add_text_field("type", text_options),
+            attachment_text: schema_builder.add_text_field("attachment", unstored_text_options),
         };
 
         (schema_builder.build(), email_schema)
     }
+
+    /// Resolve the fields of an existing index by name
+    pub fn from_schema(schema: &Schema) -> SearchResult<Self> {
+        let field = |name: &str| {
+            schema
+                .get_field(name)
+                .map_err(|_| SearchError::Other(format!("Index is missing field '{}'", name)))
+        };
+
+        Ok(Self {
+            id: field("id")?,
+            account_id: field("account_id")?,
+            folder_id: field("folder_id")?,
+            conversation_id: field("conversation_id")?,
+            subject: field("subject")?,
+            body: field("body")?,
+            from: field("from")?,
+            to: field("to")?,
+            cc: field("cc")?,
+            received: field("received")?,
+            is_read: field("is_read")?,
+            is_flagged: field("is_flagged")?,
+            is_deleted: field("is_deleted")?,
+            has_attachment: field("has_attachment")?,
+            labels: field("labels")?,
+            attachment_id: field("attachment_id")?,
+            filename: field("filename")?,
+            file_type: field("type")?,
+            attachment_text: field("attachment")?,
+        })
+    }
 }
 
 /// Search query parameters supporting user documentation syntax
 /// Examples:
 /// - Simple: "budget report"
 /// - With operators: "from:john budget is:unread"
-/// - Complex: "(from:john OR from:jane) AND budget received:[2024-01-01 TO 2024-12-31]"
+/// - Complex: "(from:john OR from:jane) AND budget in:inbox after:2024-01-01"
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SearchQuery {
-    /// Search query string (supports full Tantivy query syntax)
+    /// Search query string (see `query_language`)
     /// Users can enter:
     /// - Keywords: "budget"
     /// - Phrases: "\"fiscal year 2024\""
     /// - Email operators: "from:john to:sarah cc:team"
     /// - Boolean: "from:john AND budget", "invoice OR receipt"
     /// - Negation: "report -draft"
-    /// - Date ranges: "received:[2024-01-01 TO 2024-12-31]"
+    /// - Filters: "has:attachment is:unread in:inbox label:work"
+    /// - Date ranges: "before:2024-12-31", "received:[2024-01-01 TO 2024-12-31]"
     /// - Wildcards: "report*" (prefix matching)
     /// - Fuzzy: "rusty~1" (edit distance matching)
     pub query: String,
@@ -114,6 +175,10 @@ pub struct SearchQuery {
 
     #[serde(default)]
     pub offset: usize,
+
+    /// Folders and labels named by `in:` and `label:`, resolved by the caller
+    #[serde(skip)]
+    pub scope: QueryScope,
 }
 
 fn default_limit() -> usize {
@@ -125,6 +190,162 @@ fn default_limit() -> usize {
 pub struct SearchResultItem {
     pub id: Uuid,
     pub score: f32,
+    /// Attachments matching the query's `filename:` / `type:` terms
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_attachments: Vec<MatchedAttachment>,
+    /// Snippets of the fields the query matched, best field first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<SearchHighlight>,
+}
+
+/// A fragment of a matching field with the matched terms wrapped in
+/// `<mark>` tags. The rest of the fragment is HTML-escaped.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHighlight {
+    pub field: String,
+    pub snippet: String,
+}
+
+/// Maximum length of a highlighted fragment, per field
+const HIGHLIGHT_FIELDS: [(&str, usize); 6] = [
+    ("subject", 200),
+    ("body", 160),
+    ("from", 100),
+    ("to", 100),
+    ("cc", 100),
+    ("filename", 100),
+];
+
+/// Snippet generators for the stored text fields, seeded with the terms of one
+/// query. Attachment text is not stored, so it cannot be highlighted.
+struct Highlighter {
+    generators: Vec<(&'static str, SnippetGenerator)>,
+}
+
+impl Highlighter {
+    fn new(
+        searcher: &tantivy::Searcher,
+        query: &dyn Query,
+        schema: &EmailSchema,
+    ) -> SearchResult<Self> {
+        let mut generators = Vec::new();
+
+        for (name, max_num_chars) in HIGHLIGHT_FIELDS {
+            let field = match name {
+                "subject" => schema.subject,
+                "body" => schema.body,
+                "from" => schema.from,
+                "to" => schema.to,
+                "cc" => schema.cc,
+                _ => schema.filename,
+            };
+
+            let mut generator = SnippetGenerator::create(searcher, query, field)?;
+            generator.set_max_num_chars(max_num_chars);
+            generators.push((name, generator));
+        }
+
+        Ok(Self { generators })
+    }
+
+    fn highlights(&self, doc: &TantivyDocument) -> Vec<SearchHighlight> {
+        self.generators
+            .iter()
+            .filter_map(|(field, generator)| {
+                let mut snippet = generator.snippet_from_doc(doc);
+                if snippet.is_empty() {
+                    return None;
+                }
+
+                snippet.set_snippet_prefix_postfix("<mark>", "</mark>");
+                Some(SearchHighlight {
+                    field: field.to_string(),
+                    snippet: snippet.to_html(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MatchedAttachment {
+    pub id: Uuid,
+    pub filename: String,
+    pub file_type: String,
+}
+
+/// The `filename:` and `type:` terms of a query, as lowercase tokens
+#[derive(Debug, Default, PartialEq)]
+struct AttachmentTerms {
+    filename: Vec<Vec<String>>,
+    file_type: Vec<Vec<String>>,
+}
+
+impl AttachmentTerms {
+    fn from_query(query: &str) -> Self {
+        let mut terms = Self::default();
+        let mut rest = query;
+
+        while let Some(pos) = rest.find(':') {
+            let field_start = rest[..pos]
+                .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map_or(0, |i| i + 1);
+            let field = &rest[field_start..pos];
+            let value_start = &rest[pos + 1..];
+
+            let (value, remaining) = match value_start.strip_prefix('"') {
+                Some(quoted) => match quoted.find('"') {
+                    Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                    None => (quoted, ""),
+                },
+                None => {
+                    let end = value_start
+                        .find(|c: char| c.is_whitespace() || c == ')')
+                        .unwrap_or(value_start.len());
+                    (&value_start[..end], &value_start[end..])
+                }
+            };
+
+            let tokens = tokenize(value);
+            if !tokens.is_empty() {
+                match field {
+                    "filename" => terms.filename.push(tokens),
+                    "type" => terms.file_type.push(tokens),
+                    _ => {}
+                }
+            }
+
+            rest = remaining;
+        }
+
+        terms
+    }
+
+    fn is_empty(&self) -> bool {
+        self.filename.is_empty() && self.file_type.is_empty()
+    }
+
+    /// Whether one attachment satisfies any of the filename terms and any of
+    /// the type terms. Terms of a kind not in the query are ignored.
+    fn matches(&self, filename: &str, file_type: &str) -> bool {
+        let contains_all = |text: &str, terms: &[Vec<String>]| {
+            let tokens = tokenize(text);
+            terms.is_empty()
+                || terms
+                    .iter()
+                    .any(|term| term.iter().all(|t| tokens.contains(t)))
+        };
+
+        contains_all(filename, &self.filename) && contains_all(file_type, &self.file_type)
+    }
+}
+
+/// Splits like Tantivy's default tokenizer: alphanumeric runs, lowercased
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
 }
 
 /// Manages the Tantivy search index for emails
@@ -132,7 +353,9 @@ pub struct SearchManager {
     index: Index,
     schema: EmailSchema,
     writer: Arc<RwLock<IndexWriter>>,
-    reader: tantivy::IndexReader,
+    reader: IndexReader,
+    /// The index was recreated because its schema was outdated
+    needs_reindex: bool,
 }
 
 impl SearchManager {
@@ -141,14 +364,30 @@ impl SearchManager {
         let path = index_path.as_ref();
         std::fs::create_dir_all(path)?;
 
-        let (schema_def, schema) = EmailSchema::build();
+        let (schema_def, _) = EmailSchema::build();
 
+        let mut needs_reindex = false;
         let directory = MmapDirectory::open(path)?;
         let index = if Index::exists(&directory)? {
-            Index::open(directory)?
+            let index = Index::open(directory)?;
+            if EmailSchema::from_schema(&index.schema()).is_ok() {
+                index
+            } else {
+                // Fields can't be added to an existing index, so it is rebuilt
+                log::warn!(
+                    "[Search] Index at {} has an outdated schema, recreating it",
+                    path.display()
+                );
+                drop(index);
+                std::fs::remove_dir_all(path)?;
+                std::fs::create_dir_all(path)?;
+                needs_reindex = true;
+                Index::create(MmapDirectory::open(path)?, schema_def, Default::default())?
+            }
         } else {
-            Index::create(directory, schema_def.clone(), Default::default())?
+            Index::create(directory, schema_def, Default::default())?
         };
+        let schema = EmailSchema::from_schema(&index.schema())?;
 
         let writer = index.writer(50_000_000)?;
         let reader = index
@@ -161,11 +400,28 @@ impl SearchManager {
             schema,
             writer: Arc::new(RwLock::new(writer)),
             reader,
+            needs_reindex,
         })
     }
 
-    pub async fn index_email(&self, email: &Email) -> SearchResult<()> {
-        let doc = self.email_to_document(email)?;
+    /// Whether the index was recreated empty on startup and should be rebuilt
+    pub fn needs_reindex(&self) -> bool {
+        self.needs_reindex
+    }
+
+    /// Number of emails in the index as of the last reload
+    pub fn num_docs(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    /// `attachment_texts` holds the extracted text of attachments, by attachment ID
+    pub async fn index_email(
+        &self,
+        email: &Email,
+        attachments: &[Attachment],
+        attachment_texts: &HashMap<Uuid, String>,
+    ) -> SearchResult<()> {
+        let doc = self.email_to_document(email, attachments, attachment_texts)?;
         let writer = self.writer.write().await;
 
         writer.delete_term(Term::from_field_text(self.schema.id, &email.id.to_string()));
@@ -175,11 +431,17 @@ impl SearchManager {
     }
 
     /// Index multiple emails in batch for better performance
-    pub async fn index_emails_batch(&self, emails: &[Email]) -> SearchResult<()> {
+    pub async fn index_emails_batch(
+        &self,
+        emails: &[Email],
+        attachments: &HashMap<Uuid, Vec<Attachment>>,
+        attachment_texts: &HashMap<Uuid, String>,
+    ) -> SearchResult<()> {
         let writer = self.writer.write().await;
 
         for email in emails {
-            let doc = self.email_to_document(email)?;
+            let email_attachments = attachments.get(&email.id).map_or(&[][..], Vec::as_slice);
+            let doc = self.email_to_document(email, email_attachments, attachment_texts)?;
 
             writer.delete_term(Term::from_field_text(self.schema.id, &email.id.to_string()));
             writer.add_document(doc)?;
@@ -214,68 +476,99 @@ impl SearchManager {
     /// - Fuzzy matching: ~N
     /// - Phrase queries: ""
     /// - Negation: -
+    /// - filename:, type: for attachments (e.g. `filename:invoice.pdf`, `type:spreadsheet`)
+    /// - attachment: for text inside attachments, which plain terms match as well
     pub async fn search(&self, query: SearchQuery) -> SearchResult<Vec<SearchResultItem>> {
-        self.validate_query(&query)?;
+        let attachment_terms = AttachmentTerms::from_query(&query.query);
+        let documents = self.search_documents(&query)?;
+        let highlighter = self.highlighter(&query)?;
 
-        let searcher = self.reader.searcher();
-        let query_parser = QueryParser::for_index(
-            &self.index,
-            vec![
-                self.schema.subject,
-                self.schema.body,
-                self.schema.from,
-                self.schema.to,
-                self.schema.cc,
-                self.schema.received,
-                self.schema.is_read,
-                self.schema.labels,
-            ],
-        );
+        let results = documents
+            .into_iter()
+            .filter_map(|(score, doc)| {
+                let id_str = doc.get_first(self.schema.id)?.as_str()?;
+                let id = Uuid::parse_str(id_str).ok()?;
 
-        let parsed_query = query_parser.parse_query(&query.query)?;
-        let mut filters: Vec<Box<dyn Query>> = vec![Box::new(parsed_query)];
+                Some(SearchResultItem {
+                    id,
+                    score,
+                    matched_attachments: self.matched_attachments(&doc, &attachment_terms),
+                    highlights: highlighter.highlights(&doc),
+                })
+            })
+            .collect();
 
-        if let Some(account_id) = query.account_id {
-            let term = Term::from_field_text(self.schema.account_id, &account_id.to_string());
-            filters.push(Box::new(TermQuery::new(term, IndexRecordOption::Basic)));
-        }
+        Ok(results)
+    }
 
-        if let Some(folder_id) = query.folder_id {
-            let term = Term::from_field_text(self.schema.folder_id, &folder_id.to_string());
-            filters.push(Box::new(TermQuery::new(term, IndexRecordOption::Basic)));
-        }
+    fn highlighter(&self, query: &SearchQuery) -> SearchResult<Highlighter> {
+        let searcher = self.reader.searcher();
+        let final_query = build_query(&self.index, &self.schema, query)?;
+        Highlighter::new(&searcher, final_query.as_ref(), &self.schema)
+    }
 
-        if let Some(conversation_id) = query.conversation_id {
-            let term =
-                Term::from_field_text(self.schema.conversation_id, &conversation_id.to_string());
-            filters.push(Box::new(TermQuery::new(term, IndexRecordOption::Basic)));
+    /// The attachments of a hit that match the query's attachment terms
+    fn matched_attachments(
+        &self,
+        doc: &TantivyDocument,
+        terms: &AttachmentTerms,
+    ) -> Vec<MatchedAttachment> {
+        if terms.is_empty() {
+            return Vec::new();
         }
 
-        let final_query = if filters.len() > 1 {
-            Box::new(BooleanQuery::intersection(filters)) as Box<dyn Query>
-        } else {
-            filters.into_iter().next().unwrap()
-        };
-
-        let limit = query.limit.min(1000);
-        let offset = query.offset;
-        let top_docs = searcher.search(&final_query, &TopDocs::with_limit(limit + offset))?;
+        let values =
+            |field: Field| -> Vec<&str> { doc.get_all(field).filter_map(|v| v.as_str()).collect() };
+        let filenames = values(self.schema.filename);
+        let file_types = values(self.schema.file_type);
 
-        let results: Vec<SearchResultItem> = top_docs
+        values(self.schema.attachment_id)
             .into_iter()
-            .skip(offset)
-            .take(limit)
-            .filter_map(|(score, doc_address)| {
-                let doc: TantivyDocument = searcher.doc(doc_address).ok()?;
-                let id_field = doc.get_first(self.schema.id)?;
-                let id_str = id_field.as_str()?;
-                let id = Uuid::parse_str(id_str).ok()?;
-
-                Some(SearchResultItem { id, score })
+            .zip(filenames)
+            .zip(file_types)
+            .filter(|((_, filename), file_type)| terms.matches(filename, file_type))
+            .filter_map(|((id, filename), file_type)| {
+                Some(MatchedAttachment {
+                    id: Uuid::parse_str(id).ok()?,
+                    filename: filename.to_string(),
+                    file_type: file_type
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                })
             })
-            .collect();
+            .collect()
+    }
 
-        Ok(results)
+    pub(crate) fn schema(&self) -> &EmailSchema {
+        &self.schema
+    }
+
+    /// Validate and run a query, returning the stored documents with their scores
+    pub(crate) fn search_documents(
+        &self,
+        query: &SearchQuery,
+    ) -> SearchResult<Vec<(f32, TantivyDocument)>> {
+        self.validate_query(query)?;
+        execute_query(&self.index, &self.reader, &self.schema, query)
+    }
+
+    /// IDs of all emails matching a query, unranked and ignoring limit and offset
+    pub fn matching_ids(&self, query: &SearchQuery) -> SearchResult<Vec<Uuid>> {
+        self.validate_query(query)?;
+
+        let searcher = self.reader.searcher();
+        let final_query = build_query(&self.index, &self.schema, query)?;
+        let addresses = searcher.search(&final_query, &DocSetCollector)?;
+
+        Ok(addresses
+            .into_iter()
+            .filter_map(|address| {
+                let doc: TantivyDocument = searcher.doc(address).ok()?;
+                Uuid::parse_str(doc.get_first(self.schema.id)?.as_str()?).ok()
+            })
+            .collect())
     }
 
     /// Clear the entire index (use with caution!)
@@ -289,7 +582,12 @@ impl SearchManager {
     /// Convert an Email model to a Tantivy document
     /// Maps email fields to search schema fields for indexing
     /// Properly handles EmailAddress structs by combining address + name
-    fn email_to_document(&self, email: &Email) -> SearchResult<TantivyDocument> {
+    fn email_to_document(
+        &self,
+        email: &Email,
+        attachments: &[Attachment],
+        attachment_texts: &HashMap<Uuid, String>,
+    ) -> SearchResult<TantivyDocument> {
         let mut doc = TantivyDocument::new();
 
         doc.add_text(self.schema.id, email.id.to_string());
@@ -325,6 +623,22 @@ impl SearchManager {
         doc.add_bool(self.schema.is_flagged, email.is_flagged);
         doc.add_bool(self.schema.is_deleted, email.is_deleted);
 
+        // Inline parts are mostly logos and signature images
+        let attachments: Vec<&Attachment> = attachments.iter().filter(|a| !a.is_inline).collect();
+        doc.add_bool(self.schema.has_attachment, !attachments.is_empty());
+
+        for attachment in attachments {
+            doc.add_text(self.schema.attachment_id, attachment.id.to_string());
+            doc.add_text(self.schema.filename, &attachment.filename);
+            doc.add_text(
+                self.schema.file_type,
+                format!("{} {}", attachment.file_type(), attachment.content_type),
+            );
+            if let Some(text) = attachment_texts.get(&attachment.id) {
+                doc.add_text(self.schema.attachment_text, text);
+            }
+        }
+
         Ok(doc)
     }
 
@@ -392,6 +706,81 @@ impl SearchManager {
     }
 }
 
+/// Run a validated query against an index and load the matching stored documents
+pub(crate) fn execute_query(
+    index: &Index,
+    reader: &IndexReader,
+    schema: &EmailSchema,
+    query: &SearchQuery,
+) -> SearchResult<Vec<(f32, TantivyDocument)>> {
+    let searcher = reader.searcher();
+    let final_query = build_query(index, schema, query)?;
+
+    let limit = query.limit.min(1000);
+    let offset = query.offset;
+    let top_docs = searcher.search(&final_query, &TopDocs::with_limit(limit + offset))?;
+
+    Ok(top_docs
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .filter_map(|(score, doc_address)| {
+            let doc: TantivyDocument = searcher.doc(doc_address).ok()?;
+            Some((score, doc))
+        })
+        .collect())
+}
+
+/// Parse the query string and restrict it to the query's account, folder and
+/// conversation
+fn build_query(
+    index: &Index,
+    schema: &EmailSchema,
+    query: &SearchQuery,
+) -> SearchResult<Box<dyn Query>> {
+    let query_parser = QueryParser::for_index(
+        index,
+        vec![
+            schema.subject,
+            schema.body,
+            schema.from,
+            schema.to,
+            schema.cc,
+            schema.received,
+            schema.is_read,
+            schema.labels,
+            schema.filename,
+            schema.file_type,
+            schema.attachment_text,
+        ],
+    );
+
+    let parsed_query =
+        ParsedQuery::parse(&query.query)?.to_query(&query_parser, schema, &query.scope)?;
+    let mut filters: Vec<Box<dyn Query>> = vec![Box::new(parsed_query)];
+
+    if let Some(account_id) = query.account_id {
+        let term = Term::from_field_text(schema.account_id, &account_id.to_string());
+        filters.push(Box::new(TermQuery::new(term, IndexRecordOption::Basic)));
+    }
+
+    if let Some(folder_id) = query.folder_id {
+        let term = Term::from_field_text(schema.folder_id, &folder_id.to_string());
+        filters.push(Box::new(TermQuery::new(term, IndexRecordOption::Basic)));
+    }
+
+    if let Some(conversation_id) = query.conversation_id {
+        let term = Term::from_field_text(schema.conversation_id, &conversation_id.to_string());
+        filters.push(Box::new(TermQuery::new(term, IndexRecordOption::Basic)));
+    }
+
+    Ok(if filters.len() > 1 {
+        Box::new(BooleanQuery::intersection(filters))
+    } else {
+        filters.into_iter().next().unwrap()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -416,6 +805,7 @@ mod tests {
             conversation_id: None,
             limit: 50,
             offset: 0,
+            scope: QueryScope::default(),
         };
 
         let result = search_manager.validate_query(&query);
@@ -440,6 +830,7 @@ mod tests {
             conversation_id: None,
             limit: 50,
             offset: 0,
+            scope: QueryScope::default(),
         };
 
         let result = search_manager.validate_query(&query);
@@ -458,6 +849,7 @@ mod tests {
             conversation_id: None,
             limit: 50,
             offset: 0,
+            scope: QueryScope::default(),
         };
 
         let result = search_manager.validate_query(&query);
@@ -476,6 +868,7 @@ mod tests {
             conversation_id: None,
             limit: 1001,
             offset: 0,
+            scope: QueryScope::default(),
         };
 
         let result = search_manager.validate_query(&query);
@@ -494,9 +887,69 @@ mod tests {
             conversation_id: None,
             limit: 50,
             offset: 10001,
+            scope: QueryScope::default(),
         };
 
         let result = search_manager.validate_query(&query);
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_highlights() {
+        let temp_dir = TempDir::new().unwrap();
+        let search_manager = SearchManager::new(temp_dir.path()).unwrap();
+        let schema = &search_manager.schema;
+
+        let mut doc = TantivyDocument::default();
+        doc.add_text(schema.id, Uuid::now_v7().to_string());
+        doc.add_text(schema.subject, "Budget <draft> for Q3");
+        doc.add_text(schema.body, "Please review the attached numbers.");
+        doc.add_text(schema.from, "alice@example.com Alice");
+        search_manager
+            .writer
+            .write()
+            .await
+            .add_document(doc.clone())
+            .unwrap();
+        search_manager.commit().await.unwrap();
+        search_manager.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "budget from:alice".to_string(),
+            account_id: None,
+            folder_id: None,
+            conversation_id: None,
+            limit: 50,
+            offset: 0,
+            scope: QueryScope::default(),
+        };
+
+        let highlights = search_manager.highlighter(&query).unwrap().highlights(&doc);
+        assert_eq!(
+            highlights,
+            vec![
+                SearchHighlight {
+                    field: "subject".to_string(),
+                    snippet: "<mark>Budget</mark> &lt;draft&gt; for Q3".to_string(),
+                },
+                SearchHighlight {
+                    field: "from".to_string(),
+                    snippet: "<mark>alice</mark>@example.com <mark>Alice</mark>".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attachment_terms_from_query() {
+        let terms = AttachmentTerms::from_query(
+            r#"from:alice filename:"Q3 Invoice.pdf" (type:spreadsheet OR subject:x)"#,
+        );
+
+        assert_eq!(terms.filename, vec![vec!["q3", "invoice", "pdf"]]);
+        assert_eq!(terms.file_type, vec![vec!["spreadsheet"]]);
+        assert!(terms.matches("Q3-invoice.PDF", "spreadsheet application/pdf"));
+        assert!(!terms.matches("invoice.pdf", "pdf application/pdf"));
+        assert!(AttachmentTerms::from_query("subject:invoice").is_empty());
+    }
 }
@@ -0,0 +1,115 @@
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::config::Settings;
+use crate::database::models::email::Email;
+use crate::database::repositories::{LabelRepository, SqliteLabelRepository};
+
+const EXCLUDED_FOLDERS_SETTING: &str = "ai.exclude.folders";
+const EXCLUDED_LABELS_SETTING: &str = "ai.exclude.labels";
+
+/// Folders and labels whose emails are never sent to the AI provider
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AiExclusions {
+    pub folders: HashSet<Uuid>,
+    pub labels: HashSet<Uuid>,
+}
+
+impl AiExclusions {
+    /// Invalid IDs are left out, so a broken entry does not block all AI features
+    pub fn from_settings(settings: &Settings) -> Self {
+        let ids = |key: &str| -> HashSet<Uuid> {
+            settings
+                .get::<Vec<String>>(key)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|value| match Uuid::parse_str(value) {
+                    Ok(id) => Some(id),
+                    Err(e) => {
+                        log::warn!("Ignoring invalid ID {} in {}: {}", value, key, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+
+        Self {
+            folders: ids(EXCLUDED_FOLDERS_SETTING),
+            labels: ids(EXCLUDED_LABELS_SETTING),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty() && self.labels.is_empty()
+    }
+
+    /// Excluded folder IDs as a JSON array, for `json_each` in queries
+    pub fn folders_json(&self) -> String {
+        serde_json::to_string(&self.folders).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Excluded label IDs as a JSON array, for `json_each` in queries
+    pub fn labels_json(&self) -> String {
+        serde_json::to_string(&self.labels).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Keeps the emails the AI may see, in their order
+    pub async fn retain_allowed(
+        &self,
+        pool: &SqlitePool,
+        emails: Vec<Email>,
+    ) -> Result<Vec<Email>, String> {
+        if self.is_empty() {
+            return Ok(emails);
+        }
+
+        let labels = if self.labels.is_empty() {
+            Default::default()
+        } else {
+            let email_ids: Vec<Uuid> = emails.iter().map(|email| email.id).collect();
+            SqliteLabelRepository::new(pool.clone())
+                .find_by_emails(&email_ids)
+                .await
+                .map_err(|e| format!("Failed to fetch labels: {}", e))?
+        };
+
+        Ok(emails
+            .into_iter()
+            .filter(|email| {
+                let label_ids = labels
+                    .get(&email.id)
+                    .into_iter()
+                    .flatten()
+                    .map(|label| label.id);
+                !self.excludes(email.folder_id, label_ids)
+            })
+            .collect())
+    }
+
+    /// Whether an email in `folder_id` carrying `label_ids` is excluded
+    pub fn excludes(&self, folder_id: Uuid, mut label_ids: impl Iterator<Item = Uuid>) -> bool {
+        self.folders.contains(&folder_id) || label_ids.any(|id| self.labels.contains(&id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excludes() {
+        let excluded_folder = Uuid::now_v7();
+        let excluded_label = Uuid::now_v7();
+        let exclusions = AiExclusions {
+            folders: HashSet::from([excluded_folder]),
+            labels: HashSet::from([excluded_label]),
+        };
+
+        let other = Uuid::now_v7();
+        assert!(exclusions.excludes(excluded_folder, std::iter::empty()));
+        assert!(exclusions.excludes(other, [other, excluded_label].into_iter()));
+        assert!(!exclusions.excludes(other, [other].into_iter()));
+        assert!(AiExclusions::default().is_empty());
+    }
+}
@@ -0,0 +1,630 @@
+//! Application lock
+//!
+//! With a passphrase set the app starts locked and locks again after the
+//! configured time without user activity. While locked, commands other than
+//! the few the lock screen needs are rejected, so no mail data reaches the
+//! webviews. Touch ID or Windows Hello can unlock instead of the passphrase
+//! where the system offers them. Repeated wrong passphrases lock out further
+//! attempts for a growing time, which survives restarts.
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::time::sleep;
+
+use crate::config::Settings;
+use crate::state::AppState;
+
+/// Minutes without activity before the app locks itself, 0 to never
+pub const AUTO_LOCK_SETTING: &str = "security.autoLockMinutes";
+/// Error of commands rejected while the app is locked
+pub const LOCKED_ERROR: &str = "The app is locked";
+
+const POLL_INTERVAL_SECS: u64 = 15;
+const PASSPHRASE_FILE: &str = ".ravn_lock";
+const ATTEMPTS_FILE: &str = ".ravn_lock_attempts";
+/// Wrong passphrases allowed before attempts are locked out
+const FREE_ATTEMPTS: u32 = 5;
+const FIRST_LOCKOUT_SECS: u64 = 30;
+const MAX_LOCKOUT_SECS: u64 = 60 * 60;
+
+/// Commands answered while locked: the lock screen and the theme it is
+/// drawn in, none of which return mail data
+const UNLOCKED_COMMANDS: &[&str] = &[
+    "get_lock_status",
+    "lock",
+    "unlock",
+    "unlock_with_biometrics",
+    "record_activity",
+    "get_current_theme",
+    "get_theme",
+    "get_theme_mode",
+    "list_themes",
+];
+
+/// Settings commands let through while locked, which then only return the
+/// settings below `UNLOCKED_SETTINGS`
+const SETTINGS_COMMANDS: &[&str] = &["get_setting", "get_all_settings"];
+
+/// Top-level settings the lock screen needs to render
+const UNLOCKED_SETTINGS: &[&str] = &["appearance"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LockStatus {
+    /// A passphrase is set
+    pub enabled: bool,
+    pub locked: bool,
+    /// Touch ID or Windows Hello can unlock
+    pub biometrics_available: bool,
+    pub auto_lock_minutes: u64,
+}
+
+/// Whether the app is locked. Managed on its own so anything holding an
+/// `AppHandle` can check it, see `emit_mail_event`.
+#[derive(Debug, Default)]
+pub struct LockState(AtomicBool);
+
+impl LockState {
+    pub fn new(locked: bool) -> Self {
+        Self(AtomicBool::new(locked))
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Set the state, returning the previous one
+    fn swap(&self, locked: bool) -> bool {
+        self.0.swap(locked, Ordering::SeqCst)
+    }
+}
+
+/// Wrong passphrases since the last successful unlock
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct FailedAttempts {
+    count: u32,
+    /// No passphrase is checked before this
+    locked_until: Option<DateTime<Utc>>,
+}
+
+pub struct AppLock {
+    settings: Arc<Settings>,
+    app_handle: AppHandle,
+    passphrase_path: PathBuf,
+    attempts_path: PathBuf,
+    locked: Arc<LockState>,
+    failed_attempts: Mutex<FailedAttempts>,
+    last_activity: Mutex<Instant>,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+}
+
+impl AppLock {
+    /// Starts locked when a passphrase was set
+    pub fn new(settings: Arc<Settings>, app_handle: AppHandle, app_data_dir: &Path) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+        let passphrase_path = app_data_dir.join(PASSPHRASE_FILE);
+        let attempts_path = app_data_dir.join(ATTEMPTS_FILE);
+        let locked = Arc::new(LockState::new(passphrase_path.exists()));
+        app_handle.manage(Arc::clone(&locked));
+
+        let failed_attempts = std::fs::read_to_string(&attempts_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            settings,
+            app_handle,
+            passphrase_path,
+            attempts_path,
+            locked,
+            failed_attempts: Mutex::new(failed_attempts),
+            last_activity: Mutex::new(Instant::now()),
+            shutdown_tx,
+        }
+    }
+
+    /// Locks the app once it was idle for the configured time
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[AppLock] Starting auto-lock");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                if this.is_idle() {
+                    log::info!("[AppLock] Locking after inactivity");
+                    this.lock();
+                }
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[AppLock] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[AppLock] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.passphrase_path.exists()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.is_locked()
+    }
+
+    pub fn status(&self) -> LockStatus {
+        LockStatus {
+            enabled: self.is_enabled(),
+            locked: self.is_locked(),
+            biometrics_available: biometrics::is_available(),
+            auto_lock_minutes: self.auto_lock_minutes(),
+        }
+    }
+
+    /// Whether `command` is rejected in the current state
+    pub fn blocks(&self, command: &str) -> bool {
+        self.is_locked()
+            && !UNLOCKED_COMMANDS.contains(&command)
+            && !SETTINGS_COMMANDS.contains(&command)
+    }
+
+    /// Whether the setting `key` can be read in the current state
+    pub fn can_read_setting(&self, key: &str) -> bool {
+        !self.is_locked() || is_unlocked_setting(key)
+    }
+
+    /// All settings, or while locked only the ones the lock screen needs
+    pub fn readable_settings(&self, settings: JsonValue) -> JsonValue {
+        if self.is_locked() {
+            unlocked_settings(settings)
+        } else {
+            settings
+        }
+    }
+
+    /// Note user input, which postpones the auto-lock
+    pub fn record_activity(&self) {
+        if let Ok(mut last_activity) = self.last_activity.lock() {
+            *last_activity = Instant::now();
+        }
+    }
+
+    /// Lock the app and tell the windows to show the lock screen
+    pub fn lock(&self) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        if !self.locked.swap(true) {
+            if let Err(e) = self.app_handle.emit("app:locked", ()) {
+                log::warn!("[AppLock] Failed to emit app:locked: {}", e);
+            }
+        }
+        true
+    }
+
+    /// Unlock with the passphrase
+    pub fn unlock(&self, passphrase: &str) -> Result<(), String> {
+        self.check_passphrase(passphrase)?;
+        self.set_unlocked();
+        Ok(())
+    }
+
+    /// Unlock after Touch ID or Windows Hello verified the user
+    pub async fn unlock_with_biometrics(&self, reason: &str) -> Result<(), String> {
+        if !biometrics::verify(reason).await? {
+            return Err("Biometric verification failed".to_string());
+        }
+        self.set_unlocked();
+        Ok(())
+    }
+
+    /// Set, change or with `None` remove the passphrase. Changing or removing
+    /// one requires the current passphrase.
+    pub fn set_passphrase(&self, current: Option<&str>, new: Option<&str>) -> Result<(), String> {
+        if self.is_enabled() {
+            self.check_passphrase(current.unwrap_or_default())?;
+        }
+
+        match new {
+            Some(passphrase) if passphrase.chars().count() < 4 => {
+                Err("The passphrase needs at least 4 characters".to_string())
+            }
+            Some(passphrase) => {
+                write_private(
+                    &self.passphrase_path,
+                    hash_passphrase(passphrase)?.as_bytes(),
+                )?;
+                self.record_activity();
+                log::info!("[AppLock] Passphrase set");
+                Ok(())
+            }
+            None if !self.is_enabled() => Ok(()),
+            None => {
+                std::fs::remove_file(&self.passphrase_path)
+                    .map_err(|e| format!("Failed to remove passphrase: {}", e))?;
+                self.set_unlocked();
+                log::info!("[AppLock] Passphrase removed");
+                Ok(())
+            }
+        }
+    }
+
+    /// Verify `passphrase`, refusing to while locked out after too many
+    /// wrong ones
+    fn check_passphrase(&self, passphrase: &str) -> Result<(), String> {
+        let mut attempts = self
+            .failed_attempts
+            .lock()
+            .map_err(|_| "Failed to read failed attempts".to_string())?;
+
+        let now = Utc::now();
+        if let Some(wait) = attempts
+            .locked_until
+            .and_then(|until| (until - now).to_std().ok())
+        {
+            return Err(format!(
+                "Too many wrong passphrases, try again in {} seconds",
+                wait.as_secs().max(1)
+            ));
+        }
+
+        let hash = std::fs::read_to_string(&self.passphrase_path)
+            .map_err(|e| format!("Failed to read passphrase: {}", e))?;
+        if verify_passphrase(passphrase, hash.trim()) {
+            if attempts.count > 0 {
+                *attempts = FailedAttempts::default();
+                self.save_failed_attempts(&attempts);
+            }
+            return Ok(());
+        }
+
+        attempts.count += 1;
+        let lockout = lockout_duration(attempts.count);
+        attempts.locked_until =
+            (!lockout.is_zero()).then(|| now + chrono::Duration::seconds(lockout.as_secs() as i64));
+        self.save_failed_attempts(&attempts);
+        log::warn!(
+            "[AppLock] Wrong passphrase, {} failed attempts",
+            attempts.count
+        );
+
+        Err("Wrong passphrase".to_string())
+    }
+
+    fn save_failed_attempts(&self, attempts: &FailedAttempts) {
+        let result = if attempts.count == 0 {
+            match std::fs::remove_file(&self.attempts_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(format!("Failed to remove {:?}: {}", self.attempts_path, e))
+                }
+                _ => Ok(()),
+            }
+        } else {
+            serde_json::to_vec(attempts)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| write_private(&self.attempts_path, &contents))
+        };
+
+        if let Err(e) = result {
+            log::warn!("[AppLock] Failed to save failed attempts: {}", e);
+        }
+    }
+
+    fn set_unlocked(&self) {
+        self.record_activity();
+        if let Ok(mut attempts) = self.failed_attempts.lock() {
+            if attempts.count > 0 {
+                *attempts = FailedAttempts::default();
+                self.save_failed_attempts(&attempts);
+            }
+        }
+        if self.locked.swap(false) {
+            if let Err(e) = self.app_handle.emit("app:unlocked", ()) {
+                log::warn!("[AppLock] Failed to emit app:unlocked: {}", e);
+            }
+        }
+    }
+
+    fn auto_lock_minutes(&self) -> u64 {
+        self.settings.get::<u64>(AUTO_LOCK_SETTING).unwrap_or(0)
+    }
+
+    fn is_idle(&self) -> bool {
+        let minutes = self.auto_lock_minutes();
+        if minutes == 0 || !self.is_enabled() || self.is_locked() {
+            return false;
+        }
+
+        self.last_activity
+            .lock()
+            .map(|last_activity| last_activity.elapsed() >= Duration::from_secs(minutes * 60))
+            .unwrap_or(false)
+    }
+}
+
+/// Whether `key` is a setting the lock screen needs
+fn is_unlocked_setting(key: &str) -> bool {
+    key.split('.')
+        .next()
+        .is_some_and(|section| UNLOCKED_SETTINGS.contains(&section))
+}
+
+/// Only the settings the lock screen needs
+fn unlocked_settings(mut settings: JsonValue) -> JsonValue {
+    if let JsonValue::Object(sections) = &mut settings {
+        sections.retain(|section, _| is_unlocked_setting(section));
+    }
+    settings
+}
+
+/// How long attempts are locked out after `failures` wrong passphrases in a
+/// row: nothing for the first few, then doubling up to an hour
+fn lockout_duration(failures: u32) -> Duration {
+    if failures < FREE_ATTEMPTS {
+        return Duration::ZERO;
+    }
+    let doublings = (failures - FREE_ATTEMPTS).min(16);
+    Duration::from_secs((FIRST_LOCKOUT_SECS << doublings).min(MAX_LOCKOUT_SECS))
+}
+
+/// Whether the app behind `app_handle` is locked
+pub fn is_app_locked<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
+    app_handle
+        .try_state::<Arc<LockState>>()
+        .is_some_and(|state| state.is_locked())
+}
+
+/// Emit an event carrying mail data to the webviews, unless the app is
+/// locked. Windows reload when unlocked, so the dropped events are made up
+/// for by what they load then.
+pub fn emit_mail_event<R: Runtime, S: Serialize + Clone>(
+    app_handle: &AppHandle<R>,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    if is_app_locked(app_handle) {
+        log::debug!("[AppLock] Not emitting {} while locked", event);
+        return Ok(());
+    }
+
+    app_handle.emit(event, payload)
+}
+
+/// Wrap the app's command handler so commands are rejected while the app is
+/// locked
+pub fn guard<R, F>(handler: F) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static
+where
+    R: Runtime,
+    F: Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+{
+    move |invoke| {
+        let blocked = invoke
+            .message
+            .webview_ref()
+            .try_state::<AppState>()
+            .is_some_and(|state| state.app_lock.blocks(invoke.message.command()));
+        if blocked {
+            invoke.resolver.reject(LOCKED_ERROR);
+            return true;
+        }
+
+        handler(invoke)
+    }
+}
+
+/// PHC string of the Argon2 hash of `passphrase`
+fn hash_passphrase(passphrase: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash passphrase: {}", e))
+}
+
+fn verify_passphrase(passphrase: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(passphrase.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Write a file only the user can read
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to set permissions of {:?}: {}", path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Touch ID on macOS, Windows Hello on Windows
+mod biometrics {
+    #[cfg(target_os = "macos")]
+    pub fn is_available() -> bool {
+        use objc2_local_authentication::{LAContext, LAPolicy};
+
+        unsafe {
+            LAContext::new()
+                .canEvaluatePolicy_error(LAPolicy::DeviceOwnerAuthenticationWithBiometrics)
+                .is_ok()
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    pub async fn verify(reason: &str) -> Result<bool, String> {
+        use block2::RcBlock;
+        use objc2::runtime::Bool;
+        use objc2_foundation::{NSError, NSString};
+        use objc2_local_authentication::{LAContext, LAPolicy};
+        use std::sync::Mutex;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let tx = Mutex::new(Some(tx));
+        let reply = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+            if let Some(tx) = tx.lock().ok().and_then(|mut tx| tx.take()) {
+                let _ = tx.send(success.as_bool());
+            }
+        });
+
+        // Freeing the context cancels the evaluation, so it is kept until the
+        // reply arrived
+        let context = unsafe {
+            let context = LAContext::new();
+            context.evaluatePolicy_localizedReason_reply(
+                LAPolicy::DeviceOwnerAuthenticationWithBiometrics,
+                &NSString::from_str(reason),
+                &reply,
+            );
+            context
+        };
+
+        let result = rx.await.map_err(|_| "Touch ID did not answer".to_string());
+        drop(context);
+        result
+    }
+
+    #[cfg(windows)]
+    pub fn is_available() -> bool {
+        use windows::Security::Credentials::UI::{
+            UserConsentVerifier, UserConsentVerifierAvailability,
+        };
+
+        UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|operation| operation.get())
+            .is_ok_and(|availability| availability == UserConsentVerifierAvailability::Available)
+    }
+
+    #[cfg(windows)]
+    pub async fn verify(reason: &str) -> Result<bool, String> {
+        use windows::core::HSTRING;
+        use windows::Security::Credentials::UI::{
+            UserConsentVerificationResult, UserConsentVerifier,
+        };
+
+        let reason = HSTRING::from(reason);
+        tokio::task::spawn_blocking(move || {
+            UserConsentVerifier::RequestVerificationAsync(&reason)
+                .and_then(|operation| operation.get())
+                .map(|result| result == UserConsentVerificationResult::Verified)
+                .map_err(|e| format!("Windows Hello failed: {}", e))
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn is_available() -> bool {
+        false
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub async fn verify(_reason: &str) -> Result<bool, String> {
+        Err("Biometric unlock is not available on this system".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passphrase_hash() {
+        let hash = hash_passphrase("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_passphrase("correct horse", &hash));
+        assert!(!verify_passphrase("wrong horse", &hash));
+        assert!(!verify_passphrase("correct horse", "not a hash"));
+    }
+
+    #[test]
+    fn test_unlocked_settings() {
+        assert!(is_unlocked_setting("appearance.theme"));
+        assert!(!is_unlocked_setting("ai.api.key"));
+        assert!(!is_unlocked_setting("appearanceX"));
+
+        let settings = serde_json::json!({
+            "ai": { "api": { "key": "secret" } },
+            "appearance": { "theme": "builtin/dark.css" },
+        });
+        assert_eq!(
+            unlocked_settings(settings),
+            serde_json::json!({ "appearance": { "theme": "builtin/dark.css" } })
+        );
+    }
+
+    #[test]
+    fn test_lockout_duration() {
+        assert_eq!(lockout_duration(1), Duration::ZERO);
+        assert_eq!(lockout_duration(FREE_ATTEMPTS - 1), Duration::ZERO);
+        assert_eq!(lockout_duration(FREE_ATTEMPTS), Duration::from_secs(30));
+        assert_eq!(
+            lockout_duration(FREE_ATTEMPTS + 2),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            lockout_duration(FREE_ATTEMPTS + 100),
+            Duration::from_secs(MAX_LOCKOUT_SECS)
+        );
+    }
+
+    #[test]
+    fn test_mail_events_dropped_while_locked() {
+        use std::sync::atomic::AtomicUsize;
+        use tauri::Listener;
+
+        let app = tauri::test::mock_app();
+        let app_handle = app.handle().clone();
+        let lock_state = Arc::new(LockState::new(true));
+        app_handle.manage(Arc::clone(&lock_state));
+
+        let received = Arc::new(AtomicUsize::new(0));
+        for event in ["email:created", "emails:updated"] {
+            let received = Arc::clone(&received);
+            app_handle.listen_any(event, move |_| {
+                received.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        let emit_all = || {
+            emit_mail_event(&app_handle, "email:created", "id").unwrap();
+            crate::sync::events::emit_event(&app_handle, "emails:updated", "id");
+        };
+
+        emit_all();
+        assert_eq!(received.load(Ordering::SeqCst), 0);
+
+        lock_state.swap(false);
+        emit_all();
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+    }
+}
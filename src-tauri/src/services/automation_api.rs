@@ -0,0 +1,683 @@
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+use crate::commands::emails::{SaveDraftRequest, SendFromAccountRequest};
+use crate::config::Settings;
+use crate::database::models::email::EmailAddress;
+use crate::database::models::signature::SignatureChoice;
+use crate::services::app_lock::LOCKED_ERROR;
+use crate::state::AppState;
+
+const DEFAULT_PORT: u16 = 7787;
+const TOKEN_FILE: &str = "automation_token";
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_LIMIT: usize = 25;
+const MAX_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub allow_send: bool,
+    pub token: String,
+}
+
+/// Message composed through the automation API
+#[derive(Debug, Clone, Deserialize)]
+struct ComposeRequest {
+    account_id: Uuid,
+    to: Vec<EmailAddress>,
+    #[serde(default)]
+    cc: Vec<EmailAddress>,
+    #[serde(default)]
+    bcc: Vec<EmailAddress>,
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    body: String,
+}
+
+#[derive(Debug)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    fn query_limit(&self) -> usize {
+        self.query
+            .get("limit")
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(DEFAULT_LIMIT)
+            .min(MAX_LIMIT)
+    }
+
+    fn query_uuid(&self, key: &str) -> Result<Option<Uuid>, HttpResponse> {
+        self.query
+            .get(key)
+            .map(|value| Uuid::parse_str(value))
+            .transpose()
+            .map_err(|_| HttpResponse::error(400, &format!("Invalid '{}'", key)))
+    }
+
+    fn json<T: for<'de> Deserialize<'de>>(&self) -> Result<T, HttpResponse> {
+        serde_json::from_slice(&self.body)
+            .map_err(|e| HttpResponse::error(400, &format!("Invalid request body: {}", e)))
+    }
+}
+
+#[derive(Debug)]
+struct HttpResponse {
+    status: u16,
+    body: JsonValue,
+}
+
+impl HttpResponse {
+    fn ok<T: Serialize>(value: T) -> Self {
+        match serde_json::to_value(value) {
+            Ok(body) => Self { status: 200, body },
+            Err(e) => Self::error(500, &e.to_string()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let body = self.body.to_string();
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            body.len(),
+            body
+        )
+        .into_bytes()
+    }
+}
+
+/// Local HTTP API that lets scripts and launchers (Raycast, Alfred, ...) search mail,
+/// list unread messages, create drafts and send mail.
+///
+/// The server only listens on 127.0.0.1, requires a bearer token stored in the app data
+/// directory and rejects browser requests, so other websites cannot reach it.
+pub struct AutomationApi {
+    app_handle: AppHandle,
+    settings: Arc<Settings>,
+    token_path: PathBuf,
+    token: RwLock<Option<String>>,
+    /// Port the server is listening on, if it is running
+    bound_port: RwLock<Option<u16>>,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+}
+
+impl AutomationApi {
+    pub fn new(app_handle: AppHandle, settings: Arc<Settings>, app_data_dir: &Path) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            app_handle,
+            settings,
+            token_path: app_data_dir.join(TOKEN_FILE),
+            token: RwLock::new(None),
+            bound_port: RwLock::new(None),
+            shutdown_tx,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings
+            .get::<bool>("automation.enabled")
+            .unwrap_or(false)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.settings
+            .get::<u16>("automation.port")
+            .unwrap_or(DEFAULT_PORT)
+    }
+
+    fn bound_port(&self) -> Option<u16> {
+        self.bound_port.read().ok().and_then(|port| *port)
+    }
+
+    fn allow_send(&self) -> bool {
+        self.settings
+            .get::<bool>("automation.allowSend")
+            .unwrap_or(false)
+    }
+
+    pub fn status(&self) -> Result<AutomationApiStatus, String> {
+        Ok(AutomationApiStatus {
+            enabled: self.is_enabled(),
+            running: self.bound_port().is_some(),
+            port: self.port(),
+            allow_send: self.allow_send(),
+            token: self.token()?,
+        })
+    }
+
+    /// Returns the access token, creating one on first use
+    pub fn token(&self) -> Result<String, String> {
+        if let Some(token) = self.token.read().ok().and_then(|t| t.clone()) {
+            return Ok(token);
+        }
+
+        let token = match std::fs::read_to_string(&self.token_path) {
+            Ok(token) if !token.trim().is_empty() => token.trim().to_string(),
+            _ => return self.regenerate_token(),
+        };
+
+        if let Ok(mut cached) = self.token.write() {
+            *cached = Some(token.clone());
+        }
+        Ok(token)
+    }
+
+    /// Replaces the access token, invalidating the previous one
+    pub fn regenerate_token(&self) -> Result<String, String> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+
+        write_private_file(&self.token_path, &token)
+            .map_err(|e| format!("Failed to store automation token: {}", e))?;
+
+        if let Ok(mut cached) = self.token.write() {
+            *cached = Some(token.clone());
+        }
+        Ok(token)
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        if !self.is_enabled() {
+            log::info!("[AutomationApi] Disabled in settings");
+            return Ok(());
+        }
+
+        self.token()?;
+
+        let port = self.port();
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| format!("Failed to bind 127.0.0.1:{}: {}", port, e))?;
+
+        log::info!("[AutomationApi] Listening on 127.0.0.1:{}", port);
+        if let Ok(mut bound_port) = self.bound_port.write() {
+            *bound_port = Some(port);
+        }
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[AutomationApi] Shutdown signal received");
+                        break;
+                    }
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => {
+                            let this = Arc::clone(&this);
+                            tokio::spawn(async move {
+                                if tokio::time::timeout(REQUEST_TIMEOUT, this.handle_connection(stream))
+                                    .await
+                                    .is_err()
+                                {
+                                    log::debug!("[AutomationApi] Request timed out");
+                                }
+                            });
+                        }
+                        Err(e) => log::warn!("[AutomationApi] Failed to accept connection: {}", e),
+                    }
+                }
+            }
+
+            if let Ok(mut bound_port) = this.bound_port.write() {
+                *bound_port = None;
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[AutomationApi] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    async fn handle_connection(&self, mut stream: TcpStream) {
+        let response = match read_request(&mut stream).await {
+            Ok(request) => self.handle_request(request).await,
+            Err(response) => response,
+        };
+
+        if let Err(e) = stream.write_all(&response.to_bytes()).await {
+            log::debug!("[AutomationApi] Failed to write response: {}", e);
+        }
+        let _ = stream.shutdown().await;
+    }
+
+    async fn handle_request(&self, request: HttpRequest) -> HttpResponse {
+        if !self.is_enabled() {
+            return HttpResponse::error(503, "Automation API is disabled");
+        }
+
+        // Browsers always send Origin on cross-site requests; scripts do not
+        if request.header("origin").is_some() {
+            return HttpResponse::error(403, "Browser requests are not allowed");
+        }
+        let port = self.bound_port().unwrap_or_else(|| self.port());
+        if !is_local_host(request.header("host"), port) {
+            return HttpResponse::error(403, "Invalid Host header");
+        }
+
+        let token = match self.token() {
+            Ok(token) => token,
+            Err(e) => return HttpResponse::error(500, &e),
+        };
+        let authorized = request
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|provided| constant_time_eq(provided.trim(), &token));
+        if !authorized {
+            return HttpResponse::error(401, "Missing or invalid token");
+        }
+
+        let locked = self
+            .app_handle
+            .try_state::<AppState>()
+            .is_some_and(|state| state.app_lock.is_locked());
+        if let Some(response) = locked_response(locked) {
+            return response;
+        }
+
+        log::debug!("[AutomationApi] {} {}", request.method, request.path);
+
+        let result = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/v1/accounts") => self.list_accounts().await,
+            ("GET", "/v1/search") => self.search(&request).await,
+            ("GET", "/v1/unread") => self.list_unread(&request).await,
+            ("POST", "/v1/drafts") => self.create_draft(&request).await,
+            ("POST", "/v1/send") => self.send(&request).await,
+            (_, "/v1/accounts" | "/v1/search" | "/v1/unread" | "/v1/drafts" | "/v1/send") => {
+                Err(HttpResponse::error(405, "Method not allowed"))
+            }
+            _ => Err(HttpResponse::error(404, "Not found")),
+        };
+
+        result.unwrap_or_else(|response| response)
+    }
+
+    async fn list_accounts(&self) -> Result<HttpResponse, HttpResponse> {
+        let accounts = crate::commands::emails::get_accounts_for_sending(self.state())
+            .await
+            .map_err(|e| HttpResponse::error(500, &e))?;
+        Ok(HttpResponse::ok(accounts))
+    }
+
+    async fn search(&self, request: &HttpRequest) -> Result<HttpResponse, HttpResponse> {
+        let query = request
+            .query
+            .get("q")
+            .filter(|q| !q.trim().is_empty())
+            .cloned()
+            .ok_or_else(|| HttpResponse::error(400, "Missing 'q' parameter"))?;
+
+        let results = crate::commands::search::search_emails(
+            self.state(),
+            query,
+            request.query_uuid("account_id")?,
+            request.query_uuid("folder_id")?,
+            Some(request.query_limit()),
+            None,
+        )
+        .await
+        .map_err(|e| HttpResponse::error(500, &e))?;
+        Ok(HttpResponse::ok(results))
+    }
+
+    async fn list_unread(&self, request: &HttpRequest) -> Result<HttpResponse, HttpResponse> {
+        let account_id = request.query_uuid("account_id")?;
+        let limit = request.query_limit();
+        // Over-fetch when filtering by account so the limit still applies per account
+        let fetch_limit = if account_id.is_some() {
+            MAX_LIMIT
+        } else {
+            limit
+        };
+
+        let mut inbox = crate::commands::emails::get_unified_inbox(
+            self.state(),
+            Some(fetch_limit as i64),
+            None,
+            None,
+            None,
+            Some(false),
+        )
+        .await
+        .map_err(|e| HttpResponse::error(500, &e))?;
+
+        if let Some(account_id) = account_id {
+            inbox.emails.retain(|email| email.account_id == account_id);
+        }
+        inbox.emails.truncate(limit);
+
+        Ok(HttpResponse::ok(json!({
+            "emails": inbox.emails,
+            "unread": inbox.unread,
+            "accounts": inbox.accounts,
+        })))
+    }
+
+    async fn create_draft(&self, request: &HttpRequest) -> Result<HttpResponse, HttpResponse> {
+        let compose: ComposeRequest = request.json()?;
+
+        let response = crate::commands::emails::save_draft(
+            self.state(),
+            SaveDraftRequest {
+                account_id: compose.account_id,
+                draft_id: None,
+                to: compose.to,
+                cc: compose.cc,
+                bcc: compose.bcc,
+                subject: compose.subject,
+                body: compose.body,
+                scheduled_send_at: None,
+                conversation_id: None,
+                in_reply_to: None,
+                references: None,
+                identity_id: None,
+                signature: SignatureChoice::Auto,
+            },
+        )
+        .await
+        .map_err(|e| HttpResponse::error(500, &e))?;
+        Ok(HttpResponse::ok(response))
+    }
+
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpResponse> {
+        if !self.allow_send() {
+            return Err(HttpResponse::error(
+                403,
+                "Sending is disabled; enable 'automation.allowSend' in settings",
+            ));
+        }
+
+        let compose: ComposeRequest = request.json()?;
+        if compose.to.is_empty() {
+            return Err(HttpResponse::error(
+                400,
+                "At least one recipient is required",
+            ));
+        }
+
+        let response = crate::commands::emails::send_email_from_account(
+            self.state(),
+            SendFromAccountRequest {
+                account_id: compose.account_id,
+                to: compose.to,
+                cc: compose.cc,
+                bcc: compose.bcc,
+                subject: compose.subject,
+                body: compose.body,
+                attachments: Vec::new(),
+                draft_id: None,
+                conversation_id: None,
+                in_reply_to: None,
+                references: None,
+                identity_id: None,
+                dsn: None,
+                read_receipt: false,
+                smime: None,
+                signature: SignatureChoice::Auto,
+            },
+        )
+        .await
+        .map_err(|e| HttpResponse::error(500, &e))?;
+        Ok(HttpResponse::ok(response))
+    }
+
+    fn state(&self) -> tauri::State<'_, AppState> {
+        self.app_handle.state::<AppState>()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<HttpRequest, HttpResponse> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+
+    let head_end = loop {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| HttpResponse::error(400, "Failed to read request"))?;
+        if read == 0 {
+            return Err(HttpResponse::error(400, "Incomplete request"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position;
+        }
+        if buffer.len() > MAX_HEAD_BYTES {
+            return Err(HttpResponse::error(431, "Request headers too large"));
+        }
+    };
+
+    let head = std::str::from_utf8(&buffer[..head_end])
+        .map_err(|_| HttpResponse::error(400, "Request headers are not valid UTF-8"))?;
+    let mut request = parse_head(head).map_err(|e| HttpResponse::error(400, &e))?;
+
+    let content_length = request
+        .header("content-length")
+        .map(|value| value.parse::<usize>())
+        .transpose()
+        .map_err(|_| HttpResponse::error(400, "Invalid Content-Length"))?
+        .unwrap_or(0);
+    if content_length > MAX_BODY_BYTES {
+        return Err(HttpResponse::error(413, "Request body too large"));
+    }
+
+    let mut body = buffer.split_off(head_end + 4);
+    while body.len() < content_length {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| HttpResponse::error(400, "Failed to read request body"))?;
+        if read == 0 {
+            return Err(HttpResponse::error(400, "Incomplete request body"));
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(content_length);
+    request.body = body;
+
+    Ok(request)
+}
+
+fn parse_head(head: &str) -> Result<HttpRequest, String> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+            (method, target)
+        }
+        _ => return Err("Malformed request line".to_string()),
+    };
+
+    let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+    let query = url::form_urlencoded::parse(query_string.as_bytes())
+        .into_owned()
+        .collect();
+
+    let mut headers = HashMap::new();
+    for line in lines {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Malformed header: {}", line))?;
+        headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+
+    Ok(HttpRequest {
+        method: method.to_string(),
+        path: path.trim_end_matches('/').to_string(),
+        query,
+        headers,
+        body: Vec::new(),
+    })
+}
+
+/// Rejects requests addressed to other host names (DNS rebinding)
+fn is_local_host(host: Option<&str>, port: u16) -> bool {
+    let Some(host) = host else {
+        return false;
+    };
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, host_port)) => (name, host_port.parse::<u16>().ok()),
+        None => (host, None),
+    };
+    matches!(name, "127.0.0.1" | "localhost") && host_port.is_none_or(|p| p == port)
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// Every route returns or sends mail, so none is served while the app is
+/// locked
+fn locked_response(locked: bool) -> Option<HttpResponse> {
+    locked.then(|| HttpResponse::error(423, LOCKED_ERROR))
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        423 => "Locked",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+fn write_private_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(contents.as_bytes())
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_head() {
+        let request = parse_head(
+            "GET /v1/search/?q=from%3Aalice+invoice&limit=5 HTTP/1.1\r\nHost: 127.0.0.1:7787\r\nAuthorization: Bearer abc",
+        )
+        .unwrap();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/v1/search");
+        assert_eq!(request.query.get("q").unwrap(), "from:alice invoice");
+        assert_eq!(request.query_limit(), 5);
+        assert_eq!(request.header("authorization"), Some("Bearer abc"));
+
+        assert!(parse_head("GET /v1/search").is_err());
+        assert!(parse_head("GET / HTTP/1.1\r\nbroken header").is_err());
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host(Some("127.0.0.1:7787"), 7787));
+        assert!(is_local_host(Some("localhost"), 7787));
+        assert!(!is_local_host(Some("localhost:8080"), 7787));
+        assert!(!is_local_host(Some("attacker.example:7787"), 7787));
+        assert!(!is_local_host(None, 7787));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("token", "token"));
+        assert!(!constant_time_eq("token", "tokem"));
+        assert!(!constant_time_eq("token", "token2"));
+    }
+
+    #[test]
+    fn test_response_bytes() {
+        let response = HttpResponse::error(401, "Missing or invalid token");
+        let bytes = String::from_utf8(response.to_bytes()).unwrap();
+        assert!(bytes.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+        assert!(bytes.ends_with("\r\n\r\n{\"error\":\"Missing or invalid token\"}"));
+    }
+
+    #[test]
+    fn test_locked_response() {
+        assert!(locked_response(false).is_none());
+
+        let response = locked_response(true).unwrap();
+        let bytes = String::from_utf8(response.to_bytes()).unwrap();
+        assert!(bytes.starts_with("HTTP/1.1 423 Locked\r\n"));
+    }
+}
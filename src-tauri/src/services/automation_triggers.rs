@@ -0,0 +1,365 @@
+//! User-configured automation triggers: a webhook POST or a local script that
+//! runs when new mail arrives, a rule matches or the user flags a message.
+//!
+//! Events are written to the delivery log first and handed to their target by
+//! [`AutomationTriggerDispatcher`], so a slow or unreachable endpoint never
+//! holds up the sync and failed deliveries are retried with backoff.
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use sqlx::SqlitePool;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::database::models::automation_trigger::{
+    AutomationTrigger, TriggerDelivery, TriggerDeliveryStatus, TriggerEvent, TriggerTarget,
+};
+use crate::database::models::email::Email;
+use crate::database::models::folder::FolderType;
+use crate::database::repositories::{
+    AutomationTriggerRepository, FolderRepository, SqliteAutomationTriggerRepository,
+    SqliteFolderRepository,
+};
+use crate::sync::backoff::Backoff;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 15;
+/// Deliveries handed out per poll
+const DELIVERY_BATCH_SIZE: i64 = 50;
+/// Attempts before a delivery is marked as failed
+const MAX_DELIVERY_ATTEMPTS: i64 = 5;
+/// Retry delays of failed deliveries: 1, 4, 16 and 64 minutes
+const RETRY_BACKOFF: Backoff = Backoff {
+    base_secs: 60,
+    growth: 4,
+    max_secs: 64 * 60,
+};
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(15);
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Older mail showing up in a sync (first sync of an account, a restored
+/// folder) is not "new" and must not flood the endpoints
+const NEW_MAIL_MAX_AGE_HOURS: i64 = 24;
+const DELIVERY_LOG_RETENTION_DAYS: i64 = 30;
+
+/// Queues the `new_mail` and `rule_matched` triggers for a message that was
+/// just stored. `email` reflects the outcome of the rules.
+pub async fn enqueue_new_email(
+    pool: &SqlitePool,
+    email: &Email,
+    matched_rule_ids: &[Uuid],
+) -> Result<(), String> {
+    if email.is_draft
+        || email.is_deleted
+        || email.received_at < Utc::now() - chrono::Duration::hours(NEW_MAIL_MAX_AGE_HOURS)
+    {
+        return Ok(());
+    }
+
+    let triggers = enabled_triggers(pool, email.account_id).await?;
+    if triggers.is_empty() {
+        return Ok(());
+    }
+
+    let in_inbox = SqliteFolderRepository::new(pool.clone())
+        .find_by_id(email.folder_id)
+        .await
+        .map_err(|e| format!("Failed to load folder: {}", e))?
+        .is_some_and(|folder| folder.folder_type == FolderType::Inbox);
+
+    for trigger in &triggers {
+        let fires = match &trigger.event {
+            TriggerEvent::NewMail => in_inbox,
+            TriggerEvent::RuleMatched { rule_id } => matched_rule_ids.contains(rule_id),
+            TriggerEvent::EmailFlagged => false,
+        };
+        if fires {
+            enqueue(pool, trigger, Some(email)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Queues the `email_flagged` triggers after the user flagged a message
+pub async fn enqueue_flagged(pool: &SqlitePool, email: &Email) -> Result<(), String> {
+    for trigger in enabled_triggers(pool, email.account_id).await? {
+        if trigger.event == TriggerEvent::EmailFlagged {
+            enqueue(pool, &trigger, Some(email)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn enabled_triggers(
+    pool: &SqlitePool,
+    account_id: Uuid,
+) -> Result<Vec<AutomationTrigger>, String> {
+    SqliteAutomationTriggerRepository::new(pool.clone())
+        .find_enabled_for_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to load automation triggers: {}", e))
+}
+
+/// Adds a delivery of the trigger to the log. Without an email a sample
+/// payload is sent, for testing a trigger from the settings.
+pub async fn enqueue(
+    pool: &SqlitePool,
+    trigger: &AutomationTrigger,
+    email: Option<&Email>,
+) -> Result<Uuid, String> {
+    let now = Utc::now();
+    let delivery = TriggerDelivery {
+        id: Uuid::now_v7(),
+        trigger_id: trigger.id,
+        email_id: email.map(|email| email.id),
+        event: trigger.event.name().to_string(),
+        payload: build_payload(trigger, email),
+        status: TriggerDeliveryStatus::Pending,
+        attempts: 0,
+        last_error: None,
+        next_attempt_at: Some(now),
+        delivered_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    SqliteAutomationTriggerRepository::new(pool.clone())
+        .create_delivery(&delivery)
+        .await
+        .map_err(|e| format!("Failed to queue trigger delivery: {}", e))
+}
+
+fn build_payload(trigger: &AutomationTrigger, email: Option<&Email>) -> JsonValue {
+    let mut payload = json!({
+        "event": trigger.event.name(),
+        "trigger": {
+            "id": trigger.id,
+            "name": trigger.name,
+        },
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+
+    if let TriggerEvent::RuleMatched { rule_id } = &trigger.event {
+        payload["rule_id"] = json!(rule_id);
+    }
+
+    payload["email"] = match email {
+        Some(email) => json!({
+            "id": email.id,
+            "account_id": email.account_id,
+            "folder_id": email.folder_id,
+            "message_id": email.message_id,
+            "from": email.from.0,
+            "to": email.to.0,
+            "cc": email.cc.0,
+            "subject": email.subject,
+            "snippet": email.snippet,
+            "received_at": email.received_at.to_rfc3339(),
+            "is_read": email.is_read,
+            "is_flagged": email.is_flagged,
+            "has_attachments": email.has_attachments,
+        }),
+        None => JsonValue::Null,
+    };
+
+    payload
+}
+
+/// Delay before the next attempt after `attempts` failed ones, or `None` once
+/// the delivery is given up
+fn retry_delay(attempts: i64) -> Option<chrono::Duration> {
+    (attempts < MAX_DELIVERY_ATTEMPTS).then(|| RETRY_BACKOFF.delay(attempts - 1))
+}
+
+/// Hands queued trigger deliveries to their webhook or script
+pub struct AutomationTriggerDispatcher {
+    pool: SqlitePool,
+    client: reqwest::Client,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+}
+
+impl AutomationTriggerDispatcher {
+    pub fn new(pool: SqlitePool) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .user_agent(concat!("Ravn/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap_or_default(),
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[AutomationTriggers] Starting trigger dispatcher");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(error) = this.deliver_due().await {
+                    log::error!(
+                        "[AutomationTriggers] Failed to process trigger deliveries: {}",
+                        error
+                    );
+                }
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[AutomationTriggers] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(this.poll_interval) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[AutomationTriggers] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    async fn deliver_due(&self) -> Result<(), String> {
+        let repo = SqliteAutomationTriggerRepository::new(self.pool.clone());
+
+        let due = repo
+            .find_due_deliveries(Utc::now(), DELIVERY_BATCH_SIZE)
+            .await
+            .map_err(|e| format!("Failed to query due deliveries: {}", e))?;
+
+        for delivery in due {
+            let trigger = repo
+                .find_by_id(delivery.trigger_id)
+                .await
+                .map_err(|e| format!("Failed to load trigger: {}", e))?;
+            // Deleting a trigger cascades to its deliveries, so only a disabled
+            // trigger ends up here
+            let Some(trigger) = trigger.filter(|trigger| trigger.enabled) else {
+                repo.record_failure(delivery.id, "Trigger is disabled", None)
+                    .await
+                    .map_err(|e| format!("Failed to update delivery: {}", e))?;
+                continue;
+            };
+
+            match self.deliver(&trigger.target, &delivery.payload).await {
+                Ok(()) => {
+                    log::debug!(
+                        "[AutomationTriggers] Delivered {} to trigger '{}'",
+                        delivery.event,
+                        trigger.name
+                    );
+                    repo.mark_delivered(delivery.id)
+                        .await
+                        .map_err(|e| format!("Failed to update delivery: {}", e))?;
+                }
+                Err(error) => {
+                    let retry_at =
+                        retry_delay(delivery.attempts + 1).map(|delay| Utc::now() + delay);
+                    log::warn!(
+                        "[AutomationTriggers] Delivery {} to trigger '{}' failed (attempt {}): {}",
+                        delivery.id,
+                        trigger.name,
+                        delivery.attempts + 1,
+                        error
+                    );
+                    repo.record_failure(delivery.id, &error, retry_at)
+                        .await
+                        .map_err(|e| format!("Failed to update delivery: {}", e))?;
+                }
+            }
+        }
+
+        let pruned = repo
+            .prune_deliveries(Utc::now() - chrono::Duration::days(DELIVERY_LOG_RETENTION_DAYS))
+            .await
+            .map_err(|e| format!("Failed to prune delivery log: {}", e))?;
+        if pruned > 0 {
+            log::debug!(
+                "[AutomationTriggers] Pruned {} old deliveries from the log",
+                pruned
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn deliver(&self, target: &TriggerTarget, payload: &JsonValue) -> Result<(), String> {
+        match target {
+            TriggerTarget::Webhook { url, headers } => {
+                let mut request = self.client.post(url).json(payload);
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+
+                let response = request.send().await.map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    return Err(format!("HTTP {}", response.status()));
+                }
+                Ok(())
+            }
+            TriggerTarget::Script { path, args } => {
+                let mut child = tokio::process::Command::new(path)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|e| format!("Failed to start {}: {}", path, e))?;
+
+                if let Some(mut stdin) = child.stdin.take() {
+                    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+                    stdin
+                        .write_all(&body)
+                        .await
+                        .map_err(|e| format!("Failed to write payload: {}", e))?;
+                }
+
+                let output = tokio::time::timeout(SCRIPT_TIMEOUT, child.wait_with_output())
+                    .await
+                    .map_err(|_| format!("Timed out after {}s", SCRIPT_TIMEOUT.as_secs()))?
+                    .map_err(|e| e.to_string())?;
+
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    return Err(format!("{}: {}", output.status, stderr.trim()));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Some(chrono::Duration::minutes(1)));
+        assert_eq!(retry_delay(2), Some(chrono::Duration::minutes(4)));
+        assert_eq!(retry_delay(4), Some(chrono::Duration::minutes(64)));
+        assert_eq!(retry_delay(MAX_DELIVERY_ATTEMPTS), None);
+    }
+}
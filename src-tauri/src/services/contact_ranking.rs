@@ -0,0 +1,205 @@
+//! Ranking of recipient autocomplete. A suggestion's score blends how well the
+//! contact matches the typed text with its frecency, so a contact that is
+//! mailed often wins over a slightly better match that is never used.
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::database::models::contact::{
+    Contact, ContactField, ContactInteraction, RecipientSuggestion,
+};
+
+/// How well a contact matches the typed text
+fn match_score(query: &str, contact: &Contact) -> Option<f64> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Some(1.0);
+    }
+
+    let email = contact.email.to_lowercase();
+    if email == query {
+        return Some(4.0);
+    }
+    if email.starts_with(&query) {
+        return Some(3.0);
+    }
+
+    let names: Vec<String> = [
+        contact.display_name.as_deref(),
+        contact.first_name.as_deref(),
+        contact.last_name.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(str::to_lowercase)
+    .collect();
+    if names.iter().any(|name| {
+        name.starts_with(&query) || name.split_whitespace().any(|word| word.starts_with(&query))
+    }) {
+        return Some(2.5);
+    }
+
+    // Parts of the address, so "smith" finds jane.smith@example.com
+    let local_part = email.split('@').next().unwrap_or_default();
+    if local_part
+        .split(['.', '_', '-', '+'])
+        .chain(email.split('@').nth(1))
+        .any(|part| part.starts_with(&query))
+    {
+        return Some(2.0);
+    }
+
+    let contains = email.contains(&query) || names.iter().any(|name| name.contains(&query));
+    contains.then_some(0.5)
+}
+
+/// Weight of an interaction in the given field when completing `field`.
+/// Mail sent in the same field counts most, received mail least.
+fn field_weight(interaction: ContactField, field: Option<ContactField>) -> f64 {
+    match (interaction, field) {
+        (ContactField::From, _) => 0.3,
+        (interaction, Some(field)) if interaction == field => 1.0,
+        (_, Some(_)) => 0.6,
+        (_, None) => 1.0,
+    }
+}
+
+/// Frecency of each contact over the given interactions, as of `now`
+fn frecency(
+    interactions: &[ContactInteraction],
+    field: Option<ContactField>,
+    now: DateTime<Utc>,
+) -> HashMap<Uuid, f64> {
+    let mut scores = HashMap::new();
+    for interaction in interactions {
+        *scores.entry(interaction.contact_id).or_insert(0.0) +=
+            interaction.score_at(now) * field_weight(interaction.field, field);
+    }
+    scores
+}
+
+/// Suggestions for the typed text, best first. `interactions` are the
+/// frecency rows of the candidates for the account being written from.
+pub fn rank(
+    query: &str,
+    candidates: Vec<Contact>,
+    interactions: &[ContactInteraction],
+    field: Option<ContactField>,
+    now: DateTime<Utc>,
+    limit: usize,
+) -> Vec<RecipientSuggestion> {
+    let frecency = frecency(interactions, field, now);
+
+    let mut suggestions: Vec<RecipientSuggestion> = candidates
+        .into_iter()
+        .filter_map(|contact| {
+            let match_score = match_score(query, &contact)?;
+            let frecency = frecency.get(&contact.id).copied().unwrap_or(0.0);
+
+            Some(RecipientSuggestion {
+                id: contact.id,
+                email: contact.email,
+                display_name: contact.display_name,
+                avatar_path: contact.avatar_path,
+                score: match_score * (1.0 + frecency.ln_1p()),
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.email.cmp(&b.email))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn contact(email: &str, display_name: Option<&str>) -> Contact {
+        Contact {
+            id: Uuid::now_v7(),
+            display_name: display_name.map(ToString::to_string),
+            first_name: None,
+            last_name: None,
+            company: None,
+            email: email.to_string(),
+            ai_notes: None,
+            source: "observed".to_string(),
+            avatar_type: "none".to_string(),
+            avatar_path: None,
+            birthday: None,
+            anniversary: None,
+            is_vip: false,
+            send_count: 0,
+            receive_count: 0,
+            last_used_at: None,
+            first_seen_at: Utc::now(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_match_score() {
+        let jane = contact("jane.smith@example.com", Some("Jane Smith"));
+
+        assert_eq!(match_score("jane.smith@example.com", &jane), Some(4.0));
+        assert_eq!(match_score("jane", &jane), Some(3.0));
+        assert_eq!(match_score("Smi", &jane), Some(2.5));
+        assert_eq!(match_score("exam", &jane), Some(2.0));
+        assert_eq!(match_score("mith", &jane), Some(0.5));
+        assert_eq!(match_score("bob", &jane), None);
+    }
+
+    #[test]
+    fn test_interaction_decay() {
+        let now = Utc::now();
+        let mut interaction =
+            ContactInteraction::new(Uuid::now_v7(), Uuid::now_v7(), ContactField::To, now);
+
+        assert!((interaction.score_at(now + Duration::days(30)) - 0.5).abs() < 1e-9);
+
+        // An older interaction only adds its decayed weight
+        interaction.record(now - Duration::days(30));
+        assert!((interaction.score - 1.5).abs() < 1e-9);
+        assert_eq!(interaction.last_interaction_at, now);
+
+        interaction.record(now + Duration::days(30));
+        assert!((interaction.score - 1.75).abs() < 1e-9);
+        assert_eq!(interaction.interaction_count, 3);
+    }
+
+    #[test]
+    fn test_rank_prefers_frequent_recipients() {
+        let now = Utc::now();
+        let account_id = Uuid::now_v7();
+        let rare = contact("anna@example.com", Some("Anna"));
+        let frequent = contact("andreas@example.com", Some("Andreas"));
+
+        let mut interaction =
+            ContactInteraction::new(frequent.id, account_id, ContactField::To, now);
+        for days in 1..10 {
+            interaction.record(now - Duration::days(days));
+        }
+
+        let suggestions = rank(
+            "an",
+            vec![rare.clone(), frequent.clone()],
+            &[interaction],
+            Some(ContactField::To),
+            now,
+            10,
+        );
+        let emails: Vec<&str> = suggestions.iter().map(|s| s.email.as_str()).collect();
+        assert_eq!(emails, vec!["andreas@example.com", "anna@example.com"]);
+
+        // Without history the better match wins
+        let suggestions = rank("anna", vec![frequent, rare], &[], None, now, 10);
+        assert_eq!(suggestions[0].email, "anna@example.com");
+    }
+}
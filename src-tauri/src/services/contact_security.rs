@@ -0,0 +1,161 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+use crate::database::models::contact_security::{ContactSecurity, EncryptionPolicy, PgpKeySource};
+use crate::database::repositories::{ContactSecurityRepository, SqliteContactSecurityRepository};
+use crate::services::pgp_keys;
+use crate::services::smime::SmimeOptions;
+
+/// Discovered keys are looked up again after this many days
+const KEY_REFRESH_DAYS: i64 = 7;
+
+/// Applies the recipients' encryption preferences to what the user asked for
+/// in the composer. Contacts requiring S/MIME switch encryption on; the
+/// message is refused when the preferences cannot all be honoured.
+pub fn outgoing_options(
+    requested: &SmimeOptions,
+    preferences: &[ContactSecurity],
+) -> Result<SmimeOptions, String> {
+    let with_policy = |policy: EncryptionPolicy| -> Vec<&str> {
+        preferences
+            .iter()
+            .filter(|preference| preference.policy == policy)
+            .map(|preference| preference.email.as_str())
+            .collect()
+    };
+
+    // PGP/MIME messages cannot be composed yet, and sending in plaintext
+    // would go against the preference
+    let pgp_only = with_policy(EncryptionPolicy::AlwaysPgp);
+    if !pgp_only.is_empty() {
+        return Err(format!(
+            "Mail to {} must be encrypted with PGP, which is not supported for sending yet",
+            pgp_only.join(", ")
+        ));
+    }
+
+    let smime_required = with_policy(EncryptionPolicy::RequireSmime);
+    let options = SmimeOptions {
+        sign: requested.sign,
+        encrypt: requested.encrypt || !smime_required.is_empty(),
+    };
+
+    let plaintext_only = with_policy(EncryptionPolicy::PlaintextOnly);
+    if options.encrypt && !plaintext_only.is_empty() {
+        return Err(if requested.encrypt {
+            format!(
+                "{} only accepts unencrypted mail",
+                plaintext_only.join(", ")
+            )
+        } else {
+            format!(
+                "{} require S/MIME encryption but {} only accept unencrypted mail; send separate messages",
+                smime_required.join(", "),
+                plaintext_only.join(", ")
+            )
+        });
+    }
+
+    Ok(options)
+}
+
+/// Whether the contact's key should be looked up (again). Keys the user
+/// imported are never replaced by a lookup.
+pub fn needs_key_lookup(security: &ContactSecurity) -> bool {
+    if security.pgp_key_source == Some(PgpKeySource::Manual) {
+        return false;
+    }
+    security
+        .pgp_key_fetched_at
+        .is_none_or(|fetched_at| fetched_at < Utc::now() - chrono::Duration::days(KEY_REFRESH_DAYS))
+}
+
+/// Looks the contact's OpenPGP key up through WKD and keys.openpgp.org and
+/// stores what was found. The lookup time is recorded even when nothing was
+/// found, so the address is not queried again on every compose.
+///
+/// A key pinned under [`EncryptionPolicy::AlwaysPgp`] is only replaced by a
+/// key with the same fingerprint; a different key has to be accepted by the
+/// user.
+pub async fn refresh_key(pool: &SqlitePool, email: &str) -> Result<ContactSecurity, String> {
+    let repo = SqliteContactSecurityRepository::new(pool.clone());
+    let mut security = repo
+        .find_by_email(email)
+        .await
+        .map_err(|e| format!("Failed to get contact security preferences: {}", e))?
+        .unwrap_or_else(|| ContactSecurity::new(email));
+
+    let discovered = pgp_keys::discover(&security.email).await?;
+    security.pgp_key_fetched_at = Some(Utc::now());
+
+    match discovered {
+        Some(key)
+            if security.policy == EncryptionPolicy::AlwaysPgp
+                && security.pgp_fingerprint.as_deref() != Some(key.fingerprint.as_str()) =>
+        {
+            log::warn!(
+                "[PGP] Found key {} for {}, which differs from the pinned key",
+                key.fingerprint,
+                security.email
+            );
+        }
+        Some(key) => {
+            log::info!(
+                "[PGP] Found key {} for {} via {}",
+                key.fingerprint,
+                security.email,
+                key.source.as_str()
+            );
+            security.pgp_fingerprint = Some(key.fingerprint);
+            security.pgp_public_key = Some(key.armored);
+            security.pgp_key_source = Some(key.source);
+        }
+        None => log::debug!("[PGP] No key found for {}", security.email),
+    }
+
+    repo.upsert(&security)
+        .await
+        .map_err(|e| format!("Failed to save contact security preferences: {}", e))?;
+
+    Ok(security)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preference(email: &str, policy: EncryptionPolicy) -> ContactSecurity {
+        ContactSecurity {
+            policy,
+            ..ContactSecurity::new(email)
+        }
+    }
+
+    #[test]
+    fn test_outgoing_options() {
+        let plain = SmimeOptions::default();
+        let encrypted = SmimeOptions {
+            sign: false,
+            encrypt: true,
+        };
+
+        let smime = preference("a@example.com", EncryptionPolicy::RequireSmime);
+        let plaintext = preference("b@example.com", EncryptionPolicy::PlaintextOnly);
+        let pgp = preference("c@example.com", EncryptionPolicy::AlwaysPgp);
+        let auto = preference("d@example.com", EncryptionPolicy::Auto);
+
+        assert!(
+            !outgoing_options(&plain, &[auto.clone(), plaintext.clone()])
+                .unwrap()
+                .encrypt
+        );
+        assert!(
+            outgoing_options(&plain, &[auto, smime.clone()])
+                .unwrap()
+                .encrypt
+        );
+        assert!(outgoing_options(&encrypted, &[plaintext.clone()]).is_err());
+        assert!(outgoing_options(&plain, &[smime, plaintext]).is_err());
+        assert!(outgoing_options(&plain, &[pgp]).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Rendering a whole conversation as one self-contained HTML transcript.
+//!
+//! Inline images are embedded as `data:` URIs and quoted history is folded
+//! into `<details>` blocks. A restrictive CSP keeps scripts and remote
+//! resources of the original messages from loading when the file is opened
+//! in a browser.
+
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::database::models::attachment::Attachment;
+use crate::database::models::email::{Email, EmailAddress};
+use crate::sync::cid_utils::replace_cid_urls;
+use crate::sync::storage::PathGenerator;
+
+const TRANSCRIPT_CSS: &str = r#"
+    body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, Helvetica, Arial, sans-serif; color: #1f2937; max-width: 860px; margin: 0 auto; padding: 24px; }
+    h1 { font-size: 22px; margin: 0 0 4px; }
+    .transcript-meta { color: #6b7280; font-size: 13px; margin-bottom: 24px; }
+    .message { border: 1px solid #e5e7eb; border-radius: 8px; margin-bottom: 16px; overflow: hidden; }
+    .message-header { background: #f9fafb; border-bottom: 1px solid #e5e7eb; padding: 12px 16px; font-size: 13px; }
+    .message-header .from { font-weight: 600; font-size: 14px; }
+    .message-header .date { float: right; color: #6b7280; }
+    .message-header .recipients { color: #6b7280; margin-top: 2px; }
+    .message-body { padding: 16px; overflow-wrap: anywhere; }
+    .message-body img { max-width: 100%; height: auto; }
+    .message-body pre { white-space: pre-wrap; font-family: inherit; margin: 0; }
+    .message-attachments { border-top: 1px solid #e5e7eb; padding: 8px 16px; font-size: 13px; color: #6b7280; }
+    details.quoted { margin-top: 12px; }
+    details.quoted summary { cursor: pointer; color: #6b7280; font-size: 13px; }
+"#;
+
+/// One message of the transcript with its cached inline images
+pub struct TranscriptMessage<'a> {
+    pub email: &'a Email,
+    pub attachments: &'a [Attachment],
+    /// content_id -> `data:` URI
+    pub inline_images: HashMap<String, String>,
+}
+
+pub fn data_uri(content_type: &str, data: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        content_type,
+        general_purpose::STANDARD.encode(data)
+    )
+}
+
+/// Reads the cached inline images of a message as `data:` URIs keyed by content id
+///
+/// Images that were never downloaded are left out and keep their `cid:` reference.
+pub fn load_inline_images(
+    attachments_dir: &Path,
+    email_id: uuid::Uuid,
+    attachments: &[Attachment],
+) -> HashMap<String, String> {
+    let mut inline_images = HashMap::new();
+    for attachment in attachments {
+        let (Some(content_id), Some(cache_path)) = (&attachment.content_id, &attachment.cache_path)
+        else {
+            continue;
+        };
+        if !attachment.is_cached {
+            continue;
+        }
+
+        let path = attachments_dir.join(PathGenerator::cache_path_to_pathbuf(cache_path));
+        match std::fs::read(&path) {
+            Ok(data) => {
+                inline_images.insert(
+                    content_id.clone(),
+                    data_uri(&attachment.content_type, &data),
+                );
+            }
+            Err(e) => log::warn!(
+                "[Export] Skipping inline image {} of email {}: {}",
+                attachment.id,
+                email_id,
+                e
+            ),
+        }
+    }
+    inline_images
+}
+
+/// Messages are rendered in the given order, which callers keep chronological
+pub fn render_transcript(subject: &str, messages: &[TranscriptMessage]) -> String {
+    let body: String = messages.iter().map(render_message).collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <meta http-equiv="Content-Security-Policy" content="default-src 'none'; img-src data:; style-src 'unsafe-inline'">
+    <title>{subject}</title>
+    <style>{css}</style>
+</head>
+<body>
+    <h1>{subject}</h1>
+    <div class="transcript-meta">{count} {noun}</div>
+    {body}
+</body>
+</html>"#,
+        subject = escape_html(subject),
+        css = TRANSCRIPT_CSS,
+        count = messages.len(),
+        noun = if messages.len() == 1 {
+            "message"
+        } else {
+            "messages"
+        },
+        body = body,
+    )
+}
+
+fn render_message(message: &TranscriptMessage) -> String {
+    let email = message.email;
+    let date = email.sent_at.unwrap_or(email.received_at);
+
+    let mut recipients = Vec::new();
+    if !email.to.is_empty() {
+        recipients.push(format!("To: {}", format_addresses(&email.to)));
+    }
+    if !email.cc.is_empty() {
+        recipients.push(format!("Cc: {}", format_addresses(&email.cc)));
+    }
+
+    let body = match email.body_html.as_deref().filter(|b| !b.trim().is_empty()) {
+        Some(html) => replace_cid_urls(html, &message.inline_images),
+        None => format!(
+            "<pre>{}</pre>",
+            escape_html(email.body_plain.as_deref().unwrap_or_default())
+        ),
+    };
+
+    let quoted = email
+        .other_mails
+        .as_deref()
+        .filter(|q| !q.trim().is_empty())
+        .map(|q| {
+            format!(
+                r#"<details class="quoted"><summary>Show quoted text</summary>{}</details>"#,
+                replace_cid_urls(q, &message.inline_images)
+            )
+        })
+        .unwrap_or_default();
+
+    let files: Vec<String> = message
+        .attachments
+        .iter()
+        .filter(|a| !a.is_inline)
+        .map(|a| escape_html(&a.filename))
+        .collect();
+    let attachments = if files.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r#"<div class="message-attachments">Attachments: {}</div>"#,
+            files.join(", ")
+        )
+    };
+
+    format!(
+        r#"<div class="message">
+        <div class="message-header">
+            <span class="date">{date}</span>
+            <div class="from">{from}</div>
+            <div class="recipients">{recipients}</div>
+        </div>
+        <div class="message-body">{body}{quoted}</div>
+        {attachments}
+    </div>
+    "#,
+        date = date.format("%a, %d %b %Y %H:%M UTC"),
+        from = format_address(&email.from),
+        recipients = recipients.join("<br>"),
+        body = body,
+        quoted = quoted,
+        attachments = attachments,
+    )
+}
+
+fn format_address(address: &EmailAddress) -> String {
+    match address.name.as_deref().filter(|n| !n.trim().is_empty()) {
+        Some(name) => format!(
+            "{} &lt;{}&gt;",
+            escape_html(name),
+            escape_html(&address.address)
+        ),
+        None => escape_html(&address.address),
+    }
+}
+
+fn format_addresses(addresses: &[EmailAddress]) -> String {
+    addresses
+        .iter()
+        .map(format_address)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_uri() {
+        assert_eq!(data_uri("image/png", b"png"), "data:image/png;base64,cG5n");
+    }
+
+    #[test]
+    fn test_format_address_escapes() {
+        let address = EmailAddress {
+            address: "jane@example.com".to_string(),
+            name: Some("Jane <Admin>".to_string()),
+        };
+        assert_eq!(
+            format_address(&address),
+            "Jane &lt;Admin&gt; &lt;jane@example.com&gt;"
+        );
+    }
+}
@@ -1,24 +1,45 @@
 use crate::config::Settings;
 use crate::database::models::account::Account;
+use crate::database::models::ai_audit_entry::AiAuditEntry;
 use crate::database::models::email::Email;
+use crate::database::repositories::{AiAuditRepository, SqliteAiAuditRepository};
 use crate::licensing::LicenseManager;
+use chrono::{DateTime, Utc};
 use openrouter_rs::api::chat::{
     ChatCompletionRequest as ChatRequest, Message as OpenRouterChatMessage,
 };
 use openrouter_rs::client::OpenRouterClient;
 use openrouter_rs::types::{ProviderPreferences, ProviderSortBy, Role};
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 use std::sync::Arc;
 use turndown::Turndown;
+use uuid::Uuid;
 
 const MAX_PRIOR_EMAIL_TOKENS: usize = 500;
 const MAX_CURRENT_TEXT_TOKENS: usize = 300;
 const MAX_OTHER_MAILS_TOKENS: usize = 800;
 const APPROX_CHARS_PER_TOKEN: usize = 4;
+/// Tokens an analysis answer is expected to take, per email
+const ANALYSIS_RESPONSE_TOKENS: usize = 600;
+
+/// Thread text sent with one summary request; longer threads are chunked
+const MAX_SUMMARY_CHUNK_TOKENS: usize = 6000;
+
+/// Email text sent with one mailbox question, and the share of it one email may take
+const MAX_ASK_MAILBOX_CONTEXT_TOKENS: usize = 6000;
+const MAX_ASK_MAILBOX_EMAIL_TOKENS: usize = 800;
+
+/// Text kept of each sent email sampled to learn a writing style
+const MAX_STYLE_SAMPLE_TOKENS: usize = 300;
+
+/// Appended to the analysis prompt when several emails are sent at once
+const BATCH_ANALYSIS_INSTRUCTIONS: &str = "\n\n## Several Emails\nThe user message contains several emails, each under an `# Email <id>` heading. Analyse each one on its own as described above and output **only** a JSON array with one object per email: `{\"id\": \"<id from the heading>\", \"gist\": ..., \"responses\": [...]}`.";
 
 pub struct CorvusService {
     settings: Arc<Settings>,
     license_manager: Arc<LicenseManager>,
+    pool: SqlitePool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -33,6 +54,46 @@ pub struct EmailAnalysis {
     pub responses: Vec<EmailAnalysisResponse>,
 }
 
+/// AI summary of a conversation, cached on the conversation until a new
+/// message arrives
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationSummary {
+    pub summary: String,
+    /// Messages the summary covers
+    pub message_count: usize,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Answer to a question about the mailbox, citing the emails it is based on
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MailboxAnswer {
+    pub answer: String,
+    /// Ids of the retrieved emails the answer draws on, most relevant first
+    pub sources: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMailboxAnswer {
+    answer: String,
+    #[serde(default)]
+    sources: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchedEmailAnalysis {
+    id: String,
+    #[serde(flatten)]
+    analysis: EmailAnalysis,
+}
+
+/// An email to analyze, with what the AI should know about the people involved
+#[derive(Debug, Clone)]
+pub struct AnalysisRequest {
+    pub email: Email,
+    pub user_context: Option<UserContext>,
+    pub contact_notes: Vec<ContactNote>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModelPricing {
     pub prompt: f32,
@@ -67,6 +128,8 @@ pub struct EmailCompletionRequest {
     pub cursor_position: usize,
     /// AI notes for the primary contacts involved in this email (keyed by email address)
     pub contact_notes: Vec<ContactNote>,
+    /// Style profile learned from the sending account's sent mail
+    pub learned_style: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -201,11 +264,88 @@ impl UserEmailRole {
     }
 }
 
+/// Parses an analysis answer, stripping the markdown code fence some models
+/// add around JSON
+fn parse_analysis_json<T: serde::de::DeserializeOwned>(response_text: &str) -> Result<T, String> {
+    let json_str = response_text
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+
+    serde_json::from_str::<T>(json_str).map_err(|e| {
+        format!(
+            "Failed to parse analysis JSON: {}. Content: {}",
+            e, response_text
+        )
+    })
+}
+
+const THREAD_SEPARATOR: &str = "\n\n---\n\n";
+
+/// Cuts a message down to `max_chars`, marking where it was cut
+fn truncate_message(message: String, max_chars: usize) -> String {
+    if message.len() <= max_chars {
+        return message;
+    }
+    let mut end = max_chars;
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n[... message truncated ...]", &message[..end])
+}
+
+/// Groups thread messages, oldest first, into chunks of at most `max_chars`.
+/// A message longer than that is cut down to fit a chunk of its own.
+fn chunk_thread(messages: Vec<String>, max_chars: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for message in messages {
+        let message = truncate_message(message, max_chars);
+
+        if !current.is_empty() && current.len() + THREAD_SEPARATOR.len() + message.len() > max_chars
+        {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str(THREAD_SEPARATOR);
+        }
+        current.push_str(&message);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+/// Keeps the cited ids that name one of the retrieved emails, once each.
+/// Models sometimes cite emails they were never shown.
+fn filter_sources(cited: &[String], retrieved: &[Uuid]) -> Vec<Uuid> {
+    let mut sources = Vec::new();
+    for id in cited {
+        let Ok(id) = Uuid::parse_str(id.trim().trim_matches(['[', ']'])) else {
+            continue;
+        };
+        if retrieved.contains(&id) && !sources.contains(&id) {
+            sources.push(id);
+        }
+    }
+    sources
+}
+
 impl CorvusService {
-    pub fn new(settings: Arc<Settings>, license_manager: Arc<LicenseManager>) -> Self {
+    pub fn new(
+        settings: Arc<Settings>,
+        license_manager: Arc<LicenseManager>,
+        pool: SqlitePool,
+    ) -> Self {
         Self {
             settings,
             license_manager,
+            pool,
         }
     }
 
@@ -290,6 +430,16 @@ impl CorvusService {
         }
     }
 
+    fn build_learned_style_context(learned_style: Option<&str>) -> String {
+        match learned_style.map(str::trim).filter(|style| !style.is_empty()) {
+            Some(style) => format!(
+                "\n\nThe user's writing style, learned from their sent mail. Personal writing guides take precedence over it:\n{}",
+                style
+            ),
+            None => String::new(),
+        }
+    }
+
     fn build_contact_notes_context(contact_notes: &[ContactNote]) -> String {
         if contact_notes.is_empty() {
             return String::new();
@@ -313,6 +463,32 @@ impl CorvusService {
         )
     }
 
+    /// Records a request in the AI audit log while `ai.audit.enabled` is on.
+    /// Failing to record it is logged and does not stop the request.
+    async fn audit(&self, operation: &str, model: &str, email_ids: &[Uuid], prompts: &[&str]) {
+        if !self
+            .settings
+            .get::<bool>("ai.audit.enabled")
+            .unwrap_or(false)
+        {
+            return;
+        }
+
+        let entry = AiAuditEntry::new(
+            operation,
+            self.get_base_url().unwrap_or_default(),
+            model.to_string(),
+            email_ids,
+            prompts.join(THREAD_SEPARATOR),
+        );
+        if let Err(e) = SqliteAiAuditRepository::new(self.pool.clone())
+            .record(&entry)
+            .await
+        {
+            log::warn!("Failed to record {} in the AI audit log: {}", operation, e);
+        }
+    }
+
     async fn get_client(&self) -> Result<OpenRouterClient, String> {
         let api_key = self.get_api_key().await?;
         let base_url = self.get_base_url()?;
@@ -363,6 +539,10 @@ impl CorvusService {
         let mut system_prompt = self.get_prompt("askAi")?;
         system_prompt.push_str(&self.build_writing_style_context());
 
+        let mut prompts = vec![system_prompt.as_str()];
+        prompts.extend(request.history.iter().map(|msg| msg.content.as_str()));
+        self.audit("ask_ai", &model, &[], &prompts).await;
+
         let messages: Vec<OpenRouterChatMessage> = request
             .history
             .into_iter()
@@ -410,7 +590,17 @@ impl CorvusService {
         let user_message = self.build_autocomplete_prompt(&request);
         let mut system_prompt = self.get_prompt("generateCompletion")?;
         system_prompt.push_str(&self.build_writing_style_context());
+        system_prompt.push_str(&Self::build_learned_style_context(
+            request.learned_style.as_deref(),
+        ));
         system_prompt.push_str(&Self::build_contact_notes_context(&request.contact_notes));
+        self.audit(
+            "generate_email_completion",
+            &model,
+            &[],
+            &[&system_prompt, &user_message],
+        )
+        .await;
 
         let messages = vec![
             OpenRouterChatMessage::new(Role::System, &*system_prompt),
@@ -457,6 +647,9 @@ impl CorvusService {
             request.current_subject.unwrap_or_else(|| "None".to_string())
         );
 
+        self.audit("generate_subject", &model, &[], &[&prompt])
+            .await;
+
         let messages = vec![OpenRouterChatMessage::new(Role::User, &*prompt)];
 
         let chat_request = ChatRequest::builder()
@@ -489,11 +682,141 @@ impl CorvusService {
 
         log::debug!("Processing email analysis request for email {}", email.id);
 
-        let client = self.get_client().await?;
-        let model = self.get_model("normal")?;
+        let system_prompt = self.build_analysis_system_prompt()?;
+        let system_prompt = if contact_notes.is_empty() {
+            system_prompt
+        } else {
+            format!(
+                "{}{}",
+                system_prompt,
+                Self::build_contact_notes_context(contact_notes)
+            )
+        };
+        let user_prompt = Self::build_analysis_prompt(email, user_context);
+
+        let response_text = self
+            .send_prompt("analyze_email", &[email.id], &system_prompt, &user_prompt)
+            .await?;
+
+        log::debug!(
+            "analyze_email received response from OpenRouter ({} chars) for email '{}'",
+            response_text.len(),
+            email.id
+        );
+
+        parse_analysis_json(&response_text)
+    }
+
+    /// Analyzes several emails with a single request. Emails the model left
+    /// out of its answer are missing from the result.
+    pub async fn analyze_emails(
+        &self,
+        requests: &[AnalysisRequest],
+    ) -> Result<Vec<(Uuid, EmailAnalysis)>, String> {
+        if let [request] = requests {
+            let analysis = self
+                .analyze_email(
+                    &request.email,
+                    request.user_context.as_ref(),
+                    &request.contact_notes,
+                )
+                .await?;
+            return Ok(vec![(request.email.id, analysis)]);
+        }
+
+        if !self.is_enabled().await {
+            return Err(
+                "AI service is not enabled. Please configure an API key or activate a license."
+                    .to_string(),
+            );
+        }
+
+        log::debug!(
+            "Processing batched email analysis request for {} emails",
+            requests.len()
+        );
+
+        let system_prompt = format!(
+            "{}{}",
+            self.build_analysis_system_prompt()?,
+            BATCH_ANALYSIS_INSTRUCTIONS
+        );
+        let user_prompt = Self::build_batch_analysis_prompt(requests);
+
+        let email_ids: Vec<Uuid> = requests.iter().map(|request| request.email.id).collect();
+        let response_text = self
+            .send_prompt("analyze_emails", &email_ids, &system_prompt, &user_prompt)
+            .await?;
+
+        log::debug!(
+            "analyze_emails received response from OpenRouter ({} chars) for {} emails",
+            response_text.len(),
+            requests.len()
+        );
+
+        let analyses = parse_analysis_json::<Vec<BatchedEmailAnalysis>>(&response_text)?;
+
+        Ok(analyses
+            .into_iter()
+            .filter_map(|batched| {
+                let id = Uuid::parse_str(batched.id.trim()).ok()?;
+                requests
+                    .iter()
+                    .any(|request| request.email.id == id)
+                    .then_some((id, batched.analysis))
+            })
+            .collect())
+    }
+
+    /// Rough number of tokens analyzing the emails in one request takes,
+    /// prompts and answers included
+    pub fn estimate_analysis_tokens(&self, requests: &[AnalysisRequest]) -> usize {
+        let system_prompt_chars = self
+            .build_analysis_system_prompt()
+            .map(|prompt| prompt.len())
+            .unwrap_or_default();
+        let user_prompt_chars = if let [request] = requests {
+            Self::build_analysis_prompt(&request.email, request.user_context.as_ref()).len()
+                + Self::build_contact_notes_context(&request.contact_notes).len()
+        } else {
+            BATCH_ANALYSIS_INSTRUCTIONS.len() + Self::build_batch_analysis_prompt(requests).len()
+        };
+
+        (system_prompt_chars + user_prompt_chars) / APPROX_CHARS_PER_TOKEN
+            + requests.len() * ANALYSIS_RESPONSE_TOKENS
+    }
+
+    /// The analysis prompt from the settings, with the user's writing style
+    fn build_analysis_system_prompt(&self) -> Result<String, String> {
         let system_prompt = self.get_prompt("analyzeEmail")?;
         let writing_style = self.get_writing_style().unwrap_or_default();
 
+        Ok(if writing_style.is_empty() {
+            system_prompt
+        } else {
+            format!(
+                "{}\n\n## Personal Writing Style\n{}",
+                system_prompt, writing_style
+            )
+        })
+    }
+
+    fn build_batch_analysis_prompt(requests: &[AnalysisRequest]) -> String {
+        requests
+            .iter()
+            .map(|request| {
+                format!(
+                    "# Email {}\n{}{}",
+                    request.email.id,
+                    Self::build_analysis_prompt(&request.email, request.user_context.as_ref()),
+                    Self::build_contact_notes_context(&request.contact_notes)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn build_analysis_prompt(email: &Email, user_context: Option<&UserContext>) -> String {
         // Helper closure to format an email address as "Name <address>" or just "address"
         let fmt_addr = |name: &Option<String>, address: &str| -> String {
             match name.as_deref().filter(|n| !n.is_empty()) {
@@ -612,7 +935,7 @@ impl CorvusService {
             format!("\nBcc: {}", bcc)
         };
 
-        let user_prompt = format!(
+        format!(
             r#"Current DateTime: {}
 {}
 ## Email Details
@@ -637,47 +960,44 @@ Received At: {}
             received_at,
             content,
             thread_context_section,
-        );
-
-        let system_with_style = if writing_style.is_empty() {
-            system_prompt
-        } else {
-            format!(
-                "{}\n\n## Personal Writing Style\n{}",
-                system_prompt, writing_style
-            )
-        };
+        )
+    }
 
-        let system_with_style = if contact_notes.is_empty() {
-            system_with_style
-        } else {
-            format!(
-                "{}{}",
-                system_with_style,
-                Self::build_contact_notes_context(contact_notes)
-            )
-        };
+    /// Sends a prompt to the normal model; `operation` names it in the logs
+    /// and `email_ids` are the emails whose content it includes
+    async fn send_prompt(
+        &self,
+        operation: &str,
+        email_ids: &[Uuid],
+        system_prompt: &str,
+        user_prompt: &str,
+    ) -> Result<String, String> {
+        let client = self.get_client().await?;
+        let model = self.get_model("normal")?;
+        self.audit(operation, &model, email_ids, &[system_prompt, user_prompt])
+            .await;
 
         log::debug!(
-            "Sending analyze_email request to OpenRouter: model='{}', email_id='{}', subject='{}'",
-            model,
-            email.id,
-            subject
+            "Sending {} request to OpenRouter: model='{}'",
+            operation,
+            model
         );
         log::info!(
-            "analyze_email system prompt ({} chars): {}",
-            system_with_style.len(),
-            system_with_style
+            "{} system prompt ({} chars): {}",
+            operation,
+            system_prompt.len(),
+            system_prompt
         );
         log::info!(
-            "analyze_email user prompt ({} chars): {}",
+            "{} user prompt ({} chars): {}",
+            operation,
             user_prompt.len(),
             user_prompt
         );
 
         let messages = vec![
-            OpenRouterChatMessage::new(Role::System, &*system_with_style),
-            OpenRouterChatMessage::new(Role::User, &*user_prompt),
+            OpenRouterChatMessage::new(Role::System, system_prompt),
+            OpenRouterChatMessage::new(Role::User, user_prompt),
         ];
 
         let chat_request = ChatRequest::builder()
@@ -693,30 +1013,240 @@ Received At: {}
             .map_err(|e| format!("OpenRouter API request failed: {}", e))?;
 
         let response_text = response.choices[0].content().unwrap().to_string();
+        log::trace!("{} raw response: {}", operation, response_text);
 
+        Ok(response_text)
+    }
+
+    /// Summarizes a conversation given oldest message first. A thread too
+    /// long for one request is summarized in chunks, whose summaries are then
+    /// combined.
+    pub async fn summarize_conversation(
+        &self,
+        messages: &[Email],
+        user_context: Option<&UserContext>,
+    ) -> Result<ConversationSummary, String> {
+        if !self.is_enabled().await {
+            return Err(
+                "AI service is not enabled. Please configure an API key or activate a license."
+                    .to_string(),
+            );
+        }
+
+        let system_prompt = self.get_prompt("summarizeConversation")?;
+        let user_section = match user_context {
+            Some(ctx) => format!(
+                "## Current User\nName: {}\nEmail: {}\n\n",
+                ctx.name, ctx.email
+            ),
+            None => String::new(),
+        };
+
+        let email_ids: Vec<Uuid> = messages.iter().map(|email| email.id).collect();
+        let chunks = chunk_thread(
+            messages.iter().map(Self::format_thread_message).collect(),
+            MAX_SUMMARY_CHUNK_TOKENS * APPROX_CHARS_PER_TOKEN,
+        );
         log::debug!(
-            "analyze_email received response from OpenRouter ({} chars) for email '{}'",
-            response_text.len(),
-            email.id
+            "Summarizing conversation of {} messages in {} chunks",
+            messages.len(),
+            chunks.len()
         );
-        log::trace!("analyze_email raw response: {}", response_text);
 
-        // Strip a possible markdown code fence that some models add around JSON
-        let json_str = response_text
-            .trim()
-            .trim_start_matches("```json")
-            .trim_start_matches("```")
-            .trim_end_matches("```")
-            .trim();
+        let mut partial_summaries = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let part = if chunks.len() > 1 {
+                format!(" (part {} of {})", index + 1, chunks.len())
+            } else {
+                String::new()
+            };
+            let user_prompt = format!("{}## Email Thread{}\n{}", user_section, part, chunk);
+            partial_summaries.push(
+                self.send_prompt(
+                    "summarize_conversation",
+                    &email_ids,
+                    &system_prompt,
+                    &user_prompt,
+                )
+                .await?,
+            );
+        }
 
-        serde_json::from_str::<EmailAnalysis>(json_str).map_err(|e| {
-            format!(
-                "Failed to parse analysis JSON: {}. Content: {}",
-                e, response_text
+        let summary = if partial_summaries.len() > 1 {
+            let user_prompt = format!(
+                "{}## Summaries of Consecutive Parts of the Thread\nThe thread was too long to read at once. Combine these summaries of its parts, oldest first, into one summary of the whole thread.\n\n{}",
+                user_section,
+                partial_summaries.join(THREAD_SEPARATOR)
+            );
+            self.send_prompt(
+                "summarize_conversation",
+                &email_ids,
+                &system_prompt,
+                &user_prompt,
             )
+            .await?
+        } else {
+            partial_summaries.pop().unwrap_or_default()
+        };
+
+        Ok(ConversationSummary {
+            summary: summary.trim().to_string(),
+            message_count: messages.len(),
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// One message of a thread as the summary prompt shows it. Quoted history
+    /// is left out, the thread's other messages carry it.
+    fn format_thread_message(email: &Email) -> String {
+        let from = match email.from().name.as_deref().filter(|n| !n.is_empty()) {
+            Some(name) => format!("{} <{}>", name, email.from().address),
+            None => email.from().address.clone(),
+        };
+        let body = match (&email.body_plain, &email.body_html) {
+            (Some(plain), _) if !plain.trim().is_empty() => plain.clone(),
+            (_, Some(html)) => Turndown::default().convert(html),
+            _ => email.snippet.clone().unwrap_or_default(),
+        };
+
+        format!(
+            "From: {}\nDate: {}\nSubject: {}\n\n{}",
+            from,
+            email.received_at.to_rfc3339(),
+            email.subject.as_deref().unwrap_or("(No subject)"),
+            body.trim()
+        )
+    }
+
+    /// Answers a question from the emails retrieved for it, best match first.
+    /// Emails that do not fit the context budget are left out.
+    pub async fn ask_mailbox(
+        &self,
+        question: &str,
+        emails: &[Email],
+        user_context: Option<&UserContext>,
+    ) -> Result<MailboxAnswer, String> {
+        if !self.is_enabled().await {
+            return Err(
+                "AI service is not enabled. Please configure an API key or activate a license."
+                    .to_string(),
+            );
+        }
+
+        let system_prompt = self.get_prompt("askMailbox")?;
+        let user_section = match user_context {
+            Some(ctx) => format!(
+                "## Current User\nName: {}\nEmail: {}\n\n",
+                ctx.name, ctx.email
+            ),
+            None => String::new(),
+        };
+
+        let mut budget = MAX_ASK_MAILBOX_CONTEXT_TOKENS * APPROX_CHARS_PER_TOKEN;
+        let mut retrieved = Vec::new();
+        let mut context = String::new();
+        for email in emails {
+            let message = truncate_message(
+                Self::format_thread_message(email),
+                MAX_ASK_MAILBOX_EMAIL_TOKENS * APPROX_CHARS_PER_TOKEN,
+            );
+            let section = format!("# Email [{}]\n{}\n\n", email.id, message);
+            if section.len() > budget {
+                break;
+            }
+            budget -= section.len();
+            context.push_str(&section);
+            retrieved.push(email.id);
+        }
+        log::debug!(
+            "Asking mailbox with {} of {} retrieved emails",
+            retrieved.len(),
+            emails.len()
+        );
+
+        let user_prompt = format!(
+            "{}Current DateTime: {}\n\n## Question\n{}\n\n## Emails\n{}",
+            user_section,
+            Utc::now().to_rfc3339(),
+            question.trim(),
+            context
+        );
+        let response_text = self
+            .send_prompt("ask_mailbox", &retrieved, &system_prompt, &user_prompt)
+            .await?;
+        let raw: RawMailboxAnswer = parse_analysis_json(&response_text)?;
+
+        Ok(MailboxAnswer {
+            answer: raw.answer.trim().to_string(),
+            sources: filter_sources(&raw.sources, &retrieved),
         })
     }
 
+    /// Derives a style profile (tone, greetings, sign-offs) from a sample of
+    /// the user's sent emails
+    pub async fn learn_writing_style(&self, samples: &[Email]) -> Result<String, String> {
+        if !self.is_enabled().await {
+            return Err(
+                "AI service is not enabled. Please configure an API key or activate a license."
+                    .to_string(),
+            );
+        }
+        if samples.is_empty() {
+            return Err("No sent emails to learn a writing style from".to_string());
+        }
+
+        let system_prompt = self.get_prompt("learnWritingStyle")?;
+        let emails = samples
+            .iter()
+            .map(|email| {
+                truncate_message(
+                    Self::format_sent_sample(email),
+                    MAX_STYLE_SAMPLE_TOKENS * APPROX_CHARS_PER_TOKEN,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(THREAD_SEPARATOR);
+        let user_prompt = format!(
+            "## Sent Emails ({} samples, newest first)\n{}",
+            samples.len(),
+            emails
+        );
+
+        let email_ids: Vec<Uuid> = samples.iter().map(|email| email.id).collect();
+        let profile = self
+            .send_prompt(
+                "learn_writing_style",
+                &email_ids,
+                &system_prompt,
+                &user_prompt,
+            )
+            .await?;
+        Ok(profile.trim().to_string())
+    }
+
+    /// A sent email as the style prompt shows it. Quoted history is left out,
+    /// it was written by someone else.
+    fn format_sent_sample(email: &Email) -> String {
+        let body = match (&email.body_plain, &email.body_html) {
+            (Some(plain), _) if !plain.trim().is_empty() => plain.clone(),
+            (_, Some(html)) => Turndown::default().convert(html),
+            _ => email.snippet.clone().unwrap_or_default(),
+        };
+        let to = email
+            .to()
+            .iter()
+            .map(|recipient| recipient.address.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "To: {}\nSubject: {}\n\n{}",
+            to,
+            email.subject.as_deref().unwrap_or("(No subject)"),
+            body.trim()
+        )
+    }
+
     pub async fn generate_search_query(
         &self,
         request: GenerateSearchQueryRequest,
@@ -739,6 +1269,13 @@ Received At: {}
             request.natural_language_query,
             chrono::Utc::now().to_rfc3339()
         );
+        self.audit(
+            "generate_search_query",
+            &model,
+            &[],
+            &[&system_prompt, &prompt],
+        )
+        .await;
 
         let messages = vec![
             OpenRouterChatMessage::new(Role::System, &*system_prompt),
@@ -845,3 +1382,47 @@ Received At: {}
         message
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_thread() {
+        let messages = vec!["a".repeat(40), "b".repeat(40), "c".repeat(40)];
+
+        let chunks = chunk_thread(messages.clone(), 100);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with(&"a".repeat(40)));
+        assert!(chunks[0].ends_with(&"b".repeat(40)));
+        assert_eq!(chunks[1], "c".repeat(40));
+
+        assert_eq!(chunk_thread(messages, 1000).len(), 1);
+        assert!(chunk_thread(Vec::new(), 100).is_empty());
+
+        let long = chunk_thread(vec!["é".repeat(100), "d".repeat(10)], 51);
+        assert_eq!(long.len(), 2);
+        assert!(long[0].starts_with(&"é".repeat(25)));
+        assert!(long[0].ends_with("[... message truncated ...]"));
+        assert_eq!(long[1], "d".repeat(10));
+    }
+
+    #[test]
+    fn test_filter_sources() {
+        let first = Uuid::now_v7();
+        let second = Uuid::now_v7();
+        let cited = vec![
+            format!("[{}]", second),
+            Uuid::now_v7().to_string(),
+            "not an id".to_string(),
+            first.to_string(),
+            second.to_string(),
+        ];
+
+        assert_eq!(
+            filter_sources(&cited, &[first, second]),
+            vec![second, first]
+        );
+        assert!(filter_sources(&cited, &[]).is_empty());
+    }
+}
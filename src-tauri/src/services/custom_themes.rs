@@ -0,0 +1,383 @@
+//! User themes, kept as JSON under `app_data/themes` next to the CSS they
+//! render to. The CSS is what `list_themes` and `get_theme` serve, so user
+//! themes are picked and applied like the bundled ones; the JSON keeps the
+//! tokens editable and is the format themes are imported and exported in.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::services::theme_scheduler::Appearance;
+
+/// Version of the theme file format
+pub const THEME_FILE_VERSION: u32 = 1;
+
+/// Prefix of the ids of user themes, as used by `list_themes`
+const USER_THEME_PREFIX: &str = "user/";
+const MAX_NAME_LEN: usize = 64;
+const MAX_TOKENS: usize = 1024;
+const MAX_TOKEN_NAME_LEN: usize = 64;
+const MAX_TOKEN_VALUE_LEN: usize = 256;
+
+/// Tokens every theme sets, without which the UI is unreadable
+const REQUIRED_TOKENS: &[&str] = &[
+    "--surface",
+    "--background",
+    "--foreground",
+    "--primary",
+    "--accent",
+    "--border",
+];
+
+/// Values that could load remote content or escape the declaration
+const FORBIDDEN_IN_VALUES: &[&str] = &["url(", "@import", "expression(", "javascript:", "/*"];
+
+fn default_version() -> u32 {
+    THEME_FILE_VERSION
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomTheme {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    pub name: String,
+    /// Whether the theme is light or dark, for the theme modes that follow
+    /// the OS appearance or a schedule
+    pub appearance: Appearance,
+    /// Theme the tokens were copied from
+    #[serde(default)]
+    pub base: Option<String>,
+    /// CSS custom properties, by name including the leading `--`
+    pub tokens: BTreeMap<String, String>,
+}
+
+impl CustomTheme {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version > THEME_FILE_VERSION {
+            return Err(format!(
+                "Theme file version {} is newer than the supported version {}",
+                self.version, THEME_FILE_VERSION
+            ));
+        }
+
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Theme name must not be empty".to_string());
+        }
+        if name.chars().count() > MAX_NAME_LEN {
+            return Err(format!(
+                "Theme name must be at most {} characters",
+                MAX_NAME_LEN
+            ));
+        }
+
+        if self.tokens.len() > MAX_TOKENS {
+            return Err(format!("Themes can set at most {} tokens", MAX_TOKENS));
+        }
+
+        for token in REQUIRED_TOKENS {
+            if !self.tokens.contains_key(*token) {
+                return Err(format!("Theme is missing the required token {}", token));
+            }
+        }
+
+        for (token, value) in &self.tokens {
+            validate_token_name(token)?;
+            validate_token_value(token, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// The stylesheet applied for the theme
+    pub fn to_css(&self) -> String {
+        let mut css = format!("/* {} */\n:root {{\n", self.name.trim().replace("*/", ""));
+        for (token, value) in &self.tokens {
+            css.push_str(&format!("  {}: {};\n", token, value.trim()));
+        }
+        css.push_str("}\n");
+        css
+    }
+}
+
+fn validate_token_name(token: &str) -> Result<(), String> {
+    let valid = token.len() <= MAX_TOKEN_NAME_LEN
+        && token.strip_prefix("--").is_some_and(|name| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid token name: {}", token))
+    }
+}
+
+fn validate_token_value(token: &str, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(format!("Token {} has no value", token));
+    }
+    if value.len() > MAX_TOKEN_VALUE_LEN {
+        return Err(format!(
+            "Value of {} must be at most {} characters",
+            token, MAX_TOKEN_VALUE_LEN
+        ));
+    }
+    if value
+        .chars()
+        .any(|c| matches!(c, ';' | '{' | '}' | '<' | '>' | '\\') || c.is_control())
+    {
+        return Err(format!("Value of {} contains invalid characters", token));
+    }
+
+    let lowercase = value.to_lowercase();
+    if let Some(forbidden) = FORBIDDEN_IN_VALUES
+        .iter()
+        .find(|forbidden| lowercase.contains(*forbidden))
+    {
+        return Err(format!("Value of {} must not use {}", token, forbidden));
+    }
+
+    Ok(())
+}
+
+/// Custom properties declared in the `:root` block of a theme stylesheet
+pub fn parse_css_tokens(css: &str) -> BTreeMap<String, String> {
+    let mut without_comments = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        without_comments.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    without_comments.push_str(rest);
+
+    let Some(block) = without_comments.find(":root").and_then(|root| {
+        let open = root + without_comments[root..].find('{')? + 1;
+        let close = open + without_comments[open..].find('}')?;
+        Some(&without_comments[open..close])
+    }) else {
+        return BTreeMap::new();
+    };
+
+    block
+        .split(';')
+        .filter_map(|declaration| {
+            let (token, value) = declaration.split_once(':')?;
+            let token = token.trim();
+            token
+                .starts_with("--")
+                .then(|| (token.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn slugify(name: &str) -> String {
+    let slug = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        "theme".to_string()
+    } else {
+        slug
+    }
+}
+
+/// The user themes directory
+pub struct ThemeStore {
+    dir: PathBuf,
+}
+
+impl ThemeStore {
+    pub fn new(app_data_dir: &Path) -> Self {
+        Self {
+            dir: app_data_dir.join("themes"),
+        }
+    }
+
+    pub fn theme_id(slug: &str) -> String {
+        format!("{}{}.css", USER_THEME_PREFIX, slug)
+    }
+
+    /// The file stem of a user theme id, rejecting bundled themes and paths
+    pub fn slug_from_id(theme_id: &str) -> Result<&str, String> {
+        let slug = theme_id
+            .strip_prefix(USER_THEME_PREFIX)
+            .and_then(|filename| filename.strip_suffix(".css"))
+            .ok_or_else(|| "Only user themes can be edited".to_string())?;
+
+        if slug.is_empty() || slug != slugify(slug) {
+            return Err(format!("Invalid theme ID: {}", theme_id));
+        }
+
+        Ok(slug)
+    }
+
+    fn json_path(&self, slug: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", slug))
+    }
+
+    fn css_path(&self, slug: &str) -> PathBuf {
+        self.dir.join(format!("{}.css", slug))
+    }
+
+    /// Stylesheets written by hand rather than from a theme file can't be
+    /// edited, so they have no theme
+    pub fn load(&self, slug: &str) -> Result<CustomTheme, String> {
+        let content = fs::read_to_string(self.json_path(slug))
+            .map_err(|e| format!("Failed to read theme {}: {}", slug, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid theme file: {}", e))
+    }
+
+    pub fn appearance(&self, slug: &str) -> Option<Appearance> {
+        self.load(slug).ok().map(|theme| theme.appearance)
+    }
+
+    /// Validate and write the theme file and its stylesheet
+    pub fn save(&self, slug: &str, theme: &CustomTheme) -> Result<(), String> {
+        theme.validate()?;
+
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create themes directory: {}", e))?;
+
+        let theme = CustomTheme {
+            version: THEME_FILE_VERSION,
+            name: theme.name.trim().to_string(),
+            ..theme.clone()
+        };
+        let json = serde_json::to_string_pretty(&theme)
+            .map_err(|e| format!("Failed to serialize theme: {}", e))?;
+        fs::write(self.json_path(slug), json)
+            .map_err(|e| format!("Failed to write theme file: {}", e))?;
+        fs::write(self.css_path(slug), theme.to_css())
+            .map_err(|e| format!("Failed to write theme stylesheet: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Save a new theme under a file name derived from its name, returning
+    /// its theme id
+    pub fn create(&self, theme: &CustomTheme) -> Result<String, String> {
+        let base_slug = slugify(&theme.name);
+        let mut slug = base_slug.clone();
+        let mut suffix = 2;
+        while self.json_path(&slug).exists() || self.css_path(&slug).exists() {
+            slug = format!("{}-{}", base_slug, suffix);
+            suffix += 1;
+        }
+
+        self.save(&slug, theme)?;
+        Ok(Self::theme_id(&slug))
+    }
+
+    pub fn delete(&self, slug: &str) -> Result<(), String> {
+        for path in [self.json_path(slug), self.css_path(slug)] {
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a theme file from anywhere, for importing
+    pub fn read_file(path: &Path) -> Result<CustomTheme, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let theme: CustomTheme =
+            serde_json::from_str(&content).map_err(|e| format!("Invalid theme file: {}", e))?;
+        theme.validate()?;
+        Ok(theme)
+    }
+
+    pub fn export(&self, slug: &str, destination: &Path) -> Result<(), String> {
+        let theme = self.load(slug)?;
+        let json = serde_json::to_string_pretty(&theme)
+            .map_err(|e| format!("Failed to serialize theme: {}", e))?;
+        fs::write(destination, json)
+            .map_err(|e| format!("Failed to write {}: {}", destination.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme() -> CustomTheme {
+        let css = fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/themes/dark.css"),
+        )
+        .unwrap();
+
+        CustomTheme {
+            version: THEME_FILE_VERSION,
+            name: "Midnight".to_string(),
+            appearance: Appearance::Dark,
+            base: Some("builtin/dark.css".to_string()),
+            tokens: parse_css_tokens(&css),
+        }
+    }
+
+    #[test]
+    fn test_parse_bundled_theme() {
+        let theme = theme();
+        assert_eq!(
+            theme.tokens.get("--surface").map(String::as_str),
+            Some("var(--color-gray-950)")
+        );
+        assert!(theme.validate().is_ok());
+
+        let reparsed = parse_css_tokens(&theme.to_css());
+        assert_eq!(reparsed, theme.tokens);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut invalid = theme();
+        invalid.tokens.insert(
+            "--background".to_string(),
+            "red; } body { display: none".to_string(),
+        );
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = theme();
+        invalid.tokens.insert(
+            "--background".to_string(),
+            "url(https://example.com/pixel.png)".to_string(),
+        );
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = theme();
+        invalid
+            .tokens
+            .insert("background".to_string(), "red".to_string());
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = theme();
+        invalid.tokens.remove("--primary");
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_slug_from_id() {
+        assert_eq!(
+            ThemeStore::slug_from_id("user/midnight-2.css"),
+            Ok("midnight-2")
+        );
+        assert!(ThemeStore::slug_from_id("builtin/dark.css").is_err());
+        assert!(ThemeStore::slug_from_id("user/../settings.css").is_err());
+    }
+}
@@ -1,18 +1,37 @@
+use super::dkim::{self, DkimKey};
 use super::email_renderer::{html_to_plain_text, render_email_html};
+use super::smime::{self, SmimeRequest};
 use crate::database::models::email::EmailAddress;
+use crate::sync::tls_trust::{self, UntrustedCertificate};
+use crate::sync::types::TlsMode;
+use base64::{engine::general_purpose, Engine as _};
 /// Email sending service using SMTP
 use lettre::{
-    message::{header::ContentType, Attachment, Mailbox, Message, MultiPart, SinglePart},
+    address::Envelope,
+    message::{
+        header::{
+            ContentDisposition, ContentTransferEncoding, ContentType, Header, HeaderName,
+            HeaderValue,
+        },
+        Attachment, Body, Mailbox, Message, MultiPart, SinglePart,
+    },
     transport::smtp::{
-        authentication::Credentials,
-        client::{Tls, TlsParameters},
+        authentication::{Credentials, Mechanism},
+        client::{AsyncSmtpConnection, TlsParameters},
+        commands::{Data, Ehlo, Mail, Rcpt},
+        extension::{ClientId, MailBodyParameter, MailParameter, RcptParameter},
     },
-    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
 };
 use mime_guess::from_path;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
+use std::time::Duration;
+use uuid::Uuid;
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[derive(Debug)]
 pub enum EmailError {
@@ -22,6 +41,9 @@ pub enum EmailError {
     ConfigError(String),
     AttachmentError(String),
     IoError(String),
+    SmimeError(String),
+    DkimError(String),
+    UntrustedCertificate(String),
 }
 
 impl fmt::Display for EmailError {
@@ -33,6 +55,9 @@ impl fmt::Display for EmailError {
             EmailError::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
             EmailError::AttachmentError(msg) => write!(f, "Attachment error: {}", msg),
             EmailError::IoError(msg) => write!(f, "IO error: {}", msg),
+            EmailError::SmimeError(msg) => write!(f, "S/MIME error: {}", msg),
+            EmailError::DkimError(msg) => write!(f, "DKIM error: {}", msg),
+            EmailError::UntrustedCertificate(msg) => write!(f, "{}", msg),
         }
     }
 }
@@ -59,6 +84,16 @@ pub struct SmtpConfig {
     pub username: Option<String>,
     pub password: Option<String>,
     pub use_tls: bool,
+    /// `password` is an OAuth2 access token, sent with XOAUTH2
+    #[serde(default)]
+    pub oauth2: bool,
+    /// `None` goes by the port, see [`TlsMode::for_smtp_port`]
+    #[serde(default)]
+    pub tls_mode: Option<TlsMode>,
+    /// SHA-256 fingerprint of a certificate accepted although it fails
+    /// validation
+    #[serde(default)]
+    pub trusted_certificate: Option<String>,
 }
 
 impl SmtpConfig {
@@ -70,6 +105,9 @@ impl SmtpConfig {
             username: Option::from("".to_string()),
             password: Option::from("".to_string()),
             use_tls: true,
+            oauth2: false,
+            tls_mode: None,
+            trusted_certificate: None,
         })
     }
 }
@@ -80,6 +118,50 @@ pub struct EmailAttachment {
     pub filename: String,
     pub content: Vec<u8>,
     pub content_type: Option<String>,
+    /// Set for inline images, which the HTML body references as `cid:`
+    #[serde(default)]
+    pub content_id: Option<String>,
+}
+
+/// Images embedded in composed HTML as base64 `data:` URIs
+static DATA_URI_IMAGE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(src\s*=\s*["'])data:(image/[a-z0-9.+-]+);base64,([a-z0-9+/=\s]+)(["'])"#)
+        .expect("Failed to compile data URI regex")
+});
+
+/// Moves images embedded as `data:` URIs in composed HTML into inline
+/// attachments, since many mail clients do not display `data:` images.
+/// Returns the HTML referencing them by `cid:` instead.
+pub fn extract_inline_images(html: &str, domain: &str) -> (String, Vec<EmailAttachment>) {
+    let mut images = Vec::new();
+
+    let html = DATA_URI_IMAGE_REGEX.replace_all(html, |caps: &Captures| {
+        let data: String = caps[3].chars().filter(|c| !c.is_whitespace()).collect();
+        let Ok(content) = general_purpose::STANDARD.decode(data) else {
+            return caps[0].to_string();
+        };
+
+        let content_type = caps[2].to_lowercase();
+        let extension = content_type
+            .trim_start_matches("image/")
+            .split('+')
+            .next()
+            .unwrap_or("img")
+            .to_string();
+        let number = images.len() + 1;
+        let content_id = format!("image{}.{}@{}", number, Uuid::now_v7().simple(), domain);
+
+        images.push(EmailAttachment {
+            filename: format!("image{}.{}", number, extension),
+            content,
+            content_type: Some(content_type),
+            content_id: Some(content_id.clone()),
+        });
+
+        format!("{}cid:{}{}", &caps[1], content_id, &caps[4])
+    });
+
+    (html.into_owned(), images)
 }
 
 /// Email data for sending
@@ -91,9 +173,177 @@ pub struct EmailData {
     pub bcc: Vec<EmailAddress>,
     pub subject: String,
     pub body_html: String,
+    /// Text alternative, derived from `body_html` when `None`
+    #[serde(default)]
+    pub body_plain: Option<String>,
     pub attachments: Vec<EmailAttachment>,
     pub in_reply_to: Option<String>,
     pub references: Option<String>,
+    /// `Message-ID` to send with, including angle brackets; generated when `None`
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// Ask the recipients for a read receipt (RFC 8098) sent to the `from` address
+    #[serde(default)]
+    pub read_receipt: bool,
+    /// Sign and/or encrypt the message content
+    #[serde(skip)]
+    pub smime: Option<SmimeRequest>,
+    /// DKIM-sign the message when sent over SMTP
+    #[serde(skip)]
+    pub dkim: Option<DkimKey>,
+}
+
+/// `Disposition-Notification-To` header requesting a read receipt
+#[derive(Debug, Clone)]
+struct DispositionNotificationTo(String);
+
+impl Header for DispositionNotificationTo {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("Disposition-Notification-To")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self(s.to_string()))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), self.0.clone())
+    }
+}
+
+/// Which delivery status notifications to ask the receiving servers for (RFC 3461)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DsnOptions {
+    #[serde(default = "default_true")]
+    pub notify_success: bool,
+    #[serde(default = "default_true")]
+    pub notify_failure: bool,
+    #[serde(default)]
+    pub notify_delay: bool,
+}
+
+impl Default for DsnOptions {
+    fn default() -> Self {
+        Self {
+            notify_success: true,
+            notify_failure: true,
+            notify_delay: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl DsnOptions {
+    /// Value of the `NOTIFY` RCPT parameter
+    pub fn notify_value(&self) -> String {
+        let events: Vec<&str> = [
+            (self.notify_success, "SUCCESS"),
+            (self.notify_failure, "FAILURE"),
+            (self.notify_delay, "DELAY"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, event)| enabled.then_some(event))
+        .collect();
+
+        if events.is_empty() {
+            "NEVER".to_string()
+        } else {
+            events.join(",")
+        }
+    }
+}
+
+/// DSN request for a single submission. `envelope_id` comes back as
+/// `Original-Envelope-Id` in the reports, which ties them to the sent message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DsnRequest {
+    pub envelope_id: String,
+    pub options: DsnOptions,
+}
+
+/// What an SMTP server advertises in its EHLO response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmtpCapabilities {
+    /// A plain connection can be upgraded with STARTTLS
+    pub starttls: bool,
+    /// The session was encrypted, with implicit TLS or after STARTTLS
+    pub encrypted: bool,
+    /// Largest accepted message in bytes, `None` when not announced or unlimited
+    pub max_size: Option<u64>,
+    /// Offered SASL mechanisms, uppercase
+    pub auth_mechanisms: Vec<String>,
+    pub dsn: bool,
+    pub eight_bit_mime: bool,
+    pub smtputf8: bool,
+    pub pipelining: bool,
+}
+
+impl SmtpCapabilities {
+    /// Read the keywords of an EHLO response, whose first line is the greeting
+    fn from_ehlo<'a>(lines: impl Iterator<Item = &'a str>) -> Self {
+        let mut capabilities = Self::default();
+
+        for line in lines.skip(1) {
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+
+            match keyword.to_ascii_uppercase().as_str() {
+                "STARTTLS" => capabilities.starttls = true,
+                "SIZE" => {
+                    capabilities.max_size = words
+                        .next()
+                        .and_then(|size| size.parse().ok())
+                        .filter(|&size| size > 0)
+                }
+                "AUTH" => {
+                    for mechanism in words {
+                        let mechanism = mechanism.to_ascii_uppercase();
+                        if !capabilities.auth_mechanisms.contains(&mechanism) {
+                            capabilities.auth_mechanisms.push(mechanism);
+                        }
+                    }
+                }
+                "DSN" => capabilities.dsn = true,
+                "8BITMIME" => capabilities.eight_bit_mime = true,
+                "SMTPUTF8" => capabilities.smtputf8 = true,
+                "PIPELINING" => capabilities.pipelining = true,
+                _ => {}
+            }
+        }
+
+        capabilities
+    }
+
+    pub fn supports_auth(&self, mechanism: Mechanism) -> bool {
+        self.auth_mechanisms
+            .iter()
+            .any(|offered| *offered == mechanism.to_string())
+    }
+}
+
+/// Outcome of [`EmailService::probe`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpProbe {
+    pub capabilities: SmtpCapabilities,
+    /// Whether the credentials were accepted, `None` without credentials
+    pub authenticated: Option<bool>,
+    pub auth_error: Option<String>,
+}
+
+/// Encode a value as xtext (RFC 3461 section 4)
+fn xtext(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'!'..=b'~' if b != b'+' && b != b'=' => (b as char).to_string(),
+            _ => format!("+{:02X}", b),
+        })
+        .collect()
 }
 
 /// Email service for sending emails via SMTP
@@ -122,10 +372,69 @@ impl EmailService {
             username: Some(smtp_username),
             password: Some(smtp_password),
             use_tls: smtp_use_tls,
+            oauth2: false,
+            tls_mode: None,
+            trusted_certificate: None,
         };
         Ok(Self::new(config))
     }
 
+    /// Create email service authenticating with an OAuth2 access token
+    /// (XOAUTH2) instead of a password
+    pub fn from_oauth2(
+        smtp_host: String,
+        smtp_port: u16,
+        smtp_use_tls: bool,
+        smtp_username: String,
+        access_token: String,
+    ) -> Self {
+        Self::new(SmtpConfig {
+            host: smtp_host,
+            port: smtp_port,
+            username: Some(smtp_username),
+            password: Some(access_token),
+            use_tls: smtp_use_tls,
+            oauth2: true,
+            tls_mode: None,
+            trusted_certificate: None,
+        })
+    }
+
+    /// Encrypt connections with `tls_mode` instead of the port's usual one,
+    /// and accept the certificate with the `trusted_certificate` fingerprint
+    pub fn with_tls(
+        mut self,
+        tls_mode: Option<TlsMode>,
+        trusted_certificate: Option<String>,
+    ) -> Self {
+        self.config.tls_mode = tls_mode;
+        self.config.trusted_certificate = trusted_certificate;
+        self
+    }
+
+    fn tls_mode(&self) -> TlsMode {
+        self.config
+            .tls_mode
+            .unwrap_or_else(|| TlsMode::for_smtp_port(self.config.port))
+    }
+
+    fn credentials(&self) -> Option<Credentials> {
+        match (&self.config.username, &self.config.password) {
+            (Some(user), Some(pass)) if !user.is_empty() && !pass.is_empty() => {
+                Some(Credentials::new(user.clone(), pass.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    fn auth_mechanisms(&self) -> Vec<Mechanism> {
+        if self.config.oauth2 {
+            vec![Mechanism::Xoauth2]
+        } else {
+            vec![Mechanism::Plain, Mechanism::Login]
+        }
+    }
+
     /// Convert EmailAddress to Mailbox
     fn to_mailbox(email_address: &EmailAddress) -> Result<Mailbox, EmailError> {
         let mailbox = if let Some(name) = &email_address.name {
@@ -153,8 +462,19 @@ impl EmailService {
             .unwrap_or_else(|_| ContentType::parse("application/octet-stream").unwrap())
     }
 
-    /// Send an email
-    pub async fn send_email(&self, email_data: EmailData) -> Result<(), EmailError> {
+    fn content_type_of(attachment: &EmailAttachment) -> ContentType {
+        if let Some(ct) = &attachment.content_type {
+            ContentType::parse(ct).unwrap_or_else(|_| Self::detect_mime_type(&attachment.filename))
+        } else {
+            Self::detect_mime_type(&attachment.filename)
+        }
+    }
+
+    fn build_message(
+        email_data: EmailData,
+        keep_bcc: bool,
+        draft: bool,
+    ) -> Result<Message, EmailError> {
         let from: Mailbox = email_data
             .from
             .parse()
@@ -163,6 +483,16 @@ impl EmailService {
         let mut message_builder = Message::builder()
             .from(from.clone())
             .subject(email_data.subject);
+        if keep_bcc {
+            message_builder = message_builder.keep_bcc();
+        }
+        if draft {
+            // Drafts may have no recipients yet, while lettre derives the
+            // envelope from them by default
+            let envelope = Envelope::new(Some(from.email.clone()), vec![from.email.clone()])
+                .map_err(|e| EmailError::InvalidEmail(e.to_string()))?;
+            message_builder = message_builder.envelope(envelope);
+        }
 
         if let Some(in_reply_to) = email_data.in_reply_to {
             message_builder = message_builder.in_reply_to(in_reply_to);
@@ -170,6 +500,13 @@ impl EmailService {
         if let Some(references) = email_data.references {
             message_builder = message_builder.references(references);
         }
+        if let Some(message_id) = email_data.message_id {
+            message_builder = message_builder.message_id(Some(message_id));
+        }
+        if email_data.read_receipt {
+            message_builder =
+                message_builder.header(DispositionNotificationTo(from.email.to_string()));
+        }
 
         for to_addr in &email_data.to {
             message_builder = message_builder.to(Self::to_mailbox(to_addr)?);
@@ -183,89 +520,405 @@ impl EmailService {
             message_builder = message_builder.bcc(Self::to_mailbox(bcc_addr)?);
         }
 
-        let html_body = render_email_html(&email_data.body_html);
-        let plain_body = html_to_plain_text(&email_data.body_html);
+        // Drafts keep the composer's HTML, so they open again unchanged
+        let html_body = if draft {
+            email_data.body_html.clone()
+        } else {
+            render_email_html(&email_data.body_html)
+        };
+        let plain_body = email_data
+            .body_plain
+            .unwrap_or_else(|| html_to_plain_text(&email_data.body_html));
 
-        let alternative_part = MultiPart::alternative()
-            .singlepart(
-                SinglePart::builder()
-                    .header(ContentType::TEXT_PLAIN)
-                    .body(plain_body),
-            )
-            .singlepart(
-                SinglePart::builder()
-                    .header(ContentType::TEXT_HTML)
-                    .body(html_body),
-            );
+        let (inline_images, attachments): (Vec<_>, Vec<_>) = email_data
+            .attachments
+            .iter()
+            .partition(|attachment| attachment.content_id.is_some());
 
-        let message = if email_data.attachments.is_empty() {
-            message_builder
-                .multipart(alternative_part)
-                .map_err(|e| EmailError::BuildError(e.to_string()))?
+        let plain_part = MultiPart::alternative().singlepart(
+            SinglePart::builder()
+                .header(ContentType::TEXT_PLAIN)
+                .body(plain_body),
+        );
+        let html_part = SinglePart::builder()
+            .header(ContentType::TEXT_HTML)
+            .body(html_body);
+
+        // Inline images travel next to the HTML that references them
+        let alternative_part = if inline_images.is_empty() {
+            plain_part.singlepart(html_part)
         } else {
-            let mut mixed = MultiPart::mixed().multipart(alternative_part);
+            let mut related = MultiPart::related().singlepart(html_part);
+
+            for image in inline_images {
+                let content_id = image.content_id.clone().unwrap_or_default();
+                let image_part =
+                    Attachment::new_inline_with_name(content_id, image.filename.clone())
+                        .body(image.content.clone(), Self::content_type_of(image));
+
+                related = related.singlepart(image_part);
+            }
+
+            plain_part.multipart(related)
+        };
 
-            for attachment in &email_data.attachments {
-                let content_type = if let Some(ct) = &attachment.content_type {
-                    ContentType::parse(ct)
-                        .unwrap_or_else(|_| Self::detect_mime_type(&attachment.filename))
-                } else {
-                    Self::detect_mime_type(&attachment.filename)
-                };
+        let body = if attachments.is_empty() {
+            alternative_part
+        } else {
+            let mut mixed = MultiPart::mixed().multipart(alternative_part);
 
-                let attachment_part = Attachment::new(attachment.filename.clone())
-                    .body(attachment.content.clone(), content_type);
+            for attachment in attachments {
+                let attachment_part = Attachment::new(attachment.filename.clone()).body(
+                    attachment.content.clone(),
+                    Self::content_type_of(attachment),
+                );
 
                 mixed = mixed.singlepart(attachment_part);
             }
 
-            message_builder
-                .multipart(mixed)
-                .map_err(|e| EmailError::BuildError(e.to_string()))?
+            mixed
+        };
+
+        let message = match &email_data.smime {
+            Some(request) => {
+                let entity = smime::protect_entity(body.formatted(), request)
+                    .map_err(|e| EmailError::SmimeError(e.to_string()))?;
+                message_builder.singlepart(Self::smime_part(entity)?)
+            }
+            None => message_builder.multipart(body),
+        }
+        .map_err(|e| EmailError::BuildError(e.to_string()))?;
+
+        Ok(message)
+    }
+
+    /// Carry a signed or encrypted entity as the message body without
+    /// re-encoding it, which would break the signature
+    fn smime_part(entity: Vec<u8>) -> Result<SinglePart, EmailError> {
+        let entity =
+            String::from_utf8(entity).map_err(|e| EmailError::SmimeError(e.to_string()))?;
+        let (head, body) = entity
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| EmailError::SmimeError("S/MIME entity has no body".to_string()))?;
+        let header = |name: &str| {
+            head.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.trim()
+                    .eq_ignore_ascii_case(name)
+                    .then(|| value.trim().to_string())
+            })
         };
 
-        let mailer = if self.config.use_tls {
-            let tls_parameters = TlsParameters::builder(self.config.host.clone())
-                .build()
-                .map_err(|e| EmailError::SmtpError(e.to_string()))?;
+        let content_type = header("Content-Type").ok_or_else(|| {
+            EmailError::SmimeError("S/MIME entity has no content type".to_string())
+        })?;
+        let mut part = SinglePart::builder().header(
+            ContentType::parse(&content_type).map_err(|e| EmailError::SmimeError(e.to_string()))?,
+        );
+        if header("Content-Disposition").is_some() {
+            part = part.header(ContentDisposition::attachment("smime.p7m"));
+        }
 
-            let mut transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&self.config.host)
-                .map_err(|e| EmailError::SmtpError(e.to_string()))?
-                .port(self.config.port);
+        let encoding = match header("Content-Transfer-Encoding") {
+            Some(cte) if cte.eq_ignore_ascii_case("base64") => ContentTransferEncoding::Base64,
+            _ => ContentTransferEncoding::SevenBit,
+        };
 
-            if let (Some(user), Some(pass)) = (&self.config.username, &self.config.password) {
-                if !user.is_empty() && !pass.is_empty() {
-                    transport = transport.credentials(Credentials::new(user.clone(), pass.clone()));
+        Ok(part.body(Body::dangerous_pre_encoded(
+            body.as_bytes().to_vec(),
+            encoding,
+        )))
+    }
+
+    /// Build the complete RFC 5322 message, for providers that accept MIME
+    /// directly. `Bcc` is kept, since the provider reads recipients from it.
+    pub fn build_mime(email_data: EmailData) -> Result<Vec<u8>, EmailError> {
+        Ok(Self::build_message(email_data, true, false)?.formatted())
+    }
+
+    /// Build the complete message of a draft, for storing it on the server.
+    /// Unlike `build_mime` it does not require recipients.
+    pub fn build_draft_mime(email_data: EmailData) -> Result<Vec<u8>, EmailError> {
+        Ok(Self::build_message(email_data, true, true)?.formatted())
+    }
+
+    /// Send an email
+    pub async fn send_email(&self, mut email_data: EmailData) -> Result<(), EmailError> {
+        let recipients = email_data.to.len() + email_data.cc.len() + email_data.bcc.len();
+        let attachments = email_data.attachments.len();
+        let dkim = email_data.dkim.take();
+        let message = Self::build_message(email_data, false, false)?;
+        let body = Self::signed_body(&message, dkim.as_ref())?;
+
+        self.send_raw(message.envelope(), &body).await?;
+
+        log::info!(
+            "Email sent successfully to {} recipients with {} attachment(s)",
+            recipients,
+            attachments
+        );
+
+        Ok(())
+    }
+
+    /// Send an email asking the receiving servers for delivery status notifications.
+    ///
+    /// Returns whether the DSN request was actually made. Servers that do not
+    /// advertise the DSN extension get the message without it.
+    pub async fn send_email_with_dsn(
+        &self,
+        mut email_data: EmailData,
+        dsn: &DsnRequest,
+    ) -> Result<bool, EmailError> {
+        let recipients = email_data.to.len() + email_data.cc.len() + email_data.bcc.len();
+        let dkim = email_data.dkim.take();
+        let message = Self::build_message(email_data, false, false)?;
+        let body = Self::signed_body(&message, dkim.as_ref())?;
+
+        if self.send_with_dsn(message.envelope(), &body, dsn).await? {
+            log::info!(
+                "Email sent successfully to {} recipients with DSN requested (NOTIFY={})",
+                recipients,
+                dsn.options.notify_value()
+            );
+            return Ok(true);
+        }
+
+        log::info!(
+            "SMTP server {} does not support DSN, sending without delivery notifications",
+            self.config.host
+        );
+        self.send_raw(message.envelope(), &body).await?;
+
+        Ok(false)
+    }
+
+    /// The message as it goes out, DKIM-signed when a key is given
+    fn signed_body(message: &Message, dkim: Option<&DkimKey>) -> Result<Vec<u8>, EmailError> {
+        let body = message.formatted();
+        match dkim {
+            Some(key) => dkim::sign_message(&body, key, chrono::Utc::now())
+                .map_err(|e| EmailError::DkimError(e.to_string())),
+            None => Ok(body),
+        }
+    }
+
+    /// Send a message over a new connection
+    async fn send_raw(&self, envelope: &Envelope, body: &[u8]) -> Result<(), EmailError> {
+        let smtp_error = |e: lettre::transport::smtp::Error| EmailError::SmtpError(e.to_string());
+        let hello_name = ClientId::default();
+
+        let mut connection = self.connect(&hello_name).await?;
+        if let Some(credentials) = self.credentials() {
+            connection
+                .auth(&self.auth_mechanisms(), &credentials)
+                .await
+                .map_err(smtp_error)?;
+        }
+        connection.send(envelope, body).await.map_err(smtp_error)?;
+        let _ = connection.quit().await;
+
+        Ok(())
+    }
+
+    /// Connect to the server and encrypt the connection. A certificate that
+    /// fails validation is only accepted when it is the trusted one.
+    async fn connect(&self, hello_name: &ClientId) -> Result<AsyncSmtpConnection, EmailError> {
+        match self.connect_with(hello_name, false).await {
+            Err(EmailError::SmtpError(reason)) if tls_trust::is_certificate_error(&reason) => {
+                // Connect again without validation to see the certificate
+                let mut connection = self.connect_with(hello_name, true).await?;
+                let fingerprint = connection
+                    .peer_certificate()
+                    .map(|der| tls_trust::fingerprint(&der))
+                    .map_err(|e| EmailError::SmtpError(e.to_string()))?;
+
+                let trusted = self
+                    .config
+                    .trusted_certificate
+                    .as_deref()
+                    .is_some_and(|trusted| tls_trust::matches(trusted, &fingerprint));
+                if trusted {
+                    return Ok(connection);
                 }
+
+                let _ = connection.quit().await;
+                Err(EmailError::UntrustedCertificate(
+                    UntrustedCertificate {
+                        host: self.config.host.clone(),
+                        port: self.config.port,
+                        fingerprint,
+                        reason,
+                    }
+                    .to_string(),
+                ))
             }
+            result => result,
+        }
+    }
+
+    /// Connect with implicit TLS or STARTTLS, depending on the TLS mode, or
+    /// unencrypted when TLS is turned off
+    async fn connect_with(
+        &self,
+        hello_name: &ClientId,
+        accept_invalid_certs: bool,
+    ) -> Result<AsyncSmtpConnection, EmailError> {
+        let smtp_error = |e: lettre::transport::smtp::Error| EmailError::SmtpError(e.to_string());
 
-            transport.tls(Tls::Required(tls_parameters)).build()
+        let tls_parameters = if self.config.use_tls {
+            Some(
+                TlsParameters::builder(self.config.host.clone())
+                    .dangerous_accept_invalid_certs(accept_invalid_certs)
+                    .dangerous_accept_invalid_hostnames(accept_invalid_certs)
+                    .build()
+                    .map_err(smtp_error)?,
+            )
         } else {
-            let mut transport =
-                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.config.host)
-                    .port(self.config.port);
+            None
+        };
+        let implicit_tls = self.tls_mode() == TlsMode::Implicit;
 
-            if let (Some(user), Some(pass)) = (&self.config.username, &self.config.password) {
-                if !user.is_empty() && !pass.is_empty() {
-                    transport = transport.credentials(Credentials::new(user.clone(), pass.clone()));
-                }
+        let mut connection = AsyncSmtpConnection::connect_tokio1(
+            (self.config.host.as_str(), self.config.port),
+            Some(SMTP_TIMEOUT),
+            hello_name,
+            tls_parameters.clone().filter(|_| implicit_tls),
+            None,
+        )
+        .await
+        .map_err(smtp_error)?;
+
+        if let Some(tls_parameters) = tls_parameters.filter(|_| !implicit_tls) {
+            if !connection.can_starttls() {
+                let _ = connection.quit().await;
+                return Err(EmailError::SmtpError(
+                    "The server does not support STARTTLS".to_string(),
+                ));
             }
+            connection
+                .starttls(tls_parameters, hello_name)
+                .await
+                .map_err(smtp_error)?;
+        }
 
-            transport.build()
-        };
+        Ok(connection)
+    }
 
-        mailer
-            .send(message)
+    /// lettre's ServerInfo only tracks the extensions it uses itself, so the
+    /// EHLO keywords are read directly
+    async fn ehlo(
+        connection: &mut AsyncSmtpConnection,
+        hello_name: &ClientId,
+    ) -> Result<SmtpCapabilities, EmailError> {
+        let ehlo = connection
+            .command(Ehlo::new(hello_name.clone()))
             .await
             .map_err(|e| EmailError::SmtpError(e.to_string()))?;
 
-        log::info!(
-            "Email sent successfully to {} recipients with {} attachment(s)",
-            email_data.to.len() + email_data.cc.len() + email_data.bcc.len(),
-            email_data.attachments.len()
-        );
+        let mut capabilities = SmtpCapabilities::from_ehlo(ehlo.message());
+        capabilities.encrypted = connection.is_encrypted();
+        Ok(capabilities)
+    }
 
-        Ok(())
+    /// Connect and log in without sending anything, reporting what the server
+    /// supports. Credentials the server rejects are reported, not an error.
+    pub async fn probe(&self) -> Result<SmtpProbe, EmailError> {
+        let hello_name = ClientId::default();
+
+        // Servers often only offer AUTH once the connection is encrypted, so
+        // the capabilities are read after STARTTLS
+        let mut connection = self.connect(&hello_name).await?;
+        let mut capabilities = Self::ehlo(&mut connection, &hello_name).await?;
+        if self.config.use_tls && self.tls_mode() == TlsMode::Starttls {
+            capabilities.starttls = true;
+        }
+
+        let (authenticated, auth_error) = match self.credentials() {
+            Some(credentials) => {
+                match connection.auth(&self.auth_mechanisms(), &credentials).await {
+                    Ok(_) => (Some(true), None),
+                    Err(e) => (Some(false), Some(e.to_string())),
+                }
+            }
+            None => (None, None),
+        };
+        let _ = connection.quit().await;
+
+        Ok(SmtpProbe {
+            capabilities,
+            authenticated,
+            auth_error,
+        })
+    }
+
+    /// The transport API has no way to pass MAIL/RCPT parameters, so DSN
+    /// submissions drive the SMTP conversation directly. Returns `Ok(false)`
+    /// without sending when the server lacks the DSN extension.
+    async fn send_with_dsn(
+        &self,
+        envelope: &Envelope,
+        body: &[u8],
+        dsn: &DsnRequest,
+    ) -> Result<bool, EmailError> {
+        let smtp_error = |e: lettre::transport::smtp::Error| EmailError::SmtpError(e.to_string());
+        let hello_name = ClientId::default();
+
+        let mut connection = self.connect(&hello_name).await?;
+        if !Self::ehlo(&mut connection, &hello_name).await?.dsn {
+            let _ = connection.quit().await;
+            return Ok(false);
+        }
+
+        if let Some(credentials) = self.credentials() {
+            connection
+                .auth(&self.auth_mechanisms(), &credentials)
+                .await
+                .map_err(smtp_error)?;
+        }
+
+        let mut mail_parameters = vec![
+            MailParameter::Other {
+                keyword: "RET".to_string(),
+                value: Some("HDRS".to_string()),
+            },
+            MailParameter::Other {
+                keyword: "ENVID".to_string(),
+                value: Some(xtext(&dsn.envelope_id)),
+            },
+        ];
+        if !body.is_ascii() {
+            mail_parameters.push(MailParameter::Body(MailBodyParameter::EightBitMime));
+        }
+
+        connection
+            .command(Mail::new(envelope.from().cloned(), mail_parameters))
+            .await
+            .map_err(smtp_error)?;
+
+        let notify = dsn.options.notify_value();
+        for recipient in envelope.to() {
+            let parameters = vec![
+                RcptParameter::Other {
+                    keyword: "NOTIFY".to_string(),
+                    value: Some(notify.clone()),
+                },
+                RcptParameter::Other {
+                    keyword: "ORCPT".to_string(),
+                    value: Some(format!("rfc822;{}", xtext(&recipient.to_string()))),
+                },
+            ];
+            connection
+                .command(Rcpt::new(recipient.clone(), parameters))
+                .await
+                .map_err(smtp_error)?;
+        }
+
+        connection.command(Data).await.map_err(smtp_error)?;
+        connection.message(body).await.map_err(smtp_error)?;
+        let _ = connection.quit().await;
+
+        Ok(true)
     }
 }
 
@@ -273,6 +926,35 @@ impl EmailService {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_capabilities_from_ehlo() {
+        let lines = [
+            "smtp.example.org at your service",
+            "SIZE 35882577",
+            "8BITMIME",
+            "AUTH LOGIN PLAIN XOAUTH2",
+            "auth=login",
+            "STARTTLS",
+            "DSN",
+        ];
+        let capabilities = SmtpCapabilities::from_ehlo(lines.into_iter());
+
+        assert!(capabilities.starttls);
+        assert!(capabilities.dsn);
+        assert!(capabilities.eight_bit_mime);
+        assert!(!capabilities.smtputf8);
+        assert_eq!(capabilities.max_size, Some(35882577));
+        assert_eq!(
+            capabilities.auth_mechanisms,
+            vec!["LOGIN", "PLAIN", "XOAUTH2"]
+        );
+        assert!(capabilities.supports_auth(Mechanism::Xoauth2));
+
+        let unlimited = SmtpCapabilities::from_ehlo(["mx", "SIZE 0"].into_iter());
+        assert_eq!(unlimited.max_size, None);
+        assert!(!unlimited.supports_auth(Mechanism::Plain));
+    }
+
     #[test]
     fn test_to_mailbox() {
         let email = EmailAddress {
@@ -284,6 +966,159 @@ mod tests {
         assert_eq!(mailbox.email.to_string(), "test@example.com");
     }
 
+    #[test]
+    fn test_dsn_notify_value() {
+        assert_eq!(DsnOptions::default().notify_value(), "SUCCESS,FAILURE");
+        assert_eq!(
+            DsnOptions {
+                notify_success: false,
+                notify_failure: true,
+                notify_delay: true,
+            }
+            .notify_value(),
+            "FAILURE,DELAY"
+        );
+        assert_eq!(
+            DsnOptions {
+                notify_success: false,
+                notify_failure: false,
+                notify_delay: false,
+            }
+            .notify_value(),
+            "NEVER"
+        );
+    }
+
+    #[test]
+    fn test_smime_part_keeps_entity_body() {
+        let entity = "MIME-Version: 1.0\r\n\
+            Content-Type: multipart/signed; protocol=\"application/pkcs7-signature\"; micalg=\"sha-256\"; boundary=\"----AB12\"\r\n\
+            \r\n\
+            This is an S/MIME signed message\r\n\
+            \r\n\
+            ------AB12\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            Hello \r\n\
+            ------AB12--\r\n";
+
+        let part = EmailService::smime_part(entity.as_bytes().to_vec()).unwrap();
+        let formatted = String::from_utf8(part.formatted()).unwrap();
+
+        assert!(formatted.contains("multipart/signed"));
+        assert!(formatted.contains("Content-Transfer-Encoding: 7bit"));
+        assert!(formatted.contains(entity.split_once("\r\n\r\n").unwrap().1));
+        assert!(EmailService::smime_part(b"Content-Type: text/plain".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_build_mime_requests_read_receipt() {
+        let email_data = EmailData {
+            from: "Jane <jane@example.com>".to_string(),
+            to: vec![EmailAddress {
+                address: "bob@example.org".to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            subject: "Hello".to_string(),
+            body_html: "<p>Hello</p>".to_string(),
+            body_plain: None,
+            attachments: vec![],
+            in_reply_to: None,
+            references: None,
+            message_id: Some("<0192c3a4@example.com>".to_string()),
+            read_receipt: true,
+            smime: None,
+            dkim: None,
+        };
+
+        let mime = String::from_utf8(EmailService::build_mime(email_data).unwrap()).unwrap();
+        assert!(mime.contains("Message-ID: <0192c3a4@example.com>\r\n"));
+        assert!(mime.contains("Disposition-Notification-To: jane@example.com\r\n"));
+    }
+
+    #[test]
+    fn test_build_draft_mime_without_recipients() {
+        let email_data = EmailData {
+            from: "jane@example.com".to_string(),
+            to: vec![],
+            cc: vec![],
+            bcc: vec![],
+            subject: "Unfinished".to_string(),
+            body_html: "<p>Hello</p>".to_string(),
+            body_plain: None,
+            attachments: vec![],
+            in_reply_to: None,
+            references: None,
+            message_id: Some("<draft-0192c3a4@ravn.app>".to_string()),
+            read_receipt: false,
+            smime: None,
+            dkim: None,
+        };
+
+        assert!(EmailService::build_mime(email_data.clone()).is_err());
+        let mime = String::from_utf8(EmailService::build_draft_mime(email_data).unwrap()).unwrap();
+        assert!(mime.contains("Message-ID: <draft-0192c3a4@ravn.app>\r\n"));
+        assert!(!mime.contains("To:"));
+    }
+
+    #[test]
+    fn test_extract_inline_images() {
+        let html = r#"<p>Logo <img src="data:image/png;base64,iVBORw0K" alt="logo"></p><img src='https://example.com/a.png'>"#;
+
+        let (html, images) = extract_inline_images(html, "example.com");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].filename, "image1.png");
+        assert_eq!(images[0].content_type.as_deref(), Some("image/png"));
+        assert_eq!(images[0].content, vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a]);
+
+        let content_id = images[0].content_id.clone().unwrap();
+        assert!(content_id.ends_with("@example.com"));
+        assert!(html.contains(&format!(r#"<img src="cid:{}" alt="logo">"#, content_id)));
+        assert!(html.contains("src='https://example.com/a.png'"));
+    }
+
+    #[test]
+    fn test_build_mime_with_inline_image() {
+        let email_data = EmailData {
+            from: "jane@example.com".to_string(),
+            to: vec![EmailAddress {
+                address: "bob@example.com".to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            subject: "Logo".to_string(),
+            body_html: r#"<img src="cid:logo@example.com">"#.to_string(),
+            body_plain: None,
+            attachments: vec![EmailAttachment {
+                filename: "logo.png".to_string(),
+                content: vec![1, 2, 3],
+                content_type: Some("image/png".to_string()),
+                content_id: Some("logo@example.com".to_string()),
+            }],
+            in_reply_to: None,
+            references: None,
+            message_id: None,
+            read_receipt: false,
+            smime: None,
+            dkim: None,
+        };
+
+        let mime = String::from_utf8(EmailService::build_mime(email_data).unwrap()).unwrap();
+        assert!(mime.contains("multipart/related"));
+        assert!(mime.contains("Content-ID: <logo@example.com>"));
+        assert!(mime.contains("Content-Disposition: inline"));
+        assert!(!mime.contains("multipart/mixed"));
+    }
+
+    #[test]
+    fn test_xtext() {
+        assert_eq!(xtext("bob@example.com"), "bob@example.com");
+        assert_eq!(xtext("a+b=c d"), "a+2Bb+3Dc+20d");
+    }
+
     #[test]
     fn test_to_mailbox_without_name() {
         let email = EmailAddress {
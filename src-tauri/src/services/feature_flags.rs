@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+
+use crate::config::Settings;
+use crate::licensing::LicenseManager;
+
+/// Features that can be switched on or off at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Feature {
+    Ai,
+    JunkFilter,
+    Jmap,
+    Pgp,
+}
+
+struct FeatureDefinition {
+    feature: Feature,
+    key: &'static str,
+    setting: &'static str,
+    default_enabled: bool,
+    experimental: bool,
+    requires_license: bool,
+}
+
+const FEATURES: &[FeatureDefinition] = &[
+    FeatureDefinition {
+        feature: Feature::Ai,
+        key: "ai",
+        setting: "features.ai",
+        default_enabled: true,
+        experimental: false,
+        requires_license: true,
+    },
+    FeatureDefinition {
+        feature: Feature::JunkFilter,
+        key: "junkFilter",
+        setting: "features.junkFilter",
+        default_enabled: true,
+        experimental: false,
+        requires_license: false,
+    },
+    FeatureDefinition {
+        feature: Feature::Jmap,
+        key: "jmap",
+        setting: "features.experimental.jmap",
+        default_enabled: false,
+        experimental: true,
+        requires_license: false,
+    },
+    FeatureDefinition {
+        feature: Feature::Pgp,
+        key: "pgp",
+        setting: "features.experimental.pgp",
+        default_enabled: false,
+        experimental: true,
+        requires_license: false,
+    },
+];
+
+fn definition(feature: Feature) -> &'static FeatureDefinition {
+    FEATURES
+        .iter()
+        .find(|d| d.feature == feature)
+        .expect("every feature has a definition")
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlagState {
+    pub key: String,
+    pub enabled: bool,
+    /// Whether the user switched the feature on, regardless of license
+    pub toggled_on: bool,
+    pub experimental: bool,
+    pub requires_license: bool,
+    /// Why the feature is disabled, if it is
+    pub reason: Option<String>,
+}
+
+/// Resolves feature availability from the license, user settings and experimental toggles
+pub struct FeatureFlags {
+    settings: Arc<Settings>,
+    license_manager: Arc<LicenseManager>,
+}
+
+impl FeatureFlags {
+    pub fn new(settings: Arc<Settings>, license_manager: Arc<LicenseManager>) -> Self {
+        Self {
+            settings,
+            license_manager,
+        }
+    }
+
+    pub fn parse_key(key: &str) -> Option<Feature> {
+        FEATURES.iter().find(|d| d.key == key).map(|d| d.feature)
+    }
+
+    pub async fn is_enabled(&self, feature: Feature) -> bool {
+        self.state(feature).await.enabled
+    }
+
+    /// Fails with a user-facing message when the feature is disabled
+    pub async fn require(&self, feature: Feature) -> Result<(), String> {
+        let state = self.state(feature).await;
+        if state.enabled {
+            Ok(())
+        } else {
+            Err(state.reason.unwrap_or_default())
+        }
+    }
+
+    pub async fn all(&self) -> Vec<FeatureFlagState> {
+        let is_licensed = self.license_manager.get_status().await.is_licensed;
+        FEATURES
+            .iter()
+            .map(|d| self.resolve(d, is_licensed))
+            .collect()
+    }
+
+    /// Persists the user toggle for a feature
+    pub fn set_enabled(&self, feature: Feature, enabled: bool) -> Result<(), String> {
+        self.settings
+            .set(definition(feature).setting, JsonValue::Bool(enabled))
+            .map_err(|e| e.to_string())
+    }
+
+    async fn state(&self, feature: Feature) -> FeatureFlagState {
+        let definition = definition(feature);
+        let is_licensed = if definition.requires_license {
+            self.license_manager.get_status().await.is_licensed
+        } else {
+            true
+        };
+        self.resolve(definition, is_licensed)
+    }
+
+    fn resolve(&self, definition: &FeatureDefinition, is_licensed: bool) -> FeatureFlagState {
+        let toggled_on = self
+            .settings
+            .get::<bool>(definition.setting)
+            .unwrap_or(definition.default_enabled);
+
+        resolve_state(definition, toggled_on, is_licensed)
+    }
+}
+
+fn resolve_state(
+    definition: &FeatureDefinition,
+    toggled_on: bool,
+    is_licensed: bool,
+) -> FeatureFlagState {
+    let reason = if definition.requires_license && !is_licensed {
+        Some(format!("'{}' requires an active license", definition.key))
+    } else if !toggled_on && definition.experimental {
+        Some(format!(
+            "'{}' is experimental and has not been enabled",
+            definition.key
+        ))
+    } else if !toggled_on {
+        Some(format!("'{}' is disabled in settings", definition.key))
+    } else {
+        None
+    };
+
+    FeatureFlagState {
+        key: definition.key.to_string(),
+        enabled: reason.is_none(),
+        toggled_on,
+        experimental: definition.experimental,
+        requires_license: definition.requires_license,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_feature_has_unique_key() {
+        for definition in FEATURES {
+            assert_eq!(
+                FeatureFlags::parse_key(definition.key),
+                Some(definition.feature)
+            );
+        }
+        assert_eq!(FeatureFlags::parse_key("unknown"), None);
+    }
+
+    #[test]
+    fn test_resolve_state() {
+        let ai = definition(Feature::Ai);
+        assert!(resolve_state(ai, true, true).enabled);
+
+        let unlicensed = resolve_state(ai, true, false);
+        assert!(!unlicensed.enabled);
+        assert!(unlicensed.toggled_on);
+        assert_eq!(
+            unlicensed.reason.as_deref(),
+            Some("'ai' requires an active license")
+        );
+
+        let jmap = definition(Feature::Jmap);
+        assert!(!jmap.default_enabled);
+        assert_eq!(
+            resolve_state(jmap, false, true).reason.as_deref(),
+            Some("'jmap' is experimental and has not been enabled")
+        );
+        assert!(resolve_state(jmap, true, false).enabled);
+    }
+}
@@ -0,0 +1,320 @@
+//! Blocking of remote content in email HTML. Tracking pixels are removed and
+//! other remote images are swapped for a placeholder until the user loads
+//! them, so opening an email does not tell the sender.
+//!
+//! Trackers are recognized by the service that serves them, by the paths open
+//! tracking commonly uses, and by their size: an image of a pixel or less, or
+//! a hidden one, is there to be loaded, not to be seen.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, NoExpand, Regex};
+use url::Url;
+
+/// Domains that serve open tracking pixels, by the service behind them
+const TRACKER_DOMAINS: &[(&str, &str)] = &[
+    ("list-manage.com", "Mailchimp"),
+    ("mandrillapp.com", "Mandrill"),
+    ("sendgrid.net", "SendGrid"),
+    ("hubspotemail.net", "HubSpot"),
+    ("hubspotlinks.com", "HubSpot"),
+    ("exct.net", "Salesforce Marketing Cloud"),
+    ("exacttarget.com", "Salesforce Marketing Cloud"),
+    ("pardot.com", "Pardot"),
+    ("sparkpostmail.com", "SparkPost"),
+    ("klaviyomail.com", "Klaviyo"),
+    ("customeriomail.com", "Customer.io"),
+    ("intercom-mail.com", "Intercom"),
+    ("emltrk.com", "Litmus"),
+    ("google-analytics.com", "Google Analytics"),
+    ("doubleclick.net", "DoubleClick"),
+    ("mixpanel.com", "Mixpanel"),
+    ("mailtrack.io", "Mailtrack"),
+    ("yesware.com", "Yesware"),
+    ("bananatag.com", "Bananatag"),
+    ("mixmax.com", "Mixmax"),
+    ("mailfoogae.appspot.com", "Streak"),
+    ("superhuman.com", "Superhuman"),
+    ("getnotify.com", "Notify"),
+];
+
+/// Shown instead of a blocked image, keeping its place in the layout
+const BLOCKED_IMAGE_PLACEHOLDER: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 16 16'%3E%3Crect width='16' height='16' fill='%23e5e7eb'/%3E%3C/svg%3E";
+
+static IMG_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<img\b[^>]*>").expect("Failed to compile img regex"));
+
+static SRC_ATTR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)\ssrc\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#)
+        .expect("Failed to compile src regex")
+});
+
+static SRCSET_ATTR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)\ssrcset\s*=\s*(?:"[^"]*"|'[^']*'|[^\s"'>]+)"#)
+        .expect("Failed to compile srcset regex")
+});
+
+static SIZE_ATTR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\s(width|height)\s*=\s*["']?\s*(\d+)"#).expect("Failed to compile size regex")
+});
+
+static STYLE_ATTR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)\sstyle\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .expect("Failed to compile style regex")
+});
+
+static STYLE_SIZE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:^|[;\s])(width|height)\s*:\s*(\d+)(?:px)?\s*(?:;|$)")
+        .expect("Failed to compile style size regex")
+});
+
+static HIDDEN_STYLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)display\s*:\s*none|visibility\s*:\s*hidden")
+        .expect("Failed to compile hidden style regex")
+});
+
+static TRACKING_PATH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)/(?:wf/open|track(?:ing)?/open|open(?:ed)?\.(?:gif|png|php|aspx)|pixel(?:\.(?:gif|png))?|beacon)(?:[/?.]|$)",
+    )
+    .expect("Failed to compile tracking path regex")
+});
+
+/// Remote images in CSS, e.g. `background: url(https://...)`
+static CSS_REMOTE_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)url\(\s*["']?(?:https?:)?//[^)]*\)"#)
+        .expect("Failed to compile CSS url regex")
+});
+
+static BACKGROUND_ATTR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\sbackground\s*=\s*["']?(?:https?:)?//[^"'\s>]*["']?"#)
+        .expect("Failed to compile background regex")
+});
+
+/// What to block in an email
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockingOptions {
+    pub block_trackers: bool,
+    pub block_images: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizedHtml {
+    pub html: String,
+    /// Trackers found, by service name or by host for unknown ones
+    pub trackers: Vec<String>,
+    /// Remote images swapped for the placeholder
+    pub blocked_images: usize,
+}
+
+fn is_remote(src: &str) -> bool {
+    let src = src.trim().to_ascii_lowercase();
+    src.starts_with("http://") || src.starts_with("https://") || src.starts_with("//")
+}
+
+fn parse_remote_url(src: &str) -> Option<Url> {
+    let src = src.trim();
+    if src.starts_with("//") {
+        Url::parse(&format!("https:{}", src)).ok()
+    } else {
+        Url::parse(src).ok()
+    }
+}
+
+fn attr_value(captures: &Captures) -> String {
+    (1..=3)
+        .find_map(|group| captures.get(group))
+        .map(|value| value.as_str().to_string())
+        .unwrap_or_default()
+}
+
+/// Whether the image is a pixel or less, by its attributes or its style
+fn is_tiny(tag: &str, style: &str) -> bool {
+    let mut width = None;
+    let mut height = None;
+    for captures in SIZE_ATTR
+        .captures_iter(tag)
+        .chain(STYLE_SIZE.captures_iter(style))
+    {
+        let size = captures[2].parse::<u32>().ok();
+        if captures[1].eq_ignore_ascii_case("width") {
+            width = size;
+        } else {
+            height = size;
+        }
+    }
+
+    matches!((width, height), (Some(width), Some(height)) if width <= 1 && height <= 1)
+}
+
+/// The tracker an image is, if it is one
+pub fn tracker_name(tag: &str, src: &str) -> Option<String> {
+    let url = parse_remote_url(src)?;
+    let host = url.host_str()?.to_ascii_lowercase();
+
+    if let Some((_, name)) = TRACKER_DOMAINS
+        .iter()
+        .find(|(domain, _)| host == *domain || host.ends_with(&format!(".{}", domain)))
+    {
+        return Some(name.to_string());
+    }
+
+    let style = STYLE_ATTR
+        .captures(tag)
+        .map(|captures| attr_value(&captures))
+        .unwrap_or_default();
+    let is_tracker =
+        is_tiny(tag, &style) || HIDDEN_STYLE.is_match(&style) || TRACKING_PATH.is_match(url.path());
+
+    is_tracker.then_some(host)
+}
+
+/// Blocks the remote content of an email's HTML as the options ask. Trackers
+/// are reported whether they are blocked or not.
+pub fn sanitize(html: &str, options: BlockingOptions) -> SanitizedHtml {
+    let mut trackers: Vec<String> = Vec::new();
+    let mut blocked_images = 0;
+
+    let html = IMG_TAG.replace_all(html, |captures: &Captures| {
+        let tag = &captures[0];
+        let Some(src) = SRC_ATTR.captures(tag).map(|captures| attr_value(&captures)) else {
+            return tag.to_string();
+        };
+        if !is_remote(&src) {
+            return tag.to_string();
+        }
+
+        if let Some(tracker) = tracker_name(tag, &src) {
+            if !trackers.contains(&tracker) {
+                trackers.push(tracker);
+            }
+            if options.block_trackers {
+                return String::new();
+            }
+        }
+
+        if !options.block_images {
+            return tag.to_string();
+        }
+
+        blocked_images += 1;
+        let tag = SRCSET_ATTR.replace_all(tag, "");
+        SRC_ATTR
+            .replace(
+                &tag,
+                NoExpand(&format!(r#" src="{}""#, BLOCKED_IMAGE_PLACEHOLDER)),
+            )
+            .into_owned()
+    });
+
+    let html = if options.block_images {
+        let html = CSS_REMOTE_URL.replace_all(&html, "none");
+        BACKGROUND_ATTR.replace_all(&html, "").into_owned()
+    } else {
+        html.into_owned()
+    };
+
+    SanitizedHtml {
+        html,
+        trackers,
+        blocked_images,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_ALL: BlockingOptions = BlockingOptions {
+        block_trackers: true,
+        block_images: true,
+    };
+
+    #[test]
+    fn test_tracker_name() {
+        assert_eq!(
+            tracker_name(
+                r#"<img src="https://u123.ct.sendgrid.net/wf/open?upn=x">"#,
+                "https://u123.ct.sendgrid.net/wf/open?upn=x"
+            ),
+            Some("SendGrid".to_string())
+        );
+        assert_eq!(
+            tracker_name(
+                r#"<img width="1" height="1" src="https://mail.example.com/i.gif">"#,
+                "https://mail.example.com/i.gif"
+            ),
+            Some("mail.example.com".to_string())
+        );
+        assert_eq!(
+            tracker_name(
+                r#"<img style="display: none" src="//shop.example.com/logo.png">"#,
+                "//shop.example.com/logo.png"
+            ),
+            Some("shop.example.com".to_string())
+        );
+        assert_eq!(
+            tracker_name(
+                r#"<img src="https://news.example.com/e/track/open/123">"#,
+                "https://news.example.com/e/track/open/123"
+            ),
+            Some("news.example.com".to_string())
+        );
+        assert_eq!(
+            tracker_name(
+                r#"<img width="600" height="1" src="https://shop.example.com/divider.png">"#,
+                "https://shop.example.com/divider.png"
+            ),
+            None
+        );
+        assert_eq!(
+            tracker_name(
+                r#"<img src="https://shop.example.com/images/open-sign.png">"#,
+                "https://shop.example.com/images/open-sign.png"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let html = concat!(
+            r#"<p style="background: url('https://cdn.example.com/bg.png')">Hi</p>"#,
+            r#"<img src="https://cdn.example.com/logo.png" srcset="https://cdn.example.com/logo@2x.png 2x" alt="Logo">"#,
+            r#"<img src="cid:inline-1"><img src="data:image/png;base64,AAAA">"#,
+            r#"<img src='https://x.list-manage.com/track/open.php?u=1' width=1 height=1>"#,
+        );
+
+        let sanitized = sanitize(html, BLOCK_ALL);
+        assert_eq!(sanitized.trackers, vec!["Mailchimp"]);
+        assert_eq!(sanitized.blocked_images, 1);
+        assert!(!sanitized.html.contains("list-manage.com"));
+        assert!(!sanitized.html.contains("https://cdn.example.com"));
+        assert!(sanitized.html.contains(BLOCKED_IMAGE_PLACEHOLDER));
+        assert!(sanitized.html.contains(r#"alt="Logo""#));
+        assert!(sanitized.html.contains("cid:inline-1"));
+        assert!(sanitized.html.contains("data:image/png;base64,AAAA"));
+
+        // Loaded images still leave the trackers out
+        let loaded = sanitize(
+            html,
+            BlockingOptions {
+                block_trackers: true,
+                block_images: false,
+            },
+        );
+        assert_eq!(loaded.trackers, vec!["Mailchimp"]);
+        assert_eq!(loaded.blocked_images, 0);
+        assert!(loaded.html.contains("https://cdn.example.com/logo.png"));
+        assert!(!loaded.html.contains("list-manage.com"));
+
+        let unblocked = sanitize(
+            html,
+            BlockingOptions {
+                block_trackers: false,
+                block_images: false,
+            },
+        );
+        assert_eq!(unblocked.html, html);
+        assert_eq!(unblocked.trackers, vec!["Mailchimp"]);
+    }
+}
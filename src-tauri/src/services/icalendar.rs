@@ -0,0 +1,862 @@
+//! Reading and writing of iCalendar data (RFC 5545) as exchanged in meeting
+//! invitations (iTIP, RFC 5546). Only the `VEVENT` properties an invitation
+//! needs are read. Written are replies to invitations and single events to
+//! import into other calendars.
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use std::collections::HashMap;
+
+use crate::database::models::calendar_event::{Attendee, CalendarEvent, RsvpStatus};
+use crate::database::models::email::EmailAddress;
+
+const PRODID: &str = "-//Ravn//Ravn Mail//EN";
+
+/// Lines are folded after this many octets (RFC 5545 section 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    /// iTIP method, e.g. `REQUEST`, `CANCEL` or `REPLY`, upper-cased
+    pub method: Option<String>,
+    pub events: Vec<Event>,
+}
+
+impl Calendar {
+    /// The event an invitation is about. Invitations to a recurring event can
+    /// carry changed occurrences next to it, which have a `RECURRENCE-ID`.
+    pub fn main_event(&self) -> Option<&Event> {
+        self.events
+            .iter()
+            .find(|event| event.recurrence_id.is_none())
+            .or_else(|| self.events.first())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub uid: String,
+    pub sequence: i64,
+    pub recurrence_id: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub all_day: bool,
+    /// `STATUS`, e.g. `CONFIRMED` or `CANCELLED`, upper-cased
+    pub status: Option<String>,
+    pub organizer: Option<EmailAddress>,
+    pub attendees: Vec<Attendee>,
+}
+
+/// A content line, e.g. `ATTENDEE;PARTSTAT=ACCEPTED:mailto:a@example.com`
+#[derive(Debug, Clone, PartialEq)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Component {
+    name: String,
+    properties: Vec<Property>,
+    components: Vec<Component>,
+}
+
+impl Component {
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.property(name)
+            .map(|property| property.value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.value(name).map(unescape_text)
+    }
+}
+
+/// Parse an iCalendar object. Returns `None` if the data holds no calendar
+/// with at least one usable event.
+pub fn parse(data: &[u8]) -> Option<Calendar> {
+    let root = components(&unfold(data))
+        .into_iter()
+        .find(|component| component.name == "VCALENDAR")?;
+
+    let timezones: HashMap<String, Timezone> = root
+        .components
+        .iter()
+        .filter(|component| component.name == "VTIMEZONE")
+        .filter_map(|component| {
+            Some((
+                component.value("TZID")?.to_string(),
+                Timezone::parse(component),
+            ))
+        })
+        .collect();
+
+    let events: Vec<Event> = root
+        .components
+        .iter()
+        .filter(|component| component.name == "VEVENT")
+        .filter_map(|component| parse_event(component, &timezones))
+        .collect();
+
+    if events.is_empty() {
+        return None;
+    }
+
+    Some(Calendar {
+        method: root.value("METHOD").map(str::to_ascii_uppercase),
+        events,
+    })
+}
+
+fn parse_event(component: &Component, timezones: &HashMap<String, Timezone>) -> Option<Event> {
+    let (starts_at, all_day) = parse_time(component.property("DTSTART")?, timezones)?;
+
+    let ends_at = match component.property("DTEND") {
+        Some(property) => parse_time(property, timezones).map(|(time, _)| time),
+        None => component
+            .value("DURATION")
+            .and_then(parse_duration)
+            .map(|duration| starts_at + duration)
+            // An all-day event without an end lasts one day
+            .or_else(|| all_day.then(|| starts_at + Duration::days(1))),
+    };
+
+    Some(Event {
+        uid: component.value("UID")?.to_string(),
+        sequence: component
+            .value("SEQUENCE")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0),
+        recurrence_id: component.value("RECURRENCE-ID").map(ToString::to_string),
+        summary: component.text("SUMMARY"),
+        description: component.text("DESCRIPTION"),
+        location: component.text("LOCATION"),
+        starts_at,
+        ends_at,
+        all_day,
+        status: component.value("STATUS").map(str::to_ascii_uppercase),
+        organizer: component.property("ORGANIZER").and_then(|property| {
+            Some(EmailAddress {
+                address: calendar_address(&property.value)?,
+                name: property.param("CN").map(ToString::to_string),
+            })
+        }),
+        attendees: component
+            .properties
+            .iter()
+            .filter(|property| property.name == "ATTENDEE")
+            .filter_map(|property| {
+                Some(Attendee {
+                    address: calendar_address(&property.value)?,
+                    name: property.param("CN").map(ToString::to_string),
+                    rsvp_status: property
+                        .param("PARTSTAT")
+                        .map_or(Some(RsvpStatus::NeedsAction), RsvpStatus::from_partstat),
+                })
+            })
+            .collect(),
+    })
+}
+
+/// The email address of a `mailto:` calendar user address
+fn calendar_address(value: &str) -> Option<String> {
+    let value = value.trim();
+    let address = match value.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &value[7..],
+        _ => value,
+    };
+    address.contains('@').then(|| address.trim().to_lowercase())
+}
+
+/// Joins folded lines; a line starting with a space or tab continues the
+/// previous one. Folding happens on octets, so this works on bytes.
+fn unfold(data: &[u8]) -> Vec<String> {
+    let mut lines: Vec<Vec<u8>> = Vec::new();
+
+    for line in data.split(|&byte| byte == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        match (line.first(), lines.last_mut()) {
+            (Some(b' ' | b'\t'), Some(previous)) => previous.extend_from_slice(&line[1..]),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_vec()),
+        }
+    }
+
+    lines
+        .iter()
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect()
+}
+
+/// Splits `text` on `separator` outside of double quotes
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (index, c) in text.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == separator && !in_quotes {
+            parts.push(&text[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+
+    parts
+}
+
+fn parse_line(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(index, c)| {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => return Some(index),
+            _ => {}
+        }
+        None
+    })?;
+
+    let mut head = split_unquoted(&line[..colon], ';').into_iter();
+    let name = head.next()?.trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    let params = head
+        .filter_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            Some((
+                name.trim().to_ascii_uppercase(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value: line[colon + 1..].to_string(),
+    })
+}
+
+/// Builds the component tree. Properties outside of any component and
+/// unbalanced `END` lines are ignored.
+fn components(lines: &[String]) -> Vec<Component> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<Component> = Vec::new();
+
+    for property in lines.iter().filter_map(|line| parse_line(line)) {
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: property.value.trim().to_ascii_uppercase(),
+                ..Default::default()
+            }),
+            "END" => {
+                let name = property.value.trim().to_ascii_uppercase();
+                if stack.last().is_some_and(|component| component.name == name) {
+                    let component = stack.pop().expect("stack is not empty");
+                    match stack.last_mut() {
+                        Some(parent) => parent.components.push(component),
+                        None => roots.push(component),
+                    }
+                }
+            }
+            _ => {
+                if let Some(component) = stack.last_mut() {
+                    component.properties.push(property);
+                }
+            }
+        }
+    }
+
+    roots
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => text.push('\\'),
+        }
+    }
+
+    text
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Parse a `DATE` or `DATE-TIME` property. Times with a `TZID` are converted
+/// with the matching `VTIMEZONE`, floating times are taken as local time.
+/// Returns the time and whether it is a date only.
+fn parse_time(
+    property: &Property,
+    timezones: &HashMap<String, Timezone>,
+) -> Option<(DateTime<Utc>, bool)> {
+    let value = property.value.trim();
+
+    if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_hms_opt(0, 0, 0)?.and_utc(), true));
+    }
+
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((time.and_utc(), false));
+    }
+
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let time = match property
+        .param("TZID")
+        .and_then(|tzid| timezones.get(tzid))
+        .and_then(|timezone| timezone.offset_at(local))
+    {
+        Some(offset) => (local - Duration::seconds(offset)).and_utc(),
+        None => Local
+            .from_local_datetime(&local)
+            .earliest()?
+            .with_timezone(&Utc),
+    };
+
+    Some((time, false))
+}
+
+/// Parse a `DURATION` value such as `PT1H30M` or `P1D`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix(['P', 'p'])?;
+
+    let mut seconds = 0i64;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c.to_ascii_uppercase() {
+            'T' => in_time = true,
+            digit if digit.is_ascii_digit() => number.push(digit),
+            unit => {
+                let amount: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += amount
+                    * match (unit, in_time) {
+                        ('W', false) => 7 * 86_400,
+                        ('D', false) => 86_400,
+                        ('H', true) => 3_600,
+                        ('M', true) => 60,
+                        ('S', true) => 1,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+
+    Some(Duration::seconds(if negative { -seconds } else { seconds }))
+}
+
+/// A `VTIMEZONE`, reduced to the offsets of its observances and their
+/// yearly onset rules
+#[derive(Debug, Clone)]
+struct Timezone {
+    observances: Vec<Observance>,
+}
+
+#[derive(Debug, Clone)]
+struct Observance {
+    start: NaiveDateTime,
+    /// Offset from UTC in seconds
+    offset: i64,
+    /// Month and nth weekday of the yearly onset, from `RRULE`
+    rule: Option<(u32, i32, Weekday)>,
+}
+
+impl Timezone {
+    fn parse(component: &Component) -> Self {
+        let observances = component
+            .components
+            .iter()
+            .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT")
+            .filter_map(|c| {
+                Some(Observance {
+                    start: NaiveDateTime::parse_from_str(c.value("DTSTART")?, "%Y%m%dT%H%M%S")
+                        .ok()?,
+                    offset: parse_utc_offset(c.value("TZOFFSETTO")?)?,
+                    rule: c.value("RRULE").and_then(parse_yearly_rule),
+                })
+            })
+            .collect();
+
+        Self { observances }
+    }
+
+    /// The offset of the observance in effect at the local time
+    fn offset_at(&self, local: NaiveDateTime) -> Option<i64> {
+        let mut latest: Option<(NaiveDateTime, i64)> = None;
+
+        for observance in &self.observances {
+            for onset in observance.onsets(local.year()) {
+                if onset <= local && latest.is_none_or(|(time, _)| onset > time) {
+                    latest = Some((onset, observance.offset));
+                }
+            }
+        }
+
+        latest
+            .map(|(_, offset)| offset)
+            .or_else(|| self.observances.first().map(|observance| observance.offset))
+    }
+}
+
+impl Observance {
+    /// Onsets in the given and the previous year
+    fn onsets(&self, year: i32) -> Vec<NaiveDateTime> {
+        let Some((month, week, weekday)) = self.rule else {
+            return vec![self.start];
+        };
+
+        [year - 1, year]
+            .into_iter()
+            .filter_map(|year| {
+                let date = nth_weekday(year, month, week, weekday)?;
+                Some(date.and_time(self.start.time()))
+            })
+            .filter(|onset| *onset >= self.start)
+            .collect()
+    }
+}
+
+/// Month and weekday of a `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU` rule
+fn parse_yearly_rule(rule: &str) -> Option<(u32, i32, Weekday)> {
+    let parts: HashMap<String, &str> = rule
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(name, value)| (name.trim().to_ascii_uppercase(), value.trim()))
+        .collect();
+
+    if !parts.get("FREQ")?.eq_ignore_ascii_case("YEARLY") {
+        return None;
+    }
+    let month = parts.get("BYMONTH")?.parse().ok()?;
+
+    let by_day = parts.get("BYDAY")?;
+    let (week, day) = by_day.split_at(by_day.len().checked_sub(2)?);
+    let week = if week.is_empty() {
+        1
+    } else {
+        week.parse().ok()?
+    };
+    let weekday = match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+
+    Some((month, week, weekday))
+}
+
+/// The nth weekday of a month; negative weeks count from the end
+fn nth_weekday(year: i32, month: u32, week: i32, weekday: Weekday) -> Option<NaiveDate> {
+    if week > 0 {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let days_until = (7 + weekday.num_days_from_monday() as i64
+            - first.weekday().num_days_from_monday() as i64)
+            % 7;
+        let date = first + Duration::days(days_until + 7 * (week as i64 - 1));
+        (date.month() == month).then_some(date)
+    } else {
+        let next_month = if month == 12 {
+            NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+        } else {
+            NaiveDate::from_ymd_opt(year, month + 1, 1)?
+        };
+        let last = next_month.pred_opt()?;
+        let days_back = (7 + last.weekday().num_days_from_monday() as i64
+            - weekday.num_days_from_monday() as i64)
+            % 7;
+        let date = last - Duration::days(days_back + 7 * (-week as i64 - 1));
+        (date.month() == month).then_some(date)
+    }
+}
+
+/// Parse a UTC offset such as `+0100` or `-053000` into seconds
+fn parse_utc_offset(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let sign = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = &value[1..];
+    if digits.len() < 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours: i64 = digits[0..2].parse().ok()?;
+    let minutes: i64 = digits[2..4].parse().ok()?;
+    let seconds: i64 = digits.get(4..6).map_or(Ok(0), str::parse).ok()?;
+
+    Some(sign * (hours * 3_600 + minutes * 60 + seconds))
+}
+
+impl From<&CalendarEvent> for Event {
+    fn from(event: &CalendarEvent) -> Self {
+        Self {
+            uid: event.uid.clone(),
+            sequence: event.sequence,
+            recurrence_id: None,
+            summary: event.summary.clone(),
+            description: event.description.clone(),
+            location: event.location.clone(),
+            starts_at: event.starts_at,
+            ends_at: event.ends_at,
+            all_day: event.all_day,
+            status: event.cancelled.then(|| "CANCELLED".to_string()),
+            organizer: event.organizer.clone(),
+            attendees: event.attendees.clone(),
+        }
+    }
+}
+
+/// Builds the iTIP `REPLY` telling the organizer of `event` how `attendee`
+/// answered
+pub fn build_reply(
+    event: &Event,
+    attendee: &EmailAddress,
+    status: RsvpStatus,
+    now: DateTime<Utc>,
+) -> String {
+    let mut lines = event_lines(event, now);
+    if let Some(organizer) = &event.organizer {
+        lines.push(address_line("ORGANIZER", organizer, None));
+    }
+    lines.push(address_line("ATTENDEE", attendee, Some(status)));
+
+    write_calendar("REPLY", lines)
+}
+
+/// Builds a calendar holding `event` alone, to import it into a calendar app
+pub fn build_event(event: &Event, now: DateTime<Utc>) -> String {
+    let mut lines = event_lines(event, now);
+    if let Some(description) = &event.description {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(location) = &event.location {
+        lines.push(format!("LOCATION:{}", escape_text(location)));
+    }
+    if let Some(status) = &event.status {
+        lines.push(format!("STATUS:{}", status));
+    }
+    if let Some(organizer) = &event.organizer {
+        lines.push(address_line("ORGANIZER", organizer, None));
+    }
+    for attendee in &event.attendees {
+        let address = EmailAddress {
+            address: attendee.address.clone(),
+            name: attendee.name.clone(),
+        };
+        lines.push(address_line("ATTENDEE", &address, attendee.rsvp_status));
+    }
+
+    write_calendar("PUBLISH", lines)
+}
+
+/// The properties identifying an event and its time
+fn event_lines(event: &Event, now: DateTime<Utc>) -> Vec<String> {
+    let mut lines = vec![
+        format!("UID:{}", event.uid),
+        format!("SEQUENCE:{}", event.sequence),
+        format!("DTSTAMP:{}", format_time(now, false)),
+    ];
+
+    if let Some(recurrence_id) = &event.recurrence_id {
+        lines.push(format!("RECURRENCE-ID:{}", recurrence_id));
+    }
+    lines.push(time_line("DTSTART", event.starts_at, event.all_day));
+    if let Some(ends_at) = event.ends_at {
+        lines.push(time_line("DTEND", ends_at, event.all_day));
+    }
+    if let Some(summary) = &event.summary {
+        lines.push(format!("SUMMARY:{}", escape_text(summary)));
+    }
+
+    lines
+}
+
+/// Wraps the lines of one `VEVENT` in a calendar with the given method
+fn write_calendar(method: &str, event_lines: Vec<String>) -> String {
+    let lines = [
+        "BEGIN:VCALENDAR".to_string(),
+        format!("PRODID:{}", PRODID),
+        "VERSION:2.0".to_string(),
+        format!("METHOD:{}", method),
+        "BEGIN:VEVENT".to_string(),
+    ]
+    .into_iter()
+    .chain(event_lines)
+    .chain(["END:VEVENT".to_string(), "END:VCALENDAR".to_string()]);
+
+    let mut ics = String::new();
+    for line in lines {
+        ics.push_str(&fold(&line));
+        ics.push_str("\r\n");
+    }
+    ics
+}
+
+fn format_time(time: DateTime<Utc>, all_day: bool) -> String {
+    if all_day {
+        time.format("%Y%m%d").to_string()
+    } else {
+        time.format("%Y%m%dT%H%M%SZ").to_string()
+    }
+}
+
+fn time_line(name: &str, time: DateTime<Utc>, all_day: bool) -> String {
+    if all_day {
+        format!("{};VALUE=DATE:{}", name, format_time(time, true))
+    } else {
+        format!("{}:{}", name, format_time(time, false))
+    }
+}
+
+fn address_line(name: &str, address: &EmailAddress, status: Option<RsvpStatus>) -> String {
+    let mut line = name.to_string();
+    if let Some(status) = status {
+        line.push_str(";PARTSTAT=");
+        line.push_str(status.partstat());
+    }
+    if let Some(cn) = address.name.as_deref().filter(|cn| !cn.trim().is_empty()) {
+        line.push_str(&format!(";CN=\"{}\"", cn.replace('"', "")));
+    }
+    line.push_str(&format!(":mailto:{}", address.address));
+    line
+}
+
+/// Folds a content line into lines of at most 75 octets, without splitting
+/// a character
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVITE: &str = "BEGIN:VCALENDAR\r\n\
+        PRODID:-//Example//Calendar//EN\r\n\
+        VERSION:2.0\r\n\
+        METHOD:REQUEST\r\n\
+        BEGIN:VTIMEZONE\r\n\
+        TZID:W. Europe Standard Time\r\n\
+        BEGIN:STANDARD\r\n\
+        DTSTART:16010101T030000\r\n\
+        TZOFFSETFROM:+0200\r\n\
+        TZOFFSETTO:+0100\r\n\
+        RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=10\r\n\
+        END:STANDARD\r\n\
+        BEGIN:DAYLIGHT\r\n\
+        DTSTART:16010101T020000\r\n\
+        TZOFFSETFROM:+0100\r\n\
+        TZOFFSETTO:+0200\r\n\
+        RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=3\r\n\
+        END:DAYLIGHT\r\n\
+        END:VTIMEZONE\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:040000008200E00074C5B7101A82E008@example.com\r\n\
+        SEQUENCE:2\r\n\
+        DTSTART;TZID=W. Europe Standard Time:20250410T090000\r\n\
+        DTEND;TZID=W. Europe Standard Time:20250410T103000\r\n\
+        SUMMARY:Quarterly planning\\, part 2\r\n\
+        LOCATION:Room 4.12\r\n\
+        DESCRIPTION:Agenda:\\n- Budget\\n- Hiring\r\n\
+        ORGANIZER;CN=\"Olivia Organizer\":mailto:olivia@example.com\r\n\
+        ATTENDEE;ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;RSVP=TRUE;CN=Max Mu\r\n \
+        ster:MAILTO:Max@Example.com\r\n\
+        ATTENDEE;PARTSTAT=ACCEPTED:mailto:olivia@example.com\r\n\
+        STATUS:CONFIRMED\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_invite() {
+        let calendar = parse(INVITE.as_bytes()).unwrap();
+        assert_eq!(calendar.method.as_deref(), Some("REQUEST"));
+
+        let event = calendar.main_event().unwrap();
+        assert_eq!(event.uid, "040000008200E00074C5B7101A82E008@example.com");
+        assert_eq!(event.sequence, 2);
+        assert_eq!(event.summary.as_deref(), Some("Quarterly planning, part 2"));
+        assert_eq!(
+            event.description.as_deref(),
+            Some("Agenda:\n- Budget\n- Hiring")
+        );
+        assert!(!event.all_day);
+
+        // Summer time applies in April
+        assert_eq!(
+            event.starts_at,
+            "2025-04-10T07:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            event.ends_at,
+            Some("2025-04-10T08:30:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+
+        let organizer = event.organizer.as_ref().unwrap();
+        assert_eq!(organizer.address, "olivia@example.com");
+        assert_eq!(organizer.name.as_deref(), Some("Olivia Organizer"));
+
+        assert_eq!(event.attendees.len(), 2);
+        assert_eq!(event.attendees[0].address, "max@example.com");
+        assert_eq!(event.attendees[0].name.as_deref(), Some("Max Muster"));
+        assert_eq!(
+            event.attendees[0].rsvp_status,
+            Some(RsvpStatus::NeedsAction)
+        );
+        assert_eq!(event.attendees[1].rsvp_status, Some(RsvpStatus::Accepted));
+    }
+
+    #[test]
+    fn test_parse_times() {
+        let timezones = HashMap::new();
+        let property = |line: &str| parse_line(line).unwrap();
+
+        let (time, all_day) =
+            parse_time(&property("DTSTART;VALUE=DATE:20250410"), &timezones).unwrap();
+        assert!(all_day);
+        assert_eq!(
+            time,
+            "2025-04-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        let (time, _) = parse_time(&property("DTSTART:20250110T143000Z"), &timezones).unwrap();
+        assert_eq!(
+            time,
+            "2025-01-10T14:30:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1W"), Some(Duration::days(7)));
+        assert_eq!(parse_duration("-P1DT2H"), Some(-Duration::hours(26)));
+        assert_eq!(parse_utc_offset("-0530"), Some(-19_800));
+
+        assert_eq!(
+            nth_weekday(2025, 3, -1, Weekday::Sun),
+            NaiveDate::from_ymd_opt(2025, 3, 30)
+        );
+        assert_eq!(
+            nth_weekday(2025, 11, 1, Weekday::Sun),
+            NaiveDate::from_ymd_opt(2025, 11, 2)
+        );
+    }
+
+    #[test]
+    fn test_build_reply() {
+        let calendar = parse(INVITE.as_bytes()).unwrap();
+        let event = calendar.main_event().unwrap();
+        let attendee = EmailAddress {
+            address: "max@example.com".to_string(),
+            name: Some("Max Muster".to_string()),
+        };
+        let now = "2025-04-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let reply = build_reply(event, &attendee, RsvpStatus::Tentative, now);
+        assert!(reply.lines().all(|line| line.len() <= MAX_LINE_OCTETS + 1));
+
+        // A reply is itself a valid calendar that round-trips
+        let parsed = parse(reply.as_bytes()).unwrap();
+        assert_eq!(parsed.method.as_deref(), Some("REPLY"));
+        let replied = parsed.main_event().unwrap();
+        assert_eq!(replied.uid, event.uid);
+        assert_eq!(replied.sequence, 2);
+        assert_eq!(replied.starts_at, event.starts_at);
+        assert_eq!(replied.summary, event.summary);
+        assert_eq!(replied.attendees.len(), 1);
+        assert_eq!(replied.attendees[0].address, "max@example.com");
+        assert_eq!(
+            replied.attendees[0].rsvp_status,
+            Some(RsvpStatus::Tentative)
+        );
+    }
+
+    #[test]
+    fn test_build_event() {
+        let event = Event {
+            uid: "0192c3a4@ravn.app".to_string(),
+            sequence: 0,
+            recurrence_id: None,
+            summary: Some("Dentist; bring card".to_string()),
+            description: Some("Line one\nLine two".to_string()),
+            location: Some("Main St. 1, Springfield".to_string()),
+            starts_at: "2025-05-02T00:00:00Z".parse().unwrap(),
+            ends_at: Some("2025-05-03T00:00:00Z".parse().unwrap()),
+            all_day: true,
+            status: None,
+            organizer: None,
+            attendees: Vec::new(),
+        };
+
+        let ics = build_event(&event, Utc::now());
+        assert!(ics.contains("METHOD:PUBLISH\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20250502\r\n"));
+        assert!(ics.contains("SUMMARY:Dentist\\; bring card\r\n"));
+
+        let parsed = parse(ics.as_bytes()).unwrap();
+        assert_eq!(parsed.main_event(), Some(&event));
+    }
+}
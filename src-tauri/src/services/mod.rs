@@ -1,5 +1,24 @@
+pub mod ai_exclusions;
+pub mod app_lock;
+pub mod automation_api;
+pub mod automation_triggers;
 pub mod avatar_service;
+pub mod contact_ranking;
+pub mod contact_security;
+pub mod conversation_export;
 pub mod corvus;
+pub mod custom_themes;
+pub mod dkim;
 pub mod email_renderer;
 pub mod email_service;
+pub mod feature_flags;
+pub mod html_sanitizer;
+pub mod icalendar;
 pub mod notification_service;
+pub mod pgp_keys;
+pub mod recipient_validator;
+pub mod reply_builder;
+pub mod signatures;
+pub mod smime;
+pub mod template_renderer;
+pub mod theme_scheduler;
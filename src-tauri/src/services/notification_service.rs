@@ -1,17 +1,30 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{QueryBuilder, Sqlite, SqlitePool};
-use std::{collections::HashMap, sync::Arc};
-use tauri::{AppHandle, Emitter, Manager};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tauri::{AppHandle, Emitter, Listener, Manager};
 #[cfg(not(target_os = "macos"))]
 use tauri_plugin_notification::{NotificationExt, PermissionState};
 use uuid::Uuid;
 
 use crate::config::settings::Settings;
+use crate::database::models::contact::Contact;
 use crate::database::models::email::Email;
+use crate::database::models::view::View;
 use crate::database::repositories::{
     ContactRepository, FolderRepository, SqliteContactRepository, SqliteFolderRepository,
+    SqliteViewRepository, ViewRepository,
 };
+use crate::navigation::NavigationUrl;
+use crate::services::app_lock;
+use crate::state::AppState;
+use crate::sync::priority;
 use crate::sync::types::FolderType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +43,24 @@ pub struct NotificationSettings {
     pub badge_folders: Option<Vec<String>>,
     #[serde(rename = "badgeType")]
     pub badge_type: Option<String>,
+    #[serde(rename = "badgeSource")]
+    pub badge_source: Option<String>,
+    #[serde(rename = "badgeView")]
+    pub badge_view: Option<String>,
+    #[serde(rename = "disabledAccounts")]
+    pub disabled_accounts: Option<Vec<String>>,
+    #[serde(rename = "vipOnly")]
+    pub vip_only: Option<bool>,
+    #[serde(rename = "quietHoursEnabled")]
+    pub quiet_hours_enabled: Option<bool>,
+    #[serde(rename = "quietHoursStart")]
+    pub quiet_hours_start: Option<String>,
+    #[serde(rename = "quietHoursEnd")]
+    pub quiet_hours_end: Option<String>,
+    #[serde(rename = "alwaysNotifyFolders")]
+    pub always_notify_folders: Option<Vec<String>>,
+    #[serde(rename = "silentFolders")]
+    pub silent_folders: Option<Vec<String>>,
 }
 
 impl Default for NotificationSettings {
@@ -42,10 +73,384 @@ impl Default for NotificationSettings {
             notification_folders: Some(vec![]),
             badge_folders: Some(vec![]),
             badge_type: Some("count".to_string()),
+            badge_source: Some("folders".to_string()),
+            badge_view: None,
+            disabled_accounts: Some(vec![]),
+            vip_only: Some(false),
+            quiet_hours_enabled: Some(false),
+            quiet_hours_start: Some("22:00".to_string()),
+            quiet_hours_end: Some("07:00".to_string()),
+            always_notify_folders: Some(vec![]),
+            silent_folders: Some(vec![]),
+        }
+    }
+}
+
+/// Events after which the unread badge may be out of date
+const BADGE_REFRESH_EVENTS: &[&str] = &[
+    "email:created",
+    "email:updated",
+    "email:deleted",
+    "email:restored",
+    "sync:email-read-status-changed",
+    "sync:email-moved",
+    "sync:email-deleted",
+    "sync:email-restored",
+    "sync:folder-counts-updated",
+];
+
+/// Bursts of email events within this delay cause a single badge update
+const BADGE_REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+/// What the dock/taskbar badge counts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BadgeSource {
+    /// Unread emails of all accounts
+    AllAccounts,
+    /// Unread emails of the given folders
+    Folders(Vec<Uuid>),
+    /// Unread emails shown by a view
+    View(Uuid),
+}
+
+impl BadgeSource {
+    /// Without a source, `badgeFolders` keeps its meaning from before sources
+    /// existed: an empty list counts all folders, a missing one nothing
+    pub fn from_settings(settings: &NotificationSettings) -> Result<Self, String> {
+        match settings.badge_source.as_deref() {
+            Some("all") => Ok(Self::AllAccounts),
+            Some("view") => {
+                let view_id = settings
+                    .badge_view
+                    .as_deref()
+                    .ok_or_else(|| "No view selected for the badge count".to_string())?;
+                Uuid::parse_str(view_id)
+                    .map(Self::View)
+                    .map_err(|e| format!("Failed to parse badge view ID: {}", e))
+            }
+            _ => match &settings.badge_folders {
+                None => Ok(Self::Folders(Vec::new())),
+                Some(folder_ids) if folder_ids.is_empty() => Ok(Self::AllAccounts),
+                Some(folder_ids) => folder_ids
+                    .iter()
+                    .map(|id_str| Uuid::parse_str(id_str))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Self::Folders)
+                    .map_err(|e| format!("Failed to parse folder IDs: {}", e)),
+            },
+        }
+    }
+}
+
+/// Why an incoming email does not notify
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suppression {
+    AccountDisabled,
+    FolderSilenced,
+    FolderNotSelected,
+    QuietHours,
+    NotVip,
+}
+
+/// A daily time span without notifications, which may run past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Rules deciding which incoming emails notify, on top of the folders
+/// selected by `notificationFolders`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationRules {
+    /// Accounts that neither notify nor count towards the badge
+    pub disabled_accounts: HashSet<Uuid>,
+    /// Only emails from VIP contacts notify
+    pub vip_only: bool,
+    pub quiet_hours: Option<QuietHours>,
+    /// Folders that notify regardless of VIP mode and quiet hours
+    pub always_notify_folders: HashSet<Uuid>,
+    /// Folders that never notify
+    pub silent_folders: HashSet<Uuid>,
+}
+
+impl NotificationRules {
+    /// Invalid IDs and times are left out, so a broken entry disables one rule
+    /// instead of all notifications
+    pub fn from_settings(settings: &NotificationSettings) -> Self {
+        let ids = |values: &Option<Vec<String>>| -> HashSet<Uuid> {
+            values
+                .iter()
+                .flatten()
+                .filter_map(|value| match Uuid::parse_str(value) {
+                    Ok(id) => Some(id),
+                    Err(e) => {
+                        log::warn!("Ignoring invalid ID {} in notification rules: {}", value, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+        let time = |value: &Option<String>| -> Option<NaiveTime> {
+            let value = value.as_deref()?;
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|e| log::warn!("Ignoring invalid quiet hours time {}: {}", value, e))
+                .ok()
+        };
+
+        let quiet_hours = if settings.quiet_hours_enabled.unwrap_or(false) {
+            time(&settings.quiet_hours_start)
+                .zip(time(&settings.quiet_hours_end))
+                .map(|(start, end)| QuietHours { start, end })
+        } else {
+            None
+        };
+
+        Self {
+            disabled_accounts: ids(&settings.disabled_accounts),
+            vip_only: settings.vip_only.unwrap_or(false),
+            quiet_hours,
+            always_notify_folders: ids(&settings.always_notify_folders),
+            silent_folders: ids(&settings.silent_folders),
+        }
+    }
+
+    /// Checks an incoming email of the given priority against the rules,
+    /// `folder_selected` being whether `notificationFolders` selects its folder
+    /// and `now` the local time. Returns why it must not notify, if it must not.
+    pub fn check(
+        &self,
+        account_id: Uuid,
+        folder_id: Uuid,
+        priority: i64,
+        folder_selected: bool,
+        now: NaiveTime,
+    ) -> Option<Suppression> {
+        if self.disabled_accounts.contains(&account_id) {
+            return Some(Suppression::AccountDisabled);
+        }
+        if self.silent_folders.contains(&folder_id) {
+            return Some(Suppression::FolderSilenced);
+        }
+        if self.always_notify_folders.contains(&folder_id) {
+            return None;
+        }
+        if !folder_selected {
+            return Some(Suppression::FolderNotSelected);
+        }
+        if self.quiet_hours.is_some_and(|quiet| quiet.contains(now)) {
+            return Some(Suppression::QuietHours);
+        }
+        if self.vip_only && !priority::is_vip(priority) {
+            return Some(Suppression::NotVip);
+        }
+        None
+    }
+}
+
+/// Messages of one thread arriving within this time update a single
+/// notification instead of showing one each
+const THREAD_NOTIFICATION_WINDOW: Duration = Duration::from_secs(120);
+
+/// Recently notified messages of one thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadNotification {
+    pub count: usize,
+    /// Distinct senders, in order of arrival
+    pub senders: Vec<String>,
+    last_notified: Instant,
+}
+
+impl ThreadNotification {
+    /// Whether an earlier notification of the thread is being replaced
+    pub fn is_coalesced(&self) -> bool {
+        self.count > 1
+    }
+
+    /// "3 new messages from Alice", naming up to two senders
+    pub fn title(&self) -> String {
+        new_messages_title(self.count, &self.senders)
+    }
+}
+
+/// "3 new messages from Alice and Bob", naming up to two distinct senders
+fn new_messages_title(count: usize, senders: &[String]) -> String {
+    let senders = match senders {
+        [] => "Unknown sender".to_string(),
+        [sender] => sender.clone(),
+        [first, second] => format!("{} and {}", first, second),
+        [first, rest @ ..] => format!("{} and {} others", first, rest.len()),
+    };
+
+    format!("{} new messages from {}", count, senders)
+}
+
+/// Messages listed in the body of a batch notification
+const BATCH_NOTIFICATION_LINES: usize = 3;
+
+/// Title and body of one notification for several messages of a sync batch,
+/// from their senders and subjects
+pub fn batch_summary(messages: &[(String, String)]) -> (String, String) {
+    let mut senders: Vec<String> = Vec::new();
+    for (sender, _) in messages {
+        if !senders.contains(sender) {
+            senders.push(sender.clone());
+        }
+    }
+
+    let mut lines: Vec<String> = messages
+        .iter()
+        .take(BATCH_NOTIFICATION_LINES)
+        .map(|(sender, subject)| format!("{}: {}", sender, subject))
+        .collect();
+    if messages.len() > BATCH_NOTIFICATION_LINES {
+        lines.push(format!(
+            "and {} more",
+            messages.len() - BATCH_NOTIFICATION_LINES
+        ));
+    }
+
+    (
+        new_messages_title(messages.len(), &senders),
+        lines.join("\n"),
+    )
+}
+
+/// Actions offered on notifications of incoming mail. They run the same
+/// commands as the buttons in the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationAction {
+    Archive,
+    MarkRead,
+    Reply,
+}
+
+impl NotificationAction {
+    pub fn id(&self) -> &'static str {
+        match self {
+            NotificationAction::Archive => "archive",
+            NotificationAction::MarkRead => "mark-read",
+            NotificationAction::Reply => "reply",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationAction::Archive => "Archive",
+            NotificationAction::MarkRead => "Mark as Read",
+            NotificationAction::Reply => "Reply",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        [Self::Archive, Self::MarkRead, Self::Reply]
+            .into_iter()
+            .find(|action| action.id() == id)
+    }
+
+    /// macOS reports the label of the chosen action
+    pub fn from_label(label: &str) -> Option<Self> {
+        [Self::Archive, Self::MarkRead, Self::Reply]
+            .into_iter()
+            .find(|action| action.label() == label)
+    }
+}
+
+/// Whether a notification is shown here, with its actions, instead of by the
+/// frontend, whose notifications have no actions on desktop
+fn shows_actions_natively(payload: &NotificationEventPayload) -> bool {
+    cfg!(any(target_os = "macos", target_os = "linux")) && !payload.actions.is_empty()
+}
+
+/// Runs an action chosen on a notification for its emails. Replying opens
+/// the composer in the app; the other actions apply to every email.
+pub async fn run_notification_action(
+    app_handle: &AppHandle,
+    action: NotificationAction,
+    email_ids: &[Uuid],
+) {
+    let state = app_handle.state::<AppState>();
+
+    for &email_id in email_ids {
+        let result = match action {
+            NotificationAction::Archive => {
+                crate::commands::emails::archive(state.clone(), email_id)
+                    .await
+                    .map(|_| ())
+            }
+            NotificationAction::MarkRead => {
+                crate::commands::emails::update_read(state.clone(), email_id, true).await
+            }
+            NotificationAction::Reply => {
+                crate::navigation::dispatch_navigation_url(
+                    app_handle,
+                    NavigationUrl::reply(email_id),
+                );
+                return;
+            }
+        };
+
+        if let Err(error) = result {
+            log::warn!(
+                "Failed to {} email {} from notification: {}",
+                action.id(),
+                email_id,
+                error
+            );
+        }
+    }
+}
+
+/// Incoming notifications per thread, keyed by conversation ID
+#[derive(Debug, Default)]
+pub struct ThreadNotifications {
+    threads: HashMap<String, ThreadNotification>,
+}
+
+impl ThreadNotifications {
+    /// Count a new message of the thread. A thread that was quiet for longer
+    /// than the window starts over.
+    pub fn register(&mut self, thread: &str, sender: &str, now: Instant) -> ThreadNotification {
+        self.threads.retain(|_, notification| {
+            now.duration_since(notification.last_notified) < THREAD_NOTIFICATION_WINDOW
+        });
+
+        let notification =
+            self.threads
+                .entry(thread.to_string())
+                .or_insert_with(|| ThreadNotification {
+                    count: 0,
+                    senders: Vec::new(),
+                    last_notified: now,
+                });
+        notification.count += 1;
+        notification.last_notified = now;
+        if !notification.senders.iter().any(|known| known == sender) {
+            notification.senders.push(sender.to_string());
         }
+
+        notification.clone()
     }
 }
 
+/// Unread emails per folder and per view, for badges next to them
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadCounts {
+    pub folders: HashMap<Uuid, i64>,
+    pub views: HashMap<Uuid, i64>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct BadgeCount {
     pub count: i64,
@@ -67,6 +472,8 @@ pub struct NotificationEmailPreview {
     pub avatar_url: Option<String>,
     pub remind_at: Option<String>,
     pub navigation_target: Option<String>,
+    /// Important by its priority, so the notification stands out
+    pub important: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +486,47 @@ pub struct NotificationEventPayload {
     pub play_sound: bool,
     pub suppress_during_bootstrap: bool,
     pub tag: Option<String>,
+    pub deep_link: Option<String>,
+    /// Offered as buttons where the platform shows notification actions
+    #[serde(default)]
+    pub actions: Vec<NotificationAction>,
+    /// The emails the actions apply to
+    #[serde(default)]
+    pub email_ids: Vec<String>,
+}
+
+impl NotificationEventPayload {
+    /// The notification without any mail data, shown while the app is locked
+    pub fn redacted(&self) -> Self {
+        Self {
+            kind: self.kind.clone(),
+            title: "New mail".to_string(),
+            body: None,
+            email: None,
+            play_sound: self.play_sound,
+            suppress_during_bootstrap: self.suppress_during_bootstrap,
+            tag: self.tag.clone(),
+            deep_link: None,
+            actions: Vec::new(),
+            email_ids: Vec::new(),
+        }
+    }
+
+    /// Where clicking the notification leads
+    pub fn navigation_target(&self) -> Option<String> {
+        self.deep_link.clone().or_else(|| {
+            self.email
+                .as_ref()
+                .and_then(|email| email.navigation_target.clone())
+        })
+    }
+
+    pub fn action_email_ids(&self) -> Vec<Uuid> {
+        self.email_ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect()
+    }
 }
 
 pub struct NotificationService {
@@ -86,6 +534,7 @@ pub struct NotificationService {
     settings: Arc<Settings>,
     app_handle: Option<AppHandle>,
     suppress_notifications: bool,
+    thread_notifications: Mutex<ThreadNotifications>,
 }
 
 impl NotificationService {
@@ -95,6 +544,7 @@ impl NotificationService {
             settings,
             app_handle: None,
             suppress_notifications: false,
+            thread_notifications: Mutex::new(ThreadNotifications::default()),
         }
     }
 
@@ -155,7 +605,7 @@ impl NotificationService {
 
     #[cfg(target_os = "macos")]
     fn show_macos_notification(&self, payload: &NotificationEventPayload) -> Result<(), String> {
-        use mac_notification_sys::{Notification, NotificationResponse};
+        use mac_notification_sys::{MainButton, Notification, NotificationResponse};
 
         let Some(app_handle) = &self.app_handle else {
             log::warn!("Cannot show macOS notification: AppHandle not available");
@@ -165,10 +615,9 @@ impl NotificationService {
         let app_handle = app_handle.clone();
         let title = payload.title.clone();
         let body = payload.body.clone().unwrap_or_default();
-        let navigation_target = payload
-            .email
-            .as_ref()
-            .and_then(|email| email.navigation_target.clone());
+        let navigation_target = payload.navigation_target();
+        let actions = payload.actions.clone();
+        let email_ids = payload.action_email_ids();
         let avatar_path = payload
             .email
             .as_ref()
@@ -179,6 +628,7 @@ impl NotificationService {
             .and_then(|email| email.sender_name.clone().or(email.sender_address.clone()));
 
         std::thread::spawn(move || {
+            let labels: Vec<&str> = actions.iter().map(NotificationAction::label).collect();
             let mut notification = Notification::new();
             notification.title(&title).message(&body);
             notification.maybe_subtitle(subtitle.as_deref());
@@ -187,20 +637,36 @@ impl NotificationService {
                 notification.content_image(avatar_path);
             }
 
-            if navigation_target.is_some() {
+            if !labels.is_empty() {
+                notification.main_button(MainButton::DropdownActions("Actions", &labels));
+            }
+
+            if navigation_target.is_some() || !labels.is_empty() {
                 notification.wait_for_click(true);
             } else {
                 notification.asynchronous(true);
             }
 
+            let open = |navigation_target: Option<String>| {
+                if let Some(target) = navigation_target {
+                    crate::navigation::dispatch_navigation_url(&app_handle, target);
+                } else {
+                    crate::navigation::reveal_main_window(&app_handle);
+                }
+            };
+
             match notification.send() {
-                Ok(NotificationResponse::Click | NotificationResponse::ActionButton(_)) => {
-                    if let Some(target) = navigation_target {
-                        crate::navigation::dispatch_navigation_url(&app_handle, target);
-                    } else {
-                        crate::navigation::reveal_main_window(&app_handle);
+                Ok(NotificationResponse::ActionButton(label)) => {
+                    match NotificationAction::from_label(&label) {
+                        Some(action) => tauri::async_runtime::block_on(run_notification_action(
+                            &app_handle,
+                            action,
+                            &email_ids,
+                        )),
+                        None => open(navigation_target),
                     }
                 }
+                Ok(NotificationResponse::Click) => open(navigation_target),
                 Ok(_) => {}
                 Err(error) => {
                     log::warn!("Failed to show macOS notification: {}", error);
@@ -216,6 +682,55 @@ impl NotificationService {
         Ok(())
     }
 
+    /// Shows a notification with actions through the desktop's notification
+    /// server, which reports the chosen action back
+    #[cfg(target_os = "linux")]
+    fn show_linux_notification(&self, payload: &NotificationEventPayload) -> Result<(), String> {
+        let Some(app_handle) = &self.app_handle else {
+            log::warn!("Cannot show notification: AppHandle not available");
+            return Ok(());
+        };
+
+        let app_handle = app_handle.clone();
+        let navigation_target = payload.navigation_target();
+        let email_ids = payload.action_email_ids();
+
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname(&app_handle.package_info().name)
+            .summary(&payload.title)
+            .body(payload.body.as_deref().unwrap_or_default());
+        // "default" is the action of clicking the notification itself
+        notification.action("default", "Open");
+        for action in &payload.actions {
+            notification.action(action.id(), action.label());
+        }
+
+        std::thread::spawn(move || match notification.show() {
+            Ok(handle) => handle.wait_for_action(|id| {
+                if let Some(action) = NotificationAction::from_id(id) {
+                    tauri::async_runtime::block_on(run_notification_action(
+                        &app_handle,
+                        action,
+                        &email_ids,
+                    ));
+                } else if id == "default" {
+                    match navigation_target {
+                        Some(target) => {
+                            crate::navigation::dispatch_navigation_url(&app_handle, target)
+                        }
+                        None => crate::navigation::reveal_main_window(&app_handle),
+                    }
+                }
+            }),
+            Err(error) => {
+                log::warn!("Failed to show notification: {}", error);
+            }
+        });
+
+        Ok(())
+    }
+
     async fn apply_badge_count(&self, count: i64) -> Result<(), String> {
         let settings = self.get_notification_settings()?;
         let mode = self.badge_mode(&settings);
@@ -315,12 +830,25 @@ impl NotificationService {
         payload: &NotificationEventPayload,
         _fallback_body: &str,
     ) -> Result<(), String> {
+        let redacted;
+        let (payload, _fallback_body) = if self.is_app_locked() {
+            redacted = payload.redacted();
+            (&redacted, "")
+        } else {
+            (payload, _fallback_body)
+        };
+
         #[cfg(target_os = "macos")]
         {
             self.show_macos_notification(payload)?;
             return Ok(());
         }
 
+        #[cfg(target_os = "linux")]
+        if shows_actions_natively(payload) {
+            return self.show_linux_notification(payload);
+        }
+
         #[cfg(not(target_os = "macos"))]
         {
             if !self.can_dispatch_notifications_to_frontend() {
@@ -332,6 +860,12 @@ impl NotificationService {
         }
     }
 
+    fn is_app_locked(&self) -> bool {
+        self.app_handle
+            .as_ref()
+            .is_some_and(app_lock::is_app_locked)
+    }
+
     fn emit_native_notification_event(
         &self,
         payload: &NotificationEventPayload,
@@ -341,8 +875,7 @@ impl NotificationService {
             return Ok(());
         };
 
-        app_handle
-            .emit("native-notification", payload)
+        app_lock::emit_mail_event(app_handle, "native-notification", payload)
             .map_err(|e| format!("Failed to emit native notification event: {}", e))
     }
 
@@ -388,17 +921,7 @@ impl NotificationService {
         let folder_id = email.folder_id.to_string();
         let account_id = email.account_id.to_string();
         let conversation_id = email.conversation_id.clone();
-        let navigation_target = if let Some(conversation_id) = &conversation_id {
-            Some(format!(
-                "ravn://mail/{}/folders/{}/conversations/{}?email={}",
-                account_id, folder_id, conversation_id, email_id
-            ))
-        } else {
-            Some(format!(
-                "ravn://mail/{}/folders/{}/emails/{}",
-                account_id, folder_id, email_id
-            ))
-        };
+        let navigation_target = Some(NavigationUrl::email(email.id));
 
         NotificationEmailPreview {
             id: email_id,
@@ -412,6 +935,7 @@ impl NotificationService {
             avatar_url,
             remind_at: email.remind_at.map(|value| value.to_rfc3339()),
             navigation_target,
+            important: priority::is_important(email.priority),
         }
     }
 
@@ -426,19 +950,101 @@ impl NotificationService {
             .subject
             .clone()
             .unwrap_or_else(|| "(no subject)".to_string());
-        let body = preview
-            .snippet
+
+        // Messages of one thread share a tag, so a newer notification replaces
+        // the earlier one instead of stacking
+        let thread = email
+            .conversation_id
             .clone()
-            .unwrap_or_else(|| format!("{} — {}", sender, subject));
+            .unwrap_or_else(|| email.id.to_string());
+        let thread_notification =
+            self.thread_notifications
+                .lock()
+                .unwrap()
+                .register(&thread, &sender, Instant::now());
+
+        let (title, body) = if thread_notification.is_coalesced() {
+            (thread_notification.title(), subject)
+        } else {
+            let body = preview
+                .snippet
+                .clone()
+                .unwrap_or_else(|| format!("{} — {}", sender, subject));
+            (sender, body)
+        };
+        let title = if preview.important {
+            format!("★ {}", title)
+        } else {
+            title
+        };
 
         NotificationEventPayload {
             kind: "incoming-email".to_string(),
-            title: sender,
+            title,
             body: Some(body),
             email: Some(preview),
+            play_sound: !self.suppress_notifications && !thread_notification.is_coalesced(),
+            suppress_during_bootstrap: true,
+            tag: Some(format!("incoming-thread:{}", thread)),
+            deep_link: None,
+            actions: vec![
+                NotificationAction::Reply,
+                NotificationAction::Archive,
+                NotificationAction::MarkRead,
+            ],
+            email_ids: vec![email.id.to_string()],
+        }
+    }
+
+    /// One notification for the messages a sync brought into a folder
+    fn build_batch_notification_payload(&self, emails: &[&Email]) -> NotificationEventPayload {
+        let messages: Vec<(String, String)> = emails
+            .iter()
+            .map(|email| {
+                (
+                    email
+                        .from
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| email.from.address.clone()),
+                    email
+                        .subject
+                        .clone()
+                        .unwrap_or_else(|| "(no subject)".to_string()),
+                )
+            })
+            .collect();
+        let (title, body) = batch_summary(&messages);
+        let title = if emails
+            .iter()
+            .any(|email| priority::is_important(email.priority))
+        {
+            format!("★ {}", title)
+        } else {
+            title
+        };
+
+        // The messages of a batch share their folder, which the click opens
+        let deep_link = emails.first().map(|email| {
+            NavigationUrl::build(
+                &format!("mail/{}/folders/{}", email.account_id, email.folder_id),
+                None,
+            )
+        });
+
+        NotificationEventPayload {
+            kind: "incoming-email".to_string(),
+            title,
+            body: Some(body),
+            email: None,
             play_sound: !self.suppress_notifications,
             suppress_during_bootstrap: true,
-            tag: Some(format!("incoming-email:{}", email.id)),
+            tag: emails
+                .first()
+                .map(|email| format!("incoming-batch:{}", email.folder_id)),
+            deep_link,
+            actions: vec![NotificationAction::Archive, NotificationAction::MarkRead],
+            email_ids: emails.iter().map(|email| email.id.to_string()).collect(),
         }
     }
 
@@ -469,6 +1075,80 @@ impl NotificationService {
                 .remind_at
                 .as_ref()
                 .map(|remind_at| format!("reminder-email:{}:{}", email.id, remind_at)),
+            deep_link: None,
+            actions: Vec::new(),
+            email_ids: Vec::new(),
+        }
+    }
+
+    async fn build_snooze_notification_payload(&self, email: &Email) -> NotificationEventPayload {
+        let preview = self.build_email_preview(email).await;
+        let sender = preview
+            .sender_name
+            .clone()
+            .or(preview.sender_address.clone())
+            .unwrap_or_else(|| "Unknown sender".to_string());
+        let subject = preview
+            .subject
+            .clone()
+            .unwrap_or_else(|| "(no subject)".to_string());
+
+        NotificationEventPayload {
+            kind: "snoozed-email".to_string(),
+            title: format!("Back from snooze: {}", subject),
+            body: Some(preview.snippet.clone().unwrap_or(sender)),
+            email: Some(preview),
+            play_sound: !self.suppress_notifications,
+            suppress_during_bootstrap: false,
+            tag: Some(format!("snoozed-email:{}", email.id)),
+            deep_link: None,
+            actions: Vec::new(),
+            email_ids: Vec::new(),
+        }
+    }
+
+    fn build_contact_date_notification_payload(
+        &self,
+        contact: &Contact,
+        kind: &str,
+        year: i32,
+        years: Option<i32>,
+    ) -> NotificationEventPayload {
+        let name = contact.full_name();
+        let (title, body, subject) = match (kind, years) {
+            ("anniversary", Some(years)) => (
+                format!("Anniversary: {}", name),
+                format!("{} celebrates {} years today.", name, years),
+                "Happy anniversary!",
+            ),
+            ("anniversary", None) => (
+                format!("Anniversary: {}", name),
+                format!("{} has an anniversary today.", name),
+                "Happy anniversary!",
+            ),
+            (_, Some(years)) => (
+                format!("Birthday: {}", name),
+                format!("{} turns {} today.", name, years),
+                "Happy birthday!",
+            ),
+            (_, None) => (
+                format!("Birthday: {}", name),
+                format!("It's {}'s birthday today.", name),
+                "Happy birthday!",
+            ),
+        };
+
+        NotificationEventPayload {
+            kind: "contact-date".to_string(),
+            title,
+            body: Some(format!("{} Click to write a message.", body)),
+            email: None,
+            play_sound: !self.suppress_notifications,
+            suppress_during_bootstrap: false,
+            tag: Some(format!("contact-date:{}:{}:{}", contact.id, kind, year)),
+            deep_link: Some(NavigationUrl::compose(&contact.email, Some(subject))),
+            actions: Vec::new(),
+            email_ids: Vec::new(),
         }
     }
 
@@ -481,6 +1161,9 @@ impl NotificationService {
             play_sound: false,
             suppress_during_bootstrap: false,
             tag: Some("outgoing-email".to_string()),
+            deep_link: None,
+            actions: Vec::new(),
+            email_ids: Vec::new(),
         }
     }
 
@@ -549,9 +1232,10 @@ impl NotificationService {
         Ok(())
     }
 
+    /// Unread emails the badge shows, leaving out accounts whose
+    /// notifications are disabled
     pub async fn calculate_badge_count(&self) -> Result<i64, String> {
         let settings = self.get_notification_settings()?;
-        let folder_repo = SqliteFolderRepository::new(self.pool.clone());
 
         let mode = self.badge_mode(&settings);
         if mode == "none" {
@@ -559,48 +1243,180 @@ impl NotificationService {
             return Ok(0);
         }
 
-        let count = match &settings.badge_folders {
-            None => {
-                log::info!("Badge count disabled (no badge folders set)");
-                0
+        let excluded_accounts = NotificationRules::from_settings(&settings).disabled_accounts;
+
+        match BadgeSource::from_settings(&settings)? {
+            BadgeSource::AllAccounts => {
+                log::debug!("Calculating badge count from unread totals for all folders");
+                self.unread_count_for_all_folders(&excluded_accounts).await
             }
-            Some(folders) if folders.is_empty() => {
-                log::info!("Calculating badge count from unread totals for all folders");
-                let folders = folder_repo
-                    .get_all()
+            BadgeSource::Folders(folder_ids) => {
+                log::debug!(
+                    "Calculating badge count from unread totals for {} folders",
+                    folder_ids.len()
+                );
+                self.unread_count_for_folders(&folder_ids, &excluded_accounts)
                     .await
-                    .map_err(|e| format!("Failed to load folders for badge count: {}", e))?;
-
-                folders.iter().map(|folder| folder.unread_count).sum()
             }
-            Some(folder_ids) => {
-                let parsed_folder_ids: Result<Vec<Uuid>, _> = folder_ids
-                    .iter()
-                    .map(|id_str| Uuid::parse_str(id_str))
-                    .collect();
+            BadgeSource::View(view_id) => {
+                let view = SqliteViewRepository::new(self.pool.clone())
+                    .find_by_id(view_id)
+                    .await
+                    .map_err(|e| {
+                        format!("Failed to load view {} for badge count: {}", view_id, e)
+                    })?;
+
+                match view {
+                    Some(view) => self.unread_count_for_view(&view, &excluded_accounts).await,
+                    None => {
+                        log::warn!("Badge view {} no longer exists", view_id);
+                        Ok(0)
+                    }
+                }
+            }
+        }
+    }
 
-                let parsed_folder_ids =
-                    parsed_folder_ids.map_err(|e| format!("Failed to parse folder IDs: {}", e))?;
+    async fn unread_count_for_all_folders(
+        &self,
+        excluded_accounts: &HashSet<Uuid>,
+    ) -> Result<i64, String> {
+        let folders = SqliteFolderRepository::new(self.pool.clone())
+            .get_all()
+            .await
+            .map_err(|e| format!("Failed to load folders for badge count: {}", e))?;
 
-                log::info!(
-                    "Calculating badge count from unread totals for {} folders",
-                    parsed_folder_ids.len()
-                );
+        Ok(folders
+            .iter()
+            .filter(|folder| !excluded_accounts.contains(&folder.account_id))
+            .map(|folder| folder.unread_count)
+            .sum())
+    }
 
-                let mut total = 0_i64;
-                for folder_id in parsed_folder_ids {
-                    if let Some(folder) = folder_repo.find_by_id(folder_id).await.map_err(|e| {
-                        format!("Failed to load folder {} for badge count: {}", folder_id, e)
-                    })? {
-                        total += folder.unread_count;
-                    }
+    /// Unread emails of the given folders, from the counts kept on each folder
+    pub async fn unread_count_for_folders(
+        &self,
+        folder_ids: &[Uuid],
+        excluded_accounts: &HashSet<Uuid>,
+    ) -> Result<i64, String> {
+        let folder_repo = SqliteFolderRepository::new(self.pool.clone());
+
+        let mut total = 0_i64;
+        for folder_id in folder_ids {
+            if let Some(folder) = folder_repo.find_by_id(*folder_id).await.map_err(|e| {
+                format!("Failed to load folder {} for badge count: {}", folder_id, e)
+            })? {
+                if !excluded_accounts.contains(&folder.account_id) {
+                    total += folder.unread_count;
                 }
+            }
+        }
 
-                total
+        Ok(total)
+    }
+
+    /// Unread emails in the folders of a view that carry one of its labels
+    pub async fn unread_count_for_view(
+        &self,
+        view: &View,
+        excluded_accounts: &HashSet<Uuid>,
+    ) -> Result<i64, String> {
+        let (folder_ids, label_ids) = view.scope();
+
+        let mut query_builder: QueryBuilder<'_, Sqlite> = QueryBuilder::new(
+            "SELECT COUNT(*) FROM emails e WHERE e.is_read = 0 AND e.is_deleted = 0 AND e.is_draft = 0",
+        );
+
+        if !folder_ids.is_empty() {
+            query_builder.push(" AND e.folder_id IN (");
+            let mut separated = query_builder.separated(", ");
+            for folder_id in &folder_ids {
+                separated.push_bind(folder_id.to_string());
+            }
+            separated.push_unseparated(")");
+        }
+
+        if !label_ids.is_empty() {
+            query_builder.push(
+                " AND EXISTS (SELECT 1 FROM email_labels el WHERE el.email_id = e.id AND el.label_id IN (",
+            );
+            let mut separated = query_builder.separated(", ");
+            for label_id in &label_ids {
+                separated.push_bind(label_id.to_string());
+            }
+            separated.push_unseparated("))");
+        }
+
+        if !excluded_accounts.is_empty() {
+            query_builder.push(" AND e.account_id NOT IN (");
+            let mut separated = query_builder.separated(", ");
+            for account_id in excluded_accounts {
+                separated.push_bind(account_id.to_string());
             }
+            separated.push_unseparated(")");
+        }
+
+        query_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to count unread emails of view {}: {}", view.id, e))
+    }
+
+    /// Unread counts of every folder and view
+    pub async fn unread_counts(&self) -> Result<UnreadCounts, String> {
+        let folders = SqliteFolderRepository::new(self.pool.clone())
+            .get_all()
+            .await
+            .map_err(|e| format!("Failed to load folders: {}", e))?
+            .into_iter()
+            .map(|folder| (folder.id, folder.unread_count))
+            .collect();
+
+        let mut views = HashMap::new();
+        for view in SqliteViewRepository::new(self.pool.clone())
+            .get_all()
+            .await
+            .map_err(|e| format!("Failed to load views: {}", e))?
+        {
+            views.insert(
+                view.id,
+                self.unread_count_for_view(&view, &HashSet::new()).await?,
+            );
+        }
+
+        Ok(UnreadCounts { folders, views })
+    }
+
+    /// Recalculate the badge whenever emails change, so it stays current
+    /// without the frontend asking for it
+    pub fn watch_email_changes(self: &Arc<Self>) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
         };
 
-        Ok(count)
+        let refresh_pending = Arc::new(AtomicBool::new(false));
+        for event in BADGE_REFRESH_EVENTS {
+            let service = Arc::clone(self);
+            let refresh_pending = Arc::clone(&refresh_pending);
+
+            app_handle.listen(*event, move |_| {
+                if refresh_pending.swap(true, Ordering::SeqCst) {
+                    return;
+                }
+
+                let service = Arc::clone(&service);
+                let refresh_pending = Arc::clone(&refresh_pending);
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(BADGE_REFRESH_DELAY).await;
+                    refresh_pending.store(false, Ordering::SeqCst);
+
+                    if let Err(e) = service.update_badge_count().await {
+                        log::warn!("Failed to update badge count: {}", e);
+                    }
+                });
+            });
+        }
     }
 
     pub async fn latest_reminder_notification_map(
@@ -661,16 +1477,16 @@ impl NotificationService {
         self.apply_badge_count(count).await?;
 
         if let Some(app_handle) = &self.app_handle {
-            app_handle
-                .emit(
-                    "badge-count-updated",
-                    BadgeCount {
-                        count,
-                        visible,
-                        mode,
-                    },
-                )
-                .map_err(|e| format!("Failed to emit badge count event: {}", e))?;
+            app_lock::emit_mail_event(
+                app_handle,
+                "badge-count-updated",
+                BadgeCount {
+                    count,
+                    visible,
+                    mode,
+                },
+            )
+            .map_err(|e| format!("Failed to emit badge count event: {}", e))?;
 
             log::debug!("Updated badge count: {}, visible: {}", count, visible);
         } else {
@@ -680,25 +1496,74 @@ impl NotificationService {
         Ok(())
     }
 
-    pub async fn notify_incoming_email(
+    /// Whether an incoming email notifies, by the selected folders and the
+    /// notification rules
+    pub async fn should_notify_for_email(
         &self,
         folder_id: Uuid,
         folder_type: FolderType,
         email: &Email,
-    ) -> Result<(), String> {
-        if self
+    ) -> Result<bool, String> {
+        let settings = self.get_notification_settings()?;
+        if !self.notifications_enabled(&settings) {
+            return Ok(false);
+        }
+
+        let folder_selected = self
             .should_notify_for_folder(folder_id, folder_type)
-            .await?
-        {
-            let payload = self.build_incoming_notification_payload(email).await;
+            .await?;
+        let suppression = NotificationRules::from_settings(&settings).check(
+            email.account_id,
+            folder_id,
+            email.priority,
+            folder_selected,
+            Local::now().time(),
+        );
 
+        match suppression {
+            None => Ok(true),
+            Some(Suppression::FolderNotSelected) => Ok(false),
+            Some(reason) => {
+                log::debug!("Not notifying for email {}: {:?}", email.id, reason);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Notifies about the new messages a sync brought into a folder. A single
+    /// message gets its own notification, several are summarized in one.
+    pub async fn notify_incoming_emails(
+        &self,
+        folder_id: Uuid,
+        folder_type: FolderType,
+        emails: &[Email],
+    ) -> Result<(), String> {
+        let mut notified: Vec<&Email> = Vec::new();
+        for email in emails {
+            if self
+                .should_notify_for_email(folder_id, folder_type, email)
+                .await?
+            {
+                notified.push(email);
+            }
+        }
+
+        let payload = match notified.as_slice() {
+            [] => None,
+            [email] => Some(self.build_incoming_notification_payload(email).await),
+            emails => Some(self.build_batch_notification_payload(emails)),
+        };
+
+        if let Some(payload) = payload {
             if !self.suppress_notifications {
                 self.show_notification_payload(&payload, "You have received a new email.")
                     .await?;
-                self.play_incoming_sound().await?;
+                if payload.play_sound {
+                    self.play_incoming_sound().await?;
+                }
             }
 
-            if self.can_dispatch_notifications_to_frontend() {
+            if self.can_dispatch_notifications_to_frontend() && !shows_actions_natively(&payload) {
                 self.emit_native_notification_event(&payload)?;
             }
         }
@@ -728,6 +1593,54 @@ impl NotificationService {
         Ok(())
     }
 
+    /// Notifies that a snoozed email is back in its folder
+    pub async fn notify_snoozed_email(&self, email: &Email) -> Result<(), String> {
+        let settings = self.get_notification_settings()?;
+        if !self.notifications_enabled(&settings) {
+            return Ok(());
+        }
+
+        let payload = self.build_snooze_notification_payload(email).await;
+
+        if !self.suppress_notifications {
+            self.show_notification_payload(&payload, "A snoozed email is back in your inbox.")
+                .await?;
+            self.play_reminder_sound().await?;
+        }
+
+        if self.can_dispatch_notifications_to_frontend() {
+            self.emit_native_notification_event(&payload)?;
+        }
+        Ok(())
+    }
+
+    /// Notifies about a contact's birthday or anniversary; clicking opens a prefilled composer
+    pub async fn notify_contact_date(
+        &self,
+        contact: &Contact,
+        kind: &str,
+        year: i32,
+        years: Option<i32>,
+    ) -> Result<(), String> {
+        let settings = self.get_notification_settings()?;
+        if !self.notifications_enabled(&settings) {
+            return Ok(());
+        }
+
+        let payload = self.build_contact_date_notification_payload(contact, kind, year, years);
+
+        if !self.suppress_notifications {
+            self.show_notification_payload(&payload, "A contact has a special day today.")
+                .await?;
+            self.play_reminder_sound().await?;
+        }
+
+        if self.can_dispatch_notifications_to_frontend() {
+            self.emit_native_notification_event(&payload)?;
+        }
+        Ok(())
+    }
+
     pub async fn notify_outgoing_email(&self) -> Result<(), String> {
         let settings = self.get_notification_settings()?;
         if self.notifications_enabled(&settings) {
@@ -742,3 +1655,229 @@ impl NotificationService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn badge_settings(
+        source: Option<&str>,
+        folders: Option<Vec<String>>,
+        view: Option<String>,
+    ) -> NotificationSettings {
+        NotificationSettings {
+            badge_source: source.map(str::to_string),
+            badge_folders: folders,
+            badge_view: view,
+            ..NotificationSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_badge_source_from_settings() {
+        let folder_id = Uuid::now_v7();
+        let view_id = Uuid::now_v7();
+
+        assert_eq!(
+            BadgeSource::from_settings(&badge_settings(None, Some(vec![]), None)),
+            Ok(BadgeSource::AllAccounts)
+        );
+        assert_eq!(
+            BadgeSource::from_settings(&badge_settings(
+                Some("folders"),
+                Some(vec![folder_id.to_string()]),
+                None
+            )),
+            Ok(BadgeSource::Folders(vec![folder_id]))
+        );
+        assert_eq!(
+            BadgeSource::from_settings(&badge_settings(
+                Some("all"),
+                Some(vec![folder_id.to_string()]),
+                None
+            )),
+            Ok(BadgeSource::AllAccounts)
+        );
+        assert_eq!(
+            BadgeSource::from_settings(&badge_settings(
+                Some("view"),
+                None,
+                Some(view_id.to_string())
+            )),
+            Ok(BadgeSource::View(view_id))
+        );
+        assert!(BadgeSource::from_settings(&badge_settings(Some("view"), None, None)).is_err());
+    }
+
+    fn time(value: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(value, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let night = QuietHours {
+            start: time("22:00"),
+            end: time("07:00"),
+        };
+        assert!(night.contains(time("23:30")));
+        assert!(night.contains(time("06:59")));
+        assert!(!night.contains(time("07:00")));
+        assert!(!night.contains(time("12:00")));
+
+        let lunch = QuietHours {
+            start: time("12:00"),
+            end: time("13:00"),
+        };
+        assert!(lunch.contains(time("12:30")));
+        assert!(!lunch.contains(time("13:30")));
+    }
+
+    #[test]
+    fn test_notification_rules() {
+        let account_id = Uuid::now_v7();
+        let disabled_account_id = Uuid::now_v7();
+        let inbox_id = Uuid::now_v7();
+        let urgent_id = Uuid::now_v7();
+        let newsletters_id = Uuid::now_v7();
+
+        let rules = NotificationRules::from_settings(&NotificationSettings {
+            disabled_accounts: Some(vec![disabled_account_id.to_string(), "invalid".into()]),
+            vip_only: Some(true),
+            quiet_hours_enabled: Some(true),
+            always_notify_folders: Some(vec![urgent_id.to_string()]),
+            silent_folders: Some(vec![newsletters_id.to_string()]),
+            ..NotificationSettings::default()
+        });
+        assert_eq!(rules.disabled_accounts.len(), 1);
+
+        let vip = priority::VIP_SENDER | priority::DIRECT_TO_ME;
+        let other = priority::REPLY_TO_MY_THREAD;
+        let day = time("10:00");
+        let night = time("23:00");
+        assert_eq!(rules.check(account_id, inbox_id, vip, true, day), None);
+        assert_eq!(
+            rules.check(account_id, inbox_id, other, true, day),
+            Some(Suppression::NotVip)
+        );
+        assert_eq!(
+            rules.check(account_id, inbox_id, vip, true, night),
+            Some(Suppression::QuietHours)
+        );
+        assert_eq!(
+            rules.check(account_id, inbox_id, vip, false, day),
+            Some(Suppression::FolderNotSelected)
+        );
+        assert_eq!(
+            rules.check(disabled_account_id, urgent_id, vip, true, day),
+            Some(Suppression::AccountDisabled)
+        );
+        assert_eq!(
+            rules.check(account_id, newsletters_id, vip, true, day),
+            Some(Suppression::FolderSilenced)
+        );
+        // Overridden folders notify for anyone, at any time
+        assert_eq!(rules.check(account_id, urgent_id, 0, false, night), None);
+
+        let defaults = NotificationRules::from_settings(&NotificationSettings::default());
+        assert_eq!(defaults.quiet_hours, None);
+        assert_eq!(defaults.check(account_id, inbox_id, 0, true, night), None);
+    }
+
+    #[test]
+    fn test_thread_notifications() {
+        let mut notifications = ThreadNotifications::default();
+        let start = Instant::now();
+
+        let first = notifications.register("thread-1", "Alice", start);
+        assert!(!first.is_coalesced());
+
+        notifications.register("thread-2", "Bob", start);
+        notifications.register("thread-1", "Alice", start + Duration::from_secs(10));
+        let third = notifications.register("thread-1", "Alice", start + Duration::from_secs(20));
+        assert_eq!(third.count, 3);
+        assert_eq!(third.title(), "3 new messages from Alice");
+
+        let fourth = notifications.register("thread-1", "Bob", start + Duration::from_secs(30));
+        assert_eq!(fourth.title(), "4 new messages from Alice and Bob");
+
+        // A thread that was quiet for longer than the window starts over
+        let later = start + Duration::from_secs(30) + THREAD_NOTIFICATION_WINDOW;
+        assert_eq!(notifications.register("thread-1", "Carol", later).count, 1);
+    }
+
+    #[test]
+    fn test_batch_summary() {
+        let message = |sender: &str, subject: &str| (sender.to_string(), subject.to_string());
+
+        let (title, body) = batch_summary(&[
+            message("Alice", "Lunch"),
+            message("Bob", "Report"),
+            message("Alice", "Re: Lunch"),
+            message("Carol", "Invoice"),
+            message("Dave", "Hello"),
+        ]);
+        assert_eq!(title, "5 new messages from Alice and 3 others");
+        assert_eq!(
+            body,
+            "Alice: Lunch\nBob: Report\nAlice: Re: Lunch\nand 2 more"
+        );
+
+        let (title, body) = batch_summary(&[message("Alice", "Lunch"), message("Alice", "Dinner")]);
+        assert_eq!(title, "2 new messages from Alice");
+        assert_eq!(body, "Alice: Lunch\nAlice: Dinner");
+    }
+
+    #[test]
+    fn test_notification_actions() {
+        for action in [
+            NotificationAction::Archive,
+            NotificationAction::MarkRead,
+            NotificationAction::Reply,
+        ] {
+            assert_eq!(NotificationAction::from_id(action.id()), Some(action));
+            assert_eq!(NotificationAction::from_label(action.label()), Some(action));
+            assert_eq!(
+                serde_json::to_value(action).unwrap(),
+                serde_json::json!(action.id())
+            );
+        }
+        assert_eq!(NotificationAction::from_id("default"), None);
+    }
+
+    #[test]
+    fn test_redacted_payload() {
+        let payload = NotificationEventPayload {
+            kind: "incoming".to_string(),
+            title: "Alice".to_string(),
+            body: Some("Quarterly numbers".to_string()),
+            email: Some(NotificationEmailPreview {
+                id: Uuid::now_v7().to_string(),
+                account_id: Uuid::now_v7().to_string(),
+                folder_id: Uuid::now_v7().to_string(),
+                conversation_id: None,
+                sender_name: Some("Alice".to_string()),
+                sender_address: Some("alice@example.com".to_string()),
+                subject: Some("Quarterly numbers".to_string()),
+                snippet: Some("Please find attached".to_string()),
+                avatar_url: None,
+                remind_at: None,
+                navigation_target: None,
+                important: false,
+            }),
+            play_sound: true,
+            suppress_during_bootstrap: false,
+            tag: Some("tag".to_string()),
+            deep_link: Some("ravn://email/1".to_string()),
+            actions: Vec::new(),
+            email_ids: vec![Uuid::now_v7().to_string()],
+        };
+
+        let redacted = payload.redacted();
+        assert_eq!(redacted.title, "New mail");
+        assert!(redacted.body.is_none());
+        assert!(redacted.email.is_none());
+        assert!(redacted.deep_link.is_none());
+        assert!(redacted.email_ids.is_empty());
+        assert!(redacted.play_sound);
+    }
+}
@@ -0,0 +1,397 @@
+//! Discovery of contacts' OpenPGP public keys through the Web Key Directory
+//! of their domain and keys.openpgp.org, and the little packet parsing needed
+//! to fingerprint a key and check that it belongs to the address.
+use base64::{engine::general_purpose, Engine as _};
+use std::time::Duration;
+
+use crate::database::models::contact_security::PgpKeySource;
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+const KEYSERVER_URL: &str = "https://keys.openpgp.org/vks/v1/by-email/";
+const ZBASE32_ALPHABET: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+const ARMOR_BEGIN: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----";
+const ARMOR_END: &str = "-----END PGP PUBLIC KEY BLOCK-----";
+
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_USER_ID: u8 = 13;
+
+/// A public key found for an address
+#[derive(Debug, Clone)]
+pub struct DiscoveredKey {
+    /// Uppercase hex
+    pub fingerprint: String,
+    pub armored: String,
+    pub source: PgpKeySource,
+}
+
+/// One transferable public key out of a keyring
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKey {
+    pub fingerprint: String,
+    pub user_ids: Vec<String>,
+    /// The key's packets, as found in the keyring
+    pub data: Vec<u8>,
+}
+
+impl PublicKey {
+    /// Whether one of the key's user IDs is the address
+    pub fn has_address(&self, email: &str) -> bool {
+        let email = email.trim().to_lowercase();
+        self.user_ids.iter().any(|user_id| {
+            let user_id = user_id.to_lowercase();
+            let address = match (user_id.rfind('<'), user_id.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &user_id[start + 1..end],
+                _ => user_id.trim(),
+            };
+            address == email
+        })
+    }
+}
+
+/// Looks the address up in its domain's Web Key Directory, then on
+/// keys.openpgp.org. Unreachable sources are skipped.
+pub async fn discover(email: &str) -> Result<Option<DiscoveredKey>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(LOOKUP_TIMEOUT)
+        .user_agent(concat!("Ravn/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    for url in wkd_urls(email)? {
+        match fetch(&client, &url).await {
+            Ok(Some(data)) => {
+                if let Some(key) = select_key(&data, email)? {
+                    return Ok(Some(discovered(key, PgpKeySource::Wkd)));
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::debug!("[PGP] WKD lookup {} failed: {}", url, e),
+        }
+    }
+
+    let mut url = url::Url::parse(KEYSERVER_URL).map_err(|e| e.to_string())?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid keyserver URL".to_string())?
+        .pop_if_empty()
+        .push(&email.trim().to_lowercase());
+
+    match fetch(&client, url.as_str()).await {
+        Ok(Some(data)) => {
+            let data = dearmor(&String::from_utf8_lossy(&data))?;
+            Ok(select_key(&data, email)?.map(|key| discovered(key, PgpKeySource::Keyserver)))
+        }
+        Ok(None) => Ok(None),
+        Err(e) => {
+            log::debug!("[PGP] Keyserver lookup for {} failed: {}", email, e);
+            Ok(None)
+        }
+    }
+}
+
+fn discovered(key: PublicKey, source: PgpKeySource) -> DiscoveredKey {
+    DiscoveredKey {
+        fingerprint: key.fingerprint,
+        armored: armor(&key.data),
+        source,
+    }
+}
+
+/// The body of a successful response, or `None` when there is no key
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<Option<Vec<u8>>, String> {
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    Ok((!body.is_empty()).then(|| body.to_vec()))
+}
+
+/// The first key in the keyring that carries the address
+fn select_key(data: &[u8], email: &str) -> Result<Option<PublicKey>, String> {
+    Ok(parse_keys(data)?
+        .into_iter()
+        .find(|key| key.has_address(email)))
+}
+
+/// Advanced and direct WKD URLs of an address, in lookup order
+pub fn wkd_urls(email: &str) -> Result<[String; 2], String> {
+    let (local, domain) = email
+        .trim()
+        .rsplit_once('@')
+        .filter(|(local, domain)| !local.is_empty() && !domain.is_empty())
+        .ok_or_else(|| format!("Invalid email address: {}", email))?;
+    let domain = domain.to_lowercase();
+    let hash = wkd_hash(local);
+    let local: String = url::form_urlencoded::byte_serialize(local.as_bytes()).collect();
+
+    Ok([
+        format!("https://openpgpkey.{domain}/.well-known/openpgpkey/{domain}/hu/{hash}?l={local}"),
+        format!("https://{domain}/.well-known/openpgpkey/hu/{hash}?l={local}"),
+    ])
+}
+
+/// z-base-32 of the SHA-1 of the lowercased local part
+fn wkd_hash(local: &str) -> String {
+    zbase32(&openssl::sha::sha1(local.to_lowercase().as_bytes()))
+}
+
+fn zbase32(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ZBASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ZBASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    encoded
+}
+
+/// Splits a binary keyring into its keys
+pub fn parse_keys(data: &[u8]) -> Result<Vec<PublicKey>, String> {
+    let mut keys: Vec<PublicKey> = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let (tag, header_len, body_len) = packet_header(&data[offset..])?;
+        let body = &data[offset + header_len..offset + header_len + body_len];
+        let packet = &data[offset..offset + header_len + body_len];
+
+        match tag {
+            TAG_PUBLIC_KEY => keys.push(PublicKey {
+                fingerprint: fingerprint(body)?,
+                user_ids: Vec::new(),
+                data: packet.to_vec(),
+            }),
+            _ => {
+                let key = keys
+                    .last_mut()
+                    .ok_or_else(|| "Keyring does not start with a public key".to_string())?;
+                if tag == TAG_USER_ID {
+                    key.user_ids
+                        .push(String::from_utf8_lossy(body).into_owned());
+                }
+                key.data.extend_from_slice(packet);
+            }
+        }
+
+        offset += header_len + body_len;
+    }
+
+    Ok(keys)
+}
+
+/// Tag, header length and body length of the packet at the start of `data`
+fn packet_header(data: &[u8]) -> Result<(u8, usize, usize), String> {
+    let truncated = || "Truncated OpenPGP packet".to_string();
+    let first = *data.first().ok_or_else(truncated)?;
+    if first & 0x80 == 0 {
+        return Err("Not an OpenPGP packet".to_string());
+    }
+
+    let (tag, header_len, body_len) = if first & 0x40 != 0 {
+        let tag = first & 0x3f;
+        match *data.get(1).ok_or_else(truncated)? {
+            len @ 0..=191 => (tag, 2, len as usize),
+            len @ 192..=223 => {
+                let second = *data.get(2).ok_or_else(truncated)? as usize;
+                (tag, 3, ((len as usize - 192) << 8) + second + 192)
+            }
+            255 => {
+                let len = data.get(2..6).ok_or_else(truncated)?;
+                (
+                    tag,
+                    6,
+                    u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize,
+                )
+            }
+            _ => return Err("Partial body lengths are not allowed in keys".to_string()),
+        }
+    } else {
+        let tag = (first >> 2) & 0x0f;
+        match first & 0x03 {
+            0 => (tag, 2, *data.get(1).ok_or_else(truncated)? as usize),
+            1 => {
+                let len = data.get(1..3).ok_or_else(truncated)?;
+                (tag, 3, u16::from_be_bytes([len[0], len[1]]) as usize)
+            }
+            2 => {
+                let len = data.get(1..5).ok_or_else(truncated)?;
+                (
+                    tag,
+                    5,
+                    u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize,
+                )
+            }
+            _ => (tag, 1, data.len() - 1),
+        }
+    };
+
+    if header_len + body_len > data.len() {
+        return Err(truncated());
+    }
+    Ok((tag, header_len, body_len))
+}
+
+/// Fingerprint of a public key packet body: SHA-1 for v4 keys, SHA-256 for v5 and v6
+fn fingerprint(body: &[u8]) -> Result<String, String> {
+    let digest = match body.first() {
+        Some(4) => {
+            let len = u16::try_from(body.len()).map_err(|_| "Key packet too long".to_string())?;
+            let mut hasher = openssl::sha::Sha1::new();
+            hasher.update(&[0x99]);
+            hasher.update(&len.to_be_bytes());
+            hasher.update(body);
+            hasher.finish().to_vec()
+        }
+        Some(version @ (5 | 6)) => {
+            let prefix = if *version == 5 { 0x9a } else { 0x9b };
+            let mut hasher = openssl::sha::Sha256::new();
+            hasher.update(&[prefix]);
+            hasher.update(&(body.len() as u32).to_be_bytes());
+            hasher.update(body);
+            hasher.finish().to_vec()
+        }
+        Some(version) => return Err(format!("Unsupported key version {}", version)),
+        None => return Err("Empty key packet".to_string()),
+    };
+
+    Ok(digest.iter().map(|byte| format!("{:02X}", byte)).collect())
+}
+
+/// Decodes an ASCII-armored key block, checking its CRC if present
+pub fn dearmor(armored: &str) -> Result<Vec<u8>, String> {
+    let block = armored
+        .split_once(ARMOR_BEGIN)
+        .and_then(|(_, rest)| rest.split_once(ARMOR_END))
+        .map(|(block, _)| block)
+        .ok_or_else(|| "No PGP public key block found".to_string())?;
+
+    let mut body = String::new();
+    let mut checksum = None;
+    for line in block.lines().map(str::trim) {
+        if line.contains(':') {
+            // Armor header, e.g. "Comment: ..."
+            continue;
+        }
+        match line.strip_prefix('=') {
+            Some(crc) if crc.len() == 4 => checksum = Some(crc.to_string()),
+            _ => body.push_str(line),
+        }
+    }
+
+    let data = general_purpose::STANDARD
+        .decode(body)
+        .map_err(|e| format!("Invalid key block: {}", e))?;
+
+    if let Some(checksum) = checksum {
+        if checksum != general_purpose::STANDARD.encode(&crc24(&data).to_be_bytes()[1..]) {
+            return Err("Key block checksum mismatch".to_string());
+        }
+    }
+
+    Ok(data)
+}
+
+pub fn armor(data: &[u8]) -> String {
+    let encoded = general_purpose::STANDARD.encode(data);
+    let mut armored = format!("{}\n\n", ARMOR_BEGIN);
+    for line in encoded.as_bytes().chunks(64) {
+        armored.push_str(&String::from_utf8_lossy(line));
+        armored.push('\n');
+    }
+    armored.push('=');
+    armored.push_str(&general_purpose::STANDARD.encode(&crc24(data).to_be_bytes()[1..]));
+    armored.push('\n');
+    armored.push_str(ARMOR_END);
+    armored.push('\n');
+    armored
+}
+
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xb704ce;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A v4 key packet and a user ID packet, in new packet format
+    fn keyring(user_id: &str) -> Vec<u8> {
+        let key_body = [4, 0x5f, 0x00, 0x00, 0x00, 22, 0x00, 0x08, 0xab];
+        let mut data = vec![0xc0 | TAG_PUBLIC_KEY, key_body.len() as u8];
+        data.extend_from_slice(&key_body);
+        data.extend_from_slice(&[0xc0 | TAG_USER_ID, user_id.len() as u8]);
+        data.extend_from_slice(user_id.as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_wkd_urls() {
+        // Example from the Web Key Directory draft
+        let [advanced, direct] = wkd_urls("Joe.Doe@Example.ORG").unwrap();
+        assert_eq!(
+            advanced,
+            "https://openpgpkey.example.org/.well-known/openpgpkey/example.org/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe"
+        );
+        assert_eq!(
+            direct,
+            "https://example.org/.well-known/openpgpkey/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe"
+        );
+        assert!(wkd_urls("not-an-address").is_err());
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let data = keyring("Bob <Bob@Example.com>");
+        let keys = parse_keys(&data).unwrap();
+
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].user_ids, vec!["Bob <Bob@Example.com>".to_string()]);
+        assert_eq!(keys[0].data, data);
+        assert_eq!(
+            keys[0].fingerprint,
+            "1D8F837F54B1B0DE37131190DC89D3A6449A176C"
+        );
+        assert!(keys[0].has_address("bob@example.com"));
+        assert!(!keys[0].has_address("eve@example.com"));
+
+        assert!(select_key(&data, "eve@example.com").unwrap().is_none());
+        assert!(parse_keys(&data[..4]).is_err());
+    }
+
+    #[test]
+    fn test_armor_round_trip() {
+        let data = keyring("alice@example.com");
+        let armored = armor(&data);
+
+        assert!(armored.starts_with(ARMOR_BEGIN));
+        assert!(armored.contains("\n=gi2z\n"));
+        assert_eq!(dearmor(&armored).unwrap(), data);
+
+        let tampered = armored.replace("=gi2z", "=AAAA");
+        assert!(dearmor(&tampered).is_err());
+    }
+}
@@ -0,0 +1,297 @@
+/// Recipient validation used by the composer before sending
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Well-known mail domains used to detect typos such as `gamil.com`
+const COMMON_DOMAINS: &[&str] = &[
+    "gmail.com",
+    "googlemail.com",
+    "yahoo.com",
+    "yahoo.de",
+    "hotmail.com",
+    "hotmail.de",
+    "outlook.com",
+    "live.com",
+    "msn.com",
+    "icloud.com",
+    "me.com",
+    "mac.com",
+    "aol.com",
+    "gmx.de",
+    "gmx.net",
+    "gmx.com",
+    "web.de",
+    "t-online.de",
+    "proton.me",
+    "protonmail.com",
+    "fastmail.com",
+    "zoho.com",
+    "yandex.com",
+    "mail.com",
+];
+
+/// Maximum edit distance at which a domain is considered a typo of a known domain
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+pub const DOH_ENDPOINT: &str = "https://cloudflare-dns.com/dns-query";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipientStatus {
+    Valid,
+    InvalidSyntax,
+    NoMailServer,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientValidation {
+    pub address: String,
+    pub status: RecipientStatus,
+    pub suggestion: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer")]
+    answer: Option<Vec<DohAnswer>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+}
+
+pub struct RecipientValidator {
+    http_client: Client,
+}
+
+impl RecipientValidator {
+    pub fn new() -> Self {
+        Self {
+            http_client: Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Validates a list of recipient addresses
+    /// When `check_mx` is set, domains are additionally resolved for MX records
+    pub async fn validate(&self, addresses: &[String], check_mx: bool) -> Vec<RecipientValidation> {
+        let mut results = Vec::with_capacity(addresses.len());
+        let mut mx_cache: std::collections::HashMap<String, Option<bool>> =
+            std::collections::HashMap::new();
+
+        for address in addresses {
+            let mut validation = validate_syntax(address);
+
+            if validation.status == RecipientStatus::Valid && check_mx {
+                let domain = domain_of(address.trim()).unwrap_or_default();
+                let has_mx = match mx_cache.get(&domain) {
+                    Some(cached) => *cached,
+                    None => {
+                        let result = self.lookup_mx(&domain).await;
+                        mx_cache.insert(domain.clone(), result);
+                        result
+                    }
+                };
+
+                match has_mx {
+                    Some(true) => {}
+                    Some(false) => {
+                        validation.status = RecipientStatus::NoMailServer;
+                        validation.message =
+                            Some(format!("The domain {} does not accept email", domain));
+                    }
+                    None => {
+                        validation.status = RecipientStatus::Unknown;
+                        validation.message =
+                            Some(format!("Could not verify mail server for {}", domain));
+                    }
+                }
+            }
+
+            results.push(validation);
+        }
+
+        results
+    }
+
+    /// Returns Some(true) when the domain has MX records (or an implicit A record),
+    /// Some(false) when it definitely has none, and None when the lookup failed
+    async fn lookup_mx(&self, domain: &str) -> Option<bool> {
+        match self.query_doh(domain, "MX", 15).await {
+            Some(true) => Some(true),
+            Some(false) => {
+                // RFC 5321: fall back to the A record when no MX is published
+                self.query_doh(domain, "A", 1).await
+            }
+            None => None,
+        }
+    }
+
+    async fn query_doh(&self, domain: &str, record: &str, record_type: u16) -> Option<bool> {
+        let response = self
+            .http_client
+            .get(DOH_ENDPOINT)
+            .query(&[("name", domain), ("type", record)])
+            .header("accept", "application/dns-json")
+            .send()
+            .await
+            .map_err(|e| log::debug!("MX lookup for {} failed: {}", domain, e))
+            .ok()?;
+
+        if !response.status().is_success() {
+            log::debug!(
+                "MX lookup for {} returned HTTP {}",
+                domain,
+                response.status()
+            );
+            return None;
+        }
+
+        let body: DohResponse = response
+            .json()
+            .await
+            .map_err(|e| log::debug!("Failed to parse DNS response for {}: {}", domain, e))
+            .ok()?;
+
+        match body.status {
+            // NOERROR
+            0 => Some(
+                body.answer
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|a| a.record_type == record_type),
+            ),
+            // NXDOMAIN
+            3 => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl Default for RecipientValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn domain_of(address: &str) -> Option<String> {
+    address
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.to_lowercase())
+}
+
+/// Performs a syntax check and a typo lookup against well-known domains
+pub fn validate_syntax(address: &str) -> RecipientValidation {
+    let trimmed = address.trim();
+
+    let invalid = |message: &str| RecipientValidation {
+        address: address.to_string(),
+        status: RecipientStatus::InvalidSyntax,
+        suggestion: None,
+        message: Some(message.to_string()),
+    };
+
+    if trimmed.parse::<lettre::Address>().is_err() {
+        return invalid("Not a valid email address");
+    }
+
+    let Some((local, domain)) = trimmed.rsplit_once('@') else {
+        return invalid("Missing @ sign");
+    };
+
+    if !domain.contains('.') || domain.ends_with('.') || domain.starts_with('.') {
+        return invalid("Domain is incomplete");
+    }
+
+    let suggestion = suggest_domain(domain).map(|d| format!("{}@{}", local, d));
+    let message = suggestion.as_ref().map(|s| format!("Did you mean {}?", s));
+
+    RecipientValidation {
+        address: address.to_string(),
+        status: RecipientStatus::Valid,
+        suggestion,
+        message,
+    }
+}
+
+/// Suggests a well-known domain when `domain` looks like a typo of one
+pub fn suggest_domain(domain: &str) -> Option<String> {
+    let domain = domain.to_lowercase();
+    if COMMON_DOMAINS.contains(&domain.as_str()) {
+        return None;
+    }
+
+    COMMON_DOMAINS
+        .iter()
+        .map(|candidate| (candidate, levenshtein(&domain, candidate)))
+        .filter(|(_, distance)| *distance > 0 && *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate.to_string())
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_address_without_suggestion() {
+        let result = validate_syntax("someone@gmail.com");
+        assert_eq!(result.status, RecipientStatus::Valid);
+        assert!(result.suggestion.is_none());
+    }
+
+    #[test]
+    fn test_invalid_syntax() {
+        assert_eq!(
+            validate_syntax("not-an-address").status,
+            RecipientStatus::InvalidSyntax
+        );
+        assert_eq!(
+            validate_syntax("user@localhost").status,
+            RecipientStatus::InvalidSyntax
+        );
+    }
+
+    #[test]
+    fn test_typo_suggestion() {
+        let result = validate_syntax("someone@gamil.com");
+        assert_eq!(result.status, RecipientStatus::Valid);
+        assert_eq!(result.suggestion.as_deref(), Some("someone@gmail.com"));
+        assert_eq!(
+            suggest_domain("hotmial.com").as_deref(),
+            Some("hotmail.com")
+        );
+    }
+
+    #[test]
+    fn test_unrelated_domain_has_no_suggestion() {
+        assert!(suggest_domain("ravnmail.com").is_none());
+    }
+}
@@ -0,0 +1,453 @@
+//! Building replies and forwards from the original message: recipients,
+//! subject, threading headers and the quoted original.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use super::email_renderer::html_to_plain_text;
+use super::html_sanitizer::{self, BlockingOptions};
+use crate::database::models::email::{Email, EmailAddress};
+use crate::sync::threading;
+
+/// `References` headers beyond this many ids keep the root and the most
+/// recent ones, as some servers reject very long headers
+const MAX_REFERENCES: usize = 20;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyMode {
+    Reply,
+    ReplyAll,
+    Forward,
+}
+
+/// The parts of a reply or forward derived from the original message
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplyParts {
+    pub to: Vec<EmailAddress>,
+    pub cc: Vec<EmailAddress>,
+    pub subject: String,
+    /// `None` for forwards, which start a new branch of the thread
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+    /// The original body, stripped of trackers, for editors that show their
+    /// own attribution
+    pub quoted_body: String,
+    /// Attribution line and quoted original, or the forwarded message header
+    /// and original
+    pub quote_html: String,
+    pub quote_plain: String,
+}
+
+/// Builds a reply or forward to `email`. `own_addresses` are the addresses of
+/// the account and its identities, which are left out of the recipients.
+pub fn build(email: &Email, mode: ReplyMode, own_addresses: &[String]) -> ReplyParts {
+    let (to, cc) = recipients(email, mode, own_addresses);
+    let (in_reply_to, references) = threading_headers(email, mode);
+
+    ReplyParts {
+        to,
+        cc,
+        subject: subject(email.subject.as_deref().unwrap_or_default(), mode),
+        in_reply_to,
+        references,
+        quoted_body: quoted_body(email),
+        quote_html: quote_html(email, mode),
+        quote_plain: quote_plain(email, mode),
+    }
+}
+
+pub fn subject(original: &str, mode: ReplyMode) -> String {
+    let original = original.trim();
+    let lower = original.to_lowercase();
+
+    let (prefix, existing): (&str, &[&str]) = match mode {
+        ReplyMode::Reply | ReplyMode::ReplyAll => ("Re: ", &["re:"]),
+        ReplyMode::Forward => ("Fwd: ", &["fwd:", "fw:"]),
+    };
+    if existing.iter().any(|existing| lower.starts_with(existing)) {
+        original.to_string()
+    } else {
+        format!("{}{}", prefix, original)
+    }
+}
+
+/// The `To` and `Cc` recipients of the reply. Replies to a message sent from
+/// the account go to its original recipients.
+pub fn recipients(
+    email: &Email,
+    mode: ReplyMode,
+    own_addresses: &[String],
+) -> (Vec<EmailAddress>, Vec<EmailAddress>) {
+    let is_own = |address: &EmailAddress| {
+        own_addresses
+            .iter()
+            .any(|own| own.eq_ignore_ascii_case(&address.address))
+    };
+    let sender = email
+        .reply_to
+        .as_ref()
+        .map(|reply_to| reply_to.0.clone())
+        .unwrap_or_else(|| email.from.0.clone());
+    let sent_by_us = is_own(&email.from.0);
+
+    let mut to: Vec<EmailAddress> = Vec::new();
+    let mut cc: Vec<EmailAddress> = Vec::new();
+    match mode {
+        ReplyMode::Forward => {}
+        ReplyMode::Reply if sent_by_us => {
+            push_unique(&mut to, email.to.0.iter().filter(|a| !is_own(a)).cloned())
+        }
+        ReplyMode::Reply => push_unique(&mut to, [sender]),
+        ReplyMode::ReplyAll => {
+            if !sent_by_us {
+                push_unique(&mut to, [sender]);
+            }
+            push_unique(&mut to, email.to.0.iter().filter(|a| !is_own(a)).cloned());
+
+            let cc_candidates: Vec<EmailAddress> = email
+                .cc
+                .0
+                .iter()
+                .filter(|address| !is_own(address) && !contains(&to, address))
+                .cloned()
+                .collect();
+            push_unique(&mut cc, cc_candidates);
+        }
+    }
+
+    // A message the account sent to itself is answered to itself
+    if to.is_empty() && mode != ReplyMode::Forward {
+        to.push(email.from.0.clone());
+    }
+
+    (to, cc)
+}
+
+/// `In-Reply-To` and `References` for a reply or forward to `email`
+pub fn threading_headers(email: &Email, mode: ReplyMode) -> (Option<String>, Option<String>) {
+    let own_id = threading::normalize_message_id(&email.message_id);
+    if own_id.is_empty() {
+        return (None, None);
+    }
+
+    let headers: Option<serde_json::Value> = email
+        .headers
+        .as_deref()
+        .and_then(|headers| serde_json::from_str(headers).ok());
+    let mut ids = threading::ancestors(&own_id, headers.as_ref());
+    ids.push(own_id.clone());
+    if ids.len() > MAX_REFERENCES {
+        ids.drain(1..ids.len() - (MAX_REFERENCES - 1));
+    }
+
+    let references = ids
+        .iter()
+        .map(|id| format!("<{}>", id))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let in_reply_to = (mode != ReplyMode::Forward).then(|| format!("<{}>", own_id));
+
+    (in_reply_to, Some(references))
+}
+
+/// The original body as HTML
+pub fn quoted_body(email: &Email) -> String {
+    match email
+        .body_html
+        .as_deref()
+        .filter(|html| !html.trim().is_empty())
+    {
+        // Tracking pixels would report every time the reply is opened
+        Some(html) => {
+            html_sanitizer::sanitize(
+                html,
+                BlockingOptions {
+                    block_trackers: true,
+                    block_images: false,
+                },
+            )
+            .html
+        }
+        None => plain_to_html(email.body_plain.as_deref().unwrap_or_default()),
+    }
+}
+
+pub fn quote_html(email: &Email, mode: ReplyMode) -> String {
+    let body = quoted_body(email);
+
+    match mode {
+        ReplyMode::Reply | ReplyMode::ReplyAll => format!(
+            "<p>{}</p><blockquote type=\"cite\">{}</blockquote>",
+            escape_html(&attribution(email)),
+            body
+        ),
+        ReplyMode::Forward => {
+            let header = forward_header(email)
+                .iter()
+                .map(|line| escape_html(line))
+                .collect::<Vec<_>>()
+                .join("<br>");
+            format!("<p>{}</p>{}", header, body)
+        }
+    }
+}
+
+pub fn quote_plain(email: &Email, mode: ReplyMode) -> String {
+    let body = match email
+        .body_plain
+        .as_deref()
+        .filter(|plain| !plain.trim().is_empty())
+    {
+        Some(plain) => plain.to_string(),
+        None => html_to_plain_text(email.body_html.as_deref().unwrap_or_default()),
+    };
+
+    match mode {
+        ReplyMode::Reply | ReplyMode::ReplyAll => {
+            let quoted = body
+                .trim_end()
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        ">".to_string()
+                    } else {
+                        format!("> {}", line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{}\n{}\n", attribution(email), quoted)
+        }
+        ReplyMode::Forward => format!(
+            "{}\n\n{}\n",
+            forward_header(email).join("\n"),
+            body.trim_end()
+        ),
+    }
+}
+
+/// "On <date>, <sender> wrote:"
+fn attribution(email: &Email) -> String {
+    format!(
+        "On {}, {} wrote:",
+        format_date(email),
+        format_address(&email.from.0)
+    )
+}
+
+fn forward_header(email: &Email) -> Vec<String> {
+    let mut lines = vec![
+        "---------- Forwarded message ---------".to_string(),
+        format!("From: {}", format_address(&email.from.0)),
+        format!("Date: {}", format_date(email)),
+        format!("Subject: {}", email.subject.as_deref().unwrap_or_default()),
+        format!("To: {}", format_addresses(&email.to.0)),
+    ];
+    if !email.cc.0.is_empty() {
+        lines.push(format!("Cc: {}", format_addresses(&email.cc.0)));
+    }
+    lines
+}
+
+fn format_date(email: &Email) -> String {
+    email
+        .sent_at
+        .unwrap_or(email.received_at)
+        .with_timezone(&Local)
+        .format("%a, %b %-d, %Y at %H:%M")
+        .to_string()
+}
+
+fn format_address(address: &EmailAddress) -> String {
+    match address.name.as_deref().filter(|name| !name.is_empty()) {
+        Some(name) => format!("{} <{}>", name, address.address),
+        None => address.address.clone(),
+    }
+}
+
+fn format_addresses(addresses: &[EmailAddress]) -> String {
+    addresses
+        .iter()
+        .map(format_address)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn contains(addresses: &[EmailAddress], address: &EmailAddress) -> bool {
+    addresses
+        .iter()
+        .any(|existing| existing.address.eq_ignore_ascii_case(&address.address))
+}
+
+fn push_unique(addresses: &mut Vec<EmailAddress>, new: impl IntoIterator<Item = EmailAddress>) {
+    for address in new {
+        if !contains(addresses, &address) {
+            addresses.push(address);
+        }
+    }
+}
+
+fn plain_to_html(text: &str) -> String {
+    escape_html(text).replace('\n', "<br>")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::types::Json;
+    use uuid::Uuid;
+
+    fn address(address: &str) -> EmailAddress {
+        EmailAddress {
+            address: address.to_string(),
+            name: None,
+        }
+    }
+
+    fn email() -> Email {
+        Email {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            folder_id: Uuid::now_v7(),
+            message_id: "<c@example.com>".to_string(),
+            conversation_id: None,
+            remote_id: None,
+            from: Json(address("bob@example.com")),
+            to: Json(vec![address("me@example.com"), address("carol@example.com")]),
+            cc: Json(vec![address("dave@example.com"), address("Carol@example.com")]),
+            bcc: Json(Vec::new()),
+            reply_to: None,
+            subject: Some("Plans".to_string()),
+            snippet: None,
+            body_plain: Some("Line one\n\nLine two".to_string()),
+            body_html: None,
+            other_mails: None,
+            category: None,
+            ai_cache: None,
+            received_at: Utc::now(),
+            sent_at: None,
+            scheduled_send_at: None,
+            remind_at: None,
+            snoozed_until: None,
+            size: 0,
+            priority: 0,
+            risk_score: 0,
+            risk_reasons: Json(Vec::new()),
+            headers: Some(
+                r#"{"References": "<a@example.com> <b@example.com>", "In-Reply-To": "<b@example.com>"}"#
+                    .to_string(),
+            ),
+            is_read: true,
+            is_flagged: false,
+            is_draft: false,
+            has_attachments: false,
+            is_deleted: false,
+            sync_status: "synced".to_string(),
+            tracking_blocked: true,
+            images_blocked: true,
+            body_fetch_attempts: 0,
+            last_body_fetch_attempt: None,
+            change_key: None,
+            last_modified_at: None,
+            deleted_at: None,
+            deletion_source: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn addresses(addresses: &[EmailAddress]) -> Vec<&str> {
+        addresses.iter().map(|a| a.address.as_str()).collect()
+    }
+
+    #[test]
+    fn test_subject() {
+        assert_eq!(subject("Plans", ReplyMode::Reply), "Re: Plans");
+        assert_eq!(subject("RE: Plans", ReplyMode::ReplyAll), "RE: Plans");
+        assert_eq!(subject("Re: Plans", ReplyMode::Forward), "Fwd: Re: Plans");
+        assert_eq!(subject("FW: Plans", ReplyMode::Forward), "FW: Plans");
+    }
+
+    #[test]
+    fn test_recipients() {
+        let own = vec!["me@example.com".to_string()];
+        let original = email();
+
+        let (to, cc) = recipients(&original, ReplyMode::Reply, &own);
+        assert_eq!(addresses(&to), vec!["bob@example.com"]);
+        assert!(cc.is_empty());
+
+        let (to, cc) = recipients(&original, ReplyMode::ReplyAll, &own);
+        assert_eq!(addresses(&to), vec!["bob@example.com", "carol@example.com"]);
+        assert_eq!(addresses(&cc), vec!["dave@example.com"]);
+
+        let (to, cc) = recipients(&original, ReplyMode::Forward, &own);
+        assert!(to.is_empty() && cc.is_empty());
+    }
+
+    #[test]
+    fn test_reply_to_own_message_goes_to_recipients() {
+        let mut original = email();
+        original.from = Json(address("me@example.com"));
+        original.reply_to = Some(Json(address("list@example.com")));
+
+        let (to, _) = recipients(&original, ReplyMode::Reply, &["ME@example.com".to_string()]);
+        assert_eq!(addresses(&to), vec!["carol@example.com"]);
+    }
+
+    #[test]
+    fn test_threading_headers() {
+        let original = email();
+
+        let (in_reply_to, references) = threading_headers(&original, ReplyMode::Reply);
+        assert_eq!(in_reply_to.as_deref(), Some("<c@example.com>"));
+        assert_eq!(
+            references.as_deref(),
+            Some("<a@example.com> <b@example.com> <c@example.com>")
+        );
+
+        let (in_reply_to, references) = threading_headers(&original, ReplyMode::Forward);
+        assert!(in_reply_to.is_none());
+        assert!(references.is_some());
+    }
+
+    #[test]
+    fn test_threading_headers_trims_long_references() {
+        let mut original = email();
+        let ids: Vec<String> = (0..30).map(|i| format!("<{}@example.com>", i)).collect();
+        original.headers = Some(serde_json::json!({ "References": ids.join(" ") }).to_string());
+
+        let (_, references) = threading_headers(&original, ReplyMode::Reply);
+        let references = threading::message_ids(&references.unwrap());
+        assert_eq!(references.len(), MAX_REFERENCES);
+        assert_eq!(references[0], "0@example.com");
+        assert_eq!(references[MAX_REFERENCES - 1], "c@example.com");
+    }
+
+    #[test]
+    fn test_quotes() {
+        let original = email();
+
+        let plain = quote_plain(&original, ReplyMode::Reply);
+        assert!(plain.starts_with("On "));
+        assert!(plain.ends_with("bob@example.com wrote:\n> Line one\n>\n> Line two\n"));
+
+        let html = quote_html(&original, ReplyMode::Reply);
+        assert!(html.ends_with("<blockquote type=\"cite\">Line one<br><br>Line two</blockquote>"));
+
+        let forward = quote_plain(&original, ReplyMode::Forward);
+        assert!(
+            forward.starts_with("---------- Forwarded message ---------\nFrom: bob@example.com\n")
+        );
+        assert!(forward.contains("\nCc: dave@example.com, Carol@example.com\n"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Picking and inserting account signatures into outgoing HTML bodies.
+//!
+//! The signature block uses the composer's own markup
+//! (`<div data-type="email-signature">`), so a body that already carries a
+//! signature, whether inserted by the composer or by an earlier `save_draft`,
+//! is left alone.
+
+use crate::database::models::signature::Signature;
+
+use super::email_renderer::html_to_plain_text;
+
+const SIGNATURE_MARKER: &str = r#"data-type="email-signature""#;
+const QUOTED_CONTENT_MARKER: &str = "<div data-quoted-content";
+
+/// The default signature for a message sent from `from`. Signatures of that
+/// address win over the ones shared by every address of the account.
+pub fn select<'a>(
+    signatures: &'a [Signature],
+    from: &str,
+    is_reply: bool,
+) -> Option<&'a Signature> {
+    let is_default = |signature: &&Signature| {
+        if is_reply {
+            signature.is_reply_default
+        } else {
+            signature.is_default
+        }
+    };
+
+    signatures
+        .iter()
+        .filter(is_default)
+        .find(|s| {
+            s.identity_email
+                .as_deref()
+                .is_some_and(|email| email.eq_ignore_ascii_case(from))
+        })
+        .or_else(|| {
+            signatures
+                .iter()
+                .filter(is_default)
+                .find(|s| s.identity_email.is_none())
+        })
+}
+
+pub fn has_signature(body_html: &str) -> bool {
+    body_html.contains(SIGNATURE_MARKER)
+}
+
+/// Adds the signature after the message text and above any quoted message
+pub fn insert_html(body_html: &str, signature: &Signature) -> String {
+    let block = format!(
+        r#"<div {} data-signature-id="{}">{}</div>"#,
+        SIGNATURE_MARKER, signature.id, signature.signature
+    );
+
+    match body_html.find(QUOTED_CONTENT_MARKER) {
+        Some(pos) => format!("{}{}{}", &body_html[..pos], block, &body_html[pos..]),
+        None => format!("{}{}", body_html, block),
+    }
+}
+
+/// Plain-text alternative of a body without signature, with the signature
+/// appended after the usual `-- ` separator
+pub fn plain_text(body_html: &str, signature: &Signature) -> String {
+    let signature_text = signature
+        .signature_text
+        .clone()
+        .unwrap_or_else(|| html_to_plain_text(&signature.signature));
+
+    format!(
+        "{}\n\n-- \n{}",
+        html_to_plain_text(body_html),
+        signature_text.trim()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn signature(
+        identity_email: Option<&str>,
+        is_default: bool,
+        is_reply_default: bool,
+    ) -> Signature {
+        Signature {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            identity_email: identity_email.map(String::from),
+            name: "Work".to_string(),
+            signature: "<p>Jane &amp; Co</p>".to_string(),
+            signature_text: None,
+            is_default,
+            is_reply_default,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_select_prefers_identity_signature() {
+        let shared = signature(None, true, true);
+        let alias = signature(Some("Sales@example.com"), true, false);
+        let signatures = vec![shared.clone(), alias.clone()];
+
+        let pick = |from, is_reply| select(&signatures, from, is_reply).map(|s| s.id);
+        assert_eq!(pick("sales@example.com", false), Some(alias.id));
+        assert_eq!(pick("sales@example.com", true), Some(shared.id));
+        assert_eq!(pick("jane@example.com", false), Some(shared.id));
+        assert_eq!(
+            select(&[signature(None, false, false)], "a@b.c", false).map(|s| s.id),
+            None
+        );
+    }
+
+    #[test]
+    fn test_insert_html_above_quote() {
+        let sig = signature(None, true, false);
+
+        let reply = insert_html(
+            "<p>Thanks</p><div data-quoted-content=\"true\">old</div>",
+            &sig,
+        );
+        assert!(reply.starts_with("<p>Thanks</p><div data-type=\"email-signature\""));
+        assert!(reply.ends_with("<div data-quoted-content=\"true\">old</div>"));
+        assert!(has_signature(&reply));
+
+        let new = insert_html("<p>Hello</p>", &sig);
+        assert!(new.ends_with("<p>Jane &amp; Co</p></div>"));
+        assert!(!has_signature("<p>Hello</p>"));
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let mut sig = signature(None, true, false);
+        assert_eq!(plain_text("<p>Hello</p>", &sig), "Hello\n\n-- \nJane & Co");
+
+        sig.signature_text = Some("Jane\n".to_string());
+        assert_eq!(plain_text("<p>Hello</p>", &sig), "Hello\n\n-- \nJane");
+    }
+}
@@ -0,0 +1,628 @@
+//! S/MIME (RFC 8551) certificate import, signing, encryption and signature
+//! verification on top of OpenSSL's PKCS#7 support.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkcs7::{Pkcs7, Pkcs7Flags, Pkcs7Ref};
+use openssl::pkey::PKey;
+use openssl::stack::Stack;
+use openssl::symm::Cipher;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509NameRef, X509Ref, X509};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+use crate::database::models::smime::SmimeStatus;
+
+#[derive(Debug)]
+pub enum SmimeError {
+    InvalidBundle(String),
+    InvalidCertificate(String),
+    CryptoError(String),
+}
+
+impl fmt::Display for SmimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmimeError::InvalidBundle(msg) => write!(f, "Invalid PKCS#12 file: {}", msg),
+            SmimeError::InvalidCertificate(msg) => write!(f, "Invalid certificate: {}", msg),
+            SmimeError::CryptoError(msg) => write!(f, "S/MIME error: {}", msg),
+        }
+    }
+}
+
+impl Error for SmimeError {}
+
+impl From<ErrorStack> for SmimeError {
+    fn from(err: ErrorStack) -> Self {
+        SmimeError::CryptoError(err.to_string())
+    }
+}
+
+/// Certificate and private key imported from a PKCS#12 file. Kept in the
+/// credential store, never in the database.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SmimeIdentity {
+    pub certificate_pem: String,
+    pub private_key_pem: String,
+    /// Intermediate certificates sent along with signatures
+    #[serde(default)]
+    pub chain_pem: Vec<String>,
+}
+
+impl fmt::Debug for SmimeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmimeIdentity")
+            .field("chain_pem", &self.chain_pem.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The parts of a certificate shown to the user and stored alongside it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    /// SHA-256 of the DER encoding, hex
+    pub fingerprint: String,
+    pub email_addresses: Vec<String>,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+/// What the user asked for on an outgoing message
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmimeOptions {
+    #[serde(default)]
+    pub sign: bool,
+    #[serde(default)]
+    pub encrypt: bool,
+}
+
+/// Key material for protecting one outgoing message
+#[derive(Clone, Default)]
+pub struct SmimeRequest {
+    pub signer: Option<SmimeIdentity>,
+    /// PEM certificates to encrypt to; the message is sent unencrypted if empty
+    pub recipient_certificates: Vec<String>,
+}
+
+impl fmt::Debug for SmimeRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmimeRequest")
+            .field("sign", &self.signer.is_some())
+            .field("recipients", &self.recipient_certificates.len())
+            .finish()
+    }
+}
+
+/// Result of verifying an incoming signed message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureCheck {
+    pub status: SmimeStatus,
+    pub signer: Option<CertificateInfo>,
+    pub signer_certificate_pem: Option<String>,
+    pub error: Option<String>,
+    /// The signed MIME entity, to read the body and attachments from
+    #[serde(skip)]
+    pub content: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmimeKind {
+    Signed,
+    Enveloped,
+}
+
+/// Classify a message by its top-level `Content-Type` header
+pub fn smime_kind(content_type: &str) -> Option<SmimeKind> {
+    let content_type = content_type.to_ascii_lowercase();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+
+    match mime {
+        "multipart/signed" if content_type.contains("pkcs7-signature") => Some(SmimeKind::Signed),
+        "application/pkcs7-mime" | "application/x-pkcs7-mime" => {
+            if content_type.contains("enveloped-data") {
+                Some(SmimeKind::Enveloped)
+            } else {
+                Some(SmimeKind::Signed)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Read the signing identity out of a PKCS#12 (`.p12` / `.pfx`) file
+pub fn import_pkcs12(
+    data: &[u8],
+    password: &str,
+) -> Result<(SmimeIdentity, CertificateInfo), SmimeError> {
+    let parsed = Pkcs12::from_der(data)
+        .map_err(|_| SmimeError::InvalidBundle("not a PKCS#12 file".to_string()))?
+        .parse2(password)
+        .map_err(|_| SmimeError::InvalidBundle("wrong password or damaged file".to_string()))?;
+
+    let cert = parsed
+        .cert
+        .ok_or_else(|| SmimeError::InvalidBundle("no certificate found".to_string()))?;
+    let key = parsed
+        .pkey
+        .ok_or_else(|| SmimeError::InvalidBundle("no private key found".to_string()))?;
+    if !cert.public_key()?.public_eq(&key) {
+        return Err(SmimeError::InvalidBundle(
+            "the private key does not belong to the certificate".to_string(),
+        ));
+    }
+
+    let info = certificate_info(&cert)?;
+    if info.email_addresses.is_empty() {
+        return Err(SmimeError::InvalidCertificate(
+            "the certificate is not issued to an email address".to_string(),
+        ));
+    }
+
+    let chain_pem = parsed
+        .ca
+        .iter()
+        .flat_map(|chain| chain.iter())
+        .map(|cert| pem_string(cert.to_pem()?))
+        .collect::<Result<_, _>>()?;
+
+    let identity = SmimeIdentity {
+        certificate_pem: pem_string(cert.to_pem()?)?,
+        private_key_pem: pem_string(key.private_key_to_pem_pkcs8()?)?,
+        chain_pem,
+    };
+
+    Ok((identity, info))
+}
+
+pub fn certificate_info_from_pem(pem: &str) -> Result<CertificateInfo, SmimeError> {
+    let cert = X509::from_pem(pem.as_bytes())
+        .map_err(|e| SmimeError::InvalidCertificate(e.to_string()))?;
+    certificate_info(&cert)
+}
+
+pub fn certificate_info(cert: &X509Ref) -> Result<CertificateInfo, SmimeError> {
+    let fingerprint = cert
+        .digest(MessageDigest::sha256())?
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    Ok(CertificateInfo {
+        subject: name_to_string(cert.subject_name()),
+        issuer: name_to_string(cert.issuer_name()),
+        serial: cert.serial_number().to_bn()?.to_hex_str()?.to_string(),
+        fingerprint,
+        email_addresses: email_addresses(cert),
+        not_before: asn1_to_datetime(cert.not_before())?,
+        not_after: asn1_to_datetime(cert.not_after())?,
+    })
+}
+
+/// Verify a signed message against the system trust store.
+///
+/// Returns `None` for messages that are not S/MIME signed. A signature that is
+/// intact but comes from an untrusted chain, or from a certificate not issued
+/// to `sender`, is reported as [`SmimeStatus::Untrusted`].
+pub fn verify_message(raw: &[u8], content_type: &str, sender: &str) -> Option<SignatureCheck> {
+    if smime_kind(content_type) != Some(SmimeKind::Signed) {
+        return None;
+    }
+
+    let (pkcs7, detached) = match Pkcs7::from_smime(raw) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return Some(SignatureCheck {
+                status: SmimeStatus::Invalid,
+                signer: None,
+                signer_certificate_pem: None,
+                error: Some(format!("Unreadable signature: {}", e)),
+                content: None,
+            })
+        }
+    };
+    // Opaque application/pkcs7-mime may also be enveloped data without the
+    // smime-type parameter
+    pkcs7.signed()?;
+
+    let signer = Stack::new()
+        .and_then(|certs| pkcs7.signers(&certs, Pkcs7Flags::empty()))
+        .ok()
+        .and_then(|signers| signers.iter().next().map(ToOwned::to_owned));
+    let signer_info = signer
+        .as_deref()
+        .and_then(|cert| certificate_info(cert).ok());
+
+    let mut content = Vec::new();
+    let (mut status, mut error) = match verify_pkcs7(
+        &pkcs7,
+        detached.as_deref(),
+        &mut content,
+        Pkcs7Flags::empty(),
+    ) {
+        Ok(()) => (SmimeStatus::Valid, None),
+        Err(chain_error) => {
+            match verify_pkcs7(
+                &pkcs7,
+                detached.as_deref(),
+                &mut content,
+                Pkcs7Flags::NOVERIFY,
+            ) {
+                Ok(()) => (
+                    SmimeStatus::Untrusted,
+                    Some(format!("Certificate is not trusted: {}", chain_error)),
+                ),
+                Err(e) => {
+                    // Still unwrap opaque content so the message can be read
+                    let _ = verify_pkcs7(
+                        &pkcs7,
+                        None,
+                        &mut content,
+                        Pkcs7Flags::NOVERIFY | Pkcs7Flags::NOSIGS,
+                    );
+                    (SmimeStatus::Invalid, Some(e.to_string()))
+                }
+            }
+        }
+    };
+
+    let signed_by_sender = signer_info.as_ref().is_some_and(|info| {
+        info.email_addresses
+            .iter()
+            .any(|email| email.eq_ignore_ascii_case(sender.trim()))
+    });
+    if status == SmimeStatus::Valid && !signed_by_sender {
+        status = SmimeStatus::Untrusted;
+        error = Some("The certificate was not issued to the sender's address".to_string());
+    }
+
+    let content = detached.or((!content.is_empty()).then_some(content));
+
+    Some(SignatureCheck {
+        status,
+        signer: signer_info,
+        signer_certificate_pem: signer
+            .and_then(|cert| cert.to_pem().ok())
+            .and_then(|pem| String::from_utf8(pem).ok()),
+        error,
+        content,
+    })
+}
+
+/// [`verify_message`] for a message already parsed from `raw`
+pub fn check_message(
+    raw: &[u8],
+    message: &mail_parser::Message,
+    sender: &str,
+) -> Option<SignatureCheck> {
+    verify_message(raw, message.header_raw("Content-Type")?, sender)
+}
+
+/// Sign and/or encrypt a MIME entity (headers and body of the message content).
+///
+/// The entity is signed first, so the signature is hidden inside the
+/// encryption. Returns the new entity, starting with its own headers.
+pub fn protect_entity(entity: Vec<u8>, request: &SmimeRequest) -> Result<Vec<u8>, SmimeError> {
+    let entity = match &request.signer {
+        Some(identity) => sign_entity(&entity, identity)?,
+        None => entity,
+    };
+
+    if request.recipient_certificates.is_empty() {
+        Ok(entity)
+    } else {
+        encrypt_entity(&entity, &request.recipient_certificates)
+    }
+}
+
+/// Wrap an entity in `multipart/signed` with a detached signature
+pub fn sign_entity(entity: &[u8], identity: &SmimeIdentity) -> Result<Vec<u8>, SmimeError> {
+    let cert = X509::from_pem(identity.certificate_pem.as_bytes())?;
+    let key = PKey::private_key_from_pem(identity.private_key_pem.as_bytes())?;
+    let mut chain = Stack::new()?;
+    for pem in &identity.chain_pem {
+        chain.push(X509::from_pem(pem.as_bytes())?)?;
+    }
+
+    // The entity is already in canonical CRLF form, so it is signed as-is
+    let flags = Pkcs7Flags::DETACHED | Pkcs7Flags::BINARY;
+    let pkcs7 = Pkcs7::sign(&cert, &key, &chain, entity, flags)?;
+
+    Ok(pkcs7.to_smime(
+        entity,
+        flags | Pkcs7Flags::CRLFEOL | Pkcs7Flags::NOOLDMIMETYPE,
+    )?)
+}
+
+/// Encrypt an entity to `application/pkcs7-mime; smime-type=enveloped-data`
+pub fn encrypt_entity(
+    entity: &[u8],
+    recipient_certificates: &[String],
+) -> Result<Vec<u8>, SmimeError> {
+    let mut certs = Stack::new()?;
+    for pem in recipient_certificates {
+        certs.push(
+            X509::from_pem(pem.as_bytes())
+                .map_err(|e| SmimeError::InvalidCertificate(e.to_string()))?,
+        )?;
+    }
+
+    let pkcs7 = Pkcs7::encrypt(&certs, entity, Cipher::aes_256_cbc(), Pkcs7Flags::BINARY)?;
+
+    Ok(pkcs7.to_smime(&[], Pkcs7Flags::CRLFEOL | Pkcs7Flags::NOOLDMIMETYPE)?)
+}
+
+/// Trust roots of the platform's certificate store, loaded once. OpenSSL's own
+/// CA paths only exist on Linux distributions shipping its bundle.
+static TRUST_ROOTS: Lazy<Vec<X509>> = Lazy::new(|| {
+    let result = rustls_native_certs::load_native_certs();
+    for error in &result.errors {
+        log::warn!("[Smime] Failed to load some trust roots: {}", error);
+    }
+
+    let roots: Vec<X509> = result
+        .certs
+        .iter()
+        .filter_map(|cert| X509::from_der(cert.as_ref()).ok())
+        .collect();
+    log::debug!("[Smime] Loaded {} trust roots", roots.len());
+    roots
+});
+
+fn verify_pkcs7(
+    pkcs7: &Pkcs7Ref,
+    detached: Option<&[u8]>,
+    out: &mut Vec<u8>,
+    flags: Pkcs7Flags,
+) -> Result<(), ErrorStack> {
+    let mut store = X509StoreBuilder::new()?;
+    for root in TRUST_ROOTS.iter() {
+        // Stores may list a root twice, which OpenSSL rejects
+        let _ = store.add_cert(root.clone());
+    }
+    let store = store.build();
+    let certs = Stack::new()?;
+
+    pkcs7.verify(&certs, &store, detached, Some(out), flags)
+}
+
+fn name_to_string(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            format!(
+                "{}={}",
+                entry.object().nid().short_name().unwrap_or("?"),
+                entry
+                    .data()
+                    .as_utf8()
+                    .map(|value| value.to_string())
+                    .unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Addresses from the subjectAltName extension, then the subject's emailAddress
+fn email_addresses(cert: &X509Ref) -> Vec<String> {
+    let mut emails: Vec<String> = Vec::new();
+
+    let alt_names = cert
+        .subject_alt_names()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| name.email().map(str::to_lowercase))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let subject_emails = cert
+        .subject_name()
+        .entries_by_nid(Nid::PKCS9_EMAILADDRESS)
+        .filter_map(|entry| entry.data().as_utf8().ok())
+        .map(|email| email.to_lowercase());
+
+    for email in alt_names.into_iter().chain(subject_emails) {
+        if !emails.contains(&email) {
+            emails.push(email);
+        }
+    }
+
+    emails
+}
+
+fn asn1_to_datetime(time: &Asn1TimeRef) -> Result<DateTime<Utc>, SmimeError> {
+    let diff = Asn1Time::from_unix(0)?.diff(time)?;
+    DateTime::from_timestamp(diff.days as i64 * 86_400 + diff.secs as i64, 0)
+        .ok_or_else(|| SmimeError::InvalidCertificate("validity date out of range".to_string()))
+}
+
+fn pem_string(pem: Vec<u8>) -> Result<String, SmimeError> {
+    String::from_utf8(pem).map_err(|e| SmimeError::CryptoError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Integer;
+    use openssl::bn::BigNum;
+    use openssl::pkey::Private;
+    use openssl::rsa::Rsa;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::X509NameBuilder;
+
+    const ENTITY: &[u8] = b"Content-Type: text/plain; charset=utf-8\r\n\
+        Content-Transfer-Encoding: 7bit\r\n\
+        \r\n\
+        Quarterly numbers attached.\r\n";
+
+    fn self_signed(email: &str) -> (X509, PKey<Private>) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "Alice Example").unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = Asn1Integer::from_bn(&BigNum::from_u32(42).unwrap()).unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(365).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .email(email)
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+        (builder.build(), key)
+    }
+
+    fn identity(email: &str) -> (SmimeIdentity, X509, PKey<Private>) {
+        let (cert, key) = self_signed(email);
+        let identity = SmimeIdentity {
+            certificate_pem: pem_string(cert.to_pem().unwrap()).unwrap(),
+            private_key_pem: pem_string(key.private_key_to_pem_pkcs8().unwrap()).unwrap(),
+            chain_pem: Vec::new(),
+        };
+        (identity, cert, key)
+    }
+
+    /// Full message around a protected entity, as a receiving client sees it
+    fn message(entity: &[u8]) -> (Vec<u8>, String) {
+        let text = String::from_utf8_lossy(entity);
+        let content_type = text
+            .lines()
+            .find_map(|line| line.strip_prefix("Content-Type:"))
+            .unwrap()
+            .trim()
+            .to_string();
+        let raw = [
+            b"From: alice@example.com\r\nSubject: Numbers\r\n".as_slice(),
+            entity,
+        ]
+        .concat();
+        (raw, content_type)
+    }
+
+    #[test]
+    fn test_smime_kind() {
+        assert_eq!(
+            smime_kind(
+                "multipart/signed; protocol=\"application/pkcs7-signature\"; micalg=sha-256"
+            ),
+            Some(SmimeKind::Signed)
+        );
+        assert_eq!(
+            smime_kind("application/pkcs7-mime; smime-type=signed-data; name=smime.p7m"),
+            Some(SmimeKind::Signed)
+        );
+        assert_eq!(
+            smime_kind("application/x-pkcs7-mime; smime-type=enveloped-data"),
+            Some(SmimeKind::Enveloped)
+        );
+        assert_eq!(
+            smime_kind("multipart/signed; protocol=\"application/pgp-signature\""),
+            None
+        );
+        assert_eq!(smime_kind("text/plain"), None);
+    }
+
+    #[test]
+    fn test_import_pkcs12() {
+        let (cert, key) = self_signed("Alice@Example.com");
+        let bundle = Pkcs12::builder()
+            .name("Alice")
+            .pkey(&key)
+            .cert(&cert)
+            .build2("secret")
+            .unwrap()
+            .to_der()
+            .unwrap();
+
+        let (identity, info) = import_pkcs12(&bundle, "secret").unwrap();
+        assert_eq!(info.email_addresses, vec!["alice@example.com".to_string()]);
+        assert_eq!(info.subject, "CN=Alice Example");
+        assert_eq!(info.serial, "2A");
+        assert_eq!(info.fingerprint.len(), 64);
+        assert!(info.not_after > info.not_before);
+        assert!(identity.private_key_pem.contains("PRIVATE KEY"));
+
+        assert!(matches!(
+            import_pkcs12(&bundle, "wrong"),
+            Err(SmimeError::InvalidBundle(_))
+        ));
+        assert!(matches!(
+            import_pkcs12(b"not a bundle", "secret"),
+            Err(SmimeError::InvalidBundle(_))
+        ));
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (identity, _, _) = identity("alice@example.com");
+        let signed = sign_entity(ENTITY, &identity).unwrap();
+        let (raw, content_type) = message(&signed);
+
+        // Self-signed, so intact but not trusted
+        let check = verify_message(&raw, &content_type, "alice@example.com").unwrap();
+        assert_eq!(check.status, SmimeStatus::Untrusted);
+        assert_eq!(check.content.as_deref(), Some(ENTITY));
+        assert_eq!(
+            check.signer.unwrap().email_addresses,
+            vec!["alice@example.com".to_string()]
+        );
+        assert!(check.signer_certificate_pem.is_some());
+
+        let tampered = String::from_utf8(raw)
+            .unwrap()
+            .replace("Quarterly", "Annual");
+        let check =
+            verify_message(tampered.as_bytes(), &content_type, "alice@example.com").unwrap();
+        assert_eq!(check.status, SmimeStatus::Invalid);
+
+        assert!(verify_message(ENTITY, "text/plain", "alice@example.com").is_none());
+    }
+
+    #[test]
+    fn test_sign_then_encrypt() {
+        let (alice, _, _) = identity("alice@example.com");
+        let (_, bob_cert, bob_key) = identity("bob@example.com");
+
+        let request = SmimeRequest {
+            signer: Some(alice),
+            recipient_certificates: vec![pem_string(bob_cert.to_pem().unwrap()).unwrap()],
+        };
+        let protected = protect_entity(ENTITY.to_vec(), &request).unwrap();
+        let (_, content_type) = message(&protected);
+        assert_eq!(smime_kind(&content_type), Some(SmimeKind::Enveloped));
+
+        let (pkcs7, _) = Pkcs7::from_smime(&protected).unwrap();
+        let inner = pkcs7
+            .decrypt(&bob_key, &bob_cert, Pkcs7Flags::empty())
+            .unwrap();
+        let (raw, content_type) = message(&inner);
+        let check = verify_message(&raw, &content_type, "alice@example.com").unwrap();
+        assert_eq!(check.content.as_deref(), Some(ENTITY));
+
+        let check = verify_message(&raw, &content_type, "mallory@example.com").unwrap();
+        assert_eq!(check.status, SmimeStatus::Untrusted);
+    }
+}
@@ -0,0 +1,164 @@
+//! Placeholder substitution for message templates.
+//!
+//! Placeholders look like `{{first_name}}`, optionally with a fallback used
+//! when the value is unknown: `{{first_name|there}}`.
+
+use std::collections::HashMap;
+
+use crate::database::models::contact::Contact;
+
+/// Variables a template may use
+pub const TEMPLATE_VARIABLES: &[&str] =
+    &["first_name", "last_name", "full_name", "email", "company"];
+
+/// Variables describing the recipient. `contact` is `None` for addresses that
+/// are not in the contact list yet.
+pub fn recipient_variables(
+    email: &str,
+    contact: Option<&Contact>,
+) -> HashMap<&'static str, String> {
+    let mut variables = HashMap::new();
+    variables.insert("email", email.to_string());
+
+    let Some(contact) = contact else {
+        return variables;
+    };
+
+    let display_name = contact
+        .display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    let first_name = contact.first_name.clone().or_else(|| {
+        display_name
+            .and_then(|name| name.split_whitespace().next())
+            .map(String::from)
+    });
+
+    if let Some(first_name) = first_name {
+        variables.insert("first_name", first_name);
+    }
+    if let Some(last_name) = &contact.last_name {
+        variables.insert("last_name", last_name.clone());
+    }
+    if let Some(company) = &contact.company {
+        variables.insert("company", company.clone());
+    }
+    if display_name.is_some() || contact.first_name.is_some() || contact.last_name.is_some() {
+        variables.insert("full_name", contact.full_name());
+    }
+
+    variables
+}
+
+/// Names of the placeholders in `text` that are not template variables
+pub fn unknown_placeholders(text: &str) -> Vec<String> {
+    placeholders(text)
+        .filter(|(name, _)| !TEMPLATE_VARIABLES.contains(name))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Substitutes the placeholders in `text`. Values are HTML escaped when
+/// `html` is set. A placeholder without a value and without a fallback
+/// becomes empty.
+pub fn render(text: &str, variables: &HashMap<&str, String>, html: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some((before, name, fallback, after)) = next_placeholder(rest) {
+        output.push_str(before);
+
+        let value = variables
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+            .or(fallback)
+            .unwrap_or_default();
+        if html {
+            output.push_str(&escape_html(value));
+        } else {
+            output.push_str(value);
+        }
+
+        rest = after;
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn placeholders(text: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let (_, name, fallback, after) = next_placeholder(rest)?;
+        rest = after;
+        Some((name, fallback))
+    })
+}
+
+/// Splits off the first `{{name}}` or `{{name|fallback}}` in `text`, returning
+/// the text before it, the name, the fallback and the text after it
+fn next_placeholder(text: &str) -> Option<(&str, &str, Option<&str>, &str)> {
+    let mut offset = 0;
+
+    loop {
+        let start = offset + text[offset..].find("{{")?;
+        let end = start + 2 + text[start + 2..].find("}}")?;
+        let inner = &text[start + 2..end];
+
+        let (name, fallback) = match inner.split_once('|') {
+            Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+            None => (inner.trim(), None),
+        };
+
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Some((&text[..start], name, fallback, &text[end + 2..]));
+        }
+
+        // Not a placeholder (e.g. literal braces), keep looking after it
+        offset = start + 2;
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut variables = HashMap::new();
+        variables.insert("first_name", "Ada".to_string());
+        variables.insert("company", "Smith & Co".to_string());
+
+        assert_eq!(
+            render("Hi {{ first_name }}, {{company}}", &variables, true),
+            "Hi Ada, Smith &amp; Co"
+        );
+        assert_eq!(render("{{company}}", &variables, false), "Smith & Co");
+        assert_eq!(
+            render("Dear {{last_name|customer}}{{email}}.", &variables, true),
+            "Dear customer."
+        );
+        assert_eq!(
+            render("{{ not a placeholder }} {{first_name", &variables, true),
+            "{{ not a placeholder }} {{first_name"
+        );
+    }
+
+    #[test]
+    fn test_unknown_placeholders() {
+        assert!(unknown_placeholders("Hi {{first_name|there}} at {{ company }}").is_empty());
+        assert_eq!(
+            unknown_placeholders("{{firstname}} {{email}}"),
+            vec!["firstname".to_string()]
+        );
+    }
+}
@@ -0,0 +1,464 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::time::sleep;
+
+use crate::config::Settings;
+
+const POLL_INTERVAL_SECS: u64 = 60;
+const DEFAULT_LIGHT_THEME: &str = "builtin/light.css";
+const DEFAULT_DARK_THEME: &str = "builtin/dark.css";
+const DEFAULT_LIGHT_FROM: &str = "07:00";
+const DEFAULT_DARK_FROM: &str = "19:00";
+/// Official zenith for sunrise/sunset, accounting for refraction and the solar disc
+const SUN_ZENITH_DEG: f64 = 90.833;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    /// Always use `appearance.theme`
+    Manual,
+    /// Follow the OS light/dark appearance
+    System,
+    /// Light between sunrise and sunset (or fixed times when no location is set)
+    Schedule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+impl From<tauri::Theme> for Appearance {
+    fn from(theme: tauri::Theme) -> Self {
+        match theme {
+            tauri::Theme::Dark => Appearance::Dark,
+            _ => Appearance::Light,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeModeSettings {
+    pub mode: ThemeMode,
+    pub light_theme: String,
+    pub dark_theme: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Local start of the light period when no location is set ("HH:MM")
+    pub light_from: String,
+    /// Local start of the dark period when no location is set ("HH:MM")
+    pub dark_from: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeChangedPayload {
+    pub theme_id: String,
+    pub mode: ThemeMode,
+    pub appearance: Option<Appearance>,
+}
+
+/// Picks the active theme from the theme mode and emits `theme-changed` when it changes
+pub struct ThemeScheduler {
+    settings: Arc<Settings>,
+    app_handle: AppHandle,
+    system_appearance: RwLock<Option<Appearance>>,
+    current_theme: Mutex<Option<String>>,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+}
+
+impl ThemeScheduler {
+    pub fn new(settings: Arc<Settings>, app_handle: AppHandle) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            settings,
+            app_handle,
+            system_appearance: RwLock::new(None),
+            current_theme: Mutex::new(None),
+            shutdown_tx,
+        }
+    }
+
+    /// Re-evaluates the schedule every minute so sunrise and sunset switch the theme
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[ThemeScheduler] Starting theme scheduler");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                this.apply();
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[ThemeScheduler] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[ThemeScheduler] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    pub fn mode_settings(&self) -> ThemeModeSettings {
+        ThemeModeSettings {
+            mode: self
+                .settings
+                .get::<ThemeMode>("appearance.themeMode")
+                .unwrap_or(ThemeMode::Manual),
+            light_theme: self.string_setting("appearance.lightTheme", DEFAULT_LIGHT_THEME),
+            dark_theme: self.string_setting("appearance.darkTheme", DEFAULT_DARK_THEME),
+            latitude: self
+                .settings
+                .get::<f64>("appearance.schedule.latitude")
+                .ok(),
+            longitude: self
+                .settings
+                .get::<f64>("appearance.schedule.longitude")
+                .ok(),
+            light_from: self.string_setting("appearance.schedule.lightFrom", DEFAULT_LIGHT_FROM),
+            dark_from: self.string_setting("appearance.schedule.darkFrom", DEFAULT_DARK_FROM),
+        }
+    }
+
+    pub fn save_mode_settings(&self, mode_settings: &ThemeModeSettings) -> Result<(), String> {
+        let values = [
+            (
+                "appearance.themeMode",
+                serde_json::json!(mode_settings.mode),
+            ),
+            (
+                "appearance.lightTheme",
+                serde_json::json!(mode_settings.light_theme),
+            ),
+            (
+                "appearance.darkTheme",
+                serde_json::json!(mode_settings.dark_theme),
+            ),
+            (
+                "appearance.schedule.latitude",
+                serde_json::json!(mode_settings.latitude),
+            ),
+            (
+                "appearance.schedule.longitude",
+                serde_json::json!(mode_settings.longitude),
+            ),
+            (
+                "appearance.schedule.lightFrom",
+                serde_json::json!(mode_settings.light_from),
+            ),
+            (
+                "appearance.schedule.darkFrom",
+                serde_json::json!(mode_settings.dark_from),
+            ),
+        ];
+
+        for (key, value) in values {
+            self.settings
+                .set(key, value)
+                .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+        }
+
+        self.apply();
+        Ok(())
+    }
+
+    /// Called when the OS switches between light and dark appearance
+    pub fn set_system_appearance(&self, appearance: Appearance) {
+        if let Ok(mut system_appearance) = self.system_appearance.write() {
+            *system_appearance = Some(appearance);
+        }
+        self.apply();
+    }
+
+    /// Appearance the current mode asks for, or `None` in manual mode
+    pub fn effective_appearance(&self) -> Option<Appearance> {
+        let mode_settings = self.mode_settings();
+        match mode_settings.mode {
+            ThemeMode::Manual => None,
+            ThemeMode::System => Some(
+                self.system_appearance
+                    .read()
+                    .ok()
+                    .and_then(|appearance| *appearance)
+                    .unwrap_or(Appearance::Light),
+            ),
+            ThemeMode::Schedule => Some(scheduled_appearance(&mode_settings, Utc::now())),
+        }
+    }
+
+    pub fn effective_theme(&self) -> String {
+        let mode_settings = self.mode_settings();
+        match self.effective_appearance() {
+            Some(Appearance::Light) => mode_settings.light_theme,
+            Some(Appearance::Dark) => mode_settings.dark_theme,
+            None => self.string_setting("appearance.theme", DEFAULT_LIGHT_THEME),
+        }
+    }
+
+    /// Emits `theme-changed` if the effective theme differs from the last one applied
+    pub fn apply(&self) {
+        let theme_id = self.effective_theme();
+
+        let changed = match self.current_theme.lock() {
+            Ok(mut current) => {
+                let changed = current.as_deref() != Some(theme_id.as_str());
+                *current = Some(theme_id.clone());
+                changed
+            }
+            Err(_) => false,
+        };
+
+        if !changed {
+            return;
+        }
+
+        let payload = ThemeChangedPayload {
+            theme_id,
+            mode: self.mode_settings().mode,
+            appearance: self.effective_appearance(),
+        };
+        log::debug!("[ThemeScheduler] Theme changed: {:?}", payload);
+
+        if let Err(e) = self.app_handle.emit("theme-changed", &payload) {
+            log::error!("[ThemeScheduler] Failed to emit theme-changed event: {}", e);
+        }
+    }
+
+    /// Emits `theme-changed` even though the effective theme is the same,
+    /// after its stylesheet was edited
+    pub fn refresh(&self) {
+        if let Ok(mut current) = self.current_theme.lock() {
+            *current = None;
+        }
+        self.apply();
+    }
+
+    /// Point theme settings that use `theme_id` back at the default themes,
+    /// after the theme was deleted
+    pub fn forget_theme(&self, theme_id: &str) -> Result<(), String> {
+        let defaults = [
+            ("appearance.theme", DEFAULT_DARK_THEME),
+            ("appearance.lightTheme", DEFAULT_LIGHT_THEME),
+            ("appearance.darkTheme", DEFAULT_DARK_THEME),
+        ];
+
+        for (key, default) in defaults {
+            if self.settings.get::<String>(key).ok().as_deref() == Some(theme_id) {
+                self.settings
+                    .set(key, serde_json::json!(default))
+                    .map_err(|e| format!("Failed to save {}: {}", key, e))?;
+            }
+        }
+
+        self.apply();
+        Ok(())
+    }
+
+    fn string_setting(&self, key: &str, default: &str) -> String {
+        self.settings
+            .get::<String>(key)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| default.to_string())
+    }
+}
+
+fn scheduled_appearance(mode_settings: &ThemeModeSettings, now: DateTime<Utc>) -> Appearance {
+    if let (Some(latitude), Some(longitude)) = (mode_settings.latitude, mode_settings.longitude) {
+        return solar_appearance(now, latitude, longitude);
+    }
+
+    let parse = |value: &str, default: &str| {
+        NaiveTime::parse_from_str(value, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(default, "%H:%M"))
+            .unwrap_or(NaiveTime::MIN)
+    };
+    let light_from = parse(&mode_settings.light_from, DEFAULT_LIGHT_FROM);
+    let dark_from = parse(&mode_settings.dark_from, DEFAULT_DARK_FROM);
+
+    if is_within(now.with_timezone(&Local).time(), light_from, dark_from) {
+        Appearance::Light
+    } else {
+        Appearance::Dark
+    }
+}
+
+/// Whether `time` falls in `[start, end)`, wrapping past midnight when `end < start`
+fn is_within<T: PartialOrd>(time: T, start: T, end: T) -> bool {
+    if start <= end {
+        time >= start && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+fn solar_appearance(now: DateTime<Utc>, latitude: f64, longitude: f64) -> Appearance {
+    let hour = now.hour() as f64 + now.minute() as f64 / 60.0;
+
+    match sun_times(now.date_naive(), latitude, longitude) {
+        SunTimes::Daylight { sunrise, sunset } if is_within(hour, sunrise, sunset) => {
+            Appearance::Light
+        }
+        SunTimes::Daylight { .. } | SunTimes::PolarNight => Appearance::Dark,
+        SunTimes::PolarDay => Appearance::Light,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SunTimes {
+    /// Sunrise and sunset in UTC hours of the day
+    Daylight {
+        sunrise: f64,
+        sunset: f64,
+    },
+    PolarDay,
+    PolarNight,
+}
+
+/// Sunrise and sunset using the sunrise equation from the Almanac for Computers
+fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> SunTimes {
+    let sunrise = sun_event(date, latitude, longitude, true);
+    let sunset = sun_event(date, latitude, longitude, false);
+
+    match (sunrise, sunset) {
+        (Ok(sunrise), Ok(sunset)) => SunTimes::Daylight { sunrise, sunset },
+        (Err(SunTimes::PolarDay), _) | (_, Err(SunTimes::PolarDay)) => SunTimes::PolarDay,
+        _ => SunTimes::PolarNight,
+    }
+}
+
+fn sun_event(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+    rising: bool,
+) -> Result<f64, SunTimes> {
+    let normalize = |value: f64, max: f64| value.rem_euclid(max);
+
+    let day_of_year = date.ordinal() as f64;
+    let longitude_hour = longitude / 15.0;
+    let approx_time = day_of_year + ((if rising { 6.0 } else { 18.0 }) - longitude_hour) / 24.0;
+
+    let mean_anomaly = 0.9856 * approx_time - 3.289;
+    let true_longitude = normalize(
+        mean_anomaly
+            + 1.916 * mean_anomaly.to_radians().sin()
+            + 0.020 * (2.0 * mean_anomaly).to_radians().sin()
+            + 282.634,
+        360.0,
+    );
+
+    let mut right_ascension = normalize(
+        (0.91764 * true_longitude.to_radians().tan())
+            .atan()
+            .to_degrees(),
+        360.0,
+    );
+    // Right ascension has to be in the same quadrant as the true longitude
+    right_ascension +=
+        (true_longitude / 90.0).floor() * 90.0 - (right_ascension / 90.0).floor() * 90.0;
+    right_ascension /= 15.0;
+
+    let sin_declination = 0.39782 * true_longitude.to_radians().sin();
+    let cos_declination = sin_declination.asin().cos();
+
+    let cos_hour_angle = (SUN_ZENITH_DEG.to_radians().cos()
+        - sin_declination * latitude.to_radians().sin())
+        / (cos_declination * latitude.to_radians().cos());
+
+    if cos_hour_angle > 1.0 {
+        return Err(SunTimes::PolarNight);
+    }
+    if cos_hour_angle < -1.0 {
+        return Err(SunTimes::PolarDay);
+    }
+
+    let hour_angle = if rising {
+        360.0 - cos_hour_angle.acos().to_degrees()
+    } else {
+        cos_hour_angle.acos().to_degrees()
+    } / 15.0;
+
+    let local_mean_time = hour_angle + right_ascension - 0.06571 * approx_time - 6.622;
+    Ok(normalize(local_mean_time - longitude_hour, 24.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn assert_close(actual: f64, expected: f64) {
+        // Within five minutes
+        assert!(
+            (actual - expected).abs() < 5.0 / 60.0,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_sun_times_london_midsummer() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let SunTimes::Daylight { sunrise, sunset } = sun_times(date, 51.5074, -0.1278) else {
+            panic!("expected daylight");
+        };
+        // 04:43 and 21:21 BST
+        assert_close(sunrise, 3.0 + 43.0 / 60.0);
+        assert_close(sunset, 20.0 + 21.0 / 60.0);
+    }
+
+    #[test]
+    fn test_sun_times_polar() {
+        let midsummer = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let midwinter = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        assert_eq!(sun_times(midsummer, 78.22, 15.65), SunTimes::PolarDay);
+        assert_eq!(sun_times(midwinter, 78.22, 15.65), SunTimes::PolarNight);
+    }
+
+    #[test]
+    fn test_solar_appearance_wraps_past_midnight_utc() {
+        // Sydney sunrise and sunset are on either side of midnight UTC
+        let (latitude, longitude) = (-33.8688, 151.2093);
+        let noon_local = Utc.with_ymd_and_hms(2024, 1, 15, 2, 0, 0).unwrap();
+        let midnight_local = Utc.with_ymd_and_hms(2024, 1, 15, 14, 0, 0).unwrap();
+
+        assert_eq!(
+            solar_appearance(noon_local, latitude, longitude),
+            Appearance::Light
+        );
+        assert_eq!(
+            solar_appearance(midnight_local, latitude, longitude),
+            Appearance::Dark
+        );
+    }
+
+    #[test]
+    fn test_is_within() {
+        let at = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        assert!(is_within(at(12), at(7), at(19)));
+        assert!(!is_within(at(19), at(7), at(19)));
+        assert!(is_within(at(23), at(22), at(6)));
+        assert!(is_within(at(2), at(22), at(6)));
+        assert!(!is_within(at(12), at(22), at(6)));
+    }
+}
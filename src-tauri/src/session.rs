@@ -0,0 +1,367 @@
+use crate::database::models::session::{SessionWindow, SessionWindowType};
+use crate::database::repositories::{SessionRepository, SqliteSessionRepository};
+use crate::state::AppState;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, Runtime, WebviewWindow, Window};
+use uuid::Uuid;
+
+/// How long a window has to stay put before its geometry is saved
+const GEOMETRY_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Latest move or resize of each window, so only the last of a burst of
+/// events saves the geometry
+#[derive(Debug, Default)]
+pub struct GeometryRecorder {
+    generations: Mutex<HashMap<String, u64>>,
+}
+
+impl GeometryRecorder {
+    fn next_generation(&self, label: &str) -> u64 {
+        let mut generations = self
+            .generations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let generation = generations.entry(label.to_string()).or_default();
+        *generation += 1;
+        *generation
+    }
+
+    fn is_latest(&self, label: &str, generation: u64) -> bool {
+        self.generations
+            .lock()
+            .map(|generations| generations.get(label) == Some(&generation))
+            .unwrap_or(false)
+    }
+}
+
+/// Snapshot the geometry of a live window into a session record
+pub fn capture_window<R: Runtime>(
+    window: &WebviewWindow<R>,
+    window_type: SessionWindowType,
+    route: Option<String>,
+    view_state: serde_json::Value,
+) -> SessionWindow {
+    let is_maximized = window.is_maximized().unwrap_or(false);
+    let position = window.outer_position().ok();
+    let size = window.inner_size().ok();
+
+    SessionWindow {
+        label: window.label().to_string(),
+        window_type,
+        route,
+        x: position.map(|p| p.x),
+        y: position.map(|p| p.y),
+        width: size.map(|s| s.width),
+        height: size.map(|s| s.height),
+        is_maximized,
+        view_state,
+        updated_at: Utc::now(),
+    }
+}
+
+fn apply_geometry<R: Runtime>(window: &WebviewWindow<R>, session: &SessionWindow) {
+    if let (Some(width), Some(height)) = (session.width, session.height) {
+        if width > 0 && height > 0 {
+            let _ = window.set_size(PhysicalSize::new(width, height));
+        }
+    }
+
+    if let (Some(x), Some(y)) = (session.x, session.y) {
+        // Skip positions that no longer land on any connected monitor
+        let on_screen = window
+            .available_monitors()
+            .map(|monitors| {
+                monitors.iter().any(|monitor| {
+                    let origin = monitor.position();
+                    let size = monitor.size();
+                    x >= origin.x
+                        && y >= origin.y
+                        && x < origin.x + size.width as i32
+                        && y < origin.y + size.height as i32
+                })
+            })
+            .unwrap_or(false);
+
+        if on_screen {
+            let _ = window.set_position(PhysicalPosition::new(x, y));
+        }
+    }
+
+    if session.is_maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// Label of the main window
+pub const MAIN_WINDOW_LABEL: &str = "main";
+
+/// Session type of the window labelled `label`, `None` for windows that are
+/// not part of the session (account setup, OAuth, printing)
+pub fn window_type_for_label(label: &str) -> Option<SessionWindowType> {
+    if label == MAIN_WINDOW_LABEL {
+        return Some(SessionWindowType::Main);
+    }
+
+    [SessionWindowType::Compose, SessionWindowType::Message]
+        .into_iter()
+        .find(|window_type| label.starts_with(label_prefix(*window_type)))
+}
+
+/// Save the geometry of a window that was moved or resized once it settled,
+/// or right away when it is about to close. Message and compose windows the
+/// user closes are forgotten instead, see `forget_closed_window`.
+pub fn record_geometry<R: Runtime>(window: &Window<R>, closing: bool) {
+    let Some(recorder) = window.try_state::<GeometryRecorder>() else {
+        return;
+    };
+    let Some(state) = window.try_state::<AppState>() else {
+        return;
+    };
+
+    let label = window.label().to_string();
+    let Some(window_type) = window_type_for_label(&label) else {
+        return;
+    };
+    let generation = recorder.next_generation(&label);
+    if closing && is_detached_window(&label) {
+        return;
+    }
+
+    let app = window.app_handle().clone();
+    let pool = state.db_pool.clone();
+    let save = async move {
+        if !closing {
+            tokio::time::sleep(GEOMETRY_SAVE_DELAY).await;
+            let latest = app
+                .try_state::<GeometryRecorder>()
+                .is_some_and(|recorder| recorder.is_latest(&label, generation));
+            if !latest {
+                return;
+            }
+        }
+
+        let Some(window) = app.get_webview_window(&label) else {
+            return;
+        };
+        if window.is_minimized().unwrap_or(false) {
+            return;
+        }
+
+        let session = capture_window(&window, window_type, None, serde_json::json!({}));
+        if let Err(error) = SqliteSessionRepository::new(pool)
+            .upsert_geometry(&session)
+            .await
+        {
+            log::warn!("[Session] Failed to save geometry of {}: {}", label, error);
+        }
+    };
+
+    // The app may exit right after a close, so that save is not left to a task
+    if closing {
+        tauri::async_runtime::block_on(save);
+    } else {
+        tauri::async_runtime::spawn(save);
+    }
+}
+
+/// Label prefix of a message or compose window, followed by an id
+fn label_prefix(window_type: SessionWindowType) -> &'static str {
+    match window_type {
+        SessionWindowType::Compose => "compose-",
+        _ => "message-",
+    }
+}
+
+/// Label of the window showing the email, so opening it twice focuses the
+/// existing window
+pub fn message_window_label(email_id: Uuid) -> String {
+    format!(
+        "{}{}",
+        label_prefix(SessionWindowType::Message),
+        email_id.simple()
+    )
+}
+
+/// Label of the window showing a message file, so opening the file twice
+/// focuses the existing window
+pub fn message_file_window_label(path: &Path) -> String {
+    format!(
+        "{}file-{:x}",
+        label_prefix(SessionWindowType::Message),
+        md5::compute(path.to_string_lossy().as_bytes())
+    )
+}
+
+/// Label of a new compose window; several can be open at once
+pub fn compose_window_label() -> String {
+    format!(
+        "{}{}",
+        label_prefix(SessionWindowType::Compose),
+        Uuid::now_v7().simple()
+    )
+}
+
+/// Whether the window is a message or compose window rather than the main one
+pub fn is_detached_window(label: &str) -> bool {
+    [SessionWindowType::Message, SessionWindowType::Compose]
+        .into_iter()
+        .any(|window_type| label.starts_with(label_prefix(window_type)))
+}
+
+fn build_detached_window<R: Runtime>(
+    app: &AppHandle<R>,
+    label: &str,
+    window_type: SessionWindowType,
+    route: String,
+    title: &str,
+) -> tauri::Result<WebviewWindow<R>> {
+    let (width, height) = match window_type {
+        SessionWindowType::Compose => (760.0, 680.0),
+        _ => (900.0, 760.0),
+    };
+
+    tauri::WebviewWindowBuilder::new(app, label, tauri::WebviewUrl::App(route.into()))
+        .title(title)
+        .inner_size(width, height)
+        .min_inner_size(480.0, 400.0)
+        .build()
+}
+
+/// Open a message or compose window on `route`, or focus it if it is already
+/// open. The window is remembered in the session until the user closes it.
+pub async fn open_detached_window<R: Runtime>(
+    app: &AppHandle<R>,
+    pool: SqlitePool,
+    label: &str,
+    window_type: SessionWindowType,
+    route: String,
+    title: &str,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    let window = build_detached_window(app, label, window_type, route.clone(), title)
+        .map_err(|e| format!("Failed to open window: {}", e))?;
+    let _ = window.set_focus();
+
+    let session = capture_window(&window, window_type, Some(route), serde_json::json!({}));
+    if let Err(error) = SqliteSessionRepository::new(pool).upsert(&session).await {
+        log::warn!("[Session] Failed to remember window {}: {}", label, error);
+    }
+
+    Ok(())
+}
+
+/// Drop a message or compose window the user closed from the session, so it
+/// is not reopened on the next start. Windows destroyed on quit are kept.
+pub fn forget_closed_window(label: &str, pool: SqlitePool) {
+    if !is_detached_window(label) {
+        return;
+    }
+
+    let label = label.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Err(error) = SqliteSessionRepository::new(pool).delete(&label).await {
+            log::warn!("[Session] Failed to forget window {}: {}", label, error);
+        }
+    });
+}
+
+/// Restore windows, geometry and the last route from the previous run
+pub async fn restore_session<R: Runtime>(app: &AppHandle<R>, pool: SqlitePool) {
+    let repo = SqliteSessionRepository::new(pool);
+    let windows = match repo.get_all().await {
+        Ok(windows) => windows,
+        Err(error) => {
+            log::error!("[Session] Failed to load session: {}", error);
+            return;
+        }
+    };
+
+    for session in windows {
+        // Rows of windows that are not part of the session, e.g. saved by
+        // older versions, are dropped rather than applied to another window
+        if window_type_for_label(&session.label) != Some(session.window_type) {
+            let _ = repo.delete(&session.label).await;
+            continue;
+        }
+
+        match session.window_type {
+            SessionWindowType::Main => {
+                if let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) {
+                    apply_geometry(&window, &session);
+                }
+                if let Some(route) = session.route.clone() {
+                    crate::navigation::dispatch_navigation_url(app, route);
+                }
+            }
+            SessionWindowType::Message | SessionWindowType::Compose => {
+                if app.get_webview_window(&session.label).is_some() {
+                    continue;
+                }
+
+                let Some(route) = session.route.clone() else {
+                    let _ = repo.delete(&session.label).await;
+                    continue;
+                };
+
+                let title = match session.window_type {
+                    SessionWindowType::Compose => "New Message",
+                    _ => "Message",
+                };
+
+                match build_detached_window(app, &session.label, session.window_type, route, title)
+                {
+                    Ok(window) => apply_geometry(&window, &session),
+                    Err(error) => {
+                        log::warn!(
+                            "[Session] Failed to restore window {}: {}",
+                            session.label,
+                            error
+                        );
+                        let _ = repo.delete(&session.label).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_type_for_label() {
+        assert_eq!(window_type_for_label("main"), Some(SessionWindowType::Main));
+        assert_eq!(
+            window_type_for_label(&compose_window_label()),
+            Some(SessionWindowType::Compose)
+        );
+        assert_eq!(
+            window_type_for_label(&message_window_label(Uuid::now_v7())),
+            Some(SessionWindowType::Message)
+        );
+        assert_eq!(window_type_for_label("add-account"), None);
+        assert_eq!(window_type_for_label("oauth-google"), None);
+        assert_eq!(window_type_for_label("print-1"), None);
+    }
+
+    #[test]
+    fn test_geometry_recorder_keeps_latest() {
+        let recorder = GeometryRecorder::default();
+        let first = recorder.next_generation("main");
+        let second = recorder.next_generation("main");
+        assert!(!recorder.is_latest("main", first));
+        assert!(recorder.is_latest("main", second));
+        assert!(!recorder.is_latest("compose-1", second));
+    }
+}
@@ -1,13 +1,23 @@
 use crate::config::{ConfigWatcher, KeyBindings, KeyBindingsWatcher, Settings};
+use crate::database::migrations::MigrationReporter;
 use crate::licensing::{LicenseManager, LicenseRefreshRunner};
+use crate::search::reindex::SearchReindexer;
 use crate::search::SearchManager;
+use crate::services::app_lock::AppLock;
+use crate::services::automation_api::AutomationApi;
+use crate::services::automation_triggers::AutomationTriggerDispatcher;
 use crate::services::avatar_service::AvatarService;
 use crate::services::corvus::CorvusService;
+use crate::services::feature_flags::FeatureFlags;
 use crate::services::notification_service::NotificationService;
+use crate::services::theme_scheduler::ThemeScheduler;
 use crate::sync::auth::CredentialStore;
 use crate::sync::{
-    BackgroundAiAnalyzer, BackgroundAvatarFetcher, BackgroundBodyFetcher, BackgroundCleanup,
-    BackgroundReminderNotifier, BackgroundSyncManager, OAuthStateManager, SyncCoordinator,
+    BackgroundAiAnalyzer, BackgroundArchiveWorker, BackgroundAttachmentIndexer,
+    BackgroundAvatarFetcher, BackgroundBodyFetcher, BackgroundCleanup,
+    BackgroundContactDateNotifier, BackgroundReminderNotifier, BackgroundSnoozeWorker,
+    BackgroundStyleLearner, BackgroundSyncManager, GraphSubscriptionManager, OAuthStateManager,
+    OutboxWorker, ScheduledSendWorker, SyncCoordinator,
 };
 use sqlx::SqlitePool;
 use std::path::PathBuf;
@@ -26,12 +36,27 @@ pub struct AppState {
     pub background_avatar_fetcher: Arc<BackgroundAvatarFetcher>,
     pub background_cleanup: Arc<BackgroundCleanup>,
     pub background_reminder_notifier: Arc<BackgroundReminderNotifier>,
+    pub background_contact_date_notifier: Arc<BackgroundContactDateNotifier>,
+    pub background_snooze_worker: Arc<BackgroundSnoozeWorker>,
+    pub background_archive_worker: Arc<BackgroundArchiveWorker>,
+    pub background_attachment_indexer: Arc<BackgroundAttachmentIndexer>,
+    pub background_style_learner: Arc<BackgroundStyleLearner>,
+    pub scheduled_send_worker: Arc<ScheduledSendWorker>,
+    pub outbox_worker: Arc<OutboxWorker>,
     pub sync_coordinator: Arc<SyncCoordinator>,
+    pub graph_subscription_manager: Arc<GraphSubscriptionManager>,
     pub credential_store: Arc<CredentialStore>,
     pub search_manager: Arc<SearchManager>,
+    pub search_reindexer: Arc<SearchReindexer>,
     pub notification_service: Arc<NotificationService>,
     pub license_manager: Arc<LicenseManager>,
     pub license_refresh_runner: Arc<LicenseRefreshRunner>,
+    pub feature_flags: Arc<FeatureFlags>,
+    pub theme_scheduler: Arc<ThemeScheduler>,
+    pub app_lock: Arc<AppLock>,
+    pub automation_api: Arc<AutomationApi>,
+    pub automation_trigger_dispatcher: Arc<AutomationTriggerDispatcher>,
+    pub migration_reporter: MigrationReporter,
     pub app_handle: tauri::AppHandle,
     pub app_data_dir: PathBuf,
     pub download_dir: PathBuf,
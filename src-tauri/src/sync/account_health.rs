@@ -0,0 +1,423 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::account::{Account, AccountQuota, AccountType};
+use crate::database::repositories::{
+    AccountRepository, SqliteAccountRepository, SqliteSyncStateRepository, SyncStateRepository,
+};
+use crate::sync::auth::CredentialStore;
+use crate::sync::mailbox_quota;
+use crate::sync::rate_limit;
+use crate::sync::types::AccountSettings;
+
+/// A folder without a successful sync for this long is reported as stale
+const STALE_SYNC_HOURS: i64 = 24;
+/// Share of the storage limit from which the mailbox counts as nearly full
+const QUOTA_WARNING_PERCENT: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    Warning,
+    Error,
+}
+
+/// Something wrong with an account, for the UI to explain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HealthIssue {
+    /// No token or password is stored, the account has to be signed in again
+    MissingCredentials,
+    /// The access token expired and cannot be refreshed
+    TokenExpired,
+    /// Folders whose last sync failed
+    FolderErrors {
+        folders: usize,
+    },
+    /// No folder synced successfully for a day
+    SyncStale {
+        last_sync_at: Option<DateTime<Utc>>,
+    },
+    /// Changes that could not be applied on the server
+    FailedOperations {
+        count: i64,
+    },
+    QuotaNearlyFull {
+        percent: i64,
+    },
+    QuotaUnavailable {
+        error: String,
+    },
+    /// The provider asked to back off; scheduled syncs wait until then
+    Throttled {
+        until: DateTime<Utc>,
+    },
+}
+
+impl HealthIssue {
+    pub fn severity(&self) -> HealthStatus {
+        match self {
+            Self::MissingCredentials | Self::TokenExpired => HealthStatus::Error,
+            _ => HealthStatus::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CredentialHealth {
+    /// The account signs in with OAuth2 rather than a password
+    pub uses_oauth2: bool,
+    pub has_oauth2_token: bool,
+    pub has_password: bool,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    /// An expired token is renewed automatically with a refresh token
+    pub has_refresh_token: bool,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderHealth {
+    pub folder_id: Uuid,
+    pub folder_name: String,
+    pub sync_status: String,
+    /// Last successful sync
+    pub last_sync_at: Option<DateTime<Utc>>,
+    /// Failed syncs since the last successful one
+    pub error_count: i64,
+    pub error_message: Option<String>,
+}
+
+/// Diagnostics of an account, to show why sync stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountHealth {
+    pub account_id: Uuid,
+    pub status: HealthStatus,
+    pub issues: Vec<HealthIssue>,
+    pub credentials: CredentialHealth,
+    pub sync_enabled: bool,
+    /// Paused by the user; a paused account is not reported as stale
+    pub paused: bool,
+    /// The provider rate limited the account until then
+    pub throttled_until: Option<DateTime<Utc>>,
+    pub is_syncing: bool,
+    /// Most recent successful sync of any folder
+    pub last_sync_at: Option<DateTime<Utc>>,
+    pub folders: Vec<FolderHealth>,
+    pub pending_operations: i64,
+    pub failed_operations: i64,
+    pub quota: Option<AccountQuota>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Collects the account's credential, sync, operation queue and quota state.
+///
+/// A stale quota is refreshed from the provider, which doubles as a check that
+/// the provider can be reached; failing to do so is reported as an issue.
+pub async fn check_account_health(
+    pool: &SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    account: &Account,
+    is_syncing: bool,
+) -> Result<AccountHealth, String> {
+    let credentials = credential_health(&credential_store, account).await;
+    let folders = folder_health(pool, account.id).await?;
+    let (pending_operations, failed_operations) = operation_counts(pool, account.id).await?;
+
+    let sync_enabled = serde_json::from_value::<AccountSettings>(account.settings.clone())
+        .map(|settings| settings.sync_enabled)
+        .unwrap_or(true);
+    let paused = SqliteSyncStateRepository::new(pool.clone())
+        .is_account_paused(account.id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (quota, quota_error) =
+        match mailbox_quota::refresh_quota_if_stale(pool, credential_store, account).await {
+            Ok(Some(quota)) => (Some(quota), None),
+            Ok(None) => (stored_quota(pool, account.id).await?, None),
+            Err(error) => (stored_quota(pool, account.id).await?, Some(error)),
+        };
+
+    let throttled_until = rate_limit::throttled_until(account.id);
+
+    let now = Utc::now();
+    let mut issues = assess(
+        &credentials,
+        &folders,
+        failed_operations,
+        quota.as_ref(),
+        quota_error,
+        sync_enabled && !paused,
+        now,
+    );
+    if let Some(until) = throttled_until {
+        issues.push(HealthIssue::Throttled { until });
+    }
+    let status = issues
+        .iter()
+        .map(HealthIssue::severity)
+        .max()
+        .unwrap_or(HealthStatus::Healthy);
+
+    Ok(AccountHealth {
+        account_id: account.id,
+        status,
+        issues,
+        credentials,
+        sync_enabled,
+        paused,
+        throttled_until,
+        is_syncing,
+        last_sync_at: folders.iter().filter_map(|f| f.last_sync_at).max(),
+        folders,
+        pending_operations,
+        failed_operations,
+        quota,
+        checked_at: now,
+    })
+}
+
+async fn credential_health(
+    credential_store: &CredentialStore,
+    account: &Account,
+) -> CredentialHealth {
+    let uses_oauth2 = matches!(
+        account.account_type,
+        AccountType::Gmail | AccountType::Office365
+    );
+    let mut health = CredentialHealth {
+        uses_oauth2,
+        ..Default::default()
+    };
+
+    if uses_oauth2 {
+        if let Ok(credentials) = credential_store.get_oauth2(account.id).await {
+            health.has_oauth2_token = true;
+            health.token_expires_at = credentials.expires_at;
+            health.has_refresh_token = credentials.refresh_token.is_some();
+            health.scopes = credentials.scopes;
+        }
+    }
+    health.has_password = credential_store.get_imap(account.id).await.is_ok();
+
+    health
+}
+
+async fn folder_health(pool: &SqlitePool, account_id: Uuid) -> Result<Vec<FolderHealth>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT ss.folder_id, f.name AS folder_name, ss.sync_status,
+               ss.last_sync_at, ss.error_count, ss.error_message
+        FROM sync_state ss
+        JOIN folders f ON f.id = ss.folder_id
+        WHERE ss.account_id = ?
+        ORDER BY f.name
+        "#,
+    )
+    .bind(account_id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load sync state: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let folder_id: String = row.try_get("folder_id").ok()?;
+            Some(FolderHealth {
+                folder_id: Uuid::parse_str(&folder_id).ok()?,
+                folder_name: row.try_get("folder_name").unwrap_or_default(),
+                sync_status: row.try_get("sync_status").unwrap_or_default(),
+                last_sync_at: row.try_get("last_sync_at").ok().flatten(),
+                error_count: row.try_get("error_count").unwrap_or(0),
+                error_message: row.try_get("error_message").ok().flatten(),
+            })
+        })
+        .collect())
+}
+
+/// Pending and failed operations of the account's offline queue
+async fn operation_counts(pool: &SqlitePool, account_id: Uuid) -> Result<(i64, i64), String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            COALESCE(SUM(status IN ('pending', 'in_progress')), 0) AS pending,
+            COALESCE(SUM(status = 'failed'), 0) AS failed
+        FROM pending_operations
+        WHERE account_id = ?
+        "#,
+    )
+    .bind(account_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count pending operations: {}", e))?;
+
+    Ok((
+        row.try_get("pending").unwrap_or(0),
+        row.try_get("failed").unwrap_or(0),
+    ))
+}
+
+async fn stored_quota(pool: &SqlitePool, account_id: Uuid) -> Result<Option<AccountQuota>, String> {
+    SqliteAccountRepository::new(pool.clone())
+        .find_quota(account_id)
+        .await
+        .map_err(|e| format!("Failed to load quota: {}", e))
+}
+
+fn assess(
+    credentials: &CredentialHealth,
+    folders: &[FolderHealth],
+    failed_operations: i64,
+    quota: Option<&AccountQuota>,
+    quota_error: Option<String>,
+    sync_enabled: bool,
+    now: DateTime<Utc>,
+) -> Vec<HealthIssue> {
+    let mut issues = Vec::new();
+
+    if credentials.uses_oauth2 && credentials.has_oauth2_token {
+        let expired = credentials
+            .token_expires_at
+            .is_some_and(|expires_at| expires_at < now);
+        if expired && !credentials.has_refresh_token {
+            issues.push(HealthIssue::TokenExpired);
+        }
+    } else if !credentials.has_password {
+        issues.push(HealthIssue::MissingCredentials);
+    }
+
+    let failing = folders.iter().filter(|f| f.error_count > 0).count();
+    if failing > 0 {
+        issues.push(HealthIssue::FolderErrors { folders: failing });
+    }
+
+    let last_sync_at = folders.iter().filter_map(|f| f.last_sync_at).max();
+    let stale = last_sync_at.is_none_or(|at| now - at > Duration::hours(STALE_SYNC_HOURS));
+    if sync_enabled && !folders.is_empty() && stale {
+        issues.push(HealthIssue::SyncStale { last_sync_at });
+    }
+
+    if failed_operations > 0 {
+        issues.push(HealthIssue::FailedOperations {
+            count: failed_operations,
+        });
+    }
+
+    if let Some(quota) = quota {
+        if let (Some(used), Some(limit)) = (quota.storage_used, quota.storage_limit) {
+            let percent = if limit > 0 { used * 100 / limit } else { 0 };
+            if percent >= QUOTA_WARNING_PERCENT {
+                issues.push(HealthIssue::QuotaNearlyFull { percent });
+            }
+        }
+    }
+    if let Some(error) = quota_error {
+        issues.push(HealthIssue::QuotaUnavailable { error });
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(last_sync_at: Option<DateTime<Utc>>, error_count: i64) -> FolderHealth {
+        FolderHealth {
+            folder_id: Uuid::now_v7(),
+            folder_name: "INBOX".to_string(),
+            sync_status: if error_count > 0 { "error" } else { "idle" }.to_string(),
+            last_sync_at,
+            error_count,
+            error_message: None,
+        }
+    }
+
+    fn oauth2(expires_at: DateTime<Utc>, has_refresh_token: bool) -> CredentialHealth {
+        CredentialHealth {
+            uses_oauth2: true,
+            has_oauth2_token: true,
+            token_expires_at: Some(expires_at),
+            has_refresh_token,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_assess_healthy_account() {
+        let now = Utc::now();
+        let issues = assess(
+            &oauth2(now - Duration::minutes(5), true),
+            &[folder(Some(now - Duration::minutes(10)), 0)],
+            0,
+            None,
+            None,
+            true,
+            now,
+        );
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_assess_reports_issues() {
+        let now = Utc::now();
+        let quota = AccountQuota {
+            account_id: Uuid::now_v7(),
+            storage_used: Some(95),
+            storage_limit: Some(100),
+            message_count: None,
+            message_limit: None,
+            updated_at: now,
+        };
+        let issues = assess(
+            &oauth2(now - Duration::minutes(5), false),
+            &[folder(Some(now - Duration::days(3)), 4), folder(None, 0)],
+            2,
+            Some(&quota),
+            None,
+            true,
+            now,
+        );
+
+        assert_eq!(
+            issues,
+            vec![
+                HealthIssue::TokenExpired,
+                HealthIssue::FolderErrors { folders: 1 },
+                HealthIssue::SyncStale {
+                    last_sync_at: Some(now - Duration::days(3))
+                },
+                HealthIssue::FailedOperations { count: 2 },
+                HealthIssue::QuotaNearlyFull { percent: 95 },
+            ]
+        );
+        assert_eq!(issues[0].severity(), HealthStatus::Error);
+    }
+
+    #[test]
+    fn test_assess_password_account() {
+        let now = Utc::now();
+        let issues = assess(
+            &CredentialHealth::default(),
+            &[],
+            0,
+            None,
+            Some("Connection refused".to_string()),
+            false,
+            now,
+        );
+        assert_eq!(
+            issues,
+            vec![
+                HealthIssue::MissingCredentials,
+                HealthIssue::QuotaUnavailable {
+                    error: "Connection refused".to_string()
+                },
+            ]
+        );
+    }
+}
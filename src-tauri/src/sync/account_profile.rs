@@ -0,0 +1,104 @@
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::account::{Account, AccountType};
+use crate::database::repositories::{AccountRepository, SqliteAccountRepository};
+use crate::sync::auth::CredentialStore;
+use crate::sync::providers::{gmail::GmailProvider, office365::Office365Provider};
+
+/// Pulls the owner's display name, photo, timezone and locale from the provider
+/// and stores them on the account record.
+///
+/// Returns `Ok(None)` for IMAP-based accounts, which have no profile API.
+pub async fn refresh_account_profile(
+    pool: &SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    account_id: Uuid,
+    photo_dir: &Path,
+) -> Result<Option<Account>, String> {
+    let account_repo = SqliteAccountRepository::new(pool.clone());
+    let account = account_repo
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to load account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    let profile = match account.account_type {
+        AccountType::Gmail => {
+            GmailProvider::new(account.id, credential_store)
+                .map_err(|e| e.to_string())?
+                .fetch_owner_profile()
+                .await
+        }
+        AccountType::Office365 => {
+            Office365Provider::new(account.id, credential_store)
+                .map_err(|e| e.to_string())?
+                .fetch_owner_profile()
+                .await
+        }
+        AccountType::Apple | AccountType::Imap => return Ok(None),
+    }
+    .map_err(|e| format!("Failed to fetch profile: {}", e))?;
+
+    let photo_path = match &profile.photo {
+        Some(photo) => save_photo(photo_dir, account.id, photo).await,
+        None => None,
+    };
+
+    account_repo
+        .update_profile(
+            account.id,
+            profile.display_name.as_deref(),
+            photo_path.as_deref(),
+            profile.timezone.as_deref(),
+            profile.locale.as_deref(),
+        )
+        .await
+        .map_err(|e| format!("Failed to store profile: {}", e))?;
+
+    log::info!(
+        "[AccountProfile] Updated profile for account {} (photo: {}, timezone: {:?}, locale: {:?})",
+        account.id,
+        photo_path.is_some(),
+        profile.timezone,
+        profile.locale
+    );
+
+    account_repo
+        .find_by_id(account.id)
+        .await
+        .map_err(|e| format!("Failed to reload account: {}", e))
+}
+
+async fn save_photo(
+    photo_dir: &Path,
+    account_id: Uuid,
+    (bytes, content_type): &(Vec<u8>, String),
+) -> Option<String> {
+    let ext = match content_type.as_str() {
+        ct if ct.contains("png") => "png",
+        ct if ct.contains("webp") => "webp",
+        ct if ct.contains("gif") => "gif",
+        _ => "jpg",
+    };
+
+    if let Err(e) = tokio::fs::create_dir_all(photo_dir).await {
+        log::warn!("[AccountProfile] Could not create photo directory: {}", e);
+        return None;
+    }
+
+    let path = photo_dir.join(format!("{}.{}", account_id, ext));
+    match tokio::fs::write(&path, bytes).await {
+        Ok(()) => Some(path.to_string_lossy().to_string()),
+        Err(e) => {
+            log::warn!(
+                "[AccountProfile] Failed to save photo for account {}: {}",
+                account_id,
+                e
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use uuid::Uuid;
+
+use super::error::{SyncError, SyncResult};
+use super::storage::StorageWriter;
+
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "attachment:download-progress";
+
+/// Minimum time between two progress events of one download
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadStatus {
+    Downloading,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub attachment_id: Uuid,
+    pub email_id: Uuid,
+    pub downloaded: u64,
+    /// Size announced by the provider, if any
+    pub total: Option<u64>,
+    pub status: DownloadStatus,
+    pub error: Option<String>,
+}
+
+/// Downloads in flight, so they can be cancelled from the UI
+#[derive(Default)]
+pub struct AttachmentDownloads {
+    active: Mutex<HashMap<Uuid, Arc<AtomicBool>>>,
+}
+
+impl AttachmentDownloads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a download and return its cancellation flag
+    pub fn start(&self, attachment_id: Uuid) -> SyncResult<Arc<AtomicBool>> {
+        let mut active = self.active.lock().unwrap();
+        if active.contains_key(&attachment_id) {
+            return Err(SyncError::AttachmentError(format!(
+                "Attachment {} is already being downloaded",
+                attachment_id
+            )));
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        active.insert(attachment_id, Arc::clone(&cancelled));
+        Ok(cancelled)
+    }
+
+    pub fn finish(&self, attachment_id: Uuid) {
+        self.active.lock().unwrap().remove(&attachment_id);
+    }
+
+    /// Ask a running download to stop. Returns false if none was running.
+    pub fn cancel(&self, attachment_id: Uuid) -> bool {
+        match self.active.lock().unwrap().get(&attachment_id) {
+            Some(cancelled) => {
+                cancelled.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Receives attachment content from a provider chunk by chunk, writes it to
+/// the attachment cache and reports progress
+pub struct AttachmentSink {
+    writer: StorageWriter,
+    hasher: md5::Context,
+    cancelled: Arc<AtomicBool>,
+    progress: DownloadProgress,
+    app_handle: Option<tauri::AppHandle>,
+    last_emit: Option<Instant>,
+}
+
+impl AttachmentSink {
+    pub fn new(
+        writer: StorageWriter,
+        attachment_id: Uuid,
+        email_id: Uuid,
+        total: Option<u64>,
+        cancelled: Arc<AtomicBool>,
+        app_handle: Option<tauri::AppHandle>,
+    ) -> Self {
+        Self {
+            writer,
+            hasher: md5::Context::new(),
+            cancelled,
+            progress: DownloadProgress {
+                attachment_id,
+                email_id,
+                downloaded: 0,
+                total,
+                status: DownloadStatus::Downloading,
+                error: None,
+            },
+            app_handle,
+            last_emit: None,
+        }
+    }
+
+    /// Size reported by the server once the response arrived
+    pub fn set_total(&mut self, total: u64) {
+        self.progress.total = Some(total);
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> SyncResult<()> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(SyncError::Cancelled(format!(
+                "Download of attachment {} was cancelled",
+                self.progress.attachment_id
+            )));
+        }
+
+        self.writer.write(chunk).await?;
+        self.hasher.consume(chunk);
+        self.progress.downloaded += chunk.len() as u64;
+
+        if self
+            .last_emit
+            .is_none_or(|last_emit| last_emit.elapsed() >= PROGRESS_INTERVAL)
+        {
+            self.last_emit = Some(Instant::now());
+            self.emit();
+        }
+
+        Ok(())
+    }
+
+    pub fn downloaded(&self) -> u64 {
+        self.progress.downloaded
+    }
+
+    /// Finish the file and return it with the MD5 hash of its content
+    pub fn finish(mut self) -> (StorageWriter, String, DownloadProgress) {
+        self.progress.status = DownloadStatus::Completed;
+        self.progress.total = Some(self.progress.downloaded);
+        let hash = format!("{:x}", self.hasher.finalize());
+        (self.writer, hash, self.progress)
+    }
+
+    /// Remove the partial file and report why the download stopped
+    pub async fn abort(mut self, error: &SyncError) {
+        self.progress.status = match error {
+            SyncError::Cancelled(_) => DownloadStatus::Cancelled,
+            _ => DownloadStatus::Failed,
+        };
+        self.progress.error = Some(error.to_string());
+        self.emit();
+
+        if let Err(e) = self.writer.discard().await {
+            log::warn!(
+                "[AttachmentDownload] Failed to remove partial download of {}: {}",
+                self.progress.attachment_id,
+                e
+            );
+        }
+    }
+
+    fn emit(&self) {
+        emit_progress(self.app_handle.as_ref(), &self.progress);
+    }
+}
+
+pub fn emit_progress(app_handle: Option<&tauri::AppHandle>, progress: &DownloadProgress) {
+    if let Some(app_handle) = app_handle {
+        if let Err(e) = app_handle.emit(DOWNLOAD_PROGRESS_EVENT, progress) {
+            log::error!("Failed to emit event '{}': {}", DOWNLOAD_PROGRESS_EVENT, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::storage::LocalFileStorage;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_sink_cancellation() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::new(temp_dir.path().to_path_buf());
+        let downloads = AttachmentDownloads::new();
+        let attachment_id = Uuid::now_v7();
+
+        let cancelled = downloads.start(attachment_id).unwrap();
+        assert!(downloads.start(attachment_id).is_err());
+
+        let writer = storage
+            .create_writer(Path::new("a/b/file.bin"))
+            .await
+            .unwrap();
+        let mut sink = AttachmentSink::new(
+            writer,
+            attachment_id,
+            Uuid::now_v7(),
+            Some(10),
+            cancelled,
+            None,
+        );
+        sink.write(b"12345").await.unwrap();
+        assert_eq!(sink.downloaded(), 5);
+
+        assert!(downloads.cancel(attachment_id));
+        let error = sink.write(b"67890").await.unwrap_err();
+        assert!(matches!(error, SyncError::Cancelled(_)));
+        sink.abort(&error).await;
+
+        downloads.finish(attachment_id);
+        assert!(!downloads.cancel(attachment_id));
+        assert!(!temp_dir.path().join("a/b/file.bin.part").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sink_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::new(temp_dir.path().to_path_buf());
+        let writer = storage.create_writer(Path::new("file.txt")).await.unwrap();
+
+        let mut sink = AttachmentSink::new(
+            writer,
+            Uuid::now_v7(),
+            Uuid::now_v7(),
+            None,
+            Arc::new(AtomicBool::new(false)),
+            None,
+        );
+        sink.write(b"Hello, ").await.unwrap();
+        sink.write(b"World!").await.unwrap();
+
+        let (writer, hash, progress) = sink.finish();
+        writer.commit().await.unwrap();
+        assert_eq!(hash, format!("{:x}", md5::compute(b"Hello, World!")));
+        assert_eq!(progress.total, Some(13));
+        assert_eq!(
+            std::fs::read(temp_dir.path().join("file.txt")).unwrap(),
+            b"Hello, World!"
+        );
+    }
+}
@@ -2,10 +2,15 @@ use sqlx::SqlitePool;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use super::attachment_policy::AttachmentDownloadPolicy;
 use super::error::{SyncError, SyncResult};
-use super::storage::{FileStorage, PathGenerator};
+use super::storage::{FileStorage, LocalFileStorage, PathGenerator, StorageWriter};
 use super::types::SyncAttachment;
 
+/// Hash prefix of attachments listed from a message's structure before its
+/// content was downloaded, followed by the MIME part
+pub const STRUCTURE_HASH_PREFIX: &str = "structure:";
+
 /// AttachmentHandler coordinates attachment operations between storage and database
 /// Follows Single Responsibility and Dependency Inversion principles
 pub struct AttachmentHandler<S: FileStorage> {
@@ -20,20 +25,32 @@ impl<S: FileStorage> AttachmentHandler<S> {
 
     /// Process attachments for an email
     /// - Saves attachment metadata to database
-    /// - Caches attachment data if present (IMAP case)
-    /// Returns list of (attachment_id, is_inline, has_data) tuples
+    /// - Caches attachment data if present (IMAP case) and allowed by `policy`
+    /// Returns list of (attachment_id, is_inline) tuples
     pub async fn process_attachments(
         &self,
         email_id: Uuid,
         account_id: Uuid,
         attachments: &[SyncAttachment],
+        policy: AttachmentDownloadPolicy,
     ) -> SyncResult<Vec<(Uuid, bool)>> {
         let mut result = Vec::new();
 
+        // Attachments with content replace the ones listed from the structure
+        if attachments
+            .iter()
+            .any(|attachment| !attachment.hash.starts_with(STRUCTURE_HASH_PREFIX))
+        {
+            self.remove_structure_attachments(email_id).await?;
+        }
+
         for attachment in attachments {
             let attachment_id = self.upsert_attachment(email_id, attachment).await?;
 
-            if let Some(data) = &attachment.data {
+            if let (Some(data), true) = (
+                &attachment.data,
+                policy.should_download(attachment.is_inline, attachment.size),
+            ) {
                 self.cache_attachment(
                     attachment_id,
                     account_id,
@@ -50,6 +67,21 @@ impl<S: FileStorage> AttachmentHandler<S> {
         Ok(result)
     }
 
+    async fn remove_structure_attachments(&self, email_id: Uuid) -> SyncResult<()> {
+        let email_id_str = email_id.to_string();
+        let pattern = format!("{}%", STRUCTURE_HASH_PREFIX);
+        sqlx::query!(
+            "DELETE FROM attachments WHERE email_id = ? AND hash LIKE ?",
+            email_id_str,
+            pattern
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
     /// Upsert an attachment into the database
     /// Only stores metadata - cache_path is set to None initially
     /// Checks for duplicates by content_id first (for inline attachments), then by email_id+hash
@@ -322,3 +354,58 @@ impl<S: FileStorage> AttachmentHandler<S> {
         })
     }
 }
+
+impl AttachmentHandler<LocalFileStorage> {
+    /// Open a cache file for streaming an attachment into. Returns the cache
+    /// path to pass to [`Self::complete_download`] along with the writer.
+    pub async fn begin_download(
+        &self,
+        account_id: Uuid,
+        email_id: Uuid,
+        filename: &str,
+    ) -> SyncResult<(String, StorageWriter)> {
+        let cache_path = PathGenerator::generate_cache_path(
+            &account_id.to_string(),
+            &email_id.to_string(),
+            filename,
+        );
+        let writer = self
+            .storage
+            .create_writer(&PathGenerator::cache_path_to_pathbuf(&cache_path))
+            .await?;
+
+        Ok((cache_path, writer))
+    }
+
+    /// Move a streamed attachment into place and mark it cached. Downloads
+    /// are requested by opening the attachment, so it counts as opened.
+    pub async fn complete_download(
+        &self,
+        attachment_id: Uuid,
+        cache_path: &str,
+        writer: StorageWriter,
+        content_hash: &str,
+    ) -> SyncResult<()> {
+        writer.commit().await?;
+
+        let attachment_id_str = attachment_id.to_string();
+        sqlx::query!(
+            "UPDATE attachments SET cache_path = ?, is_cached = 1, hash = ?, last_opened_at = CURRENT_TIMESTAMP WHERE id = ?",
+            cache_path,
+            content_hash,
+            attachment_id_str
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::debug!(
+            "Cached streamed attachment {} to {} with hash {}",
+            attachment_id,
+            cache_path,
+            content_hash
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//! Which attachment contents are downloaded while syncing. Everything else is
+//! only stored as metadata and fetched when the user opens it.
+use super::bandwidth;
+use crate::config::Settings;
+
+pub const POLICY_SETTING: &str = "email.attachments.downloadPolicy";
+pub const SIZE_THRESHOLD_SETTING: &str = "email.attachments.sizeThreshold";
+
+/// Size threshold in MB used when the setting is missing
+const DEFAULT_SIZE_THRESHOLD_MB: i64 = 5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AttachmentDownloadPolicy {
+    /// Only metadata; every attachment is downloaded on demand
+    MetadataOnly,
+    /// Inline attachments, so message bodies render offline
+    #[default]
+    InlineOnly,
+    /// Inline attachments and regular ones up to the given size in bytes
+    SizeThreshold(i64),
+    /// Every attachment
+    Full,
+}
+
+impl AttachmentDownloadPolicy {
+    /// Parse the policy setting; unknown values fall back to the default
+    pub fn parse(policy: &str, size_threshold_mb: i64) -> Self {
+        match policy {
+            "metadata" => Self::MetadataOnly,
+            "inline" => Self::InlineOnly,
+            "threshold" => Self::SizeThreshold(size_threshold_mb.max(0) * 1024 * 1024),
+            "full" => Self::Full,
+            _ => Self::default(),
+        }
+    }
+
+    /// Policy from the settings. On a metered connection only metadata is
+    /// synced regardless of the policy.
+    pub fn from_settings(settings: Option<&Settings>) -> Self {
+        let Some(settings) = settings else {
+            return Self::default();
+        };
+        if bandwidth::is_metered(Some(settings)) {
+            return Self::MetadataOnly;
+        }
+
+        match settings.get::<String>(POLICY_SETTING) {
+            Ok(policy) => Self::parse(
+                &policy,
+                settings
+                    .get::<i64>(SIZE_THRESHOLD_SETTING)
+                    .unwrap_or(DEFAULT_SIZE_THRESHOLD_MB),
+            ),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Whether an attachment's content should be downloaded during sync
+    pub fn should_download(&self, is_inline: bool, size: i64) -> bool {
+        match self {
+            Self::MetadataOnly => false,
+            Self::InlineOnly => is_inline,
+            Self::SizeThreshold(max_size) => is_inline || size <= *max_size,
+            Self::Full => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            AttachmentDownloadPolicy::parse("metadata", 5),
+            AttachmentDownloadPolicy::MetadataOnly
+        );
+        assert_eq!(
+            AttachmentDownloadPolicy::parse("threshold", 2),
+            AttachmentDownloadPolicy::SizeThreshold(2 * 1024 * 1024)
+        );
+        assert_eq!(
+            AttachmentDownloadPolicy::parse("full", 5),
+            AttachmentDownloadPolicy::Full
+        );
+        assert_eq!(
+            AttachmentDownloadPolicy::parse("everything", 5),
+            AttachmentDownloadPolicy::InlineOnly
+        );
+    }
+
+    #[test]
+    fn test_should_download() {
+        let large = 20 * 1024 * 1024;
+
+        assert!(!AttachmentDownloadPolicy::MetadataOnly.should_download(true, 10));
+        assert!(AttachmentDownloadPolicy::InlineOnly.should_download(true, large));
+        assert!(!AttachmentDownloadPolicy::InlineOnly.should_download(false, 10));
+
+        let threshold = AttachmentDownloadPolicy::SizeThreshold(1024);
+        assert!(threshold.should_download(false, 1024));
+        assert!(!threshold.should_download(false, 1025));
+        assert!(threshold.should_download(true, large));
+
+        assert!(AttachmentDownloadPolicy::Full.should_download(false, large));
+    }
+}
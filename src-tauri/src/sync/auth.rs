@@ -1,4 +1,5 @@
 use keyring::Entry;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -7,9 +8,69 @@ use uuid::Uuid;
 use super::encrypted_store::EncryptedCredentialStore;
 use super::error::{SyncError, SyncResult};
 use super::types::{ImapCredentials, OAuth2Credentials};
+use crate::config::settings::Settings;
+use crate::services::dkim::{DkimKey, DkimKeys};
+use crate::services::smime::SmimeIdentity;
 
 const KEYRING_SERVICE: &str = "com.ravn.email";
 
+/// Keep OAuth refresh tokens and IMAP passwords in the OS keychain
+pub const SYSTEM_KEYCHAIN_SETTING: &str = "storage.systemKeychain";
+/// Keychain entries of secrets split off the database credentials
+const OAUTH2_SECRET: &str = "oauth2_refresh";
+const IMAP_SECRET: &str = "imap_password";
+
+/// Full mail access, the only Gmail scope that covers SMTP
+const GMAIL_SMTP_SCOPE: &str = "https://mail.google.com/";
+/// Exchange Online's SMTP, a different resource than Microsoft Graph
+const OFFICE365_SMTP_SCOPE: &str = "https://outlook.office.com/SMTP.Send";
+
+/// Access an OAuth account can be granted after sign-in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthScope {
+    Contacts,
+    Calendar,
+}
+
+impl OAuthScope {
+    /// The provider's scope for this access
+    pub fn provider_scope(self, provider: &str) -> Option<&'static str> {
+        match (provider, self) {
+            ("gmail", Self::Contacts) => Some("https://www.googleapis.com/auth/contacts.readonly"),
+            ("gmail", Self::Calendar) => Some("https://www.googleapis.com/auth/calendar.events"),
+            ("office365", Self::Contacts) => Some("https://graph.microsoft.com/Contacts.Read"),
+            ("office365", Self::Calendar) => {
+                Some("https://graph.microsoft.com/Calendars.ReadWrite")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Keychain entry holding a secret of the account
+fn keychain_entry(kind: &str, account_id: Uuid) -> keyring::Result<Entry> {
+    Entry::new(KEYRING_SERVICE, &format!("{}_account_{}", kind, account_id))
+}
+
+/// Secret of the account in the keychain, if it has one and the keychain
+/// can be read
+fn keychain_secret(kind: &str, account_id: Uuid) -> Option<String> {
+    match keychain_entry(kind, account_id).and_then(|entry| entry.get_password()) {
+        Ok(secret) => Some(secret),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            log::warn!(
+                "Failed to read {} secret of account {} from system keychain: {}",
+                kind,
+                account_id,
+                e
+            );
+            None
+        }
+    }
+}
+
 /// Detects if system keyring is using mock credentials
 fn _is_keyring_mock() -> bool {
     let test_entry = Entry::new(KEYRING_SERVICE, "__ravn_keyring_test__");
@@ -29,9 +90,17 @@ fn _is_keyring_mock() -> bool {
 }
 
 /// Secure credential storage using OS keyring with encrypted database fallback
+///
+/// Credentials are kept in the encrypted database. With the system keychain
+/// setting on, their long-lived secrets, OAuth refresh tokens and IMAP
+/// passwords, are moved to the OS keychain (macOS Keychain, Windows
+/// Credential Manager or the Secret Service) and left out of the database
+/// copy. Turning the setting on or off moves the stored secrets once, through
+/// [`CredentialStore::migrate_secrets`]; reads leave them where they are.
 pub struct CredentialStore {
     encrypted_store: Option<Arc<RwLock<EncryptedCredentialStore>>>,
     use_encrypted_fallback: bool,
+    settings: Option<Arc<Settings>>,
 }
 
 impl CredentialStore {
@@ -58,103 +127,316 @@ impl CredentialStore {
         Self {
             encrypted_store,
             use_encrypted_fallback,
+            settings: None,
         }
     }
 
+    /// Settings deciding whether secrets go to the system keychain
+    pub fn with_settings(mut self, settings: Arc<Settings>) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    fn use_keychain(&self) -> bool {
+        self.settings.as_ref().is_some_and(|settings| {
+            settings
+                .get::<bool>(SYSTEM_KEYCHAIN_SETTING)
+                .unwrap_or(false)
+        })
+    }
+
+    fn encrypted_store(&self) -> SyncResult<&Arc<RwLock<EncryptedCredentialStore>>> {
+        self.encrypted_store
+            .as_ref()
+            .ok_or_else(|| SyncError::KeyringError("No credential storage available".to_string()))
+    }
+
     /// Store OAuth2 credentials securely
     pub async fn store_oauth2(
         &self,
         account_id: Uuid,
         credentials: &OAuth2Credentials,
     ) -> SyncResult<()> {
+        let store = self.encrypted_store()?.read().await;
+        let in_keychain = credentials
+            .refresh_token
+            .as_ref()
+            .is_some_and(|token| self.keep_secret(OAUTH2_SECRET, account_id, token));
+
+        if in_keychain {
+            let credentials = OAuth2Credentials {
+                refresh_token: None,
+                ..credentials.clone()
+            };
+            store.store_oauth2(account_id, &credentials).await
+        } else {
+            store.store_oauth2(account_id, credentials).await
+        }
+    }
+
+    /// Retrieve OAuth2 credentials
+    pub async fn get_oauth2(&self, account_id: Uuid) -> SyncResult<OAuth2Credentials> {
+        let mut credentials = self
+            .encrypted_store()?
+            .read()
+            .await
+            .get_oauth2(account_id)
+            .await?;
+
+        if credentials.refresh_token.is_none() {
+            credentials.refresh_token = keychain_secret(OAUTH2_SECRET, account_id);
+        }
+
+        Ok(credentials)
+    }
+
+    /// Store IMAP credentials securely
+    pub async fn store_imap(
+        &self,
+        account_id: Uuid,
+        credentials: &ImapCredentials,
+    ) -> SyncResult<()> {
+        let store = self.encrypted_store()?.read().await;
+
+        // An empty password in the database stands for the keychain's
+        if self.keep_secret(IMAP_SECRET, account_id, &credentials.password) {
+            let credentials = ImapCredentials {
+                username: credentials.username.clone(),
+                password: String::new(),
+            };
+            store.store_imap(account_id, &credentials).await
+        } else {
+            store.store_imap(account_id, credentials).await
+        }
+    }
+
+    /// Retrieve IMAP credentials
+    pub async fn get_imap(&self, account_id: Uuid) -> SyncResult<ImapCredentials> {
+        let mut credentials = self
+            .encrypted_store()?
+            .read()
+            .await
+            .get_imap(account_id)
+            .await?;
+
+        if credentials.password.is_empty() {
+            credentials.password = keychain_secret(IMAP_SECRET, account_id).ok_or_else(|| {
+                SyncError::AuthenticationError(format!(
+                    "No IMAP password found for account {}",
+                    account_id
+                ))
+            })?;
+        }
+
+        Ok(credentials)
+    }
+
+    /// Move the stored OAuth refresh tokens and IMAP passwords to the keychain
+    /// or back into the database, following the keychain setting. Run when the
+    /// setting is changed.
+    pub async fn migrate_secrets(&self) -> SyncResult<()> {
+        let accounts = self.encrypted_store()?.read().await.accounts().await?;
+
+        for (account_id, credential_type) in accounts {
+            let result = match credential_type.as_str() {
+                "oauth2" => match self.get_oauth2(account_id).await {
+                    Ok(credentials) => self.store_oauth2(account_id, &credentials).await,
+                    Err(e) => Err(e),
+                },
+                "imap" => match self.get_imap(account_id).await {
+                    Ok(credentials) => self.store_imap(account_id, &credentials).await,
+                    Err(e) => Err(e),
+                },
+                _ => continue,
+            };
+
+            if let Err(e) = result {
+                log::warn!(
+                    "Failed to move {} secret of account {}: {}",
+                    credential_type,
+                    account_id,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Put `secret` into the keychain if it is turned on. Otherwise, or when
+    /// the keychain cannot be written, an earlier keychain copy is removed so
+    /// the database holds the secret. Whether the keychain took it.
+    fn keep_secret(&self, kind: &str, account_id: Uuid, secret: &str) -> bool {
+        if self.use_keychain() {
+            match keychain_entry(kind, account_id).and_then(|entry| entry.set_password(secret)) {
+                Ok(()) => {
+                    log::info!(
+                        "Stored {} secret in system keychain for account {}",
+                        kind,
+                        account_id
+                    );
+                    return true;
+                }
+                Err(e) => log::warn!(
+                    "Failed to store {} secret in system keychain for account {}, keeping it in the database: {}",
+                    kind,
+                    account_id,
+                    e
+                ),
+            }
+        }
+
+        if let Ok(entry) = keychain_entry(kind, account_id) {
+            let _ = entry.delete_credential();
+        }
+        false
+    }
+
+    /// Store the account's S/MIME signing identity (certificate and private key)
+    pub async fn store_smime(&self, account_id: Uuid, identity: &SmimeIdentity) -> SyncResult<()> {
         if self.use_encrypted_fallback {
             if let Some(store) = &self.encrypted_store {
                 let store = store.read().await;
-                return store.store_oauth2(account_id, credentials).await;
+                return store.store_smime(account_id, identity).await;
             }
             return Err(SyncError::KeyringError(
                 "No credential storage available".to_string(),
             ));
         }
 
-        let key = format!("oauth2_account_{}", account_id.to_string());
+        let key = format!("smime_account_{}", account_id);
         let entry = Entry::new(KEYRING_SERVICE, &key)?;
-        let json = serde_json::to_string(credentials)?;
+        let json = serde_json::to_string(identity)?;
         entry.set_password(&json)?;
         log::info!(
-            "Stored OAuth2 credentials in system keyring for account {}",
+            "Stored S/MIME identity in system keyring for account {}",
             account_id
         );
         Ok(())
     }
 
-    /// Retrieve OAuth2 credentials
-    pub async fn get_oauth2(&self, account_id: Uuid) -> SyncResult<OAuth2Credentials> {
+    /// Retrieve the account's S/MIME signing identity
+    pub async fn get_smime(&self, account_id: Uuid) -> SyncResult<SmimeIdentity> {
         if self.use_encrypted_fallback {
             if let Some(store) = &self.encrypted_store {
                 let store = store.read().await;
-                return store.get_oauth2(account_id).await;
+                return store.get_smime(account_id).await;
             }
             return Err(SyncError::KeyringError(
                 "No credential storage available".to_string(),
             ));
         }
 
-        let key = format!("oauth2_account_{}", account_id.to_string());
+        let key = format!("smime_account_{}", account_id);
         let entry = Entry::new(KEYRING_SERVICE, &key)?;
         let json = entry.get_password()?;
-        let credentials: OAuth2Credentials = serde_json::from_str(&json)?;
-        Ok(credentials)
+        let identity: SmimeIdentity = serde_json::from_str(&json)?;
+        Ok(identity)
     }
 
-    /// Store IMAP credentials securely
-    pub async fn store_imap(
-        &self,
-        account_id: Uuid,
-        credentials: &ImapCredentials,
-    ) -> SyncResult<()> {
+    /// Delete the account's S/MIME signing identity
+    pub async fn delete_smime(&self, account_id: Uuid) -> SyncResult<()> {
         if self.use_encrypted_fallback {
             if let Some(store) = &self.encrypted_store {
                 let store = store.read().await;
-                return store.store_imap(account_id, credentials).await;
+                return store.delete_smime(account_id).await;
             }
             return Err(SyncError::KeyringError(
                 "No credential storage available".to_string(),
             ));
         }
 
-        let key = format!("imap_account_{}", account_id.to_string());
+        let key = format!("smime_account_{}", account_id);
+        if let Ok(entry) = Entry::new(KEYRING_SERVICE, &key) {
+            let _ = entry.delete_credential();
+        }
+
+        log::info!("Deleted S/MIME identity for account {}", account_id);
+        Ok(())
+    }
+
+    /// Store the account's DKIM signing keys, replacing all previous ones
+    async fn store_dkim_keys(&self, account_id: Uuid, keys: &DkimKeys) -> SyncResult<()> {
+        if self.use_encrypted_fallback {
+            if let Some(store) = &self.encrypted_store {
+                let store = store.read().await;
+                return store.store_dkim(account_id, keys).await;
+            }
+            return Err(SyncError::KeyringError(
+                "No credential storage available".to_string(),
+            ));
+        }
+
+        let key = format!("dkim_account_{}", account_id);
         let entry = Entry::new(KEYRING_SERVICE, &key)?;
-        let json = serde_json::to_string(credentials)?;
+        let json = serde_json::to_string(keys)?;
         entry.set_password(&json)?;
         log::info!(
-            "Stored IMAP credentials in system keyring for account {}",
+            "Stored DKIM keys in system keyring for account {}",
             account_id
         );
         Ok(())
     }
 
-    /// Retrieve IMAP credentials
-    pub async fn get_imap(&self, account_id: Uuid) -> SyncResult<ImapCredentials> {
+    /// The account's DKIM signing keys, by sending address
+    pub async fn get_dkim_keys(&self, account_id: Uuid) -> SyncResult<DkimKeys> {
         if self.use_encrypted_fallback {
             if let Some(store) = &self.encrypted_store {
                 let store = store.read().await;
-                return store.get_imap(account_id).await;
+                return store.get_dkim(account_id).await;
             }
             return Err(SyncError::KeyringError(
                 "No credential storage available".to_string(),
             ));
         }
 
-        let key = format!("imap_account_{}", account_id.to_string());
+        let key = format!("dkim_account_{}", account_id);
         let entry = Entry::new(KEYRING_SERVICE, &key)?;
-        let json = entry.get_password()?;
-        let credentials: ImapCredentials = serde_json::from_str(&json)?;
-        Ok(credentials)
+        match entry.get_password() {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(keyring::Error::NoEntry) => Ok(DkimKeys::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The DKIM signing key for mail sent as `address`, if one was set up
+    pub async fn get_dkim(&self, account_id: Uuid, address: &str) -> SyncResult<Option<DkimKey>> {
+        Ok(self
+            .get_dkim_keys(account_id)
+            .await?
+            .remove(&address.to_lowercase()))
+    }
+
+    /// Store the DKIM signing key for mail sent as `address`
+    pub async fn store_dkim(
+        &self,
+        account_id: Uuid,
+        address: &str,
+        key: DkimKey,
+    ) -> SyncResult<()> {
+        let mut keys = self.get_dkim_keys(account_id).await?;
+        keys.insert(address.to_lowercase(), key);
+        self.store_dkim_keys(account_id, &keys).await
+    }
+
+    /// Delete the DKIM signing key for mail sent as `address`
+    pub async fn delete_dkim(&self, account_id: Uuid, address: &str) -> SyncResult<()> {
+        let mut keys = self.get_dkim_keys(account_id).await?;
+        if keys.remove(&address.to_lowercase()).is_some() {
+            self.store_dkim_keys(account_id, &keys).await?;
+            log::info!("Deleted DKIM key for {} of account {}", address, account_id);
+        }
+        Ok(())
     }
 
     /// Delete credentials for an account
     pub async fn delete(&self, account_id: Uuid) -> SyncResult<()> {
+        for kind in [OAUTH2_SECRET, IMAP_SECRET] {
+            if let Ok(entry) = keychain_entry(kind, account_id) {
+                let _ = entry.delete_credential();
+            }
+        }
+
         if self.use_encrypted_fallback {
             if let Some(store) = &self.encrypted_store {
                 let store = store.read().await;
@@ -175,6 +457,16 @@ impl CredentialStore {
             let _ = entry.delete_credential();
         }
 
+        let smime_key = format!("smime_account_{}", account_id);
+        if let Ok(entry) = Entry::new(KEYRING_SERVICE, &smime_key) {
+            let _ = entry.delete_credential();
+        }
+
+        let dkim_key = format!("dkim_account_{}", account_id);
+        if let Ok(entry) = Entry::new(KEYRING_SERVICE, &dkim_key) {
+            let _ = entry.delete_credential();
+        }
+
         log::info!("Deleted credentials for account {}", account_id);
         Ok(())
     }
@@ -217,8 +509,8 @@ impl OAuth2Helper {
         redirect_uri: &str,
     ) -> SyncResult<(String, String, String)> {
         match provider {
-            "gmail" => Self::start_gmail_oauth2(redirect_uri),
-            "office365" => Self::start_office365_oauth2(redirect_uri),
+            "gmail" => Self::start_gmail_oauth2(redirect_uri, &[]),
+            "office365" => Self::start_office365_oauth2(redirect_uri, &[]),
             _ => Err(SyncError::NotSupported(format!(
                 "OAuth2 not supported for provider: {}",
                 provider
@@ -226,7 +518,41 @@ impl OAuth2Helper {
         }
     }
 
-    fn start_gmail_oauth2(redirect_uri: &str) -> SyncResult<(String, String, String)> {
+    /// Start an OAuth2 flow that grants an existing account `scopes` on top
+    /// of the ones it signed in with. Gmail adds them to the earlier grant
+    /// (incremental authorization); Microsoft consents to them incrementally
+    /// for the same app.
+    pub fn start_reauthorization(
+        provider: &str,
+        redirect_uri: &str,
+        scopes: &[OAuthScope],
+    ) -> SyncResult<(String, String, String)> {
+        let extra_scopes = scopes
+            .iter()
+            .map(|scope| {
+                scope.provider_scope(provider).ok_or_else(|| {
+                    SyncError::NotSupported(format!(
+                        "{:?} access is not supported for provider: {}",
+                        scope, provider
+                    ))
+                })
+            })
+            .collect::<SyncResult<Vec<_>>>()?;
+
+        match provider {
+            "gmail" => Self::start_gmail_oauth2(redirect_uri, &extra_scopes),
+            "office365" => Self::start_office365_oauth2(redirect_uri, &extra_scopes),
+            _ => Err(SyncError::NotSupported(format!(
+                "OAuth2 not supported for provider: {}",
+                provider
+            ))),
+        }
+    }
+
+    fn start_gmail_oauth2(
+        redirect_uri: &str,
+        extra_scopes: &[&str],
+    ) -> SyncResult<(String, String, String)> {
         use oauth2::basic::BasicClient;
         use oauth2::{
             AuthUrl, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge, RedirectUrl, Scope,
@@ -253,7 +579,7 @@ impl OAuth2Helper {
 
         let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
 
-        let (auth_url, csrf_token) = client
+        let mut request = client
             .authorize_url(CsrfToken::new_random)
             .add_scope(Scope::new(
                 "https://www.googleapis.com/auth/gmail.modify".to_string(),
@@ -261,8 +587,28 @@ impl OAuth2Helper {
             .add_scope(Scope::new(
                 "https://www.googleapis.com/auth/gmail.settings.basic".to_string(),
             ))
-            .set_pkce_challenge(pkce_challenge)
-            .url();
+            .add_scope(Scope::new(
+                "https://www.googleapis.com/auth/contacts.readonly".to_string(),
+            ))
+            .add_scope(Scope::new(
+                "https://www.googleapis.com/auth/userinfo.profile".to_string(),
+            ))
+            .add_scope(Scope::new(GMAIL_SMTP_SCOPE.to_string()))
+            .add_scopes(
+                extra_scopes
+                    .iter()
+                    .map(|scope| Scope::new(scope.to_string())),
+            )
+            .set_pkce_challenge(pkce_challenge);
+        if !extra_scopes.is_empty() {
+            // Keep the scopes granted before, and ask again so Google issues
+            // a refresh token covering the new ones
+            request = request
+                .add_extra_param("include_granted_scopes", "true")
+                .add_extra_param("access_type", "offline")
+                .add_extra_param("prompt", "consent");
+        }
+        let (auth_url, csrf_token) = request.url();
 
         Ok((
             auth_url.to_string(),
@@ -271,7 +617,10 @@ impl OAuth2Helper {
         ))
     }
 
-    fn start_office365_oauth2(redirect_uri: &str) -> SyncResult<(String, String, String)> {
+    fn start_office365_oauth2(
+        redirect_uri: &str,
+        extra_scopes: &[&str],
+    ) -> SyncResult<(String, String, String)> {
         use oauth2::basic::BasicClient;
         use oauth2::{
             AuthUrl, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge, RedirectUrl, Scope,
@@ -312,7 +661,21 @@ impl OAuth2Helper {
             .add_scope(Scope::new(
                 "https://graph.microsoft.com/Mail.Send".to_string(),
             ))
+            .add_scope(Scope::new(
+                "https://graph.microsoft.com/Contacts.Read".to_string(),
+            ))
+            .add_scope(Scope::new(
+                "https://graph.microsoft.com/User.Read".to_string(),
+            ))
+            .add_scope(Scope::new(
+                "https://graph.microsoft.com/MailboxSettings.Read".to_string(),
+            ))
             .add_scope(Scope::new("offline_access".to_string()))
+            .add_scopes(
+                extra_scopes
+                    .iter()
+                    .map(|scope| Scope::new(scope.to_string())),
+            )
             .set_pkce_challenge(pkce_challenge)
             .url();
 
@@ -465,6 +828,63 @@ impl OAuth2Helper {
         }
     }
 
+    /// An access token for sending over SMTP with XOAUTH2. Gmail's token
+    /// covers SMTP and is refreshed when expired. Office 365 issues tokens per
+    /// resource, so one for Exchange Online is requested with the refresh token.
+    pub async fn smtp_access_token(
+        provider: &str,
+        credentials: OAuth2Credentials,
+        credential_store: &CredentialStore,
+        account_id: Uuid,
+    ) -> SyncResult<String> {
+        match provider {
+            "gmail" => {
+                // Accounts signed in before SMTP was supported lack the scope
+                if !credentials.scopes.is_empty()
+                    && !credentials.scopes.iter().any(|s| s == GMAIL_SMTP_SCOPE)
+                {
+                    return Err(SyncError::AuthenticationError(
+                        "Sign in to Gmail again to allow sending over SMTP".to_string(),
+                    ));
+                }
+
+                let expired = credentials
+                    .expires_at
+                    .is_some_and(|expires_at| expires_at < chrono::Utc::now());
+                if !expired {
+                    return Ok(credentials.access_token);
+                }
+
+                let refresh_token = credentials.refresh_token.ok_or_else(|| {
+                    SyncError::AuthenticationError(
+                        "Token expired and no refresh token available".to_string(),
+                    )
+                })?;
+                let credentials = Self::refresh_gmail_token(&refresh_token).await?;
+                credential_store
+                    .store_oauth2(account_id, &credentials)
+                    .await?;
+                Ok(credentials.access_token)
+            }
+            "office365" => {
+                let refresh_token = credentials.refresh_token.ok_or_else(|| {
+                    SyncError::AuthenticationError(
+                        "No refresh token available. Please re-authenticate.".to_string(),
+                    )
+                })?;
+                // Not stored, it would replace the Graph token
+                let credentials =
+                    Self::exchange_office365_refresh_token(&refresh_token, &[OFFICE365_SMTP_SCOPE])
+                        .await?;
+                Ok(credentials.access_token)
+            }
+            _ => Err(SyncError::NotSupported(format!(
+                "OAuth2 not supported for provider: {}",
+                provider
+            ))),
+        }
+    }
+
     async fn refresh_gmail_token(refresh_token: &str) -> SyncResult<OAuth2Credentials> {
         use oauth2::basic::BasicClient;
         use oauth2::{ClientId, ClientSecret, RefreshToken, TokenResponse, TokenUrl};
@@ -506,8 +926,17 @@ impl OAuth2Helper {
     }
 
     async fn refresh_office365_token(refresh_token: &str) -> SyncResult<OAuth2Credentials> {
+        Self::exchange_office365_refresh_token(refresh_token, &[]).await
+    }
+
+    /// Redeem a refresh token, for the scopes granted at sign-in when `scopes`
+    /// is empty
+    async fn exchange_office365_refresh_token(
+        refresh_token: &str,
+        scopes: &[&str],
+    ) -> SyncResult<OAuth2Credentials> {
         use oauth2::basic::BasicClient;
-        use oauth2::{ClientId, ClientSecret, RefreshToken, TokenResponse, TokenUrl};
+        use oauth2::{ClientId, ClientSecret, RefreshToken, Scope, TokenResponse, TokenUrl};
 
         let client_id = env!("OFFICE365_CLIENT_ID").to_string();
         let client_secret = env!("OFFICE365_CLIENT_SECRET").to_string();
@@ -531,6 +960,7 @@ impl OAuth2Helper {
 
         let token_result = client
             .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
+            .add_scopes(scopes.iter().map(|scope| Scope::new(scope.to_string())))
             .request_async(oauth2::reqwest::async_http_client)
             .await
             .map_err(|e| SyncError::OAuth2Error(e.to_string()))?;
@@ -0,0 +1,500 @@
+//! Discovery of the IMAP and SMTP servers of an email address for manual
+//! account setup
+//!
+//! Sources are tried in about the order Thunderbird uses: the domain's own
+//! autoconfig file, the Thunderbird ISPDB (for the domain itself, then for
+//! the domain of its MX host, which covers hosted domains), Exchange
+//! autodiscover and finally DNS SRV records (RFC 6186).
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::types::TlsMode;
+use crate::services::recipient_validator::DOH_ENDPOINT;
+
+/// Per request, so a host that doesn't answer does not hold up the others
+const TIMEOUT: Duration = Duration::from_secs(5);
+const ISPDB_URL: &str = "https://autoconfig.thunderbird.net/v1.1/";
+
+const AUTODISCOVER_REQUEST_SCHEMA: &str =
+    "http://schemas.microsoft.com/exchange/autodiscover/outlook/requestschema/2006";
+const AUTODISCOVER_RESPONSE_SCHEMA: &str =
+    "http://schemas.microsoft.com/exchange/autodiscover/outlook/responseschema/2006a";
+
+/// DNS record types
+const MX: u16 = 15;
+const SRV: u16 = 33;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoverySource {
+    Autoconfig,
+    Ispdb,
+    Autodiscover,
+    Srv,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiscoveredServer {
+    pub host: String,
+    pub port: u16,
+    /// `None` for an unencrypted connection
+    pub tls_mode: Option<TlsMode>,
+    /// `None` when the source does not say, usually meaning the address
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiscoveredSettings {
+    pub source: DiscoverySource,
+    pub imap: Option<DiscoveredServer>,
+    pub smtp: Option<DiscoveredServer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Answer")]
+    answer: Option<Vec<DohAnswer>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+/// Look up the servers of `email`, `None` when no source knows them
+pub async fn discover(email: &str) -> Result<Option<DiscoveredSettings>, String> {
+    let email = email.trim();
+    let domain = email
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.to_ascii_lowercase())
+        .filter(|domain| domain.contains('.'))
+        .ok_or_else(|| format!("Invalid email address: {}", email))?;
+
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let found =
+        |source: DiscoverySource, (imap, smtp): Servers| DiscoveredSettings { source, imap, smtp };
+
+    for url in [
+        format!("https://autoconfig.{}/mail/config-v1.1.xml", domain),
+        format!(
+            "https://{}/.well-known/autoconfig/mail/config-v1.1.xml",
+            domain
+        ),
+    ] {
+        if let Some(servers) = autoconfig(&client, &url, email).await {
+            return Ok(Some(found(DiscoverySource::Autoconfig, servers)));
+        }
+    }
+
+    if let Some(servers) = autoconfig(&client, &format!("{}{}", ISPDB_URL, domain), email).await {
+        return Ok(Some(found(DiscoverySource::Ispdb, servers)));
+    }
+    if let Some(mx_domain) = mx_domain(&client, &domain).await.filter(|d| *d != domain) {
+        let url = format!("{}{}", ISPDB_URL, mx_domain);
+        if let Some(servers) = autoconfig(&client, &url, email).await {
+            return Ok(Some(found(DiscoverySource::Ispdb, servers)));
+        }
+    }
+
+    if let Some(servers) = autodiscover(&client, &domain, email).await {
+        return Ok(Some(found(DiscoverySource::Autodiscover, servers)));
+    }
+
+    if let Some(servers) = srv_records(&client, &domain).await {
+        return Ok(Some(found(DiscoverySource::Srv, servers)));
+    }
+
+    log::info!("No server settings found for {}", domain);
+    Ok(None)
+}
+
+type Servers = (Option<DiscoveredServer>, Option<DiscoveredServer>);
+
+/// Servers of a Thunderbird autoconfig file
+async fn autoconfig(client: &Client, url: &str, email: &str) -> Option<Servers> {
+    let response = client
+        .get(url)
+        .query(&[("emailaddress", email)])
+        .send()
+        .await
+        .map_err(|e| log::debug!("Autoconfig from {} failed: {}", url, e))
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+
+    let servers = parse_autoconfig(&response.text().await.ok()?, email);
+    (servers.0.is_some() || servers.1.is_some()).then_some(servers)
+}
+
+fn parse_autoconfig(xml: &str, email: &str) -> Servers {
+    let server = |fields: &HashMap<String, String>| {
+        Some(DiscoveredServer {
+            host: expand_placeholders(fields.get("hostname")?, email),
+            port: fields.get("port")?.parse().ok()?,
+            tls_mode: match fields.get("socketType")?.to_ascii_uppercase().as_str() {
+                "SSL" => Some(TlsMode::Implicit),
+                "STARTTLS" => Some(TlsMode::Starttls),
+                _ => None,
+            },
+            username: fields
+                .get("username")
+                .map(|username| expand_placeholders(username, email)),
+        })
+    };
+
+    // The first server of a kind is the preferred one
+    let blocks = xml_blocks(xml, &[b"incomingServer", b"outgoingServer"]);
+    let first = |kind: &str| {
+        blocks
+            .iter()
+            .filter(|fields| fields.get("type").map(String::as_str) == Some(kind))
+            .find_map(server)
+    };
+
+    (first("imap"), first("smtp"))
+}
+
+/// Servers of an Exchange autodiscover (POX) response. Servers that ask for
+/// authentication before answering are skipped.
+async fn autodiscover(client: &Client, domain: &str, email: &str) -> Option<Servers> {
+    let request = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<Autodiscover xmlns="{}">
+  <Request>
+    <EMailAddress>{}</EMailAddress>
+    <AcceptableResponseSchema>{}</AcceptableResponseSchema>
+  </Request>
+</Autodiscover>"#,
+        AUTODISCOVER_REQUEST_SCHEMA,
+        quick_xml::escape::escape(email),
+        AUTODISCOVER_RESPONSE_SCHEMA
+    );
+
+    for url in [
+        format!(
+            "https://autodiscover.{}/autodiscover/autodiscover.xml",
+            domain
+        ),
+        format!("https://{}/autodiscover/autodiscover.xml", domain),
+    ] {
+        let Ok(response) = client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "text/xml")
+            .body(request.clone())
+            .send()
+            .await
+        else {
+            continue;
+        };
+        if !response.status().is_success() {
+            continue;
+        }
+
+        let Ok(xml) = response.text().await else {
+            continue;
+        };
+        let servers = parse_autodiscover(&xml);
+        if servers.0.is_some() || servers.1.is_some() {
+            return Some(servers);
+        }
+    }
+
+    None
+}
+
+fn parse_autodiscover(xml: &str) -> Servers {
+    let server = |fields: &HashMap<String, String>| {
+        let port: u16 = fields.get("Port")?.parse().ok()?;
+        let ssl = fields
+            .get("SSL")
+            .map_or(true, |ssl| ssl.eq_ignore_ascii_case("on"));
+        // Encryption, where given, overrides SSL; TLS means STARTTLS
+        let tls_mode = match fields.get("Encryption").map(|e| e.to_ascii_uppercase()) {
+            Some(encryption) if encryption == "SSL" => Some(TlsMode::Implicit),
+            Some(encryption) if encryption == "TLS" => Some(TlsMode::Starttls),
+            Some(encryption) if encryption == "NONE" => None,
+            _ if ssl => Some(match fields.get("Type")?.as_str() {
+                "SMTP" => TlsMode::for_smtp_port(port),
+                _ => TlsMode::for_imap_port(port),
+            }),
+            _ => None,
+        };
+
+        Some(DiscoveredServer {
+            host: fields.get("Server")?.clone(),
+            port,
+            tls_mode,
+            username: fields.get("LoginName").cloned(),
+        })
+    };
+
+    let blocks = xml_blocks(xml, &[b"Protocol"]);
+    let first = |kind: &str| {
+        blocks
+            .iter()
+            .filter(|fields| fields.get("Type").map(String::as_str) == Some(kind))
+            .find_map(server)
+    };
+
+    (first("IMAP"), first("SMTP"))
+}
+
+/// Servers published as SRV records, preferring implicit TLS (RFC 8314)
+async fn srv_records(client: &Client, domain: &str) -> Option<Servers> {
+    let lookup = |services: [(&'static str, TlsMode); 2]| async move {
+        for (service, tls_mode) in services {
+            let name = format!("{}.{}", service, domain);
+            let best = dns_query(client, &name, "SRV", SRV)
+                .await
+                .iter()
+                .filter_map(|data| parse_srv(data))
+                .min_by_key(|(priority, _, _)| *priority);
+
+            if let Some((_, port, host)) = best {
+                return Some(DiscoveredServer {
+                    host,
+                    port,
+                    tls_mode: Some(tls_mode),
+                    username: None,
+                });
+            }
+        }
+        None
+    };
+
+    let imap = lookup([
+        ("_imaps._tcp", TlsMode::Implicit),
+        ("_imap._tcp", TlsMode::Starttls),
+    ])
+    .await;
+    let smtp = lookup([
+        ("_submissions._tcp", TlsMode::Implicit),
+        ("_submission._tcp", TlsMode::Starttls),
+    ])
+    .await;
+
+    (imap.is_some() || smtp.is_some()).then_some((imap, smtp))
+}
+
+/// Priority, port and target of an SRV record like `0 1 993 imap.example.com.`.
+/// A target of `.` means the service is not offered.
+fn parse_srv(data: &str) -> Option<(u16, u16, String)> {
+    let mut parts = data.split_whitespace();
+    let priority = parts.next()?.parse().ok()?;
+    let _weight: u16 = parts.next()?.parse().ok()?;
+    let port = parts.next()?.parse().ok()?;
+    let target = parts.next()?.trim_end_matches('.');
+
+    (!target.is_empty()).then(|| (priority, port, target.to_ascii_lowercase()))
+}
+
+/// Registered domain of the domain's preferred MX host, e.g. `google.com`
+/// for `aspmx.l.google.com`. Only the last two labels are taken, which is
+/// enough to find the large hosting providers in the ISPDB.
+async fn mx_domain(client: &Client, domain: &str) -> Option<String> {
+    let host = dns_query(client, domain, "MX", MX)
+        .await
+        .iter()
+        .filter_map(|data| {
+            let (preference, host) = data.split_once(' ')?;
+            Some((preference.parse::<u16>().ok()?, host.trim_end_matches('.')))
+        })
+        .min_by_key(|(preference, _)| *preference)
+        .map(|(_, host)| host.to_ascii_lowercase())?;
+
+    let labels: Vec<&str> = host.split('.').collect();
+    (labels.len() >= 2).then(|| labels[labels.len() - 2..].join("."))
+}
+
+/// Data of the records of `record_type` for `name`, empty when the lookup
+/// fails
+async fn dns_query(client: &Client, name: &str, record: &str, record_type: u16) -> Vec<String> {
+    let response = client
+        .get(DOH_ENDPOINT)
+        .query(&[("name", name), ("type", record)])
+        .header("accept", "application/dns-json")
+        .send()
+        .await;
+
+    let body: Option<DohResponse> = match response {
+        Ok(response) if response.status().is_success() => response.json().await.ok(),
+        _ => None,
+    };
+
+    body.and_then(|body| body.answer)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|answer| answer.record_type == record_type)
+        .map(|answer| answer.data)
+        .collect()
+}
+
+/// Autoconfig placeholders for parts of the address
+fn expand_placeholders(value: &str, email: &str) -> String {
+    let (local_part, domain) = email.rsplit_once('@').unwrap_or((email, ""));
+    value
+        .replace("%EMAILADDRESS%", email)
+        .replace("%EMAILLOCALPART%", local_part)
+        .replace("%EMAILDOMAIN%", domain)
+}
+
+/// Texts of the child elements of every `blocks` element, by element name,
+/// plus its `type` attribute if it has one
+fn xml_blocks(xml: &str, blocks: &[&[u8]]) -> Vec<HashMap<String, String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut result = Vec::new();
+    let mut current: Option<HashMap<String, String>> = None;
+    let mut field: Option<String> = None;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = element.local_name();
+                if blocks.contains(&name.as_ref()) {
+                    let mut fields = HashMap::new();
+                    if let Ok(Some(kind)) = element.try_get_attribute("type") {
+                        fields.insert(
+                            "type".to_string(),
+                            String::from_utf8_lossy(&kind.value).to_ascii_lowercase(),
+                        );
+                    }
+                    current = Some(fields);
+                } else {
+                    field = Some(String::from_utf8_lossy(name.as_ref()).into_owned());
+                }
+            }
+            Ok(Event::Text(content)) => {
+                if let (Some(fields), Some(field)) = (current.as_mut(), field.as_ref()) {
+                    if let Ok(content) = content.decode() {
+                        let content = content.trim();
+                        if !content.is_empty() {
+                            fields.insert(field.clone(), content.to_string());
+                        }
+                    }
+                }
+            }
+            Ok(Event::End(element)) => {
+                if blocks.contains(&element.local_name().as_ref()) {
+                    result.extend(current.take());
+                }
+                field = None;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_autoconfig() {
+        let xml = r#"<?xml version="1.0"?>
+            <clientConfig version="1.1">
+              <emailProvider id="example.com">
+                <incomingServer type="pop3">
+                  <hostname>pop.example.com</hostname>
+                  <port>995</port>
+                  <socketType>SSL</socketType>
+                </incomingServer>
+                <incomingServer type="imap">
+                  <hostname>imap.example.com</hostname>
+                  <port>993</port>
+                  <socketType>SSL</socketType>
+                  <username>%EMAILLOCALPART%</username>
+                </incomingServer>
+                <incomingServer type="imap">
+                  <hostname>imap.example.com</hostname>
+                  <port>143</port>
+                  <socketType>STARTTLS</socketType>
+                </incomingServer>
+                <outgoingServer type="smtp">
+                  <hostname>smtp.%EMAILDOMAIN%</hostname>
+                  <port>587</port>
+                  <socketType>STARTTLS</socketType>
+                  <username>%EMAILADDRESS%</username>
+                </outgoingServer>
+              </emailProvider>
+            </clientConfig>"#;
+
+        let (imap, smtp) = parse_autoconfig(xml, "jane@example.com");
+        assert_eq!(
+            imap,
+            Some(DiscoveredServer {
+                host: "imap.example.com".to_string(),
+                port: 993,
+                tls_mode: Some(TlsMode::Implicit),
+                username: Some("jane".to_string()),
+            })
+        );
+        assert_eq!(
+            smtp,
+            Some(DiscoveredServer {
+                host: "smtp.example.com".to_string(),
+                port: 587,
+                tls_mode: Some(TlsMode::Starttls),
+                username: Some("jane@example.com".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_autodiscover() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <Autodiscover xmlns="http://schemas.microsoft.com/exchange/autodiscover/responseschema/2006">
+              <Response xmlns="http://schemas.microsoft.com/exchange/autodiscover/outlook/responseschema/2006a">
+                <Account>
+                  <AccountType>email</AccountType>
+                  <Action>settings</Action>
+                  <Protocol>
+                    <Type>IMAP</Type>
+                    <Server>mail.example.com</Server>
+                    <Port>993</Port>
+                    <LoginName>jane</LoginName>
+                    <SSL>on</SSL>
+                  </Protocol>
+                  <Protocol>
+                    <Type>SMTP</Type>
+                    <Server>mail.example.com</Server>
+                    <Port>587</Port>
+                    <SSL>on</SSL>
+                    <Encryption>TLS</Encryption>
+                  </Protocol>
+                </Account>
+              </Response>
+            </Autodiscover>"#;
+
+        let (imap, smtp) = parse_autodiscover(xml);
+        let imap = imap.unwrap();
+        assert_eq!(imap.port, 993);
+        assert_eq!(imap.tls_mode, Some(TlsMode::Implicit));
+        assert_eq!(imap.username.as_deref(), Some("jane"));
+        assert_eq!(smtp.unwrap().tls_mode, Some(TlsMode::Starttls));
+    }
+
+    #[test]
+    fn test_parse_srv() {
+        assert_eq!(
+            parse_srv("0 1 993 IMAP.example.com."),
+            Some((0, 993, "imap.example.com".to_string()))
+        );
+        // Service not offered
+        assert_eq!(parse_srv("0 0 0 ."), None);
+        assert_eq!(parse_srv("invalid"), None);
+    }
+}
@@ -0,0 +1,197 @@
+//! Windowed first sync of a folder. The first sync only fetches recent mail
+//! so the folder is usable right away; older mail is backfilled afterwards in
+//! steps that walk back in time, down to how far back the account syncs.
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::types::SyncWindow;
+use crate::config::settings::Settings;
+
+/// Days of mail fetched by the first sync of a folder
+pub const INITIAL_WINDOW_SETTING: &str = "sync.initialWindowDays";
+const DEFAULT_INITIAL_WINDOW_DAYS: i64 = 30;
+
+/// Days of mail fetched per backfill step
+const STEP_DAYS: i64 = 30;
+/// Empty windows double the step up to this, to skip gaps in a mailbox
+/// quickly
+const MAX_STEP_DAYS: i64 = 8 * 365;
+
+/// Progress of a folder's backfill, stored as the sync state's checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backfill {
+    /// Mail received before this is not synced yet
+    pub before: DateTime<Utc>,
+    pub step_days: i64,
+}
+
+/// Oldest mail any backfill looks for
+fn floor() -> DateTime<Utc> {
+    Utc.timestamp_opt(0, 0).unwrap()
+}
+
+/// Oldest mail to sync for an account that syncs `history_days` back, `None`
+/// for the whole history
+pub fn horizon(now: DateTime<Utc>, history_days: Option<u32>) -> Option<DateTime<Utc>> {
+    history_days.map(|days| now - Duration::days(days as i64))
+}
+
+/// Start of the window fetched by the first sync of a folder
+pub fn initial_window_start(
+    now: DateTime<Utc>,
+    settings: Option<&Settings>,
+    horizon: Option<DateTime<Utc>>,
+) -> DateTime<Utc> {
+    let days = settings
+        .and_then(|settings| settings.get::<i64>(INITIAL_WINDOW_SETTING).ok())
+        .unwrap_or(DEFAULT_INITIAL_WINDOW_DAYS)
+        .max(1);
+    let start = now - Duration::days(days);
+
+    horizon.map_or(start, |horizon| start.max(horizon))
+}
+
+impl Backfill {
+    /// Backfill of the mail received before `before`, if the account syncs
+    /// further back than that
+    pub fn starting_at(before: DateTime<Utc>, horizon: Option<DateTime<Utc>>) -> Option<Self> {
+        let backfill = Self {
+            before,
+            step_days: STEP_DAYS,
+        };
+        backfill.next_window(horizon).map(|_| backfill)
+    }
+
+    /// Window of the next step, `None` once the backfill reached the horizon
+    pub fn next_window(&self, horizon: Option<DateTime<Utc>>) -> Option<SyncWindow> {
+        let limit = horizon.unwrap_or_else(floor).max(floor());
+        if self.before <= limit {
+            return None;
+        }
+
+        let after = (self.before - Duration::days(self.step_days)).max(limit);
+        Some(SyncWindow {
+            after: Some(after),
+            before: Some(self.before),
+        })
+    }
+
+    /// Progress after fetching `window`, which held `fetched` messages
+    pub fn advance(&self, window: &SyncWindow, fetched: usize) -> Self {
+        Self {
+            before: window.after.unwrap_or_else(floor),
+            step_days: if fetched == 0 {
+                (self.step_days * 2).min(MAX_STEP_DAYS)
+            } else {
+                STEP_DAYS
+            },
+        }
+    }
+}
+
+/// Backfill the synced folders of an account again from their oldest mail,
+/// e.g. after it was set to sync further back. Folders with a backfill under
+/// way keep it.
+pub async fn restart(
+    pool: &SqlitePool,
+    account_id: Uuid,
+    horizon: Option<DateTime<Utc>>,
+) -> Result<usize, String> {
+    let account_id_str = account_id.to_string();
+    let folders = sqlx::query!(
+        r#"
+        SELECT ss.folder_id AS "folder_id!", MIN(e.received_at) AS "oldest: DateTime<Utc>"
+        FROM sync_state ss
+        LEFT JOIN emails e ON e.folder_id = ss.folder_id
+        WHERE ss.account_id = ?
+          AND ss.folder_id IS NOT NULL
+          AND ss.last_sync_at IS NOT NULL
+          AND ss.checkpoint_data IS NULL
+        GROUP BY ss.folder_id
+        "#,
+        account_id_str
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut restarted = 0;
+    for folder in folders {
+        let oldest = folder.oldest.unwrap_or_else(Utc::now);
+        let Some(backfill) = Backfill::starting_at(oldest, horizon) else {
+            continue;
+        };
+        let checkpoint = serde_json::to_string(&backfill).map_err(|e| e.to_string())?;
+
+        sqlx::query!(
+            "UPDATE sync_state SET checkpoint_data = ? WHERE folder_id = ?",
+            checkpoint,
+            folder.folder_id
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        restarted += 1;
+    }
+
+    Ok(restarted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_initial_window_start() {
+        let now = date(2025, 3, 31);
+        assert_eq!(initial_window_start(now, None, None), date(2025, 3, 1));
+        // An account syncing less history than the window
+        assert_eq!(
+            initial_window_start(now, None, horizon(now, Some(7))),
+            date(2025, 3, 24)
+        );
+    }
+
+    #[test]
+    fn test_backfill_steps_back_to_horizon() {
+        let horizon = Some(date(2025, 1, 15));
+        let backfill = Backfill::starting_at(date(2025, 3, 1), horizon).unwrap();
+
+        let window = backfill.next_window(horizon).unwrap();
+        assert_eq!(window.after, Some(date(2025, 1, 30)));
+        assert_eq!(window.before, Some(date(2025, 3, 1)));
+
+        let backfill = backfill.advance(&window, 10);
+        let window = backfill.next_window(horizon).unwrap();
+        assert_eq!(window.after, Some(date(2025, 1, 15)));
+
+        let backfill = backfill.advance(&window, 10);
+        assert_eq!(backfill.next_window(horizon), None);
+        assert_eq!(Backfill::starting_at(date(2025, 1, 10), horizon), None);
+    }
+
+    #[test]
+    fn test_empty_windows_widen_step() {
+        let backfill = Backfill::starting_at(date(2025, 3, 1), None).unwrap();
+        let window = backfill.next_window(None).unwrap();
+
+        let empty = backfill.advance(&window, 0);
+        assert_eq!(empty.step_days, 2 * STEP_DAYS);
+        assert_eq!(empty.advance(&window, 3).step_days, STEP_DAYS);
+
+        // Without a horizon the backfill ends at the floor
+        let last = Backfill {
+            before: date(1975, 1, 1),
+            step_days: MAX_STEP_DAYS,
+        };
+        let window = last.next_window(None).unwrap();
+        assert_eq!(window.after, Some(floor()));
+        assert_eq!(last.advance(&window, 0).next_window(None), None);
+    }
+}
@@ -1,27 +1,166 @@
+use chrono::Utc;
 use sqlx::SqlitePool;
-use std::collections::HashMap;
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use tauri::Emitter;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 use tokio::time::sleep;
 use uuid::Uuid;
 
+use super::backoff::Backoff;
 use super::error::{SyncError, SyncResult};
+use crate::config::Settings;
+use crate::database::models::ai_analysis_job::AiAnalysisJob;
 use crate::database::repositories::{
-    AccountRepository, ContactRepository, EmailRepository, SqliteAccountRepository,
-    SqliteContactRepository, SqliteEmailRepository,
+    AccountRepository, AiAnalysisQueueRepository, ContactRepository, EmailRepository,
+    SqliteAccountRepository, SqliteAiAnalysisQueueRepository, SqliteContactRepository,
+    SqliteEmailRepository,
 };
-use crate::services::corvus::{ContactNote, CorvusService, UserContext};
+use crate::services::ai_exclusions::AiExclusions;
+use crate::services::app_lock;
+use crate::services::corvus::{AnalysisRequest, ContactNote, CorvusService, UserContext};
 
-const ANALYSIS_BATCH_SIZE: i64 = 5;
 const ANALYSIS_INTERVAL_SECS: u64 = 10;
+/// Emails moved into the analysis queue per run
+const ENQUEUE_BATCH_SIZE: i64 = 50;
+/// Retry delays of failed analyses: a minute, doubled up to six hours
+const RETRY_BACKOFF: Backoff = Backoff {
+    base_secs: 60,
+    growth: 2,
+    max_secs: 6 * 60 * 60,
+};
+const TOKEN_BUDGET_WINDOW: Duration = Duration::from_secs(60);
 
-pub struct BackgroundAiAnalyzer {
+/// Emails analyzed with one request; 1 for models that cannot answer for
+/// several emails at once
+const BATCH_SIZE_SETTING: &str = "ai.analysis.batchSize";
+/// Analysis requests in flight at the same time
+const CONCURRENCY_SETTING: &str = "ai.analysis.concurrency";
+/// Tokens analysis may use per minute; 0 means unlimited
+const TOKENS_PER_MINUTE_SETTING: &str = "ai.analysis.tokensPerMinute";
+/// Attempts before an email is given up on
+const MAX_ATTEMPTS_SETTING: &str = "ai.analysis.maxAttempts";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AnalysisLimits {
+    batch_size: usize,
+    concurrency: usize,
+    tokens_per_minute: usize,
+    max_attempts: i64,
+}
+
+impl Default for AnalysisLimits {
+    fn default() -> Self {
+        Self {
+            batch_size: 5,
+            concurrency: 2,
+            tokens_per_minute: 20_000,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl AnalysisLimits {
+    fn from_settings(settings: &Settings) -> Self {
+        let default = Self::default();
+        let get = |key: &str, default: usize| {
+            settings
+                .get::<i64>(key)
+                .map(|value| value.max(0) as usize)
+                .unwrap_or(default)
+        };
+
+        Self {
+            batch_size: get(BATCH_SIZE_SETTING, default.batch_size).max(1),
+            concurrency: get(CONCURRENCY_SETTING, default.concurrency).max(1),
+            tokens_per_minute: get(TOKENS_PER_MINUTE_SETTING, default.tokens_per_minute),
+            max_attempts: get(MAX_ATTEMPTS_SETTING, default.max_attempts as usize).max(1) as i64,
+        }
+    }
+}
+
+/// Tokens spent on analysis over the last minute
+#[derive(Debug, Default)]
+struct TokenBudget {
+    spent: VecDeque<(Instant, usize)>,
+}
+
+impl TokenBudget {
+    /// Counts the tokens against a budget of `per_minute` if they fit, and
+    /// otherwise tells how long to wait before trying again. A budget of 0 is
+    /// unlimited; a request larger than the whole budget runs on its own.
+    fn try_spend(
+        &mut self,
+        tokens: usize,
+        per_minute: usize,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        while let Some((spent_at, _)) = self.spent.front() {
+            if now.duration_since(*spent_at) < TOKEN_BUDGET_WINDOW {
+                break;
+            }
+            self.spent.pop_front();
+        }
+
+        if per_minute == 0 {
+            return Ok(());
+        }
+
+        let used: usize = self.spent.iter().map(|(_, tokens)| tokens).sum();
+        match self.spent.front() {
+            Some((oldest, _)) if used + tokens > per_minute => {
+                Err(TOKEN_BUDGET_WINDOW - now.duration_since(*oldest))
+            }
+            _ => {
+                self.spent.push_back((now, tokens));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Splits due jobs into at most `max_batches` requests. Emails that failed
+/// before are analyzed on their own, so one the model chokes on does not keep
+/// failing the others.
+fn plan_batches(
+    jobs: Vec<AiAnalysisJob>,
+    batch_size: usize,
+    max_batches: usize,
+) -> Vec<Vec<AiAnalysisJob>> {
+    let (fresh, retried): (Vec<_>, Vec<_>) = jobs.into_iter().partition(|job| job.attempts == 0);
+
+    let mut batches: Vec<Vec<AiAnalysisJob>> = Vec::new();
+    for job in fresh {
+        match batches.last_mut() {
+            Some(batch) if batch.len() < batch_size => batch.push(job),
+            _ => batches.push(vec![job]),
+        }
+    }
+    batches.extend(retried.into_iter().map(|job| vec![job]));
+    batches.truncate(max_batches);
+
+    batches
+}
+
+/// State shared by the analyzer loop and the batches it runs
+#[derive(Clone)]
+struct AnalyzerContext {
     pool: SqlitePool,
     app_handle: tauri::AppHandle,
     ai_service: Arc<CorvusService>,
-    active_analysis: Arc<RwLock<HashMap<Uuid, bool>>>,
+    settings: Arc<Settings>,
+    /// Emails whose analysis is running
+    in_flight: Arc<RwLock<HashSet<Uuid>>>,
+    active_batches: Arc<AtomicUsize>,
+    token_budget: Arc<Mutex<TokenBudget>>,
+}
+
+/// Analyzes personal inbox emails in the background. Emails go through a
+/// persistent queue and are sent in batches, with limited concurrency and a
+/// per-minute token budget; failed analyses are retried with backoff.
+pub struct BackgroundAiAnalyzer {
+    context: AnalyzerContext,
     shutdown_tx: tokio::sync::broadcast::Sender<()>,
 }
 
@@ -30,14 +169,20 @@ impl BackgroundAiAnalyzer {
         pool: SqlitePool,
         app_handle: tauri::AppHandle,
         ai_service: Arc<CorvusService>,
+        settings: Arc<Settings>,
     ) -> Self {
         let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
 
         Self {
-            pool,
-            app_handle,
-            ai_service,
-            active_analysis: Arc::new(RwLock::new(HashMap::new())),
+            context: AnalyzerContext {
+                pool,
+                app_handle,
+                ai_service,
+                settings,
+                in_flight: Arc::new(RwLock::new(HashSet::new())),
+                active_batches: Arc::new(AtomicUsize::new(0)),
+                token_budget: Arc::new(Mutex::new(TokenBudget::default())),
+            },
             shutdown_tx,
         }
     }
@@ -45,10 +190,7 @@ impl BackgroundAiAnalyzer {
     pub async fn start(&self) -> SyncResult<()> {
         log::info!("[BackgroundAiAnalyzer] Starting background AI analyzer service");
 
-        let pool = self.pool.clone();
-        let app_handle = self.app_handle.clone();
-        let ai_service = Arc::clone(&self.ai_service);
-        let active_analysis = Arc::clone(&self.active_analysis);
+        let context = self.context.clone();
         let mut shutdown_rx = self.shutdown_tx.subscribe();
 
         tokio::spawn(async move {
@@ -59,12 +201,7 @@ impl BackgroundAiAnalyzer {
                         break;
                     }
                     _ = sleep(Duration::from_secs(ANALYSIS_INTERVAL_SECS)) => {
-                        if let Err(e) = Self::analyze_pending_emails(
-                            &pool,
-                            &app_handle,
-                            &ai_service,
-                            &active_analysis,
-                        ).await {
+                        if let Err(e) = context.analyze_pending_emails().await {
                             log::error!("[BackgroundAiAnalyzer] Error analyzing emails: {}", e);
                         }
                     }
@@ -79,85 +216,247 @@ impl BackgroundAiAnalyzer {
         log::info!("[BackgroundAiAnalyzer] Stopping background AI analyzer service");
         let _ = self.shutdown_tx.send(());
     }
+}
+
+impl AnalyzerContext {
+    async fn analyze_pending_emails(&self) -> SyncResult<()> {
+        if !self.ai_service.is_enabled().await {
+            return Ok(());
+        }
+
+        let limits = AnalysisLimits::from_settings(&self.settings);
+        let exclusions = AiExclusions::from_settings(&self.settings);
+        let queue = SqliteAiAnalysisQueueRepository::new(self.pool.clone());
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+
+        queue
+            .prune()
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
-    async fn analyze_pending_emails(
-        pool: &SqlitePool,
-        app_handle: &tauri::AppHandle,
-        ai_service: &Arc<CorvusService>,
-        active_analysis: &Arc<RwLock<HashMap<Uuid, bool>>>,
-    ) -> SyncResult<()> {
-        let email_repo = SqliteEmailRepository::new(pool.clone());
         let pending_email_ids = email_repo
-            .find_pending_ai_analysis(ANALYSIS_BATCH_SIZE)
+            .find_pending_ai_analysis(
+                ENQUEUE_BATCH_SIZE,
+                &exclusions.folders.iter().copied().collect::<Vec<_>>(),
+                &exclusions.labels.iter().copied().collect::<Vec<_>>(),
+            )
             .await
             .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        if !pending_email_ids.is_empty() {
+            let queued = queue
+                .enqueue(&pending_email_ids)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+            log::debug!(
+                "[BackgroundAiAnalyzer] Queued {} personal inbox emails for AI analysis",
+                queued
+            );
+        }
 
-        if pending_email_ids.is_empty() {
+        let free_slots = limits
+            .concurrency
+            .saturating_sub(self.active_batches.load(Ordering::SeqCst));
+        if free_slots == 0 {
             return Ok(());
         }
 
-        log::debug!(
-            "[BackgroundAiAnalyzer] Found {} personal inbox emails pending AI analysis",
-            pending_email_ids.len()
-        );
+        let in_flight = self.in_flight.read().await.clone();
+        let due_jobs: Vec<AiAnalysisJob> = queue
+            .find_due(
+                Utc::now(),
+                limits.max_attempts,
+                (free_slots * limits.batch_size + in_flight.len()) as i64,
+            )
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .filter(|job| !in_flight.contains(&job.email_id))
+            .collect();
 
-        for email_id in pending_email_ids {
+        for batch in plan_batches(due_jobs, limits.batch_size, free_slots) {
             {
-                let analysis = active_analysis.read().await;
-                if analysis.get(&email_id).copied().unwrap_or(false) {
-                    continue;
-                }
+                let mut in_flight = self.in_flight.write().await;
+                in_flight.extend(batch.iter().map(|job| job.email_id));
             }
+            self.active_batches.fetch_add(1, Ordering::SeqCst);
 
-            {
-                let mut analysis = active_analysis.write().await;
-                analysis.insert(email_id, true);
-            }
+            let context = self.clone();
+            let exclusions = exclusions.clone();
+            tokio::spawn(async move {
+                let email_ids: Vec<Uuid> = batch.iter().map(|job| job.email_id).collect();
 
-            let pool_clone = pool.clone();
-            let app_handle_clone = app_handle.clone();
-            let ai_service_clone = Arc::clone(ai_service);
-            let active_analysis_clone = Arc::clone(active_analysis);
+                if let Err(e) = context.analyze_batch(&batch, limits, &exclusions).await {
+                    log::error!(
+                        "[BackgroundAiAnalyzer] Failed to analyze {} emails: {}",
+                        email_ids.len(),
+                        e
+                    );
+                }
 
-            tokio::spawn(async move {
-                match Self::analyze_email_background(
-                    &pool_clone,
-                    &app_handle_clone,
-                    &ai_service_clone,
-                    email_id,
+                let mut in_flight = context.in_flight.write().await;
+                for email_id in &email_ids {
+                    in_flight.remove(email_id);
+                }
+                context.active_batches.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn analyze_batch(
+        &self,
+        jobs: &[AiAnalysisJob],
+        limits: AnalysisLimits,
+        exclusions: &AiExclusions,
+    ) -> SyncResult<()> {
+        let queue = SqliteAiAnalysisQueueRepository::new(self.pool.clone());
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+
+        let mut requests = Vec::new();
+        for job in jobs {
+            match self.build_request(job.email_id).await {
+                Ok(request) => requests.push(request),
+                Err(e) => {
+                    self.record_failure(&queue, job, &e.to_string(), limits)
+                        .await?
+                }
+            }
+        }
+
+        // Emails queued before their folder or label was excluded are dropped
+        if !exclusions.is_empty() {
+            let allowed: HashSet<Uuid> = exclusions
+                .retain_allowed(
+                    &self.pool,
+                    requests
+                        .iter()
+                        .map(|request| request.email.clone())
+                        .collect(),
                 )
                 .await
+                .map_err(SyncError::Other)?
+                .iter()
+                .map(|email| email.id)
+                .collect();
+            for request in requests.iter().filter(|r| !allowed.contains(&r.email.id)) {
+                log::debug!(
+                    "[BackgroundAiAnalyzer] Email {} is excluded from AI processing",
+                    request.email.id
+                );
+                queue
+                    .complete(request.email.id)
+                    .await
+                    .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+            }
+            requests.retain(|request| allowed.contains(&request.email.id));
+        }
+        if requests.is_empty() {
+            return Ok(());
+        }
+
+        let tokens = self.ai_service.estimate_analysis_tokens(&requests);
+        loop {
+            let wait = self.token_budget.lock().await.try_spend(
+                tokens,
+                limits.tokens_per_minute,
+                Instant::now(),
+            );
+            match wait {
+                Ok(()) => break,
+                Err(wait) => {
+                    log::debug!(
+                        "[BackgroundAiAnalyzer] Token budget used up, waiting {}s",
+                        wait.as_secs()
+                    );
+                    sleep(wait).await;
+                }
+            }
+        }
+
+        let analyses = match self.ai_service.analyze_emails(&requests).await {
+            Ok(analyses) => analyses,
+            Err(e) => {
+                for job in jobs {
+                    self.record_failure(&queue, job, &e, limits).await?;
+                }
+                return Ok(());
+            }
+        };
+
+        for job in jobs {
+            let Some((email_id, analysis)) = analyses.iter().find(|(id, _)| *id == job.email_id)
+            else {
+                if requests
+                    .iter()
+                    .any(|request| request.email.id == job.email_id)
                 {
-                    Ok(_) => {
-                        log::info!(
-                            "[BackgroundAiAnalyzer] Successfully analyzed personal email {}",
-                            email_id
-                        );
-                    }
-                    Err(e) => {
-                        log::error!(
-                            "[BackgroundAiAnalyzer] Failed to analyze personal email {}: {}",
-                            email_id,
-                            e
-                        );
-                    }
+                    self.record_failure(&queue, job, "Missing from the analysis response", limits)
+                        .await?;
                 }
+                continue;
+            };
 
-                let mut analysis = active_analysis_clone.write().await;
-                analysis.insert(email_id, false);
-            });
+            let analysis_json = serde_json::to_string(analysis)
+                .map_err(|e| SyncError::Other(format!("Failed to serialize analysis: {}", e)))?;
+
+            email_repo
+                .update_ai_cache(*email_id, &analysis_json)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+            queue
+                .complete(*email_id)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+            log::info!(
+                "[BackgroundAiAnalyzer] Successfully analyzed personal email {}",
+                email_id
+            );
+            let _ = app_lock::emit_mail_event(
+                &self.app_handle,
+                "email:ai-analysis-complete",
+                email_id.to_string(),
+            );
         }
 
         Ok(())
     }
 
-    async fn analyze_email_background(
-        pool: &SqlitePool,
-        app_handle: &tauri::AppHandle,
-        ai_service: &Arc<CorvusService>,
-        email_id: Uuid,
+    async fn record_failure(
+        &self,
+        queue: &SqliteAiAnalysisQueueRepository,
+        job: &AiAnalysisJob,
+        error: &str,
+        limits: AnalysisLimits,
     ) -> SyncResult<()> {
-        let email_repo = SqliteEmailRepository::new(pool.clone());
+        if job.attempts + 1 >= limits.max_attempts {
+            log::warn!(
+                "[BackgroundAiAnalyzer] Giving up on personal email {} after {} attempts: {}",
+                job.email_id,
+                job.attempts + 1,
+                error
+            );
+        } else {
+            log::error!(
+                "[BackgroundAiAnalyzer] Failed to analyze personal email {}: {}",
+                job.email_id,
+                error
+            );
+        }
+
+        queue
+            .schedule_retry(
+                job.email_id,
+                error,
+                Utc::now() + RETRY_BACKOFF.delay(job.attempts),
+            )
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))
+    }
+
+    async fn build_request(&self, email_id: Uuid) -> SyncResult<AnalysisRequest> {
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
         let email = email_repo
             .find_by_id(email_id)
             .await
@@ -165,7 +464,7 @@ impl BackgroundAiAnalyzer {
             .ok_or_else(|| SyncError::Other("Email not found".to_string()))?;
 
         // Resolve the account that owns this email to provide user context to the AI
-        let account_repo = SqliteAccountRepository::new(pool.clone());
+        let account_repo = SqliteAccountRepository::new(self.pool.clone());
         let user_context = account_repo
             .find_by_id(email.account_id)
             .await
@@ -190,7 +489,7 @@ impl BackgroundAiAnalyzer {
         }
 
         // Gather ai_notes for all contacts involved in this email
-        let contact_repo = SqliteContactRepository::new(pool.clone());
+        let contact_repo = SqliteContactRepository::new(self.pool.clone());
         let mut all_addresses: Vec<(String, Option<String>)> =
             vec![(email.from().address.clone(), email.from().name.clone())];
         for addr in email.to() {
@@ -213,21 +512,72 @@ impl BackgroundAiAnalyzer {
             }
         }
 
-        let analysis = ai_service
-            .analyze_email(&email, user_context.as_ref(), &contact_notes)
-            .await
-            .map_err(|e| SyncError::Other(e))?;
+        Ok(AnalysisRequest {
+            email,
+            user_context,
+            contact_notes,
+        })
+    }
+}
 
-        let analysis_json = serde_json::to_string(&analysis)
-            .map_err(|e| SyncError::Other(format!("Failed to serialize analysis: {}", e)))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        email_repo
-            .update_ai_cache(email_id, &analysis_json)
-            .await
-            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+    fn job(attempts: i64) -> AiAnalysisJob {
+        AiAnalysisJob {
+            email_id: Uuid::now_v7(),
+            attempts,
+            next_attempt_at: None,
+            last_error: None,
+            created_at: Utc::now(),
+        }
+    }
 
-        let _ = app_handle.emit("email:ai-analysis-complete", email_id.to_string());
+    #[test]
+    fn test_token_budget() {
+        let mut budget = TokenBudget::default();
+        let start = Instant::now();
 
-        Ok(())
+        assert!(budget.try_spend(600, 1000, start).is_ok());
+        assert!(budget
+            .try_spend(300, 1000, start + Duration::from_secs(10))
+            .is_ok());
+        assert_eq!(
+            budget.try_spend(300, 1000, start + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+
+        // The first request has left the window
+        assert!(budget
+            .try_spend(300, 1000, start + Duration::from_secs(60))
+            .is_ok());
+
+        // Oversized requests run alone, and 0 is unlimited
+        let mut budget = TokenBudget::default();
+        assert!(budget.try_spend(5000, 1000, start).is_ok());
+        assert!(budget.try_spend(1, 1000, start).is_err());
+        assert!(budget.try_spend(5000, 0, start).is_ok());
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(RETRY_BACKOFF.delay(0), chrono::Duration::seconds(60));
+        assert_eq!(RETRY_BACKOFF.delay(2), chrono::Duration::seconds(240));
+        assert_eq!(RETRY_BACKOFF.delay(100), chrono::Duration::hours(6));
+    }
+
+    #[test]
+    fn test_plan_batches() {
+        let jobs = vec![job(0), job(1), job(0), job(0), job(2)];
+        let retried = [jobs[1].email_id, jobs[4].email_id];
+
+        let batches = plan_batches(jobs.clone(), 2, 10);
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 1, 1, 1]);
+        assert_eq!(batches[2][0].email_id, retried[0]);
+        assert_eq!(batches[3][0].email_id, retried[1]);
+
+        assert_eq!(plan_batches(jobs, 5, 1).len(), 1);
     }
 }
@@ -0,0 +1,239 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::database::models::email::Email;
+use crate::database::models::folder::{AutoArchiveRule, Folder, FolderType};
+use crate::database::repositories::{
+    EmailRepository, FolderRepository, SqliteEmailRepository, SqliteFolderRepository,
+    SqliteSyncStateRepository, SyncStateRepository,
+};
+use crate::sync::SyncCoordinator;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 3600;
+
+/// Messages moved per query; folders with more candidates take several batches
+const BATCH_SIZE: i64 = 200;
+
+/// Messages listed in a preview
+const PREVIEW_LIMIT: i64 = 50;
+
+/// What an auto-archive rule would move right now
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoArchivePreview {
+    /// `None` if the account has no archive folder to move to
+    pub target_folder_id: Option<Uuid>,
+    pub total: i64,
+    /// The oldest matching messages
+    pub emails: Vec<Email>,
+}
+
+/// Applies the auto-archive rules of folders. Moves go through the sync
+/// manager, so they are applied locally and queued for the provider.
+pub struct BackgroundArchiveWorker {
+    pool: SqlitePool,
+    sync_coordinator: Arc<SyncCoordinator>,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+}
+
+impl BackgroundArchiveWorker {
+    pub fn new(pool: SqlitePool, sync_coordinator: Arc<SyncCoordinator>) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            sync_coordinator,
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[BackgroundArchiveWorker] Starting background archive worker");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(error) = this.archive_all().await {
+                    log::error!(
+                        "[BackgroundArchiveWorker] Failed to apply auto-archive rules: {}",
+                        error
+                    );
+                }
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[BackgroundArchiveWorker] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(this.poll_interval) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[BackgroundArchiveWorker] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    /// Dry run of a rule: the messages it would move from the folder now,
+    /// without moving anything
+    pub async fn preview(
+        &self,
+        folder: &Folder,
+        rule: &AutoArchiveRule,
+    ) -> Result<AutoArchivePreview, String> {
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+        let now = Utc::now();
+
+        let total = email_repo
+            .count_auto_archive_candidates(folder.id, rule, now)
+            .await
+            .map_err(|e| format!("Failed to count messages to archive: {}", e))?;
+        let emails = email_repo
+            .find_auto_archive_candidates(folder.id, rule, now, PREVIEW_LIMIT)
+            .await
+            .map_err(|e| format!("Failed to query messages to archive: {}", e))?;
+
+        Ok(AutoArchivePreview {
+            target_folder_id: self.target_folder(folder, rule).await?,
+            total,
+            emails,
+        })
+    }
+
+    async fn archive_all(&self) -> Result<(), String> {
+        let folder_repo = SqliteFolderRepository::new(self.pool.clone());
+
+        let folders = folder_repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Failed to query folders: {}", e))?;
+
+        let sync_state_repo = SqliteSyncStateRepository::new(self.pool.clone());
+        let mut paused = HashMap::new();
+
+        for folder in folders {
+            let Some(rule) = folder.settings.auto_archive.clone() else {
+                continue;
+            };
+
+            // Moves are server changes, held back like sync while paused
+            let is_paused = match paused.get(&folder.account_id) {
+                Some(is_paused) => *is_paused,
+                None => {
+                    let is_paused = sync_state_repo
+                        .is_account_paused(folder.account_id)
+                        .await
+                        .unwrap_or(false);
+                    paused.insert(folder.account_id, is_paused);
+                    is_paused
+                }
+            };
+            if is_paused {
+                continue;
+            }
+
+            if let Err(error) = self.archive_folder(&folder, &rule, Utc::now()).await {
+                log::warn!(
+                    "[BackgroundArchiveWorker] Failed to archive messages of folder {}: {}",
+                    folder.id,
+                    error
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn archive_folder(
+        &self,
+        folder: &Folder,
+        rule: &AutoArchiveRule,
+        now: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let Some(target_folder_id) = self.target_folder(folder, rule).await? else {
+            log::debug!(
+                "[BackgroundArchiveWorker] No archive folder for account {}, skipping folder {}",
+                folder.account_id,
+                folder.id
+            );
+            return Ok(());
+        };
+        if target_folder_id == folder.id {
+            return Ok(());
+        }
+
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+        let mut archived = 0;
+
+        loop {
+            let emails = email_repo
+                .find_auto_archive_candidates(folder.id, rule, now, BATCH_SIZE)
+                .await
+                .map_err(|e| format!("Failed to query messages to archive: {}", e))?;
+            let batch_len = emails.len() as i64;
+
+            for email in emails {
+                // A failed move leaves the message in the folder, so it would be
+                // returned again; stop here and retry on the next run
+                self.sync_coordinator
+                    .move_email(folder.account_id, email.id, target_folder_id)
+                    .await
+                    .map_err(|e| format!("Failed to move email {}: {}", email.id, e))?;
+                archived += 1;
+            }
+
+            if batch_len < BATCH_SIZE {
+                break;
+            }
+        }
+
+        if archived > 0 {
+            log::info!(
+                "[BackgroundArchiveWorker] Archived {} messages from folder {}",
+                archived,
+                folder.id
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn target_folder(
+        &self,
+        folder: &Folder,
+        rule: &AutoArchiveRule,
+    ) -> Result<Option<Uuid>, String> {
+        if let Some(target_folder_id) = rule.target_folder_id {
+            return Ok(Some(target_folder_id));
+        }
+
+        let folder_repo = SqliteFolderRepository::new(self.pool.clone());
+        folder_repo
+            .find_by_type(folder.account_id, FolderType::Archive.as_str())
+            .await
+            .map(|archive| archive.map(|archive| archive.id))
+            .map_err(|e| format!("Failed to find archive folder: {}", e))
+    }
+}
@@ -0,0 +1,206 @@
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use super::storage::{FileStorage, LocalFileStorage, PathGenerator};
+use crate::database::models::attachment::Attachment;
+use crate::database::repositories::{
+    AttachmentRepository, EmailRepository, SqliteAttachmentRepository, SqliteEmailRepository,
+};
+use crate::search::attachment_text::{self, EXTRACTABLE_FILE_TYPES, MAX_ATTACHMENT_SIZE};
+use crate::search::SearchManager;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 60;
+
+/// Attachments read per run; text extraction of large PDFs is slow
+const BATCH_SIZE: i64 = 20;
+
+/// Extracts the text of cached attachments and reindexes their emails, so
+/// searches match text that only appears in an attachment
+pub struct BackgroundAttachmentIndexer {
+    pool: SqlitePool,
+    storage: LocalFileStorage,
+    search_manager: Arc<SearchManager>,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+}
+
+impl BackgroundAttachmentIndexer {
+    pub fn new(pool: SqlitePool, app_data_dir: String, search_manager: Arc<SearchManager>) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+        let cache_dir = PathBuf::from(&app_data_dir).join("attachments");
+
+        Self {
+            pool,
+            storage: LocalFileStorage::new(cache_dir),
+            search_manager,
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[BackgroundAttachmentIndexer] Starting background attachment indexer");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let indexed = match this.index_pending().await {
+                    Ok(indexed) => indexed,
+                    Err(error) => {
+                        log::error!(
+                            "[BackgroundAttachmentIndexer] Failed to index attachments: {}",
+                            error
+                        );
+                        0
+                    }
+                };
+
+                // Keep going while there is a backlog, e.g. after the first start
+                let delay = if indexed as i64 >= BATCH_SIZE {
+                    Duration::from_secs(1)
+                } else {
+                    this.poll_interval
+                };
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[BackgroundAttachmentIndexer] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(delay) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[BackgroundAttachmentIndexer] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    /// Extract the text of the next batch of attachments, returning how many
+    /// were processed
+    async fn index_pending(&self) -> Result<usize, String> {
+        let attachment_repo = SqliteAttachmentRepository::new(self.pool.clone());
+
+        let attachments = attachment_repo
+            .find_pending_text_extraction(EXTRACTABLE_FILE_TYPES, MAX_ATTACHMENT_SIZE, BATCH_SIZE)
+            .await
+            .map_err(|e| format!("Failed to query attachments to index: {}", e))?;
+
+        let mut email_ids = HashSet::new();
+        for attachment in &attachments {
+            // Marked as attempted first, so an attachment that takes the
+            // extraction down is not tried again on every start
+            attachment_repo
+                .save_text(attachment, None)
+                .await
+                .map_err(|e| format!("Failed to mark {} as attempted: {}", attachment.id, e))?;
+
+            let text = self.extract(attachment).await;
+            attachment_repo
+                .save_text(attachment, text.as_deref())
+                .await
+                .map_err(|e| format!("Failed to save text of {}: {}", attachment.id, e))?;
+
+            if text.is_some() {
+                email_ids.insert(attachment.email_id);
+            }
+        }
+
+        if !email_ids.is_empty() {
+            self.reindex_emails(email_ids.into_iter().collect()).await?;
+        }
+
+        Ok(attachments.len())
+    }
+
+    async fn extract(&self, attachment: &Attachment) -> Option<String> {
+        let cache_path = attachment.cache_path.as_deref()?;
+        let data = match self
+            .storage
+            .retrieve(&PathGenerator::cache_path_to_pathbuf(cache_path))
+            .await
+        {
+            Ok(data) => data,
+            Err(e) => {
+                log::warn!(
+                    "[BackgroundAttachmentIndexer] Failed to read attachment {}: {}",
+                    attachment.id,
+                    e
+                );
+                return None;
+            }
+        };
+
+        let attachment = attachment.clone();
+        tokio::task::spawn_blocking(move || attachment_text::extract_text(&attachment, &data))
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    "[BackgroundAttachmentIndexer] Text extraction panicked: {}",
+                    e
+                );
+                None
+            })
+    }
+
+    async fn reindex_emails(&self, email_ids: Vec<Uuid>) -> Result<(), String> {
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+        let attachment_repo = SqliteAttachmentRepository::new(self.pool.clone());
+
+        let attachments = attachment_repo
+            .find_by_emails(&email_ids)
+            .await
+            .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+        let attachment_texts = attachment_repo
+            .find_texts_by_emails(&email_ids)
+            .await
+            .map_err(|e| format!("Failed to fetch attachment texts: {}", e))?;
+
+        let mut emails = Vec::new();
+        for email_id in email_ids {
+            match email_repo.find_by_id(email_id).await {
+                Ok(Some(email)) if email.sync_status == "synced" => emails.push(email),
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(format!("Failed to fetch email {}: {}", email_id, e));
+                }
+            }
+        }
+
+        self.search_manager
+            .index_emails_batch(&emails, &attachments, &attachment_texts)
+            .await
+            .map_err(|e| format!("Failed to index emails: {}", e))?;
+        self.search_manager
+            .commit()
+            .await
+            .map_err(|e| format!("Failed to commit index: {}", e))?;
+
+        log::debug!(
+            "[BackgroundAttachmentIndexer] Reindexed {} emails with attachment text",
+            emails.len()
+        );
+
+        Ok(())
+    }
+}
@@ -1,13 +1,30 @@
+use super::backoff::Backoff;
 use super::error::{SyncError, SyncResult};
-use crate::database::repositories::{ContactRepository, RepositoryFactory};
+use crate::database::error::DatabaseError;
+use crate::database::repositories::{
+    ContactRepository, RepositoryFactory, SqliteContactRepository,
+};
 use crate::services::avatar_service::{AvatarProvider, AvatarService};
+use chrono::Utc;
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
+use uuid::Uuid;
 
 const FETCH_BATCH_SIZE: i64 = 20;
 const FETCH_INTERVAL_SECS: u64 = 30;
+/// Senders of Inbox mail received within this window are fetched first
+const RECENT_INBOX_DAYS: i64 = 14;
+/// Contacts without any interaction in this window are not fetched at all
+const INACTIVE_AFTER_DAYS: i64 = 365;
+/// Failed lookups are retried after 1h, 4h, 16h, ... and given up after this
+const MAX_AVATAR_ATTEMPTS: i64 = 4;
+const RETRY_BACKOFF: Backoff = Backoff {
+    base_secs: 60 * 60,
+    growth: 4,
+    max_secs: 16 * 60 * 60,
+};
 
 pub struct BackgroundAvatarFetcher {
     pool: SqlitePool,
@@ -88,8 +105,13 @@ impl BackgroundAvatarFetcher {
         let repo_factory = RepositoryFactory::new(pool.clone());
         let contact_repo = repo_factory.contact_repository();
 
+        let now = Utc::now();
         let contacts = contact_repo
-            .find_contacts_without_avatars(FETCH_BATCH_SIZE)
+            .find_contacts_without_avatars(
+                FETCH_BATCH_SIZE,
+                now - chrono::Duration::days(INACTIVE_AFTER_DAYS),
+                now - chrono::Duration::days(RECENT_INBOX_DAYS),
+            )
             .await
             .map_err(|e| SyncError::DatabaseError(format!("Failed to fetch contacts: {}", e)))?;
 
@@ -121,19 +143,19 @@ impl BackgroundAvatarFetcher {
                         );
                     }
                 }
-                Err(e) => {
-                    if let Err(e) = contact_repo.update_avatar(contact.id, "none", None).await {
+                Err(fetch_error) => {
+                    log::debug!(
+                        "[BackgroundAvatarFetcher] Failed to fetch avatar for {}: {}",
+                        contact.email,
+                        fetch_error
+                    );
+
+                    if let Err(e) = Self::record_failure(&contact_repo, contact.id).await {
                         log::warn!(
                             "[BackgroundAvatarFetcher] Failed to update avatar for contact {}: {}",
                             contact.id,
                             e
                         );
-                    } else {
-                        log::debug!(
-                            "[BackgroundAvatarFetcher] Failed to fetch avatar for {}: {}",
-                            contact.email,
-                            e
-                        );
                     }
                 }
             }
@@ -143,4 +165,39 @@ impl BackgroundAvatarFetcher {
 
         Ok(())
     }
+    /// Schedules another lookup with exponential backoff, or marks the contact
+    /// as having no avatar once the attempts are used up
+    async fn record_failure(
+        contact_repo: &SqliteContactRepository,
+        contact_id: Uuid,
+    ) -> Result<(), DatabaseError> {
+        let attempts = contact_repo.increment_avatar_attempts(contact_id).await?;
+
+        match retry_delay(attempts) {
+            Some(delay) => {
+                contact_repo
+                    .schedule_avatar_retry(contact_id, Utc::now() + delay)
+                    .await
+            }
+            None => contact_repo.update_avatar(contact_id, "none", None).await,
+        }
+    }
+}
+
+/// Delay before the next lookup after `attempts` failures, `None` to give up
+fn retry_delay(attempts: i64) -> Option<chrono::Duration> {
+    (attempts < MAX_AVATAR_ATTEMPTS).then(|| RETRY_BACKOFF.delay(attempts - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Some(chrono::Duration::hours(1)));
+        assert_eq!(retry_delay(2), Some(chrono::Duration::hours(4)));
+        assert_eq!(retry_delay(3), Some(chrono::Duration::hours(16)));
+        assert_eq!(retry_delay(MAX_AVATAR_ATTEMPTS), None);
+    }
 }
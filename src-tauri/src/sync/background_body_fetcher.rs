@@ -1,12 +1,18 @@
 use super::attachment_handler::AttachmentHandler;
+use super::attachment_policy::AttachmentDownloadPolicy;
 use super::auth::CredentialStore;
+use super::bandwidth;
+use super::calendar_invites;
+use super::delivery_status;
 use super::error::{SyncError, SyncResult};
 use super::provider::ProviderFactory;
+use super::smime_signatures;
 use super::storage::LocalFileStorage;
 use super::types::{ProviderCredentials, SyncFolder};
+use crate::config::Settings;
 use crate::database::models::account::AccountType;
 use crate::database::models::{account::Account, email::EmailSyncStatus};
-use crate::database::repositories::{AccountRepository, RepositoryFactory};
+use crate::database::repositories::{AccountRepository, RepositoryFactory, SyncStateRepository};
 use chrono::Utc;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
@@ -24,6 +30,7 @@ pub struct BackgroundBodyFetcher {
     pool: SqlitePool,
     app_data_dir: String,
     credential_store: Arc<CredentialStore>,
+    settings: Arc<Settings>,
     active_fetches: Arc<RwLock<HashMap<Uuid, bool>>>,
     shutdown_tx: tokio::sync::broadcast::Sender<()>,
 }
@@ -33,6 +40,7 @@ impl BackgroundBodyFetcher {
         pool: SqlitePool,
         app_data_dir: String,
         credential_store: Arc<CredentialStore>,
+        settings: Arc<Settings>,
     ) -> Self {
         let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
 
@@ -40,6 +48,7 @@ impl BackgroundBodyFetcher {
             pool,
             app_data_dir,
             credential_store,
+            settings,
             active_fetches: Arc::new(RwLock::new(HashMap::new())),
             shutdown_tx,
         }
@@ -51,6 +60,7 @@ impl BackgroundBodyFetcher {
 
         let pool = self.pool.clone();
         let credential_store = Arc::clone(&self.credential_store);
+        let settings = Arc::clone(&self.settings);
         let active_fetches = Arc::clone(&self.active_fetches);
         let app_data_dir = self.app_data_dir.clone();
         let mut shutdown_rx = self.shutdown_tx.subscribe();
@@ -63,11 +73,17 @@ impl BackgroundBodyFetcher {
                         break;
                     }
                     _ = sleep(Duration::from_secs(FETCH_INTERVAL_SECS)) => {
+                        // Bodies are downloaded when opened instead
+                        if bandwidth::is_metered(Some(&settings)) {
+                            continue;
+                        }
+
                         if let Err(e) = Self::fetch_pending_bodies(
                             &pool,
                             &app_data_dir,
                             &credential_store,
                             &active_fetches,
+                            &settings,
                         ).await {
                             log::error!("[BackgroundBodyFetcher] Error fetching bodies: {}", e);
                         }
@@ -91,9 +107,12 @@ impl BackgroundBodyFetcher {
         app_data_dir: &str,
         credential_store: &Arc<CredentialStore>,
         active_fetches: &Arc<RwLock<HashMap<Uuid, bool>>>,
+        settings: &Arc<Settings>,
     ) -> SyncResult<()> {
         let repo_factory = RepositoryFactory::new(pool.clone());
         let account_repo = repo_factory.account_repository();
+        let sync_state_repo = repo_factory.sync_state_repository();
+        let policy = AttachmentDownloadPolicy::from_settings(Some(settings));
 
         let accounts = account_repo
             .find_all()
@@ -106,6 +125,14 @@ impl BackgroundBodyFetcher {
         );
 
         for account in accounts {
+            if sync_state_repo
+                .is_account_paused(account.id)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+            {
+                continue;
+            }
+
             {
                 let fetches = active_fetches.read().await;
                 if fetches.get(&account.id).copied().unwrap_or(false) {
@@ -126,6 +153,7 @@ impl BackgroundBodyFetcher {
             let app_data_dir_clone = app_data_dir.to_string();
             let credential_store_clone = Arc::clone(credential_store);
             let active_fetches_clone = Arc::clone(active_fetches);
+            let settings_clone = Arc::clone(settings);
 
             tokio::spawn(async move {
                 if let Err(e) = Self::fetch_bodies_for_account(
@@ -133,6 +161,8 @@ impl BackgroundBodyFetcher {
                     &app_data_dir_clone,
                     &credential_store_clone,
                     &account,
+                    policy,
+                    &settings_clone,
                 )
                 .await
                 {
@@ -157,6 +187,8 @@ impl BackgroundBodyFetcher {
         app_data_dir: &str,
         credential_store: &Arc<CredentialStore>,
         account: &Account,
+        policy: AttachmentDownloadPolicy,
+        settings: &Settings,
     ) -> SyncResult<()> {
         log::debug!(
             "[BackgroundBodyFetcher] Fetching bodies for account {} ({})",
@@ -270,7 +302,7 @@ impl BackgroundBodyFetcher {
             .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
             match imap_provider.fetch_email_body(&folder, remote_id).await {
-                Ok((body_plain, body_html, headers, sent_at, attachments, snippet)) => {
+                Ok((body_plain, body_html, headers, sent_at, attachments, snippet, signature)) => {
                     log::debug!(
                         "[BackgroundBodyFetcher] Successfully fetched body for email {}",
                         email_id
@@ -311,13 +343,16 @@ impl BackgroundBodyFetcher {
                         );
 
                         let processed = attachment_handler
-                            .process_attachments(email_id, account.id, &attachments)
+                            .process_attachments(email_id, account.id, &attachments, policy)
                             .await?;
 
                         for (att_id, is_inline) in processed {
                             if is_inline {
                                 let attachment =
                                     attachment_handler.get_attachment_metadata(att_id).await?;
+                                if !policy.should_download(true, attachment.size) {
+                                    continue;
+                                }
 
                                 if let Some(att_with_data) = attachments
                                     .iter()
@@ -345,10 +380,52 @@ impl BackgroundBodyFetcher {
                         }
                     }
 
+                    // Headers-only syncs only see a delivery report's status part now
+                    if let Err(e) =
+                        delivery_status::process_report(pool, email_id, &attachments).await
+                    {
+                        log::warn!(
+                            "[BackgroundBodyFetcher] Failed to process delivery report {}: {}",
+                            email_id,
+                            e
+                        );
+                    }
+
+                    if let Err(e) =
+                        calendar_invites::process_invite(pool, email_id, &attachments).await
+                    {
+                        log::warn!(
+                            "[BackgroundBodyFetcher] Failed to process calendar invite {}: {}",
+                            email_id,
+                            e
+                        );
+                    }
+
+                    if let Some(check) = &signature {
+                        if let Err(e) =
+                            smime_signatures::record_signature(pool, email_id, check).await
+                        {
+                            log::warn!(
+                                "[BackgroundBodyFetcher] Failed to record S/MIME signature of {}: {}",
+                                email_id,
+                                e
+                            );
+                        }
+                    }
+
                     log::info!(
                         "[BackgroundBodyFetcher] Successfully synced body for email {}",
                         email_id
                     );
+
+                    let downloaded = body_plain.as_ref().map_or(0, |b| b.len())
+                        + body_html.as_ref().map_or(0, |b| b.len())
+                        + attachments
+                            .iter()
+                            .filter_map(|a| a.data.as_ref())
+                            .map(|data| data.len())
+                            .sum::<usize>();
+                    bandwidth::throttle(settings, downloaded as u64).await;
                 }
                 Err(e) => {
                     log::error!(
@@ -1,5 +1,7 @@
 use super::error::{SyncError, SyncResult};
 use super::storage::{FileStorage, LocalFileStorage, PathGenerator};
+use crate::config::Settings;
+use serde::Serialize;
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use std::time::Duration;
@@ -10,19 +12,29 @@ use uuid::Uuid;
 const CLEANUP_BATCH_SIZE: i64 = 50;
 const CLEANUP_INTERVAL_SECS: u64 = 60;
 /// Tombstoned emails older than this are permanently deleted
-const TOMBSTONE_RETENTION_DAYS: i64 = 30;
+pub const TOMBSTONE_RETENTION_DAYS: i64 = 30;
 /// Completed pending operations older than this are cleaned up
 const COMPLETED_OPS_RETENTION_DAYS: i64 = 7;
+/// Maximum size of the attachment cache in MB; 0 means unlimited
+const ATTACHMENT_CACHE_MAX_SETTING: &str = "storage.attachmentCacheMaxMb";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentCacheUsage {
+    pub used_bytes: u64,
+    /// None when the cache size is unlimited
+    pub quota_bytes: Option<u64>,
+}
 
 pub struct BackgroundCleanup {
     pool: SqlitePool,
     storage: Arc<LocalFileStorage>,
+    settings: Arc<Settings>,
     active_cleanup: Arc<RwLock<bool>>,
     shutdown_tx: tokio::sync::broadcast::Sender<()>,
 }
 
 impl BackgroundCleanup {
-    pub fn new(pool: SqlitePool, app_data_dir: String) -> Self {
+    pub fn new(pool: SqlitePool, app_data_dir: String, settings: Arc<Settings>) -> Self {
         let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
         let cache_dir = std::path::PathBuf::from(&app_data_dir).join("attachments");
         let storage = Arc::new(LocalFileStorage::new(cache_dir));
@@ -30,6 +42,7 @@ impl BackgroundCleanup {
         Self {
             pool,
             storage,
+            settings,
             active_cleanup: Arc::new(RwLock::new(false)),
             shutdown_tx,
         }
@@ -41,6 +54,7 @@ impl BackgroundCleanup {
 
         let pool = self.pool.clone();
         let storage = Arc::clone(&self.storage);
+        let settings = Arc::clone(&self.settings);
         let active_cleanup = Arc::clone(&self.active_cleanup);
         let mut shutdown_rx = self.shutdown_tx.subscribe();
 
@@ -73,6 +87,12 @@ impl BackgroundCleanup {
                             log::error!("[BackgroundCleanup] Error during operations cleanup: {}", e);
                         }
 
+                        if let Some(quota) = Self::cache_quota(&settings) {
+                            if let Err(e) = Self::enforce_cache_quota(&pool, &storage, quota).await {
+                                log::error!("[BackgroundCleanup] Error during attachment cache eviction: {}", e);
+                            }
+                        }
+
                         {
                             let mut is_active = active_cleanup.write().await;
                             *is_active = false;
@@ -132,34 +152,8 @@ impl BackgroundCleanup {
             let email_id = Uuid::parse_str(&email_record.id)
                 .map_err(|e| SyncError::DatabaseError(format!("Invalid email ID: {}", e)))?;
 
-            log::debug!("[BackgroundCleanup] Cleaning up email {}", email_id);
-
-            if email_record.has_attachments {
-                if let Err(e) = Self::delete_email_attachments(pool, storage, email_id).await {
-                    log::error!(
-                        "[BackgroundCleanup] Failed to delete attachments for email {}: {}",
-                        email_id,
-                        e
-                    );
-                }
-            }
-
-            let email_id_str = email_id.to_string();
-            sqlx::query!("DELETE FROM email_labels WHERE email_id = ?", email_id_str)
-                .execute(pool)
-                .await
-                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
-
-            sqlx::query!("DELETE FROM emails WHERE id = ?", email_id_str)
-                .execute(pool)
-                .await
-                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
-
+            Self::purge_email(pool, storage, email_id, email_record.has_attachments).await?;
             cleaned_count += 1;
-            log::debug!(
-                "[BackgroundCleanup] Successfully cleaned up email {}",
-                email_id
-            );
         }
 
         if cleaned_count > 0 {
@@ -172,6 +166,75 @@ impl BackgroundCleanup {
         Ok(())
     }
 
+    /// Permanently delete the given tombstoned emails right away, without waiting
+    /// for the retention period. Emails that are not deleted are left alone.
+    pub async fn purge_emails(&self, email_ids: &[Uuid]) -> SyncResult<u64> {
+        let mut purged_count = 0;
+
+        for email_id in email_ids {
+            let email_id_str = email_id.to_string();
+            let has_attachments = sqlx::query_scalar!(
+                "SELECT has_attachments FROM emails WHERE id = ? AND is_deleted = 1",
+                email_id_str
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+            if let Some(has_attachments) = has_attachments {
+                Self::purge_email(&self.pool, &self.storage, *email_id, has_attachments).await?;
+                purged_count += 1;
+            }
+        }
+
+        if purged_count > 0 {
+            log::info!(
+                "[BackgroundCleanup] Purged {} deleted emails on request",
+                purged_count
+            );
+        }
+
+        Ok(purged_count)
+    }
+
+    /// Remove an email together with its labels and cached attachment files
+    async fn purge_email(
+        pool: &SqlitePool,
+        storage: &Arc<LocalFileStorage>,
+        email_id: Uuid,
+        has_attachments: bool,
+    ) -> SyncResult<()> {
+        log::debug!("[BackgroundCleanup] Cleaning up email {}", email_id);
+
+        if has_attachments {
+            if let Err(e) = Self::delete_email_attachments(pool, storage, email_id).await {
+                log::error!(
+                    "[BackgroundCleanup] Failed to delete attachments for email {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
+        let email_id_str = email_id.to_string();
+        sqlx::query!("DELETE FROM email_labels WHERE email_id = ?", email_id_str)
+            .execute(pool)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        sqlx::query!("DELETE FROM emails WHERE id = ?", email_id_str)
+            .execute(pool)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::debug!(
+            "[BackgroundCleanup] Successfully cleaned up email {}",
+            email_id
+        );
+
+        Ok(())
+    }
+
     /// Delete all attachment files for an email
     async fn delete_email_attachments(
         pool: &SqlitePool,
@@ -252,6 +315,93 @@ impl BackgroundCleanup {
         Ok(())
     }
 
+    /// Attachment cache quota in bytes, or None if unlimited
+    fn cache_quota(settings: &Settings) -> Option<u64> {
+        settings
+            .get::<u64>(ATTACHMENT_CACHE_MAX_SETTING)
+            .ok()
+            .filter(|max_mb| *max_mb > 0)
+            .map(|max_mb| max_mb * 1024 * 1024)
+    }
+
+    /// Current size of the attachment cache and its quota
+    pub async fn cache_usage(&self) -> SyncResult<AttachmentCacheUsage> {
+        Ok(AttachmentCacheUsage {
+            used_bytes: self.storage.total_size().await?,
+            quota_bytes: Self::cache_quota(&self.settings),
+        })
+    }
+
+    /// Evict the least recently opened cached attachments until the cache fits
+    /// its quota. Their metadata is kept, so they are downloaded again on demand.
+    async fn enforce_cache_quota(
+        pool: &SqlitePool,
+        storage: &Arc<LocalFileStorage>,
+        quota: u64,
+    ) -> SyncResult<()> {
+        let mut used = storage.total_size().await?;
+        if used <= quota {
+            return Ok(());
+        }
+
+        log::info!(
+            "[BackgroundCleanup] Attachment cache uses {} bytes, more than its quota of {} bytes",
+            used,
+            quota
+        );
+
+        let mut evicted_count = 0;
+
+        while used > quota {
+            let attachments = sqlx::query!(
+                r#"
+                SELECT id, cache_path as "cache_path!"
+                FROM attachments
+                WHERE is_cached = 1 AND cache_path IS NOT NULL
+                ORDER BY COALESCE(last_opened_at, created_at) ASC
+                LIMIT ?
+                "#,
+                CLEANUP_BATCH_SIZE
+            )
+            .fetch_all(pool)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+            if attachments.is_empty() {
+                break;
+            }
+
+            for attachment in attachments {
+                if used <= quota {
+                    break;
+                }
+
+                let path_buf = PathGenerator::cache_path_to_pathbuf(&attachment.cache_path);
+                let file_size = storage.file_size(&path_buf).await;
+                storage.delete(&path_buf).await?;
+
+                sqlx::query!(
+                    "UPDATE attachments SET is_cached = 0, cache_path = NULL WHERE id = ?",
+                    attachment.id
+                )
+                .execute(pool)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+                used = used.saturating_sub(file_size);
+                evicted_count += 1;
+            }
+        }
+
+        log::info!(
+            "[BackgroundCleanup] Evicted {} attachments from the cache, {} bytes remain",
+            evicted_count,
+            used
+        );
+
+        Ok(())
+    }
+
     /// Manually trigger cleanup (for testing or admin tools)
     pub async fn trigger_cleanup(&self) -> SyncResult<()> {
         log::info!("[BackgroundCleanup] Manual cleanup triggered");
@@ -0,0 +1,464 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use super::auth::CredentialStore;
+use super::providers::gmail::GmailProvider;
+use super::providers::office365::Office365Provider;
+use super::types::SyncContactDates;
+use crate::config::Settings;
+use crate::database::models::account::AccountType;
+use crate::database::models::contact::{Contact, ContactDate};
+use crate::database::repositories::{
+    AccountRepository, ContactRepository, SqliteAccountRepository, SqliteContactRepository,
+};
+use crate::services::notification_service::NotificationService;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 60 * 15;
+const PROVIDER_SYNC_INTERVAL_HOURS: i64 = 24;
+const NOTIFY_SETTING: &str = "contacts.dates.notify";
+const NOTIFY_HOUR_SETTING: &str = "contacts.dates.notifyHour";
+const DEFAULT_NOTIFY_HOUR: u32 = 9;
+
+const BIRTHDAY: &str = "birthday";
+const ANNIVERSARY: &str = "anniversary";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingContactDate {
+    pub contact_id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub kind: String,
+    pub date: NaiveDate,
+    /// Age or number of years being celebrated, when the original year is known
+    pub years: Option<i32>,
+}
+
+/// Emits birthday and anniversary notifications for contacts
+///
+/// Dates are pulled once a day from Google People and Microsoft Graph for accounts
+/// that granted contacts access; manually entered dates are used for every account.
+pub struct BackgroundContactDateNotifier {
+    pool: SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    notification_service: Arc<NotificationService>,
+    settings: Arc<Settings>,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+    last_provider_sync: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl BackgroundContactDateNotifier {
+    pub fn new(
+        pool: SqlitePool,
+        credential_store: Arc<CredentialStore>,
+        notification_service: Arc<NotificationService>,
+        settings: Arc<Settings>,
+    ) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            credential_store,
+            notification_service,
+            settings,
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+            last_provider_sync: Mutex::new(None),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[BackgroundContactDateNotifier] Starting contact date notifier");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                this.run_once().await;
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[BackgroundContactDateNotifier] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(this.poll_interval) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[BackgroundContactDateNotifier] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    async fn run_once(&self) {
+        let sync_due = {
+            let last_sync = self.last_provider_sync.lock().await;
+            last_sync.is_none_or(|at| {
+                Utc::now() - at >= chrono::Duration::hours(PROVIDER_SYNC_INTERVAL_HOURS)
+            })
+        };
+
+        if sync_due {
+            if let Err(error) = self.sync_provider_dates().await {
+                log::error!(
+                    "[BackgroundContactDateNotifier] Failed to sync contact dates: {}",
+                    error
+                );
+            }
+        }
+
+        if !self.settings.get::<bool>(NOTIFY_SETTING).unwrap_or(true) {
+            return;
+        }
+
+        let now = Local::now();
+        let notify_hour = self
+            .settings
+            .get::<u32>(NOTIFY_HOUR_SETTING)
+            .unwrap_or(DEFAULT_NOTIFY_HOUR);
+        if now.hour() < notify_hour {
+            return;
+        }
+
+        if let Err(error) = self.notify_due_dates(now.date_naive()).await {
+            log::error!(
+                "[BackgroundContactDateNotifier] Failed to process contact dates: {}",
+                error
+            );
+        }
+    }
+
+    /// Pulls birthdays and anniversaries from every account whose provider exposes them
+    pub async fn sync_provider_dates(&self) -> Result<usize, String> {
+        *self.last_provider_sync.lock().await = Some(Utc::now());
+
+        let accounts = SqliteAccountRepository::new(self.pool.clone())
+            .find_all()
+            .await
+            .map_err(|e| format!("Failed to load accounts: {}", e))?;
+
+        let mut updated = 0;
+        for account in accounts {
+            let dates = match account.account_type {
+                AccountType::Gmail => {
+                    match GmailProvider::new(account.id, Arc::clone(&self.credential_store)) {
+                        Ok(mut provider) => provider.fetch_contact_dates().await,
+                        Err(e) => Err(e),
+                    }
+                }
+                AccountType::Office365 => {
+                    match Office365Provider::new(account.id, Arc::clone(&self.credential_store)) {
+                        Ok(provider) => provider.fetch_contact_dates().await,
+                        Err(e) => Err(e),
+                    }
+                }
+                // IMAP accounts have no CardDAV address book configured
+                AccountType::Apple | AccountType::Imap => continue,
+            };
+
+            match dates {
+                Ok(dates) => updated += self.apply_provider_dates(&dates).await?,
+                Err(e) => log::warn!(
+                    "[BackgroundContactDateNotifier] Could not fetch contacts for account {}: {}",
+                    account.id,
+                    e
+                ),
+            }
+        }
+
+        log::debug!(
+            "[BackgroundContactDateNotifier] Updated dates for {} contacts",
+            updated
+        );
+        Ok(updated)
+    }
+
+    async fn apply_provider_dates(&self, dates: &[SyncContactDates]) -> Result<usize, String> {
+        let repo = SqliteContactRepository::new(self.pool.clone());
+        let mut updated = 0;
+
+        for entry in dates {
+            let birthday = normalize_date(entry.birthday.as_deref());
+            let anniversary = normalize_date(entry.anniversary.as_deref());
+            if birthday.is_none() && anniversary.is_none() {
+                continue;
+            }
+
+            let existing = repo
+                .find_by_email(&entry.email)
+                .await
+                .map_err(|e| format!("Failed to look up contact {}: {}", entry.email, e))?;
+
+            match existing {
+                Some(contact) => {
+                    // Provider dates only fill in or replace values, never clear them
+                    let birthday_unchanged = birthday.is_none() || contact.birthday == birthday;
+                    let anniversary_unchanged =
+                        anniversary.is_none() || contact.anniversary == anniversary;
+                    if birthday_unchanged && anniversary_unchanged {
+                        continue;
+                    }
+                    repo.update_dates(contact.id, birthday.as_deref(), anniversary.as_deref())
+                        .await
+                        .map_err(|e| format!("Failed to update contact dates: {}", e))?;
+                }
+                None => {
+                    let now = Utc::now();
+                    let contact = Contact {
+                        id: Uuid::now_v7(),
+                        display_name: entry.display_name.clone(),
+                        first_name: None,
+                        last_name: None,
+                        company: None,
+                        email: entry.email.clone(),
+                        ai_notes: None,
+                        source: "imported".to_string(),
+                        avatar_type: "unprocessed".to_string(),
+                        avatar_path: None,
+                        birthday,
+                        anniversary,
+                        is_vip: false,
+                        send_count: 0,
+                        receive_count: 0,
+                        last_used_at: None,
+                        first_seen_at: now,
+                        created_at: now,
+                        updated_at: now,
+                    };
+                    repo.create(&contact)
+                        .await
+                        .map_err(|e| format!("Failed to create contact: {}", e))?;
+                }
+            }
+
+            updated += 1;
+        }
+
+        Ok(updated)
+    }
+
+    async fn notify_due_dates(&self, today: NaiveDate) -> Result<(), String> {
+        let contacts = SqliteContactRepository::new(self.pool.clone())
+            .find_contacts_with_dates()
+            .await
+            .map_err(|e| format!("Failed to load contacts with dates: {}", e))?;
+
+        for contact in &contacts {
+            for (kind, years) in due_dates(contact, today) {
+                if !self
+                    .claim_notification(contact.id, kind, today.year())
+                    .await?
+                {
+                    continue;
+                }
+
+                self.notification_service
+                    .notify_contact_date(contact, kind, today.year(), years)
+                    .await
+                    .map_err(|e| {
+                        format!(
+                            "Failed to send {} notification for {}: {}",
+                            kind, contact.id, e
+                        )
+                    })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records the notification, returning false when it was already sent this year
+    async fn claim_notification(
+        &self,
+        contact_id: Uuid,
+        kind: &str,
+        year: i32,
+    ) -> Result<bool, String> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO contact_date_notifications (contact_id, kind, year)
+            VALUES (?, ?, ?)
+            "#,
+        )
+        .bind(contact_id.to_string())
+        .bind(kind)
+        .bind(year)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to store contact date notification: {}", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Lists birthdays and anniversaries falling within the next `days` days, soonest first
+pub async fn load_upcoming(
+    pool: &SqlitePool,
+    today: NaiveDate,
+    days: u32,
+) -> Result<Vec<UpcomingContactDate>, String> {
+    let contacts = SqliteContactRepository::new(pool.clone())
+        .find_contacts_with_dates()
+        .await
+        .map_err(|e| format!("Failed to load contacts with dates: {}", e))?;
+
+    let mut upcoming: Vec<UpcomingContactDate> = contacts
+        .iter()
+        .flat_map(|contact| upcoming_dates(contact, today, days))
+        .collect();
+    upcoming.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(upcoming)
+}
+
+fn normalize_date(value: Option<&str>) -> Option<String> {
+    value
+        .and_then(ContactDate::parse)
+        .map(|date| date.to_string())
+}
+
+fn contact_dates(contact: &Contact) -> impl Iterator<Item = (&'static str, ContactDate)> + '_ {
+    [
+        (BIRTHDAY, contact.birthday.as_deref()),
+        (ANNIVERSARY, contact.anniversary.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(kind, value)| Some((kind, ContactDate::parse(value?)?)))
+}
+
+/// Dates of a contact that fall on `today`, with the number of years when known
+fn due_dates(contact: &Contact, today: NaiveDate) -> Vec<(&'static str, Option<i32>)> {
+    contact_dates(contact)
+        .filter(|(_, date)| date.occurrence_in(today.year()) == Some(today))
+        .map(|(kind, date)| (kind, date.years_at(today.year())))
+        .collect()
+}
+
+fn upcoming_dates(contact: &Contact, today: NaiveDate, days: u32) -> Vec<UpcomingContactDate> {
+    let horizon = today + chrono::Duration::days(days as i64);
+
+    contact_dates(contact)
+        .filter_map(|(kind, date)| {
+            let next = [today.year(), today.year() + 1]
+                .into_iter()
+                .filter_map(|year| date.occurrence_in(year))
+                .find(|occurrence| *occurrence >= today)?;
+
+            (next <= horizon).then(|| UpcomingContactDate {
+                contact_id: contact.id,
+                name: contact.full_name(),
+                email: contact.email.clone(),
+                kind: kind.to_string(),
+                date: next,
+                years: date.years_at(next.year()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(birthday: Option<&str>, anniversary: Option<&str>) -> Contact {
+        let now = Utc::now();
+        Contact {
+            id: Uuid::now_v7(),
+            display_name: Some("Ada Lovelace".to_string()),
+            first_name: None,
+            last_name: None,
+            company: None,
+            email: "ada@example.com".to_string(),
+            ai_notes: None,
+            source: "manual".to_string(),
+            avatar_type: "none".to_string(),
+            avatar_path: None,
+            birthday: birthday.map(ToString::to_string),
+            anniversary: anniversary.map(ToString::to_string),
+            is_vip: false,
+            send_count: 0,
+            receive_count: 0,
+            last_used_at: None,
+            first_seen_at: now,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_contact_date() {
+        let full = ContactDate::parse("1815-12-10").unwrap();
+        assert_eq!(full.year, Some(1815));
+        assert_eq!(full.to_string(), "1815-12-10");
+
+        let no_year = ContactDate::parse("--02-29").unwrap();
+        assert_eq!(no_year.year, None);
+        assert_eq!(no_year.to_string(), "--02-29");
+
+        assert!(ContactDate::parse("--02-30").is_none());
+        assert!(ContactDate::parse("not a date").is_none());
+    }
+
+    #[test]
+    fn test_due_dates() {
+        let ada = contact(Some("1815-12-10"), Some("--06-08"));
+
+        assert_eq!(
+            due_dates(&ada, date(2025, 12, 10)),
+            vec![(BIRTHDAY, Some(210))]
+        );
+        assert_eq!(due_dates(&ada, date(2025, 6, 8)), vec![(ANNIVERSARY, None)]);
+        assert!(due_dates(&ada, date(2025, 6, 9)).is_empty());
+    }
+
+    #[test]
+    fn test_leap_day_falls_back_in_common_years() {
+        let leapling = contact(Some("2000-02-29"), None);
+
+        assert_eq!(
+            due_dates(&leapling, date(2025, 2, 28)),
+            vec![(BIRTHDAY, Some(25))]
+        );
+        assert_eq!(
+            due_dates(&leapling, date(2028, 2, 29)),
+            vec![(BIRTHDAY, Some(28))]
+        );
+        assert!(due_dates(&leapling, date(2028, 2, 28)).is_empty());
+    }
+
+    #[test]
+    fn test_upcoming_dates_wrap_into_next_year() {
+        let ada = contact(Some("1815-01-03"), None);
+
+        let upcoming = upcoming_dates(&ada, date(2025, 12, 30), 7);
+        assert_eq!(upcoming.len(), 1);
+        assert_eq!(upcoming[0].date, date(2026, 1, 3));
+        assert_eq!(upcoming[0].years, Some(211));
+
+        assert!(upcoming_dates(&ada, date(2025, 12, 30), 3).is_empty());
+    }
+}
@@ -306,6 +306,11 @@ impl BackgroundReminderNotifier {
                     .map_err(|error| format!("Failed to read email.remind_at: {error}"))?,
                 "email.remind_at",
             )?,
+            snoozed_until: parse_opt_dt(
+                row.try_get("snoozed_until")
+                    .map_err(|error| format!("Failed to read email.snoozed_until: {error}"))?,
+                "email.snoozed_until",
+            )?,
             is_read: row
                 .try_get("is_read")
                 .map_err(|error| format!("Failed to read email.is_read: {error}"))?,
@@ -371,6 +376,9 @@ impl BackgroundReminderNotifier {
             size: row
                 .try_get("size")
                 .map_err(|error| format!("Failed to read email.size: {error}"))?,
+            priority: row.try_get("priority").unwrap_or(0),
+            risk_score: 0,
+            risk_reasons: sqlx::types::Json(Vec::new()),
         })
     }
 
@@ -0,0 +1,120 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::time::sleep;
+
+use crate::database::repositories::{EmailRepository, SqliteEmailRepository};
+use crate::services::app_lock;
+use crate::services::notification_service::NotificationService;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 30;
+
+/// Brings snoozed emails back into their folder once `snoozed_until` has passed
+pub struct BackgroundSnoozeWorker {
+    pool: SqlitePool,
+    notification_service: Arc<NotificationService>,
+    app_handle: AppHandle,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+}
+
+impl BackgroundSnoozeWorker {
+    pub fn new(
+        pool: SqlitePool,
+        notification_service: Arc<NotificationService>,
+        app_handle: AppHandle,
+    ) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            notification_service,
+            app_handle,
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[BackgroundSnoozeWorker] Starting background snooze worker");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                // Runs immediately on startup so emails that came due while the app
+                // was closed resurface right away
+                if let Err(error) = this.resurface_due().await {
+                    log::error!(
+                        "[BackgroundSnoozeWorker] Failed to resurface snoozed emails: {}",
+                        error
+                    );
+                }
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[BackgroundSnoozeWorker] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(this.poll_interval) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[BackgroundSnoozeWorker] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    async fn resurface_due(&self) -> Result<(), String> {
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+
+        let due = email_repo
+            .find_due_snoozed(Utc::now())
+            .await
+            .map_err(|e| format!("Failed to query due snoozed emails: {}", e))?;
+
+        for mut email in due {
+            email_repo
+                .update_snoozed_until(email.id, None)
+                .await
+                .map_err(|e| format!("Failed to clear snooze for {}: {}", email.id, e))?;
+            email.snoozed_until = None;
+
+            log::debug!("[BackgroundSnoozeWorker] Resurfaced email {}", email.id);
+
+            if let Err(e) = app_lock::emit_mail_event(&self.app_handle, "email:unsnoozed", &email) {
+                log::warn!(
+                    "[BackgroundSnoozeWorker] Failed to emit email:unsnoozed for {}: {}",
+                    email.id,
+                    e
+                );
+            }
+
+            if let Err(e) = self.notification_service.notify_snoozed_email(&email).await {
+                log::warn!(
+                    "[BackgroundSnoozeWorker] Failed to notify for snoozed email {}: {}",
+                    email.id,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
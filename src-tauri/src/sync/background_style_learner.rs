@@ -0,0 +1,195 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::config::Settings;
+use crate::database::models::writing_style_profile::WritingStyleProfile;
+use crate::database::repositories::{
+    AccountRepository, EmailRepository, SqliteAccountRepository, SqliteEmailRepository,
+    SqliteWritingStyleProfileRepository, WritingStyleProfileRepository,
+};
+use crate::services::ai_exclusions::AiExclusions;
+use crate::services::corvus::CorvusService;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 60 * 60 * 6;
+const LEARN_SETTING: &str = "ai.writingStyle.learnFromSent";
+/// Sent emails sampled per account
+const STYLE_SAMPLE_SIZE: i64 = 25;
+/// Fewer sent emails than this say too little about how the user writes
+const MIN_STYLE_SAMPLES: usize = 5;
+/// Profiles are relearned once they are this old
+const PROFILE_MAX_AGE_DAYS: i64 = 14;
+
+/// Learns a writing style profile per account from its sent mail, when the
+/// user opted in with `ai.writingStyle.learnFromSent`
+pub struct BackgroundStyleLearner {
+    pool: SqlitePool,
+    ai_service: Arc<CorvusService>,
+    settings: Arc<Settings>,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+}
+
+impl BackgroundStyleLearner {
+    pub fn new(pool: SqlitePool, ai_service: Arc<CorvusService>, settings: Arc<Settings>) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            ai_service,
+            settings,
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[BackgroundStyleLearner] Starting writing style learner");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                this.run_once().await;
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[BackgroundStyleLearner] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(this.poll_interval) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[BackgroundStyleLearner] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    async fn run_once(&self) {
+        if !self.settings.get::<bool>(LEARN_SETTING).unwrap_or(false) {
+            return;
+        }
+        if !self.ai_service.is_enabled().await {
+            log::debug!("[BackgroundStyleLearner] AI service not enabled, skipping");
+            return;
+        }
+
+        let accounts = match SqliteAccountRepository::new(self.pool.clone())
+            .find_all()
+            .await
+        {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                log::error!("[BackgroundStyleLearner] Failed to fetch accounts: {}", e);
+                return;
+            }
+        };
+        let profile_repo = SqliteWritingStyleProfileRepository::new(self.pool.clone());
+        let exclusions = AiExclusions::from_settings(&self.settings);
+
+        for account in accounts {
+            let learned_at = match profile_repo.find_by_account(account.id).await {
+                Ok(profile) => profile.map(|p| p.updated_at),
+                Err(e) => {
+                    log::warn!(
+                        "[BackgroundStyleLearner] Failed to fetch profile of account {}: {}",
+                        account.id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            if !is_profile_due(learned_at, Utc::now()) {
+                continue;
+            }
+
+            match learn_account_style(&self.pool, &self.ai_service, &exclusions, account.id).await {
+                Ok(Some(profile)) => log::info!(
+                    "[BackgroundStyleLearner] Learned writing style of account {} from {} sent emails",
+                    account.id,
+                    profile.sample_count
+                ),
+                Ok(None) => log::debug!(
+                    "[BackgroundStyleLearner] Account {} has too little sent mail to learn from",
+                    account.id
+                ),
+                Err(e) => log::warn!(
+                    "[BackgroundStyleLearner] Failed to learn writing style of account {}: {}",
+                    account.id,
+                    e
+                ),
+            }
+        }
+    }
+}
+
+/// Whether a profile learned at `learned_at` (None = never) should be relearned
+fn is_profile_due(learned_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    learned_at.is_none_or(|at| now - at >= chrono::Duration::days(PROFILE_MAX_AGE_DAYS))
+}
+
+/// Samples the account's recent sent mail that is not excluded from AI and
+/// stores the style profile the AI derives from it. Returns None when there is
+/// too little sent mail.
+pub async fn learn_account_style(
+    pool: &SqlitePool,
+    ai_service: &CorvusService,
+    exclusions: &AiExclusions,
+    account_id: Uuid,
+) -> Result<Option<WritingStyleProfile>, String> {
+    let samples = SqliteEmailRepository::new(pool.clone())
+        .find_recent_sent(account_id, STYLE_SAMPLE_SIZE)
+        .await
+        .map_err(|e| format!("Failed to fetch sent emails: {}", e))?;
+    let samples = exclusions.retain_allowed(pool, samples).await?;
+    if samples.len() < MIN_STYLE_SAMPLES {
+        return Ok(None);
+    }
+
+    let profile = ai_service.learn_writing_style(&samples).await?;
+
+    let profile_repo = SqliteWritingStyleProfileRepository::new(pool.clone());
+    profile_repo
+        .upsert(account_id, &profile, samples.len() as i64)
+        .await
+        .map_err(|e| format!("Failed to store writing style profile: {}", e))?;
+    profile_repo
+        .find_by_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to fetch writing style profile: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_profile_due() {
+        let now = Utc::now();
+
+        assert!(is_profile_due(None, now));
+        assert!(!is_profile_due(Some(now - chrono::Duration::days(3)), now));
+        assert!(is_profile_due(
+            Some(now - chrono::Duration::days(PROFILE_MAX_AGE_DAYS)),
+            now
+        ));
+    }
+}
@@ -4,24 +4,42 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tokio::task::JoinHandle;
+use tokio::task::{JoinHandle, JoinSet};
 use tokio::time::sleep;
 use uuid::Uuid;
 
 use super::auth::CredentialStore;
+use super::bandwidth;
 use super::error::{SyncError, SyncResult};
+use super::provider::ProviderFactory;
+use super::providers::imap::ImapProvider;
+use super::rate_limit;
+use super::sync_limits;
 use super::sync_manager::SyncManager;
-use super::sync_queue::{SyncPriority, SyncQueue, SyncQueueItem, SyncQueueWorker};
-use super::types::FolderType;
+use super::sync_queue::{SyncOutcome, SyncPriority, SyncQueue, SyncQueueItem, SyncQueueWorker};
+use super::types::{FolderType, SyncFolder};
 use crate::config::settings::Settings;
+use crate::database::models::account::{Account, AccountType};
 use crate::database::repositories::{
     AccountRepository, SqliteAccountRepository, SqliteSyncStateRepository, SyncStateRepository,
 };
 use crate::services::notification_service::NotificationService;
 
+/// Lower bound for folder sync intervals, in seconds
+pub const MIN_SYNC_INTERVAL: i64 = 60;
+
+/// A new account has no folders until its first sync, so the inbox to IDLE on
+/// is looked up again with growing waits before IDLE is given up
+const IDLE_INBOX_LOOKUPS: u32 = 10;
+const IDLE_INBOX_INITIAL_WAIT: Duration = Duration::from_secs(15);
+const IDLE_INBOX_MAX_WAIT: Duration = Duration::from_secs(5 * 60);
+
 /// Background sync task handle
 struct SyncTask {
     handle: JoinHandle<()>,
+    idle_handle: Option<JoinHandle<()>>,
+    /// Folder syncs of the account, shared by scheduled and requested syncs
+    queue: Arc<SyncQueue>,
 }
 
 /// Manages background synchronization tasks for all accounts
@@ -142,6 +160,34 @@ impl BackgroundSyncManager {
         let app_handle = self.app_handle.clone();
         let mut shutdown_rx = self.shutdown_tx.subscribe();
         let account_id_copy = *account_id;
+        let queue = Arc::new(SyncQueue::new(sync_limits::workers(&settings)));
+        let task_queue = Arc::clone(&queue);
+
+        let idle_handle = if matches!(account.account_type, AccountType::Imap | AccountType::Apple)
+        {
+            let pool = self.pool.clone();
+            let app_data_dir = self.app_data_dir.clone();
+            let credential_store = Arc::clone(&self.credential_store);
+            let settings = Arc::clone(&self.settings);
+            let app_handle = self.app_handle.clone();
+            let idle_shutdown_rx = self.shutdown_tx.subscribe();
+            let account = account.clone();
+
+            Some(tokio::spawn(async move {
+                Self::run_imap_idle(
+                    pool,
+                    app_data_dir,
+                    credential_store,
+                    settings,
+                    app_handle,
+                    account,
+                    idle_shutdown_rx,
+                )
+                .await;
+            }))
+        } else {
+            None
+        };
 
         let handle = tokio::spawn(async move {
             log::info!(
@@ -201,7 +247,7 @@ impl BackgroundSyncManager {
                         log::info!("Shutdown signal received for account {}", account_id_copy);
                         break;
                     }
-                    _ = Self::sync_folders_periodic(&pool, &app_data_dir, Arc::clone(&credential_store), Arc::clone(&settings), app_handle.clone(), account_id_copy) => {
+                    _ = Self::sync_folders_periodic(&pool, &app_data_dir, Arc::clone(&credential_store), Arc::clone(&settings), app_handle.clone(), account_id_copy, Arc::clone(&task_queue)) => {
                     }
                 }
             }
@@ -213,7 +259,14 @@ impl BackgroundSyncManager {
         });
 
         let mut tasks = self.tasks.write().await;
-        tasks.insert(*account_id, SyncTask { handle });
+        tasks.insert(
+            *account_id,
+            SyncTask {
+                handle,
+                idle_handle,
+                queue,
+            },
+        );
 
         Ok(())
     }
@@ -227,6 +280,10 @@ impl BackgroundSyncManager {
 
             let _ = self.shutdown_tx.send(());
 
+            if let Some(idle_handle) = task.idle_handle {
+                idle_handle.abort();
+            }
+
             match tokio::time::timeout(Duration::from_secs(10), task.handle).await {
                 Ok(_) => {
                     log::info!("Background sync stopped for account {}", account_id);
@@ -279,6 +336,135 @@ impl BackgroundSyncManager {
         tasks.contains_key(account_id)
     }
 
+    /// Sync a folder ahead of any scheduled work of its account and wait for
+    /// the result. Returns `None` when background sync is not running for the
+    /// account, so the caller syncs the folder itself.
+    pub async fn sync_folder_now(
+        &self,
+        account: &Account,
+        folder: SyncFolder,
+        full: bool,
+    ) -> Option<SyncOutcome> {
+        let queue = {
+            let tasks = self.tasks.read().await;
+            Arc::clone(&tasks.get(&account.id)?.queue)
+        };
+        let folder_id = folder.id?;
+
+        let item = SyncQueueItem {
+            account_id: account.id,
+            folder_id,
+            last_synced_at: folder.synced_at,
+            folder,
+            account: account.clone(),
+            priority: SyncPriority::High,
+            enqueued_at: Utc::now(),
+            full,
+        };
+
+        Some(queue.enqueue_and_wait(item).await)
+    }
+
+    /// Hold an IMAP IDLE connection on the account's inbox and sync it as soon as
+    /// the server reports changes. Falls back silently to periodic polling when
+    /// the server lacks IDLE support.
+    async fn run_imap_idle(
+        pool: SqlitePool,
+        app_data_dir: String,
+        credential_store: Arc<CredentialStore>,
+        settings: Arc<Settings>,
+        app_handle: tauri::AppHandle,
+        account: Account,
+        mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    ) {
+        let sync_manager = Arc::new(
+            SyncManager::new(pool, app_data_dir, Arc::clone(&credential_store))
+                .with_settings(settings)
+                .with_app_handle(app_handle),
+        );
+
+        let mut inbox = None;
+        for lookup in 0..IDLE_INBOX_LOOKUPS {
+            match sync_manager.get_folders(account.id).await {
+                Ok(folders) => {
+                    inbox = folders
+                        .into_iter()
+                        .find(|f| f.folder_type == FolderType::Inbox && f.id.is_some());
+                }
+                Err(e) => log::warn!(
+                    "Failed to load folders for IMAP IDLE on account {}: {}",
+                    account.id,
+                    e
+                ),
+            }
+            if inbox.is_some() || lookup + 1 == IDLE_INBOX_LOOKUPS {
+                break;
+            }
+
+            let wait = idle_inbox_wait(lookup);
+            log::debug!(
+                "No inbox yet for account {}, looking again for IMAP IDLE in {:?}",
+                account.id,
+                wait
+            );
+            tokio::select! {
+                _ = shutdown_rx.recv() => return,
+                _ = sleep(wait) => {}
+            }
+        }
+
+        let Some(inbox) = inbox else {
+            log::warn!(
+                "No inbox found for account {}, IMAP IDLE not started",
+                account.id
+            );
+            return;
+        };
+
+        let provider = match ProviderFactory::create(&account, credential_store) {
+            Ok(provider) => provider,
+            Err(e) => {
+                log::error!(
+                    "Failed to create IMAP provider for IDLE on account {}: {}",
+                    account.id,
+                    e
+                );
+                return;
+            }
+        };
+
+        let Some(imap_provider) = provider.as_any().downcast_ref::<ImapProvider>() else {
+            return;
+        };
+
+        let result = imap_provider
+            .run_idle(&inbox, shutdown_rx, || {
+                let sync_manager = Arc::clone(&sync_manager);
+                let account = account.clone();
+                let inbox = inbox.clone();
+                async move {
+                    if let Err(e) = sync_manager.sync_folder(&account, &inbox, false).await {
+                        log::warn!(
+                            "IDLE-triggered sync failed for folder {} (account {}): {}",
+                            inbox.name,
+                            account.id,
+                            e
+                        );
+                    }
+                }
+            })
+            .await;
+
+        match result {
+            Ok(()) => log::info!("IMAP IDLE stopped for account {}", account.id),
+            Err(SyncError::NotSupported(_)) => log::info!(
+                "IMAP server for account {} does not support IDLE, using polling only",
+                account.id
+            ),
+            Err(e) => log::error!("IMAP IDLE failed for account {}: {}", account.id, e),
+        }
+    }
+
     /// Periodic sync loop for all folders of an account
     /// Uses a priority queue with configurable concurrent workers
     async fn sync_folders_periodic(
@@ -288,6 +474,7 @@ impl BackgroundSyncManager {
         settings: Arc<crate::config::settings::Settings>,
         app_handle: tauri::AppHandle,
         account_id: Uuid,
+        sync_queue: Arc<SyncQueue>,
     ) {
         let sync_manager = Arc::new(
             SyncManager::new(pool.clone(), app_data_dir.to_string(), credential_store)
@@ -328,17 +515,15 @@ impl BackgroundSyncManager {
             return;
         }
 
-        let sync_queue = Arc::new(SyncQueue::new(3));
-        let mut worker_handles = vec![];
-
+        // Dropping the set when the account's sync stops aborts the workers
+        let mut workers = JoinSet::new();
         for worker_id in 0..sync_queue.workers_limit() {
             let queue = Arc::clone(&sync_queue);
             let manager = Arc::clone(&sync_manager);
-            let handle = tokio::spawn(async move {
+            workers.spawn(async move {
                 let worker = SyncQueueWorker::new(queue, manager);
                 let _ = worker.run(worker_id).await;
             });
-            worker_handles.push(handle);
         }
 
         log::info!(
@@ -366,6 +551,51 @@ impl BackgroundSyncManager {
                 continue;
             }
 
+            match SqliteSyncStateRepository::new(pool.clone())
+                .is_account_paused(account_id)
+                .await
+            {
+                Ok(false) => {}
+                Ok(true) => {
+                    sleep(Duration::from_secs(10)).await;
+                    continue;
+                }
+                Err(e) => log::warn!(
+                    "Failed to check whether sync of account {} is paused: {}",
+                    account_id,
+                    e
+                ),
+            }
+
+            // A provider that asked to back off is left alone until then
+            if let Some(until) = rate_limit::throttled_until(account_id) {
+                log::debug!(
+                    "Account {} is throttled until {}, holding back scheduled syncs",
+                    account_id,
+                    until
+                );
+                sleep((until - Utc::now()).to_std().unwrap_or_default()).await;
+                continue;
+            }
+
+            // History is backfilled while the folders are otherwise up to
+            // date, and not at all on a metered connection
+            let backfill_folders = if bandwidth::is_metered(Some(&settings)) {
+                Vec::new()
+            } else {
+                SqliteSyncStateRepository::new(pool.clone())
+                    .find_backfill_folders(account_id)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::warn!(
+                            "Failed to load folders to backfill for account {}: {}",
+                            account_id,
+                            e
+                        );
+                        Vec::new()
+                    })
+            };
+
             let now = Utc::now();
             let mut enqueued = 0;
 
@@ -385,34 +615,43 @@ impl BackgroundSyncManager {
 
                 let should_sync = match folder.synced_at {
                     Some(synced_at) => {
-                        let next_sync_time =
-                            synced_at + chrono::Duration::seconds(folder.sync_interval);
+                        let next_sync_time = synced_at
+                            + chrono::Duration::seconds(
+                                folder.sync_interval.max(MIN_SYNC_INTERVAL),
+                            );
                         now >= next_sync_time
                     }
                     None => true,
                 };
 
-                if should_sync {
-                    let queue_item = SyncQueueItem {
-                        account_id: account.id,
-                        folder_id,
-                        folder: folder.clone(),
-                        account: account.clone(),
-                        priority: SyncPriority::Normal,
-                        last_synced_at: folder.synced_at,
-                        enqueued_at: Utc::now(),
-                    };
-
-                    if let Err(e) = sync_queue.enqueue(queue_item).await {
-                        log::warn!(
-                            "Failed to enqueue folder {} for account {}: {}",
-                            folder.name,
-                            account_id,
-                            e
-                        );
-                    } else {
-                        enqueued += 1;
-                    }
+                let priority = if should_sync {
+                    Self::scheduled_priority(folder.folder_type)
+                } else if backfill_folders.contains(&folder_id) {
+                    SyncPriority::Backfill
+                } else {
+                    continue;
+                };
+
+                let queue_item = SyncQueueItem {
+                    account_id: account.id,
+                    folder_id,
+                    folder: folder.clone(),
+                    account: account.clone(),
+                    priority,
+                    last_synced_at: folder.synced_at,
+                    enqueued_at: Utc::now(),
+                    full: false,
+                };
+
+                if let Err(e) = sync_queue.enqueue(queue_item).await {
+                    log::warn!(
+                        "Failed to enqueue folder {} for account {}: {}",
+                        folder.name,
+                        account_id,
+                        e
+                    );
+                } else {
+                    enqueued += 1;
                 }
             }
 
@@ -430,6 +669,14 @@ impl BackgroundSyncManager {
         }
     }
 
+    /// Folders the user reads all the time go before the rest of a round
+    fn scheduled_priority(folder_type: FolderType) -> SyncPriority {
+        match folder_type {
+            FolderType::Inbox => SyncPriority::Normal,
+            _ => SyncPriority::Low,
+        }
+    }
+
     /// Update sync state in database
     async fn _update_sync_state(
         pool: &SqlitePool,
@@ -453,3 +700,23 @@ impl Drop for BackgroundSyncManager {
         let _ = self.shutdown_tx.send(());
     }
 }
+
+/// Wait after the `lookup`th lookup found no inbox to IDLE on
+fn idle_inbox_wait(lookup: u32) -> Duration {
+    IDLE_INBOX_INITIAL_WAIT
+        .saturating_mul(1 << lookup.min(16))
+        .min(IDLE_INBOX_MAX_WAIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_inbox_wait() {
+        let waits: Vec<u64> = (0..IDLE_INBOX_LOOKUPS)
+            .map(|lookup| idle_inbox_wait(lookup).as_secs())
+            .collect();
+        assert_eq!(waits, vec![15, 30, 60, 120, 240, 300, 300, 300, 300, 300]);
+    }
+}
@@ -0,0 +1,55 @@
+//! Exponential backoff of retried background work
+//!
+//! Queued work that failed (operations, analysis jobs, outbox sends, webhook
+//! deliveries, avatar lookups) is retried later, each time after a longer
+//! delay. Throttled HTTP requests are retried within the request instead, see
+//! [`super::rate_limit`].
+use chrono::Duration;
+
+/// Delays growing exponentially with the retries made
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub base_secs: i64,
+    /// Factor the delay grows by with every further retry
+    pub growth: i64,
+    /// Upper bound for the delay
+    pub max_secs: i64,
+}
+
+impl Backoff {
+    /// Delay before the retry following `retries` earlier ones
+    pub fn delay(&self, retries: i64) -> Duration {
+        let factor = self.growth.saturating_pow(retries.clamp(0, 20) as u32);
+        Duration::seconds(self.base_secs.saturating_mul(factor).min(self.max_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_grows_until_max() {
+        let backoff = Backoff {
+            base_secs: 5,
+            growth: 2,
+            max_secs: 15 * 60,
+        };
+        assert_eq!(backoff.delay(0), Duration::seconds(5));
+        assert_eq!(backoff.delay(1), Duration::seconds(10));
+        assert_eq!(backoff.delay(3), Duration::seconds(40));
+        assert_eq!(backoff.delay(10), Duration::seconds(15 * 60));
+        assert_eq!(backoff.delay(1000), Duration::seconds(15 * 60));
+        // Retries counted from a negative number start at the base delay
+        assert_eq!(backoff.delay(-1), Duration::seconds(5));
+
+        let quadrupling = Backoff {
+            base_secs: 60,
+            growth: 4,
+            max_secs: 64 * 60,
+        };
+        assert_eq!(quadrupling.delay(2), Duration::minutes(16));
+        assert_eq!(quadrupling.delay(1000), Duration::minutes(64));
+    }
+}
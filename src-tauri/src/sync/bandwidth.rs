@@ -0,0 +1,105 @@
+//! Download budget of background work. On a metered connection bodies and
+//! attachments are no longer prefetched, and a bandwidth limit spreads the
+//! prefetching that remains over time. Downloads the user waits for, like
+//! opening an attachment, are not limited.
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::settings::Settings;
+
+pub const METERED_SETTING: &str = "sync.metered";
+/// Kilobytes per second, 0 for no limit
+pub const LIMIT_SETTING: &str = "sync.bandwidthLimit";
+
+struct Bucket {
+    /// Bytes that may be downloaded right away; negative while in debt
+    available: f64,
+    updated: Instant,
+}
+
+static BUCKET: Lazy<Mutex<Bucket>> = Lazy::new(|| {
+    Mutex::new(Bucket {
+        available: 0.0,
+        updated: Instant::now(),
+    })
+});
+
+/// Whether the connection is marked as metered
+pub fn is_metered(settings: Option<&Settings>) -> bool {
+    settings.is_some_and(|settings| settings.get::<bool>(METERED_SETTING).unwrap_or(false))
+}
+
+/// Bytes per second background downloads may use, if limited
+fn limit(settings: &Settings) -> Option<u64> {
+    settings
+        .get::<u64>(LIMIT_SETTING)
+        .ok()
+        .filter(|kilobytes| *kilobytes > 0)
+        .map(|kilobytes| kilobytes * 1024)
+}
+
+/// Time to wait after downloading `bytes` so the average stays at `rate`
+/// bytes per second. Up to a second's worth passes without waiting.
+fn reserve(bucket: &mut Bucket, bytes: u64, rate: u64, now: Instant) -> Duration {
+    let rate = rate as f64;
+    let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+
+    bucket.available = (bucket.available + elapsed * rate).min(rate) - bytes as f64;
+    bucket.updated = now;
+
+    if bucket.available >= 0.0 {
+        Duration::ZERO
+    } else {
+        Duration::from_secs_f64(-bucket.available / rate)
+    }
+}
+
+/// Account for `bytes` downloaded in the background, sleeping as long as the
+/// bandwidth limit requires
+pub async fn throttle(settings: &Settings, bytes: u64) {
+    let Some(rate) = limit(settings) else {
+        return;
+    };
+
+    let delay = reserve(&mut BUCKET.lock().unwrap(), bytes, rate, Instant::now());
+    if !delay.is_zero() {
+        log::debug!(
+            "Bandwidth limit reached, pausing background downloads for {}ms",
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            available: 1000.0,
+            updated: start,
+        };
+
+        // Within the burst
+        assert_eq!(reserve(&mut bucket, 600, 1000, start), Duration::ZERO);
+        // 200 bytes over budget at 1000 bytes per second
+        assert_eq!(
+            reserve(&mut bucket, 600, 1000, start),
+            Duration::from_millis(200)
+        );
+        // The debt is paid off after waiting
+        assert_eq!(
+            reserve(&mut bucket, 500, 1000, start + Duration::from_millis(700)),
+            Duration::ZERO
+        );
+        // An idle period does not save more than a second's worth
+        assert_eq!(
+            reserve(&mut bucket, 1500, 1000, start + Duration::from_secs(60)),
+            Duration::from_millis(500)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Blocked senders and domains. New mail from them goes straight to the
+//! account's spam folder, before the junk filter and the user's rules see it.
+
+use sqlx::SqlitePool;
+
+use crate::database::models::email::Email;
+use crate::database::models::folder::FolderType;
+use crate::database::repositories::{
+    BlockedSenderRepository, FolderRepository, SqliteBlockedSenderRepository,
+    SqliteFolderRepository,
+};
+use crate::sync::junk_filter::JunkFilter;
+
+/// Moves a newly synced inbox message from a blocked sender to spam.
+///
+/// Returns whether the message was moved.
+pub async fn process_synced_email(pool: &SqlitePool, email: &mut Email) -> Result<bool, String> {
+    if email.is_draft || email.is_deleted {
+        return Ok(false);
+    }
+
+    let blocked = SqliteBlockedSenderRepository::new(pool.clone())
+        .is_blocked(&email.from.address)
+        .await
+        .map_err(|e| format!("Failed to check blocked senders: {}", e))?;
+    if !blocked {
+        return Ok(false);
+    }
+
+    let folder = SqliteFolderRepository::new(pool.clone())
+        .find_by_id(email.folder_id)
+        .await
+        .map_err(|e| format!("Failed to load folder: {}", e))?;
+    if !folder.is_some_and(|f| f.folder_type == FolderType::Inbox) {
+        return Ok(false);
+    }
+
+    let Some(spam_folder_id) = JunkFilter::new(pool.clone()).move_to_spam(email).await? else {
+        return Ok(false);
+    };
+    email.folder_id = spam_folder_id;
+
+    log::info!(
+        "[BlockedSenders] Moved email {} from blocked sender to spam",
+        email.id
+    );
+
+    Ok(true)
+}
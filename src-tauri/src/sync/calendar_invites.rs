@@ -0,0 +1,177 @@
+//! Meeting invitations (iTIP, RFC 5546). The `text/calendar` part of a newly
+//! synced invitation is parsed and stored as the email's calendar event, so
+//! it can be answered from the message.
+//!
+//! Updates and cancellations of an event arrive as new emails with the same
+//! `UID`. An answer stays valid for updates of the same `SEQUENCE`; a higher
+//! one means the organizer changed the event and asks again.
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::database::models::calendar_event::{CalendarEvent, RsvpStatus};
+use crate::database::repositories::{
+    AccountRepository, CalendarEventRepository, EmailRepository, IdentityRepository,
+    SqliteAccountRepository, SqliteCalendarEventRepository, SqliteEmailRepository,
+    SqliteIdentityRepository,
+};
+use crate::services::icalendar;
+use crate::sync::types::SyncAttachment;
+
+/// Methods of messages that invite to, update or cancel an event. Replies
+/// and counter proposals are answers to our own invitations.
+const INVITE_METHODS: &[&str] = &["REQUEST", "PUBLISH", "ADD", "CANCEL"];
+
+/// Whether a MIME part holds iCalendar data
+pub fn is_calendar_part(content_type: &str, filename: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("text/calendar")
+        || mime.eq_ignore_ascii_case("application/ics")
+        || filename.to_lowercase().ends_with(".ics")
+}
+
+/// Reads the invitation attached to a newly synced message and stores its
+/// event for the email.
+///
+/// Returns `Ok(None)` if the message carries no invitation.
+pub async fn process_invite(
+    pool: &SqlitePool,
+    email_id: Uuid,
+    attachments: &[SyncAttachment],
+) -> Result<Option<CalendarEvent>, String> {
+    let Some(calendar) = attachments
+        .iter()
+        .filter(|attachment| is_calendar_part(&attachment.content_type, &attachment.filename))
+        .filter_map(|attachment| attachment.data.as_deref())
+        .find_map(icalendar::parse)
+    else {
+        return Ok(None);
+    };
+
+    // Without a method the calendar is published, e.g. an attached .ics file
+    let method = calendar.method.as_deref().unwrap_or("PUBLISH");
+    if !INVITE_METHODS.contains(&method) {
+        return Ok(None);
+    }
+    let Some(invite) = calendar.main_event() else {
+        return Ok(None);
+    };
+
+    let Some(email) = SqliteEmailRepository::new(pool.clone())
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to load email: {}", e))?
+    else {
+        return Ok(None);
+    };
+
+    let own_addresses = own_addresses(pool, email.account_id).await?;
+    let attendee = invite
+        .attendees
+        .iter()
+        .find(|attendee| own_addresses.contains(&attendee.address));
+
+    let repo = SqliteCalendarEventRepository::new(pool.clone());
+    let earlier = repo
+        .find_by_uid(email.account_id, &invite.uid)
+        .await
+        .map_err(|e| format!("Failed to load calendar event: {}", e))?;
+    let answered = earlier.iter().find(|event| {
+        event.email_id != email_id
+            && event.sequence >= invite.sequence
+            && event.rsvp_status != RsvpStatus::NeedsAction
+    });
+
+    let (rsvp_status, responded_at) = match answered {
+        Some(event) => (event.rsvp_status, event.responded_at),
+        None => (
+            attendee
+                .and_then(|attendee| attendee.rsvp_status)
+                .unwrap_or(RsvpStatus::NeedsAction),
+            None,
+        ),
+    };
+
+    let cancelled = method == "CANCEL" || invite.status.as_deref() == Some("CANCELLED");
+    if cancelled {
+        repo.cancel(email.account_id, &invite.uid)
+            .await
+            .map_err(|e| format!("Failed to cancel calendar event: {}", e))?;
+    }
+
+    let event = CalendarEvent {
+        id: earlier
+            .iter()
+            .find(|event| event.email_id == email_id)
+            .map_or_else(Uuid::now_v7, |event| event.id),
+        account_id: email.account_id,
+        email_id,
+        uid: invite.uid.clone(),
+        sequence: invite.sequence,
+        method: method.to_string(),
+        summary: invite.summary.clone(),
+        description: invite.description.clone(),
+        location: invite.location.clone(),
+        starts_at: invite.starts_at,
+        ends_at: invite.ends_at,
+        all_day: invite.all_day,
+        organizer: invite.organizer.clone(),
+        attendees: invite.attendees.clone(),
+        attendee_email: attendee.map(|attendee| attendee.address.clone()),
+        cancelled,
+        rsvp_status,
+        responded_at,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    repo.upsert(&event)
+        .await
+        .map_err(|e| format!("Failed to store calendar event: {}", e))?;
+
+    log::info!(
+        "[CalendarInvites] Stored {} of event {} (sequence {}) from email {}",
+        method,
+        event.uid,
+        event.sequence,
+        email_id
+    );
+
+    Ok(Some(event))
+}
+
+/// The account address and its aliases, lower-cased
+async fn own_addresses(pool: &SqlitePool, account_id: Uuid) -> Result<Vec<String>, String> {
+    let account = SqliteAccountRepository::new(pool.clone())
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to load account: {}", e))?;
+    let identities = SqliteIdentityRepository::new(pool.clone())
+        .find_by_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to load identities: {}", e))?;
+
+    Ok(account
+        .map(|account| account.email)
+        .into_iter()
+        .chain(identities.into_iter().map(|identity| identity.email))
+        .map(|address| address.trim().to_lowercase())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_calendar_part() {
+        assert!(is_calendar_part(
+            "text/calendar; method=REQUEST; charset=UTF-8",
+            "invite.ics"
+        ));
+        assert!(is_calendar_part("TEXT/CALENDAR", ""));
+        assert!(is_calendar_part("application/octet-stream", "Meeting.ICS"));
+        assert!(!is_calendar_part("text/plain", "notes.txt"));
+    }
+}
@@ -29,14 +29,28 @@ pub fn is_cid_referenced(html_body: &str, content_id: &str) -> bool {
 pub fn replace_cid_references(
     html_body: &str,
     cid_to_path: &std::collections::HashMap<String, String>,
+) -> String {
+    let cid_to_url = cid_to_path
+        .iter()
+        .map(|(content_id, cache_path)| {
+            (content_id.clone(), format!("attachment://{}", cache_path))
+        })
+        .collect();
+
+    replace_cid_urls(html_body, &cid_to_url)
+}
+
+/// Replace CID references in HTML with arbitrary URLs (e.g. `data:` URIs)
+/// Maps content_id -> URL for replacement
+pub fn replace_cid_urls(
+    html_body: &str,
+    cid_to_url: &std::collections::HashMap<String, String>,
 ) -> String {
     let mut result = html_body.to_string();
 
-    for (content_id, cache_path) in cid_to_path {
+    for (content_id, asset_url) in cid_to_url {
         let normalized_cid = content_id.trim_matches(|c| c == '<' || c == '>');
 
-        let asset_url = format!("attachment://{}", cache_path);
-
         let patterns = [
             format!(r#"src="cid:{}""#, normalized_cid),
             format!(r#"src='cid:{}'"#, normalized_cid),
@@ -1,3 +1,4 @@
+use crate::database::models::contact::ContactField;
 use crate::database::models::email::{Email, EmailAddress};
 use crate::database::{error::DatabaseError, repositories::ContactRepository};
 use std::sync::Arc;
@@ -41,7 +42,15 @@ impl ContactExtractor {
         &self,
         email: &Email,
     ) -> Result<(), DatabaseError> {
-        self.extract_from_sender(email.from()).await?;
+        let sender_id = self.extract_from_sender(email.from()).await?;
+        self.contact_repo
+            .record_interaction(
+                sender_id,
+                email.account_id,
+                ContactField::From,
+                email.received_at,
+            )
+            .await?;
 
         for addr in email
             .to()
@@ -60,16 +69,37 @@ impl ContactExtractor {
 
     pub async fn extract_and_store_from_sent_email(
         &self,
+        account_id: Uuid,
         to: &[EmailAddress],
         cc: &[EmailAddress],
         bcc: &[EmailAddress],
         sent_at: Option<chrono::DateTime<chrono::Utc>>,
     ) -> Result<(), DatabaseError> {
-        for addr in to.iter().chain(cc.iter()).chain(bcc.iter()) {
-            let _ = self
-                .contact_repo
-                .increment_send_count(&addr.address, addr.name.as_deref(), sent_at)
-                .await;
+        let fields = [
+            (ContactField::To, to),
+            (ContactField::Cc, cc),
+            (ContactField::Bcc, bcc),
+        ];
+
+        for (field, addrs) in fields {
+            for addr in addrs {
+                let Ok(contact_id) = self
+                    .contact_repo
+                    .increment_send_count(&addr.address, addr.name.as_deref(), sent_at)
+                    .await
+                else {
+                    continue;
+                };
+                let _ = self
+                    .contact_repo
+                    .record_interaction(
+                        contact_id,
+                        account_id,
+                        field,
+                        sent_at.unwrap_or_else(chrono::Utc::now),
+                    )
+                    .await;
+            }
         }
 
         Ok(())
@@ -0,0 +1,366 @@
+//! Parsing of delivery status notifications (RFC 3464) and read receipts
+//! (RFC 8098), the `multipart/report` messages sent back about our mail.
+//!
+//! Reports for messages sent with a DSN request carry the sent message's id as
+//! `Original-Envelope-Id`, which is how they are matched back to it. Read
+//! receipts are matched by their `Original-Message-ID`.
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::database::models::delivery_status::{
+    DeliveryAction, Disposition, RecipientDeliveryStatus,
+};
+use crate::database::models::email::Email;
+use crate::database::repositories::{
+    DeliveryStatusRepository, EmailRepository, SqliteDeliveryStatusRepository,
+    SqliteEmailRepository,
+};
+use crate::sync::events::DeliveryStatusEvent;
+use crate::sync::types::SyncAttachment;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryReport {
+    pub envelope_id: Option<String>,
+    pub recipients: Vec<RecipientReport>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipientReport {
+    pub recipient: String,
+    pub action: DeliveryAction,
+    pub status: Option<String>,
+    pub diagnostic: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadReceipt {
+    pub original_message_id: String,
+    pub recipient: String,
+    pub disposition: Disposition,
+}
+
+/// Whether a MIME part holds machine-readable delivery status fields
+pub fn is_delivery_status_part(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("message/delivery-status")
+        || mime.eq_ignore_ascii_case("message/global-delivery-status")
+}
+
+/// Whether a MIME part holds the fields of a read receipt
+pub fn is_disposition_notification_part(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("message/disposition-notification")
+        || mime.eq_ignore_ascii_case("message/global-disposition-notification")
+}
+
+/// Parse the body of a `message/disposition-notification` part
+pub fn parse_disposition_notification(text: &str) -> Option<ReadReceipt> {
+    let fields = field_blocks(text).into_iter().next()?;
+
+    let recipient = field(&fields, "original-recipient")
+        .or_else(|| field(&fields, "final-recipient"))
+        .map(strip_type)?;
+
+    Some(ReadReceipt {
+        original_message_id: field(&fields, "original-message-id")?.to_string(),
+        recipient: recipient.trim_matches(['<', '>']).to_lowercase(),
+        disposition: Disposition::from_report(field(&fields, "disposition")?)?,
+    })
+}
+
+/// Parse the body of a `message/delivery-status` part.
+///
+/// The first field block describes the message, each following block one
+/// recipient. Recipients without a recognizable action are skipped.
+pub fn parse_delivery_status(text: &str) -> Option<DeliveryReport> {
+    let mut blocks = field_blocks(text).into_iter();
+    let per_message = blocks.next()?;
+
+    let envelope_id = field(&per_message, "original-envelope-id").map(ToString::to_string);
+
+    let recipients: Vec<RecipientReport> = blocks
+        .filter_map(|block| {
+            let recipient = field(&block, "original-recipient")
+                .or_else(|| field(&block, "final-recipient"))
+                .map(strip_type)?;
+            let action = DeliveryAction::from_report(field(&block, "action")?)?;
+
+            Some(RecipientReport {
+                recipient: recipient.trim_matches(['<', '>']).to_lowercase(),
+                action,
+                status: field(&block, "status")
+                    .and_then(|s| s.split_whitespace().next())
+                    .map(ToString::to_string),
+                diagnostic: field(&block, "diagnostic-code")
+                    .map(strip_type)
+                    .map(ToString::to_string),
+            })
+        })
+        .collect();
+
+    if recipients.is_empty() {
+        return None;
+    }
+
+    Some(DeliveryReport {
+        envelope_id,
+        recipients,
+    })
+}
+
+/// Unfolds header-style fields and splits them into blank-line separated blocks
+fn field_blocks(text: &str) -> Vec<Vec<(String, String)>> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = current.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            current.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+
+    blocks
+}
+
+fn field<'a>(block: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    block
+        .iter()
+        .find(|(field_name, _)| field_name == name)
+        .map(|(_, value)| value.as_str())
+        .filter(|value| !value.is_empty())
+}
+
+/// Drops the `rfc822;` / `smtp;` type prefix from address and diagnostic fields
+fn strip_type(value: &str) -> &str {
+    value.split_once(';').map_or(value, |(_, rest)| rest).trim()
+}
+
+/// Reads the delivery report or read receipt attached to a newly synced
+/// message and stores the recipient statuses on the sent message it refers to.
+///
+/// Returns `Ok(None)` if the message is not a report about one of our messages.
+pub async fn process_report(
+    pool: &SqlitePool,
+    report_email_id: Uuid,
+    attachments: &[SyncAttachment],
+) -> Result<Option<DeliveryStatusEvent>, String> {
+    for attachment in attachments {
+        let Some(data) = attachment.data.as_deref() else {
+            continue;
+        };
+
+        if is_delivery_status_part(&attachment.content_type) {
+            if let Some(report) = parse_delivery_status(&String::from_utf8_lossy(data)) {
+                return apply_delivery_report(pool, report_email_id, report).await;
+            }
+        } else if is_disposition_notification_part(&attachment.content_type) {
+            if let Some(receipt) = parse_disposition_notification(&String::from_utf8_lossy(data)) {
+                return apply_read_receipt(pool, report_email_id, receipt).await;
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+async fn apply_delivery_report(
+    pool: &SqlitePool,
+    report_email_id: Uuid,
+    report: DeliveryReport,
+) -> Result<Option<DeliveryStatusEvent>, String> {
+    let Some(email_id) = report
+        .envelope_id
+        .as_deref()
+        .and_then(|id| Uuid::parse_str(id.trim()).ok())
+    else {
+        return Ok(None);
+    };
+
+    let sent_email = SqliteEmailRepository::new(pool.clone())
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| format!("Failed to load sent email: {}", e))?;
+    let Some(sent_email) = sent_email else {
+        return Ok(None);
+    };
+
+    let repo = SqliteDeliveryStatusRepository::new(pool.clone());
+    for recipient in &report.recipients {
+        repo.apply_report(&RecipientDeliveryStatus {
+            email_id,
+            recipient: recipient.recipient.clone(),
+            action: recipient.action,
+            status: recipient.status.clone(),
+            diagnostic: recipient.diagnostic.clone(),
+            report_email_id: Some(report_email_id),
+            disposition: None,
+            disposition_at: None,
+            updated_at: Utc::now(),
+        })
+        .await
+        .map_err(|e| format!("Failed to store delivery status: {}", e))?;
+    }
+
+    log::info!(
+        "[DeliveryStatus] Report {} updated {} recipient(s) of email {}",
+        report_email_id,
+        report.recipients.len(),
+        email_id
+    );
+
+    status_event(&repo, &sent_email).await.map(Some)
+}
+
+async fn apply_read_receipt(
+    pool: &SqlitePool,
+    report_email_id: Uuid,
+    receipt: ReadReceipt,
+) -> Result<Option<DeliveryStatusEvent>, String> {
+    let email_repo = SqliteEmailRepository::new(pool.clone());
+
+    // Message ids are stored with or without angle brackets depending on the provider
+    let bare_id = receipt.original_message_id.trim().trim_matches(['<', '>']);
+    let mut sent_email = None;
+    for message_id in [format!("<{}>", bare_id), bare_id.to_string()] {
+        sent_email = email_repo
+            .find_by_message_id(&message_id)
+            .await
+            .map_err(|e| format!("Failed to load sent email: {}", e))?
+            .filter(|email| email.id != report_email_id);
+        if sent_email.is_some() {
+            break;
+        }
+    }
+    let Some(sent_email) = sent_email else {
+        return Ok(None);
+    };
+
+    let repo = SqliteDeliveryStatusRepository::new(pool.clone());
+    repo.apply_disposition(
+        sent_email.id,
+        &receipt.recipient,
+        receipt.disposition,
+        report_email_id,
+    )
+    .await
+    .map_err(|e| format!("Failed to store read receipt: {}", e))?;
+
+    log::info!(
+        "[DeliveryStatus] Read receipt {} marked email {} as {} by {}",
+        report_email_id,
+        sent_email.id,
+        receipt.disposition.as_str(),
+        receipt.recipient
+    );
+
+    status_event(&repo, &sent_email).await.map(Some)
+}
+
+async fn status_event(
+    repo: &SqliteDeliveryStatusRepository,
+    sent_email: &Email,
+) -> Result<DeliveryStatusEvent, String> {
+    let statuses = repo
+        .find_by_email(sent_email.id)
+        .await
+        .map_err(|e| format!("Failed to load delivery status: {}", e))?;
+
+    Ok(DeliveryStatusEvent {
+        account_id: sent_email.account_id,
+        email_id: sent_email.id,
+        statuses,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = "Reporting-MTA: dns; mail.example.com\r\n\
+        Original-Envelope-Id: 0192c3a4-5b6c-7d8e-9f00-112233445566\r\n\
+        Arrival-Date: Mon, 17 Mar 2025 10:00:00 +0000\r\n\
+        \r\n\
+        Final-Recipient: rfc822; bob@example.org\r\n\
+        Original-Recipient: rfc822;Bob@Example.org\r\n\
+        Action: failed\r\n\
+        Status: 5.1.1\r\n\
+        Diagnostic-Code: smtp; 550 5.1.1 <bob@example.org>:\r\n\
+        \x20 Recipient address rejected: User unknown\r\n\
+        \r\n\
+        Final-Recipient: rfc822; carol@example.org\r\n\
+        Action: delivered (relayed to mailbox)\r\n\
+        Status: 2.0.0\r\n";
+
+    #[test]
+    fn test_parse_delivery_status() {
+        let report = parse_delivery_status(REPORT).unwrap();
+
+        assert_eq!(
+            report.envelope_id.as_deref(),
+            Some("0192c3a4-5b6c-7d8e-9f00-112233445566")
+        );
+        assert_eq!(report.recipients.len(), 2);
+
+        let bob = &report.recipients[0];
+        assert_eq!(bob.recipient, "bob@example.org");
+        assert_eq!(bob.action, DeliveryAction::Failed);
+        assert_eq!(bob.status.as_deref(), Some("5.1.1"));
+        assert_eq!(
+            bob.diagnostic.as_deref(),
+            Some("550 5.1.1 <bob@example.org>: Recipient address rejected: User unknown")
+        );
+
+        let carol = &report.recipients[1];
+        assert_eq!(carol.recipient, "carol@example.org");
+        assert_eq!(carol.action, DeliveryAction::Delivered);
+    }
+
+    #[test]
+    fn test_parse_delivery_status_without_recipients() {
+        assert!(parse_delivery_status("Reporting-MTA: dns; mail.example.com\n").is_none());
+        assert!(is_delivery_status_part(
+            "message/delivery-status; charset=us-ascii"
+        ));
+        assert!(!is_delivery_status_part("text/plain"));
+    }
+
+    #[test]
+    fn test_parse_disposition_notification() {
+        let receipt = parse_disposition_notification(
+            "Reporting-UA: mail.example.org; Mail 16.0\r\n\
+             Final-Recipient: rfc822; Bob@Example.org\r\n\
+             Original-Message-ID: <0192c3a4-5b6c-7d8e-9f00-112233445566@example.com>\r\n\
+             Disposition: manual-action/MDN-sent-manually; displayed\r\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            receipt.original_message_id,
+            "<0192c3a4-5b6c-7d8e-9f00-112233445566@example.com>"
+        );
+        assert_eq!(receipt.recipient, "bob@example.org");
+        assert_eq!(receipt.disposition, Disposition::Displayed);
+
+        assert!(
+            parse_disposition_notification("Final-Recipient: rfc822; bob@example.org\n").is_none()
+        );
+        assert!(is_disposition_notification_part(
+            "message/disposition-notification"
+        ));
+    }
+}
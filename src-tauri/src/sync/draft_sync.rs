@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::database::models::email::Email;
+use crate::database::models::pending_operation::{
+    PendingOperation, PendingOperationStatus, PendingOperationType,
+};
+use crate::database::repositories::{
+    EmailRepository, SqliteEmailRepository, SqlitePendingOperationRepository,
+};
+use crate::services::email_service::{EmailData, EmailService};
+use crate::sync::error::{SyncError, SyncResult};
+use crate::sync::provider::EmailProvider;
+use crate::sync::types::SyncFolder;
+
+const UPLOAD_TYPES: [PendingOperationType; 2] = [
+    PendingOperationType::CreateDraft,
+    PendingOperationType::UpdateDraft,
+];
+
+/// Queues uploading a saved draft to the provider's Drafts folder. The upload
+/// sends the draft as stored when it runs, so one queued upload covers any
+/// number of saves.
+pub async fn queue_upload(pool: &SqlitePool, draft: &Email) -> Result<(), String> {
+    let pending_repo = SqlitePendingOperationRepository::new(pool.clone());
+    let pending_ops = pending_repo
+        .find_pending_for_email(draft.id)
+        .await
+        .map_err(|e| format!("Failed to fetch pending operations: {}", e))?;
+
+    // An upload already running may have read the previous content, so only a
+    // waiting one is reused
+    let upload_waiting = pending_ops.iter().any(|op| {
+        op.parsed_status() == Some(PendingOperationStatus::Pending)
+            && op
+                .parsed_operation_type()
+                .is_some_and(|op_type| UPLOAD_TYPES.contains(&op_type))
+    });
+    if upload_waiting {
+        return Ok(());
+    }
+
+    let op_type = if draft.remote_id.is_some() {
+        PendingOperationType::UpdateDraft
+    } else {
+        PendingOperationType::CreateDraft
+    };
+    let op = PendingOperation::new(
+        draft.account_id,
+        Some(draft.id),
+        Some(draft.folder_id),
+        op_type,
+        serde_json::json!({
+            "email_id": draft.id.to_string(),
+            "folder_id": draft.folder_id.to_string(),
+        }),
+    );
+    pending_repo
+        .create(&op)
+        .await
+        .map_err(|e| format!("Failed to queue draft upload: {}", e))?;
+
+    Ok(())
+}
+
+/// Cancels queued uploads of a draft that was deleted or sent locally and
+/// queues removing its copy from the server
+pub async fn queue_remote_delete(pool: &SqlitePool, draft: &Email) -> Result<(), String> {
+    let pending_repo = SqlitePendingOperationRepository::new(pool.clone());
+    for op_type in &UPLOAD_TYPES {
+        pending_repo
+            .cancel_by_email_and_type(draft.id, op_type.as_str())
+            .await
+            .map_err(|e| format!("Failed to cancel draft upload: {}", e))?;
+    }
+
+    let Some(remote_id) = &draft.remote_id else {
+        return Ok(());
+    };
+
+    // Not tied to the email, which lives on as the sent message or is gone
+    let op = PendingOperation::new(
+        draft.account_id,
+        None,
+        Some(draft.folder_id),
+        PendingOperationType::PermanentDelete,
+        serde_json::json!({
+            "remote_id": remote_id,
+            "folder_id": draft.folder_id.to_string(),
+        }),
+    );
+    pending_repo
+        .create(&op)
+        .await
+        .map_err(|e| format!("Failed to queue draft removal: {}", e))?;
+
+    Ok(())
+}
+
+/// Stores the current content of a draft in `drafts_folder`, replacing its
+/// previous server copy, and records the new remote ID. Drafts that were sent
+/// or deleted in the meantime are skipped.
+pub async fn upload(
+    pool: &SqlitePool,
+    provider: &dyn EmailProvider,
+    email_id: Uuid,
+    drafts_folder: &SyncFolder,
+) -> SyncResult<()> {
+    let email_repo = SqliteEmailRepository::new(pool.clone());
+    let Some(draft) = email_repo
+        .find_by_id(email_id)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+        .filter(|email| email.is_draft && !email.is_deleted)
+    else {
+        return Ok(());
+    };
+
+    let mime = EmailService::build_draft_mime(draft_email_data(&draft))
+        .map_err(|e| SyncError::ParseError(format!("Failed to build draft: {}", e)))?;
+    let remote_id = provider
+        .save_draft(drafts_folder, mime, draft.remote_id.as_deref())
+        .await?;
+
+    email_repo
+        .mark_draft_uploaded(email_id, &remote_id, Utc::now())
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+    log::info!(
+        "[DraftSync] Uploaded draft {} as {} to {}",
+        email_id,
+        remote_id,
+        drafts_folder.name
+    );
+
+    Ok(())
+}
+
+/// Whether a local draft keeps its content over the copy found on the server
+/// during sync: while an upload is queued, or when it was edited locally after
+/// the server copy was last modified
+pub fn keeps_local_content(
+    local_updated_at: DateTime<Utc>,
+    remote_modified_at: DateTime<Utc>,
+    upload_pending: bool,
+) -> bool {
+    upload_pending || local_updated_at > remote_modified_at
+}
+
+fn draft_email_data(draft: &Email) -> EmailData {
+    let headers: serde_json::Value = draft
+        .headers
+        .as_deref()
+        .and_then(|headers| serde_json::from_str(headers).ok())
+        .unwrap_or_default();
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+    };
+
+    EmailData {
+        from: draft.from.0.address.clone(),
+        to: draft.to.0.clone(),
+        cc: draft.cc.0.clone(),
+        bcc: draft.bcc.0.clone(),
+        subject: draft.subject.clone().unwrap_or_default(),
+        body_html: draft.body_html.clone().unwrap_or_default(),
+        // Derived from the HTML, which is what the user edits
+        body_plain: None,
+        attachments: Vec::new(),
+        in_reply_to: header("In-Reply-To"),
+        references: header("References"),
+        // Keeping the Message-ID lets sync match the server copy to the draft
+        message_id: Some(draft.message_id.clone()).filter(|id| !id.is_empty()),
+        read_receipt: false,
+        smime: None,
+        dkim: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_local_content() {
+        let now = Utc::now();
+        let earlier = now - chrono::Duration::minutes(5);
+
+        assert!(keeps_local_content(now, earlier, false));
+        assert!(!keeps_local_content(earlier, now, false));
+        assert!(keeps_local_content(earlier, now, true));
+    }
+}
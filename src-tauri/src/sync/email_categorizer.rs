@@ -18,6 +18,16 @@ impl EmailCategory {
             EmailCategory::Promotions => "promotions",
         }
     }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "personal" => Some(EmailCategory::Personal),
+            "transactions" => Some(EmailCategory::Transactions),
+            "updates" => Some(EmailCategory::Updates),
+            "promotions" => Some(EmailCategory::Promotions),
+            _ => None,
+        }
+    }
 }
 
 /// Categorizes an email based on headers, subject, and body content
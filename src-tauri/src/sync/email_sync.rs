@@ -1,33 +1,64 @@
+use super::attachment_download::{emit_progress, AttachmentSink};
 use super::attachment_handler::AttachmentHandler;
+use super::attachment_policy::AttachmentDownloadPolicy;
 use super::auth::CredentialStore;
+use super::backfill::{self, Backfill};
+use super::bandwidth;
+use super::blocked_senders;
+use super::calendar_invites;
 use super::contact_extractor::ContactExtractor;
+use super::delivery_status;
+use super::draft_sync;
 use super::email_body_splitter::EmailBodySplitter;
 use super::email_categorizer::EmailCategorizer;
 use super::error::{SyncError, SyncResult};
-use super::provider::ProviderFactory;
+use super::events;
+use super::junk_filter::JunkFilter;
+use super::muted_conversations;
+use super::phishing;
+use super::priority;
+use super::provider::{EmailProvider, ProviderFactory};
+use super::rules_engine::RulesEngine;
+use super::smime_signatures;
 use super::storage::LocalFileStorage;
-use super::types::{ProviderCredentials, SyncEmail, SyncFolder};
+use super::threading;
+use super::types::{AccountSettings, ProviderCredentials, SyncAttachment, SyncEmail, SyncFolder};
+use crate::config::Settings;
 use crate::database::models::account::{Account, AccountType};
+use crate::database::models::folder::FolderType;
 use crate::database::models::pending_operation::PendingOperationType;
-use crate::database::repositories::EmailRepository;
 use crate::database::repositories::RepositoryFactory;
 use crate::database::repositories::SqlitePendingOperationRepository;
+use crate::database::repositories::{
+    AttachmentRepository, EmailRepository, FolderRepository, LabelRepository,
+    SenderCategoryRepository,
+};
 use crate::search::SearchManager;
+use crate::services::app_lock;
+use crate::services::automation_triggers;
 use crate::services::notification_service::NotificationService;
 use chrono::{DateTime, Utc};
 use sqlx::SqlitePool;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
-use tauri::Emitter;
 use turndown::Turndown;
 use uuid::Uuid;
 
+/// Chunk size when attachment content is already in memory
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// Emails missing from a sync that are looked up on the server at once
+const MISSING_FETCH_CHUNK: usize = 100;
+
 pub struct EmailSync {
     pool: SqlitePool,
     attachment_handler: AttachmentHandler<LocalFileStorage>,
     credential_store: Arc<CredentialStore>,
     contact_extractor: Arc<ContactExtractor>,
+    junk_filter: JunkFilter,
+    rules_engine: RulesEngine,
     search_manager: Option<Arc<SearchManager>>,
+    settings: Option<Arc<Settings>>,
     pub app_handle: Option<tauri::AppHandle>,
     pub notification_service: Option<Arc<NotificationService>>,
     turndown: Arc<Turndown>,
@@ -38,7 +69,7 @@ fn emit_folder_event<S: serde::Serialize + Clone>(
     event_name: &str,
     payload: S,
 ) {
-    if let Err(e) = app_handle.emit(event_name, payload) {
+    if let Err(e) = app_lock::emit_mail_event(app_handle, event_name, payload) {
         log::error!("Failed to emit folder event '{}': {}", event_name, e);
     }
 }
@@ -61,10 +92,13 @@ impl EmailSync {
 
         Self {
             attachment_handler: AttachmentHandler::new(pool.clone(), storage),
-            pool,
             credential_store,
             contact_extractor,
+            junk_filter: JunkFilter::new(pool.clone()),
+            rules_engine: RulesEngine::new(pool.clone()),
+            pool,
             search_manager: None,
+            settings: None,
             app_handle: None,
             notification_service: None,
             turndown,
@@ -76,6 +110,11 @@ impl EmailSync {
         self
     }
 
+    pub fn with_settings(mut self, settings: Arc<Settings>) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
     pub fn with_app_handle(mut self, app_handle: tauri::AppHandle) -> Self {
         self.app_handle = Some(app_handle);
         self
@@ -89,6 +128,12 @@ impl EmailSync {
         self
     }
 
+    /// Which attachment contents to download while syncing, read on every
+    /// sync so changes to the setting apply right away
+    fn attachment_policy(&self) -> AttachmentDownloadPolicy {
+        AttachmentDownloadPolicy::from_settings(self.settings.as_deref())
+    }
+
     /// Synchronize emails for a folder using provider-agnostic delta/full sync
     ///
     /// # Arguments
@@ -117,6 +162,14 @@ impl EmailSync {
             folder.id.unwrap(),
             current_status
         );
+        if current_status == "paused" {
+            log::debug!(
+                "[EmailSync] Sync of folder {} (account {}) is paused, skipping",
+                folder.name,
+                account.id
+            );
+            return Ok(0);
+        }
         if current_status == "syncing" {
             // Allow override if the syncing state is stale (older than 30 minutes).
             // This handles the case where the app crashed while syncing.
@@ -151,10 +204,13 @@ impl EmailSync {
         let result = self.sync_folder_internal(account, folder, full).await;
 
         // Update status based on result
-        if result.is_ok() {
-            let _ = self.set_sync_status(folder, "idle").await;
-        } else {
-            let _ = self.set_sync_status(folder, "error").await;
+        match &result {
+            Ok(_) => {
+                let _ = self.set_sync_status(folder, "idle").await;
+            }
+            Err(e) => {
+                let _ = self.record_sync_error(folder, &e.to_string()).await;
+            }
         }
 
         // emit event folder:updated
@@ -192,6 +248,7 @@ impl EmailSync {
 
         let credentials = self.load_credentials(account).await?;
         provider.authenticate(credentials).await?;
+        provider.set_attachment_policy(self.attachment_policy());
 
         // Get sync token for delta sync (if not forcing full sync)
         let sync_token = if !full {
@@ -200,8 +257,44 @@ impl EmailSync {
             None
         };
 
+        // The first sync of a folder only fetches recent mail, so it is
+        // usable right away; older mail is backfilled afterwards
+        let initial_window = if !full && sync_token.is_none() && !self.has_emails(folder).await? {
+            Some(backfill::initial_window_start(
+                Utc::now(),
+                self.settings.as_deref(),
+                Self::history_horizon(account),
+            ))
+        } else {
+            None
+        };
+
         // Get provider's view of the folder via unified sync_messages trait method
-        let mut diff = provider.sync_messages(folder, sync_token).await?;
+        let mut diff = match initial_window {
+            Some(since) => provider.sync_recent_messages(folder, since).await?,
+            None => provider.sync_messages(folder, sync_token).await?,
+        };
+
+        // After a UIDVALIDITY change the provider enumerates the folder again,
+        // and the stored remote IDs name other messages. They are dropped so
+        // emails are matched by Message-ID instead, and whatever is not matched
+        // again is removed after reconciliation.
+        let stored_uid_validity = self.get_uid_validity(folder).await?;
+        let invalidated = match (stored_uid_validity, diff.uid_validity) {
+            (Some(stored), Some(current)) if stored != current => {
+                log::warn!(
+                    "[EmailSync] UIDVALIDITY of folder {} changed from {} to {}, resyncing",
+                    folder.name,
+                    stored,
+                    current
+                );
+                if !diff.is_complete {
+                    diff = provider.sync_messages(folder, None).await?;
+                }
+                self.invalidate_remote_ids(folder).await?
+            }
+            _ => Vec::new(),
+        };
 
         // For full sync, compute deletions by comparing local emails with provider's additions
         // Only safe to do when the provider returned a complete enumeration of all emails
@@ -235,12 +328,32 @@ impl EmailSync {
             .reconcile_diff(account.id, folder, &diff, self)
             .await?;
 
-        let total = reconciliation.added + reconciliation.modified + reconciliation.deleted;
+        let removed = self.remove_unmatched(folder, &invalidated).await?;
+        if !invalidated.is_empty() {
+            log::info!(
+                "[EmailSync] Resync of folder {} matched {} of {} emails again",
+                folder.name,
+                invalidated.len() as u64 - removed,
+                invalidated.len()
+            );
+        }
+
+        let total = reconciliation.added
+            + reconciliation.modified
+            + reconciliation.deleted
+            + removed as usize;
 
         // Store next sync token
         if let Some(token) = &diff.next_sync_token {
             self.store_sync_token(folder, token).await.ok();
         }
+        if let Some(uid_validity) = diff.uid_validity {
+            self.store_uid_validity(folder, uid_validity).await?;
+        }
+        if let Some(since) = initial_window.filter(|_| !diff.is_complete) {
+            let backfill = Backfill::starting_at(since, Self::history_horizon(account));
+            self.store_backfill(folder, backfill.as_ref()).await?;
+        }
 
         // Update sync state and commit search indexer
         self.update_sync_state(folder).await?;
@@ -260,6 +373,149 @@ impl EmailSync {
         Ok(total)
     }
 
+    /// Sync the next step of a folder's backfill, the mail received before
+    /// the oldest synced so far. Returns the number of messages added; the
+    /// backfill is done once the folder has no pending step left.
+    pub async fn backfill_folder(
+        &self,
+        account: &Account,
+        folder: &SyncFolder,
+    ) -> SyncResult<usize> {
+        let Some(backfill) = self.get_backfill(folder).await? else {
+            return Ok(0);
+        };
+        if self.get_sync_status(folder).await? == "paused" {
+            return Ok(0);
+        }
+
+        let horizon = Self::history_horizon(account);
+        let Some(window) = backfill.next_window(horizon) else {
+            self.store_backfill(folder, None).await?;
+            return Ok(0);
+        };
+
+        let mut provider = ProviderFactory::create_with_app_handle(
+            account,
+            Arc::clone(&self.credential_store),
+            self.app_handle.clone(),
+        )?;
+        let credentials = self.load_credentials(account).await?;
+        provider.authenticate(credentials).await?;
+        provider.set_attachment_policy(self.attachment_policy());
+
+        let emails = match provider.fetch_window(folder, &window).await {
+            Ok(emails) => emails,
+            Err(SyncError::NotSupported(_)) => {
+                log::info!(
+                    "[EmailSync] {} cannot backfill folder {}, stopping",
+                    provider.name(),
+                    folder.name
+                );
+                self.store_backfill(folder, None).await?;
+                return Ok(0);
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Stored without the reconciler, so old mail does not notify as new
+        let mut added = 0;
+        for email in &emails {
+            match self.upsert_email(email, account.id, "synced").await {
+                Ok((_, _, true, _)) => added += 1,
+                Ok(_) => {}
+                Err(e) => log::error!(
+                    "[EmailSync] Failed to store backfilled email {}: {}",
+                    email.remote_id,
+                    e
+                ),
+            }
+        }
+        self.commit_search_index().await?;
+
+        let next = backfill.advance(&window, emails.len());
+        let pending = next.next_window(horizon).map(|_| next);
+        self.store_backfill(folder, pending.as_ref()).await?;
+
+        log::info!(
+            "[EmailSync] Backfilled folder {} before {}: {} emails, {} new{}",
+            folder.name,
+            backfill.before,
+            emails.len(),
+            added,
+            if pending.is_none() { ", done" } else { "" }
+        );
+
+        Ok(added)
+    }
+
+    /// Oldest mail the account syncs, `None` for its whole history
+    fn history_horizon(account: &Account) -> Option<DateTime<Utc>> {
+        let days = serde_json::from_value::<AccountSettings>(account.settings.clone())
+            .ok()
+            .and_then(|settings| settings.sync_history_days);
+        backfill::horizon(Utc::now(), days)
+    }
+
+    async fn has_emails(&self, folder: &SyncFolder) -> SyncResult<bool> {
+        let folder_id_str = folder.id.unwrap().to_string();
+        let record = sqlx::query!(
+            r#"SELECT EXISTS(SELECT 1 FROM emails WHERE folder_id = ?) AS "found!: bool""#,
+            folder_id_str
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        Ok(record.found)
+    }
+
+    async fn get_backfill(&self, folder: &SyncFolder) -> SyncResult<Option<Backfill>> {
+        let folder_id_str = folder.id.unwrap().to_string();
+        let record = sqlx::query!(
+            "SELECT checkpoint_data FROM sync_state WHERE folder_id = ?",
+            folder_id_str
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        Ok(record
+            .and_then(|r| r.checkpoint_data)
+            .and_then(|data| serde_json::from_str(&data).ok()))
+    }
+
+    /// Store the progress of a folder's backfill, `None` once it is done
+    async fn store_backfill(
+        &self,
+        folder: &SyncFolder,
+        backfill: Option<&Backfill>,
+    ) -> SyncResult<()> {
+        let id = Uuid::now_v7().to_string();
+        let account_id_str = folder.account_id.to_string();
+        let folder_id_str = folder.id.unwrap().to_string();
+        let checkpoint = backfill.map(serde_json::to_string).transpose()?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO sync_state (id, account_id, folder_id, checkpoint_data)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(account_id, folder_id)
+            DO UPDATE SET
+                checkpoint_data = excluded.checkpoint_data,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+            id,
+            account_id_str,
+            folder_id_str,
+            checkpoint
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
     async fn commit_search_index(&self) -> SyncResult<()> {
         if let Some(search_manager) = &self.search_manager {
             if let Err(e) = search_manager.commit().await {
@@ -329,133 +585,158 @@ impl EmailSync {
         let mut deleted_count = 0;
         let mut skipped_count = 0;
 
-        for remote_id in missing_remote_ids {
-            if remote_id.is_empty() {
-                continue;
-            }
+        let missing_remote_ids: Vec<String> = missing_remote_ids
+            .into_iter()
+            .filter(|remote_id| !remote_id.is_empty())
+            .collect();
 
-            match provider.fetch_email(folder, &remote_id).await {
-                Ok(email) => {
-                    log::debug!(
-                        "[EmailSync] Email {} still exists on server in folder {}, updating",
-                        remote_id,
-                        folder.name
+        for chunk in missing_remote_ids.chunks(MISSING_FETCH_CHUNK) {
+            let fetched = match provider.fetch_emails(folder, chunk).await {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    log::warn!(
+                        "[EmailSync] Failed to look up {} missing emails in folder {}: {}",
+                        chunk.len(),
+                        folder.name,
+                        e
                     );
+                    skipped_count += chunk.len();
+                    continue;
+                }
+            };
 
-                    let sync_status = if account.account_type == AccountType::Imap
-                        && email.body_plain.is_none()
-                        && email.body_html.is_none()
-                    {
-                        "headers_only"
-                    } else {
-                        "synced"
-                    };
-
-                    let (email_id, uncached_inline_ids, _is_new, _db_email) =
-                        self.upsert_email(&email, account.id, sync_status).await?;
-
-                    if sync_status == "synced" && !uncached_inline_ids.is_empty() {
-                        for attachment_id in uncached_inline_ids {
-                            let attachment = self
-                                .attachment_handler
-                                .get_attachment_metadata(attachment_id)
-                                .await?;
-
-                            if attachment.is_inline {
-                                match provider.fetch_attachment(&attachment).await {
-                                    Ok(data) => {
-                                        self.attachment_handler
-                                            .cache_attachment(
+            for (remote_id, result) in chunk.iter().zip(fetched) {
+                match result {
+                    Ok(email) => {
+                        log::debug!(
+                            "[EmailSync] Email {} still exists on server in folder {}, updating",
+                            remote_id,
+                            folder.name
+                        );
+
+                        let sync_status = if account.account_type == AccountType::Imap
+                            && email.body_plain.is_none()
+                            && email.body_html.is_none()
+                        {
+                            "headers_only"
+                        } else {
+                            "synced"
+                        };
+
+                        let (email_id, uncached_inline_ids, _is_new, _db_email) =
+                            self.upsert_email(&email, account.id, sync_status).await?;
+
+                        if sync_status == "synced" && !uncached_inline_ids.is_empty() {
+                            for attachment_id in uncached_inline_ids {
+                                let attachment = self
+                                    .attachment_handler
+                                    .get_attachment_metadata(attachment_id)
+                                    .await?;
+
+                                if attachment.is_inline
+                                    && self
+                                        .attachment_policy()
+                                        .should_download(true, attachment.size)
+                                {
+                                    match provider.fetch_attachment(&attachment).await {
+                                        Ok(data) => {
+                                            if let Some(settings) = &self.settings {
+                                                bandwidth::throttle(settings, data.len() as u64)
+                                                    .await;
+                                            }
+                                            self.attachment_handler
+                                                .cache_attachment(
+                                                    attachment_id,
+                                                    account.id,
+                                                    email_id,
+                                                    &data,
+                                                    &attachment.filename,
+                                                )
+                                                .await?;
+                                        }
+                                        Err(e) => {
+                                            log::warn!(
+                                                "[EmailSync] Failed to fetch inline attachment {} for email {}: {}",
                                                 attachment_id,
-                                                account.id,
                                                 email_id,
-                                                &data,
-                                                &attachment.filename,
-                                            )
-                                            .await?;
-                                    }
-                                    Err(e) => {
-                                        log::warn!(
-                                            "[EmailSync] Failed to fetch inline attachment {} for email {}: {}",
-                                            attachment_id,
-                                            email_id,
-                                            e
-                                        );
+                                                e
+                                            );
+                                        }
                                     }
                                 }
                             }
                         }
-                    }
 
-                    updated_count += 1;
-                }
-                Err(_) => {
-                    let other_folder = sqlx::query!(
-                        r#"
-                        SELECT id, folder_id
-                        FROM emails
-                        WHERE account_id = ? AND remote_id = ? AND folder_id != ? AND is_deleted = 0
-                        LIMIT 1
-                        "#,
-                        account_id_str,
-                        remote_id,
-                        folder_id_str
-                    )
-                    .fetch_optional(&self.pool)
-                    .await
-                    .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
-
-                    if other_folder.is_some() {
-                        log::debug!(
-                            "[EmailSync] Email {} was moved to another folder, marking old instance as deleted in {}",
-                            remote_id,
-                            folder.name
-                        );
-
-                        sqlx::query!(
+                        updated_count += 1;
+                    }
+                    Err(_) => {
+                        let other_folder = sqlx::query!(
                             r#"
-                            UPDATE emails
-                            SET is_deleted = 1, updated_at = CURRENT_TIMESTAMP
-                            WHERE account_id = ? AND folder_id = ? AND remote_id = ? AND is_deleted = 0
+                            SELECT id, folder_id
+                            FROM emails
+                            WHERE account_id = ? AND remote_id = ? AND folder_id != ? AND is_deleted = 0
+                            LIMIT 1
                             "#,
                             account_id_str,
-                            folder_id_str,
-                            remote_id
-                        )
-                        .execute(&self.pool)
-                        .await
-                        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
-
-                        deleted_count += 1;
-                    } else if is_full_sync {
-                        log::info!(
-                            "[EmailSync] Email {} deleted from server (full sync), marking as deleted in folder {}",
                             remote_id,
-                            folder.name
-                        );
-
-                        sqlx::query!(
-                            r#"
-                            UPDATE emails
-                            SET is_deleted = 1, updated_at = CURRENT_TIMESTAMP
-                            WHERE account_id = ? AND folder_id = ? AND remote_id = ? AND is_deleted = 0
-                            "#,
-                            account_id_str,
-                            folder_id_str,
-                            remote_id
+                            folder_id_str
                         )
-                        .execute(&self.pool)
+                        .fetch_optional(&self.pool)
                         .await
                         .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
-                        deleted_count += 1;
-                    } else {
-                        log::debug!(
-                            "[EmailSync] Email {} not found in folder {} (incremental sync), skipping - may be old email or moved to unsynced folder",
-                            remote_id,
-                            folder.name
-                        );
-                        skipped_count += 1;
+                        if other_folder.is_some() {
+                            log::debug!(
+                                "[EmailSync] Email {} was moved to another folder, marking old instance as deleted in {}",
+                                remote_id,
+                                folder.name
+                            );
+
+                            sqlx::query!(
+                                r#"
+                                UPDATE emails
+                                SET is_deleted = 1, updated_at = CURRENT_TIMESTAMP
+                                WHERE account_id = ? AND folder_id = ? AND remote_id = ? AND is_deleted = 0
+                                "#,
+                                account_id_str,
+                                folder_id_str,
+                                remote_id
+                            )
+                            .execute(&self.pool)
+                            .await
+                            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+                            deleted_count += 1;
+                        } else if is_full_sync {
+                            log::info!(
+                                "[EmailSync] Email {} deleted from server (full sync), marking as deleted in folder {}",
+                                remote_id,
+                                folder.name
+                            );
+
+                            sqlx::query!(
+                                r#"
+                                UPDATE emails
+                                SET is_deleted = 1, updated_at = CURRENT_TIMESTAMP
+                                WHERE account_id = ? AND folder_id = ? AND remote_id = ? AND is_deleted = 0
+                                "#,
+                                account_id_str,
+                                folder_id_str,
+                                remote_id
+                            )
+                            .execute(&self.pool)
+                            .await
+                            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+                            deleted_count += 1;
+                        } else {
+                            log::debug!(
+                                "[EmailSync] Email {} not found in folder {} (incremental sync), skipping - may be old email or moved to unsynced folder",
+                                remote_id,
+                                folder.name
+                            );
+                            skipped_count += 1;
+                        }
                     }
                 }
             }
@@ -487,6 +768,145 @@ impl EmailSync {
         Ok(deleted_count)
     }
 
+    /// Stream an attachment from the provider into the attachment cache,
+    /// emitting progress events until it completes, fails or `cancelled`
+    /// is set. Returns the cache path.
+    pub async fn download_attachment(
+        &self,
+        account: &Account,
+        attachment_id: Uuid,
+        cancelled: Arc<AtomicBool>,
+    ) -> SyncResult<String> {
+        let attachment = self
+            .attachment_handler
+            .get_attachment_metadata(attachment_id)
+            .await?;
+        if let (true, Some(cache_path)) = (attachment.is_cached, &attachment.cache_path) {
+            return Ok(cache_path.clone());
+        }
+
+        let email_id = attachment.email_id.ok_or_else(|| {
+            SyncError::AttachmentError(format!("Attachment {} has no email", attachment_id))
+        })?;
+
+        let mut provider = ProviderFactory::create_with_app_handle(
+            account,
+            Arc::clone(&self.credential_store),
+            self.app_handle.clone(),
+        )?;
+        let credentials = self.load_credentials(account).await?;
+        provider.authenticate(credentials).await?;
+
+        let (cache_path, writer) = self
+            .attachment_handler
+            .begin_download(account.id, email_id, &attachment.filename)
+            .await?;
+        let mut sink = AttachmentSink::new(
+            writer,
+            attachment_id,
+            email_id,
+            u64::try_from(attachment.size).ok().filter(|size| *size > 0),
+            cancelled,
+            self.app_handle.clone(),
+        );
+
+        let streamed = if attachment.remote_path.is_some() {
+            provider.stream_attachment(&attachment, &mut sink).await
+        } else {
+            self.stream_from_message(provider.as_ref(), email_id, &attachment, &mut sink)
+                .await
+        };
+        if let Err(e) = streamed {
+            log::warn!(
+                "[EmailSync] Download of attachment {} stopped after {} bytes: {}",
+                attachment_id,
+                sink.downloaded(),
+                e
+            );
+            sink.abort(&e).await;
+            return Err(e);
+        }
+
+        let (writer, content_hash, progress) = sink.finish();
+        self.attachment_handler
+            .complete_download(attachment_id, &cache_path, writer, &content_hash)
+            .await?;
+        emit_progress(self.app_handle.as_ref(), &progress);
+
+        log::info!(
+            "[EmailSync] Downloaded attachment {} ({} bytes)",
+            attachment.filename,
+            progress.downloaded
+        );
+
+        Ok(cache_path)
+    }
+
+    /// Attachments of providers that deliver them with the message (IMAP) have
+    /// no remote path of their own, so the message is fetched again instead
+    async fn stream_from_message(
+        &self,
+        provider: &dyn EmailProvider,
+        email_id: Uuid,
+        attachment: &SyncAttachment,
+        sink: &mut AttachmentSink,
+    ) -> SyncResult<()> {
+        let repo_factory = RepositoryFactory::new(self.pool.clone());
+        let (folder_id, remote_id) = repo_factory
+            .email_repository()
+            .find_for_remote_operation(email_id)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| SyncError::EmailNotFound(format!("Email not found: {}", email_id)))?;
+        let folder = repo_factory
+            .folder_repository()
+            .find_by_id(folder_id)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| SyncError::FolderNotFound(format!("Folder not found: {}", folder_id)))?;
+
+        let sync_folder = SyncFolder {
+            id: Some(folder.id),
+            account_id: folder.account_id,
+            name: folder.name,
+            folder_type: folder.folder_type,
+            remote_id: folder.remote_id.unwrap_or_default(),
+            parent_id: folder.parent_id,
+            icon: folder.icon,
+            color: folder.color,
+            sync_interval: folder.sync_interval,
+            synced_at: Some(folder.synced_at),
+            attributes: Vec::new(),
+            unread_count: folder.unread_count as i32,
+            total_count: folder.total_count as i32,
+            expanded: folder.expanded,
+            hidden: folder.hidden,
+        };
+
+        let email = provider.fetch_email(&sync_folder, &remote_id).await?;
+        let data = email
+            .attachments
+            .into_iter()
+            .filter(|a| {
+                a.hash == attachment.hash
+                    || (a.content_id.is_some() && a.content_id == attachment.content_id)
+            })
+            .find_map(|a| a.data)
+            .ok_or_else(|| {
+                SyncError::AttachmentError(format!(
+                    "Attachment {} is no longer part of its message",
+                    attachment.filename
+                ))
+            })?;
+
+        sink.set_total(data.len() as u64);
+        for chunk in data.chunks(STREAM_CHUNK_SIZE) {
+            sink.write(chunk).await?;
+        }
+
+        Ok(())
+    }
+
     /// Load credentials from keyring based on account type
     async fn load_credentials(&self, account: &Account) -> SyncResult<ProviderCredentials> {
         if !self.credential_store.has_credentials(account.id).await {
@@ -540,6 +960,139 @@ impl EmailSync {
         Ok(record.and_then(|r| r.sync_token))
     }
 
+    async fn get_uid_validity(&self, folder: &SyncFolder) -> SyncResult<Option<u32>> {
+        let folder_id_str = folder.id.unwrap().to_string();
+        let record = sqlx::query!(
+            "SELECT uid_validity FROM sync_state WHERE folder_id = ?",
+            folder_id_str
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        Ok(record
+            .and_then(|r| r.uid_validity)
+            .and_then(|v| u32::try_from(v).ok()))
+    }
+
+    async fn store_uid_validity(&self, folder: &SyncFolder, uid_validity: u32) -> SyncResult<()> {
+        let id = Uuid::now_v7().to_string();
+        let account_id_str = folder.account_id.to_string();
+        let folder_id_str = folder.id.unwrap().to_string();
+        let uid_validity = i64::from(uid_validity);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO sync_state (id, account_id, folder_id, uid_validity)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(account_id, folder_id)
+            DO UPDATE SET
+                uid_validity = ?,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+            id,
+            account_id_str,
+            folder_id_str,
+            uid_validity,
+            uid_validity
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Drops the remote IDs of a folder's emails, and cancels the pending
+    /// operations that would act on them. Returns the emails that were live.
+    async fn invalidate_remote_ids(&self, folder: &SyncFolder) -> SyncResult<Vec<String>> {
+        let folder_id_str = folder.id.unwrap().to_string();
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        let live = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM emails WHERE folder_id = ? AND is_deleted = 0 AND remote_id IS NOT NULL",
+        )
+        .bind(&folder_id_str)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        let cancelled = sqlx::query(
+            r#"
+            UPDATE pending_operations
+            SET status = 'cancelled'
+            WHERE status = 'pending'
+              AND email_id IN (SELECT id FROM emails WHERE folder_id = ?)
+            "#,
+        )
+        .bind(&folder_id_str)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        // Tombstones too, so a new message reusing a UID is not mistaken for
+        // a deleted one
+        sqlx::query(
+            r#"
+            UPDATE emails
+            SET remote_id = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE folder_id = ? AND remote_id IS NOT NULL
+            "#,
+        )
+        .bind(&folder_id_str)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "[EmailSync] Invalidated {} emails and cancelled {} pending operations in folder {}",
+            live.len(),
+            cancelled.rows_affected(),
+            folder.name
+        );
+
+        Ok(live)
+    }
+
+    /// Tombstones the invalidated emails that reconciliation did not match to
+    /// a message on the server again
+    async fn remove_unmatched(&self, folder: &SyncFolder, email_ids: &[String]) -> SyncResult<u64> {
+        let folder_id_str = folder.id.unwrap().to_string();
+        let now = Utc::now();
+        let mut removed = 0;
+
+        for email_id in email_ids {
+            removed += sqlx::query(
+                r#"
+                UPDATE emails
+                SET is_deleted = 1,
+                    deleted_at = ?,
+                    deletion_source = 'provider',
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = ? AND folder_id = ? AND remote_id IS NULL AND is_deleted = 0
+                "#,
+            )
+            .bind(now)
+            .bind(email_id)
+            .bind(&folder_id_str)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+            .rows_affected();
+        }
+
+        Ok(removed)
+    }
+
     /// Get current sync status to prevent concurrent syncs
     async fn get_sync_status(&self, folder: &SyncFolder) -> SyncResult<String> {
         let folder_id_str = folder.id.unwrap().to_string();
@@ -599,6 +1152,7 @@ impl EmailSync {
             DO UPDATE SET
                 sync_status = ?,
                 updated_at = CURRENT_TIMESTAMP
+            WHERE sync_status != 'paused'
             "#,
             id,
             account_id_str,
@@ -621,6 +1175,45 @@ impl EmailSync {
         Ok(())
     }
 
+    /// Mark the folder as failed, keeping the error and counting failures
+    /// since the last successful sync
+    async fn record_sync_error(&self, folder: &SyncFolder, error: &str) -> SyncResult<()> {
+        let id = Uuid::now_v7().to_string();
+        let account_id_str = folder.account_id.to_string();
+        let folder_id_str = folder.id.unwrap().to_string();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO sync_state (id, account_id, folder_id, sync_status, error_message, error_count)
+            VALUES (?, ?, ?, 'error', ?, 1)
+            ON CONFLICT(account_id, folder_id)
+            DO UPDATE SET
+                sync_status = 'error',
+                error_message = ?,
+                error_count = error_count + 1,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE sync_status != 'paused'
+            "#,
+            id,
+            account_id_str,
+            folder_id_str,
+            error,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::warn!(
+            "[EmailSync] Sync of folder {} (account {}) failed: {}",
+            folder.name,
+            folder.account_id,
+            error
+        );
+
+        Ok(())
+    }
+
     /// Store sync token (delta link) for Office365 incremental sync
     /// Preserves the current sync_status instead of resetting to idle
     async fn store_sync_token(&self, folder: &SyncFolder, token: &str) -> SyncResult<()> {
@@ -761,7 +1354,8 @@ impl EmailSync {
             folder_id: sync_email.folder_id,
             message_id: sync_email.message_id.clone(),
             conversation_id: conversation_uuid.map(|u| u.to_string()),
-            remote_id: Some(sync_email.remote_id.clone()),
+            // Imported messages have no copy on the server
+            remote_id: Some(sync_email.remote_id.clone()).filter(|id| !id.is_empty()),
             from: Json(sync_email.from.clone()),
             to: Json(sync_email.to.clone()),
             cc: Json(sync_email.cc.clone()),
@@ -778,6 +1372,7 @@ impl EmailSync {
             sent_at: sync_email.sent_at,
             scheduled_send_at: None,
             remind_at: None,
+            snoozed_until: None,
             is_read: sync_email.flags.contains(&"\\Seen".to_string()),
             is_flagged: sync_email.flags.contains(&"\\Flagged".to_string()),
             is_draft: sync_email.flags.contains(&"\\Draft".to_string()),
@@ -801,6 +1396,9 @@ impl EmailSync {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             size: sync_email.size,
+            priority: 0,
+            risk_score: 0,
+            risk_reasons: Json(Vec::new()),
         })
     }
 
@@ -815,22 +1413,36 @@ impl EmailSync {
         let repo_factory = RepositoryFactory::new(self.pool.clone());
         let email_repo = repo_factory.email_repository();
 
-        let category = EmailCategorizer::categorize(
-            email.headers.as_ref(),
-            email.subject.as_deref(),
-            email.body_plain.as_deref(),
-            email.body_html.as_deref(),
-            &email.from.address,
-        )
-        .map(|c| c.as_str().to_string());
+        // A category the user picked for the sender wins over the heuristics
+        let sender_category = repo_factory
+            .sender_category_repository()
+            .find_by_sender(&email.from.address)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        let category = match sender_category {
+            Some(sender_category) => Some(sender_category.category),
+            None => EmailCategorizer::categorize(
+                email.headers.as_ref(),
+                email.subject.as_deref(),
+                email.body_plain.as_deref(),
+                email.body_html.as_deref(),
+                &email.from.address,
+            )
+            .map(|c| c.as_str().to_string()),
+        };
 
         let existing = email_repo
             .find_by_remote_id_or_message_id(account_id, &email.remote_id, &email.message_id)
             .await
             .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
-        let mut body_plain = existing.as_ref().and_then(|e| e.body_plain.clone());
-        let mut body_html = existing.as_ref().and_then(|e| e.body_html.clone());
-        let mut other_mails = existing.as_ref().and_then(|e| e.other_mails.clone());
+        // The body of a draft follows edits on the server, other bodies never change
+        let is_remote_draft = email.flags.contains(&"\\Draft".to_string());
+        let stored_body = existing
+            .as_ref()
+            .filter(|e| !(e.is_draft && is_remote_draft && email.body_html.is_some()));
+        let mut body_plain = stored_body.and_then(|e| e.body_plain.clone());
+        let mut body_html = stored_body.and_then(|e| e.body_html.clone());
+        let mut other_mails = stored_body.and_then(|e| e.other_mails.clone());
 
         if body_html.is_none() {
             let split_result = EmailBodySplitter::split_body(email.body_html.as_deref());
@@ -862,16 +1474,65 @@ impl EmailSync {
         let conversation_uuid = if let Some(ref provider_conv_id) = email.conversation_id {
             Some(self.find_or_create_conversation(provider_conv_id).await?)
         } else {
-            None
+            // IMAP and imported mail carry no conversation, so it is built
+            // from the threading headers
+            Some(threading::resolve_conversation(&self.pool, email).await?)
         };
 
-        let (email_id, is_new, db_email) = if let Some(existing_email) = existing {
+        let mut labels_pending = false;
+        let (email_id, is_new, mut db_email) = if let Some(existing_email) = existing {
             let email_id = existing_email.id;
             let existing_sync_status = existing_email.sync_status.clone();
             let existing_folder_id = existing_email.folder_id;
             let was_deleted = existing_email.is_deleted;
 
-            if was_deleted {
+            // Local changes that have not reached the provider yet take precedence
+            // over the (potentially stale) provider state
+            let pending_repo = SqlitePendingOperationRepository::new(self.pool.clone());
+            let pending_ops = pending_repo
+                .find_pending_for_email(email_id)
+                .await
+                .unwrap_or_default();
+            let has_pending = |types: &[PendingOperationType]| {
+                pending_ops.iter().any(|op| {
+                    op.parsed_operation_type()
+                        .is_some_and(|op_type| types.contains(&op_type))
+                })
+            };
+            let pending_delete = has_pending(&[
+                PendingOperationType::Delete,
+                PendingOperationType::PermanentDelete,
+            ]);
+            let pending_move = has_pending(&[PendingOperationType::Move]);
+            labels_pending = has_pending(&[
+                PendingOperationType::AddLabel,
+                PendingOperationType::RemoveLabel,
+            ]);
+
+            // A draft still on the server as a draft keeps the content of the side
+            // that changed it last; local edits waiting for upload always win
+            let remote_modified_at = email.last_modified_at.unwrap_or(email.received_at);
+            if existing_email.is_draft
+                && !was_deleted
+                && is_remote_draft
+                && draft_sync::keeps_local_content(
+                    existing_email.updated_at,
+                    remote_modified_at,
+                    has_pending(&[
+                        PendingOperationType::CreateDraft,
+                        PendingOperationType::UpdateDraft,
+                    ]),
+                )
+            {
+                log::debug!(
+                    "[EmailSync] Keeping local content of draft {} over server copy {}",
+                    email_id,
+                    email.remote_id
+                );
+                return Ok((email_id, Vec::new(), false, existing_email));
+            }
+
+            if was_deleted && !pending_delete {
                 log::info!(
                     "[EmailSync] Un-deleting email {} (remote_id: {}) - found on server again",
                     email_id,
@@ -883,7 +1544,7 @@ impl EmailSync {
                     .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
             }
 
-            if existing_folder_id != email.folder_id {
+            if existing_folder_id != email.folder_id && !pending_move {
                 log::info!(
                     "[EmailSync] Email {} moved from folder {} to {}",
                     email_id,
@@ -919,29 +1580,32 @@ impl EmailSync {
             )?;
 
             // Protect optimistic local state: if there are pending operations for this email,
-            // preserve the local is_read/is_flagged/is_deleted values instead of overwriting
-            // with (potentially stale) provider state.
-            let pending_repo = SqlitePendingOperationRepository::new(self.pool.clone());
-            if let Ok(pending_ops) = pending_repo.find_pending_for_email(email_id).await {
-                for op in &pending_ops {
-                    match op.parsed_operation_type() {
-                        Some(PendingOperationType::MarkRead)
-                        | Some(PendingOperationType::MarkUnread) => {
-                            db_email.is_read = existing_email.is_read;
-                        }
-                        Some(PendingOperationType::Flag) | Some(PendingOperationType::Unflag) => {
-                            db_email.is_flagged = existing_email.is_flagged;
-                        }
-                        Some(PendingOperationType::Delete)
-                        | Some(PendingOperationType::PermanentDelete) => {
-                            db_email.is_deleted = existing_email.is_deleted;
-                        }
-                        _ => {}
+            // preserve the local is_read/is_flagged/is_deleted/folder values instead of
+            // overwriting with (potentially stale) provider state.
+            for op in &pending_ops {
+                match op.parsed_operation_type() {
+                    Some(PendingOperationType::MarkRead)
+                    | Some(PendingOperationType::MarkUnread) => {
+                        db_email.is_read = existing_email.is_read;
+                    }
+                    Some(PendingOperationType::Flag) | Some(PendingOperationType::Unflag) => {
+                        db_email.is_flagged = existing_email.is_flagged;
+                    }
+                    Some(PendingOperationType::Delete)
+                    | Some(PendingOperationType::PermanentDelete) => {
+                        db_email.is_deleted = existing_email.is_deleted;
                     }
+                    Some(PendingOperationType::Move) => {
+                        db_email.folder_id = existing_email.folder_id;
+                    }
+                    _ => {}
                 }
             }
 
             db_email.ai_cache = existing_email.ai_cache.clone();
+            db_email.priority = existing_email.priority;
+            db_email.risk_score = existing_email.risk_score;
+            db_email.risk_reasons = existing_email.risk_reasons.clone();
 
             if should_update_body {
                 log::debug!(
@@ -987,18 +1651,51 @@ impl EmailSync {
                 .await
                 .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
-            self.contact_extractor
-                .extract_and_store_from_received_email(&db_email)
+            let folder_type = repo_factory
+                .folder_repository()
+                .find_by_id(db_email.folder_id)
                 .await
-                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+                .map(|folder| folder.folder_type);
+            if folder_type == Some(FolderType::Sent) {
+                self.contact_extractor
+                    .extract_and_store_from_sent_email(
+                        account_id,
+                        &db_email.to.0,
+                        &db_email.cc.0,
+                        &db_email.bcc.0,
+                        db_email.sent_at,
+                    )
+                    .await
+            } else {
+                self.contact_extractor
+                    .extract_and_store_from_received_email(&db_email)
+                    .await
+            }
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
             (email_id, true, db_email)
         };
 
+        // Provider labels mirror the server, unless a local label change has not
+        // reached it yet
+        if let Some(remote_labels) = email.remote_labels.as_deref().filter(|_| !labels_pending) {
+            repo_factory
+                .label_repository()
+                .set_remote_labels(email_id, account_id, remote_labels)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        }
+
         let inline_attachment_ids = if !email.attachments.is_empty() {
             let processed = self
                 .attachment_handler
-                .process_attachments(email_id, account_id, &email.attachments)
+                .process_attachments(
+                    email_id,
+                    account_id,
+                    &email.attachments,
+                    self.attachment_policy(),
+                )
                 .await?;
 
             let mut uncached_inline = Vec::new();
@@ -1012,9 +1709,162 @@ impl EmailSync {
             Vec::new()
         };
 
+        // Blocked senders go to spam without being scored, the user already judged them
+        if is_new {
+            if let Err(e) = blocked_senders::process_synced_email(&self.pool, &mut db_email).await {
+                log::warn!(
+                    "[EmailSync] Failed to apply sender block to {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
+        // Score before the message becomes visible to notifications and the AI analyzer
+        match self.junk_filter.process_synced_email(&db_email).await {
+            Ok(Some(verdict)) => {
+                if let Some(spam_folder_id) = verdict.moved_to_folder_id {
+                    db_email.folder_id = spam_folder_id;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!(
+                    "[EmailSync] Junk filter failed for email {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
+        // Muted before rules run, which only act on inbox mail
+        if is_new {
+            if let Err(e) = muted_conversations::process_synced_email(
+                &self.pool,
+                &mut db_email,
+                email.remote_labels.as_deref(),
+            )
+            .await
+            {
+                log::warn!(
+                    "[EmailSync] Failed to apply conversation mute to {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
+        if is_new {
+            if let Err(e) = priority::process_synced_email(&self.pool, &mut db_email).await {
+                log::warn!(
+                    "[EmailSync] Failed to score priority of {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
+        if is_new {
+            if let Err(e) = phishing::process_synced_email(&self.pool, &mut db_email).await {
+                log::warn!(
+                    "[EmailSync] Failed to analyze phishing risk of {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
+        if is_new && !email.attachments.is_empty() {
+            match delivery_status::process_report(&self.pool, email_id, &email.attachments).await {
+                Ok(Some(event)) => {
+                    if let Some(app_handle) = &self.app_handle {
+                        events::emit_event(app_handle, "email:delivery-status", event);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!(
+                        "[EmailSync] Failed to process delivery report {}: {}",
+                        email_id,
+                        e
+                    );
+                }
+            }
+
+            if let Err(e) =
+                calendar_invites::process_invite(&self.pool, email_id, &email.attachments).await
+            {
+                log::warn!(
+                    "[EmailSync] Failed to process calendar invite {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
+        if is_new {
+            if let Some(check) = &email.signature {
+                if let Err(e) =
+                    smime_signatures::record_signature(&self.pool, email_id, check).await
+                {
+                    log::warn!(
+                        "[EmailSync] Failed to record S/MIME signature of {}: {}",
+                        email_id,
+                        e
+                    );
+                }
+            }
+        }
+
+        // Junk is handled first so user rules never act on mail that went to spam
+        if is_new {
+            let mut matched_rule_ids = Vec::new();
+            match self
+                .rules_engine
+                .process_synced_email(&db_email, self.app_handle.as_ref())
+                .await
+            {
+                Ok(Some(outcome)) => {
+                    if let Some(folder_id) = outcome.moved_to_folder_id {
+                        db_email.folder_id = folder_id;
+                    }
+                    db_email.is_read |= outcome.marked_read;
+                    db_email.is_deleted |= outcome.deleted;
+                    matched_rule_ids = outcome.matched_rule_ids;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!("[EmailSync] Rules failed for email {}: {}", email_id, e);
+                }
+            }
+
+            if let Err(e) =
+                automation_triggers::enqueue_new_email(&self.pool, &db_email, &matched_rule_ids)
+                    .await
+            {
+                log::warn!(
+                    "[EmailSync] Failed to queue automation triggers for {}: {}",
+                    email_id,
+                    e
+                );
+            }
+        }
+
         if sync_status == "synced" {
             if let Some(search_manager) = &self.search_manager {
-                if let Err(e) = search_manager.index_email(&db_email).await {
+                let attachment_repo = repo_factory.attachment_repository();
+                let attachments = attachment_repo
+                    .find_by_email(email_id)
+                    .await
+                    .unwrap_or_default();
+                let attachment_texts = attachment_repo
+                    .find_texts_by_emails(&[email_id])
+                    .await
+                    .unwrap_or_default();
+                if let Err(e) = search_manager
+                    .index_email(&db_email, &attachments, &attachment_texts)
+                    .await
+                {
                     log::warn!(
                         "[EmailSync] Failed to index email {} in search: {}",
                         email_id,
@@ -7,6 +7,8 @@ use uuid::Uuid;
 
 use super::error::{SyncError, SyncResult};
 use super::types::{ImapCredentials, OAuth2Credentials};
+use crate::services::dkim::DkimKeys;
+use crate::services::smime::SmimeIdentity;
 
 /// Encrypted credential storage using database with AES-256-GCM encryption
 pub struct EncryptedCredentialStore {
@@ -227,6 +229,120 @@ impl EncryptedCredentialStore {
         Ok(credentials)
     }
 
+    /// Store the account's S/MIME signing identity
+    pub async fn store_smime(&self, account_id: Uuid, identity: &SmimeIdentity) -> SyncResult<()> {
+        let json = serde_json::to_string(identity)?;
+        let (encrypted_data, nonce) = self.encrypt(json.as_bytes())?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO encrypted_credentials (id, account_id, credential_type, encrypted_data, nonce, updated_at)
+            VALUES (?, ?, 'smime', ?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(account_id, credential_type) DO UPDATE SET
+                encrypted_data = excluded.encrypted_data,
+                nonce = excluded.nonce,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(account_id.to_string())
+        .bind(encrypted_data)
+        .bind(nonce)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "Stored encrypted S/MIME identity for account {}",
+            account_id
+        );
+        Ok(())
+    }
+
+    /// Retrieve the account's S/MIME signing identity
+    pub async fn get_smime(&self, account_id: Uuid) -> SyncResult<SmimeIdentity> {
+        let (encrypted_data, nonce): (Vec<u8>, Vec<u8>) = sqlx::query_as(
+            r#"
+            SELECT encrypted_data, nonce
+            FROM encrypted_credentials
+            WHERE account_id = ? AND credential_type = 'smime'
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|_| SyncError::KeyringError("No S/MIME identity found".to_string()))?;
+
+        let plaintext = self.decrypt(&encrypted_data, &nonce)?;
+        let identity: SmimeIdentity = serde_json::from_slice(&plaintext)?;
+        Ok(identity)
+    }
+
+    /// Delete the account's S/MIME signing identity
+    pub async fn delete_smime(&self, account_id: Uuid) -> SyncResult<()> {
+        sqlx::query(
+            "DELETE FROM encrypted_credentials WHERE account_id = ? AND credential_type = 'smime'",
+        )
+        .bind(account_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::info!(
+            "Deleted encrypted S/MIME identity for account {}",
+            account_id
+        );
+        Ok(())
+    }
+
+    /// Store the account's DKIM signing keys, replacing all previous ones
+    pub async fn store_dkim(&self, account_id: Uuid, keys: &DkimKeys) -> SyncResult<()> {
+        let json = serde_json::to_string(keys)?;
+        let (encrypted_data, nonce) = self.encrypt(json.as_bytes())?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO encrypted_credentials (id, account_id, credential_type, encrypted_data, nonce, updated_at)
+            VALUES (?, ?, 'dkim', ?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(account_id, credential_type) DO UPDATE SET
+                encrypted_data = excluded.encrypted_data,
+                nonce = excluded.nonce,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(account_id.to_string())
+        .bind(encrypted_data)
+        .bind(nonce)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::info!("Stored encrypted DKIM keys for account {}", account_id);
+        Ok(())
+    }
+
+    /// Retrieve the account's DKIM signing keys, empty if it has none
+    pub async fn get_dkim(&self, account_id: Uuid) -> SyncResult<DkimKeys> {
+        let row: Option<(Vec<u8>, Vec<u8>)> = sqlx::query_as(
+            r#"
+            SELECT encrypted_data, nonce
+            FROM encrypted_credentials
+            WHERE account_id = ? AND credential_type = 'dkim'
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        let Some((encrypted_data, nonce)) = row else {
+            return Ok(DkimKeys::new());
+        };
+        let plaintext = self.decrypt(&encrypted_data, &nonce)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
     /// Delete credentials for an account
     pub async fn delete(&self, account_id: Uuid) -> SyncResult<()> {
         let account_id_str = account_id.to_string();
@@ -242,6 +358,25 @@ impl EncryptedCredentialStore {
         Ok(())
     }
 
+    /// Accounts with stored credentials, with the type of each credential
+    pub async fn accounts(&self) -> SyncResult<Vec<(Uuid, String)>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT account_id, credential_type FROM encrypted_credentials WHERE credential_type IN ('oauth2', 'imap')",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(account_id, credential_type)| {
+                Uuid::parse_str(&account_id)
+                    .ok()
+                    .map(|account_id| (account_id, credential_type))
+            })
+            .collect())
+    }
+
     /// Check if credentials exist for an account
     pub async fn has_credentials(&self, account_id: Uuid) -> bool {
         let account_id_str = account_id.to_string();
@@ -14,6 +14,13 @@ pub enum SyncError {
     #[error("Gmail API error: {0}")]
     GmailError(String),
 
+    /// A Gmail request answered with an error status
+    #[error("Gmail API error: {message}: {status}")]
+    GmailStatus {
+        status: reqwest::StatusCode,
+        message: String,
+    },
+
     #[error("Office365 API error: {0}")]
     Office365Error(String),
 
@@ -44,6 +51,9 @@ pub enum SyncError {
     #[error("Invalid configuration: {0}")]
     InvalidConfiguration(String),
 
+    #[error("Untrusted certificate: {0}")]
+    UntrustedCertificate(String),
+
     #[error("Sync in progress: {0}")]
     SyncInProgress(String),
 
@@ -62,6 +72,9 @@ pub enum SyncError {
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -84,13 +97,15 @@ impl SyncError {
     pub fn category(&self) -> ErrorCategory {
         match self {
             SyncError::NetworkError(_) | SyncError::ReqwestError(_) => ErrorCategory::Transient,
-            SyncError::GmailError(_) | SyncError::Office365Error(_) | SyncError::ImapError(_) => {
-                ErrorCategory::Provider
-            }
+            SyncError::GmailError(_)
+            | SyncError::GmailStatus { .. }
+            | SyncError::Office365Error(_)
+            | SyncError::ImapError(_) => ErrorCategory::Provider,
             SyncError::ParseError(_) | SyncError::JsonError(_) => ErrorCategory::DataCorruption,
             SyncError::AuthenticationError(_)
             | SyncError::OAuth2Error(_)
             | SyncError::InvalidConfiguration(_)
+            | SyncError::UntrustedCertificate(_)
             | SyncError::KeyringError(_) => ErrorCategory::Configuration,
             SyncError::DatabaseError(_) | SyncError::IoError(_) => ErrorCategory::Fatal,
             SyncError::SyncInProgress(_) | SyncError::NotSupported(_) => ErrorCategory::Transient,
@@ -106,6 +121,15 @@ impl SyncError {
         )
     }
 
+    /// Whether the provider could not be reached at all (offline, DNS, timeouts)
+    pub fn is_connectivity_error(&self) -> bool {
+        match self {
+            SyncError::NetworkError(_) => true,
+            SyncError::ReqwestError(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
+
     pub(crate) fn timeout(_p0: String) -> SyncError {
         todo!()
     }
@@ -1,8 +1,12 @@
 use super::types::{SyncEmail, SyncFolder};
+use crate::database::models::delivery_status::RecipientDeliveryStatus;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tauri::Emitter;
+use tauri::Runtime;
 use uuid::Uuid;
 
+use crate::services::app_lock;
+
 /// Event emitted when folders are updated
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FoldersUpdatedEvent {
@@ -63,6 +67,14 @@ pub struct EmailDeletedEvent {
     pub permanent: bool,
 }
 
+/// Event emitted when a deleted email is restored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailRestoredEvent {
+    pub account_id: Uuid,
+    pub email_id: Uuid,
+    pub folder_id: Uuid,
+}
+
 /// Event emitted when a folder is renamed
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FolderRenamedEvent {
@@ -114,6 +126,14 @@ pub struct CredentialsRequiredEvent {
     pub reason: String,
 }
 
+/// Event emitted when an account's OAuth credentials were replaced after it
+/// granted more scopes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialsUpdatedEvent {
+    pub account_id: Uuid,
+    pub scopes: Vec<String>,
+}
+
 /// Event emitted when a pending operation fails permanently
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OperationFailedEvent {
@@ -152,13 +172,63 @@ pub struct ConflictResolvedEvent {
     pub resolution: String,
 }
 
-/// Helper to emit events to the frontend
-pub fn emit_event<T: Serialize + Clone>(
-    app_handle: &tauri::AppHandle,
+/// Event emitted while a scheduled draft is being sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledSendEvent {
+    pub account_id: Uuid,
+    pub email_id: Uuid,
+    pub status: ScheduledSendStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledSendStatus {
+    Sending,
+    Sent,
+    Failed { message: String },
+}
+
+/// Event emitted as an email in the outbox is retried
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendStatusEvent {
+    pub account_id: Uuid,
+    pub outbox_id: Uuid,
+    pub status: SendStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SendStatus {
+    /// Waiting to be retried after failing to send
+    Queued {
+        attempts: i64,
+        next_attempt_at: DateTime<Utc>,
+        error: String,
+    },
+    Sending,
+    Sent,
+    /// Given up on; only sent again on request
+    Failed {
+        message: String,
+    },
+}
+
+/// Event emitted when a delivery status notification or read receipt updates a
+/// sent message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryStatusEvent {
+    pub account_id: Uuid,
+    pub email_id: Uuid,
+    pub statuses: Vec<RecipientDeliveryStatus>,
+}
+
+/// Helper to emit events to the frontend, dropped while the app is locked
+pub fn emit_event<R: Runtime, T: Serialize + Clone>(
+    app_handle: &tauri::AppHandle<R>,
     event_name: &str,
     payload: T,
 ) {
-    if let Err(e) = app_handle.emit(event_name, payload) {
+    if let Err(e) = app_lock::emit_mail_event(app_handle, event_name, payload) {
         log::error!("Failed to emit event '{}': {}", event_name, e);
     }
 }
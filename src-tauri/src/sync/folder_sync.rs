@@ -181,7 +181,7 @@ impl FolderSync {
         }
     }
 
-    /// Get folders for an account from database
+    /// Get the visible folders of an account that are synced in the background
     pub async fn get_folders(&self, account_id: Uuid) -> SyncResult<Vec<SyncFolder>> {
         let folder_repo = SqliteFolderRepository::new(self.pool.clone());
         let folders = folder_repo
@@ -191,8 +191,9 @@ impl FolderSync {
 
         let sync_folders = folders
             .into_iter()
-            .filter(|folder| !folder.hidden)
+            .filter(|folder| !folder.hidden && folder.sync_enabled)
             .map(|folder| SyncFolder {
+                sync_interval: folder.sync_interval_secs(),
                 id: Some(folder.id),
                 account_id: folder.account_id,
                 name: folder.name,
@@ -207,7 +208,6 @@ impl FolderSync {
                 expanded: folder.expanded,
                 hidden: folder.hidden,
                 synced_at: Some(folder.synced_at),
-                sync_interval: folder.sync_interval,
             })
             .collect();
 
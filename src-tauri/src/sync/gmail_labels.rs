@@ -0,0 +1,140 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::account::AccountType;
+use crate::database::models::email::Email;
+use crate::database::models::label::Label;
+use crate::database::models::pending_operation::{PendingOperation, PendingOperationType};
+use crate::database::repositories::{
+    AccountRepository, LabelRepository, SqliteAccountRepository, SqliteLabelRepository,
+    SqlitePendingOperationRepository,
+};
+use crate::sync::auth::CredentialStore;
+use crate::sync::providers::gmail::GmailProvider;
+
+/// Mirrors the account's Gmail user labels into the `labels` table, keeping
+/// local IDs stable across renames and dropping labels deleted in Gmail, and
+/// returns the account's synced labels.
+///
+/// Returns `Ok(None)` for accounts whose provider has no labels.
+pub async fn refresh_labels(
+    pool: &SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    account_id: Uuid,
+) -> Result<Option<Vec<Label>>, String> {
+    let account = SqliteAccountRepository::new(pool.clone())
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to load account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    if account.account_type != AccountType::Gmail {
+        return Ok(None);
+    }
+
+    let remote_labels = GmailProvider::new(account.id, credential_store)
+        .map_err(|e| e.to_string())?
+        .fetch_user_labels()
+        .await
+        .map_err(|e| format!("Failed to fetch labels: {}", e))?;
+
+    let label_repo = SqliteLabelRepository::new(pool.clone());
+    for remote_label in &remote_labels {
+        label_repo
+            .upsert_remote(&Label {
+                id: Uuid::now_v7(),
+                account_id: Some(account.id),
+                remote_id: Some(remote_label.remote_id.clone()),
+                name: remote_label.name.clone(),
+                color: remote_label.color.clone(),
+                icon: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .map_err(|e| format!("Failed to store label {}: {}", remote_label.name, e))?;
+    }
+
+    let remote_ids: Vec<String> = remote_labels
+        .into_iter()
+        .map(|label| label.remote_id)
+        .collect();
+    let removed = label_repo
+        .delete_remote_except(account.id, &remote_ids)
+        .await
+        .map_err(|e| format!("Failed to remove deleted labels: {}", e))?;
+
+    log::info!(
+        "[Labels] Account {} has {} Gmail labels ({} removed)",
+        account.id,
+        remote_ids.len(),
+        removed
+    );
+
+    label_repo
+        .find_remote_by_account(account.id)
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to load labels: {}", e))
+}
+
+/// Queues adding or removing a provider label on the server after it was
+/// changed locally. Local labels are not synced and are ignored.
+pub async fn queue_label_change(
+    pool: &SqlitePool,
+    email: &Email,
+    label: &Label,
+    applied: bool,
+) -> Result<(), String> {
+    let Some(label_remote_id) = &label.remote_id else {
+        return Ok(());
+    };
+
+    if label.account_id != Some(email.account_id) {
+        return Err(format!(
+            "Label {} belongs to a different account than email {}",
+            label.name, email.id
+        ));
+    }
+
+    queue_remote_label_change(pool, email, label_remote_id, applied).await
+}
+
+/// Queues adding or removing a label by its Gmail ID, which may also be a
+/// system label like `MUTED`
+pub async fn queue_remote_label_change(
+    pool: &SqlitePool,
+    email: &Email,
+    label_remote_id: &str,
+    applied: bool,
+) -> Result<(), String> {
+    let Some(remote_id) = &email.remote_id else {
+        return Ok(());
+    };
+
+    let op_type = if applied {
+        PendingOperationType::AddLabel
+    } else {
+        PendingOperationType::RemoveLabel
+    };
+    let op = PendingOperation::new(
+        email.account_id,
+        Some(email.id),
+        Some(email.folder_id),
+        op_type,
+        serde_json::json!({
+            "remote_id": remote_id,
+            "folder_id": email.folder_id.to_string(),
+            "label_remote_id": label_remote_id,
+        }),
+    );
+
+    SqlitePendingOperationRepository::new(pool.clone())
+        .create(&op)
+        .await
+        .map_err(|e| format!("Failed to queue label change: {}", e))?;
+
+    Ok(())
+}
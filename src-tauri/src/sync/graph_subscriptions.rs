@@ -0,0 +1,668 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use super::auth::CredentialStore;
+use super::folder_sync::FolderSync;
+use super::providers::office365::Office365Provider;
+use super::types::SyncFolder;
+use super::SyncCoordinator;
+use crate::config::Settings;
+use crate::database::models::account::AccountType;
+use crate::database::models::folder::FolderType;
+use crate::database::repositories::{AccountRepository, SqliteAccountRepository};
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 15 * 60;
+/// Graph caps Outlook message subscriptions at just under three days
+const SUBSCRIPTION_LIFETIME_MINS: i64 = 4230;
+const RENEWAL_WINDOW_MINS: i64 = 60;
+const NOTIFICATION_URL_SETTING: &str = "sync.office365.notificationUrl";
+const RELAY_POLL_INTERVAL_SECS: u64 = 30;
+const RELAY_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Payload delivered by Graph to the notification endpoint
+///
+/// The relay behind the notification URL answers Graph's validation handshake,
+/// queues the posted batches and hands them out on `GET`: one payload with
+/// every notification received since the previous poll, or `204 No Content`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphNotificationPayload {
+    #[serde(default)]
+    pub value: Vec<GraphNotification>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNotification {
+    pub subscription_id: String,
+    pub client_state: Option<String>,
+    pub change_type: Option<String>,
+    pub lifecycle_event: Option<String>,
+    pub resource: Option<String>,
+}
+
+struct SubscriptionRecord {
+    subscription_id: String,
+    account_id: Uuid,
+    folder_id: Uuid,
+    client_state: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Keeps Graph change-notification subscriptions alive for Office365 mail folders
+/// and turns incoming notifications into targeted folder syncs
+pub struct GraphSubscriptionManager {
+    pool: SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    sync_coordinator: Arc<SyncCoordinator>,
+    settings: Arc<Settings>,
+    client: reqwest::Client,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+    relay_poll_interval: Duration,
+}
+
+impl GraphSubscriptionManager {
+    pub fn new(
+        pool: SqlitePool,
+        credential_store: Arc<CredentialStore>,
+        sync_coordinator: Arc<SyncCoordinator>,
+        settings: Arc<Settings>,
+    ) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            credential_store,
+            sync_coordinator,
+            settings,
+            client: reqwest::Client::builder()
+                .timeout(RELAY_TIMEOUT)
+                .user_agent(concat!("Ravn/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap_or_default(),
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+            relay_poll_interval: Duration::from_secs(RELAY_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[GraphSubscriptionManager] Starting subscription manager");
+
+        let manager = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[GraphSubscriptionManager] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(manager.poll_interval) => {
+                        if let Err(error) = manager.reconcile().await {
+                            log::error!(
+                                "[GraphSubscriptionManager] Failed to reconcile subscriptions: {}",
+                                error
+                            );
+                        }
+                    }
+                }
+            }
+        });
+
+        let manager = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown_rx.recv() => break,
+                    _ = sleep(manager.relay_poll_interval) => {
+                        if let Err(error) = manager.poll_relay().await {
+                            log::warn!(
+                                "[GraphSubscriptionManager] Failed to poll notification relay: {}",
+                                error
+                            );
+                        }
+                    }
+                }
+            }
+        });
+
+        if let Err(error) = self.reconcile().await {
+            log::error!(
+                "[GraphSubscriptionManager] Initial subscription pass failed: {}",
+                error
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[GraphSubscriptionManager] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    fn notification_url(&self) -> Option<String> {
+        self.settings
+            .get::<String>(NOTIFICATION_URL_SETTING)
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+    }
+
+    /// Fetches the notifications queued at the relay and syncs the folders they touch
+    pub async fn poll_relay(&self) -> Result<usize, String> {
+        let Some(notification_url) = self.notification_url() else {
+            return Ok(0);
+        };
+
+        let response = self
+            .client
+            .get(&notification_url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NO_CONTENT {
+            return Ok(0);
+        }
+        if !status.is_success() {
+            return Err(format!("Relay returned {}", status));
+        }
+
+        let payload = response
+            .json::<GraphNotificationPayload>()
+            .await
+            .map_err(|e| format!("Invalid relay payload: {}", e))?;
+
+        if payload.value.is_empty() {
+            return Ok(0);
+        }
+
+        self.handle_notifications(payload).await
+    }
+
+    /// Creates missing subscriptions and renews the ones close to expiry
+    pub async fn reconcile(&self) -> Result<(), String> {
+        let Some(notification_url) = self.notification_url() else {
+            log::debug!("[GraphSubscriptionManager] No notification URL configured, skipping");
+            return Ok(());
+        };
+
+        let account_repo = SqliteAccountRepository::new(self.pool.clone());
+        let accounts = account_repo
+            .find_by_sync_enabled()
+            .await
+            .map_err(|e| format!("Failed to load accounts: {}", e))?;
+
+        for account in accounts
+            .into_iter()
+            .filter(|a| a.account_type == AccountType::Office365)
+        {
+            if let Err(error) = self.reconcile_account(account.id, &notification_url).await {
+                log::warn!(
+                    "[GraphSubscriptionManager] Failed to reconcile subscriptions for account {}: {}",
+                    account.id,
+                    error
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn reconcile_account(
+        &self,
+        account_id: Uuid,
+        notification_url: &str,
+    ) -> Result<(), String> {
+        let provider = Office365Provider::new(account_id, Arc::clone(&self.credential_store))
+            .map_err(|e| format!("Failed to create provider: {}", e))?;
+
+        let folders = FolderSync::new(self.pool.clone(), Arc::clone(&self.credential_store))
+            .get_folders(account_id)
+            .await
+            .map_err(|e| format!("Failed to load folders: {}", e))?;
+
+        let existing = self.load_subscriptions(account_id).await?;
+        let now = Utc::now();
+        let renew_before = now + chrono::Duration::minutes(RENEWAL_WINDOW_MINS);
+        let expires_at = now + chrono::Duration::minutes(SUBSCRIPTION_LIFETIME_MINS);
+
+        for folder in folders.iter().filter(|f| Self::is_subscribable(f)) {
+            let Some(folder_id) = folder.id else {
+                continue;
+            };
+
+            match existing.iter().find(|s| s.folder_id == folder_id) {
+                Some(record) if record.expires_at > renew_before => {}
+                Some(record) if record.expires_at > now => {
+                    match provider
+                        .renew_subscription(&record.subscription_id, expires_at)
+                        .await
+                    {
+                        Ok(renewed) => {
+                            self.update_expiry(
+                                &record.subscription_id,
+                                renewed.expiration_date_time,
+                            )
+                            .await?;
+                            log::debug!(
+                                "[GraphSubscriptionManager] Renewed subscription for folder {}",
+                                folder.name
+                            );
+                        }
+                        Err(error) => {
+                            log::warn!(
+                                "[GraphSubscriptionManager] Renewal failed for folder {}, recreating: {}",
+                                folder.name,
+                                error
+                            );
+                            self.delete_record(&record.subscription_id).await?;
+                            self.create(&provider, folder, notification_url, expires_at)
+                                .await?;
+                        }
+                    }
+                }
+                Some(record) => {
+                    // Already expired on Graph's side, nothing left to renew
+                    self.delete_record(&record.subscription_id).await?;
+                    self.create(&provider, folder, notification_url, expires_at)
+                        .await?;
+                }
+                None => {
+                    self.create(&provider, folder, notification_url, expires_at)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_subscribable(folder: &SyncFolder) -> bool {
+        !folder.remote_id.is_empty()
+            && !matches!(folder.folder_type, FolderType::Trash | FolderType::Spam)
+    }
+
+    async fn create(
+        &self,
+        provider: &Office365Provider,
+        folder: &SyncFolder,
+        notification_url: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let Some(folder_id) = folder.id else {
+            return Ok(());
+        };
+
+        let client_state = Uuid::now_v7().simple().to_string();
+        let subscription = match provider
+            .create_subscription(
+                &folder.remote_id,
+                notification_url,
+                &client_state,
+                expires_at,
+            )
+            .await
+        {
+            Ok(subscription) => subscription,
+            Err(error) => {
+                log::warn!(
+                    "[GraphSubscriptionManager] Failed to subscribe to folder {}: {}",
+                    folder.name,
+                    error
+                );
+                return Ok(());
+            }
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO graph_subscriptions (
+                subscription_id, account_id, folder_id, resource, client_state, expires_at
+            ) VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(folder_id) DO UPDATE SET
+                subscription_id = excluded.subscription_id,
+                resource = excluded.resource,
+                client_state = excluded.client_state,
+                expires_at = excluded.expires_at
+            "#,
+        )
+        .bind(&subscription.id)
+        .bind(folder.account_id.to_string())
+        .bind(folder_id.to_string())
+        .bind(&subscription.resource)
+        .bind(&client_state)
+        .bind(subscription.expiration_date_time.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to store subscription: {}", e))?;
+
+        log::info!(
+            "[GraphSubscriptionManager] Subscribed to changes in folder {} until {}",
+            folder.name,
+            subscription.expiration_date_time
+        );
+
+        Ok(())
+    }
+
+    /// Handles a notification batch fetched from the relay
+    ///
+    /// Each notification is mapped to the folder its subscription was created for;
+    /// the message resource it names lives in that folder
+    ///
+    /// Returns the number of folders that were synced as a result
+    pub async fn handle_notifications(
+        &self,
+        payload: GraphNotificationPayload,
+    ) -> Result<usize, String> {
+        let mut folders_to_sync: HashSet<(Uuid, Uuid)> = HashSet::new();
+
+        for notification in payload.value {
+            let Some(record) = self.find_record(&notification.subscription_id).await? else {
+                log::debug!(
+                    "[GraphSubscriptionManager] Ignoring notification for unknown subscription {}",
+                    notification.subscription_id
+                );
+                continue;
+            };
+
+            if notification.client_state.as_deref() != Some(record.client_state.as_str()) {
+                log::warn!(
+                    "[GraphSubscriptionManager] Rejecting notification with mismatched clientState for subscription {}",
+                    record.subscription_id
+                );
+                continue;
+            }
+
+            match notification.lifecycle_event.as_deref() {
+                Some("subscriptionRemoved") => {
+                    // Recreated on the next reconcile pass; sync now to catch missed changes
+                    self.delete_record(&record.subscription_id).await?;
+                    folders_to_sync.insert((record.account_id, record.folder_id));
+                }
+                Some("reauthorizationRequired") => {
+                    self.renew_record(&record).await;
+                }
+                Some("missed") => {
+                    folders_to_sync.insert((record.account_id, record.folder_id));
+                }
+                Some(other) => {
+                    log::debug!(
+                        "[GraphSubscriptionManager] Unhandled lifecycle event '{}'",
+                        other
+                    );
+                }
+                None => {
+                    log::debug!(
+                        "[GraphSubscriptionManager] {} change to {} in folder {}",
+                        notification.change_type.as_deref().unwrap_or("unknown"),
+                        notification
+                            .resource
+                            .as_deref()
+                            .unwrap_or("unknown resource"),
+                        record.folder_id
+                    );
+                    folders_to_sync.insert((record.account_id, record.folder_id));
+                }
+            }
+        }
+
+        let mut synced = 0;
+        for (account_id, folder_id) in folders_to_sync {
+            let folders = FolderSync::new(self.pool.clone(), Arc::clone(&self.credential_store))
+                .get_folders(account_id)
+                .await
+                .map_err(|e| format!("Failed to load folders: {}", e))?;
+
+            let Some(folder) = folders.into_iter().find(|f| f.id == Some(folder_id)) else {
+                continue;
+            };
+
+            match self
+                .sync_coordinator
+                .sync_folder(account_id, &folder, false)
+                .await
+            {
+                Ok(count) => {
+                    log::info!(
+                        "[GraphSubscriptionManager] Synced {} emails in folder {} after notification",
+                        count,
+                        folder.name
+                    );
+                    synced += 1;
+                }
+                Err(error) => {
+                    log::error!(
+                        "[GraphSubscriptionManager] Failed to sync folder {}: {}",
+                        folder.name,
+                        error
+                    );
+                }
+            }
+        }
+
+        Ok(synced)
+    }
+
+    async fn renew_record(&self, record: &SubscriptionRecord) {
+        let provider =
+            match Office365Provider::new(record.account_id, Arc::clone(&self.credential_store)) {
+                Ok(provider) => provider,
+                Err(error) => {
+                    log::warn!(
+                        "[GraphSubscriptionManager] Failed to create provider for renewal: {}",
+                        error
+                    );
+                    return;
+                }
+            };
+
+        let expires_at = Utc::now() + chrono::Duration::minutes(SUBSCRIPTION_LIFETIME_MINS);
+        match provider
+            .renew_subscription(&record.subscription_id, expires_at)
+            .await
+        {
+            Ok(renewed) => {
+                if let Err(error) = self
+                    .update_expiry(&record.subscription_id, renewed.expiration_date_time)
+                    .await
+                {
+                    log::warn!("[GraphSubscriptionManager] {}", error);
+                }
+            }
+            Err(error) => {
+                log::warn!(
+                    "[GraphSubscriptionManager] Reauthorization of subscription {} failed: {}",
+                    record.subscription_id,
+                    error
+                );
+            }
+        }
+    }
+
+    async fn load_subscriptions(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Vec<SubscriptionRecord>, String> {
+        let rows = sqlx::query(
+            r#"
+            SELECT subscription_id, account_id, folder_id, client_state, expires_at
+            FROM graph_subscriptions
+            WHERE account_id = ?
+            "#,
+        )
+        .bind(account_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to load subscriptions: {}", e))?;
+
+        rows.iter().map(Self::map_row).collect()
+    }
+
+    async fn find_record(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Option<SubscriptionRecord>, String> {
+        let row = sqlx::query(
+            r#"
+            SELECT subscription_id, account_id, folder_id, client_state, expires_at
+            FROM graph_subscriptions
+            WHERE subscription_id = ?
+            "#,
+        )
+        .bind(subscription_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to load subscription: {}", e))?;
+
+        row.as_ref().map(Self::map_row).transpose()
+    }
+
+    fn map_row(row: &sqlx::sqlite::SqliteRow) -> Result<SubscriptionRecord, String> {
+        let parse_uuid = |column: &str| -> Result<Uuid, String> {
+            let raw: String = row
+                .try_get(column)
+                .map_err(|e| format!("Failed to read {}: {}", column, e))?;
+            Uuid::parse_str(&raw).map_err(|e| format!("Invalid {} '{}': {}", column, raw, e))
+        };
+
+        let expires_at_raw: String = row
+            .try_get("expires_at")
+            .map_err(|e| format!("Failed to read expires_at: {}", e))?;
+
+        Ok(SubscriptionRecord {
+            subscription_id: row
+                .try_get("subscription_id")
+                .map_err(|e| format!("Failed to read subscription_id: {}", e))?,
+            account_id: parse_uuid("account_id")?,
+            folder_id: parse_uuid("folder_id")?,
+            client_state: row
+                .try_get("client_state")
+                .map_err(|e| format!("Failed to read client_state: {}", e))?,
+            expires_at: DateTime::parse_from_rfc3339(&expires_at_raw)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        })
+    }
+
+    async fn update_expiry(
+        &self,
+        subscription_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        sqlx::query("UPDATE graph_subscriptions SET expires_at = ? WHERE subscription_id = ?")
+            .bind(expires_at.to_rfc3339())
+            .bind(subscription_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to update subscription expiry: {}", e))?;
+        Ok(())
+    }
+
+    async fn delete_record(&self, subscription_id: &str) -> Result<(), String> {
+        sqlx::query("DELETE FROM graph_subscriptions WHERE subscription_id = ?")
+            .bind(subscription_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to delete subscription: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(folder_type: FolderType, remote_id: &str) -> SyncFolder {
+        SyncFolder {
+            id: Some(Uuid::now_v7()),
+            account_id: Uuid::now_v7(),
+            name: "Folder".to_string(),
+            folder_type,
+            remote_id: remote_id.to_string(),
+            icon: None,
+            color: None,
+            parent_id: None,
+            attributes: Vec::new(),
+            unread_count: 0,
+            total_count: 0,
+            expanded: false,
+            hidden: false,
+            synced_at: None,
+            sync_interval: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_notification_payload() {
+        let payload: GraphNotificationPayload = serde_json::from_str(
+            r#"{
+                "value": [
+                    {
+                        "subscriptionId": "sub-1",
+                        "clientState": "secret",
+                        "changeType": "created",
+                        "resource": "Users/abc/Messages/xyz"
+                    },
+                    {
+                        "subscriptionId": "sub-2",
+                        "clientState": "secret",
+                        "lifecycleEvent": "reauthorizationRequired"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(payload.value.len(), 2);
+        assert_eq!(payload.value[0].change_type.as_deref(), Some("created"));
+        assert_eq!(
+            payload.value[0].resource.as_deref(),
+            Some("Users/abc/Messages/xyz")
+        );
+        assert!(payload.value[1].resource.is_none());
+        assert_eq!(
+            payload.value[1].lifecycle_event.as_deref(),
+            Some("reauthorizationRequired")
+        );
+    }
+
+    #[test]
+    fn test_is_subscribable() {
+        assert!(GraphSubscriptionManager::is_subscribable(&folder(
+            FolderType::Inbox,
+            "AAMk"
+        )));
+        assert!(!GraphSubscriptionManager::is_subscribable(&folder(
+            FolderType::Trash,
+            "AAMk"
+        )));
+        assert!(!GraphSubscriptionManager::is_subscribable(&folder(
+            FolderType::Inbox,
+            ""
+        )));
+    }
+}
@@ -0,0 +1,71 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::models::account::AccountType;
+use crate::database::models::identity::{Identity, IdentitySource};
+use crate::database::repositories::{
+    AccountRepository, IdentityRepository, SqliteAccountRepository, SqliteIdentityRepository,
+};
+use crate::sync::auth::CredentialStore;
+use crate::sync::providers::gmail::GmailProvider;
+
+/// Replaces the account's provider-managed identities with the aliases the
+/// provider currently allows, and returns all identities of the account.
+///
+/// Returns `Ok(None)` for accounts whose provider has no alias API; their
+/// identities are managed by hand.
+pub async fn refresh_identities(
+    pool: &SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    account_id: Uuid,
+) -> Result<Option<Vec<Identity>>, String> {
+    let account = SqliteAccountRepository::new(pool.clone())
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to load account: {}", e))?
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    if account.account_type != AccountType::Gmail {
+        return Ok(None);
+    }
+
+    let aliases = GmailProvider::new(account.id, credential_store)
+        .map_err(|e| e.to_string())?
+        .fetch_send_as()
+        .await
+        .map_err(|e| format!("Failed to fetch sendAs aliases: {}", e))?;
+
+    let identities: Vec<Identity> = aliases
+        .into_iter()
+        .map(|alias| Identity {
+            id: Uuid::now_v7(),
+            account_id: account.id,
+            email: alias.email.trim().to_lowercase(),
+            name: alias.name,
+            source: IdentitySource::Gmail,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .filter(|identity| !identity.email.eq_ignore_ascii_case(&account.email))
+        .collect();
+
+    let identity_repo = SqliteIdentityRepository::new(pool.clone());
+    identity_repo
+        .replace_from_source(account.id, IdentitySource::Gmail, &identities)
+        .await
+        .map_err(|e| format!("Failed to store identities: {}", e))?;
+
+    log::info!(
+        "[Identities] Account {} has {} Gmail sendAs aliases",
+        account.id,
+        identities.len()
+    );
+
+    identity_repo
+        .find_by_account(account.id)
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to load identities: {}", e))
+}
@@ -0,0 +1,502 @@
+/// Local junk filter based on Robinson-Fisher token combining (as used by SpamBayes)
+///
+/// The model is trained from the user's junk / not-junk actions and scores newly
+/// synced messages before they reach the AI analyzer.
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::database::models::account::AccountType;
+use crate::database::models::email::Email;
+use crate::database::models::pending_operation::{PendingOperation, PendingOperationType};
+use crate::database::repositories::{
+    EmailRepository, FolderRepository, SqliteEmailRepository, SqliteFolderRepository,
+    SqlitePendingOperationRepository,
+};
+
+/// Scores at or above this value are treated as junk
+pub const JUNK_THRESHOLD: f64 = 0.9;
+/// Both classes need this many trained messages before scores are trusted
+const MIN_TRAINED_PER_CLASS: i64 = 10;
+/// Strength of the prior towards 0.5 for rarely seen tokens
+const UNKNOWN_WORD_STRENGTH: f64 = 1.0;
+const UNKNOWN_WORD_PROB: f64 = 0.5;
+/// Tokens whose probability is closer to 0.5 than this carry no evidence
+const MIN_PROB_STRENGTH: f64 = 0.1;
+const MAX_DISCRIMINATORS: usize = 150;
+const MAX_BODY_CHARS: usize = 20_000;
+const MAX_TOKENS: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenCounts {
+    pub spam: i64,
+    pub ham: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JunkVerdict {
+    pub email_id: Uuid,
+    pub score: f64,
+    pub is_junk: bool,
+    /// Spam folder the message was moved into, if any
+    pub moved_to_folder_id: Option<Uuid>,
+}
+
+/// Splits a message into the features used by the classifier
+pub fn tokenize(subject: Option<&str>, from_address: &str, body: Option<&str>) -> HashSet<String> {
+    let mut tokens = HashSet::new();
+
+    let from = from_address.trim().to_lowercase();
+    if !from.is_empty() {
+        if let Some((_, domain)) = from.rsplit_once('@') {
+            tokens.insert(format!("from-domain:{}", domain));
+        }
+        tokens.insert(format!("from:{}", from));
+    }
+
+    if let Some(subject) = subject {
+        for word in words(subject) {
+            tokens.insert(format!("subject:{}", word));
+        }
+    }
+
+    if let Some(body) = body {
+        let body: String = body.chars().take(MAX_BODY_CHARS).collect();
+        for word in words(&body) {
+            if tokens.len() >= MAX_TOKENS {
+                break;
+            }
+            tokens.insert(word);
+        }
+    }
+
+    tokens
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '$' || c == '!' || c == '\''))
+        .map(|w| w.trim_matches('\'').to_lowercase())
+        .filter(|w| {
+            let len = w.chars().count();
+            (3..=24).contains(&len) && !w.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+/// Combines per-token probabilities into a junk score between 0 and 1
+///
+/// Returns 0.5 when none of the tokens carry enough evidence either way.
+pub fn score(counts: &HashMap<String, TokenCounts>, spam_messages: i64, ham_messages: i64) -> f64 {
+    if spam_messages <= 0 || ham_messages <= 0 {
+        return UNKNOWN_WORD_PROB;
+    }
+
+    let mut clues: Vec<f64> = counts
+        .values()
+        .filter_map(|c| token_probability(*c, spam_messages, ham_messages))
+        .filter(|p| (p - 0.5).abs() >= MIN_PROB_STRENGTH)
+        .collect();
+
+    if clues.is_empty() {
+        return UNKNOWN_WORD_PROB;
+    }
+
+    clues.sort_by(|a, b| {
+        (b - 0.5)
+            .abs()
+            .partial_cmp(&(a - 0.5).abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    clues.truncate(MAX_DISCRIMINATORS);
+
+    let n = clues.len();
+    let ham_evidence: f64 = clues.iter().map(|p| p.ln()).sum();
+    let spam_evidence: f64 = clues.iter().map(|p| (1.0 - p).ln()).sum();
+
+    let s = 1.0 - chi2_q(-2.0 * spam_evidence, 2 * n);
+    let h = 1.0 - chi2_q(-2.0 * ham_evidence, 2 * n);
+
+    ((s - h + 1.0) / 2.0).clamp(0.0, 1.0)
+}
+
+fn token_probability(counts: TokenCounts, spam_messages: i64, ham_messages: i64) -> Option<f64> {
+    let total = counts.spam + counts.ham;
+    if total <= 0 {
+        return None;
+    }
+
+    let spam_ratio = counts.spam as f64 / spam_messages as f64;
+    let ham_ratio = counts.ham as f64 / ham_messages as f64;
+    let raw = spam_ratio / (spam_ratio + ham_ratio);
+
+    let n = total as f64;
+    Some((UNKNOWN_WORD_STRENGTH * UNKNOWN_WORD_PROB + n * raw) / (UNKNOWN_WORD_STRENGTH + n))
+}
+
+/// Inverse chi-squared survival function for even degrees of freedom
+fn chi2_q(x2: f64, degrees: usize) -> f64 {
+    let m = x2 / 2.0;
+    let mut term = (-m).exp();
+    let mut sum = term;
+
+    for i in 1..degrees / 2 {
+        term *= m / i as f64;
+        sum += term;
+    }
+
+    sum.min(1.0)
+}
+
+fn email_tokens(email: &Email) -> HashSet<String> {
+    tokenize(
+        email.subject.as_deref(),
+        &email.from.address,
+        email.body_plain.as_deref(),
+    )
+}
+
+pub struct JunkFilter {
+    pool: SqlitePool,
+}
+
+impl JunkFilter {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Learns from a user verdict, reversing an earlier opposite verdict for the same email
+    pub async fn train(&self, email: &Email, is_spam: bool) -> Result<(), String> {
+        let previous: Option<bool> =
+            sqlx::query_scalar("SELECT is_spam FROM junk_training WHERE email_id = ?")
+                .bind(email.id.to_string())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| format!("Failed to read training state: {}", e))?;
+
+        if previous == Some(is_spam) {
+            return Ok(());
+        }
+
+        let tokens = email_tokens(email);
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        if let Some(was_spam) = previous {
+            let column = if was_spam { "spam_count" } else { "ham_count" };
+            for token in &tokens {
+                sqlx::query(&format!(
+                    "UPDATE junk_tokens SET {column} = MAX({column} - 1, 0) WHERE token = ?"
+                ))
+                .bind(token)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to untrain token: {}", e))?;
+            }
+        }
+
+        let (spam, ham) = if is_spam { (1, 0) } else { (0, 1) };
+        for token in &tokens {
+            sqlx::query(
+                r#"
+                INSERT INTO junk_tokens (token, spam_count, ham_count) VALUES (?, ?, ?)
+                ON CONFLICT(token) DO UPDATE SET
+                    spam_count = spam_count + excluded.spam_count,
+                    ham_count = ham_count + excluded.ham_count
+                "#,
+            )
+            .bind(token)
+            .bind(spam)
+            .bind(ham)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to train token: {}", e))?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO junk_training (email_id, is_spam) VALUES (?, ?)
+            ON CONFLICT(email_id) DO UPDATE SET
+                is_spam = excluded.is_spam,
+                trained_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(email.id.to_string())
+        .bind(is_spam)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to record training: {}", e))?;
+
+        // A user verdict overrides whatever the classifier decided
+        sqlx::query("UPDATE junk_classifications SET is_junk = ? WHERE email_id = ?")
+            .bind(is_spam)
+            .bind(email.id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update classification: {}", e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit training: {}", e))?;
+
+        log::debug!(
+            "[JunkFilter] Trained email {} as {} ({} tokens)",
+            email.id,
+            if is_spam { "junk" } else { "not junk" },
+            tokens.len()
+        );
+
+        Ok(())
+    }
+
+    /// Scores a message, or returns None while the model is still undertrained
+    pub async fn classify(&self, email: &Email) -> Result<Option<f64>, String> {
+        let (spam_messages, ham_messages) = self.trained_totals().await?;
+        if spam_messages < MIN_TRAINED_PER_CLASS || ham_messages < MIN_TRAINED_PER_CLASS {
+            return Ok(None);
+        }
+
+        let tokens: Vec<String> = email_tokens(email).into_iter().collect();
+        let mut counts = HashMap::with_capacity(tokens.len());
+
+        // Stay well below SQLite's bound parameter limit
+        for chunk in tokens.chunks(500) {
+            let placeholders = chunk.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+            let sql = format!(
+                "SELECT token, spam_count, ham_count FROM junk_tokens WHERE token IN ({})",
+                placeholders
+            );
+            let mut query = sqlx::query(&sql);
+            for token in chunk {
+                query = query.bind(token);
+            }
+
+            let rows = query
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| format!("Failed to load token counts: {}", e))?;
+
+            for row in rows {
+                let token: String = row.try_get("token").map_err(|e| e.to_string())?;
+                counts.insert(
+                    token,
+                    TokenCounts {
+                        spam: row.try_get("spam_count").map_err(|e| e.to_string())?,
+                        ham: row.try_get("ham_count").map_err(|e| e.to_string())?,
+                    },
+                );
+            }
+        }
+
+        Ok(Some(score(&counts, spam_messages, ham_messages)))
+    }
+
+    async fn trained_totals(&self) -> Result<(i64, i64), String> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(SUM(CASE WHEN is_spam THEN 1 ELSE 0 END), 0) AS spam,
+                COALESCE(SUM(CASE WHEN is_spam THEN 0 ELSE 1 END), 0) AS ham
+            FROM junk_training
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to count trained messages: {}", e))?;
+
+        Ok((
+            row.try_get("spam").map_err(|e| e.to_string())?,
+            row.try_get("ham").map_err(|e| e.to_string())?,
+        ))
+    }
+
+    /// Classifies a newly synced inbox message
+    ///
+    /// Plain IMAP and Apple accounts get likely junk moved to their spam folder,
+    /// since their servers usually filter poorly. For other providers the score
+    /// is only recorded so the AI analyzer can skip the message.
+    pub async fn process_synced_email(&self, email: &Email) -> Result<Option<JunkVerdict>, String> {
+        let context = sqlx::query(
+            r#"
+            SELECT f.folder_type, a.account_type,
+                   jc.has_body AS classified_with_body,
+                   jt.email_id AS trained_email_id
+            FROM folders f
+            INNER JOIN accounts a ON a.id = f.account_id
+            LEFT JOIN junk_classifications jc ON jc.email_id = ?
+            LEFT JOIN junk_training jt ON jt.email_id = ?
+            WHERE f.id = ?
+            "#,
+        )
+        .bind(email.id.to_string())
+        .bind(email.id.to_string())
+        .bind(email.folder_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to load junk filter context: {}", e))?;
+
+        let Some(context) = context else {
+            return Ok(None);
+        };
+
+        let folder_type: String = context.try_get("folder_type").map_err(|e| e.to_string())?;
+        let account_type: AccountType =
+            context.try_get("account_type").map_err(|e| e.to_string())?;
+        let classified_with_body: Option<bool> = context
+            .try_get("classified_with_body")
+            .map_err(|e| e.to_string())?;
+        let trained: Option<String> = context
+            .try_get("trained_email_id")
+            .map_err(|e| e.to_string())?;
+
+        let has_body = email.body_plain.is_some();
+
+        if folder_type != "inbox"
+            || email.is_draft
+            || trained.is_some()
+            || classified_with_body == Some(true)
+            || (classified_with_body == Some(false) && !has_body)
+        {
+            return Ok(None);
+        }
+
+        let Some(score) = self.classify(email).await? else {
+            return Ok(None);
+        };
+        let is_junk = score >= JUNK_THRESHOLD;
+
+        sqlx::query(
+            r#"
+            INSERT INTO junk_classifications (email_id, score, is_junk, has_body)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(email_id) DO UPDATE SET
+                score = excluded.score,
+                is_junk = excluded.is_junk,
+                has_body = excluded.has_body,
+                classified_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(email.id.to_string())
+        .bind(score)
+        .bind(is_junk)
+        .bind(has_body)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to store classification: {}", e))?;
+
+        let moved_to_folder_id =
+            if is_junk && matches!(account_type, AccountType::Imap | AccountType::Apple) {
+                self.move_to_spam(email).await?
+            } else {
+                None
+            };
+
+        if is_junk {
+            log::info!(
+                "[JunkFilter] Email {} classified as junk (score {:.3}){}",
+                email.id,
+                score,
+                if moved_to_folder_id.is_some() {
+                    ", moved to spam"
+                } else {
+                    ""
+                }
+            );
+        }
+
+        Ok(Some(JunkVerdict {
+            email_id: email.id,
+            score,
+            is_junk,
+            moved_to_folder_id,
+        }))
+    }
+
+    pub(crate) async fn move_to_spam(&self, email: &Email) -> Result<Option<Uuid>, String> {
+        let folder_repo = SqliteFolderRepository::new(self.pool.clone());
+        let Some(spam_folder) = folder_repo
+            .find_by_type(email.account_id, "spam")
+            .await
+            .map_err(|e| format!("Failed to find spam folder: {}", e))?
+        else {
+            return Ok(None);
+        };
+
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+        let Some((from_folder_id, remote_id)) = email_repo
+            .find_for_remote_operation(email.id)
+            .await
+            .map_err(|e| format!("Failed to load email: {}", e))?
+        else {
+            return Ok(None);
+        };
+
+        email_repo
+            .update_folder(email.id, spam_folder.id)
+            .await
+            .map_err(|e| format!("Failed to move email to spam: {}", e))?;
+
+        let op = PendingOperation::new(
+            email.account_id,
+            Some(email.id),
+            Some(from_folder_id),
+            PendingOperationType::Move,
+            serde_json::json!({
+                "remote_id": remote_id,
+                "folder_id": from_folder_id.to_string(),
+                "to_folder_id": spam_folder.id.to_string(),
+            }),
+        );
+        SqlitePendingOperationRepository::new(self.pool.clone())
+            .create(&op)
+            .await
+            .map_err(|e| format!("Failed to queue move to spam: {}", e))?;
+
+        Ok(Some(spam_folder.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize(
+            Some("You WON a prize!"),
+            "Winner@Lottery.example",
+            Some("Claim your $1000 prize now. Call 12345"),
+        );
+
+        assert!(tokens.contains("from:winner@lottery.example"));
+        assert!(tokens.contains("from-domain:lottery.example"));
+        assert!(tokens.contains("subject:won"));
+        assert!(tokens.contains("subject:prize!"));
+        assert!(tokens.contains("$1000"));
+        assert!(tokens.contains("claim"));
+        assert!(!tokens.contains("12345"));
+        assert!(!tokens.contains("a"));
+    }
+
+    #[test]
+    fn test_score_separates_classes() {
+        let mut spammy = HashMap::new();
+        spammy.insert("viagra".to_string(), TokenCounts { spam: 40, ham: 0 });
+        spammy.insert("$1000".to_string(), TokenCounts { spam: 30, ham: 1 });
+        spammy.insert("meeting".to_string(), TokenCounts { spam: 1, ham: 2 });
+        assert!(score(&spammy, 50, 50) >= JUNK_THRESHOLD);
+
+        let mut hammy = HashMap::new();
+        hammy.insert("meeting".to_string(), TokenCounts { spam: 0, ham: 35 });
+        hammy.insert("agenda".to_string(), TokenCounts { spam: 1, ham: 25 });
+        assert!(score(&hammy, 50, 50) < 0.2);
+    }
+
+    #[test]
+    fn test_score_without_evidence_is_neutral() {
+        assert_eq!(score(&HashMap::new(), 50, 50), 0.5);
+        assert_eq!(score(&HashMap::new(), 0, 50), 0.5);
+    }
+}
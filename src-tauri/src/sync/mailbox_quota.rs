@@ -0,0 +1,98 @@
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+use crate::database::models::account::{Account, AccountQuota, AccountType};
+use crate::database::repositories::{AccountRepository, SqliteAccountRepository};
+use crate::sync::auth::CredentialStore;
+use crate::sync::provider::ProviderFactory;
+use crate::sync::providers::{
+    gmail::GmailProvider, imap::ImapProvider, office365::Office365Provider,
+};
+
+/// Sync refreshes a quota at most this often; usage changes slowly and IMAP
+/// needs an extra connection to read it
+const REFRESH_INTERVAL_HOURS: i64 = 6;
+
+/// Fetches the mailbox storage usage and limits from the provider and stores
+/// them for the account.
+///
+/// Gmail only reports the number of messages and Microsoft 365 only the used
+/// storage; IMAP servers report what their QUOTA extension covers.
+pub async fn refresh_quota(
+    pool: &SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    account: &Account,
+) -> Result<AccountQuota, String> {
+    let quota = match account.account_type {
+        AccountType::Gmail => {
+            GmailProvider::new(account.id, credential_store)
+                .map_err(|e| e.to_string())?
+                .fetch_quota()
+                .await
+        }
+        AccountType::Office365 => {
+            Office365Provider::new(account.id, credential_store)
+                .map_err(|e| e.to_string())?
+                .fetch_quota()
+                .await
+        }
+        AccountType::Apple | AccountType::Imap => {
+            let provider =
+                ProviderFactory::create(account, credential_store).map_err(|e| e.to_string())?;
+            let Some(imap_provider) = provider.as_any().downcast_ref::<ImapProvider>() else {
+                return Err(format!("Account {} has no IMAP provider", account.id));
+            };
+            imap_provider.fetch_quota().await
+        }
+    }
+    .map_err(|e| format!("Failed to fetch quota: {}", e))?;
+
+    let quota = AccountQuota {
+        account_id: account.id,
+        storage_used: quota.storage_used,
+        storage_limit: quota.storage_limit,
+        message_count: quota.message_count,
+        message_limit: quota.message_limit,
+        updated_at: Utc::now(),
+    };
+
+    SqliteAccountRepository::new(pool.clone())
+        .upsert_quota(&quota)
+        .await
+        .map_err(|e| format!("Failed to store quota: {}", e))?;
+
+    log::debug!(
+        "[MailboxQuota] Account {}: {:?} of {:?} bytes, {:?} of {:?} messages",
+        account.id,
+        quota.storage_used,
+        quota.storage_limit,
+        quota.message_count,
+        quota.message_limit
+    );
+
+    Ok(quota)
+}
+
+/// Refreshes the quota unless it was fetched within the refresh interval.
+/// Returns the new quota, or `None` if the stored one is still fresh.
+pub async fn refresh_quota_if_stale(
+    pool: &SqlitePool,
+    credential_store: Arc<CredentialStore>,
+    account: &Account,
+) -> Result<Option<AccountQuota>, String> {
+    let stored = SqliteAccountRepository::new(pool.clone())
+        .find_quota(account.id)
+        .await
+        .map_err(|e| format!("Failed to load quota: {}", e))?;
+
+    if stored.is_some_and(|quota| {
+        Utc::now() - quota.updated_at < Duration::hours(REFRESH_INTERVAL_HOURS)
+    }) {
+        return Ok(None);
+    }
+
+    refresh_quota(pool, credential_store, account)
+        .await
+        .map(Some)
+}
@@ -1,14 +1,31 @@
+pub mod account_health;
+pub mod account_profile;
+pub mod attachment_download;
 pub mod attachment_handler;
+pub mod attachment_policy;
 pub mod auth;
+pub mod autoconfig;
+pub mod backfill;
 pub mod background_ai_analyzer;
+pub mod background_archive_worker;
+pub mod background_attachment_indexer;
 pub mod background_avatar_fetcher;
 pub mod background_body_fetcher;
 pub mod background_cleanup;
+pub mod background_contact_date_notifier;
 pub mod background_reminder_notifier;
+pub mod background_snooze_worker;
+pub mod background_style_learner;
 pub mod background_sync;
+pub mod backoff;
+pub mod bandwidth;
+pub mod blocked_senders;
+pub mod calendar_invites;
 pub mod cid_utils;
 pub mod contact_extractor;
 pub mod conversion_mode;
+pub mod delivery_status;
+pub mod draft_sync;
 pub mod email_body_splitter;
 pub mod email_categorizer;
 pub mod email_sync;
@@ -16,32 +33,57 @@ pub mod encrypted_store;
 pub mod error;
 pub mod events;
 pub mod folder_sync;
+pub mod gmail_labels;
+pub mod graph_subscriptions;
+pub mod identities;
+pub mod junk_filter;
+pub mod mailbox_quota;
+pub mod muted_conversations;
+pub mod network_usage;
 pub mod oauth_state;
 pub mod operation_queue;
+pub mod outbox_worker;
+pub mod phishing;
+pub mod priority;
 pub mod provider;
 pub mod providers;
+pub mod rate_limit;
 pub mod reconciler;
+pub mod rules_engine;
+pub mod scheduled_send_worker;
+pub mod smime_signatures;
 pub mod snippet_utils;
 pub mod storage;
 pub mod sync_coordinator;
+pub mod sync_limits;
 pub mod sync_manager;
 pub mod sync_queue;
+pub mod threading;
+pub mod tls_trust;
 pub mod types;
 pub use background_ai_analyzer::BackgroundAiAnalyzer;
+pub use background_archive_worker::BackgroundArchiveWorker;
+pub use background_attachment_indexer::BackgroundAttachmentIndexer;
 pub use background_avatar_fetcher::BackgroundAvatarFetcher;
 pub use background_body_fetcher::BackgroundBodyFetcher;
 pub use background_cleanup::BackgroundCleanup;
+pub use background_contact_date_notifier::BackgroundContactDateNotifier;
 pub use background_reminder_notifier::BackgroundReminderNotifier;
+pub use background_snooze_worker::BackgroundSnoozeWorker;
+pub use background_style_learner::BackgroundStyleLearner;
 pub use background_sync::BackgroundSyncManager;
 pub use contact_extractor::ContactExtractor;
 pub use email_body_splitter::EmailBodySplitter;
 pub use email_categorizer::EmailCategorizer;
 pub use error::SyncError;
 pub use events::*;
+pub use graph_subscriptions::GraphSubscriptionManager;
 pub use oauth_state::OAuthStateManager;
 pub use operation_queue::OperationQueue;
+pub use outbox_worker::OutboxWorker;
 pub use provider::{EmailProvider, ProviderFactory};
+pub use scheduled_send_worker::ScheduledSendWorker;
 pub use sync_coordinator::SyncCoordinator;
 pub use sync_manager::SyncManager;
-pub use sync_queue::{SyncPriority, SyncQueue, SyncQueueItem, SyncQueueWorker};
+pub use sync_queue::{SyncOutcome, SyncPriority, SyncQueue, SyncQueueItem, SyncQueueWorker};
 pub use types::*;
@@ -0,0 +1,186 @@
+//! Muted conversations. New messages of a muted conversation skip the inbox
+//! and are marked read, so they neither notify nor add to the badge.
+//!
+//! Gmail keeps the mute as the `MUTED` system label, which is synced both
+//! ways: muting queues the label for the conversation's messages, and a
+//! message that arrives with it mutes its conversation.
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::database::models::account::AccountType;
+use crate::database::models::email::Email;
+use crate::database::models::folder::FolderType;
+use crate::database::models::pending_operation::{PendingOperation, PendingOperationType};
+use crate::database::repositories::{
+    AccountRepository, ConversationRepository, EmailRepository, FolderRepository,
+    SqliteAccountRepository, SqliteConversationRepository, SqliteEmailRepository,
+    SqliteFolderRepository, SqlitePendingOperationRepository,
+};
+use crate::sync::gmail_labels;
+
+/// Gmail system label of muted threads
+pub const GMAIL_MUTED_LABEL: &str = "MUTED";
+
+fn conversation_id(email: &Email) -> Option<Uuid> {
+    email
+        .conversation_id
+        .as_deref()
+        .and_then(|id| Uuid::parse_str(id).ok())
+}
+
+/// Whether the email belongs to a muted conversation
+pub async fn is_muted(pool: &SqlitePool, email: &Email) -> Result<bool, String> {
+    let Some(conversation_id) = conversation_id(email) else {
+        return Ok(false);
+    };
+
+    let conversation = SqliteConversationRepository::new(pool.clone())
+        .find_by_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+
+    Ok(conversation.is_some_and(|conversation| conversation.muted_at.is_some()))
+}
+
+/// Applies the mute of its conversation to a newly synced message, archiving
+/// it and marking it read. `remote_labels` are the message's Gmail labels.
+///
+/// Returns whether the message was muted.
+pub async fn process_synced_email(
+    pool: &SqlitePool,
+    email: &mut Email,
+    remote_labels: Option<&[String]>,
+) -> Result<bool, String> {
+    let Some(conversation_id) = conversation_id(email) else {
+        return Ok(false);
+    };
+
+    let conversation_repo = SqliteConversationRepository::new(pool.clone());
+    let Some(conversation) = conversation_repo
+        .find_by_id(conversation_id)
+        .await
+        .map_err(|e| format!("Failed to load conversation: {}", e))?
+    else {
+        return Ok(false);
+    };
+
+    if conversation.muted_at.is_none() {
+        let muted_in_gmail =
+            remote_labels.is_some_and(|labels| labels.iter().any(|l| l == GMAIL_MUTED_LABEL));
+        if !muted_in_gmail {
+            return Ok(false);
+        }
+        conversation_repo
+            .set_muted(conversation.id, Some(Utc::now()))
+            .await
+            .map_err(|e| format!("Failed to mute conversation: {}", e))?;
+    }
+
+    if email.is_draft || email.is_deleted {
+        return Ok(false);
+    }
+
+    let folder_repo = SqliteFolderRepository::new(pool.clone());
+    let folder = folder_repo
+        .find_by_id(email.folder_id)
+        .await
+        .map_err(|e| format!("Failed to load folder: {}", e))?;
+    if !folder.is_some_and(|f| f.folder_type == FolderType::Inbox) {
+        return Ok(false);
+    }
+
+    // Marked read while still in the inbox, where the provider knows it
+    let email_repo = SqliteEmailRepository::new(pool.clone());
+    if !email.is_read {
+        email_repo
+            .update_read_status(email.id, true)
+            .await
+            .map_err(|e| format!("Failed to mark email as read: {}", e))?;
+        queue_operation(pool, email, PendingOperationType::MarkRead, None).await?;
+        email.is_read = true;
+    }
+
+    let archive = folder_repo
+        .find_by_type(email.account_id, FolderType::Archive.as_str())
+        .await
+        .map_err(|e| format!("Failed to find archive folder: {}", e))?;
+    if let Some(archive) = archive {
+        email_repo
+            .update_folder(email.id, archive.id)
+            .await
+            .map_err(|e| format!("Failed to archive email: {}", e))?;
+        queue_operation(pool, email, PendingOperationType::Move, Some(archive.id)).await?;
+        email.folder_id = archive.id;
+    }
+
+    log::debug!(
+        "[MutedConversations] Muted email {} of conversation {}",
+        email.id,
+        conversation.id
+    );
+
+    Ok(true)
+}
+
+/// Mirrors a mute change to Gmail for the messages of the conversation.
+/// Other providers have no mute, so nothing is queued for them.
+pub async fn queue_mute_change(
+    pool: &SqlitePool,
+    emails: &[Email],
+    muted: bool,
+) -> Result<(), String> {
+    let Some(account_id) = emails.first().map(|email| email.account_id) else {
+        return Ok(());
+    };
+
+    let account = SqliteAccountRepository::new(pool.clone())
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to load account: {}", e))?;
+    if !account.is_some_and(|account| account.account_type == AccountType::Gmail) {
+        return Ok(());
+    }
+
+    for email in emails.iter().filter(|email| !email.is_draft) {
+        gmail_labels::queue_remote_label_change(pool, email, GMAIL_MUTED_LABEL, muted).await?;
+    }
+
+    Ok(())
+}
+
+/// Queues the provider side of a change that was already applied locally.
+/// Messages without a remote copy yet are left to the next sync.
+async fn queue_operation(
+    pool: &SqlitePool,
+    email: &Email,
+    op_type: PendingOperationType,
+    to_folder_id: Option<Uuid>,
+) -> Result<(), String> {
+    let Some(remote_id) = email.remote_id.clone() else {
+        return Ok(());
+    };
+
+    let mut params = serde_json::json!({
+        "remote_id": remote_id,
+        "folder_id": email.folder_id.to_string(),
+    });
+    if let Some(to_folder_id) = to_folder_id {
+        params["to_folder_id"] = serde_json::Value::String(to_folder_id.to_string());
+    }
+
+    let op = PendingOperation::new(
+        email.account_id,
+        Some(email.id),
+        Some(email.folder_id),
+        op_type,
+        params,
+    );
+    SqlitePendingOperationRepository::new(pool.clone())
+        .create(&op)
+        .await
+        .map_err(|e| format!("Failed to queue operation: {}", e))?;
+
+    Ok(())
+}
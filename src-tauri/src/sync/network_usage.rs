@@ -0,0 +1,508 @@
+/// Per-account network and API usage accounting
+///
+/// Providers record every request they make; counters are accumulated in memory
+/// and flushed into the `network_usage` table after each sync pass.
+use chrono::{NaiveDate, Utc};
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use once_cell::sync::Lazy;
+use reqwest::ResponseBuilderExt;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Fraction of a provider quota at which a warning is raised
+const QUOTA_WARNING_RATIO: f64 = 0.8;
+
+/// Gmail API cost per method in quota units
+/// https://developers.google.com/gmail/api/reference/quota
+pub mod gmail_units {
+    pub const GET_PROFILE: u64 = 1;
+    pub const HISTORY_LIST: u64 = 2;
+    pub const LABELS_LIST: u64 = 1;
+    pub const LABELS_UPDATE: u64 = 5;
+    pub const MESSAGES_LIST: u64 = 5;
+    pub const MESSAGES_GET: u64 = 5;
+    pub const MESSAGES_MODIFY: u64 = 5;
+    pub const MESSAGES_TRASH: u64 = 5;
+    pub const MESSAGES_DELETE: u64 = 10;
+    pub const ATTACHMENTS_GET: u64 = 5;
+    pub const DRAFTS_LIST: u64 = 5;
+    pub const DRAFTS_CREATE: u64 = 10;
+    pub const DRAFTS_UPDATE: u64 = 15;
+    pub const SEND_AS_LIST: u64 = 1;
+}
+
+/// Known rate limits enforced by providers on a single mailbox
+struct ProviderQuota {
+    provider: &'static str,
+    limit: u64,
+    window: Duration,
+    unit: &'static str,
+}
+
+const PROVIDER_QUOTAS: &[ProviderQuota] = &[
+    ProviderQuota {
+        provider: "gmail",
+        limit: 15_000,
+        window: Duration::from_secs(60),
+        unit: "quota units per minute",
+    },
+    ProviderQuota {
+        provider: "office365",
+        limit: 10_000,
+        window: Duration::from_secs(600),
+        unit: "requests per 10 minutes",
+    },
+];
+
+fn quota_for(provider: &str) -> Option<&'static ProviderQuota> {
+    PROVIDER_QUOTAS.iter().find(|q| q.provider == provider)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct UsageCounters {
+    bytes_sent: u64,
+    bytes_received: u64,
+    api_calls: u64,
+    quota_units: u64,
+    /// Highest usage observed within a single provider quota window
+    peak_window_units: u64,
+}
+
+impl UsageCounters {
+    fn add(&mut self, other: &UsageCounters) {
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.api_calls += other.api_calls;
+        self.quota_units += other.quota_units;
+        self.peak_window_units = self.peak_window_units.max(other.peak_window_units);
+    }
+}
+
+struct RateWindow {
+    started_at: Instant,
+    units: u64,
+    warned: bool,
+}
+
+/// Account, provider and day counters are kept for
+type UsageKey = (Uuid, &'static str, NaiveDate);
+
+#[derive(Default)]
+struct UsageTracker {
+    pending: HashMap<UsageKey, UsageCounters>,
+    windows: HashMap<(Uuid, &'static str), RateWindow>,
+}
+
+static TRACKER: Lazy<Mutex<UsageTracker>> = Lazy::new(|| Mutex::new(UsageTracker::default()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkUsageEntry {
+    pub account_id: Uuid,
+    pub provider: String,
+    pub day: NaiveDate,
+    pub bytes_sent: i64,
+    pub bytes_received: i64,
+    pub api_calls: i64,
+    pub quota_units: i64,
+    pub peak_window_units: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaWarning {
+    pub account_id: Uuid,
+    pub provider: String,
+    pub day: NaiveDate,
+    pub usage: i64,
+    pub limit: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkUsageReport {
+    pub entries: Vec<NetworkUsageEntry>,
+    pub warnings: Vec<QuotaWarning>,
+}
+
+/// Records a single provider request
+pub fn record(
+    account_id: Uuid,
+    provider: &'static str,
+    bytes_sent: u64,
+    bytes_received: u64,
+    quota_units: u64,
+) {
+    let Ok(mut tracker) = TRACKER.lock() else {
+        return;
+    };
+
+    let window_units = match quota_for(provider) {
+        Some(quota) => {
+            let now = Instant::now();
+            let window = tracker
+                .windows
+                .entry((account_id, provider))
+                .or_insert(RateWindow {
+                    started_at: now,
+                    units: 0,
+                    warned: false,
+                });
+
+            if now.duration_since(window.started_at) >= quota.window {
+                window.started_at = now;
+                window.units = 0;
+                window.warned = false;
+            }
+
+            window.units += quota_units;
+
+            if !window.warned && is_near_limit(window.units, quota.limit) {
+                window.warned = true;
+                log::warn!(
+                    "[NetworkUsage] Account {} is approaching the {} limit of {} {} ({} used)",
+                    account_id,
+                    provider,
+                    quota.limit,
+                    quota.unit,
+                    window.units
+                );
+            }
+
+            window.units
+        }
+        None => 0,
+    };
+
+    let counters = tracker
+        .pending
+        .entry((account_id, provider, Utc::now().date_naive()))
+        .or_default();
+    counters.bytes_sent += bytes_sent;
+    counters.bytes_received += bytes_received;
+    counters.api_calls += 1;
+    counters.quota_units += quota_units;
+    counters.peak_window_units = counters.peak_window_units.max(window_units);
+}
+
+/// Adds bytes read from a response body to the day's counters
+fn record_received(account_id: Uuid, provider: &'static str, bytes: u64) {
+    let Ok(mut tracker) = TRACKER.lock() else {
+        return;
+    };
+
+    tracker
+        .pending
+        .entry((account_id, provider, Utc::now().date_naive()))
+        .or_default()
+        .bytes_received += bytes;
+}
+
+/// Response body adding the bytes read from it to the account's usage
+struct CountedBody {
+    inner: reqwest::Body,
+    account_id: Uuid,
+    provider: &'static str,
+}
+
+impl HttpBody for CountedBody {
+    type Data = <reqwest::Body as HttpBody>::Data;
+    type Error = <reqwest::Body as HttpBody>::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &poll {
+            if let Some(data) = frame.data_ref() {
+                record_received(self.account_id, self.provider, data.len() as u64);
+            }
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Records an HTTP request and returns its response with a body that counts
+/// the bytes read from it, so chunked and compressed bodies are accounted for
+/// by what is actually received
+pub fn record_response(
+    account_id: Uuid,
+    provider: &'static str,
+    response: reqwest::Response,
+    quota_units: u64,
+) -> reqwest::Response {
+    record(account_id, provider, 0, 0, quota_units);
+
+    let url = response.url().clone();
+    let (parts, body) = http::Response::<reqwest::Body>::from(response).into_parts();
+
+    let mut builder = http::Response::builder()
+        .status(parts.status)
+        .version(parts.version);
+    if let Some(headers) = builder.headers_mut() {
+        *headers = parts.headers;
+    }
+    if let Some(extensions) = builder.extensions_mut() {
+        *extensions = parts.extensions;
+    }
+
+    let body = reqwest::Body::wrap(CountedBody {
+        inner: body,
+        account_id,
+        provider,
+    });
+    builder
+        .url(url)
+        .body(body)
+        .map(reqwest::Response::from)
+        .expect("parts of a received response are valid")
+}
+
+fn is_near_limit(usage: u64, limit: u64) -> bool {
+    usage as f64 >= limit as f64 * QUOTA_WARNING_RATIO
+}
+
+/// Writes accumulated counters to the database. Counters that could not be
+/// written are kept for the next flush.
+pub async fn flush(pool: &SqlitePool) -> Result<(), String> {
+    let pending = match TRACKER.lock() {
+        Ok(mut tracker) => std::mem::take(&mut tracker.pending),
+        Err(_) => return Err("Network usage tracker lock poisoned".to_string()),
+    };
+
+    let mut pending = pending.into_iter();
+    while let Some((key, counters)) = pending.next() {
+        if let Err(e) = store(pool, key, &counters).await {
+            restore(std::iter::once((key, counters)).chain(pending));
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds counters taken for a flush back to the pending ones
+fn restore(counters: impl Iterator<Item = (UsageKey, UsageCounters)>) {
+    let Ok(mut tracker) = TRACKER.lock() else {
+        return;
+    };
+
+    for (key, counters) in counters {
+        tracker.pending.entry(key).or_default().add(&counters);
+    }
+}
+
+async fn store(
+    pool: &SqlitePool,
+    (account_id, provider, day): UsageKey,
+    counters: &UsageCounters,
+) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        INSERT INTO network_usage (
+            account_id, provider, day, bytes_sent, bytes_received,
+            api_calls, quota_units, peak_window_units
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(account_id, provider, day) DO UPDATE SET
+            bytes_sent = bytes_sent + excluded.bytes_sent,
+            bytes_received = bytes_received + excluded.bytes_received,
+            api_calls = api_calls + excluded.api_calls,
+            quota_units = quota_units + excluded.quota_units,
+            peak_window_units = MAX(peak_window_units, excluded.peak_window_units)
+        "#,
+    )
+    .bind(account_id.to_string())
+    .bind(provider)
+    .bind(day.to_string())
+    .bind(counters.bytes_sent as i64)
+    .bind(counters.bytes_received as i64)
+    .bind(counters.api_calls as i64)
+    .bind(counters.quota_units as i64)
+    .bind(counters.peak_window_units as i64)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to store network usage: {}", e))?;
+
+    Ok(())
+}
+
+/// Loads usage for the last `days` days, optionally limited to one account
+pub async fn load_report(
+    pool: &SqlitePool,
+    account_id: Option<Uuid>,
+    days: u32,
+) -> Result<NetworkUsageReport, String> {
+    flush(pool).await?;
+
+    let since = Utc::now().date_naive() - chrono::Duration::days(days.saturating_sub(1) as i64);
+
+    let rows = sqlx::query(
+        r#"
+        SELECT account_id, provider, day, bytes_sent, bytes_received,
+               api_calls, quota_units, peak_window_units
+        FROM network_usage
+        WHERE day >= ? AND (? IS NULL OR account_id = ?)
+        ORDER BY day DESC, account_id, provider
+        "#,
+    )
+    .bind(since.to_string())
+    .bind(account_id.map(|id| id.to_string()))
+    .bind(account_id.map(|id| id.to_string()))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load network usage: {}", e))?;
+
+    let mut entries = Vec::with_capacity(rows.len());
+    for row in rows {
+        let account_raw: String = row.try_get("account_id").map_err(|e| e.to_string())?;
+        let day_raw: String = row.try_get("day").map_err(|e| e.to_string())?;
+
+        entries.push(NetworkUsageEntry {
+            account_id: Uuid::parse_str(&account_raw).map_err(|e| e.to_string())?,
+            provider: row.try_get("provider").map_err(|e| e.to_string())?,
+            day: NaiveDate::parse_from_str(&day_raw, "%Y-%m-%d").map_err(|e| e.to_string())?,
+            bytes_sent: row.try_get("bytes_sent").map_err(|e| e.to_string())?,
+            bytes_received: row.try_get("bytes_received").map_err(|e| e.to_string())?,
+            api_calls: row.try_get("api_calls").map_err(|e| e.to_string())?,
+            quota_units: row.try_get("quota_units").map_err(|e| e.to_string())?,
+            peak_window_units: row
+                .try_get("peak_window_units")
+                .map_err(|e| e.to_string())?,
+        });
+    }
+
+    let warnings = entries.iter().filter_map(quota_warning).collect();
+
+    Ok(NetworkUsageReport { entries, warnings })
+}
+
+fn quota_warning(entry: &NetworkUsageEntry) -> Option<QuotaWarning> {
+    let quota = quota_for(&entry.provider)?;
+    let usage = entry.peak_window_units.max(0) as u64;
+
+    if !is_near_limit(usage, quota.limit) {
+        return None;
+    }
+
+    Some(QuotaWarning {
+        account_id: entry.account_id,
+        provider: entry.provider.clone(),
+        day: entry.day,
+        usage: usage as i64,
+        limit: quota.limit as i64,
+        message: format!(
+            "Reached {}% of the {} limit of {} {}",
+            usage * 100 / quota.limit,
+            entry.provider,
+            quota.limit,
+            quota.unit
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(provider: &str, peak_window_units: i64) -> NetworkUsageEntry {
+        NetworkUsageEntry {
+            account_id: Uuid::now_v7(),
+            provider: provider.to_string(),
+            day: Utc::now().date_naive(),
+            bytes_sent: 0,
+            bytes_received: 0,
+            api_calls: 0,
+            quota_units: 0,
+            peak_window_units,
+        }
+    }
+
+    #[test]
+    fn test_quota_warning_threshold() {
+        assert!(quota_warning(&entry("gmail", 11_999)).is_none());
+
+        let warning = quota_warning(&entry("gmail", 12_000)).unwrap();
+        assert_eq!(warning.limit, 15_000);
+        assert!(warning.message.starts_with("Reached 80%"));
+
+        assert!(quota_warning(&entry("imap", 1_000_000)).is_none());
+    }
+
+    #[test]
+    fn test_record_accumulates_counters() {
+        let account_id = Uuid::now_v7();
+        record(account_id, "gmail", 10, 100, gmail_units::MESSAGES_GET);
+        record(account_id, "gmail", 0, 50, gmail_units::MESSAGES_LIST);
+
+        let tracker = TRACKER.lock().unwrap();
+        let counters = tracker
+            .pending
+            .get(&(account_id, "gmail", Utc::now().date_naive()))
+            .copied()
+            .unwrap();
+
+        assert_eq!(counters.bytes_sent, 10);
+        assert_eq!(counters.bytes_received, 150);
+        assert_eq!(counters.api_calls, 2);
+        assert_eq!(counters.quota_units, 10);
+        assert_eq!(counters.peak_window_units, 10);
+    }
+
+    fn pending(account_id: Uuid, provider: &'static str) -> Option<UsageCounters> {
+        TRACKER
+            .lock()
+            .unwrap()
+            .pending
+            .get(&(account_id, provider, Utc::now().date_naive()))
+            .copied()
+    }
+
+    #[tokio::test]
+    async fn test_record_response_counts_bytes_read() {
+        let account_id = Uuid::now_v7();
+        let response = reqwest::Response::from(http::Response::new("hello world"));
+
+        let response = record_response(account_id, "office365", response, 1);
+        let counters = pending(account_id, "office365").unwrap();
+        assert_eq!(counters.api_calls, 1);
+        // Nothing is counted before the body is read
+        assert_eq!(counters.bytes_received, 0);
+
+        assert_eq!(response.text().await.unwrap(), "hello world");
+        assert_eq!(pending(account_id, "office365").unwrap().bytes_received, 11);
+    }
+
+    #[test]
+    fn test_restore_merges_into_pending() {
+        let account_id = Uuid::now_v7();
+        let key = (account_id, "imap", Utc::now().date_naive());
+        record(account_id, "imap", 0, 100, 0);
+
+        // Counters a failed flush took before the request above was recorded
+        restore(std::iter::once((
+            key,
+            UsageCounters {
+                bytes_received: 50,
+                api_calls: 1,
+                ..UsageCounters::default()
+            },
+        )));
+
+        let counters = pending(account_id, "imap").unwrap();
+        assert_eq!(counters.bytes_received, 150);
+        assert_eq!(counters.api_calls, 2);
+    }
+}
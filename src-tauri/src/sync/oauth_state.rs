@@ -15,6 +15,9 @@ pub struct OAuthState {
     pub account_id: Uuid,
     pub redirect_uri: String,
     pub created_at: DateTime<Utc>,
+    /// Granting an existing account more scopes rather than signing it in
+    #[serde(default)]
+    pub reauthorization: bool,
 }
 
 impl OAuthState {
@@ -3,21 +3,38 @@ use crate::database::repositories::{
     AccountRepository, RepositoryFactory, SqlitePendingOperationRepository,
 };
 use crate::sync::auth::CredentialStore;
+use crate::sync::backoff::Backoff;
+use crate::sync::draft_sync;
 use crate::sync::error::{SyncError, SyncResult};
 use crate::sync::events;
 use crate::sync::provider::ProviderFactory;
+use crate::sync::providers::gmail::GmailProvider;
+use chrono::{Duration, Utc};
 use sqlx::SqlitePool;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+/// Retry delays of failed operations: 5s, doubled on every further attempt,
+/// up to 15 minutes
+const RETRY_BACKOFF: Backoff = Backoff {
+    base_secs: 5,
+    growth: 2,
+    max_secs: 15 * 60,
+};
+
 /// Background processor for pending email operations (mark read, move, delete, etc.)
 ///
 /// Processes operations asynchronously after they've been optimistically applied locally.
-/// Handles retries for transient errors and reports failures to the UI.
+/// Provider errors are retried with exponential backoff. When the provider is unreachable
+/// the whole account queue is deferred without using up retries, and replayed once a sync
+/// succeeds again (see `SqlitePendingOperationRepository::resume_account`).
 pub struct OperationQueue {
     pool: SqlitePool,
     credential_store: Arc<CredentialStore>,
     app_handle: Option<tauri::AppHandle>,
+    /// Consecutive connectivity failures per account, used for the offline backoff
+    offline_attempts: Mutex<HashMap<Uuid, i64>>,
 }
 
 impl OperationQueue {
@@ -26,6 +43,7 @@ impl OperationQueue {
             pool,
             credential_store,
             app_handle: None,
+            offline_attempts: Mutex::new(HashMap::new()),
         }
     }
 
@@ -40,6 +58,20 @@ impl OperationQueue {
         log::info!("[OperationQueue] Starting background operation queue");
 
         tauri::async_runtime::spawn(async move {
+            // Operations interrupted by a previous shutdown are replayed
+            let repo = SqlitePendingOperationRepository::new(self.pool.clone());
+            match repo.reset_in_progress().await {
+                Ok(0) => {}
+                Ok(count) => log::info!(
+                    "[OperationQueue] Re-queued {} interrupted operations",
+                    count
+                ),
+                Err(e) => log::error!(
+                    "[OperationQueue] Failed to re-queue interrupted operations: {}",
+                    e
+                ),
+            }
+
             loop {
                 tokio::time::sleep(std::time::Duration::from_secs(2)).await;
                 if let Err(e) = self.process_pending_operations().await {
@@ -110,7 +142,13 @@ impl OperationQueue {
         // Load and authenticate credentials
         let credentials = self.load_credentials(&account).await?;
         let mut provider = provider;
-        provider.authenticate(credentials).await?;
+        if let Err(e) = provider.authenticate(credentials).await {
+            if e.is_connectivity_error() {
+                self.defer_account(&pending_repo, account_id, &e).await;
+                return Ok(());
+            }
+            return Err(e);
+        }
 
         for op in operations {
             let op_id = op.id;
@@ -130,6 +168,13 @@ impl OperationQueue {
                         op_type
                     );
                     let _ = pending_repo.mark_completed(op_id).await;
+                    self.reset_offline_attempts(account_id);
+                }
+                Err(e) if e.is_connectivity_error() => {
+                    // The provider went away mid-batch; keep the remaining operations
+                    // queued in order until it is reachable again
+                    self.defer_account(&pending_repo, account_id, &e).await;
+                    break;
                 }
                 Err(e) => {
                     let error_msg = e.to_string();
@@ -156,12 +201,14 @@ impl OperationQueue {
                         is_retryable
                     );
 
-                    let _ = pending_repo.mark_failed(op_id, &error_msg).await;
-
-                    if is_retryable && op.retry_count < op.max_retries {
-                        // Reset for retry
-                        let _ = pending_repo.reset_for_retry(op_id).await;
+                    if is_retryable && op.retry_count + 1 < op.max_retries {
+                        let next_attempt_at = Utc::now() + RETRY_BACKOFF.delay(op.retry_count);
+                        let _ = pending_repo
+                            .schedule_retry(op_id, &error_msg, next_attempt_at)
+                            .await;
                     } else {
+                        let _ = pending_repo.mark_failed(op_id, &error_msg).await;
+
                         // Emit failure event to frontend
                         if let Some(app_handle) = &self.app_handle {
                             events::emit_event(
@@ -190,9 +237,71 @@ impl OperationQueue {
             }
         }
 
+        self.emit_pending_count(&pending_repo, account_id).await;
+
         Ok(())
     }
 
+    /// Defer all queued operations of an account after a connectivity failure
+    async fn defer_account(
+        &self,
+        pending_repo: &SqlitePendingOperationRepository,
+        account_id: Uuid,
+        error: &SyncError,
+    ) {
+        let attempt = match self.offline_attempts.lock() {
+            Ok(mut attempts) => {
+                let attempt = attempts.entry(account_id).or_insert(0);
+                *attempt += 1;
+                *attempt - 1
+            }
+            Err(_) => 0,
+        };
+        let delay = RETRY_BACKOFF.delay(attempt);
+
+        log::warn!(
+            "[OperationQueue] Provider unreachable for account {}, deferring queue for {}s: {}",
+            account_id,
+            delay.num_seconds(),
+            error
+        );
+
+        if let Err(e) = pending_repo
+            .defer_account(account_id, &error.to_string(), Utc::now() + delay)
+            .await
+        {
+            log::error!(
+                "[OperationQueue] Failed to defer operations for account {}: {}",
+                account_id,
+                e
+            );
+        }
+    }
+
+    fn reset_offline_attempts(&self, account_id: Uuid) {
+        if let Ok(mut attempts) = self.offline_attempts.lock() {
+            attempts.remove(&account_id);
+        }
+    }
+
+    async fn emit_pending_count(
+        &self,
+        pending_repo: &SqlitePendingOperationRepository,
+        account_id: Uuid,
+    ) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
+
+        if let Ok(count) = pending_repo.count_pending(account_id).await {
+            events::emit_event(
+                app_handle,
+                "sync:pending-operations-count",
+                events::PendingOperationsCountEvent { account_id, count },
+            );
+        }
+    }
+
     /// Execute a single operation against the provider
     async fn execute_operation(
         &self,
@@ -235,6 +344,42 @@ impl OperationQueue {
             Some(PendingOperationType::PermanentDelete) => {
                 provider.delete_email(remote_id, &folder, true).await
             }
+            Some(
+                op_type @ (PendingOperationType::AddLabel | PendingOperationType::RemoveLabel),
+            ) => {
+                let label_remote_id = payload
+                    .get("label_remote_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                let gmail = provider
+                    .as_any()
+                    .downcast_ref::<GmailProvider>()
+                    .ok_or_else(|| {
+                        SyncError::NotSupported(format!(
+                            "{} does not support labels",
+                            provider.name()
+                        ))
+                    })?;
+                if op_type == PendingOperationType::AddLabel {
+                    gmail
+                        .modify_labels(remote_id, vec![label_remote_id], Vec::new())
+                        .await
+                } else {
+                    gmail
+                        .modify_labels(remote_id, Vec::new(), vec![label_remote_id])
+                        .await
+                }
+            }
+            Some(PendingOperationType::CreateDraft | PendingOperationType::UpdateDraft) => {
+                let email_id_str = payload
+                    .get("email_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                let email_id = Uuid::parse_str(email_id_str)
+                    .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+                draft_sync::upload(&self.pool, provider, email_id, &folder).await
+            }
             _ => {
                 log::warn!(
                     "[OperationQueue] Unsupported operation type: {}",
@@ -318,3 +463,16 @@ impl OperationQueue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(RETRY_BACKOFF.delay(0), Duration::seconds(5));
+        assert_eq!(RETRY_BACKOFF.delay(1), Duration::seconds(10));
+        assert_eq!(RETRY_BACKOFF.delay(3), Duration::seconds(40));
+        assert_eq!(RETRY_BACKOFF.delay(10), Duration::minutes(15));
+    }
+}
@@ -0,0 +1,277 @@
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::time::sleep;
+
+use crate::commands::emails::{
+    deliver_email, SendEmailResponse, SendFailure, SendFromAccountRequest,
+};
+use crate::database::models::outbox_item::OutboxItem;
+use crate::database::repositories::{OutboxRepository, SqliteOutboxRepository};
+use crate::state::AppState;
+use crate::sync::backoff::Backoff;
+use crate::sync::events::{self, SendStatus, SendStatusEvent};
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 30;
+/// Retry delays of failed sends: 30s, doubled up to half an hour
+const RETRY_BACKOFF: Backoff = Backoff {
+    base_secs: 30,
+    growth: 2,
+    max_secs: 30 * 60,
+};
+/// Failed attempts after which an item is no longer retried automatically
+pub const MAX_SEND_ATTEMPTS: i64 = 8;
+
+/// Retries sending the emails in the outbox once their backoff has passed
+pub struct OutboxWorker {
+    pool: SqlitePool,
+    app_handle: AppHandle,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: std::time::Duration,
+}
+
+impl OutboxWorker {
+    pub fn new(pool: SqlitePool, app_handle: AppHandle) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            app_handle,
+            shutdown_tx,
+            poll_interval: std::time::Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: std::time::Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[OutboxWorker] Starting outbox worker");
+
+        // An item left sending by the previous run may have gone out already,
+        // so it waits for the user instead of risking a duplicate
+        let interrupted = SqliteOutboxRepository::new(self.pool.clone())
+            .fail_interrupted()
+            .await
+            .map_err(|e| format!("Failed to recover interrupted sends: {}", e))?;
+        if interrupted > 0 {
+            log::warn!(
+                "[OutboxWorker] {} send(s) were interrupted and need to be retried manually",
+                interrupted
+            );
+        }
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(error) = this.send_due().await {
+                    log::error!("[OutboxWorker] Failed to process the outbox: {}", error);
+                }
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[OutboxWorker] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(this.poll_interval) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[OutboxWorker] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    async fn send_due(&self) -> Result<(), String> {
+        let due = SqliteOutboxRepository::new(self.pool.clone())
+            .find_due(Utc::now())
+            .await
+            .map_err(|e| format!("Failed to query the outbox: {}", e))?;
+        if due.is_empty() {
+            return Ok(());
+        }
+
+        let state = self
+            .app_handle
+            .try_state::<AppState>()
+            .ok_or_else(|| "Application state is not available".to_string())?;
+
+        for item in due {
+            if let Err(e) = send_outbox_item(&state, &item).await {
+                log::warn!(
+                    "[OutboxWorker] Failed to send outbox item {}: {}",
+                    item.id,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds a send that failed to reach the mail server to the outbox, counting
+/// the failure as its first attempt
+pub async fn enqueue(
+    pool: &SqlitePool,
+    app_handle: &AppHandle,
+    request: &SendFromAccountRequest,
+    error: &str,
+) -> Result<OutboxItem, String> {
+    let recipients = request
+        .to
+        .iter()
+        .chain(&request.cc)
+        .chain(&request.bcc)
+        .map(|addr| addr.address.clone())
+        .collect();
+    let request_json =
+        serde_json::to_string(request).map_err(|e| format!("Failed to serialize email: {}", e))?;
+
+    let mut item = OutboxItem::new(
+        request.account_id,
+        Some(request.subject.clone()).filter(|subject| !subject.is_empty()),
+        recipients,
+        request_json,
+    );
+    item.attempts = 1;
+    item.last_error = Some(error.to_string());
+    item.next_attempt_at = Some(Utc::now() + retry_delay(item.attempts));
+
+    SqliteOutboxRepository::new(pool.clone())
+        .create(&item)
+        .await
+        .map_err(|e| format!("Failed to add email to the outbox: {}", e))?;
+
+    emit_status(
+        app_handle,
+        &item,
+        SendStatus::Queued {
+            attempts: item.attempts,
+            next_attempt_at: item.next_attempt_at.unwrap_or_else(Utc::now),
+            error: error.to_string(),
+        },
+    );
+
+    Ok(item)
+}
+
+/// Sends an outbox item now. It leaves the outbox once sent; a failed attempt
+/// is retried later unless the message was rejected or out of attempts.
+pub async fn send_outbox_item(
+    state: &AppState,
+    item: &OutboxItem,
+) -> Result<SendEmailResponse, String> {
+    let outbox_repo = SqliteOutboxRepository::new(state.db_pool.clone());
+
+    let claimed = outbox_repo
+        .claim(item.id)
+        .await
+        .map_err(|e| format!("Failed to claim outbox item: {}", e))?;
+    if !claimed {
+        return Err("The email is already being sent".to_string());
+    }
+
+    emit_status(&state.app_handle, item, SendStatus::Sending);
+
+    let result = match serde_json::from_str::<SendFromAccountRequest>(&item.request) {
+        Ok(request) => deliver_email(state, request).await,
+        Err(e) => Err(SendFailure::Rejected(format!(
+            "Failed to read queued email: {}",
+            e
+        ))),
+    };
+
+    match result {
+        Ok(response) => {
+            log::info!("[OutboxWorker] Sent outbox item {}", item.id);
+            outbox_repo
+                .delete(item.id)
+                .await
+                .map_err(|e| format!("Failed to remove sent email from the outbox: {}", e))?;
+            emit_status(&state.app_handle, item, SendStatus::Sent);
+            Ok(response)
+        }
+        Err(failure) => {
+            let message = failure.message().to_string();
+            let attempts = item.attempts + 1;
+
+            match failure {
+                SendFailure::Transport(_) if attempts < MAX_SEND_ATTEMPTS => {
+                    let next_attempt_at = Utc::now() + retry_delay(attempts);
+                    outbox_repo
+                        .schedule_retry(item.id, &message, next_attempt_at)
+                        .await
+                        .map_err(|e| format!("Failed to reschedule outbox item: {}", e))?;
+                    emit_status(
+                        &state.app_handle,
+                        item,
+                        SendStatus::Queued {
+                            attempts,
+                            next_attempt_at,
+                            error: message.clone(),
+                        },
+                    );
+                }
+                _ => {
+                    outbox_repo
+                        .mark_failed(item.id, &message)
+                        .await
+                        .map_err(|e| format!("Failed to update outbox item: {}", e))?;
+                    emit_status(
+                        &state.app_handle,
+                        item,
+                        SendStatus::Failed {
+                            message: message.clone(),
+                        },
+                    );
+                }
+            }
+
+            Err(message)
+        }
+    }
+}
+
+fn emit_status(app_handle: &AppHandle, item: &OutboxItem, status: SendStatus) {
+    events::emit_event(
+        app_handle,
+        "email:send-status",
+        SendStatusEvent {
+            account_id: item.account_id,
+            outbox_id: item.id,
+            status,
+        },
+    );
+}
+
+/// Delay before the next send after `attempts` failed ones
+fn retry_delay(attempts: i64) -> Duration {
+    RETRY_BACKOFF.delay(attempts - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+        assert_eq!(retry_delay(7), Duration::minutes(30));
+    }
+}
@@ -0,0 +1,422 @@
+//! Phishing and spoofing heuristics. Each new email is analyzed once, when it
+//! is synced, and gets a risk score from 0 to 100 with the reasons behind it,
+//! so the message view can warn before the user acts on it.
+//!
+//! The signals are the authentication results the receiving server recorded,
+//! replies that go elsewhere than the sender, sender domains that imitate a
+//! well-known or the user's own domain, and links that hide where they lead.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sqlx::SqlitePool;
+use url::Url;
+
+use crate::database::models::email::{Email, RiskReason};
+use crate::database::repositories::{EmailRepository, SqliteEmailRepository};
+
+/// Emails scoring at least this are flagged as likely phishing
+pub const RISKY: i64 = 50;
+const MAX_SCORE: i64 = 100;
+/// Links past this many are not reported, they would not change the verdict
+const MAX_LINK_REASONS: usize = 3;
+
+/// Domains commonly impersonated in phishing
+const IMPERSONATED_DOMAINS: &[&str] = &[
+    "paypal.com",
+    "apple.com",
+    "icloud.com",
+    "google.com",
+    "gmail.com",
+    "microsoft.com",
+    "outlook.com",
+    "office.com",
+    "amazon.com",
+    "netflix.com",
+    "facebook.com",
+    "instagram.com",
+    "linkedin.com",
+    "dropbox.com",
+    "docusign.com",
+    "chase.com",
+    "wellsfargo.com",
+    "bankofamerica.com",
+    "dhl.com",
+    "fedex.com",
+    "ups.com",
+];
+
+static AUTH_RESULT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(spf|dkim|dmarc)\s*=\s*([a-z]+)")
+        .expect("Failed to compile authentication result regex")
+});
+
+static LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*?\shref\s*=\s*["']([^"']+)["'][^>]*>(.*?)</a>"#)
+        .expect("Failed to compile link regex")
+});
+
+static TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<[^>]*>").expect("Failed to compile tag regex"));
+
+/// Link text that reads as an address, e.g. `www.example.com/login`
+static URL_TEXT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^(?:https?://)?(?:[a-z0-9-]+\.)+[a-z]{2,}(?::\d+)?(?:/\S*)?$")
+        .expect("Failed to compile URL text regex")
+});
+
+fn weight(reason: &RiskReason) -> i64 {
+    match reason {
+        RiskReason::AuthenticationFailed { method, result } => {
+            match (method.as_str(), result.as_str()) {
+                ("dmarc", _) => 40,
+                ("spf", "softfail") => 10,
+                _ => 20,
+            }
+        }
+        RiskReason::ReplyToMismatch { .. } => 20,
+        RiskReason::LookalikeDomain { .. } => 50,
+        RiskReason::SuspiciousLink { .. } => 30,
+    }
+}
+
+pub fn score(reasons: &[RiskReason]) -> i64 {
+    reasons.iter().map(weight).sum::<i64>().min(MAX_SCORE)
+}
+
+pub fn is_risky(risk_score: i64) -> bool {
+    risk_score >= RISKY
+}
+
+fn domain_of(address: &str) -> Option<String> {
+    address
+        .trim()
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.trim_end_matches('>').to_lowercase())
+        .filter(|domain| !domain.is_empty())
+}
+
+/// The last two labels of a domain, close enough to the registered domain to
+/// tell organizations apart
+fn base_domain(domain: &str) -> String {
+    let labels: Vec<&str> = domain.trim_end_matches('.').split('.').collect();
+    labels[labels.len().saturating_sub(2)..].join(".")
+}
+
+/// The label a domain is registered under, e.g. `paypal` for `www.paypal.com`
+fn main_label(domain: &str) -> &str {
+    let mut labels = domain.trim_end_matches('.').rsplit('.');
+    labels.next();
+    labels.next().unwrap_or(domain)
+}
+
+/// Folds characters that look alike, so `paypa1` and `rnicrosoft` read as
+/// `paypal` and `microsoft`
+fn skeleton(label: &str) -> String {
+    label
+        .to_lowercase()
+        .replace("rn", "m")
+        .replace("vv", "w")
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | 'i' => 'l',
+            '3' => 'e',
+            '5' => 's',
+            other => other,
+        })
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// The trusted domain the sender's domain imitates, if any. A domain is never
+/// a lookalike of itself or of a domain it belongs to.
+pub fn lookalike_of(domain: &str, trusted_domains: &[String]) -> Option<String> {
+    let domain = domain.trim().to_lowercase();
+
+    trusted_domains
+        .iter()
+        .map(|trusted| trusted.to_lowercase())
+        .filter(|trusted| domain != *trusted && !domain.ends_with(&format!(".{}", trusted)))
+        .find(|trusted| {
+            let label = main_label(&domain);
+            let trusted_label = main_label(trusted);
+            // e.g. paypal.com.account-check.net
+            let embeds_domain = domain.starts_with(&format!("{}.", trusted))
+                || domain.contains(&format!(".{}.", trusted));
+            // e.g. paypal-security.com
+            let embeds_label =
+                label.split('-').count() > 1 && label.split('-').any(|part| part == trusted_label);
+            // The same name under another TLD is usually the same organization
+            let looks_alike = label != trusted_label
+                && (skeleton(label) == skeleton(trusted_label)
+                    || (trusted_label.len() >= 5 && edit_distance(label, trusted_label) == 1));
+
+            embeds_domain || embeds_label || looks_alike
+        })
+}
+
+/// Reads a header from the stored header map, if the provider kept it
+fn header(headers: Option<&str>, name: &str) -> Option<String> {
+    let headers: serde_json::Value = serde_json::from_str(headers?).ok()?;
+    headers
+        .as_object()?
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .and_then(|(_, value)| value.as_str())
+        .map(ToString::to_string)
+}
+
+/// Failed checks in the Authentication-Results header the provider kept
+pub fn authentication_failures(headers: Option<&str>) -> Vec<RiskReason> {
+    let Some(value) = header(headers, "Authentication-Results") else {
+        return Vec::new();
+    };
+
+    // Only the first result of each method counts, as with several DKIM
+    // signatures one that passes is enough
+    let mut checked: Vec<String> = Vec::new();
+    let mut reasons = Vec::new();
+    for captures in AUTH_RESULT.captures_iter(&value) {
+        let method = captures[1].to_lowercase();
+        let result = captures[2].to_lowercase();
+        if checked.contains(&method) {
+            continue;
+        }
+        checked.push(method.clone());
+
+        let failed = matches!(result.as_str(), "fail" | "permerror")
+            || (method == "spf" && result == "softfail");
+        if failed {
+            reasons.push(RiskReason::AuthenticationFailed { method, result });
+        }
+    }
+
+    reasons
+}
+
+/// Links whose text shows another site than the one they open, or that open a
+/// bare IP address
+pub fn suspicious_links(html: &str) -> Vec<RiskReason> {
+    let mut reasons = Vec::new();
+
+    for captures in LINK.captures_iter(html) {
+        let href = captures[1].trim();
+        let Some(url) = Url::parse(href)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+        else {
+            continue;
+        };
+        let Some(host) = url.host_str() else {
+            continue;
+        };
+        let is_ip = url.domain().is_none();
+
+        let text = TAG.replace_all(&captures[2], "");
+        let text = text.trim();
+
+        let shown_host = URL_TEXT.is_match(text).then(|| {
+            let text = text.to_lowercase();
+            let without_scheme = text
+                .split_once("://")
+                .map_or(text.as_str(), |(_, rest)| rest);
+            without_scheme
+                .split(['/', ':'])
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        });
+        let misleading = shown_host.is_some_and(|shown| base_domain(&shown) != base_domain(host));
+
+        if is_ip || misleading {
+            reasons.push(RiskReason::SuspiciousLink {
+                text: text.to_string(),
+                url: href.to_string(),
+            });
+            if reasons.len() == MAX_LINK_REASONS {
+                break;
+            }
+        }
+    }
+
+    reasons
+}
+
+/// Every heuristic applied to an email. `trusted_domains` are the domains a
+/// lookalike sender would imitate, beside the commonly impersonated ones.
+pub fn analyze(email: &Email, trusted_domains: &[String]) -> Vec<RiskReason> {
+    let mut reasons = authentication_failures(email.headers.as_deref());
+    let from_domain = domain_of(&email.from.address);
+
+    if let (Some(from_domain), Some(reply_to_domain)) = (
+        from_domain.as_deref(),
+        email
+            .reply_to
+            .as_ref()
+            .and_then(|reply_to| domain_of(&reply_to.address)),
+    ) {
+        // Mailing lists take replies on the list's own address
+        let is_list = header(email.headers.as_deref(), "List-Id").is_some();
+        if !is_list && base_domain(from_domain) != base_domain(&reply_to_domain) {
+            reasons.push(RiskReason::ReplyToMismatch {
+                from_domain: from_domain.to_string(),
+                reply_to_domain,
+            });
+        }
+    }
+
+    if let Some(from_domain) = from_domain.as_deref() {
+        let mut candidates: Vec<String> = IMPERSONATED_DOMAINS
+            .iter()
+            .map(|domain| domain.to_string())
+            .collect();
+        candidates.extend(trusted_domains.iter().cloned());
+
+        if let Some(resembles) = lookalike_of(from_domain, &candidates) {
+            reasons.push(RiskReason::LookalikeDomain {
+                domain: from_domain.to_string(),
+                resembles,
+            });
+        }
+    }
+
+    if let Some(html) = email.body_html.as_deref() {
+        reasons.extend(suspicious_links(html));
+    }
+
+    reasons
+}
+
+/// Analyzes a newly synced email and stores its risk. The user's own domains
+/// are the ones a lookalike sender would imitate.
+pub async fn process_synced_email(pool: &SqlitePool, email: &mut Email) -> Result<i64, String> {
+    if email.is_draft {
+        return Ok(0);
+    }
+
+    let own_addresses = super::priority::own_addresses(pool, email.account_id).await?;
+    let sender = email.from.address.to_lowercase();
+    if own_addresses.contains(&sender) {
+        return Ok(0);
+    }
+
+    let mut own_domains: Vec<String> = own_addresses
+        .iter()
+        .filter_map(|address| domain_of(address))
+        .collect();
+    own_domains.sort();
+    own_domains.dedup();
+
+    let reasons = analyze(email, &own_domains);
+    let risk_score = score(&reasons);
+    if risk_score != email.risk_score || reasons != email.risk_reasons.0 {
+        SqliteEmailRepository::new(pool.clone())
+            .update_risk(email.id, risk_score, &reasons)
+            .await
+            .map_err(|e| format!("Failed to store risk: {}", e))?;
+        email.risk_score = risk_score;
+        email.risk_reasons.0 = reasons;
+    }
+
+    Ok(risk_score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookalike_of() {
+        let trusted = vec!["paypal.com".to_string(), "acme-corp.com".to_string()];
+
+        for domain in [
+            "paypa1.com",
+            "paypai.com",
+            "paypall.com",
+            "paypal-security.com",
+            "paypal.com.account-check.net",
+            "acmecorp.com",
+        ] {
+            assert!(lookalike_of(domain, &trusted).is_some(), "{}", domain);
+        }
+        for domain in [
+            "paypal.com",
+            "mail.paypal.com",
+            "paypal.de",
+            "example.com",
+            "pay.com",
+        ] {
+            assert_eq!(lookalike_of(domain, &trusted), None, "{}", domain);
+        }
+    }
+
+    #[test]
+    fn test_authentication_failures() {
+        let headers = serde_json::json!({
+            "Authentication-Results": "mx.example.com; spf=softfail smtp.mailfrom=evil.net; \
+                dkim=pass header.d=evil.net; dmarc=fail (p=REJECT) header.from=paypal.com"
+        })
+        .to_string();
+
+        let reasons = authentication_failures(Some(&headers));
+        assert_eq!(
+            reasons,
+            vec![
+                RiskReason::AuthenticationFailed {
+                    method: "spf".to_string(),
+                    result: "softfail".to_string(),
+                },
+                RiskReason::AuthenticationFailed {
+                    method: "dmarc".to_string(),
+                    result: "fail".to_string(),
+                },
+            ]
+        );
+        assert_eq!(score(&reasons), 50);
+        assert!(is_risky(score(&reasons)));
+
+        assert!(authentication_failures(Some(r#"{"List-Id": "<list.example.com>"}"#)).is_empty());
+        assert!(authentication_failures(None).is_empty());
+    }
+
+    #[test]
+    fn test_suspicious_links() {
+        let html = concat!(
+            r#"<a href="https://evil.example.net/login">https://www.paypal.com</a>"#,
+            r#"<a href="http://192.168.10.4/reset">Reset your password</a>"#,
+            r#"<a href="https://news.example.com/track?id=1"><b>example.com/news</b></a>"#,
+            r#"<a href="https://example.com">Read more</a>"#,
+            r#"<a href="mailto:support@example.com">support@example.com</a>"#,
+        );
+
+        let reasons = suspicious_links(html);
+        assert_eq!(reasons.len(), 2);
+        assert_eq!(
+            reasons[0],
+            RiskReason::SuspiciousLink {
+                text: "https://www.paypal.com".to_string(),
+                url: "https://evil.example.net/login".to_string(),
+            }
+        );
+        assert!(matches!(
+            &reasons[1],
+            RiskReason::SuspiciousLink { url, .. } if url == "http://192.168.10.4/reset"
+        ));
+    }
+}
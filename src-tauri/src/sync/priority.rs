@@ -0,0 +1,159 @@
+//! Priority of incoming mail. Each new email is scored once, when it is synced,
+//! from signals that it matters to the user. The score is a sum of flags, so
+//! the signals can be told apart again and sorting by it puts VIP mail first.
+//!
+//! The priority inbox and VIP-only notifications are driven by the score.
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::database::models::email::Email;
+use crate::database::repositories::{
+    AccountRepository, ContactRepository, EmailRepository, IdentityRepository,
+    SqliteAccountRepository, SqliteContactRepository, SqliteEmailRepository,
+    SqliteIdentityRepository,
+};
+
+/// Sent by a contact marked as VIP
+pub const VIP_SENDER: i64 = 4;
+/// A reply in a conversation the user wrote in
+pub const REPLY_TO_MY_THREAD: i64 = 2;
+/// Sent to the user directly, not on copy or through a list
+pub const DIRECT_TO_ME: i64 = 1;
+
+/// Emails of at least this priority are important and make the priority inbox
+pub const IMPORTANT: i64 = REPLY_TO_MY_THREAD;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrioritySignals {
+    pub vip_sender: bool,
+    pub reply_to_my_thread: bool,
+    pub direct_to_me: bool,
+}
+
+impl PrioritySignals {
+    pub fn score(&self) -> i64 {
+        [
+            (self.vip_sender, VIP_SENDER),
+            (self.reply_to_my_thread, REPLY_TO_MY_THREAD),
+            (self.direct_to_me, DIRECT_TO_ME),
+        ]
+        .iter()
+        .filter(|(signal, _)| *signal)
+        .map(|(_, weight)| weight)
+        .sum()
+    }
+}
+
+pub fn is_vip(priority: i64) -> bool {
+    priority & VIP_SENDER != 0
+}
+
+pub fn is_important(priority: i64) -> bool {
+    priority >= IMPORTANT
+}
+
+/// The addresses the user sends from on the account, lowercased
+pub(crate) async fn own_addresses(
+    pool: &SqlitePool,
+    account_id: Uuid,
+) -> Result<Vec<String>, String> {
+    let mut addresses: Vec<String> = SqliteIdentityRepository::new(pool.clone())
+        .find_by_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to load identities: {}", e))?
+        .into_iter()
+        .map(|identity| identity.email.to_lowercase())
+        .collect();
+
+    if let Some(account) = SqliteAccountRepository::new(pool.clone())
+        .find_by_id(account_id)
+        .await
+        .map_err(|e| format!("Failed to load account: {}", e))?
+    {
+        addresses.push(account.email.to_lowercase());
+    }
+
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
+
+/// Scores a newly synced email and stores its priority. The user's own
+/// messages are never important.
+pub async fn process_synced_email(pool: &SqlitePool, email: &mut Email) -> Result<i64, String> {
+    if email.is_draft {
+        return Ok(0);
+    }
+
+    let own_addresses = own_addresses(pool, email.account_id).await?;
+    let sender = email.from.address.to_lowercase();
+    if own_addresses.contains(&sender) {
+        return Ok(0);
+    }
+
+    let email_repo = SqliteEmailRepository::new(pool.clone());
+    let reply_to_my_thread = match email.conversation_id.as_deref() {
+        Some(conversation_id) => email_repo
+            .conversation_has_sender(conversation_id, email.id, &own_addresses)
+            .await
+            .map_err(|e| format!("Failed to check conversation: {}", e))?,
+        None => false,
+    };
+
+    let signals = PrioritySignals {
+        vip_sender: SqliteContactRepository::new(pool.clone())
+            .is_vip_address(&sender)
+            .await
+            .map_err(|e| format!("Failed to check VIP contact: {}", e))?,
+        reply_to_my_thread,
+        direct_to_me: email
+            .to
+            .iter()
+            .any(|recipient| own_addresses.contains(&recipient.address.to_lowercase())),
+    };
+
+    let priority = signals.score();
+    if priority != email.priority {
+        email_repo
+            .update_priority(email.id, priority)
+            .await
+            .map_err(|e| format!("Failed to store priority: {}", e))?;
+        email.priority = priority;
+    }
+
+    Ok(priority)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_score() {
+        assert_eq!(PrioritySignals::default().score(), 0);
+
+        let direct = PrioritySignals {
+            direct_to_me: true,
+            ..PrioritySignals::default()
+        };
+        assert_eq!(direct.score(), DIRECT_TO_ME);
+        assert!(!is_important(direct.score()));
+
+        let reply = PrioritySignals {
+            reply_to_my_thread: true,
+            direct_to_me: true,
+            ..PrioritySignals::default()
+        };
+        assert!(is_important(reply.score()));
+        assert!(!is_vip(reply.score()));
+
+        let vip = PrioritySignals {
+            vip_sender: true,
+            ..PrioritySignals::default()
+        };
+        assert!(is_important(vip.score()) && is_vip(vip.score()));
+        // VIP mail sorts before every other signal combined
+        assert!(vip.score() > reply.score());
+    }
+}
@@ -1,3 +1,5 @@
+use super::attachment_download::AttachmentSink;
+use super::attachment_policy::AttachmentDownloadPolicy;
 use super::error::SyncResult;
 use super::types::*;
 use crate::database::models::account::Account;
@@ -17,6 +19,10 @@ pub trait EmailProvider: Send + Sync {
     /// Authenticate with the provider
     async fn authenticate(&mut self, credentials: ProviderCredentials) -> SyncResult<()>;
 
+    /// Which attachment contents to download while syncing. Providers that
+    /// fetch attachments separately from the message skip the others.
+    fn set_attachment_policy(&mut self, _policy: AttachmentDownloadPolicy) {}
+
     /// Test connection to the provider
     async fn test_connection(&self) -> SyncResult<bool>;
 
@@ -40,12 +46,61 @@ pub trait EmailProvider: Send + Sync {
         sync_token: Option<String>,
     ) -> SyncResult<SyncDiff>;
 
+    /// First sync of a folder that only fetches messages received since
+    /// `since`, with a sync token for the delta syncs after it. The diff is
+    /// incomplete when older messages were left out for a backfill; providers
+    /// that cannot search by date sync the whole folder.
+    async fn sync_recent_messages(
+        &self,
+        folder: &SyncFolder,
+        _since: chrono::DateTime<chrono::Utc>,
+    ) -> SyncResult<SyncDiff> {
+        self.sync_messages(folder, None).await
+    }
+
+    /// Fetch the messages of a folder received within `window`, to backfill
+    /// history older than the first sync
+    async fn fetch_window(
+        &self,
+        _folder: &SyncFolder,
+        _window: &SyncWindow,
+    ) -> SyncResult<Vec<SyncEmail>> {
+        Err(SyncError::NotSupported(
+            "This provider does not support fetching messages by date".to_string(),
+        ))
+    }
+
     /// Fetch a single email by its remote ID
     async fn fetch_email(&self, folder: &SyncFolder, remote_id: &str) -> SyncResult<SyncEmail>;
 
+    /// Fetch several emails by their remote IDs, with one result per ID in
+    /// the same order. Providers that can bundle requests override this.
+    async fn fetch_emails(
+        &self,
+        folder: &SyncFolder,
+        remote_ids: &[String],
+    ) -> SyncResult<Vec<SyncResult<SyncEmail>>> {
+        let mut emails = Vec::with_capacity(remote_ids.len());
+        for remote_id in remote_ids {
+            emails.push(self.fetch_email(folder, remote_id).await);
+        }
+        Ok(emails)
+    }
+
     /// Fetch attachment content
     async fn fetch_attachment(&self, attachment: &SyncAttachment) -> SyncResult<Vec<u8>>;
 
+    /// Stream attachment content into `sink` chunk by chunk. Providers that
+    /// only return whole attachments hand it over as a single chunk.
+    async fn stream_attachment(
+        &self,
+        attachment: &SyncAttachment,
+        sink: &mut AttachmentSink,
+    ) -> SyncResult<()> {
+        let data = self.fetch_attachment(attachment).await?;
+        sink.write(&data).await
+    }
+
     /// Move an email to a different folder
     async fn move_email(
         &self,
@@ -104,6 +159,9 @@ pub trait EmailProvider: Send + Sync {
 
     /// Send an email via the provider's API (optional, for providers that support API-based sending)
     /// Returns NotSupported error by default - providers that support API sending should override
+    ///
+    /// `from` sends as an alias instead of the mailbox address; `read_receipt`
+    /// asks the recipients for a read receipt (MDN)
     async fn send_email(
         &self,
         _to: Vec<super::types::EmailRecipient>,
@@ -115,11 +173,35 @@ pub trait EmailProvider: Send + Sync {
         _in_reply_to: Option<String>,
         _references: Option<String>,
         _conversation_id: Option<String>,
+        _from: Option<super::types::EmailRecipient>,
+        _read_receipt: bool,
     ) -> SyncResult<()> {
         Err(SyncError::NotSupported(
             "This provider does not support API-based email sending".to_string(),
         ))
     }
+
+    /// Send a fully built RFC 5322 message via the provider's API, for content
+    /// the provider must not alter (e.g. S/MIME signed or encrypted mail)
+    async fn send_mime(&self, _mime: Vec<u8>) -> SyncResult<()> {
+        Err(SyncError::NotSupported(
+            "This provider does not support sending MIME messages".to_string(),
+        ))
+    }
+
+    /// Store a fully built RFC 5322 message as a draft in `drafts_folder`,
+    /// replacing the draft with remote ID `replaces` when given. Returns the
+    /// remote ID of the stored draft, which may differ from `replaces`.
+    async fn save_draft(
+        &self,
+        _drafts_folder: &SyncFolder,
+        _mime: Vec<u8>,
+        _replaces: Option<&str>,
+    ) -> SyncResult<String> {
+        Err(SyncError::NotSupported(
+            "This provider does not support saving drafts".to_string(),
+        ))
+    }
 }
 
 /// Factory for creating email provider instances
@@ -164,8 +246,11 @@ impl ProviderFactory {
                 Ok(Box::new(provider))
             }
             "apple" => {
+                let mut settings = settings;
+                providers::icloud::apply_server_presets(&mut settings, &account.email);
                 let provider = providers::imap::ImapProvider::new(account.id, credential_store)?
-                    .with_settings(settings);
+                    .with_settings(settings)
+                    .with_profile(providers::imap::ImapProfile::ICloud);
                 Ok(Box::new(provider))
             }
             _ => Err(super::error::SyncError::NotSupported(format!(
@@ -1,8 +1,12 @@
 use crate::database::models::email::EmailAddress;
+use crate::services::smime;
 use crate::sync::{
     auth::{CredentialStore, OAuth2Helper},
     error::{SyncError, SyncResult},
+    network_usage::{self, gmail_units},
     provider::EmailProvider,
+    rate_limit::SendRateLimited,
+    sync_limits,
     types::*,
 };
 use async_trait::async_trait;
@@ -11,10 +15,21 @@ use chrono::{DateTime, Utc};
 use mail_parser::{MessageParser, MimeHeaders};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 const GMAIL_API_BASE: &str = "https://gmail.googleapis.com/gmail/v1";
+const PEOPLE_API_BASE: &str = "https://people.googleapis.com/v1";
+
+/// System labels that are synced as folders. User labels are synced into the
+/// `labels` table instead, so a message with several labels is stored once.
+const FOLDER_LABEL_IDS: &[&str] = &["INBOX", "SENT", "DRAFT", "TRASH", "SPAM", "STARRED"];
+/// Remote ID of the local folder holding archived mail: everything outside the
+/// inbox, sent, drafts, spam and trash. Gmail has no label for it.
+pub const ARCHIVE_FOLDER_ID: &str = "ARCHIVE";
+/// Search query listing the messages of the archive folder
+const ARCHIVE_QUERY: &str = "-in:inbox -in:sent -in:drafts -in:spam -in:trash -in:chats";
 
 pub struct GmailProvider {
     account_id: Uuid,
@@ -32,14 +47,22 @@ struct GmailLabelsResponse {
 struct GmailLabel {
     id: String,
     name: String,
-    // #[serde(rename = "type")]
-    // label_type: Option<String>,
+    /// "system" or "user"
+    #[serde(rename = "type")]
+    label_type: Option<String>,
+    color: Option<GmailLabelColor>,
     #[serde(rename = "messagesTotal")]
     messages_total: Option<i32>,
     #[serde(rename = "messagesUnread")]
     messages_unread: Option<i32>,
 }
 
+#[derive(Debug, Deserialize)]
+struct GmailLabelColor {
+    #[serde(rename = "backgroundColor")]
+    background_color: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct GmailMessagesResponse {
     messages: Option<Vec<GmailMessageRef>>,
@@ -54,6 +77,19 @@ struct GmailMessageRef {
     // thread_id: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct GmailDraftsResponse {
+    drafts: Option<Vec<GmailDraft>>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmailDraft {
+    id: String,
+    message: GmailMessageRef,
+}
+
 #[derive(Debug, Deserialize)]
 struct GmailMessage {
     id: String,
@@ -129,6 +165,190 @@ struct GmailHistoryMessageRef {
     id: String,
 }
 
+/// Net effect of the history records on a single message, in the order they occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryChange {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl HistoryChange {
+    /// Fold a newer change into the state accumulated so far
+    fn merge(previous: Option<Self>, next: Self) -> Self {
+        match (previous, next) {
+            // A message that is new to this folder stays "added" while its labels change
+            (Some(HistoryChange::Added), HistoryChange::Modified) => HistoryChange::Added,
+            // Label changes on a message that already left the folder are irrelevant
+            (Some(HistoryChange::Deleted), HistoryChange::Modified) => HistoryChange::Deleted,
+            (_, next) => next,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleConnectionsResponse {
+    #[serde(default)]
+    connections: Vec<PeoplePerson>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeoplePerson {
+    #[serde(default)]
+    names: Vec<PeopleName>,
+    #[serde(rename = "emailAddresses", default)]
+    email_addresses: Vec<PeopleEmailAddress>,
+    #[serde(default)]
+    birthdays: Vec<PeopleDateField>,
+    #[serde(default)]
+    events: Vec<PeopleEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleName {
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleEmailAddress {
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleDateField {
+    date: Option<PeopleDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleEvent {
+    #[serde(rename = "type")]
+    event_type: Option<String>,
+    date: Option<PeopleDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleProfile {
+    #[serde(default)]
+    names: Vec<PeopleName>,
+    #[serde(default)]
+    photos: Vec<PeoplePhoto>,
+    #[serde(default)]
+    locales: Vec<PeopleLocale>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeoplePhoto {
+    url: Option<String>,
+    /// True for the generated letter avatar
+    #[serde(default)]
+    default: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleLocale {
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmailLanguageSettings {
+    #[serde(rename = "displayLanguage")]
+    display_language: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmailProfile {
+    #[serde(rename = "messagesTotal")]
+    messages_total: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmailSendAsResponse {
+    #[serde(rename = "sendAs", default)]
+    send_as: Vec<GmailSendAs>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GmailSendAs {
+    send_as_email: String,
+    display_name: Option<String>,
+    #[serde(default)]
+    is_primary: bool,
+    verification_status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeopleDate {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+/// Formats a People API date, which omits the year when it is unknown
+fn format_people_date(date: &PeopleDate) -> Option<String> {
+    let (month, day) = (date.month?, date.day?);
+    match date.year.filter(|year| *year > 0) {
+        Some(year) => Some(format!("{:04}-{:02}-{:02}", year, month, day)),
+        None => Some(format!("--{:02}-{:02}", month, day)),
+    }
+}
+
+/// Classify history records for a label into added, modified and deleted message IDs
+fn classify_history(
+    records: &[GmailHistoryRecord],
+    label_id: &str,
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut order: Vec<String> = Vec::new();
+    let mut changes: HashMap<String, HistoryChange> = HashMap::new();
+
+    let mut record_change = |id: &str, change: HistoryChange| {
+        let previous = changes.get(id).copied();
+        if previous.is_none() {
+            order.push(id.to_string());
+        }
+        changes.insert(id.to_string(), HistoryChange::merge(previous, change));
+    };
+
+    for record in records {
+        for msg in record.messages_added.iter().flatten() {
+            record_change(&msg.message.id, HistoryChange::Added);
+        }
+        for change in record.labels_added.iter().flatten() {
+            if change.label_ids.iter().any(|l| l == label_id) {
+                record_change(&change.message.id, HistoryChange::Added);
+            } else {
+                record_change(&change.message.id, HistoryChange::Modified);
+            }
+        }
+        for change in record.labels_removed.iter().flatten() {
+            if change.label_ids.iter().any(|l| l == label_id) {
+                record_change(&change.message.id, HistoryChange::Deleted);
+            } else {
+                record_change(&change.message.id, HistoryChange::Modified);
+            }
+        }
+        for msg in record.messages_deleted.iter().flatten() {
+            record_change(&msg.message.id, HistoryChange::Deleted);
+        }
+    }
+
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    let mut deleted = Vec::new();
+    for id in order {
+        match changes[&id] {
+            HistoryChange::Added => added.push(id),
+            HistoryChange::Modified => modified.push(id),
+            HistoryChange::Deleted => deleted.push(id),
+        }
+    }
+
+    (added, modified, deleted)
+}
+
 #[derive(Debug, Deserialize)]
 struct GmailHistoryLabelChange {
     message: GmailHistoryMessageRef,
@@ -136,6 +356,16 @@ struct GmailHistoryLabelChange {
     label_ids: Vec<String>,
 }
 
+/// Whether a message with these labels belongs in the archive folder
+fn is_archived(label_ids: &[String]) -> bool {
+    !label_ids.iter().any(|label| {
+        matches!(
+            label.as_str(),
+            "INBOX" | "SENT" | "DRAFT" | "SPAM" | "TRASH" | "CHAT"
+        )
+    })
+}
+
 /// Convert Gmail label IDs to IMAP-standard flags.
 /// Gmail uses labels like "UNREAD", "STARRED", "DRAFT" whereas the DB model
 /// expects IMAP-standard flags like "\Seen", "\Flagged", "\Draft".
@@ -163,6 +393,10 @@ impl GmailProvider {
         })
     }
 
+    fn record_usage(&self, response: reqwest::Response, quota_units: u64) -> reqwest::Response {
+        network_usage::record_response(self.account_id, "gmail", response, quota_units)
+    }
+
     async fn _ensure_token(&mut self) -> SyncResult<String> {
         if let Some(token) = &self.access_token {
             return Ok(token.clone());
@@ -190,12 +424,12 @@ impl GmailProvider {
     }
 
     /// Perform delta sync using Gmail History API
-    /// Returns (added/modified emails, deleted remote IDs, new historyId)
+    /// Returns (added emails, modified emails, deleted remote IDs, new historyId)
     async fn sync_history(
         &self,
         folder: &SyncFolder,
         start_history_id: &str,
-    ) -> SyncResult<(Vec<SyncEmail>, Vec<String>, String)> {
+    ) -> SyncResult<(Vec<SyncEmail>, Vec<SyncEmail>, Vec<String>, String)> {
         let token = self
             .access_token
             .as_ref()
@@ -205,8 +439,11 @@ impl GmailProvider {
             .id
             .ok_or_else(|| SyncError::DatabaseError("Folder ID is required".to_string()))?;
 
-        let mut added_ids = std::collections::HashSet::new();
-        let mut deleted_ids = std::collections::HashSet::new();
+        // The archive folder has no label to filter on, so it sees the history of
+        // the whole mailbox and decides membership from the current labels
+        let is_archive = folder.remote_id == ARCHIVE_FOLDER_ID;
+
+        let mut records = Vec::new();
         let mut page_token: Option<String> = None;
         let mut latest_history_id = start_history_id.to_string();
 
@@ -216,16 +453,20 @@ impl GmailProvider {
                 .client
                 .get(format!("{}/users/me/history", GMAIL_API_BASE))
                 .bearer_auth(token)
-                .query(&[
-                    ("startHistoryId", start_history_id),
-                    ("labelId", &folder.remote_id),
-                ]);
+                .query(&[("startHistoryId", start_history_id), ("maxResults", "500")]);
+
+            if !is_archive {
+                request = request.query(&[("labelId", &folder.remote_id)]);
+            }
 
             if let Some(ref pt) = page_token {
                 request = request.query(&[("pageToken", pt)]);
             }
 
-            let response = request.send().await?;
+            let response = request
+                .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+                .await?;
+            let response = self.record_usage(response, gmail_units::HISTORY_LIST);
 
             if response.status() == reqwest::StatusCode::NOT_FOUND
                 || response.status() == reqwest::StatusCode::GONE
@@ -246,37 +487,8 @@ impl GmailProvider {
             let history_response: GmailHistoryResponse = response.json().await?;
             latest_history_id = history_response.history_id.clone();
 
-            if let Some(records) = history_response.history {
-                for record in records {
-                    // Messages added to this label
-                    if let Some(added) = record.messages_added {
-                        for msg in added {
-                            added_ids.insert(msg.message.id);
-                        }
-                    }
-                    // Labels added (message moved into this folder)
-                    if let Some(label_changes) = record.labels_added {
-                        for change in label_changes {
-                            if change.label_ids.contains(&folder.remote_id) {
-                                added_ids.insert(change.message.id);
-                            }
-                        }
-                    }
-                    // Messages deleted from this label
-                    if let Some(deleted) = record.messages_deleted {
-                        for msg in deleted {
-                            deleted_ids.insert(msg.message.id);
-                        }
-                    }
-                    // Labels removed (message moved out of this folder)
-                    if let Some(label_changes) = record.labels_removed {
-                        for change in label_changes {
-                            if change.label_ids.contains(&folder.remote_id) {
-                                deleted_ids.insert(change.message.id);
-                            }
-                        }
-                    }
-                }
+            if let Some(page) = history_response.history {
+                records.extend(page);
             }
 
             match history_response.next_page_token {
@@ -285,20 +497,20 @@ impl GmailProvider {
             }
         }
 
-        // Remove IDs that were both added and deleted (net effect: no change or re-fetch)
-        // If added after deleted, we should fetch it. If deleted after added, skip.
-        // Since history is chronological, keep the add if it appears at all (fetch will verify)
-        let net_deleted: Vec<String> = deleted_ids
-            .iter()
-            .filter(|id| !added_ids.contains(*id))
-            .cloned()
-            .collect();
+        // History records are chronological, so the last change to a message wins
+        let (added_ids, modified_ids, deleted_ids) = classify_history(&records, &folder.remote_id);
 
-        // Fetch full message data for added/modified messages
-        let mut emails = Vec::new();
+        let mut added = Vec::new();
+        let mut deleted = deleted_ids;
         for msg_id in &added_ids {
             match self.fetch_email(folder, msg_id).await {
-                Ok(email) => emails.push(email),
+                Ok(email)
+                    if is_archive
+                        && !is_archived(email.remote_labels.as_deref().unwrap_or(&[])) =>
+                {
+                    // New mail outside the archive is picked up by its own folder
+                }
+                Ok(email) => added.push(email),
                 Err(e) => {
                     log::warn!(
                         "[Gmail] Failed to fetch delta message {}: {} (may have been deleted)",
@@ -309,15 +521,40 @@ impl GmailProvider {
             }
         }
 
+        let mut modified = Vec::new();
+        for msg_id in &modified_ids {
+            match self.fetch_email(folder, msg_id).await {
+                Ok(email)
+                    if is_archive
+                        && !is_archived(email.remote_labels.as_deref().unwrap_or(&[])) =>
+                {
+                    // Moved out of the archive, e.g. back to the inbox
+                    deleted.push(msg_id.clone());
+                }
+                Ok(email) => modified.push(email),
+                Err(SyncError::GmailStatus {
+                    status: reqwest::StatusCode::NOT_FOUND,
+                    ..
+                }) => {
+                    // Message vanished after the label change was recorded
+                    deleted.push(msg_id.clone());
+                }
+                Err(e) => {
+                    log::warn!("[Gmail] Failed to fetch modified message {}: {}", msg_id, e);
+                }
+            }
+        }
+
         log::info!(
-            "[Gmail] History sync: {} added/modified, {} deleted (historyId: {} -> {})",
-            emails.len(),
-            net_deleted.len(),
+            "[Gmail] History sync: +{} ~{} -{} (historyId: {} -> {})",
+            added.len(),
+            modified.len(),
+            deleted.len(),
             start_history_id,
             latest_history_id
         );
 
-        Ok((emails, net_deleted, latest_history_id))
+        Ok((added, modified, deleted, latest_history_id))
     }
 
     /// Get the latest historyId from the Gmail profile
@@ -326,9 +563,10 @@ impl GmailProvider {
             .client
             .get(format!("{}/users/me/profile", GMAIL_API_BASE))
             .bearer_auth(token)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await
             .ok()?;
+        let response = self.record_usage(response, gmail_units::GET_PROFILE);
 
         if !response.status().is_success() {
             return None;
@@ -341,6 +579,96 @@ impl GmailProvider {
             .map(|s| s.to_string())
     }
 
+    /// List the folder's messages, only those matching the search `query`
+    /// if given, and fetch them in full, newest first
+    async fn fetch_listed(
+        &self,
+        folder: &SyncFolder,
+        query: Option<String>,
+    ) -> SyncResult<Vec<SyncEmail>> {
+        let token = self
+            .access_token
+            .as_ref()
+            .ok_or_else(|| SyncError::AuthenticationError("Not authenticated".to_string()))?;
+
+        let max_results = 100;
+        let mut all_message_refs = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        let is_archive = folder.remote_id == ARCHIVE_FOLDER_ID;
+        let query = match query {
+            Some(query) if is_archive => Some(format!("{} {}", ARCHIVE_QUERY, query)),
+            None if is_archive => Some(ARCHIVE_QUERY.to_string()),
+            query => query,
+        };
+
+        loop {
+            let mut request = self
+                .client
+                .get(format!("{}/users/me/messages", GMAIL_API_BASE))
+                .bearer_auth(token)
+                .query(&[("maxResults", &max_results.to_string())]);
+
+            if !is_archive {
+                request = request.query(&[("labelIds", &folder.remote_id)]);
+            }
+            if let Some(ref q) = query {
+                request = request.query(&[("q", q)]);
+            }
+
+            if let Some(ref pt) = page_token {
+                request = request.query(&[("pageToken", pt)]);
+            }
+
+            let response = request
+                .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+                .await?;
+            let response = self.record_usage(response, gmail_units::MESSAGES_LIST);
+
+            if !response.status().is_success() {
+                return Err(SyncError::GmailError(format!(
+                    "Failed to fetch messages: {}",
+                    response.status()
+                )));
+            }
+
+            let messages_response: GmailMessagesResponse = response.json().await?;
+
+            if let Some(refs) = messages_response.messages {
+                all_message_refs.extend(refs);
+            }
+
+            match messages_response.next_page_token {
+                Some(next_token) => {
+                    log::debug!(
+                        "[Gmail] Fetching next page of messages for folder {} ({} so far)",
+                        folder.name,
+                        all_message_refs.len()
+                    );
+                    page_token = Some(next_token);
+                }
+                None => break,
+            }
+        }
+
+        log::info!(
+            "[Gmail] Found {} messages in folder {}",
+            all_message_refs.len(),
+            folder.name
+        );
+
+        let mut emails = Vec::new();
+
+        for msg_ref in all_message_refs {
+            match self.fetch_email(folder, &msg_ref.id).await {
+                Ok(email) => emails.push(email),
+                Err(e) => log::error!("Failed to fetch email {}: {}", msg_ref.id, e),
+            }
+        }
+
+        Ok(emails)
+    }
+
     fn map_label_to_folder_type(label_id: &str, label_name: &str) -> FolderType {
         match label_id {
             "INBOX" => FolderType::Inbox,
@@ -374,13 +702,14 @@ impl GmailProvider {
                 .parse(&decoded)
                 .ok_or_else(|| SyncError::ParseError("Failed to parse email".to_string()))?;
 
-            return Self::parse_mail_message(&message, msg, folder_id, account_id);
+            return Self::parse_mail_message(&decoded, &message, msg, folder_id, account_id);
         }
 
         Self::parse_from_payload(msg, folder_id, account_id)
     }
 
     fn parse_mail_message(
+        raw: &[u8],
         message: &mail_parser::Message,
         gmail_msg: &GmailMessage,
         folder_id: Uuid,
@@ -446,8 +775,17 @@ impl GmailProvider {
             });
 
         let subject = message.subject().map(|s| s.to_string());
-        let body_html = message.body_html(0).map(|s| s.to_string());
-        let body_plain = message.body_text(0).map(|s| s.to_string());
+
+        // Opaque signed messages carry their body inside the signature
+        let signature = smime::check_message(raw, message, &from.address);
+        let signed_content = signature
+            .as_ref()
+            .and_then(|check| check.content.as_deref())
+            .and_then(|content| MessageParser::default().parse(content));
+        let content = signed_content.as_ref().unwrap_or(message);
+
+        let body_html = content.body_html(0).map(|s| s.to_string());
+        let body_plain = content.body_text(0).map(|s| s.to_string());
 
         let message_id = message
             .message_id()
@@ -471,7 +809,7 @@ impl GmailProvider {
             .unwrap_or_default();
         let flags = normalize_gmail_flags(&label_ids);
 
-        let attachments: Vec<SyncAttachment> = message
+        let attachments: Vec<SyncAttachment> = content
             .attachments()
             .enumerate()
             .map(|(idx, att)| {
@@ -501,11 +839,12 @@ impl GmailProvider {
                     hash,
                     cache_path: None,
                     remote_url: None,
-                    remote_path: Some(format!("{}:{}", gmail_msg.id, idx)),
+                    // Fetched again from the raw message when opened
+                    remote_path: None,
                     is_inline,
                     is_cached: false,
                     content_id,
-                    data: None,
+                    data: Some(content.to_vec()),
                 }
             })
             .collect();
@@ -538,6 +877,8 @@ impl GmailProvider {
             attachments,
             change_key: None,
             last_modified_at: None,
+            signature,
+            remote_labels: gmail_msg.label_ids.clone(),
         })
     }
 
@@ -559,6 +900,8 @@ impl GmailProvider {
         let mut cc_addrs = Vec::new();
         let mut subject = None;
         let mut message_id = msg.id.clone();
+        let mut list_id = None;
+        let mut authentication_results = None;
 
         if let Some(headers) = &payload.headers {
             for header in headers {
@@ -578,11 +921,28 @@ impl GmailProvider {
                     "message-id" => {
                         message_id = header.value.clone();
                     }
+                    "list-id" => {
+                        list_id = Some(header.value.clone());
+                    }
+                    "authentication-results" if authentication_results.is_none() => {
+                        authentication_results = Some(header.value.clone());
+                    }
                     _ => {}
                 }
             }
         }
 
+        let mut headers_map = serde_json::Map::new();
+        if let Some(list_id) = list_id {
+            headers_map.insert("List-Id".to_string(), serde_json::Value::String(list_id));
+        }
+        if let Some(results) = authentication_results {
+            headers_map.insert(
+                "Authentication-Results".to_string(),
+                serde_json::Value::String(results),
+            );
+        }
+
         let received_at = if let Some(date_str) = &msg.internal_date {
             let millis: i64 = date_str.parse().unwrap_or(0);
             DateTime::from_timestamp_millis(millis).unwrap_or_else(|| Utc::now())
@@ -597,7 +957,14 @@ impl GmailProvider {
             .unwrap_or_default();
         let flags = normalize_gmail_flags(&label_ids);
 
-        let (body_plain, body_html, attachments) = Self::extract_parts(payload);
+        let (body_plain, body_html, mut attachments) = Self::extract_parts(payload);
+        for attachment in &mut attachments {
+            // fetch_attachment expects 'message_id:attachment_id'
+            attachment.remote_path = attachment
+                .remote_path
+                .take()
+                .map(|attachment_id| format!("{}:{}", msg.id, attachment_id));
+        }
 
         Ok(SyncEmail {
             id: None,
@@ -621,12 +988,14 @@ impl GmailProvider {
             received_at,
             sent_at: None,
             flags,
-            headers: None,
+            headers: (!headers_map.is_empty()).then(|| serde_json::Value::Object(headers_map)),
             size: msg.size_estimate.unwrap_or(0),
             has_attachments: !attachments.is_empty(),
             attachments,
             change_key: None,
             last_modified_at: None,
+            signature: None,
+            remote_labels: msg.label_ids.clone(),
         })
     }
 
@@ -764,48 +1133,381 @@ impl GmailProvider {
 
         (body_plain, body_html, attachments)
     }
-}
 
-#[async_trait]
-impl EmailProvider for GmailProvider {
-    fn name(&self) -> &str {
-        "Gmail"
-    }
+    /// Fetches birthdays and anniversaries from the user's Google contacts
+    ///
+    /// Requires the contacts.readonly scope; accounts connected before it was
+    /// requested get a 403 and have to be re-authorized.
+    pub async fn fetch_contact_dates(&mut self) -> SyncResult<Vec<SyncContactDates>> {
+        let token = self._ensure_token().await?;
+        let mut contacts = Vec::new();
+        let mut page_token: Option<String> = None;
 
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
+        loop {
+            let mut request = self
+                .client
+                .get(format!("{}/people/me/connections", PEOPLE_API_BASE))
+                .bearer_auth(&token)
+                .query(&[
+                    ("personFields", "names,emailAddresses,birthdays,events"),
+                    ("pageSize", "1000"),
+                ]);
+            if let Some(page_token) = &page_token {
+                request = request.query(&[("pageToken", page_token)]);
+            }
 
-    async fn authenticate(&mut self, credentials: ProviderCredentials) -> SyncResult<()> {
-        match credentials {
-            ProviderCredentials::OAuth2(creds) => {
-                self.access_token = Some(creds.access_token.clone());
-                self.credential_store
-                    .store_oauth2(self.account_id, &creds)
-                    .await?;
-                Ok(())
+            let response = request
+                .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+                .await?;
+            // The People API has its own quota and does not consume Gmail units
+            let response = self.record_usage(response, 0);
+
+            if !response.status().is_success() {
+                return Err(SyncError::GmailError(format!(
+                    "Failed to fetch contacts: {}",
+                    response.status()
+                )));
+            }
+
+            let page: PeopleConnectionsResponse = response.json().await?;
+
+            for person in page.connections {
+                let birthday = person
+                    .birthdays
+                    .iter()
+                    .filter_map(|b| b.date.as_ref())
+                    .find_map(format_people_date);
+                let anniversary = person
+                    .events
+                    .iter()
+                    .filter(|e| e.event_type.as_deref() == Some("anniversary"))
+                    .filter_map(|e| e.date.as_ref())
+                    .find_map(format_people_date);
+
+                if birthday.is_none() && anniversary.is_none() {
+                    continue;
+                }
+
+                let display_name = person.names.iter().find_map(|n| n.display_name.clone());
+                for email in person
+                    .email_addresses
+                    .iter()
+                    .filter_map(|e| e.value.as_ref())
+                {
+                    contacts.push(SyncContactDates {
+                        email: email.to_lowercase(),
+                        display_name: display_name.clone(),
+                        birthday: birthday.clone(),
+                        anniversary: anniversary.clone(),
+                    });
+                }
+            }
+
+            match page.next_page_token {
+                Some(next) => page_token = Some(next),
+                None => break,
             }
-            _ => Err(SyncError::InvalidConfiguration(
-                "Gmail provider requires OAuth2 credentials".to_string(),
-            )),
         }
+
+        Ok(contacts)
     }
 
-    async fn test_connection(&self) -> SyncResult<bool> {
-        if self.access_token.is_none() {
-            return Ok(false);
-        }
+    /// Fetches the account owner's name, photo and locale
+    ///
+    /// Requires the userinfo.profile scope. Gmail exposes no mailbox timezone,
+    /// so `timezone` is always `None`.
+    pub async fn fetch_owner_profile(&mut self) -> SyncResult<SyncOwnerProfile> {
+        let token = self._ensure_token().await?;
 
-        let token = self.access_token.as_ref().unwrap();
         let response = self
             .client
-            .get(format!("{}/users/me/profile", GMAIL_API_BASE))
-            .bearer_auth(token)
-            .send()
+            .get(format!("{}/people/me", PEOPLE_API_BASE))
+            .bearer_auth(&token)
+            .query(&[("personFields", "names,photos,locales")])
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, 0);
 
-        Ok(response.status().is_success())
-    }
+        if !response.status().is_success() {
+            return Err(SyncError::GmailError(format!(
+                "Failed to fetch profile: {}",
+                response.status()
+            )));
+        }
+
+        let person: PeopleProfile = response.json().await?;
+        let mut profile = SyncOwnerProfile {
+            display_name: person.names.iter().find_map(|n| n.display_name.clone()),
+            locale: person.locales.iter().find_map(|l| l.value.clone()),
+            ..Default::default()
+        };
+
+        if profile.locale.is_none() {
+            let response = self
+                .client
+                .get(format!("{}/users/me/settings/language", GMAIL_API_BASE))
+                .bearer_auth(&token)
+                .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+                .await?;
+            let response = self.record_usage(response, 1);
+
+            if response.status().is_success() {
+                let settings: GmailLanguageSettings = response.json().await?;
+                profile.locale = settings.display_language;
+            }
+        }
+
+        if let Some(url) = person
+            .photos
+            .iter()
+            .filter(|p| !p.default)
+            .find_map(|p| p.url.as_ref())
+        {
+            let response = self.client.get(url).send().await?;
+            if response.status().is_success() {
+                let content_type = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("image/jpeg")
+                    .to_string();
+                profile.photo = Some((response.bytes().await?.to_vec(), content_type));
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Reads the message count from the Gmail profile
+    ///
+    /// Gmail shares its storage with Drive and Photos and only reports it
+    /// through the Drive API, so storage usage and limit are always `None`.
+    pub async fn fetch_quota(&mut self) -> SyncResult<SyncMailboxQuota> {
+        let token = self._ensure_token().await?;
+
+        let response = self
+            .client
+            .get(format!("{}/users/me/profile", GMAIL_API_BASE))
+            .bearer_auth(&token)
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+            .await?;
+        let response = self.record_usage(response, gmail_units::GET_PROFILE);
+
+        if !response.status().is_success() {
+            return Err(SyncError::GmailError(format!(
+                "Failed to fetch profile: {}",
+                response.status()
+            )));
+        }
+
+        let profile: GmailProfile = response.json().await?;
+        Ok(SyncMailboxQuota {
+            message_count: profile.messages_total,
+            ..Default::default()
+        })
+    }
+
+    /// Lists the verified sendAs aliases, without the primary address
+    pub async fn fetch_send_as(&mut self) -> SyncResult<Vec<SyncIdentity>> {
+        let token = self._ensure_token().await?;
+
+        let response = self
+            .client
+            .get(format!("{}/users/me/settings/sendAs", GMAIL_API_BASE))
+            .bearer_auth(&token)
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+            .await?;
+        let response = self.record_usage(response, gmail_units::SEND_AS_LIST);
+
+        if !response.status().is_success() {
+            return Err(SyncError::GmailError(format!(
+                "Failed to list sendAs aliases: {}",
+                response.status()
+            )));
+        }
+
+        let send_as: GmailSendAsResponse = response.json().await?;
+        Ok(send_as
+            .send_as
+            .into_iter()
+            .filter(|alias| {
+                !alias.is_primary && alias.verification_status.as_deref() == Some("accepted")
+            })
+            .map(|alias| SyncIdentity {
+                email: alias.send_as_email,
+                name: alias.display_name.filter(|name| !name.is_empty()),
+            })
+            .collect())
+    }
+
+    /// Lists the labels created by the user, which are synced as labels rather
+    /// than folders
+    pub async fn fetch_user_labels(&mut self) -> SyncResult<Vec<SyncLabel>> {
+        let token = self._ensure_token().await?;
+
+        let response = self
+            .client
+            .get(format!("{}/users/me/labels", GMAIL_API_BASE))
+            .bearer_auth(&token)
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+            .await?;
+        let response = self.record_usage(response, gmail_units::LABELS_LIST);
+
+        if !response.status().is_success() {
+            return Err(SyncError::GmailError(format!(
+                "Failed to fetch labels: {}",
+                response.status()
+            )));
+        }
+
+        let labels: GmailLabelsResponse = response.json().await?;
+        Ok(labels
+            .labels
+            .into_iter()
+            .filter(|label| label.label_type.as_deref() == Some("user"))
+            .map(|label| SyncLabel {
+                remote_id: label.id,
+                name: label.name,
+                color: label.color.and_then(|color| color.background_color),
+            })
+            .collect())
+    }
+
+    /// Adds and removes labels on a message
+    pub async fn modify_labels(
+        &self,
+        email_remote_id: &str,
+        add_label_ids: Vec<String>,
+        remove_label_ids: Vec<String>,
+    ) -> SyncResult<()> {
+        let token = self
+            .access_token
+            .as_ref()
+            .ok_or_else(|| SyncError::AuthenticationError("Not authenticated".to_string()))?;
+
+        #[derive(Serialize)]
+        struct ModifyRequest {
+            #[serde(rename = "addLabelIds")]
+            add_label_ids: Vec<String>,
+            #[serde(rename = "removeLabelIds")]
+            remove_label_ids: Vec<String>,
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/users/me/messages/{}/modify",
+                GMAIL_API_BASE, email_remote_id
+            ))
+            .bearer_auth(token)
+            .json(&ModifyRequest {
+                add_label_ids,
+                remove_label_ids,
+            })
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+            .await?;
+        let response = self.record_usage(response, gmail_units::MESSAGES_MODIFY);
+
+        if !response.status().is_success() {
+            return Err(SyncError::GmailError(format!(
+                "Failed to modify message labels: {}",
+                response.status()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Draft ID of the draft whose current message is `message_remote_id`.
+    /// Drafts are addressed by their own ID, while sync only sees messages.
+    async fn find_draft_id(
+        &self,
+        token: &str,
+        message_remote_id: &str,
+    ) -> SyncResult<Option<String>> {
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut request = self
+                .client
+                .get(format!("{}/users/me/drafts", GMAIL_API_BASE))
+                .bearer_auth(token)
+                .query(&[("maxResults", "500")]);
+            if let Some(ref pt) = page_token {
+                request = request.query(&[("pageToken", pt)]);
+            }
+
+            let response = request
+                .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+                .await?;
+            let response = self.record_usage(response, gmail_units::DRAFTS_LIST);
+
+            if !response.status().is_success() {
+                return Err(SyncError::GmailError(format!(
+                    "Failed to list drafts: {}",
+                    response.status()
+                )));
+            }
+
+            let drafts_response: GmailDraftsResponse = response.json().await?;
+            if let Some(draft) = drafts_response
+                .drafts
+                .unwrap_or_default()
+                .into_iter()
+                .find(|draft| draft.message.id == message_remote_id)
+            {
+                return Ok(Some(draft.id));
+            }
+
+            match drafts_response.next_page_token {
+                Some(next_token) => page_token = Some(next_token),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EmailProvider for GmailProvider {
+    fn name(&self) -> &str {
+        "Gmail"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    async fn authenticate(&mut self, credentials: ProviderCredentials) -> SyncResult<()> {
+        match credentials {
+            ProviderCredentials::OAuth2(creds) => {
+                self.access_token = Some(creds.access_token.clone());
+                self.credential_store
+                    .store_oauth2(self.account_id, &creds)
+                    .await?;
+                Ok(())
+            }
+            _ => Err(SyncError::InvalidConfiguration(
+                "Gmail provider requires OAuth2 credentials".to_string(),
+            )),
+        }
+    }
+
+    async fn test_connection(&self) -> SyncResult<bool> {
+        if self.access_token.is_none() {
+            return Ok(false);
+        }
+
+        let token = self.access_token.as_ref().unwrap();
+        let response = self
+            .client
+            .get(format!("{}/users/me/profile", GMAIL_API_BASE))
+            .bearer_auth(token)
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+            .await?;
+        let response = self.record_usage(response, gmail_units::GET_PROFILE);
+
+        Ok(response.status().is_success())
+    }
 
     async fn fetch_folders(&self) -> SyncResult<Vec<SyncFolder>> {
         let token = self
@@ -817,8 +1519,9 @@ impl EmailProvider for GmailProvider {
             .client
             .get(format!("{}/users/me/labels", GMAIL_API_BASE))
             .bearer_auth(token)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, gmail_units::LABELS_LIST);
 
         if !response.status().is_success() {
             return Err(SyncError::GmailError(format!(
@@ -829,19 +1532,10 @@ impl EmailProvider for GmailProvider {
 
         let labels: GmailLabelsResponse = response.json().await?;
 
-        let folders = labels
+        let mut folders: Vec<SyncFolder> = labels
             .labels
             .into_iter()
-            .filter(|label| {
-                !matches!(
-                    label.id.as_str(),
-                    "CATEGORY_PERSONAL"
-                        | "CATEGORY_SOCIAL"
-                        | "CATEGORY_UPDATES"
-                        | "CATEGORY_FORUMS"
-                        | "CATEGORY_PROMOTIONS"
-                )
-            })
+            .filter(|label| FOLDER_LABEL_IDS.contains(&label.id.as_str()))
             .map(|label| {
                 let folder_type = Self::map_label_to_folder_type(&label.id, &label.name);
 
@@ -865,6 +1559,24 @@ impl EmailProvider for GmailProvider {
             })
             .collect();
 
+        folders.push(SyncFolder {
+            id: None,
+            account_id: self.account_id,
+            name: "Archive".to_string(),
+            folder_type: FolderType::Archive,
+            remote_id: ARCHIVE_FOLDER_ID.to_string(),
+            parent_id: None,
+            icon: None,
+            color: None,
+            sync_interval: 0,
+            synced_at: None,
+            attributes: Vec::new(),
+            unread_count: 0,
+            total_count: 0,
+            expanded: false,
+            hidden: false,
+        });
+
         Ok(folders)
     }
 
@@ -876,13 +1588,14 @@ impl EmailProvider for GmailProvider {
         // Delta sync via History API when we have a historyId
         if let Some(ref history_id) = sync_token {
             match self.sync_history(folder, history_id).await {
-                Ok((emails, deleted, new_history_id)) => {
+                Ok((added, modified, deleted, new_history_id)) => {
                     return Ok(crate::sync::types::SyncDiff {
-                        added: emails,
-                        modified: Vec::new(),
+                        added,
+                        modified,
                         deleted,
                         next_sync_token: Some(new_history_id),
                         is_complete: false, // Delta sync is not a complete enumeration
+                        uid_validity: None,
                     });
                 }
                 Err(SyncError::SyncTokenExpired(_)) => {
@@ -898,72 +1611,13 @@ impl EmailProvider for GmailProvider {
         }
 
         // Full sync: paginate through all messages
+        let emails = self.fetch_listed(folder, None).await?;
+
         let token = self
             .access_token
             .as_ref()
             .ok_or_else(|| SyncError::AuthenticationError("Not authenticated".to_string()))?;
 
-        let max_results = 100;
-        let mut all_message_refs = Vec::new();
-        let mut page_token: Option<String> = None;
-
-        loop {
-            let mut request = self
-                .client
-                .get(format!("{}/users/me/messages", GMAIL_API_BASE))
-                .bearer_auth(token)
-                .query(&[
-                    ("labelIds", &folder.remote_id),
-                    ("maxResults", &max_results.to_string()),
-                ]);
-
-            if let Some(ref pt) = page_token {
-                request = request.query(&[("pageToken", pt)]);
-            }
-
-            let response = request.send().await?;
-
-            if !response.status().is_success() {
-                return Err(SyncError::GmailError(format!(
-                    "Failed to fetch messages: {}",
-                    response.status()
-                )));
-            }
-
-            let messages_response: GmailMessagesResponse = response.json().await?;
-
-            if let Some(refs) = messages_response.messages {
-                all_message_refs.extend(refs);
-            }
-
-            match messages_response.next_page_token {
-                Some(next_token) => {
-                    log::debug!(
-                        "[Gmail] Fetching next page of messages for folder {} ({} so far)",
-                        folder.name,
-                        all_message_refs.len()
-                    );
-                    page_token = Some(next_token);
-                }
-                None => break,
-            }
-        }
-
-        log::info!(
-            "[Gmail] Found {} messages in folder {}",
-            all_message_refs.len(),
-            folder.name
-        );
-
-        let mut emails = Vec::new();
-
-        for msg_ref in all_message_refs {
-            match self.fetch_email(folder, &msg_ref.id).await {
-                Ok(email) => emails.push(email),
-                Err(e) => log::error!("Failed to fetch email {}: {}", msg_ref.id, e),
-            }
-        }
-
         // Get latest historyId from profile for future delta sync
         let latest_history_id = self.get_profile_history_id(token).await;
 
@@ -973,9 +1627,46 @@ impl EmailProvider for GmailProvider {
             deleted: Vec::new(),
             next_sync_token: latest_history_id,
             is_complete: true,
+            uid_validity: None,
         })
     }
 
+    async fn sync_recent_messages(
+        &self,
+        folder: &SyncFolder,
+        since: DateTime<Utc>,
+    ) -> SyncResult<crate::sync::types::SyncDiff> {
+        let token = self
+            .access_token
+            .as_ref()
+            .ok_or_else(|| SyncError::AuthenticationError("Not authenticated".to_string()))?;
+
+        // Taken first, so the next delta sync covers mail arriving meanwhile
+        let history_id = self.get_profile_history_id(token).await;
+        let query = window_query(&SyncWindow {
+            after: Some(since),
+            before: None,
+        });
+        let emails = self.fetch_listed(folder, Some(query)).await?;
+
+        Ok(crate::sync::types::SyncDiff {
+            added: emails,
+            modified: Vec::new(),
+            deleted: Vec::new(),
+            next_sync_token: history_id,
+            is_complete: false,
+            uid_validity: None,
+        })
+    }
+
+    async fn fetch_window(
+        &self,
+        folder: &SyncFolder,
+        window: &SyncWindow,
+    ) -> SyncResult<Vec<SyncEmail>> {
+        self.fetch_listed(folder, Some(window_query(window))).await
+    }
+
     async fn fetch_email(&self, folder: &SyncFolder, remote_id: &str) -> SyncResult<SyncEmail> {
         let token = self
             .access_token
@@ -990,14 +1681,15 @@ impl EmailProvider for GmailProvider {
             ))
             .bearer_auth(token)
             .query(&[("format", "full")])
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, gmail_units::MESSAGES_GET);
 
         if !response.status().is_success() {
-            return Err(SyncError::GmailError(format!(
-                "Failed to fetch message: {}",
-                response.status()
-            )));
+            return Err(SyncError::GmailStatus {
+                status: response.status(),
+                message: "Failed to fetch message".to_string(),
+            });
         }
 
         let message: GmailMessage = response.json().await?;
@@ -1031,8 +1723,9 @@ impl EmailProvider for GmailProvider {
                 GMAIL_API_BASE, message_id, attachment_id
             ))
             .bearer_auth(token)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, gmail_units::ATTACHMENTS_GET);
 
         if !response.status().is_success() {
             return Err(SyncError::GmailError(format!(
@@ -1060,43 +1753,22 @@ impl EmailProvider for GmailProvider {
         from_folder: &SyncFolder,
         to_folder: &SyncFolder,
     ) -> SyncResult<()> {
-        let token = self
-            .access_token
-            .as_ref()
-            .ok_or_else(|| SyncError::AuthenticationError("Not authenticated".to_string()))?;
-
-        #[derive(Serialize)]
-        struct ModifyRequest {
-            #[serde(rename = "addLabelIds")]
-            add_label_ids: Vec<String>,
-            #[serde(rename = "removeLabelIds")]
-            remove_label_ids: Vec<String>,
-        }
-
-        let request = ModifyRequest {
-            add_label_ids: vec![to_folder.remote_id.clone()],
-            remove_label_ids: vec![from_folder.remote_id.clone()],
+        // Archiving removes the source label without adding one, and moving out
+        // of the archive only adds the target label
+        let label_ids = |folder: &SyncFolder| {
+            if folder.remote_id == ARCHIVE_FOLDER_ID {
+                Vec::new()
+            } else {
+                vec![folder.remote_id.clone()]
+            }
         };
 
-        let response = self
-            .client
-            .post(format!(
-                "{}/users/me/messages/{}/modify",
-                GMAIL_API_BASE, email_remote_id
-            ))
-            .bearer_auth(token)
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(SyncError::GmailError(format!(
-                "Failed to move message: {}",
-                response.status()
-            )));
-        }
-
-        Ok(())
+        self.modify_labels(
+            email_remote_id,
+            label_ids(to_folder),
+            label_ids(from_folder),
+        )
+        .await
     }
 
     async fn delete_email(
@@ -1123,8 +1795,14 @@ impl EmailProvider for GmailProvider {
             .client
             .delete(&endpoint)
             .bearer_auth(token)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let quota_units = if permanent {
+            gmail_units::MESSAGES_DELETE
+        } else {
+            gmail_units::MESSAGES_TRASH
+        };
+        let response = self.record_usage(response, quota_units);
 
         if !response.status().is_success() {
             return Err(SyncError::GmailError(format!(
@@ -1175,8 +1853,9 @@ impl EmailProvider for GmailProvider {
             ))
             .bearer_auth(token)
             .json(&request)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, gmail_units::MESSAGES_MODIFY);
 
         if !response.status().is_success() {
             return Err(SyncError::GmailError(format!(
@@ -1227,8 +1906,9 @@ impl EmailProvider for GmailProvider {
             ))
             .bearer_auth(token)
             .json(&request)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, gmail_units::MESSAGES_MODIFY);
 
         if !response.status().is_success() {
             return Err(SyncError::GmailError(format!(
@@ -1265,8 +1945,9 @@ impl EmailProvider for GmailProvider {
             ))
             .bearer_auth(token)
             .json(&request)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, gmail_units::LABELS_UPDATE);
 
         if !response.status().is_success() {
             return Err(SyncError::GmailError(format!(
@@ -1289,6 +1970,63 @@ impl EmailProvider for GmailProvider {
         ))
     }
 
+    async fn save_draft(
+        &self,
+        _drafts_folder: &SyncFolder,
+        mime: Vec<u8>,
+        replaces: Option<&str>,
+    ) -> SyncResult<String> {
+        let token = self
+            .access_token
+            .as_ref()
+            .ok_or_else(|| SyncError::AuthenticationError("Not authenticated".to_string()))?;
+
+        let body = serde_json::json!({
+            "message": { "raw": general_purpose::URL_SAFE.encode(&mime) },
+        });
+
+        // Updating keeps the draft ID stable for other clients; a draft that is
+        // gone on the server is created again
+        let draft_id = match replaces {
+            Some(message_remote_id) => self.find_draft_id(token, message_remote_id).await?,
+            None => None,
+        };
+        let response = match &draft_id {
+            Some(draft_id) => {
+                let response = self
+                    .client
+                    .put(format!("{}/users/me/drafts/{}", GMAIL_API_BASE, draft_id))
+                    .bearer_auth(token)
+                    .json(&body)
+                    .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+                    .await?;
+                let response = self.record_usage(response, gmail_units::DRAFTS_UPDATE);
+                response
+            }
+            None => {
+                let response = self
+                    .client
+                    .post(format!("{}/users/me/drafts", GMAIL_API_BASE))
+                    .bearer_auth(token)
+                    .json(&body)
+                    .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
+                    .await?;
+                let response = self.record_usage(response, gmail_units::DRAFTS_CREATE);
+                response
+            }
+        };
+
+        if !response.status().is_success() {
+            return Err(SyncError::GmailError(format!(
+                "Failed to save draft: {}",
+                response.status()
+            )));
+        }
+
+        let draft: GmailDraft = response.json().await?;
+        Ok(draft.message.id)
+    }
+
     async fn get_sync_token(&self) -> SyncResult<Option<String>> {
         let token = self
             .access_token
@@ -1299,8 +2037,9 @@ impl EmailProvider for GmailProvider {
             .client
             .get(format!("{}/users/me/profile", GMAIL_API_BASE))
             .bearer_auth(token)
-            .send()
+            .send_rate_limited(sync_limits::GMAIL_HOST, self.account_id)
             .await?;
+        let response = self.record_usage(response, gmail_units::GET_PROFILE);
 
         if !response.status().is_success() {
             return Err(SyncError::GmailError(format!(
@@ -1330,3 +2069,99 @@ impl EmailProvider for GmailProvider {
         Ok(Vec::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_people_date() {
+        let full = PeopleDate {
+            year: Some(1985),
+            month: Some(5),
+            day: Some(4),
+        };
+        assert_eq!(format_people_date(&full).as_deref(), Some("1985-05-04"));
+
+        let no_year = PeopleDate {
+            year: None,
+            month: Some(12),
+            day: Some(24),
+        };
+        assert_eq!(format_people_date(&no_year).as_deref(), Some("--12-24"));
+
+        let no_day = PeopleDate {
+            year: Some(2000),
+            month: Some(1),
+            day: None,
+        };
+        assert_eq!(format_people_date(&no_day), None);
+    }
+
+    #[test]
+    fn test_is_archived() {
+        let labels = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        assert!(is_archived(&labels(&[])));
+        assert!(is_archived(&labels(&["Label_1", "STARRED", "UNREAD"])));
+        assert!(!is_archived(&labels(&["INBOX", "Label_1"])));
+        assert!(!is_archived(&labels(&["SENT"])));
+        assert!(!is_archived(&labels(&["TRASH", "Label_1"])));
+    }
+
+    fn record(json: serde_json::Value) -> GmailHistoryRecord {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_classify_history_added_modified_deleted() {
+        let records = vec![
+            record(serde_json::json!({
+                "messagesAdded": [{ "message": { "id": "new" } }],
+                "labelsRemoved": [{ "message": { "id": "read" }, "labelIds": ["UNREAD"] }]
+            })),
+            record(serde_json::json!({
+                "labelsRemoved": [{ "message": { "id": "moved" }, "labelIds": ["INBOX"] }],
+                "labelsAdded": [{ "message": { "id": "new" }, "labelIds": ["STARRED"] }]
+            })),
+        ];
+
+        let (added, modified, deleted) = classify_history(&records, "INBOX");
+        assert_eq!(added, vec!["new".to_string()]);
+        assert_eq!(modified, vec!["read".to_string()]);
+        assert_eq!(deleted, vec!["moved".to_string()]);
+    }
+
+    #[test]
+    fn test_classify_history_last_change_wins() {
+        let records = vec![
+            record(serde_json::json!({
+                "messagesAdded": [{ "message": { "id": "a" } }]
+            })),
+            record(serde_json::json!({
+                "messagesDeleted": [{ "message": { "id": "a" } }],
+                "labelsRemoved": [{ "message": { "id": "b" }, "labelIds": ["INBOX"] }]
+            })),
+            record(serde_json::json!({
+                "labelsAdded": [{ "message": { "id": "b" }, "labelIds": ["INBOX"] }]
+            })),
+        ];
+
+        let (added, modified, deleted) = classify_history(&records, "INBOX");
+        assert_eq!(added, vec!["b".to_string()]);
+        assert!(modified.is_empty());
+        assert_eq!(deleted, vec!["a".to_string()]);
+    }
+}
+
+/// Search terms for messages received within `window`
+fn window_query(window: &SyncWindow) -> String {
+    let mut terms = Vec::new();
+    if let Some(after) = window.after {
+        terms.push(format!("after:{}", after.timestamp()));
+    }
+    if let Some(before) = window.before {
+        terms.push(format!("before:{}", before.timestamp()));
+    }
+    terms.join(" ")
+}
@@ -0,0 +1,131 @@
+//! JSON batching of Microsoft Graph requests. Up to 20 requests go out in a
+//! single `$batch` call and each comes back with its own status, so one
+//! failed or throttled request does not fail the others.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Requests Graph accepts in a single batch
+pub const MAX_BATCH_SIZE: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchRequest {
+    pub id: String,
+    pub method: &'static str,
+    /// Relative to the API version, e.g. `/me/messages/{id}`
+    pub url: String,
+}
+
+impl BatchRequest {
+    pub fn get(id: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            method: "GET",
+            url: url.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchRequestBody<'a> {
+    pub requests: &'a [BatchRequest],
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchResponseBody {
+    pub responses: Vec<BatchResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchResponse {
+    pub id: String,
+    pub status: u16,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: serde_json::Value,
+}
+
+impl BatchResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Whether the request was throttled or failed transiently, and can be
+    /// sent again
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.status, 429 | 503 | 504)
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+            .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+
+    /// Code and message of a Graph error body
+    pub fn error_message(&self) -> String {
+        let error = &self.body["error"];
+        format!(
+            "{}: {}",
+            error["code"].as_str().unwrap_or("unknown"),
+            error["message"].as_str().unwrap_or("no error message")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch_response() {
+        let body: BatchResponseBody = serde_json::from_str(
+            r#"{
+                "responses": [
+                    { "id": "1", "status": 200, "body": { "value": [] } },
+                    {
+                        "id": "0",
+                        "status": 429,
+                        "headers": { "Retry-After": "7" },
+                        "body": { "error": { "code": "TooManyRequests", "message": "Slow down" } }
+                    },
+                    {
+                        "id": "2",
+                        "status": 404,
+                        "body": { "error": { "code": "ErrorItemNotFound", "message": "Not found" } }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let [ok, throttled, missing] = &body.responses[..] else {
+            panic!("expected three responses");
+        };
+        assert!(ok.is_success());
+        assert!(throttled.is_retryable());
+        assert_eq!(throttled.retry_after(), Some(Duration::from_secs(7)));
+        assert_eq!(throttled.error_message(), "TooManyRequests: Slow down");
+        assert!(!missing.is_success() && !missing.is_retryable());
+        assert_eq!(missing.retry_after(), None);
+    }
+
+    #[test]
+    fn test_serialize_batch_request() {
+        let requests = [BatchRequest::get("0", "/me/messages/abc/attachments")];
+        assert_eq!(
+            serde_json::to_value(BatchRequestBody {
+                requests: &requests
+            })
+            .unwrap(),
+            serde_json::json!({
+                "requests": [
+                    { "id": "0", "method": "GET", "url": "/me/messages/abc/attachments" }
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! iCloud Mail profile for the IMAP provider.
+//!
+//! iCloud is plain IMAP/SMTP, but it differs from generic servers in a few ways:
+//! it only accepts app-specific passwords, it names its special folders
+//! "Sent Messages"/"Deleted Messages" without SPECIAL-USE hints on older
+//! accounts, and it exposes a "Notes" mailbox that is not mail at all.
+
+use crate::sync::error::SyncError;
+use crate::sync::types::{AccountSettings, FolderType};
+
+pub const IMAP_HOST: &str = "imap.mail.me.com";
+pub const IMAP_PORT: u16 = 993;
+pub const SMTP_HOST: &str = "smtp.mail.me.com";
+pub const SMTP_PORT: u16 = 587;
+
+const ICLOUD_DOMAINS: &[&str] = &["icloud.com", "me.com", "mac.com"];
+const APP_PASSWORD_URL: &str = "https://account.apple.com";
+
+/// Whether an address belongs to an Apple-hosted mail domain
+pub fn is_icloud_address(email: &str) -> bool {
+    email
+        .rsplit_once('@')
+        .map(|(_, domain)| ICLOUD_DOMAINS.contains(&domain.trim().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Fill in iCloud's server settings, keeping anything the user set explicitly
+pub fn apply_server_presets(settings: &mut AccountSettings, email: &str) {
+    settings
+        .imap_host
+        .get_or_insert_with(|| IMAP_HOST.to_string());
+    settings.imap_port.get_or_insert(IMAP_PORT);
+    settings.imap_use_tls = Some(true);
+    settings
+        .imap_username
+        .get_or_insert_with(|| email.to_string());
+
+    settings
+        .smtp_host
+        .get_or_insert_with(|| SMTP_HOST.to_string());
+    settings.smtp_port.get_or_insert(SMTP_PORT);
+    settings.smtp_use_tls = Some(true);
+    settings
+        .smtp_username
+        .get_or_insert_with(|| email.to_string());
+}
+
+/// Validate and normalize an app-specific password.
+///
+/// Apple shows these as `abcd-efgh-ijkl-mnop`; users often paste them with
+/// spaces or without dashes, so both forms are accepted and normalized.
+pub fn normalize_app_password(password: &str) -> Result<String, SyncError> {
+    let letters: String = password
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_lowercase();
+
+    if letters.len() != 16 || !letters.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(SyncError::AuthenticationError(format!(
+            "iCloud Mail requires an app-specific password (format xxxx-xxxx-xxxx-xxxx). \
+             Your Apple Account password will not work; generate one under \
+             Sign-In and Security > App-Specific Passwords at {}",
+            APP_PASSWORD_URL
+        )));
+    }
+
+    Ok(letters
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join("-"))
+}
+
+/// Replace a login failure with a message that points at app-specific passwords
+pub fn map_authentication_error(error: SyncError) -> SyncError {
+    match error {
+        SyncError::AuthenticationError(details) => SyncError::AuthenticationError(format!(
+            "iCloud rejected the login. Make sure you are using an app-specific password \
+             generated at {} and that two-factor authentication is enabled ({})",
+            APP_PASSWORD_URL, details
+        )),
+        other => other,
+    }
+}
+
+/// Map an iCloud mailbox to a folder type and visibility.
+///
+/// `name` must already be decoded from modified UTF-7. Returns `None` when the
+/// mailbox is not one of Apple's well-known folders, so the generic
+/// heuristics can take over.
+pub fn map_folder(name: &str) -> Option<(FolderType, bool)> {
+    match name.trim() {
+        n if n.eq_ignore_ascii_case("INBOX") => Some((FolderType::Inbox, false)),
+        "Sent Messages" => Some((FolderType::Sent, false)),
+        "Drafts" => Some((FolderType::Draft, false)),
+        "Deleted Messages" => Some((FolderType::Trash, false)),
+        "Junk" => Some((FolderType::Spam, false)),
+        "Archive" => Some((FolderType::Archive, false)),
+        // Apple Notes synced over IMAP; not useful as a mail folder
+        "Notes" => Some((FolderType::Custom, true)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_app_password() {
+        assert_eq!(
+            normalize_app_password("abcd-efgh-ijkl-mnop").unwrap(),
+            "abcd-efgh-ijkl-mnop"
+        );
+        assert_eq!(
+            normalize_app_password(" ABCD EFGH ijkl mnop ").unwrap(),
+            "abcd-efgh-ijkl-mnop"
+        );
+        assert_eq!(
+            normalize_app_password("abcdefghijklmnop").unwrap(),
+            "abcd-efgh-ijkl-mnop"
+        );
+        assert!(normalize_app_password("hunter2").is_err());
+        assert!(normalize_app_password("abcd-efgh-ijkl-mno1").is_err());
+    }
+
+    #[test]
+    fn test_map_folder() {
+        assert!(matches!(
+            map_folder("Sent Messages"),
+            Some((FolderType::Sent, false))
+        ));
+        assert!(matches!(
+            map_folder("Deleted Messages"),
+            Some((FolderType::Trash, false))
+        ));
+        assert!(matches!(map_folder("Notes"), Some((_, true))));
+        assert!(map_folder("Receipts").is_none());
+    }
+
+    #[test]
+    fn test_apply_server_presets_keeps_overrides() {
+        let mut settings = AccountSettings {
+            imap_username: Some("jane".to_string()),
+            ..AccountSettings::default()
+        };
+        apply_server_presets(&mut settings, "jane@icloud.com");
+
+        assert_eq!(settings.imap_host.as_deref(), Some(IMAP_HOST));
+        assert_eq!(settings.imap_username.as_deref(), Some("jane"));
+        assert_eq!(settings.smtp_port, Some(SMTP_PORT));
+        assert_eq!(settings.smtp_username.as_deref(), Some("jane@icloud.com"));
+        assert!(is_icloud_address("Jane@ME.com"));
+        assert!(!is_icloud_address("jane@example.com"));
+    }
+}
@@ -1,6 +1,7 @@
 use async_compat::CompatExt;
-use async_imap::imap_proto::Address;
-use async_imap::types::{Fetch, Flag};
+use async_imap::extensions::idle::IdleResponse;
+use async_imap::imap_proto::{Address, BodyParams, BodyStructure, ContentEncoding, Response};
+use async_imap::types::{Fetch, Flag, QuotaResourceName, UnsolicitedResponse};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use futures::TryStreamExt;
@@ -13,25 +14,36 @@ use tokio_native_tls::TlsStream;
 use uuid::Uuid;
 
 use crate::database::models::email::EmailAddress;
+use crate::services::smime::{self, SignatureCheck};
 use crate::sync::{
+    attachment_handler::STRUCTURE_HASH_PREFIX,
     auth::CredentialStore,
     error::{SyncError, SyncResult},
+    network_usage,
     provider::EmailProvider,
+    tls_trust,
     types::*,
 };
+
+use super::icloud;
+use super::imap_flags::{ChangeMarker, FlagSnapshot, ImapSyncToken, MessageFlags, FLAG_WINDOW};
+use super::imap_pool;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 
-// Wrapper to provide Debug trait for Compat
-struct DebugCompat(async_compat::Compat<TlsStream<tokio::net::TcpStream>>);
+// Wrapper to provide Debug trait for Compat. Plain streams are only used
+// until STARTTLS.
+pub(super) struct DebugCompat<S = TlsStream<tokio::net::TcpStream>>(async_compat::Compat<S>);
 
-impl std::fmt::Debug for DebugCompat {
+impl<S> std::fmt::Debug for DebugCompat<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "DebugCompat(TlsStream)")
+        write!(f, "DebugCompat({})", std::any::type_name::<S>())
     }
 }
 
-impl futures::io::AsyncRead for DebugCompat {
+impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> futures::io::AsyncRead
+    for DebugCompat<S>
+{
     fn poll_read(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -41,7 +53,9 @@ impl futures::io::AsyncRead for DebugCompat {
     }
 }
 
-impl futures::io::AsyncWrite for DebugCompat {
+impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> futures::io::AsyncWrite
+    for DebugCompat<S>
+{
     fn poll_write(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -59,7 +73,36 @@ impl futures::io::AsyncWrite for DebugCompat {
     }
 }
 
-type ImapSession = async_imap::Session<DebugCompat>;
+pub(super) type ImapSession = async_imap::Session<DebugCompat>;
+
+/// UIDs per UID FETCH command when fetching headers or flags
+const FETCH_BATCH_SIZE: usize = 500;
+/// UIDs per UID FETCH command when fetching whole messages, which are held
+/// in memory until parsed
+const BODY_FETCH_BATCH_SIZE: usize = 100;
+const FULL_MESSAGE_ATTRS: &str = "(UID FLAGS BODY.PEEK[])";
+const HEADER_ATTRS: &str = "(UID FLAGS ENVELOPE RFC822.SIZE BODYSTRUCTURE)";
+
+/// Mailbox created for deleted mail on servers without a Trash folder
+const TRASH_MAILBOX: &str = "Trash";
+/// Servers may drop IDLE after 30 minutes (RFC 2177), so re-issue before that
+const IDLE_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(29 * 60);
+const IDLE_INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_secs(5);
+const IDLE_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Wait before the next IDLE reconnect after one failed after `backoff`
+fn next_idle_backoff(backoff: std::time::Duration) -> std::time::Duration {
+    (backoff * 2).min(IDLE_MAX_BACKOFF)
+}
+
+/// Whether an IDLE connection error ends IDLE instead of being retried: the
+/// credentials are rejected or the server cannot IDLE at all
+fn is_fatal_idle_error(error: &SyncError) -> bool {
+    matches!(
+        error,
+        SyncError::AuthenticationError(_) | SyncError::NotSupported(_)
+    )
+}
 
 pub struct ImapProvider {
     account_id: Uuid,
@@ -67,6 +110,15 @@ pub struct ImapProvider {
     config: Arc<Mutex<Option<ImapConfig>>>,
     account_settings: Option<AccountSettings>,
     credential_store: Arc<CredentialStore>,
+    profile: ImapProfile,
+}
+
+/// Server-specific behaviour layered on top of plain IMAP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImapProfile {
+    #[default]
+    Generic,
+    ICloud,
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +128,8 @@ struct ImapConfig {
     username: String,
     password: String,
     use_tls: bool,
+    tls_mode: TlsMode,
+    trusted_certificate: Option<String>,
 }
 
 impl ImapProvider {
@@ -86,6 +140,7 @@ impl ImapProvider {
             config: Arc::new(Mutex::new(None)),
             account_settings: None,
             credential_store,
+            profile: ImapProfile::Generic,
         })
     }
 
@@ -94,112 +149,298 @@ impl ImapProvider {
         self
     }
 
-    async fn ensure_connected(&self) -> SyncResult<()> {
-        // First, ensure config is loaded
-        {
-            let config_guard = self.config.lock().await;
-            if config_guard.is_none() {
-                drop(config_guard);
-
-                log::info!(
-                    "[ImapProvider] Config not set, loading credentials for account {}",
-                    self.account_id
-                );
+    pub fn with_profile(mut self, profile: ImapProfile) -> Self {
+        self.profile = profile;
+        self
+    }
 
-                let settings = self.account_settings.as_ref().ok_or_else(|| {
-                    SyncError::InvalidConfiguration("Account settings not provided".to_string())
-                })?;
+    async fn ensure_config(&self) -> SyncResult<ImapConfig> {
+        let mut config_guard = self.config.lock().await;
+        if let Some(config) = config_guard.as_ref() {
+            return Ok(config.clone());
+        }
 
-                let host = settings.imap_host.as_ref().ok_or_else(|| {
-                    SyncError::InvalidConfiguration("IMAP host not configured".to_string())
-                })?;
+        log::info!(
+            "[ImapProvider] Config not set, loading credentials for account {}",
+            self.account_id
+        );
 
-                let port = settings.imap_port.unwrap_or(993);
-                let use_tls = settings.imap_use_tls.unwrap_or(true);
+        let settings = self.account_settings.as_ref().ok_or_else(|| {
+            SyncError::InvalidConfiguration("Account settings not provided".to_string())
+        })?;
 
-                let creds = self
-                    .credential_store
-                    .get_imap(self.account_id)
-                    .await
-                    .map_err(|e| {
-                        log::error!(
-                            "[ImapProvider] Failed to load credentials for account {}: {}",
-                            self.account_id,
-                            e
-                        );
-                        SyncError::InvalidConfiguration(format!(
-                            "Failed to load IMAP credentials: {}",
-                            e
-                        ))
-                    })?;
+        let host = settings.imap_host.as_ref().ok_or_else(|| {
+            SyncError::InvalidConfiguration("IMAP host not configured".to_string())
+        })?;
 
-                let mut config_guard = self.config.lock().await;
-                *config_guard = Some(ImapConfig {
-                    host: host.clone(),
-                    port,
-                    username: creds.username,
-                    password: creds.password,
-                    use_tls,
-                });
+        let port = settings.imap_port.unwrap_or(993);
+        let use_tls = settings.imap_use_tls.unwrap_or(true);
 
-                log::info!(
-                    "[ImapProvider] Config initialized for account {} ({}:{})",
+        let creds = self
+            .credential_store
+            .get_imap(self.account_id)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "[ImapProvider] Failed to load credentials for account {}: {}",
                     self.account_id,
-                    host,
-                    port
+                    e
                 );
-            }
+                SyncError::InvalidConfiguration(format!("Failed to load IMAP credentials: {}", e))
+            })?;
+
+        let config = ImapConfig {
+            host: host.clone(),
+            port,
+            username: creds.username,
+            password: creds.password,
+            use_tls,
+            tls_mode: settings
+                .imap_tls_mode
+                .unwrap_or_else(|| TlsMode::for_imap_port(port)),
+            trusted_certificate: settings.imap_trusted_certificate.clone(),
+        };
+        *config_guard = Some(config.clone());
+
+        log::info!(
+            "[ImapProvider] Config initialized for account {} ({}:{})",
+            self.account_id,
+            host,
+            port
+        );
+
+        Ok(config)
+    }
+
+    /// Open and authenticate a new IMAP session for the given config
+    async fn connect(config: &ImapConfig) -> SyncResult<ImapSession> {
+        if !config.use_tls {
+            return Err(SyncError::InvalidConfiguration(
+                "Non-TLS IMAP connections are not supported".to_string(),
+            ));
         }
 
-        let mut session = self.session.lock().await;
+        let tls_stream = match Self::open_tls(config, false).await? {
+            Ok(tls_stream) => tls_stream,
+            Err(e) if tls_trust::is_certificate_error(&e.to_string()) => {
+                // Connect again without validation to see the certificate
+                let tls_stream = Self::open_tls(config, true)
+                    .await?
+                    .map_err(|e| SyncError::ImapError(format!("TLS connection failed: {}", e)))?;
+                let fingerprint = tls_stream
+                    .get_ref()
+                    .peer_certificate()
+                    .ok()
+                    .flatten()
+                    .and_then(|certificate| certificate.to_der().ok())
+                    .map(|der| tls_trust::fingerprint(&der))
+                    .ok_or_else(|| {
+                        SyncError::ImapError("The server sent no certificate".to_string())
+                    })?;
 
-        if session.is_none() {
-            let config_guard = self.config.lock().await;
-            let config = config_guard.as_ref().unwrap(); // Safe because we just ensured it's Some above
+                let trusted = config
+                    .trusted_certificate
+                    .as_deref()
+                    .is_some_and(|trusted| tls_trust::matches(trusted, &fingerprint));
+                if !trusted {
+                    return Err(SyncError::UntrustedCertificate(
+                        tls_trust::UntrustedCertificate {
+                            host: config.host.clone(),
+                            port: config.port,
+                            fingerprint,
+                            reason: e.to_string(),
+                        }
+                        .to_string(),
+                    ));
+                }
+                tls_stream
+            }
+            Err(e) => {
+                return Err(SyncError::ImapError(format!(
+                    "TLS connection failed: {}",
+                    e
+                )))
+            }
+        };
 
-            let addr = format!("{}:{}", config.host, config.port);
+        let client = async_imap::Client::new(DebugCompat(tls_stream.compat()));
+        client
+            .login(&config.username, &config.password)
+            .await
+            .map_err(|e| SyncError::AuthenticationError(format!("IMAP login failed: {:?}", e)))
+    }
 
-            let tcp_stream = tokio::net::TcpStream::connect(&addr)
+    /// Connect to the server and negotiate TLS, upgrading a plain connection
+    /// with STARTTLS first if the config asks for it. A failed TLS handshake
+    /// is returned as the inner error so certificate problems can be told
+    /// apart.
+    async fn open_tls(
+        config: &ImapConfig,
+        accept_invalid_certs: bool,
+    ) -> SyncResult<Result<TlsStream<tokio::net::TcpStream>, tokio_native_tls::native_tls::Error>>
+    {
+        let addr = format!("{}:{}", config.host, config.port);
+
+        let mut tcp_stream = tokio::net::TcpStream::connect(&addr)
+            .await
+            .map_err(|e| SyncError::ImapError(format!("TCP connection failed: {}", e)))?;
+
+        if config.tls_mode == TlsMode::Starttls {
+            let mut client = async_imap::Client::new(DebugCompat(tcp_stream.compat()));
+            client
+                .read_response()
+                .await?
+                .ok_or_else(|| SyncError::ImapError("No greeting from server".to_string()))?;
+            client
+                .run_command_and_check_ok("STARTTLS", None)
                 .await
-                .map_err(|e| SyncError::ImapError(format!("TCP connection failed: {}", e)))?;
+                .map_err(|e| SyncError::ImapError(format!("STARTTLS failed: {}", e)))?;
+            tcp_stream = client.into_inner().0.into_inner();
+        }
 
-            if config.use_tls {
-                let tls_connector = tokio_native_tls::native_tls::TlsConnector::builder()
-                    .build()
-                    .map_err(|e| SyncError::ImapError(format!("TLS setup failed: {}", e)))?;
-                let tls_connector = tokio_native_tls::TlsConnector::from(tls_connector);
+        let tls_connector = tokio_native_tls::native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .danger_accept_invalid_hostnames(accept_invalid_certs)
+            .build()
+            .map_err(|e| SyncError::ImapError(format!("TLS setup failed: {}", e)))?;
+        let tls_connector = tokio_native_tls::TlsConnector::from(tls_connector);
 
-                let tls_stream = tls_connector
-                    .connect(&config.host, tcp_stream)
-                    .await
-                    .map_err(|e| SyncError::ImapError(format!("TLS connection failed: {}", e)))?;
+        Ok(tls_connector.connect(&config.host, tcp_stream).await)
+    }
 
-                let client = async_imap::Client::new(DebugCompat(tls_stream.compat()));
-                let imap_session = client
-                    .login(&config.username, &config.password)
-                    .await
-                    .map_err(|e| {
-                        SyncError::AuthenticationError(format!("IMAP login failed: {:?}", e))
-                    })?;
+    async fn ensure_connected(&self) -> SyncResult<()> {
+        let config = self.ensure_config().await?;
 
-                *session = Some(imap_session);
-                log::info!(
-                    "[ImapProvider] IMAP connection established for account {}",
-                    self.account_id
-                );
-            } else {
-                return Err(SyncError::InvalidConfiguration(
-                    "Non-TLS IMAP connections are not supported".to_string(),
-                ));
+        let mut session = self.session.lock().await;
+
+        if session.is_none() {
+            if let Some(mut pooled) = imap_pool::checkout(self.account_id) {
+                // The server may have closed the connection while it was idle
+                if pooled.noop().await.is_ok() {
+                    *session = Some(pooled);
+                    return Ok(());
+                }
+            }
+
+            let connected = Self::connect(&config).await;
+            let connected = match self.profile {
+                ImapProfile::ICloud => connected.map_err(icloud::map_authentication_error),
+                ImapProfile::Generic => connected,
+            };
+            let mut connected = connected?;
+            // QRESYNC has to be enabled before a mailbox is selected
+            if connected.capabilities().await?.has_str("QRESYNC") {
+                connected.run_command_and_check_ok("ENABLE QRESYNC").await?;
             }
+            *session = Some(connected);
+            log::info!(
+                "[ImapProvider] IMAP connection established for account {}",
+                self.account_id
+            );
         }
 
         Ok(())
     }
 
-    async fn get_session(&self) -> SyncResult<tokio::sync::MutexGuard<'_, Option<ImapSession>>> {
+    async fn get_session(&self) -> SyncResult<SessionGuard<'_>> {
         self.ensure_connected().await?;
-        Ok(self.session.lock().await)
+        Ok(SessionGuard {
+            session: self.session.lock().await,
+            completed: false,
+        })
+    }
+
+    /// Folder type of a mailbox and whether it is hidden
+    fn classify_mailbox(&self, mailbox: &async_imap::types::Name) -> (FolderType, bool) {
+        let remote_id = mailbox.name();
+        let profile_mapping = match self.profile {
+            ImapProfile::ICloud => icloud::map_folder(&decode_modified_utf7(remote_id)),
+            ImapProfile::Generic => None,
+        };
+        // Map on the raw mailbox name: decoding twice would mangle names
+        // containing a literal '&'
+        profile_mapping.unwrap_or_else(|| {
+            (
+                Self::map_folder_type(remote_id, mailbox.attributes()),
+                false,
+            )
+        })
+    }
+
+    fn delete_policy(&self) -> ImapDeletePolicy {
+        self.account_settings
+            .as_ref()
+            .map(|settings| settings.imap_delete_policy)
+            .unwrap_or_default()
+    }
+
+    /// Remote name of the Trash mailbox, created when the server has none
+    async fn find_or_create_trash(&self, session: &mut ImapSession) -> SyncResult<String> {
+        let mailboxes = session
+            .list(Some(""), Some("*"))
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        if let Some(trash) = mailboxes
+            .iter()
+            .find(|mailbox| self.classify_mailbox(mailbox).0 == FolderType::Trash)
+        {
+            return Ok(trash.name().to_string());
+        }
+
+        log::info!(
+            "[ImapProvider] No Trash mailbox for account {}, creating one",
+            self.account_id
+        );
+        session.create(TRASH_MAILBOX).await?;
+        Ok(TRASH_MAILBOX.to_string())
+    }
+
+    /// Flags a message `\Deleted` in the selected mailbox and, unless the policy
+    /// leaves it to the server, expunges it. With UIDPLUS only this message is
+    /// expunged; otherwise plain EXPUNGE also removes other flagged messages.
+    async fn remove_message(&self, session: &mut ImapSession, uid: u32) -> SyncResult<()> {
+        let _ = session
+            .uid_store(uid.to_string(), "+FLAGS.SILENT (\\Deleted)")
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        if self.delete_policy() == ImapDeletePolicy::FlagOnly {
+            return Ok(());
+        }
+
+        let capabilities = session.capabilities().await?;
+        if capabilities.has_str("UIDPLUS") {
+            let _ = session
+                .uid_expunge(uid.to_string())
+                .await?
+                .try_collect::<Vec<_>>()
+                .await?;
+        } else {
+            let _ = session.expunge().await?.try_collect::<Vec<_>>().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves a message out of the selected mailbox, with UID MOVE when the
+    /// server supports it and COPY plus delete otherwise
+    async fn move_message(
+        &self,
+        session: &mut ImapSession,
+        uid: u32,
+        to_mailbox: &str,
+    ) -> SyncResult<()> {
+        let capabilities = session.capabilities().await?;
+        if capabilities.has_str("MOVE") {
+            session.uid_mv(uid.to_string(), to_mailbox).await?;
+            return Ok(());
+        }
+
+        session.uid_copy(uid.to_string(), to_mailbox).await?;
+        self.remove_message(session, uid).await
     }
 
     fn map_folder_type(name: &str, _attributes: &[async_imap::types::NameAttribute]) -> FolderType {
@@ -571,17 +812,13 @@ impl ImapProvider {
         // It will be populated during body fetch
         let snippet = None;
 
-        // For headers-only mode, check BODYSTRUCTURE for attachments
-        // This allows us to set has_attachments without fetching the full body
-        let has_attachments = fetch
+        // Attachments are listed from BODYSTRUCTURE so they show before the
+        // body is fetched
+        let attachments = fetch
             .bodystructure()
-            .map(|_bs| {
-                // Check if bodystructure indicates attachments
-                // This is a simplified check - a full implementation would parse the structure
-                // For now, we'll be conservative and set to false
-                false
-            })
-            .unwrap_or(false);
+            .map(structure_attachments)
+            .unwrap_or_default();
+        let has_attachments = !attachments.is_empty();
 
         Ok(SyncEmail {
             id: None,
@@ -608,9 +845,11 @@ impl ImapProvider {
             headers: headers_json,
             size,
             has_attachments,
-            attachments: Vec::new(),
+            attachments,
             change_key: None,
             last_modified_at: None,
+            signature: None,
+            remote_labels: None,
         })
     }
 
@@ -634,6 +873,41 @@ impl ImapProvider {
             .body()
             .ok_or_else(|| SyncError::ParseError("Email body not found".to_string()))?;
 
+        let flags: Vec<String> = fetch
+            .flags()
+            .map(|flag| match flag {
+                Flag::Seen => "\\Seen".to_string(),
+                Flag::Answered => "\\Answered".to_string(),
+                Flag::Flagged => "\\Flagged".to_string(),
+                Flag::Deleted => "\\Deleted".to_string(),
+                Flag::Draft => "\\Draft".to_string(),
+                Flag::Recent => "\\Recent".to_string(),
+                Flag::Custom(s) => s.to_string(),
+                _ => String::new(),
+            })
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        Self::parse_raw_email(
+            body,
+            folder_id,
+            account_id,
+            &uid.to_string(),
+            format!("{}@{}", uid, "imap.local"),
+            flags,
+        )
+    }
+
+    /// Builds a `SyncEmail` from a complete RFC 5322 message. Also used for
+    /// messages imported from mbox and EML files.
+    pub fn parse_raw_email(
+        body: &[u8],
+        folder_id: Uuid,
+        account_id: Uuid,
+        remote_id: &str,
+        fallback_message_id: String,
+        flags: Vec<String>,
+    ) -> SyncResult<SyncEmail> {
         let parser = MessageParser::default();
         let message = parser
             .parse(body)
@@ -701,13 +975,22 @@ impl ImapProvider {
 
         let subject = message.subject().map(|s| s.to_string());
 
-        let body_html = message.body_html(0).map(|s| s.to_string());
-        let body_plain = message.body_text(0).map(|s| s.to_string());
+        // Opaque signed messages carry their body inside the signature, so the
+        // body and attachments are read from the signed content
+        let signature = smime::check_message(body, &message, &from.address);
+        let signed_content = signature
+            .as_ref()
+            .and_then(|check| check.content.as_deref())
+            .and_then(|content| parser.parse(content));
+        let content = signed_content.as_ref().unwrap_or(&message);
+
+        let body_html = content.body_html(0).map(|s| s.to_string());
+        let body_plain = content.body_text(0).map(|s| s.to_string());
 
         let message_id = message
             .message_id()
             .map(|id| id.to_string())
-            .unwrap_or_else(|| format!("{}@{}", uid, "imap.local"));
+            .unwrap_or(fallback_message_id);
 
         let received_at = message
             .date()
@@ -719,22 +1002,7 @@ impl ImapProvider {
             .date()
             .and_then(|ts| DateTime::from_timestamp(ts.to_timestamp(), 0));
 
-        let flags: Vec<String> = fetch
-            .flags()
-            .map(|flag| match flag {
-                Flag::Seen => "\\Seen".to_string(),
-                Flag::Answered => "\\Answered".to_string(),
-                Flag::Flagged => "\\Flagged".to_string(),
-                Flag::Deleted => "\\Deleted".to_string(),
-                Flag::Draft => "\\Draft".to_string(),
-                Flag::Recent => "\\Recent".to_string(),
-                Flag::Custom(s) => s.to_string(),
-                _ => String::new(),
-            })
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        let attachments: Vec<SyncAttachment> = message
+        let attachments: Vec<SyncAttachment> = content
             .attachments()
             .map(|att| {
                 let content = att.contents();
@@ -776,11 +1044,33 @@ impl ImapProvider {
         // Use snippet extraction utility for proper trimming at word boundary
         let snippet = crate::sync::snippet_utils::extract_snippet(body_plain.as_deref());
 
-        log::debug!("[Imap] Extracted snippet for UID {}: {:?}", uid, snippet);
+        log::debug!(
+            "[Imap] Extracted snippet for UID {}: {:?}",
+            remote_id,
+            snippet
+        );
 
         // Extract comprehensive headers as JSON (including DKIM, List-*, Return-Path, etc.)
         let headers_json = {
-            let headers_map = serde_json::Map::new();
+            let mut headers_map = serde_json::Map::new();
+            // Raw value, since mail-parser reads List-Id as an address
+            if let Some(list_id) = message.header_raw("List-Id") {
+                headers_map.insert(
+                    "List-Id".to_string(),
+                    serde_json::Value::String(list_id.trim().to_string()),
+                );
+            }
+            // Kept raw for threading, which reads every id of the list, and
+            // for the phishing heuristics, which read the receiving server's
+            // verdict on the sender
+            for name in ["In-Reply-To", "References", "Authentication-Results"] {
+                if let Some(value) = message.header_raw(name) {
+                    headers_map.insert(
+                        name.to_string(),
+                        serde_json::Value::String(value.trim().to_string()),
+                    );
+                }
+            }
             // for header in message.headers().iter() {
             //     let value_str = String::from_utf8_lossy(header.value.as_text().unwrap().as_ref()).to_string();
             //     headers_map.insert(header.name.to_string(), serde_json::Value::String(value_str));
@@ -794,7 +1084,7 @@ impl ImapProvider {
             folder_id,
             message_id,
             conversation_id: None,
-            remote_id: uid.to_string(),
+            remote_id: remote_id.to_string(),
             from,
             to,
             cc,
@@ -816,34 +1106,128 @@ impl ImapProvider {
             attachments,
             change_key: None,
             last_modified_at: None,
+            signature,
+            remote_labels: None,
         })
     }
 
-    /// Helper to fetch messages with whole bodies using sequence numbers.
-    /// UID can't be fetched reliably; we only request full RFC822 and FLAGS.
-    async fn fetch_messages_with_bodies(
+    /// Fetches and parses whole messages by UID in batches, skipping ones
+    /// that are gone
+    async fn fetch_full_emails(
+        &self,
         session: &mut ImapSession,
-        seqset: &str,
-        _use_uid: bool, // kept for compatibility; ignored
-    ) -> SyncResult<Vec<Fetch>> {
-        let fetch_attrs = "RFC822";
-        let messages: Vec<_> = session
-            .fetch(seqset, fetch_attrs)
+        folder: &SyncFolder,
+        uids: &[u32],
+    ) -> SyncResult<Vec<SyncEmail>> {
+        let mut emails: Vec<SyncEmail> = Vec::new();
+        let Some(folder_id) = folder.id else {
+            return Ok(emails);
+        };
+
+        for set in uid_sets(uids, BODY_FETCH_BATCH_SIZE) {
+            let messages = Self::uid_fetch(session, &set, FULL_MESSAGE_ATTRS).await?;
+            self.record_usage(&messages);
+            for fetch in &messages {
+                match Self::parse_email(fetch, folder_id, self.account_id, None) {
+                    Ok(email) => emails.push(email),
+                    Err(e) => log::warn!("Failed to parse email UID {:?}: {}", fetch.uid, e),
+                }
+            }
+        }
+
+        Ok(emails)
+    }
+
+    /// UIDs up to `last_uid` whose flags changed since `modseq` (CONDSTORE),
+    /// and with QRESYNC the UIDs that were expunged since then
+    async fn fetch_changed_since(
+        session: &mut ImapSession,
+        last_uid: u32,
+        modseq: u64,
+        qresync: bool,
+    ) -> SyncResult<(Vec<u32>, Vec<u32>)> {
+        // VANISHED arrives as an unsolicited response. Nothing else reads the
+        // channel, so it is drained first to make room.
+        while session.unsolicited_responses.try_recv().is_ok() {}
+
+        let modifier = if qresync {
+            format!("(CHANGEDSINCE {} VANISHED)", modseq)
+        } else {
+            format!("(CHANGEDSINCE {})", modseq)
+        };
+        let messages: Vec<Fetch> = session
+            .uid_fetch(
+                format!("1:{}", last_uid),
+                format!("(UID FLAGS) {}", modifier),
+            )
             .await?
             .try_collect()
             .await?;
-        Ok(messages)
+
+        let mut changed: Vec<u32> = messages.iter().filter_map(|fetch| fetch.uid).collect();
+        changed.sort_unstable();
+        changed.dedup();
+
+        let mut vanished: Vec<u32> = Vec::new();
+        while let Ok(response) = session.unsolicited_responses.try_recv() {
+            if let UnsolicitedResponse::Other(data) = response {
+                if let Response::Vanished { uids, .. } = data.parsed() {
+                    vanished.extend(
+                        uids.iter()
+                            .flat_map(|range| range.clone())
+                            .filter(|uid| *uid <= last_uid),
+                    );
+                }
+            }
+        }
+
+        Ok((changed, vanished))
+    }
+
+    /// Read and flagged state of the most recent messages in the mailbox
+    async fn fetch_recent_flags(
+        session: &mut ImapSession,
+        exists: u32,
+    ) -> SyncResult<Vec<MessageFlags>> {
+        let first_seq = exists.saturating_sub(FLAG_WINDOW) + 1;
+        let messages: Vec<Fetch> = session
+            .fetch(format!("{}:*", first_seq), "(UID FLAGS)")
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(messages
+            .iter()
+            .filter_map(|fetch| {
+                let flags: Vec<Flag> = fetch.flags().collect();
+                Some(MessageFlags {
+                    uid: fetch.uid?,
+                    seen: flags.contains(&Flag::Seen),
+                    flagged: flags.contains(&Flag::Flagged),
+                })
+            })
+            .collect())
     }
 
-    /// Helper to fetch message headers only (no body) using sequence numbers.
-    /// Fetches: ENVELOPE, FLAGS, RFC822.SIZE, BODYSTRUCTURE
-    async fn fetch_messages_headers_only(
+    /// Counts downloaded message bodies towards the account's network usage
+    fn record_usage(&self, messages: &[Fetch]) {
+        let bytes: usize = messages
+            .iter()
+            .filter_map(|fetch| fetch.body())
+            .map(|body| body.len())
+            .sum();
+        network_usage::record(self.account_id, "imap", 0, bytes as u64, 0);
+    }
+
+    /// Runs a UID FETCH of `uid_set`. Bodies are peeked so that syncing does
+    /// not mark messages as read.
+    async fn uid_fetch(
         session: &mut ImapSession,
-        seqset: &str,
+        uid_set: &str,
+        attrs: &str,
     ) -> SyncResult<Vec<Fetch>> {
-        let fetch_attrs = "(FLAGS ENVELOPE RFC822.SIZE BODYSTRUCTURE)";
         let messages: Vec<_> = session
-            .fetch(seqset, fetch_attrs)
+            .uid_fetch(uid_set, attrs)
             .await?
             .try_collect()
             .await?;
@@ -865,83 +1249,87 @@ impl ImapProvider {
         );
 
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
-
-        let mailbox = session.select(&folder.remote_id).await?;
-        let exists = mailbox.exists;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-        log::debug!(
-            "[IMAP] Selected folder {} - {} messages exist",
-            folder.remote_id,
-            exists
-        );
-
-        if exists == 0 {
-            log::info!("[IMAP] Folder {} is empty", folder.name);
-            return Ok(Vec::new());
-        }
+            let mailbox = session.select(&folder.remote_id).await?;
+            let exists = mailbox.exists;
 
-        let uids: Vec<u32> = if let Some(uid) = since_uid {
             log::debug!(
-                "[IMAP] UID SEARCH for folder {}: UID {}:*",
+                "[IMAP] Selected folder {} - {} messages exist",
                 folder.remote_id,
-                uid + 1
+                exists
             );
-            let set = session.uid_search(format!("UID {}:*", uid + 1)).await?;
-            let mut v: Vec<u32> = set.into_iter().collect();
-            v.sort_unstable();
-            v
-        } else {
-            log::debug!("[IMAP] UID SEARCH for folder {}: ALL", folder.remote_id);
-            let set = session.uid_search("ALL").await?;
-            let mut v: Vec<u32> = set.into_iter().collect();
-            v.sort_unstable();
-            v
-        };
-
-        if uids.is_empty() {
-            log::info!("[IMAP] No matching UIDs in folder {}", folder.name);
-            return Ok(Vec::new());
-        }
 
-        log::debug!("[IMAP] Fetching headers for {} emails", uids.len());
+            if exists == 0 {
+                log::info!("[IMAP] Folder {} is empty", folder.name);
+                return Ok(Vec::new());
+            }
 
-        let mut emails: Vec<SyncEmail> = Vec::new();
-        for uid in uids {
-            let seq_nums_set = session.search(format!("UID {}", uid)).await?;
-            let seq = seq_nums_set.iter().min().copied();
-            if seq.is_none() {
-                continue;
+            let uids: Vec<u32> = if let Some(uid) = since_uid {
+                log::debug!(
+                    "[IMAP] UID SEARCH for folder {}: UID {}:*",
+                    folder.remote_id,
+                    uid + 1
+                );
+                let set = session.uid_search(format!("UID {}:*", uid + 1)).await?;
+                let mut v: Vec<u32> = set.into_iter().collect();
+                v.sort_unstable();
+                v
+            } else {
+                log::debug!("[IMAP] UID SEARCH for folder {}: ALL", folder.remote_id);
+                let set = session.uid_search("ALL").await?;
+                let mut v: Vec<u32> = set.into_iter().collect();
+                v.sort_unstable();
+                v
+            };
+
+            if uids.is_empty() {
+                log::info!("[IMAP] No matching UIDs in folder {}", folder.name);
+                return Ok(Vec::new());
             }
-            let seq = seq.unwrap();
 
-            let messages = Self::fetch_messages_headers_only(session, &seq.to_string()).await?;
-            if let Some(fetch) = messages.first() {
-                if let Some(folder_id) = folder.id {
-                    match Self::parse_email_headers(fetch, folder_id, self.account_id, Some(uid)) {
+            log::debug!("[IMAP] Fetching headers for {} emails", uids.len());
+
+            let mut emails: Vec<SyncEmail> = Vec::new();
+            let Some(folder_id) = folder.id else {
+                return Ok(emails);
+            };
+
+            for set in uid_sets(&uids, FETCH_BATCH_SIZE) {
+                let messages = Self::uid_fetch(session, &set, HEADER_ATTRS).await?;
+                self.record_usage(&messages);
+                for fetch in &messages {
+                    match Self::parse_email_headers(fetch, folder_id, self.account_id, None) {
                         Ok(email) => emails.push(email),
                         Err(e) => {
-                            log::warn!("[IMAP] Failed to parse email headers UID {}: {}", uid, e)
+                            log::warn!(
+                                "[IMAP] Failed to parse email headers UID {:?}: {}",
+                                fetch.uid,
+                                e
+                            )
                         }
                     }
                 }
             }
-        }
 
-        log::info!(
-            "[IMAP] Successfully fetched headers for {} emails from folder {} for account {}",
-            emails.len(),
-            folder.name,
-            self.account_id
-        );
+            log::info!(
+                "[IMAP] Successfully fetched headers for {} emails from folder {} for account {}",
+                emails.len(),
+                folder.name,
+                self.account_id
+            );
 
-        Ok(emails)
+            Ok(emails)
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     /// Fetch the full body for an email that only has headers
-    /// Returns: (body_plain, body_html, headers, sent_at, attachments)
+    /// Returns: (body_plain, body_html, headers, sent_at, attachments, snippet, signature)
     pub async fn fetch_email_body(
         &self,
         folder: &SyncFolder,
@@ -953,6 +1341,7 @@ impl ImapProvider {
         Option<DateTime<Utc>>,
         Vec<SyncAttachment>,
         Option<String>,
+        Option<SignatureCheck>,
     )> {
         log::debug!(
             "[IMAP] Fetching body for email {} in folder {}",
@@ -971,27 +1360,393 @@ impl ImapProvider {
             email.sent_at,
             email.attachments,
             email.snippet,
+            email.signature,
         ))
     }
-}
 
-#[async_trait]
-impl EmailProvider for ImapProvider {
-    fn name(&self) -> &str {
-        "IMAP"
-    }
+    /// Reads the quota of the INBOX quota root (RFC 2087)
+    ///
+    /// Returns an empty quota if the server does not advertise QUOTA.
+    pub async fn fetch_quota(&self) -> SyncResult<SyncMailboxQuota> {
+        let mut session_guard = self.get_session().await?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
+            if !session.capabilities().await?.has_str("QUOTA") {
+                return Ok(SyncMailboxQuota::default());
+            }
 
-    async fn authenticate(&mut self, credentials: ProviderCredentials) -> SyncResult<()> {
-        match credentials {
-            ProviderCredentials::Imap(creds) => {
-                // Get IMAP settings from account settings
-                let settings = self.account_settings.as_ref().ok_or_else(|| {
-                    SyncError::InvalidConfiguration("Account settings not provided".to_string())
-                })?;
+            let (_, quotas) = session.get_quota_root("INBOX").await?;
+            let mut quota = SyncMailboxQuota::default();
+            for resource in quotas.iter().flat_map(|q| &q.resources) {
+                let usage = i64::try_from(resource.usage).ok();
+                // A limit of 0 means unlimited
+                let limit = i64::try_from(resource.limit).ok().filter(|l| *l > 0);
+
+                match resource.name {
+                    // Reported in units of 1024 octets
+                    QuotaResourceName::Storage => {
+                        quota.storage_used = usage.map(|u| u.saturating_mul(1024));
+                        quota.storage_limit = limit.map(|l| l.saturating_mul(1024));
+                    }
+                    QuotaResourceName::Message => {
+                        quota.message_count = usage;
+                        quota.message_limit = limit;
+                    }
+                    QuotaResourceName::Atom(_) => {}
+                }
+            }
+
+            Ok(quota)
+        }
+        .await;
+        session_guard.finish(result)
+    }
+
+    /// Hold a dedicated IDLE connection on `folder` until shutdown is signalled.
+    ///
+    /// `on_new_mail` is invoked whenever the server pushes an untagged response
+    /// (EXISTS, EXPUNGE, FETCH) for the idled mailbox. Dropped connections are
+    /// re-established with exponential backoff. Returns `NotSupported` if the
+    /// server does not advertise the IDLE capability.
+    pub async fn run_idle<F, Fut>(
+        &self,
+        folder: &SyncFolder,
+        mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+        mut on_new_mail: F,
+    ) -> SyncResult<()>
+    where
+        F: FnMut() -> Fut + Send,
+        Fut: std::future::Future<Output = ()> + Send,
+    {
+        let mut backoff = IDLE_INITIAL_BACKOFF;
+
+        loop {
+            let config = self.ensure_config().await?;
+
+            let session = tokio::select! {
+                _ = shutdown_rx.recv() => return Ok(()),
+                result = Self::open_idle_session(&config, folder) => result,
+            };
+
+            let mut session = match session {
+                Ok(session) => session,
+                Err(e) if is_fatal_idle_error(&e) => return Err(e),
+                Err(e) => {
+                    log::warn!(
+                        "[ImapProvider] IDLE connection for account {} failed: {}; retrying in {:?}",
+                        self.account_id,
+                        e,
+                        backoff
+                    );
+                    tokio::select! {
+                        _ = shutdown_rx.recv() => return Ok(()),
+                        _ = tokio::time::sleep(backoff) => {}
+                    }
+                    backoff = next_idle_backoff(backoff);
+                    continue;
+                }
+            };
+            backoff = IDLE_INITIAL_BACKOFF;
+
+            log::info!(
+                "[ImapProvider] IDLE started on folder {} for account {}",
+                folder.name,
+                self.account_id
+            );
+
+            // Inner loop: re-issue IDLE until the connection breaks
+            let disconnected = loop {
+                let mut handle = session.idle();
+                if let Err(e) = handle.init().await {
+                    break e.to_string();
+                }
+
+                let (wait, _stop) = handle.wait_with_timeout(IDLE_REFRESH_INTERVAL);
+                let response = tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!(
+                            "[ImapProvider] IDLE stopped for account {}",
+                            self.account_id
+                        );
+                        return Ok(());
+                    }
+                    response = wait => response,
+                };
+
+                let new_mail = match response {
+                    Ok(IdleResponse::NewData(_)) => true,
+                    Ok(IdleResponse::Timeout) | Ok(IdleResponse::ManualInterrupt) => false,
+                    Err(e) => break e.to_string(),
+                };
+
+                session = match handle.done().await {
+                    Ok(session) => session,
+                    Err(e) => break e.to_string(),
+                };
+
+                if new_mail {
+                    log::debug!(
+                        "[ImapProvider] IDLE change on folder {} for account {}",
+                        folder.name,
+                        self.account_id
+                    );
+                    on_new_mail().await;
+                }
+            };
+
+            log::warn!(
+                "[ImapProvider] IDLE connection for account {} dropped: {}; reconnecting in {:?}",
+                self.account_id,
+                disconnected,
+                backoff
+            );
+            tokio::select! {
+                _ = shutdown_rx.recv() => return Ok(()),
+                _ = tokio::time::sleep(backoff) => {}
+            }
+            backoff = next_idle_backoff(backoff);
+        }
+    }
+
+    /// Connect and select `folder` for IDLE
+    async fn open_idle_session(
+        config: &ImapConfig,
+        folder: &SyncFolder,
+    ) -> SyncResult<ImapSession> {
+        let mut session = Self::connect(config).await?;
+
+        let capabilities = session.capabilities().await?;
+        if !capabilities.has_str("IDLE") {
+            let _ = session.logout().await;
+            return Err(SyncError::NotSupported(
+                "IMAP server does not support IDLE".to_string(),
+            ));
+        }
+
+        session.select(&folder.remote_id).await?;
+        Ok(session)
+    }
+
+    /// Sync a folder's messages: all of them without a sync token, the ones
+    /// received since `since` for a windowed first sync, or the changes since
+    /// the token
+    async fn sync_folder_messages(
+        &self,
+        folder: &SyncFolder,
+        sync_token: Option<String>,
+        since: Option<DateTime<Utc>>,
+    ) -> SyncResult<crate::sync::types::SyncDiff> {
+        // The token carries the last UID for incremental sync, and the
+        // HIGHESTMODSEQ or flag snapshot to reconcile flags against
+        let previous = sync_token.as_deref().and_then(ImapSyncToken::parse);
+
+        let mut session_guard = self.get_session().await?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+
+            let capabilities = session.capabilities().await?;
+            let condstore = capabilities.has_str("CONDSTORE") || capabilities.has_str("QRESYNC");
+            let qresync = capabilities.has_str("QRESYNC");
+
+            // Select folder and get mailbox info
+            let mailbox = if condstore {
+                session.select_condstore(&folder.remote_id).await?
+            } else {
+                session.select(&folder.remote_id).await?
+            };
+            let exists = mailbox.exists;
+            let uid_validity = mailbox.uid_validity;
+
+            // UIDs from before a UIDVALIDITY change name other messages, so the
+            // folder is enumerated again
+            let previous = previous.filter(|token| {
+                let valid = token.uid_validity.is_none()
+                    || uid_validity.is_none()
+                    || token.uid_validity == uid_validity;
+                if !valid {
+                    log::warn!(
+                        "UIDVALIDITY of folder {} changed from {:?} to {:?}, running a full sync",
+                        folder.remote_id,
+                        token.uid_validity,
+                        uid_validity
+                    );
+                }
+                valid
+            });
+            let since_uid = previous.as_ref().map(|token| token.last_uid);
+
+            log::debug!(
+                "Selected folder {} - {} messages exist",
+                folder.remote_id,
+                exists
+            );
+
+            if exists == 0 {
+                log::info!("Folder {} is empty", folder.name);
+                return Ok(crate::sync::types::SyncDiff {
+                    added: Vec::new(),
+                    modified: Vec::new(),
+                    deleted: Vec::new(),
+                    next_sync_token: None,
+                    is_complete: since_uid.is_none(), // Complete only for full sync
+                    uid_validity,
+                });
+            }
+
+            // 1) Use SEARCH to get candidate UIDs (HashSet -> sorted Vec)
+            let uids: Vec<u32> = if let Some(uid) = since_uid {
+                log::debug!(
+                    "UID SEARCH for folder {}: UID {}:*",
+                    folder.remote_id,
+                    uid + 1
+                );
+                let set = session.uid_search(format!("UID {}:*", uid + 1)).await?;
+                let mut v: Vec<u32> = set.into_iter().filter(|u| *u > uid).collect();
+                v.sort_unstable();
+                v
+            } else {
+                let criteria = window_criteria(&SyncWindow {
+                    after: since,
+                    before: None,
+                });
+                log::debug!("UID SEARCH for folder {}: {}", folder.remote_id, criteria);
+                let set = session.uid_search(criteria).await?;
+                let mut v: Vec<u32> = set.into_iter().collect();
+                v.sort_unstable();
+                v
+            };
+
+            // 2) Find messages whose flags were changed by other clients, and
+            // with QRESYNC the ones that were expunged. A full sync refetches
+            // every message, so it only records the marker.
+            let (changes, modified_uids, vanished_uids) =
+                match mailbox.highest_modseq.filter(|_| condstore) {
+                    Some(highest_modseq) => {
+                        let (modified_uids, vanished_uids) = match &previous {
+                            Some(ImapSyncToken {
+                                last_uid,
+                                changes: ChangeMarker::ModSeq(modseq),
+                                ..
+                            }) if *modseq < highest_modseq => {
+                                Self::fetch_changed_since(session, *last_uid, *modseq, qresync)
+                                    .await?
+                            }
+                            _ => (Vec::new(), Vec::new()),
+                        };
+                        (
+                            ChangeMarker::ModSeq(highest_modseq),
+                            modified_uids,
+                            vanished_uids,
+                        )
+                    }
+                    None => {
+                        let flags = Self::fetch_recent_flags(session, exists).await?;
+                        let modified_uids = match &previous {
+                            Some(ImapSyncToken {
+                                last_uid,
+                                changes: ChangeMarker::Flags(snapshot),
+                                ..
+                            }) => snapshot.changed_uids(&flags, *last_uid),
+                            _ => Vec::new(),
+                        };
+                        (
+                            ChangeMarker::Flags(FlagSnapshot::from_messages(&flags)),
+                            modified_uids,
+                            Vec::new(),
+                        )
+                    }
+                };
+
+            if !modified_uids.is_empty() {
+                log::info!(
+                    "{} messages in folder {} were changed by other clients",
+                    modified_uids.len(),
+                    folder.name
+                );
+            }
+
+            log::debug!("Fetching {} emails", uids.len());
+
+            // 3) Fetch new and changed messages in full
+            let emails = self.fetch_full_emails(session, folder, &uids).await?;
+            let modified = self
+                .fetch_full_emails(session, folder, &modified_uids)
+                .await?;
+
+            log::info!(
+                "Successfully parsed {} emails from folder {} for account {}",
+                emails.len(),
+                folder.name,
+                self.account_id
+            );
+
+            // The highest UID seen so far, for the next incremental sync. A
+            // windowed sync has seen every UID below UIDNEXT; older mail among
+            // them is left to the backfill.
+            let last_uid = emails
+                .iter()
+                .filter_map(|e| e.remote_id.parse::<u32>().ok())
+                .chain(since_uid)
+                .chain(
+                    mailbox
+                        .uid_next
+                        .filter(|_| since.is_some())
+                        .map(|uid_next| uid_next.saturating_sub(1)),
+                )
+                .max();
+            let next_token = last_uid.map(|last_uid| {
+                ImapSyncToken {
+                    last_uid,
+                    uid_validity,
+                    changes,
+                }
+                .to_string()
+            });
+
+            Ok(crate::sync::types::SyncDiff {
+                added: emails,
+                modified,
+                deleted: vanished_uids.iter().map(u32::to_string).collect(),
+                next_sync_token: next_token,
+                // Complete only for full sync (no since_uid) of the whole folder
+                is_complete: since_uid.is_none() && since.is_none(),
+                uid_validity,
+            })
+        }
+        .await;
+        session_guard.finish(result)
+    }
+}
+
+#[async_trait]
+impl EmailProvider for ImapProvider {
+    fn name(&self) -> &str {
+        match self.profile {
+            ImapProfile::ICloud => "iCloud",
+            ImapProfile::Generic => "IMAP",
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    async fn authenticate(&mut self, credentials: ProviderCredentials) -> SyncResult<()> {
+        match credentials {
+            ProviderCredentials::Imap(mut creds) => {
+                if self.profile == ImapProfile::ICloud {
+                    creds.password = icloud::normalize_app_password(&creds.password)?;
+                }
+
+                // Get IMAP settings from account settings
+                let settings = self.account_settings.as_ref().ok_or_else(|| {
+                    SyncError::InvalidConfiguration("Account settings not provided".to_string())
+                })?;
 
                 let host = settings.imap_host.as_ref().ok_or_else(|| {
                     SyncError::InvalidConfiguration("IMAP host not configured".to_string())
@@ -1007,6 +1762,10 @@ impl EmailProvider for ImapProvider {
                     username: creds.username.clone(),
                     password: creds.password.clone(),
                     use_tls,
+                    tls_mode: settings
+                        .imap_tls_mode
+                        .unwrap_or_else(|| TlsMode::for_imap_port(port)),
+                    trusted_certificate: settings.imap_trusted_certificate.clone(),
                 });
                 drop(config_guard);
 
@@ -1040,55 +1799,59 @@ impl EmailProvider for ImapProvider {
 
     async fn fetch_folders(&self) -> SyncResult<Vec<SyncFolder>> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
-
-        let folders = session
-            .list(Some(""), Some("*"))
-            .await?
-            .try_collect::<Vec<_>>()
-            .await?;
-
-        let sync_folders: Vec<SyncFolder> = folders
-            .iter()
-            .map(|folder| {
-                let remote_id = folder.name();
-                let name = decode_modified_utf7(remote_id);
-                let folder_type = Self::map_folder_type(&*name, folder.attributes());
-                let attributes: Vec<String> = folder
-                    .attributes()
-                    .iter()
-                    .map(|attr| format!("{:?}", attr))
-                    .collect();
-
-                SyncFolder {
-                    id: None,
-                    account_id: self.account_id,
-                    name: name.to_string(),
-                    folder_type,
-                    icon: None,
-                    color: None,
-                    synced_at: None,
-                    sync_interval: 0,
-                    remote_id: remote_id.to_string(),
-                    parent_id: None,
-                    attributes,
-                    unread_count: 0,
-                    total_count: 0,
-                    expanded: false,
-                    hidden: false,
-                }
-            })
-            .collect();
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+
+            let folders = session
+                .list(Some(""), Some("*"))
+                .await?
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            let sync_folders: Vec<SyncFolder> = folders
+                .iter()
+                .map(|folder| {
+                    let remote_id = folder.name();
+                    let name = decode_modified_utf7(remote_id);
+                    let (folder_type, hidden) = self.classify_mailbox(folder);
+                    let attributes: Vec<String> = folder
+                        .attributes()
+                        .iter()
+                        .map(|attr| format!("{:?}", attr))
+                        .collect();
+
+                    SyncFolder {
+                        id: None,
+                        account_id: self.account_id,
+                        name: name.to_string(),
+                        folder_type,
+                        icon: None,
+                        color: None,
+                        synced_at: None,
+                        sync_interval: 0,
+                        remote_id: remote_id.to_string(),
+                        parent_id: None,
+                        attributes,
+                        unread_count: 0,
+                        total_count: 0,
+                        expanded: false,
+                        hidden,
+                    }
+                })
+                .collect();
 
-        log::info!(
-            "Fetched {} folders for account {}",
-            sync_folders.len(),
-            self.account_id
-        );
+            log::info!(
+                "Fetched {} folders for account {}",
+                sync_folders.len(),
+                self.account_id
+            );
 
-        Ok(sync_folders)
+            Ok(sync_folders)
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     async fn sync_messages(
@@ -1096,144 +1859,112 @@ impl EmailProvider for ImapProvider {
         folder: &SyncFolder,
         sync_token: Option<String>,
     ) -> SyncResult<crate::sync::types::SyncDiff> {
-        // Parse sync_token (last UID) into since_uid for incremental sync
-        let since_uid = sync_token.and_then(|t| t.parse::<u32>().ok());
-
-        let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
-
-        // Select folder and get mailbox info
-        let mailbox = session.select(&folder.remote_id).await?;
-        let exists = mailbox.exists;
-
-        log::debug!(
-            "Selected folder {} - {} messages exist",
-            folder.remote_id,
-            exists
-        );
-
-        if exists == 0 {
-            log::info!("Folder {} is empty", folder.name);
-            return Ok(crate::sync::types::SyncDiff {
-                added: Vec::new(),
-                modified: Vec::new(),
-                deleted: Vec::new(),
-                next_sync_token: None,
-                is_complete: since_uid.is_none(), // Complete only for full sync
-            });
-        }
-
-        // 1) Use SEARCH to get candidate UIDs (HashSet -> sorted Vec)
-        let uids: Vec<u32> = if let Some(uid) = since_uid {
-            log::debug!(
-                "UID SEARCH for folder {}: UID {}:*",
-                folder.remote_id,
-                uid + 1
-            );
-            let set = session.uid_search(format!("UID {}:*", uid + 1)).await?;
-            let mut v: Vec<u32> = set.into_iter().collect();
-            v.sort_unstable();
-            v
-        } else {
-            log::debug!("UID SEARCH for folder {}: ALL", folder.remote_id);
-            let set = session.uid_search("ALL").await?;
-            let mut v: Vec<u32> = set.into_iter().collect();
-            v.sort_unstable();
-            v
-        };
-
-        if uids.is_empty() {
-            log::info!("No matching UIDs in folder {}", folder.name);
-            return Ok(crate::sync::types::SyncDiff {
-                added: Vec::new(),
-                modified: Vec::new(),
-                deleted: Vec::new(),
-                next_sync_token: None,
-                is_complete: since_uid.is_none(),
-            });
-        }
-
-        log::debug!("Fetching {} emails", uids.len());
+        self.sync_folder_messages(folder, sync_token, None).await
+    }
 
-        // 2) Iterate per UID: map to seq num, fetch by seq, and parse using fallback UID
-        let mut emails: Vec<SyncEmail> = Vec::new();
-        for uid in uids {
-            let seq_nums_set = session.search(format!("UID {}", uid)).await?;
-            let seq = seq_nums_set.iter().min().copied();
-            if seq.is_none() {
-                continue;
-            }
-            let seq = seq.unwrap();
+    async fn sync_recent_messages(
+        &self,
+        folder: &SyncFolder,
+        since: DateTime<Utc>,
+    ) -> SyncResult<crate::sync::types::SyncDiff> {
+        self.sync_folder_messages(folder, None, Some(since)).await
+    }
 
-            let messages =
-                Self::fetch_messages_with_bodies(session, &seq.to_string(), false).await?;
-            if let Some(fetch) = messages.first() {
-                if let Some(folder_id) = folder.id {
-                    match Self::parse_email(fetch, folder_id, self.account_id, Some(uid)) {
-                        Ok(email) => emails.push(email),
-                        Err(e) => log::warn!("Failed to parse email UID {}: {}", uid, e),
-                    }
-                }
-            }
+    async fn fetch_window(
+        &self,
+        folder: &SyncFolder,
+        window: &SyncWindow,
+    ) -> SyncResult<Vec<SyncEmail>> {
+        let mut session_guard = self.get_session().await?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+
+            session.examine(&folder.remote_id).await?;
+
+            // Newest first, like the first sync
+            let mut uids: Vec<u32> = session
+                .uid_search(window_criteria(window))
+                .await?
+                .into_iter()
+                .collect();
+            uids.sort_unstable_by(|a, b| b.cmp(a));
+
+            self.fetch_full_emails(session, folder, &uids).await
         }
-
-        log::info!(
-            "Successfully parsed {} emails from folder {} for account {}",
-            emails.len(),
-            folder.name,
-            self.account_id
-        );
-
-        // Get the highest UID from fetched emails for next incremental sync
-        let next_token = emails
-            .iter()
-            .filter_map(|e| e.remote_id.parse::<u32>().ok())
-            .max()
-            .map(|uid| uid.to_string());
-
-        Ok(crate::sync::types::SyncDiff {
-            added: emails,
-            modified: Vec::new(),
-            deleted: Vec::new(),
-            next_sync_token: next_token,
-            is_complete: since_uid.is_none(), // Complete only for full sync (no since_uid)
-        })
+        .await;
+        session_guard.finish(result)
     }
 
     async fn fetch_email(&self, folder: &SyncFolder, remote_id: &str) -> SyncResult<SyncEmail> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
-
-        // Read-only is fine
-        session.examine(&folder.remote_id).await?;
-
-        let uid: u32 = remote_id
-            .parse()
-            .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+
+            // Read-only is fine
+            session.examine(&folder.remote_id).await?;
+
+            let uid: u32 = remote_id
+                .parse()
+                .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
+
+            let messages = Self::uid_fetch(session, &uid.to_string(), FULL_MESSAGE_ATTRS).await?;
+            self.record_usage(&messages);
+            let fetch = messages
+                .iter()
+                .find(|fetch| fetch.uid == Some(uid))
+                .ok_or_else(|| SyncError::EmailNotFound(remote_id.to_string()))?;
+
+            let folder_id = folder
+                .id
+                .ok_or_else(|| SyncError::DatabaseError("Folder ID is required".to_string()))?;
+
+            Self::parse_email(fetch, folder_id, self.account_id, Some(uid))
+        }
+        .await;
+        session_guard.finish(result)
+    }
 
-        // Map UID -> sequence number via SEARCH (returns HashSet)
-        let seq_nums_set = session.search(format!("UID {}", uid)).await?;
-        let seq = seq_nums_set
+    async fn fetch_emails(
+        &self,
+        folder: &SyncFolder,
+        remote_ids: &[String],
+    ) -> SyncResult<Vec<SyncResult<SyncEmail>>> {
+        let uids: Vec<u32> = remote_ids
             .iter()
-            .min()
-            .copied()
-            .ok_or_else(|| SyncError::EmailNotFound(remote_id.to_string()))?;
+            .filter_map(|remote_id| remote_id.parse().ok())
+            .collect();
 
-        // Fetch by sequence number, parse using fallback UID from the request
-        let messages = Self::fetch_messages_with_bodies(session, &seq.to_string(), false).await?;
-        let fetch = messages
-            .first()
-            .ok_or_else(|| SyncError::EmailNotFound(remote_id.to_string()))?;
+        let fetched = {
+            let mut session_guard = self.get_session().await?;
+            let result: SyncResult<_> = async {
+                let session = session_guard
+                    .as_mut()
+                    .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-        let folder_id = folder
-            .id
-            .ok_or_else(|| SyncError::DatabaseError("Folder ID is required".to_string()))?;
+                session.examine(&folder.remote_id).await?;
+                self.fetch_full_emails(session, folder, &uids).await
+            }
+            .await;
+            session_guard.finish(result)?
+        };
+
+        let mut by_uid: std::collections::HashMap<String, SyncEmail> = fetched
+            .into_iter()
+            .map(|email| (email.remote_id.clone(), email))
+            .collect();
 
-        Self::parse_email(fetch, folder_id, self.account_id, Some(uid))
+        Ok(remote_ids
+            .iter()
+            .map(|remote_id| {
+                by_uid
+                    .remove(remote_id)
+                    .ok_or_else(|| SyncError::EmailNotFound(remote_id.clone()))
+            })
+            .collect())
     }
 
     async fn fetch_attachment(&self, _attachment: &SyncAttachment) -> SyncResult<Vec<u8>> {
@@ -1251,37 +1982,31 @@ impl EmailProvider for ImapProvider {
         to_folder: &SyncFolder,
     ) -> SyncResult<()> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-        session.select(&from_folder.remote_id).await?;
+            session.select(&from_folder.remote_id).await?;
 
-        let uid: u32 = email_remote_id
-            .parse()
-            .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
-
-        // Copy to destination
-        session
-            .uid_copy(uid.to_string(), &to_folder.remote_id)
-            .await?;
-
-        // Mark as deleted in source
-        let _ = session
-            .uid_store(uid.to_string(), "+FLAGS (\\Deleted)")
-            .await?;
+            let uid: u32 = email_remote_id
+                .parse()
+                .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
 
-        // Expunge to permanently remove
-        let _ = session.expunge().await?;
+            self.move_message(session, uid, &to_folder.remote_id)
+                .await?;
 
-        log::info!(
-            "Moved email {} from {} to {}",
-            email_remote_id,
-            from_folder.name,
-            to_folder.name
-        );
+            log::info!(
+                "Moved email {} from {} to {}",
+                email_remote_id,
+                from_folder.name,
+                to_folder.name
+            );
 
-        Ok(())
+            Ok(())
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     async fn delete_email(
@@ -1291,27 +2016,39 @@ impl EmailProvider for ImapProvider {
         permanent: bool,
     ) -> SyncResult<()> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
-
-        session.select(&folder.remote_id).await?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-        let uid: u32 = email_remote_id
-            .parse()
-            .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
+            session.select(&folder.remote_id).await?;
 
-        let _ = session
-            .uid_store(uid.to_string(), "+FLAGS (\\Deleted)")
-            .await?;
+            let uid: u32 = email_remote_id
+                .parse()
+                .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
 
-        if permanent {
-            let _ = session.expunge().await?;
-        }
+            let to_trash = !permanent
+                && folder.folder_type != FolderType::Trash
+                && self.delete_policy() == ImapDeletePolicy::MoveToTrash;
 
-        log::info!("Deleted email {} from {}", email_remote_id, folder.name);
+            if to_trash {
+                // LIST and CREATE leave the selected mailbox alone
+                let trash = self.find_or_create_trash(session).await?;
+                self.move_message(session, uid, &trash).await?;
+                log::info!(
+                    "Moved email {} from {} to Trash",
+                    email_remote_id,
+                    folder.name
+                );
+            } else {
+                self.remove_message(session, uid).await?;
+                log::info!("Deleted email {} from {}", email_remote_id, folder.name);
+            }
 
-        Ok(())
+            Ok(())
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     async fn mark_as_read(
@@ -1321,25 +2058,29 @@ impl EmailProvider for ImapProvider {
         is_read: bool,
     ) -> SyncResult<()> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-        session.select(&folder.remote_id).await?;
+            session.select(&folder.remote_id).await?;
 
-        let uid: u32 = email_remote_id
-            .parse()
-            .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
+            let uid: u32 = email_remote_id
+                .parse()
+                .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
 
-        let flag_cmd = if is_read {
-            "+FLAGS (\\Seen)"
-        } else {
-            "-FLAGS (\\Seen)"
-        };
+            let flag_cmd = if is_read {
+                "+FLAGS (\\Seen)"
+            } else {
+                "-FLAGS (\\Seen)"
+            };
 
-        let _ = session.uid_store(uid.to_string(), flag_cmd).await?;
+            let _ = session.uid_store(uid.to_string(), flag_cmd).await?;
 
-        Ok(())
+            Ok(())
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     async fn set_flag(
@@ -1349,46 +2090,54 @@ impl EmailProvider for ImapProvider {
         flagged: bool,
     ) -> SyncResult<()> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-        session.select(&folder.remote_id).await?;
+            session.select(&folder.remote_id).await?;
 
-        let uid: u32 = email_remote_id
-            .parse()
-            .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
+            let uid: u32 = email_remote_id
+                .parse()
+                .map_err(|_| SyncError::ParseError("Invalid UID".to_string()))?;
 
-        let flag_cmd = if flagged {
-            "+FLAGS (\\Flagged)"
-        } else {
-            "-FLAGS (\\Flagged)"
-        };
+            let flag_cmd = if flagged {
+                "+FLAGS (\\Flagged)"
+            } else {
+                "-FLAGS (\\Flagged)"
+            };
 
-        let _ = session.uid_store(uid.to_string(), flag_cmd).await?;
+            let _ = session.uid_store(uid.to_string(), flag_cmd).await?;
 
-        Ok(())
+            Ok(())
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     async fn rename_folder(&self, folder: &SyncFolder, new_name: &str) -> SyncResult<()> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
-
-        // IMAP RENAME command: RENAME old_name new_name
-        // Build the full path for the new name (keeping the same parent)
-        let old_path = &folder.remote_id;
-        let new_path = if let Some(parent_sep_pos) = old_path.rfind('/') {
-            format!("{}/{}", &old_path[..parent_sep_pos], new_name)
-        } else {
-            new_name.to_string()
-        };
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+
+            // IMAP RENAME command: RENAME old_name new_name
+            // Build the full path for the new name (keeping the same parent)
+            let old_path = &folder.remote_id;
+            let new_path = if let Some(parent_sep_pos) = old_path.rfind('/') {
+                format!("{}/{}", &old_path[..parent_sep_pos], new_name)
+            } else {
+                new_name.to_string()
+            };
 
-        session.rename(old_path, &new_path).await?;
+            session.rename(old_path, &new_path).await?;
 
-        log::info!("Renamed IMAP folder from '{}' to '{}'", old_path, new_path);
-        Ok(())
+            log::info!("Renamed IMAP folder from '{}' to '{}'", old_path, new_path);
+            Ok(())
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     async fn move_folder(
@@ -1397,28 +2146,89 @@ impl EmailProvider for ImapProvider {
         new_parent_path: Option<&str>,
     ) -> SyncResult<()> {
         let mut session_guard = self.get_session().await?;
-        let session = session_guard
-            .as_mut()
-            .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
 
-        let old_path = &folder.remote_id;
+            let old_path = &folder.remote_id;
 
-        // Extract folder name from current path
-        let folder_name = old_path.rsplit('/').next().unwrap_or(old_path);
+            // Extract folder name from current path
+            let folder_name = old_path.rsplit('/').next().unwrap_or(old_path);
 
-        // Build new path based on parent
-        let new_path = if let Some(parent) = new_parent_path {
-            format!("{}/{}", parent, folder_name)
-        } else {
-            // Moving to root
-            folder_name.to_string()
-        };
+            // Build new path based on parent
+            let new_path = if let Some(parent) = new_parent_path {
+                format!("{}/{}", parent, folder_name)
+            } else {
+                // Moving to root
+                folder_name.to_string()
+            };
 
-        // IMAP RENAME command works for moving folders too
-        session.rename(old_path, &new_path).await?;
+            // IMAP RENAME command works for moving folders too
+            session.rename(old_path, &new_path).await?;
 
-        log::info!("Moved IMAP folder from '{}' to '{}'", old_path, new_path);
-        Ok(())
+            log::info!("Moved IMAP folder from '{}' to '{}'", old_path, new_path);
+            Ok(())
+        }
+        .await;
+        session_guard.finish(result)
+    }
+
+    async fn save_draft(
+        &self,
+        drafts_folder: &SyncFolder,
+        mime: Vec<u8>,
+        replaces: Option<&str>,
+    ) -> SyncResult<String> {
+        let message_id = MessageParser::default()
+            .parse(&mime)
+            .and_then(|message| message.message_id().map(|id| id.to_string()))
+            .ok_or_else(|| SyncError::ParseError("Draft has no Message-ID".to_string()))?;
+
+        let mut session_guard = self.get_session().await?;
+        let result: SyncResult<_> = async {
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| SyncError::ImapError("No active session".to_string()))?;
+
+            session
+                .append(
+                    &drafts_folder.remote_id,
+                    Some("(\\Draft \\Seen)"),
+                    None,
+                    &mime,
+                )
+                .await?;
+
+            // APPEND does not return the new UID without UIDPLUS, so the copy is
+            // looked up by its Message-ID; UIDs only grow, so it is the newest match
+            session.select(&drafts_folder.remote_id).await?;
+            let uid = session
+                .uid_search(format!("HEADER Message-ID \"{}\"", message_id))
+                .await?
+                .into_iter()
+                .max()
+                .ok_or_else(|| {
+                    SyncError::ImapError(format!("Saved draft {} not found", message_id))
+                })?;
+
+            if let Some(previous_uid) = replaces.and_then(|id| id.parse::<u32>().ok()) {
+                if previous_uid != uid {
+                    self.remove_message(session, previous_uid).await?;
+                }
+            }
+
+            log::info!(
+                "Saved draft {} to {} as UID {}",
+                message_id,
+                drafts_folder.name,
+                uid
+            );
+
+            Ok(uid.to_string())
+        }
+        .await;
+        session_guard.finish(result)
     }
 
     async fn get_sync_token(&self) -> SyncResult<Option<String>> {
@@ -1504,3 +2314,297 @@ fn decode_modified_utf7(input: &str) -> String {
 
     out
 }
+
+/// UID SEARCH criteria for messages received within `window`. IMAP compares
+/// dates without the time, so the windows of a backfill overlap by a day.
+fn window_criteria(window: &SyncWindow) -> String {
+    let mut criteria = Vec::new();
+    if let Some(after) = window.after {
+        criteria.push(format!("SINCE {}", after.format("%-d-%b-%Y")));
+    }
+    if let Some(before) = window.before {
+        criteria.push(format!("BEFORE {}", before.format("%-d-%b-%Y")));
+    }
+
+    if criteria.is_empty() {
+        "ALL".to_string()
+    } else {
+        criteria.join(" ")
+    }
+}
+
+/// UID sets of at most `batch_size` UIDs each, with consecutive UIDs
+/// collapsed into ranges (`1:500,734`)
+fn uid_sets(uids: &[u32], batch_size: usize) -> Vec<String> {
+    let mut sorted = uids.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    sorted
+        .chunks(batch_size.max(1))
+        .map(|chunk| {
+            let mut ranges: Vec<(u32, u32)> = Vec::new();
+            for &uid in chunk {
+                match ranges.last_mut() {
+                    Some((_, end)) if *end + 1 == uid => *end = uid,
+                    _ => ranges.push((uid, uid)),
+                }
+            }
+            ranges
+                .iter()
+                .map(|&(start, end)| {
+                    if start == end {
+                        start.to_string()
+                    } else {
+                        format!("{}:{}", start, end)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect()
+}
+
+/// Attachments listed in a message's BODYSTRUCTURE. They carry no content
+/// and are replaced by the attachments of the body once it is fetched.
+fn structure_attachments(structure: &BodyStructure) -> Vec<SyncAttachment> {
+    let mut attachments = Vec::new();
+    collect_structure_attachments(structure, "", &mut attachments);
+    attachments
+}
+
+/// Walk the parts below `part` (`1.2` style section numbers), the way
+/// mail_parser tells attachments from body parts: text parts are body unless
+/// sent as attachment, any other part is an attachment
+fn collect_structure_attachments(
+    structure: &BodyStructure,
+    part: &str,
+    attachments: &mut Vec<SyncAttachment>,
+) {
+    let (common, other) = match structure {
+        BodyStructure::Multipart { bodies, .. } => {
+            for (index, body) in bodies.iter().enumerate() {
+                let child = if part.is_empty() {
+                    (index + 1).to_string()
+                } else {
+                    format!("{}.{}", part, index + 1)
+                };
+                collect_structure_attachments(body, &child, attachments);
+            }
+            return;
+        }
+        BodyStructure::Basic { common, other, .. }
+        | BodyStructure::Text { common, other, .. }
+        | BodyStructure::Message { common, other, .. } => (common, other),
+    };
+
+    let disposition = common.disposition.as_ref();
+    let is_attachment =
+        disposition.is_some_and(|disposition| disposition.ty.eq_ignore_ascii_case("attachment"));
+    if common.ty.ty.eq_ignore_ascii_case("text") && !is_attachment {
+        return;
+    }
+
+    let filename = disposition
+        .and_then(|disposition| param_value(&disposition.params, "filename"))
+        .or_else(|| param_value(&common.ty.params, "name"))
+        .unwrap_or_else(|| "attachment".to_string());
+    let content_id = other.id.as_ref().map(|id| {
+        id.trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_string()
+    });
+    // Without the HTML body the Content-ID cannot be checked for a reference,
+    // so inline parts with one are taken as embedded
+    let is_inline = content_id.is_some()
+        && disposition.map_or(true, |disposition| {
+            disposition.ty.eq_ignore_ascii_case("inline")
+        });
+    // BODYSTRUCTURE gives the encoded size
+    let size = match other.transfer_encoding {
+        ContentEncoding::Base64 => other.octets as i64 * 3 / 4,
+        _ => other.octets as i64,
+    };
+
+    attachments.push(SyncAttachment {
+        id: None,
+        email_id: None,
+        filename,
+        content_type: format!("{}/{}", common.ty.ty, common.ty.subtype).to_ascii_lowercase(),
+        size,
+        hash: format!(
+            "{}{}",
+            STRUCTURE_HASH_PREFIX,
+            if part.is_empty() { "1" } else { part }
+        ),
+        cache_path: None,
+        remote_url: None,
+        remote_path: None,
+        is_inline,
+        is_cached: false,
+        content_id,
+        data: None,
+    });
+}
+
+/// Value of a Content-Type or Content-Disposition parameter, decoding RFC 2047
+/// encoded words and RFC 2231 extended values (`filename*=UTF-8''a%20b.pdf`)
+fn param_value(params: &BodyParams, name: &str) -> Option<String> {
+    let params = params.as_ref()?;
+    if let Some((_, value)) = params
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    {
+        if !value.contains("=?") {
+            return Some(value.to_string());
+        }
+        let header = format!("Subject: {}\r\n\r\n", value);
+        return MessageParser::default()
+            .parse_headers(header.as_bytes())
+            .and_then(|message| message.subject().map(|s| s.to_string()))
+            .or_else(|| Some(value.to_string()));
+    }
+
+    let extended = format!("{}*", name);
+    params
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(&extended))
+        .map(|(_, value)| {
+            let encoded = value.splitn(3, '\'').nth(2).unwrap_or(value);
+            percent_encoding::percent_decode_str(encoded)
+                .decode_utf8_lossy()
+                .into_owned()
+        })
+}
+
+/// Session locked for one provider call
+///
+/// A command that failed or was cancelled halfway may leave the connection in
+/// an unknown state (a mailbox selected, a response not read to the end), so
+/// unless the call is finished successfully the session is closed when the
+/// guard is dropped and the next call connects again.
+struct SessionGuard<'a> {
+    session: tokio::sync::MutexGuard<'a, Option<ImapSession>>,
+    completed: bool,
+}
+
+impl SessionGuard<'_> {
+    /// Keep the session for further calls if `result` is a success
+    fn finish<T>(mut self, result: SyncResult<T>) -> SyncResult<T> {
+        self.completed = result.is_ok();
+        result
+    }
+}
+
+impl std::ops::Deref for SessionGuard<'_> {
+    type Target = Option<ImapSession>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl std::ops::DerefMut for SessionGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.session
+    }
+}
+
+impl Drop for SessionGuard<'_> {
+    fn drop(&mut self) {
+        if !self.completed && self.session.take().is_some() {
+            log::debug!("[ImapProvider] Closing IMAP session after an unfinished command");
+        }
+    }
+}
+
+impl Drop for ImapProvider {
+    fn drop(&mut self) {
+        // Only sessions whose last command completed are left here (see
+        // `SessionGuard`); they go back to the pool for the account's next sync
+        if let Some(session) = self
+            .session
+            .try_lock()
+            .ok()
+            .and_then(|mut session| session.take())
+        {
+            imap_pool::checkin(self.account_id, session);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uid_sets() {
+        assert!(uid_sets(&[], 500).is_empty());
+        assert_eq!(uid_sets(&[5, 1, 2, 3, 9, 3], 500), vec!["1:3,5,9"]);
+        assert_eq!(uid_sets(&[1, 2, 3, 4, 5], 2), vec!["1:2", "3:4", "5"]);
+    }
+
+    #[test]
+    fn test_structure_attachments() {
+        let response = b"* 1 FETCH (BODYSTRUCTURE (\
+            ((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"utf-8\") NIL NIL \"7BIT\" 120 4 NIL NIL NIL NIL)\
+             (\"TEXT\" \"HTML\" (\"CHARSET\" \"utf-8\") NIL NIL \"7BIT\" 300 9 NIL NIL NIL NIL)\
+             \"ALTERNATIVE\" (\"BOUNDARY\" \"b2\") NIL NIL NIL)\
+            (\"IMAGE\" \"PNG\" (\"NAME\" \"logo.png\") \"<logo@ravn>\" NIL \"BASE64\" 400 NIL (\"INLINE\" NIL) NIL NIL)\
+            (\"APPLICATION\" \"PDF\" NIL NIL NIL \"BASE64\" 4000 NIL \
+             (\"ATTACHMENT\" (\"FILENAME*\" \"UTF-8''Rechnung%20M%C3%A4rz.pdf\")) NIL NIL)\
+            \"MIXED\" (\"BOUNDARY\" \"b1\") NIL NIL NIL))\r\n";
+        let (_, Response::Fetch(_, attributes)) =
+            async_imap::imap_proto::parser::parse_response(response).unwrap()
+        else {
+            panic!("expected a FETCH response");
+        };
+        let Some(async_imap::imap_proto::AttributeValue::BodyStructure(structure)) =
+            attributes.first()
+        else {
+            panic!("expected a BODYSTRUCTURE");
+        };
+
+        let attachments = structure_attachments(structure);
+        assert_eq!(attachments.len(), 2);
+
+        assert_eq!(attachments[0].filename, "logo.png");
+        assert_eq!(attachments[0].content_type, "image/png");
+        assert_eq!(attachments[0].content_id.as_deref(), Some("logo@ravn"));
+        assert!(attachments[0].is_inline);
+        assert_eq!(attachments[0].hash, "structure:2");
+
+        assert_eq!(attachments[1].filename, "Rechnung März.pdf");
+        assert_eq!(attachments[1].size, 3000);
+        assert!(!attachments[1].is_inline);
+        assert_eq!(attachments[1].hash, "structure:3");
+    }
+
+    #[test]
+    fn test_idle_backoff() {
+        let mut backoff = IDLE_INITIAL_BACKOFF;
+        let mut waits = Vec::new();
+        for _ in 0..8 {
+            waits.push(backoff.as_secs());
+            backoff = next_idle_backoff(backoff);
+        }
+        assert_eq!(waits, vec![5, 10, 20, 40, 80, 160, 300, 300]);
+    }
+
+    #[test]
+    fn test_idle_errors_retried() {
+        assert!(is_fatal_idle_error(&SyncError::AuthenticationError(
+            "bad credentials".to_string()
+        )));
+        assert!(is_fatal_idle_error(&SyncError::NotSupported(
+            "no IDLE".to_string()
+        )));
+        assert!(!is_fatal_idle_error(&SyncError::ImapError(
+            "SELECT failed".to_string()
+        )));
+        assert!(!is_fatal_idle_error(&SyncError::NetworkError(
+            "connection reset".to_string()
+        )));
+    }
+}
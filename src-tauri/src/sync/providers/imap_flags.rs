@@ -0,0 +1,220 @@
+//! Delta tokens of IMAP folders, and detection of read and flagged changes
+//! made by other clients. Servers with CONDSTORE report the changed messages
+//! since the last HIGHESTMODSEQ; on other servers the flags of the most recent
+//! messages are compared with a snapshot kept in the sync token.
+use std::collections::BTreeSet;
+
+/// Most recent messages whose flags are compared on servers without CONDSTORE
+pub const FLAG_WINDOW: u32 = 500;
+
+/// Delta token of an IMAP folder: the highest UID seen, the UIDVALIDITY the
+/// UIDs belong to, and what the next sync compares flags against.
+///
+/// Serialized as `<uid>` followed by `;validity=<n>` and either
+/// `;modseq=<n>` or `;flags=<first uid>/<unseen uids>/<flagged uids>`.
+/// Tokens from before flag reconciliation are plain UIDs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImapSyncToken {
+    pub last_uid: u32,
+    pub uid_validity: Option<u32>,
+    pub changes: ChangeMarker,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeMarker {
+    None,
+    /// HIGHESTMODSEQ of the mailbox at the last sync
+    ModSeq(u64),
+    Flags(FlagSnapshot),
+}
+
+/// Read and flagged state of the most recent messages at the last sync
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlagSnapshot {
+    /// Lowest UID covered by the snapshot
+    pub first_uid: u32,
+    pub unseen: BTreeSet<u32>,
+    pub flagged: BTreeSet<u32>,
+}
+
+/// Flags of one message that are mirrored locally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageFlags {
+    pub uid: u32,
+    pub seen: bool,
+    pub flagged: bool,
+}
+
+impl ImapSyncToken {
+    pub fn parse(token: &str) -> Option<Self> {
+        let mut parts = token.split(';');
+        let mut parsed = Self {
+            last_uid: parts.next()?.trim().parse().ok()?,
+            uid_validity: None,
+            changes: ChangeMarker::None,
+        };
+
+        for part in parts {
+            match part.split_once('=') {
+                Some(("validity", validity)) => parsed.uid_validity = validity.parse().ok(),
+                Some(("modseq", modseq)) => {
+                    if let Ok(modseq) = modseq.parse() {
+                        parsed.changes = ChangeMarker::ModSeq(modseq);
+                    }
+                }
+                Some(("flags", snapshot)) => {
+                    if let Some(snapshot) = FlagSnapshot::parse(snapshot) {
+                        parsed.changes = ChangeMarker::Flags(snapshot);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(parsed)
+    }
+}
+
+impl std::fmt::Display for ImapSyncToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.last_uid)?;
+        if let Some(uid_validity) = self.uid_validity {
+            write!(f, ";validity={}", uid_validity)?;
+        }
+        match &self.changes {
+            ChangeMarker::None => Ok(()),
+            ChangeMarker::ModSeq(modseq) => write!(f, ";modseq={}", modseq),
+            ChangeMarker::Flags(snapshot) => write!(
+                f,
+                ";flags={}/{}/{}",
+                snapshot.first_uid,
+                join_uids(&snapshot.unseen),
+                join_uids(&snapshot.flagged)
+            ),
+        }
+    }
+}
+
+fn join_uids(uids: &BTreeSet<u32>) -> String {
+    uids.iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_uids(uids: &str) -> Option<BTreeSet<u32>> {
+    uids.split(',')
+        .filter(|uid| !uid.is_empty())
+        .map(|uid| uid.parse().ok())
+        .collect()
+}
+
+impl FlagSnapshot {
+    fn parse(snapshot: &str) -> Option<Self> {
+        let mut parts = snapshot.split('/');
+        Some(Self {
+            first_uid: parts.next()?.parse().ok()?,
+            unseen: parse_uids(parts.next()?)?,
+            flagged: parse_uids(parts.next()?)?,
+        })
+    }
+
+    pub fn from_messages(messages: &[MessageFlags]) -> Self {
+        Self {
+            first_uid: messages.iter().map(|m| m.uid).min().unwrap_or(0),
+            unseen: messages.iter().filter(|m| !m.seen).map(|m| m.uid).collect(),
+            flagged: messages
+                .iter()
+                .filter(|m| m.flagged)
+                .map(|m| m.uid)
+                .collect(),
+        }
+    }
+
+    /// UIDs of the given messages whose read or flagged state differs from
+    /// this snapshot. Only messages covered by the snapshot are compared:
+    /// those from `first_uid` up to `last_uid`, the highest UID synced then.
+    pub fn changed_uids(&self, messages: &[MessageFlags], last_uid: u32) -> Vec<u32> {
+        messages
+            .iter()
+            .filter(|m| m.uid >= self.first_uid && m.uid <= last_uid)
+            .filter(|m| {
+                m.seen == self.unseen.contains(&m.uid) || m.flagged != self.flagged.contains(&m.uid)
+            })
+            .map(|m| m.uid)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(uid: u32, seen: bool, flagged: bool) -> MessageFlags {
+        MessageFlags { uid, seen, flagged }
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let legacy = ImapSyncToken::parse("42").unwrap();
+        assert_eq!(legacy.last_uid, 42);
+        assert_eq!(legacy.changes, ChangeMarker::None);
+
+        let condstore = ImapSyncToken {
+            last_uid: 42,
+            uid_validity: Some(7),
+            changes: ChangeMarker::ModSeq(9001),
+        };
+        assert_eq!(condstore.to_string(), "42;validity=7;modseq=9001");
+        assert_eq!(
+            ImapSyncToken::parse("42;validity=7;modseq=9001"),
+            Some(condstore)
+        );
+        // Tokens written before UIDVALIDITY was tracked
+        assert_eq!(
+            ImapSyncToken::parse("42;modseq=9001").unwrap().uid_validity,
+            None
+        );
+
+        let snapshot = ImapSyncToken {
+            last_uid: 42,
+            uid_validity: None,
+            changes: ChangeMarker::Flags(FlagSnapshot::from_messages(&[
+                flags(40, true, false),
+                flags(41, false, true),
+                flags(42, true, false),
+            ])),
+        };
+        assert_eq!(snapshot.to_string(), "42;flags=40/41/41");
+        assert_eq!(ImapSyncToken::parse("42;flags=40/41/41"), Some(snapshot));
+
+        assert_eq!(
+            ImapSyncToken::parse("42;flags=40/x/").unwrap().changes,
+            ChangeMarker::None
+        );
+        assert!(ImapSyncToken::parse("not-a-uid").is_none());
+    }
+
+    #[test]
+    fn test_changed_uids() {
+        let snapshot = FlagSnapshot::from_messages(&[
+            flags(10, true, false),
+            flags(11, false, false),
+            flags(12, true, true),
+        ]);
+
+        let current = [
+            // Older than the snapshot
+            flags(9, false, false),
+            flags(10, true, false),
+            // Read elsewhere
+            flags(11, true, false),
+            // Unflagged elsewhere
+            flags(12, true, false),
+            // New since the last sync
+            flags(13, false, false),
+        ];
+
+        assert_eq!(snapshot.changed_uids(&current, 12), vec![11, 12]);
+    }
+}
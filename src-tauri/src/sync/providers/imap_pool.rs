@@ -0,0 +1,62 @@
+//! IMAP connections kept open per account between provider instances
+//!
+//! A provider is created for every folder sync, so without a pool each sync
+//! would connect and log in again. Providers check a session out when they
+//! first need one and return it when dropped; folder syncs running in
+//! parallel each get their own session. A session whose last command failed
+//! or was cancelled is closed instead of returned, as its state is unknown.
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use super::imap::ImapSession;
+
+/// Idle sessions kept per account
+const MAX_IDLE: usize = 3;
+/// Servers drop idle connections after a while (RFC 3501 allows 30 minutes),
+/// so older sessions are closed rather than handed out
+const MAX_IDLE_TIME: Duration = Duration::from_secs(5 * 60);
+
+struct IdleSession {
+    session: ImapSession,
+    since: Instant,
+}
+
+static POOL: Lazy<Mutex<HashMap<Uuid, Vec<IdleSession>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Most recently used idle session of the account. It may still have been
+/// closed by the server, so callers check it before use.
+pub fn checkout(account_id: Uuid) -> Option<ImapSession> {
+    let mut pool = POOL.lock().unwrap();
+    let idle = pool.get_mut(&account_id)?;
+    idle.retain(|entry| entry.since.elapsed() < MAX_IDLE_TIME);
+
+    idle.pop().map(|entry| entry.session)
+}
+
+/// Keep `session` for the account's next sync. Sessions beyond the pool
+/// size are closed.
+pub fn checkin(account_id: Uuid, session: ImapSession) {
+    let mut pool = POOL.lock().unwrap();
+    for idle in pool.values_mut() {
+        idle.retain(|entry| entry.since.elapsed() < MAX_IDLE_TIME);
+    }
+    pool.retain(|_, idle| !idle.is_empty());
+
+    let idle = pool.entry(account_id).or_default();
+    if idle.len() < MAX_IDLE {
+        idle.push(IdleSession {
+            session,
+            since: Instant::now(),
+        });
+    }
+}
+
+/// Close the idle sessions of the account, e.g. after its credentials
+/// changed
+pub fn clear(account_id: Uuid) {
+    POOL.lock().unwrap().remove(&account_id);
+}
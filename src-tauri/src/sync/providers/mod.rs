@@ -1,3 +1,7 @@
 pub mod gmail;
+pub mod graph_batch;
+pub mod icloud;
 pub mod imap;
+pub mod imap_flags;
+pub mod imap_pool;
 pub mod office365;
@@ -1,8 +1,13 @@
+use super::graph_batch::{BatchRequest, BatchRequestBody, BatchResponseBody, MAX_BATCH_SIZE};
 use crate::database::models::email::EmailAddress;
 use crate::sync::{
+    attachment_download::AttachmentSink,
+    attachment_policy::AttachmentDownloadPolicy,
     auth::{CredentialStore, OAuth2Helper},
     error::{SyncError, SyncResult},
+    network_usage,
     provider::EmailProvider,
+    rate_limit, sync_limits,
     types::*,
 };
 use async_trait::async_trait;
@@ -14,6 +19,11 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 
 const GRAPH_API_BASE: &str = "https://graph.microsoft.com/v1.0";
+/// Graph rejects larger attachments inline in a message; they go through an
+/// upload session instead
+const UPLOAD_SESSION_THRESHOLD: usize = 3 * 1024 * 1024;
+/// Upload session chunks must be a multiple of 320 KiB
+const UPLOAD_CHUNK_SIZE: usize = 10 * 320 * 1024;
 
 pub struct Office365Provider {
     account_id: Uuid,
@@ -21,6 +31,7 @@ pub struct Office365Provider {
     access_token: Arc<RwLock<Option<String>>>,
     credential_store: Arc<CredentialStore>,
     app_handle: Option<tauri::AppHandle>,
+    attachment_policy: AttachmentDownloadPolicy,
 }
 
 #[derive(Debug, Deserialize)]
@@ -137,6 +148,76 @@ struct GraphAttachmentsResponse {
     value: Vec<GraphAttachment>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphSubscription {
+    pub id: String,
+    pub resource: String,
+    #[serde(rename = "expirationDateTime")]
+    pub expiration_date_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSubscriptionRequest {
+    change_type: String,
+    notification_url: String,
+    lifecycle_notification_url: String,
+    resource: String,
+    expiration_date_time: String,
+    client_state: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenewSubscriptionRequest {
+    expiration_date_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphContactsResponse {
+    value: Vec<GraphContact>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphContact {
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+    #[serde(rename = "emailAddresses", default)]
+    email_addresses: Vec<GraphContactEmail>,
+    birthday: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphUser {
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphMailboxSettings {
+    #[serde(rename = "timeZone")]
+    time_zone: Option<String>,
+    language: Option<GraphLocaleInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphFolderSize {
+    #[serde(rename = "sizeInBytes")]
+    size_in_bytes: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphLocaleInfo {
+    locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphContactEmail {
+    address: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct GraphErrorResponse {
     error: Option<GraphErrorBody>,
@@ -204,6 +285,7 @@ impl Office365Provider {
             access_token: Arc::new(RwLock::new(None)),
             credential_store,
             app_handle: None,
+            attachment_policy: AttachmentDownloadPolicy::default(),
         })
     }
 
@@ -332,9 +414,13 @@ impl Office365Provider {
         Fut: std::future::Future<Output = Result<reqwest::Response, reqwest::Error>>,
     {
         let token = self.ensure_token().await?;
-        let response = operation(token)
+        let response =
+            rate_limit::send_with_retry(sync_limits::GRAPH_HOST, self.account_id, || {
+                operation(token.clone())
+            })
             .await
             .map_err(|e| SyncError::NetworkError(e.to_string()))?;
+        let response = network_usage::record_response(self.account_id, "office365", response, 1);
 
         if response.status().as_u16() == 401 {
             log::warn!("[Office365] Got 401 Unauthorized, attempting token refresh");
@@ -342,9 +428,14 @@ impl Office365Provider {
             self.handle_401_error().await?;
 
             let new_token = self.ensure_token().await?;
-            let retry_response = operation(new_token)
+            let retry_response =
+                rate_limit::send_with_retry(sync_limits::GRAPH_HOST, self.account_id, || {
+                    operation(new_token.clone())
+                })
                 .await
                 .map_err(|e| SyncError::NetworkError(e.to_string()))?;
+            let retry_response =
+                network_usage::record_response(self.account_id, "office365", retry_response, 1);
 
             Ok(retry_response)
         } else {
@@ -352,11 +443,211 @@ impl Office365Provider {
         }
     }
 
-    fn parse_retry_after_seconds(headers: &reqwest::header::HeaderMap) -> Option<u64> {
-        headers
-            .get(reqwest::header::RETRY_AFTER)
-            .and_then(|value| value.to_str().ok())
-            .and_then(|value| value.trim().parse::<u64>().ok())
+    /// Sends a message with attachments too large to include inline: the
+    /// message is created as a draft, the attachments are uploaded to it in
+    /// chunks, and the draft is sent. The draft is removed again on failure.
+    async fn send_with_upload_sessions(
+        &self,
+        message: serde_json::Value,
+        large_attachments: Vec<EmailAttachmentData>,
+    ) -> SyncResult<()> {
+        #[derive(Deserialize)]
+        struct CreatedMessage {
+            id: String,
+        }
+
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                let body = message.clone();
+                async move {
+                    client
+                        .post(format!("{}/me/messages", GRAPH_API_BASE))
+                        .bearer_auth(token)
+                        .json(&body)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read error".to_string());
+            return Err(SyncError::Office365Error(format!(
+                "Failed to create message (status {}): {}",
+                status, error_text
+            )));
+        }
+
+        let created: CreatedMessage = response.json().await?;
+
+        let result = async {
+            for attachment in &large_attachments {
+                self.upload_attachment(&created.id, attachment).await?;
+            }
+
+            let response = self
+                .execute_with_401_retry(|token| {
+                    let client = self.client.clone();
+                    let message_id = created.id.clone();
+                    async move {
+                        client
+                            .post(format!(
+                                "{}/me/messages/{}/send",
+                                GRAPH_API_BASE, message_id
+                            ))
+                            .bearer_auth(token)
+                            .header(reqwest::header::CONTENT_LENGTH, 0)
+                            .send()
+                            .await
+                    }
+                })
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unable to read error".to_string());
+                return Err(SyncError::Office365Error(format!(
+                    "Failed to send email (status {}): {}",
+                    status, error_text
+                )));
+            }
+
+            Ok::<(), SyncError>(())
+        }
+        .await;
+
+        if result.is_err() {
+            let removed = self
+                .execute_with_401_retry(|token| {
+                    let client = self.client.clone();
+                    let message_id = created.id.clone();
+                    async move {
+                        client
+                            .delete(format!("{}/me/messages/{}", GRAPH_API_BASE, message_id))
+                            .bearer_auth(token)
+                            .send()
+                            .await
+                    }
+                })
+                .await;
+            if let Err(e) = removed {
+                log::warn!(
+                    "[Office365] Failed to remove unsent message {}: {}",
+                    created.id,
+                    e
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Uploads an attachment to a message through an upload session
+    async fn upload_attachment(
+        &self,
+        message_id: &str,
+        attachment: &EmailAttachmentData,
+    ) -> SyncResult<()> {
+        #[derive(Deserialize)]
+        struct UploadSession {
+            #[serde(rename = "uploadUrl")]
+            upload_url: String,
+        }
+
+        let size = attachment.content.len();
+        let mut item = serde_json::json!({
+            "attachmentType": "file",
+            "name": attachment.filename,
+            "size": size,
+            "contentType": attachment
+                .content_type
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+        });
+        if let Some(content_id) = &attachment.content_id {
+            item["isInline"] = serde_json::json!(true);
+            item["contentId"] = serde_json::json!(content_id);
+        }
+        let body = serde_json::json!({ "AttachmentItem": item });
+
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                let body = body.clone();
+                async move {
+                    client
+                        .post(format!(
+                            "{}/me/messages/{}/attachments/createUploadSession",
+                            GRAPH_API_BASE, message_id
+                        ))
+                        .bearer_auth(token)
+                        .json(&body)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read error".to_string());
+            return Err(SyncError::Office365Error(format!(
+                "Failed to start uploading {} (status {}): {}",
+                attachment.filename, status, error_text
+            )));
+        }
+
+        let session: UploadSession = response.json().await?;
+
+        for (index, chunk) in attachment.content.chunks(UPLOAD_CHUNK_SIZE).enumerate() {
+            let start = index * UPLOAD_CHUNK_SIZE;
+            let end = start + chunk.len() - 1;
+
+            // The upload URL is pre-authorized and rejects a bearer token
+            let response = self
+                .client
+                .put(&session.upload_url)
+                .header(
+                    reqwest::header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, size),
+                )
+                .body(chunk.to_vec())
+                .send()
+                .await
+                .map_err(|e| SyncError::NetworkError(e.to_string()))?;
+            let response =
+                network_usage::record_response(self.account_id, "office365", response, 1);
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unable to read error".to_string());
+                return Err(SyncError::Office365Error(format!(
+                    "Failed to upload {} (status {}): {}",
+                    attachment.filename, status, error_text
+                )));
+            }
+        }
+
+        log::info!(
+            "[Office365] Uploaded attachment {} ({} bytes)",
+            attachment.filename,
+            size
+        );
+        Ok(())
     }
 
     async fn graph_get_json_with_retry<T, F, Fut>(
@@ -413,42 +704,453 @@ impl Office365Provider {
                 .and_then(|err| err.message.as_deref())
                 .unwrap_or(error_text.as_str());
 
-            let is_retryable = status.as_u16() == 429
-                || status.as_u16() == 503
-                || status.as_u16() == 504
-                || matches!(error_code, "ApplicationThrottled" | "TooManyRequests");
+            // Throttling responses were already retried by the rate limiter,
+            // which marks the account as throttled once it gives up
+            let is_retryable = status.as_u16() == 504;
+
+            if !is_retryable || attempt >= 3 {
+                return Err(SyncError::Office365Error(format!(
+                    "{} (status {} / {}): {}",
+                    operation_name, status, error_code, error_message
+                )));
+            }
+
+            let delay = rate_limit::retry_after(&headers, Utc::now())
+                .unwrap_or_else(|| rate_limit::backoff(attempt));
+
+            log::warn!(
+                "[Office365] {} retryable failure (status {} / {}, attempt {}/4). Retrying in {}s",
+                operation_name,
+                status,
+                error_code,
+                attempt + 1,
+                delay.as_secs()
+            );
+
+            last_error = Some(SyncError::Office365Error(format!(
+                "{} (status {} / {}): {}",
+                operation_name, status, error_code, error_message
+            )));
+
+            tokio::time::sleep(delay).await;
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            SyncError::Office365Error(format!("{} failed after retries", operation_name))
+        }))
+    }
+
+    /// GETs `urls` (relative to the API version) through `$batch`,
+    /// MAX_BATCH_SIZE at a time, with one result per URL in the same order.
+    /// Throttled requests are sent again after the longest Retry-After of
+    /// their batch; the others resolve right away.
+    async fn graph_batch<T>(
+        &self,
+        operation_name: &str,
+        urls: &[String],
+    ) -> SyncResult<Vec<SyncResult<T>>>
+    where
+        T: serde::de::DeserializeOwned + Send,
+    {
+        let mut results: Vec<Option<SyncResult<T>>> = urls.iter().map(|_| None).collect();
+
+        for (chunk_index, chunk) in urls.chunks(MAX_BATCH_SIZE).enumerate() {
+            let offset = chunk_index * MAX_BATCH_SIZE;
+            let mut pending: Vec<BatchRequest> = chunk
+                .iter()
+                .enumerate()
+                .map(|(index, url)| BatchRequest::get((offset + index).to_string(), url.clone()))
+                .collect();
+
+            for attempt in 0..=3u32 {
+                let body = serde_json::to_value(BatchRequestBody { requests: &pending })?;
+                let response = self
+                    .execute_with_401_retry(|token| {
+                        let client = self.client.clone();
+                        let body = body.clone();
+                        async move {
+                            client
+                                .post(format!("{}/$batch", GRAPH_API_BASE))
+                                .bearer_auth(token)
+                                .json(&body)
+                                .send()
+                                .await
+                        }
+                    })
+                    .await?;
+
+                let status = response.status();
+                let mut retry = Vec::new();
+                let mut delay = None;
+                let mut throttled = false;
+
+                if status.is_success() {
+                    let batch: BatchResponseBody = response.json().await?;
+
+                    for item in batch.responses {
+                        let Some(index) = item.id.parse::<usize>().ok().filter(|i| *i < urls.len())
+                        else {
+                            continue;
+                        };
+
+                        if item.is_retryable() {
+                            if attempt < 3 {
+                                throttled |= item.status == 429;
+                                delay = delay.max(item.retry_after());
+                                retry.extend(pending.iter().find(|r| r.id == item.id).cloned());
+                                continue;
+                            }
+                            rate_limit::throttle_account(
+                                self.account_id,
+                                item.retry_after()
+                                    .unwrap_or_else(|| rate_limit::backoff(attempt)),
+                            );
+                        }
+
+                        results[index] = Some(if item.is_success() {
+                            serde_json::from_value(item.body).map_err(|e| {
+                                SyncError::Office365Error(format!(
+                                    "{}: failed to parse response body: {}",
+                                    operation_name, e
+                                ))
+                            })
+                        } else if item.status == 404 {
+                            Err(SyncError::Office365Error(format!(
+                                "{}: resource not found",
+                                operation_name
+                            )))
+                        } else {
+                            Err(SyncError::Office365Error(format!(
+                                "{} (status {}): {}",
+                                operation_name,
+                                item.status,
+                                item.error_message()
+                            )))
+                        });
+                    }
+                } else {
+                    return Err(SyncError::Office365Error(format!(
+                        "{}: batch request failed with status {}",
+                        operation_name, status
+                    )));
+                }
+
+                if retry.is_empty() {
+                    break;
+                }
+
+                let delay = delay.unwrap_or_else(|| rate_limit::backoff(attempt));
+                if throttled {
+                    // Hold back the other folder workers as well
+                    sync_limits::throttle_host(sync_limits::GRAPH_HOST, self.account_id, delay);
+                }
+                log::warn!(
+                    "[Office365] {}: {} of {} batched requests throttled (attempt {}/4). Retrying in {}s",
+                    operation_name,
+                    retry.len(),
+                    pending.len(),
+                    attempt + 1,
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+                pending = retry;
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(SyncError::Office365Error(format!(
+                        "{}: missing from batch response",
+                        operation_name
+                    )))
+                })
+            })
+            .collect())
+    }
+
+    /// Registers a change-notification subscription for the messages of a mail folder
+    pub async fn create_subscription(
+        &self,
+        folder_remote_id: &str,
+        notification_url: &str,
+        client_state: &str,
+        expires_at: DateTime<Utc>,
+    ) -> SyncResult<GraphSubscription> {
+        let request = CreateSubscriptionRequest {
+            change_type: "created,updated,deleted".to_string(),
+            notification_url: notification_url.to_string(),
+            lifecycle_notification_url: notification_url.to_string(),
+            resource: format!("me/mailFolders('{}')/messages", folder_remote_id),
+            expiration_date_time: expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            client_state: client_state.to_string(),
+        };
+
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                let req = request.clone();
+                async move {
+                    client
+                        .post(format!("{}/subscriptions", GRAPH_API_BASE))
+                        .bearer_auth(token)
+                        .json(&req)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(SyncError::Office365Error(format!(
+                "Failed to create subscription ({}): {}",
+                status, error_text
+            )));
+        }
+
+        response.json::<GraphSubscription>().await.map_err(|e| {
+            SyncError::Office365Error(format!("Failed to parse subscription response: {}", e))
+        })
+    }
+
+    /// Extends the expiration of an existing subscription
+    pub async fn renew_subscription(
+        &self,
+        subscription_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> SyncResult<GraphSubscription> {
+        let request = RenewSubscriptionRequest {
+            expiration_date_time: expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        };
+
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                let req = request.clone();
+                async move {
+                    client
+                        .patch(format!(
+                            "{}/subscriptions/{}",
+                            GRAPH_API_BASE, subscription_id
+                        ))
+                        .bearer_auth(token)
+                        .json(&req)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SyncError::Office365Error(format!(
+                "Failed to renew subscription {}: {}",
+                subscription_id,
+                response.status()
+            )));
+        }
+
+        response.json::<GraphSubscription>().await.map_err(|e| {
+            SyncError::Office365Error(format!("Failed to parse subscription response: {}", e))
+        })
+    }
+
+    pub async fn delete_subscription(&self, subscription_id: &str) -> SyncResult<()> {
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                async move {
+                    client
+                        .delete(format!(
+                            "{}/subscriptions/{}",
+                            GRAPH_API_BASE, subscription_id
+                        ))
+                        .bearer_auth(token)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
+
+        // A missing subscription has already expired or been removed by Graph
+        if !response.status().is_success() && response.status().as_u16() != 404 {
+            return Err(SyncError::Office365Error(format!(
+                "Failed to delete subscription {}: {}",
+                subscription_id,
+                response.status()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Fetches birthdays from the user's Outlook contacts
+    ///
+    /// Requires the Contacts.Read scope; accounts connected before it was requested
+    /// get a 403 and have to be re-authorized. Graph contacts have no anniversary field.
+    pub async fn fetch_contact_dates(&self) -> SyncResult<Vec<SyncContactDates>> {
+        let mut contacts = Vec::new();
+        let mut next_link = Some(format!(
+            "{}/me/contacts?$select=displayName,emailAddresses,birthday&$top=100",
+            GRAPH_API_BASE
+        ));
+
+        while let Some(url) = next_link.take() {
+            let response = self
+                .execute_with_401_retry(|token| {
+                    let client = self.client.clone();
+                    let url = url.clone();
+                    async move { client.get(url).bearer_auth(token).send().await }
+                })
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(SyncError::Office365Error(format!(
+                    "Failed to fetch contacts: {}",
+                    response.status()
+                )));
+            }
+
+            let page: GraphContactsResponse = response.json().await.map_err(|e| {
+                SyncError::Office365Error(format!("Failed to parse contacts response: {}", e))
+            })?;
+
+            for contact in page.value {
+                // Graph returns birthdays as midnight UTC timestamps
+                let Some(birthday) = contact
+                    .birthday
+                    .as_deref()
+                    .and_then(|value| value.get(..10))
+                    .map(ToString::to_string)
+                else {
+                    continue;
+                };
+
+                for email in contact
+                    .email_addresses
+                    .iter()
+                    .filter_map(|e| e.address.as_ref())
+                {
+                    contacts.push(SyncContactDates {
+                        email: email.to_lowercase(),
+                        display_name: contact.display_name.clone(),
+                        birthday: Some(birthday.clone()),
+                        anniversary: None,
+                    });
+                }
+            }
+
+            next_link = page.next_link;
+        }
+
+        Ok(contacts)
+    }
+
+    /// Fetches the account owner's name, photo and mailbox timezone/locale
+    ///
+    /// Requires the User.Read and MailboxSettings.Read scopes. A missing photo
+    /// (404) or unreadable mailbox settings leave those fields empty.
+    pub async fn fetch_owner_profile(&self) -> SyncResult<SyncOwnerProfile> {
+        let get = |url: String| {
+            self.execute_with_401_retry(move |token| {
+                let client = self.client.clone();
+                let url = url.clone();
+                async move { client.get(url).bearer_auth(token).send().await }
+            })
+        };
+
+        let response = get(format!("{}/me?$select=displayName", GRAPH_API_BASE)).await?;
+        if !response.status().is_success() {
+            return Err(SyncError::Office365Error(format!(
+                "Failed to fetch profile: {}",
+                response.status()
+            )));
+        }
+        let user: GraphUser = response.json().await.map_err(|e| {
+            SyncError::Office365Error(format!("Failed to parse profile response: {}", e))
+        })?;
+
+        let mut profile = SyncOwnerProfile {
+            display_name: user.display_name,
+            ..Default::default()
+        };
+
+        let response = get(format!(
+            "{}/me/mailboxSettings?$select=timeZone,language",
+            GRAPH_API_BASE
+        ))
+        .await?;
+        if response.status().is_success() {
+            let settings: GraphMailboxSettings = response.json().await.map_err(|e| {
+                SyncError::Office365Error(format!("Failed to parse mailbox settings: {}", e))
+            })?;
+            profile.timezone = settings.time_zone;
+            profile.locale = settings.language.and_then(|l| l.locale);
+        } else {
+            log::debug!(
+                "[Office365] Mailbox settings unavailable for account {}: {}",
+                self.account_id,
+                response.status()
+            );
+        }
 
-            if !is_retryable || attempt >= 3 {
-                return Err(SyncError::Office365Error(format!(
-                    "{} (status {} / {}): {}",
-                    operation_name, status, error_code, error_message
-                )));
-            }
+        let response = get(format!("{}/me/photos/240x240/$value", GRAPH_API_BASE)).await?;
+        if response.status().is_success() {
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("image/jpeg")
+                .to_string();
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| SyncError::NetworkError(e.to_string()))?;
+            profile.photo = Some((bytes.to_vec(), content_type));
+        }
 
-            let delay_secs = Self::parse_retry_after_seconds(&headers)
-                .unwrap_or_else(|| 2u64.saturating_pow(attempt + 1))
-                .max(1);
+        Ok(profile)
+    }
 
-            log::warn!(
-                "[Office365] {} throttled/retryable failure (status {} / {}, attempt {}/4). Retrying in {}s",
-                operation_name,
-                status,
-                error_code,
-                attempt + 1,
-                delay_secs
-            );
+    /// Reads the mailbox size from the root mail folder, whose size includes
+    /// all its subfolders
+    ///
+    /// Graph does not expose the mailbox quota to its owner, so the storage
+    /// limit is always `None`.
+    pub async fn fetch_quota(&self) -> SyncResult<SyncMailboxQuota> {
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                async move {
+                    client
+                        .get(format!(
+                            "{}/me/mailFolders/msgfolderroot?$select=sizeInBytes",
+                            GRAPH_API_BASE
+                        ))
+                        .bearer_auth(token)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
 
-            last_error = Some(SyncError::Office365Error(format!(
-                "{} (status {} / {}): {}",
-                operation_name, status, error_code, error_message
+        if !response.status().is_success() {
+            return Err(SyncError::Office365Error(format!(
+                "Failed to fetch mailbox size: {}",
+                response.status()
             )));
-
-            tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
         }
 
-        Err(last_error.unwrap_or_else(|| {
-            SyncError::Office365Error(format!("{} failed after retries", operation_name))
-        }))
+        let root: GraphFolderSize = response.json().await.map_err(|e| {
+            SyncError::Office365Error(format!("Failed to parse mailbox size: {}", e))
+        })?;
+
+        Ok(SyncMailboxQuota {
+            storage_used: root.size_in_bytes,
+            ..Default::default()
+        })
     }
 
     fn map_folder_type(display_name: &str) -> FolderType {
@@ -585,10 +1287,19 @@ impl Office365Provider {
             Err(err) => return Err(err),
         };
 
+        Ok(Self::collect_attachments(message_id, attachments_response))
+    }
+
+    /// File attachments of a message listing, without the duplicates Graph
+    /// sometimes returns
+    fn collect_attachments(
+        message_id: &str,
+        attachments_response: GraphAttachmentsResponse,
+    ) -> Vec<SyncAttachment> {
         let mut seen_remote_paths = HashSet::new();
         let mut seen_inline_content_ids = HashSet::new();
 
-        let sync_attachments: Vec<SyncAttachment> = attachments_response
+        attachments_response
             .value
             .into_iter()
             .filter(|att| {
@@ -646,22 +1357,19 @@ impl Office365Provider {
 
                 Some(sync_attachment)
             })
-            .collect();
-
-        Ok(sync_attachments)
+            .collect()
     }
 
     /// Enrich emails with attachment metadata and data
-    /// - Downloads all attachments (inline + regular)
-    /// - Inline attachments needed for cid: links in HTML
-    /// - Regular attachments downloaded for complete email sync
-    pub async fn enrich_emails_with_attachments(
-        &self,
-        emails: &mut [SyncEmail],
-        download_all: bool,
-    ) -> SyncResult<()> {
+    /// - Fetches metadata of all attachments, listing those of up to
+    ///   MAX_BATCH_SIZE messages per request
+    /// - Downloads the ones the attachment policy asks for, e.g. inline
+    ///   attachments needed for cid: links in HTML
+    /// - Others are streamed into the cache when the user opens them
+    pub async fn enrich_emails_with_attachments(&self, emails: &mut [SyncEmail]) -> SyncResult<()> {
         // Process attachments for emails that have them
-        for email in emails.iter_mut() {
+        let mut pending = Vec::new();
+        for (index, email) in emails.iter().enumerate() {
             if !email.has_attachments {
                 continue;
             }
@@ -675,7 +1383,25 @@ impl Office365Provider {
                 continue;
             }
 
-            match self.fetch_attachments_for_message(&email.remote_id).await {
+            pending.push(index);
+        }
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let urls: Vec<String> = pending
+            .iter()
+            .map(|index| format!("/me/messages/{}/attachments", emails[*index].remote_id))
+            .collect();
+        let listings = self
+            .graph_batch::<GraphAttachmentsResponse>("Failed to fetch attachments", &urls)
+            .await?;
+
+        for (index, listing) in pending.into_iter().zip(listings) {
+            let email = &mut emails[index];
+
+            match listing.map(|listing| Self::collect_attachments(&email.remote_id, listing)) {
                 Ok(mut attachments) => {
                     if attachments.is_empty() {
                         continue;
@@ -687,41 +1413,60 @@ impl Office365Provider {
                         email.remote_id
                     );
 
-                    if download_all {
-                        for attachment in &mut attachments {
-                            if attachment.is_cached && attachment.data.is_none() {
+                    for attachment in &mut attachments {
+                        if attachment.is_cached && attachment.data.is_none() {
+                            log::debug!(
+                                "[Office365] Skipping download for already cached attachment {} on email {}",
+                                attachment.filename,
+                                email.remote_id
+                            );
+                            continue;
+                        }
+
+                        if !self
+                            .attachment_policy
+                            .should_download(attachment.is_inline, attachment.size)
+                        {
+                            log::debug!(
+                                "[Office365] Leaving attachment {} ({} bytes) on email {} for an on-demand download",
+                                attachment.filename,
+                                attachment.size,
+                                email.remote_id
+                            );
+                            continue;
+                        }
+
+                        match self.fetch_attachment(attachment).await {
+                            Ok(data) => {
                                 log::debug!(
-                                    "[Office365] Skipping download for already cached attachment {} on email {}",
+                                    "[Office365] Downloaded attachment {} ({} bytes)",
                                     attachment.filename,
-                                    email.remote_id
+                                    data.len()
                                 );
-                                continue;
+                                attachment.data = Some(data);
                             }
-
-                            match self.fetch_attachment(attachment).await {
-                                Ok(data) => {
-                                    log::debug!(
-                                        "[Office365] Downloaded attachment {} ({} bytes)",
-                                        attachment.filename,
-                                        data.len()
-                                    );
-                                    attachment.data = Some(data);
-                                }
-                                Err(e) => {
-                                    log::warn!(
-                                        "[Office365] Failed to download attachment {} for email {}: {}",
-                                        attachment.filename,
-                                        email.remote_id,
-                                        e
-                                    );
-                                    // Continue - email can still be synced without this attachment
-                                }
+                            Err(e) => {
+                                log::warn!(
+                                    "[Office365] Failed to download attachment {} for email {}: {}",
+                                    attachment.filename,
+                                    email.remote_id,
+                                    e
+                                );
+                                // Continue - email can still be synced without this attachment
                             }
                         }
                     }
 
                     email.attachments = attachments;
                 }
+                Err(SyncError::Office365Error(message))
+                    if message.contains("resource not found") =>
+                {
+                    log::debug!(
+                        "Message {} not found when fetching attachments",
+                        email.remote_id
+                    );
+                }
                 Err(e) => {
                     log::warn!(
                         "[Office365] Failed to fetch attachments for email {}: {}",
@@ -856,6 +1601,8 @@ impl Office365Provider {
             attachments: Vec::new(),
             change_key: msg.change_key.clone(),
             last_modified_at,
+            signature: None,
+            remote_labels: None,
         })
     }
 
@@ -1007,6 +1754,117 @@ impl Office365Provider {
         Ok((all_emails, final_delta_link))
     }
 
+    /// OData filter for messages received within `window`
+    fn window_filter(window: &SyncWindow) -> Option<String> {
+        let bounds: Vec<String> = [("ge", window.after), ("lt", window.before)]
+            .into_iter()
+            .filter_map(|(op, bound)| {
+                bound.map(|bound| {
+                    format!(
+                        "receivedDateTime {} {}",
+                        op,
+                        bound.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                    )
+                })
+            })
+            .collect();
+
+        (!bounds.is_empty()).then(|| bounds.join(" and "))
+    }
+
+    /// Delta query of the messages received since `since`. The delta link it
+    /// ends with keeps tracking the folder from then on.
+    async fn fetch_emails_since(
+        &self,
+        folder: &SyncFolder,
+        since: DateTime<Utc>,
+    ) -> SyncResult<(Vec<SyncEmail>, Option<String>)> {
+        let filter = Self::window_filter(&SyncWindow {
+            after: Some(since),
+            before: None,
+        });
+        let url = reqwest::Url::parse_with_params(
+            &format!(
+                "{}/me/mailFolders/{}/messages/delta",
+                GRAPH_API_BASE, folder.remote_id
+            ),
+            filter.iter().map(|filter| ("$filter", filter)),
+        )
+        .map_err(|e| SyncError::Office365Error(format!("Invalid delta URL: {}", e)))?;
+
+        self.fetch_emails_delta(folder, url.as_str()).await
+    }
+
+    /// Messages of a folder received within `window`, newest first
+    async fn fetch_emails_in_window(
+        &self,
+        folder: &SyncFolder,
+        window: &SyncWindow,
+    ) -> SyncResult<Vec<SyncEmail>> {
+        let folder_id = folder
+            .id
+            .ok_or_else(|| SyncError::DatabaseError("Folder ID is required".to_string()))?;
+
+        let mut params = vec![
+            ("$top", "100".to_string()),
+            ("$orderby", "receivedDateTime desc".to_string()),
+        ];
+        if let Some(filter) = Self::window_filter(window) {
+            params.push(("$filter", filter));
+        }
+        let url = reqwest::Url::parse_with_params(
+            &format!(
+                "{}/me/mailFolders/{}/messages",
+                GRAPH_API_BASE, folder.remote_id
+            ),
+            &params,
+        )
+        .map_err(|e| SyncError::Office365Error(format!("Invalid messages URL: {}", e)))?;
+
+        let mut emails = Vec::new();
+        let mut next_link = Some(url.to_string());
+        let mut page_count = 0;
+        const MAX_WINDOW_PAGES: usize = 1000;
+
+        while let Some(link) = next_link.take() {
+            page_count += 1;
+            if page_count > MAX_WINDOW_PAGES {
+                log::warn!(
+                    "[Office365] Window fetch for folder {} exceeded max pages ({}), stopping",
+                    folder.name,
+                    MAX_WINDOW_PAGES
+                );
+                break;
+            }
+
+            let response = self
+                .execute_with_401_retry(|token| {
+                    let client = self.client.clone();
+                    let url = link.clone();
+                    async move { client.get(url).bearer_auth(token).send().await }
+                })
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(SyncError::Office365Error(format!(
+                    "Failed to fetch messages: {}",
+                    response.status()
+                )));
+            }
+
+            let messages_response: GraphMessagesResponse = response.json().await?;
+            for msg in messages_response.value.iter() {
+                match Self::parse_graph_message(msg, folder_id, self.account_id, true) {
+                    Ok(email) => emails.push(email),
+                    Err(e) => log::error!("Failed to parse message: {}", e),
+                }
+            }
+            next_link = messages_response.next_link;
+        }
+
+        Ok(emails)
+    }
+
     pub async fn fetch_emails_full(
         &self,
         folder: &SyncFolder,
@@ -1221,7 +2079,7 @@ impl Office365Provider {
             }
 
             // Enrich this page with attachments immediately
-            self.enrich_emails_with_attachments(&mut page_emails, true)
+            self.enrich_emails_with_attachments(&mut page_emails)
                 .await
                 .ok();
 
@@ -1355,7 +2213,7 @@ impl Office365Provider {
             }
 
             // Enrich this page with attachments immediately
-            self.enrich_emails_with_attachments(&mut page_emails, true)
+            self.enrich_emails_with_attachments(&mut page_emails)
                 .await
                 .ok();
 
@@ -1372,24 +2230,154 @@ impl Office365Provider {
 
             next_link = messages_response.next_link;
 
-            // Capture delta link from the response
-            if messages_response.delta_link.is_some() {
-                delta_link = messages_response.delta_link;
-            }
+            // Capture delta link from the response
+            if messages_response.delta_link.is_some() {
+                delta_link = messages_response.delta_link;
+            }
+
+            if next_link.is_none() {
+                log::info!(
+                    "[Office365] Completed full fetch for folder {}: {} emails in {} pages (has delta link: {})",
+                    folder.name,
+                    total_emails,
+                    page_count,
+                    delta_link.is_some()
+                );
+                break;
+            }
+        }
+
+        Ok(delta_link)
+    }
+
+    /// Request the content of an attachment, retrying throttled requests.
+    /// Returns the successful response with its body still unread; `timeout`
+    /// covers reading the body too, so streamed downloads go without one.
+    async fn request_attachment(
+        &self,
+        attachment: &SyncAttachment,
+        timeout: Option<std::time::Duration>,
+    ) -> SyncResult<reqwest::Response> {
+        let remote_path = attachment.remote_path.as_ref().ok_or_else(|| {
+            SyncError::AttachmentError("No remote path for attachment".to_string())
+        })?;
+
+        let (message_id, attachment_id) = remote_path.rsplit_once(':').ok_or_else(|| {
+            SyncError::AttachmentError(format!(
+                "Invalid remote path format: {}. Expected 'message_id:attachment_id'",
+                remote_path
+            ))
+        })?;
+
+        log::debug!(
+            "Downloading attachment {} ({} bytes)",
+            attachment.filename,
+            attachment.size
+        );
+
+        let message_id_owned = message_id.to_string();
+        let attachment_id_owned = attachment_id.to_string();
+        let filename = attachment.filename.clone();
+        let expected_size = attachment.size;
+        let operation_name = format!(
+            "Failed to download attachment {} for message {}",
+            filename, message_id
+        );
+
+        let mut last_error = None;
+
+        for attempt in 0..=3u32 {
+            let response = self
+                .execute_with_401_retry(|token| {
+                    let client = self.client.clone();
+                    let msg_id = message_id_owned.clone();
+                    let att_id = attachment_id_owned.clone();
+                    async move {
+                        let request = client
+                            .get(format!(
+                                "{}/me/messages/{}/attachments/{}/$value",
+                                GRAPH_API_BASE, msg_id, att_id
+                            ))
+                            .bearer_auth(token);
+                        match timeout {
+                            Some(timeout) => request.timeout(timeout),
+                            None => request,
+                        }
+                        .send()
+                        .await
+                    }
+                })
+                .await?;
+
+            let status = response.status();
+
+            if status.is_success() {
+                if let Some(content_length) = response.content_length() {
+                    if content_length as i64 != expected_size {
+                        log::warn!(
+                            "Attachment size mismatch for {}: expected {}, got {}",
+                            filename,
+                            expected_size,
+                            content_length
+                        );
+                    }
+                }
+
+                return Ok(response);
+            }
+
+            let headers = response.headers().clone();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read error".to_string());
+
+            let graph_error = serde_json::from_str::<GraphErrorResponse>(&error_text)
+                .ok()
+                .and_then(|body| body.error);
+
+            let error_code = graph_error
+                .as_ref()
+                .and_then(|err| err.code.as_deref())
+                .unwrap_or("unknown");
+            let error_message = graph_error
+                .as_ref()
+                .and_then(|err| err.message.as_deref())
+                .unwrap_or(error_text.as_str());
+
+            // Throttling responses were already retried by the rate limiter
+            let is_retryable = status.as_u16() == 504;
+
+            if !is_retryable || attempt >= 3 {
+                return Err(SyncError::Office365Error(format!(
+                    "{} (status {} / {}): {}",
+                    operation_name, status, error_code, error_message
+                )));
+            }
+
+            let delay = rate_limit::retry_after(&headers, Utc::now())
+                .unwrap_or_else(|| rate_limit::backoff(attempt));
+
+            log::warn!(
+                "[Office365] Attachment download retryable failure for {} (status {} / {}, attempt {}/4). Retrying in {}s",
+                filename,
+                status,
+                error_code,
+                attempt + 1,
+                delay.as_secs()
+            );
+
+            last_error = Some(SyncError::Office365Error(format!(
+                "{} (status {} / {}): {}",
+                operation_name, status, error_code, error_message
+            )));
 
-            if next_link.is_none() {
-                log::info!(
-                    "[Office365] Completed full fetch for folder {}: {} emails in {} pages (has delta link: {})",
-                    folder.name,
-                    total_emails,
-                    page_count,
-                    delta_link.is_some()
-                );
-                break;
-            }
+            tokio::time::sleep(delay).await;
         }
 
-        Ok(delta_link)
+        Err(last_error.unwrap_or_else(|| {
+            SyncError::Office365Error(format!("{} failed after retries", operation_name))
+        }))
     }
 }
 
@@ -1403,6 +2391,10 @@ impl EmailProvider for Office365Provider {
         self
     }
 
+    fn set_attachment_policy(&mut self, policy: AttachmentDownloadPolicy) {
+        self.attachment_policy = policy;
+    }
+
     async fn authenticate(&mut self, credentials: ProviderCredentials) -> SyncResult<()> {
         match credentials {
             ProviderCredentials::OAuth2(creds) => {
@@ -1530,11 +2522,9 @@ impl EmailProvider for Office365Provider {
                 }
             }
 
-            // Enrich added and modified emails with attachment data (download ALL)
-            self.enrich_emails_with_attachments(&mut added, true)
-                .await
-                .ok();
-            self.enrich_emails_with_attachments(&mut modified, true)
+            // Enrich added and modified emails with attachment metadata
+            self.enrich_emails_with_attachments(&mut added).await.ok();
+            self.enrich_emails_with_attachments(&mut modified)
                 .await
                 .ok();
 
@@ -1544,15 +2534,14 @@ impl EmailProvider for Office365Provider {
                 deleted,
                 next_sync_token: next_token,
                 is_complete: false, // Delta sync is not a complete enumeration
+                uid_validity: None,
             })
         } else {
             // Full sync: fetch all emails
             let (mut emails, next_token) = self.fetch_emails_full(folder).await?;
 
-            // Enrich emails with attachment data (download ALL)
-            self.enrich_emails_with_attachments(&mut emails, true)
-                .await
-                .ok();
+            // Enrich emails with attachment metadata
+            self.enrich_emails_with_attachments(&mut emails).await.ok();
 
             Ok(crate::sync::types::SyncDiff {
                 added: emails,
@@ -1560,10 +2549,39 @@ impl EmailProvider for Office365Provider {
                 deleted: Vec::new(),
                 next_sync_token: next_token,
                 is_complete: true, // Full sync is a complete enumeration
+                uid_validity: None,
             })
         }
     }
 
+    async fn sync_recent_messages(
+        &self,
+        folder: &SyncFolder,
+        since: DateTime<Utc>,
+    ) -> SyncResult<crate::sync::types::SyncDiff> {
+        let (mut emails, next_token) = self.fetch_emails_since(folder, since).await?;
+        self.enrich_emails_with_attachments(&mut emails).await.ok();
+
+        Ok(crate::sync::types::SyncDiff {
+            added: emails,
+            modified: Vec::new(),
+            deleted: Vec::new(),
+            next_sync_token: next_token,
+            is_complete: false,
+            uid_validity: None,
+        })
+    }
+
+    async fn fetch_window(
+        &self,
+        folder: &SyncFolder,
+        window: &SyncWindow,
+    ) -> SyncResult<Vec<SyncEmail>> {
+        let mut emails = self.fetch_emails_in_window(folder, window).await?;
+        self.enrich_emails_with_attachments(&mut emails).await.ok();
+        Ok(emails)
+    }
+
     async fn fetch_email(&self, folder: &SyncFolder, remote_id: &str) -> SyncResult<SyncEmail> {
         let remote_id_owned = remote_id.to_string();
 
@@ -1595,144 +2613,86 @@ impl EmailProvider for Office365Provider {
 
         let email = Self::parse_graph_message(&message, folder_id, self.account_id, true)?;
 
-        // Enrich with attachment data (download ALL)
+        // Enrich with attachment metadata
         let mut emails = vec![email];
-        self.enrich_emails_with_attachments(&mut emails, true)
-            .await
-            .ok();
+        self.enrich_emails_with_attachments(&mut emails).await.ok();
         let email = emails.into_iter().next().unwrap();
 
         Ok(email)
     }
 
-    async fn fetch_attachment(&self, attachment: &SyncAttachment) -> SyncResult<Vec<u8>> {
-        let remote_path = attachment.remote_path.as_ref().ok_or_else(|| {
-            SyncError::AttachmentError("No remote path for attachment".to_string())
-        })?;
-
-        let (message_id, attachment_id) = remote_path.rsplit_once(':').ok_or_else(|| {
-            SyncError::AttachmentError(format!(
-                "Invalid remote path format: {}. Expected 'message_id:attachment_id'",
-                remote_path
-            ))
-        })?;
-
-        log::debug!(
-            "Downloading attachment {} ({} bytes)",
-            attachment.filename,
-            attachment.size
-        );
-
-        let message_id_owned = message_id.to_string();
-        let attachment_id_owned = attachment_id.to_string();
-        let filename = attachment.filename.clone();
-        let expected_size = attachment.size;
-        let operation_name = format!(
-            "Failed to download attachment {} for message {}",
-            filename, message_id
-        );
-
-        let mut last_error = None;
-
-        for attempt in 0..=3u32 {
-            let response = self
-                .execute_with_401_retry(|token| {
-                    let client = self.client.clone();
-                    let msg_id = message_id_owned.clone();
-                    let att_id = attachment_id_owned.clone();
-                    async move {
-                        client
-                            .get(format!(
-                                "{}/me/messages/{}/attachments/{}/$value",
-                                GRAPH_API_BASE, msg_id, att_id
-                            ))
-                            .bearer_auth(token)
-                            .timeout(std::time::Duration::from_secs(300))
-                            .send()
-                            .await
-                    }
-                })
-                .await?;
-
-            let status = response.status();
-
-            if status.is_success() {
-                if let Some(content_length) = response.content_length() {
-                    if content_length as i64 != expected_size {
-                        log::warn!(
-                            "Attachment size mismatch for {}: expected {}, got {}",
-                            filename,
-                            expected_size,
-                            content_length
-                        );
-                    }
-                }
-
-                let bytes = response.bytes().await.map_err(|e| {
-                    SyncError::NetworkError(format!(
-                        "Failed to read attachment bytes for {}: {}",
-                        filename, e
-                    ))
-                })?;
-
-                return Ok(bytes.to_vec());
-            }
-
-            let headers = response.headers().clone();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read error".to_string());
+    async fn fetch_emails(
+        &self,
+        folder: &SyncFolder,
+        remote_ids: &[String],
+    ) -> SyncResult<Vec<SyncResult<SyncEmail>>> {
+        let folder_id = folder
+            .id
+            .ok_or_else(|| SyncError::DatabaseError("Folder ID is required".to_string()))?;
 
-            let graph_error = serde_json::from_str::<GraphErrorResponse>(&error_text)
-                .ok()
-                .and_then(|body| body.error);
+        let urls: Vec<String> = remote_ids
+            .iter()
+            .map(|remote_id| format!("/me/messages/{}", remote_id))
+            .collect();
+        let messages = self
+            .graph_batch::<GraphMessage>("Failed to fetch message", &urls)
+            .await?;
 
-            let error_code = graph_error
-                .as_ref()
-                .and_then(|err| err.code.as_deref())
-                .unwrap_or("unknown");
-            let error_message = graph_error
-                .as_ref()
-                .and_then(|err| err.message.as_deref())
-                .unwrap_or(error_text.as_str());
+        // The fetched emails are enriched together; the results keep their
+        // position among the failures
+        let mut emails = Vec::new();
+        let positions: Vec<SyncResult<usize>> = messages
+            .into_iter()
+            .map(|message| {
+                let email = Self::parse_graph_message(&message?, folder_id, self.account_id, true)?;
+                emails.push(email);
+                Ok(emails.len() - 1)
+            })
+            .collect();
 
-            let is_retryable = status.as_u16() == 429
-                || status.as_u16() == 503
-                || status.as_u16() == 504
-                || matches!(error_code, "ApplicationThrottled" | "TooManyRequests");
+        self.enrich_emails_with_attachments(&mut emails).await.ok();
 
-            if !is_retryable || attempt >= 3 {
-                return Err(SyncError::Office365Error(format!(
-                    "{} (status {} / {}): {}",
-                    operation_name, status, error_code, error_message
-                )));
-            }
+        let mut emails = emails.into_iter();
+        Ok(positions
+            .into_iter()
+            .map(|position| position.map(|_| emails.next().unwrap()))
+            .collect())
+    }
 
-            let delay_secs = Self::parse_retry_after_seconds(&headers)
-                .unwrap_or_else(|| 2u64.saturating_pow(attempt + 1))
-                .max(1);
+    async fn fetch_attachment(&self, attachment: &SyncAttachment) -> SyncResult<Vec<u8>> {
+        let response = self
+            .request_attachment(attachment, Some(std::time::Duration::from_secs(300)))
+            .await?;
+        let bytes = response.bytes().await.map_err(|e| {
+            SyncError::NetworkError(format!(
+                "Failed to read attachment bytes for {}: {}",
+                attachment.filename, e
+            ))
+        })?;
 
-            log::warn!(
-                "[Office365] Attachment download throttled/retryable failure for {} (status {} / {}, attempt {}/4). Retrying in {}s",
-                filename,
-                status,
-                error_code,
-                attempt + 1,
-                delay_secs
-            );
+        Ok(bytes.to_vec())
+    }
 
-            last_error = Some(SyncError::Office365Error(format!(
-                "{} (status {} / {}): {}",
-                operation_name, status, error_code, error_message
-            )));
+    async fn stream_attachment(
+        &self,
+        attachment: &SyncAttachment,
+        sink: &mut AttachmentSink,
+    ) -> SyncResult<()> {
+        let mut response = self.request_attachment(attachment, None).await?;
+        if let Some(content_length) = response.content_length() {
+            sink.set_total(content_length);
+        }
 
-            tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+        while let Some(chunk) = response.chunk().await.map_err(|e| {
+            SyncError::NetworkError(format!(
+                "Failed to read attachment bytes for {}: {}",
+                attachment.filename, e
+            ))
+        })? {
+            sink.write(&chunk).await?;
         }
 
-        Err(last_error.unwrap_or_else(|| {
-            SyncError::Office365Error(format!("{} failed after retries", operation_name))
-        }))
+        Ok(())
     }
 
     async fn move_email(
@@ -2065,6 +3025,8 @@ impl EmailProvider for Office365Provider {
         in_reply_to: Option<String>,
         references: Option<String>,
         conversation_id: Option<String>,
+        from: Option<crate::sync::types::EmailRecipient>,
+        read_receipt: bool,
     ) -> SyncResult<()> {
         log::info!("[Office365] Sending email with subject: {}", subject);
 
@@ -2094,6 +3056,11 @@ impl EmailProvider for Office365Provider {
             internet_message_headers: Vec<InternetMessageHeader>,
             #[serde(rename = "conversationId", skip_serializing_if = "Option::is_none")]
             conversation_id: Option<String>,
+            /// Needs Send As or Send on Behalf permission on that address
+            #[serde(skip_serializing_if = "Option::is_none")]
+            from: Option<Recipient>,
+            #[serde(rename = "isReadReceiptRequested")]
+            is_read_receipt_requested: bool,
         }
 
         #[derive(Serialize)]
@@ -2131,6 +3098,10 @@ impl EmailProvider for Office365Provider {
             content_type: String,
             #[serde(rename = "contentBytes")]
             content_bytes: String,
+            #[serde(rename = "isInline")]
+            is_inline: bool,
+            #[serde(rename = "contentId", skip_serializing_if = "Option::is_none")]
+            content_id: Option<String>,
         }
 
         let to_recipients: Vec<Recipient> = to
@@ -2163,6 +3134,10 @@ impl EmailProvider for Office365Provider {
             })
             .collect();
 
+        let (large_attachments, attachments): (Vec<_>, Vec<_>) = attachments
+            .into_iter()
+            .partition(|att| att.content.len() > UPLOAD_SESSION_THRESHOLD);
+
         let graph_attachments: Vec<Attachment> = attachments
             .into_iter()
             .map(|att| {
@@ -2174,6 +3149,8 @@ impl EmailProvider for Office365Provider {
                         .content_type
                         .unwrap_or_else(|| "application/octet-stream".to_string()),
                     content_bytes: general_purpose::STANDARD.encode(&att.content),
+                    is_inline: att.content_id.is_some(),
+                    content_id: att.content_id,
                 }
             })
             .collect();
@@ -2192,20 +3169,37 @@ impl EmailProvider for Office365Provider {
             });
         }
 
-        let request_body = SendMailRequest {
-            message: Message {
-                subject,
-                body: Body {
-                    content_type: "HTML".to_string(),
-                    content: body_html,
-                },
-                to_recipients,
-                cc_recipients,
-                bcc_recipients,
-                attachments: graph_attachments,
-                internet_message_headers,
-                conversation_id,
+        let message = Message {
+            subject,
+            body: Body {
+                content_type: "HTML".to_string(),
+                content: body_html,
             },
+            to_recipients,
+            cc_recipients,
+            bcc_recipients,
+            attachments: graph_attachments,
+            internet_message_headers,
+            conversation_id,
+            from: from.map(|r| Recipient {
+                email_address: EmailAddr {
+                    address: r.address,
+                    name: r.name,
+                },
+            }),
+            is_read_receipt_requested: read_receipt,
+        };
+
+        if !large_attachments.is_empty() {
+            let message = serde_json::to_value(&message)?;
+            self.send_with_upload_sessions(message, large_attachments)
+                .await?;
+            log::info!("[Office365] Email sent successfully");
+            return Ok(());
+        }
+
+        let request_body = SendMailRequest {
+            message,
             save_to_sent_items: true,
         };
 
@@ -2239,6 +3233,117 @@ impl EmailProvider for Office365Provider {
         log::info!("[Office365] Email sent successfully");
         Ok(())
     }
+
+    async fn send_mime(&self, mime: Vec<u8>) -> SyncResult<()> {
+        use base64::{engine::general_purpose, Engine as _};
+
+        // Graph takes MIME content base64 encoded, as text/plain
+        let encoded = general_purpose::STANDARD.encode(&mime);
+
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                let body = encoded.clone();
+                async move {
+                    client
+                        .post(format!("{}/me/sendMail", GRAPH_API_BASE))
+                        .bearer_auth(token)
+                        .header("Content-Type", "text/plain")
+                        .body(body)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read error".to_string());
+            return Err(SyncError::Office365Error(format!(
+                "Failed to send MIME email (status {}): {}",
+                status, error_text
+            )));
+        }
+
+        log::info!("[Office365] MIME email sent successfully");
+        Ok(())
+    }
+
+    async fn save_draft(
+        &self,
+        _drafts_folder: &SyncFolder,
+        mime: Vec<u8>,
+        replaces: Option<&str>,
+    ) -> SyncResult<String> {
+        use base64::{engine::general_purpose, Engine as _};
+
+        // Messages created from MIME land in Drafts. Graph cannot update a
+        // message from MIME, so a changed draft is created anew and the previous
+        // copy removed.
+        let encoded = general_purpose::STANDARD.encode(&mime);
+
+        let response = self
+            .execute_with_401_retry(|token| {
+                let client = self.client.clone();
+                let body = encoded.clone();
+                async move {
+                    client
+                        .post(format!("{}/me/messages", GRAPH_API_BASE))
+                        .bearer_auth(token)
+                        .header("Content-Type", "text/plain")
+                        .body(body)
+                        .send()
+                        .await
+                }
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read error".to_string());
+            return Err(SyncError::Office365Error(format!(
+                "Failed to save draft (status {}): {}",
+                status, error_text
+            )));
+        }
+
+        let draft: GraphMessage = response.json().await?;
+
+        if let Some(previous) = replaces {
+            let previous = previous.to_string();
+            let response = self
+                .execute_with_401_retry(|token| {
+                    let client = self.client.clone();
+                    let remote_id = previous.clone();
+                    async move {
+                        client
+                            .delete(format!("{}/me/messages/{}", GRAPH_API_BASE, remote_id))
+                            .bearer_auth(token)
+                            .send()
+                            .await
+                    }
+                })
+                .await?;
+
+            if !response.status().is_success()
+                && response.status() != reqwest::StatusCode::NOT_FOUND
+            {
+                log::warn!(
+                    "[Office365] Failed to remove previous draft {}: {}",
+                    previous,
+                    response.status()
+                );
+            }
+        }
+
+        Ok(draft.id)
+    }
 }
 
 fn fetch_child_folders_recursive<'a>(
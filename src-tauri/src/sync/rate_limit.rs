@@ -0,0 +1,228 @@
+//! Retry of throttled requests to the provider HTTP APIs
+//!
+//! A 429 or 503 answer is sent again after the delay its Retry-After header
+//! asks for, or after a jittered exponential backoff when it has none. A
+//! delay too long to wait out within the request fails it instead and marks
+//! the account as throttled until then, so background sync leaves the account
+//! alone rather than retrying blindly.
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::sync_limits::{self, MAX_BACKOFF};
+
+/// Sends of a request before its throttling response is returned
+const MAX_ATTEMPTS: u32 = 4;
+/// Longest delay waited out within a request
+const MAX_INLINE_WAIT: Duration = Duration::from_secs(60);
+/// First backoff without a Retry-After header, doubled on every attempt
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Accounts throttled until the given time
+static THROTTLED: Lazy<Mutex<HashMap<Uuid, DateTime<Utc>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Whether `status` asks the client to back off and try again
+pub fn is_retryable(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    )
+}
+
+/// Delay requested by a Retry-After header, given in seconds or as an HTTP
+/// date
+pub fn retry_after(headers: &reqwest::header::HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => DateTime::parse_from_rfc2822(value)
+            .ok()
+            .map(|at| (at.with_timezone(&Utc) - now).to_std().unwrap_or_default()),
+    }
+}
+
+/// Backoff before the attempt after `attempt`: exponential, with up to half
+/// of it randomized so parallel workers do not retry in lockstep
+pub fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    let jitter = RandomState::new().build_hasher().finish() % 1000;
+
+    delay / 2 + delay / 2 * jitter as u32 / 1000
+}
+
+/// Leave the account alone for `delay`
+pub fn throttle_account(account_id: Uuid, delay: Duration) {
+    let until = Utc::now()
+        + chrono::Duration::from_std(delay.min(MAX_BACKOFF))
+            .unwrap_or_else(|_| chrono::Duration::zero());
+    let mut throttled = THROTTLED.lock().unwrap();
+    let entry = throttled.entry(account_id).or_insert(until);
+    if *entry < until {
+        *entry = until;
+    }
+
+    log::warn!("Account {} throttled until {}", account_id, until);
+}
+
+/// Time until which the account is throttled, if it still is
+pub fn throttled_until(account_id: Uuid) -> Option<DateTime<Utc>> {
+    let mut throttled = THROTTLED.lock().unwrap();
+    match throttled.get(&account_id) {
+        Some(until) if *until > Utc::now() => Some(*until),
+        Some(_) => {
+            throttled.remove(&account_id);
+            None
+        }
+        None => None,
+    }
+}
+
+/// Send requests made by `send` until the answer is not a throttling one,
+/// waiting while the account is throttled on the host and backing off
+/// between attempts. The last throttling response is returned when the
+/// attempts run out or the server asks for a longer delay than is waited out
+/// here.
+pub async fn send_with_retry<F, Fut>(
+    host: &str,
+    account_id: Uuid,
+    mut send: F,
+) -> Result<Response, reqwest::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<Response, reqwest::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        sync_limits::wait_for_host(host, account_id).await;
+
+        let response = send().await?;
+        let status = response.status();
+        if !is_retryable(status) {
+            return Ok(response);
+        }
+
+        let delay = retry_after(response.headers(), Utc::now()).unwrap_or_else(|| backoff(attempt));
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            // Hold back the account's other workers as well
+            sync_limits::throttle_host(host, account_id, delay);
+        }
+
+        attempt += 1;
+        if attempt >= MAX_ATTEMPTS || delay > MAX_INLINE_WAIT {
+            throttle_account(account_id, delay);
+            return Ok(response);
+        }
+
+        log::debug!(
+            "{} answered {}, retrying in {}ms (attempt {}/{})",
+            host,
+            status,
+            delay.as_millis(),
+            attempt + 1,
+            MAX_ATTEMPTS
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Rate-limited sending of a request
+#[async_trait]
+pub trait SendRateLimited {
+    /// Send the request through [`send_with_retry`]. Requests with a body
+    /// that cannot be cloned are sent once.
+    async fn send_rate_limited(
+        self,
+        host: &str,
+        account_id: Uuid,
+    ) -> Result<Response, reqwest::Error>;
+}
+
+#[async_trait]
+impl SendRateLimited for RequestBuilder {
+    async fn send_rate_limited(
+        self,
+        host: &str,
+        account_id: Uuid,
+    ) -> Result<Response, reqwest::Error> {
+        let Some(template) = self.try_clone() else {
+            sync_limits::wait_for_host(host, account_id).await;
+            return self.send().await;
+        };
+
+        send_with_retry(host, account_id, || {
+            template
+                .try_clone()
+                .expect("request was cloned before")
+                .send()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    #[test]
+    fn test_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:30:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+
+        // A date in the past means no delay
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:00:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers, now), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_backoff_is_jittered_exponential() {
+        for attempt in 0..4 {
+            let full = BASE_BACKOFF * 2u32.pow(attempt);
+            let delay = backoff(attempt);
+            assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+        }
+        assert!(backoff(20) <= MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_throttle_account() {
+        let account_id = Uuid::now_v7();
+        assert_eq!(throttled_until(account_id), None);
+
+        throttle_account(account_id, Duration::from_secs(120));
+        let until = throttled_until(account_id).unwrap();
+        // A shorter delay does not shorten the throttling
+        throttle_account(account_id, Duration::from_secs(5));
+        assert_eq!(throttled_until(account_id), Some(until));
+    }
+}
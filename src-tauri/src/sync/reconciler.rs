@@ -2,11 +2,12 @@ use crate::database::models::pending_operation::PendingOperationType;
 use crate::database::repositories::{
     FolderRepository, SqliteFolderRepository, SqlitePendingOperationRepository,
 };
+use crate::services::app_lock;
 use crate::sync::error::{SyncError, SyncResult};
+use crate::sync::muted_conversations;
 use crate::sync::types::{SyncDiff, SyncEmail, SyncFolder};
 use chrono::Utc;
 use sqlx::SqlitePool;
-use tauri::Emitter;
 use uuid::Uuid;
 
 /// Result of a reconciliation pass
@@ -51,7 +52,9 @@ impl Reconciler {
 
         let folder_repo = SqliteFolderRepository::new(self.pool.clone());
 
-        // Process added emails
+        // Process added emails, notifying about the new ones together once
+        // they are all stored
+        let mut new_emails = Vec::new();
         for email in &diff.added {
             let conflicts = self.resolve_conflicts_for_email(email, &pending_repo).await;
             result.conflicts_resolved += conflicts;
@@ -61,16 +64,7 @@ impl Reconciler {
                     result.added += 1;
 
                     if is_new {
-                        if let Err(e) = self
-                            .notify_for_new_email(&folder_repo, email_sync, email, &db_email)
-                            .await
-                        {
-                            log::warn!(
-                                "[Reconciler] Failed to notify for new email {}: {}",
-                                email.remote_id,
-                                e
-                            );
-                        }
+                        new_emails.push(db_email);
                     }
                 }
                 Err(e) => {
@@ -83,6 +77,21 @@ impl Reconciler {
             }
         }
 
+        if !new_emails.is_empty() {
+            if let Some(folder_id) = folder.id {
+                if let Err(e) = self
+                    .notify_for_new_emails(&folder_repo, email_sync, folder_id, &new_emails)
+                    .await
+                {
+                    log::warn!(
+                        "[Reconciler] Failed to notify for {} new emails: {}",
+                        new_emails.len(),
+                        e
+                    );
+                }
+            }
+        }
+
         // Process modified emails
         for email in &diff.modified {
             let conflicts = self.resolve_conflicts_for_email(email, &pending_repo).await;
@@ -200,6 +209,11 @@ impl Reconciler {
                 Some(PendingOperationType::Unflag) => {
                     !email.flags.contains(&"\\Flagged".to_string())
                 }
+                Some(PendingOperationType::Move) => op
+                    .parsed_payload()
+                    .get("to_folder_id")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|to_folder_id| to_folder_id == email.folder_id.to_string()),
                 _ => false,
             };
 
@@ -217,19 +231,19 @@ impl Reconciler {
         cancelled
     }
 
-    async fn notify_for_new_email(
+    async fn notify_for_new_emails(
         &self,
         folder_repo: &SqliteFolderRepository,
         email_sync: &super::email_sync::EmailSync,
-        email: &SyncEmail,
-        db_email: &crate::database::models::email::Email,
+        folder_id: Uuid,
+        new_emails: &[crate::database::models::email::Email],
     ) -> SyncResult<()> {
         let folder = folder_repo
-            .find_by_id(email.folder_id)
+            .find_by_id(folder_id)
             .await
             .map_err(|e| SyncError::DatabaseError(e.to_string()))?
             .ok_or_else(|| {
-                SyncError::DatabaseError(format!("Folder not found for email: {}", email.folder_id))
+                SyncError::DatabaseError(format!("Folder not found for emails: {}", folder_id))
             })?;
 
         let app_handle = email_sync
@@ -238,25 +252,51 @@ impl Reconciler {
             .ok_or_else(|| SyncError::DatabaseError("App handle not available".to_string()))?
             .clone();
 
+        let mut unmuted = Vec::new();
+        for db_email in new_emails {
+            let muted = muted_conversations::is_muted(&self.pool, db_email)
+                .await
+                .unwrap_or_else(|e| {
+                    log::warn!(
+                        "[Reconciler] Failed to check mute of new email {}: {}",
+                        db_email.id,
+                        e
+                    );
+                    false
+                });
+
+            if muted {
+                log::debug!(
+                    "[Reconciler] Not notifying for email {} of a muted conversation",
+                    db_email.id
+                );
+            } else {
+                unmuted.push(db_email.clone());
+            }
+        }
+
         if let Some(notification_service) = &email_sync.notification_service {
             if let Err(e) = notification_service
-                .notify_incoming_email(folder.id, folder.folder_type, db_email)
+                .notify_incoming_emails(folder.id, folder.folder_type, &unmuted)
                 .await
             {
                 log::warn!(
-                    "[Reconciler] Notification service failed for new email {}: {}",
-                    email.remote_id,
+                    "[Reconciler] Notification service failed for {} new emails: {}",
+                    unmuted.len(),
                     e
                 );
             }
         }
 
-        if let Err(e) = app_handle.emit("email:created", db_email.clone()) {
-            log::warn!(
-                "[Reconciler] Failed to emit email:created for {}: {}",
-                email.remote_id,
-                e
-            );
+        for db_email in new_emails {
+            if let Err(e) = app_lock::emit_mail_event(app_handle, "email:created", db_email.clone())
+            {
+                log::warn!(
+                    "[Reconciler] Failed to emit email:created for {}: {}",
+                    db_email.id,
+                    e
+                );
+            }
         }
 
         Ok(())
@@ -0,0 +1,600 @@
+/// User-defined filter rules for incoming mail
+///
+/// Rules are evaluated locally after a message is stored. Actions follow the
+/// local-first pattern: the database is updated immediately and the change is
+/// queued as a pending operation for the provider.
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::commands::emails::{send_email_from_account, SendFromAccountRequest};
+use crate::database::models::email::{Email, EmailAddress};
+use crate::database::models::folder::FolderType;
+use crate::database::models::pending_operation::{PendingOperation, PendingOperationType};
+use crate::database::models::rule::{Rule, RuleAction, RuleCondition};
+use crate::database::models::signature::SignatureChoice;
+use crate::database::repositories::{
+    EmailRepository, FolderRepository, LabelRepository, RuleRepository, SqliteEmailRepository,
+    SqliteFolderRepository, SqliteLabelRepository, SqlitePendingOperationRepository,
+    SqliteRuleRepository,
+};
+use crate::state::AppState;
+use crate::sync::gmail_labels;
+
+/// Only mail received this recently is forwarded during sync. Older messages
+/// showing up (a resync, older pages, a re-added account) are not sent out
+/// again.
+const FORWARD_MAX_AGE_HOURS: i64 = 24;
+
+/// What the rules did to a single message
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuleOutcome {
+    pub email_id: Uuid,
+    pub matched_rule_ids: Vec<Uuid>,
+    /// Folder the message was moved into, if any
+    pub moved_to_folder_id: Option<Uuid>,
+    pub marked_read: bool,
+    pub deleted: bool,
+}
+
+/// Compile a subject pattern the way rules match it
+pub fn compile_subject_pattern(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid subject pattern: {}", e))
+}
+
+/// Subject patterns compiled once and reused for every message
+#[derive(Debug, Default)]
+pub struct SubjectPatterns {
+    compiled: Mutex<HashMap<String, Option<Regex>>>,
+}
+
+impl SubjectPatterns {
+    /// Whether `pattern` matches `subject`. Invalid patterns, which saving a
+    /// rule rejects, never match.
+    fn is_match(&self, pattern: &str, subject: &str) -> bool {
+        let mut compiled = self
+            .compiled
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        compiled
+            .entry(pattern.to_string())
+            .or_insert_with(|| {
+                compile_subject_pattern(pattern)
+                    .map_err(|e| log::warn!("[RulesEngine] '{}': {}", pattern, e))
+                    .ok()
+            })
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(subject))
+    }
+}
+
+/// Whether forwarding rules may send `email` out: only recent mail is
+/// forwarded
+fn is_recent_for_forwarding(email: &Email, now: DateTime<Utc>) -> bool {
+    email.received_at >= now - chrono::Duration::hours(FORWARD_MAX_AGE_HOURS)
+}
+
+/// Whether a single condition holds for the message
+pub fn condition_matches(
+    condition: &RuleCondition,
+    email: &Email,
+    patterns: &SubjectPatterns,
+) -> bool {
+    match condition {
+        RuleCondition::From { value } => {
+            let needle = value.trim().to_lowercase();
+            !needle.is_empty()
+                && (email.from.address.to_lowercase().contains(&needle)
+                    || email
+                        .from
+                        .name
+                        .as_deref()
+                        .is_some_and(|name| name.to_lowercase().contains(&needle)))
+        }
+        RuleCondition::SubjectRegex { pattern } => {
+            patterns.is_match(pattern, email.subject.as_deref().unwrap_or_default())
+        }
+        RuleCondition::ListId { value } => {
+            let needle = value.trim().to_lowercase();
+            !needle.is_empty()
+                && list_id(email).is_some_and(|id| id.to_lowercase().contains(&needle))
+        }
+        RuleCondition::HasAttachment { value } => email.has_attachments == *value,
+    }
+}
+
+/// Whether a rule's conditions hold for the message. Rules without conditions
+/// never match, so an empty rule cannot act on every message.
+pub fn rule_matches(rule: &Rule, email: &Email, patterns: &SubjectPatterns) -> bool {
+    if rule.conditions.is_empty() {
+        return false;
+    }
+
+    if rule.match_all {
+        rule.conditions
+            .iter()
+            .all(|c| condition_matches(c, email, patterns))
+    } else {
+        rule.conditions
+            .iter()
+            .any(|c| condition_matches(c, email, patterns))
+    }
+}
+
+/// Reads the List-Id header from the stored header map, if the provider kept it
+fn list_id(email: &Email) -> Option<String> {
+    let headers: serde_json::Value = serde_json::from_str(email.headers.as_deref()?).ok()?;
+    headers
+        .as_object()?
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("list-id"))
+        .and_then(|(_, value)| value.as_str())
+        .map(ToString::to_string)
+}
+
+pub struct RulesEngine {
+    pool: SqlitePool,
+    patterns: SubjectPatterns,
+}
+
+impl RulesEngine {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            pool,
+            patterns: SubjectPatterns::default(),
+        }
+    }
+
+    /// Applies the account's rules to a newly synced inbox message. Forward
+    /// actions only run for recently received mail.
+    pub async fn process_synced_email(
+        &self,
+        email: &Email,
+        app_handle: Option<&AppHandle>,
+    ) -> Result<Option<RuleOutcome>, String> {
+        if email.is_draft || email.is_deleted {
+            return Ok(None);
+        }
+
+        let folder = SqliteFolderRepository::new(self.pool.clone())
+            .find_by_id(email.folder_id)
+            .await
+            .map_err(|e| format!("Failed to load folder: {}", e))?;
+        if !folder.is_some_and(|f| f.folder_type == FolderType::Inbox) {
+            return Ok(None);
+        }
+
+        let rules = SqliteRuleRepository::new(self.pool.clone())
+            .find_enabled_for_account(email.account_id)
+            .await
+            .map_err(|e| format!("Failed to load rules: {}", e))?;
+
+        if rules.is_empty() {
+            return Ok(None);
+        }
+
+        let app_handle = app_handle.filter(|_| is_recent_for_forwarding(email, Utc::now()));
+        let outcome = self.apply_rules(&rules, email, app_handle).await?;
+        Ok((!outcome.matched_rule_ids.is_empty()).then_some(outcome))
+    }
+
+    /// Evaluates `rules` in order and runs the actions of each matching rule.
+    ///
+    /// Forwarding only happens when `app_handle` is given; callers re-running
+    /// rules over existing mail pass `None` so old messages are never sent out.
+    pub async fn apply_rules(
+        &self,
+        rules: &[Rule],
+        email: &Email,
+        app_handle: Option<&AppHandle>,
+    ) -> Result<RuleOutcome, String> {
+        let mut outcome = RuleOutcome {
+            email_id: email.id,
+            ..RuleOutcome::default()
+        };
+        let mut current = email.clone();
+
+        for rule in rules {
+            if !rule.enabled || !rule_matches(rule, &current, &self.patterns) {
+                continue;
+            }
+
+            log::info!(
+                "[RulesEngine] Rule '{}' matched email {}",
+                rule.name,
+                email.id
+            );
+            outcome.matched_rule_ids.push(rule.id);
+
+            for action in &rule.actions {
+                self.run_action(action, &mut current, &mut outcome, app_handle)
+                    .await?;
+                if outcome.deleted {
+                    return Ok(outcome);
+                }
+            }
+
+            if rule.stop_processing {
+                break;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    async fn run_action(
+        &self,
+        action: &RuleAction,
+        email: &mut Email,
+        outcome: &mut RuleOutcome,
+        app_handle: Option<&AppHandle>,
+    ) -> Result<(), String> {
+        match action {
+            RuleAction::MoveToFolder { folder_id } => {
+                if *folder_id == email.folder_id {
+                    return Ok(());
+                }
+                let folder = SqliteFolderRepository::new(self.pool.clone())
+                    .find_by_id(*folder_id)
+                    .await
+                    .map_err(|e| format!("Failed to load folder: {}", e))?;
+                if !folder.is_some_and(|f| f.account_id == email.account_id) {
+                    log::warn!(
+                        "[RulesEngine] Skipping move of email {}: folder {} does not belong to its account",
+                        email.id,
+                        folder_id
+                    );
+                    return Ok(());
+                }
+
+                let email_repo = SqliteEmailRepository::new(self.pool.clone());
+                email_repo
+                    .update_folder(email.id, *folder_id)
+                    .await
+                    .map_err(|e| format!("Failed to move email: {}", e))?;
+                self.queue_operation(
+                    email,
+                    PendingOperationType::Move,
+                    Some(folder_id.to_string()),
+                )
+                .await?;
+
+                email.folder_id = *folder_id;
+                outcome.moved_to_folder_id = Some(*folder_id);
+            }
+            RuleAction::ApplyLabel { label_id } => {
+                let label_repo = SqliteLabelRepository::new(self.pool.clone());
+                label_repo
+                    .add_to_email(email.id, *label_id)
+                    .await
+                    .map_err(|e| format!("Failed to apply label: {}", e))?;
+
+                // Gmail labels are pushed back, or the next sync would drop them again
+                if let Some(label) = label_repo
+                    .find_by_id(*label_id)
+                    .await
+                    .map_err(|e| format!("Failed to load label: {}", e))?
+                {
+                    gmail_labels::queue_label_change(&self.pool, email, &label, true).await?;
+                }
+            }
+            RuleAction::MarkRead => {
+                if email.is_read {
+                    return Ok(());
+                }
+                SqliteEmailRepository::new(self.pool.clone())
+                    .update_read_status(email.id, true)
+                    .await
+                    .map_err(|e| format!("Failed to mark email as read: {}", e))?;
+                self.queue_operation(email, PendingOperationType::MarkRead, None)
+                    .await?;
+
+                email.is_read = true;
+                outcome.marked_read = true;
+            }
+            RuleAction::Delete => {
+                SqliteEmailRepository::new(self.pool.clone())
+                    .soft_delete(email.id)
+                    .await
+                    .map_err(|e| format!("Failed to delete email: {}", e))?;
+                self.queue_operation(email, PendingOperationType::Delete, None)
+                    .await?;
+
+                email.is_deleted = true;
+                outcome.deleted = true;
+            }
+            RuleAction::Forward { to } => match app_handle {
+                Some(app_handle) => forward(app_handle.clone(), email, to),
+                None => log::debug!(
+                    "[RulesEngine] Not forwarding existing email {} to {}",
+                    email.id,
+                    to
+                ),
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Queues the provider side of an action that was already applied locally.
+    /// Messages without a remote copy yet are left to the next sync.
+    async fn queue_operation(
+        &self,
+        email: &Email,
+        op_type: PendingOperationType,
+        to_folder_id: Option<String>,
+    ) -> Result<(), String> {
+        let Some(remote_id) = email.remote_id.clone() else {
+            return Ok(());
+        };
+
+        let mut params = serde_json::json!({
+            "remote_id": remote_id,
+            "folder_id": email.folder_id.to_string(),
+        });
+        if let Some(to_folder_id) = to_folder_id {
+            params["to_folder_id"] = serde_json::Value::String(to_folder_id);
+        }
+
+        let op = PendingOperation::new(
+            email.account_id,
+            Some(email.id),
+            Some(email.folder_id),
+            op_type,
+            params,
+        );
+        SqlitePendingOperationRepository::new(self.pool.clone())
+            .create(&op)
+            .await
+            .map_err(|e| format!("Failed to queue rule action: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Sends a copy of the message in the background so a slow SMTP server never
+/// holds up the sync
+fn forward(app_handle: AppHandle, email: &Email, to: &str) {
+    let request = SendFromAccountRequest {
+        account_id: email.account_id,
+        to: vec![EmailAddress {
+            address: to.trim().to_string(),
+            name: None,
+        }],
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        subject: format!("Fwd: {}", email.subject.as_deref().unwrap_or_default()),
+        body: forward_body(email),
+        attachments: Vec::new(),
+        draft_id: None,
+        conversation_id: None,
+        in_reply_to: None,
+        references: None,
+        identity_id: None,
+        dsn: None,
+        read_receipt: false,
+        smime: None,
+        signature: SignatureChoice::None,
+    };
+    let email_id = email.id;
+
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app_handle.try_state::<AppState>() else {
+            return;
+        };
+        if let Err(e) = send_email_from_account(state, request).await {
+            log::error!("[RulesEngine] Failed to forward email {}: {}", email_id, e);
+        }
+    });
+}
+
+fn forward_body(email: &Email) -> String {
+    let from = match &email.from.name {
+        Some(name) => format!("{} &lt;{}&gt;", escape_html(name), email.from.address),
+        None => email.from.address.clone(),
+    };
+    let original = match (&email.body_html, &email.body_plain) {
+        (Some(html), _) => html.clone(),
+        (None, Some(plain)) => format!("<pre>{}</pre>", escape_html(plain)),
+        (None, None) => String::new(),
+    };
+
+    format!(
+        "<div>---------- Forwarded message ---------</div>\
+         <div>From: {}</div>\
+         <div>Date: {}</div>\
+         <div>Subject: {}</div>\
+         <br>{}",
+        from,
+        email.received_at.to_rfc2822(),
+        escape_html(email.subject.as_deref().unwrap_or_default()),
+        original
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::types::Json;
+
+    fn email(from: &str, subject: &str, headers: Option<&str>) -> Email {
+        Email {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            folder_id: Uuid::now_v7(),
+            message_id: "<test@example.com>".to_string(),
+            conversation_id: None,
+            remote_id: Some("1".to_string()),
+            from: Json(EmailAddress {
+                address: from.to_string(),
+                name: Some("Example News".to_string()),
+            }),
+            to: Json(vec![]),
+            cc: Json(vec![]),
+            bcc: Json(vec![]),
+            reply_to: None,
+            subject: Some(subject.to_string()),
+            snippet: None,
+            body_plain: None,
+            body_html: None,
+            other_mails: None,
+            category: None,
+            ai_cache: None,
+            received_at: Utc::now(),
+            sent_at: None,
+            scheduled_send_at: None,
+            remind_at: None,
+            snoozed_until: None,
+            is_read: false,
+            is_flagged: false,
+            has_attachments: false,
+            is_draft: false,
+            is_deleted: false,
+            headers: headers.map(ToString::to_string),
+            sync_status: "synced".to_string(),
+            tracking_blocked: false,
+            images_blocked: false,
+            body_fetch_attempts: 0,
+            last_body_fetch_attempt: None,
+            change_key: None,
+            last_modified_at: None,
+            deleted_at: None,
+            deletion_source: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            size: 0,
+            priority: 0,
+            risk_score: 0,
+            risk_reasons: Json(Vec::new()),
+        }
+    }
+
+    fn rule(match_all: bool, conditions: Vec<RuleCondition>) -> Rule {
+        Rule {
+            id: Uuid::now_v7(),
+            account_id: None,
+            name: "Test".to_string(),
+            enabled: true,
+            match_all,
+            conditions,
+            actions: vec![RuleAction::MarkRead],
+            stop_processing: false,
+            sort_order: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_condition_matches() {
+        let patterns = SubjectPatterns::default();
+        let email = email(
+            "News@Example.com",
+            "Your weekly digest #42",
+            Some(r#"{"List-ID": "<weekly.example.com>"}"#),
+        );
+
+        assert!(condition_matches(
+            &RuleCondition::From {
+                value: "news@example.com".to_string()
+            },
+            &email,
+            &patterns
+        ));
+        assert!(condition_matches(
+            &RuleCondition::From {
+                value: "example news".to_string()
+            },
+            &email,
+            &patterns
+        ));
+        assert!(condition_matches(
+            &RuleCondition::SubjectRegex {
+                pattern: r"weekly digest #\d+".to_string()
+            },
+            &email,
+            &patterns
+        ));
+        assert!(!condition_matches(
+            &RuleCondition::SubjectRegex {
+                pattern: "(unclosed".to_string()
+            },
+            &email,
+            &patterns
+        ));
+        assert!(condition_matches(
+            &RuleCondition::ListId {
+                value: "weekly.example.com".to_string()
+            },
+            &email,
+            &patterns
+        ));
+        assert!(condition_matches(
+            &RuleCondition::HasAttachment { value: false },
+            &email,
+            &patterns
+        ));
+    }
+
+    #[test]
+    fn test_subject_patterns_compiled_once() {
+        let patterns = SubjectPatterns::default();
+        assert!(patterns.is_match(r"INVOICE \d+", "invoice 42"));
+        assert!(!patterns.is_match(r"INVOICE \d+", "receipt"));
+        assert!(!patterns.is_match("(unclosed", "(unclosed"));
+        assert_eq!(patterns.compiled.lock().unwrap().len(), 2);
+
+        assert!(compile_subject_pattern("(unclosed").is_err());
+        assert!(compile_subject_pattern(r"weekly digest #\d+").is_ok());
+    }
+
+    #[test]
+    fn test_only_recent_mail_is_forwarded() {
+        let now = Utc::now();
+        let mut email = email("news@example.com", "Hello", None);
+
+        email.received_at = now - chrono::Duration::hours(1);
+        assert!(is_recent_for_forwarding(&email, now));
+
+        email.received_at = now - chrono::Duration::days(30);
+        assert!(!is_recent_for_forwarding(&email, now));
+    }
+
+    #[test]
+    fn test_rule_matches_all_or_any() {
+        let email = email("news@example.com", "Hello", None);
+        let conditions = vec![
+            RuleCondition::From {
+                value: "example.com".to_string(),
+            },
+            RuleCondition::ListId {
+                value: "list".to_string(),
+            },
+        ];
+
+        let patterns = SubjectPatterns::default();
+
+        assert!(!rule_matches(
+            &rule(true, conditions.clone()),
+            &email,
+            &patterns
+        ));
+        assert!(rule_matches(&rule(false, conditions), &email, &patterns));
+        assert!(!rule_matches(&rule(false, Vec::new()), &email, &patterns));
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::time::sleep;
+
+use crate::commands::emails::{send_email_from_account, SendFromAccountRequest};
+use crate::database::models::email::Email;
+use crate::database::models::signature::SignatureChoice;
+use crate::database::repositories::{
+    EmailRepository, IdentityRepository, SqliteEmailRepository, SqliteIdentityRepository,
+};
+use crate::state::AppState;
+use crate::sync::events::{self, ScheduledSendEvent, ScheduledSendStatus};
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 30;
+
+/// Sends drafts whose `scheduled_send_at` has passed and moves them to Sent
+pub struct ScheduledSendWorker {
+    pool: SqlitePool,
+    app_handle: AppHandle,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    poll_interval: Duration,
+}
+
+impl ScheduledSendWorker {
+    pub fn new(pool: SqlitePool, app_handle: AppHandle) -> Self {
+        let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+        Self {
+            pool,
+            app_handle,
+            shutdown_tx,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn start(self: &Arc<Self>) -> Result<(), String> {
+        log::info!("[ScheduledSendWorker] Starting scheduled send worker");
+
+        let this = Arc::clone(self);
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                // Runs immediately on startup so drafts that came due while the app
+                // was closed go out right away
+                if let Err(error) = this.send_due().await {
+                    log::error!(
+                        "[ScheduledSendWorker] Failed to process scheduled sends: {}",
+                        error
+                    );
+                }
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        log::info!("[ScheduledSendWorker] Shutdown signal received");
+                        break;
+                    }
+                    _ = sleep(this.poll_interval) => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn shutdown(&self) {
+        if let Err(error) = self.shutdown_tx.send(()) {
+            log::debug!(
+                "[ScheduledSendWorker] Shutdown signal could not be delivered: {}",
+                error
+            );
+        }
+    }
+
+    async fn send_due(&self) -> Result<(), String> {
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+
+        let due = email_repo
+            .find_due_scheduled_sends(Utc::now())
+            .await
+            .map_err(|e| format!("Failed to query due scheduled sends: {}", e))?;
+
+        for draft in due {
+            // Clear the schedule before sending so a crash mid-send never results
+            // in the same draft going out twice
+            email_repo
+                .update_scheduled_send_at(draft.id, None)
+                .await
+                .map_err(|e| format!("Failed to claim scheduled draft {}: {}", draft.id, e))?;
+
+            self.emit_status(&draft, ScheduledSendStatus::Sending);
+
+            match self.send_draft(&draft).await {
+                Ok(()) => {
+                    log::info!("[ScheduledSendWorker] Sent scheduled draft {}", draft.id);
+                    self.emit_status(&draft, ScheduledSendStatus::Sent);
+                }
+                Err(e) => {
+                    log::error!(
+                        "[ScheduledSendWorker] Failed to send scheduled draft {}: {}",
+                        draft.id,
+                        e
+                    );
+                    self.emit_status(&draft, ScheduledSendStatus::Failed { message: e });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_draft(&self, draft: &Email) -> Result<(), String> {
+        let state = self
+            .app_handle
+            .try_state::<AppState>()
+            .ok_or_else(|| "Application state is not available".to_string())?;
+
+        // The draft's sender may be one of the account's aliases
+        let identity = SqliteIdentityRepository::new(self.pool.clone())
+            .find_by_email(draft.account_id, &draft.from.0.address)
+            .await
+            .map_err(|e| format!("Failed to find identity: {}", e))?;
+
+        let request = SendFromAccountRequest {
+            account_id: draft.account_id,
+            to: draft.to.0.clone(),
+            cc: draft.cc.0.clone(),
+            bcc: draft.bcc.0.clone(),
+            subject: draft.subject.clone().unwrap_or_default(),
+            body: draft.body_html.clone().unwrap_or_default(),
+            attachments: Vec::new(),
+            draft_id: Some(draft.id),
+            conversation_id: draft.conversation_id.clone(),
+            // Threading headers are resolved from the stored draft
+            in_reply_to: None,
+            references: None,
+            identity_id: identity.map(|identity| identity.id),
+            dsn: None,
+            read_receipt: false,
+            smime: None,
+            signature: SignatureChoice::Auto,
+        };
+
+        // A draft that could not reach the server is retried from the outbox
+        let response = send_email_from_account(state, request).await?;
+        if !response.success {
+            return Err(response.message);
+        }
+
+        Ok(())
+    }
+
+    fn emit_status(&self, draft: &Email, status: ScheduledSendStatus) {
+        events::emit_event(
+            &self.app_handle,
+            "email:scheduled-send",
+            ScheduledSendEvent {
+                account_id: draft.account_id,
+                email_id: draft.id,
+                status,
+            },
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Storage of S/MIME signature checks made while syncing.
+//!
+//! Certificates of intact signatures are collected so replies to their owners
+//! can be encrypted.
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::database::models::smime::{SmimeCertificate, SmimeStatus, SmimeVerification};
+use crate::database::repositories::{SmimeRepository, SqliteSmimeRepository};
+use crate::services::smime::SignatureCheck;
+
+/// Stores the signature check of a newly synced message and collects the
+/// signer's certificate
+pub async fn record_signature(
+    pool: &SqlitePool,
+    email_id: Uuid,
+    check: &SignatureCheck,
+) -> Result<(), String> {
+    let repo = SqliteSmimeRepository::new(pool.clone());
+    let signer = check.signer.as_ref();
+
+    repo.save_verification(&SmimeVerification {
+        email_id,
+        status: check.status,
+        signer_email: signer.and_then(|info| info.email_addresses.first().cloned()),
+        signer_subject: signer.map(|info| info.subject.clone()),
+        issuer: signer.map(|info| info.issuer.clone()),
+        fingerprint: signer.map(|info| info.fingerprint.clone()),
+        not_after: signer.map(|info| info.not_after),
+        error: check.error.clone(),
+        verified_at: Utc::now(),
+    })
+    .await
+    .map_err(|e| format!("Failed to store signature status: {}", e))?;
+
+    if check.status == SmimeStatus::Invalid {
+        return Ok(());
+    }
+
+    let (Some(info), Some(certificate_pem)) = (signer, &check.signer_certificate_pem) else {
+        return Ok(());
+    };
+    // Certificates are stored once per fingerprint, under their first address
+    let Some(email) = info.email_addresses.first() else {
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    repo.upsert(&SmimeCertificate {
+        id: Uuid::now_v7(),
+        account_id: None,
+        email: email.clone(),
+        subject: info.subject.clone(),
+        issuer: info.issuer.clone(),
+        serial: info.serial.clone(),
+        fingerprint: info.fingerprint.clone(),
+        not_before: info.not_before,
+        not_after: info.not_after,
+        certificate_pem: certificate_pem.clone(),
+        has_private_key: false,
+        created_at: now,
+        updated_at: now,
+    })
+    .await
+    .map_err(|e| format!("Failed to store signer certificate: {}", e))?;
+
+    Ok(())
+}
@@ -47,6 +47,85 @@ impl LocalFileStorage {
         }
         Ok(())
     }
+
+    /// Open a writer for storing a file chunk by chunk. The data goes to a
+    /// `.part` file next to the destination until the writer is committed.
+    pub async fn create_writer(&self, path: &Path) -> SyncResult<StorageWriter> {
+        let full_path = self.full_path(path);
+        self.ensure_parent_dir(&full_path).await?;
+
+        let mut part_path = full_path.clone().into_os_string();
+        part_path.push(".part");
+        let part_path = PathBuf::from(part_path);
+        let file = fs::File::create(&part_path).await?;
+
+        Ok(StorageWriter {
+            file,
+            part_path,
+            full_path,
+        })
+    }
+
+    /// Size of a stored file in bytes, or 0 if it does not exist
+    pub async fn file_size(&self, path: &Path) -> u64 {
+        fs::metadata(self.full_path(path))
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0)
+    }
+
+    /// Combined size of all stored files in bytes, including partial downloads
+    pub async fn total_size(&self) -> SyncResult<u64> {
+        if !self.base_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut total = 0;
+        let mut directories = vec![self.base_dir.clone()];
+
+        while let Some(directory) = directories.pop() {
+            let mut entries = fs::read_dir(&directory).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    directories.push(entry.path());
+                } else {
+                    total += metadata.len();
+                }
+            }
+        }
+
+        Ok(total)
+    }
+}
+
+/// File being written to [`LocalFileStorage`] in chunks
+pub struct StorageWriter {
+    file: fs::File,
+    part_path: PathBuf,
+    full_path: PathBuf,
+}
+
+impl StorageWriter {
+    pub async fn write(&mut self, chunk: &[u8]) -> SyncResult<()> {
+        self.file.write_all(chunk).await?;
+        Ok(())
+    }
+
+    /// Flush the file and move it to its destination
+    pub async fn commit(self) -> SyncResult<()> {
+        self.file.sync_all().await?;
+        drop(self.file);
+        fs::rename(&self.part_path, &self.full_path).await?;
+        Ok(())
+    }
+
+    /// Remove the partial file
+    pub async fn discard(self) -> SyncResult<()> {
+        drop(self.file);
+        fs::remove_file(&self.part_path).await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -165,6 +244,31 @@ mod tests {
         assert!(!storage.exists(test_path).await);
     }
 
+    #[tokio::test]
+    async fn test_storage_writer() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::new(temp_dir.path().to_path_buf());
+        let test_path = Path::new("account1/email1/large.bin");
+
+        let mut writer = storage.create_writer(test_path).await.unwrap();
+        writer.write(b"Hello, ").await.unwrap();
+        // Nothing is visible before the commit
+        assert!(!storage.exists(test_path).await);
+        writer.write(b"World!").await.unwrap();
+        writer.commit().await.unwrap();
+        assert_eq!(storage.retrieve(test_path).await.unwrap(), b"Hello, World!");
+
+        let discarded = Path::new("account1/email1/cancelled.bin");
+        let mut writer = storage.create_writer(discarded).await.unwrap();
+        writer.write(b"partial").await.unwrap();
+        writer.discard().await.unwrap();
+        assert!(!storage.exists(discarded).await);
+        assert!(!temp_dir
+            .path()
+            .join("account1/email1/cancelled.bin.part")
+            .exists());
+    }
+
     #[tokio::test]
     async fn test_storage_directory_operations() {
         let temp_dir = TempDir::new().unwrap();
@@ -184,4 +288,27 @@ mod tests {
         assert!(!storage.exists(&dir_path.join("file1.txt")).await);
         assert!(!storage.exists(&dir_path.join("file2.txt")).await);
     }
+
+    #[tokio::test]
+    async fn test_storage_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalFileStorage::new(temp_dir.path().join("attachments"));
+        assert_eq!(storage.total_size().await.unwrap(), 0);
+
+        storage
+            .store(Path::new("account1/email1/a.txt"), b"12345")
+            .await
+            .unwrap();
+        storage
+            .store(Path::new("account1/email2/b.txt"), b"123")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.file_size(Path::new("account1/email1/a.txt")).await,
+            5
+        );
+        assert_eq!(storage.file_size(Path::new("missing.txt")).await, 0);
+        assert_eq!(storage.total_size().await.unwrap(), 8);
+    }
 }
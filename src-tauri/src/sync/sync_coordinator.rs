@@ -4,8 +4,10 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use super::attachment_download::AttachmentDownloads;
 use super::auth::CredentialStore;
 use super::error::{SyncError, SyncResult};
+use super::events::emit_event;
 use super::SyncManager;
 use crate::config::settings::Settings;
 use crate::database::models::account::Account;
@@ -27,6 +29,8 @@ pub struct SyncCoordinator {
     settings: Option<Arc<Settings>>,
     /// Cache of account_id -> SyncManager instances
     managers: Arc<RwLock<HashMap<Uuid, Arc<SyncManager>>>>,
+    /// Attachment downloads in flight
+    downloads: Arc<AttachmentDownloads>,
 }
 
 impl SyncCoordinator {
@@ -44,6 +48,7 @@ impl SyncCoordinator {
             notification_service: None,
             settings: None,
             managers: Arc::new(RwLock::new(HashMap::new())),
+            downloads: Arc::new(AttachmentDownloads::new()),
         }
     }
 
@@ -255,6 +260,12 @@ impl SyncCoordinator {
         manager.delete_email(&account, email_id, permanent).await
     }
 
+    pub async fn restore_email(&self, account_id: Uuid, email_id: Uuid) -> SyncResult<()> {
+        let account = self.get_account(account_id).await?;
+        let manager = self.get_manager_for_account(&account).await?;
+        manager.restore_email(&account, email_id).await
+    }
+
     pub async fn mark_as_read(
         &self,
         account_id: Uuid,
@@ -311,4 +322,67 @@ impl SyncCoordinator {
             .move_folder(&account, folder_id, old_parent_id, new_parent_id)
             .await
     }
+
+    pub async fn download_attachment(
+        &self,
+        account_id: Uuid,
+        attachment_id: Uuid,
+    ) -> SyncResult<String> {
+        let cancelled = self.downloads.start(attachment_id)?;
+
+        let result = async {
+            let account = self.get_account(account_id).await?;
+            let manager = self.get_manager_for_account(&account).await?;
+            manager
+                .download_attachment(&account, attachment_id, cancelled)
+                .await
+        }
+        .await;
+
+        self.downloads.finish(attachment_id);
+        result
+    }
+
+    /// Stop a running attachment download. Returns false if none was running.
+    pub fn cancel_attachment_download(&self, attachment_id: Uuid) -> bool {
+        self.downloads.cancel(attachment_id)
+    }
+
+    /// Download attachments of an email the user opened in the background,
+    /// one at a time. Emits `email:attachments-cached` once any were stored.
+    pub fn prefetch_attachments(
+        self: &Arc<Self>,
+        account_id: Uuid,
+        email_id: Uuid,
+        attachment_ids: Vec<Uuid>,
+    ) {
+        if attachment_ids.is_empty() {
+            return;
+        }
+
+        let coordinator = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut cached = 0;
+
+            for attachment_id in attachment_ids {
+                match coordinator
+                    .download_attachment(account_id, attachment_id)
+                    .await
+                {
+                    Ok(_) => cached += 1,
+                    Err(e) => log::debug!(
+                        "[SyncCoordinator] Failed to prefetch attachment {}: {}",
+                        attachment_id,
+                        e
+                    ),
+                }
+            }
+
+            if cached > 0 {
+                if let Some(app_handle) = &coordinator.app_handle {
+                    emit_event(app_handle, "email:attachments-cached", email_id);
+                }
+            }
+        });
+    }
 }
@@ -0,0 +1,232 @@
+/// Limits on concurrent folder syncs
+///
+/// Each account gets a cap, set per provider, on folders synced at the same
+/// time from its host. Providers enforce their limits per mailbox, so slots and
+/// throttling are keyed by host and account: an account that answered with a
+/// throttling response is left alone until its Retry-After delay passed, so
+/// its parallel workers do not keep hitting a server that asked them to back
+/// off, while other accounts on the same host carry on.
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use super::types::AccountSettings;
+use crate::config::settings::Settings;
+use crate::database::models::account::{Account, AccountType};
+
+/// Folder workers per account
+pub const WORKERS_SETTING: &str = "sync.workers";
+pub const DEFAULT_WORKERS: usize = 3;
+
+/// Hosts of the HTTP APIs, used to key throttling
+pub const GMAIL_HOST: &str = "gmail.googleapis.com";
+pub const GRAPH_HOST: &str = "graph.microsoft.com";
+const ICLOUD_IMAP_HOST: &str = "imap.mail.me.com";
+
+/// Longest delay honoured from a Retry-After header
+pub const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Host and account a slot or throttle applies to
+type HostKey = (String, Uuid);
+
+struct SyncLimits {
+    /// Configured caps, the defaults where missing
+    caps: Mutex<HashMap<AccountType, usize>>,
+    /// Folder sync slots per account on a host
+    slots: Mutex<HashMap<HostKey, Arc<Semaphore>>>,
+    /// Accounts on a host throttled until the given instant
+    throttled: Mutex<HashMap<HostKey, Instant>>,
+}
+
+static LIMITS: Lazy<SyncLimits> = Lazy::new(|| SyncLimits {
+    caps: Mutex::new(HashMap::new()),
+    slots: Mutex::new(HashMap::new()),
+    throttled: Mutex::new(HashMap::new()),
+});
+
+/// Permission to sync a folder, released on drop
+pub struct SyncPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+fn setting_key(account_type: AccountType) -> &'static str {
+    match account_type {
+        AccountType::Gmail => "sync.concurrency.gmail",
+        AccountType::Office365 => "sync.concurrency.office365",
+        AccountType::Apple => "sync.concurrency.apple",
+        AccountType::Imap => "sync.concurrency.imap",
+    }
+}
+
+/// Folders of one account synced at the same time unless configured. Graph
+/// allows four concurrent requests per mailbox; IMAP servers often limit the
+/// connections of a user to a handful.
+fn default_cap(account_type: AccountType) -> usize {
+    match account_type {
+        AccountType::Gmail => 6,
+        AccountType::Office365 => 4,
+        AccountType::Apple | AccountType::Imap => 2,
+    }
+}
+
+/// Apply the configured caps. Syncs holding a slot under the previous caps
+/// finish unaffected.
+pub fn configure(settings: &Settings) {
+    let mut caps = LIMITS.caps.lock().unwrap();
+    for account_type in [
+        AccountType::Gmail,
+        AccountType::Office365,
+        AccountType::Apple,
+        AccountType::Imap,
+    ] {
+        let cap = settings
+            .get::<usize>(setting_key(account_type))
+            .unwrap_or_else(|_| default_cap(account_type))
+            .clamp(1, 32);
+        caps.insert(account_type, cap);
+    }
+    LIMITS.slots.lock().unwrap().clear();
+}
+
+/// Folder workers per account from the settings
+pub fn workers(settings: &Settings) -> usize {
+    settings
+        .get::<usize>(WORKERS_SETTING)
+        .unwrap_or(DEFAULT_WORKERS)
+        .clamp(1, 16)
+}
+
+fn host_slots(host: &str, account_id: Uuid, account_type: AccountType) -> Arc<Semaphore> {
+    let cap = LIMITS
+        .caps
+        .lock()
+        .unwrap()
+        .get(&account_type)
+        .copied()
+        .unwrap_or_else(|| default_cap(account_type));
+
+    let mut slots = LIMITS.slots.lock().unwrap();
+    Arc::clone(
+        slots
+            .entry((host.to_string(), account_id))
+            .or_insert_with(|| Arc::new(Semaphore::new(cap))),
+    )
+}
+
+/// Host the account's mail is synced from
+pub fn sync_host(account: &Account) -> String {
+    match account.account_type {
+        AccountType::Gmail => GMAIL_HOST.to_string(),
+        AccountType::Office365 => GRAPH_HOST.to_string(),
+        AccountType::Apple => ICLOUD_IMAP_HOST.to_string(),
+        AccountType::Imap => serde_json::from_value::<AccountSettings>(account.settings.clone())
+            .ok()
+            .and_then(|settings| settings.imap_host)
+            .map(|host| host.to_lowercase())
+            .unwrap_or_else(|| account.id.to_string()),
+    }
+}
+
+/// Wait until the account has a free slot and is not throttled
+pub async fn acquire(account: &Account) -> SyncPermit {
+    let host = sync_host(account);
+    wait_for_host(&host, account.id).await;
+
+    let permit = host_slots(&host, account.id, account.account_type)
+        .acquire_owned()
+        .await
+        .expect("sync semaphores are never closed");
+
+    SyncPermit { _permit: permit }
+}
+
+/// Leave the account's requests to `host` alone for `retry_after`, e.g.
+/// after a 429 response
+pub fn throttle_host(host: &str, account_id: Uuid, retry_after: Duration) {
+    let until = Instant::now() + retry_after.min(MAX_BACKOFF);
+    let mut throttled = LIMITS.throttled.lock().unwrap();
+    let entry = throttled
+        .entry((host.to_string(), account_id))
+        .or_insert(until);
+    if *entry < until {
+        *entry = until;
+    }
+
+    log::warn!(
+        "Throttling requests of account {} to {} for {}s",
+        account_id,
+        host,
+        retry_after.min(MAX_BACKOFF).as_secs()
+    );
+}
+
+/// Point in time until which the account's requests to `host` are throttled
+fn throttled_until(host: &str, account_id: Uuid) -> Option<Instant> {
+    let key = (host.to_string(), account_id);
+    let mut throttled = LIMITS.throttled.lock().unwrap();
+    match throttled.get(&key) {
+        Some(until) if *until > Instant::now() => Some(*until),
+        Some(_) => {
+            throttled.remove(&key);
+            None
+        }
+        None => None,
+    }
+}
+
+/// Sleep while the account's requests to `host` are throttled
+pub async fn wait_for_host(host: &str, account_id: Uuid) {
+    while let Some(until) = throttled_until(host, account_id) {
+        tokio::time::sleep_until(until).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_host() {
+        let host = "throttle.example.com";
+        let account_id = Uuid::now_v7();
+        let other_account_id = Uuid::now_v7();
+        assert!(throttled_until(host, account_id).is_none());
+
+        throttle_host(host, account_id, Duration::from_secs(30));
+        // A shorter delay does not shorten the backoff
+        throttle_host(host, account_id, Duration::from_secs(5));
+        // Other accounts on the host are not held back
+        assert!(throttled_until(host, other_account_id).is_none());
+
+        let start = Instant::now();
+        wait_for_host(host, account_id).await;
+        assert_eq!(start.elapsed().as_secs(), 30);
+        assert!(throttled_until(host, account_id).is_none());
+
+        throttle_host(host, account_id, Duration::from_secs(3600));
+        assert_eq!(
+            throttled_until(host, account_id).unwrap() - Instant::now(),
+            MAX_BACKOFF
+        );
+    }
+
+    #[test]
+    fn test_slots_are_per_account() {
+        let host = "slots.example.com";
+        let account_id = Uuid::now_v7();
+
+        let slots = host_slots(host, account_id, AccountType::Imap);
+        assert!(Arc::ptr_eq(
+            &slots,
+            &host_slots(host, account_id, AccountType::Imap)
+        ));
+        assert!(!Arc::ptr_eq(
+            &slots,
+            &host_slots(host, Uuid::now_v7(), AccountType::Imap)
+        ));
+    }
+}
@@ -1,5 +1,7 @@
+use futures::stream::{self, StreamExt};
 use sqlx::SqlitePool;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
@@ -9,6 +11,10 @@ use super::email_sync::EmailSync;
 use super::error::{SyncError, SyncResult};
 use super::events::*;
 use super::folder_sync::FolderSync;
+use super::gmail_labels;
+use super::mailbox_quota;
+use super::network_usage;
+use super::sync_limits;
 use super::types::SyncFolder;
 use crate::config::Settings;
 use crate::database::error::DatabaseError;
@@ -16,11 +22,16 @@ use crate::database::models::account::Account;
 use crate::database::models::pending_operation::{PendingOperation, PendingOperationType};
 use crate::database::repositories::{
     EmailRepository, FolderRepository, SqliteEmailRepository, SqliteFolderRepository,
-    SqlitePendingOperationRepository,
+    SqlitePendingOperationRepository, SqliteSyncStateRepository, SyncStateRepository,
 };
 use crate::search::SearchManager;
+use crate::services::automation_triggers;
 use crate::services::notification_service::NotificationService;
 
+/// How long a permanent delete waits before it is sent to the provider,
+/// during which the email can still be restored
+const PERMANENT_DELETE_DELAY_MINS: i64 = 10;
+
 /// Central sync manager that coordinates all sync operations
 pub struct SyncManager {
     pool: SqlitePool,
@@ -76,6 +87,10 @@ impl SyncManager {
             email_sync_builder = email_sync_builder.with_app_handle(app_handle.clone());
         }
 
+        if let Some(settings) = &self.settings {
+            email_sync_builder = email_sync_builder.with_settings(Arc::clone(settings));
+        }
+
         if let Some(notification_service) = &self.notification_service {
             email_sync_builder =
                 email_sync_builder.with_notification_service(Arc::clone(notification_service));
@@ -108,6 +123,10 @@ impl SyncManager {
             email_sync_builder = email_sync_builder.with_search_manager(Arc::clone(search_manager));
         }
 
+        if let Some(settings) = &self.settings {
+            email_sync_builder = email_sync_builder.with_settings(Arc::clone(settings));
+        }
+
         if let Some(notification_service) = &self.notification_service {
             email_sync_builder =
                 email_sync_builder.with_notification_service(Arc::clone(notification_service));
@@ -131,7 +150,8 @@ impl SyncManager {
             self.pool.clone(),
             self.app_data_dir.clone(),
             Arc::clone(&self.credential_store),
-        );
+        )
+        .with_settings(Arc::clone(&settings));
 
         if let Some(search_manager) = &self.search_manager {
             email_sync_builder = email_sync_builder.with_search_manager(Arc::clone(search_manager));
@@ -177,6 +197,10 @@ impl SyncManager {
             email_sync_builder = email_sync_builder.with_app_handle(app_handle.clone());
         }
 
+        if let Some(settings) = &self.settings {
+            email_sync_builder = email_sync_builder.with_settings(Arc::clone(settings));
+        }
+
         email_sync_builder = email_sync_builder.with_notification_service(notification_service);
 
         self.email_sync = Arc::new(email_sync_builder);
@@ -204,6 +228,15 @@ impl SyncManager {
             )));
         }
 
+        let paused = SqliteSyncStateRepository::new(self.pool.clone())
+            .is_account_paused(account.id)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        if paused {
+            log::debug!("Sync of account {} is paused, skipping", account.id);
+            return Ok(SyncReport::default());
+        }
+
         {
             let mut syncs = self.active_syncs.write().await;
             syncs.insert(account.id, true);
@@ -282,11 +315,36 @@ impl SyncManager {
         .await
         .map_err(DatabaseError::ConnectionError);
 
-        // Step 2: Sync emails for each folder (prioritize by lowest sync_interval)
-        let mut sorted_folders = folders.clone();
-        sorted_folders.sort_by_key(|folder| folder.sync_interval);
+        // Labels go first so synced messages can be attached to them
+        if let Err(e) =
+            gmail_labels::refresh_labels(&self.pool, Arc::clone(&self.credential_store), account.id)
+                .await
+        {
+            log::warn!("Failed to sync labels for account {}: {}", account.id, e);
+            report.errors.push(format!("Label sync failed: {}", e));
+        }
 
-        for folder in &sorted_folders {
+        // Folders the user excluded from sync
+        let disabled: HashSet<Uuid> = SqliteFolderRepository::new(self.pool.clone())
+            .find_by_account(account.id)
+            .await
+            .map(|folders| {
+                folders
+                    .into_iter()
+                    .filter(|folder| !folder.sync_enabled)
+                    .map(|folder| folder.id)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Step 2: Sync emails for each folder (prioritize by lowest sync_interval),
+        // several at a time within the limits of the provider's host
+        let mut sorted_folders = Vec::new();
+        for folder in &folders {
+            if folder.id.is_some_and(|id| disabled.contains(&id)) {
+                log::debug!("Sync disabled for folder: {}", folder.name);
+                continue;
+            }
             if matches!(
                 folder.folder_type,
                 super::types::FolderType::Trash | super::types::FolderType::Spam
@@ -294,10 +352,29 @@ impl SyncManager {
                 log::debug!("Skipping auto-sync for folder: {}", folder.name);
                 continue;
             }
+            sorted_folders.push(folder);
+        }
+        sorted_folders.sort_by_key(|folder| folder.sync_interval);
 
-            log::info!("Syncing emails for folder: {}", folder.name);
-
-            match self.email_sync.sync_folder(account, folder, false).await {
+        let workers = self
+            .settings
+            .as_deref()
+            .map_or(sync_limits::DEFAULT_WORKERS, sync_limits::workers);
+        let results: Vec<_> = stream::iter(sorted_folders)
+            .map(|folder| async move {
+                let _permit = sync_limits::acquire(account).await;
+                log::info!("Syncing emails for folder: {}", folder.name);
+                (
+                    folder,
+                    self.email_sync.sync_folder(account, folder, false).await,
+                )
+            })
+            .buffered(workers)
+            .collect()
+            .await;
+
+        for (folder, result) in results {
+            match result {
                 Ok(count) => {
                     report.emails_synced += count;
                     log::info!("Synced {} emails in folder {}", count, folder.name);
@@ -313,6 +390,22 @@ impl SyncManager {
             }
         }
 
+        if let Err(e) = network_usage::flush(&self.pool).await {
+            log::warn!("Failed to store network usage: {}", e);
+        }
+
+        match mailbox_quota::refresh_quota_if_stale(
+            &self.pool,
+            Arc::clone(&self.credential_store),
+            account,
+        )
+        .await
+        {
+            Ok(Some(quota)) => self.emit_event("account:quota-updated", quota),
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to refresh quota for account {}: {}", account.id, e),
+        }
+
         log::info!(
             "Sync complete for account {}: {} folders, {} emails",
             account.id,
@@ -320,6 +413,18 @@ impl SyncManager {
             report.emails_synced
         );
 
+        // The provider is reachable again: replay operations deferred while offline
+        let pending_repo = SqlitePendingOperationRepository::new(self.pool.clone());
+        match pending_repo.resume_account(account.id).await {
+            Ok(0) => {}
+            Ok(count) => log::info!(
+                "Resuming {} deferred operations for account {}",
+                count,
+                account.id
+            ),
+            Err(e) => log::warn!("Failed to resume deferred operations: {}", e),
+        }
+
         self.emit_event(
             "sync:status",
             SyncStatusEvent {
@@ -349,7 +454,14 @@ impl SyncManager {
         folder: &SyncFolder,
         full: bool,
     ) -> SyncResult<usize> {
-        let count = self.email_sync.sync_folder(account, folder, full).await?;
+        let count = {
+            let _permit = sync_limits::acquire(account).await;
+            self.email_sync.sync_folder(account, folder, full).await?
+        };
+
+        if let Err(e) = network_usage::flush(&self.pool).await {
+            log::warn!("Failed to store network usage: {}", e);
+        }
 
         if let Some(folder_id) = folder.id {
             self.emit_event(
@@ -366,6 +478,24 @@ impl SyncManager {
         Ok(count)
     }
 
+    /// Sync the next step of a folder's history backfill
+    pub async fn backfill_folder(
+        &self,
+        account: &Account,
+        folder: &SyncFolder,
+    ) -> SyncResult<usize> {
+        let count = {
+            let _permit = sync_limits::acquire(account).await;
+            self.email_sync.backfill_folder(account, folder).await?
+        };
+
+        if let Err(e) = network_usage::flush(&self.pool).await {
+            log::warn!("Failed to store network usage: {}", e);
+        }
+
+        Ok(count)
+    }
+
     /// Get folders for an account
     pub async fn get_folders(&self, account_id: Uuid) -> SyncResult<Vec<SyncFolder>> {
         self.folder_sync.get_folders(account_id).await
@@ -446,26 +576,21 @@ impl SyncManager {
             .map_err(|e| SyncError::DatabaseError(e.to_string()))?
             .ok_or_else(|| SyncError::EmailNotFound(format!("Email not found: {}", email_id)))?;
 
-        // 1. Optimistic local update
-        if permanent {
-            email_repo
-                .delete(email_id)
-                .await
-                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
-        } else {
-            email_repo
-                .soft_delete(email_id)
-                .await
-                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
-        }
+        // 1. Optimistic local update. The email is kept as a tombstone so it shows up
+        // under recently deleted until the background cleanup purges it.
+        email_repo
+            .soft_delete(email_id)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
-        // 2. Queue provider operation
+        // 2. Queue provider operation. Permanent deletes wait a while, so they can
+        // still be restored before the provider removes the message for good.
         let op_type = if permanent {
             PendingOperationType::PermanentDelete
         } else {
             PendingOperationType::Delete
         };
-        let op = PendingOperation::new(
+        let mut op = PendingOperation::new(
             account.id,
             Some(email_id),
             Some(folder_id),
@@ -475,6 +600,10 @@ impl SyncManager {
                 "folder_id": folder_id.to_string(),
             }),
         );
+        if permanent {
+            op.next_attempt_at =
+                Some(chrono::Utc::now() + chrono::Duration::minutes(PERMANENT_DELETE_DELAY_MINS));
+        }
         let _ = pending_repo
             .create(&op)
             .await
@@ -504,6 +633,65 @@ impl SyncManager {
         Ok(())
     }
 
+    /// Restore a deleted email whose deletion has not reached the provider yet
+    pub async fn restore_email(&self, account: &Account, email_id: Uuid) -> SyncResult<()> {
+        let email_repo = SqliteEmailRepository::new(self.pool.clone());
+        let pending_repo = SqlitePendingOperationRepository::new(self.pool.clone());
+
+        let email = email_repo
+            .find_by_id(email_id)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| SyncError::EmailNotFound(format!("Email not found: {}", email_id)))?;
+
+        if !email.is_deleted {
+            return Ok(());
+        }
+
+        let pending_ops = pending_repo
+            .find_pending_for_email(email_id)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        let mut cancelled = false;
+        for op in pending_ops.iter().filter(|op| {
+            matches!(
+                op.parsed_operation_type(),
+                Some(PendingOperationType::Delete | PendingOperationType::PermanentDelete)
+            )
+        }) {
+            cancelled |= pending_repo
+                .cancel(op.id)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        }
+
+        if !cancelled {
+            return Err(SyncError::Other(format!(
+                "Email {} was already deleted on the server and cannot be restored",
+                email_id
+            )));
+        }
+
+        email_repo
+            .undelete(email_id)
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+        log::info!("Restored deleted email {}", email_id);
+
+        self.emit_event(
+            "sync:email-restored",
+            EmailRestoredEvent {
+                account_id: account.id,
+                email_id,
+                folder_id: email.folder_id,
+            },
+        );
+
+        Ok(())
+    }
+
     /// Mark email as read/unread (local-first: updates DB immediately, queues provider sync)
     pub async fn mark_as_read(
         &self,
@@ -526,12 +714,22 @@ impl SyncManager {
             .await
             .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
-        // 2. Queue provider operation
-        let op_type = if is_read {
-            PendingOperationType::MarkRead
+        // 2. Queue provider operation, dropping a queued opposite change that has not
+        // reached the provider yet
+        let (op_type, opposite) = if is_read {
+            (
+                PendingOperationType::MarkRead,
+                PendingOperationType::MarkUnread,
+            )
         } else {
-            PendingOperationType::MarkUnread
+            (
+                PendingOperationType::MarkUnread,
+                PendingOperationType::MarkRead,
+            )
         };
+        let _ = pending_repo
+            .cancel_by_email_and_type(email_id, opposite.as_str())
+            .await;
         let op = PendingOperation::new(
             account.id,
             Some(email_id),
@@ -560,13 +758,6 @@ impl SyncManager {
             },
         );
 
-        if let Some(notification_service) = &self.notification_service {
-            notification_service
-                .update_badge_count()
-                .await
-                .map_err(SyncError::InvalidConfiguration)?;
-        }
-
         Ok(())
     }
 
@@ -592,12 +783,16 @@ impl SyncManager {
             .await
             .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
 
-        // 2. Queue provider operation
-        let op_type = if flagged {
-            PendingOperationType::Flag
+        // 2. Queue provider operation, dropping a queued opposite change that has not
+        // reached the provider yet
+        let (op_type, opposite) = if flagged {
+            (PendingOperationType::Flag, PendingOperationType::Unflag)
         } else {
-            PendingOperationType::Unflag
+            (PendingOperationType::Unflag, PendingOperationType::Flag)
         };
+        let _ = pending_repo
+            .cancel_by_email_and_type(email_id, opposite.as_str())
+            .await;
         let op = PendingOperation::new(
             account.id,
             Some(email_id),
@@ -626,9 +821,37 @@ impl SyncManager {
             },
         );
 
+        if flagged {
+            match email_repo.find_by_id(email_id).await {
+                Ok(Some(email)) => {
+                    if let Err(e) = automation_triggers::enqueue_flagged(&self.pool, &email).await {
+                        log::warn!(
+                            "Failed to queue automation triggers for email {}: {}",
+                            email_id,
+                            e
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to load flagged email {}: {}", email_id, e),
+            }
+        }
+
         Ok(())
     }
 
+    /// Stream an attachment into the attachment cache
+    pub async fn download_attachment(
+        &self,
+        account: &Account,
+        attachment_id: Uuid,
+        cancelled: Arc<AtomicBool>,
+    ) -> SyncResult<String> {
+        self.email_sync
+            .download_attachment(account, attachment_id, cancelled)
+            .await
+    }
+
     /// Rename a folder and sync to provider
     pub async fn rename_folder(
         &self,
@@ -1,8 +1,8 @@
 use chrono::{DateTime, Utc};
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{oneshot, Mutex, RwLock};
 use uuid::Uuid;
 
 use super::error::SyncResult;
@@ -19,13 +19,18 @@ pub struct SyncQueueItem {
     pub priority: SyncPriority,
     pub last_synced_at: Option<DateTime<Utc>>,
     pub enqueued_at: DateTime<Utc>,
+    /// Fetch the whole folder instead of the changes since the last sync
+    pub full: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SyncPriority {
+    /// Requested by the user, runs before any scheduled sync
     High,
     Normal,
     Low,
+    /// A step of a folder's history backfill, runs when nothing else waits
+    Backfill,
 }
 
 impl PartialEq for SyncQueueItem {
@@ -43,31 +48,27 @@ impl PartialOrd for SyncQueueItem {
 }
 
 impl Ord for SyncQueueItem {
+    /// The heap pops the greatest item: the highest priority first, then the
+    /// folder synced longest ago, then the one waiting longest
     fn cmp(&self, other: &Self) -> Ordering {
-        match (self.priority, other.priority) {
-            (SyncPriority::High, SyncPriority::High) => self
-                .last_synced_at
-                .cmp(&other.last_synced_at)
-                .then_with(|| other.enqueued_at.cmp(&self.enqueued_at)),
-            (SyncPriority::High, _) => Ordering::Less,
-            (_, SyncPriority::High) => Ordering::Greater,
-            (SyncPriority::Normal, SyncPriority::Normal) => self
-                .last_synced_at
-                .cmp(&other.last_synced_at)
-                .then_with(|| other.enqueued_at.cmp(&self.enqueued_at)),
-            (SyncPriority::Normal, SyncPriority::Low) => Ordering::Less,
-            (SyncPriority::Low, SyncPriority::Normal) => Ordering::Greater,
-            (SyncPriority::Low, SyncPriority::Low) => self
-                .last_synced_at
-                .cmp(&other.last_synced_at)
-                .then_with(|| other.enqueued_at.cmp(&self.enqueued_at)),
-        }
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| other.last_synced_at.cmp(&self.last_synced_at))
+            .then_with(|| other.enqueued_at.cmp(&self.enqueued_at))
     }
 }
 
+/// Result of a sync handed to whoever waits for it
+pub type SyncOutcome = Result<usize, String>;
+
 pub struct SyncQueue {
     queue: Arc<Mutex<BinaryHeap<SyncQueueItem>>>,
     active_syncs: Arc<RwLock<std::collections::HashSet<Uuid>>>,
+    /// Callers waiting for a queued sync of a folder
+    waiters: Arc<Mutex<HashMap<Uuid, Vec<oneshot::Sender<SyncOutcome>>>>>,
+    /// Callers waiting for the running sync of a folder
+    running_waiters: Arc<Mutex<HashMap<Uuid, Vec<oneshot::Sender<SyncOutcome>>>>>,
     workers_limit: usize,
 }
 
@@ -76,15 +77,30 @@ impl SyncQueue {
         Self {
             queue: Arc::new(Mutex::new(BinaryHeap::new())),
             active_syncs: Arc::new(RwLock::new(std::collections::HashSet::new())),
+            waiters: Arc::new(Mutex::new(HashMap::new())),
+            running_waiters: Arc::new(Mutex::new(HashMap::new())),
             workers_limit: workers_limit.min(100).max(1),
         }
     }
 
+    /// Queue a folder sync. A folder is queued once; queuing it again keeps
+    /// the higher of both priorities and a full sync if either asks for one.
     pub async fn enqueue(&self, mut item: SyncQueueItem) -> SyncResult<()> {
         let priority = item.priority;
         item.enqueued_at = Utc::now();
 
         let mut queue = self.queue.lock().await;
+        if let Some(queued) = queue
+            .iter()
+            .find(|queued| queued.folder_id == item.folder_id)
+        {
+            if queued.priority <= item.priority && (queued.full || !item.full) {
+                return Ok(());
+            }
+            item.priority = item.priority.min(queued.priority);
+            item.full |= queued.full;
+            queue.retain(|queued| queued.folder_id != item.folder_id);
+        }
         queue.push(item.clone());
 
         log::debug!(
@@ -97,9 +113,56 @@ impl SyncQueue {
         Ok(())
     }
 
+    /// Queue a folder sync and wait for its result
+    pub async fn enqueue_and_wait(&self, item: SyncQueueItem) -> SyncOutcome {
+        let (tx, rx) = oneshot::channel();
+        self.waiters
+            .lock()
+            .await
+            .entry(item.folder_id)
+            .or_default()
+            .push(tx);
+
+        self.enqueue(item).await.map_err(|e| e.to_string())?;
+
+        rx.await
+            .unwrap_or_else(|_| Err("The sync was cancelled".to_string()))
+    }
+
+    /// Take the next folder that is not being synced already and mark it as
+    /// processing
     pub async fn dequeue(&self) -> Option<SyncQueueItem> {
         let mut queue = self.queue.lock().await;
-        queue.pop()
+        let mut active = self.active_syncs.write().await;
+
+        let mut busy = Vec::new();
+        let next = loop {
+            match queue.pop() {
+                Some(item) if active.contains(&item.folder_id) => busy.push(item),
+                Some(item) => {
+                    active.insert(item.folder_id);
+                    if let Some(waiters) = self.waiters.lock().await.remove(&item.folder_id) {
+                        self.running_waiters
+                            .lock()
+                            .await
+                            .insert(item.folder_id, waiters);
+                    }
+                    break Some(item);
+                }
+                None => break None,
+            }
+        };
+        queue.extend(busy);
+
+        next
+    }
+
+    /// Hand the result of a folder sync to everyone waiting for it
+    pub async fn complete(&self, folder_id: Uuid, outcome: SyncOutcome) {
+        let waiters = self.running_waiters.lock().await.remove(&folder_id);
+        for waiter in waiters.into_iter().flatten() {
+            let _ = waiter.send(outcome.clone());
+        }
     }
 
     pub async fn is_processing(&self, folder_id: Uuid) -> bool {
@@ -159,8 +222,6 @@ impl SyncQueueWorker {
                 let folder_name = item.folder.name.clone();
                 let account_email = item.account.email.clone();
 
-                self.queue.mark_processing(folder_id).await;
-
                 log::debug!(
                     "[Worker {}] Processing folder sync: account={}, folder={}",
                     worker_id,
@@ -168,12 +229,17 @@ impl SyncQueueWorker {
                     folder_name
                 );
 
-                let result = self
-                    .sync_manager
-                    .sync_folder(&item.account, &item.folder, false)
-                    .await;
+                let result = if item.priority == SyncPriority::Backfill {
+                    self.sync_manager
+                        .backfill_folder(&item.account, &item.folder)
+                        .await
+                } else {
+                    self.sync_manager
+                        .sync_folder(&item.account, &item.folder, item.full)
+                        .await
+                };
 
-                match result {
+                match &result {
                     Ok(count) => {
                         log::info!(
                             "[Worker {}] Synced {} emails from folder {} (account {})",
@@ -195,6 +261,9 @@ impl SyncQueueWorker {
                 }
 
                 self.queue.mark_done(folder_id).await;
+                self.queue
+                    .complete(folder_id, result.map_err(|e| e.to_string()))
+                    .await;
             } else {
                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
             }
@@ -0,0 +1,347 @@
+//! Conversation threading for emails whose provider has no conversations of
+//! its own, like IMAP accounts and imported messages. Follows JWZ's threading
+//! algorithm: a message joins the thread of any message its `References` or
+//! `In-Reply-To` headers name, and a thread is known by its root, the first
+//! id of `References`.
+//!
+//! Messages arrive in any order. A reply synced before its parent starts the
+//! thread of the root it names, which the parent finds later by its own id.
+//! Threads that turn out to be one, e.g. because a client trimmed
+//! `References`, are merged as soon as a message links them.
+//!
+//! Messages without references are threaded by subject only if they are a
+//! reply ("Re: ...") among the same participants within a month.
+
+use chrono::Duration;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::database::models::email::Email;
+use crate::database::repositories::{
+    ConversationRepository, EmailRepository, SqliteConversationRepository, SqliteEmailRepository,
+};
+use crate::sync::error::{SyncError, SyncResult};
+use crate::sync::types::SyncEmail;
+
+/// Prefixes of replies and forwards, as mail clients in various languages
+/// write them
+const REPLY_PREFIXES: &[&str] = &[
+    "re", "fw", "fwd", "aw", "wg", "sv", "vs", "antw", "rif", "r", "tr", "res", "enc",
+];
+
+/// Remote ids of threads built here start with this, unlike provider ids
+const LOCAL_THREAD_PREFIX: &str = "thread:";
+
+/// How far back a reply without references looks for its thread by subject
+const SUBJECT_WINDOW_DAYS: i64 = 30;
+
+/// The subject without reply and forward prefixes or mailing list tags, and
+/// whether any prefix was removed
+pub fn normalize_subject(subject: &str) -> (String, bool) {
+    let mut rest = subject.trim();
+    let mut is_reply = false;
+
+    loop {
+        // Mailing list tags, e.g. "[dev] Re: ..."
+        if let Some(tagged) = rest.strip_prefix('[') {
+            if let Some((_, after)) = tagged.split_once(']') {
+                if !after.trim().is_empty() {
+                    rest = after.trim_start();
+                    continue;
+                }
+            }
+        }
+
+        let Some((prefix, after)) = rest.split_once(':') else {
+            break;
+        };
+        // Counted prefixes, e.g. "Re[2]:" or "Re(2):"
+        let prefix = prefix
+            .trim_end()
+            .trim_end_matches(|c: char| c.is_ascii_digit() || matches!(c, '[' | ']' | '(' | ')'));
+        if !REPLY_PREFIXES.contains(&prefix.to_lowercase().as_str()) {
+            break;
+        }
+        rest = after.trim_start();
+        is_reply = true;
+    }
+
+    (
+        rest.split_whitespace().collect::<Vec<_>>().join(" "),
+        is_reply,
+    )
+}
+
+/// The message ids of a `References` or `In-Reply-To` header, without angle
+/// brackets
+pub fn message_ids(value: &str) -> Vec<String> {
+    let bracketed: Vec<String> = value
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.split_once('>'))
+        .map(|(id, _)| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    if !bracketed.is_empty() {
+        return bracketed;
+    }
+
+    // Some clients leave out the brackets
+    value
+        .split_whitespace()
+        .filter(|token| token.contains('@'))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// A message id without angle brackets
+pub fn normalize_message_id(message_id: &str) -> String {
+    message_id
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_string()
+}
+
+/// The ancestors of a message from the root down to its parent, read from
+/// the synced headers
+pub fn ancestors(message_id: &str, headers: Option<&serde_json::Value>) -> Vec<String> {
+    let header = |name: &str| {
+        headers
+            .and_then(|headers| headers.as_object())
+            .and_then(|headers| {
+                headers
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .and_then(|(_, value)| value.as_str())
+            })
+            .map(message_ids)
+            .unwrap_or_default()
+    };
+
+    let own_id = normalize_message_id(message_id);
+    let mut ancestors: Vec<String> = Vec::new();
+    for id in header("References") {
+        if id != own_id && !ancestors.contains(&id) {
+            ancestors.push(id);
+        }
+    }
+    // The parent named by In-Reply-To goes last, as References may be missing
+    // or trimmed
+    if let Some(parent) = header("In-Reply-To").into_iter().next() {
+        if parent != own_id {
+            ancestors.retain(|id| *id != parent);
+            ancestors.push(parent);
+        }
+    }
+    ancestors
+}
+
+fn local_remote_id(account_id: Uuid, root: &str) -> String {
+    format!("{}{}:{}", LOCAL_THREAD_PREFIX, account_id, root)
+}
+
+/// Message ids as stored, which depends on the provider: with and without
+/// angle brackets
+fn stored_forms(ids: &[String]) -> Vec<String> {
+    ids.iter()
+        .flat_map(|id| [id.clone(), format!("<{}>", id)])
+        .collect()
+}
+
+fn participants(email: &Email) -> HashSet<String> {
+    std::iter::once(&email.from.0)
+        .chain(email.to.0.iter())
+        .chain(email.cc.0.iter())
+        .map(|address| address.address.to_lowercase())
+        .collect()
+}
+
+/// Finds the conversation of an email without a provider conversation,
+/// creating it if the email starts a thread
+pub async fn resolve_conversation(pool: &SqlitePool, email: &SyncEmail) -> SyncResult<Uuid> {
+    let conversation_repo = SqliteConversationRepository::new(pool.clone());
+    let email_repo = SqliteEmailRepository::new(pool.clone());
+
+    let own_id = normalize_message_id(&email.message_id);
+    let ancestors = ancestors(&email.message_id, email.headers.as_ref());
+    let root = ancestors.first().unwrap_or(&own_id).clone();
+
+    let mut thread_ids = ancestors.clone();
+    thread_ids.push(own_id.clone());
+
+    // Threads of the messages this one names, of earlier copies of it, and
+    // threads replies to it started before it arrived
+    let mut linked = email_repo
+        .find_threaded_by_message_ids(email.account_id, &stored_forms(&thread_ids))
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+    // Closest to the root first
+    linked.sort_by_key(|linked| {
+        let id = normalize_message_id(&linked.message_id);
+        thread_ids.iter().position(|thread_id| *thread_id == id)
+    });
+    let local_threads = conversation_repo
+        .find_by_remote_ids(
+            &thread_ids
+                .iter()
+                .map(|id| local_remote_id(email.account_id, id))
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+    let root_remote_id = local_remote_id(email.account_id, &root);
+    let mut candidates: Vec<Uuid> = Vec::new();
+    let ordered = local_threads
+        .iter()
+        .filter(|conversation| conversation.remote_id == root_remote_id)
+        .map(|conversation| conversation.id)
+        .chain(
+            linked
+                .iter()
+                .filter_map(|linked| linked.conversation_id.as_deref())
+                .filter_map(|id| Uuid::parse_str(id).ok()),
+        )
+        .chain(local_threads.iter().map(|conversation| conversation.id));
+    for id in ordered {
+        if !candidates.contains(&id) {
+            candidates.push(id);
+        }
+    }
+
+    if let Some((&target, others)) = candidates.split_first() {
+        // Only threads built here are merged; provider conversations are
+        // restored on the next sync
+        let mergeable = conversation_repo
+            .find_by_ids(others.to_vec())
+            .await
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        for conversation in mergeable
+            .iter()
+            .filter(|conversation| conversation.remote_id.starts_with(LOCAL_THREAD_PREFIX))
+        {
+            let moved = conversation_repo
+                .merge(target, conversation.id)
+                .await
+                .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+            log::debug!(
+                "[Threading] Merged thread {} into {} ({} emails)",
+                conversation.id,
+                target,
+                moved
+            );
+        }
+        return Ok(target);
+    }
+
+    if ancestors.is_empty() {
+        if let Some(conversation_id) = find_by_subject(&email_repo, email).await? {
+            return Ok(conversation_id);
+        }
+    }
+
+    conversation_repo
+        .find_or_create_by_remote_id(&root_remote_id)
+        .await
+        .map(|conversation| conversation.id)
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))
+}
+
+/// The thread of a reply without references: a recent email with the same
+/// subject that shares a participant
+async fn find_by_subject(
+    email_repo: &SqliteEmailRepository,
+    email: &SyncEmail,
+) -> SyncResult<Option<Uuid>> {
+    let (subject, is_reply) = normalize_subject(email.subject.as_deref().unwrap_or_default());
+    if !is_reply || subject.is_empty() {
+        return Ok(None);
+    }
+
+    let candidates = email_repo
+        .find_threaded_by_subject(
+            email.account_id,
+            &subject,
+            email.received_at - Duration::days(SUBJECT_WINDOW_DAYS),
+        )
+        .await
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+
+    let own_participants: HashSet<String> = std::iter::once(&email.from)
+        .chain(email.to.iter())
+        .chain(email.cc.iter())
+        .map(|address| address.address.to_lowercase())
+        .collect();
+
+    Ok(candidates
+        .iter()
+        .filter(|candidate| {
+            normalize_subject(candidate.subject.as_deref().unwrap_or_default())
+                .0
+                .eq_ignore_ascii_case(&subject)
+        })
+        .filter(|candidate| !participants(candidate).is_disjoint(&own_participants))
+        .filter_map(|candidate| candidate.conversation_id.as_deref())
+        .find_map(|id| Uuid::parse_str(id).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_subject() {
+        assert_eq!(
+            normalize_subject("Quarterly report"),
+            ("Quarterly report".to_string(), false)
+        );
+        assert_eq!(
+            normalize_subject("Re: Fwd: RE:  Quarterly   report"),
+            ("Quarterly report".to_string(), true)
+        );
+        assert_eq!(
+            normalize_subject("[dev] AW: Re[2]: Quarterly report"),
+            ("Quarterly report".to_string(), true)
+        );
+        assert_eq!(
+            normalize_subject("Meeting: Quarterly report"),
+            ("Meeting: Quarterly report".to_string(), false)
+        );
+        assert_eq!(normalize_subject("[dev]"), ("[dev]".to_string(), false));
+    }
+
+    #[test]
+    fn test_message_ids() {
+        assert_eq!(
+            message_ids("<a@example.com>\r\n <b@example.com> <c@example.com>"),
+            vec!["a@example.com", "b@example.com", "c@example.com"]
+        );
+        assert_eq!(
+            message_ids("a@example.com b@example.com"),
+            vec!["a@example.com", "b@example.com"]
+        );
+        assert!(message_ids("").is_empty());
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let headers = serde_json::json!({
+            "References": "<a@example.com> <c@example.com> <b@example.com> <d@example.com>",
+            "In-Reply-To": "<c@example.com>",
+        });
+        assert_eq!(
+            ancestors("<d@example.com>", Some(&headers)),
+            vec!["a@example.com", "b@example.com", "c@example.com"]
+        );
+
+        let headers = serde_json::json!({ "in-reply-to": "<c@example.com>" });
+        assert_eq!(
+            ancestors("d@example.com", Some(&headers)),
+            vec!["c@example.com"]
+        );
+
+        assert!(ancestors("<d@example.com>", None).is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+//! Trust of mail server certificates that fail validation
+//!
+//! Self-hosted servers often use self-signed certificates. Connections first
+//! validate the certificate as usual; when that fails the server is connected
+//! again without validation only to read its certificate, which is accepted
+//! if its SHA-256 fingerprint is the one the user trusted for the account.
+//! Otherwise the fingerprint is reported so the user can check and trust it.
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Mail server of an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailServer {
+    Imap,
+    Smtp,
+}
+
+/// SHA-256 fingerprint of a DER encoded certificate, as colon separated
+/// uppercase hex like certificate viewers show it
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Whether `fingerprint` is the trusted one, ignoring case and separators
+pub fn matches(trusted: &str, fingerprint: &str) -> bool {
+    let normalize = |value: &str| {
+        value
+            .chars()
+            .filter(char::is_ascii_hexdigit)
+            .collect::<String>()
+            .to_ascii_uppercase()
+    };
+    let trusted = normalize(trusted);
+
+    !trusted.is_empty() && trusted == normalize(fingerprint)
+}
+
+/// Whether a TLS error is about the server certificate rather than the
+/// connection. native-tls reports these in the platform's own words, all of
+/// which mention the certificate.
+pub fn is_certificate_error(message: &str) -> bool {
+    message.to_ascii_lowercase().contains("certificate")
+}
+
+/// A certificate that failed validation and is not trusted for the account
+#[derive(Debug, Clone, Serialize)]
+pub struct UntrustedCertificate {
+    pub host: String,
+    pub port: u16,
+    pub fingerprint: String,
+    /// Why validation failed
+    pub reason: String,
+}
+
+impl fmt::Display for UntrustedCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The certificate of {}:{} is not trusted ({}). If the server uses a self-signed \
+             certificate, check with its administrator that the SHA-256 fingerprint is {} \
+             and trust it for this account.",
+            self.host, self.port, self.reason, self.fingerprint
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_matches() {
+        let fingerprint = fingerprint(b"certificate");
+        assert_eq!(fingerprint.len(), 32 * 3 - 1);
+        assert!(matches(&fingerprint, &fingerprint));
+        assert!(matches(
+            &fingerprint.replace(':', "").to_lowercase(),
+            &fingerprint
+        ));
+        assert!(!matches("", &fingerprint));
+        assert!(!matches(&fingerprint, &super::fingerprint(b"other")));
+    }
+}
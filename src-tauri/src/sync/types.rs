@@ -1,4 +1,5 @@
 use crate::database::models::email::{Email, EmailAddress};
+use crate::services::smime::SignatureCheck;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
@@ -58,6 +59,13 @@ pub struct SyncEmail {
     pub attachments: Vec<SyncAttachment>,
     pub change_key: Option<String>,
     pub last_modified_at: Option<DateTime<Utc>>,
+    /// S/MIME signature check, for signed messages parsed from full MIME
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureCheck>,
+    /// Provider label IDs of the message, for providers with labels (Gmail).
+    /// `None` leaves the email's labels untouched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_labels: Option<Vec<String>>,
 }
 
 impl SyncEmail {
@@ -96,6 +104,8 @@ impl SyncEmail {
             attachments: Vec::new(),
             change_key: email.change_key.clone(),
             last_modified_at: email.last_modified_at,
+            signature: None,
+            remote_labels: None,
         }
     }
 }
@@ -119,6 +129,52 @@ pub struct SyncAttachment {
     pub data: Option<Vec<u8>>,
 }
 
+/// Birthday and anniversary of a provider-side contact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncContactDates {
+    pub email: String,
+    pub display_name: Option<String>,
+    /// 'YYYY-MM-DD', or '--MM-DD' when the year is unknown
+    pub birthday: Option<String>,
+    pub anniversary: Option<String>,
+}
+
+/// Identity of the signed-in mailbox owner
+#[derive(Debug, Clone, Default)]
+pub struct SyncOwnerProfile {
+    pub display_name: Option<String>,
+    /// Raw photo bytes and their content type
+    pub photo: Option<(Vec<u8>, String)>,
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+}
+
+/// Mailbox storage usage and limits, `None` where the provider reports nothing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncMailboxQuota {
+    /// Used storage in bytes
+    pub storage_used: Option<i64>,
+    /// Storage limit in bytes
+    pub storage_limit: Option<i64>,
+    pub message_count: Option<i64>,
+    pub message_limit: Option<i64>,
+}
+
+/// An address the provider lets the mailbox owner send as
+#[derive(Debug, Clone)]
+pub struct SyncIdentity {
+    pub email: String,
+    pub name: Option<String>,
+}
+
+/// A user-created provider label (Gmail)
+#[derive(Debug, Clone)]
+pub struct SyncLabel {
+    pub remote_id: String,
+    pub name: String,
+    pub color: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SyncDiff {
     /// New emails to be inserted
@@ -132,6 +188,18 @@ pub struct SyncDiff {
     /// Whether this diff represents a complete view of the folder
     /// (true = all emails enumerated; safe to compute deletions by diffing)
     pub is_complete: bool,
+    /// UIDVALIDITY of an IMAP mailbox. Remote IDs are only meaningful
+    /// together with it; `None` for providers with stable IDs.
+    pub uid_validity: Option<u32>,
+}
+
+/// Range of receive dates, for fetching part of a folder's history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncWindow {
+    /// Inclusive lower bound, unbounded if `None`
+    pub after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound, unbounded if `None`
+    pub before: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone)]
@@ -171,22 +239,78 @@ pub struct AccountSettings {
     pub imap_host: Option<String>,
     pub imap_port: Option<u16>,
     pub imap_use_tls: Option<bool>,
+    /// `None` goes by the port: STARTTLS on 143, implicit TLS otherwise
+    pub imap_tls_mode: Option<TlsMode>,
+    /// SHA-256 fingerprint of a certificate accepted although it fails
+    /// validation, e.g. a self-signed one
+    pub imap_trusted_certificate: Option<String>,
     pub imap_username: Option<String>,
 
     pub smtp_host: Option<String>,
     pub smtp_port: Option<u16>,
     pub smtp_use_tls: Option<bool>,
+    /// `None` goes by the port: implicit TLS on 465, STARTTLS otherwise
+    pub smtp_tls_mode: Option<TlsMode>,
+    pub smtp_trusted_certificate: Option<String>,
     pub smtp_username: Option<String>,
 
     pub sync_enabled: bool,
     pub sync_interval: Option<u64>,
     pub sync_on_startup: bool,
+    /// How many days of mail are synced; `None` syncs the whole history
+    pub sync_history_days: Option<u32>,
 
     pub cache_attachments: bool,
     pub max_attachment_cache_size: Option<i64>, // in bytes
     pub auto_download_inline: bool,
 
     pub provider_settings: Option<serde_json::Value>,
+
+    /// How deleted messages are removed from IMAP mailboxes
+    pub imap_delete_policy: ImapDeletePolicy,
+}
+
+/// What deleting a message does on an IMAP server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImapDeletePolicy {
+    /// Move the message to the Trash mailbox, which is created when the server
+    /// has none. Deleting from the Trash removes the message for good.
+    #[default]
+    MoveToTrash,
+    /// Flag the message `\Deleted` and expunge it right away
+    FlagAndExpunge,
+    /// Only flag the message `\Deleted`, for servers that expunge on their own
+    /// or when another client should decide
+    FlagOnly,
+}
+
+/// How a connection to a mail server is encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsMode {
+    /// TLS from the start of the connection
+    Implicit,
+    /// A plain connection upgraded with STARTTLS
+    Starttls,
+}
+
+impl TlsMode {
+    pub fn for_imap_port(port: u16) -> Self {
+        if port == 143 {
+            Self::Starttls
+        } else {
+            Self::Implicit
+        }
+    }
+
+    pub fn for_smtp_port(port: u16) -> Self {
+        if port == 465 {
+            Self::Implicit
+        } else {
+            Self::Starttls
+        }
+    }
 }
 
 impl Default for AccountSettings {
@@ -195,18 +319,24 @@ impl Default for AccountSettings {
             imap_host: None,
             imap_port: None,
             imap_use_tls: Some(true),
+            imap_tls_mode: None,
+            imap_trusted_certificate: None,
             imap_username: None,
             smtp_host: None,
             smtp_port: None,
             smtp_use_tls: Some(true),
+            smtp_tls_mode: None,
+            smtp_trusted_certificate: None,
             smtp_username: None,
             sync_enabled: true,
             sync_interval: Some(5 * 60),
             sync_on_startup: true,
+            sync_history_days: None,
             cache_attachments: true,
             max_attachment_cache_size: Some(1024 * 1024 * 1024),
             auto_download_inline: true,
             provider_settings: None,
+            imap_delete_policy: ImapDeletePolicy::default(),
         }
     }
 }
@@ -217,22 +347,28 @@ impl serde::Serialize for AccountSettings {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("AccountSettings", 15)?;
+        let mut state = serializer.serialize_struct("AccountSettings", 21)?;
         state.serialize_field("imap_host", &self.imap_host)?;
         state.serialize_field("imap_port", &self.imap_port)?;
         state.serialize_field("imap_use_tls", &self.imap_use_tls)?;
+        state.serialize_field("imap_tls_mode", &self.imap_tls_mode)?;
+        state.serialize_field("imap_trusted_certificate", &self.imap_trusted_certificate)?;
         state.serialize_field("imap_username", &self.imap_username)?;
         state.serialize_field("smtp_host", &self.smtp_host)?;
         state.serialize_field("smtp_port", &self.smtp_port)?;
         state.serialize_field("smtp_use_tls", &self.smtp_use_tls)?;
+        state.serialize_field("smtp_tls_mode", &self.smtp_tls_mode)?;
+        state.serialize_field("smtp_trusted_certificate", &self.smtp_trusted_certificate)?;
         state.serialize_field("smtp_username", &self.smtp_username)?;
         state.serialize_field("sync_enabled", &self.sync_enabled)?;
         state.serialize_field("sync_interval", &self.sync_interval)?;
         state.serialize_field("sync_on_startup", &self.sync_on_startup)?;
+        state.serialize_field("sync_history_days", &self.sync_history_days)?;
         state.serialize_field("cache_attachments", &self.cache_attachments)?;
         state.serialize_field("max_attachment_cache_size", &self.max_attachment_cache_size)?;
         state.serialize_field("auto_download_inline", &self.auto_download_inline)?;
         state.serialize_field("provider_settings", &self.provider_settings)?;
+        state.serialize_field("imap_delete_policy", &self.imap_delete_policy)?;
         state.end()
     }
 }
@@ -248,18 +384,24 @@ impl<'de> serde::Deserialize<'de> for AccountSettings {
             ImapHost,
             ImapPort,
             ImapUseTls,
+            ImapTlsMode,
+            ImapTrustedCertificate,
             ImapUsername,
             SmtpHost,
             SmtpPort,
             SmtpUseTls,
+            SmtpTlsMode,
+            SmtpTrustedCertificate,
             SmtpUsername,
             SyncEnabled,
             SyncInterval,
             SyncOnStartup,
+            SyncHistoryDays,
             CacheAttachments,
             MaxAttachmentCacheSize,
             AutoDownloadInline,
             ProviderSettings,
+            ImapDeletePolicy,
         }
 
         struct AccountSettingsVisitor;
@@ -278,38 +420,54 @@ impl<'de> serde::Deserialize<'de> for AccountSettings {
                 let mut imap_host = None;
                 let mut imap_port = None;
                 let mut imap_use_tls = None;
+                let mut imap_tls_mode = None;
+                let mut imap_trusted_certificate = None;
                 let mut imap_username = None;
                 let mut smtp_host = None;
                 let mut smtp_port = None;
                 let mut smtp_use_tls = None;
+                let mut smtp_tls_mode = None;
+                let mut smtp_trusted_certificate = None;
                 let mut smtp_username = None;
                 let mut sync_enabled = None;
                 let mut sync_interval = None;
                 let mut sync_on_startup = None;
+                let mut sync_history_days = None;
                 let mut cache_attachments = None;
                 let mut max_attachment_cache_size = None;
                 let mut auto_download_inline = None;
                 let mut provider_settings = None;
+                let mut imap_delete_policy = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::ImapHost => imap_host = map.next_value()?,
                         Field::ImapPort => imap_port = map.next_value()?,
                         Field::ImapUseTls => imap_use_tls = map.next_value()?,
+                        Field::ImapTlsMode => imap_tls_mode = map.next_value()?,
+                        Field::ImapTrustedCertificate => {
+                            imap_trusted_certificate = map.next_value()?
+                        }
                         Field::ImapUsername => imap_username = map.next_value()?,
                         Field::SmtpHost => smtp_host = map.next_value()?,
                         Field::SmtpPort => smtp_port = map.next_value()?,
                         Field::SmtpUseTls => smtp_use_tls = map.next_value()?,
+                        Field::SmtpTlsMode => smtp_tls_mode = map.next_value()?,
+                        Field::SmtpTrustedCertificate => {
+                            smtp_trusted_certificate = map.next_value()?
+                        }
                         Field::SmtpUsername => smtp_username = map.next_value()?,
                         Field::SyncEnabled => sync_enabled = map.next_value()?,
                         Field::SyncInterval => sync_interval = map.next_value()?,
                         Field::SyncOnStartup => sync_on_startup = map.next_value()?,
+                        Field::SyncHistoryDays => sync_history_days = map.next_value()?,
                         Field::CacheAttachments => cache_attachments = map.next_value()?,
                         Field::MaxAttachmentCacheSize => {
                             max_attachment_cache_size = map.next_value()?
                         }
                         Field::AutoDownloadInline => auto_download_inline = map.next_value()?,
                         Field::ProviderSettings => provider_settings = map.next_value()?,
+                        Field::ImapDeletePolicy => imap_delete_policy = map.next_value()?,
                     }
                 }
 
@@ -317,18 +475,24 @@ impl<'de> serde::Deserialize<'de> for AccountSettings {
                     imap_host,
                     imap_port,
                     imap_use_tls,
+                    imap_tls_mode,
+                    imap_trusted_certificate,
                     imap_username,
                     smtp_host,
                     smtp_port,
                     smtp_use_tls,
+                    smtp_tls_mode,
+                    smtp_trusted_certificate,
                     smtp_username,
                     sync_enabled: sync_enabled.unwrap_or(true),
                     sync_interval,
                     sync_on_startup: sync_on_startup.unwrap_or(true),
+                    sync_history_days,
                     cache_attachments: cache_attachments.unwrap_or(true),
                     max_attachment_cache_size,
                     auto_download_inline: auto_download_inline.unwrap_or(true),
                     provider_settings,
+                    imap_delete_policy: imap_delete_policy.unwrap_or_default(),
                 })
             }
         }
@@ -337,18 +501,24 @@ impl<'de> serde::Deserialize<'de> for AccountSettings {
             "imap_host",
             "imap_port",
             "imap_use_tls",
+            "imap_tls_mode",
+            "imap_trusted_certificate",
             "imap_username",
             "smtp_host",
             "smtp_port",
             "smtp_use_tls",
+            "smtp_tls_mode",
+            "smtp_trusted_certificate",
             "smtp_username",
             "sync_enabled",
             "sync_interval",
             "sync_on_startup",
+            "sync_history_days",
             "cache_attachments",
             "max_attachment_cache_size",
             "auto_download_inline",
             "provider_settings",
+            "imap_delete_policy",
         ];
         deserializer.deserialize_struct("AccountSettings", FIELDS, AccountSettingsVisitor)
     }
@@ -386,4 +556,6 @@ pub struct EmailAttachmentData {
     pub filename: String,
     pub content: Vec<u8>,
     pub content_type: Option<String>,
+    /// Set for inline images, which the body references as `cid:`
+    pub content_id: Option<String>,
 }